@@ -1,7 +1,10 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context as _;
-use ethereum_rpc::{EthRpcConfig, FeeHistoryCacheConfig, GasPriceOracleConfig};
+use citrea_common::RpcConfig;
+use citrea_evm::LogsQueryLimits;
+use ethereum_rpc::{EthRpcConfig, FeeHistoryCacheConfig, FilterStoreConfig};
 use sov_db::ledger_db::LedgerDB;
 use sov_modules_api::default_context::DefaultContext;
 use sov_prover_storage_manager::SnapshotManager;
@@ -10,18 +13,30 @@ use sov_state::ProverStorage;
 use tokio::sync::broadcast;
 
 // register ethereum methods.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn register_ethereum<Da: DaService>(
     da_service: Arc<Da>,
     storage: ProverStorage<SnapshotManager>,
     ledger_db: LedgerDB,
     methods: &mut jsonrpsee::RpcModule<()>,
+    rpc_config: &RpcConfig,
     sequencer_client_url: Option<String>,
+    read_only: bool,
     soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
 ) -> Result<(), anyhow::Error> {
     let eth_rpc_config = {
         EthRpcConfig {
-            gas_price_oracle_config: GasPriceOracleConfig::default(),
+            gas_price_oracle_config: rpc_config.gas_price_oracle.clone(),
             fee_history_cache_config: FeeHistoryCacheConfig::default(),
+            logs_query_limits: LogsQueryLimits {
+                max_block_range: rpc_config.max_logs_block_range,
+                max_logs_per_response: rpc_config.max_logs_per_response,
+            },
+            filter_store_config: FilterStoreConfig {
+                filter_timeout: Duration::from_secs(rpc_config.filter_timeout_secs),
+                max_filters_per_connection: rpc_config.max_filters_per_connection,
+                max_filters: rpc_config.max_filters,
+            },
         }
     };
 
@@ -31,6 +46,7 @@ pub(crate) fn register_ethereum<Da: DaService>(
         storage,
         ledger_db,
         sequencer_client_url,
+        read_only,
         soft_confirmation_rx,
     );
     methods
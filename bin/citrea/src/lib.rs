@@ -13,6 +13,7 @@ use tracing_subscriber::{fmt, EnvFilter};
 
 mod eth;
 mod guests;
+mod metrics;
 mod rollup;
 pub use rollup::*;
 
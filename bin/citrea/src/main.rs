@@ -1,26 +1,31 @@
 use core::fmt::Debug as DebugTrait;
-use std::net::SocketAddr;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
 
-use anyhow::{anyhow, Context as _};
+use anyhow::Context as _;
 use bitcoin_da::service::BitcoinServiceConfig;
 use citrea::{
     initialize_logging, BitcoinRollup, CitreaRollupBlueprint, MockDemoRollup, NetworkArg,
 };
+use citrea_common::db_tools::{read_node_status, rollback_to_l2_height, NodeKind};
 use citrea_common::{
     from_toml_path, BatchProverConfig, FromEnv, FullNodeConfig, LightClientProverConfig,
     SequencerConfig,
 };
-use citrea_stf::genesis_config::GenesisPaths;
-use clap::Parser;
-use metrics_exporter_prometheus::PrometheusBuilder;
-use metrics_util::MetricKindMask;
+use citrea_fullnode::commitment_proof::{
+    verify_commitment_inclusion_proof, CommitmentInclusionProof,
+};
+use citrea_primitives::forks::{network_forks_with_override, use_forks, use_network_forks};
+use citrea_stf::genesis_config::{GenesisManifest, GenesisPaths};
+use clap::{Parser, Subcommand};
 use sov_mock_da::MockDaConfig;
 use sov_modules_api::Spec;
 use sov_modules_rollup_blueprint::RollupBlueprint;
+use sov_rollup_interface::fork::Fork;
+use sov_rollup_interface::rpc::SoftConfirmationResponse;
+use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::Network;
 use sov_state::storage::NativeStorage;
-use tracing::{debug, error, info, instrument};
+use tracing::{error, info, instrument};
 
 #[cfg(test)]
 mod test_rpc;
@@ -28,8 +33,11 @@ mod test_rpc;
 /// Main runner. Initializes a DA service, and starts a node using the provided arguments.
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     /// The mode in which the node runs.
     /// This determines which guest code to use.
     /// Default is Mainnet.
@@ -42,8 +50,8 @@ struct Args {
 
     /// Path to the genesis configuration.
     /// Defines the genesis of module states like evm.
-    #[arg(long)]
-    genesis_paths: String,
+    #[arg(long, required = true)]
+    genesis_paths: Option<String>,
 
     /// The data layer type.
     #[arg(long, default_value = "mock")]
@@ -73,6 +81,79 @@ struct Args {
     quiet: bool,
 }
 
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Runs the batch proof guest on a circuit input archived by the batch prover.
+    ProveFromFile {
+        /// Path to the archived circuit input.
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Generate a proof instead of only executing the guest.
+        #[arg(long, default_value_t)]
+        with_proof: bool,
+
+        /// Path to write the resulting proof to.
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Path to the ledger used for the zkVM host's proving sessions.
+        /// Defaults to a directory under the system temp dir.
+        #[arg(long)]
+        ledger_path: Option<PathBuf>,
+    },
+    /// Prints the progress recorded in the node's ledger.
+    /// The node mode is taken from the --sequencer, --batch-prover and --light-client-prover flags.
+    Status,
+    /// Rolls back the node's storage to the given L2 height.
+    /// The node must not be running.
+    Rollback {
+        /// L2 height to keep as the new head.
+        #[arg(long)]
+        to_l2_height: u64,
+    },
+    /// Re-executes the stored L2 blocks in a range on the node's historical state
+    /// and prints the ones which do not match the stored results.
+    /// The node must not be running.
+    Replay {
+        /// First L2 height to replay.
+        #[arg(long)]
+        from_l2_height: u64,
+
+        /// Last L2 height to replay.
+        #[arg(long)]
+        to_l2_height: u64,
+
+        /// Spec id to replay every block with, e.g. 1 for Fork1.
+        /// Defaults to the spec active at each height.
+        #[arg(long, value_parser = parse_spec_id)]
+        spec_id: Option<SpecId>,
+    },
+    /// Writes the node's state after an L2 block as genesis files a new chain can start from,
+    /// along with a manifest of the exported chain.
+    /// The node must not be running.
+    ExportGenesis {
+        /// L2 height to export the state after.
+        #[arg(long)]
+        at_l2_height: u64,
+
+        /// Directory to write the genesis files to.
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Verifies offline that a soft confirmation served by a node is included in a sequencer commitment.
+    /// The soft confirmation is hashed with the scheme of the fork active at its height on --network.
+    VerifyConfirmation {
+        /// Path to the JSON of a `ledger_getSoftConfirmationByNumber` response, with the transactions.
+        #[arg(long)]
+        soft_confirmation: PathBuf,
+
+        /// Path to the JSON of the `ledger_getCommitmentInclusionProof` response for the same height.
+        #[arg(long)]
+        proof: PathBuf,
+    },
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum SupportedDaLayer {
     Mock,
@@ -96,6 +177,145 @@ async fn main() -> Result<(), anyhow::Error> {
     };
     initialize_logging(logging_level);
 
+    let mut network = args.network.into();
+    if args.dev {
+        network = Network::Nightly;
+    }
+
+    if let Some(Commands::ProveFromFile {
+        input,
+        with_proof,
+        output,
+        ledger_path,
+    }) = args.command
+    {
+        let ledger_path =
+            ledger_path.unwrap_or_else(|| std::env::temp_dir().join("citrea-prove-from-file"));
+        use_network_forks(network);
+        let proof =
+            match args.da_layer {
+                SupportedDaLayer::Mock => MockDemoRollup::new(network)
+                    .prove_circuit_input_from_file(&input, &ledger_path, with_proof)?,
+                SupportedDaLayer::Bitcoin => BitcoinRollup::new(network)
+                    .prove_circuit_input_from_file(&input, &ledger_path, with_proof)?,
+            };
+        info!("Ran batch proof guest on {}", input.display());
+
+        if let Some(output) = output {
+            std::fs::write(&output, proof)
+                .with_context(|| format!("Failed to write proof to {}", output.display()))?;
+            info!("Proof written to {}", output.display());
+        }
+        return Ok(());
+    }
+
+    if let Some(Commands::Replay {
+        from_l2_height,
+        to_l2_height,
+        spec_id,
+    }) = args.command
+    {
+        match args.da_layer {
+            SupportedDaLayer::Mock => {
+                replay::<MockDemoRollup, MockDaConfig>(
+                    network,
+                    args.rollup_config_path,
+                    from_l2_height,
+                    to_l2_height,
+                    spec_id,
+                )
+                .await?
+            }
+            SupportedDaLayer::Bitcoin => {
+                replay::<BitcoinRollup, BitcoinServiceConfig>(
+                    network,
+                    args.rollup_config_path,
+                    from_l2_height,
+                    to_l2_height,
+                    spec_id,
+                )
+                .await?
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(Commands::ExportGenesis { at_l2_height, out }) = &args.command {
+        let manifest = match args.da_layer {
+            SupportedDaLayer::Mock => export_genesis::<MockDemoRollup, MockDaConfig>(
+                network,
+                args.rollup_config_path,
+                *at_l2_height,
+                out,
+            )?,
+            SupportedDaLayer::Bitcoin => export_genesis::<BitcoinRollup, BitcoinServiceConfig>(
+                network,
+                args.rollup_config_path,
+                *at_l2_height,
+                out,
+            )?,
+        };
+        info!(
+            "Exported genesis of chain {} at L2 height {} to {}",
+            manifest.chain_id,
+            manifest.l2_height,
+            out.display()
+        );
+        return Ok(());
+    }
+
+    if let Some(Commands::VerifyConfirmation {
+        soft_confirmation,
+        proof,
+    }) = &args.command
+    {
+        use_network_forks(network);
+        let soft_confirmation: SoftConfirmationResponse = read_json(soft_confirmation)?;
+        let inclusion_proof: CommitmentInclusionProof = read_json(proof)?;
+        let hash = verify_commitment_inclusion_proof(&soft_confirmation, &inclusion_proof)?;
+        info!(
+            "Soft confirmation {} with hash {} is included in the commitment with merkle root {} found in L1 block {}",
+            soft_confirmation.l2_height,
+            alloy_primitives::hex::encode(hash),
+            alloy_primitives::hex::encode(inclusion_proof.commitment.merkle_root),
+            inclusion_proof.commitment.found_in_l1
+        );
+        return Ok(());
+    }
+
+    if let Some(command @ (Commands::Status | Commands::Rollback { .. })) = &args.command {
+        let node_kind = if args.sequencer.is_some() {
+            NodeKind::Sequencer
+        } else if args.batch_prover.is_some() {
+            NodeKind::BatchProver
+        } else if args.light_client_prover.is_some() {
+            NodeKind::LightClientProver
+        } else {
+            NodeKind::FullNode
+        };
+        let storage_path = match args.da_layer {
+            SupportedDaLayer::Mock => {
+                storage_path::<MockDaConfig>(args.rollup_config_path.clone())?
+            }
+            SupportedDaLayer::Bitcoin => {
+                storage_path::<BitcoinServiceConfig>(args.rollup_config_path.clone())?
+            }
+        };
+
+        match command {
+            Commands::Status => print!("{}", read_node_status(&storage_path, node_kind)?),
+            Commands::Rollback { to_l2_height } => {
+                rollback_to_l2_height(&storage_path, node_kind, *to_l2_height)?;
+                info!("Rolled back {} to L2 height {}", node_kind, to_l2_height);
+            }
+            Commands::ProveFromFile { .. }
+            | Commands::Replay { .. }
+            | Commands::ExportGenesis { .. }
+            | Commands::VerifyConfirmation { .. } => unreachable!(),
+        }
+        return Ok(());
+    }
+
     let sequencer_config = match args.sequencer {
         Some(Some(path)) => Some(
             from_toml_path(path)
@@ -148,10 +368,9 @@ async fn main() -> Result<(), anyhow::Error> {
         ));
     }
 
-    let mut network = args.network.into();
-    if args.dev {
-        network = Network::Nightly;
-    }
+    let genesis_paths = args
+        .genesis_paths
+        .expect("Genesis paths are required when running a node");
 
     info!("Starting node on {network}");
 
@@ -159,7 +378,7 @@ async fn main() -> Result<(), anyhow::Error> {
         SupportedDaLayer::Mock => {
             start_rollup::<MockDemoRollup, MockDaConfig>(
                 network,
-                &GenesisPaths::from_dir(&args.genesis_paths),
+                &GenesisPaths::from_dir(&genesis_paths),
                 args.rollup_config_path,
                 batch_prover_config,
                 light_client_prover_config,
@@ -170,7 +389,7 @@ async fn main() -> Result<(), anyhow::Error> {
         SupportedDaLayer::Bitcoin => {
             start_rollup::<BitcoinRollup, BitcoinServiceConfig>(
                 network,
-                &GenesisPaths::from_dir(&args.genesis_paths),
+                &GenesisPaths::from_dir(&genesis_paths),
                 args.rollup_config_path,
                 batch_prover_config,
                 light_client_prover_config,
@@ -183,6 +402,103 @@ async fn main() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+fn storage_path<DaC>(rollup_config_path: Option<String>) -> Result<PathBuf, anyhow::Error>
+where
+    DaC: serde::de::DeserializeOwned + DebugTrait + Clone + FromEnv,
+{
+    let rollup_config: FullNodeConfig<DaC> = match rollup_config_path {
+        Some(path) => from_toml_path(path)
+            .context("Failed to read rollup configuration from the config file")?,
+        None => FullNodeConfig::from_env()
+            .context("Failed to read rollup configuration from the environment")?,
+    };
+    Ok(rollup_config.storage.path)
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, anyhow::Error> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn parse_spec_id(spec_id: &str) -> Result<SpecId, String> {
+    spec_id
+        .parse()
+        .ok()
+        .and_then(SpecId::from_u8)
+        .ok_or_else(|| format!("Unknown spec id {}", spec_id))
+}
+
+/// Sets the fork schedule of the network with the overrides from the rollup config
+fn use_config_forks<DaC>(
+    network: Network,
+    rollup_config: &FullNodeConfig<DaC>,
+) -> Result<(), anyhow::Error> {
+    let fork_overrides: Vec<Fork> = rollup_config
+        .forks
+        .iter()
+        .copied()
+        .map(Into::into)
+        .collect();
+    let forks = network_forks_with_override(network, &fork_overrides)
+        .context("Invalid forks override in the rollup configuration")?;
+    use_forks(forks);
+    Ok(())
+}
+
+async fn replay<S, DaC>(
+    network: Network,
+    rollup_config_path: Option<String>,
+    from_l2_height: u64,
+    to_l2_height: u64,
+    spec_id: Option<SpecId>,
+) -> Result<(), anyhow::Error>
+where
+    DaC: serde::de::DeserializeOwned + DebugTrait + Clone + FromEnv,
+    S: CitreaRollupBlueprint<DaConfig = DaC>,
+    <<S as RollupBlueprint>::NativeContext as Spec>::Storage: NativeStorage,
+{
+    let rollup_config: FullNodeConfig<DaC> = match rollup_config_path {
+        Some(path) => from_toml_path(path)
+            .context("Failed to read rollup configuration from the config file")?,
+        None => FullNodeConfig::from_env()
+            .context("Failed to read rollup configuration from the environment")?,
+    };
+    use_config_forks(network, &rollup_config)?;
+    rollup_config.storage.validate()?;
+    rollup_config.public_keys.validate()?;
+
+    let mismatches = S::new(network)
+        .replay_soft_confirmations(rollup_config, from_l2_height..=to_l2_height, spec_id)
+        .await?;
+    for mismatch in &mismatches {
+        print!("{}", mismatch);
+    }
+    Ok(())
+}
+
+fn export_genesis<S, DaC>(
+    network: Network,
+    rollup_config_path: Option<String>,
+    at_l2_height: u64,
+    out: &Path,
+) -> Result<GenesisManifest, anyhow::Error>
+where
+    DaC: serde::de::DeserializeOwned + DebugTrait + Clone + FromEnv,
+    S: CitreaRollupBlueprint<DaConfig = DaC>,
+{
+    let rollup_config: FullNodeConfig<DaC> = match rollup_config_path {
+        Some(path) => from_toml_path(path)
+            .context("Failed to read rollup configuration from the config file")?,
+        None => FullNodeConfig::from_env()
+            .context("Failed to read rollup configuration from the environment")?,
+    };
+    rollup_config.storage.validate()?;
+
+    S::new(network).export_genesis(&rollup_config, at_l2_height, out)
+}
+
 #[instrument(level = "trace", skip_all, err)]
 async fn start_rollup<S, DaC>(
     network: Network,
@@ -207,24 +523,10 @@ where
             .context("Failed to read rollup configuration from the environment")?,
     };
 
-    if rollup_config.telemetry.bind_host.is_some() && rollup_config.telemetry.bind_port.is_some() {
-        let bind_host = rollup_config.telemetry.bind_host.as_ref().unwrap();
-        let bind_port = rollup_config.telemetry.bind_port.as_ref().unwrap();
-        let telemetry_addr: SocketAddr = format!("{}:{}", bind_host, bind_port)
-            .parse()
-            .map_err(|_| anyhow!("Invalid telemetry address"))?;
-
-        debug!("Starting telemetry server on: {}", telemetry_addr);
-
-        let builder = PrometheusBuilder::new().with_http_listener(telemetry_addr);
-        builder
-            .idle_timeout(
-                MetricKindMask::GAUGE | MetricKindMask::HISTOGRAM,
-                Some(Duration::from_secs(30)),
-            )
-            .install()
-            .map_err(|_| anyhow!("failed to install Prometheus recorder"))?;
-    }
+    // Every component reads the fork schedule from here, so it is set before any of them is created
+    use_config_forks(network, &rollup_config)?;
+    rollup_config.storage.validate()?;
+    rollup_config.public_keys.validate()?;
 
     let rollup_blueprint = S::new(network);
 
@@ -237,6 +539,8 @@ where
 
         if let Err(e) = sequencer.run().await {
             error!("Error: {}", e);
+            // Exit with a non-zero code
+            return Err(e);
         }
     } else if let Some(batch_prover_config) = batch_prover_config {
         let (mut prover, rpc_methods) = CitreaRollupBlueprint::create_new_batch_prover(
@@ -255,6 +559,8 @@ where
 
         if let Err(e) = prover.run().await {
             error!("Error: {}", e);
+            // Exit with a non-zero code
+            return Err(e);
         }
     } else if let Some(light_client_prover_config) = light_client_prover_config {
         let (mut prover, rpc_methods) = CitreaRollupBlueprint::create_new_light_client_prover(
@@ -272,6 +578,8 @@ where
 
         if let Err(e) = prover.run().await {
             error!("Error: {}", e);
+            // Exit with a non-zero code
+            return Err(e);
         }
     } else {
         let (mut rollup, rpc_methods) = CitreaRollupBlueprint::create_new_rollup(
@@ -282,10 +590,12 @@ where
         .await
         .expect("Could not start full-node");
 
-        rollup.start_rpc_server(rpc_methods, None).await;
+        rollup.start_rpc_server(rpc_methods, None).await?;
 
         if let Err(e) = rollup.run().await {
             error!("Error: {}", e);
+            // Exit with a non-zero code
+            return Err(e);
         }
     }
 
@@ -0,0 +1,65 @@
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use citrea_common::MetricsConfig;
+use metrics_exporter_prometheus::PrometheusBuilder;
+use metrics_util::MetricKindMask;
+use tracing::{debug, error, warn};
+
+/// Address of the exporter serving the process wide metrics recorder, once installed
+static EXPORTER_ADDR: Mutex<Option<SocketAddr>> = Mutex::new(None);
+
+/// Starts the Prometheus exporter if enabled in `config`.
+/// The recorder is process wide, so only the first node started in a process binds its exporter.
+/// The exporter runs on its own thread to outlive the runtime of the node that started it.
+pub(crate) fn start_metrics_exporter(config: &MetricsConfig) -> anyhow::Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let addr: SocketAddr = format!("{}:{}", config.bind_host, config.bind_port)
+        .parse()
+        .map_err(|_| anyhow!("Invalid metrics exporter address"))?;
+
+    let mut exporter_addr = EXPORTER_ADDR.lock().expect("Lock was poisoned");
+    if let Some(exporter_addr) = *exporter_addr {
+        if exporter_addr != addr {
+            warn!(
+                "Metrics exporter already serving on {}, not binding {}",
+                exporter_addr, addr
+            );
+        }
+        return Ok(());
+    }
+
+    debug!("Starting metrics exporter on: {}", addr);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let (recorder, exporter) = {
+        let _guard = runtime.enter();
+        PrometheusBuilder::new()
+            .with_http_listener(addr)
+            .idle_timeout(
+                MetricKindMask::GAUGE | MetricKindMask::HISTOGRAM,
+                Some(Duration::from_secs(30)),
+            )
+            .build()
+            .map_err(|e| anyhow!("Failed to build Prometheus exporter: {}", e))?
+    };
+    metrics::set_global_recorder(recorder)
+        .map_err(|e| anyhow!("Failed to install Prometheus recorder: {}", e))?;
+    std::thread::Builder::new()
+        .name("metrics-exporter".to_string())
+        .spawn(move || {
+            if let Err(e) = runtime.block_on(exporter) {
+                error!("Metrics exporter stopped: {:?}", e);
+            }
+        })?;
+
+    *exporter_addr = Some(addr);
+    Ok(())
+}
@@ -4,12 +4,15 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use bitcoin_da::rpc::create_rpc_module as create_da_rpc_module;
 use bitcoin_da::service::{BitcoinService, BitcoinServiceConfig, TxidWrapper};
-use bitcoin_da::spec::{BitcoinSpec, RollupParams};
+use bitcoin_da::spec::{BitcoinNetwork, BitcoinSpec, RollupParams};
 use bitcoin_da::verifier::BitcoinVerifier;
-use citrea_common::rpc::register_healthcheck_rpc;
+use citrea_common::rpc::{
+    register_fork_schedule_rpc, register_healthcheck_rpc, register_state_diff_size_rpc,
+    register_sync_status_rpc, register_tx_soft_confirmation_rpc,
+};
 use citrea_common::tasks::manager::TaskManager;
-use citrea_common::FullNodeConfig;
-use citrea_primitives::forks::use_network_forks;
+use citrea_common::{FullNodeConfig, RpcConfig};
+use citrea_fullnode::commitment_proof::register_commitment_inclusion_proof_rpc;
 use citrea_primitives::{TO_BATCH_PROOF_PREFIX, TO_LIGHT_CLIENT_PREFIX};
 use citrea_risc0_adapter::host::Risc0BonsaiHost;
 // use citrea_sp1::host::SP1Host;
@@ -59,18 +62,24 @@ impl RollupBlueprint for BitcoinRollup {
     type ProverService = ParallelProverService<Self::DaService, Self::Vm>;
 
     fn new(network: Network) -> Self {
-        use_network_forks(network);
         Self { network }
     }
 
+    fn network(&self) -> Network {
+        self.network
+    }
+
     #[instrument(level = "trace", skip_all, err)]
     fn create_rpc_methods(
         &self,
         storage: &ProverStorage<SnapshotManager>,
         ledger_db: &LedgerDB,
+        rpc_config: &RpcConfig,
         da_service: &Arc<Self::DaService>,
         sequencer_client_url: Option<String>,
+        read_only: bool,
         soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
+        task_manager: &mut TaskManager<()>,
     ) -> Result<jsonrpsee::RpcModule<()>, anyhow::Error> {
         // unused inside register RPC
         let sov_sequencer = Address::new([0; 32]);
@@ -80,18 +89,51 @@ impl RollupBlueprint for BitcoinRollup {
             Self::NativeRuntime,
             Self::NativeContext,
             Self::DaService,
-        >(storage, ledger_db, da_service, sov_sequencer)?;
+        >(
+            storage,
+            ledger_db,
+            rpc_config,
+            da_service,
+            sov_sequencer,
+            soft_confirmation_rx.as_ref().map(|rx| rx.resubscribe()),
+        )?;
 
         crate::eth::register_ethereum::<Self::DaService>(
             da_service.clone(),
             storage.clone(),
             ledger_db.clone(),
             &mut rpc_methods,
-            sequencer_client_url,
+            rpc_config,
+            sequencer_client_url.clone(),
+            read_only,
             soft_confirmation_rx,
         )?;
 
-        register_healthcheck_rpc(&mut rpc_methods, ledger_db.clone())?;
+        register_healthcheck_rpc(
+            &mut rpc_methods,
+            ledger_db.clone(),
+            rpc_config.healthcheck_stall_multiple,
+            task_manager,
+        )?;
+
+        register_tx_soft_confirmation_rpc(&mut rpc_methods, ledger_db.clone(), storage.clone())?;
+
+        register_fork_schedule_rpc(&mut rpc_methods, ledger_db.clone())?;
+
+        register_state_diff_size_rpc(&mut rpc_methods, ledger_db.clone())?;
+
+        register_commitment_inclusion_proof_rpc(&mut rpc_methods, ledger_db.clone())?;
+
+        // The sequencer is the head itself, only the nodes following it report their sync status
+        if let Some(sequencer_client_url) = sequencer_client_url {
+            register_sync_status_rpc(
+                &mut rpc_methods,
+                ledger_db.clone(),
+                da_service.clone(),
+                &sequencer_client_url,
+                rpc_config.sync_status_lag_tolerance,
+            )?;
+        }
 
         let da_methods = create_da_rpc_module(da_service.clone());
         rpc_methods.merge(da_methods)?;
@@ -126,6 +168,7 @@ impl RollupBlueprint for BitcoinRollup {
                 RollupParams {
                     to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
                     to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
+                    network: BitcoinNetwork::from_network(self.network),
                 },
                 tx,
             )
@@ -136,6 +179,7 @@ impl RollupBlueprint for BitcoinRollup {
                 RollupParams {
                     to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
                     to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
+                    network: BitcoinNetwork::from_network(self.network),
                 },
                 tx,
             )
@@ -148,8 +192,10 @@ impl RollupBlueprint for BitcoinRollup {
             // run only for sequencer and prover
             service.monitoring.restore().await?;
 
-            task_manager.spawn(|tk| Arc::clone(&service).run_da_queue(rx, tk));
-            task_manager.spawn(|tk| Arc::clone(&service.monitoring).run(tk));
+            task_manager.spawn("da_queue", |tk| Arc::clone(&service).run_da_queue(rx, tk));
+            task_manager.spawn("da_monitoring", |tk| {
+                Arc::clone(&service.monitoring).run(tk)
+            });
         }
 
         Ok(service)
@@ -159,6 +205,7 @@ impl RollupBlueprint for BitcoinRollup {
         BitcoinVerifier::new(RollupParams {
             to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
             to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
+            network: BitcoinNetwork::from_network(self.network),
         })
     }
 
@@ -250,6 +297,10 @@ impl RollupBlueprint for BitcoinRollup {
         }
     }
 
+    fn create_vm(&self, ledger_db: LedgerDB) -> Self::Vm {
+        Risc0BonsaiHost::new(ledger_db)
+    }
+
     #[instrument(level = "trace", skip_all)]
     async fn create_prover_service(
         &self,
@@ -258,7 +309,7 @@ impl RollupBlueprint for BitcoinRollup {
         ledger_db: LedgerDB,
         proof_sampling_number: usize,
     ) -> Self::ProverService {
-        let vm = Risc0BonsaiHost::new(ledger_db.clone());
+        let vm = self.create_vm(ledger_db.clone());
         // let vm = SP1Host::new(
         //     include_bytes!("../guests/sp1/batch-prover-bitcoin/elf/zkvm-elf"),
         //     ledger_db.clone(),
@@ -271,6 +322,8 @@ impl RollupBlueprint for BitcoinRollup {
             ProverGuestRunConfig::ProveWithFakeProofs => {
                 ProofGenMode::ProveWithSamplingWithFakeProofs(proof_sampling_number)
             }
+            // Simulated inputs never reach the prover service
+            ProverGuestRunConfig::Simulate => ProofGenMode::Skip,
         };
 
         ParallelProverService::new_from_env(da_service.clone(), vm, proof_mode, ledger_db)
@@ -1,11 +1,15 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
-use citrea_common::rpc::register_healthcheck_rpc;
+use citrea_common::rpc::{
+    register_fork_schedule_rpc, register_healthcheck_rpc, register_state_diff_size_rpc,
+    register_sync_status_rpc, register_tx_soft_confirmation_rpc,
+};
 use citrea_common::tasks::manager::TaskManager;
-use citrea_common::FullNodeConfig;
-use citrea_primitives::forks::use_network_forks;
+use citrea_common::{FullNodeConfig, RpcConfig};
+use citrea_fullnode::commitment_proof::register_commitment_inclusion_proof_rpc;
 // use citrea_sp1::host::SP1Host;
 use citrea_risc0_adapter::host::Risc0BonsaiHost;
 use citrea_stf::genesis_config::StorageConfig;
@@ -25,7 +29,7 @@ use crate::{CitreaRollupBlueprint, Network};
 
 /// Rollup with MockDa
 pub struct MockDemoRollup {
-    _network: Network,
+    network: Network,
 }
 
 impl CitreaRollupBlueprint for MockDemoRollup {}
@@ -44,17 +48,23 @@ impl RollupBlueprint for MockDemoRollup {
     type ProverService = ParallelProverService<Self::DaService, Self::Vm>;
 
     fn new(network: Network) -> Self {
-        use_network_forks(network);
-        Self { _network: network }
+        Self { network }
+    }
+
+    fn network(&self) -> Network {
+        self.network
     }
 
     fn create_rpc_methods(
         &self,
         storage: &<Self::NativeContext as Spec>::Storage,
         ledger_db: &LedgerDB,
+        rpc_config: &RpcConfig,
         da_service: &Arc<Self::DaService>,
         sequencer_client_url: Option<String>,
+        read_only: bool,
         soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
+        task_manager: &mut TaskManager<()>,
     ) -> Result<jsonrpsee::RpcModule<()>, anyhow::Error> {
         // TODO set the sequencer address
         let sequencer = Address::new([0; 32]);
@@ -63,18 +73,51 @@ impl RollupBlueprint for MockDemoRollup {
             Self::NativeRuntime,
             Self::NativeContext,
             Self::DaService,
-        >(storage, ledger_db, da_service, sequencer)?;
+        >(
+            storage,
+            ledger_db,
+            rpc_config,
+            da_service,
+            sequencer,
+            soft_confirmation_rx.as_ref().map(|rx| rx.resubscribe()),
+        )?;
 
         crate::eth::register_ethereum::<Self::DaService>(
             da_service.clone(),
             storage.clone(),
             ledger_db.clone(),
             &mut rpc_methods,
-            sequencer_client_url,
+            rpc_config,
+            sequencer_client_url.clone(),
+            read_only,
             soft_confirmation_rx,
         )?;
 
-        register_healthcheck_rpc(&mut rpc_methods, ledger_db.clone())?;
+        register_healthcheck_rpc(
+            &mut rpc_methods,
+            ledger_db.clone(),
+            rpc_config.healthcheck_stall_multiple,
+            task_manager,
+        )?;
+
+        register_tx_soft_confirmation_rpc(&mut rpc_methods, ledger_db.clone(), storage.clone())?;
+
+        register_fork_schedule_rpc(&mut rpc_methods, ledger_db.clone())?;
+
+        register_state_diff_size_rpc(&mut rpc_methods, ledger_db.clone())?;
+
+        register_commitment_inclusion_proof_rpc(&mut rpc_methods, ledger_db.clone())?;
+
+        // The sequencer is the head itself, only the nodes following it report their sync status
+        if let Some(sequencer_client_url) = sequencer_client_url {
+            register_sync_status_rpc(
+                &mut rpc_methods,
+                ledger_db.clone(),
+                da_service.clone(),
+                &sequencer_client_url,
+                rpc_config.sync_status_lag_tolerance,
+            )?;
+        }
 
         Ok(rpc_methods)
     }
@@ -85,10 +128,14 @@ impl RollupBlueprint for MockDemoRollup {
         _require_wallet_check: bool,
         _task_manager: &mut TaskManager<()>,
     ) -> Result<Arc<Self::DaService>, anyhow::Error> {
-        Ok(Arc::new(MockDaService::new(
+        let mut da_service = MockDaService::new(
             rollup_config.da.sender_address.clone(),
             &rollup_config.da.db_path,
-        )))
+        );
+        da_service.set_send_transaction_delay(Duration::from_millis(
+            rollup_config.da.send_transaction_delay_ms,
+        ));
+        Ok(Arc::new(da_service))
     }
 
     fn create_da_verifier(&self) -> Self::DaVerifier {
@@ -127,6 +174,10 @@ impl RollupBlueprint for MockDemoRollup {
             .collect()
     }
 
+    fn create_vm(&self, ledger_db: LedgerDB) -> Self::Vm {
+        Risc0BonsaiHost::new(ledger_db)
+    }
+
     async fn create_prover_service(
         &self,
         proving_mode: ProverGuestRunConfig,
@@ -134,7 +185,7 @@ impl RollupBlueprint for MockDemoRollup {
         ledger_db: LedgerDB,
         proof_sampling_number: usize,
     ) -> Self::ProverService {
-        let vm = Risc0BonsaiHost::new(ledger_db.clone());
+        let vm = self.create_vm(ledger_db.clone());
 
         let proof_mode = match proving_mode {
             ProverGuestRunConfig::Skip => ProofGenMode::Skip,
@@ -143,6 +194,8 @@ impl RollupBlueprint for MockDemoRollup {
             ProverGuestRunConfig::ProveWithFakeProofs => {
                 ProofGenMode::ProveWithSamplingWithFakeProofs(proof_sampling_number)
             }
+            // Simulated inputs never reach the prover service
+            ProverGuestRunConfig::Simulate => ProofGenMode::Skip,
         };
 
         ParallelProverService::new(da_service.clone(), vm, proof_mode, 1, ledger_db)
@@ -1,27 +1,49 @@
-use std::sync::Arc;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
 
-use anyhow::anyhow;
+use alloy_primitives::Address;
+use anyhow::{anyhow, bail};
 use async_trait::async_trait;
-use citrea_batch_prover::CitreaBatchProver;
+use citrea_batch_prover::{BatchProofSimulator, CitreaBatchProver};
 use citrea_common::tasks::manager::TaskManager;
-use citrea_common::{BatchProverConfig, FullNodeConfig, LightClientProverConfig, SequencerConfig};
-use citrea_fullnode::CitreaFullnode;
+use citrea_common::{
+    BatchProverConfig, FullNodeConfig, LightClientProverConfig, SequencerConfig,
+    SequencerKeySchedule,
+};
+use citrea_evm::Evm;
+use citrea_fullnode::replay::{ReplayMismatch, SoftConfirmationReplayer};
+use citrea_fullnode::{BalanceReader, CitreaFullnode};
 use citrea_light_client_prover::runner::CitreaLightClientProver;
 use citrea_primitives::forks::get_forks;
+use citrea_pruning::evm_pruning_callback;
 use citrea_sequencer::CitreaSequencer;
+use citrea_stf::genesis_config::{export_genesis, GenesisManifest};
+use citrea_stf::runtime::Runtime;
+use citrea_stf::StfVerifier;
 use jsonrpsee::RpcModule;
+use reth_primitives::{BlockId, BlockNumberOrTag};
 use sov_db::ledger_db::migrations::LedgerDBMigrator;
 use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
 use sov_db::rocks_db_config::RocksdbConfig;
 use sov_db::schema::types::SoftConfirmationNumber;
-use sov_modules_api::Spec;
+use sov_db::state_db::StateDB;
+use sov_modules_api::default_context::{DefaultContext, ZkDefaultContext};
+use sov_modules_api::{Spec, StateKeys, WorkingSet};
 use sov_modules_rollup_blueprint::RollupBlueprint;
 use sov_modules_stf_blueprint::{Runtime as RuntimeTrait, StfBlueprint};
-use sov_rollup_interface::fork::ForkManager;
+use sov_prover_storage_manager::{ProverStorage, ProverStorageManager, SnapshotManager};
+use sov_rollup_interface::da::DaVerifier;
+use sov_rollup_interface::fork::{Fork, ForkManager};
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::zk::{Proof, ZkvmHost};
 use sov_state::storage::NativeStorage;
-use sov_stf_runner::InitVariant;
+use sov_state::ZkStorage;
+use sov_stf_runner::{InitVariant, ProverGuestRunConfig};
 use tokio::sync::broadcast;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
+
+use crate::metrics::start_metrics_exporter;
 
 mod bitcoin;
 mod mock;
@@ -51,6 +73,8 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
     where
         <Self::NativeContext as Spec>::Storage: NativeStorage,
     {
+        start_metrics_exporter(&rollup_config.telemetry.metrics)?;
+
         let mut task_manager = TaskManager::default();
         let da_service = self
             .create_da_service(&rollup_config, true, &mut task_manager)
@@ -67,15 +91,19 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         );
         migrator.migrate(rollup_config.storage.db_max_open_files)?;
 
-        let rocksdb_config = RocksdbConfig::new(
-            rollup_config.storage.path.as_path(),
-            rollup_config.storage.db_max_open_files,
-            None,
-        );
+        let rocksdb_config = rollup_config.storage.ledger_rocksdb_config();
         let ledger_db = self.create_ledger_db(&rocksdb_config);
         let genesis_config = self.create_genesis_config(runtime_genesis_paths, &rollup_config)?;
 
-        let mut storage_manager = self.create_storage_manager(&rollup_config)?;
+        let storage_manager = self.create_storage_manager(&rollup_config)?;
+        let mut storage_manager = self
+            .restore_unpersisted_state(
+                &rollup_config,
+                da_service.clone(),
+                &ledger_db,
+                storage_manager,
+            )
+            .await?;
         let prover_storage = storage_manager.create_finalized_storage()?;
 
         let (soft_confirmation_tx, soft_confirmation_rx) = broadcast::channel(10);
@@ -89,9 +117,12 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         let rpc_methods = self.create_rpc_methods(
             &prover_storage,
             &ledger_db,
+            &rollup_config.rpc,
             &da_service,
             None,
+            false,
             soft_confirmation_rx,
+            &mut task_manager,
         )?;
 
         let native_stf = StfBlueprint::new();
@@ -140,6 +171,7 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
             rollup_config.rpc,
             fork_manager,
             soft_confirmation_tx,
+            self.network(),
             task_manager,
         )
         .unwrap();
@@ -172,6 +204,8 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
     where
         <Self::NativeContext as Spec>::Storage: NativeStorage,
     {
+        start_metrics_exporter(&rollup_config.telemetry.metrics)?;
+
         let mut task_manager = TaskManager::default();
         let da_service = self
             .create_da_service(&rollup_config, false, &mut task_manager)
@@ -189,17 +223,21 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
 
         migrator.migrate(rollup_config.storage.db_max_open_files)?;
 
-        let rocksdb_config = RocksdbConfig::new(
-            rollup_config.storage.path.as_path(),
-            rollup_config.storage.db_max_open_files,
-            None,
-        );
+        let rocksdb_config = rollup_config.storage.ledger_rocksdb_config();
 
         let ledger_db = self.create_ledger_db(&rocksdb_config);
 
         let genesis_config = self.create_genesis_config(runtime_genesis_paths, &rollup_config)?;
 
-        let mut storage_manager = self.create_storage_manager(&rollup_config)?;
+        let storage_manager = self.create_storage_manager(&rollup_config)?;
+        let mut storage_manager = self
+            .restore_unpersisted_state(
+                &rollup_config,
+                da_service.clone(),
+                &ledger_db,
+                storage_manager,
+            )
+            .await?;
 
         let prover_storage = storage_manager.create_finalized_storage()?;
 
@@ -215,9 +253,12 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         let rpc_methods = self.create_rpc_methods(
             &prover_storage,
             &ledger_db,
+            &rollup_config.rpc,
             &da_service,
-            Some(runner_config.sequencer_client_url.clone()),
+            (!runner_config.read_only).then(|| runner_config.sequencer_client_url.clone()),
+            runner_config.read_only,
             soft_confirmation_rx,
+            &mut task_manager,
         )?;
 
         let native_stf = StfBlueprint::new();
@@ -259,6 +300,23 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         let mut fork_manager = ForkManager::new(get_forks(), current_l2_height.0);
         fork_manager.register_handler(Box::new(ledger_db.clone()));
 
+        if rollup_config.storage.archive && runner_config.pruning_config.is_some() {
+            bail!("Pruning can not be enabled on an archive node");
+        }
+        let evm_pruning_callback = runner_config.pruning_config.as_ref().map(|_| {
+            evm_pruning_callback(
+                prover_storage.clone(),
+                storage_manager.accessory_state_pruner(),
+            )
+        });
+
+        // Balances are only compared if there are accounts to compare
+        let balance_reader = runner_config
+            .consistency_check
+            .as_ref()
+            .filter(|config| !config.balance_addresses.is_empty())
+            .map(|_| evm_balance_reader(prover_storage.clone()));
+
         let runner = CitreaFullnode::new(
             runner_config,
             rollup_config.public_keys,
@@ -271,6 +329,8 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
             code_commitments_by_spec,
             fork_manager,
             soft_confirmation_tx,
+            evm_pruning_callback,
+            balance_reader,
             task_manager,
         )?;
 
@@ -304,6 +364,8 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
     where
         <Self::NativeContext as Spec>::Storage: NativeStorage,
     {
+        start_metrics_exporter(&rollup_config.telemetry.metrics)?;
+
         let mut task_manager = TaskManager::default();
         let da_service = self
             .create_da_service(&rollup_config, true, &mut task_manager)
@@ -316,11 +378,7 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         );
         migrator.migrate(rollup_config.storage.db_max_open_files)?;
 
-        let rocksdb_config = RocksdbConfig::new(
-            rollup_config.storage.path.as_path(),
-            rollup_config.storage.db_max_open_files,
-            None,
-        );
+        let rocksdb_config = rollup_config.storage.ledger_rocksdb_config();
         let ledger_db = self.create_ledger_db(&rocksdb_config);
 
         let prover_service = self
@@ -338,7 +396,15 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
 
         let genesis_config = self.create_genesis_config(runtime_genesis_paths, &rollup_config)?;
 
-        let mut storage_manager = self.create_storage_manager(&rollup_config)?;
+        let storage_manager = self.create_storage_manager(&rollup_config)?;
+        let mut storage_manager = self
+            .restore_unpersisted_state(
+                &rollup_config,
+                da_service.clone(),
+                &ledger_db,
+                storage_manager,
+            )
+            .await?;
         let prover_storage = storage_manager.create_finalized_storage()?;
 
         let (soft_confirmation_tx, soft_confirmation_rx) = broadcast::channel(10);
@@ -353,9 +419,12 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         let rpc_methods = self.create_rpc_methods(
             &prover_storage,
             &ledger_db,
+            &rollup_config.rpc,
             &da_service,
             Some(runner_config.sequencer_client_url.clone()),
+            false,
             soft_confirmation_rx,
+            &mut task_manager,
         )?;
 
         let native_stf = StfBlueprint::new();
@@ -385,13 +454,25 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
 
         let code_commitments_by_spec = self.get_batch_proof_code_commitments();
         let elfs_by_spec = self.get_batch_proof_elfs();
+        // The effective schedule, with the overrides of the rollup config
+        let forks = get_forks();
+        let simulator = match prover_config.proving_mode {
+            ProverGuestRunConfig::Simulate => Some(create_batch_proof_simulator(
+                self.create_vm(ledger_db.clone()),
+                self.create_da_verifier(),
+                rollup_config.public_keys.sequencer_key_schedule(),
+                rollup_config.public_keys.sequencer_da_pub_keys(),
+                forks,
+            )),
+            _ => None,
+        };
 
         let current_l2_height = ledger_db
             .get_head_soft_confirmation_height()
             .map_err(|e| anyhow!("Failed to get head soft confirmation: {}", e))?
             .unwrap_or(0);
 
-        let mut fork_manager = ForkManager::new(get_forks(), current_l2_height);
+        let mut fork_manager = ForkManager::new(forks, current_l2_height);
         fork_manager.register_handler(Box::new(ledger_db.clone()));
 
         let runner = CitreaBatchProver::new(
@@ -407,6 +488,7 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
             prover_config,
             code_commitments_by_spec,
             elfs_by_spec,
+            simulator,
             fork_manager,
             soft_confirmation_tx,
             task_manager,
@@ -438,16 +520,14 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         );
         migrator.migrate(rollup_config.storage.db_max_open_files)?;
 
+        start_metrics_exporter(&rollup_config.telemetry.metrics)?;
+
         let mut task_manager = TaskManager::default();
         let da_service = self
             .create_da_service(&rollup_config, true, &mut task_manager)
             .await?;
 
-        let rocksdb_config = RocksdbConfig::new(
-            rollup_config.storage.path.as_path(),
-            rollup_config.storage.db_max_open_files,
-            None,
-        );
+        let rocksdb_config = rollup_config.storage.ledger_rocksdb_config();
         let ledger_db = self.create_ledger_db(&rocksdb_config);
 
         let prover_service = self
@@ -471,9 +551,12 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         let rpc_methods = self.create_rpc_methods(
             &prover_storage,
             &ledger_db,
+            &rollup_config.rpc,
             &da_service,
             Some(runner_config.sequencer_client_url.clone()),
+            false,
             None,
+            &mut task_manager,
         )?;
 
         let batch_prover_code_commitments_by_spec = self.get_batch_proof_code_commitments();
@@ -505,4 +588,214 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
 
         Ok((runner, rpc_methods))
     }
+
+    /// Runs the batch proof guest on a circuit input archived by the batch prover.
+    /// `ledger_path` is only used to back the zkVM host's proving sessions.
+    fn prove_circuit_input_from_file(
+        &self,
+        input_path: &Path,
+        ledger_path: &Path,
+        with_proof: bool,
+    ) -> Result<Proof, anyhow::Error> {
+        let rocksdb_config = RocksdbConfig::new(ledger_path, None, None);
+        let ledger_db = self.create_ledger_db(&rocksdb_config);
+        let vm = self.create_vm(ledger_db);
+
+        citrea_batch_prover::prove_from_file(
+            vm,
+            &self.get_batch_proof_elfs(),
+            input_path,
+            with_proof,
+        )
+    }
+
+    /// Finalized L2 blocks are written to the state database in the background,
+    /// so the ones not yet written when the node stopped are applied again from the ledger.
+    /// If the ledger does not have their transactions, it is rolled back for the blocks to be synced again.
+    async fn restore_unpersisted_state(
+        &self,
+        rollup_config: &FullNodeConfig<Self::DaConfig>,
+        da_service: Arc<Self::DaService>,
+        ledger_db: &LedgerDB,
+        storage_manager: ProverStorageManager<Self::DaSpec>,
+    ) -> Result<ProverStorageManager<Self::DaSpec>, anyhow::Error>
+    where
+        <Self::NativeContext as Spec>::Storage: NativeStorage,
+    {
+        let Some(persisted_l2_height) = storage_manager.persisted_l2_height()? else {
+            return Ok(storage_manager);
+        };
+        let Some(head_l2_height) = ledger_db.get_head_soft_confirmation_height()? else {
+            return Ok(storage_manager);
+        };
+        if head_l2_height <= persisted_l2_height {
+            return Ok(storage_manager);
+        }
+
+        let unpersisted = persisted_l2_height + 1..=head_l2_height;
+        warn!(
+            "State is persisted up to L2 height {}, restoring L2 blocks {} to {}",
+            persisted_l2_height,
+            unpersisted.start(),
+            unpersisted.end()
+        );
+
+        let has_tx_bodies = ledger_db
+            .get_soft_confirmation_range(
+                &(SoftConfirmationNumber(*unpersisted.start())
+                    ..=SoftConfirmationNumber(*unpersisted.end())),
+            )?
+            .iter()
+            .all(|soft_confirmation| soft_confirmation.txs.iter().all(|tx| tx.body.is_some()));
+        if !has_tx_bodies {
+            warn!(
+                "L2 blocks were stored without transaction bodies, rolling the ledger back to L2 height {}",
+                persisted_l2_height
+            );
+            ledger_db.rollback_to_l2_height(persisted_l2_height)?;
+            return Ok(storage_manager);
+        }
+
+        let mut replayer = SoftConfirmationReplayer::<_, _, Self::NativeRuntime>::new(
+            da_service,
+            StfBlueprint::new(),
+            storage_manager,
+            ledger_db.clone(),
+            rollup_config.public_keys.sequencer_key_schedule(),
+            None,
+        );
+        replayer.restore(unpersisted).await?;
+        Ok(replayer.into_storage_manager())
+    }
+
+    /// Re-executes the stored L2 blocks in `l2_heights` on the node's historical state,
+    /// returning the ones which do not match the stored results.
+    /// The node must not be running.
+    async fn replay_soft_confirmations(
+        &self,
+        rollup_config: FullNodeConfig<Self::DaConfig>,
+        l2_heights: RangeInclusive<u64>,
+        spec_override: Option<SpecId>,
+    ) -> Result<Vec<ReplayMismatch>, anyhow::Error>
+    where
+        <Self::NativeContext as Spec>::Storage: NativeStorage,
+    {
+        let mut task_manager = TaskManager::default();
+        let da_service = self
+            .create_da_service(&rollup_config, false, &mut task_manager)
+            .await?;
+
+        let rocksdb_config = rollup_config.storage.ledger_rocksdb_config();
+        let ledger_db = self.create_ledger_db(&rocksdb_config);
+        let storage_manager = self.create_storage_manager(&rollup_config)?;
+
+        let mut replayer = SoftConfirmationReplayer::<_, _, Self::NativeRuntime>::new(
+            da_service,
+            StfBlueprint::new(),
+            storage_manager,
+            ledger_db,
+            rollup_config.public_keys.sequencer_key_schedule(),
+            spec_override,
+        );
+        let mismatches = replayer.replay(l2_heights).await;
+
+        task_manager.abort().await;
+        mismatches
+    }
+
+    /// Writes the state after the L2 block at `l2_height` to `out_dir` as genesis files,
+    /// along with the manifest of the exported chain.
+    /// The node must not be running.
+    fn export_genesis(
+        &self,
+        rollup_config: &FullNodeConfig<Self::DaConfig>,
+        l2_height: u64,
+        out_dir: &Path,
+    ) -> Result<GenesisManifest, anyhow::Error> {
+        let rocksdb_config = rollup_config.storage.ledger_rocksdb_config();
+        let ledger_db = self.create_ledger_db(&rocksdb_config);
+        let soft_confirmation = ledger_db
+            .get_soft_confirmation_by_number(&SoftConfirmationNumber(l2_height))?
+            .ok_or_else(|| anyhow!("L2 block {} is not stored", l2_height))?;
+
+        // Opened read-only next to the storage manager, which holds the lock
+        let state_db = StateDB::<SnapshotManager>::setup_read_only_schema_db(&rocksdb_config)?;
+        let keys: &StateKeys = &|prefix, key_len| {
+            let keys = StateDB::<SnapshotManager>::iter_keys(&state_db, prefix, key_len)?;
+            Ok(Box::new(keys))
+        };
+
+        let mut storage_manager = self.create_storage_manager(rollup_config)?;
+        // The historical storage on a height is the state before its block
+        let storage = storage_manager.create_historical_storage_on_l2_height(l2_height + 1)?;
+        let mut working_set = WorkingSet::new(storage);
+
+        export_genesis::<Self::NativeContext, Self::DaSpec>(
+            out_dir,
+            keys,
+            l2_height,
+            soft_confirmation.state_root,
+            &mut working_set,
+        )
+    }
+}
+
+/// Creates the reader of EVM balances at the end of L2 blocks from the given finalized storage
+fn evm_balance_reader(storage: ProverStorage<SnapshotManager>) -> BalanceReader {
+    Arc::new(move |address: Address, l2_height| {
+        let evm = Evm::<DefaultContext>::default();
+        let mut working_set = WorkingSet::new(storage.clone());
+        evm.get_balance(
+            address,
+            Some(BlockId::Number(BlockNumberOrTag::Number(l2_height))),
+            &mut working_set,
+        )
+        .map_err(|e| anyhow!("{}", e.message()))
+    })
+}
+
+/// Runs the batch proof circuit on the simulated guest of `vm`, natively in the process,
+/// for the batch prover in simulate proving mode.
+/// The circuit is run the way the guests run it, with the configured sequencer keys and `forks`.
+fn create_batch_proof_simulator<Vm, DaV>(
+    vm: Vm,
+    da_verifier: DaV,
+    sequencer_pub_keys: SequencerKeySchedule,
+    sequencer_da_pub_keys: Vec<Vec<u8>>,
+    forks: &'static [Fork],
+) -> BatchProofSimulator
+where
+    Vm: ZkvmHost + Send + Sync + 'static,
+    DaV: DaVerifier + Send + Sync + 'static,
+{
+    let stf_verifier: StfVerifier<DaV, ZkDefaultContext, Runtime<ZkDefaultContext, DaV::Spec>> =
+        StfVerifier::new(StfBlueprint::new(), da_verifier);
+    let stf_verifier = Mutex::new(stf_verifier);
+
+    Arc::new(move |input| {
+        let mut vm = vm.clone();
+        vm.add_hint(input);
+        let guest = vm.simulate_with_hints();
+
+        // Keys are taken from the configured schedule like the circuit takes its own,
+        // never from the input
+        let sequencer_pub_keys = sequencer_pub_keys.activations();
+        let sequencer_da_pub_keys: Vec<&[u8]> =
+            sequencer_da_pub_keys.iter().map(Vec::as_slice).collect();
+        // The circuit panics on rejected inputs while the verifier is locked,
+        // the verifier itself keeps no state between inputs
+        let mut stf_verifier = stf_verifier.lock().unwrap_or_else(PoisonError::into_inner);
+        stf_verifier
+            .run_batch_proof_guest(
+                &guest,
+                ZkStorage::new(),
+                &sequencer_pub_keys,
+                &sequencer_da_pub_keys,
+                forks,
+            )
+            .map_err(|e| anyhow!("DA verification failed: {:?}", e))?;
+
+        // Cycles are only counted when running in the zkVM
+        Ok(None)
+    })
 }
@@ -94,6 +94,21 @@ fn test_helper(
             batch_requests_limit: 50,
             enable_subscriptions: true,
             max_subscriptions_per_connection: 100,
+            max_soft_confirmation_hashes_per_request: 100,
+            max_verified_proofs_slot_range: 1000,
+            healthcheck_stall_multiple: 3.0,
+            sync_status_lag_tolerance: 5,
+            max_logs_block_range: 1_000,
+            max_logs_per_response: 10_000,
+            filter_timeout_secs: 300,
+            max_filters_per_connection: 100,
+            max_filters: 10_000,
+            admin_token: None,
+            rate_limit: Default::default(),
+            method_filter: Default::default(),
+            cors: Default::default(),
+            compression: Default::default(),
+            gas_price_oracle: Default::default(),
         };
 
         queries_test_runner(test_queries, rpc_config).await;
@@ -1,12 +1,18 @@
 use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use alloy_primitives::{Address, U64};
 use anyhow::bail;
 use async_trait::async_trait;
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use bitcoin_da::helpers::parsers::{parse_light_client_transaction, ParsedLightClientTransaction};
 use bitcoin_da::service::{BitcoinService, BitcoinServiceConfig, FINALITY_DEPTH};
-use bitcoin_da::spec::RollupParams;
+use bitcoin_da::spec::{BitcoinNetwork, RollupParams};
+use bitcoin_da::verifier::BitcoinVerifier;
+use citrea_batch_prover::rpc::BatchProverRpcClient;
+use citrea_common::rpc::BlockStateDiffSize;
 use citrea_common::tasks::manager::TaskManager;
 use citrea_e2e::config::{
     BatchProverConfig, ProverGuestRunConfig, SequencerConfig, SequencerMempoolConfig,
@@ -18,10 +24,17 @@ use citrea_e2e::node::{Config, NodeKind};
 use citrea_e2e::test_case::{TestCase, TestCaseRunner};
 use citrea_e2e::traits::NodeT;
 use citrea_e2e::Result;
-use citrea_primitives::{TO_BATCH_PROOF_PREFIX, TO_LIGHT_CLIENT_PREFIX};
+use citrea_primitives::{MAX_TXBODY_SIZE, TO_BATCH_PROOF_PREFIX, TO_LIGHT_CLIENT_PREFIX};
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::rpc_params;
 use sov_ledger_rpc::LedgerRpcClient;
-use sov_rollup_interface::da::{DaData, SequencerCommitment};
+use sov_rollup_interface::da::{
+    BlobReaderTrait, DaData, DaDataLightClient, DaNamespace, DaVerifier, SequencerCommitment,
+    VersionedDaData,
+};
 use sov_rollup_interface::rpc::VerifiedBatchProofResponse;
+use sov_rollup_interface::services::da::DaService;
+use sov_rollup_interface::spec::SpecId;
 use tokio::time::sleep;
 
 use super::get_citrea_path;
@@ -91,6 +104,19 @@ impl TestCase for BasicProverTest {
             .wait_for_l1_height(finalized_height, None)
             .await?;
 
+        // Proving queue must be drained once the finalized height is scanned
+        let proving_status = batch_prover
+            .client
+            .http_client()
+            .get_proving_status()
+            .await?;
+        assert_eq!(proving_status.pending_proving_sessions, 0);
+        assert_eq!(proving_status.ongoing_proofs, 0);
+        assert!(proving_status.l1_heights_awaiting_proof.is_empty());
+        assert!(proving_status.executing_sessions.is_empty());
+        assert!(proving_status.parallel_proof_limit > 0);
+        assert!(!proving_status.blocked_on_parallel_proof_limit);
+
         // Wait for batch proof tx to hit mempool
         da.wait_mempool_len(2, None).await?;
 
@@ -118,6 +144,45 @@ impl TestCase for BasicProverTest {
                 state_diff_size,
                 compressed_state_diff.len()
             );
+
+            // Both nodes record the same per block state diff sizes, which add up to
+            // at least the size of the merged state diff of the commitment
+            let range_params = rpc_params![
+                U64::from(1),
+                U64::from(min_soft_confirmations_per_commitment)
+            ];
+            let sequencer_sizes: Vec<Option<BlockStateDiffSize>> = sequencer
+                .client
+                .http_client()
+                .request("citrea_getBlockStateDiffSizeRange", range_params.clone())
+                .await?;
+            let full_node_sizes: Vec<Option<BlockStateDiffSize>> = full_node
+                .client
+                .http_client()
+                .request("citrea_getBlockStateDiffSizeRange", range_params)
+                .await?;
+            assert_eq!(sequencer_sizes, full_node_sizes);
+            let block_sizes = sequencer_sizes
+                .into_iter()
+                .collect::<Option<Vec<_>>>()
+                .expect("State diff sizes of the committed blocks must be recorded");
+            assert_eq!(
+                block_sizes.len() as u64,
+                min_soft_confirmations_per_commitment
+            );
+
+            let last_block_size: Option<BlockStateDiffSize> = full_node
+                .client
+                .http_client()
+                .request(
+                    "citrea_getBlockStateDiffSize",
+                    rpc_params![U64::from(min_soft_confirmations_per_commitment)],
+                )
+                .await?;
+            assert_eq!(last_block_size, block_sizes.last().copied());
+
+            let total_size: u64 = block_sizes.iter().map(|size| size.size).sum();
+            assert!(total_size >= borshed_state_diff.len() as u64);
         }
 
         Ok(())
@@ -191,6 +256,11 @@ impl TestCase for SkipPreprovenCommitmentsTest {
                 .display()
                 .to_string(),
             monitoring: Default::default(),
+            fee_bump_after_blocks: 0,
+            min_fee_rate: 1,
+            max_fee_rate: 100,
+            fee_rate_override: None,
+            proof_chunk_threshold: MAX_TXBODY_SIZE,
         };
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
@@ -200,6 +270,7 @@ impl TestCase for SkipPreprovenCommitmentsTest {
                 RollupParams {
                     to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
                     to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
+                    network: BitcoinNetwork::Regtest,
                 },
                 tx,
             )
@@ -207,8 +278,9 @@ impl TestCase for SkipPreprovenCommitmentsTest {
             .unwrap(),
         );
 
-        self.task_manager
-            .spawn(|tk| bitcoin_da_service.clone().run_da_queue(rx, tk));
+        self.task_manager.spawn("da_queue", |tk| {
+            bitcoin_da_service.clone().run_da_queue(rx, tk)
+        });
 
         // Generate FINALIZED DA block.
         da.generate(FINALITY_DEPTH).await?;
@@ -273,6 +345,7 @@ impl TestCase for SkipPreprovenCommitmentsTest {
         bitcoin_da_service
             .send_transaction_with_fee_rate(
                 DaData::SequencerCommitment(commitments.first().unwrap().clone()),
+                SpecId::Fork1,
                 1,
             )
             .await
@@ -522,3 +595,173 @@ async fn parallel_proving_test() -> Result<()> {
         .run()
         .await
 }
+
+/// Tests that a batch proof larger than `proof_chunk_threshold` is sent in chunks
+/// and reassembled by both the full node and the light client extraction.
+/// Chunks referenced by wtxid are only written after Fork1, so the proof is sent as Fork2.
+///
+/// # Flow
+/// 1. Sends an inflated Fork2 proof through a DA service with a small chunk threshold
+/// 2. Verifies the full node extraction reassembles the original proof
+/// 3. Verifies the light client blobs carry the reassembled proof and pass the DA verifier
+/// 4. Verifies a block missing one of the chunks is rejected
+#[derive(Default)]
+struct ChunkedProofTest {
+    task_manager: TaskManager<()>,
+}
+
+// Small enough to split the inflated proof into multiple chunks
+// while keeping the commit/reveal chain under the mempool ancestor limit
+const PROOF_CHUNK_THRESHOLD: usize = 1000;
+
+#[async_trait]
+impl TestCase for ChunkedProofTest {
+    async fn run_test(&mut self, f: &mut TestFramework) -> Result<()> {
+        let da = f.bitcoin_nodes.get(0).unwrap();
+        let sequencer = f.sequencer.as_ref().unwrap();
+
+        let da_private_key = sequencer
+            .config()
+            .rollup
+            .da
+            .da_private_key
+            .as_ref()
+            .unwrap()
+            .clone();
+        let da_public_key = SecretKey::from_str(&da_private_key)
+            .unwrap()
+            .public_key(&Secp256k1::new())
+            .serialize()
+            .to_vec();
+
+        let bitcoin_da_service_config = BitcoinServiceConfig {
+            node_url: format!(
+                "http://127.0.0.1:{}/wallet/{}",
+                da.config.rpc_port,
+                NodeKind::Bitcoin
+            ),
+            node_username: da.config.rpc_user.clone(),
+            node_password: da.config.rpc_password.clone(),
+            network: bitcoin::Network::Regtest,
+            da_private_key: Some(da_private_key),
+            tx_backup_dir: Self::test_config()
+                .dir
+                .join("tx_backup_dir")
+                .display()
+                .to_string(),
+            monitoring: Default::default(),
+            fee_bump_after_blocks: 0,
+            min_fee_rate: 1,
+            max_fee_rate: 100,
+            fee_rate_override: None,
+            proof_chunk_threshold: PROOF_CHUNK_THRESHOLD,
+        };
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let bitcoin_da_service = Arc::new(
+            BitcoinService::new_with_wallet_check(
+                bitcoin_da_service_config,
+                RollupParams {
+                    to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
+                    to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
+                    network: BitcoinNetwork::Regtest,
+                },
+                tx,
+            )
+            .await
+            .unwrap(),
+        );
+
+        self.task_manager.spawn("da_queue", |tk| {
+            bitcoin_da_service.clone().run_da_queue(rx, tk)
+        });
+
+        // Pseudo random bytes do not compress, so the proof is split into 5 chunks
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let proof: Vec<u8> = (0..PROOF_CHUNK_THRESHOLD * 5 - 100)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect();
+
+        bitcoin_da_service
+            .send_transaction_with_fee_rate(DaData::ZKProof(proof.clone()), SpecId::Fork2, 1)
+            .await
+            .unwrap();
+
+        // 5 chunk commit/reveal pairs and the aggregate commit/reveal
+        da.wait_mempool_len(12, None).await?;
+
+        let block_hash = da.generate(1).await?[0];
+        let block = bitcoin_da_service.get_block_by_hash(block_hash).await?;
+
+        // Full node extraction
+        let proofs = bitcoin_da_service
+            .extract_relevant_zk_proofs(&block, &da_public_key)
+            .await?;
+        assert_eq!(proofs, vec![proof.clone()]);
+
+        // Light client extraction
+        let (mut blobs, inclusion_proof, completeness_proof) = bitcoin_da_service
+            .extract_relevant_blobs_with_proof(&block, DaNamespace::ToLightClientProver);
+        // Chunk reveals are a part of the completeness proof
+        assert!(completeness_proof.len() >= 6);
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].sender.0, da_public_key);
+        assert_eq!(
+            DaDataLightClient::decode_versioned(blobs[0].full_data()).unwrap(),
+            DaDataLightClient::Complete(proof)
+        );
+
+        let verifier = BitcoinVerifier::new(RollupParams {
+            to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
+            to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
+            network: BitcoinNetwork::Regtest,
+        });
+        assert!(verifier
+            .verify_transactions(
+                &block.header,
+                &blobs,
+                inclusion_proof,
+                completeness_proof,
+                DaNamespace::ToLightClientProver,
+            )
+            .is_ok());
+
+        // A partial chunk set must not produce a truncated proof
+        let mut partial_block = block.clone();
+        let chunk_idx = partial_block
+            .txdata
+            .iter()
+            .position(|tx| {
+                matches!(
+                    parse_light_client_transaction(tx),
+                    Ok(ParsedLightClientTransaction::ChunkV2(_))
+                )
+            })
+            .unwrap();
+        partial_block.txdata.remove(chunk_idx);
+        assert!(bitcoin_da_service
+            .extract_relevant_zk_proofs(&partial_block, &da_public_key)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    async fn cleanup(&self) -> Result<()> {
+        self.task_manager.abort().await;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn chunked_proof_test() -> Result<()> {
+    TestCaseRunner::new(ChunkedProofTest::default())
+        .set_citrea_path(get_citrea_path())
+        .run()
+        .await
+}
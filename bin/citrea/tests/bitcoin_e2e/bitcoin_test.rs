@@ -1,20 +1,32 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use alloy_primitives::U64;
 use anyhow::bail;
 use async_trait::async_trait;
+use bitcoin::hashes::Hash;
 use bitcoin::Txid;
 use bitcoin_da::monitoring::TxStatus;
 use bitcoin_da::rpc::DaRpcClient;
-use bitcoin_da::service::FINALITY_DEPTH;
+use bitcoin_da::service::{BitcoinService, BitcoinServiceConfig, FINALITY_DEPTH};
+use bitcoin_da::spec::{BitcoinNetwork, RollupParams};
 use bitcoincore_rpc::RpcApi;
+use citrea_common::tasks::manager::TaskManager;
 use citrea_e2e::bitcoin::BitcoinNode;
-use citrea_e2e::config::TestCaseConfig;
+use citrea_e2e::config::{SequencerConfig, TestCaseConfig};
 use citrea_e2e::framework::TestFramework;
+use citrea_e2e::node::NodeKind;
 use citrea_e2e::test_case::{TestCase, TestCaseRunner};
-use citrea_e2e::traits::Restart;
+use citrea_e2e::traits::{NodeT, Restart};
 use citrea_e2e::Result;
+use citrea_primitives::{MAX_TXBODY_SIZE, TO_BATCH_PROOF_PREFIX, TO_LIGHT_CLIENT_PREFIX};
+use citrea_sequencer::SequencerRpcClient;
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::MerkleTree;
 use sov_ledger_rpc::LedgerRpcClient;
+use sov_rollup_interface::da::{DaData, SequencerCommitment};
+use sov_rollup_interface::services::da::DaService;
+use sov_rollup_interface::spec::SpecId;
 use tokio::time::sleep;
 
 use super::get_citrea_path;
@@ -248,6 +260,62 @@ async fn test_da_monitoring() -> Result<()> {
         .await
 }
 
+struct DaStatusTest;
+
+#[async_trait]
+impl TestCase for DaStatusTest {
+    async fn run_test(&mut self, f: &mut TestFramework) -> Result<()> {
+        let da = f.bitcoin_nodes.get(0).unwrap();
+        let sequencer = f.sequencer.as_mut().unwrap();
+
+        let status = sequencer.client.http_client().da_get_status().await?;
+        assert_eq!(status.network, "regtest");
+        assert_eq!(status.block_height, da.get_block_count().await?);
+        assert!(status.spendable_balance > 0);
+        assert!(status.commitment_fee_rate >= status.proof_fee_rate);
+
+        let min_soft_confirmations_per_commitment =
+            sequencer.min_soft_confirmations_per_commitment();
+
+        for _ in 0..min_soft_confirmations_per_commitment {
+            sequencer.client.send_publish_batch_request().await?;
+        }
+
+        // Wait for the sequencer commitment to hit the mempool
+        da.wait_mempool_len(2, None).await?;
+        let mempool = da.get_raw_mempool().await?;
+
+        sleep(Duration::from_secs(1)).await;
+        let sent_status = sequencer.client.http_client().da_get_status().await?;
+        // The commitment is paid from the wallet
+        assert!(sent_status.spendable_balance < status.spendable_balance);
+        assert_eq!(sent_status.unconfirmed_txs, 2);
+        let last_submission = sent_status.last_submission.unwrap();
+        assert!(mempool.contains(&last_submission.txid));
+        assert_eq!(last_submission.confirmations, 0);
+
+        da.generate(1).await?;
+
+        sleep(Duration::from_secs(1)).await;
+        let confirmed_status = sequencer.client.http_client().da_get_status().await?;
+        assert_eq!(confirmed_status.block_height, sent_status.block_height + 1);
+        assert_eq!(confirmed_status.unconfirmed_txs, 0);
+        let confirmed_submission = confirmed_status.last_submission.unwrap();
+        assert_eq!(confirmed_submission.txid, last_submission.txid);
+        assert_eq!(confirmed_submission.confirmations, 1);
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_da_status() -> Result<()> {
+    TestCaseRunner::new(DaStatusTest)
+        .set_citrea_path(get_citrea_path())
+        .run()
+        .await
+}
+
 struct CpfpFeeBumpingTest;
 
 #[async_trait]
@@ -476,3 +544,223 @@ async fn test_cpfp_fee_bump() -> Result<()> {
         .run()
         .await
 }
+
+/// Tests that the DA queue rebuilds a stuck commitment with a higher fee via RBF
+///
+/// # Flow
+/// 1. Sends a commitment through a DA service with fee bumping enabled in its config
+/// 2. Lowers the fee of the commitment txs so that they are left out of blocks
+/// 3. Verifies the commitment is replaced after `fee_bump_after_blocks` blocks
+/// 4. Verifies the bumped commitment is mined and processed by the full node and batch prover
+#[derive(Default)]
+struct RbfFeeBumpingTest {
+    task_manager: TaskManager<()>,
+}
+
+const FEE_BUMP_AFTER_BLOCKS: u64 = 3;
+const COMMITMENT_L2_BLOCKS: u64 = 4;
+
+#[async_trait]
+impl TestCase for RbfFeeBumpingTest {
+    fn test_config() -> TestCaseConfig {
+        TestCaseConfig {
+            with_sequencer: true,
+            with_full_node: true,
+            with_batch_prover: true,
+            ..Default::default()
+        }
+    }
+
+    fn sequencer_config() -> SequencerConfig {
+        // The commitment is sent by the test, never by the sequencer
+        SequencerConfig {
+            min_soft_confirmations_per_commitment: 1000,
+            ..Default::default()
+        }
+    }
+
+    async fn run_test(&mut self, f: &mut TestFramework) -> Result<()> {
+        let sequencer = f.sequencer.as_ref().unwrap();
+        let full_node = f.full_node.as_ref().unwrap();
+        let batch_prover = f.batch_prover.as_ref().unwrap();
+        let da = f.bitcoin_nodes.get(0).unwrap();
+
+        // Fee bumping is opt-in, the nodes of the framework run without it.
+        // Signed with the sequencer DA key, so the commitment is accepted by the other nodes
+        let da_config = &da.config;
+        let bitcoin_da_service_config = BitcoinServiceConfig {
+            node_url: format!(
+                "http://127.0.0.1:{}/wallet/{}",
+                da_config.rpc_port,
+                NodeKind::Bitcoin
+            ),
+            node_username: da_config.rpc_user.clone(),
+            node_password: da_config.rpc_password.clone(),
+            network: bitcoin::Network::Regtest,
+            da_private_key: sequencer.config().rollup.da.da_private_key.clone(),
+            tx_backup_dir: Self::test_config()
+                .dir
+                .join("tx_backup_dir")
+                .display()
+                .to_string(),
+            monitoring: Default::default(),
+            fee_bump_after_blocks: FEE_BUMP_AFTER_BLOCKS,
+            min_fee_rate: 1,
+            max_fee_rate: 100,
+            fee_rate_override: None,
+            proof_chunk_threshold: MAX_TXBODY_SIZE,
+        };
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let bitcoin_da_service = Arc::new(
+            BitcoinService::new_with_wallet_check(
+                bitcoin_da_service_config,
+                RollupParams {
+                    to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
+                    to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
+                    network: BitcoinNetwork::Regtest,
+                },
+                tx,
+            )
+            .await?,
+        );
+        self.task_manager.spawn("da_queue", |tk| {
+            bitcoin_da_service.clone().run_da_queue(rx, tk)
+        });
+
+        for _ in 0..COMMITMENT_L2_BLOCKS {
+            sequencer.client.send_publish_batch_request().await?;
+        }
+
+        let mut soft_confirmation_hashes = vec![];
+        for l2_height in 1..=COMMITMENT_L2_BLOCKS {
+            let soft_confirmation = sequencer
+                .client
+                .http_client()
+                .get_soft_confirmation_by_number(U64::from(l2_height), None)
+                .await?
+                .unwrap();
+            soft_confirmation_hashes.push(soft_confirmation.hash);
+        }
+        let commitment = SequencerCommitment {
+            merkle_root: MerkleTree::<Sha256>::from_leaves(&soft_confirmation_hashes)
+                .root()
+                .unwrap(),
+            l2_start_block_number: 1,
+            l2_end_block_number: COMMITMENT_L2_BLOCKS,
+        };
+        let reveal_txid: [u8; 32] = bitcoin_da_service
+            .send_transaction(DaData::SequencerCommitment(commitment), SpecId::Fork1)
+            .await?
+            .into();
+        let reveal_txid = Txid::from_byte_array(reveal_txid);
+
+        // Wait for seqcommitments txs to hit mempool
+        da.wait_mempool_len(2, None).await?;
+
+        let original_txids = da.get_raw_mempool().await?;
+        assert!(original_txids.contains(&reveal_txid));
+
+        // Artificially lower the fee of the commitment so that it is never mined
+        for txid in &original_txids {
+            da.call::<bool>(
+                "prioritisetransaction",
+                &[txid.to_string().into(), 0.into(), (-100_000).into()],
+            )
+            .await?;
+        }
+
+        // Not bumped before the threshold
+        da.generate(1).await?;
+        let mempool = da.get_raw_mempool().await?;
+        assert_eq!(mempool.len(), 2);
+        assert!(original_txids.iter().all(|txid| mempool.contains(txid)));
+
+        da.generate(FEE_BUMP_AFTER_BLOCKS - 1).await?;
+
+        // Wait for the DA queue to replace the stuck commitment
+        let start = std::time::Instant::now();
+        let bumped_txids = loop {
+            let mempool = da.get_raw_mempool().await?;
+            if mempool.len() == 2 && mempool.iter().all(|txid| !original_txids.contains(txid)) {
+                break mempool;
+            }
+            if start.elapsed() > Duration::from_secs(60) {
+                bail!("Commitment was not fee bumped in time");
+            }
+            sleep(Duration::from_millis(500)).await;
+        };
+
+        let Some(TxStatus::Replaced { by_txid }) = bitcoin_da_service
+            .monitoring
+            .get_tx_status(&reveal_txid)
+            .await
+        else {
+            bail!("Stuck reveal tx should be replaced")
+        };
+        assert!(bumped_txids.contains(&by_txid));
+
+        let bump_count = bitcoin_da_service
+            .monitoring
+            .get_bump_count(&reveal_txid)
+            .await;
+        assert_eq!(bump_count, Some(1));
+
+        let (bumped_reveal_txid, bumped_reveal_tx) =
+            bitcoin_da_service.monitoring.get_last_tx().await.unwrap();
+        assert_eq!(bumped_reveal_txid, by_txid);
+        assert_eq!(bumped_reveal_tx.bump_count, 1);
+
+        // Bumped commitment is mined in the next block
+        da.generate(1).await?;
+        let commitment_height = da.get_block_count().await?;
+        let block = da.get_block(&da.get_best_block_hash().await?).await?;
+        let block_txids = block
+            .txdata
+            .iter()
+            .map(|tx| tx.compute_txid())
+            .collect::<Vec<_>>();
+        assert!(bumped_txids.iter().all(|txid| block_txids.contains(txid)));
+
+        da.generate(FINALITY_DEPTH - 1).await?;
+        let finalized_height = da.get_finalized_height().await?;
+        assert!(finalized_height >= commitment_height);
+
+        full_node.wait_for_l1_height(finalized_height, None).await?;
+        batch_prover
+            .wait_for_l1_height(finalized_height, None)
+            .await?;
+
+        for commitments in [
+            full_node
+                .client
+                .http_client()
+                .get_sequencer_commitments_on_slot_by_number(U64::from(commitment_height))
+                .await?,
+            batch_prover
+                .client
+                .http_client()
+                .get_sequencer_commitments_on_slot_by_number(U64::from(commitment_height))
+                .await?,
+        ] {
+            let commitments = commitments.unwrap();
+            assert_eq!(commitments.len(), 1);
+            assert_eq!(commitments[0].l2_start_block_number, 1);
+            assert_eq!(commitments[0].l2_end_block_number, COMMITMENT_L2_BLOCKS);
+        }
+
+        Ok(())
+    }
+
+    async fn cleanup(&self) -> Result<()> {
+        self.task_manager.abort().await;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_rbf_fee_bump() -> Result<()> {
+    TestCaseRunner::new(RbfFeeBumpingTest::default())
+        .set_citrea_path(get_citrea_path())
+        .run()
+        .await
+}
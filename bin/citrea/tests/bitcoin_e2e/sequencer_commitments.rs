@@ -6,7 +6,6 @@ use async_trait::async_trait;
 use bitcoin::hashes::Hash;
 use bitcoin_da::service::{get_relevant_blobs_from_txs, FINALITY_DEPTH};
 use bitcoincore_rpc::RpcApi;
-use borsh::BorshDeserialize;
 use citrea_e2e::bitcoin::BitcoinNode;
 use citrea_e2e::config::{SequencerConfig, TestCaseConfig};
 use citrea_e2e::framework::TestFramework;
@@ -18,7 +17,7 @@ use citrea_primitives::TO_BATCH_PROOF_PREFIX;
 use rs_merkle::algorithms::Sha256;
 use rs_merkle::MerkleTree;
 use sov_ledger_rpc::LedgerRpcClient;
-use sov_rollup_interface::da::{BlobReaderTrait, DaData};
+use sov_rollup_interface::da::{BlobReaderTrait, DaData, VersionedDaData};
 use sov_rollup_interface::rpc::SequencerCommitmentResponse;
 use tokio::time::sleep;
 
@@ -309,7 +308,7 @@ impl SequencerSendCommitmentsToDaTest {
 
         let data = BlobReaderTrait::full_data(&mut blob);
 
-        let commitment = DaData::try_from_slice(data).unwrap();
+        let commitment = DaData::decode_versioned(data).unwrap();
 
         matches!(commitment, DaData::SequencerCommitment(_));
 
@@ -324,7 +323,7 @@ impl SequencerSendCommitmentsToDaTest {
                 sequencer
                     .client
                     .http_client()
-                    .get_soft_confirmation_by_number(U64::from(i))
+                    .get_soft_confirmation_by_number(U64::from(i), None)
                     .await?
                     .unwrap(),
             );
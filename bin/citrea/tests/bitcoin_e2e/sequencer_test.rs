@@ -115,7 +115,7 @@ impl TestCase for SequencerMissedDaBlocksTest {
             let soft_confirmation = sequencer
                 .client
                 .http_client()
-                .get_soft_confirmation_by_number(U64::from(i))
+                .get_soft_confirmation_by_number(U64::from(i), None)
                 .await?
                 .unwrap();
 
@@ -0,0 +1,295 @@
+/// Testing the structured lifecycle events emitted by the nodes.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use citrea_common::{events, BatchProverConfig, SequencerConfig};
+use citrea_stf::genesis_config::GenesisPaths;
+use sov_mock_da::{MockAddress, MockDaService};
+use tracing::field::{Field, Visit};
+use tracing::{Dispatch, Event, Level, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
+
+use crate::evm::make_test_client;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l1_block,
+    wait_for_l2_block, wait_for_proof, wait_for_prover_l1_height, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+type CapturedEvents = Arc<Mutex<Vec<HashMap<String, String>>>>;
+
+/// Records the fields of every event it sees
+#[derive(Clone, Default)]
+struct CaptureLayer {
+    events: CapturedEvents,
+}
+
+struct FieldRecorder<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for FieldRecorder<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = HashMap::new();
+        event.record(&mut FieldRecorder(&mut fields));
+        self.events.lock().unwrap().push(fields);
+    }
+}
+
+/// Returns the captured events with the given name
+fn events_named(captured: &CapturedEvents, name: &str) -> Vec<HashMap<String, String>> {
+    captured
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|fields| fields.get("event").map(String::as_str) == Some(name))
+        .cloned()
+        .collect()
+}
+
+/// Asserts that an event with the given name was emitted with all the given fields
+fn assert_emitted(captured: &CapturedEvents, name: &str, required_fields: &[&str]) {
+    let emitted = events_named(captured, name);
+    assert!(!emitted.is_empty(), "No {} event was emitted", name);
+    for fields in emitted {
+        for field in required_fields {
+            assert!(
+                fields.contains_key(*field),
+                "{} event is missing the {} field: {:?}",
+                name,
+                field,
+                fields
+            );
+        }
+    }
+}
+
+/// Run the sequencer, prover and full node on threads that report to a capturing subscriber.
+/// Produce blocks until a commitment is submitted, proven and the proof is verified.
+/// Check if all lifecycle events are emitted with their fields.
+#[test]
+fn test_lifecycle_events() {
+    let layer = CaptureLayer::default();
+    let captured = layer.events.clone();
+    let filter = Targets::new().with_target("citrea_common::events", Level::INFO);
+    let dispatch = Dispatch::new(tracing_subscriber::registry().with(layer.with_filter(filter)));
+
+    // Only the threads of this runtime report to the capturing subscriber,
+    // so the events of tests running in parallel are not captured
+    let thread_dispatch = dispatch.clone();
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .on_thread_start(move || {
+            // Keep the subscriber for the lifetime of the thread
+            std::mem::forget(tracing::dispatcher::set_default(&thread_dispatch));
+        })
+        .build()
+        .unwrap();
+    tracing::dispatcher::with_default(&dispatch, || runtime.block_on(run_nodes()));
+
+    assert_emitted(
+        &captured,
+        events::L2_BLOCK_PRODUCED,
+        &[
+            "l2_height",
+            "l2_hash",
+            "l1_height",
+            "tx_count",
+            "tx_ordering_policy",
+            "duration_ms",
+        ],
+    );
+    assert_emitted(
+        &captured,
+        events::L2_BLOCK_APPLIED,
+        &[
+            "l2_height",
+            "l2_hash",
+            "l1_height",
+            "state_root",
+            "duration_ms",
+        ],
+    );
+    assert_emitted(
+        &captured,
+        events::COMMITMENT_SUBMITTED,
+        &[
+            "l2_start_height",
+            "l2_end_height",
+            "merkle_root",
+            "duration_ms",
+        ],
+    );
+    assert_emitted(
+        &captured,
+        events::COMMITMENT_PROCESSED,
+        &[
+            "l1_height",
+            "l2_start_height",
+            "l2_end_height",
+            "merkle_root",
+        ],
+    );
+    assert_emitted(
+        &captured,
+        events::PROOF_GENERATED,
+        &[
+            "l1_height",
+            "first_commitment_index",
+            "last_commitment_index",
+            "proof_size",
+            "duration_ms",
+        ],
+    );
+    assert_emitted(
+        &captured,
+        events::PROOF_VERIFIED,
+        &[
+            "l1_height",
+            "l2_start_height",
+            "l2_end_height",
+            "proof_size",
+            "duration_ms",
+        ],
+    );
+
+    // The produced blocks are numbered from the first one
+    let produced_heights: Vec<String> = events_named(&captured, events::L2_BLOCK_PRODUCED)
+        .into_iter()
+        .map(|fields| fields["l2_height"].clone())
+        .collect();
+    assert_eq!(produced_heights[..4], ["1", "2", "3", "4"]);
+
+    let submitted = &events_named(&captured, events::COMMITMENT_SUBMITTED)[0];
+    assert_eq!(submitted["l2_start_height"], "1");
+    assert_eq!(submitted["l2_end_height"], "4");
+
+    // The full node processes the commitment the sequencer submitted
+    let processed = events_named(&captured, events::COMMITMENT_PROCESSED);
+    assert!(processed
+        .iter()
+        .any(|fields| fields["merkle_root"] == submitted["merkle_root"]));
+}
+
+async fn run_nodes() {
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "prover", "full-node"]);
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig::default();
+
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await.unwrap();
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &prover_db_dir, &da_db_dir, NodeMode::Prover(seq_port));
+
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(BatchProverConfig {
+                proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
+                proof_sampling_number: 0,
+                enable_recovery: true,
+                ..Default::default()
+            }),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let prover_node_port = prover_node_port_rx.await.unwrap();
+    let prover_node_test_client = make_test_client(prover_node_port).await.unwrap();
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let full_node_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await.unwrap();
+
+    da_service.publish_test_block().await.unwrap();
+    wait_for_l1_block(&da_service, 2, None).await;
+
+    for i in 1..=4 {
+        test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&full_node_test_client, i, None).await;
+    }
+
+    // Commitment submitted
+    wait_for_l1_block(&da_service, 3, None).await;
+
+    // Full node sync commitment block
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 5, None).await;
+
+    // Prover proves the commitment and publishes the proof in l1 block #4
+    wait_for_prover_l1_height(&prover_node_test_client, 4, None)
+        .await
+        .unwrap();
+    wait_for_l1_block(&da_service, 4, None).await;
+
+    // Full node needs new L2 blocks to see the proof
+    for i in 6..=7 {
+        test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&full_node_test_client, i, None).await;
+    }
+    wait_for_proof(&full_node_test_client, 4, Some(Duration::from_secs(60))).await;
+
+    seq_task.abort();
+    prover_node_task.abort();
+    full_node_task.abort();
+}
@@ -0,0 +1,240 @@
+/// Tests for starting a new chain from the exported state of a stopped node
+use alloy_primitives::{Address, U256};
+use citrea::{CitreaRollupBlueprint, MockDemoRollup};
+use citrea_evm::smart_contracts::SimpleStorageContract;
+use citrea_stf::genesis_config::{GenesisPaths, GENESIS_MANIFEST_FILE};
+use sov_db::ledger_db::migrations::copy_db_dir_recursive;
+use sov_mock_da::MockDaSpec;
+use sov_modules_rollup_blueprint::RollupBlueprint;
+use sov_rollup_interface::Network;
+
+use crate::evm::{init_test_rollup, make_test_client};
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l2_block, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+/// Run a sequencer and a full node, transfer to some addresses and write to a contract.
+/// Export the state of the full node and start a new sequencer from it.
+/// Check that the new chain starts with the balances, nonces, code and storage of the exported one.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_genesis_export_round_trip() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+    let storage_dir = tempdir_with_children(&[
+        "DA",
+        "sequencer",
+        "full-node",
+        "genesis",
+        "new-DA",
+        "new-sequencer",
+    ]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+    let genesis_dir = storage_dir.path().join("genesis").to_path_buf();
+    let new_da_db_dir = storage_dir.path().join("new-DA").to_path_buf();
+    let new_sequencer_db_dir = storage_dir.path().join("new-sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(Default::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let full_node_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+
+    let seq_test_client = init_test_rollup(seq_port).await;
+    let full_node_test_client = init_test_rollup(full_node_port).await;
+
+    let contract = SimpleStorageContract::default();
+    let deploy_contract_req = seq_test_client
+        .deploy_contract(contract.byte_code(), None)
+        .await
+        .unwrap();
+    seq_test_client.send_publish_batch_request().await;
+    let contract_address = deploy_contract_req
+        .get_receipt()
+        .await?
+        .contract_address
+        .unwrap();
+
+    let _set_value_req = seq_test_client
+        .contract_transaction(contract_address, contract.set_call_data(42), None)
+        .await;
+    let recipients = [Address::repeat_byte(1), Address::repeat_byte(2)];
+    for (i, recipient) in recipients.iter().enumerate() {
+        let _pending = seq_test_client
+            .send_eth(*recipient, None, None, None, 1000 + i as u128)
+            .await
+            .unwrap();
+    }
+    seq_test_client.send_publish_batch_request().await;
+    // and an empty one
+    seq_test_client.send_publish_batch_request().await;
+
+    let l2_height = 3;
+    wait_for_l2_block(&full_node_test_client, l2_height, None).await;
+
+    let mut addresses = vec![seq_test_client.from_addr, contract_address];
+    addresses.extend(recipients);
+    let mut balances = vec![];
+    let mut nonces = vec![];
+    for address in &addresses {
+        balances.push(
+            full_node_test_client
+                .eth_get_balance(*address, None)
+                .await
+                .unwrap(),
+        );
+        nonces.push(
+            full_node_test_client
+                .eth_get_transaction_count(*address, None)
+                .await
+                .unwrap(),
+        );
+    }
+    let code = full_node_test_client
+        .eth_get_code(contract_address, None)
+        .await
+        .unwrap();
+    let value = full_node_test_client
+        .eth_get_storage_at(contract_address, U256::ZERO, None)
+        .await
+        .unwrap();
+    assert_eq!(value, U256::from(42));
+    let state_root = full_node_test_client
+        .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+        .await
+        .unwrap()
+        .state_root;
+
+    full_node_task.abort();
+    seq_task.abort();
+
+    // Copy the db to a new path with the same contents because
+    // the lock is not released on the db directory even though the task is aborted
+    let _ = copy_db_dir_recursive(&fullnode_db_dir, &storage_dir.path().join("fullnode_copy"));
+    let fullnode_db_dir = storage_dir.path().join("fullnode_copy");
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+
+    let manifest = MockDemoRollup::new(Network::Nightly).export_genesis(
+        &rollup_config,
+        l2_height,
+        &genesis_dir,
+    )?;
+    assert_eq!(manifest.chain_id, 5655);
+    assert_eq!(manifest.l2_height, l2_height);
+    assert_eq!(manifest.state_root, state_root);
+    assert!(genesis_dir.join(GENESIS_MANIFEST_FILE).exists());
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &new_sequencer_db_dir,
+        &new_da_db_dir,
+        NodeMode::SequencerNode,
+    );
+    let genesis_paths = GenesisPaths::from_dir(&genesis_dir);
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            genesis_paths,
+            None,
+            None,
+            rollup_config,
+            Some(Default::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await?;
+
+    assert_eq!(seq_test_client.eth_chain_id().await, manifest.chain_id);
+    for ((address, balance), nonce) in addresses.iter().zip(balances).zip(nonces) {
+        assert_eq!(
+            seq_test_client
+                .eth_get_balance(*address, None)
+                .await
+                .unwrap(),
+            balance
+        );
+        assert_eq!(
+            seq_test_client
+                .eth_get_transaction_count(*address, None)
+                .await
+                .unwrap(),
+            nonce
+        );
+    }
+    assert_eq!(
+        seq_test_client
+            .eth_get_code(contract_address, None)
+            .await
+            .unwrap(),
+        code
+    );
+    assert_eq!(
+        seq_test_client
+            .eth_get_storage_at(contract_address, U256::ZERO, None)
+            .await
+            .unwrap(),
+        value
+    );
+
+    // The new chain keeps going from the exported state
+    let set_value_req = seq_test_client
+        .contract_transaction(contract_address, contract.set_call_data(43), None)
+        .await;
+    seq_test_client.send_publish_batch_request().await;
+    set_value_req.watch().await.unwrap();
+    assert_eq!(
+        seq_test_client
+            .eth_get_storage_at(contract_address, U256::ZERO, None)
+            .await
+            .unwrap(),
+        U256::from(43)
+    );
+
+    seq_task.abort();
+
+    Ok(())
+}
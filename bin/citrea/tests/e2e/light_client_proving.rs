@@ -0,0 +1,149 @@
+/// Light client prover node related tests
+use citrea_common::{LightClientProverConfig, SequencerConfig};
+use citrea_stf::genesis_config::GenesisPaths;
+use sov_mock_da::{MockAddress, MockDaService};
+use sov_rollup_interface::services::da::DaService;
+
+use crate::evm::make_test_client;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l1_block,
+    wait_for_l2_block, wait_for_prover_l1_height, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+/// Run the sequencer and the light client prover.
+/// Produce light client proofs for a few L1 blocks.
+/// Check that the proofs and their outputs can be queried from the light client prover.
+#[tokio::test(flavor = "multi_thread")]
+async fn light_client_prover_serves_proofs() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "light-client-prover"]);
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let light_client_prover_db_dir = storage_dir.path().join("light-client-prover").to_path_buf();
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig::default();
+
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await.unwrap();
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    test_client.send_publish_batch_request().await;
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 2, None).await;
+
+    // The light client prover starts right after the L1 block of the first soft confirmation
+    let first_soft_confirmation = test_client
+        .ledger_get_soft_confirmation_by_number::<sov_mock_da::MockDaSpec>(1)
+        .await
+        .unwrap();
+    let initial_da_height = first_soft_confirmation.da_slot_height + 1;
+
+    let (light_client_prover_port_tx, light_client_prover_port_rx) =
+        tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &light_client_prover_db_dir,
+        &da_db_dir,
+        NodeMode::LightClientProver(seq_port),
+    );
+
+    let light_client_prover_task = tokio::spawn(async move {
+        start_rollup(
+            light_client_prover_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            Some(LightClientProverConfig {
+                initial_da_height,
+                enable_recovery: false,
+                ..Default::default()
+            }),
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let light_client_prover_port = light_client_prover_port_rx.await.unwrap();
+    let light_client_prover_test_client = make_test_client(light_client_prover_port).await.unwrap();
+
+    // No proof is generated before the initial DA height is processed
+    assert!(light_client_prover_test_client
+        .light_client_prover_get_last_light_client_proof()
+        .await
+        .unwrap()
+        .is_none());
+
+    let last_da_height = initial_da_height + 1;
+    for _ in initial_da_height..=last_da_height {
+        da_service.publish_test_block().await.unwrap();
+    }
+    wait_for_l1_block(&da_service, last_da_height, None).await;
+
+    wait_for_prover_l1_height(&light_client_prover_test_client, last_da_height, None)
+        .await
+        .unwrap();
+
+    for l1_height in initial_da_height..=last_da_height {
+        let proof = light_client_prover_test_client
+            .light_client_prover_get_light_client_proof_by_l1_height(l1_height)
+            .await
+            .unwrap()
+            .unwrap();
+        let output = proof.light_client_proof_output;
+        let l1_block = da_service.get_block_at(l1_height).await.unwrap();
+
+        assert_eq!(output.da_block_height, l1_height);
+        assert_eq!(output.da_block_hash, l1_block.header.hash.0);
+        assert!(output.unchained_batch_proofs_info.is_empty());
+    }
+
+    let last_proof = light_client_prover_test_client
+        .light_client_prover_get_last_light_client_proof()
+        .await
+        .unwrap()
+        .unwrap();
+    let last_proof_by_height = light_client_prover_test_client
+        .light_client_prover_get_light_client_proof_by_l1_height(last_da_height)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(last_proof.proof, last_proof_by_height.proof);
+    assert_eq!(
+        last_proof.light_client_proof_output.da_block_height,
+        last_da_height
+    );
+    assert_eq!(
+        last_proof.light_client_proof_output.state_root,
+        last_proof_by_height.light_client_proof_output.state_root
+    );
+
+    // Nothing is proven beyond the last processed L1 block
+    assert!(light_client_prover_test_client
+        .light_client_prover_get_light_client_proof_by_l1_height(last_da_height + 1)
+        .await
+        .unwrap()
+        .is_none());
+
+    seq_task.abort();
+    light_client_prover_task.abort();
+}
@@ -0,0 +1,124 @@
+/// Testing the Prometheus metrics served by the nodes.
+use std::str::FromStr;
+
+use alloy_primitives::Address;
+use citrea_common::SequencerConfig;
+use citrea_stf::genesis_config::GenesisPaths;
+
+use crate::evm::init_test_rollup;
+use crate::test_client::TestClient;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l2_block, NodeMode,
+    TEST_METRICS_PORT,
+};
+use crate::{
+    TEST_DATA_GENESIS_PATH, TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+};
+
+/// Returns the value of the first sample of `series` in a Prometheus text exposition
+fn sample(metrics: &str, series: &str) -> Option<f64> {
+    metrics
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let (name, value) = line.rsplit_once(' ')?;
+            let name = name.split('{').next()?;
+            (name == series).then(|| value.parse().ok())?
+        })
+}
+
+async fn scrape() -> String {
+    reqwest::get(format!("http://127.0.0.1:{}/metrics", TEST_METRICS_PORT))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap()
+}
+
+async fn publish_blocks(test_client: &TestClient, count: u64) {
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+    for _ in 0..count {
+        let _pending = test_client
+            .send_eth(addr, None, None, None, 1u128)
+            .await
+            .unwrap();
+        test_client.send_publish_batch_request().await;
+    }
+}
+
+/// Run the sequencer and publish blocks.
+/// Scrape the metrics endpoint and check if the key series are present.
+/// Publish more blocks and check if the series increased.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_metrics_endpoint() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    publish_blocks(&seq_test_client, 3).await;
+    wait_for_l2_block(&seq_test_client, 3, None).await;
+
+    // Metrics are shared with the other nodes of the test process,
+    // so only monotonic series are compared across scrapes
+    let before = scrape().await;
+    for series in [
+        "citrea_sequencer_current_l2_block",
+        "citrea_sequencer_mempool_txs",
+        "citrea_sequencer_soft_confirmation_txs_count",
+        "citrea_sequencer_block_production_execution_count",
+        "citrea_rpc_request_latency_seconds_count",
+    ] {
+        assert!(
+            sample(&before, series).is_some(),
+            "Missing series {}",
+            series
+        );
+    }
+
+    publish_blocks(&seq_test_client, 2).await;
+    wait_for_l2_block(&seq_test_client, 5, None).await;
+
+    let after = scrape().await;
+    for series in [
+        "citrea_sequencer_soft_confirmation_txs_count",
+        "citrea_sequencer_block_production_execution_count",
+        "citrea_rpc_request_latency_seconds_count",
+    ] {
+        assert!(
+            sample(&after, series).unwrap() > sample(&before, series).unwrap(),
+            "Series {} did not increase",
+            series
+        );
+    }
+
+    seq_task.abort();
+
+    Ok(())
+}
@@ -1,6 +1,16 @@
+mod events;
+mod genesis_export;
+mod light_client_proving;
+mod metrics;
 mod proving;
 mod reopen;
+mod rpc_compression;
+mod rpc_cors;
+mod rpc_method_filter;
+mod replay;
 mod sequencer_behaviour;
+mod sequencer_da_key_rotation;
+mod sequencer_key_rotation;
 mod sequencer_replacement;
 mod soft_confirmation_status;
 mod syncing;
@@ -10,14 +20,19 @@ use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 
-use alloy_primitives::{Address, U256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::Signer;
+use alloy_primitives::{Address, U256, U64};
+use citrea_common::rpc::ForkActivation;
 use citrea_common::{BatchProverConfig, SequencerConfig};
 use citrea_evm::smart_contracts::SimpleStorageContract;
-use citrea_primitives::forks::fork_from_block_number;
+use citrea_primitives::forks::{fork_from_block_number, get_forks};
 use citrea_stf::genesis_config::GenesisPaths;
 use reth_primitives::BlockNumberOrTag;
-use sov_mock_da::{MockAddress, MockDaService};
-use sov_rollup_interface::rpc::{LastVerifiedBatchProofResponse, SoftConfirmationStatus};
+use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
+use sov_rollup_interface::rpc::{
+    LastVerifiedBatchProofResponse, SoftConfirmationDetail, SoftConfirmationStatus,
+};
 use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::spec::SpecId;
 use tokio::task::JoinHandle;
@@ -96,6 +111,7 @@ async fn test_all_flow() {
                 proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
                 proof_sampling_number: 0,
                 enable_recovery: true,
+                ..Default::default()
             }),
             None,
             rollup_config,
@@ -315,6 +331,27 @@ async fn test_all_flow() {
         full_node_proof_data[0].proof_output
     );
 
+    // Both proofs should be returned in slot order by a single range query
+    let slot_proofs = full_node_test_client
+        .ledger_get_verified_batch_proofs_by_slot_range(0, 10, 10)
+        .await
+        .unwrap();
+    assert_eq!(slot_proofs.len(), 2);
+    assert_eq!(slot_proofs[0].height, 4);
+    assert_eq!(slot_proofs[0].proofs[0].proof, full_node_proof[0].proof);
+    assert_eq!(slot_proofs[1].height, 6);
+    assert_eq!(
+        slot_proofs[1].proofs[0].proof,
+        full_node_proof_data[0].proof
+    );
+
+    let slot_proofs = full_node_test_client
+        .ledger_get_verified_batch_proofs_by_slot_range(0, 10, 1)
+        .await
+        .unwrap();
+    assert_eq!(slot_proofs.len(), 1);
+    assert_eq!(slot_proofs[0].height, 4);
+
     let balance = full_node_test_client
         .eth_get_balance(addr, None)
         .await
@@ -415,6 +452,163 @@ async fn test_ledger_get_head_soft_confirmation() {
         .unwrap();
     assert_eq!(head_soft_confirmation_height, 2);
 
+    let first_soft_confirmation = seq_test_client
+        .ledger_get_soft_confirmation_by_number::<MockDaSpec>(1)
+        .await
+        .unwrap();
+    let soft_confirmations = seq_test_client
+        .ledger_get_soft_confirmations_by_hashes(vec![
+            head_soft_confirmation.hash,
+            [0; 32],
+            first_soft_confirmation.hash,
+        ])
+        .await
+        .unwrap();
+    assert_eq!(soft_confirmations.len(), 3);
+    assert_eq!(soft_confirmations[0].as_ref().unwrap().l2_height, 2);
+    assert!(soft_confirmations[1].is_none());
+    assert_eq!(soft_confirmations[2].as_ref().unwrap().l2_height, 1);
+
+    seq_task.abort();
+}
+
+/// Test RPC `citrea_getForkSchedule`
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_fork_schedule() {
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(SequencerConfig::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 1, None).await;
+
+    let schedule = seq_test_client.citrea_get_fork_schedule().await;
+    let expected_forks: Vec<_> = get_forks()
+        .iter()
+        .map(|fork| ForkActivation {
+            spec_id: fork.spec_id,
+            activation_l2_height: fork.activation_height,
+        })
+        .collect();
+    assert_eq!(schedule.forks, expected_forks);
+    assert_eq!(schedule.active_spec_id, fork_from_block_number(1).spec_id);
+
+    seq_task.abort();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ledger_soft_confirmation_detail_levels() {
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    // The first block only has system transactions
+    seq_test_client.send_publish_batch_request().await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+    for _ in 0..2 {
+        seq_test_client
+            .send_eth(addr, None, None, None, 1u128)
+            .await
+            .unwrap();
+    }
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 2, None).await;
+
+    for height in 1..=2 {
+        let block = seq_test_client
+            .eth_get_block_by_number(Some(BlockNumberOrTag::Number(height)))
+            .await;
+        let block_tx_hashes: Vec<[u8; 32]> =
+            block.transactions.hashes().map(|hash| hash.0).collect();
+        assert!(!block_tx_hashes.is_empty());
+
+        let soft_confirmation = seq_test_client
+            .ledger_get_soft_confirmation_by_number_with_detail(
+                height,
+                SoftConfirmationDetail::Hashes,
+            )
+            .await
+            .unwrap();
+        assert!(soft_confirmation.txs.is_none());
+        let tx_summaries = soft_confirmation.tx_summaries.unwrap();
+        assert_eq!(
+            tx_summaries
+                .iter()
+                .map(|summary| summary.hash)
+                .collect::<Vec<_>>(),
+            block_tx_hashes
+        );
+
+        for summary in tx_summaries {
+            let receipt = seq_test_client
+                .eth_get_transaction_receipt(summary.hash.into())
+                .await
+                .unwrap();
+            assert_eq!(summary.success, receipt.status());
+            assert_eq!(summary.gas_used, receipt.gas_used as u64);
+        }
+    }
+
+    let soft_confirmations = seq_test_client
+        .ledger_get_soft_confirmation_range_with_detail(1, 2, SoftConfirmationDetail::Minimal)
+        .await;
+    for soft_confirmation in soft_confirmations {
+        let soft_confirmation = soft_confirmation.unwrap();
+        assert!(soft_confirmation.txs.is_none());
+        assert!(soft_confirmation.tx_summaries.is_none());
+    }
+
+    let soft_confirmation = seq_test_client
+        .ledger_get_soft_confirmation_by_number::<MockDaSpec>(2)
+        .await
+        .unwrap();
+    assert!(soft_confirmation.txs.is_some());
+    assert!(soft_confirmation.tx_summaries.is_none());
+
     seq_task.abort();
 }
 
@@ -577,6 +771,59 @@ async fn execute_blocks(
 
 /// Deploy pre-fork contract, activate a fork and then check fetching the contract's code
 /// through RPC to make sure that the actual code is fetched properly pre and post fork.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ledger_subscribe_soft_confirmations() {
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    let soft_confirmation_rx = seq_test_client.subscribe_soft_confirmations().await;
+
+    for _ in 0..3 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&seq_test_client, 3, None).await;
+
+    for expected_height in 1..=3 {
+        let soft_confirmation = soft_confirmation_rx
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(soft_confirmation.l2_height, expected_height);
+
+        let stored = seq_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(expected_height)
+            .await
+            .unwrap();
+        assert_eq!(soft_confirmation.hash, stored.hash);
+    }
+
+    seq_task.abort();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_offchain_contract_storage() {
     // citrea::initialize_logging(tracing::Level::DEBUG);
@@ -708,3 +955,75 @@ async fn test_offchain_contract_storage() {
     }
     seq_task.abort();
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_node_validates_raw_transactions_before_forwarding() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let (seq_test_client, full_node_test_client, seq_task, full_node_task, addr) =
+        initialize_test(TestConfig {
+            sequencer_path: sequencer_db_dir,
+            fullnode_path: fullnode_db_dir,
+            da_path: da_db_dir,
+            ..Default::default()
+        })
+        .await;
+
+    // An account without any balance sends through the full node
+    let key = "0xdcf2cbdd171a21c480aa7f53d77f31bb102282b3ff099c78e3118b37348c72f7"
+        .parse::<PrivateKeySigner>()
+        .unwrap()
+        .with_chain_id(Some(seq_test_client.chain_id));
+    let poor_addr = key.address();
+    let poor_test_client = TestClient::new(
+        seq_test_client.chain_id,
+        key,
+        poor_addr,
+        full_node_test_client.rpc_addr,
+    )
+    .await
+    .unwrap();
+
+    let err = poor_test_client
+        .send_eth(addr, None, None, None, 1u128)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("insufficient funds"));
+
+    // Rejected locally, the sequencer never saw it
+    let txpool_status = seq_test_client.txpool_status().await;
+    assert_eq!(txpool_status.pending, U64::from(0));
+    assert_eq!(txpool_status.queued, U64::from(0));
+
+    // Valid transactions are forwarded
+    let pending = full_node_test_client
+        .send_eth(poor_addr, None, None, None, 1u128)
+        .await
+        .unwrap();
+    assert_eq!(seq_test_client.txpool_status().await.pending, U64::from(1));
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 1, None).await;
+    let block = full_node_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Latest))
+        .await;
+    assert!(block
+        .transactions
+        .as_hashes()
+        .unwrap()
+        .contains(pending.tx_hash()));
+
+    // A nonce already used in a block is stale
+    let err = full_node_test_client
+        .send_eth(poor_addr, None, None, Some(0), 1u128)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("nonce too low"));
+
+    seq_task.abort();
+    full_node_task.abort();
+}
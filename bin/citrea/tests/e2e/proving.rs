@@ -1,19 +1,30 @@
 /// Prover node, proving and full node proof verification related tests
 use std::time::Duration;
 
-use citrea_batch_prover::GroupCommitments;
+use citrea::{CitreaRollupBlueprint, MockDemoRollup};
+use citrea_batch_prover::{circuit_input_path, GroupCommitments};
 use citrea_common::{BatchProverConfig, SequencerConfig};
+use citrea_risc0_adapter::host::Risc0BonsaiHost;
 use citrea_stf::genesis_config::GenesisPaths;
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::MerkleTree;
 use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
+use sov_modules_rollup_blueprint::RollupBlueprint;
+use sov_rollup_interface::da::{DaData, SequencerCommitment};
 use sov_rollup_interface::rpc::SoftConfirmationStatus;
 use sov_rollup_interface::services::da::DaService;
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::zk::{BatchProofCircuitOutputV1, BatchProofOutputVersion, ZkvmHost};
+use sov_rollup_interface::Network;
 
 use crate::evm::make_test_client;
 use crate::test_helpers::{
     create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l1_block,
     wait_for_l2_block, wait_for_proof, wait_for_prover_l1_height, NodeMode,
 };
-use crate::TEST_DATA_GENESIS_PATH;
+use crate::{
+    TEST_DATA_GENESIS_PATH, TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+};
 
 /// Run the sequencer, prover and full node.
 /// Trigger proof production.
@@ -64,6 +75,7 @@ async fn full_node_verify_proof_and_store() {
                 proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
                 proof_sampling_number: 0,
                 enable_recovery: true,
+                ..Default::default()
             }),
             None,
             rollup_config,
@@ -114,6 +126,11 @@ async fn full_node_verify_proof_and_store() {
     // Full node sync commitment block
     test_client.send_publish_batch_request().await;
     wait_for_l2_block(&full_node_test_client, 5, None).await;
+    let commitment = full_node_test_client
+        .wait_for_commitment_covering(4, None)
+        .await;
+    assert_eq!(commitment.l2_start_block_number, 1);
+    assert_eq!(commitment.l2_end_block_number, 4);
 
     // wait here until we see from prover's rpc that it finished proving
     wait_for_prover_l1_height(&prover_node_test_client, 4, None)
@@ -146,6 +163,11 @@ async fn full_node_verify_proof_and_store() {
         .await
         .unwrap()[0]
         .clone();
+    let prover_proofs_hash = prover_node_test_client
+        .ledger_get_batch_proofs_by_slot_hash(third_block_hash.0)
+        .await
+        .unwrap();
+    assert_eq!(prover_proofs_hash, vec![prover_proof.clone()]);
 
     // The proof will be in l1 block #4 because prover publishes it after the commitment and
     // in mock da submitting proof and commitments creates a new block.
@@ -168,8 +190,14 @@ async fn full_node_verify_proof_and_store() {
     assert_eq!(prover_proof.proof, full_node_proof[0].proof);
 
     assert_eq!(prover_proof.proof_output, full_node_proof[0].proof_output);
+    assert_eq!(
+        full_node_proof[0].proof_output.output_version,
+        BatchProofOutputVersion::V1
+    );
 
     let proof_height = full_node_proof[0].proof_output.last_l2_height;
+    // Fork1 proofs do not commit to the first l2 height
+    assert_eq!(full_node_proof[0].proof_output.first_l2_height, 0);
     let soft_confirmation = full_node_test_client
         .ledger_get_soft_confirmation_by_number::<MockDaSpec>(proof_height)
         .await
@@ -179,6 +207,12 @@ async fn full_node_verify_proof_and_store() {
         full_node_proof[0].proof_output.final_state_root,
         soft_confirmation.state_root
     );
+    assert_eq!(
+        full_node_test_client
+            .ledger_get_soft_confirmation_by_hash(soft_confirmation.hash)
+            .await,
+        Some(soft_confirmation)
+    );
 
     full_node_test_client
         .ledger_get_soft_confirmation_status(5)
@@ -186,12 +220,9 @@ async fn full_node_verify_proof_and_store() {
         .unwrap();
 
     for i in 1..=4 {
-        let status = full_node_test_client
-            .ledger_get_soft_confirmation_status(i)
-            .await
-            .unwrap();
-
-        assert_eq!(status, SoftConfirmationStatus::Proven);
+        full_node_test_client
+            .wait_for_status(i, SoftConfirmationStatus::Proven, None)
+            .await;
     }
 
     seq_task.abort();
@@ -246,6 +277,7 @@ async fn test_batch_prover_prove_rpc() {
                 // Make it impossible for proving to happen
                 proof_sampling_number: 1_000_000,
                 enable_recovery: true,
+                ..Default::default()
             }),
             None,
             rollup_config,
@@ -333,6 +365,11 @@ async fn test_batch_prover_prove_rpc() {
         .await
         .unwrap()[0]
         .clone();
+    let prover_proofs_hash = prover_node_test_client
+        .ledger_get_batch_proofs_by_slot_hash(third_block_hash.0)
+        .await
+        .unwrap();
+    assert_eq!(prover_proofs_hash, vec![prover_proof.clone()]);
 
     // The proof will be in l1 block #4 because prover publishes it after the commitment and
     // in mock da submitting proof and commitments creates a new block.
@@ -362,15 +399,453 @@ async fn test_batch_prover_prove_rpc() {
         .unwrap();
 
     for i in 1..=4 {
-        let status = full_node_test_client
-            .ledger_get_soft_confirmation_status(i)
-            .await
-            .unwrap();
+        full_node_test_client
+            .wait_for_status(i, SoftConfirmationStatus::Proven, None)
+            .await;
+    }
+
+    seq_task.abort();
+    prover_node_task.abort();
+    full_node_task.abort();
+}
+
+/// Run the sequencer and a prover archiving its circuit inputs.
+/// Re-run the guest on the archived input and check that it
+/// produces the same output as the proof the prover submitted.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_prove_from_archived_circuit_input() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir =
+        tempdir_with_children(&["DA", "sequencer", "prover", "circuit-inputs", "replay"]);
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let circuit_input_dir = storage_dir.path().join("circuit-inputs").to_path_buf();
+    let replay_db_dir = storage_dir.path().join("replay").to_path_buf();
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig::default();
+
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await.unwrap();
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &prover_db_dir, &da_db_dir, NodeMode::Prover(seq_port));
+    let prover_config = BatchProverConfig {
+        proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
+        proof_sampling_number: 0,
+        enable_recovery: true,
+        circuit_input_dir: Some(circuit_input_dir.clone()),
+        keep_circuit_inputs: true,
+        ..Default::default()
+    };
+
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(prover_config),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let prover_node_port = prover_node_port_rx.await.unwrap();
+    let prover_node_test_client = make_test_client(prover_node_port).await.unwrap();
+
+    da_service.publish_test_block().await.unwrap();
+    wait_for_l1_block(&da_service, 2, None).await;
+
+    for _ in 0..4 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&test_client, 4, None).await;
+
+    // Commitment submitted
+    wait_for_l1_block(&da_service, 3, None).await;
+
+    // wait here until we see from prover's rpc that it finished proving
+    wait_for_prover_l1_height(&prover_node_test_client, 4, None)
+        .await
+        .unwrap();
+
+    let prover_proof = prover_node_test_client
+        .ledger_get_batch_proofs_by_slot_height(3)
+        .await
+        .unwrap()[0]
+        .clone();
+
+    // The input is kept after submission since `keep_circuit_inputs` is set
+    let input_path = circuit_input_path(&circuit_input_dir, 3, (0, 0));
+    assert!(input_path.exists());
+
+    let proof = MockDemoRollup::new(Network::Nightly)
+        .prove_circuit_input_from_file(&input_path, &replay_db_dir, false)
+        .unwrap();
+    let output = Risc0BonsaiHost::extract_output::<
+        MockDaSpec,
+        BatchProofCircuitOutputV1<MockDaSpec, [u8; 32]>,
+    >(&proof)
+    .unwrap();
+
+    let expected = prover_proof.proof_output;
+    assert_eq!(
+        output.initial_state_root.to_vec(),
+        expected.initial_state_root
+    );
+    assert_eq!(output.final_state_root.to_vec(), expected.final_state_root);
+    assert_eq!(
+        output.final_soft_confirmation_hash,
+        expected.final_soft_confirmation_hash
+    );
+    assert_eq!(output.state_diff, expected.state_diff);
+    assert_eq!(
+        output.sequencer_commitments_range,
+        expected.sequencer_commitments_range
+    );
+    assert_eq!(output.last_l2_height, expected.last_l2_height);
+
+    seq_task.abort();
+    prover_node_task.abort();
+}
+
+/// Run the sequencer without commitments, a prover limited to 8 soft confirmations per proof and the full node.
+/// Publish an L1 block with three commitments of 4 soft confirmations each.
+/// Check if the prover splits them into contiguous proofs with chained state roots
+/// and the full node marks the whole L2 range proven.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_batch_prover_splits_oversized_commitment_ranges() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "prover", "full-node"]);
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await?;
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &prover_db_dir, &da_db_dir, NodeMode::Prover(seq_port));
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(BatchProverConfig {
+                proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
+                proof_sampling_number: 0,
+                enable_recovery: true,
+                max_soft_confirmations_per_proof: 8,
+                ..Default::default()
+            }),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let prover_node_port = prover_node_port_rx.await.unwrap();
+    let prover_node_test_client = make_test_client(prover_node_port).await?;
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let full_node_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await?;
+
+    for _ in 0..12 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 12, None).await;
+
+    let mut commitments = vec![];
+    for l2_start in [1, 5, 9] {
+        let mut soft_confirmation_hashes = vec![];
+        for l2_height in l2_start..l2_start + 4 {
+            let soft_confirmation = test_client
+                .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+                .await
+                .unwrap();
+            soft_confirmation_hashes.push(soft_confirmation.hash);
+        }
+        commitments.push(SequencerCommitment {
+            merkle_root: MerkleTree::<Sha256>::from_leaves(&soft_confirmation_hashes)
+                .root()
+                .unwrap(),
+            l2_start_block_number: l2_start,
+            l2_end_block_number: l2_start + 3,
+        });
+    }
+
+    da_service
+        .publish_test_block_with_da_data(
+            commitments
+                .into_iter()
+                .map(DaData::SequencerCommitment)
+                .collect(),
+            SpecId::Fork1,
+        )
+        .await?;
+    let commitment_l1_height = da_service.get_height().await;
+
+    wait_for_prover_l1_height(&prover_node_test_client, commitment_l1_height, None).await?;
+
+    // 12 soft confirmations do not fit in a single proof, so the first two commitments
+    // are proven together and the last one on its own
+    let mut prover_proofs = prover_node_test_client
+        .ledger_get_batch_proofs_by_slot_height(commitment_l1_height)
+        .await
+        .unwrap();
+    prover_proofs.sort_by_key(|proof| proof.proof_output.sequencer_commitments_range);
+    assert_eq!(prover_proofs.len(), 2);
+    assert_eq!(
+        prover_proofs[0].proof_output.sequencer_commitments_range,
+        (0, 1)
+    );
+    assert_eq!(prover_proofs[0].proof_output.last_l2_height, 8);
+    assert_eq!(
+        prover_proofs[1].proof_output.sequencer_commitments_range,
+        (2, 2)
+    );
+    assert_eq!(prover_proofs[1].proof_output.last_l2_height, 12);
+    assert_eq!(
+        prover_proofs[0].proof_output.final_state_root,
+        prover_proofs[1].proof_output.initial_state_root
+    );
+    assert_eq!(
+        prover_proofs[0].proof_output.final_soft_confirmation_hash,
+        prover_proofs[1].proof_output.prev_soft_confirmation_hash
+    );
+
+    // Each proof is submitted in its own mock DA block
+    let last_proof_l1_height = commitment_l1_height + 2;
+    wait_for_l1_block(&da_service, last_proof_l1_height, None).await;
+
+    // Make the full node sync up to the proof blocks
+    for l2_height in 13..=14 {
+        test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&full_node_test_client, l2_height, None).await;
+    }
+
+    for proof_l1_height in commitment_l1_height + 1..=last_proof_l1_height {
+        wait_for_proof(
+            &full_node_test_client,
+            proof_l1_height,
+            Some(Duration::from_secs(60)),
+        )
+        .await;
+    }
 
-        assert_eq!(status, SoftConfirmationStatus::Proven);
+    for l2_height in 1..=12 {
+        full_node_test_client
+            .wait_for_status(l2_height, SoftConfirmationStatus::Proven, None)
+            .await;
     }
 
     seq_task.abort();
     prover_node_task.abort();
     full_node_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer without commitments and a prover in simulate mode.
+/// Publish an L1 block with a valid commitment and one with a corrupted merkle root.
+/// Check if the simulation flags the corrupted commitment range only,
+/// and nothing is proven or submitted to DA.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_batch_prover_simulate_mode() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "prover"]);
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await?;
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &prover_db_dir, &da_db_dir, NodeMode::Prover(seq_port));
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(BatchProverConfig {
+                proving_mode: sov_stf_runner::ProverGuestRunConfig::Simulate,
+                proof_sampling_number: 0,
+                enable_recovery: true,
+                max_soft_confirmations_per_proof: 4,
+                ..Default::default()
+            }),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let prover_node_port = prover_node_port_rx.await.unwrap();
+    let prover_node_test_client = make_test_client(prover_node_port).await?;
+
+    for _ in 0..8 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&prover_node_test_client, 8, None).await;
+
+    let mut commitments = vec![];
+    for l2_start in [1, 5] {
+        let mut soft_confirmation_hashes = vec![];
+        for l2_height in l2_start..l2_start + 4 {
+            let soft_confirmation = test_client
+                .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+                .await
+                .unwrap();
+            soft_confirmation_hashes.push(soft_confirmation.hash);
+        }
+        commitments.push(SequencerCommitment {
+            merkle_root: MerkleTree::<Sha256>::from_leaves(&soft_confirmation_hashes)
+                .root()
+                .unwrap(),
+            l2_start_block_number: l2_start,
+            l2_end_block_number: l2_start + 3,
+        });
+    }
+    // The soft confirmations of the second commitment do not match its merkle root
+    commitments[1].merkle_root = [1; 32];
+
+    da_service
+        .publish_test_block_with_da_data(
+            commitments
+                .into_iter()
+                .map(DaData::SequencerCommitment)
+                .collect(),
+            SpecId::Fork1,
+        )
+        .await?;
+    let commitment_l1_height = da_service.get_height().await;
+
+    wait_for_prover_l1_height(&prover_node_test_client, commitment_l1_height, None).await?;
+
+    // Each commitment fits in a proof of its own
+    let results = prover_node_test_client
+        .batch_prover_get_simulation_results(1, commitment_l1_height)
+        .await;
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].l1_height, commitment_l1_height);
+    assert_eq!(results[0].commitment_range, (0, 0));
+    assert!(results[0].success);
+    assert_eq!(results[0].failure, None);
+    assert_eq!(results[1].l1_height, commitment_l1_height);
+    assert_eq!(results[1].commitment_range, (1, 1));
+    assert!(!results[1].success);
+    assert!(results[1]
+        .failure
+        .as_ref()
+        .unwrap()
+        .contains("Invalid merkle root"));
+
+    // Nothing is proven or submitted to DA
+    assert!(prover_node_test_client
+        .ledger_get_batch_proofs_by_slot_height(commitment_l1_height)
+        .await
+        .is_none());
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(da_service.get_height().await, commitment_l1_height);
+
+    seq_task.abort();
+    prover_node_task.abort();
+
+    Ok(())
 }
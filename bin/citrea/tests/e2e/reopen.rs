@@ -5,18 +5,24 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use alloy_primitives::Address;
-use citrea_common::{BatchProverConfig, SequencerConfig};
+use citrea_common::db_tools::{read_node_status, rollback_to_l2_height, NodeKind};
+use citrea_common::{BatchProverConfig, SequencerConfig, TxBodyBackfillConfig};
 use citrea_stf::genesis_config::GenesisPaths;
 use reth_primitives::BlockNumberOrTag;
 use sov_db::ledger_db::migrations::copy_db_dir_recursive;
-use sov_mock_da::{MockAddress, MockDaService};
+use sov_db::ledger_db::{BatchProverLedgerOps, LedgerDB};
+use sov_db::rocks_db_config::RocksdbConfig;
+use sov_db::schema::types::StoredProvingSessionStatus;
+use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
+use sov_rollup_interface::rpc::SoftConfirmationStatus;
 use tokio::runtime::Runtime;
 use tokio::time::sleep;
 
 use crate::evm::{init_test_rollup, make_test_client};
 use crate::test_helpers::{
-    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l1_block,
-    wait_for_l2_block, wait_for_prover_l1_height, NodeMode,
+    create_default_rollup_config, start_rollup, start_sequencer_with_shutdown_signal,
+    tempdir_with_children, wait_for_l1_block, wait_for_l2_block, wait_for_prover_l1_height,
+    NodeMode,
 };
 use crate::TEST_DATA_GENESIS_PATH;
 
@@ -278,6 +284,130 @@ async fn test_reopen_sequencer() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Run the sequencer producing blocks on its own while txs keep coming in.
+/// Send the shutdown signal during block production.
+/// Check that txs are rejected while stopping, and that the sequencer continues
+/// from a consistent height with the txs left in the mempool after a restart.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequencer_graceful_shutdown() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let (shutdown_tx, shutdown_rx) = tokio::sync::mpsc::channel(1);
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        test_mode: false,
+        block_production_interval_ms: 200,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(start_sequencer_with_shutdown_signal(
+        seq_port_tx,
+        GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+        rollup_config,
+        sequencer_config.clone(),
+        shutdown_rx,
+    ));
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    let mut tx_hashes = vec![];
+    for _ in 0..10 {
+        let tx = seq_test_client
+            .send_eth(addr, None, None, None, 0u128)
+            .await
+            .unwrap();
+        tx_hashes.push(*tx.tx_hash());
+    }
+    wait_for_l2_block(&seq_test_client, 3, None).await;
+
+    // Some of these are still in the mempool when the signal arrives
+    for _ in 0..10 {
+        let tx = seq_test_client
+            .send_eth(addr, None, None, None, 0u128)
+            .await
+            .unwrap();
+        tx_hashes.push(*tx.tx_hash());
+    }
+    shutdown_tx.send(()).await.unwrap();
+
+    // RPC server is kept alive while the tasks are stopping
+    sleep(Duration::from_secs(1)).await;
+    assert!(seq_test_client
+        .send_eth(addr, None, None, None, 0u128)
+        .await
+        .is_err());
+
+    // The sequencer stops by itself, without being aborted
+    tokio::time::timeout(Duration::from_secs(30), seq_task)
+        .await
+        .expect("Sequencer did not shut down")??;
+
+    // Copy the db to a new path, lingering RPC connections may still hold the lock
+    let _ = copy_db_dir_recursive(
+        &sequencer_db_dir,
+        &storage_dir.path().join("sequencer_copy"),
+    );
+    let sequencer_db_dir = storage_dir.path().join("sequencer_copy");
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await?;
+
+    // Ledger and EVM agree on the head
+    let head_height = seq_test_client
+        .ledger_get_head_soft_confirmation_height()
+        .await
+        .unwrap();
+    let evm_head = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Latest))
+        .await;
+    assert!(head_height >= 3);
+    assert_eq!(evm_head.header.number, head_height);
+
+    wait_for_l2_block(&seq_test_client, head_height + 2, None).await;
+
+    // Txs left in the mempool are restored and included
+    for tx_hash in tx_hashes {
+        let mut receipt = seq_test_client.eth_get_transaction_receipt(tx_hash).await;
+        for _ in 0..50 {
+            if receipt.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(200)).await;
+            receipt = seq_test_client.eth_get_transaction_receipt(tx_hash).await;
+        }
+        assert!(receipt.is_some(), "Tx {} was lost on shutdown", tx_hash);
+    }
+
+    seq_task.abort();
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_reopen_prover() -> Result<(), anyhow::Error> {
     // citrea::initialize_logging(tracing::Level::DEBUG);
@@ -497,3 +627,614 @@ async fn test_reopen_prover() -> Result<(), anyhow::Error> {
     thread_kill_sender.send("kill").unwrap();
     Ok(())
 }
+
+/// Simulates a prover killed after generating a proof but before submitting it to DA,
+/// by storing the generated proof as the status of its proving session.
+/// The restarted prover must submit the stored proof without proving it again,
+/// which is observable since it is configured to never prove.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_reopen_prover_resumes_proving_session() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "prover", "idle-prover"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let idle_prover_db_dir = storage_dir.path().join("idle-prover").to_path_buf();
+
+    let da_service = MockDaService::new(MockAddress::default(), &da_db_dir);
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(SequencerConfig::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await?;
+
+    // Generates the proof
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+    let rollup_config =
+        create_default_rollup_config(true, &prover_db_dir, &da_db_dir, NodeMode::Prover(seq_port));
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(BatchProverConfig::default()),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+    let prover_node_port = prover_node_port_rx.await.unwrap();
+    let prover_node_test_client = make_test_client(prover_node_port).await?;
+
+    // Never proves, so a proof stored by it can only come from a resumed session
+    let idle_prover_config = BatchProverConfig {
+        proof_sampling_number: 1_000_000,
+        enable_recovery: true,
+        ..Default::default()
+    };
+    let (idle_prover_port_tx, idle_prover_port_rx) = tokio::sync::oneshot::channel();
+    let rollup_config = create_default_rollup_config(
+        true,
+        &idle_prover_db_dir,
+        &da_db_dir,
+        NodeMode::Prover(seq_port),
+    );
+    let config = idle_prover_config.clone();
+    let idle_prover_task = tokio::spawn(async {
+        start_rollup(
+            idle_prover_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(config),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+    let idle_prover_port = idle_prover_port_rx.await.unwrap();
+    let idle_prover_test_client = make_test_client(idle_prover_port).await?;
+
+    for _ in 0..3 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&seq_test_client, 3, None).await;
+
+    da_service.publish_test_block().await.unwrap();
+    wait_for_l1_block(&da_service, 2, None).await;
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 4, None).await;
+    da_service.publish_test_block().await.unwrap();
+    wait_for_l1_block(&da_service, 3, None).await;
+    // Block that contains the commitment
+    wait_for_l1_block(&da_service, 4, None).await;
+
+    wait_for_prover_l1_height(&prover_node_test_client, 5, None).await?;
+    // Contains the proof
+    wait_for_l1_block(&da_service, 5, None).await;
+    wait_for_prover_l1_height(&idle_prover_test_client, 5, None).await?;
+
+    let proofs = prover_node_test_client
+        .ledger_get_batch_proofs_by_slot_height(4)
+        .await
+        .unwrap();
+    assert_eq!(proofs.len(), 1);
+    let range = proofs[0].proof_output.sequencer_commitments_range;
+    assert!(idle_prover_test_client
+        .ledger_get_batch_proofs_by_slot_height(4)
+        .await
+        .is_none());
+
+    prover_node_task.abort();
+    idle_prover_task.abort();
+    sleep(Duration::from_secs(1)).await;
+
+    // Copy the db to a new path with the same contents because
+    // the lock is not released on the db directory even though the task is aborted
+    let idle_prover_copy_db_dir = storage_dir.path().join("idle_prover_copy");
+    let _ = copy_db_dir_recursive(&idle_prover_db_dir, &idle_prover_copy_db_dir);
+
+    // State of a prover killed after generating the proof
+    {
+        let ledger_db =
+            LedgerDB::with_config(&RocksdbConfig::new(&idle_prover_copy_db_dir, None, None))?;
+        ledger_db.put_batch_proving_session(
+            4,
+            range,
+            StoredProvingSessionStatus::Proven(proofs[0].proof.clone()),
+        )?;
+    }
+
+    let (idle_prover_port_tx, idle_prover_port_rx) = tokio::sync::oneshot::channel();
+    let rollup_config = create_default_rollup_config(
+        true,
+        &idle_prover_copy_db_dir,
+        &da_db_dir,
+        NodeMode::Prover(seq_port),
+    );
+    let idle_prover_task = tokio::spawn(async {
+        start_rollup(
+            idle_prover_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(idle_prover_config),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+    let idle_prover_port = idle_prover_port_rx.await.unwrap();
+    let idle_prover_test_client = make_test_client(idle_prover_port).await?;
+
+    // The stored proof is submitted on startup, before the block containing it is scanned
+    wait_for_l1_block(&da_service, 6, None).await;
+    wait_for_prover_l1_height(&idle_prover_test_client, 6, None).await?;
+    let resumed_proofs = idle_prover_test_client
+        .ledger_get_batch_proofs_by_slot_height(4)
+        .await
+        .unwrap();
+    assert_eq!(resumed_proofs.len(), 1);
+    assert_eq!(resumed_proofs[0].proof, proofs[0].proof);
+    assert_eq!(
+        resumed_proofs[0].proof_output.sequencer_commitments_range,
+        range
+    );
+
+    seq_task.abort();
+    idle_prover_task.abort();
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rollback_full_node() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let da_service = MockDaService::new(MockAddress::default(), &da_db_dir);
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment: 3,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let rollup_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+
+    let seq_test_client = init_test_rollup(seq_port).await;
+    let full_node_test_client = init_test_rollup(full_node_port).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    // L2 blocks 1-6 are committed on L1 block 2
+    for _ in 0..6 {
+        let _pending = seq_test_client
+            .send_eth(addr, None, None, None, 0u128)
+            .await
+            .unwrap();
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 6, None).await;
+    wait_for_l1_block(&da_service, 2, None).await;
+    full_node_test_client
+        .wait_for_status(6, SoftConfirmationStatus::Finalized, None)
+        .await;
+
+    // close full node
+    rollup_task.abort();
+
+    // Copy the db to a new path with the same contents because
+    // the lock is not released on the db directory even though the task is aborted
+    let _ = copy_db_dir_recursive(&fullnode_db_dir, &storage_dir.path().join("fullnode_copy"));
+    let fullnode_db_dir = storage_dir.path().join("fullnode_copy");
+
+    rollback_to_l2_height(&fullnode_db_dir, NodeKind::FullNode, 3)?;
+
+    // The commitment covers rolled back heights, so its L1 block is scanned again
+    let status = read_node_status(&fullnode_db_dir, NodeKind::FullNode)?;
+    assert_eq!(status.head_l2_height, Some(3));
+    assert_eq!(status.last_scanned_l1_height, Some(1));
+    assert_eq!(status.last_commitment_l2_height, Some(0));
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    // spin up the full node again from the rolled back state
+    let rollup_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+
+    let full_node_test_client = make_test_client(full_node_port).await?;
+
+    // The full node syncs the rolled back blocks again and finds the commitment on rescan
+    wait_for_l2_block(&full_node_test_client, 6, None).await;
+    for l2_height in 1..=6 {
+        full_node_test_client
+            .wait_for_status(l2_height, SoftConfirmationStatus::Finalized, None)
+            .await;
+    }
+
+    let seq_last_block = seq_test_client
+        .eth_get_block_by_number_with_detail(Some(BlockNumberOrTag::Latest))
+        .await;
+    let full_node_last_block = full_node_test_client
+        .eth_get_block_by_number_with_detail(Some(BlockNumberOrTag::Latest))
+        .await;
+
+    assert_eq!(full_node_last_block.header.number, 6);
+    assert_eq!(
+        seq_last_block.header.state_root,
+        full_node_last_block.header.state_root
+    );
+    assert_eq!(seq_last_block.header.hash, full_node_last_block.header.hash);
+
+    seq_task.abort();
+    rollup_task.abort();
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_read_only_full_node() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig::default();
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let rollup_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+
+    let seq_test_client = init_test_rollup(seq_port).await;
+    let full_node_test_client = init_test_rollup(full_node_port).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    for _ in 0..5 {
+        let _pending = seq_test_client
+            .send_eth(addr, None, None, None, 1u128)
+            .await
+            .unwrap();
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 5, None).await;
+
+    let synced_block = full_node_test_client
+        .eth_get_block_by_number_with_detail(Some(BlockNumberOrTag::Latest))
+        .await;
+    let synced_balance = full_node_test_client
+        .eth_get_balance(addr, None)
+        .await
+        .unwrap();
+
+    // close both nodes, the read-only node runs without a sequencer
+    rollup_task.abort();
+    seq_task.abort();
+
+    // Copy the db to a new path with the same contents because
+    // the lock is not released on the db directory even though the task is aborted
+    let _ = copy_db_dir_recursive(&fullnode_db_dir, &storage_dir.path().join("fullnode_copy"));
+    let fullnode_db_dir = storage_dir.path().join("fullnode_copy");
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let runner_config = rollup_config.runner.as_mut().unwrap();
+    runner_config.read_only = true;
+    runner_config.sequencer_client_url = String::new();
+
+    let rollup_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+
+    let full_node_test_client = make_test_client(full_node_port).await?;
+
+    // Queries are served from the synced data
+    let last_block = full_node_test_client
+        .eth_get_block_by_number_with_detail(Some(BlockNumberOrTag::Latest))
+        .await;
+    assert_eq!(last_block.header.number, 5);
+    assert_eq!(last_block.header.hash, synced_block.header.hash);
+    assert_eq!(
+        full_node_test_client
+            .eth_get_balance(addr, None)
+            .await
+            .unwrap(),
+        synced_balance
+    );
+    let soft_confirmation = full_node_test_client
+        .ledger_get_soft_confirmation_by_number::<MockDaSpec>(5)
+        .await
+        .unwrap();
+    assert_eq!(soft_confirmation.hash, synced_block.header.hash.0);
+
+    // Transactions are rejected
+    let err = full_node_test_client
+        .send_eth(addr, None, None, None, 1u128)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("read-only node"));
+
+    // The head stays where the copied data left off
+    sleep(Duration::from_secs(2)).await;
+    assert_eq!(
+        full_node_test_client
+            .ledger_get_head_soft_confirmation_height()
+            .await
+            .unwrap(),
+        5
+    );
+
+    rollup_task.abort();
+
+    Ok(())
+}
+
+/// Sync a full node without tx bodies, then restart it with tx bodies and backfill.
+/// Check if the bodies of the blocks synced before the restart match the sequencer's.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_backfill_tx_bodies() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig::default();
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        false,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let rollup_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+
+    let seq_test_client = init_test_rollup(seq_port).await;
+    let full_node_test_client = init_test_rollup(full_node_port).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    for _ in 0..5 {
+        for _ in 0..3 {
+            let _pending = seq_test_client
+                .send_eth(addr, None, None, None, 1u128)
+                .await
+                .unwrap();
+        }
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 5, None).await;
+
+    // Synced without bodies
+    for l2_height in 1..=5 {
+        let soft_confirmation = full_node_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        assert_eq!(soft_confirmation.txs, Some(vec![]));
+    }
+
+    rollup_task.abort();
+
+    // Copy the db to a new path with the same contents because
+    // the lock is not released on the db directory even though the task is aborted
+    let _ = copy_db_dir_recursive(&fullnode_db_dir, &storage_dir.path().join("fullnode_copy"));
+    let fullnode_db_dir = storage_dir.path().join("fullnode_copy");
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    rollup_config.runner.as_mut().unwrap().tx_body_backfill = Some(TxBodyBackfillConfig {
+        requests_per_second: 100,
+    });
+
+    let rollup_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await?;
+
+    // Blocks synced after the restart are stored with their bodies
+    let _pending = seq_test_client
+        .send_eth(addr, None, None, None, 1u128)
+        .await
+        .unwrap();
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 6, None).await;
+
+    for l2_height in 1..=6 {
+        let seq_soft_confirmation = seq_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        assert!(!seq_soft_confirmation.txs.as_ref().unwrap().is_empty());
+
+        let mut full_node_soft_confirmation;
+        let mut retries = 0;
+        loop {
+            full_node_soft_confirmation = full_node_test_client
+                .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+                .await
+                .unwrap();
+            if full_node_soft_confirmation.txs == seq_soft_confirmation.txs || retries == 30 {
+                break;
+            }
+            retries += 1;
+            sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(full_node_soft_confirmation, seq_soft_confirmation);
+    }
+
+    seq_task.abort();
+    rollup_task.abort();
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+/// Tests for re-executing the stored L2 blocks of a stopped node
+use alloy_primitives::Address;
+use citrea::{CitreaRollupBlueprint, MockDemoRollup};
+use citrea_common::tasks::manager::TaskManager;
+use citrea_common::SequencerConfig;
+use citrea_fullnode::replay::SoftConfirmationReplayer;
+use citrea_stf::genesis_config::GenesisPaths;
+use sov_db::ledger_db::migrations::copy_db_dir_recursive;
+use sov_db::ledger_db::BatchProverLedgerOps;
+use sov_db::rocks_db_config::RocksdbConfig;
+use sov_db::schema::types::SoftConfirmationNumber;
+use sov_modules_rollup_blueprint::RollupBlueprint;
+use sov_modules_stf_blueprint::StfBlueprint;
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::Network;
+
+use crate::evm::init_test_rollup;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l2_block, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_replay_full_node() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig::default();
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let rollup_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+
+    let seq_test_client = init_test_rollup(seq_port).await;
+    let full_node_test_client = init_test_rollup(full_node_port).await;
+
+    // 3 blocks with 2 transfers to different addresses each
+    for i in 0..3u8 {
+        for j in 0..2u8 {
+            let _pending = seq_test_client
+                .send_eth(Address::repeat_byte(i * 2 + j + 1), None, None, None, 1u128)
+                .await
+                .unwrap();
+        }
+        seq_test_client.send_publish_batch_request().await;
+    }
+    // and an empty one
+    seq_test_client.send_publish_batch_request().await;
+
+    wait_for_l2_block(&full_node_test_client, 4, None).await;
+
+    rollup_task.abort();
+
+    // Copy the db to a new path with the same contents because
+    // the lock is not released on the db directory even though the task is aborted
+    let _ = copy_db_dir_recursive(&fullnode_db_dir, &storage_dir.path().join("fullnode_copy"));
+    let fullnode_db_dir = storage_dir.path().join("fullnode_copy");
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+
+    let rollup_blueprint = MockDemoRollup::new(Network::Nightly);
+    let mismatches = rollup_blueprint
+        .replay_soft_confirmations(rollup_config.clone(), 1..=4, None)
+        .await?;
+    assert!(mismatches.is_empty());
+
+    // A block with a state diff that the replay does not reproduce
+    let mut task_manager = TaskManager::default();
+    let da_service = rollup_blueprint
+        .create_da_service(&rollup_config, false, &mut task_manager)
+        .await?;
+    let ledger_db =
+        rollup_blueprint.create_ledger_db(&RocksdbConfig::new(&fullnode_db_dir, None, None));
+    let mut replayer =
+        SoftConfirmationReplayer::<_, _, <MockDemoRollup as RollupBlueprint>::NativeRuntime>::new(
+            da_service,
+            StfBlueprint::new(),
+            rollup_blueprint.create_storage_manager(&rollup_config)?,
+            ledger_db.clone(),
+            rollup_config.public_keys.sequencer_key_schedule(),
+            Some(SpecId::Fork1),
+        );
+
+    let (state_root, mut state_diff) = replayer.execute(2, None).await?;
+    let (_, first_tx_state_diff) = replayer.execute(2, Some(1)).await?;
+    // Written by the second transfer only
+    let second_tx_key = state_diff
+        .keys()
+        .find(|key| !first_tx_state_diff.contains_key(*key))
+        .cloned()
+        .unwrap();
+    state_diff.insert(second_tx_key.clone(), Some(vec![1, 2, 3]));
+    ledger_db.set_l2_state_diff(SoftConfirmationNumber(2), state_diff.into_iter().collect())?;
+
+    let mismatch = replayer.replay_l2_block(2).await?.unwrap();
+    assert_eq!(mismatch.spec_id, SpecId::Fork1);
+    assert_eq!(mismatch.replayed_state_root, state_root);
+    assert_eq!(mismatch.stored_state_root, state_root);
+    let state_diff = mismatch.state_diff.unwrap();
+    assert!(state_diff.only_replayed.is_empty());
+    assert!(state_diff.only_stored.is_empty());
+    assert_eq!(
+        state_diff.differing.keys().collect::<Vec<_>>(),
+        vec![&second_tx_key]
+    );
+    assert_eq!(mismatch.offending_tx_index, Some(1));
+
+    assert_eq!(replayer.replay(1..=4).await?.len(), 1);
+
+    // The replay leaves the stored state untouched
+    drop(replayer);
+    drop(ledger_db);
+    task_manager.abort().await;
+    let mismatches = rollup_blueprint
+        .replay_soft_confirmations(rollup_config, 3..=4, None)
+        .await?;
+    assert!(mismatches.is_empty());
+
+    seq_task.abort();
+
+    Ok(())
+}
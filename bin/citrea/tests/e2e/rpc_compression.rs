@@ -0,0 +1,135 @@
+/// Testing the compression of the HTTP responses of the RPC server.
+use std::io::Read;
+use std::net::SocketAddr;
+
+use citrea_common::SequencerConfig;
+use citrea_stf::genesis_config::GenesisPaths;
+use jsonrpsee::types::error::OVERSIZED_RESPONSE_CODE;
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE};
+use serde_json::{json, Value};
+
+use crate::evm::init_test_rollup;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l2_block, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+/// Returns the content encoding and the decoded body of the response
+async fn raw_rpc_request(
+    rpc_address: SocketAddr,
+    body: Value,
+    accept_encoding: Option<&str>,
+) -> (Option<String>, Value) {
+    let mut request = reqwest::Client::new()
+        .post(format!("http://localhost:{}", rpc_address.port()))
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    if let Some(accept_encoding) = accept_encoding {
+        request = request.header(ACCEPT_ENCODING, accept_encoding);
+    }
+    let response = request.send().await.unwrap();
+    let content_encoding = response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .map(|encoding| encoding.to_str().unwrap().to_string());
+    let body = response.bytes().await.unwrap();
+
+    let mut decoded = String::new();
+    match content_encoding.as_deref() {
+        None => decoded = String::from_utf8(body.to_vec()).unwrap(),
+        Some("gzip") => {
+            flate2::read::GzDecoder::new(&body[..])
+                .read_to_string(&mut decoded)
+                .unwrap();
+        }
+        Some("br") => {
+            brotli::Decompressor::new(&body[..], 4096)
+                .read_to_string(&mut decoded)
+                .unwrap();
+        }
+        Some(encoding) => panic!("Unexpected content encoding {}", encoding),
+    }
+    (content_encoding, serde_json::from_str(&decoded).unwrap())
+}
+
+/// Run the sequencer and request a range of soft confirmations with each encoding.
+/// Responses must only be compressed when asked for and above the size threshold,
+/// and the response size limit must apply to the uncompressed response.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rpc_response_compression() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let max_response_body_size = 16 * 1024;
+    let mut rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    rollup_config.rpc.max_response_body_size = max_response_body_size;
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(SequencerConfig::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = init_test_rollup(seq_port).await;
+
+    for _ in 0..10 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&test_client, 10, None).await;
+
+    let range_request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "ledger_getSoftConfirmationRange",
+        "params": ["0x2", "0xa"],
+    });
+
+    let (content_encoding, uncompressed) =
+        raw_rpc_request(seq_port, range_request.clone(), None).await;
+    assert_eq!(content_encoding, None);
+    assert_eq!(uncompressed["result"].as_array().unwrap().len(), 9);
+
+    for encoding in ["gzip", "br"] {
+        let (content_encoding, decoded) =
+            raw_rpc_request(seq_port, range_request.clone(), Some(encoding)).await;
+        assert_eq!(content_encoding.as_deref(), Some(encoding));
+        assert_eq!(decoded, uncompressed);
+    }
+
+    // Responses below the threshold are sent as is
+    let (content_encoding, response) = raw_rpc_request(
+        seq_port,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_blockNumber", "params": [] }),
+        Some("gzip"),
+    )
+    .await;
+    assert_eq!(content_encoding, None);
+    assert_eq!(response["result"], "0xa");
+
+    // The batch is over the limit uncompressed, compressing it does not let it through
+    let batch = (0..4)
+        .map(|id| {
+            let mut request = range_request.clone();
+            request["id"] = json!(id);
+            request
+        })
+        .collect::<Vec<_>>();
+    assert!(4 * uncompressed.to_string().len() > max_response_body_size as usize);
+    let (_, response) = raw_rpc_request(seq_port, json!(batch), Some("br")).await;
+    assert_eq!(response["error"]["code"], OVERSIZED_RESPONSE_CODE);
+
+    seq_task.abort();
+    Ok(())
+}
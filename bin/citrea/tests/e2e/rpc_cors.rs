@@ -0,0 +1,94 @@
+/// Testing the CORS policy of the RPC server restricted to a set of origins.
+use std::net::SocketAddr;
+
+use citrea_common::SequencerConfig;
+use citrea_stf::genesis_config::GenesisPaths;
+use reqwest::header::{
+    ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+};
+use reqwest::{Method, Response};
+
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+async fn preflight_request(rpc_address: SocketAddr, origin: &str) -> Response {
+    reqwest::Client::new()
+        .request(
+            Method::OPTIONS,
+            format!("http://localhost:{}", rpc_address.port()),
+        )
+        .header(ORIGIN, origin)
+        .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Run the sequencer accepting cross origin requests from a domain and its subdomains only.
+/// Preflight requests of the other origins must not be allowed.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_restricted_cors_origins() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    rollup_config.rpc.cors.allowed_origins = vec![
+        "https://example.com".to_string(),
+        "https://*.example.com".to_string(),
+    ];
+    rollup_config.rpc.cors.allowed_headers = vec!["content-type".to_string()];
+    rollup_config.rpc.cors.allow_credentials = true;
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(SequencerConfig::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+
+    for origin in ["https://example.com", "https://app.example.com"] {
+        let response = preflight_request(seq_port, origin).await;
+        assert!(response.status().is_success());
+        let headers = response.headers();
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], origin);
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_HEADERS], "content-type");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+    }
+
+    for origin in [
+        "http://example.com",
+        "https://example.com:8443",
+        "https://evilexample.com",
+        "https://example.com.evil.com",
+        "null",
+    ] {
+        let response = preflight_request(seq_port, origin).await;
+        assert!(
+            response
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none(),
+            "{}",
+            origin
+        );
+    }
+
+    seq_task.abort();
+    Ok(())
+}
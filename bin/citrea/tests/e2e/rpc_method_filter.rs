@@ -0,0 +1,99 @@
+/// Testing the allowlist and denylist of the RPC methods served by the nodes.
+use std::net::SocketAddr;
+
+use alloy_primitives::Address;
+use citrea_common::SequencerConfig;
+use citrea_stf::genesis_config::GenesisPaths;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+
+use crate::evm::init_test_rollup;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l2_block, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+async fn raw_rpc_request(rpc_address: SocketAddr, body: Value) -> Value {
+    let response = reqwest::Client::new()
+        .post(format!("http://localhost:{}", rpc_address.port()))
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()
+        .await
+        .unwrap();
+    serde_json::from_str(&response.text().await.unwrap()).unwrap()
+}
+
+/// Run the sequencer with eth_sendRawTransaction denied.
+/// Sending transactions must fail as if the method did not exist, while the other methods still work.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_denied_rpc_method() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    rollup_config.rpc.method_filter.deny = vec!["eth_sendRawTransaction".to_string()];
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(SequencerConfig::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = init_test_rollup(seq_port).await;
+
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 1, None).await;
+    assert_eq!(test_client.eth_block_number().await, 1);
+
+    let err = test_client
+        .send_eth(Address::random(), None, None, None, 1u128)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Method not found"), "{err}");
+
+    // Every request of a batch is filtered on its own,
+    // the denied method is rejected exactly like an unknown one
+    let responses = raw_rpc_request(
+        seq_port,
+        json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "eth_blockNumber", "params": [] },
+            { "jsonrpc": "2.0", "id": 2, "method": "eth_sendRawTransaction", "params": ["0x00"] },
+            { "jsonrpc": "2.0", "id": 3, "method": "eth_unknownMethod", "params": [] },
+        ]),
+    )
+    .await;
+    let response = |id: u64| {
+        responses
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|response| response["id"] == id)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(response(1)["result"], "0x1");
+    assert_eq!(response(2)["error"]["code"], -32601);
+    assert_eq!(response(2)["error"], response(3)["error"]);
+
+    // No transaction made it into the mempool
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 2, None).await;
+    let block = test_client.eth_get_block_by_number(None).await;
+    assert!(block.transactions.is_empty());
+
+    seq_task.abort();
+    Ok(())
+}
@@ -1,13 +1,14 @@
 /// Testing specific features of the sequencer
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use alloy::consensus::{Signed, TxEip1559, TxEnvelope};
 use alloy::signers::local::PrivateKeySigner;
 use alloy::signers::Signer;
-use alloy_primitives::Address;
+use alloy_primitives::{Address, Bytes, TxHash, B256, U128, U64};
 use alloy_rlp::{BytesMut, Encodable};
-use citrea_common::{SequencerConfig, SequencerMempoolConfig};
+use citrea_common::{SequencerConfig, SequencerMempoolConfig, TxOrderingPolicy};
+use citrea_sequencer::{CommitmentL2Range, ProductionState};
 use citrea_stf::genesis_config::GenesisPaths;
 use reth_primitives::BlockNumberOrTag;
 use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
@@ -20,7 +21,9 @@ use crate::test_helpers::{
     create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_commitment,
     wait_for_l1_block, wait_for_l2_block, NodeMode,
 };
-use crate::TEST_DATA_GENESIS_PATH;
+use crate::{
+    TEST_DATA_GENESIS_PATH, TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+};
 
 /// Run the sequencer.
 /// Create some blocks.
@@ -82,15 +85,16 @@ async fn test_sequencer_fill_missing_da_blocks() -> Result<(), anyhow::Error> {
     // wait for all corresponding da blocks to be filled by sequencer
     wait_for_l2_block(&seq_test_client, last_filler_l2_block, None).await;
 
-    let mut next_da_block = 2;
+    let filler_soft_confirmations = seq_test_client
+        .ledger_get_soft_confirmation_range(first_filler_l2_block, last_filler_l2_block)
+        .await;
+    assert_eq!(
+        filler_soft_confirmations.len() as u64,
+        to_be_filled_da_block_count
+    );
     // ensure that all the filled l2 blocks correspond to correct da blocks
-    for filler_l2_block in first_filler_l2_block..=last_filler_l2_block {
-        let soft_confirmation = seq_test_client
-            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(filler_l2_block)
-            .await
-            .unwrap();
-        assert_eq!(soft_confirmation.da_slot_height, next_da_block);
-        next_da_block += 1;
+    for (soft_confirmation, next_da_block) in filler_soft_confirmations.into_iter().zip(2..) {
+        assert_eq!(soft_confirmation.unwrap().da_slot_height, next_da_block);
     }
 
     // publish an extra l2 block
@@ -446,6 +450,84 @@ async fn test_gas_limit_too_high() {
     full_node_task.abort();
 }
 
+/// Run the sequencer with a small soft confirmation size limit.
+/// Send transactions with large calldata.
+/// Check if the transactions over the size limit are left for the next blocks.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_soft_confirmation_size_limit() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir: tempfile::TempDir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+
+    // Each transaction is estimated ~6.6kb after compression, so only 7 of them fit in a block
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment: 1000,
+        max_soft_confirmation_size_bytes: 50_000,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await.unwrap();
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+    let input = Bytes::from(vec![1u8; 20_000]);
+
+    let mut tx_hashes = vec![];
+    for _ in 0..10 {
+        let tx_hash = seq_test_client
+            .send_eth_with_input(addr, input.clone(), 1_000_000)
+            .await
+            .unwrap();
+        tx_hashes.push(*tx_hash.tx_hash());
+    }
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 1, None).await;
+
+    let block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(1)))
+        .await;
+    // The first block also contains system transactions
+    let block_transactions = block.transactions.as_hashes().unwrap();
+    for tx_hash in tx_hashes[..7].iter() {
+        assert!(block_transactions.contains(tx_hash));
+    }
+    for tx_hash in tx_hashes[7..].iter() {
+        assert!(!block_transactions.contains(tx_hash));
+    }
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 2, None).await;
+
+    let block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(2)))
+        .await;
+    let block_transactions = block.transactions.as_hashes().unwrap();
+    for tx_hash in tx_hashes[7..].iter() {
+        assert!(block_transactions.contains(tx_hash));
+    }
+
+    seq_task.abort();
+}
+
 /// Run the sequencer.
 /// Fill the mempool with transactions.
 /// Create a block with a system transaction.
@@ -618,6 +700,843 @@ async fn test_system_tx_effect_on_block_gas_limit() -> Result<(), anyhow::Error>
     Ok(())
 }
 
+/// Run the sequencer in production mode with admin methods enabled.
+/// Halt block production and check that no new L2 blocks are produced
+/// while transactions are still accepted. Then resume and check that
+/// heights continue monotonically and the pending transaction is included.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequencer_halt_and_resume_production() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let admin_token = "admin-secret";
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    rollup_config.rpc.admin_token = Some(admin_token.to_string());
+    let sequencer_config = SequencerConfig {
+        test_mode: false,
+        block_production_interval_ms: 500,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    wait_for_l2_block(&seq_test_client, 2, None).await;
+
+    // A wrong token must not change anything
+    assert!(seq_test_client
+        .sequencer_halt_production("wrong-token")
+        .await
+        .is_err());
+
+    assert_eq!(
+        seq_test_client
+            .sequencer_halt_production(admin_token)
+            .await
+            .unwrap(),
+        ProductionState::Halted
+    );
+
+    // A block that was already being produced may still land
+    sleep(Duration::from_secs(1)).await;
+    let halted_height = seq_test_client.eth_block_number().await;
+
+    // Transactions are still accepted into the mempool while halted
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92265").unwrap();
+    let pending_tx = seq_test_client
+        .send_eth(addr, None, None, None, 1_000_000_000)
+        .await
+        .unwrap();
+
+    sleep(Duration::from_secs(4)).await;
+    assert_eq!(seq_test_client.eth_block_number().await, halted_height);
+
+    assert_eq!(
+        seq_test_client
+            .sequencer_resume_production(admin_token)
+            .await
+            .unwrap(),
+        ProductionState::Running
+    );
+
+    wait_for_l2_block(&seq_test_client, halted_height + 3, None).await;
+
+    // Heights continue from where production stopped without gaps
+    let mut previous_timestamp = 0;
+    for height in 1..=halted_height + 3 {
+        let block = seq_test_client
+            .eth_get_block_by_number(Some(BlockNumberOrTag::Number(height)))
+            .await;
+        assert_eq!(block.header.number, height);
+        assert!(block.header.timestamp >= previous_timestamp);
+        previous_timestamp = block.header.timestamp;
+    }
+
+    let receipt = pending_tx.get_receipt().await.unwrap();
+    assert!(receipt.block_number.unwrap() > halted_height);
+
+    seq_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer.
+/// Create blocks up to the commitment threshold.
+/// Check that the pending commitment is reported until it lands on the DA.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequencer_pending_commitments() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let min_soft_confirmations_per_commitment = 4;
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    for i in 1..min_soft_confirmations_per_commitment {
+        seq_test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&seq_test_client, i, None).await;
+    }
+
+    // Threshold is not reached yet, state diff keeps accumulating
+    let before_commitment = seq_test_client.sequencer_get_pending_commitments().await;
+    assert!(before_commitment.pending_l2_ranges.is_empty());
+    assert_eq!(before_commitment.last_committed_l2_height, U64::ZERO);
+    assert!(before_commitment.state_diff_size > U64::ZERO);
+    assert!(before_commitment.state_diff_size < before_commitment.state_diff_threshold);
+    assert_eq!(
+        before_commitment.min_soft_confirmations_per_commitment,
+        U64::from(min_soft_confirmations_per_commitment)
+    );
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(
+        &seq_test_client,
+        min_soft_confirmations_per_commitment,
+        None,
+    )
+    .await;
+
+    // MockDa lands the commitment right away, so the pending range is only seen
+    // if the DA response has not been handled yet.
+    let expected_range = CommitmentL2Range {
+        start: U64::from(1),
+        end: U64::from(min_soft_confirmations_per_commitment),
+    };
+    let mut after_commitment = seq_test_client.sequencer_get_pending_commitments().await;
+    for _ in 0..50 {
+        if after_commitment.pending_l2_ranges.is_empty()
+            && after_commitment.last_committed_l2_height
+                == U64::from(min_soft_confirmations_per_commitment)
+        {
+            break;
+        }
+        assert_eq!(after_commitment.pending_l2_ranges, vec![expected_range]);
+
+        sleep(Duration::from_millis(100)).await;
+        after_commitment = seq_test_client.sequencer_get_pending_commitments().await;
+    }
+
+    // The commitment landed and the state diff is reset
+    assert!(after_commitment.pending_l2_ranges.is_empty());
+    assert_eq!(
+        after_commitment.last_committed_l2_height,
+        U64::from(min_soft_confirmations_per_commitment)
+    );
+    assert!(after_commitment.state_diff_size < before_commitment.state_diff_size);
+
+    seq_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer.
+/// Create blocks for two commitments.
+/// Check that the commitment history grows with each commitment, and that the recorded
+/// ranges match the commitments found on the DA.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequencer_commitment_history() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let min_soft_confirmations_per_commitment = 4;
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    assert!(seq_test_client
+        .sequencer_get_commitment_history(None, None)
+        .await
+        .is_empty());
+
+    let mut l2_height = 0;
+    for (index, l1_height) in [2, 3].into_iter().enumerate() {
+        for _ in 0..min_soft_confirmations_per_commitment {
+            l2_height += 1;
+            seq_test_client.send_publish_batch_request().await;
+            wait_for_l2_block(&seq_test_client, l2_height, None).await;
+        }
+
+        let commitments = wait_for_commitment(&da_service, l1_height, None).await;
+        assert_eq!(commitments.len(), 1);
+        let commitment = &commitments[0];
+
+        // The confirmation is recorded once the DA block monitor sees the commitment
+        let mut history = seq_test_client
+            .sequencer_get_commitment_history(None, None)
+            .await;
+        for _ in 0..100 {
+            if history.len() == index + 1 && history[0].confirmed_in_l1.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+            history = seq_test_client
+                .sequencer_get_commitment_history(None, None)
+                .await;
+        }
+        assert_eq!(history.len(), index + 1);
+
+        // Newest first
+        let newest = &history[0];
+        assert_eq!(
+            newest.l2_start_block_number,
+            U64::from(commitment.l2_start_block_number)
+        );
+        assert_eq!(
+            newest.l2_end_block_number,
+            U64::from(commitment.l2_end_block_number)
+        );
+        assert_eq!(newest.merkle_root, B256::from(commitment.merkle_root));
+        assert_eq!(newest.da_tx_ids.len(), 1);
+        assert_eq!(newest.confirmed_in_l1, Some(U64::from(l1_height)));
+        assert!(newest.submitted_at > U64::ZERO);
+
+        assert_eq!(
+            seq_test_client
+                .sequencer_get_commitment_by_l2_range(
+                    commitment.l2_start_block_number,
+                    commitment.l2_end_block_number
+                )
+                .await
+                .as_ref(),
+            Some(newest)
+        );
+    }
+
+    let history = seq_test_client
+        .sequencer_get_commitment_history(None, None)
+        .await;
+    assert_eq!(history[1].l2_start_block_number, U64::from(1));
+    assert_eq!(
+        history[1].l2_end_block_number,
+        U64::from(min_soft_confirmations_per_commitment)
+    );
+    assert_eq!(
+        seq_test_client
+            .sequencer_get_commitment_history(Some(1), Some(1))
+            .await,
+        vec![history[1].clone()]
+    );
+    assert_eq!(
+        seq_test_client
+            .sequencer_get_commitment_by_l2_range(1, l2_height)
+            .await,
+        None
+    );
+
+    seq_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer with a MockDa that takes seconds to accept a transaction.
+/// Check that blocks keep being produced at the configured interval while the commitment
+/// is being submitted, and that the commitment lands eventually.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_slow_da_does_not_delay_block_production() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let min_soft_confirmations_per_commitment = 4;
+    let block_production_interval = Duration::from_millis(200);
+    let send_transaction_delay = Duration::from_secs(5);
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    rollup_config.da.send_transaction_delay_ms = send_transaction_delay.as_millis() as u64;
+    let sequencer_config = SequencerConfig {
+        test_mode: false,
+        block_production_interval_ms: block_production_interval.as_millis() as u64,
+        min_soft_confirmations_per_commitment,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    // The first commitment is due once this block is produced
+    wait_for_l2_block(
+        &seq_test_client,
+        min_soft_confirmations_per_commitment,
+        None,
+    )
+    .await;
+
+    // Record when each block is seen, for a period shorter than the DA delay
+    let mut last_height = seq_test_client.eth_block_number().await;
+    let mut last_block_seen_at = Instant::now();
+    let mut max_block_gap = Duration::ZERO;
+    let started_at = Instant::now();
+    while started_at.elapsed() < send_transaction_delay - Duration::from_secs(1) {
+        let height = seq_test_client.eth_block_number().await;
+        if height > last_height {
+            max_block_gap = max_block_gap.max(last_block_seen_at.elapsed());
+            last_height = height;
+            last_block_seen_at = Instant::now();
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    assert!(
+        max_block_gap < 3 * block_production_interval,
+        "Block production stalled for {:?}",
+        max_block_gap
+    );
+    assert!(last_height >= min_soft_confirmations_per_commitment + 10);
+
+    // DA has not accepted the commitment yet
+    let pending_commitments = seq_test_client.sequencer_get_pending_commitments().await;
+    assert_eq!(pending_commitments.last_committed_l2_height, U64::ZERO);
+    assert!(pending_commitments
+        .pending_l2_ranges
+        .contains(&CommitmentL2Range {
+            start: U64::from(1),
+            end: U64::from(min_soft_confirmations_per_commitment),
+        }));
+
+    let mut pending_commitments = pending_commitments;
+    for _ in 0..100 {
+        if pending_commitments.last_committed_l2_height
+            >= U64::from(min_soft_confirmations_per_commitment)
+        {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+        pending_commitments = seq_test_client.sequencer_get_pending_commitments().await;
+    }
+    assert!(
+        pending_commitments.last_committed_l2_height
+            >= U64::from(min_soft_confirmations_per_commitment)
+    );
+    // Commitments are submitted in order, and never overlap
+    let mut next_start = pending_commitments.last_committed_l2_height + U64::from(1);
+    for range in &pending_commitments.pending_l2_ranges {
+        assert!(range.start >= next_start);
+        next_start = range.end + U64::from(1);
+    }
+
+    seq_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer and produce some blocks with the constant fee rate of MockDa.
+/// Check that the smoothed L1 fee rate is the constant one, and matches the soft confirmations.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequencer_l1_fee_rate() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(SequencerConfig::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    // Nothing is produced yet
+    let l1_fee_rate = seq_test_client.sequencer_get_l1_fee_rate().await;
+    assert_eq!(l1_fee_rate.raw, None);
+    assert_eq!(l1_fee_rate.smoothed, None);
+
+    for i in 1..=3 {
+        seq_test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&seq_test_client, i, None).await;
+    }
+
+    let l1_fee_rate = seq_test_client.sequencer_get_l1_fee_rate().await;
+    assert_eq!(l1_fee_rate.raw, Some(U128::from(10)));
+    assert_eq!(l1_fee_rate.smoothed, Some(U128::from(10)));
+    for i in 1..=3 {
+        let soft_confirmation = seq_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(i)
+            .await
+            .unwrap();
+        assert_eq!(soft_confirmation.l1_fee_rate, 10);
+    }
+
+    seq_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer with admin methods enabled and produce 5 blocks with a transaction each.
+/// Roll back the last 2 blocks and check that their transactions are back in the mempool.
+/// Then check that the rebuilt blocks contain the recycled transactions with new timestamps.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequencer_rollback_to_height() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let admin_token = "admin-secret";
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    rollup_config.rpc.admin_token = Some(admin_token.to_string());
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92265").unwrap();
+    let mut tx_hashes = vec![];
+    for i in 1..=5 {
+        let pending_tx = seq_test_client
+            .send_eth(addr, None, None, None, 1_000_000_000)
+            .await
+            .unwrap();
+        tx_hashes.push(*pending_tx.tx_hash());
+        seq_test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&seq_test_client, i, None).await;
+    }
+
+    let mut dropped_blocks = vec![];
+    for height in 4..=5 {
+        dropped_blocks.push(
+            seq_test_client
+                .eth_get_block_by_number(Some(BlockNumberOrTag::Number(height)))
+                .await,
+        );
+    }
+
+    // Block timestamps are in seconds
+    sleep(Duration::from_secs(1)).await;
+
+    assert!(seq_test_client
+        .sequencer_rollback_to_height("wrong-token", 3)
+        .await
+        .is_err());
+    assert!(seq_test_client
+        .sequencer_rollback_to_height(admin_token, 6)
+        .await
+        .is_err());
+    assert_eq!(seq_test_client.eth_block_number().await, 5);
+
+    assert_eq!(
+        seq_test_client
+            .sequencer_rollback_to_height(admin_token, 3)
+            .await
+            .unwrap(),
+        U64::from(2)
+    );
+    assert_eq!(seq_test_client.eth_block_number().await, 3);
+    assert_eq!(seq_test_client.txpool_status().await.pending, U64::from(2));
+
+    for i in 4..=5 {
+        seq_test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&seq_test_client, i, None).await;
+    }
+
+    let mut rebuilt_txs = vec![];
+    for (height, dropped_block) in (4..=5).zip(&dropped_blocks) {
+        let block = seq_test_client
+            .eth_get_block_by_number(Some(BlockNumberOrTag::Number(height)))
+            .await;
+        assert_ne!(block.header.hash, dropped_block.header.hash);
+        assert!(block.header.timestamp > dropped_block.header.timestamp);
+        rebuilt_txs.extend(block.transactions.as_hashes().unwrap().iter().copied());
+    }
+    assert_eq!(rebuilt_txs, tx_hashes[3..]);
+    assert_eq!(seq_test_client.txpool_status().await.pending, U64::ZERO);
+
+    // The rebuilt chain continues from the kept blocks
+    let kept_block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(3)))
+        .await;
+    let first_rebuilt_block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(4)))
+        .await;
+    assert_eq!(
+        first_rebuilt_block.header.parent_hash,
+        kept_block.header.hash
+    );
+
+    seq_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer.
+/// Create a different number of blocks on top of several DA blocks.
+/// Check that the L2 range of each DA block matches the DA heights of its soft confirmations.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_soft_confirmation_range_by_l1_height() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment: 1000,
+        da_update_interval_ms: 500,
+        block_production_interval_ms: 500,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    // L2 block counts built on top of DA blocks 1, 2 and 3
+    let blocks_per_da_block = [3, 2, 4];
+    let mut l2_height = 0;
+    for (i, block_count) in blocks_per_da_block.iter().enumerate() {
+        if i > 0 {
+            da_service.publish_test_block().await.unwrap();
+            wait_for_l1_block(&da_service, i as u64 + 1, None).await;
+            sleep(Duration::from_secs(1)).await;
+        }
+        for _ in 0..*block_count {
+            seq_test_client.send_publish_batch_request().await;
+            l2_height += 1;
+            wait_for_l2_block(&seq_test_client, l2_height, None).await;
+        }
+    }
+
+    let mut next_l2_start = 1;
+    for (i, block_count) in blocks_per_da_block.iter().enumerate() {
+        let l1_height = i as u64 + 1;
+        let range = seq_test_client
+            .ledger_get_soft_confirmation_range_by_l1_height(l1_height, None)
+            .await
+            .unwrap();
+
+        assert_eq!(range.l1_height, l1_height);
+        assert_eq!(range.l2_start, next_l2_start);
+        assert_eq!(range.l2_end, next_l2_start + block_count - 1);
+        assert_eq!(range.soft_confirmations.len() as u64, *block_count);
+        for (soft_confirmation, expected_l2_height) in range
+            .soft_confirmations
+            .iter()
+            .zip(range.l2_start..=range.l2_end)
+        {
+            assert_eq!(soft_confirmation.l2_height, expected_l2_height);
+            assert_eq!(soft_confirmation.da_slot_height, l1_height);
+            assert!(soft_confirmation.txs.is_some());
+        }
+
+        let range_without_txs = seq_test_client
+            .ledger_get_soft_confirmation_range_by_l1_height(l1_height, Some(false))
+            .await
+            .unwrap();
+        assert_eq!(range_without_txs.l2_start, range.l2_start);
+        assert_eq!(range_without_txs.l2_end, range.l2_end);
+        assert!(range_without_txs
+            .soft_confirmations
+            .iter()
+            .all(|soft_confirmation| soft_confirmation.txs.is_none()));
+
+        next_l2_start = range.l2_end + 1;
+    }
+
+    // No L2 blocks were built on top of a DA block that does not exist yet
+    assert!(seq_test_client
+        .ledger_get_soft_confirmation_range_by_l1_height(blocks_per_da_block.len() as u64 + 1, None)
+        .await
+        .is_none());
+
+    seq_task.abort();
+
+    Ok(())
+}
+
+/// Runs a fresh sequencer with the given ordering policy and funds three senders in the first block.
+/// Sends equal fee transfers from the senders in the given order and publishes the second block.
+/// Returns the tx bodies of the second soft confirmation, the hashes of its EVM transactions
+/// and the senders and hashes of the sent transactions in the order they were sent.
+async fn build_block_with_equal_fee_txs(
+    tx_ordering_policy: TxOrderingPolicy,
+    sender_order: &[usize],
+) -> (Vec<Vec<u8>>, Vec<TxHash>, Vec<(Address, TxHash)>) {
+    let db_dir: tempfile::TempDir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment: 1000,
+        tx_ordering_policy,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await.unwrap();
+
+    // Senders have fixed keys so their transactions are the same in every run
+    let mut senders = vec![];
+    for i in 1..=3u8 {
+        let key = PrivateKeySigner::from_bytes(&B256::repeat_byte(i))
+            .unwrap()
+            .with_chain_id(Some(seq_test_client.chain_id));
+        let _pending = seq_test_client
+            .send_eth(key.address(), None, None, None, 10u128.pow(18))
+            .await
+            .unwrap();
+        senders.push(key);
+    }
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 1, None).await;
+
+    let mut sender_clients = vec![];
+    for key in senders {
+        let address = key.address();
+        sender_clients.push(
+            TestClient::new(seq_test_client.chain_id, key, address, seq_port)
+                .await
+                .unwrap(),
+        );
+    }
+
+    let recipient = Address::repeat_byte(0x11);
+    let mut sent_txs = vec![];
+    for sender in sender_order {
+        let client = &sender_clients[*sender];
+        let pending = client
+            .send_eth(recipient, None, None, None, 1u128)
+            .await
+            .unwrap();
+        sent_txs.push((client.from_addr, *pending.tx_hash()));
+    }
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 2, None).await;
+
+    let tx_bodies = seq_test_client
+        .ledger_get_soft_confirmation_by_number::<MockDaSpec>(2)
+        .await
+        .unwrap()
+        .txs
+        .unwrap()
+        .into_iter()
+        .map(|tx| tx.tx)
+        .collect();
+    let block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(2)))
+        .await;
+    let block_txs = block
+        .transactions
+        .as_hashes()
+        .unwrap()
+        .iter()
+        .filter(|tx_hash| sent_txs.iter().any(|(_, sent)| sent == *tx_hash))
+        .copied()
+        .collect();
+
+    seq_task.abort();
+
+    (tx_bodies, block_txs, sent_txs)
+}
+
+/// Run the sequencer twice with the priority fee ordering policy.
+/// Send the same equal fee transactions, in a different order in each run.
+/// Check if both runs build the same soft confirmation, with the transactions ordered by sender and nonce.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_priority_fee_ordering_is_deterministic() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let (first_tx_bodies, first_block_txs, sent_txs) =
+        build_block_with_equal_fee_txs(TxOrderingPolicy::PriorityFee, &[0, 1, 2, 0, 1, 2]).await;
+    let (second_tx_bodies, second_block_txs, _) =
+        build_block_with_equal_fee_txs(TxOrderingPolicy::PriorityFee, &[2, 1, 0, 2, 1, 0]).await;
+
+    assert_eq!(first_tx_bodies.len(), 6);
+    assert_eq!(first_tx_bodies, second_tx_bodies);
+    assert_eq!(first_block_txs, second_block_txs);
+
+    // The transactions of a sender were sent in nonce order
+    let mut expected_txs = sent_txs;
+    expected_txs.sort_by_key(|(sender, _)| *sender);
+    let expected_txs: Vec<TxHash> = expected_txs.into_iter().map(|(_, tx)| tx).collect();
+    assert_eq!(first_block_txs, expected_txs);
+}
+
+/// Run the sequencer twice with the fifo ordering policy.
+/// Send the same equal fee transactions in the same order in each run.
+/// Check if both runs build the same soft confirmation, with the transactions in the order they were sent.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_fifo_ordering_is_deterministic() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let sender_order = [0, 1, 2, 2, 1, 0];
+    let (first_tx_bodies, first_block_txs, sent_txs) =
+        build_block_with_equal_fee_txs(TxOrderingPolicy::Fifo, &sender_order).await;
+    let (second_tx_bodies, second_block_txs, _) =
+        build_block_with_equal_fee_txs(TxOrderingPolicy::Fifo, &sender_order).await;
+
+    assert_eq!(first_tx_bodies.len(), 6);
+    assert_eq!(first_tx_bodies, second_tx_bodies);
+    assert_eq!(first_block_txs, second_block_txs);
+
+    let expected_txs: Vec<TxHash> = sent_txs.into_iter().map(|(_, tx)| tx).collect();
+    assert_eq!(first_block_txs, expected_txs);
+}
+
 fn find_subarray(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     haystack
         .windows(needle.len())
@@ -0,0 +1,253 @@
+/// Sequencer commitments signed with the whitelisted DA keys of the sequencer
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use citrea_common::{BatchProverConfig, SequencerConfig};
+use citrea_stf::genesis_config::GenesisPaths;
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::MerkleTree;
+use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
+use sov_rollup_interface::da::{DaData, SequencerCommitment};
+use sov_rollup_interface::rpc::SoftConfirmationStatus;
+use sov_rollup_interface::spec::SpecId;
+
+use crate::evm::make_test_client;
+use crate::test_client::TestClient;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l1_block,
+    wait_for_l2_block, wait_for_proof, wait_for_prover_l1_height, NodeMode,
+};
+use crate::{
+    TEST_DATA_GENESIS_PATH, TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+};
+
+/// Second DA key of the sequencer, also accepted by the mock batch proof guest
+const ROTATED_SEQUENCER_DA_PUB_KEY: [u8; 33] = [
+    2, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17,
+    17, 17, 17, 17, 17, 17, 17, 17, 17,
+];
+
+/// Commitment of the sequencer's L2 blocks in the given range
+async fn sequencer_commitment(
+    test_client: &TestClient,
+    l2_heights: RangeInclusive<u64>,
+) -> SequencerCommitment {
+    let mut soft_confirmation_hashes = vec![];
+    for l2_height in l2_heights.clone() {
+        let soft_confirmation = test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        soft_confirmation_hashes.push(soft_confirmation.hash);
+    }
+    SequencerCommitment {
+        merkle_root: MerkleTree::<Sha256>::from_leaves(&soft_confirmation_hashes)
+            .root()
+            .unwrap(),
+        l2_start_block_number: *l2_heights.start(),
+        l2_end_block_number: *l2_heights.end(),
+    }
+}
+
+/// Run the sequencer without commitments, the prover and the full node accepting a second sequencer DA key.
+/// Publish commitments alternating between the sequencer's DA key and the second one,
+/// then one signed with a key outside the whitelist.
+/// Check that the whitelisted commitments are stored and proven with their signer recorded in the proofs,
+/// and that the other one is ignored.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_commitments_of_accepted_sequencer_da_keys() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "prover", "full-node"]);
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_da_pub_key = rollup_config.public_keys.sequencer_da_pub_key.clone();
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await.unwrap();
+
+    // Not a sequencer DA key
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &prover_db_dir, &da_db_dir, NodeMode::Prover(seq_port));
+    rollup_config.public_keys.accepted_sequencer_da_pub_keys =
+        vec![ROTATED_SEQUENCER_DA_PUB_KEY.to_vec()];
+
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(BatchProverConfig {
+                proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
+                proof_sampling_number: 0,
+                enable_recovery: true,
+                ..Default::default()
+            }),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let prover_node_port = prover_node_port_rx.await.unwrap();
+    let prover_node_test_client = make_test_client(prover_node_port).await.unwrap();
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    rollup_config.public_keys.accepted_sequencer_da_pub_keys =
+        vec![ROTATED_SEQUENCER_DA_PUB_KEY.to_vec()];
+
+    let full_node_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await.unwrap();
+
+    da_service.publish_test_block().await.unwrap();
+    wait_for_l1_block(&da_service, 2, None).await;
+
+    let signers = [
+        sequencer_da_pub_key.clone(),
+        ROTATED_SEQUENCER_DA_PUB_KEY.to_vec(),
+        sequencer_da_pub_key,
+    ];
+    let mut l2_height = 0;
+    let mut l1_height = 2;
+    let mut commitment_l1_heights = vec![];
+    for signer in &signers {
+        for _ in 0..4 {
+            test_client.send_publish_batch_request().await;
+        }
+        wait_for_l2_block(&full_node_test_client, l2_height + 4, None).await;
+
+        let commitment = sequencer_commitment(&test_client, l2_height + 1..=l2_height + 4).await;
+        MockDaService::new(MockAddress::from(signer.clone()), &da_db_dir)
+            .publish_test_block_with_da_data(
+                vec![DaData::SequencerCommitment(commitment)],
+                SpecId::Fork1,
+            )
+            .await
+            .unwrap();
+        commitment_l1_heights.push(l1_height + 1);
+
+        // The prover publishes the proof of the commitment in the next L1 block
+        l1_height += 2;
+        wait_for_prover_l1_height(&prover_node_test_client, l1_height, None)
+            .await
+            .unwrap();
+        wait_for_l1_block(&da_service, l1_height, None).await;
+        l2_height += 4;
+    }
+
+    // Make the full node scan the DA blocks with the proofs
+    for _ in 0..2 {
+        test_client.send_publish_batch_request().await;
+    }
+    l2_height += 2;
+    wait_for_l2_block(&full_node_test_client, l2_height, None).await;
+
+    for (signer, commitment_l1_height) in signers.iter().zip(commitment_l1_heights) {
+        let commitments = full_node_test_client
+            .ledger_get_sequencer_commitments_on_slot_by_number(commitment_l1_height)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(commitments.len(), 1);
+
+        wait_for_proof(
+            &full_node_test_client,
+            commitment_l1_height + 1,
+            Some(Duration::from_secs(60)),
+        )
+        .await;
+        let proofs = full_node_test_client
+            .ledger_get_verified_batch_proofs_by_slot_height(commitment_l1_height + 1)
+            .await
+            .unwrap();
+        assert_eq!(&proofs[0].proof_output.sequencer_da_public_key, signer);
+    }
+
+    for i in 1..=12 {
+        let status = full_node_test_client
+            .ledger_get_soft_confirmation_status(i)
+            .await
+            .unwrap();
+        assert_eq!(status, SoftConfirmationStatus::Proven);
+    }
+
+    // A commitment signed with a key outside the whitelist
+    let commitment = sequencer_commitment(&test_client, l2_height - 1..=l2_height).await;
+    da_service
+        .publish_test_block_with_da_data(
+            vec![DaData::SequencerCommitment(commitment)],
+            SpecId::Fork1,
+        )
+        .await
+        .unwrap();
+    l1_height += 1;
+    wait_for_l1_block(&da_service, l1_height, None).await;
+
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, l2_height + 1, None).await;
+    wait_for_prover_l1_height(&full_node_test_client, l1_height, None)
+        .await
+        .unwrap();
+
+    assert!(full_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(l1_height)
+        .await
+        .unwrap()
+        .is_none());
+    let status = full_node_test_client
+        .ledger_get_soft_confirmation_status(l2_height)
+        .await
+        .unwrap();
+    assert_eq!(status, SoftConfirmationStatus::Trusted);
+
+    seq_task.abort();
+    prover_node_task.abort();
+    full_node_task.abort();
+}
@@ -0,0 +1,232 @@
+/// Scheduled rotation of the sequencer's soft confirmation signing key
+use std::time::Duration;
+
+use alloy_primitives::Address;
+use citrea_common::{BatchProverConfig, SequencerConfig, SequencerKeyRotation};
+use citrea_primitives::TEST_PRIVATE_KEY;
+use citrea_stf::genesis_config::GenesisPaths;
+use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
+use sov_modules_api::default_signature::private_key::DefaultPrivateKey;
+use sov_modules_api::PrivateKey;
+use sov_rollup_interface::rpc::SoftConfirmationStatus;
+
+use crate::evm::make_test_client;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l1_block,
+    wait_for_l2_block, wait_for_proof, wait_for_prover_l1_height, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+const ROTATED_PRIVATE_KEY: &str =
+    "3434343434343434343434343434343434343434343434343434343434343434";
+
+const ROTATION_L2_HEIGHT: u64 = 3;
+
+fn public_key(private_key: &str) -> Vec<u8> {
+    borsh::to_vec(&DefaultPrivateKey::from_hex(private_key).unwrap().pub_key()).unwrap()
+}
+
+/// Run the sequencer, prover and full node with a key rotation at L2 height 3.
+/// Check that the blocks before and after the rotation are signed with the scheduled keys,
+/// and that the full node syncs and the prover proves both of them.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequencer_key_rotation() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "prover", "full-node"]);
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+
+    let old_pub_key = public_key(TEST_PRIVATE_KEY);
+    let new_pub_key = public_key(ROTATED_PRIVATE_KEY);
+    let rotations = vec![SequencerKeyRotation {
+        activation_l2_height: ROTATION_L2_HEIGHT,
+        sequencer_public_key: new_pub_key.clone(),
+    }];
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    rollup_config.public_keys.sequencer_key_rotations = rotations.clone();
+    let sequencer_config = SequencerConfig {
+        rotated_private_keys: vec![ROTATED_PRIVATE_KEY.to_string()],
+        ..Default::default()
+    };
+
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await.unwrap();
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &prover_db_dir, &da_db_dir, NodeMode::Prover(seq_port));
+    rollup_config.public_keys.sequencer_key_rotations = rotations.clone();
+
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(BatchProverConfig {
+                proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
+                proof_sampling_number: 0,
+                enable_recovery: true,
+                ..Default::default()
+            }),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let prover_node_port = prover_node_port_rx.await.unwrap();
+    let prover_node_test_client = make_test_client(prover_node_port).await.unwrap();
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    rollup_config.public_keys.sequencer_key_rotations = rotations;
+
+    let full_node_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await.unwrap();
+
+    da_service.publish_test_block().await.unwrap();
+    wait_for_l1_block(&da_service, 2, None).await;
+
+    // The first block signed with the new key commits the blocks signed with the old one
+    for _ in 0..ROTATION_L2_HEIGHT {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, ROTATION_L2_HEIGHT, None).await;
+    wait_for_l1_block(&da_service, 3, None).await;
+
+    // Proof of the blocks before the rotation
+    wait_for_prover_l1_height(&prover_node_test_client, 4, None)
+        .await
+        .unwrap();
+    wait_for_l1_block(&da_service, 4, None).await;
+
+    // A transaction signed into a sov tx with the new key
+    let _pending = test_client
+        .send_eth(Address::repeat_byte(1), None, None, None, 1u128)
+        .await
+        .unwrap();
+    // Blocks up to 6 are committed once there are 4 uncommitted blocks
+    for _ in ROTATION_L2_HEIGHT + 1..=6 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 6, None).await;
+    wait_for_l1_block(&da_service, 5, None).await;
+
+    // Proof of the blocks after the rotation
+    wait_for_prover_l1_height(&prover_node_test_client, 6, None)
+        .await
+        .unwrap();
+    wait_for_l1_block(&da_service, 6, None).await;
+
+    let pre_rotation_commitments = prover_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(3)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(pre_rotation_commitments.len(), 1);
+    assert_eq!(pre_rotation_commitments[0].l2_start_block_number, 1);
+    assert_eq!(
+        pre_rotation_commitments[0].l2_end_block_number,
+        ROTATION_L2_HEIGHT - 1
+    );
+
+    let post_rotation_commitments = prover_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(5)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(post_rotation_commitments.len(), 1);
+    assert_eq!(
+        post_rotation_commitments[0].l2_start_block_number,
+        ROTATION_L2_HEIGHT
+    );
+    assert_eq!(post_rotation_commitments[0].l2_end_block_number, 6);
+
+    // Make the full node scan the DA blocks with the proofs
+    for i in 7..=8 {
+        test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&full_node_test_client, i, None).await;
+    }
+    wait_for_proof(&full_node_test_client, 4, Some(Duration::from_secs(60))).await;
+    wait_for_proof(&full_node_test_client, 6, Some(Duration::from_secs(60))).await;
+
+    let pre_rotation_proof = full_node_test_client
+        .ledger_get_verified_batch_proofs_by_slot_height(4)
+        .await
+        .unwrap();
+    assert_eq!(
+        pre_rotation_proof[0].proof_output.sequencer_public_key,
+        old_pub_key
+    );
+    let post_rotation_proof = full_node_test_client
+        .ledger_get_verified_batch_proofs_by_slot_height(6)
+        .await
+        .unwrap();
+    assert_eq!(
+        post_rotation_proof[0].proof_output.sequencer_public_key,
+        new_pub_key
+    );
+
+    for i in 1..=6 {
+        let soft_confirmation = full_node_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(i)
+            .await
+            .unwrap();
+        let expected_pub_key = if i < ROTATION_L2_HEIGHT {
+            &old_pub_key
+        } else {
+            &new_pub_key
+        };
+        assert_eq!(&soft_confirmation.pub_key, expected_pub_key);
+
+        let status = full_node_test_client
+            .ledger_get_soft_confirmation_status(i)
+            .await
+            .unwrap();
+        assert_eq!(status, SoftConfirmationStatus::Proven);
+    }
+
+    seq_task.abort();
+    prover_node_task.abort();
+    full_node_task.abort();
+}
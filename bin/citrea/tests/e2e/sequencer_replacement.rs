@@ -313,6 +313,14 @@ async fn test_sequencer_crash_restore_mempool() -> Result<(), anyhow::Error> {
     // tokio task, wait for 2 seconds for this to execute.
     sleep(Duration::from_secs(2)).await;
 
+    // restored txs should be mined in the first block after restart
+    let block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(1)))
+        .await;
+    let block_transactions = block.transactions.as_hashes().unwrap();
+    assert!(block_transactions.contains(tx_hash));
+    assert!(block_transactions.contains(tx_hash2));
+
     // should be removed from mempool
     assert!(seq_test_client
         .eth_get_transaction_by_hash(*tx_hash, Some(true))
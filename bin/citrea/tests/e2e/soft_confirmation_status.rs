@@ -1,8 +1,25 @@
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use alloy_primitives::Address;
+use citrea_common::rpc::TxSoftConfirmation;
+use citrea_common::BatchProverConfig;
+use citrea_fullnode::commitment_proof::verify_commitment_inclusion_proof;
+use citrea_stf::genesis_config::GenesisPaths;
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::{MerkleProof, MerkleTree};
 use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
+use sov_rollup_interface::da::{DaData, SequencerCommitment};
 use sov_rollup_interface::rpc::SoftConfirmationStatus;
+use sov_rollup_interface::spec::SpecId;
 
 use crate::e2e::{initialize_test, TestConfig};
-use crate::test_helpers::{tempdir_with_children, wait_for_l1_block, wait_for_l2_block};
+use crate::test_client::TestClient;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l1_block,
+    wait_for_l2_block, wait_for_proof, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
 
 /// Run the sequencer and full node.
 /// Trigger sequencer commitments.
@@ -163,3 +180,547 @@ async fn test_soft_confirmations_status_two_l1() -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+/// Run the sequencer and full node, then a prover once the commitment is seen.
+/// Check that a single soft confirmation goes through Trusted, Finalized and
+/// Proven on the full node in that order.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_soft_confirmation_status_three_stages() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "prover", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let da_service = MockDaService::new(MockAddress::default(), &da_db_dir);
+
+    let (seq_test_client, full_node_test_client, seq_task, full_node_task, _) =
+        initialize_test(TestConfig {
+            da_path: da_db_dir.clone(),
+            sequencer_path: sequencer_db_dir.clone(),
+            fullnode_path: fullnode_db_dir.clone(),
+            seq_min_soft_confirmations: 3,
+            deposit_mempool_fetch_limit: 10,
+        })
+        .await;
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 1, None).await;
+
+    // Synced but not committed yet, the status is stored explicitly
+    assert_eq!(
+        full_node_test_client
+            .ledger_get_soft_confirmation_status(1)
+            .await
+            .unwrap(),
+        SoftConfirmationStatus::Trusted
+    );
+
+    for _ in 2..=3 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 3, None).await;
+
+    // L2 blocks 1-3 create an L1 block with commitment
+    wait_for_l1_block(&da_service, 2, None).await;
+    full_node_test_client
+        .wait_for_status(1, SoftConfirmationStatus::Finalized, None)
+        .await;
+
+    // Start the prover only now so the proof cannot land before the commitment is processed
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+    let rollup_config = create_default_rollup_config(
+        true,
+        &prover_db_dir,
+        &da_db_dir,
+        NodeMode::Prover(seq_test_client.rpc_addr),
+    );
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(BatchProverConfig {
+                proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
+                proof_sampling_number: 0,
+                enable_recovery: true,
+                ..Default::default()
+            }),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+    prover_node_port_rx.await.unwrap();
+
+    // The proof is published in L1 block #3
+    wait_for_l1_block(&da_service, 3, None).await;
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_proof(&full_node_test_client, 3, Some(Duration::from_secs(120))).await;
+
+    full_node_test_client
+        .wait_for_status(1, SoftConfirmationStatus::Proven, None)
+        .await;
+
+    seq_task.abort();
+    prover_node_task.abort();
+    full_node_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer, full node and a prover.
+/// Send a tx and check that `citrea_getSoftConfirmationByTxHash` resolves it
+/// to the same soft confirmation while it goes through Trusted, Finalized and Proven.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_soft_confirmation_by_tx_hash() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "prover", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let prover_db_dir = storage_dir.path().join("prover").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let da_service = MockDaService::new(MockAddress::default(), &da_db_dir);
+
+    let (seq_test_client, full_node_test_client, seq_task, full_node_task, _) =
+        initialize_test(TestConfig {
+            da_path: da_db_dir.clone(),
+            sequencer_path: sequencer_db_dir.clone(),
+            fullnode_path: fullnode_db_dir.clone(),
+            seq_min_soft_confirmations: 3,
+            deposit_mempool_fetch_limit: 10,
+        })
+        .await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+    let pending_tx = seq_test_client
+        .send_eth(addr, None, None, None, 1u128)
+        .await
+        .unwrap();
+    let tx_hash = *pending_tx.tx_hash();
+
+    // Still in the mempool
+    assert_eq!(
+        seq_test_client
+            .citrea_get_soft_confirmation_by_tx_hash(tx_hash)
+            .await,
+        None
+    );
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 1, None).await;
+
+    let trusted = full_node_test_client
+        .citrea_get_soft_confirmation_by_tx_hash(tx_hash)
+        .await
+        .unwrap();
+    let soft_confirmation = full_node_test_client
+        .ledger_get_soft_confirmation_by_number::<MockDaSpec>(1)
+        .await
+        .unwrap();
+    assert_eq!(trusted.l2_height, 1);
+    assert_eq!(trusted.hash, soft_confirmation.hash);
+    assert_eq!(trusted.da_slot_height, soft_confirmation.da_slot_height);
+    assert_eq!(trusted.status, SoftConfirmationStatus::Trusted);
+    assert_eq!(trusted.commitment_l1_height, None);
+
+    for _ in 2..=3 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 3, None).await;
+
+    // L2 blocks 1-3 create an L1 block with commitment
+    wait_for_l1_block(&da_service, 2, None).await;
+    full_node_test_client
+        .wait_for_status(1, SoftConfirmationStatus::Finalized, None)
+        .await;
+
+    let finalized = full_node_test_client
+        .citrea_get_soft_confirmation_by_tx_hash(tx_hash)
+        .await
+        .unwrap();
+    assert_eq!(finalized.status, SoftConfirmationStatus::Finalized);
+    assert_eq!(finalized.commitment_l1_height, Some(2));
+    assert_eq!(finalized.hash, trusted.hash);
+
+    let (prover_node_port_tx, prover_node_port_rx) = tokio::sync::oneshot::channel();
+    let rollup_config = create_default_rollup_config(
+        true,
+        &prover_db_dir,
+        &da_db_dir,
+        NodeMode::Prover(seq_test_client.rpc_addr),
+    );
+    let prover_node_task = tokio::spawn(async {
+        start_rollup(
+            prover_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            Some(BatchProverConfig {
+                proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
+                proof_sampling_number: 0,
+                enable_recovery: true,
+                ..Default::default()
+            }),
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+    prover_node_port_rx.await.unwrap();
+
+    // The proof is published in L1 block #3
+    wait_for_l1_block(&da_service, 3, None).await;
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_proof(&full_node_test_client, 3, Some(Duration::from_secs(120))).await;
+    full_node_test_client
+        .wait_for_status(1, SoftConfirmationStatus::Proven, None)
+        .await;
+
+    let proven = full_node_test_client
+        .citrea_get_soft_confirmation_by_tx_hash(tx_hash)
+        .await
+        .unwrap();
+    assert_eq!(
+        proven,
+        TxSoftConfirmation {
+            status: SoftConfirmationStatus::Proven,
+            ..finalized
+        }
+    );
+
+    seq_task.abort();
+    prover_node_task.abort();
+    full_node_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer and full node.
+/// Trigger a sequencer commitment over an odd number of soft confirmations.
+/// Check that `ledger_getCommitmentInclusionProof` returns nothing before the commitment,
+/// then proofs which verify against the root of the commitment stored for the L1 block.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_commitment_inclusion_proof() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let da_service = MockDaService::new(MockAddress::default(), &da_db_dir);
+
+    let (seq_test_client, full_node_test_client, seq_task, full_node_task, _) =
+        initialize_test(TestConfig {
+            da_path: da_db_dir.clone(),
+            sequencer_path: sequencer_db_dir.clone(),
+            fullnode_path: fullnode_db_dir.clone(),
+            seq_min_soft_confirmations: 5,
+            deposit_mempool_fetch_limit: 10,
+        })
+        .await;
+
+    for _ in 1..=4 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 4, None).await;
+
+    // Not covered by a commitment yet
+    assert!(full_node_test_client
+        .ledger_get_commitment_inclusion_proof(1)
+        .await
+        .is_none());
+
+    // L2 blocks 1-5 create an L1 block with commitment
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 5, None).await;
+    wait_for_l1_block(&da_service, 2, None).await;
+    full_node_test_client
+        .wait_for_status(5, SoftConfirmationStatus::Finalized, None)
+        .await;
+
+    let commitments = full_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(2)
+        .await?
+        .unwrap();
+    assert_eq!(commitments.len(), 1);
+    let commitment = &commitments[0];
+
+    for l2_height in 1..=5 {
+        let inclusion_proof = full_node_test_client
+            .ledger_get_commitment_inclusion_proof(l2_height)
+            .await
+            .unwrap();
+        let soft_confirmation = full_node_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        assert_eq!(inclusion_proof.l2_height, l2_height);
+        assert_eq!(inclusion_proof.leaf_index, l2_height - 1);
+        assert_eq!(inclusion_proof.leaf_count, 5);
+        assert_eq!(inclusion_proof.leaf_hash, soft_confirmation.hash);
+        assert_eq!(inclusion_proof.commitment.found_in_l1, 2);
+
+        // The full node recomputes the hash signed by the sequencer
+        let sequencer_soft_confirmation = seq_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        assert_eq!(sequencer_soft_confirmation.hash, soft_confirmation.hash);
+        let hash_response = full_node_test_client
+            .ledger_compute_soft_confirmation_hash(sequencer_soft_confirmation)
+            .await;
+        assert_eq!(hash_response.hash, soft_confirmation.hash);
+        assert!(hash_response.matches_stored);
+
+        // As does the offline verification of the `verify-confirmation` command
+        assert_eq!(
+            verify_commitment_inclusion_proof(&soft_confirmation, &inclusion_proof)?,
+            soft_confirmation.hash
+        );
+        let mut tampered = soft_confirmation.clone();
+        tampered.timestamp += 1;
+        assert!(verify_commitment_inclusion_proof(&tampered, &inclusion_proof).is_err());
+        assert_eq!(
+            inclusion_proof.commitment.merkle_root,
+            commitment.merkle_root
+        );
+
+        let proof =
+            MerkleProof::<Sha256>::new(inclusion_proof.proof.iter().map(|hash| hash.0).collect());
+        assert!(proof.verify(
+            commitment.merkle_root,
+            &[inclusion_proof.leaf_index as usize],
+            &[inclusion_proof.leaf_hash],
+            inclusion_proof.leaf_count as usize,
+        ));
+        // The proof is bound to its leaf
+        assert!(!proof.verify(
+            commitment.merkle_root,
+            &[inclusion_proof.leaf_index as usize],
+            &[[0; 32]],
+            inclusion_proof.leaf_count as usize,
+        ));
+    }
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 6, None).await;
+    assert!(full_node_test_client
+        .ledger_get_commitment_inclusion_proof(6)
+        .await
+        .is_none());
+
+    seq_task.abort();
+    full_node_task.abort();
+
+    Ok(())
+}
+
+/// Run the sequencer and full node.
+/// Trigger a sequencer commitment landing on DA block #2, then reorg the DA
+/// layer below it with `depth` orphaned blocks.
+/// Check if the full node rolls back the commitment and the soft confirmation
+/// statuses before processing the new chain.
+async fn test_soft_confirmations_status_after_l1_reorg(depth: u64) {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let da_service = MockDaService::new(MockAddress::default(), &da_db_dir);
+
+    let (seq_test_client, full_node_test_client, seq_task, full_node_task, _) =
+        initialize_test(TestConfig {
+            da_path: da_db_dir.clone(),
+            sequencer_path: sequencer_db_dir.clone(),
+            fullnode_path: fullnode_db_dir.clone(),
+            seq_min_soft_confirmations: 3,
+            deposit_mempool_fetch_limit: 10,
+        })
+        .await;
+
+    for _ in 1..=3 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 3, None).await;
+
+    // DA block #2 contains the commitment for L2 blocks 1-3
+    wait_for_l1_block(&da_service, 2, None).await;
+    for _ in 2..=depth {
+        da_service.publish_test_block().await.unwrap();
+    }
+    let orphaned_head = depth + 1;
+    wait_for_scanned_l1_height(&full_node_test_client, orphaned_head).await;
+
+    let commitments = full_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(2)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(commitments.len(), 1);
+    for i in 1..=3 {
+        let status = full_node_test_client
+            .ledger_get_soft_confirmation_status(i)
+            .await
+            .unwrap();
+        assert_eq!(SoftConfirmationStatus::Finalized, status);
+    }
+
+    // Replace blocks 2..=orphaned_head with a longer chain without the commitment
+    da_service
+        .reorg_finalized_at(1, orphaned_head)
+        .await
+        .unwrap();
+    wait_for_scanned_l1_height(&full_node_test_client, orphaned_head + 1).await;
+
+    assert!(full_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(2)
+        .await
+        .unwrap()
+        .is_none());
+    for i in 1..=3 {
+        let status = full_node_test_client
+            .ledger_get_soft_confirmation_status(i)
+            .await
+            .unwrap();
+        assert_eq!(SoftConfirmationStatus::Trusted, status);
+    }
+
+    seq_task.abort();
+    full_node_task.abort();
+}
+
+async fn wait_for_scanned_l1_height(client: &TestClient, l1_height: u64) {
+    let start = SystemTime::now();
+    let timeout = Duration::from_secs(60);
+    while client.ledger_get_last_scanned_l1_height().await < l1_height {
+        if start + timeout <= SystemTime::now() {
+            panic!(
+                "Timeout while waiting for L1 height {} to be scanned",
+                l1_height
+            );
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_soft_confirmations_status_after_l1_reorg_depth_1() {
+    test_soft_confirmations_status_after_l1_reorg(1).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_soft_confirmations_status_after_l1_reorg_depth_2() {
+    test_soft_confirmations_status_after_l1_reorg(2).await;
+}
+
+/// Run the sequencer and full node.
+/// Post a commitment matching the soft confirmations from a DA address other than the sequencer's.
+/// Check if the full node ignores it, then finalizes the blocks once the sequencer posts
+/// the same commitment, exposing the sequencer as its sender.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_commitment_of_other_sender_is_ignored() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let sequencer_da_service = MockDaService::new(MockAddress::default(), &da_db_dir);
+    let other_da_service = MockDaService::new(MockAddress::from([1; 32]), &da_db_dir);
+
+    // The sequencer does not commit on its own
+    let (seq_test_client, full_node_test_client, seq_task, full_node_task, _) =
+        initialize_test(TestConfig {
+            da_path: da_db_dir.clone(),
+            sequencer_path: sequencer_db_dir.clone(),
+            fullnode_path: fullnode_db_dir.clone(),
+            seq_min_soft_confirmations: 1000,
+            deposit_mempool_fetch_limit: 10,
+        })
+        .await;
+
+    for _ in 1..=3 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 3, None).await;
+
+    let mut soft_confirmation_hashes = vec![];
+    for l2_height in 1..=3 {
+        let soft_confirmation = full_node_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        soft_confirmation_hashes.push(soft_confirmation.hash);
+    }
+    let commitment = SequencerCommitment {
+        merkle_root: MerkleTree::<Sha256>::from_leaves(&soft_confirmation_hashes)
+            .root()
+            .unwrap(),
+        l2_start_block_number: 1,
+        l2_end_block_number: 3,
+    };
+
+    other_da_service
+        .publish_test_block_with_da_data(
+            vec![DaData::SequencerCommitment(commitment.clone())],
+            SpecId::Fork1,
+        )
+        .await
+        .unwrap();
+    let other_commitment_l1_height = other_da_service.get_height().await;
+    wait_for_scanned_l1_height(&full_node_test_client, other_commitment_l1_height).await;
+
+    assert!(full_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(other_commitment_l1_height)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(full_node_test_client
+        .ledger_get_sequencer_commitment_by_l2_height(1)
+        .await
+        .is_none());
+    for i in 1..=3 {
+        let status = full_node_test_client
+            .ledger_get_soft_confirmation_status(i)
+            .await
+            .unwrap();
+        assert_eq!(SoftConfirmationStatus::Trusted, status);
+    }
+
+    sequencer_da_service
+        .publish_test_block_with_da_data(
+            vec![DaData::SequencerCommitment(commitment.clone())],
+            SpecId::Fork1,
+        )
+        .await
+        .unwrap();
+    let commitment_l1_height = sequencer_da_service.get_height().await;
+    wait_for_scanned_l1_height(&full_node_test_client, commitment_l1_height).await;
+
+    let commitment_response = full_node_test_client
+        .wait_for_commitment_covering(1, None)
+        .await;
+    assert_eq!(commitment_response.found_in_l1, commitment_l1_height);
+    assert_eq!(commitment_response.merkle_root, commitment.merkle_root);
+    assert_eq!(
+        commitment_response.sender.unwrap().tx,
+        MockAddress::default().as_ref().to_vec()
+    );
+    for i in 1..=3 {
+        full_node_test_client
+            .wait_for_status(i, SoftConfirmationStatus::Finalized, None)
+            .await;
+    }
+
+    seq_task.abort();
+    full_node_task.abort();
+}
@@ -1,19 +1,41 @@
 /// Testing sycning behaviour of the full nodes and the prover node.
+use std::net::SocketAddr;
+use std::path::Path;
 use std::str::FromStr;
 use std::time::Duration;
 
-use alloy_primitives::Address;
+use alloy_primitives::{Address, U64};
+use citrea::{CitreaRollupBlueprint, MockDemoRollup};
+use citrea_common::utils::check_l2_genesis_state_root;
 use citrea_common::{BatchProverConfig, SequencerConfig};
 use citrea_stf::genesis_config::GenesisPaths;
-use ethereum_rpc::LayerStatus;
+use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use jsonrpsee::types::error::INTERNAL_ERROR_CODE;
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
 use reth_primitives::BlockNumberOrTag;
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::MerkleTree;
+use sov_db::ledger_db::migrations::copy_db_dir_recursive;
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_db::rocks_db_config::RocksdbConfig;
+use sov_ledger_rpc::LedgerRpcClient;
 use sov_mock_da::{MockAddress, MockDaService, MockDaSpec, MockHash};
-use sov_rollup_interface::da::{DaDataLightClient, DaSpec};
+use sov_rollup_interface::da::{
+    DaData, DaDataLightClient, DaSpec, SequencerCommitment, VersionedDaData,
+};
+use sov_rollup_interface::rpc::{
+    SoftConfirmationDetail, SoftConfirmationResponse, SoftConfirmationStatus,
+};
 use sov_rollup_interface::services::da::DaService;
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::Network;
 use tokio::time::sleep;
 
 use crate::e2e::{execute_blocks, initialize_test, TestConfig};
 use crate::evm::{init_test_rollup, make_test_client};
+use crate::test_client::TestClient;
 use crate::test_helpers::{
     create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l1_block,
     wait_for_l2_block, wait_for_prover_l1_height, NodeMode,
@@ -299,6 +321,7 @@ async fn test_prover_sync_with_commitments() -> Result<(), anyhow::Error> {
                 proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
                 proof_sampling_number: 0,
                 enable_recovery: true,
+                ..Default::default()
             }),
             None,
             rollup_config,
@@ -375,7 +398,7 @@ async fn test_prover_sync_with_commitments() -> Result<(), anyhow::Error> {
 
     let da_data = blob.data.accumulator();
 
-    let data: DaDataLightClient = borsh::BorshDeserialize::try_from_slice(da_data).unwrap();
+    let data = DaDataLightClient::decode_versioned(da_data).unwrap();
 
     // Test we got zkproof indeed
     let DaDataLightClient::Complete(_proof) = data else {
@@ -461,22 +484,18 @@ async fn test_full_node_sync_status() {
 
     wait_for_l2_block(&full_node_test_client, 5, Some(Duration::from_secs(60))).await;
 
-    let l2_status = full_node_test_client.citrea_sync_status().await.l2_status;
-    match l2_status {
-        LayerStatus::Syncing(syncing) => {
-            assert!(syncing.synced_block_number > 0 && syncing.synced_block_number < 300);
-            assert_eq!(syncing.head_block_number, 300);
-        }
-        _ => panic!("Expected syncing status"),
-    }
+    // The full node is still far behind the sequencer
+    let status = full_node_test_client.citrea_sync_status().await;
+    assert!(status.l2_head > 0 && status.l2_head < 300);
+    assert_eq!(status.sequencer_l2_head, Some(300));
+    assert!(!status.synced);
 
     wait_for_l2_block(&full_node_test_client, 300, Some(Duration::from_secs(60))).await;
 
-    let l2_status = full_node_test_client.citrea_sync_status().await.l2_status;
-    match l2_status {
-        LayerStatus::Synced(synced_up_to) => assert_eq!(synced_up_to, 300),
-        _ => panic!("Expected synced status"),
-    }
+    let status = full_node_test_client.citrea_sync_status().await;
+    assert_eq!(status.l2_head, 300);
+    assert_eq!(status.sequencer_l2_head, Some(300));
+    assert!(status.synced);
 
     // test l1 sync status
     let da_service = MockDaService::new(MockAddress::default(), &da_db_dir);
@@ -486,22 +505,17 @@ async fn test_full_node_sync_status() {
     wait_for_prover_l1_height(&full_node_test_client, 1, Some(Duration::from_secs(60)))
         .await
         .unwrap();
-    let l1_status = full_node_test_client.citrea_sync_status().await.l1_status;
-    match l1_status {
-        LayerStatus::Syncing(syncing) => {
-            assert!(syncing.synced_block_number > 0 && syncing.synced_block_number < 20);
-            assert_eq!(syncing.head_block_number, 20);
-        }
-        _ => panic!("Expected syncing status"),
-    }
+    let status = full_node_test_client.citrea_sync_status().await;
+    assert!(status.last_scanned_l1_height > 0 && status.last_scanned_l1_height < 20);
+    assert_eq!(status.last_finalized_l1_height, Some(20));
+
     wait_for_prover_l1_height(&full_node_test_client, 20, Some(Duration::from_secs(60)))
         .await
         .unwrap();
-    let l1_status = full_node_test_client.citrea_sync_status().await.l1_status;
-    match l1_status {
-        LayerStatus::Synced(synced_up_to) => assert_eq!(synced_up_to, 20),
-        _ => panic!("Expected synced status"),
-    }
+    let status = full_node_test_client.citrea_sync_status().await;
+    assert_eq!(status.last_scanned_l1_height, 20);
+    assert_eq!(status.last_finalized_l1_height, Some(20));
+    assert!(status.synced);
 
     seq_task.abort();
     full_node_task.abort();
@@ -576,3 +590,505 @@ async fn test_healthcheck() {
 
     full_node_task.abort();
 }
+
+async fn start_full_node(
+    fullnode_db_dir: &std::path::Path,
+    da_db_dir: &std::path::Path,
+    seq_port: std::net::SocketAddr,
+    sync_blocks_count: u64,
+    commit_blocks_count: u64,
+) -> (Box<TestClient>, tokio::task::JoinHandle<()>) {
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config = create_default_rollup_config(
+        true,
+        fullnode_db_dir,
+        da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let runner_config = rollup_config.runner.as_mut().unwrap();
+    runner_config.sync_blocks_count = sync_blocks_count;
+    runner_config.commit_blocks_count = commit_blocks_count;
+    let full_node_task = tokio::spawn(async {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            None,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await.unwrap();
+
+    (full_node_test_client, full_node_task)
+}
+
+/// Run the sequencer.
+/// Publish blocks.
+/// Catch up with a full node committing every block and one committing blocks in batches.
+/// Check if both full nodes store the same state roots as the sequencer.
+/// Restart the batching full node and check if it resumes from its last committed block.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_batched_catch_up_sync() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir =
+        tempdir_with_children(&["DA", "sequencer", "full-node-single", "full-node-batched"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let single_db_dir = storage_dir.path().join("full-node-single").to_path_buf();
+    let batched_db_dir = storage_dir.path().join("full-node-batched").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+
+    let seq_test_client = init_test_rollup(seq_port).await;
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    for _ in 0..100 {
+        let _pending = seq_test_client
+            .send_eth(addr, None, None, None, 1u128)
+            .await
+            .unwrap();
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&seq_test_client, 100, Some(Duration::from_secs(60))).await;
+
+    let mut full_nodes = vec![];
+    for (db_dir, commit_blocks_count) in [(&single_db_dir, 1), (&batched_db_dir, 25)] {
+        let (full_node_test_client, full_node_task) =
+            start_full_node(db_dir, &da_db_dir, seq_port, 25, commit_blocks_count).await;
+        wait_for_l2_block(&full_node_test_client, 100, Some(Duration::from_secs(60))).await;
+        full_nodes.push((full_node_test_client, full_node_task));
+    }
+
+    for l2_height in 1..=100 {
+        let seq_soft_confirmation = seq_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        for (full_node_test_client, _) in &full_nodes {
+            let full_node_soft_confirmation = full_node_test_client
+                .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+                .await
+                .unwrap();
+            assert_eq!(
+                seq_soft_confirmation.state_root,
+                full_node_soft_confirmation.state_root
+            );
+            assert_eq!(seq_soft_confirmation.hash, full_node_soft_confirmation.hash);
+        }
+    }
+
+    // Restart the batching full node on its storage while the sequencer moves on
+    let (_, batched_full_node_task) = full_nodes.pop().unwrap();
+    batched_full_node_task.abort();
+
+    for _ in 0..30 {
+        let _pending = seq_test_client
+            .send_eth(addr, None, None, None, 1u128)
+            .await
+            .unwrap();
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&seq_test_client, 130, Some(Duration::from_secs(60))).await;
+
+    let (full_node_test_client, full_node_task) =
+        start_full_node(&batched_db_dir, &da_db_dir, seq_port, 25, 25).await;
+    wait_for_l2_block(&full_node_test_client, 130, Some(Duration::from_secs(60))).await;
+
+    let seq_block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(130)))
+        .await;
+    let full_node_block = full_node_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(130)))
+        .await;
+    assert_eq!(
+        seq_block.header.state_root,
+        full_node_block.header.state_root
+    );
+    assert_eq!(seq_block.header.hash, full_node_block.header.hash);
+
+    seq_task.abort();
+    full_node_task.abort();
+    for (_, task) in full_nodes {
+        task.abort();
+    }
+
+    Ok(())
+}
+
+/// Run the sequencer without commitments and the full node.
+/// Publish an L1 block containing the same sequencer commitment twice, then one more copy in a later block.
+/// Check if the full node stores the commitment once and finalizes its L2 range.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_duplicate_sequencer_commitments() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    // The commitments are published with the sequencer's DA key
+    let sequencer_da_address = rollup_config.da.sender_address.clone();
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await?;
+
+    let da_service = MockDaService::new(sequencer_da_address, &da_db_dir);
+
+    for _ in 0..4 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&seq_test_client, 4, None).await;
+
+    let (full_node_test_client, full_node_task) =
+        start_full_node(&fullnode_db_dir, &da_db_dir, seq_port, 10, 10).await;
+    wait_for_l2_block(&full_node_test_client, 4, None).await;
+
+    let mut soft_confirmation_hashes = vec![];
+    for l2_height in 1..=4 {
+        let soft_confirmation = seq_test_client
+            .ledger_get_soft_confirmation_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        soft_confirmation_hashes.push(soft_confirmation.hash);
+    }
+    let commitment = SequencerCommitment {
+        merkle_root: MerkleTree::<Sha256>::from_leaves(&soft_confirmation_hashes)
+            .root()
+            .unwrap(),
+        l2_start_block_number: 1,
+        l2_end_block_number: 4,
+    };
+
+    // The same commitment blob twice in one block, as if the DA tx was rebroadcast
+    da_service
+        .publish_test_block_with_da_data(
+            vec![
+                DaData::SequencerCommitment(commitment.clone()),
+                DaData::SequencerCommitment(commitment.clone()),
+            ],
+            SpecId::Fork1,
+        )
+        .await?;
+    let commitment_l1_height = da_service.get_height().await;
+    // And once more in a later block
+    da_service
+        .publish_test_block_with_da_data(
+            vec![DaData::SequencerCommitment(commitment.clone())],
+            SpecId::Fork1,
+        )
+        .await?;
+    let duplicate_l1_height = da_service.get_height().await;
+    wait_for_l1_block(&da_service, duplicate_l1_height, None).await;
+
+    wait_for_prover_l1_height(&full_node_test_client, duplicate_l1_height, None).await?;
+
+    let commitments = full_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(commitment_l1_height)
+        .await?
+        .unwrap();
+    assert_eq!(commitments.len(), 1);
+    assert_eq!(commitments[0].merkle_root, commitment.merkle_root);
+    assert_eq!(commitments[0].l2_start_block_number, 1);
+    assert_eq!(commitments[0].l2_end_block_number, 4);
+    assert_eq!(commitments[0].found_in_l1, commitment_l1_height);
+
+    assert!(full_node_test_client
+        .ledger_get_sequencer_commitments_on_slot_by_number(duplicate_l1_height)
+        .await?
+        .is_none());
+
+    for l2_height in 1..=4 {
+        let status = full_node_test_client
+            .ledger_get_soft_confirmation_status(l2_height)
+            .await
+            .unwrap();
+        assert_eq!(status, SoftConfirmationStatus::Finalized);
+    }
+
+    seq_task.abort();
+    full_node_task.abort();
+
+    Ok(())
+}
+
+/// Proxies the ledger RPCs the full node syncs from to the sequencer at `seq_port`,
+/// altering the soft confirmation at `tampered_height` with `tamper`.
+async fn start_tampering_sequencer_proxy(
+    seq_port: SocketAddr,
+    tampered_height: u64,
+    tamper: fn(&mut SoftConfirmationResponse),
+) -> (SocketAddr, ServerHandle) {
+    let client = HttpClientBuilder::default()
+        .build(format!("http://{}", seq_port))
+        .unwrap();
+    let mut rpc = RpcModule::new(client);
+
+    rpc.register_async_method(
+        "ledger_getSoftConfirmationByNumber",
+        |params, client, _| async move {
+            let mut params = params.sequence();
+            let number: U64 = params.next()?;
+            let detail: Option<SoftConfirmationDetail> = params.optional_next()?;
+            client
+                .get_soft_confirmation_by_number(number, detail)
+                .await
+                .map_err(proxy_error)
+        },
+    )
+    .unwrap();
+    rpc.register_async_method(
+        "ledger_getSoftConfirmationRange",
+        move |params, client, _| async move {
+            let mut params = params.sequence();
+            let start: U64 = params.next()?;
+            let end: U64 = params.next()?;
+            let detail: Option<SoftConfirmationDetail> = params.optional_next()?;
+            let mut soft_confirmations = client
+                .get_soft_confirmation_range(start, end, detail)
+                .await
+                .map_err(proxy_error)?;
+            for soft_confirmation in soft_confirmations.iter_mut().flatten() {
+                if soft_confirmation.l2_height == tampered_height {
+                    tamper(soft_confirmation);
+                }
+            }
+            Ok::<_, ErrorObjectOwned>(soft_confirmations)
+        },
+    )
+    .unwrap();
+
+    let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap();
+    (addr, server.start(rpc))
+}
+
+fn proxy_error(e: jsonrpsee::core::client::Error) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, e.to_string(), None::<()>)
+}
+
+/// Run the sequencer and publish blocks.
+/// Run the full node syncing through a proxy that alters one soft confirmation with `tamper`.
+/// Check if the full node stops syncing right before the altered soft confirmation.
+async fn assert_full_node_rejects_tampered_soft_confirmation(
+    tamper: fn(&mut SoftConfirmationResponse),
+) -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig {
+        min_soft_confirmations_per_commitment:
+            TEST_SEND_NO_COMMITMENT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        ..Default::default()
+    };
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    for _ in 0..6 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&seq_test_client, 6, None).await;
+
+    let (proxy_addr, proxy_handle) = start_tampering_sequencer_proxy(seq_port, 4, tamper).await;
+    let (full_node_test_client, full_node_task) =
+        start_full_node(&fullnode_db_dir, &da_db_dir, proxy_addr, 10, 1).await;
+
+    wait_for_l2_block(&full_node_test_client, 3, None).await;
+    // Give the full node time to retry the altered soft confirmation
+    sleep(Duration::from_secs(5)).await;
+
+    assert_eq!(
+        full_node_test_client
+            .ledger_get_head_soft_confirmation_height()
+            .await
+            .unwrap(),
+        3
+    );
+    assert!(full_node_test_client
+        .ledger_get_soft_confirmation_by_number::<MockDaSpec>(4)
+        .await
+        .is_none());
+
+    seq_task.abort();
+    full_node_task.abort();
+    proxy_handle.stop().unwrap();
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_node_rejects_invalid_soft_confirmation_signature() -> Result<(), anyhow::Error> {
+    assert_full_node_rejects_tampered_soft_confirmation(|soft_confirmation| {
+        let signature = &mut soft_confirmation.soft_confirmation_signature;
+        let last = signature.len() - 1;
+        signature[last] ^= 1;
+    })
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_node_rejects_wrong_da_slot_txs_commitment() -> Result<(), anyhow::Error> {
+    assert_full_node_rejects_tampered_soft_confirmation(|soft_confirmation| {
+        soft_confirmation.da_slot_txs_commitment[0] ^= 1;
+    })
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_node_rejects_decreasing_timestamp() -> Result<(), anyhow::Error> {
+    assert_full_node_rejects_tampered_soft_confirmation(|soft_confirmation| {
+        soft_confirmation.timestamp = 0;
+    })
+    .await
+}
+
+/// Run the sequencer.
+/// Run a full node whose genesis differs from the sequencer's.
+/// Check that the full node refuses to start, naming both genesis state roots,
+/// and that it only starts if the mismatch is allowed.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_node_genesis_state_root_mismatch() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node", "genesis"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+    let genesis_dir = storage_dir.path().join("genesis").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(Default::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let sequencer_client =
+        HttpClientBuilder::default().build(format!("http://localhost:{}", seq_port.port()))?;
+    let sequencer_genesis_root = sequencer_client.get_l2_genesis_state_root().await?.unwrap();
+
+    // Same genesis, with a different balance for the first account
+    for file in [
+        "accounts.json",
+        "evm.json",
+        "soft_confirmation_rule_enforcer.json",
+    ] {
+        std::fs::copy(
+            Path::new(TEST_DATA_GENESIS_PATH).join(file),
+            genesis_dir.join(file),
+        )?;
+    }
+    let evm_genesis_path = genesis_dir.join("evm.json");
+    let mut evm_genesis: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&evm_genesis_path)?)?;
+    evm_genesis["data"][0]["balance"] = "0x1".into();
+    std::fs::write(&evm_genesis_path, serde_json::to_string(&evm_genesis)?)?;
+
+    let rollup_config = create_default_rollup_config(
+        true,
+        &fullnode_db_dir,
+        &da_db_dir,
+        NodeMode::FullNode(seq_port),
+    );
+    let (mut full_node, _) = CitreaRollupBlueprint::create_new_rollup(
+        &MockDemoRollup::new(Network::Nightly),
+        &GenesisPaths::from_dir(&genesis_dir),
+        rollup_config,
+    )
+    .await?;
+
+    let err = full_node.run().await.unwrap_err().to_string();
+    assert!(err.contains("L2 genesis state root"));
+    assert!(err.contains(&hex::encode(sequencer_genesis_root.0)));
+
+    // Copy the db to a new path with the same contents because
+    // the lock is not released on the db directory of the full node
+    let fullnode_copy_dir = storage_dir.path().join("fullnode_copy");
+    copy_db_dir_recursive(&fullnode_db_dir, &fullnode_copy_dir)?;
+    let ledger_db = LedgerDB::with_config(&RocksdbConfig::new(&fullnode_copy_dir, None, None))?;
+    let fullnode_genesis_root = ledger_db.get_l2_state_root::<[u8; 32]>(0)?.unwrap();
+    assert_ne!(fullnode_genesis_root, sequencer_genesis_root.0);
+    assert!(err.contains(&hex::encode(fullnode_genesis_root)));
+
+    check_l2_genesis_state_root(&ledger_db, &sequencer_client, true).await?;
+
+    seq_task.abort();
+
+    Ok(())
+}
@@ -53,6 +53,121 @@ async fn test_archival_state() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Run an archive sequencer and write a different value to a contract in each of 100 blocks.
+/// Check that `eth_call` at historical blocks returns the value of their time.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_archival_eth_call() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let mut rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    rollup_config.storage.archive = true;
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(SequencerConfig::default()),
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    let contract = SimpleStorageContract::default();
+    let deploy_contract_req = seq_test_client
+        .deploy_contract(contract.byte_code(), None)
+        .await
+        .unwrap();
+    seq_test_client.send_publish_batch_request().await;
+    let contract_address = deploy_contract_req
+        .get_receipt()
+        .await
+        .unwrap()
+        .contract_address
+        .unwrap();
+    let deploy_block = seq_test_client.eth_block_number().await;
+
+    // The value `i` is set in block `deploy_block + i`
+    for value in 1..=100u32 {
+        let set_value_req = seq_test_client
+            .contract_transaction(contract_address, contract.set_call_data(value), None)
+            .await;
+        seq_test_client.send_publish_batch_request().await;
+        set_value_req.get_receipt().await.unwrap();
+        wait_for_l2_block(&seq_test_client, deploy_block + value as u64, None).await;
+    }
+    let head = seq_test_client.eth_block_number().await;
+    assert_eq!(head, deploy_block + 100);
+
+    let contract = &contract;
+    let value_at = |block_id: BlockId| {
+        let seq_test_client = &seq_test_client;
+        async move {
+            let value: U256 = seq_test_client
+                .contract_call_at_block(contract_address, contract.get_call_data(), block_id)
+                .await
+                .unwrap();
+            value.saturating_to::<u32>()
+        }
+    };
+
+    assert_eq!(
+        value_at(BlockId::Number(BlockNumberOrTag::Number(deploy_block))).await,
+        0
+    );
+    for value in [1, 2, 37, 50, 99, 100] {
+        let block_number = deploy_block + value as u64;
+        assert_eq!(
+            value_at(BlockId::Number(BlockNumberOrTag::Number(block_number))).await,
+            value
+        );
+
+        let block_hash = seq_test_client
+            .eth_get_block_by_number(Some(BlockNumberOrTag::Number(block_number)))
+            .await
+            .header
+            .hash;
+        assert_eq!(value_at(BlockId::Hash(block_hash.into())).await, value);
+    }
+    assert_eq!(
+        value_at(BlockId::Number(BlockNumberOrTag::Latest)).await,
+        100
+    );
+
+    // Blocks the node never had are rejected
+    let err = seq_test_client
+        .contract_call_at_block::<U256>(
+            contract_address,
+            contract.get_call_data(),
+            BlockId::Number(BlockNumberOrTag::Number(head + 1)),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("block not found"), "{err}");
+    let err = seq_test_client
+        .contract_call_at_block::<U256>(
+            contract_address,
+            contract.get_call_data(),
+            BlockId::Hash(B256::random().into()),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("block not found: hash"), "{err}");
+
+    seq_task.abort();
+    Ok(())
+}
+
 async fn run_archival_fail_tests(addr: Address, seq_test_client: &TestClient) {
     let invalid_block_hash = B256::random();
     let invalid_block_balance = seq_test_client
@@ -71,7 +186,7 @@ async fn run_archival_fail_tests(addr: Address, seq_test_client: &TestClient) {
 
     assert!(invalid_block_balance
         .to_string()
-        .contains("unknown block or tx index"));
+        .contains("block not found: hash"));
 
     let invalid_block_storage = seq_test_client
         .eth_get_storage_at(
@@ -95,7 +210,7 @@ async fn run_archival_fail_tests(addr: Address, seq_test_client: &TestClient) {
         .unwrap_err();
     assert!(invalid_block_storage
         .to_string()
-        .contains("unknown block or tx index"));
+        .contains("block not found: hash"));
 
     let invalid_block_code = seq_test_client
         .eth_get_code(addr, Some(BlockId::Number(BlockNumberOrTag::Number(722))))
@@ -109,7 +224,7 @@ async fn run_archival_fail_tests(addr: Address, seq_test_client: &TestClient) {
         .unwrap_err();
     assert!(invalid_block_code
         .to_string()
-        .contains("unknown block or tx index"));
+        .contains("block not found: hash"));
 
     let invalid_block_tx_count = seq_test_client
         .eth_get_transaction_count(addr, Some(BlockId::Number(BlockNumberOrTag::Number(722))))
@@ -125,7 +240,7 @@ async fn run_archival_fail_tests(addr: Address, seq_test_client: &TestClient) {
         .unwrap_err();
     assert!(invalid_block_tx_count
         .to_string()
-        .contains("unknown block or tx index"));
+        .contains("block not found: hash"));
 }
 
 async fn run_archival_valid_tests(addr: Address, seq_test_client: &TestClient) {
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+
+use alloy_primitives::Address;
+use alloy_sol_types::SolEvent;
+use citrea_common::SequencerConfig;
+use citrea_evm::smart_contracts::{LogEvent, LogsContract, TestContract};
+use citrea_evm::{Filter, FilterBlockOption, LogResponse};
+use citrea_stf::genesis_config::GenesisPaths;
+use ethereum_rpc::FilterChanges;
+use reth_primitives::BlockNumberOrTag;
+
+use crate::evm::init_test_rollup;
+use crate::test_client::TestClient;
+use crate::test_helpers::{
+    create_default_rollup_config, start_rollup, tempdir_with_children, wait_for_l2_block, NodeMode,
+};
+use crate::TEST_DATA_GENESIS_PATH;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_eth_filters() -> Result<(), Box<dyn std::error::Error>> {
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (port_tx, port_rx) = tokio::sync::oneshot::channel();
+
+    let rollup_config =
+        create_default_rollup_config(true, &sequencer_db_dir, &da_db_dir, NodeMode::SequencerNode);
+    let sequencer_config = SequencerConfig::default();
+    let rollup_task = tokio::spawn(async {
+        start_rollup(
+            port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            None,
+            rollup_config,
+            Some(sequencer_config),
+        )
+        .await;
+    });
+
+    let port = port_rx.await.unwrap();
+    let test_client = init_test_rollup(port).await;
+
+    // Deploy 2 LogsContract, only the events of the first one match the log filter
+    let logs_contract = LogsContract::default();
+    let deploy_req1 = test_client
+        .deploy_contract(logs_contract.byte_code(), None)
+        .await?;
+    let deploy_req2 = test_client
+        .deploy_contract(logs_contract.byte_code(), None)
+        .await?;
+    test_client.send_publish_batch_request().await;
+    let contract_address1 = deploy_req1.get_receipt().await?.contract_address.unwrap();
+    let contract_address2 = deploy_req2.get_receipt().await?.contract_address.unwrap();
+
+    let head = test_client.eth_block_number().await;
+    let mut filter = Filter {
+        block_option: FilterBlockOption::Range {
+            from_block: Some(BlockNumberOrTag::Number(head + 1)),
+            to_block: None,
+        },
+        ..Default::default()
+    };
+    filter.address.0.insert(contract_address1);
+    filter.topics[0].0.insert(LogEvent::SIGNATURE_HASH);
+    let log_filter = test_client.eth_new_filter(filter).await;
+    let block_filter = test_client.eth_new_block_filter().await;
+
+    // Nothing happened since the filters were installed, an empty array is parsed as logs
+    assert_no_changes(test_client.eth_get_filter_changes(log_filter).await?);
+    assert_no_changes(test_client.eth_get_filter_changes(block_filter).await?);
+
+    // Poll after every round, some rounds produce several blocks and some produce empty blocks
+    let mut polled_logs: Vec<LogResponse> = vec![];
+    let mut polled_block_hashes = vec![];
+    let mut matching_tx_hashes = vec![];
+    for (round, blocks) in [1, 3, 1, 2].into_iter().enumerate() {
+        for block in 0..blocks {
+            // Every other block is empty
+            if block % 2 == 0 {
+                for _ in 0..=round {
+                    let tx = test_client
+                        .contract_transaction(
+                            contract_address1,
+                            logs_contract.publish_event("match".into()),
+                            None,
+                        )
+                        .await;
+                    matching_tx_hashes.push(*tx.tx_hash());
+                }
+                test_client
+                    .contract_transaction(
+                        contract_address2,
+                        logs_contract.publish_event("no match".into()),
+                        None,
+                    )
+                    .await;
+            }
+            let next_block = test_client.eth_block_number().await + 1;
+            test_client.send_publish_batch_request().await;
+            wait_for_l2_block(&test_client, next_block, None).await;
+        }
+
+        let FilterChanges::Logs(logs) = test_client.eth_get_filter_changes(log_filter).await?
+        else {
+            panic!("Log filter must return logs");
+        };
+        assert!(!logs.is_empty());
+        polled_logs.extend(logs);
+
+        let FilterChanges::Hashes(hashes) =
+            test_client.eth_get_filter_changes(block_filter).await?
+        else {
+            panic!("Block filter must return hashes");
+        };
+        assert_eq!(hashes.len(), blocks);
+        polled_block_hashes.extend(hashes);
+    }
+
+    // Polling again returns no changes
+    assert_no_changes(test_client.eth_get_filter_changes(log_filter).await?);
+    assert_no_changes(test_client.eth_get_filter_changes(block_filter).await?);
+
+    // Each matching transaction emitted a single log matching the filter, none is missed or repeated
+    assert_eq!(
+        polled_logs
+            .iter()
+            .map(|log| log.transaction_hash.unwrap())
+            .collect::<Vec<_>>(),
+        matching_tx_hashes
+    );
+    assert!(polled_logs
+        .iter()
+        .all(|log| log.address == contract_address1 && log.topics[0] == LogEvent::SIGNATURE_HASH));
+
+    // The polled logs are the ones eth_getLogs returns for the filter
+    assert_eq!(
+        test_client.eth_get_filter_logs(log_filter).await,
+        polled_logs
+    );
+    let get_logs = test_client
+        .eth_get_logs(serde_json::json!({
+            "fromBlock": format!("0x{:x}", head + 1),
+            "toBlock": "latest",
+            "address": contract_address1,
+            "topics": [LogEvent::SIGNATURE_HASH],
+        }))
+        .await;
+    assert_eq!(get_logs, polled_logs);
+
+    // Every block produced since the block filter was installed is polled once, in order
+    let latest = test_client.eth_block_number().await;
+    let mut expected_block_hashes = vec![];
+    for block_number in head + 1..=latest {
+        let block = test_client
+            .eth_get_block_by_number(Some(BlockNumberOrTag::Number(block_number)))
+            .await;
+        expected_block_hashes.push(block.header.hash);
+    }
+    assert_eq!(polled_block_hashes, expected_block_hashes);
+
+    test_pending_transaction_filter(&test_client).await?;
+
+    // Uninstalled filters can't be polled
+    assert!(test_client.eth_uninstall_filter(log_filter).await);
+    assert!(!test_client.eth_uninstall_filter(log_filter).await);
+    assert!(test_client
+        .eth_get_filter_changes(log_filter)
+        .await
+        .is_err());
+    assert!(test_client.eth_uninstall_filter(block_filter).await);
+
+    rollup_task.abort();
+    Ok(())
+}
+
+async fn test_pending_transaction_filter(
+    test_client: &TestClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pending_tx_filter = test_client.eth_new_pending_transaction_filter().await;
+
+    let tx1 = test_client
+        .send_eth(Address::random(), None, None, None, 10000)
+        .await?;
+    let tx2 = test_client
+        .send_eth(Address::random(), None, None, None, 10000)
+        .await?;
+
+    let FilterChanges::Hashes(hashes) = test_client
+        .eth_get_filter_changes(pending_tx_filter)
+        .await?
+    else {
+        panic!("Pending transaction filter must return hashes");
+    };
+    assert_eq!(
+        hashes.into_iter().collect::<HashSet<_>>(),
+        HashSet::from([*tx1.tx_hash(), *tx2.tx_hash()])
+    );
+
+    // Included transactions are not reported again
+    let next_block = test_client.eth_block_number().await + 1;
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(test_client, next_block, None).await;
+    assert_no_changes(
+        test_client
+            .eth_get_filter_changes(pending_tx_filter)
+            .await?,
+    );
+
+    assert!(test_client.eth_uninstall_filter(pending_tx_filter).await);
+    Ok(())
+}
+
+fn assert_no_changes(changes: FilterChanges) {
+    match changes {
+        FilterChanges::Logs(logs) => assert!(logs.is_empty()),
+        FilterChanges::Hashes(hashes) => assert!(hashes.is_empty()),
+    }
+}
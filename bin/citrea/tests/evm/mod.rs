@@ -23,6 +23,7 @@ use crate::{
 
 mod archival_state;
 mod fee;
+mod filter;
 mod gas_price;
 mod subscription;
 mod tracing;
@@ -1,11 +1,16 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 
 use alloy::signers::local::PrivateKeySigner;
 use alloy::signers::Signer;
-use alloy_primitives::Address;
-use citrea_common::SequencerConfig;
+use alloy_primitives::{Address, B256, U256, U64};
+use citrea_common::{SequencerConfig, SequencerMempoolConfig};
+use citrea_evm::smart_contracts::SimpleStorageContract;
+use citrea_evm::LIMIT_EXCEEDED_ERROR_CODE;
+use citrea_sequencer::{KnownAccount, TransactionConditional, CONDITIONS_NOT_MET_ERROR_CODE};
 use citrea_stf::genesis_config::GenesisPaths;
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
 use reth_primitives::BlockNumberOrTag;
 use tokio::task::JoinHandle;
 
@@ -468,3 +473,269 @@ async fn test_same_nonce_tx_replacement() {
 
     seq_task.abort();
 }
+
+/// Transactions after a nonce gap should be reported as queued by the txpool namespace
+#[tokio::test(flavor = "multi_thread")]
+async fn test_txpool_queued_after_nonce_gap() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let (seq_task, test_client) = initialize_test(sequencer_db_dir, da_db_dir).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    // nonces 0 and 1 are executable, 3 and 4 wait for nonce 2
+    let mut pending_hashes = vec![];
+    for nonce in [0, 1] {
+        let tx = test_client
+            .send_eth(addr, None, None, Some(nonce), 0u128)
+            .await
+            .unwrap();
+        pending_hashes.push(*tx.tx_hash());
+    }
+    let mut queued_hashes = vec![];
+    for nonce in [3, 4] {
+        let tx = test_client
+            .send_eth(addr, None, None, Some(nonce), 0u128)
+            .await
+            .unwrap();
+        queued_hashes.push(*tx.tx_hash());
+    }
+
+    let status = test_client.txpool_status().await;
+    assert_eq!(status.pending, U64::from(2));
+    assert_eq!(status.queued, U64::from(2));
+
+    let content = test_client.txpool_content().await;
+    let sender = test_client.from_addr;
+    assert_eq!(content.pending.len(), 1);
+    assert_eq!(content.queued.len(), 1);
+
+    let pending = &content.pending[&sender];
+    assert_eq!(pending.keys().collect::<Vec<_>>(), vec!["0", "1"]);
+    assert_eq!(pending["0"].hash, pending_hashes[0]);
+    assert_eq!(pending["1"].hash, pending_hashes[1]);
+
+    let queued = &content.queued[&sender];
+    assert_eq!(queued.keys().collect::<Vec<_>>(), vec!["3", "4"]);
+    assert_eq!(queued["3"].hash, queued_hashes[0]);
+    assert_eq!(queued["4"].hash, queued_hashes[1]);
+
+    // the pending transactions are rendered the same way as eth_getTransactionByHash
+    let tx = test_client
+        .eth_get_transaction_by_hash(pending_hashes[0], Some(true))
+        .await
+        .unwrap();
+    assert_eq!(tx.hash, pending["0"].hash);
+    assert_eq!(tx.nonce, pending["0"].nonce);
+    assert_eq!(tx.input, pending["0"].input);
+
+    // once the gap is filled, all transactions are executable
+    let _pending = test_client
+        .send_eth(addr, None, None, Some(2), 0u128)
+        .await
+        .unwrap();
+
+    let status = test_client.txpool_status().await;
+    assert_eq!(status.pending, U64::from(5));
+    assert_eq!(status.queued, U64::from(0));
+
+    seq_task.abort();
+}
+
+fn assert_error_code(err: jsonrpsee::core::ClientError, code: i32) {
+    match err {
+        jsonrpsee::core::ClientError::Call(err) => assert_eq!(err.code(), code, "{}", err),
+        err => panic!("unexpected error: {err}"),
+    }
+}
+
+/// A conditional transaction whose validity window passes while it waits in the mempool
+/// should be evicted instead of being mined late.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_conditional_tx_validity_window() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let (seq_task, test_client) = initialize_test(sequencer_db_dir, da_db_dir).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+    let valid_until = |l2_height: u64| TransactionConditional {
+        valid_until_l2_block: Some(U64::from(l2_height)),
+        ..Default::default()
+    };
+
+    // nonce 1 waits for nonce 0, which is only sent after the first block
+    let raw_tx = test_client.sign_eth_transfer(addr, 1, 0).await;
+
+    // windows which already passed are rejected
+    let err = test_client
+        .send_raw_transaction_conditional(raw_tx.clone(), valid_until(0))
+        .await
+        .unwrap_err();
+    assert_error_code(err, CONDITIONS_NOT_MET_ERROR_CODE);
+
+    let tx_hash = test_client
+        .send_raw_transaction_conditional(raw_tx, valid_until(1))
+        .await
+        .unwrap();
+
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 1, None).await;
+    assert!(test_client
+        .eth_get_transaction_by_hash(tx_hash, Some(true))
+        .await
+        .is_some());
+
+    let filler_tx = test_client
+        .send_eth(addr, None, None, Some(0), 0u128)
+        .await
+        .unwrap();
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 2, None).await;
+
+    let block = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Latest))
+        .await;
+    let block_transactions = block.transactions.as_hashes().unwrap();
+    assert!(block_transactions.contains(filler_tx.tx_hash()));
+    assert!(!block_transactions.contains(&tx_hash));
+
+    // evicted, so it is never mined
+    assert!(test_client
+        .eth_get_transaction_by_hash(tx_hash, Some(true))
+        .await
+        .is_none());
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 3, None).await;
+    assert_eq!(
+        test_client
+            .eth_get_transaction_count(test_client.from_addr, None)
+            .await
+            .unwrap(),
+        1
+    );
+
+    seq_task.abort();
+}
+
+/// A conditional transaction whose known storage changes while it waits in the mempool
+/// should be evicted instead of being mined.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_conditional_tx_known_accounts() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let (seq_task, test_client) = initialize_test(sequencer_db_dir, da_db_dir).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    let contract = SimpleStorageContract::default();
+    let deploy_tx = test_client
+        .deploy_contract(contract.byte_code(), Some(0))
+        .await
+        .unwrap();
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 1, None).await;
+    let contract_address = deploy_tx
+        .get_receipt()
+        .await
+        .unwrap()
+        .contract_address
+        .unwrap();
+
+    let slot_value = test_client
+        .eth_get_storage_at(contract_address, U256::ZERO, None)
+        .await
+        .unwrap();
+    let known_accounts = |account: KnownAccount| TransactionConditional {
+        valid_until_l2_block: None,
+        known_accounts: BTreeMap::from([(contract_address, account)]),
+    };
+    let known_slot = |value: U256| {
+        known_accounts(KnownAccount::Slots(BTreeMap::from([(
+            B256::ZERO,
+            B256::from(value),
+        )])))
+    };
+
+    // nonce 3 waits for nonce 2, which is only sent after the slot changes
+    let raw_tx = test_client.sign_eth_transfer(addr, 3, 0).await;
+
+    // conditions which do not hold already are rejected
+    let err = test_client
+        .send_raw_transaction_conditional(raw_tx.clone(), known_slot(slot_value + U256::from(1)))
+        .await
+        .unwrap_err();
+    assert_error_code(err, CONDITIONS_NOT_MET_ERROR_CODE);
+    // accounts have no storage roots of their own
+    let err = test_client
+        .send_raw_transaction_conditional(
+            raw_tx.clone(),
+            known_accounts(KnownAccount::StorageRoot(B256::ZERO)),
+        )
+        .await
+        .unwrap_err();
+    assert_error_code(err, INVALID_PARAMS_CODE);
+    // the number of slots to check is capped
+    let too_many_slots = (0..=SequencerMempoolConfig::default().conditional_tx_max_slots)
+        .map(|slot| (B256::from(U256::from(slot)), B256::ZERO))
+        .collect();
+    let err = test_client
+        .send_raw_transaction_conditional(
+            raw_tx.clone(),
+            known_accounts(KnownAccount::Slots(too_many_slots)),
+        )
+        .await
+        .unwrap_err();
+    assert_error_code(err, LIMIT_EXCEEDED_ERROR_CODE);
+
+    let tx_hash = test_client
+        .send_raw_transaction_conditional(raw_tx, known_slot(slot_value))
+        .await
+        .unwrap();
+
+    let set_value_tx = test_client
+        .contract_transaction(contract_address, contract.set_call_data(42), Some(1))
+        .await;
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 2, None).await;
+    set_value_tx.get_receipt().await.unwrap();
+    let new_slot_value = test_client
+        .eth_get_storage_at(contract_address, U256::ZERO, None)
+        .await
+        .unwrap();
+    assert_ne!(new_slot_value, slot_value);
+
+    // the condition held for the second block, the transaction only waited for nonce 2
+    assert!(test_client
+        .eth_get_transaction_by_hash(tx_hash, Some(true))
+        .await
+        .is_some());
+
+    let filler_tx = test_client
+        .send_eth(addr, None, None, Some(2), 0u128)
+        .await
+        .unwrap();
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 3, None).await;
+
+    let block = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Latest))
+        .await;
+    let block_transactions = block.transactions.as_hashes().unwrap();
+    assert!(block_transactions.contains(filler_tx.tx_hash()));
+    assert!(!block_transactions.contains(&tx_hash));
+    assert!(test_client
+        .eth_get_transaction_by_hash(tx_hash, Some(true))
+        .await
+        .is_none());
+
+    seq_task.abort();
+}
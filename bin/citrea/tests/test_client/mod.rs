@@ -1,20 +1,29 @@
 use std::str::FromStr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
-use alloy::providers::network::{Ethereum, EthereumWallet};
+use alloy::providers::network::eip2718::Encodable2718;
+use alloy::providers::network::{Ethereum, EthereumWallet, TransactionBuilder};
 use alloy::providers::{PendingTransactionBuilder, Provider as AlloyProvider, ProviderBuilder};
 use alloy::rpc::types::eth::{Block, Transaction, TransactionReceipt, TransactionRequest};
 use alloy::signers::local::PrivateKeySigner;
 use alloy::transports::http::{Http, HyperClient};
-use alloy_primitives::{Address, Bytes, TxHash, TxKind, B256, U256, U64};
+use alloy_primitives::{Address, Bytes, TxHash, TxKind, B256, U128, U256, U64};
 // use reth_rpc_types::TransactionReceipt;
 use alloy_rpc_types::AnyNetworkBlock;
 use alloy_rpc_types_trace::geth::{GethDebugTracingOptions, GethTrace, TraceResult};
+use citrea_batch_prover::rpc::SimulationResultResponse;
 use citrea_batch_prover::GroupCommitments;
+use citrea_common::rpc::{ForkSchedule, SyncStatus, TxSoftConfirmation};
 use citrea_evm::{Filter, LogResponse};
-use ethereum_rpc::SyncStatus;
+use citrea_fullnode::commitment_proof::CommitmentInclusionProof;
+use citrea_light_client_prover::rpc::LightClientProverRpcClient;
+use citrea_sequencer::{
+    CommitmentSubmission, L1FeeRate, PendingCommitments, ProductionState, TransactionConditional,
+    TxpoolContent, TxpoolStatus,
+};
+use ethereum_rpc::FilterChanges;
 use jsonrpsee::core::client::{ClientT, SubscriptionClientT};
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
 use jsonrpsee::rpc_params;
@@ -22,8 +31,10 @@ use jsonrpsee::ws_client::{PingConfig, WsClient, WsClientBuilder};
 use reth_primitives::{BlockId, BlockNumberOrTag};
 use sov_ledger_rpc::{HexHash, LedgerRpcClient};
 use sov_rollup_interface::rpc::{
-    BatchProofResponse, LastVerifiedBatchProofResponse, SequencerCommitmentResponse,
-    SoftConfirmationResponse, SoftConfirmationStatus, VerifiedBatchProofResponse,
+    BatchProofResponse, L1SlotSoftConfirmationsResponse, LastVerifiedBatchProofResponse,
+    LightClientProofResponse, SequencerCommitmentResponse, SlotVerifiedBatchProofsResponse,
+    SoftConfirmationDetail, SoftConfirmationHashResponse, SoftConfirmationResponse,
+    SoftConfirmationStatus, VerifiedBatchProofResponse,
 };
 
 pub const SEND_ETH_GAS: u64 = 21001;
@@ -34,6 +45,7 @@ pub struct TestClient {
     pub(crate) from_addr: Address,
     //client: SignerMiddleware<Provider<Http>, PrivateKeySigner>,
     client: Box<dyn AlloyProvider<Http<HyperClient>>>,
+    wallet: EthereumWallet,
     http_client: HttpClient,
     ws_client: WsClient,
     current_nonce: AtomicU64,
@@ -50,10 +62,11 @@ impl TestClient {
         let http_host = format!("http://localhost:{}", rpc_addr.port());
         let ws_host = format!("ws://localhost:{}", rpc_addr.port());
 
+        let wallet = EthereumWallet::from(key);
         let provider = ProviderBuilder::new()
             // .with_recommended_fillers()
             .with_chain_id(chain_id)
-            .wallet(EthereumWallet::from(key))
+            .wallet(wallet.clone())
             .on_hyper_http(http_host.parse().unwrap());
         let client: Box<dyn AlloyProvider<Http<HyperClient>>> = Box::new(provider);
 
@@ -70,6 +83,7 @@ impl TestClient {
             chain_id,
             from_addr,
             client,
+            wallet,
             ws_client,
             http_client,
             current_nonce: AtomicU64::new(0),
@@ -94,6 +108,96 @@ impl TestClient {
             .map_err(|e| e.into())
     }
 
+    pub(crate) async fn sequencer_halt_production(
+        &self,
+        admin_token: &str,
+    ) -> Result<ProductionState, Box<dyn std::error::Error>> {
+        self.http_client
+            .request("sequencer_haltProduction", rpc_params![admin_token])
+            .await
+            .map_err(|e| e.into())
+    }
+
+    pub(crate) async fn sequencer_resume_production(
+        &self,
+        admin_token: &str,
+    ) -> Result<ProductionState, Box<dyn std::error::Error>> {
+        self.http_client
+            .request("sequencer_resumeProduction", rpc_params![admin_token])
+            .await
+            .map_err(|e| e.into())
+    }
+
+    pub(crate) async fn sequencer_rollback_to_height(
+        &self,
+        admin_token: &str,
+        l2_height: u64,
+    ) -> Result<U64, Box<dyn std::error::Error>> {
+        self.http_client
+            .request(
+                "sequencer_rollbackToHeight",
+                rpc_params![admin_token, U64::from(l2_height)],
+            )
+            .await
+            .map_err(|e| e.into())
+    }
+
+    pub(crate) async fn sequencer_get_pending_commitments(&self) -> PendingCommitments {
+        self.http_client
+            .request("sequencer_getPendingCommitments", rpc_params![])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn sequencer_get_commitment_history(
+        &self,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    ) -> Vec<CommitmentSubmission> {
+        self.http_client
+            .request(
+                "sequencer_getCommitmentHistory",
+                rpc_params![limit.map(U64::from), offset.map(U64::from)],
+            )
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn sequencer_get_commitment_by_l2_range(
+        &self,
+        l2_start: u64,
+        l2_end: u64,
+    ) -> Option<CommitmentSubmission> {
+        self.http_client
+            .request(
+                "sequencer_getCommitmentByL2Range",
+                rpc_params![U64::from(l2_start), U64::from(l2_end)],
+            )
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn sequencer_get_l1_fee_rate(&self) -> L1FeeRate {
+        self.http_client
+            .request("sequencer_getL1FeeRate", rpc_params![])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn txpool_status(&self) -> TxpoolStatus {
+        self.http_client
+            .request("txpool_status", rpc_params![])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn txpool_content(&self) -> TxpoolContent {
+        self.http_client
+            .request("txpool_content", rpc_params![])
+            .await
+            .unwrap()
+    }
+
     pub(crate) async fn send_publish_batch_request(&self) {
         let _: () = self
             .http_client
@@ -240,6 +344,25 @@ impl TestClient {
         T::from_str(&receipt_req.to_string()).map_err(|_| "Failed to parse bytes".into())
     }
 
+    pub(crate) async fn contract_call_at_block<T: FromStr>(
+        &self,
+        contract_address: Address,
+        data: Vec<u8>,
+        block_id: BlockId,
+    ) -> Result<T, Box<dyn std::error::Error>> {
+        let req = TransactionRequest::default()
+            .from(self.from_addr)
+            .to(contract_address)
+            .input(data.into());
+
+        let output: Bytes = self
+            .http_client
+            .request("eth_call", rpc_params![req, block_id])
+            .await?;
+
+        T::from_str(&output.to_string()).map_err(|_| "Failed to parse bytes".into())
+    }
+
     pub(crate) async fn send_eth(
         &self,
         to_addr: Address,
@@ -268,6 +391,41 @@ impl TestClient {
             .map_err(|e| e.into())
     }
 
+    /// Signs an eth transfer without sending it, returning the raw transaction
+    pub(crate) async fn sign_eth_transfer(
+        &self,
+        to_addr: Address,
+        nonce: u64,
+        value: u128,
+    ) -> Bytes {
+        let envelope = TransactionRequest::default()
+            .from(self.from_addr)
+            .to(to_addr)
+            .value(U256::from(value))
+            .gas_limit(SEND_ETH_GAS)
+            .nonce(nonce)
+            .max_priority_fee_per_gas(10)
+            .max_fee_per_gas(MAX_FEE_PER_GAS)
+            .with_chain_id(self.chain_id)
+            .build(&self.wallet)
+            .await
+            .unwrap();
+        envelope.encoded_2718().into()
+    }
+
+    pub(crate) async fn send_raw_transaction_conditional(
+        &self,
+        raw_tx: Bytes,
+        options: TransactionConditional,
+    ) -> Result<TxHash, jsonrpsee::core::ClientError> {
+        self.http_client
+            .request(
+                "eth_sendRawTransactionConditional",
+                rpc_params![raw_tx, options],
+            )
+            .await
+    }
+
     pub(crate) async fn send_eth_with_gas(
         &self,
         to_addr: Address,
@@ -293,6 +451,29 @@ impl TestClient {
             .map_err(|e| e.into())
     }
 
+    pub(crate) async fn send_eth_with_input(
+        &self,
+        to_addr: Address,
+        input: Bytes,
+        gas: u64,
+    ) -> Result<PendingTransactionBuilder<'_, Http<HyperClient>, Ethereum>, anyhow::Error> {
+        let nonce = self.current_nonce.fetch_add(1, Ordering::Relaxed);
+
+        let req = TransactionRequest::default()
+            .from(self.from_addr)
+            .to(to_addr)
+            .input(input.into())
+            .gas_limit(gas)
+            .nonce(nonce)
+            .max_priority_fee_per_gas(10)
+            .max_fee_per_gas(MAX_FEE_PER_GAS);
+
+        self.client
+            .send_transaction(req)
+            .await
+            .map_err(|e| e.into())
+    }
+
     pub(crate) async fn web3_client_version(&self) -> String {
         self.http_client
             .request("web3_clientVersion", rpc_params![])
@@ -482,6 +663,51 @@ impl TestClient {
         eth_logs
     }
 
+    pub(crate) async fn eth_new_filter(&self, filter: Filter) -> U128 {
+        self.http_client
+            .request("eth_newFilter", rpc_params![filter])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn eth_new_block_filter(&self) -> U128 {
+        self.http_client
+            .request("eth_newBlockFilter", rpc_params![])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn eth_new_pending_transaction_filter(&self) -> U128 {
+        self.http_client
+            .request("eth_newPendingTransactionFilter", rpc_params![])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn eth_get_filter_changes(
+        &self,
+        id: U128,
+    ) -> Result<FilterChanges, Box<dyn std::error::Error>> {
+        self.http_client
+            .request("eth_getFilterChanges", rpc_params![id])
+            .await
+            .map_err(|e| e.into())
+    }
+
+    pub(crate) async fn eth_get_filter_logs(&self, id: U128) -> Vec<LogResponse> {
+        self.http_client
+            .request("eth_getFilterLogs", rpc_params![id])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn eth_uninstall_filter(&self, id: U128) -> bool {
+        self.http_client
+            .request("eth_uninstallFilter", rpc_params![id])
+            .await
+            .unwrap()
+    }
+
     #[allow(clippy::extra_unused_type_parameters)]
     pub(crate) async fn ledger_get_soft_confirmation_by_number<
         DaSpec: sov_rollup_interface::da::DaSpec,
@@ -490,7 +716,51 @@ impl TestClient {
         num: u64,
     ) -> Option<SoftConfirmationResponse> {
         self.http_client
-            .get_soft_confirmation_by_number(U64::from(num))
+            .get_soft_confirmation_by_number(U64::from(num), None)
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn ledger_get_soft_confirmation_by_number_with_detail(
+        &self,
+        num: u64,
+        detail: SoftConfirmationDetail,
+    ) -> Option<SoftConfirmationResponse> {
+        self.http_client
+            .get_soft_confirmation_by_number(U64::from(num), Some(detail))
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn ledger_get_soft_confirmation_by_hash(
+        &self,
+        hash: [u8; 32],
+    ) -> Option<SoftConfirmationResponse> {
+        self.http_client
+            .get_soft_confirmation_by_hash(HexHash(hash))
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn ledger_get_soft_confirmation_range(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> Vec<Option<SoftConfirmationResponse>> {
+        self.http_client
+            .get_soft_confirmation_range(U64::from(start), U64::from(end), None)
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn ledger_get_soft_confirmation_range_with_detail(
+        &self,
+        start: u64,
+        end: u64,
+        detail: SoftConfirmationDetail,
+    ) -> Vec<Option<SoftConfirmationResponse>> {
+        self.http_client
+            .get_soft_confirmation_range(U64::from(start), U64::from(end), Some(detail))
             .await
             .unwrap()
     }
@@ -505,10 +775,67 @@ impl TestClient {
             .await?)
     }
 
+    /// Waits until the soft confirmation at `l2_height` has the given status.
+    /// Statuses only move forward, so seeing a later status than the expected one fails.
+    pub(crate) async fn wait_for_status(
+        &self,
+        l2_height: u64,
+        status: SoftConfirmationStatus,
+        timeout: Option<Duration>,
+    ) {
+        let start = SystemTime::now();
+        let timeout = timeout.unwrap_or(Duration::from_secs(60));
+        loop {
+            let current = self
+                .ledger_get_soft_confirmation_status(l2_height)
+                .await
+                .unwrap();
+            if current == status {
+                break;
+            }
+            assert!(
+                current < status,
+                "Soft confirmation {} skipped past {:?}: {:?}",
+                l2_height,
+                status,
+                current
+            );
+
+            if start + timeout <= SystemTime::now() {
+                panic!(
+                    "Timeout while waiting for soft confirmation {} to be {:?}",
+                    l2_height, status
+                );
+            }
+
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) async fn ledger_get_l2_genesis_state_root(&self) -> Option<[u8; 32]> {
+        self.http_client
+            .get_l2_genesis_state_root()
+            .await
+            .unwrap()
+            .map(|hash| hash.0)
+    }
+
     pub(crate) async fn ledger_get_last_scanned_l1_height(&self) -> u64 {
         self.http_client.get_last_scanned_l1_height().await.unwrap()
     }
 
+    pub(crate) async fn ledger_get_soft_confirmation_range_by_l1_height(
+        &self,
+        height: u64,
+        include_txs: Option<bool>,
+    ) -> Option<L1SlotSoftConfirmationsResponse> {
+        self.http_client
+            .get_soft_confirmation_range_by_l1_height(U64::from(height), include_txs)
+            .await
+            .unwrap()
+    }
+
     pub(crate) async fn ledger_get_sequencer_commitments_on_slot_by_number(
         &self,
         height: u64,
@@ -529,6 +856,16 @@ impl TestClient {
             .unwrap()
     }
 
+    pub(crate) async fn ledger_get_batch_proofs_by_slot_hash(
+        &self,
+        hash: [u8; 32],
+    ) -> Option<Vec<BatchProofResponse>> {
+        self.http_client
+            .get_batch_proofs_by_slot_hash(HexHash(hash))
+            .await
+            .unwrap()
+    }
+
     pub(crate) async fn ledger_get_verified_batch_proofs_by_slot_height(
         &self,
         height: u64,
@@ -539,6 +876,22 @@ impl TestClient {
             .unwrap()
     }
 
+    pub(crate) async fn ledger_get_verified_batch_proofs_by_slot_range(
+        &self,
+        start: u64,
+        end: u64,
+        limit: u64,
+    ) -> Result<Vec<SlotVerifiedBatchProofsResponse>, Box<dyn std::error::Error>> {
+        self.http_client
+            .get_verified_batch_proofs_by_slot_range(
+                U64::from(start),
+                U64::from(end),
+                U64::from(limit),
+            )
+            .await
+            .map_err(|e| e.into())
+    }
+
     pub(crate) async fn ledger_get_last_verified_batch_proof(
         &self,
     ) -> Option<LastVerifiedBatchProofResponse> {
@@ -558,6 +911,81 @@ impl TestClient {
             .map_err(|e| e.into())
     }
 
+    pub(crate) async fn ledger_get_sequencer_commitment_by_l2_height(
+        &self,
+        l2_height: u64,
+    ) -> Option<SequencerCommitmentResponse> {
+        self.http_client
+            .get_sequencer_commitment_by_l2_height(U64::from(l2_height))
+            .await
+            .unwrap()
+    }
+
+    /// Waits until the node knows of a sequencer commitment covering `l2_height`, and returns it.
+    /// Only full nodes index commitments by L2 height.
+    pub(crate) async fn wait_for_commitment_covering(
+        &self,
+        l2_height: u64,
+        timeout: Option<Duration>,
+    ) -> SequencerCommitmentResponse {
+        let start = SystemTime::now();
+        let timeout = timeout.unwrap_or(Duration::from_secs(60));
+        loop {
+            if let Some(commitment) = self
+                .ledger_get_sequencer_commitment_by_l2_height(l2_height)
+                .await
+            {
+                assert!(
+                    commitment.l2_start_block_number <= l2_height
+                        && l2_height <= commitment.l2_end_block_number,
+                    "Commitment #{}-{} does not cover L2 block {}",
+                    commitment.l2_start_block_number,
+                    commitment.l2_end_block_number,
+                    l2_height
+                );
+                return commitment;
+            }
+
+            if start + timeout <= SystemTime::now() {
+                panic!(
+                    "Timeout while waiting for a commitment covering L2 block {}",
+                    l2_height
+                );
+            }
+
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    pub(crate) async fn ledger_get_soft_confirmations_by_hashes(
+        &self,
+        hashes: Vec<[u8; 32]>,
+    ) -> Result<Vec<Option<SoftConfirmationResponse>>, Box<dyn std::error::Error>> {
+        self.http_client
+            .get_soft_confirmations_by_hashes(hashes.into_iter().map(HexHash).collect())
+            .await
+            .map_err(|e| e.into())
+    }
+
+    pub(crate) async fn light_client_prover_get_light_client_proof_by_l1_height(
+        &self,
+        l1_height: u64,
+    ) -> anyhow::Result<Option<LightClientProofResponse>> {
+        self.http_client
+            .get_light_client_proof_by_l1_height(l1_height)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    pub(crate) async fn light_client_prover_get_last_light_client_proof(
+        &self,
+    ) -> anyhow::Result<Option<LightClientProofResponse>> {
+        self.http_client
+            .get_last_light_client_proof()
+            .await
+            .map_err(|e| e.into())
+    }
+
     pub(crate) async fn ledger_get_head_soft_confirmation(
         &self,
     ) -> Result<Option<SoftConfirmationResponse>, Box<dyn std::error::Error>> {
@@ -696,6 +1124,24 @@ impl TestClient {
         rx
     }
 
+    pub(crate) async fn subscribe_soft_confirmations(
+        &self,
+    ) -> mpsc::Receiver<SoftConfirmationResponse> {
+        let (tx, rx) = mpsc::channel();
+        let mut subscription = self.ws_client.subscribe_soft_confirmations().await.unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let Some(Ok(soft_confirmation)) = subscription.next().await else {
+                    return;
+                };
+                tx.send(soft_confirmation).unwrap();
+            }
+        });
+
+        rx
+    }
+
     pub(crate) async fn eth_block_number(&self) -> u64 {
         let block_number: U256 = self
             .http_client
@@ -713,6 +1159,46 @@ impl TestClient {
             .unwrap()
     }
 
+    pub(crate) async fn citrea_get_fork_schedule(&self) -> ForkSchedule {
+        self.http_client
+            .request("citrea_getForkSchedule", rpc_params![])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn citrea_get_soft_confirmation_by_tx_hash(
+        &self,
+        tx_hash: TxHash,
+    ) -> Option<TxSoftConfirmation> {
+        self.http_client
+            .request("citrea_getSoftConfirmationByTxHash", rpc_params![tx_hash])
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn ledger_get_commitment_inclusion_proof(
+        &self,
+        l2_height: u64,
+    ) -> Option<CommitmentInclusionProof> {
+        self.http_client
+            .request(
+                "ledger_getCommitmentInclusionProof",
+                rpc_params![U64::from(l2_height)],
+            )
+            .await
+            .unwrap()
+    }
+
+    pub(crate) async fn ledger_compute_soft_confirmation_hash(
+        &self,
+        soft_confirmation: SoftConfirmationResponse,
+    ) -> SoftConfirmationHashResponse {
+        self.http_client
+            .compute_soft_confirmation_hash(soft_confirmation)
+            .await
+            .unwrap()
+    }
+
     pub(crate) async fn batch_prover_prove(
         &self,
         l1_height: u64,
@@ -726,6 +1212,20 @@ impl TestClient {
             .await
             .unwrap()
     }
+
+    pub(crate) async fn batch_prover_get_simulation_results(
+        &self,
+        start_l1_height: u64,
+        end_l1_height: u64,
+    ) -> Vec<SimulationResultResponse> {
+        self.http_client
+            .request(
+                "batchProver_getSimulationResults",
+                rpc_params![start_l1_height, end_l1_height],
+            )
+            .await
+            .unwrap()
+    }
 }
 
 #[derive(serde::Deserialize, Debug)]
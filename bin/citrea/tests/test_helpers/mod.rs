@@ -3,11 +3,10 @@ use std::path::Path;
 use std::time::{Duration, SystemTime};
 
 use anyhow::bail;
-use borsh::BorshDeserialize;
 use citrea::{CitreaRollupBlueprint, MockDemoRollup};
 use citrea_common::{
-    BatchProverConfig, FullNodeConfig, LightClientProverConfig, RollupPublicKeys, RpcConfig,
-    RunnerConfig, SequencerConfig, StorageConfig,
+    BatchProverConfig, FullNodeConfig, LightClientProverConfig, MetricsConfig, RollupPublicKeys,
+    RpcConfig, RunnerConfig, SequencerConfig, StorageConfig, TelemetryConfig,
 };
 use citrea_primitives::TEST_PRIVATE_KEY;
 use citrea_stf::genesis_config::GenesisPaths;
@@ -15,18 +14,21 @@ use sov_mock_da::{MockAddress, MockBlock, MockDaConfig, MockDaService};
 use sov_modules_api::default_signature::private_key::DefaultPrivateKey;
 use sov_modules_api::PrivateKey;
 use sov_modules_rollup_blueprint::RollupBlueprint as _;
-use sov_rollup_interface::da::{BlobReaderTrait, DaData, SequencerCommitment};
+use sov_rollup_interface::da::{BlobReaderTrait, DaData, SequencerCommitment, VersionedDaData};
 use sov_rollup_interface::services::da::{DaService, SlotData};
 use sov_rollup_interface::zk::Proof;
 use sov_rollup_interface::Network;
 use tempfile::TempDir;
-use tokio::sync::oneshot;
+use tokio::sync::{mpsc, oneshot};
 use tokio::time::sleep;
 use tracing::{debug, info_span, instrument, warn, Instrument};
 
 use crate::test_client::TestClient;
 use crate::DEFAULT_PROOF_WAIT_DURATION;
 
+/// Port of the metrics exporter shared by all nodes started by the tests
+pub const TEST_METRICS_PORT: u16 = 18001;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NodeMode {
     FullNode(SocketAddr),
@@ -36,6 +38,40 @@ pub enum NodeMode {
     LightClientProver(SocketAddr),
 }
 
+/// Starts a sequencer that shuts down, the same way it does on SIGTERM, once
+/// a message is sent to `shutdown_signal`.
+pub async fn start_sequencer_with_shutdown_signal(
+    rpc_reporting_channel: oneshot::Sender<SocketAddr>,
+    rt_genesis_paths: GenesisPaths,
+    rollup_config: FullNodeConfig<MockDaConfig>,
+    sequencer_config: SequencerConfig,
+    shutdown_signal: mpsc::Receiver<()>,
+) -> anyhow::Result<()> {
+    std::env::set_var("RISC0_DEV_MODE", "1");
+
+    let mock_demo_rollup = MockDemoRollup::new(Network::Nightly);
+
+    let span = info_span!("Sequencer");
+    let (mut sequencer, rpc_methods) = CitreaRollupBlueprint::create_new_sequencer(
+        &mock_demo_rollup,
+        &rt_genesis_paths,
+        rollup_config,
+        sequencer_config,
+    )
+    .instrument(span.clone())
+    .await?;
+
+    sequencer
+        .start_rpc_server(rpc_methods, Some(rpc_reporting_channel))
+        .instrument(span.clone())
+        .await?;
+
+    sequencer
+        .run_until_shutdown(shutdown_signal)
+        .instrument(span)
+        .await
+}
+
 pub async fn start_rollup(
     rpc_reporting_channel: oneshot::Sender<SocketAddr>,
     rt_genesis_paths: GenesisPaths,
@@ -138,7 +174,8 @@ pub async fn start_rollup(
         rollup
             .start_rpc_server(rpc_methods, Some(rpc_reporting_channel))
             .instrument(span.clone())
-            .await;
+            .await
+            .unwrap();
 
         rollup.run().instrument(span).await.unwrap();
     }
@@ -167,10 +204,14 @@ pub fn create_default_rollup_config(
             ],
             sequencer_da_pub_key: sequencer_da_pub_key.clone(),
             prover_da_pub_key: prover_da_pub_key.clone(),
+            sequencer_key_rotations: vec![],
+            accepted_sequencer_da_pub_keys: vec![],
         },
         storage: StorageConfig {
             path: rollup_path.to_path_buf(),
             db_max_open_files: None,
+            rocksdb: None,
+            archive: false,
         },
         rpc: RpcConfig {
             bind_host: "127.0.0.1".into(),
@@ -181,6 +222,21 @@ pub fn create_default_rollup_config(
             batch_requests_limit: 50,
             enable_subscriptions: true,
             max_subscriptions_per_connection: 100,
+            max_soft_confirmation_hashes_per_request: 100,
+            max_verified_proofs_slot_range: 1000,
+            healthcheck_stall_multiple: 3.0,
+            sync_status_lag_tolerance: 5,
+            max_logs_block_range: 1_000,
+            max_logs_per_response: 10_000,
+            filter_timeout_secs: 300,
+            max_filters_per_connection: 100,
+            max_filters: 10_000,
+            admin_token: None,
+            rate_limit: Default::default(),
+            method_filter: Default::default(),
+            cors: Default::default(),
+            compression: Default::default(),
+            gas_price_oracle: Default::default(),
         },
         runner: match node_mode {
             NodeMode::FullNode(socket_addr)
@@ -188,8 +244,14 @@ pub fn create_default_rollup_config(
             | NodeMode::LightClientProver(socket_addr) => Some(RunnerConfig {
                 include_tx_body,
                 sequencer_client_url: format!("http://localhost:{}", socket_addr.port()),
+                secondary_sequencer_client_urls: vec![],
                 sync_blocks_count: 10,
+                commit_blocks_count: 10,
                 pruning_config: None,
+                read_only: false,
+                tx_body_backfill: None,
+                allow_genesis_state_root_mismatch: false,
+                consistency_check: None,
             }),
             NodeMode::SequencerNode => None,
         },
@@ -200,8 +262,17 @@ pub fn create_default_rollup_config(
                 _ => MockAddress::new([0; 32]),
             },
             db_path: da_path.to_path_buf(),
+            send_transaction_delay_ms: 0,
+        },
+        // Metrics are process wide, the first node started by the tests serves them for all
+        telemetry: TelemetryConfig {
+            metrics: MetricsConfig {
+                enabled: true,
+                bind_host: "127.0.0.1".into(),
+                bind_port: TEST_METRICS_PORT,
+            },
         },
-        telemetry: Default::default(),
+        forks: vec![],
     }
 }
 
@@ -362,7 +433,7 @@ fn extract_da_data(
         .extract_relevant_blobs(&block)
         .into_iter()
         .for_each(|mut tx| {
-            let data = DaData::try_from_slice(tx.full_data());
+            let data = DaData::decode_versioned(tx.full_data());
             if let Ok(DaData::SequencerCommitment(seq_com)) = data {
                 sequencer_commitments.push(seq_com);
             } else if let Ok(DaData::ZKProof(proof)) = data {
@@ -0,0 +1,177 @@
+//! Serialization of batch proof circuit inputs straight from the ledger.
+//!
+//! The soft confirmations of the proven commitments, their witnesses and the DA block headers
+//! they were built on are read in chunks and appended to the serialized input, instead of being
+//! collected into a `BatchProofCircuitInput` first. The bytes are exactly the borsh serialization
+//! of `BatchProofCircuitInput`, which is what the guest reads.
+use std::sync::Arc;
+
+use anyhow::{anyhow, ensure, Context as _};
+use borsh::{BorshDeserialize, BorshSerialize};
+use citrea_common::cache::L1BlockCache;
+use citrea_common::da::get_da_block_at_height;
+use serde::de::DeserializeOwned;
+use sov_db::ledger_db::BatchProverLedgerOps;
+use sov_db::schema::types::SoftConfirmationNumber;
+use sov_rollup_interface::da::{DaSpec, SequencerCommitment};
+use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::soft_confirmation::SignedSoftConfirmation;
+use tokio::sync::Mutex;
+
+/// Number of soft confirmations read from the ledger at once
+pub const SOFT_CONFIRMATIONS_PER_CHUNK: u64 = 256;
+
+/// The fields of a `BatchProofCircuitInput`, apart from the soft confirmations of its
+/// commitments, their witnesses and DA block headers, which are read from the ledger.
+pub struct CircuitInputParts<StateRoot, Da: DaSpec> {
+    /// The state root before the state transition
+    pub initial_state_root: StateRoot,
+    /// The state root after the state transition
+    pub final_state_root: StateRoot,
+    /// The hash before the state transition
+    pub prev_soft_confirmation_hash: [u8; 32],
+    /// The DA data the sequencer commitments were found in
+    pub da_data: Vec<Da::BlobTransaction>,
+    /// DA block header that the sequencer commitments were found in
+    pub da_block_header_of_commitments: Da::BlockHeader,
+    /// The inclusion proof for all DA data
+    pub inclusion_proof: Da::InclusionMultiProof,
+    /// The completeness proof for all DA data
+    pub completeness_proof: Da::CompletenessProof,
+    /// Pre-proven commitments which also exist in the DA data
+    pub preproven_commitments: Vec<usize>,
+    /// Sequencer soft confirmation public key
+    pub sequencer_public_key: Vec<u8>,
+    /// Sequencer DA public key
+    pub sequencer_da_public_key: Vec<u8>,
+    /// The inclusive range of sequencer commitments that are being processed
+    pub sequencer_commitments_range: (u32, u32),
+}
+
+/// A borsh serialized `BatchProofCircuitInput`, with the fields the batch prover needs
+/// before proving it.
+#[derive(Debug, Clone)]
+pub struct SerializedCircuitInput<StateRoot> {
+    /// The state root before the state transition
+    pub initial_state_root: StateRoot,
+    /// The inclusive range of sequencer commitments that are being processed
+    pub sequencer_commitments_range: (u32, u32),
+    /// The serialized input, exactly as passed to the guest
+    pub input: Vec<u8>,
+}
+
+/// Serializes the circuit input proving the given sequencer commitments.
+///
+/// At most [`SOFT_CONFIRMATIONS_PER_CHUNK`] soft confirmations are held in memory at once,
+/// besides the serialized input itself.
+pub async fn serialize_circuit_input<Da, DB, StateRoot, Witness, Tx>(
+    parts: CircuitInputParts<StateRoot, Da::Spec>,
+    sequencer_commitments: &[SequencerCommitment],
+    da_service: &Arc<Da>,
+    ledger_db: &DB,
+    l1_block_cache: &Arc<Mutex<L1BlockCache<Da>>>,
+) -> anyhow::Result<SerializedCircuitInput<StateRoot>>
+where
+    Da: DaService,
+    DB: BatchProverLedgerOps,
+    StateRoot: BorshSerialize,
+    Witness: DeserializeOwned + BorshSerialize,
+    Tx: Clone + BorshSerialize + BorshDeserialize,
+{
+    let mut input = vec![];
+    parts.initial_state_root.serialize(&mut input)?;
+    parts.final_state_root.serialize(&mut input)?;
+    parts.prev_soft_confirmation_hash.serialize(&mut input)?;
+    parts.da_data.serialize(&mut input)?;
+    parts.da_block_header_of_commitments.serialize(&mut input)?;
+    parts.inclusion_proof.serialize(&mut input)?;
+    parts.completeness_proof.serialize(&mut input)?;
+    parts.preproven_commitments.serialize(&mut input)?;
+
+    // Soft confirmations, the DA heights they were built on are recorded to fetch their headers
+    let mut da_slot_heights_of_commitments = Vec::with_capacity(sequencer_commitments.len());
+    write_len(&mut input, sequencer_commitments.len())?;
+    for sequencer_commitment in sequencer_commitments {
+        let start_l2 = sequencer_commitment.l2_start_block_number;
+        let end_l2 = sequencer_commitment.l2_end_block_number;
+        write_len(&mut input, (end_l2 - start_l2 + 1) as usize)?;
+
+        let mut da_slot_heights: Vec<u64> = vec![];
+        for chunk_start in (start_l2..=end_l2).step_by(SOFT_CONFIRMATIONS_PER_CHUNK as usize) {
+            let chunk_end = end_l2.min(chunk_start + SOFT_CONFIRMATIONS_PER_CHUNK - 1);
+            let soft_confirmations = ledger_db
+                .get_soft_confirmation_range(
+                    &(SoftConfirmationNumber(chunk_start)..=SoftConfirmationNumber(chunk_end)),
+                )
+                .context("Failed to get soft confirmations from the ledger db")?;
+            ensure!(
+                soft_confirmations.len() as u64 == chunk_end - chunk_start + 1,
+                "Soft confirmations {} - {} are missing from the ledger db",
+                chunk_start,
+                chunk_end
+            );
+
+            for soft_confirmation in soft_confirmations {
+                if da_slot_heights.last() != Some(&soft_confirmation.da_slot_height) {
+                    da_slot_heights.push(soft_confirmation.da_slot_height);
+                }
+                let signed_soft_confirmation: SignedSoftConfirmation<Tx> = soft_confirmation
+                    .try_into()
+                    .context("Failed to parse transactions")?;
+                signed_soft_confirmation.serialize(&mut input)?;
+            }
+        }
+        da_slot_heights_of_commitments.push(da_slot_heights);
+    }
+
+    // State transition witnesses
+    write_len(&mut input, sequencer_commitments.len())?;
+    for sequencer_commitment in sequencer_commitments {
+        let start_l2 = sequencer_commitment.l2_start_block_number;
+        let end_l2 = sequencer_commitment.l2_end_block_number;
+        write_len(&mut input, (end_l2 - start_l2 + 1) as usize)?;
+
+        for l2_height in start_l2..=end_l2 {
+            let witnesses = ledger_db
+                .get_l2_witness::<Witness>(l2_height)
+                .context("Failed to get witness from the ledger db")?
+                .ok_or_else(|| anyhow!("Witness of L2 block {} is missing", l2_height))?;
+            witnesses.serialize(&mut input)?;
+        }
+    }
+
+    // DA block headers of the soft confirmations
+    write_len(&mut input, da_slot_heights_of_commitments.len())?;
+    for da_slot_heights in da_slot_heights_of_commitments {
+        write_len(&mut input, da_slot_heights.len())?;
+        for da_slot_height in da_slot_heights {
+            let filtered_block =
+                get_da_block_at_height(da_service, da_slot_height, l1_block_cache.clone())
+                    .await
+                    .map_err(|_| {
+                        anyhow!(
+                            "Error while fetching DA block at height: {}",
+                            da_slot_height
+                        )
+                    })?;
+            filtered_block.header().serialize(&mut input)?;
+        }
+    }
+
+    parts.sequencer_public_key.serialize(&mut input)?;
+    parts.sequencer_da_public_key.serialize(&mut input)?;
+    parts.sequencer_commitments_range.serialize(&mut input)?;
+
+    Ok(SerializedCircuitInput {
+        initial_state_root: parts.initial_state_root,
+        sequencer_commitments_range: parts.sequencer_commitments_range,
+        input,
+    })
+}
+
+/// Writes the length prefix of a borsh serialized collection
+fn write_len(input: &mut Vec<u8>, len: usize) -> anyhow::Result<()> {
+    let len = u32::try_from(len).context("Collection is too long to be serialized")?;
+    len.serialize(input)?;
+    Ok(())
+}
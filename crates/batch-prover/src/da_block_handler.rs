@@ -1,15 +1,15 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops::RangeInclusive;
 use std::sync::Arc;
 
-use anyhow::{anyhow, Context as _};
+use anyhow::anyhow;
 use borsh::{BorshDeserialize, BorshSerialize};
 use citrea_common::cache::L1BlockCache;
 use citrea_common::da::get_da_block_at_height;
-use citrea_common::utils::merge_state_diffs;
-use citrea_common::BatchProverConfig;
+use citrea_common::utils::{extract_batch_proof_output, merge_state_diffs};
+use citrea_common::{BatchProverConfig, SequencerKeySchedule};
 use citrea_primitives::compression::compress_blob;
 use citrea_primitives::forks::fork_from_block_number;
 use citrea_primitives::MAX_TXBODY_SIZE;
@@ -17,13 +17,12 @@ use rand::Rng;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use sov_db::ledger_db::BatchProverLedgerOps;
-use sov_db::schema::types::{SlotNumber, SoftConfirmationNumber};
-use sov_modules_api::{DaSpec, StateDiff, Zkvm};
+use sov_db::schema::types::{SlotNumber, SoftConfirmationNumber, StoredProvingSessionStatus};
+use sov_modules_api::{StateDiff, Zkvm};
 use sov_rollup_interface::da::{BlockHeaderTrait, SequencerCommitment};
 use sov_rollup_interface::services::da::{DaService, SlotData};
-use sov_rollup_interface::soft_confirmation::SignedSoftConfirmation;
 use sov_rollup_interface::spec::SpecId;
-use sov_rollup_interface::zk::ZkvmHost;
+use sov_rollup_interface::zk::{Proof, ZkvmHost};
 use sov_stf_runner::{ProverGuestRunConfig, ProverService};
 use tokio::select;
 use tokio::sync::{mpsc, Mutex};
@@ -33,13 +32,10 @@ use tracing::{error, info, warn};
 
 use crate::errors::L1ProcessingError;
 use crate::metrics::BATCH_PROVER_METRICS;
-use crate::proving::{data_to_prove, extract_and_store_proof, prove_l1, GroupCommitments};
-
-type CommitmentStateTransitionData<'txs, Witness, Da, Tx> = (
-    VecDeque<Vec<(Witness, Witness)>>,
-    VecDeque<Vec<SignedSoftConfirmation<'txs, Tx>>>,
-    VecDeque<Vec<<<Da as DaService>::Spec as DaSpec>::BlockHeader>>,
-);
+use crate::proving::{
+    data_to_prove, proof_already_submitted, prove_l1, submit_and_store_proof, GroupCommitments,
+};
+use crate::simulation::{simulate_l1, BatchProofSimulator};
 
 pub(crate) struct L1BlockHandler<Vm, Da, Ps, DB, StateRoot, Witness, Tx>
 where
@@ -60,13 +56,16 @@ where
     prover_service: Arc<Ps>,
     ledger_db: DB,
     da_service: Arc<Da>,
-    sequencer_pub_key: Vec<u8>,
-    sequencer_da_pub_key: Vec<u8>,
+    sequencer_pub_keys: SequencerKeySchedule,
+    sequencer_da_pub_keys: Vec<Vec<u8>>,
     code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
     elfs_by_spec: HashMap<SpecId, Vec<u8>>,
+    /// Set in simulate proving mode, in which the inputs are simulated instead of proven
+    simulator: Option<BatchProofSimulator>,
     l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
     skip_submission_until_l1: u64,
     pending_l1_blocks: VecDeque<<Da as DaService>::FilteredBlock>,
+    l1_heights_awaiting_proof: Arc<Mutex<BTreeSet<u64>>>,
     _state_root: PhantomData<StateRoot>,
     _witness: PhantomData<Witness>,
     _tx: PhantomData<Tx>,
@@ -94,25 +93,29 @@ where
         prover_service: Arc<Ps>,
         ledger_db: DB,
         da_service: Arc<Da>,
-        sequencer_pub_key: Vec<u8>,
-        sequencer_da_pub_key: Vec<u8>,
+        sequencer_pub_keys: SequencerKeySchedule,
+        sequencer_da_pub_keys: Vec<Vec<u8>>,
         code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
         elfs_by_spec: HashMap<SpecId, Vec<u8>>,
+        simulator: Option<BatchProofSimulator>,
         skip_submission_until_l1: u64,
         l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
+        l1_heights_awaiting_proof: Arc<Mutex<BTreeSet<u64>>>,
     ) -> Self {
         Self {
             prover_config,
             prover_service,
             ledger_db,
             da_service,
-            sequencer_pub_key,
-            sequencer_da_pub_key,
+            sequencer_pub_keys,
+            sequencer_da_pub_keys,
             code_commitments_by_spec,
             elfs_by_spec,
+            simulator,
             skip_submission_until_l1,
             l1_block_cache,
             pending_l1_blocks: VecDeque::new(),
+            l1_heights_awaiting_proof,
             _state_root: PhantomData,
             _witness: PhantomData,
             _tx: PhantomData,
@@ -120,15 +123,21 @@ where
     }
 
     pub async fn run(mut self, start_l1_height: u64, cancellation_token: CancellationToken) {
-        if self.prover_config.enable_recovery {
-            if let Err(e) = self.check_and_recover_ongoing_proving_sessions().await {
-                error!("Failed to recover ongoing proving sessions: {:?}", e);
+        if self.simulator.is_some() {
+            // Resuming would submit the proofs of previous proving sessions to DA
+            info!("Simulating batch proofs, nothing will be proven or submitted to DA");
+        } else if self.prover_config.enable_recovery {
+            if let Err(e) = self.resume_proving_sessions().await {
+                error!("Failed to resume proving sessions: {:?}", e);
             }
         } else {
             // If recovery is disabled, clear pending proving sessions
             self.ledger_db
                 .clear_pending_proving_sessions()
                 .expect("Failed to clear pending proving sessions");
+            self.ledger_db
+                .clear_batch_proving_sessions()
+                .expect("Failed to clear batch proving sessions");
         }
 
         let (l1_tx, mut l1_rx) = mpsc::channel(1);
@@ -181,11 +190,12 @@ where
             let data_to_prove = data_to_prove::<Da, DB, StateRoot, Witness, Tx>(
                 self.da_service.clone(),
                 self.ledger_db.clone(),
-                self.sequencer_pub_key.clone(),
-                self.sequencer_da_pub_key.clone(),
+                self.sequencer_pub_keys.clone(),
+                self.sequencer_da_pub_keys.clone(),
                 self.l1_block_cache.clone(),
                 l1_block,
                 Some(GroupCommitments::Normal),
+                self.prover_config.max_soft_confirmations_per_proof,
             )
             .await;
 
@@ -223,8 +233,16 @@ where
                         end_block_number,
                     } => {
                         warn!("L2 range of commitments is not synced yet: {start_block_number} - {end_block_number}");
+                        self.l1_heights_awaiting_proof
+                            .lock()
+                            .await
+                            .insert(l1_height);
                         break;
                     }
+                    e @ L1ProcessingError::NonContiguousCommitments { .. } => {
+                        error!("{e}");
+                        return Err(anyhow!("{}", e));
+                    }
                     L1ProcessingError::Other(msg) => {
                         error!("{msg}");
                         return Err(anyhow!("{}", msg));
@@ -233,14 +251,19 @@ where
             };
 
             info!(
-                "Processing {} sequencer commitments at height {}",
+                "Processing {} sequencer commitments in {} proofs at height {}",
                 sequencer_commitments.len(),
+                inputs.len(),
                 l1_block.header().height(),
             );
+            self.l1_heights_awaiting_proof
+                .lock()
+                .await
+                .insert(l1_height);
 
             let should_prove = match self.prover_config.proving_mode {
-                ProverGuestRunConfig::ProveWithFakeProofs => {
-                    // Unconditionally call `prove_l1()`
+                ProverGuestRunConfig::ProveWithFakeProofs | ProverGuestRunConfig::Simulate => {
+                    // Unconditionally call `prove_l1()` or `simulate_l1()`
                     true
                 }
                 _ => {
@@ -251,10 +274,22 @@ where
                 }
             };
             if should_prove {
-                if l1_height >= self.skip_submission_until_l1 {
+                if l1_height < self.skip_submission_until_l1 {
+                    info!("Skipping proving for l1 height {}", l1_height);
+                } else if let Some(simulator) = &self.simulator {
+                    simulate_l1(
+                        simulator.clone(),
+                        self.ledger_db.clone(),
+                        l1_height,
+                        sequencer_commitments,
+                        inputs,
+                    )
+                    .await?;
+                } else {
                     prove_l1::<Da, Ps, Vm, DB, StateRoot, Witness, Tx>(
                         self.prover_service.clone(),
                         self.ledger_db.clone(),
+                        &self.prover_config,
                         self.code_commitments_by_spec.clone(),
                         self.elfs_by_spec.clone(),
                         l1_block,
@@ -262,8 +297,6 @@ where
                         inputs,
                     )
                     .await?;
-                } else {
-                    info!("Skipping proving for l1 height {}", l1_height);
                 }
             }
 
@@ -279,23 +312,96 @@ where
 
             BATCH_PROVER_METRICS.current_l1_block.set(l1_height as f64);
 
+            self.l1_heights_awaiting_proof
+                .lock()
+                .await
+                .remove(&l1_height);
             self.pending_l1_blocks.pop_front();
         }
         Ok(())
     }
 
-    async fn check_and_recover_ongoing_proving_sessions(&self) -> Result<(), anyhow::Error> {
-        let prover_service = self.prover_service.as_ref();
-        let txs_and_proofs = prover_service.recover_and_submit_proving_sessions().await?;
+    /// Reconciles the proving sessions stored in the ledger with the ones recovered by the zkvm host.
+    /// Sessions with proofs already on DA are dropped and generated proofs are submitted.
+    /// Sessions without a proof are dropped as well, their l1 blocks are proven again when processed.
+    async fn resume_proving_sessions(&self) -> Result<(), anyhow::Error> {
+        let mut recovered_proofs = HashMap::new();
+        for proof in self.prover_service.recover_proving_sessions().await? {
+            let (_, output) = extract_batch_proof_output::<Vm, Da::Spec, StateRoot>(&proof)?;
+            let Some(l1_height) = self
+                .ledger_db
+                .get_l1_height_of_l1_hash(output.da_slot_hash.into())?
+            else {
+                warn!("Recovered a proof of an unknown l1 block, skipping it");
+                continue;
+            };
+            recovered_proofs.insert((l1_height, output.sequencer_commitments_range), proof);
+        }
+
+        for (l1_height, range, status) in self.ledger_db.get_batch_proving_sessions()? {
+            let recovered_proof = recovered_proofs.remove(&(l1_height, range));
+
+            if proof_already_submitted(&self.ledger_db, l1_height, range)? {
+                info!(
+                    "Proof of commitments {:?} at l1 height {} is already on DA",
+                    range, l1_height
+                );
+                self.ledger_db
+                    .remove_batch_proving_session(l1_height, range)?;
+                continue;
+            }
+
+            let proof = match status {
+                StoredProvingSessionStatus::Proven(proof) => Some(proof),
+                StoredProvingSessionStatus::Proving => recovered_proof,
+            };
+            match proof {
+                Some(proof) => {
+                    info!(
+                        "Submitting the proof of commitments {:?} at l1 height {} from an interrupted proving session",
+                        range, l1_height
+                    );
+                    self.submit_and_store_proof(l1_height, range, proof).await?;
+                    BATCH_PROVER_METRICS.resumed_proving_sessions.increment(1);
+                }
+                None => {
+                    info!(
+                        "Proving session of commitments {:?} at l1 height {} is lost, proving it again",
+                        range, l1_height
+                    );
+                    self.ledger_db
+                        .remove_batch_proving_session(l1_height, range)?;
+                    BATCH_PROVER_METRICS.reproved_proving_sessions.increment(1);
+                }
+            }
+        }
+
+        // Sessions started by the zkvm host without a record in the ledger
+        for ((l1_height, range), proof) in recovered_proofs {
+            if !proof_already_submitted(&self.ledger_db, l1_height, range)? {
+                self.submit_and_store_proof(l1_height, range, proof).await?;
+                BATCH_PROVER_METRICS.resumed_proving_sessions.increment(1);
+            }
+        }
+
+        Ok(())
+    }
 
-        extract_and_store_proof::<DB, Da, Vm, StateRoot>(
+    async fn submit_and_store_proof(
+        &self,
+        l1_height: u64,
+        range: (u32, u32),
+        proof: Proof,
+    ) -> Result<(), anyhow::Error> {
+        submit_and_store_proof::<Da, Ps, Vm, DB, StateRoot>(
+            self.prover_service.as_ref(),
             self.ledger_db.clone(),
-            txs_and_proofs,
             self.code_commitments_by_spec.clone(),
+            l1_height,
+            range,
+            proof,
         )
-        .await?;
-
-        Ok(())
+        .await
     }
 }
 
@@ -350,96 +456,12 @@ async fn sync_l1<Da>(
     }
 }
 
-pub(crate) async fn get_batch_proof_circuit_input_from_commitments<
-    'txs,
-    Da: DaService,
-    DB: BatchProverLedgerOps,
-    Witness: DeserializeOwned,
-    Tx: Clone + BorshDeserialize + 'txs,
->(
-    sequencer_commitments: &[SequencerCommitment],
-    da_service: &Arc<Da>,
-    ledger_db: &DB,
-    l1_block_cache: &Arc<Mutex<L1BlockCache<Da>>>,
-) -> Result<CommitmentStateTransitionData<'txs, Witness, Da, Tx>, anyhow::Error> {
-    let mut state_transition_witnesses: VecDeque<Vec<(Witness, Witness)>> = VecDeque::new();
-    let mut soft_confirmations: VecDeque<Vec<SignedSoftConfirmation<Tx>>> = VecDeque::new();
-    let mut da_block_headers_of_soft_confirmations: VecDeque<
-        Vec<<<Da as DaService>::Spec as DaSpec>::BlockHeader>,
-    > = VecDeque::new();
-    for sequencer_commitment in sequencer_commitments.to_owned().iter() {
-        // get the l2 height ranges of each seq_commitments
-        let mut witnesses = vec![];
-        let start_l2 = sequencer_commitment.l2_start_block_number;
-        let end_l2 = sequencer_commitment.l2_end_block_number;
-        let soft_confirmations_in_commitment = match ledger_db.get_soft_confirmation_range(
-            &(SoftConfirmationNumber(start_l2)..=SoftConfirmationNumber(end_l2)),
-        ) {
-            Ok(soft_confirmations) => soft_confirmations,
-            Err(e) => {
-                return Err(anyhow!(
-                    "Failed to get soft confirmations from the ledger db: {}",
-                    e
-                ));
-            }
-        };
-        let mut commitment_soft_confirmations = vec![];
-        let mut da_block_headers_to_push: Vec<<<Da as DaService>::Spec as DaSpec>::BlockHeader> =
-            vec![];
-        for soft_confirmation in soft_confirmations_in_commitment {
-            if da_block_headers_to_push.is_empty()
-                || da_block_headers_to_push.last().unwrap().height()
-                    != soft_confirmation.da_slot_height
-            {
-                let filtered_block = match get_da_block_at_height(
-                    da_service,
-                    soft_confirmation.da_slot_height,
-                    l1_block_cache.clone(),
-                )
-                .await
-                {
-                    Ok(block) => block,
-                    Err(_) => {
-                        return Err(anyhow!(
-                            "Error while fetching DA block at height: {}",
-                            soft_confirmation.da_slot_height
-                        ));
-                    }
-                };
-                da_block_headers_to_push.push(filtered_block.header().clone());
-            }
-            let signed_soft_confirmation: SignedSoftConfirmation<Tx> = soft_confirmation
-                .try_into()
-                .context("Failed to parse transactions")?;
-            commitment_soft_confirmations.push(signed_soft_confirmation);
-        }
-        soft_confirmations.push_back(commitment_soft_confirmations);
-
-        da_block_headers_of_soft_confirmations.push_back(da_block_headers_to_push);
-        for l2_height in
-            sequencer_commitment.l2_start_block_number..=sequencer_commitment.l2_end_block_number
-        {
-            let (state_witness, offchain_witness) = match ledger_db
-                .get_l2_witness::<Witness>(l2_height)
-            {
-                Ok(inner) => inner.expect("Witnesses must be present"),
-                Err(e) => return Err(anyhow!("Failed to get witness from the ledger db: {}", e)),
-            };
-
-            witnesses.push((state_witness, offchain_witness));
-        }
-        state_transition_witnesses.push_back(witnesses);
-    }
-    Ok((
-        state_transition_witnesses,
-        soft_confirmations,
-        da_block_headers_of_soft_confirmations,
-    ))
-}
-
 pub(crate) fn break_sequencer_commitments_into_groups<DB: BatchProverLedgerOps>(
     ledger_db: &DB,
     sequencer_commitments: &[SequencerCommitment],
+    max_soft_confirmations_per_proof: u64,
+    sequencer_pub_keys: &SequencerKeySchedule,
+    commitment_signers: &[Vec<u8>],
 ) -> anyhow::Result<Vec<RangeInclusive<usize>>> {
     let mut result_range = vec![];
 
@@ -449,9 +471,12 @@ pub(crate) fn break_sequencer_commitments_into_groups<DB: BatchProverLedgerOps>(
         .ok_or(anyhow!("No Sequencer commitments found"))?
         .l2_start_block_number;
     let mut current_spec = fork_from_block_number(first_block_number).spec_id;
+    let mut current_sequencer_pub_key = sequencer_pub_keys.key_at(first_block_number);
+    let mut current_signer = &commitment_signers[0];
 
     let mut range = 0usize..=0usize;
     let mut cumulative_state_diff = StateDiff::new();
+    let mut cumulative_soft_confirmations = 0u64;
     for (index, sequencer_commitment) in sequencer_commitments.iter().enumerate() {
         let mut sequencer_commitment_state_diff = StateDiff::new();
         for l2_height in
@@ -467,28 +492,51 @@ pub(crate) fn break_sequencer_commitments_into_groups<DB: BatchProverLedgerOps>(
             sequencer_commitment_state_diff =
                 merge_state_diffs(sequencer_commitment_state_diff, state_diff);
         }
+        let sequencer_commitment_soft_confirmations = sequencer_commitment.l2_end_block_number
+            - sequencer_commitment.l2_start_block_number
+            + 1;
+
         cumulative_state_diff = merge_state_diffs(
             cumulative_state_diff,
             sequencer_commitment_state_diff.clone(),
         );
+        cumulative_soft_confirmations += sequencer_commitment_soft_confirmations;
 
         let compressed_state_diff = compress_blob(&borsh::to_vec(&cumulative_state_diff)?);
 
         // Threshold is checked by comparing compressed state diff size as the data will be compressed before it is written on DA
         let state_diff_threshold_reached = compressed_state_diff.len() > MAX_TXBODY_SIZE;
+        // Proving cost grows with the number of soft confirmations executed in the guest
+        let soft_confirmation_threshold_reached =
+            cumulative_soft_confirmations > max_soft_confirmations_per_proof;
 
         let commitment_spec =
             fork_from_block_number(sequencer_commitment.l2_end_block_number).spec_id;
-
-        if commitment_spec != current_spec || state_diff_threshold_reached {
+        // The circuit verifies all blocks of a proof with a single sequencer key
+        let commitment_sequencer_pub_key =
+            sequencer_pub_keys.key_at(sequencer_commitment.l2_start_block_number);
+        // The circuit accepts commitments of a single DA key per proof
+        let commitment_signer = &commitment_signers[index];
+
+        // A single commitment cannot be split, so the first one always starts the first group
+        if index > 0
+            && (commitment_spec != current_spec
+                || commitment_sequencer_pub_key != current_sequencer_pub_key
+                || commitment_signer != current_signer
+                || state_diff_threshold_reached
+                || soft_confirmation_threshold_reached)
+        {
             result_range.push(range);
-            // Reset the cumulative state diff to be equal to the current commitment state diff
+            // Reset the cumulative state diff and soft confirmation count to be equal to the current commitment's
             cumulative_state_diff = sequencer_commitment_state_diff;
+            cumulative_soft_confirmations = sequencer_commitment_soft_confirmations;
             range = index..=index;
-            current_spec = commitment_spec
         } else {
             range = *range.start()..=index;
         }
+        current_spec = commitment_spec;
+        current_sequencer_pub_key = commitment_sequencer_pub_key;
+        current_signer = commitment_signer;
     }
 
     // If the last group hasn't been reset because it has not reached the threshold,
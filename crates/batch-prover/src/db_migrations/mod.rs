@@ -2,9 +2,12 @@ use std::sync::OnceLock;
 
 use sov_db::ledger_db::migrations::LedgerMigration;
 
-#[allow(dead_code)]
+use crate::db_migrations::proofs_method_id::MigrateProofsMethodId;
+
+mod proofs_method_id;
+
 pub fn migrations() -> &'static Vec<Box<dyn LedgerMigration + Send + Sync + 'static>> {
     static MIGRATIONS: OnceLock<Vec<Box<dyn LedgerMigration + Send + Sync + 'static>>> =
         OnceLock::new();
-    MIGRATIONS.get_or_init(Vec::new)
+    MIGRATIONS.get_or_init(|| vec![Box::new(MigrateProofsMethodId {})])
 }
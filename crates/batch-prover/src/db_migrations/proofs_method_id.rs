@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use sov_db::ledger_db::migrations::legacy_types::StoredBatchProofV1;
+use sov_db::ledger_db::migrations::{LedgerMigration, MigrationName, MigrationVersion};
+use sov_db::ledger_db::LedgerDB;
+use sov_db::schema::types::StoredBatchProof;
+
+/// Value encoding migration
+/// "ProofsBySlotNumberV2" values now record the method id the proof was verified against.
+/// Existing proofs are stored without one.
+pub(crate) struct MigrateProofsMethodId {}
+
+impl LedgerMigration for MigrateProofsMethodId {
+    fn identifier(&self) -> (MigrationName, MigrationVersion) {
+        ("MigrateProofsMethodId".to_owned(), 1)
+    }
+
+    fn execute(
+        &self,
+        ledger_db: Arc<LedgerDB>,
+        _tables_to_drop: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let table = "ProofsBySlotNumberV2";
+
+        let handle = ledger_db.get_cf_handle(table)?;
+
+        let entries = ledger_db
+            .get_iterator_for_cf(handle, None)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (key, value) in entries {
+            let proofs: Vec<StoredBatchProofV1> = borsh::from_slice(&value)?;
+            let proofs = proofs
+                .into_iter()
+                .map(StoredBatchProof::from)
+                .collect::<Vec<_>>();
+            ledger_db.insert_into_cf_raw(handle, &key, &borsh::to_vec(&proofs)?)?;
+        }
+
+        Ok(())
+    }
+}
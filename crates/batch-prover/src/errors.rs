@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use sov_rollup_interface::da::SequencerCommitmentRangeError;
+
 pub enum L1ProcessingError {
     NoSeqCommitments {
         l1_height: u64,
@@ -11,6 +13,11 @@ pub enum L1ProcessingError {
         start_block_number: u64,
         end_block_number: u64,
     },
+    NonContiguousCommitments {
+        l1_height: u64,
+        sequencer_commitments_range: (u32, u32),
+        error: SequencerCommitmentRangeError,
+    },
     Other(String),
 }
 
@@ -37,6 +44,17 @@ impl Display for L1ProcessingError {
                     start_block_number, end_block_number
                 )
             }
+            L1ProcessingError::NonContiguousCommitments {
+                l1_height,
+                sequencer_commitments_range,
+                error,
+            } => {
+                write!(
+                    f,
+                    "Sequencer commitments {:?} at height {} can not be proven: {}",
+                    sequencer_commitments_range, l1_height, error
+                )
+            }
             L1ProcessingError::Other(e) => write!(f, "{}", e),
         }
     }
@@ -1,3 +1,4 @@
+pub mod circuit_input;
 mod da_block_handler;
 pub mod db_migrations;
 mod errors;
@@ -6,5 +7,7 @@ pub use runner::*;
 mod metrics;
 mod proving;
 pub mod rpc;
+mod simulation;
 
-pub use proving::GroupCommitments;
+pub use proving::{circuit_input_path, prove_from_file, ArchivedCircuitInput, GroupCommitments};
+pub use simulation::BatchProofSimulator;
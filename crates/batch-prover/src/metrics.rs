@@ -1,9 +1,9 @@
-use metrics::{Gauge, Histogram};
+use metrics::{Counter, Gauge, Histogram};
 use metrics_derive::Metrics;
 use once_cell::sync::Lazy;
 
 #[derive(Metrics)]
-#[metrics(scope = "batch_prover")]
+#[metrics(scope = "citrea_batch_prover")]
 pub struct BatchProverMetrics {
     #[metric(describe = "The current L1 block number which is used to produce L2 blocks")]
     pub current_l1_block: Gauge,
@@ -11,6 +11,18 @@ pub struct BatchProverMetrics {
     pub current_l2_block: Gauge,
     #[metric(describe = "The duration of processing a single soft confirmation")]
     pub process_soft_confirmation: Histogram,
+    #[metric(describe = "The duration of a proving session")]
+    pub proving_session: Histogram,
+    #[metric(describe = "The size of a generated proof in bytes")]
+    pub proof_size: Histogram,
+    #[metric(describe = "The duration of submitting proofs to DA")]
+    pub da_submission: Histogram,
+    #[metric(describe = "The number of proof submissions to DA that failed")]
+    pub da_submission_failures: Counter,
+    #[metric(describe = "The number of interrupted proving sessions resumed on restart")]
+    pub resumed_proving_sessions: Counter,
+    #[metric(describe = "The number of interrupted proving sessions re-proved on restart")]
+    pub reproved_proving_sessions: Counter,
 }
 
 /// Batch prover metrics
@@ -1,30 +1,42 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::anyhow;
 use borsh::{BorshDeserialize, BorshSerialize};
 use citrea_common::cache::L1BlockCache;
-use citrea_common::da::extract_sequencer_commitments;
-use citrea_common::utils::{check_l2_range_exists, filter_out_proven_commitments};
+use citrea_common::da::extract_signed_sequencer_commitments;
+use citrea_common::utils::{
+    check_l2_range_exists, extract_batch_proof_output, filter_out_proven_commitments,
+};
+use citrea_common::{events, BatchProverConfig, SequencerKeySchedule};
 use citrea_primitives::forks::fork_from_block_number;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sov_db::ledger_db::BatchProverLedgerOps;
-use sov_db::schema::types::{SoftConfirmationNumber, StoredBatchProof, StoredBatchProofOutput};
-use sov_modules_api::{BatchProofCircuitOutput, BlobReaderTrait, SlotData, SpecId, Zkvm};
-use sov_rollup_interface::da::{BlockHeaderTrait, DaNamespace, DaSpec, SequencerCommitment};
+use sov_db::schema::types::{
+    SoftConfirmationNumber, StoredBatchProof, StoredBatchProofOutput, StoredProvingSessionStatus,
+};
+use sov_modules_api::{BlobReaderTrait, SlotData, SpecId, Zkvm};
+use sov_rollup_interface::da::{
+    check_sequencer_commitments_contiguous, BlockHeaderTrait, DaNamespace, DaSpec,
+    SequencerCommitment,
+};
 use sov_rollup_interface::rpc::SoftConfirmationStatus;
 use sov_rollup_interface::services::da::DaService;
-use sov_rollup_interface::zk::{BatchProofCircuitInput, Proof, ZkvmHost};
+use sov_rollup_interface::zk::{Proof, ZkvmHost};
 use sov_stf_runner::ProverService;
 use tokio::sync::Mutex;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::da_block_handler::{
-    break_sequencer_commitments_into_groups, get_batch_proof_circuit_input_from_commitments,
-};
+use crate::circuit_input::{serialize_circuit_input, CircuitInputParts, SerializedCircuitInput};
+use crate::da_block_handler::break_sequencer_commitments_into_groups;
 use crate::errors::L1ProcessingError;
+use crate::metrics::BATCH_PROVER_METRICS;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 /// Enum to determine how to group commitments
@@ -33,33 +45,84 @@ pub enum GroupCommitments {
     /// Generates proof(s) given l1 height using the same strategy of batch prover
     Normal,
     /// Breaks all commitments into a single group and generates a single proof
+    /// The commitments must be signed with the same DA key
     SingleShot,
     /// Every commitment is a group on their own
     /// Generates a proof for every commitment
     OneByOne,
 }
 
-pub(crate) async fn data_to_prove<'txs, Da, DB, StateRoot, Witness, Tx>(
+/// A batch proof circuit input archived to disk before proving,
+/// so that the same proof can be reproduced later with `prove-from-file`.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct ArchivedCircuitInput {
+    /// Spec of the guest the input was proven with
+    pub spec_id: SpecId,
+    /// Borsh serialized `BatchProofCircuitInput`, exactly as passed to the guest
+    pub input: Vec<u8>,
+}
+
+/// Path of the archived circuit input for the given l1 height and commitment range
+pub fn circuit_input_path(dir: &Path, l1_height: u64, range: (u32, u32)) -> PathBuf {
+    dir.join(format!("{}_{}-{}.bin", l1_height, range.0, range.1))
+}
+
+/// Writes the input as an [`ArchivedCircuitInput`], without copying it
+fn archive_circuit_input(
+    dir: &Path,
+    l1_height: u64,
+    range: (u32, u32),
+    spec_id: SpecId,
+    input: &[u8],
+) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = circuit_input_path(dir, l1_height, range);
+    let mut writer = BufWriter::new(File::create(&path)?);
+    (spec_id, input).serialize(&mut writer)?;
+    writer.flush()?;
+    debug!("Archived batch proof circuit input to {}", path.display());
+    Ok(path)
+}
+
+/// Runs the batch proof guest on a circuit input archived by the batch prover.
+pub fn prove_from_file<Vm: ZkvmHost>(
+    mut vm: Vm,
+    elfs_by_spec: &HashMap<SpecId, Vec<u8>>,
+    path: &Path,
+    with_proof: bool,
+) -> anyhow::Result<Proof> {
+    let archived = ArchivedCircuitInput::try_from_slice(&std::fs::read(path)?)?;
+    let elf = elfs_by_spec
+        .get(&archived.spec_id)
+        .ok_or_else(|| anyhow!("No elf found for spec {:?}", archived.spec_id))?
+        .clone();
+
+    vm.add_hint(archived.input);
+    vm.run(elf, with_proof)
+}
+
+pub(crate) async fn data_to_prove<Da, DB, StateRoot, Witness, Tx>(
     da_service: Arc<Da>,
     ledger: DB,
-    sequencer_pub_key: Vec<u8>,
-    sequencer_da_pub_key: Vec<u8>,
+    sequencer_pub_keys: SequencerKeySchedule,
+    sequencer_da_pub_keys: Vec<Vec<u8>>,
     l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
     l1_block: &<Da as DaService>::FilteredBlock,
     group_commitments: Option<GroupCommitments>,
+    max_soft_confirmations_per_proof: u64,
 ) -> Result<
     (
         Vec<SequencerCommitment>,
-        Vec<BatchProofCircuitInput<'txs, StateRoot, Witness, Da::Spec, Tx>>,
+        Vec<SerializedCircuitInput<StateRoot>>,
     ),
     L1ProcessingError,
 >
 where
     Da: DaService,
     DB: BatchProverLedgerOps,
-    StateRoot: DeserializeOwned,
-    Witness: DeserializeOwned,
-    Tx: Clone + BorshDeserialize + 'txs,
+    StateRoot: BorshSerialize + DeserializeOwned,
+    Witness: BorshSerialize + DeserializeOwned,
+    Tx: Clone + BorshSerialize + BorshDeserialize,
 {
     let l1_height = l1_block.header().height();
 
@@ -71,8 +134,15 @@ where
         blob.full_data();
     });
 
-    let sequencer_commitments: Vec<SequencerCommitment> =
-        extract_sequencer_commitments::<Da>(da_service.clone(), l1_block, &sequencer_da_pub_key);
+    let signed_sequencer_commitments = extract_signed_sequencer_commitments::<Da>(
+        da_service.clone(),
+        l1_block,
+        &sequencer_da_pub_keys,
+    );
+    let sequencer_commitments: Vec<SequencerCommitment> = signed_sequencer_commitments
+        .iter()
+        .map(|(sequencer_commitment, _)| sequencer_commitment.clone())
+        .collect();
 
     if sequencer_commitments.is_empty() {
         return Err(L1ProcessingError::NoSeqCommitments {
@@ -104,6 +174,18 @@ where
         return Err(L1ProcessingError::DuplicateCommitments { l1_height });
     }
 
+    // DA keys that signed the commitments to prove
+    let commitment_signers: Vec<Vec<u8>> = sequencer_commitments
+        .iter()
+        .map(|sequencer_commitment| {
+            signed_sequencer_commitments
+                .iter()
+                .find(|(signed_commitment, _)| signed_commitment == sequencer_commitment)
+                .map(|(_, signer)| signer.clone())
+                .expect("Commitments to prove are extracted from the block")
+        })
+        .collect();
+
     let da_block_header_of_commitments: <<Da as DaService>::Spec as DaSpec>::BlockHeader =
         l1_block.header().clone();
 
@@ -115,40 +197,41 @@ where
             .map(|(i, _)| (i..=i))
             .collect(),
         // Default behavior is the normal grouping
-        _ => break_sequencer_commitments_into_groups(&ledger, &sequencer_commitments).map_err(
-            |e| {
-                L1ProcessingError::Other(format!(
-                    "Error breaking sequencer commitments into groups: {:?}",
-                    e
-                ))
-            },
-        )?,
+        _ => break_sequencer_commitments_into_groups(
+            &ledger,
+            &sequencer_commitments,
+            max_soft_confirmations_per_proof,
+            &sequencer_pub_keys,
+            &commitment_signers,
+        )
+        .map_err(|e| {
+            L1ProcessingError::Other(format!(
+                "Error breaking sequencer commitments into groups: {:?}",
+                e
+            ))
+        })?,
     };
 
     let mut batch_proof_circuit_inputs = vec![];
 
     for sequencer_commitments_range in ranges {
+        // The circuit rejects commitments leaving gaps or overlapping, do not start proving them
+        check_sequencer_commitments_contiguous(
+            &sequencer_commitments[sequencer_commitments_range.clone()],
+        )
+        .map_err(|error| L1ProcessingError::NonContiguousCommitments {
+            l1_height,
+            sequencer_commitments_range: (
+                *sequencer_commitments_range.start() as u32,
+                *sequencer_commitments_range.end() as u32,
+            ),
+            error,
+        })?;
+
         let first_l2_height_of_l1 =
             sequencer_commitments[*sequencer_commitments_range.start()].l2_start_block_number;
         let last_l2_height_of_l1 =
             sequencer_commitments[*sequencer_commitments_range.end()].l2_end_block_number;
-        let (
-            state_transition_witnesses,
-            soft_confirmations,
-            da_block_headers_of_soft_confirmations,
-        ) = get_batch_proof_circuit_input_from_commitments(
-            &sequencer_commitments[sequencer_commitments_range.clone()],
-            &da_service,
-            &ledger,
-            &l1_block_cache,
-        )
-        .await
-        .map_err(|e| {
-            L1ProcessingError::Other(format!(
-                "Error getting state transition data from commitments: {:?}",
-                e
-            ))
-        })?;
         let initial_state_root = ledger
             .get_l2_state_root::<StateRoot>(first_l2_height_of_l1 - 1)
             .map_err(|e| {
@@ -174,26 +257,40 @@ where
             )))?
             .prev_hash;
 
-        let input: BatchProofCircuitInput<StateRoot, Witness, Da::Spec, Tx> =
-            BatchProofCircuitInput {
-                initial_state_root,
-                da_data: da_data.clone(),
-                da_block_header_of_commitments: da_block_header_of_commitments.clone(),
-                inclusion_proof: inclusion_proof.clone(),
-                completeness_proof: completeness_proof.clone(),
-                soft_confirmations,
-                state_transition_witnesses,
-                da_block_headers_of_soft_confirmations,
-                preproven_commitments: preproven_commitments.to_vec(),
-                sequencer_commitments_range: (
-                    *sequencer_commitments_range.start() as u32,
-                    *sequencer_commitments_range.end() as u32,
-                ),
-                sequencer_public_key: sequencer_pub_key.clone(),
-                sequencer_da_public_key: sequencer_da_pub_key.clone(),
-                final_state_root,
-                prev_soft_confirmation_hash: initial_batch_hash,
-            };
+        let parts: CircuitInputParts<StateRoot, Da::Spec> = CircuitInputParts {
+            initial_state_root,
+            da_data: da_data.clone(),
+            da_block_header_of_commitments: da_block_header_of_commitments.clone(),
+            inclusion_proof: inclusion_proof.clone(),
+            completeness_proof: completeness_proof.clone(),
+            preproven_commitments: preproven_commitments.to_vec(),
+            sequencer_commitments_range: (
+                *sequencer_commitments_range.start() as u32,
+                *sequencer_commitments_range.end() as u32,
+            ),
+            // Groups do not span key rotations, so a single key signs all of their blocks
+            sequencer_public_key: sequencer_pub_keys.key_at(first_l2_height_of_l1).to_vec(),
+            // Groups do not span DA key changes either, the circuit records the key of their commitments
+            sequencer_da_public_key: commitment_signers[*sequencer_commitments_range.start()]
+                .clone(),
+            final_state_root,
+            prev_soft_confirmation_hash: initial_batch_hash,
+        };
+
+        let input = serialize_circuit_input::<Da, DB, StateRoot, Witness, Tx>(
+            parts,
+            &sequencer_commitments[sequencer_commitments_range],
+            &da_service,
+            &ledger,
+            &l1_block_cache,
+        )
+        .await
+        .map_err(|e| {
+            L1ProcessingError::Other(format!(
+                "Error serializing circuit input from commitments: {:?}",
+                e
+            ))
+        })?;
 
         batch_proof_circuit_inputs.push(input);
     }
@@ -204,11 +301,12 @@ where
 pub(crate) async fn prove_l1<Da, Ps, Vm, DB, StateRoot, Witness, Tx>(
     prover_service: Arc<Ps>,
     ledger: DB,
+    prover_config: &BatchProverConfig,
     code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
     elfs_by_spec: HashMap<SpecId, Vec<u8>>,
     l1_block: &Da::FilteredBlock,
     sequencer_commitments: Vec<SequencerCommitment>,
-    inputs: Vec<BatchProofCircuitInput<'_, StateRoot, Witness, Da::Spec, Tx>>,
+    inputs: Vec<SerializedCircuitInput<StateRoot>>,
 ) -> anyhow::Result<()>
 where
     Da: DaService,
@@ -225,68 +323,107 @@ where
     Witness: Default + BorshSerialize + BorshDeserialize + Serialize + DeserializeOwned,
     Tx: Clone + BorshSerialize,
 {
+    let l1_height = l1_block.header().height();
     let submitted_proofs = ledger
-        .get_proofs_by_l1_height(l1_block.header().height())
+        .get_proofs_by_l1_height(l1_height)
         .map_err(|e| anyhow!("{e}"))?
         .unwrap_or(vec![]);
 
-    // Add each non-proven proof's data to ProverService
-    for input in inputs {
-        if !state_transition_already_proven::<StateRoot, Witness, Da, Tx>(&input, &submitted_proofs)
-        {
-            prover_service
-                .add_proof_data((borsh::to_vec(&input)?, vec![]))
-                .await;
-        }
-    }
-
     let last_l2_height = sequencer_commitments
         .last()
         .expect("Should have at least 1 commitment")
         .l2_end_block_number;
     let current_spec = fork_from_block_number(last_l2_height).spec_id;
-    let elf = elfs_by_spec
-        .get(&current_spec)
-        .expect("Every fork should have an elf attached")
-        .clone();
 
-    // Prove all proofs in parallel
-    let proofs = prover_service.prove(elf).await?;
+    // Add each non-proven proof's data to ProverService
+    let mut archived_paths = vec![];
+    let mut ranges = vec![];
+    for input in inputs {
+        if !state_transition_already_proven(&input, &submitted_proofs) {
+            if let Some(dir) = &prover_config.circuit_input_dir {
+                archived_paths.push(archive_circuit_input(
+                    dir,
+                    l1_height,
+                    input.sequencer_commitments_range,
+                    current_spec,
+                    &input.input,
+                )?);
+            }
+
+            ledger.put_batch_proving_session(
+                l1_height,
+                input.sequencer_commitments_range,
+                StoredProvingSessionStatus::Proving,
+            )?;
+            ranges.push(input.sequencer_commitments_range);
+
+            prover_service.add_proof_data((input.input, vec![])).await;
+        }
+    }
 
-    let txs_and_proofs = prover_service.submit_proofs(proofs).await?;
+    if !ranges.is_empty() {
+        let elf = elfs_by_spec
+            .get(&current_spec)
+            .expect("Every fork should have an elf attached")
+            .clone();
+
+        // Prove all proofs in parallel
+        let start = Instant::now();
+        let proofs = prover_service.prove(elf).await?;
+        let proving_duration = start.elapsed();
+        BATCH_PROVER_METRICS
+            .proving_session
+            .record(proving_duration.as_secs_f64());
+        for proof in &proofs {
+            BATCH_PROVER_METRICS.proof_size.record(proof.len() as f64);
+        }
 
-    extract_and_store_proof::<DB, Da, Vm, StateRoot>(
-        ledger.clone(),
-        txs_and_proofs,
-        code_commitments_by_spec.clone(),
-    )
-    .await
-    .map_err(|e| anyhow!("{e}"))?;
+        // Proofs are returned in the order their data is added
+        for (range, proof) in ranges.iter().zip(&proofs) {
+            ledger.put_batch_proving_session(
+                l1_height,
+                *range,
+                StoredProvingSessionStatus::Proven(proof.clone()),
+            )?;
+            events::proof_generated(l1_height, *range, proof.len(), proving_duration);
+        }
 
-    save_commitments(
-        ledger.clone(),
-        &sequencer_commitments,
-        l1_block.header().height(),
-    );
+        for (range, proof) in ranges.into_iter().zip(proofs) {
+            submit_and_store_proof::<Da, Ps, Vm, DB, StateRoot>(
+                prover_service.as_ref(),
+                ledger.clone(),
+                code_commitments_by_spec.clone(),
+                l1_height,
+                range,
+                proof,
+            )
+            .await?;
+        }
+    }
+
+    if !prover_config.keep_circuit_inputs {
+        for path in archived_paths {
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!(
+                    "Failed to remove archived circuit input {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    save_commitments(ledger.clone(), &sequencer_commitments, l1_height);
 
     Ok(())
 }
 
-pub(crate) fn state_transition_already_proven<StateRoot, Witness, Da, Tx>(
-    input: &BatchProofCircuitInput<StateRoot, Witness, Da::Spec, Tx>,
+pub(crate) fn state_transition_already_proven<StateRoot>(
+    input: &SerializedCircuitInput<StateRoot>,
     proofs: &Vec<StoredBatchProof>,
 ) -> bool
 where
-    Da: DaService,
-    StateRoot: BorshDeserialize
-        + BorshSerialize
-        + Serialize
-        + DeserializeOwned
-        + Clone
-        + AsRef<[u8]>
-        + Debug,
-    Witness: Default + BorshDeserialize + Serialize + DeserializeOwned,
-    Tx: Clone,
+    StateRoot: AsRef<[u8]>,
 {
     for proof in proofs {
         if proof.proof_output.initial_state_root == input.initial_state_root.as_ref()
@@ -298,6 +435,72 @@ where
     false
 }
 
+/// Returns whether the proof of the given commitment range found in the l1 block is stored,
+/// meaning that it is already submitted to DA.
+pub(crate) fn proof_already_submitted<DB>(
+    ledger: &DB,
+    l1_height: u64,
+    sequencer_commitments_range: (u32, u32),
+) -> anyhow::Result<bool>
+where
+    DB: BatchProverLedgerOps,
+{
+    Ok(ledger
+        .get_proofs_by_l1_height(l1_height)?
+        .unwrap_or_default()
+        .iter()
+        .any(|proof| proof.proof_output.sequencer_commitments_range == sequencer_commitments_range))
+}
+
+/// Submits the proof of a proving session to DA and stores it,
+/// after which the session is removed from the ledger.
+pub(crate) async fn submit_and_store_proof<Da, Ps, Vm, DB, StateRoot>(
+    prover_service: &Ps,
+    ledger: DB,
+    code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
+    l1_height: u64,
+    sequencer_commitments_range: (u32, u32),
+    proof: Proof,
+) -> anyhow::Result<()>
+where
+    Da: DaService,
+    DB: BatchProverLedgerOps + Clone,
+    Vm: ZkvmHost + Zkvm,
+    Ps: ProverService<DaService = Da>,
+    StateRoot: BorshDeserialize
+        + BorshSerialize
+        + Serialize
+        + DeserializeOwned
+        + Clone
+        + AsRef<[u8]>
+        + Debug,
+{
+    // Proofs are written to DA in the encoding of the spec of the last proven L2 block
+    let (_, circuit_output) =
+        extract_batch_proof_output::<Vm, <Da as DaService>::Spec, StateRoot>(&proof)?;
+    let spec_id = fork_from_block_number(circuit_output.last_l2_height).spec_id;
+
+    let start = Instant::now();
+    let txs_and_proofs = prover_service
+        .submit_proofs(vec![proof], spec_id)
+        .await
+        .inspect_err(|_| BATCH_PROVER_METRICS.da_submission_failures.increment(1))?;
+    BATCH_PROVER_METRICS.da_submission.record(
+        Instant::now()
+            .saturating_duration_since(start)
+            .as_secs_f64(),
+    );
+
+    extract_and_store_proof::<DB, Da, Vm, StateRoot>(
+        ledger.clone(),
+        txs_and_proofs,
+        code_commitments_by_spec,
+    )
+    .await?;
+
+    ledger.remove_batch_proving_session(l1_height, sequencer_commitments_range)
+}
+
 pub(crate) async fn extract_and_store_proof<DB, Da, Vm, StateRoot>(
     ledger_db: DB,
     txs_and_proofs: Vec<(<Da as DaService>::TransactionId, Proof)>,
@@ -320,12 +523,9 @@ where
 
         // l1_height => (tx_id, proof, circuit_output)
         // save proof along with tx id to db, should be queryable by slot number or slot hash
-        // TODO: select output version based on spec
-        let circuit_output = Vm::extract_output::<
-            <Da as DaService>::Spec,
-            BatchProofCircuitOutput<<Da as DaService>::Spec, StateRoot>,
-        >(&proof)
-        .expect("Proof should be deserializable");
+        let (output_version, circuit_output) =
+            extract_batch_proof_output::<Vm, <Da as DaService>::Spec, StateRoot>(&proof)
+                .expect("Proof should be deserializable");
 
         let last_active_spec_id = fork_from_block_number(circuit_output.last_l2_height).spec_id;
 
@@ -343,6 +543,7 @@ where
         let slot_hash = circuit_output.da_slot_hash.into();
 
         let stored_batch_proof_output = StoredBatchProofOutput {
+            output_version,
             initial_state_root: circuit_output.initial_state_root.as_ref().to_vec(),
             final_state_root: circuit_output.final_state_root.as_ref().to_vec(),
             state_diff: circuit_output.state_diff,
@@ -354,6 +555,8 @@ where
             prev_soft_confirmation_hash: circuit_output.prev_soft_confirmation_hash,
             final_soft_confirmation_hash: circuit_output.final_soft_confirmation_hash,
             last_l2_height: circuit_output.last_l2_height,
+            first_l2_height: circuit_output.first_l2_height,
+            verified_method_id: Some(code_commitment.clone().into()),
         };
         let l1_height = ledger_db
             .get_l1_height_of_l1_hash(slot_hash)?
@@ -1,15 +1,18 @@
 #![allow(clippy::type_complexity)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use citrea_common::cache::L1BlockCache;
+use citrea_common::{BatchProverConfig, SequencerKeySchedule};
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
-use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee::types::error::{
+    INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, INVALID_PARAMS_CODE, INVALID_PARAMS_MSG,
+};
 use jsonrpsee::types::ErrorObjectOwned;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -21,6 +24,7 @@ use sov_stf_runner::ProverService;
 use tokio::sync::Mutex;
 
 use crate::proving::{data_to_prove, prove_l1, GroupCommitments};
+use crate::simulation::{simulate_l1, BatchProofSimulator};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProverInputResponse {
@@ -29,6 +33,39 @@ pub struct ProverInputResponse {
     pub encoded_serialized_batch_proof_input: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvingStatusResponse {
+    /// Number of proofs that are queued or being generated
+    pub pending_proving_sessions: usize,
+    /// L1 heights with sequencer commitments that are not proven yet
+    pub l1_heights_awaiting_proof: Vec<u64>,
+    /// Hex encoded identifiers of the proving sessions in progress
+    pub executing_sessions: Vec<String>,
+    /// Number of proofs being generated right now
+    pub ongoing_proofs: usize,
+    /// Maximum number of proofs generated in parallel, set by `PARALLEL_PROOF_LIMIT`
+    pub parallel_proof_limit: usize,
+    /// Whether queued proofs are waiting for a free slot under the parallel proof limit
+    pub blocked_on_parallel_proof_limit: bool,
+}
+
+/// Whether a commitment range would prove, as simulated in simulate proving mode
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResultResponse {
+    /// L1 height the commitments were found at
+    pub l1_height: u64,
+    /// Inclusive range of the simulated commitments in the l1 block
+    pub commitment_range: (u32, u32),
+    /// Whether the circuit accepted the input
+    pub success: bool,
+    /// Cycles the circuit took, if measured
+    pub cycles: Option<u64>,
+    /// Why the circuit rejected the input
+    pub failure: Option<String>,
+}
+
 pub struct RpcContext<C, Da, Ps, Vm, DB, StateRoot, Witness, Tx>
 where
     C: sov_modules_api::Context,
@@ -47,12 +84,16 @@ where
 {
     pub da_service: Arc<Da>,
     pub prover_service: Arc<Ps>,
+    pub l1_heights_awaiting_proof: Arc<Mutex<BTreeSet<u64>>>,
     pub ledger: DB,
-    pub sequencer_da_pub_key: Vec<u8>,
-    pub sequencer_pub_key: Vec<u8>,
+    pub prover_config: BatchProverConfig,
+    pub sequencer_da_pub_keys: Vec<Vec<u8>>,
+    pub sequencer_pub_keys: SequencerKeySchedule,
     pub l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
     pub code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
     pub elfs_by_spec: HashMap<SpecId, Vec<u8>>,
+    /// Set in simulate proving mode, in which `batchProver_prove` simulates instead of proving
+    pub simulator: Option<BatchProofSimulator>,
     pub(crate) phantom_c: PhantomData<fn() -> C>,
     pub(crate) phantom_vm: PhantomData<fn() -> Vm>,
     pub(crate) phantom_sr: PhantomData<fn() -> StateRoot>,
@@ -77,6 +118,19 @@ pub trait BatchProverRpc {
         l1_height: u64,
         group_commitments: Option<GroupCommitments>,
     ) -> RpcResult<()>;
+
+    /// Report the proving queue and ongoing proving sessions.
+    #[method(name = "getProvingStatus")]
+    async fn get_proving_status(&self) -> RpcResult<ProvingStatusResponse>;
+
+    /// Get the simulation results of the commitment ranges found in the given L1 blocks.
+    /// Results are only recorded in simulate proving mode.
+    #[method(name = "getSimulationResults")]
+    async fn get_simulation_results(
+        &self,
+        start_l1_height: u64,
+        end_l1_height: u64,
+    ) -> RpcResult<Vec<SimulationResultResponse>>;
 }
 
 pub struct BatchProverRpcServerImpl<C, Da, Ps, Vm, DB, StateRoot, Witness, Tx>
@@ -165,11 +219,12 @@ where
         let (_, inputs) = data_to_prove::<Da, DB, StateRoot, Witness, Tx>(
             self.context.da_service.clone(),
             self.context.ledger.clone(),
-            self.context.sequencer_pub_key.clone(),
-            self.context.sequencer_da_pub_key.clone(),
+            self.context.sequencer_pub_keys.clone(),
+            self.context.sequencer_da_pub_keys.clone(),
             self.context.l1_block_cache.clone(),
             &l1_block,
             group_commitments,
+            self.context.prover_config.max_soft_confirmations_per_proof,
         )
         .await
         .map_err(|e| {
@@ -183,14 +238,10 @@ where
         let mut batch_proof_circuit_input_responses = vec![];
 
         for input in inputs {
-            let range_start = input.sequencer_commitments_range.0;
-            let range_end = input.sequencer_commitments_range.1;
-            let serialized_circuit_input = serialize_batch_proof_circuit_input(input);
-
             let response = ProverInputResponse {
-                commitment_range: (range_start, range_end),
+                commitment_range: input.sequencer_commitments_range,
                 l1_block_height: l1_height,
-                encoded_serialized_batch_proof_input: hex::encode(serialized_circuit_input),
+                encoded_serialized_batch_proof_input: hex::encode(input.input),
             };
 
             batch_proof_circuit_input_responses.push(response);
@@ -220,11 +271,12 @@ where
         let (sequencer_commitments, inputs) = data_to_prove::<Da, DB, StateRoot, Witness, Tx>(
             self.context.da_service.clone(),
             self.context.ledger.clone(),
-            self.context.sequencer_pub_key.clone(),
-            self.context.sequencer_da_pub_key.clone(),
+            self.context.sequencer_pub_keys.clone(),
+            self.context.sequencer_da_pub_keys.clone(),
             self.context.l1_block_cache.clone(),
             &l1_block,
             group_commitments,
+            self.context.prover_config.max_soft_confirmations_per_proof,
         )
         .await
         .map_err(|e| {
@@ -235,17 +287,32 @@ where
             )
         })?;
 
-        prove_l1::<Da, Ps, Vm, DB, StateRoot, Witness, Tx>(
-            self.context.prover_service.clone(),
-            self.context.ledger.clone(),
-            self.context.code_commitments_by_spec.clone(),
-            self.context.elfs_by_spec.clone(),
-            &l1_block,
-            sequencer_commitments,
-            inputs,
-        )
-        .await
-        .map_err(|e| {
+        let result = match &self.context.simulator {
+            Some(simulator) => {
+                simulate_l1(
+                    simulator.clone(),
+                    self.context.ledger.clone(),
+                    l1_height,
+                    sequencer_commitments,
+                    inputs,
+                )
+                .await
+            }
+            None => {
+                prove_l1::<Da, Ps, Vm, DB, StateRoot, Witness, Tx>(
+                    self.context.prover_service.clone(),
+                    self.context.ledger.clone(),
+                    &self.context.prover_config,
+                    self.context.code_commitments_by_spec.clone(),
+                    self.context.elfs_by_spec.clone(),
+                    &l1_block,
+                    sequencer_commitments,
+                    inputs,
+                )
+                .await
+            }
+        };
+        result.map_err(|e| {
             ErrorObjectOwned::owned(
                 INTERNAL_ERROR_CODE,
                 INTERNAL_ERROR_MSG,
@@ -255,10 +322,77 @@ where
 
         Ok(())
     }
-}
 
-fn serialize_batch_proof_circuit_input<T: BorshSerialize>(item: T) -> Vec<u8> {
-    borsh::to_vec(&item).expect("Risc0 hint serialization is infallible")
+    async fn get_proving_status(&self) -> RpcResult<ProvingStatusResponse> {
+        let status = self.context.prover_service.status().map_err(|e| {
+            ErrorObjectOwned::owned(
+                INTERNAL_ERROR_CODE,
+                INTERNAL_ERROR_MSG,
+                Some(format!("{e}",)),
+            )
+        })?;
+
+        let l1_heights_awaiting_proof = self
+            .context
+            .l1_heights_awaiting_proof
+            .lock()
+            .await
+            .iter()
+            .copied()
+            .collect();
+
+        Ok(ProvingStatusResponse {
+            pending_proving_sessions: status.queued_proofs + status.ongoing_proofs,
+            l1_heights_awaiting_proof,
+            executing_sessions: status.pending_sessions.iter().map(hex::encode).collect(),
+            ongoing_proofs: status.ongoing_proofs,
+            parallel_proof_limit: status.parallel_proof_limit,
+            blocked_on_parallel_proof_limit: status.queued_proofs > 0
+                && status.ongoing_proofs >= status.parallel_proof_limit,
+        })
+    }
+
+    async fn get_simulation_results(
+        &self,
+        start_l1_height: u64,
+        end_l1_height: u64,
+    ) -> RpcResult<Vec<SimulationResultResponse>> {
+        if start_l1_height > end_l1_height {
+            return Err(ErrorObjectOwned::owned(
+                INVALID_PARAMS_CODE,
+                INVALID_PARAMS_MSG,
+                Some(format!(
+                    "invalid L1 range. Start: {} is greater than end: {}",
+                    start_l1_height, end_l1_height
+                )),
+            ));
+        }
+
+        let simulations = self
+            .context
+            .ledger
+            .get_batch_proof_simulations(start_l1_height..=end_l1_height)
+            .map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(format!("{e}",)),
+                )
+            })?;
+
+        Ok(simulations
+            .into_iter()
+            .map(
+                |(l1_height, commitment_range, simulation)| SimulationResultResponse {
+                    l1_height,
+                    commitment_range,
+                    success: simulation.failure.is_none(),
+                    cycles: simulation.cycles,
+                    failure: simulation.failure,
+                },
+            )
+            .collect())
+    }
 }
 
 pub fn create_rpc_module<C, Da, Ps, Vm, DB, StateRoot, Witness, Tx>(
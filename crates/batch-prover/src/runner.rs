@@ -1,25 +1,29 @@
 use core::panic;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use alloy_primitives::U64;
-use anyhow::{anyhow, bail, Context as _};
+use anyhow::{bail, Context as _};
 use backoff::exponential::ExponentialBackoffBuilder;
 use backoff::future::retry as retry_backoff;
 use citrea_common::cache::L1BlockCache;
 use citrea_common::da::{get_da_block_at_height, get_initial_slot_height};
 use citrea_common::tasks::manager::TaskManager;
-use citrea_common::utils::{create_shutdown_signal, soft_confirmation_to_receipt};
-use citrea_common::{BatchProverConfig, RollupPublicKeys, RpcConfig, RunnerConfig};
+use citrea_common::utils::{
+    check_l2_genesis_state_root, commit_finalized_staged_soft_confirmations,
+    create_shutdown_signal, soft_confirmation_to_receipt, soft_confirmations_with_heights,
+};
+use citrea_common::{
+    events, BatchProverConfig, RollupPublicKeys, RpcConfig, RunnerConfig, SequencerKeySchedule,
+};
 use citrea_primitives::types::SoftConfirmationHash;
 use jsonrpsee::core::client::Error as JsonrpseeError;
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
-use jsonrpsee::server::{BatchRequestConfig, ServerBuilder};
 use jsonrpsee::RpcModule;
 use sov_db::ledger_db::BatchProverLedgerOps;
-use sov_db::schema::types::{SlotNumber, SoftConfirmationNumber};
+use sov_db::schema::types::SoftConfirmationNumber;
 use sov_ledger_rpc::LedgerRpcClient;
 use sov_modules_api::{Context, SignedSoftConfirmation, SlotData, Spec};
 use sov_modules_stf_blueprint::{Runtime, StfBlueprint};
@@ -31,7 +35,8 @@ use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::stf::StateTransitionFunction;
 use sov_rollup_interface::zk::ZkvmHost;
-use sov_stf_runner::{InitVariant, ProverService};
+use sov_state::storage::NativeStorage;
+use sov_stf_runner::{InitVariant, ProverGuestRunConfig, ProverService};
 use tokio::select;
 use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
 use tokio::time::sleep;
@@ -40,6 +45,7 @@ use tracing::{debug, error, info, instrument};
 use crate::da_block_handler::L1BlockHandler;
 use crate::metrics::BATCH_PROVER_METRICS;
 use crate::rpc::{create_rpc_module, RpcContext};
+use crate::simulation::BatchProofSimulator;
 
 type StfStateRoot<C, Da, RT> = <StfBlueprint<C, Da, RT> as StateTransitionFunction<Da>>::StateRoot;
 type StfTransaction<C, Da, RT> =
@@ -65,16 +71,19 @@ where
     rpc_config: RpcConfig,
     prover_service: Arc<Ps>,
     sequencer_client: HttpClient,
-    sequencer_pub_key: Vec<u8>,
-    sequencer_da_pub_key: Vec<u8>,
+    sequencer_pub_keys: SequencerKeySchedule,
+    sequencer_da_pub_keys: Vec<Vec<u8>>,
     phantom: std::marker::PhantomData<C>,
     prover_config: BatchProverConfig,
     code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
     elfs_by_spec: HashMap<SpecId, Vec<u8>>,
+    simulator: Option<BatchProofSimulator>,
     l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
+    l1_heights_awaiting_proof: Arc<Mutex<BTreeSet<u64>>>,
     sync_blocks_count: u64,
     fork_manager: ForkManager<'static>,
     soft_confirmation_tx: broadcast::Sender<u64>,
+    allow_genesis_state_root_mismatch: bool,
     task_manager: TaskManager<()>,
 }
 
@@ -92,6 +101,7 @@ where
     /// If a previous state root is provided, uses that as the starting point
     /// for execution. Otherwise, initializes the chain using the provided
     /// genesis config.
+    /// The `simulator` is required in simulate proving mode, and only used in that mode.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         runner_config: RunnerConfig,
@@ -106,11 +116,20 @@ where
         prover_config: BatchProverConfig,
         code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
         elfs_by_spec: HashMap<SpecId, Vec<u8>>,
-        fork_manager: ForkManager<'static>,
+        simulator: Option<BatchProofSimulator>,
+        mut fork_manager: ForkManager<'static>,
         soft_confirmation_tx: broadcast::Sender<u64>,
         task_manager: TaskManager<()>,
     ) -> Result<Self, anyhow::Error> {
-        let (prev_state_root, prev_batch_hash) = match init_variant {
+        let simulator = match prover_config.proving_mode {
+            ProverGuestRunConfig::Simulate => match simulator {
+                Some(simulator) => Some(simulator),
+                None => bail!("Simulate proving mode requires a batch proof simulator"),
+            },
+            _ => None,
+        };
+
+        let (mut prev_state_root, mut prev_batch_hash) = match init_variant {
             InitVariant::Initialized((state_root, batch_hash)) => {
                 debug!("Chain is already initialized. Skipping initialization.");
                 (state_root, batch_hash)
@@ -130,6 +149,26 @@ where
             }
         };
 
+        // Commit the ledger data of L2 blocks whose state was finalized before the last shutdown
+        let finalized_storage = storage_manager.create_finalized_storage()?;
+        let recovered = commit_finalized_staged_soft_confirmations(&ledger_db, |l2_height| {
+            finalized_storage
+                .get_root_hash(l2_height + 1)
+                .ok()
+                .map(|root| root.as_ref().to_vec())
+        })?;
+        if let Some(last) = recovered.last() {
+            for soft_confirmation in &recovered {
+                fork_manager.register_block(soft_confirmation.l2_height)?;
+            }
+            prev_state_root = finalized_storage.get_root_hash(last.l2_height + 1)?;
+            prev_batch_hash = last.hash;
+            info!(
+                "Recovered ledger data of L2 blocks #{} to #{}",
+                recovered[0].l2_height, last.l2_height
+            );
+        }
+
         // Last L1/L2 height before shutdown.
         let start_l2_height = ledger_db.get_head_soft_confirmation_height()?.unwrap_or(0) + 1;
 
@@ -145,13 +184,16 @@ where
             prover_service,
             sequencer_client: HttpClientBuilder::default()
                 .build(runner_config.sequencer_client_url)?,
-            sequencer_pub_key: public_keys.sequencer_public_key,
-            sequencer_da_pub_key: public_keys.sequencer_da_pub_key,
+            allow_genesis_state_root_mismatch: runner_config.allow_genesis_state_root_mismatch,
+            sequencer_pub_keys: public_keys.sequencer_key_schedule(),
+            sequencer_da_pub_keys: public_keys.sequencer_da_pub_keys(),
             phantom: std::marker::PhantomData,
             prover_config,
             code_commitments_by_spec,
             elfs_by_spec,
+            simulator,
             l1_block_cache: Arc::new(Mutex::new(L1BlockCache::new())),
+            l1_heights_awaiting_proof: Arc::new(Mutex::new(BTreeSet::new())),
             sync_blocks_count: runner_config.sync_blocks_count,
             fork_manager,
             soft_confirmation_tx,
@@ -175,13 +217,16 @@ where
     > {
         RpcContext {
             ledger: self.ledger_db.clone(),
+            prover_config: self.prover_config.clone(),
             da_service: self.da_service.clone(),
-            sequencer_da_pub_key: self.sequencer_da_pub_key.clone(),
-            sequencer_pub_key: self.sequencer_pub_key.clone(),
+            sequencer_da_pub_keys: self.sequencer_da_pub_keys.clone(),
+            sequencer_pub_keys: self.sequencer_pub_keys.clone(),
             l1_block_cache: self.l1_block_cache.clone(),
             prover_service: self.prover_service.clone(),
+            l1_heights_awaiting_proof: self.l1_heights_awaiting_proof.clone(),
             code_commitments_by_spec: self.code_commitments_by_spec.clone(),
             elfs_by_spec: self.elfs_by_spec.clone(),
+            simulator: self.simulator.clone(),
             phantom_c: std::marker::PhantomData,
             phantom_vm: std::marker::PhantomData,
             phantom_sr: std::marker::PhantomData,
@@ -209,65 +254,19 @@ where
     ) -> anyhow::Result<()> {
         let methods = self.register_rpc_methods(methods)?;
 
-        let listen_address = SocketAddr::new(
-            self.rpc_config
-                .bind_host
-                .parse()
-                .map_err(|e| anyhow!("Failed to parse bind host: {}", e))?,
-            self.rpc_config.bind_port,
-        );
-
-        let max_connections = self.rpc_config.max_connections;
-        let max_subscriptions_per_connection = self.rpc_config.max_subscriptions_per_connection;
-        let max_request_body_size = self.rpc_config.max_request_body_size;
-        let max_response_body_size = self.rpc_config.max_response_body_size;
-        let batch_requests_limit = self.rpc_config.batch_requests_limit;
-
-        let middleware = tower::ServiceBuilder::new().layer(citrea_common::rpc::get_cors_layer());
-        //  .layer(citrea_common::rpc::get_healthcheck_proxy_layer());
-
-        self.task_manager.spawn(|cancellation_token| async move {
-            let server = ServerBuilder::default()
-                .max_connections(max_connections)
-                .max_subscriptions_per_connection(max_subscriptions_per_connection)
-                .max_request_body_size(max_request_body_size)
-                .max_response_body_size(max_response_body_size)
-                .set_batch_request_config(BatchRequestConfig::Limit(batch_requests_limit))
-                .set_http_middleware(middleware)
-                .build([listen_address].as_ref())
-                .await;
-
-            match server {
-                Ok(server) => {
-                    let bound_address = match server.local_addr() {
-                        Ok(address) => address,
-                        Err(e) => {
-                            error!("{}", e);
-                            return;
-                        }
-                    };
-                    if let Some(channel) = channel {
-                        if let Err(e) = channel.send(bound_address) {
-                            error!("Could not send bound_address {}: {}", bound_address, e);
-                            return;
-                        }
-                    }
-                    info!("Starting RPC server at {} ", &bound_address);
-
-                    let _server_handle = server.start(methods);
-                    cancellation_token.cancelled().await;
-                }
-                Err(e) => {
-                    error!("Could not start RPC server: {}", e);
-                }
-            }
-        });
-        Ok(())
+        citrea_common::rpc::start_server(&self.rpc_config, methods, channel, &mut self.task_manager)
     }
 
     /// Runs the rollup.
     #[instrument(level = "trace", skip_all, err)]
     pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        check_l2_genesis_state_root(
+            &self.ledger_db,
+            &self.sequencer_client,
+            self.allow_genesis_state_root_mismatch,
+        )
+        .await?;
+
         let skip_submission_until_l1 = std::env::var("SKIP_PROOF_SUBMISSION_UNTIL_L1")
             .map_or(0u64, |v| v.parse().unwrap_or(0));
 
@@ -287,37 +286,42 @@ where
         let prover_config = self.prover_config.clone();
         let prover_service = self.prover_service.clone();
         let da_service = self.da_service.clone();
-        let sequencer_pub_key = self.sequencer_pub_key.clone();
-        let sequencer_da_pub_key = self.sequencer_da_pub_key.clone();
+        let sequencer_pub_keys = self.sequencer_pub_keys.clone();
+        let sequencer_da_pub_keys = self.sequencer_da_pub_keys.clone();
         let code_commitments_by_spec = self.code_commitments_by_spec.clone();
         let elfs_by_spec = self.elfs_by_spec.clone();
+        let simulator = self.simulator.clone();
         let l1_block_cache = self.l1_block_cache.clone();
-
-        self.task_manager.spawn(|cancellation_token| async move {
-            let l1_block_handler = L1BlockHandler::<
-                Vm,
-                Da,
-                Ps,
-                DB,
-                StfStateRoot<C, Da::Spec, RT>,
-                StfWitness<C, Da::Spec, RT>,
-                StfTransaction<C, Da::Spec, RT>,
-            >::new(
-                prover_config,
-                prover_service,
-                ledger_db,
-                da_service,
-                sequencer_pub_key,
-                sequencer_da_pub_key,
-                code_commitments_by_spec,
-                elfs_by_spec,
-                skip_submission_until_l1,
-                l1_block_cache.clone(),
-            );
-            l1_block_handler
-                .run(start_l1_height, cancellation_token)
-                .await
-        });
+        let l1_heights_awaiting_proof = self.l1_heights_awaiting_proof.clone();
+
+        self.task_manager
+            .spawn("l1_block_handler", |cancellation_token| async move {
+                let l1_block_handler = L1BlockHandler::<
+                    Vm,
+                    Da,
+                    Ps,
+                    DB,
+                    StfStateRoot<C, Da::Spec, RT>,
+                    StfWitness<C, Da::Spec, RT>,
+                    StfTransaction<C, Da::Spec, RT>,
+                >::new(
+                    prover_config,
+                    prover_service,
+                    ledger_db,
+                    da_service,
+                    sequencer_pub_keys,
+                    sequencer_da_pub_keys,
+                    code_commitments_by_spec,
+                    elfs_by_spec,
+                    simulator,
+                    skip_submission_until_l1,
+                    l1_block_cache.clone(),
+                    l1_heights_awaiting_proof,
+                );
+                l1_block_handler
+                    .run(start_l1_height, cancellation_token)
+                    .await
+            });
 
         // Create l2 sync worker task
         let (l2_tx, mut l2_rx) = mpsc::channel(1);
@@ -336,6 +340,7 @@ where
         interval.tick().await;
 
         let mut shutdown_signal = create_shutdown_signal().await;
+        let mut critical_task_failures = self.task_manager.critical_task_failures();
 
         loop {
             select! {
@@ -376,6 +381,11 @@ where
                         }
                     }
                 },
+                // A critical task died, the node can not keep running without it
+                failure = critical_task_failures.recv() => {
+                    self.shutdown().await?;
+                    return Err(failure.into());
+                },
                 Some(_) = shutdown_signal.recv() => return self.shutdown().await,
             }
         }
@@ -401,7 +411,7 @@ where
         )
         .await?;
 
-        info!(
+        debug!(
             "Running soft confirmation batch #{} with hash: 0x{} on DA block #{}",
             l2_height,
             hex::encode(soft_confirmation.hash),
@@ -424,7 +434,7 @@ where
         let current_spec = self.fork_manager.active_fork().spec_id;
         let soft_confirmation_result = self.stf.apply_soft_confirmation(
             current_spec,
-            self.sequencer_pub_key.as_slice(),
+            self.sequencer_pub_keys.key_at(l2_height),
             // TODO(https://github.com/Sovereign-Labs/sovereign-sdk/issues/1247): incorrect pre-state root in case of re-org
             &self.state_root,
             pre_state,
@@ -457,21 +467,22 @@ where
         self.storage_manager
             .save_change_set_l2(l2_height, soft_confirmation_result.change_set)?;
 
-        self.storage_manager.finalize_l2(l2_height)?;
-
         let receipt =
             soft_confirmation_to_receipt::<C, _, Da::Spec>(signed_soft_confirmation, current_spec);
 
-        self.ledger_db.commit_soft_confirmation(
-            next_state_root.as_ref(),
+        // Stage the ledger data before finalizing, so it is committed on restart
+        // if the node stops before the commit below
+        self.ledger_db.stage_soft_confirmations(vec![(
+            next_state_root.as_ref().to_vec(),
             receipt,
             Some(txs_bodies),
-        )?;
+        )])?;
 
-        self.ledger_db.extend_l2_range_of_l1_slot(
-            SlotNumber(current_l1_block.header().height()),
-            SoftConfirmationNumber(l2_height),
-        )?;
+        self.storage_manager.finalize_l2(l2_height)?;
+
+        // Also extends the L2 range of the L1 slot of the soft confirmation
+        self.ledger_db
+            .commit_staged_soft_confirmations(Some(SoftConfirmationNumber(l2_height)))?;
 
         // Register this new block with the fork manager to active
         // the new fork on the next block
@@ -483,9 +494,12 @@ where
         self.state_root = next_state_root;
         self.batch_hash = soft_confirmation.hash;
 
-        info!(
-            "New State Root after soft confirmation #{} is: {:?}",
-            l2_height, self.state_root
+        events::l2_block_applied(
+            l2_height,
+            soft_confirmation.hash,
+            current_l1_block.header().height(),
+            self.state_root.as_ref(),
+            start.elapsed(),
         );
 
         BATCH_PROVER_METRICS.current_l2_block.set(l2_height as f64);
@@ -525,13 +539,12 @@ async fn sync_l2(
                 .get_soft_confirmation_range(
                     U64::from(l2_height),
                     U64::from(l2_height + sync_blocks_count - 1),
+                    None,
                 )
                 .await;
 
             match soft_confirmations {
-                Ok(soft_confirmations) => {
-                    Ok(soft_confirmations.into_iter().flatten().collect::<Vec<_>>())
-                }
+                Ok(soft_confirmations) => Ok(soft_confirmations),
                 Err(e) => match e {
                     JsonrpseeError::Transport(e) => {
                         let error_msg = format!(
@@ -559,6 +572,9 @@ async fn sync_l2(
             }
         };
 
+        // Soft confirmations after a missing one are fetched again on the next request
+        let soft_confirmations = soft_confirmations_with_heights(l2_height, soft_confirmations);
+
         if soft_confirmations.is_empty() {
             debug!(
                 "Soft Confirmation: no batch at starting height {}, retrying...",
@@ -569,11 +585,6 @@ async fn sync_l2(
             continue;
         }
 
-        let soft_confirmations: Vec<(u64, SoftConfirmationResponse)> = (l2_height
-            ..l2_height + soft_confirmations.len() as u64)
-            .zip(soft_confirmations)
-            .collect();
-
         l2_height += soft_confirmations.len() as u64;
 
         if let Err(e) = sender.send(soft_confirmations).await {
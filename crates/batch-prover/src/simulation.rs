@@ -0,0 +1,85 @@
+//! Simulation of batch proofs, used by the batch prover in simulate mode.
+//!
+//! The circuit inputs are assembled exactly as for proving, but instead of being proven they
+//! are run natively. Whether each commitment range would prove is recorded in the ledger,
+//! nothing is submitted to DA.
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use sov_db::ledger_db::BatchProverLedgerOps;
+use sov_db::schema::types::StoredBatchProofSimulation;
+use sov_rollup_interface::da::SequencerCommitment;
+use tracing::{info, warn};
+
+use crate::circuit_input::SerializedCircuitInput;
+use crate::proving::save_commitments;
+
+/// Runs the batch proof circuit natively on a serialized circuit input.
+/// Returns the cycles the circuit took, if measured.
+/// The circuit panics on the inputs it rejects, panics are recorded as failures.
+pub type BatchProofSimulator = Arc<dyn Fn(Vec<u8>) -> anyhow::Result<Option<u64>> + Send + Sync>;
+
+pub(crate) async fn simulate_l1<DB, StateRoot>(
+    simulator: BatchProofSimulator,
+    ledger: DB,
+    l1_height: u64,
+    sequencer_commitments: Vec<SequencerCommitment>,
+    inputs: Vec<SerializedCircuitInput<StateRoot>>,
+) -> anyhow::Result<()>
+where
+    DB: BatchProverLedgerOps,
+{
+    for input in inputs {
+        let range = input.sequencer_commitments_range;
+        let simulation = simulate(simulator.clone(), input.input).await;
+        match &simulation.failure {
+            None => info!(
+                "Commitment range {:?} at l1 height {} would prove. Cycles: {:?}",
+                range, l1_height, simulation.cycles
+            ),
+            Some(failure) => warn!(
+                "Commitment range {:?} at l1 height {} would not prove: {}",
+                range, l1_height, failure
+            ),
+        }
+        ledger.put_batch_proof_simulation(l1_height, range, simulation)?;
+    }
+
+    save_commitments(ledger, &sequencer_commitments, l1_height);
+
+    Ok(())
+}
+
+async fn simulate(simulator: BatchProofSimulator, input: Vec<u8>) -> StoredBatchProofSimulation {
+    let result = tokio::task::spawn_blocking(move || {
+        catch_unwind(AssertUnwindSafe(|| simulator(input)))
+            .map_err(|panic| anyhow!("Circuit panicked: {}", panic_message(panic.as_ref())))
+            .and_then(|result| result)
+    })
+    .await
+    .map_err(|e| anyhow!("Simulation task failed: {}", e))
+    .and_then(|result| result);
+
+    match result {
+        Ok(cycles) => StoredBatchProofSimulation {
+            cycles,
+            failure: None,
+        },
+        Err(e) => StoredBatchProofSimulation {
+            cycles: None,
+            failure: Some(format!("{:#}", e)),
+        },
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
@@ -0,0 +1,303 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use citrea_batch_prover::circuit_input::{serialize_circuit_input, CircuitInputParts};
+use citrea_common::cache::L1BlockCache;
+use sov_db::ledger_db::{BatchProverLedgerOps, LedgerDB, SharedLedgerOps};
+use sov_db::rocks_db_config::RocksdbConfig;
+use sov_db::schema::types::SoftConfirmationNumber;
+use sov_mock_da::{MockAddress, MockBlock, MockBlockHeader, MockDaService, MockDaSpec, MockHash};
+use sov_rollup_interface::da::SequencerCommitment;
+use sov_rollup_interface::soft_confirmation::SignedSoftConfirmation;
+use sov_rollup_interface::stf::SoftConfirmationReceipt;
+use sov_rollup_interface::zk::BatchProofCircuitInput;
+use tokio::sync::Mutex;
+
+/// Tracks the live and the peak heap usage of the test binary
+struct CountingAllocator;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+impl CountingAllocator {
+    fn allocated(size: usize) {
+        let live = LIVE_BYTES.fetch_add(size, Ordering::SeqCst) + size;
+        PEAK_BYTES.fetch_max(live, Ordering::SeqCst);
+    }
+
+    fn deallocated(size: usize) {
+        LIVE_BYTES.fetch_sub(size, Ordering::SeqCst);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::deallocated(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size >= layout.size() {
+                Self::allocated(new_size - layout.size());
+            } else {
+                Self::deallocated(layout.size() - new_size);
+            }
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const WITNESS_SIZE: usize = 512;
+
+struct TestLedger {
+    _tmpdir: tempfile::TempDir,
+    ledger_db: LedgerDB,
+    da_service: Arc<MockDaService>,
+    l1_block_cache: Arc<Mutex<L1BlockCache<MockDaService>>>,
+}
+
+/// Commits the soft confirmations `1..=count` and their witnesses,
+/// every `blocks_per_da_slot` consecutive ones built on the same DA block.
+fn make_test_ledger(count: u64, blocks_per_da_slot: u64) -> TestLedger {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
+    let da_service = Arc::new(MockDaService::new(
+        MockAddress::from([0; 32]),
+        &tmpdir.path().join("da"),
+    ));
+
+    // The cache holds every DA block the soft confirmations were built on,
+    // the mock DA service is never queried
+    let mut l1_block_cache = L1BlockCache::new();
+    for da_slot_height in 1..=count.div_ceil(blocks_per_da_slot) {
+        l1_block_cache.put(
+            da_slot_height,
+            MockBlock {
+                header: MockBlockHeader::from_height(da_slot_height),
+                is_valid: true,
+                blobs: vec![],
+            },
+        );
+    }
+
+    for l2_height in 1..=count {
+        let da_slot_height = (l2_height - 1) / blocks_per_da_slot + 1;
+        let soft_confirmation_receipt = SoftConfirmationReceipt::<MockDaSpec> {
+            l2_height,
+            da_slot_height,
+            da_slot_hash: MockHash([da_slot_height as u8; 32]),
+            da_slot_txs_commitment: MockHash([da_slot_height as u8; 32]),
+            hash: block_hash(l2_height),
+            prev_hash: block_hash(l2_height - 1),
+            tx_hashes: vec![],
+            soft_confirmation_signature: vec![1; 64],
+            pub_key: vec![2; 33],
+            deposit_data: vec![],
+            l1_fee_rate: 1,
+            timestamp: l2_height,
+        };
+        ledger_db
+            .commit_soft_confirmation(&[1; 32], soft_confirmation_receipt, Some(vec![]))
+            .unwrap();
+        ledger_db
+            .set_l2_witness(
+                l2_height,
+                &vec![l2_height as u8; WITNESS_SIZE],
+                &vec![!(l2_height as u8); WITNESS_SIZE],
+            )
+            .unwrap();
+    }
+
+    TestLedger {
+        _tmpdir: tmpdir,
+        ledger_db,
+        da_service,
+        l1_block_cache: Arc::new(Mutex::new(l1_block_cache)),
+    }
+}
+
+fn block_hash(l2_height: u64) -> [u8; 32] {
+    let mut hash = [0; 32];
+    hash[..8].copy_from_slice(&l2_height.to_be_bytes());
+    hash
+}
+
+/// Commitments of `commitment_size` consecutive soft confirmations, covering `1..=count`
+fn make_commitments(count: u64, commitment_size: u64) -> Vec<SequencerCommitment> {
+    (1..=count)
+        .step_by(commitment_size as usize)
+        .map(|l2_start_block_number| SequencerCommitment {
+            merkle_root: [0; 32],
+            l2_start_block_number,
+            l2_end_block_number: count.min(l2_start_block_number + commitment_size - 1),
+        })
+        .collect()
+}
+
+fn make_parts(
+    da_block_header_of_commitments: MockBlockHeader,
+    commitments: usize,
+) -> CircuitInputParts<[u8; 32], MockDaSpec> {
+    CircuitInputParts {
+        initial_state_root: [1; 32],
+        final_state_root: [2; 32],
+        prev_soft_confirmation_hash: [0; 32],
+        da_data: vec![],
+        da_block_header_of_commitments,
+        inclusion_proof: [0; 32],
+        completeness_proof: (),
+        preproven_commitments: vec![],
+        sequencer_public_key: vec![3; 33],
+        sequencer_da_public_key: vec![4; 33],
+        sequencer_commitments_range: (0, commitments as u32 - 1),
+    }
+}
+
+#[tokio::test]
+async fn test_serialized_circuit_input_matches_borsh_layout() {
+    let test_ledger = make_test_ledger(20, 3);
+    let commitments = make_commitments(20, 8);
+    let da_block_header_of_commitments = MockBlockHeader::from_height(100);
+
+    let serialized = serialize_circuit_input::<_, _, _, Vec<u8>, ()>(
+        make_parts(da_block_header_of_commitments.clone(), commitments.len()),
+        &commitments,
+        &test_ledger.da_service,
+        &test_ledger.ledger_db,
+        &test_ledger.l1_block_cache,
+    )
+    .await
+    .unwrap();
+    assert_eq!(serialized.initial_state_root, [1; 32]);
+    assert_eq!(serialized.sequencer_commitments_range, (0, 2));
+
+    let mut soft_confirmations = VecDeque::new();
+    let mut state_transition_witnesses = VecDeque::new();
+    let mut da_block_headers_of_soft_confirmations = VecDeque::new();
+    for commitment in &commitments {
+        let stored_soft_confirmations = test_ledger
+            .ledger_db
+            .get_soft_confirmation_range(
+                &(SoftConfirmationNumber(commitment.l2_start_block_number)
+                    ..=SoftConfirmationNumber(commitment.l2_end_block_number)),
+            )
+            .unwrap();
+
+        let mut da_block_headers: Vec<MockBlockHeader> = vec![];
+        let mut l1_block_cache = test_ledger.l1_block_cache.lock().await;
+        for soft_confirmation in &stored_soft_confirmations {
+            if da_block_headers.last().map(|header| header.height)
+                != Some(soft_confirmation.da_slot_height)
+            {
+                let block = l1_block_cache
+                    .get(&soft_confirmation.da_slot_height)
+                    .unwrap();
+                da_block_headers.push(block.header.clone());
+            }
+        }
+        da_block_headers_of_soft_confirmations.push_back(da_block_headers);
+
+        soft_confirmations.push_back(
+            stored_soft_confirmations
+                .into_iter()
+                .map(|soft_confirmation| soft_confirmation.try_into().unwrap())
+                .collect::<Vec<SignedSoftConfirmation<()>>>(),
+        );
+        state_transition_witnesses.push_back(
+            (commitment.l2_start_block_number..=commitment.l2_end_block_number)
+                .map(|l2_height| {
+                    test_ledger
+                        .ledger_db
+                        .get_l2_witness::<Vec<u8>>(l2_height)
+                        .unwrap()
+                        .unwrap()
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+    // Commitments do not share DA block headers, even if their soft confirmations were built on the same one
+    assert_eq!(
+        da_block_headers_of_soft_confirmations
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<_>>(),
+        vec![3, 4, 2]
+    );
+
+    let parts = make_parts(da_block_header_of_commitments, commitments.len());
+    let input: BatchProofCircuitInput<[u8; 32], Vec<u8>, MockDaSpec, ()> = BatchProofCircuitInput {
+        initial_state_root: parts.initial_state_root,
+        final_state_root: parts.final_state_root,
+        prev_soft_confirmation_hash: parts.prev_soft_confirmation_hash,
+        da_data: parts.da_data,
+        da_block_header_of_commitments: parts.da_block_header_of_commitments,
+        inclusion_proof: parts.inclusion_proof,
+        completeness_proof: parts.completeness_proof,
+        preproven_commitments: parts.preproven_commitments,
+        soft_confirmations,
+        state_transition_witnesses,
+        da_block_headers_of_soft_confirmations,
+        sequencer_public_key: parts.sequencer_public_key,
+        sequencer_da_public_key: parts.sequencer_da_public_key,
+        sequencer_commitments_range: parts.sequencer_commitments_range,
+    };
+    assert_eq!(serialized.input, borsh::to_vec(&input).unwrap());
+}
+
+#[tokio::test]
+async fn test_circuit_input_serialization_memory_is_bounded() {
+    const SOFT_CONFIRMATIONS: u64 = 50_000;
+    // Holding the whole range in memory besides its serialization would take way more,
+    // the witnesses alone are 50 MB
+    const MAX_EXTRA_BYTES: usize = 16 * 1024 * 1024;
+
+    let test_ledger = make_test_ledger(SOFT_CONFIRMATIONS, 10_000);
+    let commitments = make_commitments(SOFT_CONFIRMATIONS, 10_000);
+    let parts = make_parts(MockBlockHeader::from_height(100), commitments.len());
+
+    let baseline = LIVE_BYTES.load(Ordering::SeqCst);
+    PEAK_BYTES.store(baseline, Ordering::SeqCst);
+
+    let serialized = serialize_circuit_input::<_, _, _, Vec<u8>, ()>(
+        parts,
+        &commitments,
+        &test_ledger.da_service,
+        &test_ledger.ledger_db,
+        &test_ledger.l1_block_cache,
+    )
+    .await
+    .unwrap();
+
+    let peak = PEAK_BYTES.load(Ordering::SeqCst) - baseline;
+    assert!(serialized.input.len() > 2 * WITNESS_SIZE * SOFT_CONFIRMATIONS as usize);
+    assert!(
+        peak <= serialized.input.capacity() + MAX_EXTRA_BYTES,
+        "Peak heap usage {} is above the serialized input capacity {} by more than {}",
+        peak,
+        serialized.input.capacity(),
+        MAX_EXTRA_BYTES
+    );
+}
@@ -3,14 +3,18 @@
 
 use core::result::Result::Ok;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
 use bitcoin::{Amount, Network, Sequence, Txid};
 use bitcoincore_rpc::json::{
     BumpFeeResult, CreateRawTransactionInput, WalletCreateFundedPsbtOptions,
 };
 use bitcoincore_rpc::{Client, RpcApi};
+use serde::Deserialize;
 use tracing::{debug, instrument, trace, warn};
 
 use crate::monitoring::{MonitoredTx, MonitoredTxKind};
@@ -19,6 +23,11 @@ use crate::spec::utxo::UTXO;
 const MEMPOOL_SPACE_URL: &str = "https://mempool.space/";
 const MEMPOOL_SPACE_RECOMMENDED_FEE_ENDPOINT: &str = "api/v1/fees/recommended";
 
+// estimates are reused for this long before querying the node again
+const FEE_RATE_CACHE_TTL: Duration = Duration::from_secs(30);
+// number of recent blocks whose fee rate percentiles are taken into account
+const FEE_RATE_BLOCK_WINDOW: u64 = 6;
+
 pub type Psbt = String;
 
 pub enum BumpFeeMethod {
@@ -26,47 +35,228 @@ pub enum BumpFeeMethod {
     Rbf,
 }
 
+/// How fast a DA tx is expected to confirm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeePriority {
+    Low,
+    Medium,
+    High,
+}
+
+impl FeePriority {
+    /// Confirmation target in blocks passed to `estimatesmartfee`
+    fn conf_target(self) -> u16 {
+        match self {
+            FeePriority::Low => 6,
+            FeePriority::Medium => 3,
+            FeePriority::High => 1,
+        }
+    }
+
+    /// Index into the 10th, 25th, 50th, 75th and 90th `getblockstats` fee rate percentiles
+    fn percentile_index(self) -> usize {
+        match self {
+            FeePriority::Low => 1,
+            FeePriority::Medium => 2,
+            FeePriority::High => 3,
+        }
+    }
+}
+
+/// Bounds applied to estimated fee rates, in sat/vB
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeRateLimits {
+    pub min_fee_rate: u64,
+    pub max_fee_rate: u64,
+    /// Used as is for every priority instead of estimating
+    pub fee_rate_override: Option<u64>,
+}
+
+/// Node queries the fee rate estimation is based on, all rates in sat/vB
+#[async_trait]
+pub(crate) trait FeeRateSource: Debug + Send + Sync {
+    /// `estimatesmartfee` for `conf_target` blocks
+    async fn smart_fee_rate(&self, conf_target: u16) -> Result<u64>;
+
+    /// `getblockstats` fee rate percentiles of the last `blocks` blocks
+    async fn block_fee_rate_percentiles(&self, blocks: u64) -> Result<Vec<[u64; 5]>>;
+}
+
+#[derive(Deserialize)]
+struct BlockFeeRateStats {
+    feerate_percentiles: [u64; 5],
+}
+
+#[async_trait]
+impl FeeRateSource for Client {
+    async fn smart_fee_rate(&self, conf_target: u16) -> Result<u64> {
+        let res = self.estimate_smart_fee(conf_target, None).await?;
+        match res.fee_rate {
+            // sat/kvB to sat/vB
+            Some(rate) => Ok(rate.to_sat().div_ceil(1000)),
+            None => bail!(
+                "estimatesmartfee returned no fee rate: {:?}",
+                res.errors.unwrap_or_default()
+            ),
+        }
+    }
+
+    async fn block_fee_rate_percentiles(&self, blocks: u64) -> Result<Vec<[u64; 5]>> {
+        let tip = self.get_block_count().await?;
+        let mut percentiles = Vec::with_capacity(blocks as usize);
+        for height in tip.saturating_sub(blocks.saturating_sub(1))..=tip {
+            let stats: BlockFeeRateStats = self
+                .call(
+                    "getblockstats",
+                    &[height.into(), serde_json::json!(["feerate_percentiles"])],
+                )
+                .await?;
+            percentiles.push(stats.feerate_percentiles);
+        }
+        Ok(percentiles)
+    }
+}
+
 #[derive(Debug)]
-pub struct FeeService {
-    client: Arc<Client>,
+pub(crate) struct FeeEstimator {
+    source: Arc<dyn FeeRateSource>,
     network: Network,
+    limits: FeeRateLimits,
+    cache: Mutex<HashMap<FeePriority, (Instant, u64)>>,
 }
 
-impl FeeService {
-    pub fn new(client: Arc<Client>, network: bitcoin::Network) -> Self {
-        Self { client, network }
+impl FeeEstimator {
+    pub(crate) fn new(
+        source: Arc<dyn FeeRateSource>,
+        network: Network,
+        limits: FeeRateLimits,
+    ) -> Self {
+        Self {
+            source,
+            network,
+            limits,
+            cache: Mutex::new(HashMap::new()),
+        }
     }
 
-    #[instrument(level = "trace", skip_all, ret)]
-    pub async fn get_fee_rate(&self) -> Result<u64> {
-        match self.get_fee_rate_as_sat_vb().await {
-            Ok(fee) => Ok(fee),
-            Err(e) => {
-                if self.network == bitcoin::Network::Regtest
-                    || self.network == bitcoin::Network::Testnet
-                {
-                    Ok(1)
-                } else {
-                    Err(e)
-                }
+    /// Returns the fee rate in sat/vB for `priority`, clamped to the configured limits.
+    /// Takes the higher of the node's smart fee estimate and the recent block percentile,
+    /// falling back to the last estimate or, outside mainnet, to the min fee rate.
+    #[instrument(level = "trace", skip(self), ret)]
+    pub(crate) async fn get_fee_rate(&self, priority: FeePriority) -> Result<u64> {
+        if let Some(fee_rate) = self.limits.fee_rate_override {
+            return Ok(fee_rate);
+        }
+
+        let cached = self.cache.lock().unwrap().get(&priority).copied();
+        if let Some((estimated_at, fee_rate)) = cached {
+            if estimated_at.elapsed() < FEE_RATE_CACHE_TTL {
+                return Ok(fee_rate);
             }
         }
+
+        let estimate = match self.estimate(priority).await {
+            Some(fee_rate) => fee_rate,
+            None => match cached {
+                Some((_, fee_rate)) => {
+                    warn!(
+                        ?priority,
+                        fee_rate, "Fee estimation failed, reusing last estimate"
+                    );
+                    fee_rate
+                }
+                None if self.network != Network::Bitcoin => self.limits.min_fee_rate,
+                None => bail!("Failed to estimate fee rate for {priority:?} priority"),
+            },
+        };
+        let fee_rate = estimate.clamp(
+            self.limits.min_fee_rate,
+            self.limits.max_fee_rate.max(self.limits.min_fee_rate),
+        );
+
+        debug!(?priority, estimate, fee_rate, "Estimated fee rate");
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(priority, (Instant::now(), fee_rate));
+        Ok(fee_rate)
     }
 
-    #[instrument(level = "trace", skip_all, ret)]
-    pub async fn get_fee_rate_as_sat_vb(&self) -> Result<u64> {
-        // If network is regtest or signet, mempool space is not available
-        let smart_fee = match get_fee_rate_from_mempool_space(self.network).await {
-            Ok(fee_rate) => fee_rate,
+    async fn estimate(&self, priority: FeePriority) -> Option<u64> {
+        let smart_fee_rate = match self.source.smart_fee_rate(priority.conf_target()).await {
+            Ok(fee_rate) => Some(fee_rate),
             Err(e) => {
-                tracing::error!(?e, "Failed to get fee rate from mempool.space");
-                self.client.estimate_smart_fee(1, None).await?.fee_rate
+                debug!(?e, "Failed to get smart fee estimate");
+                None
             }
         };
-        let sat_vkb = smart_fee.map_or(1000, |rate| rate.to_sat());
 
-        tracing::debug!("Fee rate: {} sat/vb", sat_vkb / 1000);
-        Ok(sat_vkb / 1000)
+        let block_fee_rate = match self
+            .source
+            .block_fee_rate_percentiles(FEE_RATE_BLOCK_WINDOW)
+            .await
+        {
+            Ok(percentiles) => median_fee_rate(&percentiles, priority.percentile_index()),
+            Err(e) => {
+                debug!(?e, "Failed to get recent block fee rates");
+                None
+            }
+        };
+
+        // mempool.space only provides the next block fee rate
+        let mempool_space_fee_rate = if priority == FeePriority::High {
+            match get_fee_rate_from_mempool_space(self.network).await {
+                Ok(fee_rate) => fee_rate.map(|rate| rate.to_sat().div_ceil(1000)),
+                Err(e) => {
+                    debug!(?e, "Failed to get fee rate from mempool.space");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        [smart_fee_rate, block_fee_rate, mempool_space_fee_rate]
+            .into_iter()
+            .flatten()
+            .max()
+    }
+}
+
+/// Median of the given percentile over blocks that had any fee paying txs
+fn median_fee_rate(percentiles: &[[u64; 5]], index: usize) -> Option<u64> {
+    let mut fee_rates: Vec<u64> = percentiles
+        .iter()
+        .map(|block| block[index])
+        .filter(|fee_rate| *fee_rate > 0)
+        .collect();
+    if fee_rates.is_empty() {
+        return None;
+    }
+    fee_rates.sort_unstable();
+    Some(fee_rates[fee_rates.len() / 2])
+}
+
+#[derive(Debug)]
+pub struct FeeService {
+    client: Arc<Client>,
+    network: Network,
+    estimator: FeeEstimator,
+}
+
+impl FeeService {
+    pub fn new(client: Arc<Client>, network: bitcoin::Network, limits: FeeRateLimits) -> Self {
+        let estimator = FeeEstimator::new(client.clone(), network, limits);
+        Self {
+            client,
+            network,
+            estimator,
+        }
+    }
+
+    /// Returns the fee rate in sat/vB to send a DA tx with `priority`.
+    pub async fn get_fee_rate(&self, priority: FeePriority) -> Result<u64> {
+        self.estimator.get_fee_rate(priority).await
     }
 
     /// Bump TX fee via cpfp.
@@ -143,6 +333,15 @@ impl FeeService {
     }
 }
 
+/// Returns the fee rate in sat/vB to rebuild a stuck tx with.
+/// It is at least 50% above the current one, follows the network estimate if that is higher
+/// and never exceeds `max_fee_rate`.
+pub(crate) fn bumped_fee_rate(current: u64, estimate: u64, max_fee_rate: u64) -> u64 {
+    (current + current.div_ceil(2))
+        .max(estimate)
+        .min(max_fee_rate)
+}
+
 pub(crate) async fn get_fee_rate_from_mempool_space(
     network: bitcoin::Network,
 ) -> Result<Option<Amount>> {
@@ -175,8 +374,196 @@ pub(crate) async fn get_fee_rate_from_mempool_space(
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use bitcoin::Network;
+
+    use super::{
+        bumped_fee_rate, get_fee_rate_from_mempool_space, FeeEstimator, FeePriority, FeeRateLimits,
+        FeeRateSource,
+    };
+
+    const LIMITS: FeeRateLimits = FeeRateLimits {
+        min_fee_rate: 1,
+        max_fee_rate: 100,
+        fee_rate_override: None,
+    };
+
+    /// Replays canned `estimatesmartfee` and `getblockstats` responses
+    #[derive(Debug, Default)]
+    struct MockFeeRateSource {
+        // None mimics regtest, where estimatesmartfee has insufficient data
+        smart_fee_rate: Option<u64>,
+        percentiles: Vec<[u64; 5]>,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl FeeRateSource for MockFeeRateSource {
+        async fn smart_fee_rate(&self, conf_target: u16) -> Result<u64> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.smart_fee_rate
+                .map(|fee_rate| fee_rate / conf_target as u64)
+                .ok_or_else(|| anyhow!("Insufficient data or no feerate found"))
+        }
+
+        async fn block_fee_rate_percentiles(&self, blocks: u64) -> Result<Vec<[u64; 5]>> {
+            Ok(self
+                .percentiles
+                .iter()
+                .take(blocks as usize)
+                .copied()
+                .collect())
+        }
+    }
+
+    fn mock_estimator(
+        source: MockFeeRateSource,
+        network: Network,
+        limits: FeeRateLimits,
+    ) -> (Arc<MockFeeRateSource>, FeeEstimator) {
+        let source = Arc::new(source);
+        let estimator = FeeEstimator::new(source.clone(), network, limits);
+        (source, estimator)
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_regtest_fallback() {
+        // estimatesmartfee errors and blocks are empty
+        let (_, estimator) = mock_estimator(
+            MockFeeRateSource {
+                percentiles: vec![[0; 5]; 3],
+                ..Default::default()
+            },
+            Network::Regtest,
+            FeeRateLimits {
+                min_fee_rate: 2,
+                ..LIMITS
+            },
+        );
+        assert_eq!(estimator.get_fee_rate(FeePriority::High).await.unwrap(), 2);
+        assert_eq!(estimator.get_fee_rate(FeePriority::Low).await.unwrap(), 2);
+
+        // estimatesmartfee errors but recent blocks paid fees
+        let (_, estimator) = mock_estimator(
+            MockFeeRateSource {
+                percentiles: vec![[1, 2, 5, 8, 10], [0; 5], [3, 4, 7, 9, 20], [1, 3, 6, 8, 9]],
+                ..Default::default()
+            },
+            Network::Regtest,
+            LIMITS,
+        );
+        assert_eq!(estimator.get_fee_rate(FeePriority::Low).await.unwrap(), 3);
+        assert_eq!(
+            estimator.get_fee_rate(FeePriority::Medium).await.unwrap(),
+            6
+        );
+        assert_eq!(estimator.get_fee_rate(FeePriority::High).await.unwrap(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_mainnet_without_estimate() {
+        let (_, estimator) = mock_estimator(MockFeeRateSource::default(), Network::Bitcoin, LIMITS);
+        assert!(estimator.get_fee_rate(FeePriority::Medium).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_takes_higher_estimate() {
+        let (_, estimator) = mock_estimator(
+            MockFeeRateSource {
+                smart_fee_rate: Some(30),
+                percentiles: vec![[5, 10, 12, 20, 40]; 6],
+                ..Default::default()
+            },
+            Network::Regtest,
+            LIMITS,
+        );
+        // smart fee of 30 sat/vB for next block
+        assert_eq!(estimator.get_fee_rate(FeePriority::High).await.unwrap(), 30);
+        // smart fee of 10 sat/vB for 3 blocks, median is 12
+        assert_eq!(
+            estimator.get_fee_rate(FeePriority::Medium).await.unwrap(),
+            12
+        );
+        // smart fee of 5 sat/vB for 6 blocks, 25th percentile is 10
+        assert_eq!(estimator.get_fee_rate(FeePriority::Low).await.unwrap(), 10);
+    }
 
-    use super::get_fee_rate_from_mempool_space;
+    #[tokio::test]
+    async fn test_fee_rate_limits_and_override() {
+        let mock_source = || MockFeeRateSource {
+            smart_fee_rate: Some(500),
+            percentiles: vec![[1; 5]; 6],
+            ..Default::default()
+        };
+
+        let (_, estimator) = mock_estimator(mock_source(), Network::Regtest, LIMITS);
+        assert_eq!(
+            estimator.get_fee_rate(FeePriority::High).await.unwrap(),
+            100
+        );
+        let (_, estimator) = mock_estimator(
+            mock_source(),
+            Network::Regtest,
+            FeeRateLimits {
+                min_fee_rate: 120,
+                max_fee_rate: 200,
+                ..LIMITS
+            },
+        );
+        // 500 / 6 is below the min fee rate
+        assert_eq!(estimator.get_fee_rate(FeePriority::Low).await.unwrap(), 120);
+
+        let (source, estimator) = mock_estimator(
+            mock_source(),
+            Network::Regtest,
+            FeeRateLimits {
+                fee_rate_override: Some(7),
+                ..LIMITS
+            },
+        );
+        assert_eq!(estimator.get_fee_rate(FeePriority::High).await.unwrap(), 7);
+        assert_eq!(source.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_is_cached() {
+        let (source, estimator) = mock_estimator(
+            MockFeeRateSource {
+                smart_fee_rate: Some(12),
+                ..Default::default()
+            },
+            Network::Regtest,
+            LIMITS,
+        );
+        assert_eq!(
+            estimator.get_fee_rate(FeePriority::Medium).await.unwrap(),
+            4
+        );
+        assert_eq!(
+            estimator.get_fee_rate(FeePriority::Medium).await.unwrap(),
+            4
+        );
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+
+        // each priority is cached separately
+        assert_eq!(estimator.get_fee_rate(FeePriority::Low).await.unwrap(), 2);
+        assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_bumped_fee_rate() {
+        assert_eq!(bumped_fee_rate(1, 1, 100), 2);
+        assert_eq!(bumped_fee_rate(10, 1, 100), 15);
+        // Network estimate is higher than the bump
+        assert_eq!(bumped_fee_rate(10, 40, 100), 40);
+        // Bounded by max fee rate
+        assert_eq!(bumped_fee_rate(80, 1, 100), 100);
+        assert_eq!(bumped_fee_rate(100, 150, 100), 100);
+    }
 
     #[tokio::test]
     async fn test_mempool_space_fee_rate() {
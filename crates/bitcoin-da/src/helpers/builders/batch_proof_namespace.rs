@@ -213,7 +213,7 @@ pub fn create_batchproof_type_0(
                     commit_tx_address
                 );
 
-                histogram!("mine_da_transaction").record(
+                histogram!("citrea_mine_da_transaction").record(
                     Instant::now()
                         .saturating_duration_since(start)
                         .as_secs_f64(),
@@ -11,10 +11,11 @@ use bitcoin::hashes::Hash;
 use bitcoin::key::{TapTweak, TweakedPublicKey, UntweakedKeypair};
 use bitcoin::opcodes::all::{OP_CHECKSIGVERIFY, OP_NIP};
 use bitcoin::script::PushBytesBuf;
-use bitcoin::secp256k1::{Secp256k1, SecretKey, XOnlyPublicKey};
+use bitcoin::secp256k1::{All, Secp256k1, SecretKey, XOnlyPublicKey};
 use bitcoin::{Address, Amount, Network, Transaction};
 use serde::Serialize;
-use sov_rollup_interface::da::DaDataLightClient;
+use sov_rollup_interface::da::{DaDataLightClient, VersionedDaData};
+use sov_rollup_interface::spec::SpecId;
 use tracing::{instrument, trace, warn};
 
 use super::{
@@ -26,11 +27,11 @@ use crate::spec::utxo::UTXO;
 use crate::{REVEAL_OUTPUT_AMOUNT, REVEAL_OUTPUT_THRESHOLD};
 
 pub(crate) enum RawLightClientData {
-    /// compress(borsh(DaDataLightClient::Complete(Proof)))
+    /// compress(versioned(DaDataLightClient::Complete(Proof)))
     Complete(Vec<u8>),
     /// let compressed = compress(borsh(Proof))
-    /// let chunks = compressed.chunks(MAX_TXBODY_SIZE)
-    /// [borsh(DaDataLightClient::Chunk(chunk)) for chunk in chunks]
+    /// let chunks = compressed.chunks(proof_chunk_threshold)
+    /// [versioned(DaDataLightClient::Chunk(chunk)) for chunk in chunks]
     Chunks(Vec<Vec<u8>>),
 }
 
@@ -104,6 +105,7 @@ pub fn create_zkproof_transactions(
     reveal_fee_rate: u64,
     network: Network,
     reveal_tx_prefix: Vec<u8>,
+    spec_id: SpecId,
 ) -> Result<LightClientTxs, anyhow::Error> {
     match data {
         RawLightClientData::Complete(body) => create_inscription_type_0(
@@ -127,6 +129,7 @@ pub fn create_zkproof_transactions(
             reveal_fee_rate,
             network,
             &reveal_tx_prefix,
+            spec_id,
         ),
     }
 }
@@ -310,17 +313,27 @@ pub fn create_inscription_type_1(
     reveal_fee_rate: u64,
     network: Network,
     reveal_tx_prefix: &[u8],
+    spec_id: SpecId,
 ) -> Result<LightClientTxs, anyhow::Error> {
     // Create reveal key
     let secp256k1 = Secp256k1::new();
     let key_pair = UntweakedKeypair::new(&secp256k1, &mut rand::thread_rng());
     let (public_key, _parity) = XOnlyPublicKey::from_keypair(&key_pair);
 
+    // After Fork1 chunks are mined with the light client prefix and referenced by wtxid,
+    // so that they are a part of the completeness proof of the block.
+    // Up to Fork1 they are referenced by txid, as the light client proof guests of those forks read them
+    let chunks_in_block = spec_id > SpecId::Fork1;
+
     let mut commit_chunks: Vec<Transaction> = vec![];
     let mut reveal_chunks: Vec<Transaction> = vec![];
 
     for body in chunks {
-        let kind = TransactionKindLightClient::ChunkedPart;
+        let kind = if chunks_in_block {
+            TransactionKindLightClient::ChunkedPartV2
+        } else {
+            TransactionKindLightClient::ChunkedPart
+        };
         let kind_bytes = kind.to_bytes();
 
         // start creating inscription content
@@ -337,35 +350,36 @@ pub fn create_inscription_type_1(
             );
         }
         // push end if
-        let reveal_script = reveal_script_builder.push_opcode(OP_ENDIF).into_script();
-
-        let (control_block, merkle_root, tapscript_hash) =
-            build_taproot(&reveal_script, public_key, &secp256k1);
-
-        // create commit tx address
-        let commit_tx_address = Address::p2tr(&secp256k1, public_key, merkle_root, network);
-
-        let reveal_value = REVEAL_OUTPUT_AMOUNT;
-        let fee = get_size_reveal(
-            change_address.script_pubkey(),
-            reveal_value,
-            &reveal_script,
-            &control_block,
-        ) as u64
-            * reveal_fee_rate;
-        let reveal_input_value = fee + reveal_value;
-
-        // build commit tx
-        let (unsigned_commit_tx, leftover_utxos) = build_commit_transaction(
-            prev_utxo.clone(),
-            utxos,
-            commit_tx_address.clone(),
-            change_address.clone(),
-            reveal_input_value,
-            commit_fee_rate,
-        )?;
-
-        let output_to_reveal = unsigned_commit_tx.output[0].clone();
+        reveal_script_builder = reveal_script_builder.push_opcode(OP_ENDIF);
+
+        let (unsigned_commit_tx, reveal_tx, leftover_utxos) = if chunks_in_block {
+            build_chunk_txs_with_prefix(
+                reveal_script_builder,
+                public_key,
+                &key_pair,
+                &secp256k1,
+                prev_utxo.clone(),
+                utxos,
+                change_address.clone(),
+                commit_fee_rate,
+                reveal_fee_rate,
+                network,
+                reveal_tx_prefix,
+            )?
+        } else {
+            build_chunk_txs(
+                reveal_script_builder,
+                public_key,
+                &key_pair,
+                &secp256k1,
+                prev_utxo.clone(),
+                utxos,
+                change_address.clone(),
+                commit_fee_rate,
+                reveal_fee_rate,
+                network,
+            )?
+        };
 
         // If commit
         let commit_change = if unsigned_commit_tx.output.len() > 1 {
@@ -373,7 +387,7 @@ pub fn create_inscription_type_1(
                 tx_id: unsigned_commit_tx.compute_txid(),
                 vout: 1,
                 address: None,
-                script_pubkey: unsigned_commit_tx.output[0].script_pubkey.to_hex_string(),
+                script_pubkey: unsigned_commit_tx.output[1].script_pubkey.to_hex_string(),
                 amount: unsigned_commit_tx.output[1].value.to_sat(),
                 confirmations: 0,
                 spendable: true,
@@ -383,38 +397,6 @@ pub fn create_inscription_type_1(
             None
         };
 
-        let mut reveal_tx = build_reveal_transaction(
-            output_to_reveal.clone(),
-            unsigned_commit_tx.compute_txid(),
-            0,
-            change_address.clone(),
-            reveal_value,
-            reveal_fee_rate,
-            &reveal_script,
-            &control_block,
-        )?;
-
-        build_witness(
-            &unsigned_commit_tx,
-            &mut reveal_tx,
-            tapscript_hash,
-            reveal_script,
-            control_block,
-            &key_pair,
-            &secp256k1,
-        );
-
-        // check if inscription locked to the correct address
-        let recovery_key_pair = key_pair.tap_tweak(&secp256k1, merkle_root);
-        let (x_only_pub_key, _parity) = recovery_key_pair.to_inner().x_only_public_key();
-        assert_eq!(
-            Address::p2tr_tweaked(
-                TweakedPublicKey::dangerous_assume_tweaked(x_only_pub_key),
-                network,
-            ),
-            commit_tx_address
-        );
-
         // set prev utxo to last reveal tx[0] to chain txs in order
         prev_utxo = Some(UTXO {
             tx_id: reveal_tx.compute_txid(),
@@ -437,20 +419,28 @@ pub fn create_inscription_type_1(
         }
     }
 
-    let reveal_tx_ids: Vec<_> = reveal_chunks
-        .iter()
-        .map(|tx| tx.compute_txid().to_byte_array())
-        .collect();
-
-    let aggregate = DaDataLightClient::Aggregate(reveal_tx_ids);
-
-    // To sign the list of tx ids we assume they form a contigious list of bytes
-    let reveal_body: Vec<u8> =
-        borsh::to_vec(&aggregate).expect("Aggregate serialize must not fail");
+    let (reveal_chunk_ids, kind): (Vec<_>, _) = if chunks_in_block {
+        // Chunks are referenced by wtxid so that they can be looked up in the completeness proof
+        let reveal_wtxids = reveal_chunks
+            .iter()
+            .map(|tx| tx.compute_wtxid().to_byte_array())
+            .collect();
+        (reveal_wtxids, TransactionKindLightClient::ChunkedV2)
+    } else {
+        let reveal_tx_ids = reveal_chunks
+            .iter()
+            .map(|tx| tx.compute_txid().to_byte_array())
+            .collect();
+        (reveal_tx_ids, TransactionKindLightClient::Chunked)
+    };
+
+    let aggregate = DaDataLightClient::Aggregate(reveal_chunk_ids);
+
+    // To sign the list of chunk ids we assume they form a contigious list of bytes
+    let reveal_body: Vec<u8> = aggregate.encode_versioned(spec_id);
     // sign the body for authentication of the sequencer
     let (signature, signer_public_key) = sign_blob_with_private_key(&reveal_body, da_private_key);
 
-    let kind = TransactionKindLightClient::Chunked;
     let kind_bytes = kind.to_bytes();
 
     // start creating inscription content
@@ -591,3 +581,204 @@ pub fn create_inscription_type_1(
         nonce += 1;
     }
 }
+
+// Builds the commit and reveal txs of a chunk of Type 1, which are referenced by txid
+#[allow(clippy::too_many_arguments)]
+fn build_chunk_txs(
+    reveal_script_builder: script::Builder,
+    public_key: XOnlyPublicKey,
+    key_pair: &UntweakedKeypair,
+    secp256k1: &Secp256k1<All>,
+    prev_utxo: Option<UTXO>,
+    utxos: Vec<UTXO>,
+    change_address: Address,
+    commit_fee_rate: u64,
+    reveal_fee_rate: u64,
+    network: Network,
+) -> Result<(Transaction, Transaction, Vec<UTXO>), anyhow::Error> {
+    let reveal_script = reveal_script_builder.into_script();
+
+    let (control_block, merkle_root, tapscript_hash) =
+        build_taproot(&reveal_script, public_key, secp256k1);
+
+    // create commit tx address
+    let commit_tx_address = Address::p2tr(secp256k1, public_key, merkle_root, network);
+
+    let reveal_value = REVEAL_OUTPUT_AMOUNT;
+    let fee = get_size_reveal(
+        change_address.script_pubkey(),
+        reveal_value,
+        &reveal_script,
+        &control_block,
+    ) as u64
+        * reveal_fee_rate;
+    let reveal_input_value = fee + reveal_value;
+
+    // build commit tx
+    let (unsigned_commit_tx, leftover_utxos) = build_commit_transaction(
+        prev_utxo,
+        utxos,
+        commit_tx_address.clone(),
+        change_address.clone(),
+        reveal_input_value,
+        commit_fee_rate,
+    )?;
+
+    let output_to_reveal = unsigned_commit_tx.output[0].clone();
+
+    let mut reveal_tx = build_reveal_transaction(
+        output_to_reveal,
+        unsigned_commit_tx.compute_txid(),
+        0,
+        change_address,
+        reveal_value,
+        reveal_fee_rate,
+        &reveal_script,
+        &control_block,
+    )?;
+
+    build_witness(
+        &unsigned_commit_tx,
+        &mut reveal_tx,
+        tapscript_hash,
+        reveal_script,
+        control_block,
+        key_pair,
+        secp256k1,
+    );
+
+    // check if inscription locked to the correct address
+    let recovery_key_pair = key_pair.tap_tweak(secp256k1, merkle_root);
+    let (x_only_pub_key, _parity) = recovery_key_pair.to_inner().x_only_public_key();
+    assert_eq!(
+        Address::p2tr_tweaked(
+            TweakedPublicKey::dangerous_assume_tweaked(x_only_pub_key),
+            network,
+        ),
+        commit_tx_address
+    );
+
+    Ok((unsigned_commit_tx, reveal_tx, leftover_utxos))
+}
+
+// Builds the commit and reveal txs of a chunk of Type 4, whose reveal wtxid starts with the given prefix
+#[allow(clippy::too_many_arguments)]
+fn build_chunk_txs_with_prefix(
+    reveal_script_builder: script::Builder,
+    public_key: XOnlyPublicKey,
+    key_pair: &UntweakedKeypair,
+    secp256k1: &Secp256k1<All>,
+    prev_utxo: Option<UTXO>,
+    utxos: Vec<UTXO>,
+    change_address: Address,
+    commit_fee_rate: u64,
+    reveal_fee_rate: u64,
+    network: Network,
+    reveal_tx_prefix: &[u8],
+) -> Result<(Transaction, Transaction, Vec<UTXO>), anyhow::Error> {
+    let mut nonce: i64 = 16; // skip the first digits to avoid OP_PUSHNUM_X
+    loop {
+        if nonce % 1000 == 0 {
+            trace!(nonce, "Trying to find chunk commit & reveal nonce");
+            if nonce > 16384 {
+                warn!("Too many iterations finding nonce");
+            }
+        }
+        // ownerships are moved to the loop
+        let mut reveal_script_builder = reveal_script_builder.clone();
+
+        // push nonce
+        reveal_script_builder = reveal_script_builder
+            .push_slice(nonce.to_le_bytes())
+            // drop the second item, bc there is a big chance it's 0 (tx kind) and nonce is >= 16
+            .push_opcode(OP_NIP);
+
+        // finalize reveal script
+        let reveal_script = reveal_script_builder.into_script();
+
+        let (control_block, merkle_root, tapscript_hash) =
+            build_taproot(&reveal_script, public_key, secp256k1);
+
+        // create commit tx address
+        let commit_tx_address = Address::p2tr(secp256k1, public_key, merkle_root, network);
+
+        let reveal_value = REVEAL_OUTPUT_AMOUNT;
+        let fee = get_size_reveal(
+            change_address.script_pubkey(),
+            reveal_value,
+            &reveal_script,
+            &control_block,
+        ) as u64
+            * reveal_fee_rate;
+        let reveal_input_value = fee + reveal_value + REVEAL_OUTPUT_THRESHOLD;
+
+        // build commit tx
+        let (mut unsigned_commit_tx, leftover_utxos) = build_commit_transaction(
+            prev_utxo.clone(),
+            utxos.clone(),
+            commit_tx_address.clone(),
+            change_address.clone(),
+            reveal_input_value,
+            commit_fee_rate,
+        )?;
+
+        let output_to_reveal = unsigned_commit_tx.output[0].clone();
+
+        let mut reveal_tx = build_reveal_transaction(
+            output_to_reveal.clone(),
+            unsigned_commit_tx.compute_txid(),
+            0,
+            change_address.clone(),
+            reveal_value + REVEAL_OUTPUT_THRESHOLD,
+            reveal_fee_rate,
+            &reveal_script,
+            &control_block,
+        )?;
+
+        build_witness(
+            &unsigned_commit_tx,
+            &mut reveal_tx,
+            tapscript_hash,
+            reveal_script,
+            control_block,
+            key_pair,
+            secp256k1,
+        );
+
+        let min_commit_value = Amount::from_sat(fee + reveal_value);
+        while unsigned_commit_tx.output[0].value >= min_commit_value {
+            let reveal_wtxid = reveal_tx.compute_wtxid();
+            let reveal_hash = reveal_wtxid.as_raw_hash().to_byte_array();
+
+            // check if first N bytes equal to the given prefix
+            if reveal_hash.starts_with(reveal_tx_prefix) {
+                // check if inscription locked to the correct address
+                let recovery_key_pair = key_pair.tap_tweak(secp256k1, merkle_root);
+                let (x_only_pub_key, _parity) = recovery_key_pair.to_inner().x_only_public_key();
+                assert_eq!(
+                    Address::p2tr_tweaked(
+                        TweakedPublicKey::dangerous_assume_tweaked(x_only_pub_key),
+                        network,
+                    ),
+                    commit_tx_address
+                );
+
+                return Ok((unsigned_commit_tx, reveal_tx, leftover_utxos));
+            } else {
+                unsigned_commit_tx.output[0].value -= Amount::ONE_SAT;
+                unsigned_commit_tx.output[1].value += Amount::ONE_SAT;
+                reveal_tx.output[0].value -= Amount::ONE_SAT;
+                reveal_tx.input[0].previous_output.txid = unsigned_commit_tx.compute_txid();
+                update_witness(
+                    &unsigned_commit_tx,
+                    &mut reveal_tx,
+                    tapscript_hash,
+                    key_pair,
+                    secp256k1,
+                );
+            }
+        }
+
+        nonce += 1;
+    }
+}
@@ -7,6 +7,7 @@ use bitcoin::secp256k1::SecretKey;
 use bitcoin::taproot::ControlBlock;
 use bitcoin::{Address, Amount, ScriptBuf, TxOut, Txid};
 use citrea_primitives::compression::{compress_blob, decompress_blob};
+use sov_rollup_interface::spec::SpecId;
 
 use super::light_client_proof_namespace::{LightClientTxs, RawLightClientData};
 use crate::helpers::builders::sign_blob_with_private_key;
@@ -478,6 +479,7 @@ fn create_inscription_transactions() {
             10,
             bitcoin::Network::Bitcoin,
             tx_prefix.to_vec(),
+            SpecId::Fork1,
         )
         .unwrap()
     else {
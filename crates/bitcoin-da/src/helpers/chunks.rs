@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use citrea_primitives::compression::decompress_blob;
+use sov_rollup_interface::da::{DaDataLightClient, VersionedDaData};
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::zk::Proof;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ChunkError {
+    #[error("Aggregate body could not be parsed")]
+    InvalidAggregate,
+    #[error("Aggregate does not reference any chunk")]
+    EmptyAggregate,
+    #[error("Chunk {} is missing", hex::encode(.0))]
+    MissingChunk([u8; 32]),
+    #[error("Chunk {} could not be parsed", hex::encode(.0))]
+    InvalidChunk([u8; 32]),
+    #[error("Reassembled chunks do not form a proof")]
+    InvalidProof,
+}
+
+/// Splits compress(borsh(Proof)) into chunk bodies of at most `chunk_size` bytes:
+///   [versioned(DaDataLightClient::Chunk(chunk)) for chunk in compressed.chunks(chunk_size)]
+pub fn encode_chunks(compressed: &[u8], chunk_size: usize, spec_id: SpecId) -> Vec<Vec<u8>> {
+    compressed
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let data = DaDataLightClient::Chunk(chunk.to_vec());
+            data.encode_versioned(spec_id)
+        })
+        .collect()
+}
+
+/// Parses the body of an aggregate tx into the wtxids of its chunks, in order
+pub fn parse_aggregate(body: &[u8]) -> Result<Vec<[u8; 32]>, ChunkError> {
+    match DaDataLightClient::decode_versioned(body) {
+        Ok(DaDataLightClient::Aggregate(chunk_wtxids)) if chunk_wtxids.is_empty() => {
+            Err(ChunkError::EmptyAggregate)
+        }
+        Ok(DaDataLightClient::Aggregate(chunk_wtxids)) => Ok(chunk_wtxids),
+        _ => Err(ChunkError::InvalidAggregate),
+    }
+}
+
+/// Reassembles the proof referenced by an aggregate from the chunk bodies found in the block.
+/// Every referenced chunk must be present, a partial set is an error and never a truncated proof.
+pub fn decode_chunks(
+    chunk_wtxids: &[[u8; 32]],
+    chunk_bodies: &BTreeMap<[u8; 32], Vec<u8>>,
+) -> Result<Proof, ChunkError> {
+    if chunk_wtxids.is_empty() {
+        return Err(ChunkError::EmptyAggregate);
+    }
+
+    let mut compressed = Vec::new();
+    for wtxid in chunk_wtxids {
+        let body = chunk_bodies
+            .get(wtxid)
+            .ok_or(ChunkError::MissingChunk(*wtxid))?;
+        let Ok(DaDataLightClient::Chunk(chunk)) = DaDataLightClient::decode_versioned(body) else {
+            return Err(ChunkError::InvalidChunk(*wtxid));
+        };
+        compressed.extend(chunk);
+    }
+
+    borsh::from_slice(&decompress_blob(&compressed)).map_err(|_| ChunkError::InvalidProof)
+}
+
+#[cfg(test)]
+mod tests {
+    use citrea_primitives::compression::compress_blob;
+
+    use super::*;
+
+    fn chunked(proof: &Proof, chunk_size: usize) -> (Vec<[u8; 32]>, BTreeMap<[u8; 32], Vec<u8>>) {
+        let compressed = compress_blob(&borsh::to_vec(proof).unwrap());
+        let bodies = encode_chunks(&compressed, chunk_size, SpecId::Fork1);
+        let wtxids: Vec<[u8; 32]> = (0..bodies.len())
+            .map(|i| {
+                let mut wtxid = [0; 32];
+                wtxid[..8].copy_from_slice(&(i as u64).to_le_bytes());
+                wtxid
+            })
+            .collect();
+        let map = wtxids.iter().copied().zip(bodies).collect();
+        (wtxids, map)
+    }
+
+    #[test]
+    fn test_chunks_round_trip() {
+        let proof: Proof = (0..20_000u32).flat_map(|i| i.to_le_bytes()).collect();
+
+        for chunk_size in [1, 100, 4096, 1 << 20] {
+            let (wtxids, bodies) = chunked(&proof, chunk_size);
+            assert_eq!(decode_chunks(&wtxids, &bodies), Ok(proof.clone()));
+        }
+    }
+
+    #[test]
+    fn test_chunks_missing_chunk() {
+        let proof: Proof = (0..20_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        let (wtxids, mut bodies) = chunked(&proof, 100);
+        assert!(wtxids.len() > 2);
+
+        bodies.remove(&wtxids[1]);
+        assert_eq!(
+            decode_chunks(&wtxids, &bodies),
+            Err(ChunkError::MissingChunk(wtxids[1]))
+        );
+        assert_eq!(decode_chunks(&[], &bodies), Err(ChunkError::EmptyAggregate));
+    }
+
+    #[test]
+    fn test_parse_aggregate() {
+        let wtxids = vec![[1; 32], [2; 32]];
+        let body = DaDataLightClient::Aggregate(wtxids.clone()).encode_versioned(SpecId::Fork1);
+        assert_eq!(parse_aggregate(&body), Ok(wtxids.clone()));
+
+        // Aggregates written before versioning
+        let body = DaDataLightClient::Aggregate(wtxids.clone()).encode_versioned(SpecId::Genesis);
+        assert_eq!(parse_aggregate(&body), Ok(wtxids));
+
+        let body = DaDataLightClient::Aggregate(vec![]).encode_versioned(SpecId::Fork1);
+        assert_eq!(parse_aggregate(&body), Err(ChunkError::EmptyAggregate));
+
+        let body = DaDataLightClient::Chunk(vec![1, 2, 3]).encode_versioned(SpecId::Fork1);
+        assert_eq!(parse_aggregate(&body), Err(ChunkError::InvalidAggregate));
+    }
+}
@@ -4,6 +4,7 @@ use sha2::{Digest, Sha256};
 
 #[cfg(feature = "native")]
 pub mod builders;
+pub mod chunks;
 pub mod merkle_tree;
 pub mod parsers;
 
@@ -16,6 +17,12 @@ enum TransactionKindLightClient {
     Chunked = 1,
     /// This type of transaction includes chunk parts of body (>= 400kb)
     ChunkedPart = 2,
+    /// This type of transaction includes wtxids of chunks in the same block (>= 400kb).
+    /// Written after Fork1, light client proof guests reading it have a new method id
+    ChunkedV2 = 3,
+    /// This type of transaction includes chunk parts of body, mined with the light client
+    /// prefix so that they are in the completeness proof (>= 400kb). Written after Fork1
+    ChunkedPartV2 = 4,
     Unknown(NonZeroU16),
 }
 
@@ -26,6 +33,8 @@ impl TransactionKindLightClient {
             TransactionKindLightClient::Complete => 0u16.to_le_bytes().to_vec(),
             TransactionKindLightClient::Chunked => 1u16.to_le_bytes().to_vec(),
             TransactionKindLightClient::ChunkedPart => 2u16.to_le_bytes().to_vec(),
+            TransactionKindLightClient::ChunkedV2 => 3u16.to_le_bytes().to_vec(),
+            TransactionKindLightClient::ChunkedPartV2 => 4u16.to_le_bytes().to_vec(),
             TransactionKindLightClient::Unknown(v) => v.get().to_le_bytes().to_vec(),
         }
     }
@@ -39,6 +48,8 @@ impl TransactionKindLightClient {
             0 => Some(TransactionKindLightClient::Complete),
             1 => Some(TransactionKindLightClient::Chunked),
             2 => Some(TransactionKindLightClient::ChunkedPart),
+            3 => Some(TransactionKindLightClient::ChunkedV2),
+            4 => Some(TransactionKindLightClient::ChunkedPartV2),
             n => Some(TransactionKindLightClient::Unknown(
                 NonZeroU16::new(n).expect("Is not zero"),
             )),
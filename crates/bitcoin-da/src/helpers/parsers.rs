@@ -18,6 +18,10 @@ pub enum ParsedLightClientTransaction {
     Aggregate(ParsedAggregate),
     /// Kind 2
     Chunk(ParsedChunk),
+    /// Kind 3
+    AggregateV2(ParsedAggregate),
+    /// Kind 4
+    ChunkV2(ParsedChunk),
 }
 
 #[derive(Debug, Clone)]
@@ -196,6 +200,12 @@ fn parse_relevant_lightclient(
         TransactionKindLightClient::ChunkedPart => {
             light_client::parse_type_2_body(instructions).map(ParsedLightClientTransaction::Chunk)
         }
+        // Only the body of the aggregate is interpreted differently
+        TransactionKindLightClient::ChunkedV2 => light_client::parse_type_1_body(instructions)
+            .map(ParsedLightClientTransaction::AggregateV2),
+        TransactionKindLightClient::ChunkedPartV2 => {
+            light_client::parse_type_4_body(instructions).map(ParsedLightClientTransaction::ChunkV2)
+        }
         TransactionKindLightClient::Unknown(n) => Err(ParserError::InvalidHeaderType(n)),
     }
 }
@@ -417,6 +427,25 @@ mod light_client {
 
         Ok(ParsedChunk { body })
     }
+
+    // Parse transaction body of Type4, which is the body of Type2 followed by a nonce
+    pub(super) fn parse_type_4_body(
+        instructions: &mut dyn Iterator<Item = Result<Instruction<'_>, ParserError>>,
+    ) -> Result<ParsedChunk, ParserError> {
+        let chunk = parse_type_2_body(instructions)?;
+
+        // Nonce
+        let _nonce = read_push_bytes(instructions)?;
+        if OP_NIP != read_opcode(instructions)? {
+            return Err(ParserError::UnexpectedOpcode);
+        }
+        // END of transaction
+        if instructions.next().is_some() {
+            return Err(ParserError::UnexpectedOpcode);
+        }
+
+        Ok(chunk)
+    }
 }
 
 mod batch_proof {
@@ -491,9 +520,11 @@ mod tests {
     use bitcoin::opcodes::{OP_FALSE, OP_TRUE};
     use bitcoin::script::{self, PushBytesBuf};
     use bitcoin::Transaction;
+    use sov_rollup_interface::da::{DaDataBatchProof, SequencerCommitment, VersionedDaData};
 
     use super::{
-        parse_light_client_transaction, parse_relevant_lightclient, ParsedLightClientTransaction,
+        parse_batch_proof_transaction, parse_hex_transaction, parse_light_client_transaction,
+        parse_relevant_lightclient, ParsedBatchProofTransaction, ParsedLightClientTransaction,
         ParserError,
     };
     use crate::helpers::TransactionKindLightClient;
@@ -679,4 +710,37 @@ mod tests {
         assert_eq!(result.signature, vec![2u8; 64]);
         assert_eq!(result.public_key, vec![3u8; 64]);
     }
+
+    #[test]
+    fn decode_legacy_sequencer_commitments() {
+        // Sequencer commitments of the mock block, inscribed before DA data was versioned
+        let txs = std::fs::read_to_string("test_data/mock_txs.txt").unwrap();
+        let txs: Vec<&str> = txs.lines().collect();
+        let commitments: Vec<_> = [4, 6, 18, 34]
+            .iter()
+            .map(|&position| {
+                let tx = parse_hex_transaction(txs[position]).unwrap();
+                let ParsedBatchProofTransaction::SequencerCommitment(commitment) =
+                    parse_batch_proof_transaction(&tx).unwrap();
+                DaDataBatchProof::decode_versioned(&commitment.body).unwrap()
+            })
+            .collect();
+
+        let expected = [
+            ([0x0d; 32], 1002, 1100),
+            ([0x0e; 32], 1101, 1245),
+            ([0x0f; 32], 1246, 1268),
+            ([0x1e; 32], 1268, 1314),
+        ]
+        .map(
+            |(merkle_root, l2_start_block_number, l2_end_block_number)| {
+                DaDataBatchProof::SequencerCommitment(SequencerCommitment {
+                    merkle_root,
+                    l2_start_block_number,
+                    l2_end_block_number,
+                })
+            },
+        );
+        assert_eq!(commitments, expected);
+    }
 }
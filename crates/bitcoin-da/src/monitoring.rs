@@ -16,6 +16,7 @@ use tokio::time::interval;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, instrument};
 
+use crate::fee::FeePriority;
 use crate::service::FINALITY_DEPTH;
 use crate::spec::utxo::UTXO;
 
@@ -67,6 +68,7 @@ pub struct MonitoredTx {
     pub prev_txid: Option<Txid>, // Previous tx in chain
     pub next_txid: Option<Txid>, // Next tx in chain
     pub kind: MonitoredTxKind,
+    pub bump_count: u32, // Number of times the tx was replaced by a fee bump
 }
 
 impl MonitoredTx {
@@ -118,6 +120,8 @@ pub enum MonitorError {
     PrevTxNotMonitored(Txid),
     #[error("Invalid tx chain, odd number of txs")]
     OddNumberOfTxs,
+    #[error("Replacement tx chain length mismatch")]
+    ChainLengthMismatch,
     #[error(transparent)]
     BitcoinRpcError(#[from] bitcoincore_rpc::Error),
     #[error(transparent)]
@@ -266,6 +270,19 @@ impl MonitoringService {
         Ok(())
     }
 
+    /// Logs the fee rate a DA tx chain was submitted with
+    pub fn log_submission(&self, txids: &[Txid], priority: FeePriority, fee_rate: u64) {
+        if let (Some(commit_txid), Some(reveal_txid)) = (txids.first(), txids.last()) {
+            info!(
+                %commit_txid,
+                %reveal_txid,
+                ?priority,
+                fee_rate,
+                "Submitted DA tx chain"
+            );
+        }
+    }
+
     #[instrument(skip(self))]
     pub async fn monitor_transaction(
         &self,
@@ -310,6 +327,7 @@ impl MonitoringService {
             prev_txid,
             next_txid,
             kind,
+            bump_count: 0,
         };
 
         self.monitored_txs.write().await.insert(txid, monitored_tx);
@@ -353,6 +371,7 @@ impl MonitoringService {
             kind: monitored_tx.kind,
             prev_txid: monitored_tx.prev_txid,
             next_txid: monitored_tx.next_txid,
+            bump_count: monitored_tx.bump_count + 1,
         };
 
         {
@@ -373,6 +392,77 @@ impl MonitoringService {
         Ok(())
     }
 
+    /// Replace the commit/reveal chain of a blob with its fee bumped rebuild.
+    /// `txids` are expected in the same order as in `monitor_transaction_chain`
+    /// and replace `prev_txids` one by one
+    #[instrument(level = "trace", skip(self))]
+    pub async fn replace_transaction_chain(
+        &self,
+        prev_txids: &[Txid],
+        txids: Vec<Txid>,
+    ) -> Result<()> {
+        if txids.len() % 2 != 0 {
+            return Err(MonitorError::OddNumberOfTxs);
+        }
+        if txids.len() != prev_txids.len() || txids.is_empty() {
+            return Err(MonitorError::ChainLengthMismatch);
+        }
+
+        let (chain_parent, bump_count) = {
+            let monitored_txs = self.monitored_txs.read().await;
+            let first_tx = monitored_txs
+                .get(&prev_txids[0])
+                .ok_or(MonitorError::PrevTxNotMonitored(prev_txids[0]))?;
+
+            // The parent blob might have been rebuilt right before this one
+            let chain_parent = first_tx.prev_txid.map(|txid| {
+                match monitored_txs.get(&txid).map(|tx| &tx.status) {
+                    Some(TxStatus::Replaced { by_txid }) => *by_txid,
+                    _ => txid,
+                }
+            });
+            (chain_parent, first_tx.bump_count + 1)
+        };
+
+        let mut last_tx = chain_parent;
+
+        let mut txids_iter = txids.iter().copied();
+        while let (Some(commit_txid), Some(reveal_txid)) = (txids_iter.next(), txids_iter.next()) {
+            self.monitor_transaction(
+                commit_txid,
+                last_tx,
+                Some(reveal_txid),
+                MonitoredTxKind::Commit,
+            )
+            .await?;
+
+            self.monitor_transaction(
+                reveal_txid,
+                Some(commit_txid),
+                None,
+                MonitoredTxKind::Reveal,
+            )
+            .await?;
+
+            last_tx = Some(reveal_txid)
+        }
+
+        let mut monitored_txs = self.monitored_txs.write().await;
+        for (prev_txid, txid) in prev_txids.iter().zip(&txids) {
+            if let Some(prev_tx) = monitored_txs.get_mut(prev_txid) {
+                prev_tx.status = TxStatus::Replaced { by_txid: *txid };
+            }
+            if let Some(tx) = monitored_txs.get_mut(txid) {
+                tx.bump_count = bump_count;
+            }
+        }
+        if let Some(parent) = chain_parent.and_then(|txid| monitored_txs.get_mut(&txid)) {
+            parent.next_txid = Some(txids[0]);
+        }
+
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     async fn check_chain_state(&self) -> Result<()> {
         let new_height = self.client.get_block_count().await?;
@@ -449,9 +539,12 @@ impl MonitoringService {
                 | TxStatus::Confirmed { .. }
                 | TxStatus::Replaced { .. } => {
                     let tx_result = self.client.get_transaction(txid, None).await?;
-                    let new_status = self.determine_tx_status(&tx_result).await?;
-
-                    monitored_tx.status = new_status;
+                    // A replaced tx conflicts with its replacement and reports negative confirmations
+                    let is_conflicted = tx_result.info.confirmations < 0;
+                    if !(is_conflicted && matches!(monitored_tx.status, TxStatus::Replaced { .. }))
+                    {
+                        monitored_tx.status = self.determine_tx_status(&tx_result).await?;
+                    }
                 }
                 _ => {}
             }
@@ -541,6 +634,20 @@ impl MonitoringService {
         self.get_monitored_tx(txid).await.map(|tx| tx.status)
     }
 
+    /// Returns how many times the blob containing `txid` was fee bumped,
+    /// following replacements up to its latest version
+    pub async fn get_bump_count(&self, txid: &Txid) -> Option<u32> {
+        let monitored_txs = self.monitored_txs.read().await;
+        let mut tx = monitored_txs.get(txid)?;
+        while let TxStatus::Replaced { by_txid } = tx.status {
+            match monitored_txs.get(&by_txid) {
+                Some(replacement) => tx = replacement,
+                None => break,
+            }
+        }
+        Some(tx.bump_count)
+    }
+
     pub async fn get_monitored_tx(&self, txid: &Txid) -> Option<MonitoredTx> {
         self.monitored_txs.read().await.get(txid).cloned()
     }
@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::fee::BumpFeeMethod;
 use crate::monitoring::{MonitoredTx, TxStatus};
-use crate::service::BitcoinService;
+use crate::service::{BitcoinService, DaStatus};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoredTxResponse {
@@ -21,6 +21,7 @@ pub struct MonitoredTxResponse {
     pub prev_txid: Option<Txid>,
     pub next_txid: Option<Txid>,
     pub status: TxStatus,
+    pub bump_count: u32,
 }
 
 impl From<(Txid, MonitoredTx)> for MonitoredTxResponse {
@@ -40,6 +41,7 @@ impl From<(Txid, MonitoredTx)> for MonitoredTxResponse {
             prev_txid: tx.prev_txid,
             next_txid: tx.next_txid,
             status: tx.status,
+            bump_count: tx.bump_count,
         }
     }
 }
@@ -58,6 +60,12 @@ pub trait DaRpc {
     #[method(name = "getLastMonitoredTx")]
     async fn da_get_last_monitored_tx(&self) -> RpcResult<Option<MonitoredTxResponse>>;
 
+    #[method(name = "getBumpCount")]
+    async fn da_get_bump_count(&self, txid: Txid) -> RpcResult<Option<u32>>;
+
+    #[method(name = "getStatus")]
+    async fn da_get_status(&self) -> RpcResult<DaStatus>;
+
     #[method(name = "bumpFeeCpfp")]
     async fn da_bump_transaction_fee_cpfp(
         &self,
@@ -110,6 +118,20 @@ impl DaRpcServer for DaRpcServerImpl {
         Ok(self.da.monitoring.get_last_tx().await.map(Into::into))
     }
 
+    async fn da_get_bump_count(&self, txid: Txid) -> RpcResult<Option<u32>> {
+        Ok(self.da.monitoring.get_bump_count(&txid).await)
+    }
+
+    async fn da_get_status(&self) -> RpcResult<DaStatus> {
+        self.da.get_status().await.map_err(|e| {
+            ErrorObjectOwned::owned(
+                INTERNAL_ERROR_CODE,
+                INTERNAL_ERROR_MSG,
+                Some(format!("{e}",)),
+            )
+        })
+    }
+
     async fn da_bump_transaction_fee_cpfp(
         &self,
         txid: Option<Txid>,
@@ -4,7 +4,7 @@
 use core::result::Result::Ok;
 use core::str::FromStr;
 use core::time::Duration;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -16,25 +16,29 @@ use bitcoin::block::Header;
 use bitcoin::consensus::{encode, Decodable};
 use bitcoin::hashes::Hash;
 use bitcoin::secp256k1::SecretKey;
-use bitcoin::{Amount, BlockHash, CompactTarget, Transaction, Txid, Wtxid};
-use bitcoincore_rpc::json::{SignRawTransactionInput, TestMempoolAcceptResult};
+use bitcoin::{Address, Amount, BlockHash, CompactTarget, OutPoint, Transaction, Txid, Wtxid};
+use bitcoincore_rpc::json::{
+    ListUnspentResultEntry, SignRawTransactionInput, TestMempoolAcceptResult,
+};
 use bitcoincore_rpc::{Auth, Client, Error, RpcApi, RpcError};
-use borsh::BorshDeserialize;
 use citrea_primitives::compression::{compress_blob, decompress_blob};
 use citrea_primitives::MAX_TXBODY_SIZE;
 use serde::{Deserialize, Serialize};
 use sov_rollup_interface::da::{
     DaData, DaDataBatchProof, DaDataLightClient, DaNamespace, DaSpec, SequencerCommitment,
+    VersionedDaData,
 };
 use sov_rollup_interface::services::da::{DaService, SenderWithNotifier};
+use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::zk::Proof;
 use tokio::select;
+use tokio::sync::broadcast;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::sync::oneshot::channel as oneshot_channel;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, instrument, trace, warn};
 
-use crate::fee::{BumpFeeMethod, FeeService};
+use crate::fee::{bumped_fee_rate, BumpFeeMethod, FeePriority, FeeRateLimits, FeeService};
 use crate::helpers::builders::batch_proof_namespace::{
     create_seqcommitment_transactions, BatchProvingTxs,
 };
@@ -42,6 +46,7 @@ use crate::helpers::builders::light_client_proof_namespace::{
     create_zkproof_transactions, LightClientTxs, RawLightClientData,
 };
 use crate::helpers::builders::{TxListWithReveal, TxWithId};
+use crate::helpers::chunks::{decode_chunks, encode_chunks, parse_aggregate};
 use crate::helpers::merkle_tree;
 use crate::helpers::merkle_tree::BitcoinMerkleTree;
 use crate::helpers::parsers::{
@@ -62,6 +67,16 @@ use crate::REVEAL_OUTPUT_AMOUNT;
 
 pub const FINALITY_DEPTH: u64 = 30; // blocks
 const POLLING_INTERVAL: u64 = 10; // seconds
+const DEFAULT_FEE_BUMP_AFTER_BLOCKS: u64 = 0; // disabled
+
+// file in the tx backup dir the unconfirmed blobs of the DA queue are persisted to
+const PENDING_BLOBS_FILE: &str = "pending_blobs.json";
+// file in the tx backup dir the requests left in the DA queue on shutdown are persisted to
+const QUEUED_REQUESTS_FILE: &str = "queued_requests.json";
+// replacements kept for subscribers that are lagging behind
+const REPLACED_TXS_CAPACITY: usize = 64;
+const DEFAULT_MIN_FEE_RATE: u64 = 1; // sat/vB
+const DEFAULT_MAX_FEE_RATE: u64 = 100; // sat/vB
 
 /// Runtime configuration for the DA service
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -81,6 +96,48 @@ pub struct BitcoinServiceConfig {
     pub tx_backup_dir: String,
 
     pub monitoring: Option<MonitoringConfig>,
+
+    // number of blocks a da tx can stay unconfirmed before it is rebuilt with a higher fee
+    // 0 disables fee bumping, which is the default
+    #[serde(default = "default_fee_bump_after_blocks")]
+    pub fee_bump_after_blocks: u64,
+
+    // lower bound in sat/vB of the estimated fee rate of da txs
+    #[serde(default = "default_min_fee_rate")]
+    pub min_fee_rate: u64,
+
+    // upper bound in sat/vB of the fee rate used when sending or bumping da txs
+    #[serde(default = "default_max_fee_rate")]
+    pub max_fee_rate: u64,
+
+    // fixed fee rate in sat/vB used instead of estimating it from the bitcoin node
+    #[serde(default)]
+    pub fee_rate_override: Option<u64>,
+
+    // compressed zk proofs of this size or larger are split into chunk txs
+    // capped at MAX_TXBODY_SIZE
+    #[serde(default = "default_proof_chunk_threshold")]
+    pub proof_chunk_threshold: usize,
+}
+
+#[inline]
+const fn default_fee_bump_after_blocks() -> u64 {
+    DEFAULT_FEE_BUMP_AFTER_BLOCKS
+}
+
+#[inline]
+const fn default_min_fee_rate() -> u64 {
+    DEFAULT_MIN_FEE_RATE
+}
+
+#[inline]
+const fn default_max_fee_rate() -> u64 {
+    DEFAULT_MAX_FEE_RATE
+}
+
+#[inline]
+const fn default_proof_chunk_threshold() -> usize {
+    MAX_TXBODY_SIZE
 }
 
 impl citrea_common::FromEnv for BitcoinServiceConfig {
@@ -97,6 +154,30 @@ impl citrea_common::FromEnv for BitcoinServiceConfig {
                 history_limit: std::env::var("DA_MONITORING_HISTORY_LIMIT")?.parse()?,
                 max_history_size: std::env::var("DA_MONITORING_MAX_HISTORY_SIZE")?.parse()?,
             }),
+            fee_bump_after_blocks: std::env::var("DA_FEE_BUMP_AFTER_BLOCKS")
+                .ok()
+                .map(|blocks| blocks.parse())
+                .transpose()?
+                .unwrap_or(DEFAULT_FEE_BUMP_AFTER_BLOCKS),
+            min_fee_rate: std::env::var("DA_MIN_FEE_RATE")
+                .ok()
+                .map(|rate| rate.parse())
+                .transpose()?
+                .unwrap_or(DEFAULT_MIN_FEE_RATE),
+            max_fee_rate: std::env::var("DA_MAX_FEE_RATE")
+                .ok()
+                .map(|rate| rate.parse())
+                .transpose()?
+                .unwrap_or(DEFAULT_MAX_FEE_RATE),
+            fee_rate_override: std::env::var("DA_FEE_RATE_OVERRIDE")
+                .ok()
+                .map(|rate| rate.parse())
+                .transpose()?,
+            proof_chunk_threshold: std::env::var("DA_PROOF_CHUNK_THRESHOLD")
+                .ok()
+                .map(|threshold| threshold.parse())
+                .transpose()?
+                .unwrap_or(MAX_TXBODY_SIZE),
         })
     }
 }
@@ -113,6 +194,58 @@ pub struct BitcoinService {
     tx_backup_dir: PathBuf,
     pub monitoring: Arc<MonitoringService>,
     fee: FeeService,
+    fee_bump_after_blocks: u64,
+    max_fee_rate: u64,
+    proof_chunk_threshold: usize,
+    // (replaced, replacement) reveal txids of the blobs rebuilt with a higher fee
+    replaced_txs: broadcast::Sender<(TxidWrapper, TxidWrapper)>,
+}
+
+/// Health of the DA service and its wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaStatus {
+    // network of the connected bitcoin node
+    pub network: String,
+    pub block_height: u64,
+    // total amount in sats of the wallet UTXOs that can fund DA txs
+    pub spendable_balance: u64,
+    // number of monitored DA txs that are not confirmed yet
+    pub unconfirmed_txs: usize,
+    pub last_submission: Option<DaSubmissionStatus>,
+    // estimated fee rates in sat/vB of sequencer commitments and zk proofs
+    pub commitment_fee_rate: u64,
+    pub proof_fee_rate: u64,
+}
+
+/// A submitted DA tx with its number of confirmations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaSubmissionStatus {
+    pub txid: Txid,
+    pub confirmations: u64,
+}
+
+/// Commit/reveal txs of a blob, built for its namespace
+enum InscriptionTxs {
+    LightClient(LightClientTxs),
+    BatchProof(BatchProvingTxs),
+}
+
+/// A blob sent by the DA queue whose reveal tx is not confirmed yet
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingBlob {
+    da_data: DaData,
+    spec_id: SpecId,
+    // Commit/reveal chain of the latest broadcasted version
+    txids: Vec<Txid>,
+    fee_rate: u64,
+    broadcast_height: u64,
+}
+
+/// A request left in the DA queue on shutdown, sent on the next start
+#[derive(Debug, Serialize, Deserialize)]
+struct QueuedRequest {
+    da_data: DaData,
+    spec_id: SpecId,
 }
 
 impl BitcoinService {
@@ -153,7 +286,15 @@ impl BitcoinService {
         }
 
         let monitoring = Arc::new(MonitoringService::new(client.clone(), config.monitoring));
-        let fee = FeeService::new(client.clone(), config.network);
+        let fee = FeeService::new(
+            client.clone(),
+            config.network,
+            FeeRateLimits {
+                min_fee_rate: config.min_fee_rate,
+                max_fee_rate: config.max_fee_rate,
+                fee_rate_override: config.fee_rate_override,
+            },
+        );
         Ok(Self {
             client,
             network: config.network,
@@ -164,6 +305,10 @@ impl BitcoinService {
             tx_backup_dir: tx_backup_dir.to_path_buf(),
             monitoring,
             fee,
+            fee_bump_after_blocks: config.fee_bump_after_blocks,
+            max_fee_rate: config.max_fee_rate,
+            proof_chunk_threshold: config.proof_chunk_threshold.clamp(1, MAX_TXBODY_SIZE),
+            replaced_txs: broadcast::channel(REPLACED_TXS_CAPACITY).0,
         })
     }
 
@@ -195,7 +340,15 @@ impl BitcoinService {
         }
 
         let monitoring = Arc::new(MonitoringService::new(client.clone(), config.monitoring));
-        let fee = FeeService::new(client.clone(), config.network);
+        let fee = FeeService::new(
+            client.clone(),
+            config.network,
+            FeeRateLimits {
+                min_fee_rate: config.min_fee_rate,
+                max_fee_rate: config.max_fee_rate,
+                fee_rate_override: config.fee_rate_override,
+            },
+        );
 
         Ok(Self {
             client,
@@ -207,6 +360,10 @@ impl BitcoinService {
             tx_backup_dir: tx_backup_dir.to_path_buf(),
             monitoring,
             fee,
+            fee_bump_after_blocks: config.fee_bump_after_blocks,
+            max_fee_rate: config.max_fee_rate,
+            proof_chunk_threshold: config.proof_chunk_threshold.clamp(1, MAX_TXBODY_SIZE),
+            replaced_txs: broadcast::channel(REPLACED_TXS_CAPACITY).0,
         })
     }
 
@@ -217,19 +374,31 @@ impl BitcoinService {
     ) {
         trace!("BitcoinDA queue is initialized. Waiting for the first request...");
 
+        self.requeue_queued_requests();
+        let mut pending_blobs = self.load_pending_blobs();
+        let mut fee_bump_interval = tokio::time::interval(Duration::from_secs(POLLING_INTERVAL));
+
         loop {
             select! {
                 biased;
                 _ = token.cancelled() => {
                     debug!("DA queue service received shutdown signal");
+                    self.backup_queued_requests(&mut rx).await;
                     break;
                 }
+                _ = fee_bump_interval.tick(), if self.fee_bump_after_blocks > 0 => {
+                    if let Err(e) = self.bump_pending_blobs(&mut pending_blobs).await {
+                        error!(?e, "Failed to bump fee of pending DA txs");
+                    }
+                    self.save_pending_blobs(&pending_blobs);
+                }
                 request_opt = rx.recv() => {
                     if let Some(request) = request_opt {
                         trace!("A new request is received");
                         loop {
                             // Build and send tx with retries:
-                            let fee_sat_per_vbyte = match self.fee.get_fee_rate().await {
+                            let priority = fee_priority(&request.da_data);
+                            let fee_sat_per_vbyte = match self.fee.get_fee_rate(priority).await {
                                 Ok(rate) => rate,
                                 Err(e) => {
                                     error!(?e, "Failed to call get_fee_rate. Retrying...");
@@ -240,6 +409,7 @@ impl BitcoinService {
                             match self
                                 .send_transaction_with_fee_rate(
                                     request.da_data.clone(),
+                                    request.spec_id,
                                     fee_sat_per_vbyte,
                                 )
                                 .await
@@ -250,9 +420,26 @@ impl BitcoinService {
                                 info!(%txid, "Sent tx to BitcoinDA");
                                 let _ = request.notify.send(Ok(tx_id));
 
-                                if let Err(e) = self.monitoring.monitor_transaction_chain(txids).await {
+                                if let Err(e) = self.monitoring.monitor_transaction_chain(txids.clone()).await {
                                     error!(?e, "Failed to monitor tx chain");
                                 }
+                                self.monitoring.log_submission(&txids, priority, fee_sat_per_vbyte);
+
+                                if self.fee_bump_after_blocks > 0 {
+                                    match self.client.get_block_count().await {
+                                        Ok(broadcast_height) => {
+                                            pending_blobs.push(PendingBlob {
+                                                da_data: request.da_data,
+                                                spec_id: request.spec_id,
+                                                txids,
+                                                fee_rate: fee_sat_per_vbyte,
+                                                broadcast_height,
+                                            });
+                                            self.save_pending_blobs(&pending_blobs);
+                                        }
+                                        Err(e) => error!(?e, "Failed to get block count, tx fee will not be bumped"),
+                                    }
+                                }
                             }
                             Err(e) => {
                                 error!(?e, "Failed to send transaction to DA layer");
@@ -293,15 +480,7 @@ impl BitcoinService {
             bail!("There are no UTXOs");
         }
 
-        let utxos: Vec<UTXO> = utxos
-            .into_iter()
-            .filter(|utxo| {
-                utxo.spendable
-                    && utxo.solvable
-                    && utxo.amount > Amount::from_sat(REVEAL_OUTPUT_AMOUNT)
-            })
-            .map(Into::into)
-            .collect();
+        let utxos: Vec<UTXO> = spendable_utxos(utxos).collect();
         if utxos.is_empty() {
             bail!("There are no spendable UTXOs");
         }
@@ -309,6 +488,51 @@ impl BitcoinService {
         Ok(utxos)
     }
 
+    /// Returns the total amount in sats of the wallet UTXOs that can fund DA txs.
+    pub async fn get_spendable_balance(&self) -> Result<u64> {
+        let utxos = self
+            .client
+            .list_unspent(Some(0), None, None, None, None)
+            .await?;
+
+        Ok(spendable_utxos(utxos).map(|utxo| utxo.amount).sum())
+    }
+
+    /// Returns the network and the block height of the connected bitcoin node.
+    pub async fn get_node_info(&self) -> Result<(String, u64)> {
+        let info = self.client.get_blockchain_info().await?;
+        Ok((info.chain.to_string(), info.blocks))
+    }
+
+    /// Returns the last submitted DA tx with its number of confirmations, as tracked by monitoring.
+    pub async fn get_last_submission(&self) -> Option<DaSubmissionStatus> {
+        let (txid, tx) = self.monitoring.get_last_tx().await?;
+        let confirmations = match tx.status {
+            TxStatus::Confirmed { confirmations, .. }
+            | TxStatus::Finalized { confirmations, .. } => confirmations,
+            _ => 0,
+        };
+        Some(DaSubmissionStatus {
+            txid,
+            confirmations,
+        })
+    }
+
+    /// Collects the health of the connected node, the wallet and the DA txs.
+    /// Node credentials are never part of the status.
+    pub async fn get_status(&self) -> Result<DaStatus> {
+        let (network, block_height) = self.get_node_info().await?;
+        Ok(DaStatus {
+            network,
+            block_height,
+            spendable_balance: self.get_spendable_balance().await?,
+            unconfirmed_txs: self.get_pending_transactions().await.len(),
+            last_submission: self.get_last_submission().await,
+            commitment_fee_rate: self.fee.get_fee_rate(FeePriority::High).await?,
+            proof_fee_rate: self.fee.get_fee_rate(FeePriority::Medium).await?,
+        })
+    }
+
     #[instrument(level = "trace", skip_all, ret)]
     async fn get_pending_transactions(&self) -> Vec<Transaction> {
         self.monitoring
@@ -324,18 +548,96 @@ impl BitcoinService {
     pub async fn send_transaction_with_fee_rate(
         &self,
         da_data: DaData,
+        spec_id: SpecId,
         fee_sat_per_vbyte: u64,
     ) -> Result<Vec<Txid>> {
-        let network = self.network;
-
-        let da_private_key = self.da_private_key.expect("No private key set");
-
         // get all available utxos
         let utxos = self.get_utxos().await?;
         let prev_utxo = self.get_prev_utxo().await;
 
+        self.inscribe(
+            da_data,
+            spec_id,
+            prev_utxo,
+            utxos,
+            fee_sat_per_vbyte,
+            fee_sat_per_vbyte,
+            false,
+        )
+        .await
+    }
+
+    /// Builds, backs up and broadcasts the commit/reveal txs of `da_data`.
+    /// If `replacement` is set, the first commit is expected to replace a tx in the mempool.
+    #[allow(clippy::too_many_arguments)]
+    async fn inscribe(
+        &self,
+        da_data: DaData,
+        spec_id: SpecId,
+        prev_utxo: Option<UTXO>,
+        utxos: Vec<UTXO>,
+        commit_fee_rate: u64,
+        reveal_fee_rate: u64,
+        replacement: bool,
+    ) -> Result<Vec<Txid>> {
+        let inscription_txs = self
+            .create_inscription_txs(
+                da_data,
+                spec_id,
+                prev_utxo,
+                utxos,
+                commit_fee_rate,
+                reveal_fee_rate,
+            )
+            .await?;
+
+        match inscription_txs {
+            InscriptionTxs::LightClient(LightClientTxs::Complete { commit, reveal }) => {
+                self.send_complete_transaction(commit, reveal, replacement)
+                    .await
+            }
+            InscriptionTxs::LightClient(LightClientTxs::Chunked {
+                commit_chunks,
+                reveal_chunks,
+                commit,
+                reveal,
+            }) => {
+                self.send_chunked_transaction(
+                    commit_chunks,
+                    reveal_chunks,
+                    commit,
+                    reveal,
+                    replacement,
+                )
+                .await
+            }
+            InscriptionTxs::BatchProof(BatchProvingTxs { commit, reveal }) => {
+                self.send_complete_transaction(commit, reveal, replacement)
+                    .await
+            }
+        }
+    }
+
+    /// Builds the commit/reveal txs of `da_data` and writes them to the tx backup dir.
+    /// The data is encoded as written by the L2 blocks of `spec_id`.
+    async fn create_inscription_txs(
+        &self,
+        da_data: DaData,
+        spec_id: SpecId,
+        prev_utxo: Option<UTXO>,
+        utxos: Vec<UTXO>,
+        commit_fee_rate: u64,
+        reveal_fee_rate: u64,
+    ) -> Result<InscriptionTxs> {
+        let network = self.network;
+
+        let da_private_key = self.da_private_key.expect("No private key set");
+
         // get address from a utxo
-        let address = utxos[0]
+        let address = utxos
+            .first()
+            .or(prev_utxo.as_ref())
+            .context("No UTXOs")?
             .address
             .clone()
             .context("Missing address")?
@@ -344,7 +646,7 @@ impl BitcoinService {
 
         match da_data {
             DaData::ZKProof(zkproof) => {
-                let data = split_proof(zkproof);
+                let data = split_proof(zkproof, self.proof_chunk_threshold, spec_id);
 
                 let reveal_light_client_prefix = self.to_light_client_prefix.clone();
                 // create inscribe transactions
@@ -357,10 +659,11 @@ impl BitcoinService {
                         prev_utxo,
                         utxos,
                         address,
-                        fee_sat_per_vbyte,
-                        fee_sat_per_vbyte,
+                        commit_fee_rate,
+                        reveal_fee_rate,
                         network,
                         reveal_light_client_prefix,
+                        spec_id,
                     )
                 })
                 .await??;
@@ -368,24 +671,11 @@ impl BitcoinService {
                 // write txs to file, it can be used to continue revealing blob if something goes wrong
                 inscription_txs.write_to_file(self.tx_backup_dir.clone())?;
 
-                match inscription_txs {
-                    LightClientTxs::Complete { commit, reveal } => {
-                        self.send_complete_transaction(commit, reveal).await
-                    }
-                    LightClientTxs::Chunked {
-                        commit_chunks,
-                        reveal_chunks,
-                        commit,
-                        reveal,
-                    } => {
-                        self.send_chunked_transaction(commit_chunks, reveal_chunks, commit, reveal)
-                            .await
-                    }
-                }
+                Ok(InscriptionTxs::LightClient(inscription_txs))
             }
             DaData::SequencerCommitment(comm) => {
                 let data = DaDataBatchProof::SequencerCommitment(comm);
-                let blob = borsh::to_vec(&data).expect("DaDataBatchProof serialize must not fail");
+                let blob = data.encode_versioned(spec_id);
 
                 let prefix = self.to_batch_proof_prefix.clone();
                 // create inscribe transactions
@@ -398,8 +688,8 @@ impl BitcoinService {
                         prev_utxo,
                         utxos,
                         address,
-                        fee_sat_per_vbyte,
-                        fee_sat_per_vbyte,
+                        commit_fee_rate,
+                        reveal_fee_rate,
                         network,
                         prefix,
                     )
@@ -409,11 +699,81 @@ impl BitcoinService {
                 // write txs to file, it can be used to continue revealing blob if something goes wrong
                 inscription_txs.write_to_file(self.tx_backup_dir.clone())?;
 
-                let BatchProvingTxs { commit, reveal } = inscription_txs;
+                Ok(InscriptionTxs::BatchProof(inscription_txs))
+            }
+        }
+    }
+
+    /// Persists the payloads of the requests still waiting in the DA queue. Called on shutdown
+    /// so that no queued blob is lost, their txs are built on the next start.
+    async fn backup_queued_requests(
+        &self,
+        rx: &mut UnboundedReceiver<SenderWithNotifier<TxidWrapper>>,
+    ) {
+        let mut queued_requests = vec![];
+        while let Ok(request) = rx.try_recv() {
+            let _ = request
+                .notify
+                .send(Err(anyhow!("DA queue is shut down before sending the tx")));
+            queued_requests.push(QueuedRequest {
+                da_data: request.da_data,
+                spec_id: request.spec_id,
+            });
+        }
+        if queued_requests.is_empty() {
+            return;
+        }
+
+        let path = self.tx_backup_dir.join(QUEUED_REQUESTS_FILE);
+        let result = serde_json::to_vec(&queued_requests)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| std::fs::write(path, data).map_err(Into::into));
+        match result {
+            Ok(()) => info!(
+                count = queued_requests.len(),
+                "Backed up queued DA requests on shutdown"
+            ),
+            Err(e) => error!(?e, "Failed to back up queued DA requests on shutdown"),
+        }
+    }
+
+    /// Sends the requests left in the DA queue by the previous run through the DA queue again
+    fn requeue_queued_requests(&self) {
+        let path = self.tx_backup_dir.join(QUEUED_REQUESTS_FILE);
+        if !path.exists() {
+            return;
+        }
+
+        let queued_requests: Vec<QueuedRequest> = match std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| serde_json::from_slice(&data).map_err(Into::into))
+        {
+            Ok(queued_requests) => queued_requests,
+            Err(e) => {
+                error!(?e, "Failed to load queued DA requests of the previous run");
+                return;
+            }
+        };
 
-                self.send_complete_transaction(commit, reveal).await
+        for queued_request in queued_requests {
+            // The original request is notified already
+            let (notify, _) = oneshot_channel();
+            let request = SenderWithNotifier {
+                da_data: queued_request.da_data,
+                spec_id: queued_request.spec_id,
+                notify,
+            };
+            if self.inscribes_queue.send(request).is_err() {
+                error!("DA queue is closed, queued DA request is dropped");
             }
         }
+        // Removed once requeued, so that the requests are not sent twice
+        if let Err(e) = std::fs::remove_file(&path) {
+            error!(
+                ?e,
+                "Failed to remove queued DA requests of the previous run"
+            );
+        }
     }
 
     pub async fn send_chunked_transaction(
@@ -422,6 +782,7 @@ impl BitcoinService {
         reveal_chunks: Vec<Transaction>,
         commit: Transaction,
         reveal: TxWithId,
+        replacement: bool,
     ) -> Result<Vec<Txid>> {
         assert!(!commit_chunks.is_empty(), "Received empty chunks");
         assert_eq!(
@@ -493,7 +854,8 @@ impl BitcoinService {
         let serialized_reveal_tx = encode::serialize(&reveal.tx);
         raw_txs.push(serialized_reveal_tx);
 
-        self.test_mempool_accept(&raw_txs).await?;
+        self.test_mempool_accept(mempool_test_txs(&raw_txs, replacement))
+            .await?;
 
         let txids = self.send_raw_transactions(&raw_txs).await?;
 
@@ -512,6 +874,7 @@ impl BitcoinService {
         &self,
         commit: Transaction,
         reveal: TxWithId,
+        replacement: bool,
     ) -> Result<Vec<Txid>> {
         let signed_raw_commit_tx = self
             .client
@@ -520,7 +883,8 @@ impl BitcoinService {
         let serialized_reveal_tx = encode::serialize(&reveal.tx);
         let raw_txs = [signed_raw_commit_tx.hex, serialized_reveal_tx];
 
-        self.test_mempool_accept(&raw_txs).await?;
+        self.test_mempool_accept(mempool_test_txs(&raw_txs, replacement))
+            .await?;
 
         let txids = self.send_raw_transactions(&raw_txs).await?;
         info!("Blob inscribe tx sent. Hash: {}", txids[1]);
@@ -639,6 +1003,276 @@ impl BitcoinService {
 
         Ok(new_txid)
     }
+
+    /// Rebuilds unconfirmed blobs with a higher fee rate once the oldest of them has been waiting
+    /// for `fee_bump_after_blocks` blocks. Replacing its first commit evicts every later blob
+    /// chained on it from the mempool, so those are rebuilt on top of the replacement as well.
+    #[instrument(level = "trace", skip_all, err)]
+    async fn bump_pending_blobs(&self, pending_blobs: &mut Vec<PendingBlob>) -> Result<()> {
+        // Blobs are only dropped once their reveal is confirmed, a failed lookup keeps the rest
+        let mut i = 0;
+        while i < pending_blobs.len() {
+            let reveal_txid = pending_blobs[i].txids.last().expect("Blob has txs");
+            let confirmations = self
+                .client
+                .get_transaction(reveal_txid, None)
+                .await?
+                .info
+                .confirmations;
+            if confirmations > 0 {
+                pending_blobs.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        let current_height = self.client.get_block_count().await?;
+        let Some(stuck) = pending_blobs.iter().position(|blob| {
+            current_height.saturating_sub(blob.broadcast_height) >= self.fee_bump_after_blocks
+        }) else {
+            return Ok(());
+        };
+
+        let prev_fee_rate = pending_blobs[stuck].fee_rate;
+        let fee_rate = bumped_fee_rate(
+            prev_fee_rate,
+            self.fee
+                .get_fee_rate(fee_priority(&pending_blobs[stuck].da_data))
+                .await?,
+            self.max_fee_rate,
+        );
+        if fee_rate <= prev_fee_rate {
+            debug!(fee_rate, "DA txs are stuck at max fee rate");
+            return Ok(());
+        }
+
+        let replaced_txids: HashSet<Txid> = pending_blobs[stuck..]
+            .iter()
+            .flat_map(|blob| blob.txids.iter().copied())
+            .collect();
+
+        // RBF requires the replacement to pay for every tx it evicts on top of its own fee
+        let mut replaced_fee = 0;
+        for txid in &replaced_txids {
+            if let Ok(entry) = self.client.get_mempool_entry(txid).await {
+                replaced_fee += entry.fees.base.to_sat();
+            }
+        }
+
+        for i in stuck..pending_blobs.len() {
+            let txids = match self
+                .rebuild_pending_blob(
+                    pending_blobs,
+                    i,
+                    stuck,
+                    &replaced_txids,
+                    replaced_fee,
+                    fee_rate,
+                )
+                .await
+            {
+                Ok(txids) => txids,
+                // Nothing is replaced yet, the stuck blob is bumped again on the next tick
+                Err(e) if i == stuck => return Err(e),
+                Err(e) => {
+                    // The rest are evicted along with the replaced blob, so they are sent anew
+                    self.requeue_blobs(pending_blobs.drain(i..));
+                    return Err(e);
+                }
+            };
+
+            let blob = &mut pending_blobs[i];
+            let prev_reveal_txid = *blob.txids.last().expect("Blob has txs");
+            let reveal_txid = *txids.last().expect("Blob has txs");
+            info!(
+                %prev_reveal_txid,
+                %reveal_txid,
+                fee_rate,
+                "Bumped fee of DA tx"
+            );
+
+            if let Err(e) = self
+                .monitoring
+                .replace_transaction_chain(&blob.txids, txids.clone())
+                .await
+            {
+                error!(?e, "Failed to monitor replaced tx chain");
+            }
+            self.remove_tx_backup(&prev_reveal_txid);
+            // No subscribers is not an error
+            let _ = self
+                .replaced_txs
+                .send((TxidWrapper(prev_reveal_txid), TxidWrapper(reveal_txid)));
+
+            blob.txids = txids;
+            blob.fee_rate = fee_rate;
+            blob.broadcast_height = current_height;
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds the `i`th pending blob at `fee_rate`, replacing the stuck one or chaining
+    /// on the rebuilt previous blob
+    async fn rebuild_pending_blob(
+        &self,
+        pending_blobs: &[PendingBlob],
+        i: usize,
+        stuck: usize,
+        replaced_txids: &HashSet<Txid>,
+        replaced_fee: u64,
+        fee_rate: u64,
+    ) -> Result<Vec<Txid>> {
+        let commit = self
+            .client
+            .get_transaction(&pending_blobs[i].txids[0], None)
+            .await?
+            .transaction()?;
+
+        // Spend the same first input so the rebuilt blob conflicts with the stuck one,
+        // or chain it on the rebuilt reveal of the previous pending blob
+        let prev_utxo = match i.checked_sub(1).map(|prev| &pending_blobs[prev]) {
+            Some(prev_blob) => {
+                let reveal_txid = *prev_blob.txids.last().expect("Blob has txs");
+                self.get_wallet_utxo(OutPoint::new(reveal_txid, 0)).await?
+            }
+            None => {
+                let first_input = commit.input.first().context("Commit tx has no inputs")?;
+                self.get_wallet_utxo(first_input.previous_output).await?
+            }
+        };
+
+        let mut outpoints = HashSet::from([OutPoint::new(prev_utxo.tx_id, prev_utxo.vout)]);
+        let mut utxos = vec![];
+        for input in commit.input.iter().skip(1) {
+            if !replaced_txids.contains(&input.previous_output.txid)
+                && outpoints.insert(input.previous_output)
+            {
+                utxos.push(self.get_wallet_utxo(input.previous_output).await?);
+            }
+        }
+        // Additional funds in case the higher fee is not covered by the previous inputs
+        for utxo in self.get_utxos().await.unwrap_or_default() {
+            if !replaced_txids.contains(&utxo.tx_id)
+                && outpoints.insert(OutPoint::new(utxo.tx_id, utxo.vout))
+            {
+                utxos.push(utxo);
+            }
+        }
+
+        let commit_fee_rate = if i == stuck {
+            fee_rate + replaced_fee.div_ceil(commit.vsize() as u64)
+        } else {
+            fee_rate
+        };
+
+        let blob = &pending_blobs[i];
+        self.inscribe(
+            blob.da_data.clone(),
+            blob.spec_id,
+            Some(prev_utxo),
+            utxos,
+            commit_fee_rate,
+            fee_rate,
+            i == stuck,
+        )
+        .await
+    }
+
+    /// Sends blobs evicted from the mempool through the DA queue again
+    fn requeue_blobs(&self, blobs: impl Iterator<Item = PendingBlob>) {
+        for blob in blobs {
+            warn!(reveal_txid = %blob.txids.last().expect("Blob has txs"), "Requeueing evicted DA tx");
+            // The original request is notified already
+            let (notify, _) = oneshot_channel();
+            let request = SenderWithNotifier {
+                da_data: blob.da_data,
+                spec_id: blob.spec_id,
+                notify,
+            };
+            if self.inscribes_queue.send(request).is_err() {
+                error!("DA queue is closed, evicted DA tx is dropped");
+            }
+        }
+    }
+
+    /// Loads the blobs left unconfirmed by the previous run
+    fn load_pending_blobs(&self) -> Vec<PendingBlob> {
+        let path = self.tx_backup_dir.join(PENDING_BLOBS_FILE);
+        if !path.exists() {
+            return vec![];
+        }
+
+        match std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| serde_json::from_slice(&data).map_err(Into::into))
+        {
+            Ok(pending_blobs) => pending_blobs,
+            Err(e) => {
+                error!(
+                    ?e,
+                    "Failed to load pending DA txs, their fees will not be bumped"
+                );
+                vec![]
+            }
+        }
+    }
+
+    /// Persists the unconfirmed blobs, so their fees are still bumped after a restart.
+    /// Written to a temporary file first, so a crash never leaves a truncated file behind.
+    fn save_pending_blobs(&self, pending_blobs: &[PendingBlob]) {
+        let path = self.tx_backup_dir.join(PENDING_BLOBS_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        let result = serde_json::to_vec(pending_blobs)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| std::fs::write(&tmp_path, data).map_err(Into::into))
+            .and_then(|_| std::fs::rename(&tmp_path, &path).map_err(Into::into));
+        if let Err(e) = result {
+            error!(?e, "Failed to persist pending DA txs");
+        }
+    }
+
+    /// Builds a UTXO out of a wallet tx output, even if it is already spent in the mempool
+    async fn get_wallet_utxo(&self, outpoint: OutPoint) -> Result<UTXO> {
+        let tx = self
+            .client
+            .get_transaction(&outpoint.txid, None)
+            .await?
+            .transaction()?;
+        let output = tx
+            .output
+            .get(outpoint.vout as usize)
+            .context("Missing tx output")?;
+
+        Ok(UTXO {
+            tx_id: outpoint.txid,
+            vout: outpoint.vout,
+            address: Address::from_script(&output.script_pubkey, self.network)
+                .ok()
+                .map(|address| address.as_unchecked().clone()),
+            script_pubkey: output.script_pubkey.to_hex_string(),
+            amount: output.value.to_sat(),
+            confirmations: 0,
+            spendable: true,
+            solvable: true,
+        })
+    }
+
+    /// Removes the backup file of a replaced blob, its rebuild is written under the new reveal id
+    fn remove_tx_backup(&self, reveal_txid: &Txid) {
+        let suffix = format!("_with_reveal_id_{reveal_txid}.txs");
+        let Ok(entries) = std::fs::read_dir(&self.tx_backup_dir) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().ends_with(&suffix) {
+                if let Err(e) = std::fs::remove_file(entry.path()) {
+                    warn!(?e, "Failed to remove backup of replaced DA txs");
+                }
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -724,14 +1358,13 @@ impl DaService for BitcoinService {
     ) -> Result<Vec<Proof>> {
         let mut completes = Vec::new();
         let mut aggregate_idxs = Vec::new();
+        let mut aggregate_v2_idxs = Vec::new();
+        // chunks of V2 aggregates are looked up by the wtxids listed in their aggregate
+        let mut chunks = BTreeMap::new();
 
         for (i, tx) in block.txdata.iter().enumerate() {
-            if !tx
-                .compute_wtxid()
-                .to_byte_array()
-                .as_slice()
-                .starts_with(&self.to_light_client_prefix)
-            {
+            let wtxid = tx.compute_wtxid().to_byte_array();
+            if !wtxid.as_slice().starts_with(&self.to_light_client_prefix) {
                 continue;
             }
 
@@ -744,7 +1377,7 @@ impl DaService for BitcoinService {
                         {
                             // push only when signature is correct
                             let body = decompress_blob(&complete.body);
-                            let data = DaDataLightClient::try_from_slice(&body)
+                            let data = DaDataLightClient::decode_versioned(&body)
                                 .map_err(|e| anyhow!("{}: Failed to parse complete: {e}", tx_id))?;
                             let DaDataLightClient::Complete(zk_proof) = data else {
                                 bail!("{}: Complete: unexpected kind", tx_id);
@@ -764,6 +1397,18 @@ impl DaService for BitcoinService {
                     ParsedLightClientTransaction::Chunk(_chunk) => {
                         // we ignore them for now
                     }
+                    ParsedLightClientTransaction::AggregateV2(aggregate) => {
+                        if aggregate.public_key() == prover_da_pub_key
+                            && aggregate.get_sig_verified_hash().is_some()
+                        {
+                            // push only when signature is correct
+                            // collect chunk wtxids
+                            aggregate_v2_idxs.push((i, tx_id, aggregate));
+                        }
+                    }
+                    ParsedLightClientTransaction::ChunkV2(chunk) => {
+                        chunks.insert(wtxid, chunk.body);
+                    }
                 }
             }
         }
@@ -772,16 +1417,13 @@ impl DaService for BitcoinService {
         let mut aggregates = Vec::new();
         'aggregate: for (i, tx_id, aggregate) in aggregate_idxs {
             let mut body = Vec::new();
-            let data = DaDataLightClient::try_from_slice(&aggregate.body)
-                .map_err(|e| anyhow!("{}: Failed to parse aggregate: {e}", tx_id))?;
-            let DaDataLightClient::Aggregate(chunk_ids) = data else {
-                error!("{}: Aggregate: unexpected kind", tx_id);
-                continue;
+            let chunk_ids = match parse_aggregate(&aggregate.body) {
+                Ok(chunk_ids) => chunk_ids,
+                Err(e) => {
+                    error!("{}: Failed to parse aggregate: {e}", tx_id);
+                    continue;
+                }
             };
-            if chunk_ids.is_empty() {
-                error!("{}: Empty aggregate tx list", tx_id);
-                continue;
-            }
             for chunk_id in chunk_ids {
                 let chunk_id = Txid::from_byte_array(chunk_id);
                 let tx_raw = {
@@ -817,7 +1459,7 @@ impl DaService for BitcoinService {
                 };
                 match parsed {
                     ParsedLightClientTransaction::Chunk(part) => {
-                        let data = DaDataLightClient::try_from_slice(&part.body)
+                        let data = DaDataLightClient::decode_versioned(&part.body)
                             .map_err(|e| anyhow!("{}: Failed to parse chunk: {e}", tx_id))?;
                         let DaDataLightClient::Chunk(chunk) = data else {
                             bail!("{}: Chunk: unexpected kind", tx_id);
@@ -825,7 +1467,9 @@ impl DaService for BitcoinService {
                         body.extend(chunk);
                     }
                     ParsedLightClientTransaction::Complete(_)
-                    | ParsedLightClientTransaction::Aggregate(_) => {
+                    | ParsedLightClientTransaction::Aggregate(_)
+                    | ParsedLightClientTransaction::AggregateV2(_)
+                    | ParsedLightClientTransaction::ChunkV2(_) => {
                         error!("{}:{}: Expected chunk, got other tx kind", tx_id, chunk_id);
                         continue 'aggregate;
                     }
@@ -836,6 +1480,15 @@ impl DaService for BitcoinService {
             aggregates.push((i, zk_proof));
         }
 
+        // reassemble V2 aggregated proofs from the chunks in the block
+        for (i, tx_id, aggregate) in aggregate_v2_idxs {
+            let chunk_wtxids = parse_aggregate(&aggregate.body)
+                .map_err(|e| anyhow!("{}: Failed to parse aggregate: {e}", tx_id))?;
+            let zk_proof = decode_chunks(&chunk_wtxids, &chunks)
+                .map_err(|e| anyhow!("{}: Failed to reassemble aggregate: {e}", tx_id))?;
+            aggregates.push((i, zk_proof));
+        }
+
         let mut proofs: Vec<_> = completes.into_iter().chain(aggregates).collect();
         // restore the order of tx they appear in the block
         proofs.sort_by_key(|b| b.0);
@@ -871,7 +1524,7 @@ impl DaService for BitcoinService {
                         if seq_comm.get_sig_verified_hash().is_some()
                             && seq_comm.public_key() == sequencer_da_pub_key
                         {
-                            let data = DaDataBatchProof::try_from_slice(&seq_comm.body);
+                            let data = DaDataBatchProof::decode_versioned(&seq_comm.body);
                             if let Ok(DaDataBatchProof::SequencerCommitment(seq_com)) = data {
                                 sequencer_commitments.push(seq_com);
                             }
@@ -947,6 +1600,7 @@ impl DaService for BitcoinService {
             InclusionMultiProof::new(wtxids, block.txdata[0].clone(), coinbase_proof);
 
         let mut relevant_txs = vec![];
+        let mut chunks = BTreeMap::new();
         for tx in &completeness_proof {
             match namespace {
                 DaNamespace::ToBatchProver => {
@@ -992,6 +1646,35 @@ impl DaService for BitcoinService {
                             ParsedLightClientTransaction::Chunk(_) => {
                                 // ignore
                             }
+                            ParsedLightClientTransaction::AggregateV2(aggregate) => {
+                                if let Some(hash) = aggregate.get_sig_verified_hash() {
+                                    // chunks precede their aggregate in the block,
+                                    // the reassembled proof is passed on as a complete one,
+                                    // in raw borsh which every guest decodes
+                                    let blob = match parse_aggregate(&aggregate.body)
+                                        .and_then(|wtxids| decode_chunks(&wtxids, &chunks))
+                                    {
+                                        Ok(zk_proof) => {
+                                            borsh::to_vec(&DaDataLightClient::Complete(zk_proof))
+                                                .expect("DaDataLightClient serialize must not fail")
+                                        }
+                                        Err(e) => {
+                                            error!(
+                                                "{}: Failed to reassemble aggregate: {e}",
+                                                tx.compute_txid()
+                                            );
+                                            aggregate.body
+                                        }
+                                    };
+                                    let relevant_tx =
+                                        BlobWithSender::new(blob, aggregate.public_key, hash);
+
+                                    relevant_txs.push(relevant_tx);
+                                }
+                            }
+                            ParsedLightClientTransaction::ChunkV2(chunk) => {
+                                chunks.insert(tx.compute_wtxid().to_byte_array(), chunk.body);
+                            }
                         }
                     }
                 }
@@ -1005,11 +1688,13 @@ impl DaService for BitcoinService {
     async fn send_transaction(
         &self,
         da_data: DaData,
+        spec_id: SpecId,
     ) -> Result<<Self as DaService>::TransactionId> {
         let queue = self.get_send_transaction_queue();
         let (tx, rx) = oneshot_channel();
         queue.send(SenderWithNotifier {
             da_data,
+            spec_id,
             notify: tx,
         })?;
         rx.await?
@@ -1021,9 +1706,15 @@ impl DaService for BitcoinService {
         self.inscribes_queue.clone()
     }
 
+    fn subscribe_replaced_transactions(
+        &self,
+    ) -> broadcast::Receiver<(Self::TransactionId, Self::TransactionId)> {
+        self.replaced_txs.subscribe()
+    }
+
     #[instrument(level = "trace", skip(self))]
     async fn get_fee_rate(&self) -> Result<u128> {
-        let sat_vb_ceil = self.fee.get_fee_rate_as_sat_vb().await? as u128;
+        let sat_vb_ceil = self.fee.get_fee_rate(FeePriority::High).await? as u128;
 
         // multiply with 10^10/4 = 25*10^8 = 2_500_000_000 for BTC to CBTC conversion (decimals)
         let multiplied_fee = sat_vb_ceil.saturating_mul(2_500_000_000);
@@ -1087,7 +1778,7 @@ impl DaService for BitcoinService {
                         if seq_comm.get_sig_verified_hash().is_some()
                             && seq_comm.public_key == sequencer_da_pub_key
                         {
-                            let da_data = DaDataBatchProof::try_from_slice(&seq_comm.body);
+                            let da_data = DaDataBatchProof::decode_versioned(&seq_comm.body);
                             match da_data {
                                 Ok(da_data) => match da_data {
                                     DaDataBatchProof::SequencerCommitment(commitment) => {
@@ -1139,7 +1830,7 @@ pub fn get_relevant_blobs_from_txs(
     relevant_txs
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, core::hash::Hash)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, core::hash::Hash)]
 pub struct TxidWrapper(Txid);
 impl From<TxidWrapper> for [u8; 32] {
     fn from(val: TxidWrapper) -> Self {
@@ -1147,28 +1838,56 @@ impl From<TxidWrapper> for [u8; 32] {
     }
 }
 
+// Packages replacing mempool txs are rejected by testmempoolaccept,
+// so only the replacing commit can be tested before broadcasting
+/// Sequencer commitments gate the finality of L2 blocks, so they are sent with a higher priority
+/// than zk proofs.
+fn fee_priority(da_data: &DaData) -> FeePriority {
+    match da_data {
+        DaData::SequencerCommitment(_) => FeePriority::High,
+        DaData::ZKProof(_) => FeePriority::Medium,
+    }
+}
+
+/// Wallet UTXOs that can fund DA txs, those at or below the reveal output amount are left out
+fn spendable_utxos(utxos: Vec<ListUnspentResultEntry>) -> impl Iterator<Item = UTXO> {
+    utxos
+        .into_iter()
+        .filter(|utxo| {
+            utxo.spendable && utxo.solvable && utxo.amount > Amount::from_sat(REVEAL_OUTPUT_AMOUNT)
+        })
+        .map(Into::into)
+}
+
+fn mempool_test_txs(raw_txs: &[Vec<u8>], replacement: bool) -> &[Vec<u8>] {
+    if replacement {
+        &raw_txs[..1]
+    } else {
+        raw_txs
+    }
+}
+
 /// This function splits Proof based on its size. It is either:
-/// 1: compress(borsh(DaDataLightClient::Complete(Proof)))
+/// 1: compress(versioned(DaDataLightClient::Complete(Proof)))
 /// 2:
 ///   let compressed = compress(borsh(Proof))
-///   let chunks = compressed.chunks(MAX_TXBODY_SIZE)
-///   [borsh(DaDataLightClient::Chunk(chunk)) for chunk in chunks]
-fn split_proof(zk_proof: Proof) -> RawLightClientData {
+///   let chunks = compressed.chunks(chunk_threshold)
+///   [versioned(DaDataLightClient::Chunk(chunk)) for chunk in chunks]
+/// where versioned is the encoding of `spec_id`.
+fn split_proof(zk_proof: Proof, chunk_threshold: usize, spec_id: SpecId) -> RawLightClientData {
     let original_blob = borsh::to_vec(&zk_proof).expect("zk::Proof serialize must not fail");
     let original_compressed = compress_blob(&original_blob);
-    if original_compressed.len() < MAX_TXBODY_SIZE {
+    if original_compressed.len() < chunk_threshold {
         let data = DaDataLightClient::Complete(zk_proof);
-        let blob = borsh::to_vec(&data).expect("zk::Proof serialize must not fail");
+        let blob = data.encode_versioned(spec_id);
         let blob = compress_blob(&blob);
         RawLightClientData::Complete(blob)
     } else {
-        let mut chunks = vec![];
-        for chunk in original_compressed.chunks(MAX_TXBODY_SIZE) {
-            let data = DaDataLightClient::Chunk(chunk.to_vec());
-            let blob = borsh::to_vec(&data).expect("zk::Proof Chunk serialize must not fail");
-            chunks.push(blob)
-        }
-        RawLightClientData::Chunks(chunks)
+        RawLightClientData::Chunks(encode_chunks(
+            &original_compressed,
+            chunk_threshold,
+            spec_id,
+        ))
     }
 }
 
@@ -1,6 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use sov_rollup_interface::da::DaSpec;
+use sov_rollup_interface::Network;
 
 use self::address::AddressWrapper;
 use self::blob::BlobWithSender;
@@ -26,6 +27,48 @@ pub struct BitcoinSpec;
 pub struct RollupParams {
     pub to_light_client_prefix: Vec<u8>,
     pub to_batch_proof_prefix: Vec<u8>,
+    /// Bitcoin network whose consensus rules the header chain is verified with
+    pub network: BitcoinNetwork,
+}
+
+/// Bitcoin networks with different difficulty adjustment rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitcoinNetwork {
+    Mainnet,
+    /// Allows minimum difficulty blocks and enforces the BIP94 timewarp fix
+    Testnet4,
+    Signet,
+    /// Allows minimum difficulty blocks and never adjusts the difficulty
+    Regtest,
+}
+
+impl BitcoinNetwork {
+    /// Bitcoin network Citrea runs on for each of its networks
+    pub const fn from_network(network: Network) -> Self {
+        match network {
+            Network::Mainnet => BitcoinNetwork::Mainnet,
+            Network::Testnet => BitcoinNetwork::Testnet4,
+            Network::Devnet => BitcoinNetwork::Signet,
+            Network::Nightly => BitcoinNetwork::Regtest,
+        }
+    }
+
+    /// Whether a block found more than 20 minutes after the previous one can have the
+    /// minimum difficulty
+    pub const fn allows_min_difficulty_blocks(self) -> bool {
+        matches!(self, BitcoinNetwork::Testnet4 | BitcoinNetwork::Regtest)
+    }
+
+    /// Whether the difficulty is adjusted at epoch boundaries
+    pub const fn adjusts_difficulty(self) -> bool {
+        !matches!(self, BitcoinNetwork::Regtest)
+    }
+
+    /// Whether the first block of an epoch can not be more than 10 minutes older than the
+    /// last block of the previous epoch, see BIP94
+    pub const fn enforces_bip94(self) -> bool {
+        matches!(self, BitcoinNetwork::Testnet4)
+    }
 }
 
 impl DaSpec for BitcoinSpec {
@@ -1,19 +1,22 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use bitcoin::hashes::Hash;
 use citrea_primitives::compression::decompress_blob;
 use crypto_bigint::{Encoding, U256};
-use itertools::Itertools;
 use sov_rollup_interface::da::{
-    BlobReaderTrait, BlockHeaderTrait, DaNamespace, DaSpec, DaVerifier, UpdatedDaState,
+    BlobReaderTrait, BlockHeaderTrait, DaDataLightClient, DaNamespace, DaSpec, DaVerifier,
+    UpdatedDaState,
 };
 use sov_rollup_interface::zk::LightClientCircuitOutput;
 
+use crate::helpers::chunks::{decode_chunks, parse_aggregate};
 use crate::helpers::parsers::{
     parse_batch_proof_transaction, parse_light_client_transaction, ParsedBatchProofTransaction,
     ParsedLightClientTransaction, VerifyParsed,
 };
 use crate::helpers::{calculate_double_sha256, merkle_tree};
 use crate::spec::blob::BlobWithSender;
-use crate::spec::BitcoinSpec;
+use crate::spec::{BitcoinNetwork, BitcoinSpec};
 
 pub const WITNESS_COMMITMENT_PREFIX: &[u8] = &[0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
 
@@ -21,6 +24,21 @@ pub const WITNESS_COMMITMENT_PREFIX: &[u8] = &[0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xe
 const MAX_TARGET: U256 =
     U256::from_be_hex("00000000FFFF0000000000000000000000000000000000000000000000000000");
 
+/// The maximum target value of signet
+const SIGNET_MAX_TARGET: U256 =
+    U256::from_be_hex("00000377AE000000000000000000000000000000000000000000000000000000");
+
+/// The maximum target value of regtest
+const REGTEST_MAX_TARGET: U256 =
+    U256::from_be_hex("7FFFFF0000000000000000000000000000000000000000000000000000000000");
+
+/// Expected time between blocks, 10 minutes
+const TARGET_SPACING: u32 = 60 * 10;
+
+/// How much older than the last block of the previous epoch the first block of an epoch can be
+/// with BIP94, 10 minutes
+const MAX_TIMEWARP: u32 = 60 * 10;
+
 /// An epoch should be two weeks (represented as number of seconds)
 /// seconds/minute * minutes/hour * hours/day * 14 days
 const EXPECTED_EPOCH_TIMESPAN: u32 = 60 * 60 * 24 * 14;
@@ -31,6 +49,7 @@ const BLOCKS_PER_EPOCH: u64 = 2016;
 pub struct BitcoinVerifier {
     to_batch_proof_prefix: Vec<u8>,
     to_light_client_prefix: Vec<u8>,
+    network: BitcoinNetwork,
 }
 
 // TODO: custom errors based on our implementation
@@ -56,6 +75,8 @@ pub enum ValidationError {
     InvalidTargetHash,
     InvalidTimestamp,
     HeaderInclusionTxCountMismatch,
+    DuplicateTxInCompletenessProof,
+    UnorderedCompletenessProof,
 }
 
 impl DaVerifier for BitcoinVerifier {
@@ -67,6 +88,7 @@ impl DaVerifier for BitcoinVerifier {
         Self {
             to_batch_proof_prefix: params.to_batch_proof_prefix,
             to_light_client_prefix: params.to_light_client_prefix,
+            network: params.network,
         }
     }
 
@@ -91,16 +113,16 @@ impl DaVerifier for BitcoinVerifier {
             DaNamespace::ToLightClientProver => self.to_light_client_prefix.as_slice(),
         };
 
-        let relevant_wtxid_iter = inclusion_proof
-            .wtxids
+        // chunks of V2 aggregates in the block, by wtxid
+        let mut chunks = BTreeMap::new();
+
+        let proof_wtxids = completeness_proof
             .iter()
-            .filter(|wtxid| wtxid.starts_with(prefix));
-        for (wtxid, tx) in relevant_wtxid_iter.zip_eq(&completeness_proof) {
-            // ensure completeness proof tx matches the inclusion tx
-            if tx.compute_wtxid().as_byte_array() != wtxid {
-                return Err(ValidationError::RelevantTxNotInProof);
-            }
+            .map(|tx| tx.compute_wtxid().to_byte_array())
+            .collect::<Vec<_>>();
+        verify_completeness_proof(&inclusion_proof.wtxids, prefix, &proof_wtxids)?;
 
+        for (wtxid, tx) in proof_wtxids.iter().zip(&completeness_proof) {
             // it must be parsed correctly
             match namespace {
                 DaNamespace::ToBatchProver => {
@@ -146,6 +168,33 @@ impl DaVerifier for BitcoinVerifier {
                             ParsedLightClientTransaction::Chunk(_chunk) => {
                                 // ignore
                             }
+                            // Reassembling V2 aggregates changes the light client proof circuit,
+                            // the guests reading them are built with a new method id
+                            ParsedLightClientTransaction::AggregateV2(aggregate) => {
+                                if let Some(blob_content) =
+                                    verified_blob_content(&aggregate, &mut blobs_iter)?
+                                {
+                                    // the blob is the reassembled proof if all the chunks are in the block,
+                                    // otherwise the aggregate body itself
+                                    let reassembled = parse_aggregate(&aggregate.body)
+                                        .and_then(|wtxids| decode_chunks(&wtxids, &chunks));
+                                    // assert tx content is not modified
+                                    let is_modified = match reassembled {
+                                        Ok(zk_proof) => {
+                                            borsh::to_vec(&DaDataLightClient::Complete(zk_proof))
+                                                .expect("DaDataLightClient serialize must not fail")
+                                                != blob_content
+                                        }
+                                        Err(_) => blob_content != aggregate.body,
+                                    };
+                                    if is_modified {
+                                        return Err(ValidationError::BlobContentWasModified);
+                                    }
+                                }
+                            }
+                            ParsedLightClientTransaction::ChunkV2(chunk) => {
+                                chunks.insert(*wtxid, chunk.body);
+                            }
                         }
                     }
                 }
@@ -254,43 +303,22 @@ impl DaVerifier for BitcoinVerifier {
         if block_header.prev_hash() != previous_light_client_proof_output.da_block_hash {
             return Err(ValidationError::InvalidPrevBlockHash);
         }
-        // Check 4: valid bits
-        if block_header.bits() != previous_light_client_proof_output.da_current_target_bits {
-            return Err(ValidationError::InvalidBlockBits);
-        }
+        // Check 4: bits and timestamp follow the difficulty adjustment and timestamp rules
+        let chain_time_state = verify_difficulty_and_time(
+            self.network,
+            &ChainTimeState {
+                epoch_start_time: previous_light_client_proof_output.da_epoch_start_time,
+                prev_11_timestamps: previous_light_client_proof_output.da_prev_11_timestamps,
+                current_target_bits: previous_light_client_proof_output.da_current_target_bits,
+            },
+            block_header.height(),
+            block_header.time().secs() as u32,
+            block_header.bits(),
+        )?;
         // Check 5: proof of work
         if !verify_target_hash(block_header.hash().into(), target) {
             return Err(ValidationError::InvalidTargetHash);
         }
-        // Check 6: valid timestamp
-        if !verify_timestamp(
-            block_header.time().secs() as u32,
-            previous_light_client_proof_output.da_prev_11_timestamps,
-        ) {
-            return Err(ValidationError::InvalidTimestamp);
-        }
-
-        let epoch_block = block_header.height() % BLOCKS_PER_EPOCH;
-        // Check if this is epoch block, and update time accordingly
-        let mut epoch_start_time = previous_light_client_proof_output.da_epoch_start_time;
-        if epoch_block == 0 {
-            epoch_start_time = block_header.time().secs() as u32;
-        }
-
-        // Update previous timestamps
-        let mut prev_11_timestamps = previous_light_client_proof_output.da_prev_11_timestamps;
-        prev_11_timestamps[block_header.height() as usize % 11] = block_header.time().secs() as u32;
-
-        // If the next block is epoch start block, calculate the next epoch's difficulty target
-        let mut current_target_bits = block_header.bits();
-        if epoch_block == BLOCKS_PER_EPOCH - 1 {
-            let next_target = calculate_new_difficulty(
-                epoch_start_time,
-                block_header.time().secs() as u32,
-                block_header.bits(),
-            );
-            current_target_bits = target_to_bits(&next_target);
-        }
 
         let total_work = U256::from_be_bytes(previous_light_client_proof_output.da_total_work)
             .saturating_add(&work_add)
@@ -300,13 +328,97 @@ impl DaVerifier for BitcoinVerifier {
             hash: block_header.hash(),
             height: block_header.height(),
             total_work,
-            epoch_start_time,
-            prev_11_timestamps,
-            current_target_bits,
+            epoch_start_time: chain_time_state.epoch_start_time,
+            prev_11_timestamps: chain_time_state.prev_11_timestamps,
+            current_target_bits: chain_time_state.current_target_bits,
         })
     }
 }
 
+/// Difficulty and time related state of the header chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChainTimeState {
+    /// Timestamp of the first block of the current epoch
+    epoch_start_time: u32,
+    /// Timestamps of the last 11 blocks, the one of height `h` is at `h % 11`
+    prev_11_timestamps: [u32; 11],
+    /// Bits of the blocks of the current epoch which are not minimum difficulty blocks
+    current_target_bits: u32,
+}
+
+/// Checks that a header at `height` with the given timestamp and bits extends a chain in
+/// `state` with the difficulty adjustment and timestamp rules of `network`.
+/// Returns the state of the chain extended with the header.
+fn verify_difficulty_and_time(
+    network: BitcoinNetwork,
+    state: &ChainTimeState,
+    height: u64,
+    time: u32,
+    bits: u32,
+) -> Result<ChainTimeState, ValidationError> {
+    let prev_time = state.prev_11_timestamps[((height - 1) % 11) as usize];
+    let epoch_block = height % BLOCKS_PER_EPOCH;
+
+    // Bits of the epoch, or the minimum difficulty for late blocks within an epoch if allowed
+    let expected_bits = if network.allows_min_difficulty_blocks()
+        && epoch_block != 0
+        && time > prev_time.saturating_add(TARGET_SPACING * 2)
+    {
+        target_to_bits(&max_target(network).to_be_bytes())
+    } else {
+        state.current_target_bits
+    };
+    if bits != expected_bits {
+        return Err(ValidationError::InvalidBlockBits);
+    }
+
+    if !verify_timestamp(time, state.prev_11_timestamps) {
+        return Err(ValidationError::InvalidTimestamp);
+    }
+    if network.enforces_bip94() && epoch_block == 0 && time < prev_time.saturating_sub(MAX_TIMEWARP)
+    {
+        return Err(ValidationError::InvalidTimestamp);
+    }
+
+    // Check if this is epoch block, and update time accordingly
+    let mut epoch_start_time = state.epoch_start_time;
+    if epoch_block == 0 {
+        epoch_start_time = time;
+    }
+
+    // Update previous timestamps
+    let mut prev_11_timestamps = state.prev_11_timestamps;
+    prev_11_timestamps[(height % 11) as usize] = time;
+
+    // If the next block is epoch start block, calculate the next epoch's difficulty target
+    // from the bits of the epoch, minimum difficulty blocks do not count
+    let mut current_target_bits = state.current_target_bits;
+    if network.adjusts_difficulty() && epoch_block == BLOCKS_PER_EPOCH - 1 {
+        let next_target = calculate_new_difficulty(
+            epoch_start_time,
+            time,
+            state.current_target_bits,
+            max_target(network),
+        );
+        current_target_bits = target_to_bits(&next_target);
+    }
+
+    Ok(ChainTimeState {
+        epoch_start_time,
+        prev_11_timestamps,
+        current_target_bits,
+    })
+}
+
+/// The maximum target value of the network, which corresponds to its minimum difficulty
+const fn max_target(network: BitcoinNetwork) -> U256 {
+    match network {
+        BitcoinNetwork::Mainnet | BitcoinNetwork::Testnet4 => MAX_TARGET,
+        BitcoinNetwork::Signet => SIGNET_MAX_TARGET,
+        BitcoinNetwork::Regtest => REGTEST_MAX_TARGET,
+    }
+}
+
 // Get associated blob content only if signatures, hashes and public keys match
 fn verified_blob_content<'a, T, I>(
     tx: &T,
@@ -415,9 +527,11 @@ fn calculate_new_difficulty(
     epoch_start_time: u32,
     last_timestamp: u32,
     current_target: u32,
+    max_target: U256,
 ) -> [u8; 32] {
     // Step 1: Calculate the actual timespan of the epoch
-    let mut actual_timespan = last_timestamp - epoch_start_time;
+    // timestamps are not monotonic, the last block can be older than the first one
+    let mut actual_timespan = last_timestamp.saturating_sub(epoch_start_time);
     if actual_timespan < EXPECTED_EPOCH_TIMESPAN / 4 {
         actual_timespan = EXPECTED_EPOCH_TIMESPAN / 4;
     } else if actual_timespan > EXPECTED_EPOCH_TIMESPAN * 4 {
@@ -429,9 +543,418 @@ fn calculate_new_difficulty(
         .wrapping_mul(&U256::from(actual_timespan))
         .wrapping_div(&U256::from(EXPECTED_EPOCH_TIMESPAN));
     // Step 3: Clamp the new target to the maximum target
-    if new_target > MAX_TARGET {
-        new_target = MAX_TARGET;
+    if new_target > max_target {
+        new_target = max_target;
     }
 
     new_target.to_be_bytes()
 }
+
+/// Verifies that the completeness proof is made of the relevant transactions of the block,
+/// the ones whose wtxid starts with `prefix`, each exactly once and in block order.
+fn verify_completeness_proof(
+    block_wtxids: &[[u8; 32]],
+    prefix: &[u8],
+    proof_wtxids: &[[u8; 32]],
+) -> Result<(), ValidationError> {
+    // position of the relevant txs in the block, by wtxid
+    let mut relevant_positions = BTreeMap::new();
+    let mut relevant_count = 0;
+    for (position, wtxid) in block_wtxids.iter().enumerate() {
+        if wtxid.starts_with(prefix) {
+            relevant_positions.entry(wtxid).or_insert(position);
+            relevant_count += 1;
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut last_position = None;
+    for wtxid in proof_wtxids {
+        if !seen.insert(wtxid) {
+            return Err(ValidationError::DuplicateTxInCompletenessProof);
+        }
+        let Some(&position) = relevant_positions.get(wtxid) else {
+            return Err(ValidationError::NonRelevantTxInProof);
+        };
+        if last_position.is_some_and(|last_position| position <= last_position) {
+            return Err(ValidationError::UnorderedCompletenessProof);
+        }
+        last_position = Some(position);
+    }
+
+    if proof_wtxids.len() != relevant_count {
+        return Err(ValidationError::RelevantTxNotInProof);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::collection::vec;
+    use proptest::sample::subsequence;
+    use proptest::strategy::Strategy;
+
+    use super::*;
+    use crate::helpers::parsers::parse_hex_transaction;
+
+    const MAINNET_BITS: u32 = 0x1d00ffff;
+    const REGTEST_BITS: u32 = 0x207fffff;
+    const START_TIME: u32 = 1_700_000_000;
+
+    /// State of a chain whose block at `height` has `time`, with blocks `spacing` seconds apart
+    fn chain_state(
+        height: u64,
+        time: u32,
+        spacing: u32,
+        epoch_start_time: u32,
+        current_target_bits: u32,
+    ) -> ChainTimeState {
+        let mut prev_11_timestamps = [0; 11];
+        for i in 0..11 {
+            prev_11_timestamps[((height - i) % 11) as usize] = time - i as u32 * spacing;
+        }
+        ChainTimeState {
+            epoch_start_time,
+            prev_11_timestamps,
+            current_target_bits,
+        }
+    }
+
+    #[test]
+    fn test_mainnet_retarget() {
+        // The epoch is mined twice as fast as expected
+        let last_time = START_TIME + EXPECTED_EPOCH_TIMESPAN / 2;
+        let state = chain_state(4030, last_time - 300, 300, START_TIME, MAINNET_BITS);
+
+        let state = verify_difficulty_and_time(
+            BitcoinNetwork::Mainnet,
+            &state,
+            4031,
+            last_time,
+            MAINNET_BITS,
+        )
+        .unwrap();
+        assert_eq!(state.epoch_start_time, START_TIME);
+        assert_eq!(state.current_target_bits, 0x1c7fff80);
+
+        // The first block of the next epoch must have the new bits
+        assert_eq!(
+            verify_difficulty_and_time(
+                BitcoinNetwork::Mainnet,
+                &state,
+                4032,
+                last_time + 300,
+                MAINNET_BITS
+            ),
+            Err(ValidationError::InvalidBlockBits)
+        );
+        let state = verify_difficulty_and_time(
+            BitcoinNetwork::Mainnet,
+            &state,
+            4032,
+            last_time + 300,
+            0x1c7fff80,
+        )
+        .unwrap();
+        assert_eq!(state.epoch_start_time, last_time + 300);
+        assert_eq!(state.current_target_bits, 0x1c7fff80);
+    }
+
+    #[test]
+    fn test_calculate_new_difficulty_clamps() {
+        // Slow epochs can not lower the difficulty below the minimum
+        let target = calculate_new_difficulty(
+            START_TIME,
+            START_TIME + EXPECTED_EPOCH_TIMESPAN * 2,
+            MAINNET_BITS,
+            MAX_TARGET,
+        );
+        assert_eq!(target_to_bits(&target), MAINNET_BITS);
+
+        // The difficulty grows at most 4 times, even if the last block is older than the first
+        let target = calculate_new_difficulty(START_TIME, START_TIME - 1, 0x1c7fff80, MAX_TARGET);
+        assert_eq!(target_to_bits(&target), 0x1c1fffe0);
+    }
+
+    #[test]
+    fn test_min_difficulty_blocks() {
+        let hard_bits = 0x1c7fff80;
+        let state = chain_state(4040, START_TIME, 600, START_TIME - 600 * 8, hard_bits);
+        let late_time = START_TIME + TARGET_SPACING * 2 + 1;
+
+        assert_eq!(
+            verify_difficulty_and_time(
+                BitcoinNetwork::Mainnet,
+                &state,
+                4041,
+                late_time,
+                MAINNET_BITS
+            ),
+            Err(ValidationError::InvalidBlockBits)
+        );
+
+        // Late blocks must have the minimum difficulty, which does not change the epoch's bits
+        let next = verify_difficulty_and_time(
+            BitcoinNetwork::Testnet4,
+            &state,
+            4041,
+            late_time,
+            MAINNET_BITS,
+        )
+        .unwrap();
+        assert_eq!(next.current_target_bits, hard_bits);
+        assert_eq!(
+            verify_difficulty_and_time(
+                BitcoinNetwork::Testnet4,
+                &state,
+                4041,
+                late_time,
+                hard_bits
+            ),
+            Err(ValidationError::InvalidBlockBits)
+        );
+        assert_eq!(
+            verify_difficulty_and_time(
+                BitcoinNetwork::Testnet4,
+                &state,
+                4041,
+                START_TIME + 600,
+                MAINNET_BITS
+            ),
+            Err(ValidationError::InvalidBlockBits)
+        );
+
+        // The first block of an epoch can not have the minimum difficulty
+        let state = chain_state(4031, START_TIME, 600, START_TIME - 600 * 2015, hard_bits);
+        assert_eq!(
+            verify_difficulty_and_time(
+                BitcoinNetwork::Testnet4,
+                &state,
+                4032,
+                late_time,
+                MAINNET_BITS
+            ),
+            Err(ValidationError::InvalidBlockBits)
+        );
+    }
+
+    #[test]
+    fn test_regtest_does_not_retarget() {
+        let last_time = START_TIME + EXPECTED_EPOCH_TIMESPAN / 4;
+        let state = chain_state(2014, last_time - 150, 150, START_TIME, REGTEST_BITS);
+
+        let state = verify_difficulty_and_time(
+            BitcoinNetwork::Regtest,
+            &state,
+            2015,
+            last_time,
+            REGTEST_BITS,
+        )
+        .unwrap();
+        assert_eq!(state.current_target_bits, REGTEST_BITS);
+        assert!(verify_difficulty_and_time(
+            BitcoinNetwork::Regtest,
+            &state,
+            2016,
+            last_time + 150,
+            REGTEST_BITS
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_timestamp_rules() {
+        let state = chain_state(4040, START_TIME, 600, START_TIME - 600 * 8, MAINNET_BITS);
+
+        // Must be past the median time of the previous 11 blocks
+        let median_time = START_TIME - 600 * 5;
+        assert_eq!(
+            verify_difficulty_and_time(
+                BitcoinNetwork::Mainnet,
+                &state,
+                4041,
+                median_time,
+                MAINNET_BITS
+            ),
+            Err(ValidationError::InvalidTimestamp)
+        );
+        assert!(verify_difficulty_and_time(
+            BitcoinNetwork::Mainnet,
+            &state,
+            4041,
+            median_time + 1,
+            MAINNET_BITS
+        )
+        .is_ok());
+
+        // On testnet4 the first block of an epoch can be at most 10 minutes older than the
+        // previous block
+        let state = chain_state(4031, START_TIME, 600, START_TIME - 600 * 2015, MAINNET_BITS);
+        let early_time = START_TIME - MAX_TIMEWARP - 1;
+        assert_eq!(
+            verify_difficulty_and_time(
+                BitcoinNetwork::Testnet4,
+                &state,
+                4032,
+                early_time,
+                MAINNET_BITS
+            ),
+            Err(ValidationError::InvalidTimestamp)
+        );
+        assert!(verify_difficulty_and_time(
+            BitcoinNetwork::Testnet4,
+            &state,
+            4032,
+            early_time + 1,
+            MAINNET_BITS
+        )
+        .is_ok());
+        assert!(verify_difficulty_and_time(
+            BitcoinNetwork::Mainnet,
+            &state,
+            4032,
+            early_time,
+            MAINNET_BITS
+        )
+        .is_ok());
+    }
+
+    const BATCH_PROOF_PREFIX: &[u8] = &[1, 1];
+    const MOCK_BLOCK_TX_COUNT: usize = 35;
+    // Positions of the txs with the batch proof prefix in the mock block
+    const RELEVANT_POSITIONS: [usize; 5] = [4, 6, 18, 28, 34];
+
+    /// Wtxids of the mock block, the coinbase one being zeroes as in inclusion proofs
+    fn mock_block_wtxids() -> Vec<[u8; 32]> {
+        let mut wtxids = std::fs::read_to_string("test_data/mock_txs.txt")
+            .unwrap()
+            .lines()
+            .map(|tx| {
+                parse_hex_transaction(tx)
+                    .unwrap()
+                    .compute_wtxid()
+                    .to_byte_array()
+            })
+            .collect::<Vec<_>>();
+        wtxids[0] = [0; 32];
+        wtxids
+    }
+
+    #[test]
+    fn test_completeness_proof() {
+        let block_wtxids = mock_block_wtxids();
+        assert_eq!(block_wtxids.len(), MOCK_BLOCK_TX_COUNT);
+        let proof = RELEVANT_POSITIONS
+            .iter()
+            .map(|&position| block_wtxids[position])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            proof,
+            block_wtxids
+                .iter()
+                .copied()
+                .filter(|wtxid| wtxid.starts_with(BATCH_PROOF_PREFIX))
+                .collect::<Vec<_>>()
+        );
+        let verify = |proof: &[[u8; 32]]| {
+            verify_completeness_proof(&block_wtxids, BATCH_PROOF_PREFIX, proof)
+        };
+
+        assert_eq!(verify_difficulty_and_time(&proof), Ok(()));
+
+        // Duplicate txs
+        let mut duplicated = proof.clone();
+        duplicated.insert(1, proof[0]);
+        assert_eq!(
+            verify_difficulty_and_time(&duplicated),
+            Err(ValidationError::DuplicateTxInCompletenessProof)
+        );
+        let mut duplicated = proof.clone();
+        duplicated.push(proof[0]);
+        assert_eq!(
+            verify_difficulty_and_time(&duplicated),
+            Err(ValidationError::DuplicateTxInCompletenessProof)
+        );
+
+        // Shuffled txs
+        let mut shuffled = proof.clone();
+        shuffled.swap(1, 3);
+        assert_eq!(
+            verify_difficulty_and_time(&shuffled),
+            Err(ValidationError::UnorderedCompletenessProof)
+        );
+        shuffled.reverse();
+        assert_eq!(
+            verify_difficulty_and_time(&shuffled),
+            Err(ValidationError::UnorderedCompletenessProof)
+        );
+
+        // Missing txs
+        for i in 0..proof.len() {
+            let mut missing = proof.clone();
+            missing.remove(i);
+            assert_eq!(
+                verify_difficulty_and_time(&missing),
+                Err(ValidationError::RelevantTxNotInProof)
+            );
+        }
+        assert_eq!(
+            verify_difficulty_and_time(&[]),
+            Err(ValidationError::RelevantTxNotInProof)
+        );
+
+        // Non relevant txs: the coinbase, a light client tx and a tx not in the block
+        for nonrelevant in [block_wtxids[0], block_wtxids[8], [1; 32]] {
+            let mut with_nonrelevant = proof.clone();
+            with_nonrelevant.insert(2, nonrelevant);
+            assert_eq!(
+                verify_difficulty_and_time(&with_nonrelevant),
+                Err(ValidationError::NonRelevantTxInProof)
+            );
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_completeness_proof_of_shuffled_relevant_txs(
+            positions in subsequence(RELEVANT_POSITIONS.to_vec(), 0..=RELEVANT_POSITIONS.len())
+                .prop_shuffle()
+        ) {
+            let block_wtxids = mock_block_wtxids();
+            let proof = positions
+                .iter()
+                .map(|&position| block_wtxids[position])
+                .collect::<Vec<_>>();
+
+            let expected = if !positions.windows(2).all(|pair| pair[0] < pair[1]) {
+                Err(ValidationError::UnorderedCompletenessProof)
+            } else if positions.len() != RELEVANT_POSITIONS.len() {
+                Err(ValidationError::RelevantTxNotInProof)
+            } else {
+                Ok(())
+            };
+            assert_eq!(
+                verify_completeness_proof(&block_wtxids, BATCH_PROOF_PREFIX, &proof),
+                expected
+            );
+        }
+
+        #[test]
+        fn test_completeness_proof_of_random_txs(
+            positions in vec(0..MOCK_BLOCK_TX_COUNT, 0..=2 * RELEVANT_POSITIONS.len())
+        ) {
+            let block_wtxids = mock_block_wtxids();
+            let proof = positions
+                .iter()
+                .map(|&position| block_wtxids[position])
+                .collect::<Vec<_>>();
+
+            // only the relevant txs in block order make a valid proof
+            assert_eq!(
+                verify_completeness_proof(&block_wtxids, BATCH_PROOF_PREFIX, &proof).is_ok(),
+                positions == RELEVANT_POSITIONS
+            );
+        }
+    }
+}
@@ -8,7 +8,7 @@ use bitcoin::hashes::Hash;
 use bitcoin::key::Secp256k1;
 use bitcoin::secp256k1::SecretKey;
 use bitcoin_da::service::get_relevant_blobs_from_txs;
-use bitcoin_da::spec::RollupParams;
+use bitcoin_da::spec::{BitcoinNetwork, RollupParams};
 use bitcoin_da::verifier::BitcoinVerifier;
 use citrea_common::tasks::manager::TaskManager;
 use citrea_e2e::config::TestCaseConfig;
@@ -43,6 +43,7 @@ impl TestCase for BitcoinServiceTest {
         let verifier = BitcoinVerifier::new(RollupParams {
             to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
             to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
+            network: BitcoinNetwork::Regtest,
         });
 
         let (block, block_commitments, block_proofs) =
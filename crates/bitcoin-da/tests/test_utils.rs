@@ -14,7 +14,7 @@ use bitcoin_da::spec::blob::BlobWithSender;
 use bitcoin_da::spec::block::BitcoinBlock;
 use bitcoin_da::spec::header::HeaderWrapper;
 use bitcoin_da::spec::transaction::TransactionWrapper;
-use bitcoin_da::spec::RollupParams;
+use bitcoin_da::spec::{BitcoinNetwork, RollupParams};
 use bitcoincore_rpc::RpcApi;
 use citrea_common::tasks::manager::TaskManager;
 use citrea_e2e::bitcoin::BitcoinNode;
@@ -25,6 +25,7 @@ use citrea_primitives::compression::decompress_blob;
 use citrea_primitives::{MAX_TXBODY_SIZE, TO_BATCH_PROOF_PREFIX, TO_LIGHT_CLIENT_PREFIX};
 use sov_rollup_interface::da::{DaData, SequencerCommitment};
 use sov_rollup_interface::services::da::DaService;
+use sov_rollup_interface::spec::SpecId;
 
 pub const DEFAULT_DA_PRIVATE_KEY: &str =
     "E9873D79C6D87DC0FB6A5778633389F4453213303DA61F20BD67FC233AA33262";
@@ -62,6 +63,11 @@ pub async fn get_service(
         da_private_key: Some(da_private_key),
         tx_backup_dir: get_tx_backup_dir(),
         monitoring: None,
+        fee_bump_after_blocks: 0,
+        min_fee_rate: 1,
+        max_fee_rate: 100,
+        fee_rate_override: None,
+        proof_chunk_threshold: MAX_TXBODY_SIZE,
     };
 
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
@@ -71,6 +77,7 @@ pub async fn get_service(
         RollupParams {
             to_batch_proof_prefix,
             to_light_client_prefix,
+            network: BitcoinNetwork::Regtest,
         },
         tx,
     )
@@ -78,7 +85,7 @@ pub async fn get_service(
     .expect("Error initializing BitcoinService");
 
     let da_service = Arc::new(da_service);
-    task_manager.spawn(|tk| da_service.clone().run_da_queue(rx, tk));
+    task_manager.spawn("da_queue", |tk| da_service.clone().run_da_queue(rx, tk));
 
     da_service
 }
@@ -139,7 +146,7 @@ pub async fn generate_mock_txs(
     };
     valid_commitments.push(commitment.clone());
     da_service
-        .send_transaction(DaData::SequencerCommitment(commitment))
+        .send_transaction(DaData::SequencerCommitment(commitment), SpecId::Fork1)
         .await
         .expect("Failed to send transaction");
 
@@ -150,7 +157,7 @@ pub async fn generate_mock_txs(
     };
     valid_commitments.push(commitment.clone());
     da_service
-        .send_transaction(DaData::SequencerCommitment(commitment))
+        .send_transaction(DaData::SequencerCommitment(commitment), SpecId::Fork1)
         .await
         .expect("Failed to send transaction");
 
@@ -159,7 +166,7 @@ pub async fn generate_mock_txs(
 
     valid_proofs.push(blob.clone());
     da_service
-        .send_transaction(DaData::ZKProof(blob))
+        .send_transaction(DaData::ZKProof(blob), SpecId::Fork1)
         .await
         .expect("Failed to send transaction");
 
@@ -169,17 +176,20 @@ pub async fn generate_mock_txs(
 
     valid_proofs.push(blob.clone());
     da_service
-        .send_transaction(DaData::ZKProof(blob))
+        .send_transaction(DaData::ZKProof(blob), SpecId::Fork1)
         .await
         .expect("Failed to send transaction");
 
     // Sequencer commitment with wrong tx prefix
     wrong_prefix_da_service
-        .send_transaction(DaData::SequencerCommitment(SequencerCommitment {
-            merkle_root: [15; 32],
-            l2_start_block_number: 1246,
-            l2_end_block_number: 1268,
-        }))
+        .send_transaction(
+            DaData::SequencerCommitment(SequencerCommitment {
+                merkle_root: [15; 32],
+                l2_start_block_number: 1246,
+                l2_end_block_number: 1268,
+            }),
+            SpecId::Fork1,
+        )
         .await
         .expect("Failed to send transaction");
 
@@ -188,17 +198,20 @@ pub async fn generate_mock_txs(
 
     valid_proofs.push(blob.clone());
     da_service
-        .send_transaction(DaData::ZKProof(blob))
+        .send_transaction(DaData::ZKProof(blob), SpecId::Fork1)
         .await
         .expect("Failed to send transaction");
 
     // Sequencer commitment with wrong key and signature
     wrong_key_da_service
-        .send_transaction(DaData::SequencerCommitment(SequencerCommitment {
-            merkle_root: [15; 32],
-            l2_start_block_number: 1246,
-            l2_end_block_number: 1268,
-        }))
+        .send_transaction(
+            DaData::SequencerCommitment(SequencerCommitment {
+                merkle_root: [15; 32],
+                l2_start_block_number: 1246,
+                l2_end_block_number: 1268,
+            }),
+            SpecId::Fork1,
+        )
         .await
         .expect("Failed to send transaction");
 
@@ -209,7 +222,7 @@ pub async fn generate_mock_txs(
     };
     valid_commitments.push(commitment.clone());
     da_service
-        .send_transaction(DaData::SequencerCommitment(commitment))
+        .send_transaction(DaData::SequencerCommitment(commitment), SpecId::Fork1)
         .await
         .expect("Failed to send transaction");
 
@@ -219,7 +232,7 @@ pub async fn generate_mock_txs(
 
     valid_proofs.push(blob.clone());
     da_service
-        .send_transaction(DaData::ZKProof(blob))
+        .send_transaction(DaData::ZKProof(blob), SpecId::Fork1)
         .await
         .expect("Failed to send transaction");
 
@@ -7,7 +7,7 @@ use bitcoin_da::helpers::merkle_tree::BitcoinMerkleTree;
 use bitcoin_da::helpers::parsers::{parse_light_client_transaction, ParsedLightClientTransaction};
 use bitcoin_da::spec::blob::BlobWithSender;
 use bitcoin_da::spec::proof::InclusionMultiProof;
-use bitcoin_da::spec::RollupParams;
+use bitcoin_da::spec::{BitcoinNetwork, RollupParams};
 use bitcoin_da::verifier::{BitcoinVerifier, ValidationError, WITNESS_COMMITMENT_PREFIX};
 use citrea_common::tasks::manager::TaskManager;
 use citrea_e2e::config::TestCaseConfig;
@@ -17,7 +17,6 @@ use citrea_e2e::Result;
 use citrea_primitives::{TO_BATCH_PROOF_PREFIX, TO_LIGHT_CLIENT_PREFIX};
 use sov_rollup_interface::da::{BlobReaderTrait, DaNamespace, DaVerifier};
 use sov_rollup_interface::services::da::DaService;
-use test_utils::macros::assert_panic;
 use test_utils::{
     generate_mock_txs, get_blob_with_sender, get_citrea_path, get_default_service,
     get_mock_nonsegwit_block, MockData,
@@ -56,6 +55,7 @@ impl TestCase for BitcoinVerifierTest {
         let verifier = BitcoinVerifier::new(RollupParams {
             to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
             to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
+            network: BitcoinNetwork::Regtest,
         });
 
         // Correct batch proof
@@ -97,7 +97,7 @@ impl TestCase for BitcoinVerifierTest {
                     b_completeness_proof.clone(),
                     DaNamespace::ToLightClientProver,
                 ),
-                Err(ValidationError::RelevantTxNotInProof),
+                Err(ValidationError::NonRelevantTxInProof),
             );
 
             // light client transactions with batch namespace
@@ -109,7 +109,7 @@ impl TestCase for BitcoinVerifierTest {
                     l_completeness_proof.clone(),
                     DaNamespace::ToBatchProver,
                 ),
-                Err(ValidationError::RelevantTxNotInProof),
+                Err(ValidationError::NonRelevantTxInProof),
             );
         }
 
@@ -284,7 +284,7 @@ impl TestCase for BitcoinVerifierTest {
                     completeness_proof,
                     DaNamespace::ToBatchProver,
                 ),
-                Err(ValidationError::RelevantTxNotInProof),
+                Err(ValidationError::NonRelevantTxInProof),
             );
         }
 
@@ -372,12 +372,12 @@ impl TestCase for BitcoinVerifierTest {
             );
         }
 
-        // Missing tx in completeness proof should panic
+        // Missing tx in completeness proof should fail
         {
             let mut b_completeness_proof = b_completeness_proof.clone();
 
             b_completeness_proof.pop();
-            assert_panic!(
+            assert_eq!(
                 verifier.verify_transactions(
                     &block.header,
                     &b_txs,
@@ -385,16 +385,16 @@ impl TestCase for BitcoinVerifierTest {
                     b_completeness_proof,
                     DaNamespace::ToBatchProver,
                 ),
-                "itertools: .zip_eq() reached end of one iterator before the other"
+                Err(ValidationError::RelevantTxNotInProof),
             );
         }
 
-        // Extra tx in completeness proof should panic
+        // Extra tx in completeness proof should fail
         {
             let mut b_completeness_proof = b_completeness_proof.clone();
 
             b_completeness_proof.push(block.txdata[0].clone());
-            assert_panic!(
+            assert_eq!(
                 verifier.verify_transactions(
                     &block.header,
                     &b_txs,
@@ -402,7 +402,24 @@ impl TestCase for BitcoinVerifierTest {
                     b_completeness_proof,
                     DaNamespace::ToBatchProver,
                 ),
-                "itertools: .zip_eq() reached end of one iterator before the other"
+                Err(ValidationError::NonRelevantTxInProof),
+            );
+        }
+
+        // Duplicate tx in completeness proof should fail
+        {
+            let mut b_completeness_proof = b_completeness_proof.clone();
+
+            b_completeness_proof.insert(1, b_completeness_proof[0].clone());
+            assert_eq!(
+                verifier.verify_transactions(
+                    &block.header,
+                    &b_txs,
+                    b_inclusion_proof.clone(),
+                    b_completeness_proof,
+                    DaNamespace::ToBatchProver,
+                ),
+                Err(ValidationError::DuplicateTxInCompletenessProof),
             );
         }
 
@@ -425,7 +442,7 @@ impl TestCase for BitcoinVerifierTest {
                     b_completeness_proof.clone(),
                     DaNamespace::ToBatchProver,
                 ),
-                Err(ValidationError::RelevantTxNotInProof),
+                Err(ValidationError::NonRelevantTxInProof),
             );
         }
 
@@ -442,7 +459,7 @@ impl TestCase for BitcoinVerifierTest {
                     b_completeness_proof,
                     DaNamespace::ToBatchProver,
                 ),
-                Err(ValidationError::RelevantTxNotInProof),
+                Err(ValidationError::UnorderedCompletenessProof),
             );
         }
 
@@ -478,7 +495,7 @@ impl TestCase for BitcoinVerifierTest {
                     b_completeness_proof,
                     DaNamespace::ToBatchProver,
                 ),
-                Err(ValidationError::RelevantTxNotInProof),
+                Err(ValidationError::UnorderedCompletenessProof),
             );
         }
 
@@ -575,17 +592,53 @@ impl TestCase for BitcoinVerifierTest {
             );
         }
 
+        // Modified aggregate blob should fail
+        {
+            let mut l_txs = l_txs.clone();
+
+            // blobs are in the order of complete and aggregate txs in the completeness proof
+            let aggregate_idx = l_completeness_proof
+                .iter()
+                .filter_map(|tx| match parse_light_client_transaction(tx) {
+                    Ok(ParsedLightClientTransaction::Complete(_)) => Some(false),
+                    Ok(ParsedLightClientTransaction::Aggregate(_)) => Some(true),
+                    _ => None,
+                })
+                .position(|is_aggregate| is_aggregate)
+                .expect("Should have an aggregate zk proof tx");
+
+            let mut blob = l_txs[aggregate_idx].verified_data().to_vec();
+            blob.pop();
+
+            l_txs[aggregate_idx] = BlobWithSender::new(
+                blob,
+                l_txs[aggregate_idx].sender.0.clone(),
+                l_txs[aggregate_idx].hash,
+            );
+            assert_eq!(
+                verifier.verify_transactions(
+                    &block.header,
+                    &l_txs,
+                    l_inclusion_proof.clone(),
+                    l_completeness_proof.clone(),
+                    DaNamespace::ToLightClientProver,
+                ),
+                Err(ValidationError::BlobContentWasModified),
+            );
+        }
+
         // Non-decompressed light client proof blob should fail
         {
             let mut l_txs = l_txs.clone();
 
-            let body = {
-                let parsed = parse_light_client_transaction(&l_completeness_proof[0]).unwrap();
-                match parsed {
-                    ParsedLightClientTransaction::Complete(complete) => complete.body, // normally we should decompress the tx body
-                    _ => panic!("Should not select zk proof tx other than complete"),
-                }
-            };
+            // chunk reveals are also a part of the completeness proof, so find the first complete
+            let body = l_completeness_proof
+                .iter()
+                .find_map(|tx| match parse_light_client_transaction(tx) {
+                    Ok(ParsedLightClientTransaction::Complete(complete)) => Some(complete.body), // normally we should decompress the tx body
+                    _ => None,
+                })
+                .expect("Should have a complete zk proof tx");
 
             l_txs[0] = BlobWithSender::new(body, l_txs[0].sender.0.clone(), l_txs[0].hash);
             assert_eq!(
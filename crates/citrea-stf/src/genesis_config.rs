@@ -3,6 +3,8 @@
 //! that transforms module genesis data into Rollup genesis data.
 
 use std::convert::AsRef;
+use std::fs::File;
+use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 
 use anyhow::Context as _;
@@ -10,7 +12,7 @@ use citrea_evm::EvmConfig;
 use soft_confirmation_rule_enforcer::SoftConfirmationRuleEnforcerConfig;
 use sov_accounts::AccountConfig;
 pub use sov_modules_api::default_context::DefaultContext;
-use sov_modules_api::Context;
+use sov_modules_api::{Context, StateKeys, WorkingSet};
 use sov_modules_stf_blueprint::Runtime as RuntimeTrait;
 use sov_rollup_interface::da::DaSpec;
 pub use sov_state::config::Config as StorageConfig;
@@ -35,7 +37,7 @@ impl GenesisPaths {
     /// directory.
     ///
     /// Take a look at the contents of the `test_data` directory to see the
-    /// expected files.
+    /// expected files. Directories written by [`export_genesis`] can be used as well.
     pub fn from_dir(dir: impl AsRef<Path>) -> Self {
         Self {
             accounts_genesis_path: dir.as_ref().join("accounts.json"),
@@ -80,3 +82,80 @@ fn create_genesis_config<C: Context, Da: DaSpec>(
         soft_confirmation_rule_enforcer_config,
     ))
 }
+
+/// Name of the file describing the source of an exported genesis.
+pub const GENESIS_MANIFEST_FILE: &str = "manifest.json";
+
+/// Source of a genesis exported from the state of a chain.
+/// Written next to the genesis files, it is not read when the genesis is applied.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct GenesisManifest {
+    /// Chain id of the exported chain.
+    pub chain_id: u64,
+    /// L2 height the state was exported at.
+    pub l2_height: u64,
+    /// State root of the exported chain at `l2_height`.
+    #[serde(with = "hex::serde")]
+    pub state_root: Vec<u8>,
+}
+
+/// Writes the state of the runtime modules in `working_set` to `dir` as genesis files
+/// readable with [`GenesisPaths::from_dir`], along with the manifest of the exported state.
+///
+/// `working_set` must be on the state after the L2 block at `l2_height`, whose state root is
+/// `state_root`, and `keys` must list the keys of that state.
+///
+/// The system contracts are exported already initialized, so the initialization
+/// system transactions of the first L2 block of a chain started from the export revert.
+pub fn export_genesis<C: Context, Da: DaSpec>(
+    dir: impl AsRef<Path>,
+    keys: &StateKeys,
+    l2_height: u64,
+    state_root: Vec<u8>,
+    working_set: &mut WorkingSet<C::Storage>,
+) -> anyhow::Result<GenesisManifest> {
+    let runtime = Runtime::<C, Da>::default();
+    let genesis_paths = GenesisPaths::from_dir(&dir);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create {}", dir.as_ref().display()))?;
+
+    let accounts_config = runtime
+        .accounts
+        .export_genesis_config(keys, working_set)
+        .context("Failed to export accounts")?;
+    write_json_file(&genesis_paths.accounts_genesis_path, &accounts_config)?;
+
+    let evm_genesis_file = File::create(&genesis_paths.evm_genesis_path).with_context(|| {
+        format!(
+            "Failed to create {}",
+            genesis_paths.evm_genesis_path.display()
+        )
+    })?;
+    let chain_id = runtime
+        .evm
+        .export_genesis_config(keys, BufWriter::new(evm_genesis_file), working_set)
+        .context("Failed to export EVM")?;
+
+    let soft_confirmation_rule_enforcer_config = runtime
+        .soft_confirmation_rule_enforcer
+        .export_genesis_config(working_set)
+        .context("Soft confirmation rule enforcer is not initialized")?;
+    write_json_file(
+        &genesis_paths.soft_confirmation_rule_enforcer_genesis_path,
+        &soft_confirmation_rule_enforcer_config,
+    )?;
+
+    let manifest = GenesisManifest {
+        chain_id,
+        l2_height,
+        state_root,
+    };
+    write_json_file(dir.as_ref().join(GENESIS_MANIFEST_FILE), &manifest)?;
+    Ok(manifest)
+}
+
+fn write_json_file(path: impl AsRef<Path>, value: &impl serde::Serialize) -> anyhow::Result<()> {
+    let data = serde_json::to_vec_pretty(value)?;
+    std::fs::write(&path, data)
+        .with_context(|| format!("Failed to write genesis to {}", path.as_ref().display()))
+}
@@ -1,7 +1,12 @@
-use sov_modules_api::fork::Fork;
+use sov_modules_api::fork::{fork_pos_from_block_number, Fork};
 use sov_rollup_interface::da::{BlockHeaderTrait, DaNamespace, DaVerifier};
-use sov_rollup_interface::stf::{ApplySequencerCommitmentsOutput, StateTransitionFunction};
-use sov_rollup_interface::zk::{BatchProofCircuitInput, BatchProofCircuitOutput};
+use sov_rollup_interface::stf::{
+    sequencer_public_key_at, ApplySequencerCommitmentsOutput, StateTransitionFunction,
+};
+use sov_rollup_interface::zk::{
+    BatchProofCircuitInput, BatchProofCircuitOutput, BatchProofCircuitOutputV1,
+    BatchProofOutputVersion, ZkvmGuest,
+};
 
 /// Verifies a state transition
 pub struct StateTransitionVerifier<ST, Da>
@@ -23,13 +28,48 @@ where
         Self { app, da_verifier }
     }
 
+    /// Run the batch proof circuit: verify the input read from `guest` and commit the output.
+    /// Shared by the guests and the simulator of the batch prover,
+    /// which pass the sequencer keys and forks the circuit is built with.
+    pub fn run_batch_proof_guest<G: ZkvmGuest>(
+        &mut self,
+        guest: &G,
+        pre_state: Stf::PreState,
+        sequencer_public_keys: &[(u64, &[u8])],
+        sequencer_da_public_keys: &[&[u8]],
+        forks: &[Fork],
+    ) -> Result<(), Da::Error> {
+        let data = guest.read_from_host();
+
+        let out = self.run_sequencer_commitments_in_da_slot(
+            data,
+            pre_state,
+            sequencer_public_keys,
+            sequencer_da_public_keys,
+            forks,
+        )?;
+
+        // Proofs up to Fork1 are output in the layout without the first l2 height
+        let spec_id = forks[fork_pos_from_block_number(forks, out.last_l2_height)].spec_id;
+        match BatchProofOutputVersion::from_spec(spec_id) {
+            BatchProofOutputVersion::V1 => guest.commit(&BatchProofCircuitOutputV1::from(out)),
+            BatchProofOutputVersion::V2 => guest.commit(&out),
+        }
+
+        Ok(())
+    }
+
     /// Verify the next block
+    /// Soft confirmations are verified with the key of the `sequencer_public_keys` schedule active at their height,
+    /// the output records the key of the last proven one.
+    /// Sequencer commitments signed with any of `sequencer_da_public_keys` are accepted,
+    /// the output records the key that signed the proven ones.
     pub fn run_sequencer_commitments_in_da_slot(
         &mut self,
         data: BatchProofCircuitInput<Stf::StateRoot, Stf::Witness, Da::Spec, Stf::Transaction>,
         pre_state: Stf::PreState,
-        sequencer_public_key: &[u8],
-        sequencer_da_public_key: &[u8],
+        sequencer_public_keys: &[(u64, &[u8])],
+        sequencer_da_public_keys: &[&[u8]],
         forks: &[Fork],
     ) -> Result<BatchProofCircuitOutput<Da::Spec, Stf::StateRoot>, Da::Error> {
         println!("Running sequencer commitments in DA slot");
@@ -63,12 +103,14 @@ where
         let ApplySequencerCommitmentsOutput {
             final_state_root,
             state_diff,
+            first_l2_height,
             last_l2_height,
+            sequencer_da_public_key,
         } = self
             .app
             .apply_soft_confirmations_from_sequencer_commitments(
-                sequencer_public_key,
-                sequencer_da_public_key,
+                sequencer_public_keys,
+                sequencer_da_public_keys,
                 &data.initial_state_root,
                 pre_state,
                 data.da_data,
@@ -89,11 +131,13 @@ where
             state_diff,
             prev_soft_confirmation_hash: data.prev_soft_confirmation_hash,
             da_slot_hash: data.da_block_header_of_commitments.hash(),
-            sequencer_public_key: sequencer_public_key.to_vec(),
-            sequencer_da_public_key: sequencer_da_public_key.to_vec(),
+            sequencer_public_key: sequencer_public_key_at(sequencer_public_keys, last_l2_height)
+                .to_vec(),
+            sequencer_da_public_key,
             sequencer_commitments_range: data.sequencer_commitments_range,
             preproven_commitments: data.preproven_commitments,
             last_l2_height,
+            first_l2_height,
         };
 
         Ok(out)
@@ -31,4 +31,17 @@ where
     pub fn put(&mut self, height: u64, block: Da::FilteredBlock) {
         self.0.put(height, block);
     }
+
+    /// Evicts the blocks above the given height, e.g. after they were orphaned by a reorg
+    pub fn remove_above(&mut self, height: u64) {
+        let orphaned: Vec<u64> = self
+            .0
+            .iter()
+            .map(|(block_height, _)| *block_height)
+            .filter(|block_height| *block_height > height)
+            .collect();
+        for block_height in orphaned {
+            self.0.pop(&block_height);
+        }
+    }
 }
@@ -2,9 +2,16 @@ use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 
+use alloy_primitives::Address;
+use anyhow::Context;
+use citrea_evm::GasPriceOracleConfig;
 use citrea_pruning::PruningConfig;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use sov_db::rocks_db_config::{RocksdbConfig, RocksdbTuning};
+use sov_db::schema::tables::LEDGER_TABLES;
+use sov_rollup_interface::fork::Fork;
+use sov_rollup_interface::spec::SpecId;
 use sov_stf_runner::ProverGuestRunConfig;
 
 pub trait FromEnv: Sized {
@@ -15,6 +22,9 @@ impl FromEnv for PruningConfig {
     fn from_env() -> anyhow::Result<Self> {
         Ok(PruningConfig {
             distance: std::env::var("PRUNING_DISTANCE")?.parse()?,
+            l1_distance: std::env::var("PRUNING_L1_DISTANCE")
+                .ok()
+                .and_then(|val| val.parse().ok()),
         })
     }
 }
@@ -24,6 +34,11 @@ impl FromEnv for sov_mock_da::MockDaConfig {
         Ok(Self {
             sender_address: std::env::var("SENDER_ADDRESS")?.parse()?,
             db_path: std::env::var("DB_PATH")?.into(),
+            send_transaction_delay_ms: std::env::var("SEND_TRANSACTION_DELAY_MS")
+                .ok()
+                .map(|val| val.parse())
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -32,26 +47,150 @@ impl FromEnv for sov_mock_da::MockDaConfig {
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct RunnerConfig {
     /// Sequencer client configuration.
+    /// Not needed when the node is read-only.
+    #[serde(default)]
     pub sequencer_client_url: String,
+    /// Endpoints serving the sequencer API, e.g. read replicas, in order of priority.
+    /// Full nodes sync from these while `sequencer_client_url` is unreachable.
+    #[serde(default)]
+    pub secondary_sequencer_client_urls: Vec<String>,
     /// Saves sequencer soft confirmations if set to true
     pub include_tx_body: bool,
     /// Number of blocks to request during sync
     #[serde(default = "default_sync_blocks_count")]
     pub sync_blocks_count: u64,
+    /// Number of synced blocks to commit to storage at once.
+    /// The head height is only advanced once a commit is done.
+    #[serde(default = "default_commit_blocks_count")]
+    pub commit_blocks_count: u64,
     /// Configurations for pruning
     pub pruning_config: Option<PruningConfig>,
+    /// Serves RPC from the local databases without syncing L2 blocks from the sequencer.
+    /// L1 blocks are still processed.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Backfills the transaction bodies of L2 blocks synced without them from the sequencer.
+    /// Only runs if `include_tx_body` is set.
+    #[serde(default)]
+    pub tx_body_backfill: Option<TxBodyBackfillConfig>,
+    /// Starts the node even if its L2 genesis state root differs from the sequencer's.
+    /// Only meant for intentionally diverging nodes, e.g. in tests.
+    #[serde(default)]
+    pub allow_genesis_state_root_mismatch: bool,
+    /// Periodically compares the synced L2 blocks and state with the sequencer's
+    #[serde(default)]
+    pub consistency_check: Option<ConsistencyCheckConfig>,
+}
+
+/// Backfilling of the transaction bodies of L2 blocks synced while `include_tx_body` was off
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TxBodyBackfillConfig {
+    /// Maximum number of L2 blocks requested from the sequencer per second
+    #[serde(default = "default_tx_body_backfill_requests_per_second")]
+    pub requests_per_second: u32,
+}
+
+impl Default for TxBodyBackfillConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: default_tx_body_backfill_requests_per_second(),
+        }
+    }
+}
+
+impl FromEnv for TxBodyBackfillConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            requests_per_second: std::env::var("TX_BODY_BACKFILL_REQUESTS_PER_SECOND")?.parse()?,
+        })
+    }
+}
+
+/// Comparison of the synced L2 blocks and state with the sequencer's, to catch state divergence
+/// before a commitment or proof fails
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ConsistencyCheckConfig {
+    /// Number of committed L2 blocks between checks.
+    /// Each check compares the hashes and state roots of all L2 blocks since the last check.
+    #[serde(default = "default_consistency_check_interval")]
+    pub interval: u64,
+    /// Accounts whose balances at the last checked L2 block are compared with the sequencer's
+    #[serde(default)]
+    pub balance_addresses: Vec<Address>,
+    /// Stops syncing L2 blocks on a divergence, keeping the diverged state for inspection
+    #[serde(default)]
+    pub halt_on_divergence: bool,
+}
+
+impl Default for ConsistencyCheckConfig {
+    fn default() -> Self {
+        Self {
+            interval: default_consistency_check_interval(),
+            balance_addresses: vec![],
+            halt_on_divergence: false,
+        }
+    }
+}
+
+impl FromEnv for ConsistencyCheckConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            interval: std::env::var("CONSISTENCY_CHECK_INTERVAL")?.parse()?,
+            balance_addresses: std::env::var("CONSISTENCY_CHECK_BALANCE_ADDRESSES")
+                .map(|val| {
+                    val.split(',')
+                        .map(str::trim)
+                        .filter(|address| !address.is_empty())
+                        .map(str::parse)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .unwrap_or_else(|_| Ok(vec![]))?,
+            halt_on_divergence: std::env::var("CONSISTENCY_CHECK_HALT_ON_DIVERGENCE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default(),
+        })
+    }
 }
 
 impl FromEnv for RunnerConfig {
     fn from_env() -> anyhow::Result<Self> {
+        let read_only = std::env::var("READ_ONLY")
+            .ok()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or_default();
+        let sequencer_client_url = match std::env::var("SEQUENCER_CLIENT_URL") {
+            Ok(url) => url,
+            Err(_) if read_only => String::new(),
+            Err(e) => return Err(e.into()),
+        };
         Ok(Self {
-            sequencer_client_url: std::env::var("SEQUENCER_CLIENT_URL")?,
+            sequencer_client_url,
+            secondary_sequencer_client_urls: std::env::var("SECONDARY_SEQUENCER_CLIENT_URLS")
+                .map(|val| {
+                    val.split(',')
+                        .map(|url| url.trim().to_string())
+                        .filter(|url| !url.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
             include_tx_body: std::env::var("INCLUDE_TX_BODY")?.parse()?,
             sync_blocks_count: std::env::var("SYNC_BLOCKS_COUNT")
                 .ok()
                 .and_then(|val| val.parse().ok())
                 .unwrap_or_else(default_sync_blocks_count),
+            commit_blocks_count: std::env::var("COMMIT_BLOCKS_COUNT")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_commit_blocks_count),
             pruning_config: PruningConfig::from_env().ok(),
+            read_only,
+            tx_body_backfill: TxBodyBackfillConfig::from_env().ok(),
+            allow_genesis_state_root_mismatch: std::env::var("ALLOW_GENESIS_STATE_ROOT_MISMATCH")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default(),
+            consistency_check: ConsistencyCheckConfig::from_env().ok(),
         })
     }
 }
@@ -82,6 +221,290 @@ pub struct RpcConfig {
     /// Maximum number of subscription connections
     #[serde(default = "default_max_subscriptions_per_connection")]
     pub max_subscriptions_per_connection: u32,
+    /// Maximum number of hashes in a single `ledger_getSoftConfirmationsByHashes` request
+    #[serde(default = "default_max_soft_confirmation_hashes_per_request")]
+    pub max_soft_confirmation_hashes_per_request: u32,
+    /// Maximum number of slots spanned by a single `ledger_getVerifiedBatchProofsBySlotRange` request
+    #[serde(default = "default_max_verified_proofs_slot_range")]
+    pub max_verified_proofs_slot_range: u64,
+    /// Health check reports unhealthy once the head has not advanced for this many observed block times
+    #[serde(default = "default_healthcheck_stall_multiple")]
+    pub healthcheck_stall_multiple: f64,
+    /// Number of blocks a node may lag behind the sequencer and L1 heads while `citrea_syncStatus` still reports it synced
+    #[serde(default = "default_sync_status_lag_tolerance")]
+    pub sync_status_lag_tolerance: u64,
+    /// Maximum number of blocks spanned by a single `eth_getLogs` request
+    #[serde(default = "default_max_logs_block_range")]
+    pub max_logs_block_range: u64,
+    /// Maximum number of logs returned by a single multi block `eth_getLogs` request
+    #[serde(default = "default_max_logs_per_response")]
+    pub max_logs_per_response: usize,
+    /// Seconds after which a filter installed with `eth_newFilter` and not polled since is removed
+    #[serde(default = "default_filter_timeout_secs")]
+    pub filter_timeout_secs: u64,
+    /// Maximum number of filters installed by a single connection
+    #[serde(default = "default_max_filters_per_connection")]
+    pub max_filters_per_connection: usize,
+    /// Maximum number of filters installed by all connections together
+    #[serde(default = "default_max_filters")]
+    pub max_filters: usize,
+    /// Token required by admin RPC methods. Admin methods are disabled if not set.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Per remote IP rate limiting of RPC methods, unlimited by default
+    #[serde(default)]
+    pub rate_limit: RpcRateLimitConfig,
+    /// Methods served by the RPC server, all of them by default
+    #[serde(default)]
+    pub method_filter: RpcMethodFilterConfig,
+    /// Cross origin requests accepted by the RPC server, any origin by default
+    #[serde(default)]
+    pub cors: RpcCorsConfig,
+    /// Compression of the HTTP responses, enabled by default
+    #[serde(default)]
+    pub compression: RpcCompressionConfig,
+    /// Settings of the gas price oracle backing `eth_gasPrice` and `eth_maxPriorityFeePerGas`
+    #[serde(default)]
+    pub gas_price_oracle: GasPriceOracleConfig,
+}
+
+/// Token bucket limit of a group of RPC methods
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RpcRateLimit {
+    /// Sustained number of requests allowed per second
+    pub requests_per_second: u32,
+    /// Number of requests that can be made at once before throttling kicks in
+    pub burst: u32,
+}
+
+/// Per remote IP RPC rate limiting configuration.
+/// Methods are split into a cheap and an expensive group, each metered by its own token bucket.
+/// A group without a limit is not metered.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RpcRateLimitConfig {
+    /// Limit of the methods not listed in `expensive_methods`
+    #[serde(default)]
+    pub cheap: Option<RpcRateLimit>,
+    /// Limit of the methods listed in `expensive_methods`
+    #[serde(default)]
+    pub expensive: Option<RpcRateLimit>,
+    /// Methods metered by the expensive limit
+    #[serde(default = "default_expensive_rpc_methods")]
+    pub expensive_methods: Vec<String>,
+}
+
+impl Default for RpcRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            cheap: None,
+            expensive: None,
+            expensive_methods: default_expensive_rpc_methods(),
+        }
+    }
+}
+
+impl FromEnv for RpcRateLimitConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            cheap: rpc_rate_limit_from_env("RPC_CHEAP"),
+            expensive: rpc_rate_limit_from_env("RPC_EXPENSIVE"),
+            expensive_methods: std::env::var("RPC_EXPENSIVE_METHODS")
+                .map(|val| val.split(',').map(|m| m.trim().to_string()).collect())
+                .unwrap_or_else(|_| default_expensive_rpc_methods()),
+        })
+    }
+}
+
+/// Reads `{prefix}_REQUESTS_PER_SECOND` and `{prefix}_BURST`, the burst defaults to the rate
+fn rpc_rate_limit_from_env(prefix: &str) -> Option<RpcRateLimit> {
+    let requests_per_second = std::env::var(format!("{prefix}_REQUESTS_PER_SECOND"))
+        .ok()?
+        .parse()
+        .ok()?;
+    let burst = std::env::var(format!("{prefix}_BURST"))
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(requests_per_second);
+    Some(RpcRateLimit {
+        requests_per_second,
+        burst,
+    })
+}
+
+/// Allowlist and denylist of the methods served by the RPC server, as method name globs
+/// where `*` matches any sequence of characters and `?` a single one.
+/// A method is served if it matches the allowlist, or the allowlist is empty, and does not match the denylist.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct RpcMethodFilterConfig {
+    /// Globs of the served methods, every method is allowed if empty
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Globs of the methods that are not served, even if allowed
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl FromEnv for RpcMethodFilterConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            allow: rpc_list_from_env("RPC_METHOD_FILTER_ALLOW").unwrap_or_default(),
+            deny: rpc_list_from_env("RPC_METHOD_FILTER_DENY").unwrap_or_default(),
+        })
+    }
+}
+
+/// Reads a comma separated list, such as method name globs or CORS origins
+fn rpc_list_from_env(var: &str) -> Option<Vec<String>> {
+    std::env::var(var).ok().map(|val| {
+        val.split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    })
+}
+
+/// CORS policy of the RPC server.
+/// Origins are either exact, like `https://app.example.com:8080`, or match any subdomain,
+/// like `https://*.example.com`. A single `*` allows any origin or header.
+/// Malformed origins and headers are rejected on startup.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RpcCorsConfig {
+    /// Origins allowed to make cross origin requests
+    #[serde(default = "default_cors_allow_any")]
+    pub allowed_origins: Vec<String>,
+    /// Request headers allowed in cross origin requests
+    #[serde(default = "default_cors_allow_any")]
+    pub allowed_headers: Vec<String>,
+    /// Whether cross origin requests may carry credentials.
+    /// Requires explicitly listed origins and headers.
+    #[serde(default)]
+    pub allow_credentials: bool,
+}
+
+impl Default for RpcCorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: default_cors_allow_any(),
+            allowed_headers: default_cors_allow_any(),
+            allow_credentials: false,
+        }
+    }
+}
+
+impl FromEnv for RpcCorsConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            allowed_origins: rpc_list_from_env("RPC_CORS_ALLOWED_ORIGINS")
+                .unwrap_or_else(default_cors_allow_any),
+            allowed_headers: rpc_list_from_env("RPC_CORS_ALLOWED_HEADERS")
+                .unwrap_or_else(default_cors_allow_any),
+            allow_credentials: std::env::var("RPC_CORS_ALLOW_CREDENTIALS")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Compression of the HTTP responses of the RPC server.
+/// Responses are compressed with gzip or brotli when the `Accept-Encoding` header of the request
+/// asks for it. WebSocket connections are not compressed.
+/// `max_response_body_size` limits the uncompressed response.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RpcCompressionConfig {
+    /// Whether responses are compressed
+    #[serde(default = "default_compression_enabled")]
+    pub enabled: bool,
+    /// Responses smaller than this many bytes are not compressed
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: u16,
+}
+
+impl Default for RpcCompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_compression_enabled(),
+            min_size: default_compression_min_size(),
+        }
+    }
+}
+
+impl FromEnv for RpcCompressionConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            enabled: std::env::var("RPC_COMPRESSION_ENABLED")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_compression_enabled),
+            min_size: std::env::var("RPC_COMPRESSION_MIN_SIZE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_compression_min_size),
+        })
+    }
+}
+
+impl FromEnv for GasPriceOracleConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        let default = GasPriceOracleConfig::default();
+        Ok(Self {
+            blocks: std::env::var("GAS_PRICE_ORACLE_BLOCKS")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or(default.blocks),
+            percentile: std::env::var("GAS_PRICE_ORACLE_PERCENTILE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or(default.percentile),
+            max_header_history: std::env::var("GAS_PRICE_ORACLE_MAX_HEADER_HISTORY")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or(default.max_header_history),
+            max_block_history: std::env::var("GAS_PRICE_ORACLE_MAX_BLOCK_HISTORY")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or(default.max_block_history),
+            default: std::env::var("GAS_PRICE_ORACLE_DEFAULT_PRIORITY_FEE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .or(default.default),
+            max_price: std::env::var("GAS_PRICE_ORACLE_MAX_PRICE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .or(default.max_price),
+            ignore_price: std::env::var("GAS_PRICE_ORACLE_IGNORE_PRICE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .or(default.ignore_price),
+        })
+    }
+}
+
+fn default_expensive_rpc_methods() -> Vec<String> {
+    [
+        "eth_getLogs",
+        "eth_call",
+        "eth_estimateGas",
+        "eth_createAccessList",
+        "eth_getProof",
+        "eth_feeHistory",
+        "citrea_estimateFee",
+        "citrea_callBundle",
+        "debug_traceTransaction",
+        "debug_traceBlockByNumber",
+        "debug_traceBlockByHash",
+        "ledger_getSoftConfirmationRange",
+        "ledger_getSoftConfirmationsByHashes",
+        "ledger_getSoftConfirmationRangeByL1Height",
+        "ledger_getVerifiedBatchProofsBySlotRange",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+fn default_cors_allow_any() -> Vec<String> {
+    vec!["*".to_string()]
 }
 
 impl FromEnv for RpcConfig {
@@ -114,6 +537,50 @@ impl FromEnv for RpcConfig {
                 .ok()
                 .and_then(|val| val.parse().ok())
                 .unwrap_or_else(default_max_subscriptions_per_connection),
+            max_soft_confirmation_hashes_per_request: std::env::var(
+                "RPC_MAX_SOFT_CONFIRMATION_HASHES_PER_REQUEST",
+            )
+            .ok()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or_else(default_max_soft_confirmation_hashes_per_request),
+            max_verified_proofs_slot_range: std::env::var("RPC_MAX_VERIFIED_PROOFS_SLOT_RANGE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_max_verified_proofs_slot_range),
+            healthcheck_stall_multiple: std::env::var("RPC_HEALTHCHECK_STALL_MULTIPLE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_healthcheck_stall_multiple),
+            sync_status_lag_tolerance: std::env::var("RPC_SYNC_STATUS_LAG_TOLERANCE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_sync_status_lag_tolerance),
+            max_logs_block_range: std::env::var("RPC_MAX_LOGS_BLOCK_RANGE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_max_logs_block_range),
+            max_logs_per_response: std::env::var("RPC_MAX_LOGS_PER_RESPONSE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_max_logs_per_response),
+            filter_timeout_secs: std::env::var("RPC_FILTER_TIMEOUT_SECS")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_filter_timeout_secs),
+            max_filters_per_connection: std::env::var("RPC_MAX_FILTERS_PER_CONNECTION")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_max_filters_per_connection),
+            max_filters: std::env::var("RPC_MAX_FILTERS")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_max_filters),
+            admin_token: std::env::var("RPC_ADMIN_TOKEN").ok(),
+            rate_limit: RpcRateLimitConfig::from_env()?,
+            method_filter: RpcMethodFilterConfig::from_env()?,
+            cors: RpcCorsConfig::from_env()?,
+            compression: RpcCompressionConfig::from_env()?,
+            gas_price_oracle: GasPriceOracleConfig::from_env()?,
         })
     }
 }
@@ -143,6 +610,21 @@ const fn default_sync_blocks_count() -> u64 {
     10
 }
 
+#[inline]
+const fn default_commit_blocks_count() -> u64 {
+    10
+}
+
+#[inline]
+const fn default_tx_body_backfill_requests_per_second() -> u32 {
+    10
+}
+
+#[inline]
+const fn default_consistency_check_interval() -> u64 {
+    100
+}
+
 #[inline]
 const fn default_enable_subscriptions() -> bool {
     true
@@ -153,6 +635,62 @@ const fn default_max_subscriptions_per_connection() -> u32 {
     100
 }
 
+#[inline]
+const fn default_max_soft_confirmation_hashes_per_request() -> u32 {
+    100
+}
+
+#[inline]
+const fn default_max_verified_proofs_slot_range() -> u64 {
+    1000
+}
+
+#[inline]
+const fn default_healthcheck_stall_multiple() -> f64 {
+    3.0
+}
+
+#[inline]
+const fn default_sync_status_lag_tolerance() -> u64 {
+    5
+}
+
+#[inline]
+const fn default_max_logs_block_range() -> u64 {
+    1_000
+}
+
+#[inline]
+const fn default_max_logs_per_response() -> usize {
+    10_000
+}
+
+#[inline]
+const fn default_filter_timeout_secs() -> u64 {
+    // Same as geth
+    5 * 60
+}
+
+#[inline]
+const fn default_max_filters_per_connection() -> usize {
+    100
+}
+
+#[inline]
+const fn default_max_filters() -> usize {
+    10_000
+}
+
+#[inline]
+const fn default_compression_enabled() -> bool {
+    true
+}
+
+#[inline]
+const fn default_compression_min_size() -> u16 {
+    1024
+}
+
 /// Simple storage configuration
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct StorageConfig {
@@ -160,15 +698,64 @@ pub struct StorageConfig {
     pub path: PathBuf,
     /// File descriptor limit for RocksDB
     pub db_max_open_files: Option<i32>,
+    /// Tuning of the ledger RocksDB, the defaults are used if not set
+    #[serde(default)]
+    pub rocksdb: Option<RocksdbTuning>,
+    /// Keeps the state of every finalized L2 height, so that it can be queried at any
+    /// historical block. Pruning can not be enabled on archive nodes.
+    #[serde(default)]
+    pub archive: bool,
+}
+
+impl StorageConfig {
+    /// Checks the RocksDB tuning, so that an invalid one fails at startup
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(tuning) = &self.rocksdb {
+            tuning
+                .validate(LEDGER_TABLES)
+                .context("Invalid storage.rocksdb configuration")?;
+        }
+        Ok(())
+    }
+
+    /// RocksDB config of the ledger db
+    pub fn ledger_rocksdb_config(&self) -> RocksdbConfig<'_> {
+        let rocksdb_config = RocksdbConfig::new(self.path.as_path(), self.db_max_open_files, None);
+        match &self.rocksdb {
+            Some(tuning) => rocksdb_config.with_tuning(tuning),
+            None => rocksdb_config,
+        }
+    }
 }
 
 impl FromEnv for StorageConfig {
     fn from_env() -> anyhow::Result<Self> {
+        let rocksdb = RocksdbTuning {
+            write_buffer_size: std::env::var("ROCKSDB_WRITE_BUFFER_SIZE")
+                .ok()
+                .and_then(|val| val.parse().ok()),
+            max_background_jobs: std::env::var("ROCKSDB_MAX_BACKGROUND_JOBS")
+                .ok()
+                .and_then(|val| val.parse().ok()),
+            block_cache_size: std::env::var("ROCKSDB_BLOCK_CACHE_SIZE")
+                .ok()
+                .and_then(|val| val.parse().ok()),
+            compression: std::env::var("ROCKSDB_COMPRESSION")
+                .ok()
+                .map(|val| val.parse())
+                .transpose()?,
+            column_family_compression: Default::default(),
+        };
         Ok(Self {
             path: std::env::var("STORAGE_PATH")?.into(),
             db_max_open_files: std::env::var("DB_MAX_OPEN_FILES")
                 .ok()
                 .and_then(|val| val.parse().ok()),
+            rocksdb: (rocksdb != RocksdbTuning::default()).then_some(rocksdb),
+            archive: std::env::var("STORAGE_ARCHIVE")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default(),
         })
     }
 }
@@ -187,6 +774,56 @@ pub struct RollupPublicKeys {
     /// serialized as hex
     #[serde(with = "hex::serde")]
     pub prover_da_pub_key: Vec<u8>,
+    /// Scheduled rotations of the soft confirmation signing key of the Sequencer,
+    /// ordered by activation height. `sequencer_public_key` signs the blocks before the first one.
+    #[serde(default)]
+    pub sequencer_key_rotations: Vec<SequencerKeyRotation>,
+    /// DA Signing Public Keys of the Sequencer accepted besides `sequencer_da_pub_key`,
+    /// e.g. the previous key while the Sequencer's DA key is being rotated.
+    /// serialized as hex
+    #[serde(default, with = "hex_list")]
+    pub accepted_sequencer_da_pub_keys: Vec<Vec<u8>>,
+}
+
+impl RollupPublicKeys {
+    /// Checks the key rotations, so that an invalid schedule fails at startup
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut last_activation_l2_height = 0;
+        for rotation in &self.sequencer_key_rotations {
+            anyhow::ensure!(
+                rotation.activation_l2_height > last_activation_l2_height,
+                "Sequencer key rotations must be ordered by activation height and activate after genesis, got {} after {}",
+                rotation.activation_l2_height,
+                last_activation_l2_height
+            );
+            last_activation_l2_height = rotation.activation_l2_height;
+        }
+        Ok(())
+    }
+
+    /// Soft confirmation signing public keys of the Sequencer by the L2 heights they are active at
+    pub fn sequencer_key_schedule(&self) -> SequencerKeySchedule {
+        let mut keys = vec![(0, self.sequencer_public_key.clone())];
+        keys.extend(self.sequencer_key_rotations.iter().map(|rotation| {
+            (
+                rotation.activation_l2_height,
+                rotation.sequencer_public_key.clone(),
+            )
+        }));
+        SequencerKeySchedule { keys }
+    }
+
+    /// DA Signing Public Keys whose sequencer commitments are accepted,
+    /// starting with `sequencer_da_pub_key`
+    pub fn sequencer_da_pub_keys(&self) -> Vec<Vec<u8>> {
+        let mut keys = vec![self.sequencer_da_pub_key.clone()];
+        for key in &self.accepted_sequencer_da_pub_keys {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        keys
+    }
 }
 
 impl FromEnv for RollupPublicKeys {
@@ -195,10 +832,117 @@ impl FromEnv for RollupPublicKeys {
             sequencer_public_key: hex::decode(std::env::var("SEQUENCER_PUBLIC_KEY")?)?,
             sequencer_da_pub_key: hex::decode(std::env::var("SEQUENCER_DA_PUB_KEY")?)?,
             prover_da_pub_key: hex::decode(std::env::var("PROVER_DA_PUB_KEY")?)?,
+            // JSON list, e.g. [{"activation_l2_height":100,"sequencer_public_key":"<hex>"}]
+            sequencer_key_rotations: std::env::var("SEQUENCER_KEY_ROTATIONS")
+                .ok()
+                .map(|rotations| serde_json::from_str(&rotations))
+                .transpose()?
+                .unwrap_or_default(),
+            // Comma separated hex keys
+            accepted_sequencer_da_pub_keys: std::env::var("ACCEPTED_SEQUENCER_DA_PUB_KEYS")
+                .ok()
+                .map(|keys| keys.split(',').map(|key| hex::decode(key.trim())).collect())
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
 
+/// (De)serializes a list of byte vectors as a list of hex strings
+mod hex_list {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(keys: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(keys.iter().map(hex::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|key| hex::decode(key).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+/// Soft confirmation signing public key of the Sequencer replacing the previous one
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SequencerKeyRotation {
+    /// L2 height of the first block signed with the key
+    pub activation_l2_height: u64,
+    /// Soft confirmation signing public key of the Sequencer
+    /// serialized as hex
+    #[serde(with = "hex::serde")]
+    pub sequencer_public_key: Vec<u8>,
+}
+
+/// Soft confirmation signing public keys of the Sequencer with the L2 heights they activate at.
+/// Blocks keep verifying with the key that was active at their height after a rotation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencerKeySchedule {
+    /// Sorted by activation height, the first key is active from genesis
+    keys: Vec<(u64, Vec<u8>)>,
+}
+
+impl SequencerKeySchedule {
+    /// Public key that signs the block at the given L2 height
+    pub fn key_at(&self, l2_height: u64) -> &[u8] {
+        let index = self
+            .keys
+            .partition_point(|(activation_l2_height, _)| *activation_l2_height <= l2_height);
+        // The first key activates at 0, so there is always one at or before the height
+        &self.keys[index.saturating_sub(1)].1
+    }
+
+    /// Whether the key of the block at the given L2 height differs from the previous block's
+    pub fn is_rotation_height(&self, l2_height: u64) -> bool {
+        l2_height > 0
+            && self
+                .keys
+                .iter()
+                .skip(1)
+                .any(|(activation_l2_height, _)| *activation_l2_height == l2_height)
+    }
+
+    /// All public keys of the schedule in activation order
+    pub fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.keys.iter().map(|(_, key)| key.as_slice())
+    }
+
+    /// The schedule as `(activation_l2_height, public_key)` pairs, as the batch proof circuit takes it
+    pub fn activations(&self) -> Vec<(u64, &[u8])> {
+        self.keys
+            .iter()
+            .map(|(activation_l2_height, key)| (*activation_l2_height, key.as_slice()))
+            .collect()
+    }
+}
+
+impl From<Vec<u8>> for SequencerKeySchedule {
+    /// Schedule of a single key that is never rotated
+    fn from(key: Vec<u8>) -> Self {
+        Self {
+            keys: vec![(0, key)],
+        }
+    }
+}
+
+/// Activation height of a fork, overriding the compiled fork schedule
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ForkOverride {
+    /// Spec id of the fork
+    pub spec_id: SpecId,
+    /// L2 height the fork activates at
+    pub activation_l2_height: u64,
+}
+
+impl From<ForkOverride> for Fork {
+    fn from(fork: ForkOverride) -> Self {
+        Fork::new(fork.spec_id, fork.activation_l2_height)
+    }
+}
+
 /// Rollup Configuration
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct FullNodeConfig<BitcoinServiceConfig> {
@@ -215,6 +959,10 @@ pub struct FullNodeConfig<BitcoinServiceConfig> {
     /// Telemetry configuration
     #[serde(default)]
     pub telemetry: TelemetryConfig,
+    /// Fork activation heights overriding the compiled schedule.
+    /// Only allowed on Devnet and Nightly.
+    #[serde(default)]
+    pub forks: Vec<ForkOverride>,
 }
 
 impl<DaC: FromEnv> FromEnv for FullNodeConfig<DaC> {
@@ -226,6 +974,12 @@ impl<DaC: FromEnv> FromEnv for FullNodeConfig<DaC> {
             da: DaC::from_env()?,
             public_keys: RollupPublicKeys::from_env()?,
             telemetry: TelemetryConfig::from_env()?,
+            // JSON list, e.g. [{"spec_id":"Fork1","activation_l2_height":100}]
+            forks: std::env::var("FORKS")
+                .ok()
+                .map(|forks| serde_json::from_str(&forks))
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -239,6 +993,22 @@ pub struct BatchProverConfig {
     pub proof_sampling_number: usize,
     /// If true prover will try to recover ongoing proving sessions
     pub enable_recovery: bool,
+    /// If set, every batch proof circuit input is written to this directory
+    /// before proving, so that the proof can be reproduced with `prove-from-file`
+    #[serde(default)]
+    pub circuit_input_dir: Option<PathBuf>,
+    /// Keep archived circuit inputs after their proofs are submitted
+    #[serde(default)]
+    pub keep_circuit_inputs: bool,
+    /// Maximum number of soft confirmations proven by a single proof.
+    /// Commitments of an L1 block spanning more are split into multiple proofs.
+    #[serde(default = "default_max_soft_confirmations_per_proof")]
+    pub max_soft_confirmations_per_proof: u64,
+}
+
+#[inline]
+const fn default_max_soft_confirmations_per_proof() -> u64 {
+    10_000
 }
 
 /// Prover configuration
@@ -263,6 +1033,9 @@ impl Default for BatchProverConfig {
             proving_mode: ProverGuestRunConfig::Execute,
             proof_sampling_number: 0,
             enable_recovery: true,
+            circuit_input_dir: None,
+            keep_circuit_inputs: false,
+            max_soft_confirmations_per_proof: default_max_soft_confirmations_per_proof(),
         }
     }
 }
@@ -284,6 +1057,15 @@ impl FromEnv for BatchProverConfig {
             proving_mode: serde_json::from_str(&format!("\"{}\"", std::env::var("PROVING_MODE")?))?,
             proof_sampling_number: std::env::var("PROOF_SAMPLING_NUMBER")?.parse()?,
             enable_recovery: std::env::var("ENABLE_RECOVERY")?.parse()?,
+            circuit_input_dir: std::env::var("CIRCUIT_INPUT_DIR").ok().map(PathBuf::from),
+            keep_circuit_inputs: std::env::var("KEEP_CIRCUIT_INPUTS")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default(),
+            max_soft_confirmations_per_proof: std::env::var("MAX_SOFT_CONFIRMATIONS_PER_PROOF")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_max_soft_confirmations_per_proof),
         })
     }
 }
@@ -331,6 +1113,120 @@ pub struct SequencerConfig {
     pub da_update_interval_ms: u64,
     /// Block production interval in ms
     pub block_production_interval_ms: u64,
+    /// Max estimated compressed size in bytes of the transactions in a soft confirmation
+    #[serde(default = "default_max_soft_confirmation_size_bytes")]
+    pub max_soft_confirmation_size_bytes: u64,
+    /// Private keys of the scheduled sequencer key rotations.
+    /// Each block is signed with the key whose public key is active at its height.
+    #[serde(default)]
+    pub rotated_private_keys: Vec<String>,
+    /// Order of the mempool transactions in a soft confirmation
+    #[serde(default)]
+    pub tx_ordering_policy: TxOrderingPolicy,
+    /// Smoothing of the L1 fee rate committed into the soft confirmations
+    #[serde(default)]
+    pub l1_fee_rate_smoothing: L1FeeRateSmoothingConfig,
+    /// Max seconds a soft confirmation timestamp can be ahead of the clock.
+    /// Timestamps never decrease, so a soft confirmation is timestamped after its parent
+    /// even if the clock is behind, up to this drift.
+    #[serde(default = "default_max_timestamp_drift_secs")]
+    pub max_timestamp_drift_secs: u64,
+}
+
+#[inline]
+const fn default_max_soft_confirmation_size_bytes() -> u64 {
+    1024 * 1024
+}
+
+#[inline]
+const fn default_max_timestamp_drift_secs() -> u64 {
+    60
+}
+
+/// The L1 fee rate of a soft confirmation is an exponential moving average of the DA fee rate
+/// estimates, so that the fees users pay do not follow the spikes of the DA layer fees.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct L1FeeRateSmoothingConfig {
+    /// Weight of the latest DA fee rate estimate in the moving average, in (0, 1].
+    /// 1 follows the estimates, within the per block change limit.
+    #[serde(default = "default_l1_fee_rate_alpha")]
+    pub alpha: f64,
+    /// Max change of the fee rate from one soft confirmation to the next, in basis points
+    #[serde(default = "default_l1_fee_rate_max_change_bps")]
+    pub max_change_per_block_bps: u64,
+    /// Lower bound of the fee rate
+    #[serde(default)]
+    pub min_fee_rate: Option<u128>,
+    /// Upper bound of the fee rate
+    #[serde(default)]
+    pub max_fee_rate: Option<u128>,
+}
+
+#[inline]
+const fn default_l1_fee_rate_alpha() -> f64 {
+    0.1
+}
+
+#[inline]
+const fn default_l1_fee_rate_max_change_bps() -> u64 {
+    // 12.5%, as the base fee of EIP-1559
+    1250
+}
+
+impl Default for L1FeeRateSmoothingConfig {
+    fn default() -> Self {
+        Self {
+            alpha: default_l1_fee_rate_alpha(),
+            max_change_per_block_bps: default_l1_fee_rate_max_change_bps(),
+            min_fee_rate: None,
+            max_fee_rate: None,
+        }
+    }
+}
+
+impl FromEnv for L1FeeRateSmoothingConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            alpha: std::env::var("L1_FEE_RATE_ALPHA")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(default_l1_fee_rate_alpha),
+            max_change_per_block_bps: std::env::var("L1_FEE_RATE_MAX_CHANGE_PER_BLOCK_BPS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(default_l1_fee_rate_max_change_bps),
+            min_fee_rate: std::env::var("L1_FEE_RATE_MIN")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            max_fee_rate: std::env::var("L1_FEE_RATE_MAX")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+        })
+    }
+}
+
+/// Order in which the sequencer includes mempool transactions in a soft confirmation.
+/// Transactions of the same sender are always included in nonce order, so the same
+/// mempool contents always result in the same soft confirmation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxOrderingPolicy {
+    /// Highest effective tip first, ties are broken by sender, nonce and hash
+    #[default]
+    PriorityFee,
+    /// Highest effective tip first, transactions with the same tip are included
+    /// in the order they were inserted into the mempool
+    Fifo,
+}
+
+impl TxOrderingPolicy {
+    /// Name of the policy as it is set in the config
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxOrderingPolicy::PriorityFee => "priority_fee",
+            TxOrderingPolicy::Fifo => "fifo",
+        }
+    }
 }
 
 impl Default for SequencerConfig {
@@ -344,6 +1240,11 @@ impl Default for SequencerConfig {
             block_production_interval_ms: 100,
             da_update_interval_ms: 100,
             mempool_conf: Default::default(),
+            max_soft_confirmation_size_bytes: default_max_soft_confirmation_size_bytes(),
+            rotated_private_keys: vec![],
+            tx_ordering_policy: TxOrderingPolicy::default(),
+            l1_fee_rate_smoothing: L1FeeRateSmoothingConfig::default(),
+            max_timestamp_drift_secs: default_max_timestamp_drift_secs(),
         }
     }
 }
@@ -361,6 +1262,24 @@ impl FromEnv for SequencerConfig {
             mempool_conf: SequencerMempoolConfig::from_env()?,
             da_update_interval_ms: std::env::var("DA_UPDATE_INTERVAL_MS")?.parse()?,
             block_production_interval_ms: std::env::var("BLOCK_PRODUCTION_INTERVAL_MS")?.parse()?,
+            max_soft_confirmation_size_bytes: std::env::var("MAX_SOFT_CONFIRMATION_SIZE_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(default_max_soft_confirmation_size_bytes),
+            rotated_private_keys: std::env::var("ROTATED_PRIVATE_KEYS")
+                .ok()
+                .map(|val| val.split(',').map(|key| key.trim().to_string()).collect())
+                .unwrap_or_default(),
+            tx_ordering_policy: std::env::var("TX_ORDERING_POLICY")
+                .ok()
+                .map(|policy| serde_json::from_str(&format!("\"{}\"", policy)))
+                .transpose()?
+                .unwrap_or_default(),
+            l1_fee_rate_smoothing: L1FeeRateSmoothingConfig::from_env()?,
+            max_timestamp_drift_secs: std::env::var("MAX_TIMESTAMP_DRIFT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(default_max_timestamp_drift_secs),
         })
     }
 }
@@ -383,6 +1302,40 @@ pub struct SequencerMempoolConfig {
     pub base_fee_tx_size: u64,
     /// Max number of executable transaction slots guaranteed per account
     pub max_account_slots: u64,
+    /// Seconds after which executable transactions still in the mempool are evicted
+    #[serde(default = "default_tx_ttl_secs")]
+    pub tx_ttl_secs: u64,
+    /// Seconds after which queued transactions, e.g. nonce-gapped ones or the ones not paying
+    /// the base fee, are evicted
+    #[serde(default = "default_queued_tx_ttl_secs")]
+    pub queued_tx_ttl_secs: u64,
+    /// Max number of conditional transactions, sent with `eth_sendRawTransactionConditional`,
+    /// in the mempool
+    #[serde(default = "default_conditional_tx_limit")]
+    pub conditional_tx_limit: u64,
+    /// Max number of storage slots a conditional transaction can require known values for
+    #[serde(default = "default_conditional_tx_max_slots")]
+    pub conditional_tx_max_slots: u64,
+}
+
+#[inline]
+const fn default_tx_ttl_secs() -> u64 {
+    3 * 60 * 60
+}
+
+#[inline]
+const fn default_queued_tx_ttl_secs() -> u64 {
+    60 * 60
+}
+
+#[inline]
+const fn default_conditional_tx_limit() -> u64 {
+    1000
+}
+
+#[inline]
+const fn default_conditional_tx_max_slots() -> u64 {
+    100
 }
 
 impl Default for SequencerMempoolConfig {
@@ -395,6 +1348,10 @@ impl Default for SequencerMempoolConfig {
             base_fee_tx_limit: 100000,
             base_fee_tx_size: 200,
             max_account_slots: 16,
+            tx_ttl_secs: default_tx_ttl_secs(),
+            queued_tx_ttl_secs: default_queued_tx_ttl_secs(),
+            conditional_tx_limit: default_conditional_tx_limit(),
+            conditional_tx_max_slots: default_conditional_tx_max_slots(),
         }
     }
 }
@@ -409,6 +1366,22 @@ impl FromEnv for SequencerMempoolConfig {
             base_fee_tx_limit: std::env::var("BASE_FEE_TX_LIMIT")?.parse()?,
             base_fee_tx_size: std::env::var("BASE_FEE_TX_SIZE")?.parse()?,
             max_account_slots: std::env::var("MAX_ACCOUNT_SLOTS")?.parse()?,
+            tx_ttl_secs: std::env::var("TX_TTL_SECS")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_tx_ttl_secs),
+            queued_tx_ttl_secs: std::env::var("QUEUED_TX_TTL_SECS")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_queued_tx_ttl_secs),
+            conditional_tx_limit: std::env::var("CONDITIONAL_TX_LIMIT")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_conditional_tx_limit),
+            conditional_tx_max_slots: std::env::var("CONDITIONAL_TX_MAX_SLOTS")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_conditional_tx_max_slots),
         })
     }
 }
@@ -416,27 +1389,77 @@ impl FromEnv for SequencerMempoolConfig {
 /// Telemetry configuration.
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 pub struct TelemetryConfig {
-    /// Server host.
-    pub bind_host: Option<String>,
-    /// Server port.
-    pub bind_port: Option<u16>,
+    /// Prometheus metrics exporter configuration.
+    #[serde(default)]
+    pub metrics: MetricsConfig,
 }
 
 impl FromEnv for TelemetryConfig {
     fn from_env() -> anyhow::Result<Self> {
-        let bind_host = std::env::var("TELEMETRY_BIND_HOST").ok();
-        let bind_port = std::env::var("TELEMETRY_BIND_PORT").ok();
         Ok(Self {
-            bind_host,
-            bind_port: bind_port.map(|p| p.parse()).transpose()?,
+            metrics: MetricsConfig::from_env()?,
         })
     }
 }
 
+/// Prometheus metrics exporter configuration.
+/// Nodes running on the same machine need distinct ports.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MetricsConfig {
+    /// Whether to serve metrics.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Exporter host.
+    #[serde(default = "default_metrics_bind_host")]
+    pub bind_host: String,
+    /// Exporter port.
+    #[serde(default = "default_metrics_bind_port")]
+    pub bind_port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_host: default_metrics_bind_host(),
+            bind_port: default_metrics_bind_port(),
+        }
+    }
+}
+
+impl FromEnv for MetricsConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            enabled: std::env::var("METRICS_ENABLED")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_default(),
+            bind_host: std::env::var("METRICS_BIND_HOST")
+                .ok()
+                .unwrap_or_else(default_metrics_bind_host),
+            bind_port: std::env::var("METRICS_BIND_PORT")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or_else(default_metrics_bind_port),
+        })
+    }
+}
+
+#[inline]
+fn default_metrics_bind_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+#[inline]
+const fn default_metrics_bind_port() -> u16 {
+    8001
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
 
+    use sov_db::rocks_db_config::RocksdbCompression;
     use tempfile::NamedTempFile;
 
     use super::*;
@@ -463,6 +1486,23 @@ mod tests {
             enable_subscriptions = true
             max_subscriptions_per_connection = 200
 
+            [rpc.rate_limit.expensive]
+            requests_per_second = 5
+            burst = 10
+
+            [rpc.method_filter]
+            deny = ["debug_*", "txpool_*"]
+
+            [rpc.cors]
+            allowed_origins = ["https://*.citrea.xyz"]
+
+            [rpc.compression]
+            min_size = 4096
+
+            [rpc.gas_price_oracle]
+            blocks = 10
+            percentile = 50
+
             [da]
             sender_address = "0000000000000000000000000000000000000000000000000000000000000000"
             db_path = "/tmp/da"
@@ -470,14 +1510,40 @@ mod tests {
             [storage]
             path = "/tmp/rollup"
             db_max_open_files = 123
+            archive = true
+
+            [storage.rocksdb]
+            write_buffer_size = 134217728
+            compression = "zstd"
+
+            [storage.rocksdb.column_family_compression]
+            SoftConfirmationByNumber = "lz4"
             
             [runner]
             include_tx_body = true
             sequencer_client_url = "http://0.0.0.0:12346"
+            secondary_sequencer_client_urls = ["http://0.0.0.0:12347"]
+
+            [runner.tx_body_backfill]
+            requests_per_second = 5
 
-            [telemetry]
+            [runner.consistency_check]
+            interval = 50
+            balance_addresses = ["0x0000000000000000000000000000000000000001"]
+            halt_on_divergence = true
+
+            [telemetry.metrics]
+            enabled = true
             bind_host = "0.0.0.0"
             bind_port = 8001
+
+            [[forks]]
+            spec_id = "Genesis"
+            activation_l2_height = 0
+
+            [[forks]]
+            spec_id = "Fork1"
+            activation_l2_height = 1000
         "#.to_owned();
 
         let config_file = create_config_from(&config);
@@ -488,17 +1554,41 @@ mod tests {
         let expected = FullNodeConfig {
             runner: Some(RunnerConfig {
                 sequencer_client_url: "http://0.0.0.0:12346".to_owned(),
+                secondary_sequencer_client_urls: vec!["http://0.0.0.0:12347".to_owned()],
                 include_tx_body: true,
                 sync_blocks_count: 10,
+                commit_blocks_count: 10,
                 pruning_config: None,
+                read_only: false,
+                tx_body_backfill: Some(TxBodyBackfillConfig {
+                    requests_per_second: 5,
+                }),
+                allow_genesis_state_root_mismatch: false,
+                consistency_check: Some(ConsistencyCheckConfig {
+                    interval: 50,
+                    balance_addresses: vec![Address::with_last_byte(1)],
+                    halt_on_divergence: true,
+                }),
             }),
             da: sov_mock_da::MockDaConfig {
                 sender_address: [0; 32].into(),
                 db_path: "/tmp/da".into(),
+                send_transaction_delay_ms: 0,
             },
             storage: StorageConfig {
                 path: "/tmp/rollup".into(),
                 db_max_open_files: Some(123),
+                rocksdb: Some(RocksdbTuning {
+                    write_buffer_size: Some(128 * 1024 * 1024),
+                    compression: Some(RocksdbCompression::Zstd),
+                    column_family_compression: [(
+                        "SoftConfirmationByNumber".to_string(),
+                        RocksdbCompression::Lz4,
+                    )]
+                    .into(),
+                    ..Default::default()
+                }),
+                archive: true,
             },
             rpc: RpcConfig {
                 bind_host: "127.0.0.1".to_string(),
@@ -509,16 +1599,172 @@ mod tests {
                 batch_requests_limit: 50,
                 enable_subscriptions: true,
                 max_subscriptions_per_connection: 200,
+                max_soft_confirmation_hashes_per_request: 100,
+                max_verified_proofs_slot_range: 1000,
+                healthcheck_stall_multiple: 3.0,
+                sync_status_lag_tolerance: 5,
+                max_logs_block_range: 1_000,
+                max_logs_per_response: 10_000,
+                filter_timeout_secs: 300,
+                max_filters_per_connection: 100,
+                max_filters: 10_000,
+                admin_token: None,
+                rate_limit: RpcRateLimitConfig {
+                    expensive: Some(RpcRateLimit {
+                        requests_per_second: 5,
+                        burst: 10,
+                    }),
+                    ..Default::default()
+                },
+                method_filter: RpcMethodFilterConfig {
+                    allow: vec![],
+                    deny: vec!["debug_*".to_string(), "txpool_*".to_string()],
+                },
+                cors: RpcCorsConfig {
+                    allowed_origins: vec!["https://*.citrea.xyz".to_string()],
+                    ..Default::default()
+                },
+                compression: RpcCompressionConfig {
+                    enabled: true,
+                    min_size: 4096,
+                },
+                gas_price_oracle: GasPriceOracleConfig {
+                    blocks: 10,
+                    percentile: 50,
+                    ..Default::default()
+                },
             },
             public_keys: RollupPublicKeys {
                 sequencer_public_key: vec![0; 32],
                 sequencer_da_pub_key: vec![119; 32],
                 prover_da_pub_key: vec![],
+                sequencer_key_rotations: vec![],
+                accepted_sequencer_da_pub_keys: vec![],
             },
             telemetry: TelemetryConfig {
-                bind_host: Some("0.0.0.0".to_owned()),
-                bind_port: Some(8001),
+                metrics: MetricsConfig {
+                    enabled: true,
+                    bind_host: "0.0.0.0".to_owned(),
+                    bind_port: 8001,
+                },
             },
+            forks: vec![
+                ForkOverride {
+                    spec_id: SpecId::Genesis,
+                    activation_l2_height: 0,
+                },
+                ForkOverride {
+                    spec_id: SpecId::Fork1,
+                    activation_l2_height: 1000,
+                },
+            ],
+        };
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn test_sequencer_key_rotations() {
+        let config = r#"
+            sequencer_public_key = "0000000000000000000000000000000000000000000000000000000000000000"
+            sequencer_da_pub_key = "7777777777777777777777777777777777777777777777777777777777777777"
+            prover_da_pub_key = ""
+
+            [[sequencer_key_rotations]]
+            activation_l2_height = 10
+            sequencer_public_key = "1111111111111111111111111111111111111111111111111111111111111111"
+
+            [[sequencer_key_rotations]]
+            activation_l2_height = 20
+            sequencer_public_key = "2222222222222222222222222222222222222222222222222222222222222222"
+        "#;
+
+        let config_file = create_config_from(config);
+
+        let public_keys: RollupPublicKeys = from_toml_path(config_file.path()).unwrap();
+        assert_eq!(
+            public_keys.sequencer_key_rotations,
+            vec![
+                SequencerKeyRotation {
+                    activation_l2_height: 10,
+                    sequencer_public_key: vec![17; 32],
+                },
+                SequencerKeyRotation {
+                    activation_l2_height: 20,
+                    sequencer_public_key: vec![34; 32],
+                },
+            ]
+        );
+        public_keys.validate().unwrap();
+
+        let schedule = public_keys.sequencer_key_schedule();
+        assert_eq!(schedule.key_at(1), [0; 32]);
+        assert_eq!(schedule.key_at(9), [0; 32]);
+        assert_eq!(schedule.key_at(10), [17; 32]);
+        assert_eq!(schedule.key_at(19), [17; 32]);
+        assert_eq!(schedule.key_at(20), [34; 32]);
+        assert_eq!(schedule.key_at(u64::MAX), [34; 32]);
+        assert!(!schedule.is_rotation_height(9));
+        assert!(schedule.is_rotation_height(10));
+        assert!(schedule.is_rotation_height(20));
+        assert_eq!(schedule.keys().count(), 3);
+
+        let mut unordered = public_keys.clone();
+        unordered.sequencer_key_rotations.swap(0, 1);
+        assert!(unordered.validate().is_err());
+
+        let mut at_genesis = public_keys;
+        at_genesis.sequencer_key_rotations[0].activation_l2_height = 0;
+        assert!(at_genesis.validate().is_err());
+    }
+
+    #[test]
+    fn test_accepted_sequencer_da_pub_keys() {
+        let config = r#"
+            sequencer_public_key = "0000000000000000000000000000000000000000000000000000000000000000"
+            sequencer_da_pub_key = "7777777777777777777777777777777777777777777777777777777777777777"
+            prover_da_pub_key = ""
+            accepted_sequencer_da_pub_keys = [
+                "1111111111111111111111111111111111111111111111111111111111111111",
+                "7777777777777777777777777777777777777777777777777777777777777777",
+            ]
+        "#;
+
+        let config_file = create_config_from(config);
+
+        let public_keys: RollupPublicKeys = from_toml_path(config_file.path()).unwrap();
+        assert_eq!(
+            public_keys.accepted_sequencer_da_pub_keys,
+            vec![vec![17; 32], vec![119; 32]]
+        );
+        // The primary key comes first and is not repeated
+        assert_eq!(
+            public_keys.sequencer_da_pub_keys(),
+            vec![vec![119; 32], vec![17; 32]]
+        );
+    }
+
+    #[test]
+    fn test_read_only_runner_config_without_sequencer() {
+        let config = r#"
+            include_tx_body = false
+            read_only = true
+        "#;
+
+        let config_file = create_config_from(config);
+
+        let config: RunnerConfig = from_toml_path(config_file.path()).unwrap();
+
+        let expected = RunnerConfig {
+            sequencer_client_url: String::new(),
+            secondary_sequencer_client_urls: vec![],
+            include_tx_body: false,
+            sync_blocks_count: 10,
+            commit_blocks_count: 10,
+            pruning_config: None,
+            read_only: true,
+            tx_body_backfill: None,
+            allow_genesis_state_root_mismatch: false,
+            consistency_check: None,
         };
         assert_eq!(config, expected);
     }
@@ -529,6 +1775,8 @@ mod tests {
             proving_mode = "skip"
             proof_sampling_number = 500
             enable_recovery = true
+            circuit_input_dir = "/tmp/circuit_inputs"
+            max_soft_confirmations_per_proof = 2000
         "#;
 
         let config_file = create_config_from(config);
@@ -538,6 +1786,9 @@ mod tests {
             proving_mode: ProverGuestRunConfig::Skip,
             proof_sampling_number: 500,
             enable_recovery: true,
+            circuit_input_dir: Some(PathBuf::from("/tmp/circuit_inputs")),
+            keep_circuit_inputs: false,
+            max_soft_confirmations_per_proof: 2000,
         };
         assert_eq!(config, expected);
     }
@@ -550,6 +1801,12 @@ mod tests {
             deposit_mempool_fetch_limit = 10
             da_update_interval_ms = 1000
             block_production_interval_ms = 1000
+            max_soft_confirmation_size_bytes = 500000
+            tx_ordering_policy = "fifo"
+            max_timestamp_drift_secs = 30
+            [l1_fee_rate_smoothing]
+            alpha = 0.5
+            max_fee_rate = 1000000
             [mempool_conf]
             pending_tx_limit = 100000
             pending_tx_size = 200
@@ -558,6 +1815,8 @@ mod tests {
             base_fee_tx_limit = 100000
             base_fee_tx_size = 200
             max_account_slots = 16
+            queued_tx_ttl_secs = 600
+            conditional_tx_max_slots = 10
         "#;
 
         let config_file = create_config_from(config);
@@ -578,9 +1837,23 @@ mod tests {
                 base_fee_tx_limit: 100000,
                 base_fee_tx_size: 200,
                 max_account_slots: 16,
+                tx_ttl_secs: default_tx_ttl_secs(),
+                queued_tx_ttl_secs: 600,
+                conditional_tx_limit: default_conditional_tx_limit(),
+                conditional_tx_max_slots: 10,
             },
             da_update_interval_ms: 1000,
             block_production_interval_ms: 1000,
+            max_soft_confirmation_size_bytes: 500000,
+            rotated_private_keys: vec![],
+            tx_ordering_policy: TxOrderingPolicy::Fifo,
+            l1_fee_rate_smoothing: L1FeeRateSmoothingConfig {
+                alpha: 0.5,
+                max_change_per_block_bps: default_l1_fee_rate_max_change_bps(),
+                min_fee_rate: None,
+                max_fee_rate: Some(1000000),
+            },
+            max_timestamp_drift_secs: 30,
         };
         assert_eq!(config, expected);
     }
@@ -590,6 +1863,8 @@ mod tests {
         std::env::set_var("PROVING_MODE", "skip");
         std::env::set_var("PROOF_SAMPLING_NUMBER", "500");
         std::env::set_var("ENABLE_RECOVERY", "true");
+        std::env::set_var("CIRCUIT_INPUT_DIR", "/tmp/circuit_inputs");
+        std::env::set_var("KEEP_CIRCUIT_INPUTS", "true");
 
         let prover_config = BatchProverConfig::from_env().unwrap();
 
@@ -597,6 +1872,9 @@ mod tests {
             proving_mode: ProverGuestRunConfig::Skip,
             proof_sampling_number: 500,
             enable_recovery: true,
+            circuit_input_dir: Some(PathBuf::from("/tmp/circuit_inputs")),
+            keep_circuit_inputs: true,
+            max_soft_confirmations_per_proof: 10_000,
         };
         assert_eq!(prover_config, expected);
     }
@@ -611,6 +1889,9 @@ mod tests {
         std::env::set_var("DEPOSIT_MEMPOOL_FETCH_LIMIT", "10");
         std::env::set_var("DA_UPDATE_INTERVAL_MS", "1000");
         std::env::set_var("BLOCK_PRODUCTION_INTERVAL_MS", "1000");
+        std::env::set_var("MAX_SOFT_CONFIRMATION_SIZE_BYTES", "500000");
+        std::env::set_var("TX_ORDERING_POLICY", "fifo");
+        std::env::set_var("L1_FEE_RATE_MAX_CHANGE_PER_BLOCK_BPS", "500");
         std::env::set_var("PENDING_TX_LIMIT", "100000");
         std::env::set_var("PENDING_TX_SIZE", "200");
         std::env::set_var("QUEUE_TX_LIMIT", "100000");
@@ -635,9 +1916,23 @@ mod tests {
                 base_fee_tx_limit: 100000,
                 base_fee_tx_size: 200,
                 max_account_slots: 16,
+                tx_ttl_secs: default_tx_ttl_secs(),
+                queued_tx_ttl_secs: default_queued_tx_ttl_secs(),
+                conditional_tx_limit: default_conditional_tx_limit(),
+                conditional_tx_max_slots: default_conditional_tx_max_slots(),
             },
             da_update_interval_ms: 1000,
             block_production_interval_ms: 1000,
+            max_soft_confirmation_size_bytes: 500000,
+            rotated_private_keys: vec![],
+            tx_ordering_policy: TxOrderingPolicy::Fifo,
+            l1_fee_rate_smoothing: L1FeeRateSmoothingConfig {
+                alpha: default_l1_fee_rate_alpha(),
+                max_change_per_block_bps: 500,
+                min_fee_rate: None,
+                max_fee_rate: None,
+            },
+            max_timestamp_drift_secs: default_max_timestamp_drift_secs(),
         };
         assert_eq!(sequencer_config, expected);
     }
@@ -659,6 +1954,14 @@ mod tests {
         std::env::set_var("RPC_MAX_CONNECTIONS", "500");
         std::env::set_var("RPC_ENABLE_SUBSCRIPTIONS", "true");
         std::env::set_var("RPC_MAX_SUBSCRIPTIONS_PER_CONNECTION", "200");
+        std::env::set_var("RPC_METHOD_FILTER_ALLOW", "eth_*, citrea_*,");
+        std::env::set_var(
+            "RPC_CORS_ALLOWED_ORIGINS",
+            "https://citrea.xyz,https://*.citrea.xyz",
+        );
+        std::env::set_var("RPC_CORS_ALLOWED_HEADERS", "content-type");
+        std::env::set_var("RPC_CORS_ALLOW_CREDENTIALS", "true");
+        std::env::set_var("RPC_COMPRESSION_ENABLED", "false");
 
         std::env::set_var(
             "SENDER_ADDRESS",
@@ -672,9 +1975,13 @@ mod tests {
         std::env::set_var("INCLUDE_TX_BODY", "true");
         std::env::set_var("SEQUENCER_CLIENT_URL", "http://0.0.0.0:12346");
         std::env::set_var("PRUNING_DISTANCE", "1000");
+        std::env::set_var("PRUNING_L1_DISTANCE", "100");
+        std::env::set_var("TX_BODY_BACKFILL_REQUESTS_PER_SECOND", "20");
+        std::env::set_var("CONSISTENCY_CHECK_INTERVAL", "200");
 
-        std::env::set_var("TELEMETRY_BIND_HOST", "0.0.0.0");
-        std::env::set_var("TELEMETRY_BIND_PORT", "8082");
+        std::env::set_var("METRICS_ENABLED", "true");
+        std::env::set_var("METRICS_BIND_HOST", "0.0.0.0");
+        std::env::set_var("METRICS_BIND_PORT", "8082");
         let full_node_config: FullNodeConfig<sov_mock_da::MockDaConfig> =
             FullNodeConfig::from_env().unwrap();
 
@@ -688,30 +1995,81 @@ mod tests {
                 batch_requests_limit: default_batch_requests_limit(),
                 enable_subscriptions: true,
                 max_subscriptions_per_connection: 200,
+                max_soft_confirmation_hashes_per_request: 100,
+                max_verified_proofs_slot_range: 1000,
+                healthcheck_stall_multiple: 3.0,
+                sync_status_lag_tolerance: 5,
+                max_logs_block_range: 1_000,
+                max_logs_per_response: 10_000,
+                filter_timeout_secs: 300,
+                max_filters_per_connection: 100,
+                max_filters: 10_000,
+                admin_token: None,
+                rate_limit: Default::default(),
+                method_filter: RpcMethodFilterConfig {
+                    allow: vec!["eth_*".to_string(), "citrea_*".to_string()],
+                    deny: vec![],
+                },
+                cors: RpcCorsConfig {
+                    allowed_origins: vec![
+                        "https://citrea.xyz".to_string(),
+                        "https://*.citrea.xyz".to_string(),
+                    ],
+                    allowed_headers: vec!["content-type".to_string()],
+                    allow_credentials: true,
+                },
+                compression: RpcCompressionConfig {
+                    enabled: false,
+                    min_size: default_compression_min_size(),
+                },
+                gas_price_oracle: Default::default(),
             },
             storage: StorageConfig {
                 path: "/tmp/rollup".into(),
                 db_max_open_files: Some(123),
+                rocksdb: None,
+                archive: false,
             },
             runner: Some(RunnerConfig {
                 sequencer_client_url: "http://0.0.0.0:12346".to_string(),
+                secondary_sequencer_client_urls: vec![],
                 include_tx_body: true,
                 sync_blocks_count: default_sync_blocks_count(),
-                pruning_config: Some(PruningConfig { distance: 1000 }),
+                commit_blocks_count: default_commit_blocks_count(),
+                pruning_config: Some(PruningConfig {
+                    distance: 1000,
+                    l1_distance: Some(100),
+                }),
+                read_only: false,
+                tx_body_backfill: Some(TxBodyBackfillConfig {
+                    requests_per_second: 20,
+                }),
+                allow_genesis_state_root_mismatch: false,
+                consistency_check: Some(ConsistencyCheckConfig {
+                    interval: 200,
+                    ..Default::default()
+                }),
             }),
             da: sov_mock_da::MockDaConfig {
                 sender_address: [0; 32].into(),
                 db_path: "/tmp/da".into(),
+                send_transaction_delay_ms: 0,
             },
             public_keys: RollupPublicKeys {
                 sequencer_public_key: vec![0; 32],
                 sequencer_da_pub_key: vec![119; 32],
                 prover_da_pub_key: vec![],
+                sequencer_key_rotations: vec![],
+                accepted_sequencer_da_pub_keys: vec![],
             },
             telemetry: TelemetryConfig {
-                bind_host: Some("0.0.0.0".to_owned()),
-                bind_port: Some(8082),
+                metrics: MetricsConfig {
+                    enabled: true,
+                    bind_host: "0.0.0.0".to_owned(),
+                    bind_port: 8082,
+                },
             },
+            forks: vec![],
         };
         assert_eq!(full_node_config, expected);
     }
@@ -721,18 +2079,25 @@ mod tests {
         let telemetry_config = TelemetryConfig::from_env().unwrap();
 
         let expected = TelemetryConfig {
-            bind_host: None,
-            bind_port: None,
+            metrics: MetricsConfig {
+                enabled: false,
+                bind_host: "127.0.0.1".to_owned(),
+                bind_port: 8001,
+            },
         };
         assert_eq!(telemetry_config, expected);
 
-        std::env::set_var("TELEMETRY_BIND_HOST", "0.0.0.0");
-        std::env::set_var("TELEMETRY_BIND_PORT", "5000");
+        std::env::set_var("METRICS_ENABLED", "true");
+        std::env::set_var("METRICS_BIND_HOST", "0.0.0.0");
+        std::env::set_var("METRICS_BIND_PORT", "5000");
         let telemetry_config = TelemetryConfig::from_env().unwrap();
 
         let expected = TelemetryConfig {
-            bind_host: Some("0.0.0.0".to_owned()),
-            bind_port: Some(5000),
+            metrics: MetricsConfig {
+                enabled: true,
+                bind_host: "0.0.0.0".to_owned(),
+                bind_port: 5000,
+            },
         };
         assert_eq!(telemetry_config, expected);
     }
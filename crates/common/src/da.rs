@@ -46,21 +46,46 @@ pub async fn get_da_block_at_height<Da: DaService>(
 pub fn extract_sequencer_commitments<Da>(
     da_service: Arc<Da>,
     l1_block: &Da::FilteredBlock,
-    sequencer_da_pub_key: &[u8],
+    sequencer_da_pub_keys: &[Vec<u8>],
 ) -> Vec<SequencerCommitment>
 where
     Da: DaService,
 {
-    let mut sequencer_commitments = da_service
-        .as_ref()
-        .extract_relevant_sequencer_commitments(l1_block, sequencer_da_pub_key)
-        .inspect_err(|e| {
-            warn!("Failed to get sequencer commitments: {e}");
-        })
-        .unwrap_or_default();
+    extract_signed_sequencer_commitments(da_service, l1_block, sequencer_da_pub_keys)
+        .into_iter()
+        .map(|(sequencer_commitment, _)| sequencer_commitment)
+        .collect()
+}
+
+/// Extracts the sequencer commitments signed with any of the given DA public keys,
+/// each with the key that signed it. Commitments of other keys are ignored.
+pub fn extract_signed_sequencer_commitments<Da>(
+    da_service: Arc<Da>,
+    l1_block: &Da::FilteredBlock,
+    sequencer_da_pub_keys: &[Vec<u8>],
+) -> Vec<(SequencerCommitment, Vec<u8>)>
+where
+    Da: DaService,
+{
+    let mut sequencer_commitments = vec![];
+    for sequencer_da_pub_key in sequencer_da_pub_keys {
+        let signed_commitments = da_service
+            .as_ref()
+            .extract_relevant_sequencer_commitments(l1_block, sequencer_da_pub_key)
+            .inspect_err(|e| {
+                warn!("Failed to get sequencer commitments: {e}");
+            })
+            .unwrap_or_default();
+        sequencer_commitments.extend(
+            signed_commitments
+                .into_iter()
+                .map(|sequencer_commitment| (sequencer_commitment, sequencer_da_pub_key.clone())),
+        );
+    }
 
     // Make sure all sequencer commitments are stored in ascending order.
     // We sort before checking ranges to prevent substraction errors.
+    // The batch proof circuit orders the commitments the same way.
     sequencer_commitments.sort();
 
     sequencer_commitments
@@ -78,7 +103,10 @@ pub async fn extract_zk_proofs<Da: DaService>(
 
 pub async fn get_initial_slot_height(client: &HttpClient) -> u64 {
     loop {
-        match client.get_soft_confirmation_by_number(U64::from(1)).await {
+        match client
+            .get_soft_confirmation_by_number(U64::from(1), None)
+            .await
+        {
             Ok(Some(batch)) => return batch.da_slot_height,
             _ => {
                 // sleep 1
@@ -88,3 +116,65 @@ pub async fn get_initial_slot_height(client: &HttpClient) -> u64 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sov_mock_da::{MockAddress, MockBlob, MockBlock, MockDaService};
+    use sov_rollup_interface::da::{DaDataBatchProof, VersionedDaData};
+    use sov_rollup_interface::spec::SpecId;
+
+    use super::*;
+
+    fn commitment(l2_start_block_number: u64) -> SequencerCommitment {
+        SequencerCommitment {
+            merkle_root: [l2_start_block_number as u8; 32],
+            l2_start_block_number,
+            l2_end_block_number: l2_start_block_number + 9,
+        }
+    }
+
+    fn blob(commitment: SequencerCommitment, sender: &[u8]) -> MockBlob {
+        let data =
+            DaDataBatchProof::SequencerCommitment(commitment).encode_versioned(SpecId::Fork1);
+        MockBlob::new(data, MockAddress::from(sender.to_vec()), [0; 32])
+    }
+
+    #[test]
+    fn test_extract_sequencer_commitments_of_whitelisted_keys() {
+        let old_key = vec![1; 33];
+        let new_key = vec![2; 33];
+        let unknown_key = vec![3; 33];
+
+        let db_dir = tempfile::tempdir().unwrap();
+        let da_service = Arc::new(MockDaService::new(MockAddress::new([0; 32]), db_dir.path()));
+        let l1_block = MockBlock {
+            blobs: vec![
+                blob(commitment(21), &new_key),
+                blob(commitment(11), &old_key),
+                blob(commitment(31), &unknown_key),
+                blob(commitment(1), &new_key),
+            ],
+            ..Default::default()
+        };
+
+        let signed = extract_signed_sequencer_commitments(
+            da_service.clone(),
+            &l1_block,
+            &[new_key.clone(), old_key.clone()],
+        );
+        assert_eq!(
+            signed,
+            vec![
+                (commitment(1), new_key.clone()),
+                (commitment(11), old_key.clone()),
+                (commitment(21), new_key.clone()),
+            ]
+        );
+
+        assert_eq!(
+            extract_sequencer_commitments(da_service.clone(), &l1_block, &[old_key]),
+            vec![commitment(11)]
+        );
+        assert!(extract_sequencer_commitments(da_service, &l1_block, &[]).is_empty());
+    }
+}
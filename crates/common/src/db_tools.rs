@@ -0,0 +1,249 @@
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context};
+use sov_db::ledger_db::{LedgerDB, ProvingServiceLedgerOps, SequencerLedgerOps, SharedLedgerOps};
+use sov_db::native_db::NativeDB;
+use sov_db::rocks_db_config::RocksdbConfig;
+use sov_db::state_db::StateDB;
+use sov_prover_storage_manager::SnapshotManager;
+use tracing::info;
+
+const DB_LOCKED_HINT: &str = "Failed to open the databases, make sure the node is not running";
+
+/// The kind of node a storage directory belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Sequencer,
+    FullNode,
+    BatchProver,
+    LightClientProver,
+}
+
+impl fmt::Display for NodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeKind::Sequencer => write!(f, "sequencer"),
+            NodeKind::FullNode => write!(f, "full node"),
+            NodeKind::BatchProver => write!(f, "batch prover"),
+            NodeKind::LightClientProver => write!(f, "light client prover"),
+        }
+    }
+}
+
+/// Progress of a node as recorded in its ledger
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeStatus {
+    pub kind: NodeKind,
+    pub head_l2_height: Option<u64>,
+    pub last_scanned_l1_height: Option<u64>,
+    pub last_commitment_l2_height: Option<u64>,
+    pub last_pruned_l2_height: Option<u64>,
+    pub pending_commitments: usize,
+    pub pending_proving_sessions: usize,
+}
+
+impl fmt::Display for NodeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn height(height: Option<u64>) -> String {
+            height.map_or_else(|| "none".to_string(), |height| height.to_string())
+        }
+
+        writeln!(f, "Node: {}", self.kind)?;
+        if self.kind != NodeKind::LightClientProver {
+            writeln!(f, "Head L2 height: {}", height(self.head_l2_height))?;
+        }
+        if self.kind != NodeKind::Sequencer {
+            writeln!(
+                f,
+                "Last scanned L1 height: {}",
+                height(self.last_scanned_l1_height)
+            )?;
+        }
+        if self.kind != NodeKind::LightClientProver {
+            writeln!(
+                f,
+                "Last commitment L2 height: {}",
+                height(self.last_commitment_l2_height)
+            )?;
+            writeln!(
+                f,
+                "Last pruned L2 height: {}",
+                height(self.last_pruned_l2_height)
+            )?;
+        }
+        match self.kind {
+            NodeKind::Sequencer => {
+                writeln!(f, "Pending commitments: {}", self.pending_commitments)?
+            }
+            NodeKind::BatchProver => writeln!(
+                f,
+                "Pending proving sessions: {}",
+                self.pending_proving_sessions
+            )?,
+            NodeKind::FullNode | NodeKind::LightClientProver => {}
+        }
+        Ok(())
+    }
+}
+
+/// Reads the status of the node with storage at `storage_path`.
+/// The ledger is opened read-only, so this works while the node is running.
+pub fn read_node_status(storage_path: &Path, kind: NodeKind) -> anyhow::Result<NodeStatus> {
+    let ledger_db = LedgerDB::with_config_read_only(&RocksdbConfig::new(storage_path, None, None))
+        .context("Failed to open the ledger database")?;
+
+    let pending_commitments = match kind {
+        NodeKind::Sequencer => ledger_db.get_pending_commitments_l2_range()?.len(),
+        _ => 0,
+    };
+    let pending_proving_sessions = match kind {
+        NodeKind::BatchProver => ledger_db.get_pending_proving_sessions()?.len(),
+        _ => 0,
+    };
+
+    Ok(NodeStatus {
+        kind,
+        head_l2_height: ledger_db.get_head_soft_confirmation_height()?,
+        last_scanned_l1_height: ledger_db.get_last_scanned_l1_height()?.map(|h| h.0),
+        last_commitment_l2_height: ledger_db.get_last_commitment_l2_height()?.map(|h| h.0),
+        last_pruned_l2_height: ledger_db.get_last_pruned_l2_height()?,
+        pending_commitments,
+        pending_proving_sessions,
+    })
+}
+
+/// Rolls back the node with storage at `storage_path` to `l2_height`.
+/// The state and accessory storage are truncated first, then the ledger,
+/// so an interrupted rollback can be completed by running it again.
+pub fn rollback_to_l2_height(
+    storage_path: &Path,
+    kind: NodeKind,
+    l2_height: u64,
+) -> anyhow::Result<()> {
+    if kind == NodeKind::LightClientProver {
+        bail!("Rollback is not supported for the light client prover");
+    }
+
+    let rocksdb_config = RocksdbConfig::new(storage_path, None, None);
+    let ledger_db = LedgerDB::with_config(&rocksdb_config).context(DB_LOCKED_HINT)?;
+
+    let head_l2_height = ledger_db.get_head_soft_confirmation_height()?.unwrap_or(0);
+    ensure!(
+        l2_height <= head_l2_height,
+        "Cannot roll back to L2 height {} above the head L2 height {}",
+        l2_height,
+        head_l2_height
+    );
+    if let Some(last_pruned_l2_height) = ledger_db.get_last_pruned_l2_height()? {
+        ensure!(
+            l2_height >= last_pruned_l2_height,
+            "Cannot roll back to L2 height {} below the last pruned L2 height {}",
+            l2_height,
+            last_pruned_l2_height
+        );
+    }
+    if kind == NodeKind::Sequencer {
+        ensure_sequencer_can_roll_back(&ledger_db, l2_height)?;
+    }
+
+    info!(
+        "Rolling back {} from L2 height {} to {}",
+        kind, head_l2_height, l2_height
+    );
+
+    // Genesis is committed at state version 1, so L2 height `h` is at version `h + 1`
+    let state_db =
+        StateDB::<SnapshotManager>::setup_schema_db(&rocksdb_config).context(DB_LOCKED_HINT)?;
+    StateDB::<SnapshotManager>::rollback_schema_db(&state_db, l2_height + 1)?;
+
+    let native_db =
+        NativeDB::<SnapshotManager>::setup_schema_db(&rocksdb_config).context(DB_LOCKED_HINT)?;
+    NativeDB::<SnapshotManager>::rollback_schema_db(&native_db, l2_height)?;
+
+    ledger_db.rollback_to_l2_height(l2_height)
+}
+
+/// Fails if any of the sequencer's soft confirmations above `l2_height` is covered by
+/// a commitment, either confirmed or still pending on DA.
+pub fn ensure_sequencer_can_roll_back(
+    ledger_db: &impl SequencerLedgerOps,
+    l2_height: u64,
+) -> anyhow::Result<()> {
+    // Commitments published to DA cannot be taken back
+    let last_commitment_l2_height = ledger_db
+        .get_last_commitment_l2_height()?
+        .map_or(0, |h| h.0);
+    ensure!(
+        l2_height >= last_commitment_l2_height,
+        "Cannot roll back to L2 height {} below the last commitment L2 height {}",
+        l2_height,
+        last_commitment_l2_height
+    );
+    if let Some((_, end)) = ledger_db
+        .get_pending_commitments_l2_range()?
+        .into_iter()
+        .find(|(_, end)| end.0 > l2_height)
+    {
+        bail!(
+            "Cannot roll back to L2 height {} below the pending commitment L2 height {}",
+            l2_height,
+            end.0
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_of_empty_node() {
+        let storage = tempfile::tempdir().unwrap();
+        LedgerDB::with_config(&RocksdbConfig::new(storage.path(), None, None)).unwrap();
+
+        let status = read_node_status(storage.path(), NodeKind::BatchProver).unwrap();
+        assert_eq!(
+            status,
+            NodeStatus {
+                kind: NodeKind::BatchProver,
+                head_l2_height: None,
+                last_scanned_l1_height: None,
+                last_commitment_l2_height: None,
+                last_pruned_l2_height: None,
+                pending_commitments: 0,
+                pending_proving_sessions: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_rollback_refused_while_node_is_running() {
+        let storage = tempfile::tempdir().unwrap();
+        let _running =
+            LedgerDB::with_config(&RocksdbConfig::new(storage.path(), None, None)).unwrap();
+
+        // The ledger can still be read while it is locked
+        assert!(read_node_status(storage.path(), NodeKind::FullNode).is_ok());
+
+        let err = rollback_to_l2_height(storage.path(), NodeKind::FullNode, 0).unwrap_err();
+        assert_eq!(err.to_string(), DB_LOCKED_HINT);
+    }
+
+    #[test]
+    fn test_rollback_refused_below_last_pruned_height() {
+        let storage = tempfile::tempdir().unwrap();
+        {
+            let ledger_db =
+                LedgerDB::with_config(&RocksdbConfig::new(storage.path(), None, None)).unwrap();
+            ledger_db.set_last_pruned_l2_height(5).unwrap();
+        }
+
+        let err = rollback_to_l2_height(storage.path(), NodeKind::FullNode, 0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot roll back to L2 height 0 below the last pruned L2 height 5"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Structured events of the L2 block, sequencer commitment and batch proof lifecycles.
+//!
+//! Every event is emitted with the `citrea_common::events` target and carries an `event` field
+//! set to one of the names below, next to typed fields. Hashes and state roots are hex encoded
+//! and durations are in milliseconds. Event and field names are part of the JSON log schema,
+//! so they must not be renamed.
+
+use std::time::Duration;
+
+use tracing::info;
+
+use crate::TxOrderingPolicy;
+
+/// The sequencer produced an L2 block.
+pub const L2_BLOCK_PRODUCED: &str = "l2_block_produced";
+/// A full node or prover applied an L2 block received from the sequencer.
+pub const L2_BLOCK_APPLIED: &str = "l2_block_applied";
+/// A sequencer commitment was submitted to DA by the sequencer.
+pub const COMMITMENT_SUBMITTED: &str = "commitment_submitted";
+/// A sequencer commitment found on DA was verified and stored.
+pub const COMMITMENT_PROCESSED: &str = "commitment_processed";
+/// A batch proof was generated by the batch prover.
+pub const PROOF_GENERATED: &str = "proof_generated";
+/// A batch proof found on DA was verified and stored.
+pub const PROOF_VERIFIED: &str = "proof_verified";
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+pub fn l2_block_produced(
+    l2_height: u64,
+    l2_hash: [u8; 32],
+    l1_height: u64,
+    tx_count: usize,
+    tx_ordering_policy: TxOrderingPolicy,
+    duration: Duration,
+) {
+    info!(
+        event = L2_BLOCK_PRODUCED,
+        l2_height,
+        l2_hash = %hex::encode(l2_hash),
+        l1_height,
+        tx_count,
+        tx_ordering_policy = tx_ordering_policy.as_str(),
+        duration_ms = millis(duration),
+        "L2 block produced"
+    );
+}
+
+pub fn l2_block_applied(
+    l2_height: u64,
+    l2_hash: [u8; 32],
+    l1_height: u64,
+    state_root: &[u8],
+    duration: Duration,
+) {
+    info!(
+        event = L2_BLOCK_APPLIED,
+        l2_height,
+        l2_hash = %hex::encode(l2_hash),
+        l1_height,
+        state_root = %hex::encode(state_root),
+        duration_ms = millis(duration),
+        "L2 block applied"
+    );
+}
+
+pub fn commitment_submitted(
+    l2_start_height: u64,
+    l2_end_height: u64,
+    merkle_root: [u8; 32],
+    duration: Duration,
+) {
+    info!(
+        event = COMMITMENT_SUBMITTED,
+        l2_start_height,
+        l2_end_height,
+        merkle_root = %hex::encode(merkle_root),
+        duration_ms = millis(duration),
+        "Sequencer commitment submitted"
+    );
+}
+
+pub fn commitment_processed(
+    l1_height: u64,
+    l2_start_height: u64,
+    l2_end_height: u64,
+    merkle_root: [u8; 32],
+) {
+    info!(
+        event = COMMITMENT_PROCESSED,
+        l1_height,
+        l2_start_height,
+        l2_end_height,
+        merkle_root = %hex::encode(merkle_root),
+        "Sequencer commitment processed"
+    );
+}
+
+pub fn proof_generated(
+    l1_height: u64,
+    sequencer_commitments_range: (u32, u32),
+    proof_size: usize,
+    duration: Duration,
+) {
+    info!(
+        event = PROOF_GENERATED,
+        l1_height,
+        first_commitment_index = sequencer_commitments_range.0,
+        last_commitment_index = sequencer_commitments_range.1,
+        proof_size,
+        duration_ms = millis(duration),
+        "Batch proof generated"
+    );
+}
+
+pub fn proof_verified(
+    l1_height: u64,
+    l2_start_height: u64,
+    l2_end_height: u64,
+    proof_size: usize,
+    duration: Duration,
+) {
+    info!(
+        event = PROOF_VERIFIED,
+        l1_height,
+        l2_start_height,
+        l2_end_height,
+        proof_size,
+        duration_ms = millis(duration),
+        "Batch proof verified"
+    );
+}
@@ -4,7 +4,9 @@
 pub mod cache;
 pub mod config;
 pub mod da;
+pub mod db_tools;
 pub mod error;
+pub mod events;
 pub mod rpc;
 pub mod tasks;
 pub mod utils;
@@ -0,0 +1,38 @@
+//! Compression of the HTTP responses of the RPC servers
+use hyper::body::Body;
+use hyper::{Response, StatusCode};
+use tower_http::compression::predicate::{Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+
+use crate::RpcCompressionConfig;
+
+/// Returns compression layer to be used as http middleware.
+/// Responses are compressed with gzip or brotli, as negotiated with the `Accept-Encoding` header.
+pub fn get_compression_layer(config: &RpcCompressionConfig) -> CompressionLayer<CompressResponse> {
+    CompressionLayer::new()
+        .no_deflate()
+        .no_zstd()
+        .compress_when(CompressResponse {
+            enabled: config.enabled,
+            size_above: SizeAbove::new(config.min_size),
+        })
+}
+
+/// Compresses the responses of at least the configured size, if compression is enabled.
+/// WebSocket handshakes are never compressed.
+#[derive(Debug, Clone, Copy)]
+pub struct CompressResponse {
+    enabled: bool,
+    size_above: SizeAbove,
+}
+
+impl Predicate for CompressResponse {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: Body,
+    {
+        self.enabled
+            && response.status() != StatusCode::SWITCHING_PROTOCOLS
+            && self.size_above.should_compress(response)
+    }
+}
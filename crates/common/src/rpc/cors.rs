@@ -0,0 +1,244 @@
+//! CORS policy of the RPC servers
+use std::sync::Arc;
+
+use anyhow::{anyhow, ensure};
+use hyper::header::HeaderName;
+use hyper::Method;
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
+
+use crate::RpcCorsConfig;
+
+/// Returns cors layer to be used as http middleware.
+/// Fails if the configured origins or headers are malformed.
+pub fn get_cors_layer(config: &RpcCorsConfig) -> anyhow::Result<CorsLayer> {
+    let allow_any_origin = config.allowed_origins.iter().any(|origin| origin == "*");
+    ensure!(
+        !allow_any_origin || config.allowed_origins.len() == 1,
+        "CORS origin `*` allows any origin and cannot be combined with other origins"
+    );
+    let allow_any_header = config.allowed_headers.iter().any(|header| header == "*");
+    ensure!(
+        !allow_any_header || config.allowed_headers.len() == 1,
+        "CORS header `*` allows any header and cannot be combined with other headers"
+    );
+    ensure!(
+        !config.allow_credentials || (!allow_any_origin && !allow_any_header),
+        "CORS credentials can only be allowed with explicitly listed origins and headers"
+    );
+
+    let allow_origin = if allow_any_origin {
+        AllowOrigin::from(Any)
+    } else {
+        let patterns = config
+            .allowed_origins
+            .iter()
+            .map(|origin| OriginPattern::parse(origin))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let patterns = Arc::new(patterns);
+        AllowOrigin::predicate(move |origin, _| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| patterns.iter().any(|pattern| pattern.matches(origin)))
+        })
+    };
+
+    let allow_headers = if allow_any_header {
+        AllowHeaders::from(Any)
+    } else {
+        let headers = config
+            .allowed_headers
+            .iter()
+            .map(|header| {
+                HeaderName::from_bytes(header.as_bytes())
+                    .map_err(|_| anyhow!("Invalid CORS header name: `{}`", header))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowHeaders::list(headers)
+    };
+
+    Ok(CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_origin(allow_origin)
+        .allow_headers(allow_headers)
+        .allow_credentials(config.allow_credentials))
+}
+
+/// An allowed origin, either exact like `https://example.com:8080`
+/// or matching any subdomain like `https://*.example.com`
+#[derive(Debug, Clone, PartialEq)]
+struct OriginPattern {
+    scheme: String,
+    /// The host, without the leading `*.` of subdomain patterns
+    host: String,
+    port: Option<u16>,
+    any_subdomain: bool,
+}
+
+impl OriginPattern {
+    fn parse(pattern: &str) -> anyhow::Result<Self> {
+        let invalid = |reason: &str| {
+            anyhow!(
+                "Invalid CORS origin `{}`: {}. Expected an origin like `https://example.com` or `https://*.example.com`",
+                pattern,
+                reason
+            )
+        };
+
+        let normalized = pattern.to_ascii_lowercase();
+        let Some((scheme, authority)) = normalized.split_once("://") else {
+            return Err(invalid("missing scheme"));
+        };
+        if scheme != "http" && scheme != "https" {
+            return Err(invalid("scheme must be http or https"));
+        }
+        if authority.contains(['/', '?', '#', '@']) {
+            return Err(invalid("origins cannot have a path, query or user info"));
+        }
+
+        // IPv6 hosts are bracketed and contain colons themselves
+        let port_separator = match authority.rfind(']') {
+            Some(end) => authority[end..].find(':').map(|i| end + i),
+            None => authority.find(':'),
+        };
+        let (host, port) = match port_separator {
+            Some(i) => {
+                let port = authority[i + 1..]
+                    .parse::<u16>()
+                    .map_err(|_| invalid("invalid port"))?;
+                (&authority[..i], Some(port))
+            }
+            None => (authority, None),
+        };
+
+        let (host, any_subdomain) = match host.strip_prefix("*.") {
+            Some(host) => (host, true),
+            None => (host, false),
+        };
+        let is_valid_host = if host.starts_with('[') {
+            !any_subdomain
+                && host.len() > 2
+                && host.ends_with(']')
+                && host[1..host.len() - 1]
+                    .chars()
+                    .all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.')
+        } else {
+            !host.is_empty()
+                && host.split('.').all(|label| {
+                    !label.is_empty()
+                        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+        };
+        if !is_valid_host {
+            return Err(invalid(
+                "invalid host, `*` is only allowed as the leftmost label of a domain",
+            ));
+        }
+
+        Ok(Self {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            port,
+            any_subdomain,
+        })
+    }
+
+    /// Returns whether the `Origin` header value is allowed by the pattern
+    fn matches(&self, origin: &str) -> bool {
+        let Ok(origin) = Self::parse(origin) else {
+            return false;
+        };
+        if origin.any_subdomain || origin.scheme != self.scheme || origin.port != self.port {
+            return false;
+        }
+
+        if self.any_subdomain {
+            origin
+                .host
+                .strip_suffix(self.host.as_str())
+                .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.'))
+        } else {
+            origin.host == self.host
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(origin: &str) -> OriginPattern {
+        OriginPattern::parse(origin).unwrap()
+    }
+
+    #[test]
+    fn origin_patterns() {
+        let exact = pattern("https://app.example.com");
+        assert!(exact.matches("https://app.example.com"));
+        assert!(exact.matches("HTTPS://App.Example.com"));
+        assert!(!exact.matches("http://app.example.com"));
+        assert!(!exact.matches("https://app.example.com:8080"));
+        assert!(!exact.matches("https://app.example.com.evil.com"));
+        assert!(!exact.matches("https://evilapp.example.com"));
+
+        let with_port = pattern("http://localhost:3000");
+        assert!(with_port.matches("http://localhost:3000"));
+        assert!(!with_port.matches("http://localhost"));
+        assert!(!with_port.matches("http://localhost:3001"));
+
+        let subdomains = pattern("https://*.example.com");
+        assert!(subdomains.matches("https://app.example.com"));
+        assert!(subdomains.matches("https://a.b.example.com"));
+        assert!(!subdomains.matches("https://example.com"));
+        assert!(!subdomains.matches("https://evilexample.com"));
+        assert!(!subdomains.matches("https://example.com.evil.com"));
+        assert!(!subdomains.matches("http://app.example.com"));
+        assert!(!subdomains.matches("https://app.example.com:8443"));
+
+        let ipv6 = pattern("http://[::1]:8080");
+        assert!(ipv6.matches("http://[::1]:8080"));
+        assert!(!ipv6.matches("http://[::1]"));
+    }
+
+    #[test]
+    fn malformed_origin_patterns() {
+        for origin in [
+            "example.com",
+            "ftp://example.com",
+            "https://",
+            "https://example.com/",
+            "https://example.com/path",
+            "https://user@example.com",
+            "https://example.com:port",
+            "https://example.com:99999",
+            "https://app.*.example.com",
+            "https://*example.com",
+            "https://*",
+            "https://exa mple.com",
+            "https://example..com",
+        ] {
+            assert!(OriginPattern::parse(origin).is_err(), "{}", origin);
+        }
+    }
+
+    #[test]
+    fn cors_layer_validation() {
+        let config = |origins: &[&str], headers: &[&str], allow_credentials: bool| RpcCorsConfig {
+            allowed_origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            allowed_headers: headers.iter().map(|header| header.to_string()).collect(),
+            allow_credentials,
+        };
+
+        assert!(get_cors_layer(&RpcCorsConfig::default()).is_ok());
+        assert!(
+            get_cors_layer(&config(&["https://*.example.com"], &["content-type"], true)).is_ok()
+        );
+        assert!(get_cors_layer(&config(&[], &["*"], false)).is_ok());
+
+        assert!(get_cors_layer(&config(&["*", "https://example.com"], &["*"], false)).is_err());
+        assert!(get_cors_layer(&config(&["*"], &["*", "content-type"], false)).is_err());
+        assert!(get_cors_layer(&config(&["*"], &["content-type"], true)).is_err());
+        assert!(get_cors_layer(&config(&["https://example.com"], &["*"], true)).is_err());
+        assert!(get_cors_layer(&config(&["https://example.com/"], &["*"], false)).is_err());
+        assert!(get_cors_layer(&config(&["*"], &["content type"], false)).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! Exposes the fork schedule the node runs with
+use citrea_primitives::forks::{fork_from_block_number, get_forks};
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use serde::{Deserialize, Serialize};
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_rollup_interface::spec::SpecId;
+
+/// A fork of the schedule returned by `citrea_getForkSchedule`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkActivation {
+    pub spec_id: SpecId,
+    /// L2 height the fork activates at
+    pub activation_l2_height: u64,
+}
+
+/// Response of `citrea_getForkSchedule`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkSchedule {
+    /// Effective schedule, including overrides from the config
+    pub forks: Vec<ForkActivation>,
+    /// Spec id of the head L2 block
+    pub active_spec_id: SpecId,
+}
+
+/// Register the `citrea_getForkSchedule` rpc.
+pub fn register_fork_schedule_rpc<T: Send + Sync + 'static>(
+    rpc_methods: &mut RpcModule<T>,
+    ledger_db: LedgerDB,
+) -> anyhow::Result<()> {
+    let mut rpc = RpcModule::new(ledger_db);
+    rpc.register_method("citrea_getForkSchedule", |_, ledger_db, _| {
+        let head_l2_height = ledger_db
+            .get_head_soft_confirmation_height()
+            .map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(e.to_string()),
+                )
+            })?
+            .unwrap_or(0);
+
+        Ok::<_, ErrorObjectOwned>(ForkSchedule {
+            forks: get_forks()
+                .iter()
+                .map(|fork| ForkActivation {
+                    spec_id: fork.spec_id,
+                    activation_l2_height: fork.activation_height,
+                })
+                .collect(),
+            active_spec_id: fork_from_block_number(head_l2_height).spec_id,
+        })
+    })?;
+
+    rpc_methods.merge(rpc)?;
+    Ok(())
+}
@@ -0,0 +1,225 @@
+//! Background tracking of the head soft confirmation for the health check
+use std::time::Duration;
+
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_db::schema::types::SoftConfirmationNumber;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+// Exit early if head_batch_num is below this threshold
+const BLOCK_NUM_THRESHOLD: u64 = 2;
+
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Cached result of the latest health evaluation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HealthState {
+    Healthy,
+    Unhealthy(String),
+}
+
+/// Tracks head progression and decides whether the node is stalled.
+/// Time is passed in explicitly so the decision logic does not depend on real sleeps.
+#[derive(Debug)]
+pub(crate) struct HeadTracker {
+    stall_multiple: f64,
+    head: Option<u64>,
+    last_advance: Instant,
+    block_time: Duration,
+}
+
+impl HeadTracker {
+    pub(crate) fn new(stall_multiple: f64, now: Instant) -> Self {
+        Self {
+            stall_multiple,
+            head: None,
+            last_advance: now,
+            block_time: Duration::from_secs(1),
+        }
+    }
+
+    /// Returns whether `head` differs from the last observed head
+    pub(crate) fn is_new_head(&self, head: u64) -> bool {
+        self.head != Some(head)
+    }
+
+    /// Records the current head and returns the resulting health state.
+    /// `block_time` is only taken into account when the head has moved.
+    pub(crate) fn observe(
+        &mut self,
+        head: Option<u64>,
+        block_time: Option<Duration>,
+        now: Instant,
+    ) -> HealthState {
+        // No soft confirmations yet, nothing to compare against
+        let Some(head) = head else {
+            return HealthState::Healthy;
+        };
+
+        if self.is_new_head(head) {
+            self.head = Some(head);
+            self.last_advance = now;
+            if let Some(block_time) = block_time {
+                self.block_time = block_time;
+            }
+        }
+
+        // TODO: if the first blocks are not being produced properly, this might cause healthcheck to always return Ok
+        if head < BLOCK_NUM_THRESHOLD {
+            return HealthState::Healthy;
+        }
+
+        let stalled_for = now.saturating_duration_since(self.last_advance);
+        if stalled_for > self.block_time.mul_f64(self.stall_multiple) {
+            HealthState::Unhealthy(format!(
+                "Block number is not increasing, head {} unchanged for {}ms",
+                head,
+                stalled_for.as_millis()
+            ))
+        } else {
+            HealthState::Healthy
+        }
+    }
+}
+
+/// Polls the ledger for the head soft confirmation and publishes the health state
+/// until the node shuts down or every receiver is dropped.
+pub(crate) async fn watch_head(
+    ledger_db: LedgerDB,
+    mut tracker: HeadTracker,
+    health_tx: watch::Sender<HealthState>,
+    cancellation_token: CancellationToken,
+) {
+    let mut interval = tokio::time::interval(HEALTH_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            biased;
+            _ = cancellation_token.cancelled() => return,
+            _ = interval.tick() => {},
+        }
+
+        let state = match poll_head(&ledger_db, &tracker) {
+            Ok((head, block_time)) => tracker.observe(head, block_time, Instant::now()),
+            Err(e) => {
+                warn!(
+                    "Health check failed to read head soft confirmation: {:?}",
+                    e
+                );
+                HealthState::Unhealthy(e.to_string())
+            }
+        };
+
+        if health_tx.send(state).is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads the head height and, if it moved, the block time between the last two soft confirmations
+fn poll_head(
+    ledger_db: &LedgerDB,
+    tracker: &HeadTracker,
+) -> anyhow::Result<(Option<u64>, Option<Duration>)> {
+    let Some((SoftConfirmationNumber(head), _)) = ledger_db.get_head_soft_confirmation()? else {
+        return Ok((None, None));
+    };
+
+    if head < BLOCK_NUM_THRESHOLD || !tracker.is_new_head(head) {
+        return Ok((Some(head), None));
+    }
+
+    let soft_batches = ledger_db.get_soft_confirmation_range(
+        &(SoftConfirmationNumber(head - 1)..=SoftConfirmationNumber(head)),
+    )?;
+    let block_time = match soft_batches.as_slice() {
+        [prev, last] => Some(Duration::from_secs(
+            last.timestamp.saturating_sub(prev.timestamp).max(1),
+        )),
+        _ => None,
+    };
+
+    Ok((Some(head), block_time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_TIME: Duration = Duration::from_secs(2);
+
+    #[test]
+    fn node_just_started_is_healthy() {
+        let start = Instant::now();
+        let mut tracker = HeadTracker::new(1.5, start);
+
+        assert_eq!(tracker.observe(None, None, start), HealthState::Healthy);
+        assert_eq!(tracker.observe(Some(1), None, start), HealthState::Healthy);
+        // Below the threshold a stuck head is not reported
+        assert_eq!(
+            tracker.observe(Some(1), None, start + Duration::from_secs(60)),
+            HealthState::Healthy
+        );
+    }
+
+    #[test]
+    fn stalled_head_is_unhealthy() {
+        let start = Instant::now();
+        let mut tracker = HeadTracker::new(1.5, start);
+
+        assert_eq!(
+            tracker.observe(Some(5), Some(BLOCK_TIME), start),
+            HealthState::Healthy
+        );
+        // Within 1.5 block times
+        assert_eq!(
+            tracker.observe(Some(5), None, start + Duration::from_secs(3)),
+            HealthState::Healthy
+        );
+        assert!(matches!(
+            tracker.observe(Some(5), None, start + Duration::from_millis(3001)),
+            HealthState::Unhealthy(_)
+        ));
+    }
+
+    #[test]
+    fn recovering_head_is_healthy_again() {
+        let start = Instant::now();
+        let mut tracker = HeadTracker::new(1.5, start);
+
+        tracker.observe(Some(5), Some(BLOCK_TIME), start);
+        let stalled = start + Duration::from_secs(10);
+        assert!(matches!(
+            tracker.observe(Some(5), None, stalled),
+            HealthState::Unhealthy(_)
+        ));
+
+        // Head moved again, the stall window restarts from here
+        assert_eq!(
+            tracker.observe(Some(6), Some(BLOCK_TIME), stalled),
+            HealthState::Healthy
+        );
+        assert_eq!(
+            tracker.observe(Some(6), None, stalled + Duration::from_secs(3)),
+            HealthState::Healthy
+        );
+        assert!(matches!(
+            tracker.observe(Some(6), None, stalled + Duration::from_secs(4)),
+            HealthState::Unhealthy(_)
+        ));
+    }
+
+    #[test]
+    fn block_time_follows_latest_observation() {
+        let start = Instant::now();
+        let mut tracker = HeadTracker::new(2.0, start);
+
+        tracker.observe(Some(5), Some(Duration::from_secs(10)), start);
+        tracker.observe(Some(6), Some(Duration::from_secs(1)), start);
+        assert!(matches!(
+            tracker.observe(Some(6), None, start + Duration::from_secs(3)),
+            HealthState::Unhealthy(_)
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+//! Allowlist and denylist of the RPC methods served by a node
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::types::{ErrorCode, ErrorObjectOwned, Request};
+use jsonrpsee::MethodResponse;
+
+use crate::RpcMethodFilterConfig;
+
+/// Decides which methods are served, see [`RpcMethodFilterConfig`]
+#[derive(Debug, Clone)]
+pub struct MethodFilter {
+    allow: Arc<Vec<String>>,
+    deny: Arc<Vec<String>>,
+}
+
+impl MethodFilter {
+    pub fn new(config: &RpcMethodFilterConfig) -> Self {
+        Self {
+            allow: Arc::new(config.allow.clone()),
+            deny: Arc::new(config.deny.clone()),
+        }
+    }
+
+    /// Returns whether the method is served
+    pub fn is_enabled(&self, method: &str) -> bool {
+        let allowed =
+            self.allow.is_empty() || self.allow.iter().any(|glob| glob_matches(glob, method));
+        allowed && !self.deny.iter().any(|glob| glob_matches(glob, method))
+    }
+}
+
+/// RPC middleware rejecting the filtered methods before they reach their handlers.
+/// They are rejected with the error of unknown methods, so a disabled method looks absent.
+///
+/// The middleware sees every call on its own, so each request of a batch is filtered separately
+/// and subscriptions are filtered by their subscribe and unsubscribe methods.
+#[derive(Debug, Clone)]
+pub struct FilteredMethods<S> {
+    service: S,
+    filter: MethodFilter,
+}
+
+impl<S> FilteredMethods<S> {
+    pub fn new(service: S, filter: MethodFilter) -> Self {
+        Self { service, filter }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for FilteredMethods<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'a,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, req: Request<'a>) -> Self::Future {
+        if !self.filter.is_enabled(req.method_name()) {
+            let err = ErrorObjectOwned::from(ErrorCode::MethodNotFound);
+            return futures::future::ready(MethodResponse::error(req.id, err)).boxed();
+        }
+
+        self.service.call(req).boxed()
+    }
+}
+
+/// Matches a method name against a glob, where `*` matches any sequence of characters
+/// and `?` matches a single character.
+fn glob_matches(glob: &str, method: &str) -> bool {
+    let glob = glob.as_bytes();
+    let method = method.as_bytes();
+    let (mut g, mut m) = (0, 0);
+    // Position of the last `*` in the glob, and of the method character it was matched up to
+    let mut backtrack = None;
+
+    while m < method.len() {
+        match glob.get(g) {
+            Some(b'*') => {
+                backtrack = Some((g, m));
+                g += 1;
+            }
+            Some(&c) if c == b'?' || c == method[m] => {
+                g += 1;
+                m += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` match one more character
+                Some((star, star_m)) => {
+                    backtrack = Some((star, star_m + 1));
+                    g = star + 1;
+                    m = star_m + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method_filter(allow: &[&str], deny: &[&str]) -> MethodFilter {
+        MethodFilter::new(&RpcMethodFilterConfig {
+            allow: allow.iter().map(|glob| glob.to_string()).collect(),
+            deny: deny.iter().map(|glob| glob.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_matches(
+            "eth_sendRawTransaction",
+            "eth_sendRawTransaction"
+        ));
+        assert!(!glob_matches(
+            "eth_sendRawTransaction",
+            "eth_sendRawTransactions"
+        ));
+        assert!(!glob_matches(
+            "eth_sendRawTransaction",
+            "eth_sendTransaction"
+        ));
+
+        assert!(glob_matches("debug_*", "debug_traceTransaction"));
+        assert!(glob_matches("debug_*", "debug_"));
+        assert!(!glob_matches("debug_*", "eth_debug_trace"));
+        assert!(glob_matches("*", "eth_blockNumber"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches(
+            "eth_*Filter*",
+            "eth_newPendingTransactionFilter"
+        ));
+        assert!(glob_matches("eth_*Filter*", "eth_getFilterChanges"));
+        assert!(!glob_matches("eth_*Filter*", "eth_getLogs"));
+        assert!(glob_matches("*_subscribe", "eth_subscribe"));
+        assert!(glob_matches("*subscribe", "eth_unsubscribe"));
+        // The `*` backtracks over repeated prefixes
+        assert!(glob_matches("*aab", "aaaab"));
+        assert!(!glob_matches("*aab", "aaaba"));
+
+        assert!(glob_matches("eth_get?ode", "eth_getCode"));
+        assert!(!glob_matches("eth_get?ode", "eth_getode"));
+        assert!(!glob_matches("", "eth_blockNumber"));
+    }
+
+    #[test]
+    fn everything_enabled_by_default() {
+        let filter = MethodFilter::new(&RpcMethodFilterConfig::default());
+        for method in [
+            "eth_sendRawTransaction",
+            "debug_traceTransaction",
+            "txpool_content",
+        ] {
+            assert!(filter.is_enabled(method));
+        }
+    }
+
+    #[test]
+    fn denylist_wins_over_allowlist() {
+        let filter = method_filter(&["eth_*", "citrea_*"], &["eth_sendRawTransaction"]);
+        assert!(filter.is_enabled("eth_blockNumber"));
+        assert!(filter.is_enabled("citrea_syncStatus"));
+        assert!(!filter.is_enabled("eth_sendRawTransaction"));
+        assert!(!filter.is_enabled("debug_traceTransaction"));
+
+        let filter = method_filter(&[], &["debug_*", "txpool_*"]);
+        assert!(filter.is_enabled("eth_sendRawTransaction"));
+        assert!(!filter.is_enabled("debug_traceTransaction"));
+        assert!(!filter.is_enabled("txpool_content"));
+    }
+}
@@ -1,68 +1,68 @@
 //! Common RPC crate provides helper methods that are needed in rpc servers
-use std::time::Duration;
+mod compression;
+mod cors;
+mod fork_schedule;
+mod health;
+mod method_filter;
+mod rate_limit;
+mod server;
+mod state_diff_size;
+mod sync_status;
+mod tx_soft_confirmation;
+mod tx_summary;
 
 use futures::future::BoxFuture;
 use futures::FutureExt;
-use hyper::Method;
 use jsonrpsee::core::RegisterMethodError;
 use jsonrpsee::server::middleware::http::ProxyGetRequestLayer;
 use jsonrpsee::server::middleware::rpc::RpcServiceT;
 use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
 use jsonrpsee::types::{ErrorObjectOwned, Request};
 use jsonrpsee::{MethodResponse, RpcModule};
-use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
-use sov_db::schema::types::SoftConfirmationNumber;
-use tower_http::cors::{Any, CorsLayer};
-
-// Exit early if head_batch_num is below this threshold
-const BLOCK_NUM_THRESHOLD: u64 = 2;
-
-/// Register the healthcheck rpc
+use metrics::histogram;
+use sov_db::ledger_db::LedgerDB;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+pub use self::compression::{get_compression_layer, CompressResponse};
+pub use self::cors::get_cors_layer;
+pub use self::fork_schedule::{register_fork_schedule_rpc, ForkActivation, ForkSchedule};
+use self::health::{watch_head, HeadTracker, HealthState};
+pub use self::method_filter::{FilteredMethods, MethodFilter};
+pub use self::rate_limit::{RateLimit, RateLimiter, RateLimiters, RATE_LIMIT_EXCEEDED_ERROR_CODE};
+pub use self::server::start_server;
+pub use self::state_diff_size::{
+    register_state_diff_size_rpc, BlockStateDiffSize, MAX_STATE_DIFF_SIZE_RANGE,
+};
+pub use self::sync_status::{register_sync_status_rpc, SyncStatus};
+pub use self::tx_soft_confirmation::{register_tx_soft_confirmation_rpc, TxSoftConfirmation};
+pub use self::tx_summary::EvmTxSummaryProvider;
+use crate::tasks::manager::TaskManager;
+
+/// Register the healthcheck rpc.
+/// Head progression is tracked by a background task of the node and the method only reads the cached state.
 pub fn register_healthcheck_rpc<T: Send + Sync + 'static>(
     rpc_methods: &mut RpcModule<T>,
     ledger_db: LedgerDB,
+    stall_multiple: f64,
+    task_manager: &mut TaskManager<()>,
 ) -> Result<(), RegisterMethodError> {
-    let mut rpc = RpcModule::new(ledger_db);
-
-    rpc.register_async_method("health_check", |_, ledger_db, _| async move {
-        let error = |msg: &str| {
-            ErrorObjectOwned::owned(
+    let (health_tx, health_rx) = watch::channel(HealthState::Healthy);
+    let tracker = HeadTracker::new(stall_multiple, Instant::now());
+    task_manager.spawn_best_effort("health_watcher", |tk| {
+        watch_head(ledger_db, tracker, health_tx, tk)
+    });
+
+    let mut rpc = RpcModule::new(health_rx);
+
+    rpc.register_method("health_check", |_, health_rx, _| {
+        match &*health_rx.borrow() {
+            HealthState::Healthy => Ok::<(), ErrorObjectOwned>(()),
+            HealthState::Unhealthy(msg) => Err(ErrorObjectOwned::owned(
                 INTERNAL_ERROR_CODE,
                 INTERNAL_ERROR_MSG,
-                Some(msg.to_string()),
-            )
-        };
-
-        let Some((SoftConfirmationNumber(head_batch_num), _)) = ledger_db
-            .get_head_soft_confirmation()
-            .map_err(|err| error(&format!("Failed to get head soft batch: {}", err)))?
-        else {
-            return Ok::<(), ErrorObjectOwned>(());
-        };
-
-        // TODO: if the first blocks are not being produced properly, this might cause healthcheck to always return Ok
-        if head_batch_num < BLOCK_NUM_THRESHOLD {
-            return Ok::<(), ErrorObjectOwned>(());
-        }
-
-        let soft_batches = ledger_db
-            .get_soft_confirmation_range(
-                &(SoftConfirmationNumber(head_batch_num - 1)
-                    ..=SoftConfirmationNumber(head_batch_num)),
-            )
-            .map_err(|err| error(&format!("Failed to get soft batch range: {}", err)))?;
-
-        let block_time_s = (soft_batches[1].timestamp - soft_batches[0].timestamp).max(1);
-        tokio::time::sleep(Duration::from_millis(block_time_s * 1500)).await;
-
-        let (new_head_batch_num, _) = ledger_db
-            .get_head_soft_confirmation()
-            .map_err(|err| error(&format!("Failed to get head soft batch: {}", err)))?
-            .unwrap();
-        if new_head_batch_num > SoftConfirmationNumber(head_batch_num) {
-            Ok::<(), ErrorObjectOwned>(())
-        } else {
-            Err(error("Block number is not increasing"))
+                Some(msg.clone()),
+            )),
         }
     })?;
 
@@ -74,14 +74,6 @@ pub fn get_healthcheck_proxy_layer() -> ProxyGetRequestLayer {
     ProxyGetRequestLayer::new("/health", "health_check").unwrap()
 }
 
-/// Returns cors layer to be used as http middleware
-pub fn get_cors_layer() -> CorsLayer {
-    CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
-        .allow_origin(Any)
-        .allow_headers(Any)
-}
-
 #[derive(Debug, Clone)]
 pub struct Logger<S>(pub S);
 
@@ -99,7 +91,10 @@ where
 
         let service = self.0.clone();
         async move {
+            let start = Instant::now();
             let resp = service.call(req).await;
+            histogram!("citrea_rpc_request_latency_seconds", "method" => req_method.clone())
+                .record(start.elapsed().as_secs_f64());
             if resp.is_success() {
                 tracing::debug!(id = ?req_id, method = ?req_method, result = ?resp.as_result(), "rpc_success");
             } else {
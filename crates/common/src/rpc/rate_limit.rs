@@ -0,0 +1,325 @@
+//! Per remote IP token bucket rate limiting of RPC methods
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::types::{ErrorObjectOwned, Request};
+use jsonrpsee::MethodResponse;
+use tokio::time::Instant;
+
+use crate::{RpcRateLimit, RpcRateLimitConfig};
+
+/// Same code as the other "limit exceeded" errors returned by the rpc servers
+pub const RATE_LIMIT_EXCEEDED_ERROR_CODE: i32 = -32005;
+
+/// Number of remote IPs above which the ones with full buckets are forgotten
+const MAX_IDLE_REMOTE_IPS: usize = 10_000;
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &RpcRateLimit, now: Instant) -> Self {
+        let capacity = f64::from(limit.burst.max(1));
+        Self {
+            capacity,
+            refill_per_sec: f64::from(limit.requests_per_second),
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token if one is available, refilling the bucket for the time passed since the last call
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the bucket would be full after refilling it, i.e. it is not throttling anyone
+    fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens + elapsed.as_secs_f64() * self.refill_per_sec >= self.capacity
+    }
+
+    /// Time until the next token is available
+    fn retry_after(&self) -> Duration {
+        if self.refill_per_sec == 0.0 {
+            return Duration::MAX;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens).max(0.0) / self.refill_per_sec)
+    }
+}
+
+/// Token buckets of a single remote IP, shared by all of its connections
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    cheap: Option<Arc<Mutex<TokenBucket>>>,
+    expensive: Option<Arc<Mutex<TokenBucket>>>,
+    expensive_methods: Arc<HashSet<String>>,
+}
+
+impl RateLimiter {
+    /// Creates the token buckets of a new remote IP, all of them full
+    pub fn new(config: &RpcRateLimitConfig) -> Self {
+        let now = Instant::now();
+        let bucket = |limit: &RpcRateLimit| Arc::new(Mutex::new(TokenBucket::new(limit, now)));
+        Self {
+            cheap: config.cheap.as_ref().map(bucket),
+            expensive: config.expensive.as_ref().map(bucket),
+            expensive_methods: Arc::new(config.expensive_methods.iter().cloned().collect()),
+        }
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        [&self.cheap, &self.expensive]
+            .into_iter()
+            .flatten()
+            .all(|bucket| bucket.lock().unwrap().is_full(now))
+    }
+
+    /// Returns an error if the method's group ran out of tokens
+    pub fn check(&self, method: &str) -> Result<(), ErrorObjectOwned> {
+        let bucket = if self.expensive_methods.contains(method) {
+            &self.expensive
+        } else {
+            &self.cheap
+        };
+        let Some(bucket) = bucket else {
+            return Ok(());
+        };
+
+        let mut bucket = bucket.lock().unwrap();
+        if bucket.try_acquire(Instant::now()) {
+            return Ok(());
+        }
+
+        Err(ErrorObjectOwned::owned(
+            RATE_LIMIT_EXCEEDED_ERROR_CODE,
+            "Rate limit exceeded",
+            Some(format!(
+                "{} can be retried in {}ms",
+                method,
+                bucket.retry_after().as_millis()
+            )),
+        ))
+    }
+}
+
+/// Token buckets of all remote IPs, so that reconnecting does not refill them
+#[derive(Debug, Clone)]
+pub struct RateLimiters {
+    config: Arc<RpcRateLimitConfig>,
+    limiters: Arc<Mutex<HashMap<IpAddr, RateLimiter>>>,
+}
+
+impl RateLimiters {
+    /// Creates the map, buckets are created as remote IPs connect
+    pub fn new(config: &RpcRateLimitConfig) -> Self {
+        Self {
+            config: Arc::new(config.clone()),
+            limiters: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the token buckets of `ip`, full ones if it was not seen before
+    pub fn get(&self, ip: IpAddr) -> RateLimiter {
+        let mut limiters = self.limiters.lock().unwrap();
+        if let Some(limiter) = limiters.get(&ip) {
+            return limiter.clone();
+        }
+
+        // IPs whose buckets are full would get the same buckets if they came back
+        if limiters.len() >= MAX_IDLE_REMOTE_IPS {
+            let now = Instant::now();
+            limiters.retain(|_, limiter| !limiter.is_idle(now));
+        }
+        limiters
+            .entry(ip)
+            .or_insert_with(|| RateLimiter::new(&self.config))
+            .clone()
+    }
+}
+
+/// RPC middleware rejecting requests over the rate limit of the connection's remote IP.
+/// The server creates one per connection, with the buckets of the remote IP of the connection.
+#[derive(Debug, Clone)]
+pub struct RateLimit<S> {
+    service: S,
+    limiter: RateLimiter,
+}
+
+impl<S> RateLimit<S> {
+    /// Wraps `service` with the token buckets of a remote IP
+    pub fn new(service: S, limiter: RateLimiter) -> Self {
+        Self { service, limiter }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for RateLimit<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'a,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, req: Request<'a>) -> Self::Future {
+        if let Err(e) = self.limiter.check(req.method_name()) {
+            return futures::future::ready(MethodResponse::error(req.id, e)).boxed();
+        }
+
+        self.service.call(req).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(cheap: Option<RpcRateLimit>, expensive: Option<RpcRateLimit>) -> RpcRateLimitConfig {
+        RpcRateLimitConfig {
+            cheap,
+            expensive,
+            expensive_methods: vec!["eth_getLogs".to_string()],
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn throttles_and_recovers() {
+        let limiter = RateLimiter::new(&config(
+            None,
+            Some(RpcRateLimit {
+                requests_per_second: 2,
+                burst: 3,
+            }),
+        ));
+
+        for _ in 0..3 {
+            assert!(limiter.check("eth_getLogs").is_ok());
+        }
+        let err = limiter.check("eth_getLogs").unwrap_err();
+        assert_eq!(err.code(), RATE_LIMIT_EXCEEDED_ERROR_CODE);
+
+        // Half a second refills a single token
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert!(limiter.check("eth_getLogs").is_ok());
+        assert!(limiter.check("eth_getLogs").is_err());
+
+        // The bucket never holds more than the burst
+        tokio::time::advance(Duration::from_secs(60)).await;
+        for _ in 0..3 {
+            assert!(limiter.check("eth_getLogs").is_ok());
+        }
+        assert!(limiter.check("eth_getLogs").is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn groups_are_metered_separately() {
+        let limiter = RateLimiter::new(&config(
+            Some(RpcRateLimit {
+                requests_per_second: 10,
+                burst: 10,
+            }),
+            Some(RpcRateLimit {
+                requests_per_second: 1,
+                burst: 1,
+            }),
+        ));
+
+        assert!(limiter.check("eth_getLogs").is_ok());
+        assert!(limiter.check("eth_getLogs").is_err());
+
+        // Cheap methods still go through while the expensive group is throttled
+        for _ in 0..10 {
+            assert!(limiter.check("eth_blockNumber").is_ok());
+        }
+        assert!(limiter.check("eth_blockNumber").is_err());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(limiter.check("eth_getLogs").is_ok());
+        assert!(limiter.check("eth_blockNumber").is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unlimited_by_default() {
+        let limiter = RateLimiter::new(&RpcRateLimitConfig::default());
+
+        for _ in 0..10_000 {
+            assert!(limiter.check("eth_getLogs").is_ok());
+            assert!(limiter.check("eth_blockNumber").is_ok());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ips_are_metered_separately() {
+        let limiters = RateLimiters::new(&config(
+            Some(RpcRateLimit {
+                requests_per_second: 1,
+                burst: 1,
+            }),
+            None,
+        ));
+        let first = IpAddr::from([127, 0, 0, 1]);
+        let second = IpAddr::from([127, 0, 0, 2]);
+
+        assert!(limiters.get(first).check("eth_blockNumber").is_ok());
+        assert!(limiters.get(first).check("eth_blockNumber").is_err());
+        assert!(limiters.get(second).check("eth_blockNumber").is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn connections_of_an_ip_share_buckets() {
+        let limiters = RateLimiters::new(&config(
+            Some(RpcRateLimit {
+                requests_per_second: 1,
+                burst: 2,
+            }),
+            None,
+        ));
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        let first_connection = limiters.get(ip);
+        let second_connection = limiters.get(ip);
+
+        assert!(first_connection.check("eth_blockNumber").is_ok());
+        assert!(second_connection.check("eth_blockNumber").is_ok());
+        assert!(first_connection.check("eth_blockNumber").is_err());
+        // Reconnecting does not refill the buckets
+        assert!(limiters.get(ip).check("eth_blockNumber").is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_ips_are_forgotten() {
+        let limiters = RateLimiters::new(&config(
+            Some(RpcRateLimit {
+                requests_per_second: 1,
+                burst: 1,
+            }),
+            None,
+        ));
+        let throttled = IpAddr::from([10, 0, 0, 1]);
+        assert!(limiters.get(throttled).check("eth_blockNumber").is_ok());
+        for i in 0..MAX_IDLE_REMOTE_IPS as u32 {
+            limiters.get(IpAddr::from((i + 1).to_be_bytes()));
+        }
+
+        let limiters_map = limiters.limiters.lock().unwrap();
+        assert_eq!(limiters_map.len(), 2);
+        assert!(limiters_map.contains_key(&throttled));
+    }
+}
@@ -0,0 +1,119 @@
+use std::net::{IpAddr, SocketAddr};
+
+use anyhow::anyhow;
+use jsonrpsee::server::{
+    serve_with_graceful_shutdown, stop_channel, BatchRequestConfig, RpcServiceBuilder,
+    ServerBuilder,
+};
+use jsonrpsee::{Methods, RpcModule};
+use tokio::net::TcpListener;
+use tokio::select;
+use tokio::sync::oneshot;
+use tracing::{error, info};
+
+use super::{
+    get_compression_layer, get_cors_layer, get_healthcheck_proxy_layer, FilteredMethods, Logger,
+    MethodFilter, RateLimit, RateLimiters,
+};
+use crate::tasks::manager::TaskManager;
+use crate::RpcConfig;
+
+/// Starts the RPC server of a node as a task of `task_manager`, serving `methods` as set in `rpc_config`.
+/// The bound address is sent to `channel`, if given.
+pub fn start_server(
+    rpc_config: &RpcConfig,
+    methods: RpcModule<()>,
+    channel: Option<oneshot::Sender<SocketAddr>>,
+    task_manager: &mut TaskManager<()>,
+) -> anyhow::Result<()> {
+    let listen_address = SocketAddr::new(
+        rpc_config
+            .bind_host
+            .parse()
+            .map_err(|e| anyhow!("Failed to parse bind host: {}", e))?,
+        rpc_config.bind_port,
+    );
+
+    let max_connections = rpc_config.max_connections;
+    let max_subscriptions_per_connection = rpc_config.max_subscriptions_per_connection;
+    let max_request_body_size = rpc_config.max_request_body_size;
+    let max_response_body_size = rpc_config.max_response_body_size;
+    let batch_requests_limit = rpc_config.batch_requests_limit;
+
+    let middleware = tower::ServiceBuilder::new()
+        .layer(get_cors_layer(&rpc_config.cors)?)
+        .layer(get_compression_layer(&rpc_config.compression))
+        .layer(get_healthcheck_proxy_layer());
+    let rate_limiters = RateLimiters::new(&rpc_config.rate_limit);
+    let method_filter = MethodFilter::new(&rpc_config.method_filter);
+    // Built for every connection, so that it is metered by the buckets of its remote IP
+    let rpc_middleware = move |remote_ip: IpAddr| {
+        let limiter = rate_limiters.get(remote_ip);
+        let method_filter = method_filter.clone();
+        RpcServiceBuilder::new()
+            .layer_fn(Logger)
+            .layer_fn(move |service| RateLimit::new(service, limiter.clone()))
+            .layer_fn(move |service| FilteredMethods::new(service, method_filter.clone()))
+    };
+
+    task_manager.spawn("rpc_server", move |cancellation_token| async move {
+        let listener = match TcpListener::bind(listen_address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Could not start RPC server: {}", e);
+                return;
+            }
+        };
+        let bound_address = match listener.local_addr() {
+            Ok(address) => address,
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        };
+        if let Some(channel) = channel {
+            if let Err(e) = channel.send(bound_address) {
+                error!("Could not send bound_address {}: {}", bound_address, e);
+                return;
+            }
+        }
+        info!("Starting RPC server at {} ", &bound_address);
+
+        // Connections are accepted here rather than by the server,
+        // as the remote address of a connection is not visible to the rpc middleware
+        let service_builder = ServerBuilder::default()
+            .max_connections(max_connections)
+            .max_subscriptions_per_connection(max_subscriptions_per_connection)
+            .max_request_body_size(max_request_body_size)
+            .max_response_body_size(max_response_body_size)
+            .set_batch_request_config(BatchRequestConfig::Limit(batch_requests_limit))
+            .set_http_middleware(middleware)
+            .to_service_builder();
+        let methods = Methods::from(methods);
+        let (stop_handle, server_handle) = stop_channel();
+        loop {
+            let (socket, remote_address) = select! {
+                _ = cancellation_token.cancelled() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        error!("Could not accept RPC connection: {}", e);
+                        continue;
+                    }
+                },
+            };
+            let service = service_builder
+                .clone()
+                .set_rpc_middleware(rpc_middleware(remote_address.ip()))
+                .build(methods.clone(), stop_handle.clone());
+            tokio::spawn(serve_with_graceful_shutdown(
+                socket,
+                service,
+                stop_handle.clone().shutdown(),
+            ));
+        }
+        let _ = server_handle.stop();
+    });
+
+    Ok(())
+}
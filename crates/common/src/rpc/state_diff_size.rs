@@ -0,0 +1,90 @@
+//! Exposes the state diff sizes of the soft confirmations recorded in the ledger
+use alloy_primitives::U64;
+use jsonrpsee::types::error::{
+    INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, INVALID_PARAMS_CODE, INVALID_PARAMS_MSG,
+};
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use serde::{Deserialize, Serialize};
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_db::schema::types::SoftConfirmationNumber;
+
+/// Max number of L2 blocks `citrea_getBlockStateDiffSizeRange` can be queried for at once
+pub const MAX_STATE_DIFF_SIZE_RANGE: u64 = 1000;
+
+/// Response of `citrea_getBlockStateDiffSize`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStateDiffSize {
+    pub l2_height: u64,
+    /// Size of the borsh serialized state diff of the block
+    pub size: u64,
+    /// Size of the state diff once compressed, as it would be written to the DA
+    pub compressed_size: u64,
+}
+
+fn get_block_state_diff_size(
+    ledger_db: &LedgerDB,
+    l2_height: u64,
+) -> Result<Option<BlockStateDiffSize>, ErrorObjectOwned> {
+    let state_diff_size = ledger_db
+        .get_state_diff_size(SoftConfirmationNumber(l2_height))
+        .map_err(|e| {
+            ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(e.to_string()))
+        })?;
+
+    Ok(state_diff_size.map(|state_diff_size| BlockStateDiffSize {
+        l2_height,
+        size: state_diff_size.size,
+        compressed_size: state_diff_size.compressed_size,
+    }))
+}
+
+/// Register the `citrea_getBlockStateDiffSize` and `citrea_getBlockStateDiffSizeRange` rpcs.
+/// Sizes are only known for the blocks the node produced or applied itself.
+pub fn register_state_diff_size_rpc<T: Send + Sync + 'static>(
+    rpc_methods: &mut RpcModule<T>,
+    ledger_db: LedgerDB,
+) -> anyhow::Result<()> {
+    let mut rpc = RpcModule::new(ledger_db);
+    rpc.register_method("citrea_getBlockStateDiffSize", |params, ledger_db, _| {
+        let l2_height: U64 = params.one()?;
+        get_block_state_diff_size(ledger_db, l2_height.to())
+    })?;
+
+    rpc.register_blocking_method(
+        "citrea_getBlockStateDiffSizeRange",
+        |params, ledger_db, _| {
+            let (start, end): (U64, U64) = params.parse()?;
+            let (start, end): (u64, u64) = (start.to(), end.to());
+            if start > end {
+                return Err(ErrorObjectOwned::owned(
+                    INVALID_PARAMS_CODE,
+                    INVALID_PARAMS_MSG,
+                    Some(format!(
+                        "invalid L2 range. Start: {} is greater than end: {}",
+                        start, end
+                    )),
+                ));
+            }
+            if end - start >= MAX_STATE_DIFF_SIZE_RANGE {
+                return Err(ErrorObjectOwned::owned(
+                    INVALID_PARAMS_CODE,
+                    INVALID_PARAMS_MSG,
+                    Some(format!(
+                        "requested L2 range too large. Requested: {}. Max: {}",
+                        end - start + 1,
+                        MAX_STATE_DIFF_SIZE_RANGE
+                    )),
+                ));
+            }
+
+            (start..=end)
+                .map(|l2_height| get_block_state_diff_size(&ledger_db, l2_height))
+                .collect::<Result<Vec<_>, _>>()
+        },
+    )?;
+
+    rpc_methods.merge(rpc)?;
+    Ok(())
+}
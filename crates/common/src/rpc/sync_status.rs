@@ -0,0 +1,219 @@
+//! Aggregated L1 and L2 sync progress of a node following the sequencer
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use serde::{Deserialize, Serialize};
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_ledger_rpc::LedgerRpcClient;
+use sov_rollup_interface::da::BlockHeaderTrait;
+use sov_rollup_interface::services::da::DaService;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// How long the sequencer head is served from the cache before being fetched again
+const SEQUENCER_HEAD_TTL: Duration = Duration::from_secs(1);
+
+/// Response of `citrea_syncStatus`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    /// Head soft confirmation height of the node
+    pub l2_head: u64,
+    /// Head soft confirmation height of the sequencer, null if the sequencer could not be reached
+    pub sequencer_l2_head: Option<u64>,
+    /// Last L1 height scanned by the node
+    pub last_scanned_l1_height: u64,
+    /// Last finalized L1 height, null if the DA service could not be reached
+    pub last_finalized_l1_height: Option<u64>,
+    /// Whether both layers are behind their heads by at most the lag tolerance
+    pub synced: bool,
+}
+
+/// Returns whether the node is within `lag_tolerance` blocks of both heads.
+/// A node is never considered synced against a head it could not fetch.
+fn is_synced(
+    l2_head: u64,
+    sequencer_l2_head: Option<u64>,
+    last_scanned_l1_height: u64,
+    last_finalized_l1_height: Option<u64>,
+    lag_tolerance: u64,
+) -> bool {
+    let (Some(sequencer_l2_head), Some(last_finalized_l1_height)) =
+        (sequencer_l2_head, last_finalized_l1_height)
+    else {
+        return false;
+    };
+
+    l2_head.saturating_add(lag_tolerance) >= sequencer_l2_head
+        && last_scanned_l1_height.saturating_add(lag_tolerance) >= last_finalized_l1_height
+}
+
+/// Sequencer head, cached for `SEQUENCER_HEAD_TTL` to not forward every call to the sequencer
+struct SequencerHead {
+    client: HttpClient,
+    cached: Mutex<Option<(Instant, u64)>>,
+}
+
+impl SequencerHead {
+    async fn get(&self) -> Option<u64> {
+        let mut cached = self.cached.lock().await;
+        if let Some((fetched_at, head)) = *cached {
+            if fetched_at.elapsed() < SEQUENCER_HEAD_TTL {
+                return Some(head);
+            }
+        }
+
+        match self.client.get_head_soft_confirmation_height().await {
+            Ok(head) => {
+                *cached = Some((Instant::now(), head));
+                Some(head)
+            }
+            Err(e) => {
+                warn!("Failed to fetch sequencer head for sync status: {}", e);
+                None
+            }
+        }
+    }
+}
+
+struct SyncStatusContext<Da> {
+    ledger_db: LedgerDB,
+    da_service: Arc<Da>,
+    sequencer_head: SequencerHead,
+    lag_tolerance: u64,
+}
+
+impl<Da: DaService> SyncStatusContext<Da> {
+    async fn sync_status(&self) -> Result<SyncStatus, ErrorObjectOwned> {
+        let (sequencer_l2_head, last_finalized_header) = tokio::join!(
+            self.sequencer_head.get(),
+            self.da_service.get_last_finalized_block_header()
+        );
+        let last_finalized_l1_height = match last_finalized_header {
+            Ok(header) => Some(header.height()),
+            Err(e) => {
+                warn!("Failed to fetch finalized L1 height for sync status: {}", e);
+                None
+            }
+        };
+
+        let l2_head = self
+            .ledger_db
+            .get_head_soft_confirmation_height()
+            .map_err(internal_error)?
+            .unwrap_or(0);
+        let last_scanned_l1_height = self
+            .ledger_db
+            .get_last_scanned_l1_height()
+            .map_err(internal_error)?
+            .map(|height| height.0)
+            .unwrap_or(0);
+
+        Ok(SyncStatus {
+            l2_head,
+            sequencer_l2_head,
+            last_scanned_l1_height,
+            last_finalized_l1_height,
+            synced: is_synced(
+                l2_head,
+                sequencer_l2_head,
+                last_scanned_l1_height,
+                last_finalized_l1_height,
+                self.lag_tolerance,
+            ),
+        })
+    }
+}
+
+fn internal_error(e: anyhow::Error) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(e.to_string()))
+}
+
+/// Register the `citrea_syncStatus` rpc of nodes following the sequencer at `sequencer_client_url`.
+pub fn register_sync_status_rpc<T: Send + Sync + 'static, Da: DaService>(
+    rpc_methods: &mut RpcModule<T>,
+    ledger_db: LedgerDB,
+    da_service: Arc<Da>,
+    sequencer_client_url: &str,
+    lag_tolerance: u64,
+) -> anyhow::Result<()> {
+    let context = SyncStatusContext {
+        ledger_db,
+        da_service,
+        sequencer_head: SequencerHead {
+            client: HttpClientBuilder::default().build(sequencer_client_url)?,
+            cached: Mutex::new(None),
+        },
+        lag_tolerance,
+    };
+
+    let mut rpc = RpcModule::new(context);
+    rpc.register_async_method("citrea_syncStatus", |_, context, _| async move {
+        context.sync_status().await
+    })?;
+
+    rpc_methods.merge(rpc)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_db::rocks_db_config::RocksdbConfig;
+    use sov_mock_da::{MockAddress, MockDaService};
+
+    use super::*;
+
+    #[test]
+    fn synced_within_lag_tolerance() {
+        assert!(is_synced(100, Some(100), 20, Some(20), 0));
+        assert!(is_synced(95, Some(100), 18, Some(20), 5));
+        assert!(!is_synced(94, Some(100), 20, Some(20), 5));
+        assert!(!is_synced(100, Some(100), 14, Some(20), 5));
+        // Heads behind the node, e.g. a stale cached sequencer head
+        assert!(is_synced(101, Some(100), 21, Some(20), 0));
+    }
+
+    #[test]
+    fn not_synced_against_unknown_heads() {
+        assert!(!is_synced(100, None, 20, Some(20), 5));
+        assert!(!is_synced(100, Some(100), 20, None, 5));
+    }
+
+    #[tokio::test]
+    async fn unreachable_sequencer_degrades_gracefully() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_config(&RocksdbConfig::new(
+            &tmpdir.path().join("ledger"),
+            None,
+            None,
+        ))
+        .unwrap();
+        let da_service = Arc::new(MockDaService::new(
+            MockAddress::default(),
+            &tmpdir.path().join("da"),
+        ));
+
+        let context = SyncStatusContext {
+            ledger_db,
+            da_service,
+            sequencer_head: SequencerHead {
+                // Nothing listens on port 1
+                client: HttpClientBuilder::default()
+                    .build("http://127.0.0.1:1")
+                    .unwrap(),
+                cached: Mutex::new(None),
+            },
+            lag_tolerance: 5,
+        };
+
+        let status = context.sync_status().await.unwrap();
+        assert_eq!(status.l2_head, 0);
+        assert_eq!(status.sequencer_l2_head, None);
+        assert!(!status.synced);
+    }
+}
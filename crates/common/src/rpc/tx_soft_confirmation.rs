@@ -0,0 +1,100 @@
+//! Resolves EVM transactions to the soft confirmation including them
+use alloy_primitives::B256;
+use citrea_evm::Evm;
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use serde::{Deserialize, Serialize};
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_db::schema::types::SoftConfirmationNumber;
+use sov_modules_api::default_context::DefaultContext;
+use sov_modules_api::WorkingSet;
+use sov_prover_storage_manager::{ProverStorage, SnapshotManager};
+use sov_rollup_interface::rpc::SoftConfirmationStatus;
+
+/// Response of `citrea_getSoftConfirmationByTxHash`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TxSoftConfirmation {
+    /// Height of the soft confirmation, same as the number of the EVM block
+    pub l2_height: u64,
+    /// Hash of the soft confirmation
+    #[serde(with = "hex::serde")]
+    pub hash: [u8; 32],
+    pub status: SoftConfirmationStatus,
+    /// L1 height the soft confirmation was produced on
+    pub da_slot_height: u64,
+    /// L1 height of the sequencer commitment covering the soft confirmation,
+    /// null until the commitment is seen on L1
+    pub commitment_l1_height: Option<u64>,
+}
+
+struct TxSoftConfirmationContext {
+    ledger_db: LedgerDB,
+    storage: ProverStorage<SnapshotManager>,
+}
+
+impl TxSoftConfirmationContext {
+    /// Returns None for txs that are not in a soft confirmation yet, e.g. still in the mempool
+    fn get_soft_confirmation_by_tx_hash(
+        &self,
+        tx_hash: B256,
+    ) -> anyhow::Result<Option<TxSoftConfirmation>> {
+        let evm = Evm::<DefaultContext>::default();
+        let mut working_set = WorkingSet::new(self.storage.clone());
+        let Some(l2_height) = evm.get_block_number_by_tx_hash(tx_hash, &mut working_set) else {
+            return Ok(None);
+        };
+
+        let l2_height = SoftConfirmationNumber(l2_height);
+        // The EVM state is committed before the ledger, the soft confirmation may not be stored yet
+        let Some(soft_confirmation) = self.ledger_db.get_soft_confirmation_by_number(&l2_height)?
+        else {
+            return Ok(None);
+        };
+
+        let status = self
+            .ledger_db
+            .get_soft_confirmation_status(l2_height)?
+            .unwrap_or(SoftConfirmationStatus::Trusted);
+        let commitment_l1_height = self
+            .ledger_db
+            .get_commitment_by_l2_height(l2_height)?
+            .map(|(l1_height, _)| l1_height.0);
+
+        Ok(Some(TxSoftConfirmation {
+            l2_height: l2_height.0,
+            hash: soft_confirmation.hash,
+            status,
+            da_slot_height: soft_confirmation.da_slot_height,
+            commitment_l1_height,
+        }))
+    }
+}
+
+/// Register the `citrea_getSoftConfirmationByTxHash` rpc.
+pub fn register_tx_soft_confirmation_rpc<T: Send + Sync + 'static>(
+    rpc_methods: &mut RpcModule<T>,
+    ledger_db: LedgerDB,
+    storage: ProverStorage<SnapshotManager>,
+) -> anyhow::Result<()> {
+    let mut rpc = RpcModule::new(TxSoftConfirmationContext { ledger_db, storage });
+    rpc.register_method(
+        "citrea_getSoftConfirmationByTxHash",
+        |params, context, _| {
+            let tx_hash: B256 = params.one()?;
+            context
+                .get_soft_confirmation_by_tx_hash(tx_hash)
+                .map_err(|e| {
+                    ErrorObjectOwned::owned(
+                        INTERNAL_ERROR_CODE,
+                        INTERNAL_ERROR_MSG,
+                        Some(e.to_string()),
+                    )
+                })
+        },
+    )?;
+
+    rpc_methods.merge(rpc)?;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use alloy_eips::{BlockId, BlockNumberOrTag};
+use anyhow::anyhow;
+use citrea_evm::Evm;
+use sov_ledger_rpc::server::TxSummaryProvider;
+use sov_modules_api::default_context::DefaultContext;
+use sov_modules_api::WorkingSet;
+use sov_prover_storage_manager::{ProverStorage, SnapshotManager};
+use sov_rollup_interface::rpc::SoftConfirmationTxSummary;
+
+/// Serves the transaction summaries of soft confirmations from the receipts of the EVM block
+/// with the same height, so they match `eth_getBlockByNumber` including system transactions.
+pub struct EvmTxSummaryProvider {
+    storage: ProverStorage<SnapshotManager>,
+}
+
+impl EvmTxSummaryProvider {
+    pub fn new(storage: ProverStorage<SnapshotManager>) -> Self {
+        Self { storage }
+    }
+}
+
+impl TxSummaryProvider for EvmTxSummaryProvider {
+    fn get_tx_summaries(&self, l2_height: u64) -> anyhow::Result<Vec<SoftConfirmationTxSummary>> {
+        let evm = Evm::<DefaultContext>::default();
+        let mut working_set = WorkingSet::new(self.storage.clone());
+
+        let receipts = evm
+            .get_block_receipts(
+                BlockId::Number(BlockNumberOrTag::Number(l2_height)),
+                &mut working_set,
+            )
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to get receipts of block {}: {}",
+                    l2_height,
+                    e.message()
+                )
+            })?
+            .ok_or_else(|| anyhow!("Block {} not found", l2_height))?;
+
+        Ok(receipts
+            .into_iter()
+            .map(|receipt| SoftConfirmationTxSummary {
+                hash: receipt.transaction_hash.0,
+                success: receipt.status(),
+                gas_used: receipt.gas_used as u64,
+            })
+            .collect())
+    }
+}
@@ -1,20 +1,81 @@
+use std::any::Any;
+use std::fmt;
 use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
 use std::time::Duration;
 
+use futures::FutureExt;
+use tokio::sync::watch;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
 
 const WAIT_DURATION: u64 = 5; // 5 seconds
 
+/// Whether the node can keep running without a task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskKind {
+    /// The node is shut down if the task dies before it is cancelled.
+    Critical,
+    /// The death of the task is only logged.
+    BestEffort,
+}
+
+/// A task which panicked, or returned before it was cancelled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskFailure {
+    /// Name the task was spawned with
+    pub name: &'static str,
+    /// Panic message, `None` if the task returned
+    pub panic: Option<String>,
+}
+
+impl fmt::Display for TaskFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.panic {
+            Some(message) => write!(f, "Task {} panicked: {}", self.name, message),
+            None => write!(f, "Task {} exited unexpectedly", self.name),
+        }
+    }
+}
+
+impl std::error::Error for TaskFailure {}
+
+/// Receives the first failure of a critical task.
+/// Runners select on it to shut the node down instead of running half-functional.
+pub struct CriticalTaskFailures(watch::Receiver<Option<TaskFailure>>);
+
+impl CriticalTaskFailures {
+    /// Waits until a critical task fails.
+    /// Resolves right away if one already failed before this was called.
+    pub async fn recv(&mut self) -> TaskFailure {
+        let failure = self
+            .0
+            .wait_for(Option::is_some)
+            .await
+            .map(|failure| failure.clone());
+        match failure {
+            Ok(Some(failure)) => failure,
+            // The sender lives as long as the task manager, nothing can fail without it
+            _ => std::future::pending().await,
+        }
+    }
+}
+
 /// TaskManager manages tasks spawned using tokio and keeps
 /// track of handles so that these tasks are cancellable.
 /// This provides a way to implement graceful shutdown of our
 /// nodes by completing tasks as such read/write to DBs and then
 /// performing the shutdown so that the database does not get corrupted.
+///
+/// Panics of the tasks are caught. Critical tasks dying before they are cancelled
+/// are reported to [`CriticalTaskFailures`], best-effort ones are only logged.
 pub struct TaskManager<T: Send> {
-    handles: Vec<JoinHandle<T>>,
+    handles: Vec<JoinHandle<Option<T>>>,
     cancellation_token: CancellationToken,
+    failure_tx: Arc<watch::Sender<Option<TaskFailure>>>,
 }
 
 impl<T: Send + 'static> Default for TaskManager<T> {
@@ -22,24 +83,80 @@ impl<T: Send + 'static> Default for TaskManager<T> {
         Self {
             handles: vec![],
             cancellation_token: CancellationToken::new(),
+            failure_tx: Arc::new(watch::channel(None).0),
         }
     }
 }
 
 impl<T: Send + 'static> TaskManager<T> {
-    /// Spawn a new asynchronous task.
+    /// Spawn a new critical asynchronous task.
     ///
     /// Tasks are forced to accept a cancellation token so that they can be notified
     /// about the cancellation using the passed token.
-    pub fn spawn<F, Fut>(&mut self, callback: F)
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, callback: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.spawn_with_kind(name, TaskKind::Critical, callback)
+    }
+
+    /// Spawn a new asynchronous task the node can run without.
+    pub fn spawn_best_effort<F, Fut>(&mut self, name: &'static str, callback: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.spawn_with_kind(name, TaskKind::BestEffort, callback)
+    }
+
+    /// Spawn a new asynchronous task of the given kind.
+    pub fn spawn_with_kind<F, Fut>(&mut self, name: &'static str, kind: TaskKind, callback: F)
     where
         F: FnOnce(CancellationToken) -> Fut,
         Fut: Future<Output = T> + Send + 'static,
     {
-        let handle = tokio::spawn(callback(self.child_token()));
+        let task = AssertUnwindSafe(callback(self.child_token())).catch_unwind();
+        let cancellation_token = self.cancellation_token.clone();
+        let failure_tx = self.failure_tx.clone();
+
+        let handle = tokio::spawn(async move {
+            let result = task.await;
+            let panic = result
+                .as_ref()
+                .err()
+                .map(|payload| panic_message(payload.as_ref()));
+            // Tasks return on their own once they are cancelled
+            if panic.is_none() && cancellation_token.is_cancelled() {
+                return result.ok();
+            }
+
+            let failure = TaskFailure { name, panic };
+            match kind {
+                TaskKind::Critical => {
+                    error!("{}, shutting down", failure);
+                    // Only the first failure is kept, it is the cause of the shutdown
+                    failure_tx.send_if_modified(|current| {
+                        if current.is_some() {
+                            return false;
+                        }
+                        *current = Some(failure);
+                        true
+                    });
+                }
+                TaskKind::BestEffort if failure.panic.is_some() => error!("{}", failure),
+                TaskKind::BestEffort => info!("Task {} finished", name),
+            }
+            result.ok()
+        });
         self.handles.push(handle);
     }
 
+    /// Subscribes to the failures of the critical tasks.
+    pub fn critical_task_failures(&self) -> CriticalTaskFailures {
+        CriticalTaskFailures(self.failure_tx.subscribe())
+    }
+
     /// Notify all running tasks to stop.
     pub async fn abort(&self) {
         self.cancellation_token.cancel();
@@ -56,3 +173,89 @@ impl<T: Send + 'static> TaskManager<T> {
         self.cancellation_token.child_token()
     }
 }
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::timeout;
+
+    use super::*;
+
+    /// Stands for the select loop of a runner
+    async fn run(task_manager: &TaskManager<()>) -> anyhow::Result<()> {
+        let mut critical_task_failures = task_manager.critical_task_failures();
+        tokio::select! {
+            failure = critical_task_failures.recv() => Err(failure.into()),
+            _ = sleep(Duration::from_secs(1)) => Ok(()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_critical_task_panic_fails_run() {
+        let mut task_manager = TaskManager::<()>::default();
+        task_manager.spawn("l1_block_handler", |_| async {
+            panic!("poisoned cache");
+        });
+
+        let err = run(&task_manager).await.unwrap_err();
+        assert_eq!(
+            err.downcast::<TaskFailure>().unwrap(),
+            TaskFailure {
+                name: "l1_block_handler",
+                panic: Some("poisoned cache".to_string()),
+            }
+        );
+
+        // The failure is kept for later subscribers
+        let failure = timeout(
+            Duration::from_secs(1),
+            task_manager.critical_task_failures().recv(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(failure.name, "l1_block_handler");
+    }
+
+    #[tokio::test]
+    async fn test_critical_task_returning_early_fails_run() {
+        let mut task_manager = TaskManager::<()>::default();
+        task_manager.spawn("rpc_server", |_| async {});
+
+        let err = run(&task_manager).await.unwrap_err();
+        assert_eq!(err.to_string(), "Task rpc_server exited unexpectedly");
+    }
+
+    #[tokio::test]
+    async fn test_best_effort_task_death_is_only_logged() {
+        let mut task_manager = TaskManager::<()>::default();
+        task_manager.spawn_best_effort("pruner", |_| async {
+            panic!("pruning failed");
+        });
+        task_manager.spawn_best_effort("tx_body_backfill", |_| async {});
+        task_manager.spawn("rpc_server", |cancellation_token| async move {
+            cancellation_token.cancelled().await;
+        });
+
+        run(&task_manager).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_critical_task_does_not_fail() {
+        let mut task_manager = TaskManager::<()>::default();
+        task_manager.spawn("rpc_server", |cancellation_token| async move {
+            cancellation_token.cancelled().await;
+        });
+
+        task_manager.cancellation_token.cancel();
+        run(&task_manager).await.unwrap();
+    }
+}
@@ -1,7 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
+use anyhow::{anyhow, bail};
+use borsh::BorshDeserialize;
+use citrea_primitives::compression::compress_blob;
+use citrea_primitives::forks::fork_from_block_number;
+use jsonrpsee::http_client::HttpClient;
 use sov_db::ledger_db::SharedLedgerOps;
-use sov_db::schema::types::SoftConfirmationNumber;
+use sov_db::schema::types::{SoftConfirmationNumber, StoredSoftConfirmation, StoredStateDiffSize};
+use sov_ledger_rpc::LedgerRpcClient;
 use sov_modules_api::{Context, Spec};
 use sov_rollup_interface::da::{DaSpec, SequencerCommitment};
 use sov_rollup_interface::digest::Digest;
@@ -9,9 +15,13 @@ use sov_rollup_interface::rpc::SoftConfirmationStatus;
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmation;
 use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::stf::{SoftConfirmationReceipt, StateDiff, TransactionDigest};
+use sov_rollup_interface::zk::{
+    BatchProofCircuitOutput, BatchProofCircuitOutputV1, BatchProofOutputVersion, Proof, ZkvmHost,
+};
 use tokio::signal;
 use tokio::signal::unix::{signal, SignalKind};
 use tokio::sync::mpsc;
+use tracing::warn;
 
 pub fn merge_state_diffs(old_diff: StateDiff, new_diff: StateDiff) -> StateDiff {
     let mut new_diff_map = HashMap::<Vec<u8>, Option<Vec<u8>>>::from_iter(old_diff);
@@ -20,6 +30,16 @@ pub fn merge_state_diffs(old_diff: StateDiff, new_diff: StateDiff) -> StateDiff
     new_diff_map.into_iter().collect()
 }
 
+/// Returns the size of the state diff when serialized, and once compressed as it is written to the DA.
+pub fn state_diff_size(state_diff: &StateDiff) -> StoredStateDiffSize {
+    let serialized_state_diff =
+        borsh::to_vec(state_diff).expect("State diff serialization can not fail");
+    StoredStateDiffSize {
+        size: serialized_state_diff.len() as u64,
+        compressed_size: compress_blob(&serialized_state_diff).len() as u64,
+    }
+}
+
 /// Remove proven commitments using the end block number of the L2 range.
 /// This is basically filtering out proven soft confirmations.
 pub fn filter_out_proven_commitments<DB: SharedLedgerOps>(
@@ -120,6 +140,148 @@ pub fn soft_confirmation_to_receipt<C: Context, Tx: TransactionDigest + Clone, D
         pub_key: soft_confirmation.pub_key().to_vec(),
     }
 }
+
+/// Recovers the ledger after the node stopped between finalizing the state of soft confirmations
+/// and committing their ledger data. Staged soft confirmations following the ledger head whose
+/// state root matches `finalized_state_root` of their height are committed, the rest are dropped
+/// to be processed again. Returns the committed soft confirmations.
+pub fn commit_finalized_staged_soft_confirmations<DB: SharedLedgerOps>(
+    ledger_db: &DB,
+    finalized_state_root: impl Fn(u64) -> Option<Vec<u8>>,
+) -> anyhow::Result<Vec<StoredSoftConfirmation>> {
+    let staged = ledger_db.get_staged_soft_confirmations()?;
+    if staged.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let head_l2_height = ledger_db.get_head_soft_confirmation_height()?.unwrap_or(0);
+    let finalized: Vec<StoredSoftConfirmation> = staged
+        .into_iter()
+        .enumerate()
+        .take_while(|(index, soft_confirmation)| {
+            soft_confirmation.l2_height == head_l2_height + 1 + *index as u64
+                && finalized_state_root(soft_confirmation.l2_height)
+                    .is_some_and(|state_root| state_root == soft_confirmation.state_root)
+        })
+        .map(|(_, soft_confirmation)| soft_confirmation)
+        .collect();
+
+    ledger_db.commit_staged_soft_confirmations(
+        finalized
+            .last()
+            .map(|soft_confirmation| SoftConfirmationNumber(soft_confirmation.l2_height)),
+    )?;
+
+    Ok(finalized)
+}
+
+/// Ensures the node was initialized from the same genesis as the sequencer it syncs from,
+/// a node with a different genesis directory would only diverge at the first state root check.
+/// The check is skipped if the sequencer cannot be queried, and a mismatch is only logged if
+/// `allow_mismatch` is set.
+pub async fn check_l2_genesis_state_root<DB: SharedLedgerOps>(
+    ledger_db: &DB,
+    sequencer_client: &HttpClient,
+    allow_mismatch: bool,
+) -> anyhow::Result<()> {
+    let Some(local_root) = ledger_db.get_l2_state_root::<[u8; 32]>(0)? else {
+        return Ok(());
+    };
+    let sequencer_root = match sequencer_client.get_l2_genesis_state_root().await {
+        Ok(Some(root)) => root.0,
+        Ok(None) => {
+            warn!("Sequencer has no L2 genesis state root, skipping the genesis check");
+            return Ok(());
+        }
+        Err(e) => {
+            warn!(
+                "Failed to get the L2 genesis state root of the sequencer, skipping the genesis check: {:?}",
+                e
+            );
+            return Ok(());
+        }
+    };
+    if local_root == sequencer_root {
+        return Ok(());
+    }
+
+    if allow_mismatch {
+        warn!(
+            "L2 genesis state root 0x{} differs from the sequencer's 0x{}, starting anyway",
+            hex::encode(local_root),
+            hex::encode(sequencer_root)
+        );
+        return Ok(());
+    }
+    bail!(
+        "L2 genesis state root 0x{} differs from the sequencer's 0x{}, the node is configured with a different genesis. \
+        Set allow_genesis_state_root_mismatch to start anyway",
+        hex::encode(local_root),
+        hex::encode(sequencer_root)
+    )
+}
+
+/// Extracts the output of a batch proof and checks it is in the layout of the guest of the fork
+/// active at its last L2 height. Later layouts only append fields, so a journal decodes strictly
+/// as exactly one of them.
+pub fn extract_batch_proof_output<Vm: ZkvmHost, Da: DaSpec, StateRoot: BorshDeserialize>(
+    proof: &Proof,
+) -> anyhow::Result<(
+    BatchProofOutputVersion,
+    BatchProofCircuitOutput<Da, StateRoot>,
+)> {
+    let (version, output) =
+        match Vm::extract_output::<Da, BatchProofCircuitOutputV1<Da, StateRoot>>(proof) {
+            Ok(output) => (BatchProofOutputVersion::V1, output.into()),
+            Err(_) => (
+                BatchProofOutputVersion::V2,
+                Vm::extract_output::<Da, BatchProofCircuitOutput<Da, StateRoot>>(proof)
+                    .map_err(|e| anyhow!("Proof output is not deserializable: {:?}", e))?,
+            ),
+        };
+
+    let expected_version =
+        BatchProofOutputVersion::from_spec(fork_from_block_number(output.last_l2_height).spec_id);
+    if version != expected_version {
+        bail!(
+            "Proof output is in the {:?} layout, but the guest active at L2 height {} outputs {:?}",
+            version,
+            output.last_l2_height,
+            expected_version
+        );
+    }
+
+    Ok((version, output))
+}
+
+/// Pairs the soft confirmations of a range starting at `start_l2_height` with their heights.
+///
+/// Only the leading soft confirmations are kept. A soft confirmation missing inside the range
+/// ends it, the ones after the gap are fetched again once it is filled.
+pub fn soft_confirmations_with_heights<T>(
+    start_l2_height: u64,
+    soft_confirmations: Vec<Option<T>>,
+) -> Vec<(u64, T)> {
+    let mut pairs = Vec::with_capacity(soft_confirmations.len());
+    let mut soft_confirmations = soft_confirmations.into_iter();
+    for l2_height in start_l2_height.. {
+        match soft_confirmations.next() {
+            Some(Some(soft_confirmation)) => pairs.push((l2_height, soft_confirmation)),
+            Some(None) => {
+                if soft_confirmations.any(|soft_confirmation| soft_confirmation.is_some()) {
+                    warn!(
+                        "Soft confirmation #{} is missing from the range starting at {}, syncing stops before it",
+                        l2_height, start_l2_height
+                    );
+                }
+                break;
+            }
+            None => break,
+        }
+    }
+    pairs
+}
+
 pub async fn create_shutdown_signal() -> tokio::sync::mpsc::Receiver<()> {
     let (tx, rx) = mpsc::channel(1);
 
@@ -143,3 +305,156 @@ pub async fn create_shutdown_signal() -> tokio::sync::mpsc::Receiver<()> {
 
     rx
 }
+
+#[cfg(test)]
+mod tests {
+    use sov_db::ledger_db::{LedgerDB, NodeLedgerOps, SharedLedgerOps};
+    use sov_db::rocks_db_config::RocksdbConfig;
+    use sov_db::schema::types::{SlotNumber, SoftConfirmationNumber};
+    use sov_mock_da::{MockDaSpec, MockHash};
+    use sov_rollup_interface::stf::SoftConfirmationReceipt;
+
+    use super::{commit_finalized_staged_soft_confirmations, soft_confirmations_with_heights};
+
+    fn state_root(l2_height: u64) -> Vec<u8> {
+        vec![l2_height as u8; 32]
+    }
+
+    fn l2_commit(
+        l2_height: u64,
+    ) -> (
+        Vec<u8>,
+        SoftConfirmationReceipt<MockDaSpec>,
+        Option<Vec<Vec<u8>>>,
+    ) {
+        let receipt = SoftConfirmationReceipt {
+            l2_height,
+            da_slot_height: 1,
+            da_slot_hash: MockHash([1; 32]),
+            da_slot_txs_commitment: MockHash([0; 32]),
+            hash: [l2_height as u8; 32],
+            prev_hash: [l2_height as u8 - 1; 32],
+            tx_hashes: vec![],
+            soft_confirmation_signature: vec![],
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: 0,
+        };
+        (state_root(l2_height), receipt, Some(vec![]))
+    }
+
+    // Stages the L2 blocks and leaves the ledger commit out, like a node stopped after finalizing their state
+    fn ledger_with_torn_commit(committed: u64, staged: u64) -> (tempfile::TempDir, LedgerDB) {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db =
+            LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
+
+        ledger_db
+            .commit_soft_confirmation_batch((1..=committed).map(l2_commit).collect())
+            .unwrap();
+        ledger_db
+            .stage_soft_confirmations(
+                (committed + 1..=committed + staged)
+                    .map(l2_commit)
+                    .collect(),
+            )
+            .unwrap();
+
+        (tmpdir, ledger_db)
+    }
+
+    #[test]
+    fn test_commit_finalized_staged_soft_confirmations() {
+        let (_tmpdir, ledger_db) = ledger_with_torn_commit(2, 3);
+
+        let committed = commit_finalized_staged_soft_confirmations(&ledger_db, |l2_height| {
+            Some(state_root(l2_height))
+        })
+        .unwrap();
+
+        assert_eq!(
+            committed.iter().map(|sc| sc.l2_height).collect::<Vec<_>>(),
+            vec![3, 4, 5]
+        );
+        assert_eq!(
+            ledger_db.get_head_soft_confirmation_height().unwrap(),
+            Some(5)
+        );
+        assert_eq!(
+            ledger_db.get_l2_range_by_l1_height(SlotNumber(1)).unwrap(),
+            Some((SoftConfirmationNumber(1), SoftConfirmationNumber(5)))
+        );
+        assert!(ledger_db
+            .get_staged_soft_confirmations()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_commit_partially_finalized_staged_soft_confirmations() {
+        let (_tmpdir, ledger_db) = ledger_with_torn_commit(2, 3);
+
+        // Only the state of the first staged L2 block was finalized
+        let committed = commit_finalized_staged_soft_confirmations(&ledger_db, |l2_height| {
+            (l2_height <= 3).then(|| state_root(l2_height))
+        })
+        .unwrap();
+
+        assert_eq!(
+            committed.iter().map(|sc| sc.l2_height).collect::<Vec<_>>(),
+            vec![3]
+        );
+        assert_eq!(
+            ledger_db.get_head_soft_confirmation_height().unwrap(),
+            Some(3)
+        );
+        assert!(ledger_db
+            .get_staged_soft_confirmations()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_drop_staged_soft_confirmations_with_unknown_state() {
+        let (_tmpdir, ledger_db) = ledger_with_torn_commit(2, 2);
+
+        let committed =
+            commit_finalized_staged_soft_confirmations(&ledger_db, |_| Some(vec![0; 32])).unwrap();
+
+        assert!(committed.is_empty());
+        assert_eq!(
+            ledger_db.get_head_soft_confirmation_height().unwrap(),
+            Some(2)
+        );
+        assert!(ledger_db
+            .get_staged_soft_confirmations()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_soft_confirmations_with_heights() {
+        assert_eq!(
+            soft_confirmations_with_heights(5, vec![Some('a'), Some('b'), Some('c')]),
+            vec![(5, 'a'), (6, 'b'), (7, 'c')]
+        );
+
+        // Nothing after a gap is kept, so the heights stay aligned
+        assert_eq!(
+            soft_confirmations_with_heights(5, vec![Some('a'), None, Some('c'), Some('d')]),
+            vec![(5, 'a')]
+        );
+        assert_eq!(
+            soft_confirmations_with_heights(5, vec![None, Some('b')]),
+            vec![]
+        );
+
+        // Trailing soft confirmations not produced yet
+        assert_eq!(
+            soft_confirmations_with_heights(5, vec![Some('a'), Some('b'), None, None]),
+            vec![(5, 'a'), (6, 'b')]
+        );
+        assert_eq!(soft_confirmations_with_heights::<char>(5, vec![]), vec![]);
+    }
+}
@@ -2,40 +2,47 @@ use std::sync::{Arc, Mutex};
 
 use alloy_primitives::U256;
 use alloy_rpc_types_trace::geth::TraceResult;
-use citrea_evm::Evm;
+use citrea_evm::{Evm, LogsQueryLimits};
 use jsonrpsee::http_client::HttpClient;
+use reth_primitives::{BlockId, BlockNumberOrTag};
+use reth_rpc_eth_types::{EthApiError, EthResult};
 use rustc_version_runtime::version;
 use schnellru::{ByLength, LruMap};
-use sov_db::ledger_db::LedgerDB;
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
 use sov_modules_api::WorkingSet;
 use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::CITREA_VERSION;
+use sov_state::storage::NativeStorage;
 use tokio::sync::broadcast;
 use tracing::instrument;
 
+use crate::filter::{FilterStore, FilterStoreConfig};
 use crate::gas_price::fee_history::FeeHistoryCacheConfig;
 use crate::gas_price::gas_oracle::{GasPriceOracle, GasPriceOracleConfig};
 use crate::subscription::SubscriptionManager;
 
 const MAX_TRACE_BLOCK: u32 = 1000;
-const DEFAULT_PRIORITY_FEE: U256 = U256::from_limbs([100, 0, 0, 0]);
 
 #[derive(Clone)]
 pub struct EthRpcConfig {
     pub gas_price_oracle_config: GasPriceOracleConfig,
     pub fee_history_cache_config: FeeHistoryCacheConfig,
+    pub logs_query_limits: LogsQueryLimits,
+    pub filter_store_config: FilterStoreConfig,
 }
 
 pub struct Ethereum<C: sov_modules_api::Context, Da: DaService> {
     #[allow(dead_code)]
     pub(crate) da_service: Arc<Da>,
     pub(crate) gas_price_oracle: GasPriceOracle<C>,
+    pub(crate) logs_query_limits: LogsQueryLimits,
     pub(crate) storage: C::Storage,
     pub(crate) ledger_db: LedgerDB,
     pub(crate) sequencer_client: Option<HttpClient>,
     pub(crate) web3_client_version: String,
     pub(crate) trace_cache: Mutex<LruMap<u64, Vec<TraceResult>, ByLength>>,
     pub(crate) subscription_manager: Option<SubscriptionManager>,
+    pub(crate) filter_store: FilterStore,
 }
 
 impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
@@ -44,6 +51,8 @@ impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
         da_service: Arc<Da>,
         gas_price_oracle_config: GasPriceOracleConfig,
         fee_history_cache_config: FeeHistoryCacheConfig,
+        logs_query_limits: LogsQueryLimits,
+        filter_store_config: FilterStoreConfig,
         storage: C::Storage,
         ledger_db: LedgerDB,
         sequencer_client: Option<HttpClient>,
@@ -67,17 +76,22 @@ impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
         Self {
             da_service,
             gas_price_oracle,
+            logs_query_limits,
             storage,
             ledger_db,
             sequencer_client,
             web3_client_version: current_version,
             trace_cache,
             subscription_manager,
+            filter_store: FilterStore::new(filter_store_config),
         }
     }
 
     #[instrument(level = "trace", skip_all)]
-    pub(crate) fn max_fee_per_gas(&self, working_set: &mut WorkingSet<C::Storage>) -> (U256, U256) {
+    pub(crate) fn max_fee_per_gas(
+        &self,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> EthResult<(U256, U256)> {
         let evm = Evm::<C>::default();
         let base_fee = evm
             .get_block_by_number(None, None, working_set)
@@ -87,10 +101,9 @@ impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
             .base_fee_per_gas
             .unwrap_or_default();
 
-        // We return a default priority of 100 wei. Small enough to
-        // not make a difference to price, but also allows bumping tip
-        // of EIP-1559 transactions in times of congestion.
-        (U256::from(base_fee), DEFAULT_PRIORITY_FEE)
+        let suggested_tip = self.gas_price_oracle.suggest_tip_cap(working_set)?;
+
+        Ok((U256::from(base_fee), U256::from(suggested_tip)))
     }
 
     //     fn make_raw_tx(
@@ -107,3 +120,64 @@ impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
     //         Ok((B256::from(tx_hash), message))
     //     }
 }
+
+impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da>
+where
+    C::Storage: NativeStorage,
+{
+    /// Checks that the state at the end of the given block can be served.
+    ///
+    /// The L2 height of a historical block is resolved to the state root the ledger recorded
+    /// for it, which must be the root of the state version the block is read at.
+    /// The latest and pending states are always available.
+    pub(crate) fn ensure_state_available(
+        &self,
+        evm: &Evm<C>,
+        block_id: Option<BlockId>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> EthResult<()> {
+        let block_number = match block_id {
+            None
+            | Some(BlockId::Number(BlockNumberOrTag::Latest))
+            | Some(BlockId::Number(BlockNumberOrTag::Pending)) => return Ok(()),
+            Some(BlockId::Number(block_number)) => {
+                evm.block_number_for_id(&block_number, working_set)?
+            }
+            Some(BlockId::Hash(block_hash)) => {
+                evm.block_number_for_hash(block_hash, working_set)?
+            }
+        };
+
+        let ledger_error =
+            |e: anyhow::Error| EthApiError::EvmCustom(format!("failed to read the ledger: {e}"));
+        let ledger_state_root = self
+            .ledger_db
+            .get_l2_state_root::<[u8; 32]>(block_number)
+            .map_err(ledger_error)?;
+        // Genesis is committed at state version 1, so L2 height `h` is at version `h + 1`
+        let state_root = working_set
+            .get_root_hash(block_number + 1)
+            .ok()
+            .map(Into::<[u8; 32]>::into);
+
+        match (ledger_state_root, state_root) {
+            (Some(ledger_state_root), Some(state_root)) if ledger_state_root == state_root => {
+                Ok(())
+            }
+            // The state of a block is finalized right before the ledger commits it
+            (None, Some(_))
+                if self
+                    .ledger_db
+                    .get_head_soft_confirmation_height()
+                    .map_err(ledger_error)?
+                    .map_or(true, |head| block_number > head) =>
+            {
+                Ok(())
+            }
+            _ => Err(EthApiError::InvalidParams(format!(
+                "State of block {} is not available on this node",
+                block_number
+            ))),
+        }
+    }
+}
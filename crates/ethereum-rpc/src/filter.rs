@@ -0,0 +1,319 @@
+//! Filters installed with `eth_newFilter`, `eth_newBlockFilter` and `eth_newPendingTransactionFilter`,
+//! whose changes are polled with `eth_getFilterChanges`.
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alloy_primitives::{B256, U128};
+use citrea_evm::{
+    Evm, Filter, FilterBlockOption, FilterError, LogResponse, LogsQueryLimits,
+    LIMIT_EXCEEDED_ERROR_CODE,
+};
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::ConnectionId;
+use parking_lot::Mutex;
+use reth_primitives::BlockNumberOrTag;
+use reth_rpc_eth_types::EthApiError;
+use serde::{Deserialize, Serialize};
+use sov_db::ledger_db::{LedgerDB, SequencerLedgerOps};
+use sov_modules_api::WorkingSet;
+
+/// Configuration of the installed filters
+#[derive(Clone, Debug)]
+pub struct FilterStoreConfig {
+    /// Filters not polled for this long are removed
+    pub filter_timeout: Duration,
+    /// Maximum number of filters installed by a single connection
+    pub max_filters_per_connection: usize,
+    /// Maximum number of filters installed by all connections together
+    pub max_filters: usize,
+}
+
+impl Default for FilterStoreConfig {
+    fn default() -> Self {
+        Self {
+            filter_timeout: Duration::from_secs(5 * 60),
+            max_filters_per_connection: 100,
+            max_filters: 10_000,
+        }
+    }
+}
+
+/// Response of `eth_getFilterChanges`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FilterChanges {
+    /// New logs matching a log filter
+    Logs(Vec<LogResponse>),
+    /// Hashes of new blocks, or of new pending transactions
+    Hashes(Vec<B256>),
+}
+
+pub(crate) enum FilterKind {
+    Logs(Box<Filter>),
+    Blocks,
+    PendingTransactions,
+}
+
+struct FilterState {
+    kind: FilterKind,
+    /// First block whose changes were not returned yet
+    next_block: u64,
+    /// Pending transactions already returned, only kept for pending transaction filters
+    seen_pending_txs: HashSet<B256>,
+}
+
+struct InstalledFilter {
+    connection_id: Option<ConnectionId>,
+    last_polled_at: Instant,
+    // Locked while polled, so concurrent polls of a filter never return the same changes twice
+    state: Arc<Mutex<FilterState>>,
+}
+
+/// In-memory store of the installed filters, which expire once not polled for the configured timeout.
+pub(crate) struct FilterStore {
+    config: FilterStoreConfig,
+    filters: Mutex<HashMap<U128, InstalledFilter>>,
+}
+
+impl FilterStore {
+    pub(crate) fn new(config: FilterStoreConfig) -> Self {
+        Self {
+            config,
+            filters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Installs a filter whose changes start from the block after the current head
+    pub(crate) fn install<C: sov_modules_api::Context>(
+        &self,
+        kind: FilterKind,
+        connection_id: Option<ConnectionId>,
+        storage: &C::Storage,
+        ledger_db: &LedgerDB,
+    ) -> RpcResult<U128> {
+        if let FilterKind::Logs(filter) = &kind {
+            if let FilterBlockOption::AtBlockHash(_) = filter.block_option {
+                return Err(EthApiError::InvalidParams(
+                    "blockHash is not supported by eth_newFilter, use eth_getLogs instead".into(),
+                )
+                .into());
+            }
+        }
+
+        let mut working_set = WorkingSet::new(storage.clone());
+        let head: u64 = Evm::<C>::default()
+            .block_number(&mut working_set)?
+            .saturating_to();
+        // Transactions already in the mempool are not new to the filter
+        let seen_pending_txs = match kind {
+            FilterKind::PendingTransactions => pending_tx_hashes(ledger_db)?,
+            _ => HashSet::new(),
+        };
+
+        let mut filters = self.filters.lock();
+        self.remove_expired(&mut filters);
+
+        // Connections are cheap to open, so the filters of all of them are capped too
+        if filters.len() >= self.config.max_filters {
+            return Err(ErrorObjectOwned::owned(
+                LIMIT_EXCEEDED_ERROR_CODE,
+                format!(
+                    "the maximum of {} installed filters is reached",
+                    self.config.max_filters
+                ),
+                None::<String>,
+            ));
+        }
+
+        let connection_filters = filters
+            .values()
+            .filter(|filter| filter.connection_id == connection_id)
+            .count();
+        if connection_filters >= self.config.max_filters_per_connection {
+            return Err(ErrorObjectOwned::owned(
+                LIMIT_EXCEEDED_ERROR_CODE,
+                format!(
+                    "connection already has the maximum of {} installed filters",
+                    self.config.max_filters_per_connection
+                ),
+                None::<String>,
+            ));
+        }
+
+        let id = loop {
+            let id = U128::from(rand::random::<u128>());
+            if !filters.contains_key(&id) {
+                break id;
+            }
+        };
+        filters.insert(
+            id,
+            InstalledFilter {
+                connection_id,
+                last_polled_at: Instant::now(),
+                state: Arc::new(Mutex::new(FilterState {
+                    kind,
+                    next_block: head + 1,
+                    seen_pending_txs,
+                })),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Removes the filter, returns false if it was not installed
+    pub(crate) fn uninstall(&self, id: U128) -> bool {
+        let mut filters = self.filters.lock();
+        self.remove_expired(&mut filters);
+        filters.remove(&id).is_some()
+    }
+
+    /// Returns the changes of the filter since it was last polled.
+    ///
+    /// Logs are matched with the same logic as `eth_getLogs`. A poll covers at most
+    /// `max_block_range` blocks, the blocks above are returned by the next polls.
+    pub(crate) fn changes<C: sov_modules_api::Context>(
+        &self,
+        id: U128,
+        storage: &C::Storage,
+        ledger_db: &LedgerDB,
+        limits: &LogsQueryLimits,
+    ) -> RpcResult<FilterChanges> {
+        let state = self.poll(id)?;
+        let mut state = state.lock();
+        let state = &mut *state;
+
+        let evm = Evm::<C>::default();
+        let mut working_set = WorkingSet::new(storage.clone());
+        let head: u64 = evm.block_number(&mut working_set)?.saturating_to();
+
+        match &state.kind {
+            FilterKind::Logs(filter) => {
+                let (from_block, to_block) = polled_block_range(filter);
+                let from_block = from_block.max(state.next_block);
+                let to_block = to_block
+                    .min(head)
+                    .min(from_block.saturating_add(limits.max_block_range.saturating_sub(1)));
+                if from_block > to_block {
+                    return Ok(FilterChanges::Logs(vec![]));
+                }
+
+                let logs = evm.get_logs_in_block_range(
+                    &mut working_set,
+                    filter,
+                    from_block,
+                    to_block,
+                    limits,
+                )?;
+                state.next_block = to_block + 1;
+                Ok(FilterChanges::Logs(logs))
+            }
+            FilterKind::Blocks => {
+                let hashes = (state.next_block..=head)
+                    .map(|block_number| {
+                        evm.block_hash_from_number(block_number, &mut working_set)
+                            .ok_or_else(|| EthApiError::HeaderNotFound(block_number.into()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                state.next_block = state.next_block.max(head + 1);
+                Ok(FilterChanges::Hashes(hashes))
+            }
+            FilterKind::PendingTransactions => {
+                let pending_txs = pending_tx_hashes(ledger_db)?;
+                let new_txs = pending_txs
+                    .difference(&state.seen_pending_txs)
+                    .copied()
+                    .collect();
+                // Included transactions are forgotten, the mempool only holds pending ones
+                state.seen_pending_txs = pending_txs;
+                Ok(FilterChanges::Hashes(new_txs))
+            }
+        }
+    }
+
+    /// Returns all logs matching a log filter, as `eth_getLogs` would
+    pub(crate) fn logs<C: sov_modules_api::Context>(
+        &self,
+        id: U128,
+        storage: &C::Storage,
+        limits: &LogsQueryLimits,
+    ) -> RpcResult<Vec<LogResponse>> {
+        let state = self.poll(id)?;
+        let filter = match &state.lock().kind {
+            FilterKind::Logs(filter) => filter.as_ref().clone(),
+            _ => return Err(FilterError::FilterNotFound.into()),
+        };
+
+        let evm = Evm::<C>::default();
+        let mut working_set = WorkingSet::new(storage.clone());
+        evm.eth_get_logs(filter, limits, &mut working_set)
+    }
+
+    /// Resets the expiry of the filter
+    fn poll(&self, id: U128) -> Result<Arc<Mutex<FilterState>>, FilterError> {
+        let mut filters = self.filters.lock();
+        self.remove_expired(&mut filters);
+
+        let filter = filters.get_mut(&id).ok_or(FilterError::FilterNotFound)?;
+        filter.last_polled_at = Instant::now();
+        Ok(filter.state.clone())
+    }
+
+    fn remove_expired(&self, filters: &mut HashMap<U128, InstalledFilter>) {
+        filters.retain(|_, filter| filter.last_polled_at.elapsed() < self.config.filter_timeout);
+    }
+}
+
+/// Inclusive block range a log filter is polled over, the tags other than
+/// block numbers leave the range open, so it follows the head.
+fn polled_block_range(filter: &Filter) -> (u64, u64) {
+    let (from_block, to_block) = filter.block_option.as_range();
+    let from_block = match from_block {
+        Some(BlockNumberOrTag::Number(number)) => *number,
+        _ => 0,
+    };
+    let to_block = match to_block {
+        Some(BlockNumberOrTag::Number(number)) => *number,
+        _ => u64::MAX,
+    };
+    (from_block, to_block)
+}
+
+/// Hashes of the transactions in the sequencer's mempool
+fn pending_tx_hashes(ledger_db: &LedgerDB) -> RpcResult<HashSet<B256>> {
+    let mempool_txs = ledger_db
+        .get_mempool_txs()
+        .map_err(|e| EthApiError::EvmCustom(format!("failed to read the mempool: {e}")))?;
+    Ok(mempool_txs
+        .into_iter()
+        .map(|(tx_hash, _)| B256::from_slice(&tx_hash))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range_filter(from_block: BlockNumberOrTag, to_block: BlockNumberOrTag) -> Filter {
+        Filter {
+            block_option: FilterBlockOption::Range {
+                from_block: Some(from_block),
+                to_block: Some(to_block),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_polled_block_range() {
+        assert_eq!(polled_block_range(&Filter::default()), (0, u64::MAX));
+
+        let filter = range_filter(BlockNumberOrTag::Number(5), BlockNumberOrTag::Number(10));
+        assert_eq!(polled_block_range(&filter), (5, 10));
+
+        let filter = range_filter(BlockNumberOrTag::Latest, BlockNumberOrTag::Pending);
+        assert_eq!(polled_block_range(&filter), (0, u64::MAX));
+    }
+}
@@ -0,0 +1,187 @@
+use alloy_primitives::{Bytes, B256, U256};
+use alloy_rpc_types::{TransactionInput, TransactionRequest};
+use backoff::future::retry as retry_backoff;
+use backoff::ExponentialBackoffBuilder;
+use citrea_evm::Evm;
+use citrea_sequencer::{recover_raw_transaction, SequencerRpcClient};
+use jsonrpsee::core::client::Error as JsonrpseeError;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::http_client::HttpClient;
+use reth_rpc_eth_types::error::RpcInvalidTransactionError;
+use sov_modules_api::utils::to_jsonrpsee_error_object;
+use sov_modules_api::WorkingSet;
+use sov_rollup_interface::services::da::DaService;
+use tokio::time::Duration;
+use tracing::{debug, instrument};
+
+use crate::ethereum::Ethereum;
+
+/// Time after which an unreachable sequencer is reported to the user
+const FORWARD_MAX_ELAPSED_TIME: Duration = Duration::from_secs(10);
+
+impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
+    /// Checks a raw transaction against the local state before it is forwarded to the sequencer.
+    /// Fails with the same errors the sequencer mempool rejects the transaction with.
+    #[instrument(level = "trace", skip_all)]
+    pub(crate) fn validate_raw_transaction(&self, data: Bytes) -> RpcResult<()> {
+        let tx = recover_raw_transaction(data)?.into_ecrecovered_transaction();
+
+        let evm = Evm::<C>::default();
+        let mut working_set = WorkingSet::new(self.storage.clone());
+
+        let chain_id = evm.get_chain_config(&mut working_set).chain_id;
+        if tx
+            .chain_id()
+            .is_some_and(|tx_chain_id| tx_chain_id != chain_id)
+        {
+            return Err(RpcInvalidTransactionError::InvalidChainId.into());
+        }
+
+        let state_nonce = evm
+            .get_transaction_count(tx.signer(), None, &mut working_set)?
+            .saturating_to::<u64>();
+        // Nonces ahead of the state are queued by the sequencer
+        if tx.nonce() < state_nonce {
+            return Err(RpcInvalidTransactionError::NonceTooLow {
+                tx: tx.nonce(),
+                state: state_nonce,
+            }
+            .into());
+        }
+
+        // The L1 fee depends on the state diff, so it is only known after execution.
+        // Transactions that fail to execute are left to the sequencer.
+        let request = TransactionRequest {
+            from: Some(tx.signer()),
+            to: Some(tx.kind()),
+            gas: Some(tx.gas_limit()),
+            max_fee_per_gas: Some(tx.max_fee_per_gas()),
+            value: Some(tx.value()),
+            input: TransactionInput::new(tx.input().clone()),
+            nonce: Some(tx.nonce()),
+            ..Default::default()
+        };
+        let l1_fee = evm
+            .citrea_estimate_fee(request, None, None, &mut working_set)
+            .map(|estimated| estimated.l1_fee)
+            .unwrap_or_default();
+
+        let balance = evm.get_balance(tx.signer(), None, &mut working_set)?;
+        let cost = U256::from(tx.gas_limit())
+            .saturating_mul(U256::from(tx.max_fee_per_gas()))
+            .saturating_add(tx.value())
+            .saturating_add(l1_fee);
+        if balance < cost {
+            return Err(RpcInvalidTransactionError::InsufficientFunds { cost, balance }.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Sends a raw transaction to the sequencer and returns the transaction hash it responds with.
+/// Connection errors are retried with backoff, errors returned by the sequencer are not.
+pub(crate) async fn forward_raw_transaction(
+    sequencer_client: &HttpClient,
+    data: Bytes,
+) -> RpcResult<B256> {
+    let exponential_backoff = ExponentialBackoffBuilder::new()
+        .with_initial_interval(Duration::from_millis(200))
+        .with_max_elapsed_time(Some(FORWARD_MAX_ELAPSED_TIME))
+        .build();
+
+    retry_backoff(exponential_backoff, || {
+        let data = data.clone();
+        async move {
+            match sequencer_client.eth_send_raw_transaction(data).await {
+                Ok(hash) => Ok(hash),
+                Err(JsonrpseeError::Call(e_owned)) => Err(backoff::Error::Permanent(e_owned)),
+                Err(e @ (JsonrpseeError::Transport(_) | JsonrpseeError::RequestTimeout)) => {
+                    debug!("Failed to forward transaction to the sequencer: {:?}", e);
+                    Err(backoff::Error::Transient {
+                        err: to_jsonrpsee_error_object("SEQUENCER_CLIENT_ERROR", e),
+                        retry_after: None,
+                    })
+                }
+                Err(e) => Err(backoff::Error::Permanent(to_jsonrpsee_error_object(
+                    "SEQUENCER_CLIENT_ERROR",
+                    e,
+                ))),
+            }
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, TcpListener};
+
+    use jsonrpsee::http_client::HttpClientBuilder;
+    use jsonrpsee::server::ServerBuilder;
+    use jsonrpsee::RpcModule;
+
+    use super::*;
+
+    const TX_HASH: B256 = B256::repeat_byte(0xab);
+
+    async fn start_sequencer(addr: SocketAddr) -> jsonrpsee::server::ServerHandle {
+        let mut module = RpcModule::new(());
+        module
+            .register_method("eth_sendRawTransaction", |_, _, _| {
+                RpcResult::<B256>::Ok(TX_HASH)
+            })
+            .unwrap();
+        let server = ServerBuilder::default().build(addr).await.unwrap();
+        server.start(module)
+    }
+
+    #[tokio::test]
+    async fn test_forward_retries_until_sequencer_is_reachable() {
+        // Reserve a port nothing is listening on yet
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = HttpClientBuilder::default()
+            .build(format!("http://{}", addr))
+            .unwrap();
+
+        let forward = tokio::spawn(async move {
+            forward_raw_transaction(&client, Bytes::from_static(&[1, 2, 3])).await
+        });
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let sequencer = start_sequencer(addr).await;
+
+        assert_eq!(forward.await.unwrap().unwrap(), TX_HASH);
+
+        sequencer.stop().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_forward_does_not_retry_sequencer_errors() {
+        let mut module = RpcModule::new(());
+        module
+            .register_method("eth_sendRawTransaction", |_, _, _| {
+                RpcResult::<B256>::Err(RpcInvalidTransactionError::InvalidChainId.into())
+            })
+            .unwrap();
+        let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let sequencer = server.start(module);
+
+        let client = HttpClientBuilder::default()
+            .build(format!("http://{}", addr))
+            .unwrap();
+        let err = forward_raw_transaction(&client, Bytes::from_static(&[1, 2, 3]))
+            .await
+            .unwrap_err();
+        let expected =
+            jsonrpsee::types::ErrorObjectOwned::from(RpcInvalidTransactionError::InvalidChainId);
+        assert_eq!(err.code(), expected.code());
+        assert_eq!(err.message(), expected.message());
+
+        sequencer.stop().unwrap();
+    }
+}
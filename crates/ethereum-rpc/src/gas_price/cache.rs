@@ -1,5 +1,5 @@
 use alloy_primitives::B256;
-use alloy_rpc_types::{AnyNetworkBlock, AnyTransactionReceipt, BlockTransactions};
+use alloy_rpc_types::AnyNetworkBlock;
 use reth_primitives::BlockNumberOrTag;
 use reth_rpc_eth_types::EthResult;
 use schnellru::{ByLength, LruMap};
@@ -85,34 +85,4 @@ impl<C: sov_modules_api::Context> BlockCache<C> {
 
         Ok(block)
     }
-
-    pub fn get_block_with_receipts(
-        &mut self,
-        block_number: u64,
-        working_set: &mut WorkingSet<C::Storage>,
-    ) -> EthResult<Option<(AnyNetworkBlock, Vec<AnyTransactionReceipt>)>> {
-        // if height not in cache, get hash from provider and call get_block
-        let block = self.get_block_by_number(block_number, working_set)?;
-        if let Some(block) = block {
-            // Receipts are not added to cache but their fee history will be kept in cache in fee_history.rs
-            let receipts: Vec<_> = match &block.transactions {
-                BlockTransactions::Full(transactions) => {
-                    transactions
-                        .iter()
-                        .map(|tx| {
-                            self.provider
-                                .get_transaction_receipt(tx.hash, working_set)
-                                .unwrap()
-                                .unwrap() // There is no way to get None here
-                        })
-                        .collect()
-                }
-                _ => unreachable!(),
-            };
-
-            return Ok(Some((block, receipts)));
-        }
-
-        Ok(None)
-    }
 }
@@ -4,97 +4,19 @@
 
 // Adopted from: https://github.com/paradigmxyz/reth/blob/main/crates/rpc/rpc/src/eth/gas_oracle.rs
 
-use alloy_network::AnyNetwork;
 use alloy_primitives::{B256, U256};
-use alloy_rpc_types::{BlockTransactions, FeeHistory};
-use citrea_evm::{Evm, SYSTEM_SIGNER};
+use alloy_rpc_types::FeeHistory;
+pub use citrea_evm::GasPriceOracleConfig;
+use citrea_evm::{Evm, MAX_FEE_HISTORY_BLOCK_COUNT};
 use citrea_primitives::basefee::calculate_next_block_base_fee;
 use parking_lot::Mutex;
 use reth_primitives::BlockNumberOrTag;
-use reth_rpc_eth_api::RpcTransaction;
-use reth_rpc_eth_types::error::{EthApiError, EthResult, RpcInvalidTransactionError};
-use serde::{Deserialize, Serialize};
+use reth_rpc_eth_types::error::{EthApiError, EthResult};
 use sov_modules_api::WorkingSet;
 use tracing::warn;
 
 use super::cache::BlockCache;
-use super::fee_history::{FeeHistoryCache, FeeHistoryCacheConfig, FeeHistoryEntry};
-
-/// The number of transactions sampled in a block
-pub const SAMPLE_NUMBER: u32 = 3;
-
-/// The default maximum number of blocks to use for the gas price oracle.
-pub const MAX_HEADER_HISTORY: u64 = 1024;
-
-/// The default maximum gas price to use for the estimate
-pub const DEFAULT_MAX_PRICE: U256 = U256::from_limbs([500_000_000_000u64, 0, 0, 0]);
-
-/// The default minimum gas price, under which the sample will be ignored
-pub const DEFAULT_IGNORE_PRICE: U256 = U256::from_limbs([2u64, 0, 0, 0]);
-
-/// Settings for the gas price oracle configured by node operators
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GasPriceOracleConfig {
-    /// The number of populated blocks to produce the gas price estimate
-    pub blocks: u32,
-
-    /// The percentile of gas prices to use for the estimate
-    pub percentile: u32,
-
-    /// The maximum number of headers to keep in the cache
-    pub max_header_history: u64,
-
-    /// The maximum number of blocks for estimating gas price
-    pub max_block_history: u64,
-
-    /// The default gas price to use if there are no blocks to use
-    pub default: Option<u128>,
-
-    /// The maximum gas price to use for the estimate
-    pub max_price: Option<u128>,
-
-    /// The minimum gas price, under which the sample will be ignored
-    pub ignore_price: Option<u128>,
-}
-
-impl Default for GasPriceOracleConfig {
-    fn default() -> Self {
-        GasPriceOracleConfig {
-            blocks: 20,
-            percentile: 60,
-            max_header_history: MAX_HEADER_HISTORY,
-            max_block_history: MAX_HEADER_HISTORY,
-            default: None,
-            max_price: Some(DEFAULT_MAX_PRICE.saturating_to()),
-            ignore_price: Some(DEFAULT_IGNORE_PRICE.saturating_to()),
-        }
-    }
-}
-
-impl GasPriceOracleConfig {
-    /// Creating a new gpo config with blocks, ignoreprice, maxprice and percentile
-    pub fn new(
-        blocks: Option<u32>,
-        ignore_price: Option<u64>,
-        max_price: Option<u64>,
-        percentile: Option<u32>,
-    ) -> Self {
-        Self {
-            blocks: blocks.unwrap_or(20),
-            percentile: percentile.unwrap_or(60),
-            max_header_history: 1024,
-            max_block_history: 1024,
-            default: None,
-            max_price: max_price
-                .map(u128::from)
-                .or(Some(DEFAULT_MAX_PRICE.saturating_to())),
-            ignore_price: ignore_price
-                .map(u128::from)
-                .or(Some(DEFAULT_IGNORE_PRICE.saturating_to())),
-        }
-    }
-}
+use super::fee_history::{FeeHistoryCache, FeeHistoryCacheConfig};
 
 /// Calculates a gas price depending on recent blocks.
 pub struct GasPriceOracle<C: sov_modules_api::Context> {
@@ -161,6 +83,7 @@ impl<C: sov_modules_api::Context> GasPriceOracle<C> {
         if block_count > max_fee_history {
             block_count = max_fee_history
         }
+        block_count = block_count.min(MAX_FEE_HISTORY_BLOCK_COUNT);
 
         let end_block = self
             .provider
@@ -193,16 +116,11 @@ impl<C: sov_modules_api::Context> GasPriceOracle<C> {
         // Collect base fees, gas usage ratios and (optionally) reward percentile data
         let mut base_fee_per_gas: Vec<u128> = Vec::new();
         let mut gas_used_ratio: Vec<f64> = Vec::new();
-        let mut rewards: Vec<Vec<u128>> = Vec::new();
 
-        let (fee_entries, resolution) = {
-            let mut fee_history_cache = self.fee_history_cache.lock();
-
-            (
-                fee_history_cache.get_history(start_block, end_block, working_set),
-                fee_history_cache.resolution(),
-            )
-        };
+        let fee_entries =
+            self.fee_history_cache
+                .lock()
+                .get_history(start_block, end_block, working_set);
 
         if fee_entries.len() != block_count as usize {
             return Err(EthApiError::InvalidBlockRange);
@@ -211,15 +129,18 @@ impl<C: sov_modules_api::Context> GasPriceOracle<C> {
         for entry in &fee_entries {
             base_fee_per_gas.push(entry.base_fee_per_gas as u128);
             gas_used_ratio.push(entry.gas_used_ratio);
-
-            if let Some(percentiles) = &reward_percentiles {
-                let mut block_rewards = Vec::with_capacity(percentiles.len());
-                for &percentile in percentiles.iter() {
-                    block_rewards.push(self.approximate_percentile(entry, percentile, resolution));
-                }
-                rewards.push(block_rewards);
-            }
         }
+
+        // Rewards are computed exactly from the stored receipts rather than
+        // approximated from the cached percentiles
+        let rewards = match &reward_percentiles {
+            Some(percentiles) => Some(self.provider.fee_history_rewards(
+                start_block..=end_block,
+                percentiles,
+                working_set,
+            )?),
+            None => None,
+        };
         let last_entry = fee_entries.last().expect("is not empty");
         base_fee_per_gas.push(calculate_next_block_base_fee(
             last_entry.gas_used,
@@ -232,13 +153,14 @@ impl<C: sov_modules_api::Context> GasPriceOracle<C> {
             base_fee_per_gas,
             gas_used_ratio,
             oldest_block: start_block,
-            reward: reward_percentiles.map(|_| rewards),
+            reward: rewards,
             base_fee_per_blob_gas: Default::default(),
             blob_gas_used_ratio: Default::default(),
         })
     }
 
-    /// Suggests a gas price estimate based on recent blocks, using the configured percentile.
+    /// Suggests a priority fee based on recent blocks, using the configured percentile.
+    /// The estimate is cached until the head block changes.
     pub fn suggest_tip_cap(&self, working_set: &mut WorkingSet<C::Storage>) -> EthResult<u128> {
         let header = &self
             .provider
@@ -254,59 +176,9 @@ impl<C: sov_modules_api::Context> GasPriceOracle<C> {
             return Ok(last_price.price);
         }
 
-        // if all responses are empty, then we can return a maximum of 2*check_block blocks' worth
-        // of prices
-        //
-        // we only return more than check_block blocks' worth of prices if one or more return empty
-        // transactions
-        let mut current_hash = header.hash;
-        let mut results = Vec::new();
-        let mut populated_blocks = 0;
-
-        let header_number = header.number;
-
-        // we only check a maximum of 2 * max_block_history, or the number of blocks in the chain
-        let max_blocks = if self.oracle_config.max_block_history * 2 > header_number {
-            header_number
-        } else {
-            self.oracle_config.max_block_history * 2
-        };
-
-        for _ in 0..max_blocks {
-            let (parent_hash, block_values) = self
-                .get_block_values(current_hash, SAMPLE_NUMBER as usize, working_set)?
-                .ok_or(EthApiError::HeaderNotFound(current_hash.into()))?;
-
-            if block_values.is_empty() {
-                results.push(last_price.price);
-            } else {
-                results.extend(block_values);
-                populated_blocks += 1;
-            }
-
-            // break when we have enough populated blocks
-            if populated_blocks >= self.oracle_config.blocks {
-                break;
-            }
-
-            current_hash = parent_hash;
-        }
-
-        // sort results then take the configured percentile result
-        let mut price = last_price.price;
-        if !results.is_empty() {
-            results.sort_unstable();
-            price = *results
-                .get((results.len() - 1) * self.oracle_config.percentile as usize / 100)
-                .expect("gas price index is a percent of nonzero array length, so a value always exists; qed");
-        }
-
-        // constrain to the max price
-        if let Some(max_price) = self.oracle_config.max_price {
-            if price > max_price {
-                price = max_price;
-            }
-        }
+        let price = self
+            .provider
+            .suggest_priority_fee(&self.oracle_config, working_set);
 
         *last_price = GasPriceOracleResult {
             block_hash: header.hash,
@@ -315,98 +187,6 @@ impl<C: sov_modules_api::Context> GasPriceOracle<C> {
 
         Ok(price)
     }
-
-    /// Get the `limit` lowest effective tip values for the given block. If the oracle has a
-    /// configured `ignore_price` threshold, then tip values under that threshold will be ignored
-    /// before returning a result.
-    ///
-    /// If the block cannot be found, then this will return `None`.
-    ///
-    /// This method also returns the parent hash for the given block.
-    fn get_block_values(
-        &self,
-        block_hash: B256,
-        limit: usize,
-        working_set: &mut WorkingSet<C::Storage>,
-    ) -> EthResult<Option<(B256, Vec<u128>)>> {
-        // check the cache (this will hit the disk if the block is not cached)
-        let block_hit = {
-            let mut cache = self.fee_history_cache.lock();
-            cache.block_cache.get_block(block_hash, working_set)?
-        };
-        let block = match block_hit {
-            Some(block) => block,
-            None => return Ok(None),
-        };
-
-        // sort the transactions by effective tip
-        // but first filter those that should be ignored
-
-        // get the transactions (block.transactions is a enum but we only care about the 2nd arm)
-        let txs = match &block.transactions {
-            BlockTransactions::Full(txs) => txs,
-            _ => return Ok(None),
-        };
-
-        let mut txs = txs
-            .iter()
-            .filter(|tx| {
-                if let Some(ignore_under) = self.oracle_config.ignore_price {
-                    let effective_gas_tip = effective_gas_tip(
-                        tx,
-                        block.header.base_fee_per_gas.map(|basefee| basefee as u128),
-                    );
-                    if effective_gas_tip < Some(ignore_under) {
-                        return false;
-                    }
-                }
-
-                // check if coinbase
-                let sender = tx.from;
-                sender != block.header.miner && sender != SYSTEM_SIGNER
-            })
-            // map all values to effective_gas_tip because we will be returning those values
-            // anyways
-            .map(|tx| {
-                effective_gas_tip(
-                    tx,
-                    block.header.base_fee_per_gas.map(|basefee| basefee as u128),
-                )
-            })
-            .collect::<Vec<_>>();
-
-        // now do the sort
-        txs.sort_unstable();
-
-        // fill result with the top `limit` transactions
-        let mut final_result = Vec::with_capacity(limit);
-        for tx in txs.iter().take(limit) {
-            // a `None` effective_gas_tip represents a transaction where the max_fee_per_gas is
-            // less than the base fee
-            let effective_tip = tx.ok_or(RpcInvalidTransactionError::FeeCapTooLow)?;
-            final_result.push(effective_tip);
-        }
-
-        Ok(Some((block.header.parent_hash, final_result)))
-    }
-
-    /// Approximates reward at a given percentile for a specific block
-    /// Based on the configured resolution
-    fn approximate_percentile(
-        &self,
-        entry: &FeeHistoryEntry,
-        requested_percentile: f64,
-        resolution: u64,
-    ) -> u128 {
-        let rounded_percentile =
-            (requested_percentile * resolution as f64).round() / resolution as f64;
-        let clamped_percentile = rounded_percentile.clamp(0.0, 100.0);
-
-        // Calculate the index in the precomputed rewards array
-        let index = (clamped_percentile / (1.0 / resolution as f64)).round() as usize;
-        // Fetch the reward from the FeeHistoryEntry
-        entry.rewards.get(index).cloned().unwrap_or_default()
-    }
 }
 
 /// Stores the last result that the oracle returned
@@ -428,47 +208,9 @@ impl Default for GasPriceOracleResult {
     }
 }
 
-// Adopted from: https://github.com/paradigmxyz/reth/blob/main/crates/primitives/src/transaction/mod.rs#L297
-pub(crate) fn effective_gas_tip(
-    transaction: &RpcTransaction<AnyNetwork>,
-    base_fee: Option<u128>,
-) -> Option<u128> {
-    let priority_fee_or_price = match transaction.transaction_type {
-        Some(tx_type) => {
-            if tx_type == 2 {
-                transaction.max_priority_fee_per_gas.unwrap()
-            } else {
-                transaction.gas_price.unwrap()
-            }
-        }
-        _ => transaction.gas_price.unwrap(),
-    };
-
-    if let Some(base_fee) = base_fee {
-        let max_fee_per_gas = match transaction.transaction_type {
-            Some(tx_type) => {
-                if tx_type == 2 {
-                    transaction.max_priority_fee_per_gas.unwrap()
-                } else {
-                    transaction.gas_price.unwrap()
-                }
-            }
-            _ => transaction.gas_price.unwrap(),
-        };
-
-        if max_fee_per_gas < base_fee {
-            None
-        } else {
-            let effective_max_fee = max_fee_per_gas - base_fee;
-            Some(std::cmp::min(effective_max_fee, priority_fee_or_price))
-        }
-    } else {
-        Some(priority_fee_or_price)
-    }
-}
-
 #[cfg(test)]
 mod tests {
+    use citrea_evm::{DEFAULT_IGNORE_PRICE, DEFAULT_MAX_PRICE};
     use reth_primitives::constants::GWEI_TO_WEI;
 
     use super::*;
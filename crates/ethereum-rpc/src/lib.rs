@@ -1,4 +1,6 @@
 mod ethereum;
+mod filter;
+mod forwarding;
 mod gas_price;
 mod subscription;
 mod trace;
@@ -6,51 +8,37 @@ mod trace;
 use std::sync::Arc;
 
 use alloy_network::AnyNetwork;
-use alloy_primitives::{keccak256, Bytes, B256, U256};
-use alloy_rpc_types::{FeeHistory, Index};
+use alloy_primitives::{keccak256, Address, Bytes, B256, U128, U256};
+use alloy_rpc_types::state::StateOverride;
+use alloy_rpc_types::{
+    BlockOverrides, EIP1186AccountProofResponse, FeeHistory, Index, TransactionRequest,
+};
 use alloy_rpc_types_trace::geth::{GethDebugTracingOptions, GethTrace, TraceResult};
-use citrea_evm::{Evm, Filter};
+use alloy_serde::JsonStorageKey;
+use citrea_evm::{Evm, Filter, LogResponse};
 use citrea_sequencer::SequencerRpcClient;
 pub use ethereum::{EthRpcConfig, Ethereum};
+use filter::FilterKind;
+pub use filter::{FilterChanges, FilterStoreConfig};
 pub use gas_price::fee_history::FeeHistoryCacheConfig;
 pub use gas_price::gas_oracle::GasPriceOracleConfig;
 use jsonrpsee::core::{RpcResult, SubscriptionResult};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::types::ErrorObjectOwned;
-use jsonrpsee::{PendingSubscriptionSink, RpcModule};
-use reth_primitives::BlockNumberOrTag;
+use jsonrpsee::{ConnectionId, Extensions, PendingSubscriptionSink, RpcModule};
+use reth_primitives::{BlockId, BlockNumberOrTag};
 use reth_rpc_eth_api::RpcTransaction;
 use reth_rpc_eth_types::EthApiError;
 use serde_json::{json, Value};
-use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
-use sov_ledger_rpc::LedgerRpcClient;
-use sov_modules_api::da::BlockHeaderTrait;
+use sov_db::ledger_db::LedgerDB;
 use sov_modules_api::utils::to_jsonrpsee_error_object;
 use sov_modules_api::WorkingSet;
 use sov_rollup_interface::services::da::DaService;
-use tokio::join;
+use sov_state::storage::NativeStorage;
 use tokio::sync::broadcast;
 use trace::{debug_trace_by_block_number, handle_debug_trace_chain};
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
-pub struct SyncValues {
-    pub head_block_number: u64,
-    pub synced_block_number: u64,
-}
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
-pub enum LayerStatus {
-    Synced(u64),
-    Syncing(SyncValues),
-}
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
-pub struct SyncStatus {
-    pub l1_status: LayerStatus,
-    pub l2_status: LayerStatus,
-}
-
 #[rpc(server)]
 pub trait EthereumRpc {
     /// Returns the client version.
@@ -87,6 +75,61 @@ pub trait EthereumRpc {
         reward_percentiles: Option<Vec<f64>>,
     ) -> RpcResult<FeeHistory>;
 
+    /// Executes a call on the state at the end of the given block, without creating a transaction.
+    #[method(name = "eth_call")]
+    #[blocking]
+    fn eth_call(
+        &self,
+        request: TransactionRequest,
+        block_id: Option<BlockId>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<Bytes>;
+
+    /// Returns the account and storage values of the given address with their state proofs.
+    #[method(name = "eth_getProof")]
+    #[blocking]
+    fn eth_get_proof(
+        &self,
+        address: Address,
+        keys: Vec<JsonStorageKey>,
+        block_id: Option<BlockId>,
+    ) -> RpcResult<EIP1186AccountProofResponse>;
+
+    /// Returns logs matching given filter object.
+    #[method(name = "eth_getLogs")]
+    #[blocking]
+    fn eth_get_logs(&self, filter: Filter) -> RpcResult<Vec<LogResponse>>;
+
+    /// Installs a filter of the logs matching the filter object, polled with `eth_getFilterChanges`.
+    #[method(name = "eth_newFilter", with_extensions)]
+    #[blocking]
+    fn eth_new_filter(&self, filter: Filter) -> RpcResult<U128>;
+
+    /// Installs a filter of the new blocks, polled with `eth_getFilterChanges`.
+    #[method(name = "eth_newBlockFilter", with_extensions)]
+    #[blocking]
+    fn eth_new_block_filter(&self) -> RpcResult<U128>;
+
+    /// Installs a filter of the new pending transactions, polled with `eth_getFilterChanges` (sequencer only).
+    #[method(name = "eth_newPendingTransactionFilter", with_extensions)]
+    #[blocking]
+    fn eth_new_pending_transaction_filter(&self) -> RpcResult<U128>;
+
+    /// Returns the changes of the filter since it was last polled.
+    #[method(name = "eth_getFilterChanges")]
+    #[blocking]
+    fn eth_get_filter_changes(&self, id: U128) -> RpcResult<FilterChanges>;
+
+    /// Returns all logs matching the log filter.
+    #[method(name = "eth_getFilterLogs")]
+    #[blocking]
+    fn eth_get_filter_logs(&self, id: U128) -> RpcResult<Vec<LogResponse>>;
+
+    /// Uninstalls the filter, returns false if it was not installed.
+    #[method(name = "eth_uninstallFilter")]
+    fn eth_uninstall_filter(&self, id: U128) -> RpcResult<bool>;
+
     /// Returns traces for a block by hash.
     #[method(name = "debug_traceBlockByHash")]
     #[blocking]
@@ -138,10 +181,6 @@ pub trait EthereumRpc {
         mempool_only: Option<bool>,
     ) -> RpcResult<Option<RpcTransaction<AnyNetwork>>>;
 
-    /// Gets sync status (full node only).
-    #[method(name = "citrea_syncStatus")]
-    async fn citrea_sync_status(&self) -> RpcResult<SyncStatus>;
-
     /// Subscribe to debug events.
     #[subscription(name = "debug_subscribe" => "debug_subscription", unsubscribe = "debug_unsubscribe", item = GethTrace)]
     async fn subscribe_debug(
@@ -181,10 +220,27 @@ where
     }
 }
 
+impl<C, Da> EthereumRpcServerImpl<C, Da>
+where
+    C: sov_modules_api::Context,
+    C::Storage: NativeStorage,
+    Da: DaService,
+{
+    fn install_filter(&self, ext: &Extensions, kind: FilterKind) -> RpcResult<U128> {
+        self.ethereum.filter_store.install::<C>(
+            kind,
+            ext.get::<ConnectionId>().copied(),
+            &self.ethereum.storage,
+            &self.ethereum.ledger_db,
+        )
+    }
+}
+
 #[async_trait::async_trait]
 impl<C, Da> EthereumRpcServer for EthereumRpcServerImpl<C, Da>
 where
     C: sov_modules_api::Context,
+    C::Storage: NativeStorage,
     Da: DaService,
 {
     fn web3_client_version(&self) -> RpcResult<String> {
@@ -197,19 +253,28 @@ where
 
     fn eth_gas_price(&self) -> RpcResult<U256> {
         let mut working_set = WorkingSet::new(self.ethereum.storage.clone());
-        let (base_fee, suggested_tip) = self.ethereum.max_fee_per_gas(&mut working_set);
+        let (base_fee, suggested_tip) = self
+            .ethereum
+            .max_fee_per_gas(&mut working_set)
+            .map_err(to_eth_rpc_error)?;
         Ok(suggested_tip + base_fee)
     }
 
     fn eth_max_fee_per_gas(&self) -> RpcResult<U256> {
         let mut working_set = WorkingSet::new(self.ethereum.storage.clone());
-        let (base_fee, suggested_tip) = self.ethereum.max_fee_per_gas(&mut working_set);
+        let (base_fee, suggested_tip) = self
+            .ethereum
+            .max_fee_per_gas(&mut working_set)
+            .map_err(to_eth_rpc_error)?;
         Ok(suggested_tip + base_fee)
     }
 
     fn eth_max_priority_fee_per_gas(&self) -> RpcResult<U256> {
         let mut working_set = WorkingSet::new(self.ethereum.storage.clone());
-        let (_base_fee, suggested_tip) = self.ethereum.max_fee_per_gas(&mut working_set);
+        let (_base_fee, suggested_tip) = self
+            .ethereum
+            .max_fee_per_gas(&mut working_set)
+            .map_err(to_eth_rpc_error)?;
         Ok(suggested_tip)
     }
 
@@ -233,6 +298,79 @@ where
             .map_err(to_eth_rpc_error)
     }
 
+    fn eth_call(
+        &self,
+        request: TransactionRequest,
+        block_id: Option<BlockId>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<Bytes> {
+        let evm = Evm::<C>::default();
+        let mut working_set = WorkingSet::new(self.ethereum.storage.clone());
+
+        self.ethereum
+            .ensure_state_available(&evm, block_id, &mut working_set)?;
+        evm.get_call(
+            request,
+            block_id,
+            state_overrides,
+            block_overrides,
+            &mut working_set,
+        )
+    }
+
+    fn eth_get_proof(
+        &self,
+        address: Address,
+        keys: Vec<JsonStorageKey>,
+        block_id: Option<BlockId>,
+    ) -> RpcResult<EIP1186AccountProofResponse> {
+        let evm = Evm::<C>::default();
+        let mut working_set = WorkingSet::new(self.ethereum.storage.clone());
+
+        evm.get_proof(address, keys, block_id, &mut working_set)
+    }
+
+    fn eth_get_logs(&self, filter: Filter) -> RpcResult<Vec<LogResponse>> {
+        let evm = Evm::<C>::default();
+        let mut working_set = WorkingSet::new(self.ethereum.storage.clone());
+
+        evm.eth_get_logs(filter, &self.ethereum.logs_query_limits, &mut working_set)
+    }
+
+    fn eth_new_filter(&self, ext: &Extensions, filter: Filter) -> RpcResult<U128> {
+        self.install_filter(ext, FilterKind::Logs(Box::new(filter)))
+    }
+
+    fn eth_new_block_filter(&self, ext: &Extensions) -> RpcResult<U128> {
+        self.install_filter(ext, FilterKind::Blocks)
+    }
+
+    fn eth_new_pending_transaction_filter(&self, ext: &Extensions) -> RpcResult<U128> {
+        self.install_filter(ext, FilterKind::PendingTransactions)
+    }
+
+    fn eth_get_filter_changes(&self, id: U128) -> RpcResult<FilterChanges> {
+        self.ethereum.filter_store.changes::<C>(
+            id,
+            &self.ethereum.storage,
+            &self.ethereum.ledger_db,
+            &self.ethereum.logs_query_limits,
+        )
+    }
+
+    fn eth_get_filter_logs(&self, id: U128) -> RpcResult<Vec<LogResponse>> {
+        self.ethereum.filter_store.logs::<C>(
+            id,
+            &self.ethereum.storage,
+            &self.ethereum.logs_query_limits,
+        )
+    }
+
+    fn eth_uninstall_filter(&self, id: U128) -> RpcResult<bool> {
+        Ok(self.ethereum.filter_store.uninstall(id))
+    }
+
     fn debug_trace_block_by_hash(
         &self,
         block_hash: B256,
@@ -302,9 +440,8 @@ where
         let mut working_set = WorkingSet::new(self.ethereum.storage.clone());
 
         let tx = evm
-            .get_transaction_by_hash(tx_hash, &mut working_set)
-            .unwrap()
-            .ok_or_else(|| EthApiError::UnknownBlockOrTxIndex)?;
+            .get_transaction_by_hash(tx_hash, &mut working_set)?
+            .ok_or_else(|| EthApiError::TransactionNotFound)?;
 
         let trace_idx: u64 = tx
             .transaction_index
@@ -350,16 +487,16 @@ where
     }
 
     async fn eth_send_raw_transaction(&self, data: Bytes) -> RpcResult<B256> {
-        self.ethereum
-            .sequencer_client
-            .as_ref()
-            .unwrap()
-            .eth_send_raw_transaction(data)
-            .await
-            .map_err(|e| match e {
-                jsonrpsee::core::client::Error::Call(e_owned) => e_owned,
-                _ => to_jsonrpsee_error_object("SEQUENCER_CLIENT_ERROR", e),
-            })
+        // Only read-only nodes serve this method without a sequencer client
+        let Some(sequencer_client) = self.ethereum.sequencer_client.as_ref() else {
+            return Err(to_jsonrpsee_error_object(
+                "READ_ONLY_NODE",
+                "read-only node does not accept transactions",
+            ));
+        };
+        // Reject transactions the sequencer would reject without a round trip
+        self.ethereum.validate_raw_transaction(data.clone())?;
+        forwarding::forward_raw_transaction(sequencer_client, data).await
     }
 
     async fn eth_get_transaction_by_hash(
@@ -369,11 +506,11 @@ where
     ) -> RpcResult<Option<RpcTransaction<AnyNetwork>>> {
         match mempool_only {
             Some(true) => {
-                match self
-                    .ethereum
-                    .sequencer_client
-                    .as_ref()
-                    .unwrap()
+                // A read-only node has no mempool to look into
+                let Some(sequencer_client) = self.ethereum.sequencer_client.as_ref() else {
+                    return Ok(None);
+                };
+                match sequencer_client
                     .eth_get_transaction_by_hash(hash, Some(true))
                     .await
                 {
@@ -390,11 +527,10 @@ where
                 match evm.get_transaction_by_hash(hash, &mut working_set) {
                     Ok(Some(tx)) => Ok(Some(tx)),
                     Ok(None) => {
-                        match self
-                            .ethereum
-                            .sequencer_client
-                            .as_ref()
-                            .unwrap()
+                        let Some(sequencer_client) = self.ethereum.sequencer_client.as_ref() else {
+                            return Ok(None);
+                        };
+                        match sequencer_client
                             .eth_get_transaction_by_hash(hash, Some(true))
                             .await
                         {
@@ -411,66 +547,6 @@ where
         }
     }
 
-    async fn citrea_sync_status(&self) -> RpcResult<SyncStatus> {
-        let (sequencer_response, da_response) = join!(
-            self.ethereum
-                .sequencer_client
-                .as_ref()
-                .unwrap()
-                .get_head_soft_confirmation_height(),
-            self.ethereum.da_service.get_last_finalized_block_header()
-        );
-
-        let l2_head_block_number = match sequencer_response {
-            Ok(block_number) => block_number,
-            Err(e) => match e {
-                jsonrpsee::core::client::Error::Call(e_owned) => return Err(e_owned),
-                _ => return Err(to_jsonrpsee_error_object("SEQUENCER_CLIENT_ERROR", e)),
-            },
-        };
-
-        let head_soft_confirmation = self.ethereum.ledger_db.get_head_soft_confirmation();
-        let l2_synced_block_number = match head_soft_confirmation {
-            Ok(Some((height, _))) => height.0,
-            Ok(None) => 0u64,
-            Err(e) => return Err(to_jsonrpsee_error_object("LEDGER_DB_ERROR", e)),
-        };
-
-        let l1_head_block_number = match da_response {
-            Ok(header) => header.height(),
-            Err(e) => return Err(to_jsonrpsee_error_object("DA_SERVICE_ERROR", e)),
-        };
-
-        let l1_synced_block_number = match self.ethereum.ledger_db.get_last_scanned_l1_height() {
-            Ok(Some(slot_number)) => slot_number.0,
-            Ok(None) => 0u64,
-            Err(e) => return Err(to_jsonrpsee_error_object("LEDGER_DB_ERROR", e)),
-        };
-
-        let l1_status = if l1_synced_block_number < l1_head_block_number {
-            LayerStatus::Syncing(SyncValues {
-                synced_block_number: l1_synced_block_number,
-                head_block_number: l1_head_block_number,
-            })
-        } else {
-            LayerStatus::Synced(l1_head_block_number)
-        };
-
-        let l2_status = if l2_synced_block_number < l2_head_block_number {
-            LayerStatus::Syncing(SyncValues {
-                synced_block_number: l2_synced_block_number,
-                head_block_number: l2_head_block_number,
-            })
-        } else {
-            LayerStatus::Synced(l2_head_block_number)
-        };
-
-        Ok(SyncStatus {
-            l1_status,
-            l2_status,
-        })
-    }
-
     async fn subscribe_debug(
         &self,
         pending: PendingSubscriptionSink,
@@ -531,20 +607,25 @@ pub fn create_rpc_module<C, Da>(
     storage: C::Storage,
     ledger_db: LedgerDB,
     sequencer_client_url: Option<String>,
+    read_only: bool,
     soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
 ) -> RpcModule<EthereumRpcServerImpl<C, Da>>
 where
     C: sov_modules_api::Context,
+    C::Storage: NativeStorage,
     Da: DaService,
 {
     // Unpack config
     let EthRpcConfig {
         gas_price_oracle_config,
         fee_history_cache_config,
+        logs_query_limits,
+        filter_store_config,
     } = eth_rpc_config;
 
-    // If the node does not have a sequencer client, then it is the sequencer.
-    let is_sequencer = sequencer_client_url.is_none();
+    // If the node does not have a sequencer client, then it is the sequencer
+    // unless it is a read-only node, which rejects transactions instead.
+    let is_sequencer = sequencer_client_url.is_none() && !read_only;
     let enable_subscriptions = soft_confirmation_rx.is_some();
 
     // If the running node is a full node rpc context should also have sequencer client so that it can send txs to sequencer
@@ -552,6 +633,8 @@ where
         da_service,
         gas_price_oracle_config,
         fee_history_cache_config,
+        logs_query_limits,
+        filter_store_config,
         storage,
         ledger_db,
         sequencer_client_url.map(|url| HttpClientBuilder::default().build(url).unwrap()),
@@ -564,7 +647,11 @@ where
     if is_sequencer {
         module.remove_method("eth_sendRawTransaction");
         module.remove_method("eth_getTransactionByHash");
-        module.remove_method("citrea_syncStatus");
+        // The sequencer serves the content of its own mempool
+        module.remove_method("txpool_content");
+    } else {
+        // Only the sequencer has a mempool to filter
+        module.remove_method("eth_newPendingTransactionFilter");
     }
 
     if !enable_subscriptions {
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use alloy_rpc_types::AnyNetworkBlock;
-use citrea_evm::{log_matches_filter, Evm, Filter, LogResponse};
+use citrea_evm::{log_matches_filter, Evm, Filter, LogResponse, LogsQueryLimits};
 use futures::future;
 use jsonrpsee::{SubscriptionMessage, SubscriptionSink};
 use reth_primitives::BlockNumberOrTag;
@@ -154,7 +154,13 @@ pub async fn soft_confirmation_event_handler<C: sov_modules_api::Context>(
 
         let mut working_set = WorkingSet::new(storage.clone());
         let logs = evm
-            .get_logs_in_block_range(&mut working_set, &Filter::default(), height, height)
+            .get_logs_in_block_range(
+                &mut working_set,
+                &Filter::default(),
+                height,
+                height,
+                &LogsQueryLimits::default(),
+            )
             .expect("Error getting logs in block range");
 
         // Only possible error is no receiver
@@ -144,7 +144,7 @@ pub fn debug_trace_by_block_number<C: sov_modules_api::Context, Da: DaService>(
         return Ok(traces);
     }
 
-    let cache_options = create_trace_cache_opts();
+    let cache_options = create_trace_cache_opts(requested_opts.timeout);
     let traces = evm.trace_block_transactions_by_number(
         block_number,
         Some(cache_options),
@@ -294,7 +294,7 @@ fn convert_call_trace_into_4byte_map(
     four_byte_map
 }
 
-fn create_trace_cache_opts() -> GethDebugTracingOptions {
+fn create_trace_cache_opts(timeout: Option<String>) -> GethDebugTracingOptions {
     // Get the traces with call tracer onlytopcall false and withlog true and always cache this way
     let mut call_config_map = serde_json::Map::new();
     call_config_map.insert("only_top_call".to_string(), serde_json::Value::Bool(false));
@@ -305,6 +305,7 @@ fn create_trace_cache_opts() -> GethDebugTracingOptions {
             GethDebugBuiltInTracerType::CallTracer,
         )),
         tracer_config: GethDebugTracerConfig(call_config),
+        timeout,
         ..Default::default()
     }
 }
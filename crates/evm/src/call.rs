@@ -1,9 +1,11 @@
 use core::panic;
 
 use reth_primitives::TransactionSignedEcRecovered;
-use revm::primitives::{BlockEnv, CfgEnv, CfgEnvWithHandlerCfg, SpecId};
+use revm::primitives::{BlockEnv, CfgEnv, CfgEnvWithHandlerCfg, U256};
 use sov_modules_api::prelude::*;
-use sov_modules_api::{native_error, CallResponse, SoftConfirmationModuleCallError, WorkingSet};
+use sov_modules_api::{
+    native_error, CallResponse, SoftConfirmationModuleCallError, SpecId as CitreaSpecId, WorkingSet,
+};
 
 use crate::conversions::ConversionError;
 use crate::evm::db::EvmDb;
@@ -13,7 +15,11 @@ use crate::evm::primitive_types::{Receipt, TransactionSignedAndRecovered};
 use crate::evm::{EvmChainConfig, RlpEvmTransaction};
 use crate::system_contracts::{BitcoinLightClient, BridgeWrapper};
 use crate::system_events::{create_system_transactions, SYSTEM_SIGNER};
-use crate::{citrea_spec_id_to_evm_spec_id, Evm, PendingTransaction, SystemEvent};
+use crate::{
+    citrea_spec_id_reserves_system_gas, citrea_spec_id_to_contract_code_size_limit,
+    citrea_spec_id_to_evm_spec_id, citrea_spec_id_tracks_total_supply, Evm, PendingTransaction,
+    SystemEvent, BRIDGE_GENESIS_BALANCE,
+};
 
 #[cfg_attr(
     feature = "serde",
@@ -36,7 +42,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         l1_fee_rate: u128,
         cfg: EvmChainConfig,
         block_env: BlockEnv,
-        active_spec: SpecId,
+        active_spec: CitreaSpecId,
         working_set: &mut WorkingSet<C::Storage>,
     ) {
         // don't use self.block_env here
@@ -67,6 +73,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .get(&SYSTEM_SIGNER, working_set)
             .map(|info| info.nonce)
             .unwrap_or(0);
+        let bridge_balance = self.bridge_balance(working_set);
 
         let db: EvmDb<'_, C> = self.get_db(working_set, cfg_env.handler_cfg.spec_id);
         let system_txs = create_system_transactions(system_events, system_nonce, cfg_env.chain_id);
@@ -80,6 +87,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             cfg_env,
             &mut citrea_handler_ext,
         );
+        self.update_total_supply(active_spec, bridge_balance, working_set);
 
         let mut cumulative_gas_used = 0;
         let mut log_index_start = 0;
@@ -139,8 +147,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .map_err(|_| SoftConfirmationModuleCallError::EvmTxNotSerializable)?;
 
         let cfg = self.cfg.get(working_set).expect("Evm config must be set");
-        let active_evm_spec = citrea_spec_id_to_evm_spec_id(context.active_spec());
-        let cfg_env: CfgEnvWithHandlerCfg = get_cfg_env(cfg, active_evm_spec);
+        let cfg_env: CfgEnvWithHandlerCfg = get_cfg_env(cfg, context.active_spec());
 
         let l1_fee_rate = context.l1_fee_rate();
         let mut citrea_handler_ext = CitreaExternal::new(l1_fee_rate);
@@ -154,6 +161,35 @@ impl<C: sov_modules_api::Context> Evm<C> {
             log_index_start = tx.receipt.log_index_start + tx.receipt.receipt.logs.len() as u64;
         }
 
+        let block_gas_limit: u64 = self.block_env.gas_limit.saturating_to();
+        let (gas_limit, prev_gas_used) =
+            match self.active_system_gas_reserve(context.active_spec(), working_set) {
+                // system transactions are paid from the reserve, user transactions from the rest
+                Some(system_gas_reserve) => {
+                    let system_gas_used: u64 = self
+                        .pending_transactions
+                        .iter()
+                        .filter(|tx| tx.transaction.signer == SYSTEM_SIGNER)
+                        .map(|tx| tx.receipt.gas_used as u64)
+                        .sum();
+                    // user transactions can't be fit into the block in this case
+                    if system_gas_used > system_gas_reserve {
+                        return Err(
+                            SoftConfirmationModuleCallError::EvmSystemGasExceedsReserve {
+                                system_gas_used,
+                                system_gas_reserve,
+                            },
+                        );
+                    }
+                    (
+                        block_gas_limit.saturating_sub(system_gas_reserve),
+                        cumulative_gas_used - system_gas_used,
+                    )
+                }
+                None => (block_gas_limit, cumulative_gas_used),
+            };
+
+        let bridge_balance = self.bridge_balance(working_set);
         let evm_db: EvmDb<'_, C> = self.get_db(working_set, cfg_env.handler_cfg.spec_id);
 
         let results = executor::execute_multiple_tx(
@@ -162,8 +198,10 @@ impl<C: sov_modules_api::Context> Evm<C> {
             &users_txs,
             cfg_env,
             &mut citrea_handler_ext,
-            cumulative_gas_used,
+            prev_gas_used,
+            gas_limit,
         )?;
+        self.update_total_supply(context.active_spec(), bridge_balance, working_set);
 
         // Iterate each evm_txs_recovered and results pair
         // Create a PendingTransaction for each pair
@@ -208,13 +246,76 @@ impl<C: sov_modules_api::Context> Evm<C> {
         }
         Ok(CallResponse::default())
     }
+
+    /// Returns the gas reserved for the system transactions, if the reserve is set and `spec_id` activates it.
+    fn active_system_gas_reserve(
+        &self,
+        spec_id: CitreaSpecId,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Option<u64> {
+        if !citrea_spec_id_reserves_system_gas(spec_id) {
+            return None;
+        }
+        self.system_gas_reserve.get(working_set)
+    }
+
+    /// Returns the balance of the bridge, which holds the cBTC that is not minted
+    fn bridge_balance(&self, working_set: &mut WorkingSet<C::Storage>) -> U256 {
+        self.accounts
+            .get(&BridgeWrapper::address(), working_set)
+            .map(|info| info.balance)
+            .unwrap_or_default()
+    }
+
+    /// Returns the cBTC total supply.
+    /// Until the counter is seeded, this is the cBTC that left the bridge since genesis.
+    pub(crate) fn total_supply(&self, working_set: &mut WorkingSet<C::Storage>) -> U256 {
+        self.total_supply.get(working_set).unwrap_or_else(|| {
+            BRIDGE_GENESIS_BALANCE.saturating_sub(self.bridge_balance(working_set))
+        })
+    }
+
+    /// Counts the cBTC that left the bridge since `bridge_balance_before` as minted,
+    /// and the cBTC sent to it as burned.
+    /// Does nothing before the total supply is tracked by the fork.
+    fn update_total_supply(
+        &self,
+        spec_id: CitreaSpecId,
+        bridge_balance_before: U256,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) {
+        if !citrea_spec_id_tracks_total_supply(spec_id) {
+            return;
+        }
+
+        let bridge_balance_after = self.bridge_balance(working_set);
+        if bridge_balance_after == bridge_balance_before {
+            return;
+        }
+
+        let total_supply = self.total_supply.get(working_set).unwrap_or_else(|| {
+            // first change after the fork, seed the counter from the bridge balance before it
+            BRIDGE_GENESIS_BALANCE.saturating_sub(bridge_balance_before)
+        });
+        let total_supply = if bridge_balance_after < bridge_balance_before {
+            total_supply + (bridge_balance_before - bridge_balance_after)
+        } else {
+            // Withdrawals of cBTC set in genesis can burn more than what was minted
+            total_supply.saturating_sub(bridge_balance_after - bridge_balance_before)
+        };
+        self.total_supply.set(&total_supply, working_set);
+    }
 }
 
 /// Get cfg env for a given block number
 /// Returns correct config depending on spec for given block number
-pub(crate) fn get_cfg_env(cfg: EvmChainConfig, spec_id: SpecId) -> CfgEnvWithHandlerCfg {
-    let mut cfg_env = CfgEnvWithHandlerCfg::new_with_spec_id(CfgEnv::default(), spec_id);
+pub(crate) fn get_cfg_env(cfg: EvmChainConfig, spec_id: CitreaSpecId) -> CfgEnvWithHandlerCfg {
+    let mut cfg_env = CfgEnvWithHandlerCfg::new_with_spec_id(
+        CfgEnv::default(),
+        citrea_spec_id_to_evm_spec_id(spec_id),
+    );
     cfg_env.chain_id = cfg.chain_id;
-    cfg_env.limit_contract_code_size = cfg.limit_contract_code_size;
+    cfg_env.limit_contract_code_size =
+        citrea_spec_id_to_contract_code_size_limit(spec_id).or(cfg.limit_contract_code_size);
     cfg_env
 }
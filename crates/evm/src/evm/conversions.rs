@@ -3,7 +3,9 @@ use alloy_primitives::Bytes as RethBytes;
 use reth_primitives::{
     TransactionSigned, TransactionSignedEcRecovered, TransactionSignedNoHash, KECCAK_EMPTY,
 };
-use revm::primitives::{AccountInfo as ReVmAccountInfo, SpecId, TransactTo, TxEnv, U256};
+use revm::primitives::{
+    AccountInfo as ReVmAccountInfo, AuthorizationList, SpecId, TransactTo, TxEnv, U256,
+};
 
 use super::primitive_types::{RlpEvmTransaction, TransactionSignedAndRecovered};
 use super::AccountInfo;
@@ -79,6 +81,13 @@ pub(crate) fn create_tx_env(tx: &TransactionSignedEcRecovered, spec_id: SpecId)
         tx_env.max_fee_per_blob_gas = tx.max_fee_per_blob_gas().map(U256::from);
     }
 
+    if spec_id >= SpecId::PRAGUE {
+        // EIP-7702 related fields
+        tx_env.authorization_list = tx
+            .authorization_list()
+            .map(|list| AuthorizationList::Signed(list.to_vec()));
+    }
+
     tx_env
 }
 
@@ -1,6 +1,7 @@
 use reth_primitives::TransactionSignedEcRecovered;
 use revm::primitives::{
     BlockEnv, CfgEnvWithHandlerCfg, EVMError, Env, EvmState, ExecutionResult, ResultAndState,
+    SpecId,
 };
 use revm::{self, Context, Database, DatabaseCommit, EvmContext};
 use sov_modules_api::{native_error, native_trace, SoftConfirmationModuleCallError};
@@ -66,6 +67,8 @@ where
 
 /// Will fail on the first error.
 /// Rendering the soft confirmation invalid
+///
+/// `prev_gas_used` of the block's earlier transactions and the gas used by `txs` must fit in `gas_limit`.
 pub(crate) fn execute_multiple_tx<
     DB: Database<Error = DBError> + DatabaseCommit,
     EXT: CitreaExternalExt,
@@ -76,12 +79,13 @@ pub(crate) fn execute_multiple_tx<
     config_env: CfgEnvWithHandlerCfg,
     ext: &mut EXT,
     prev_gas_used: u64,
+    gas_limit: u64,
 ) -> Result<Vec<ExecutionResult>, SoftConfirmationModuleCallError> {
     if txs.is_empty() {
         return Ok(vec![]);
     }
 
-    let block_gas_limit: u64 = block_env.gas_limit.saturating_to();
+    let set_code_enabled = config_env.handler_cfg.spec_id.is_enabled_in(SpecId::PRAGUE);
 
     let mut cumulative_gas_used = prev_gas_used;
 
@@ -107,6 +111,14 @@ pub(crate) fn execute_multiple_tx<
             ));
         }
 
+        // if tx is eip7702 error out before the fork
+        if tx.is_eip7702() && !set_code_enabled {
+            native_error!("EIP-7702 transaction is not supported");
+            return Err(SoftConfirmationModuleCallError::EvmTxTypeNotSupported(
+                "EIP-7702".to_string(),
+            ));
+        }
+
         let result_and_state = evm.transact(tx).map_err(|e| {
             native_error!("Invalid tx {}. Error: {}", tx.hash(), e);
             match e {
@@ -117,13 +129,13 @@ pub(crate) fn execute_multiple_tx<
         })?;
 
         // Check if the transaction used more gas than the available block gas limit
-        if cumulative_gas_used + result_and_state.result.gas_used() > block_gas_limit {
+        if cumulative_gas_used + result_and_state.result.gas_used() > gas_limit {
             native_error!("Gas used exceeds block gas limit");
             return Err(
                 SoftConfirmationModuleCallError::EvmGasUsedExceedsBlockGasLimit {
                     cumulative_gas: cumulative_gas_used,
                     tx_gas_used: result_and_state.result.gas_used(),
-                    block_gas_limit,
+                    block_gas_limit: gas_limit,
                 },
             );
         }
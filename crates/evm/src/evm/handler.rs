@@ -47,6 +47,9 @@ const STORAGE_VALUE_SIZE: usize = 33;
 /// Code key size "Evm/c/" + 1 byte of length + 32 bytes of code hash = 39 bytes
 const CODE_KEY_SIZE: usize = 39;
 
+/// EIP-7702 authorization size: 32 bytes of chain id + 20 bytes of address + 8 bytes of nonce + 65 bytes of signature = 125 bytes
+const AUTHORIZATION_SIZE: usize = 125;
+
 /// We write data to da besides account and code data like block hashes, pending transactions and some other state variables that are in modules: evm, soft_confirmation_rule_enforcer and sov_accounts
 /// The L1 fee overhead is to compensate for the data written to da that is not accounted for in the diff size
 /// It is calculated by measuring the state diff we write to da in a single batch every 10 minutes which is about 300 soft confirmations
@@ -293,6 +296,7 @@ where
             Arc::new(CitreaHandler::<SPEC, EXT, DB>::validate_tx_against_state);
         pre_execution.load_precompiles = Arc::new(CitreaHandler::<SPEC, EXT, DB>::load_precompiles);
         // pre_execution.load_accounts =
+        // pre_execution.apply_eip7702_auth_list = mainnet validates and applies the authorizations
         pre_execution.deduct_caller = Arc::new(CitreaHandler::<SPEC, EXT, DB>::deduct_caller);
         // execution.last_frame_return =
         // execution.call =
@@ -565,6 +569,12 @@ fn calc_diff_size<EXT, SPEC: Spec, DB: Database>(
         diff_size += size_of::<Address>() + size_of::<U256>();
     }
 
+    // EIP-7702 authorizations are applied to the authority accounts before execution, so they are not in the journal
+    // charge for the authorization list bytes instead
+    if let Some(authorization_list) = env.tx.authorization_list.as_ref() {
+        diff_size += authorization_list.len() * AUTHORIZATION_SIZE;
+    }
+
     for (addr, account) in account_changes {
         if account.destroyed && !SPEC::enabled(SpecId::CANCUN) {
             // Each 'delete' key produces a write of 'key' + 1 byte
@@ -23,6 +23,7 @@ pub(crate) mod call;
 #[cfg(all(test, feature = "native"))]
 mod tests;
 
+pub use handler::BROTLI_COMPRESSION_PERCENTAGE;
 pub use primitive_types::RlpEvmTransaction;
 use sov_state::codec::BcsCodec;
 
@@ -34,6 +35,8 @@ pub const BITCOIN_LIGHT_CLIENT_CONTRACT_ADDRESS: Address =
     address!("3100000000000000000000000000000000000001");
 /// Bridge contract address
 pub const BRIDGE_CONTRACT_ADDRESS: Address = address!("3100000000000000000000000000000000000002");
+/// cBTC the bridge contract is funded with in genesis, 21 million cBTC
+pub const BRIDGE_GENESIS_BALANCE: U256 = U256::from_limbs([0x47f6cf7e35000000, 0x115eec, 0, 0]);
 /// Base fee vault address
 pub const BASE_FEE_VAULT: Address = address!("3100000000000000000000000000000000000003");
 /// L1 fee vault address
@@ -1,6 +1,7 @@
 #![allow(missing_docs)]
-use alloy_primitives::{address, Address, Bytes, U256};
+use alloy_primitives::{address, Address, Bytes, B256, U256};
 use alloy_sol_types::{sol, SolCall};
+use sha2::{Digest, Sha256};
 
 // BitcoinLightClient wrapper.
 sol! {
@@ -132,6 +133,23 @@ impl BridgeWrapper {
         func_selector.extend(params);
         func_selector.into()
     }
+
+    /// Return the Bitcoin txid of the move transaction in the deposit params,
+    /// calculated the same way as the Bridge contract does.
+    /// Returns `None` if the params cannot be decoded.
+    pub(crate) fn deposit_txid(params: &[u8]) -> Option<B256> {
+        let move_tp = BridgeContract::depositCall::abi_decode_raw(params, true)
+            .ok()?
+            .moveTp;
+        let preimage = [
+            move_tp.version.as_slice(),
+            &move_tp.vin[..],
+            &move_tp.vout[..],
+            move_tp.locktime.as_slice(),
+        ]
+        .concat();
+        Some(B256::from_slice(&Sha256::digest(Sha256::digest(preimage))))
+    }
 }
 
 sol! {
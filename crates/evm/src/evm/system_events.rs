@@ -18,6 +18,10 @@ pub(crate) enum SystemEvent {
     BitcoinLightClientSetBlockInfo(/*hash*/ [u8; 32], /*merkle root*/ [u8; 32]),
     BridgeInitialize,
     BridgeDeposit(Vec<u8>), // version, flag, vin, vout, witness, locktime, intermediate nodes, block height, index
+    /// A deposit whose txid was already processed. Results in a no-op transaction.
+    BridgeDepositReplayed(/*txid*/ [u8; 32]),
+    /// A deposit whose params could not be decoded. Results in a failed transaction.
+    BridgeDepositMalformed,
 }
 
 fn system_event_to_transaction(event: SystemEvent, nonce: u64, chain_id: u64) -> Transaction {
@@ -62,6 +66,29 @@ fn system_event_to_transaction(event: SystemEvent, nonce: u64, chain_id: u64) ->
             max_fee_per_gas: u64::MAX as u128,
             ..Default::default()
         },
+        // Doesn't touch the bridge, the txid is only kept as input for the record
+        SystemEvent::BridgeDepositReplayed(txid) => TxEip1559 {
+            to: TxKind::Call(SYSTEM_SIGNER),
+            input: txid.into(),
+            nonce,
+            chain_id,
+            value: U256::ZERO,
+            gas_limit: 1_000_000u64,
+            max_fee_per_gas: u64::MAX as u128,
+            ..Default::default()
+        },
+        // A deposit call without params, which the bridge reverts.
+        // The malformed params aren't passed on, as they might not even fit in the gas limit
+        SystemEvent::BridgeDepositMalformed => TxEip1559 {
+            to: TxKind::Call(BridgeWrapper::address()),
+            input: BridgeWrapper::deposit(vec![]),
+            nonce,
+            chain_id,
+            value: U256::ZERO,
+            gas_limit: 1_000_000u64,
+            max_fee_per_gas: u64::MAX as u128,
+            ..Default::default()
+        },
     };
     Transaction::Eip1559(body)
 }
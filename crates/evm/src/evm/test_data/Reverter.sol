@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: GPL-3
+
+pragma solidity ^0.8.0;
+
+// solc --abi --bin  Reverter.sol  -o . --overwrite
+// Reverter.bin is a hand-assembled equivalent of this contract with a minimal dispatcher,
+// so that the exact revert payloads do not depend on the compiler version.
+contract Reverter {
+    error CustomError(uint256 value);
+
+    // Reverts with Error(string)
+    function revertWithReason() pure public {
+        revert("Reverter: reason");
+    }
+
+    // Reverts with CustomError(42)
+    function revertWithCustomError() pure public {
+        revert CustomError(42);
+    }
+
+    // Halts on the designated invalid opcode 0xfe
+    function invalidOpcode() pure public {
+        assembly {
+            invalid()
+        }
+    }
+}
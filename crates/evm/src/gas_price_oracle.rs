@@ -0,0 +1,137 @@
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
+use sov_modules_api::WorkingSet;
+
+use crate::{Evm, SYSTEM_SIGNER};
+
+/// The default maximum number of blocks to use for the gas price oracle.
+pub const MAX_HEADER_HISTORY: u64 = 1024;
+
+/// The default maximum gas price to use for the estimate
+pub const DEFAULT_MAX_PRICE: U256 = U256::from_limbs([500_000_000_000u64, 0, 0, 0]);
+
+/// The default minimum gas price, under which the sample will be ignored
+pub const DEFAULT_IGNORE_PRICE: U256 = U256::from_limbs([2u64, 0, 0, 0]);
+
+/// The default priority fee suggested while there are no recent transactions.
+/// Small enough to not make a difference to price, but also allows bumping tip
+/// of EIP-1559 transactions in times of congestion.
+pub const DEFAULT_PRIORITY_FEE: U256 = U256::from_limbs([100u64, 0, 0, 0]);
+
+/// Settings for the gas price oracle configured by node operators
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GasPriceOracleConfig {
+    /// The number of most recent blocks whose transactions are sampled for the estimate
+    pub blocks: u32,
+
+    /// The percentile of the sampled priority fees to use for the estimate
+    pub percentile: u32,
+
+    /// The maximum number of headers to keep in the cache
+    pub max_header_history: u64,
+
+    /// The maximum number of blocks for estimating gas price
+    pub max_block_history: u64,
+
+    /// The priority fee to use if there are no transactions in the recent blocks
+    pub default: Option<u128>,
+
+    /// The maximum gas price to use for the estimate
+    pub max_price: Option<u128>,
+
+    /// The minimum gas price, under which the sample will be ignored
+    pub ignore_price: Option<u128>,
+}
+
+impl Default for GasPriceOracleConfig {
+    fn default() -> Self {
+        GasPriceOracleConfig {
+            blocks: 20,
+            percentile: 60,
+            max_header_history: MAX_HEADER_HISTORY,
+            max_block_history: MAX_HEADER_HISTORY,
+            default: Some(DEFAULT_PRIORITY_FEE.saturating_to()),
+            max_price: Some(DEFAULT_MAX_PRICE.saturating_to()),
+            ignore_price: Some(DEFAULT_IGNORE_PRICE.saturating_to()),
+        }
+    }
+}
+
+impl GasPriceOracleConfig {
+    /// Creating a new gpo config with blocks, ignoreprice, maxprice and percentile
+    pub fn new(
+        blocks: Option<u32>,
+        ignore_price: Option<u64>,
+        max_price: Option<u64>,
+        percentile: Option<u32>,
+    ) -> Self {
+        let default = Self::default();
+        Self {
+            blocks: blocks.unwrap_or(default.blocks),
+            percentile: percentile.unwrap_or(default.percentile),
+            max_price: max_price.map(u128::from).or(default.max_price),
+            ignore_price: ignore_price.map(u128::from).or(default.ignore_price),
+            ..default
+        }
+    }
+}
+
+impl<C: sov_modules_api::Context> Evm<C> {
+    /// Suggests a priority fee from the effective priority fees paid by the user transactions
+    /// of the last `config.blocks` blocks, taking the configured percentile of them.
+    /// Falls back to the configured default if the window has no transactions to sample.
+    pub fn suggest_priority_fee(
+        &self,
+        config: &GasPriceOracleConfig,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> u128 {
+        let mut accessory_state = working_set.accessory_state();
+        let head = self.blocks.len(&mut accessory_state) as u64;
+
+        let mut tips = Vec::new();
+        for block_number in (0..head).rev().take(config.blocks as usize) {
+            // Stop at pruned blocks, the window is the history that is left
+            let Some(block) = self.blocks.get(block_number as usize, &mut accessory_state) else {
+                break;
+            };
+            let base_fee = block.header.base_fee_per_gas;
+
+            for id in block.transactions {
+                let tx = self
+                    .transactions
+                    .get(id as usize, &mut accessory_state)
+                    .expect("Transaction must be set");
+                if tx.signer == SYSTEM_SIGNER {
+                    continue;
+                }
+                let Some(tip) = tx
+                    .signed_transaction
+                    .transaction
+                    .effective_tip_per_gas(base_fee)
+                else {
+                    continue;
+                };
+                if tip >= config.ignore_price.unwrap_or_default() {
+                    tips.push(tip);
+                }
+            }
+        }
+
+        let mut price = if tips.is_empty() {
+            config
+                .default
+                .unwrap_or(DEFAULT_PRIORITY_FEE.saturating_to())
+        } else {
+            tips.sort_unstable();
+            tips[(tips.len() - 1) * config.percentile.min(100) as usize / 100]
+        };
+
+        // constrain to the max price
+        if let Some(max_price) = config.max_price {
+            price = price.min(max_price);
+        }
+
+        price
+    }
+}
@@ -87,14 +87,18 @@ impl<'de> Deserialize<'de> for AccountData {
                 skip_serializing_if = "HashMap::is_empty"
             )]
             storage: HashMap<U256, U256>,
+            /// Set by genesis exports, contracts start at nonce 1 otherwise
+            #[serde(default)]
+            nonce: Option<u64>,
         }
 
         let helper = AccountDataHelper::deserialize(deserializer)?;
-        let (code_hash, nonce) = if helper.code.is_empty() {
+        let (code_hash, default_nonce) = if helper.code.is_empty() {
             (KECCAK_EMPTY, 0)
         } else {
             (keccak256(&helper.code), 1)
         };
+        let nonce = helper.nonce.unwrap_or(default_nonce);
 
         Ok(AccountData {
             address: helper.address,
@@ -125,6 +129,11 @@ pub struct EvmConfig {
     pub starting_base_fee: u64,
     /// Gas limit for single block
     pub block_gas_limit: u64,
+    /// Gas of the block gas limit reserved for the system transactions.
+    /// If set, once activated by a fork, user transactions can only use the rest of the block gas limit,
+    /// and the system transactions of a block must fit in the reserve.
+    #[serde(default)]
+    pub system_gas_reserve: u64,
     /// Base fee params.
     pub base_fee_params: BaseFeeParams,
     /// Timestamp of the genesis block.
@@ -135,6 +144,10 @@ pub struct EvmConfig {
     pub nonce: u64,
     /// Difficulty of the genesis block.
     pub difficulty: U256,
+    /// Whether to index the logs by address to speed up log queries filtering on addresses.
+    /// The index is only maintained by native nodes and is not part of the state.
+    #[serde(default)]
+    pub index_logs_by_address: bool,
 }
 
 #[cfg(all(test, feature = "native"))]
@@ -148,11 +161,13 @@ impl Default for EvmConfig {
             coinbase: Address::ZERO,
             starting_base_fee: reth_primitives::constants::EIP1559_INITIAL_BASE_FEE,
             block_gas_limit: reth_primitives::constants::ETHEREUM_BLOCK_GAS_LIMIT,
+            system_gas_reserve: 0,
             base_fee_params: BaseFeeParams::ethereum(),
             timestamp: 0,
             extra_data: Bytes::default(),
             nonce: 0,
             difficulty: U256::ZERO,
+            index_logs_by_address: false,
         }
     }
 }
@@ -207,6 +222,18 @@ impl<C: sov_modules_api::Context> Evm<C> {
 
         self.cfg.set(&chain_cfg, working_set);
 
+        assert!(
+            config.system_gas_reserve <= config.block_gas_limit,
+            "System gas reserve {} exceeds the block gas limit {}",
+            config.system_gas_reserve,
+            config.block_gas_limit
+        );
+        // Only set when something is reserved, so the genesis state root does not change otherwise
+        if config.system_gas_reserve > 0 {
+            self.system_gas_reserve
+                .set(&config.system_gas_reserve, working_set);
+        }
+
         let header = crate::primitive_types::DoNotUseHeader {
             parent_hash: B256::default(),
             ommers_hash: EMPTY_OMMER_ROOT_HASH,
@@ -247,8 +274,12 @@ impl<C: sov_modules_api::Context> Evm<C> {
         self.head.set(&block, working_set);
 
         #[cfg(feature = "native")]
-        self.pending_head
-            .set(&block.into(), &mut working_set.accessory_state());
+        {
+            let mut accessory_state = working_set.accessory_state();
+            self.pending_head.set(&block.into(), &mut accessory_state);
+            self.log_index_enabled
+                .set(&config.index_logs_by_address, &mut accessory_state);
+        }
     }
 }
 
@@ -0,0 +1,149 @@
+use std::io::Write;
+
+use alloy_primitives::{Address, Bytes, U256};
+use anyhow::Context as _;
+use sov_modules_api::{StateKeys, StateMapAccessor, StateValueAccessor, WorkingSet};
+use sov_state::codec::BcsCodec;
+use sov_state::storage::{StateKeyCodec, StateValueCodec};
+
+use crate::evm::primitive_types::{Block, DoNotUseHeader};
+use crate::evm::{AccountInfo, DbAccount};
+use crate::{Evm, EvmConfig};
+
+impl<C: sov_modules_api::Context> Evm<C> {
+    /// Writes the config recreating the accounts of the working set at genesis to `writer`,
+    /// as the JSON of an [`EvmConfig`]. Returns the chain id.
+    ///
+    /// Accounts and their storage slots are written as they are read,
+    /// so the storage is never held in memory.
+    pub fn export_genesis_config(
+        &self,
+        keys: &StateKeys,
+        mut writer: impl Write,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> anyhow::Result<u64> {
+        let cfg = self
+            .cfg
+            .get(working_set)
+            .context("EVM chain config is not set")?;
+        let head: Block<DoNotUseHeader> = match self.head_rlp.get(working_set) {
+            Some(block) => block.into(),
+            None => self.head.get(working_set).context("EVM head is not set")?,
+        };
+
+        let config = EvmConfig {
+            data: vec![],
+            chain_id: cfg.chain_id,
+            limit_contract_code_size: cfg.limit_contract_code_size,
+            coinbase: cfg.coinbase,
+            starting_base_fee: head
+                .header
+                .base_fee_per_gas
+                .context("EVM head has no base fee")?,
+            block_gas_limit: cfg.block_gas_limit,
+            system_gas_reserve: self.get_system_gas_reserve(working_set),
+            base_fee_params: cfg.base_fee_params,
+            timestamp: head.header.timestamp,
+            extra_data: head.header.extra_data,
+            nonce: head.header.nonce,
+            difficulty: head.header.difficulty,
+            index_logs_by_address: self.is_log_index_enabled(working_set),
+        };
+        let serde_json::Value::Object(mut fields) = serde_json::to_value(&config)? else {
+            unreachable!("EvmConfig is serialized as an object");
+        };
+        // The accounts are streamed after the other fields
+        fields.remove("data");
+
+        writer.write_all(b"{")?;
+        for (name, value) in &fields {
+            serde_json::to_writer(&mut writer, name)?;
+            writer.write_all(b":")?;
+            serde_json::to_writer(&mut writer, value)?;
+            writer.write_all(b",")?;
+        }
+        writer.write_all(b"\"data\":[")?;
+
+        let prefix: &[u8] = self.accounts.prefix().as_aligned_vec().as_ref();
+        let key_len = prefix.len() + BcsCodec.encode_key(&Address::ZERO).len();
+        let mut first = true;
+        for key in keys(prefix, key_len)? {
+            let address: Address = BcsCodec.try_decode_value(&key?[prefix.len()..])?;
+            // Accounts deleted before the exported height
+            let Some(info) = self.accounts.get(&address, working_set) else {
+                continue;
+            };
+
+            if !first {
+                writer.write_all(b",")?;
+            }
+            first = false;
+            self.export_account(address, info, keys, &mut writer, working_set)
+                .with_context(|| format!("Failed to export account {}", address))?;
+        }
+
+        writer.write_all(b"]}")?;
+        writer.flush()?;
+        Ok(cfg.chain_id)
+    }
+
+    fn export_account(
+        &self,
+        address: Address,
+        info: AccountInfo,
+        keys: &StateKeys,
+        mut writer: impl Write,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> anyhow::Result<()> {
+        let code = match info.code_hash {
+            Some(code_hash) => {
+                // Contracts deployed before Fork1 may not be moved to the offchain code yet
+                let offchain_code = self
+                    .offchain_code
+                    .get(&code_hash, &mut working_set.offchain_state());
+                offchain_code
+                    .or_else(|| self.code.get(&code_hash, working_set))
+                    .context("Code of the account is missing")?
+                    .original_bytes()
+            }
+            None => Bytes::new(),
+        };
+
+        writer.write_all(b"{\"address\":")?;
+        serde_json::to_writer(&mut writer, &address)?;
+        writer.write_all(b",\"balance\":")?;
+        serde_json::to_writer(&mut writer, &info.balance)?;
+        writer.write_all(b",\"nonce\":")?;
+        serde_json::to_writer(&mut writer, &info.nonce)?;
+        writer.write_all(b",\"code\":")?;
+        serde_json::to_writer(&mut writer, &code)?;
+        writer.write_all(b",\"storage\":{")?;
+
+        let db_account = DbAccount::new(address);
+        let prefix: &[u8] = db_account.storage.prefix().as_aligned_vec().as_ref();
+        let key_len = prefix.len() + BcsCodec.encode_key(&U256::ZERO).len();
+        let mut first = true;
+        for key in keys(prefix, key_len)? {
+            let slot: U256 = BcsCodec.try_decode_value(&key?[prefix.len()..])?;
+            // Cleared slots read as zero, they are left out
+            let value = db_account
+                .storage
+                .get(&slot, working_set)
+                .unwrap_or_default();
+            if value.is_zero() {
+                continue;
+            }
+
+            if !first {
+                writer.write_all(b",")?;
+            }
+            first = false;
+            serde_json::to_writer(&mut writer, &slot)?;
+            writer.write_all(b":")?;
+            serde_json::to_writer(&mut writer, &value)?;
+        }
+
+        writer.write_all(b"}}")?;
+        Ok(())
+    }
+}
@@ -1,18 +1,29 @@
+use std::ops::RangeInclusive;
+
 use alloy_consensus::Header as AlloyHeader;
 use alloy_primitives::{Bloom, Bytes, B256, B64, U256};
 use citrea_primitives::basefee::calculate_next_block_base_fee;
 use revm::primitives::{BlobExcessGasAndPrice, BlockEnv, SpecId};
 use sov_modules_api::hooks::HookSoftConfirmationInfo;
 use sov_modules_api::prelude::*;
-use sov_modules_api::{AccessoryWorkingSet, WorkingSet};
+use sov_modules_api::{native_error, native_warn, AccessoryWorkingSet, WorkingSet};
 use sov_rollup_interface::spec::SpecId as CitreaSpecId;
 use sov_state::Storage;
 #[cfg(feature = "native")]
 use tracing::instrument;
 
 use crate::evm::primitive_types::Block;
+use crate::evm::system_contracts::BridgeWrapper;
 use crate::evm::system_events::SystemEvent;
-use crate::{citrea_spec_id_to_evm_spec_id, Evm};
+use crate::{citrea_spec_id_checks_deposits, citrea_spec_id_to_evm_spec_id, Evm};
+
+/// Number of the most recent block hashes available to the `BLOCKHASH` opcode
+pub(crate) const BLOCK_HASH_WINDOW_SIZE: u64 = 256;
+
+/// Returns the inclusive range of the blocks whose hashes are available to the block `block_number`
+pub(crate) fn block_hash_window(block_number: u64) -> RangeInclusive<u64> {
+    block_number.saturating_sub(BLOCK_HASH_WINDOW_SIZE)..=block_number.saturating_sub(1)
+}
 
 impl<C: sov_modules_api::Context> Evm<C>
 where
@@ -62,13 +73,10 @@ where
 
         let sealed_parent_block = parent_block.clone().seal();
         let last_block_hash = sealed_parent_block.header.hash();
+        let block_number = parent_block.header.number + 1;
 
         // since we know the previous state root only here, we can set the last block hash
-        self.latest_block_hashes.set(
-            &U256::from(parent_block.header.number),
-            &last_block_hash,
-            working_set,
-        );
+        self.update_latest_block_hashes(block_number, last_block_hash, working_set);
 
         // populate system events
         let mut system_events = vec![];
@@ -92,12 +100,14 @@ where
             system_events.push(SystemEvent::BridgeInitialize);
         }
 
-        soft_confirmation_info
-            .deposit_data
-            .iter()
-            .for_each(|params| {
+        let checks_deposits = citrea_spec_id_checks_deposits(current_spec);
+        for params in soft_confirmation_info.deposit_data.iter() {
+            if checks_deposits {
+                system_events.push(self.deposit_system_event(params, block_number, working_set));
+            } else {
                 system_events.push(SystemEvent::BridgeDeposit(params.clone()));
-            });
+            }
+        }
 
         let cfg = self
             .cfg
@@ -119,7 +129,7 @@ where
         };
 
         let new_pending_env = BlockEnv {
-            number: U256::from(parent_block.header.number + 1),
+            number: U256::from(block_number),
             coinbase: cfg.coinbase,
             timestamp: U256::from(soft_confirmation_info.timestamp),
             prevrandao: Some(soft_confirmation_info.da_slot_hash.into()),
@@ -138,27 +148,42 @@ where
                 system_events,
                 soft_confirmation_info.l1_fee_rate(),
                 cfg,
-                new_pending_env.clone(),
-                active_evm_spec,
+                new_pending_env,
+                soft_confirmation_info.current_spec,
                 working_set,
             );
         }
 
-        // if height > 256, start removing the oldest block
-        // keeping only 256 most recent blocks
-        // this first happens on txs in block 257
-        // remove block 0, keep blocks 1-256
-        // then on block 258
-        // remove block 1, keep blocks 2-257
-        if new_pending_env.number > U256::from(256) {
-            self.latest_block_hashes
-                .remove(&(new_pending_env.number - U256::from(257)), working_set);
-        }
-
         self.last_l1_hash
             .set(&soft_confirmation_info.da_slot_hash.into(), working_set);
     }
 
+    /// Checks the deposit params against the already processed deposits.
+    /// Only new deposits are passed to the bridge, and their txids are recorded.
+    fn deposit_system_event(
+        &self,
+        params: &[u8],
+        block_number: u64,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> SystemEvent {
+        let Some(txid) = BridgeWrapper::deposit_txid(params) else {
+            native_warn!("Malformed deposit data of {} bytes, skipping", params.len());
+            return SystemEvent::BridgeDepositMalformed;
+        };
+
+        if let Some(processed_in) = self.processed_deposits.get(&txid, working_set) {
+            native_warn!(
+                "Deposit with txid {} was already processed in block {}, skipping",
+                txid,
+                processed_in
+            );
+            return SystemEvent::BridgeDepositReplayed(txid.0);
+        }
+
+        self.processed_deposits.set(&txid, &block_number, working_set);
+        SystemEvent::BridgeDeposit(params.to_vec())
+    }
+
     /// Logic executed at the end of the slot. Here, we generate an authenticated block and set it as the new head of the chain.
     /// It's important to note that the state root hash is not known at this moment, so we postpone setting this field until the begin_slot_hook of the next slot.
     #[cfg_attr(feature = "native", instrument(level = "trace", skip_all, ret))]
@@ -303,6 +328,13 @@ where
 
                 tx_index += 1
             }
+
+            self.index_block_logs(
+                block.header.number,
+                start_tx_index,
+                self.pending_transactions.iter().map(|tx| &tx.receipt),
+                &mut accessory_state,
+            );
             self.pending_transactions.clear();
         }
     }
@@ -357,3 +389,43 @@ where
         }
     }
 }
+
+impl<C: sov_modules_api::Context> Evm<C> {
+    /// Sets the hash of the parent of `block_number`, and evicts the hashes below the window
+    /// of `block_number`.
+    ///
+    /// Normally the only hash to evict is the one leaving the window. Stale hashes left below it
+    /// are found by walking down from the window until a missing hash, and evicted as well.
+    pub(crate) fn update_latest_block_hashes(
+        &self,
+        block_number: u64,
+        parent_hash: B256,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) {
+        let window = block_hash_window(block_number);
+        self.latest_block_hashes
+            .set(&U256::from(*window.end()), &parent_hash, working_set);
+
+        let mut evicted = 0;
+        for number in (0..*window.start()).rev() {
+            if self
+                .latest_block_hashes
+                .remove(&U256::from(number), working_set)
+                .is_none()
+            {
+                break;
+            }
+            evicted += 1;
+        }
+
+        // A single hash leaves the window per block, evicting more means
+        // the map held more than `BLOCK_HASH_WINDOW_SIZE` hashes
+        if evicted > 1 {
+            native_error!(
+                "Block hash window of block {} held {} stale hashes, evicted them",
+                block_number,
+                evicted - 1
+            );
+        }
+    }
+}
@@ -2,15 +2,25 @@
 #![doc = include_str!("../README.md")]
 mod call;
 mod evm;
+#[cfg(feature = "native")]
+mod gas_price_oracle;
 mod genesis;
+#[cfg(feature = "native")]
+mod genesis_export;
 mod hooks;
 #[cfg(feature = "native")]
+mod log_index;
+#[cfg(feature = "native")]
 mod provider_functions;
 
 use alloy_rlp::{RlpDecodable, RlpEncodable};
 pub use call::*;
 pub use evm::*;
+#[cfg(feature = "native")]
+pub use gas_price_oracle::*;
 pub use genesis::*;
+#[cfg(feature = "native")]
+pub use log_index::{LogLocation, LOG_INDEX_BUCKET_SIZE};
 pub use system_events::SYSTEM_SIGNER;
 
 #[cfg(feature = "native")]
@@ -124,6 +134,23 @@ pub struct Evm<C: sov_modules_api::Context> {
     #[state(rename = "h")]
     pub(crate) latest_block_hashes: sov_modules_api::StateMap<U256, B256, BcsCodec>,
 
+    /// Bitcoin txids of the processed bridge deposits, mapped to the L2 block number they were processed in.
+    /// Used to reject deposits that are sent more than once. Only recorded from Fork2 on.
+    #[state(rename = "d")]
+    pub(crate) processed_deposits: sov_modules_api::StateMap<B256, u64, BcsCodec>,
+
+    /// cBTC minted by the bridge deposits, minus the cBTC burned by withdrawals to the bridge.
+    /// Updated with the change of the bridge balance by the system transactions and the user transactions.
+    /// Balances set in genesis are not counted.
+    /// Only tracked from Fork2 on, seeded with the cBTC that left the bridge before.
+    #[state(rename = "s")]
+    pub(crate) total_supply: sov_modules_api::StateValue<U256, BcsCodec>,
+
+    /// Gas of the block gas limit reserved for the system transactions, set in genesis.
+    /// Not set when nothing is reserved.
+    #[state(rename = "g")]
+    pub(crate) system_gas_reserve: sov_modules_api::StateValue<u64, BcsCodec>,
+
     /// Used only by the RPC: This represents the head of the chain and is set in two distinct stages:
     /// 1. `end_slot_hook`: the pending head is populated with data from pending_transactions.
     /// 2. `finalize_hook` the `root_hash` is populated.
@@ -158,6 +185,21 @@ pub struct Evm<C: sov_modules_api::Context> {
     #[cfg(feature = "native")]
     #[state]
     pub(crate) receipts: sov_modules_api::AccessoryStateVec<Receipt, RlpCodec>,
+
+    /// Used only by the RPC: Whether the logs are indexed by address, set in genesis.
+    #[cfg(feature = "native")]
+    #[state]
+    pub(crate) log_index_enabled: sov_modules_api::AccessoryStateValue<bool, BcsCodec>,
+
+    /// Used only by the RPC: (address, block number / LOG_INDEX_BUCKET_SIZE) => locations of the logs
+    /// emitted by the address in these blocks. Only maintained if `log_index_enabled` is set.
+    #[cfg(feature = "native")]
+    #[state]
+    pub(crate) logs_by_address: sov_modules_api::AccessoryStateMap<
+        (Address, u64),
+        Vec<log_index::LogLocation>,
+        BcsCodec,
+    >,
 }
 
 impl<C: sov_modules_api::Context> sov_modules_api::Module for Evm<C> {
@@ -201,7 +243,49 @@ impl<C: sov_modules_api::Context> Evm<C> {
 const fn citrea_spec_id_to_evm_spec_id(spec_id: CitreaSpecId) -> EvmSpecId {
     match spec_id {
         CitreaSpecId::Genesis => EvmSpecId::SHANGHAI,
-        // Any other citrea spec id mapped to cancun
-        _ => EvmSpecId::CANCUN,
+        CitreaSpecId::Fork1 => EvmSpecId::CANCUN,
+        // Any later citrea spec id mapped to prague, activating EIP-7702
+        #[allow(unreachable_patterns)]
+        _ => EvmSpecId::PRAGUE,
+    }
+}
+
+/// Contract code size limit a fork sets over `limit_contract_code_size` of the chain config.
+/// `None` keeps the configured limit, a fork raising the limit returns it here.
+const fn citrea_spec_id_to_contract_code_size_limit(spec_id: CitreaSpecId) -> Option<usize> {
+    match spec_id {
+        CitreaSpecId::Genesis | CitreaSpecId::Fork1 => None,
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Whether a fork rejects replayed and malformed bridge deposits and records the processed ones.
+/// Before, all deposit data is passed to the bridge as it is.
+const fn citrea_spec_id_checks_deposits(spec_id: CitreaSpecId) -> bool {
+    match spec_id {
+        CitreaSpecId::Genesis | CitreaSpecId::Fork1 => false,
+        #[allow(unreachable_patterns)]
+        _ => true,
+    }
+}
+
+/// Whether a fork tracks the cBTC total supply.
+/// The counter is seeded from the bridge balance in the first block that changes it.
+const fn citrea_spec_id_tracks_total_supply(spec_id: CitreaSpecId) -> bool {
+    match spec_id {
+        CitreaSpecId::Genesis | CitreaSpecId::Fork1 => false,
+        #[allow(unreachable_patterns)]
+        _ => true,
+    }
+}
+
+/// Whether a fork reserves the system gas reserve of the block gas limit for the system transactions.
+/// Before, user transactions may use all of the block gas limit left by the system transactions.
+const fn citrea_spec_id_reserves_system_gas(spec_id: CitreaSpecId) -> bool {
+    match spec_id {
+        CitreaSpecId::Genesis | CitreaSpecId::Fork1 => false,
+        #[allow(unreachable_patterns)]
+        _ => true,
     }
 }
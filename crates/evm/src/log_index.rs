@@ -0,0 +1,135 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{AccessoryWorkingSet, StateMapAccessor, StateValueAccessor, WorkingSet};
+
+use crate::evm::primitive_types::Receipt;
+use crate::{Evm, FilterSet};
+
+/// Number of blocks covered by a single entry of the log index of an address.
+pub const LOG_INDEX_BUCKET_SIZE: u64 = 100;
+
+/// Position of a log in the chain, as recorded in the log index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogLocation {
+    /// Number of the block the log was emitted in.
+    pub block_number: u64,
+    /// Index of the transaction among all transactions of the chain.
+    pub tx_index: u64,
+    /// Index of the log in its block.
+    pub log_index: u64,
+}
+
+impl<C: sov_modules_api::Context> Evm<C> {
+    /// Returns whether the logs are indexed by address, which is set in the genesis config.
+    pub(crate) fn is_log_index_enabled(&self, working_set: &mut WorkingSet<C::Storage>) -> bool {
+        self.log_index_enabled
+            .get(&mut working_set.accessory_state())
+            .unwrap_or_default()
+    }
+
+    /// Adds the logs of a new block to the index of their emitting addresses.
+    /// Transactions of the block start at `start_tx_index`.
+    pub(crate) fn index_block_logs<'a>(
+        &self,
+        block_number: u64,
+        start_tx_index: u64,
+        receipts: impl Iterator<Item = &'a Receipt>,
+        accessory_state: &mut AccessoryWorkingSet<C::Storage>,
+    ) {
+        if !self
+            .log_index_enabled
+            .get(accessory_state)
+            .unwrap_or_default()
+        {
+            return;
+        }
+
+        // every address is updated once per block
+        let mut block_logs: BTreeMap<Address, Vec<LogLocation>> = BTreeMap::new();
+        for (tx_index, receipt) in (start_tx_index..).zip(receipts) {
+            for (log_index, log) in (receipt.log_index_start..).zip(&receipt.receipt.logs) {
+                block_logs
+                    .entry(log.address)
+                    .or_default()
+                    .push(LogLocation {
+                        block_number,
+                        tx_index,
+                        log_index,
+                    });
+            }
+        }
+
+        let bucket = block_number / LOG_INDEX_BUCKET_SIZE;
+        for (address, mut locations) in block_logs {
+            let key = (address, bucket);
+            let mut indexed = self
+                .logs_by_address
+                .get(&key, accessory_state)
+                .unwrap_or_default();
+            indexed.append(&mut locations);
+            self.logs_by_address.set(&key, &indexed, accessory_state);
+        }
+    }
+
+    /// Removes the logs of a pruned block from the index.
+    pub(crate) fn prune_block_log_index<'a>(
+        &self,
+        block_number: u64,
+        receipts: impl Iterator<Item = &'a Receipt>,
+        accessory_state: &mut AccessoryWorkingSet<C::Storage>,
+    ) {
+        let addresses: BTreeSet<Address> = receipts
+            .flat_map(|receipt| receipt.receipt.logs.iter().map(|log| log.address))
+            .collect();
+
+        let bucket = block_number / LOG_INDEX_BUCKET_SIZE;
+        for address in addresses {
+            let key = (address, bucket);
+            let Some(mut indexed) = self.logs_by_address.get(&key, accessory_state) else {
+                continue;
+            };
+            indexed.retain(|location| location.block_number != block_number);
+            if indexed.is_empty() {
+                self.logs_by_address.delete(&key, accessory_state);
+            } else {
+                self.logs_by_address.set(&key, &indexed, accessory_state);
+            }
+        }
+    }
+
+    /// Returns the blocks in the given _inclusive_ range with logs of the given addresses.
+    /// Returns `None` if the logs are not indexed or no address is given,
+    /// in which case every block of the range has to be checked.
+    pub(crate) fn indexed_log_blocks(
+        &self,
+        addresses: &FilterSet<Address>,
+        from_block_number: u64,
+        to_block_number: u64,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Option<BTreeSet<u64>> {
+        if addresses.is_empty() || !self.is_log_index_enabled(working_set) {
+            return None;
+        }
+
+        let mut accessory_state = working_set.accessory_state();
+        let buckets =
+            from_block_number / LOG_INDEX_BUCKET_SIZE..=to_block_number / LOG_INDEX_BUCKET_SIZE;
+        let mut block_numbers = BTreeSet::new();
+        for address in addresses.0.iter() {
+            for bucket in buckets.clone() {
+                let Some(indexed) = self
+                    .logs_by_address
+                    .get(&(*address, bucket), &mut accessory_state)
+                else {
+                    continue;
+                };
+                block_numbers.extend(indexed.iter().map(|location| location.block_number).filter(
+                    |block_number| (from_block_number..=to_block_number).contains(block_number),
+                ));
+            }
+        }
+        Some(block_numbers)
+    }
+}
@@ -1,7 +1,10 @@
-use alloy_primitives::Address;
-use reth_primitives::{Account, SealedHeader};
-use sov_modules_api::{StateMapAccessor, StateVecAccessor, WorkingSet};
+use std::ops::RangeInclusive;
 
+use alloy_primitives::{Address, U256};
+use reth_primitives::{Account, SealedHeader, TransactionSigned};
+use sov_modules_api::{StateMapAccessor, StateValueAccessor, StateVecAccessor, WorkingSet};
+
+use crate::evm::DbAccount;
 use crate::Evm;
 
 impl<C: sov_modules_api::Context> Evm<C> {
@@ -19,6 +22,23 @@ impl<C: sov_modules_api::Context> Evm<C> {
         )
     }
 
+    /// Returns the value of a storage slot of the account at the given address,
+    /// zero if the account or the slot does not exist.
+    pub fn storage_value(
+        &self,
+        address: &Address,
+        index: U256,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> U256 {
+        if self.accounts.get(address, working_set).is_none() {
+            return U256::ZERO;
+        }
+        DbAccount::new(*address)
+            .storage
+            .get(&index, working_set)
+            .unwrap_or_default()
+    }
+
     /// Returns the sealed head block.
     pub fn last_sealed_header(&self, working_set: &mut WorkingSet<C::Storage>) -> SealedHeader {
         self.blocks
@@ -26,4 +46,100 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .unwrap()
             .header
     }
+
+    /// Returns the signed transactions of the block, empty if the block does not exist or was pruned.
+    pub fn block_transactions(
+        &self,
+        block_number: u64,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Vec<TransactionSigned> {
+        let mut accessory_state = working_set.accessory_state();
+        let Some(block) = self.blocks.get(block_number as usize, &mut accessory_state) else {
+            return vec![];
+        };
+
+        block
+            .transactions
+            .filter_map(|tx_number| {
+                self.transactions
+                    .get(tx_number as usize, &mut accessory_state)
+                    .map(|tx| tx.signed_transaction)
+            })
+            .collect()
+    }
+
+    /// Whether the given block is the head both in the state and in the sealed blocks, with no
+    /// pending head left behind. Only then the next block can be built on top of it, so this
+    /// must hold after the state and the accessory state are rolled back to the block.
+    pub fn is_head_at(&self, block_number: u64, working_set: &mut WorkingSet<C::Storage>) -> bool {
+        let head_number = match self.head_rlp.get(working_set) {
+            Some(block) => block.header.number,
+            None => match self.head.get(working_set) {
+                Some(block) => block.header.number,
+                None => return false,
+            },
+        };
+
+        let mut accessory_state = working_set.accessory_state();
+        let sealed_head_number = (self.blocks.len(&mut accessory_state) as u64).checked_sub(1);
+
+        head_number == block_number
+            && sealed_head_number == Some(block_number)
+            && self.pending_head.get(&mut accessory_state).is_none()
+    }
+
+    /// Deletes the accessory data of the blocks in the given range: the blocks with their
+    /// block hash entries and their transactions with the transaction hash entries and receipts.
+    /// Logs of the blocks are removed from the log index if it is enabled.
+    /// Lengths of the vectors are kept, so the retained entries keep their indices.
+    /// The head block is never pruned.
+    pub fn prune_accessory_state(
+        &self,
+        blocks: RangeInclusive<u64>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) {
+        let mut accessory_state = working_set.accessory_state();
+        let head_block_number = (self.blocks.len(&mut accessory_state) as u64).saturating_sub(1);
+
+        for block_number in blocks.take_while(|block_number| *block_number < head_block_number) {
+            // Already pruned
+            let Some(block) = self.blocks.get(block_number as usize, &mut accessory_state) else {
+                continue;
+            };
+
+            if self
+                .log_index_enabled
+                .get(&mut accessory_state)
+                .unwrap_or_default()
+            {
+                let receipts: Vec<_> = block
+                    .transactions
+                    .clone()
+                    .filter_map(|tx_number| {
+                        self.receipts.get(tx_number as usize, &mut accessory_state)
+                    })
+                    .collect();
+                self.prune_block_log_index(block_number, receipts.iter(), &mut accessory_state);
+            }
+
+            for tx_number in block.transactions.clone() {
+                if let Some(tx) = self
+                    .transactions
+                    .get(tx_number as usize, &mut accessory_state)
+                {
+                    self.transaction_hashes
+                        .delete(&tx.signed_transaction.hash, &mut accessory_state);
+                }
+                self.transactions
+                    .delete(tx_number as usize, &mut accessory_state);
+                self.receipts
+                    .delete(tx_number as usize, &mut accessory_state);
+            }
+
+            self.block_hashes
+                .delete(&block.header.hash(), &mut accessory_state);
+            self.blocks
+                .delete(block_number as usize, &mut accessory_state);
+        }
+    }
 }
@@ -1,37 +1,42 @@
 use std::collections::BTreeMap;
 use std::ops::{Range, RangeInclusive};
+use std::time::Instant;
 
 use alloy_consensus::Eip658Value;
+use alloy_eips::eip1559::BaseFeeParams;
 use alloy_eips::eip2930::AccessListWithGasUsed;
+use alloy_eips::RpcBlockHash;
 use alloy_network::AnyNetwork;
 use alloy_primitives::TxKind::{Call, Create};
-use alloy_primitives::{Address, Bytes, Uint, B256, U256, U64};
+use alloy_primitives::{Address, Bytes, Uint, B256, U128, U256, U64};
 use alloy_rlp::Encodable;
 use alloy_rpc_types::state::StateOverride;
 use alloy_rpc_types::{
-    AnyNetworkBlock, AnyReceiptEnvelope, AnyTransactionReceipt, BlockOverrides, Log,
-    ReceiptWithBloom, TransactionInfo, TransactionReceipt,
+    AnyNetworkBlock, AnyReceiptEnvelope, AnyTransactionReceipt, BlockOverrides,
+    EIP1186AccountProofResponse, EIP1186StorageProof, Log, ReceiptWithBloom, TransactionInfo,
+    TransactionReceipt,
 };
 use alloy_rpc_types_eth::transaction::TransactionRequest;
 use alloy_rpc_types_eth::Block as AlloyRpcBlock;
 use alloy_rpc_types_trace::geth::{GethDebugTracingOptions, TraceResult};
-use alloy_serde::OtherFields;
+use alloy_serde::{JsonStorageKey, OtherFields};
 use citrea_primitives::basefee::calculate_next_block_base_fee;
 use citrea_primitives::forks::fork_from_block_number;
+use itertools::Either;
 use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::ErrorObjectOwned;
 use reth_primitives::{
     Block, BlockBody, BlockId, BlockNumberOrTag, SealedHeader, TransactionSignedEcRecovered,
+    KECCAK_EMPTY,
 };
 use reth_provider::ProviderError;
 use reth_rpc::eth::EthTxBuilder;
 use reth_rpc_eth_api::types::RpcTransaction;
-use reth_rpc_eth_types::error::{
-    ensure_success, EthApiError, EthResult, RevertError, RpcInvalidTransactionError,
-};
+use reth_rpc_eth_types::error::{EthApiError, EthResult, RpcInvalidTransactionError};
 use reth_rpc_types_compat::block::from_primitive_with_hash;
 use revm::primitives::{
     BlobExcessGasAndPrice, BlockEnv, CfgEnvWithHandlerCfg, EVMError, ExecutionResult, HaltReason,
-    InvalidTransaction, SpecId, TransactTo,
+    InvalidTransaction, ResultAndState, SpecId, TransactTo, MAX_CODE_SIZE,
 };
 use revm::{Database, DatabaseCommit};
 use revm_inspectors::access_list::AccessListInspector;
@@ -40,27 +45,43 @@ use serde::{Deserialize, Serialize};
 use sov_modules_api::macros::rpc_gen;
 use sov_modules_api::prelude::*;
 use sov_modules_api::WorkingSet;
+use sov_state::storage::{NativeStorage, StateValueCodec, StorageKey, StorageProof};
 
 use crate::call::get_cfg_env;
 use crate::conversions::{create_tx_env, sealed_block_to_block_env};
 use crate::evm::call::{create_txn_env, prepare_call_env};
 use crate::evm::db::EvmDb;
 use crate::evm::primitive_types::{Receipt, SealedBlock, TransactionSignedAndRecovered};
-use crate::evm::DbAccount;
-use crate::handler::{diff_size_send_eth_eoa, TxInfo};
+use crate::evm::{AccountInfo, DbAccount};
+use crate::handler::{diff_size_send_eth_eoa, TxInfo, L1_FEE_OVERHEAD};
 use crate::rpc_helpers::*;
 use crate::{
-    citrea_spec_id_to_evm_spec_id, BloomFilter, Evm, EvmChainConfig, FilterBlockOption, FilterError,
+    citrea_spec_id_to_evm_spec_id, BloomFilter, Evm, EvmChainConfig, FilterBlockOption,
+    FilterError, BASE_FEE_VAULT, L1_FEE_VAULT, PRIORITY_FEE_VAULT, SYSTEM_SIGNER,
 };
 /// Gas per transaction not creating a contract.
 pub const MIN_TRANSACTION_GAS: u64 = 21_000u64;
 
+/// Maximum number of blocks that can be queried in a single fee history request.
+pub const MAX_FEE_HISTORY_BLOCK_COUNT: u64 = 1024;
+
+/// Maximum number of calls in a single `citrea_callBundle` request.
+pub const MAX_CALL_BUNDLE_SIZE: usize = 100;
+
 /// https://github.com/paradigmxyz/reth/pull/7133/files
 /// Allowed error ratio for gas estimation
 /// Taken from Geth's implementation in order to pass the hive tests
 /// <https://github.com/ethereum/go-ethereum/blob/a5a4fa7032bb248f5a7c40f4e8df2b131c4186a4/internal/ethapi/api.go#L56>
 const ESTIMATE_GAS_ERROR_RATIO: f64 = 0.015;
 
+#[cfg(test)]
+thread_local! {
+    /// Number of blocks whose receipts were read while serving log queries.
+    pub(crate) static SCANNED_LOG_BLOCKS: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+    /// Number of blocks whose blooms were checked while serving log queries.
+    pub(crate) static CHECKED_LOG_BLOCKS: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
 /// The result of gas/diffsize estimation.
 /// This struct holds estimated gas and l1_fee_overhead.
 /// This is very useful for users to test their balance after calling to `eth_estimateGas`
@@ -96,6 +117,84 @@ pub struct EstimatedDiffSize {
     pub l1_diff_size: U64,
 }
 
+/// Result of fee estimation.
+/// Splits the cost of a transaction into the evm gas and the L1 fee charged after execution,
+/// so that users can check upfront whether they can afford the transaction.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimatedFee {
+    /// Gas used.
+    pub gas: U64,
+    /// Gas price the evm gas is paid with.
+    /// Max fee per gas or gas price of the request, base fee of the block otherwise.
+    pub gas_price: U256,
+    /// L1 fee rate the L1 fee is calculated with.
+    pub l1_fee_rate: U128,
+    /// Diff size, after the compression discount.
+    pub l1_diff_size: U64,
+    /// L1 fee.
+    pub l1_fee: U256,
+    /// Balance the sender must hold: gas * gas_price + value + l1_fee.
+    pub required_balance: U256,
+}
+
+/// L1 fee breakdown of a mined transaction.
+/// The L1 fee is l1_fee_rate * (l1_diff_size + l1_fee_overhead), charged on top of the evm gas.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionL1Fee {
+    /// Diff size, after the compression discount.
+    pub l1_diff_size: U64,
+    /// L1 fee rate of the block the transaction is in.
+    pub l1_fee_rate: U128,
+    /// Overhead added to the diff size.
+    pub l1_fee_overhead: U64,
+    /// L1 fee charged. System transactions don't pay the L1 fee.
+    pub l1_fee: U256,
+    /// Whether the diff size was discounted for the compression of the state diff.
+    pub compression_discount_active: bool,
+}
+
+/// Chain config the blocks are executed with.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainConfig {
+    /// Chain id.
+    pub chain_id: U64,
+    /// Max size of the code of a deployed contract, in bytes.
+    pub limit_contract_code_size: U64,
+    /// Gas limit of a block.
+    pub block_gas_limit: U64,
+    /// Base fee params.
+    pub base_fee_params: BaseFeeParams,
+}
+
+/// cBTC supply at a block.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotalSupply {
+    /// cBTC minted by the bridge deposits, minus the cBTC burned by withdrawals.
+    pub total_supply: U256,
+    /// Balance of the base fee vault, part of the total supply.
+    pub base_fee_vault_balance: U256,
+    /// Balance of the L1 fee vault, part of the total supply.
+    pub l1_fee_vault_balance: U256,
+    /// Balance of the priority fee vault, part of the total supply.
+    pub priority_fee_vault_balance: U256,
+}
+
+/// Result of a call of a `citrea_callBundle` request.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallBundleResult {
+    /// Whether the call succeeded. Reverted and halted calls are not successful.
+    pub success: bool,
+    /// Returned data, or the revert data of a reverted call.
+    pub return_data: Bytes,
+    /// Gas used by the call.
+    pub gas_used: U64,
+}
+
 #[rpc_gen(client, server)]
 impl<C: sov_modules_api::Context> Evm<C> {
     /// Handler for `net_version`
@@ -370,10 +469,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         let block_number = match block_id {
             Some(BlockId::Number(block_num)) => block_num,
             Some(BlockId::Hash(block_hash)) => {
-                let block_number = self
-                    .get_block_number_by_block_hash(block_hash.block_hash, working_set)
-                    .ok_or_else(|| EthApiError::UnknownBlockOrTxIndex)?;
-                BlockNumberOrTag::Number(block_number)
+                BlockNumberOrTag::Number(self.block_number_for_hash(block_hash, working_set)?)
             }
             None => BlockNumberOrTag::Latest,
         };
@@ -468,8 +564,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         };
 
         let block = self
-            .blocks
-            .get(block_number as usize, &mut working_set.accessory_state())
+            .get_sealed_block(block_number, working_set)?
             .expect("Block must be set");
 
         match check_tx_range(&block.transactions, index) {
@@ -511,35 +606,88 @@ impl<C: sov_modules_api::Context> Evm<C> {
         hash: B256,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> RpcResult<Option<AnyTransactionReceipt>> {
-        let mut accessory_state = working_set.accessory_state();
+        let receipt = self
+            .mined_transaction(hash, working_set)
+            .map(|(block, tx, number, receipt)| build_rpc_receipt(&block, tx, number, receipt));
 
-        let tx_number = self.transaction_hashes.get(&hash, &mut accessory_state);
+        Ok(receipt)
+    }
 
-        let receipt = tx_number.map(|number| {
-            let tx = self
-                .transactions
-                .get(number as usize, &mut accessory_state)
-                .expect("Transaction with known hash must be set");
-            let block = self
-                .blocks
-                .get(tx.block_number as usize, &mut accessory_state)
-                .expect("Block number for known transaction must be set");
+    /// Handler for: `citrea_getTransactionReceipt`
+    /// Same as `eth_getTransactionReceipt` with the L1 fee breakdown of `citrea_getTransactionL1Fee`
+    /// added to the receipt, so that the full cost of the transaction can be displayed.
+    #[rpc_method(name = "citrea_getTransactionReceipt")]
+    pub fn citrea_get_transaction_receipt(
+        &self,
+        hash: B256,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> RpcResult<Option<AnyTransactionReceipt>> {
+        let receipt =
+            self.mined_transaction(hash, working_set)
+                .map(|(block, tx, number, receipt)| {
+                    let l1_fee = transaction_l1_fee(&block, &tx, &receipt);
+                    let mut rpc_receipt = build_rpc_receipt(&block, tx, number, receipt);
+                    rpc_receipt.other.insert(
+                        "l1FeeOverhead".into(),
+                        format!("{:#x}", l1_fee.l1_fee_overhead).into(),
+                    );
+                    rpc_receipt
+                        .other
+                        .insert("l1Fee".into(), format!("{:#x}", l1_fee.l1_fee).into());
+                    rpc_receipt.other.insert(
+                        "compressionDiscountActive".into(),
+                        l1_fee.compression_discount_active.into(),
+                    );
+                    rpc_receipt
+                });
 
-            let receipt = self
-                .receipts
-                .get(number as usize, &mut accessory_state)
-                .expect("Receipt for known transaction must be set");
+        Ok(receipt)
+    }
 
-            build_rpc_receipt(&block, tx, number, receipt)
-        });
+    /// Handler for: `citrea_getTransactionL1Fee`
+    /// Returns the L1 fee the transaction was charged on top of its evm gas, with its breakdown.
+    #[rpc_method(name = "citrea_getTransactionL1Fee")]
+    pub fn citrea_get_transaction_l1_fee(
+        &self,
+        hash: B256,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> RpcResult<Option<TransactionL1Fee>> {
+        let l1_fee = self
+            .mined_transaction(hash, working_set)
+            .map(|(block, tx, _, receipt)| transaction_l1_fee(&block, &tx, &receipt));
 
-        Ok(receipt)
+        Ok(l1_fee)
     }
 
-    /// Handler for: `eth_call`
+    // Returns the block, the transaction, its number and receipt of a mined transaction.
+    fn mined_transaction(
+        &self,
+        hash: B256,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Option<(SealedBlock, TransactionSignedAndRecovered, u64, Receipt)> {
+        let mut accessory_state = working_set.accessory_state();
+
+        let number = self.transaction_hashes.get(&hash, &mut accessory_state)?;
+        let tx = self
+            .transactions
+            .get(number as usize, &mut accessory_state)
+            .expect("Transaction with known hash must be set");
+        let block = self
+            .blocks
+            .get(tx.block_number as usize, &mut accessory_state)
+            .expect("Block number for known transaction must be set");
+
+        let receipt = self
+            .receipts
+            .get(number as usize, &mut accessory_state)
+            .expect("Receipt for known transaction must be set");
+
+        Some((block, tx, number, receipt))
+    }
+
+    /// Handler for: `eth_call`, served by the Ethereum RPC which checks that the state is available
     //https://github.com/paradigmxyz/reth/blob/f577e147807a783438a3f16aad968b4396274483/crates/rpc/rpc/src/eth/api/transactions.rs#L502
     //https://github.com/paradigmxyz/reth/blob/main/crates/rpc/rpc-types/src/eth/call.rs#L7
-    #[rpc_method(name = "eth_call", blocking)]
     pub fn get_call(
         &self,
         request: TransactionRequest,
@@ -548,18 +696,129 @@ impl<C: sov_modules_api::Context> Evm<C> {
         block_overrides: Option<BlockOverrides>,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> RpcResult<Bytes> {
+        let (block_env, mut cfg_env, mut evm_db) =
+            self.call_env(block_id, state_overrides, block_overrides, working_set)?;
+
+        let cap_to_balance = evm_db
+            .basic(request.from.unwrap_or_default())
+            .map_err(EthApiError::from)?
+            .unwrap_or_default()
+            .balance;
+        let tx_env = prepare_call_env(&block_env, &mut cfg_env, request, cap_to_balance)?;
+
+        let result = match inspect(
+            evm_db,
+            cfg_env,
+            block_env,
+            tx_env,
+            TracingInspector::new(TracingInspectorConfig::all()),
+        ) {
+            Ok(result) => result.result,
+            Err(err) => {
+                return Err(EthApiError::from(err).into());
+            }
+        };
+
+        Ok(ensure_call_success(result)?)
+    }
+
+    /// Handler for: `citrea_callBundle`
+    ///
+    /// Executes the calls one after another on the state of a single block, with the overrides
+    /// applied once. By default each call sees the state changes of the calls before it, with
+    /// `independent` set every call is executed on the state of the block.
+    /// The calls share the gas limit of a block and at most [`MAX_CALL_BUNDLE_SIZE`] calls are
+    /// accepted. A failing call does not fail the bundle, it is reported in its result.
+    #[rpc_method(name = "citrea_callBundle", blocking)]
+    pub fn citrea_call_bundle(
+        &self,
+        calls: Vec<TransactionRequest>,
+        block_id: Option<BlockId>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+        independent: Option<bool>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> RpcResult<Vec<CallBundleResult>> {
+        if calls.len() > MAX_CALL_BUNDLE_SIZE {
+            return Err(EthApiError::InvalidParams(format!(
+                "Call bundle has {} calls, at most {} are allowed",
+                calls.len(),
+                MAX_CALL_BUNDLE_SIZE
+            ))
+            .into());
+        }
+        let independent = independent.unwrap_or(false);
+
+        let block_gas_limit = self
+            .cfg
+            .get(working_set)
+            .expect("EVM chain config should be set")
+            .block_gas_limit;
+
+        let (block_env, mut cfg_env, mut evm_db) =
+            self.call_env(block_id, state_overrides, block_overrides, working_set)?;
+
+        let mut remaining_gas = block_gas_limit;
+        let mut results = Vec::with_capacity(calls.len());
+        for request in calls {
+            if remaining_gas == 0 {
+                return Err(EthApiError::InvalidParams(format!(
+                    "Call bundle exceeds the gas limit of {}",
+                    block_gas_limit
+                ))
+                .into());
+            }
+
+            let cap_to_balance = evm_db
+                .basic(request.from.unwrap_or_default())
+                .map_err(EthApiError::from)?
+                .unwrap_or_default()
+                .balance;
+            let mut tx_env = prepare_call_env(&block_env, &mut cfg_env, request, cap_to_balance)?;
+            tx_env.gas_limit = tx_env.gas_limit.min(remaining_gas);
+
+            let ResultAndState { result, state } = inspect(
+                &mut evm_db,
+                cfg_env.clone(),
+                block_env.clone(),
+                tx_env,
+                TracingInspector::new(TracingInspectorConfig::all()),
+            )
+            .map_err(EthApiError::from)?;
+
+            if !independent {
+                evm_db.commit(state);
+            }
+
+            remaining_gas = remaining_gas.saturating_sub(result.gas_used());
+            results.push(CallBundleResult {
+                success: result.is_success(),
+                gas_used: U64::from(result.gas_used()),
+                return_data: result.into_output().unwrap_or_default(),
+            });
+        }
+
+        Ok(results)
+    }
+
+    // Prepares the block env, cfg env and database of a call on the state of `block_id`,
+    // with the block and state overrides applied.
+    fn call_env<'a>(
+        &self,
+        block_id: Option<BlockId>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+        working_set: &'a mut WorkingSet<C::Storage>,
+    ) -> RpcResult<(BlockEnv, CfgEnvWithHandlerCfg, EvmDb<'a, C>)> {
         let block_number = match block_id {
             Some(BlockId::Number(block_num)) => block_num,
             Some(BlockId::Hash(block_hash)) => {
-                let block_number = self
-                    .get_block_number_by_block_hash(block_hash.block_hash, working_set)
-                    .ok_or_else(|| EthApiError::UnknownBlockOrTxIndex)?;
-                BlockNumberOrTag::Number(block_number)
+                BlockNumberOrTag::Number(self.block_number_for_hash(block_hash, working_set)?)
             }
             None => BlockNumberOrTag::Latest,
         };
 
-        let (mut block_env, mut cfg_env) = {
+        let (mut block_env, cfg_env) = {
             let block_env = match block_number {
                 BlockNumberOrTag::Pending => get_pending_block_env(self, working_set),
                 _ => {
@@ -587,9 +846,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 .expect("EVM chain config should be set");
 
             let citrea_spec_id = fork_from_block_number(block_num).spec_id;
-            let evm_spec_id = citrea_spec_id_to_evm_spec_id(citrea_spec_id);
-
-            let cfg_env = get_cfg_env(cfg, evm_spec_id);
+            let cfg_env = get_cfg_env(cfg, citrea_spec_id);
 
             (block_env, cfg_env)
         };
@@ -605,27 +862,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             apply_state_overrides(state_overrides, &mut evm_db)?;
         }
 
-        let cap_to_balance = evm_db
-            .basic(request.from.unwrap_or_default())
-            .map_err(EthApiError::from)?
-            .unwrap_or_default()
-            .balance;
-        let tx_env = prepare_call_env(&block_env, &mut cfg_env, request, cap_to_balance)?;
-
-        let result = match inspect(
-            evm_db,
-            cfg_env,
-            block_env,
-            tx_env,
-            TracingInspector::new(TracingInspectorConfig::all()),
-        ) {
-            Ok(result) => result.result,
-            Err(err) => {
-                return Err(EthApiError::from(err).into());
-            }
-        };
-
-        Ok(ensure_success(result)?)
+        Ok((block_env, cfg_env, evm_db))
     }
 
     /// Handler for: `eth_blockNumber`
@@ -682,9 +919,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 .expect("EVM chain config should be set");
 
             let citrea_spec_id = fork_from_block_number(block_num).spec_id;
-            let evm_spec_id = citrea_spec_id_to_evm_spec_id(citrea_spec_id);
-
-            let cfg_env = get_cfg_env(cfg, evm_spec_id);
+            let cfg_env = get_cfg_env(cfg, citrea_spec_id);
 
             (l1_fee_rate, block_env, cfg_env)
         };
@@ -730,16 +965,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         )
         .map_err(EthApiError::from)?;
 
-        match result.result {
-            ExecutionResult::Halt { reason, .. } => Err(match reason {
-                HaltReason::NonceOverflow => RpcInvalidTransactionError::NonceMaxValue,
-                halt => RpcInvalidTransactionError::EvmHalt(halt),
-            }),
-            ExecutionResult::Revert { output, .. } => {
-                Err(RpcInvalidTransactionError::Revert(RevertError::new(output)))
-            }
-            ExecutionResult::Success { .. } => Ok(()),
-        }?;
+        ensure_call_success(result.result)?;
 
         let access_list = inspector.into_access_list();
 
@@ -767,42 +993,47 @@ impl<C: sov_modules_api::Context> Evm<C> {
         block_number: Option<BlockNumberOrTag>,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> RpcResult<EstimatedTxExpenses> {
-        let (l1_fee_rate, block_env, cfg_env) = {
-            let (l1_fee_rate, block_env) = match block_number {
-                Some(BlockNumberOrTag::Pending) => {
-                    let l1_fee_rate = self
-                        .blocks
-                        .last(&mut working_set.accessory_state())
-                        .expect("Head block must be set")
-                        .l1_fee_rate;
-                    (l1_fee_rate, get_pending_block_env(self, working_set))
-                }
-                _ => {
-                    let block = self
-                        .get_sealed_block_by_number(block_number, working_set)?
-                        .ok_or(EthApiError::HeaderNotFound(
-                            block_number.unwrap_or_default().into(),
-                        ))?;
-                    (
-                        block.l1_fee_rate,
-                        sealed_block_to_block_env(&block.header), // correct spec will be set later
-                    )
-                }
-            };
-            let cfg = self
-                .cfg
-                .get(working_set)
-                .expect("EVM chain config should be set");
-
-            let citrea_spec_id = fork_from_block_number(block_env.number.saturating_to()).spec_id;
-            let evm_spec_id = citrea_spec_id_to_evm_spec_id(citrea_spec_id);
+        let (l1_fee_rate, block_env, cfg_env) = self.estimation_env(block_number, working_set)?;
 
-            let cfg_env = get_cfg_env(cfg, evm_spec_id);
+        self.estimate_gas_with_env(request, l1_fee_rate, block_env, cfg_env, working_set)
+    }
 
-            (l1_fee_rate, block_env, cfg_env)
+    // Prepares the l1 fee rate, block env and cfg env the estimation is run with.
+    fn estimation_env(
+        &self,
+        block_number: Option<BlockNumberOrTag>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> RpcResult<(u128, BlockEnv, CfgEnvWithHandlerCfg)> {
+        let (l1_fee_rate, block_env) = match block_number {
+            Some(BlockNumberOrTag::Pending) => {
+                let l1_fee_rate = self
+                    .blocks
+                    .last(&mut working_set.accessory_state())
+                    .expect("Head block must be set")
+                    .l1_fee_rate;
+                (l1_fee_rate, get_pending_block_env(self, working_set))
+            }
+            _ => {
+                let block = self
+                    .get_sealed_block_by_number(block_number, working_set)?
+                    .ok_or(EthApiError::HeaderNotFound(
+                        block_number.unwrap_or_default().into(),
+                    ))?;
+                (
+                    block.l1_fee_rate,
+                    sealed_block_to_block_env(&block.header), // correct spec will be set later
+                )
+            }
         };
+        let cfg = self
+            .cfg
+            .get(working_set)
+            .expect("EVM chain config should be set");
 
-        self.estimate_gas_with_env(request, l1_fee_rate, block_env, cfg_env, working_set)
+        let citrea_spec_id = fork_from_block_number(block_env.number.saturating_to()).spec_id;
+        let cfg_env = get_cfg_env(cfg, citrea_spec_id);
+
+        Ok((l1_fee_rate, block_env, cfg_env))
     }
 
     /// Handler for: `eth_estimateGas`
@@ -844,6 +1075,104 @@ impl<C: sov_modules_api::Context> Evm<C> {
         })
     }
 
+    /// Handler for: `citrea_estimateFee`
+    /// Estimates the evm gas together with the L1 fee charged after execution.
+    /// `l1_fee_rate` overrides the L1 fee rate of the block for what-if queries.
+    #[rpc_method(name = "citrea_estimateFee", blocking)]
+    pub fn citrea_estimate_fee(
+        &self,
+        request: TransactionRequest,
+        block_number: Option<BlockNumberOrTag>,
+        l1_fee_rate: Option<U128>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> RpcResult<EstimatedFee> {
+        let (block_l1_fee_rate, block_env, cfg_env) =
+            self.estimation_env(block_number, working_set)?;
+        let l1_fee_rate = l1_fee_rate.map_or(block_l1_fee_rate, |rate| rate.saturating_to());
+
+        let gas_price = request
+            .max_fee_per_gas
+            .or(request.gas_price)
+            .map(U256::from)
+            .unwrap_or(block_env.basefee);
+        let value = request.value.unwrap_or_default();
+
+        let estimated =
+            self.estimate_gas_with_env(request, l1_fee_rate, block_env, cfg_env, working_set)?;
+
+        let required_balance = U256::from(estimated.gas_used)
+            .saturating_mul(gas_price)
+            .saturating_add(value)
+            .saturating_add(estimated.l1_fee);
+
+        Ok(EstimatedFee {
+            gas: estimated.gas_used,
+            gas_price,
+            l1_fee_rate: U128::from(l1_fee_rate),
+            l1_diff_size: U64::from(estimated.l1_diff_size),
+            l1_fee: estimated.l1_fee,
+            required_balance,
+        })
+    }
+
+    /// Handler for: `citrea_getChainConfig`
+    /// Returns the chain config the next block is executed with,
+    /// the contract code size limit being the one of its fork.
+    #[rpc_method(name = "citrea_getChainConfig")]
+    pub fn citrea_get_chain_config(
+        &self,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> RpcResult<ChainConfig> {
+        let cfg = self
+            .cfg
+            .get(working_set)
+            .expect("EVM chain config should be set");
+        let latest_block_number = self
+            .blocks
+            .last(&mut working_set.accessory_state())
+            .map(|block| block.header.number)
+            .expect("Head block must be set");
+
+        let citrea_spec_id = fork_from_block_number(latest_block_number + 1).spec_id;
+        let cfg_env = get_cfg_env(cfg.clone(), citrea_spec_id);
+
+        Ok(ChainConfig {
+            chain_id: U64::from(cfg.chain_id),
+            limit_contract_code_size: U64::from(
+                cfg_env.limit_contract_code_size.unwrap_or(MAX_CODE_SIZE),
+            ),
+            block_gas_limit: U64::from(cfg.block_gas_limit),
+            base_fee_params: cfg.base_fee_params,
+        })
+    }
+
+    /// Handler for: `citrea_getTotalSupply`
+    /// Returns the cBTC supply at the block, with the balances of the fee vaults.
+    /// Fees are not burned, they are collected in the vaults.
+    #[rpc_method(name = "citrea_getTotalSupply")]
+    pub fn citrea_get_total_supply(
+        &self,
+        block_id: Option<BlockId>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> RpcResult<TotalSupply> {
+        self.set_state_to_end_of_evm_block_by_block_id(block_id, working_set)?;
+
+        let total_supply = self.total_supply(working_set);
+        let mut balance_of = |address: Address| {
+            self.accounts
+                .get(&address, working_set)
+                .map(|info| info.balance)
+                .unwrap_or_default()
+        };
+
+        Ok(TotalSupply {
+            total_supply,
+            base_fee_vault_balance: balance_of(BASE_FEE_VAULT),
+            l1_fee_vault_balance: balance_of(L1_FEE_VAULT),
+            priority_fee_vault_balance: balance_of(PRIORITY_FEE_VAULT),
+        })
+    }
+
     /// Handler for: `eth_getBlockTransactionCountByHash`
     // https://github.com/paradigmxyz/reth/blob/main/crates/rpc/rpc/src/eth/api/call.rs#L172
     #[rpc_method(name = "eth_getBlockTransactionCountByHash")]
@@ -997,8 +1326,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
                     cfg_env,
                     evm_db,
                     l1_fee_rate,
-                )
-                .into());
+                ));
             }
         }
 
@@ -1008,7 +1336,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
                     (result.result, tx_info.l1_fee, tx_info.l1_diff_size)
                 }
                 ExecutionResult::Halt { reason, gas_used } => {
-                    return Err(RpcInvalidTransactionError::halt(reason, gas_used).into())
+                    return Err(CallError::Halt { reason, gas_used }.into())
                 }
                 ExecutionResult::Revert { output, .. } => {
                     // if price or limit was included in the request then we can execute the request
@@ -1021,11 +1349,10 @@ impl<C: sov_modules_api::Context> Evm<C> {
                             cfg_env,
                             evm_db,
                             l1_fee_rate,
-                        )
-                        .into())
+                        ))
                     } else {
                         // the transaction did revert
-                        Err(RpcInvalidTransactionError::Revert(RevertError::new(output)).into())
+                        Err(CallError::Revert(output).into())
                     };
                 }
             },
@@ -1143,14 +1470,15 @@ impl<C: sov_modules_api::Context> Evm<C> {
     /// Returns logs matching given filter object.
     ///
     /// Handler for `eth_getLogs`
-    #[rpc_method(name = "eth_getLogs")]
+    /// RPC method is moved to ethereum-rpc module, which applies the configured limits
     pub fn eth_get_logs(
         &self,
         filter: Filter,
+        limits: &LogsQueryLimits,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> RpcResult<Vec<LogResponse>> {
         // https://github.com/paradigmxyz/reth/blob/8892d04a88365ba507f28c3314d99a6b54735d3f/crates/rpc/rpc/src/eth/filter.rs#L302
-        Ok(self.logs_for_filter(filter, working_set)?)
+        Ok(self.logs_for_filter(filter, limits, working_set)?)
     }
 
     /// Handler for: `eth_getTransactionByHash`
@@ -1199,6 +1527,10 @@ impl<C: sov_modules_api::Context> Evm<C> {
     }
 
     /// Traces the entire block txs and returns the traces
+    ///
+    /// Transactions are re-executed on top of the versioned state at the end of the
+    /// previous block, so no ancestor blocks need to be re-executed. Tracing fails
+    /// once the `timeout` tracing option (5s by default) elapses.
     pub fn trace_block_transactions_by_number(
         &self,
         block_number: u64,
@@ -1206,6 +1538,12 @@ impl<C: sov_modules_api::Context> Evm<C> {
         stop_at: Option<usize>,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> RpcResult<Vec<TraceResult>> {
+        let started_at = Instant::now();
+        let timeout = match opts.as_ref().and_then(|opts| opts.timeout.as_deref()) {
+            Some(timeout) => parse_trace_timeout(timeout)?,
+            None => DEFAULT_TRACE_TIMEOUT,
+        };
+
         let sealed_block = self
             .get_sealed_block_by_number(Some(BlockNumberOrTag::Number(block_number)), working_set)?
             .ok_or_else(|| EthApiError::HeaderNotFound(block_number.into()))?;
@@ -1228,7 +1566,6 @@ impl<C: sov_modules_api::Context> Evm<C> {
         set_state_to_end_of_evm_block::<C>(block_number - 1, working_set);
 
         let citrea_spec_id = fork_from_block_number(block_number).spec_id;
-        let evm_spec_id = citrea_spec_id_to_evm_spec_id(citrea_spec_id);
 
         let block_env = sealed_block_to_block_env(&sealed_block.header);
         let cfg = self
@@ -1236,7 +1573,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .get(working_set)
             .expect("EVM chain config should be set");
 
-        let cfg_env = get_cfg_env(cfg, evm_spec_id);
+        let cfg_env = get_cfg_env(cfg, citrea_spec_id);
         let l1_fee_rate = sealed_block.l1_fee_rate;
         let current_spec = cfg_env.handler_cfg.spec_id;
 
@@ -1260,6 +1597,10 @@ impl<C: sov_modules_api::Context> Evm<C> {
             )?;
             traces.push(TraceResult::new_success(trace, Some(tx.hash())));
 
+            if started_at.elapsed() > timeout {
+                return Err(EthApiError::ExecutionTimedOut(timeout).into());
+            }
+
             if limit == index {
                 break;
             }
@@ -1277,6 +1618,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
     fn logs_for_filter(
         &self,
         filter: Filter,
+        limits: &LogsQueryLimits,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<Vec<LogResponse>, FilterError> {
         match filter.block_option {
@@ -1295,8 +1637,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
 
                 // if we know the hash, but can't find the block, fail
                 let block = self
-                    .blocks
-                    .get(block_number as usize, &mut working_set.accessory_state())
+                    .get_sealed_block(block_number, working_set)?
                     .expect("Block must be set");
 
                 // all of the logs we have in the block
@@ -1332,6 +1673,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
                     &filter,
                     from_block_number,
                     to_block_number,
+                    limits,
                 )
             }
         }
@@ -1340,19 +1682,23 @@ impl<C: sov_modules_api::Context> Evm<C> {
     // https://github.com/paradigmxyz/reth/blob/8892d04a88365ba507f28c3314d99a6b54735d3f/crates/rpc/rpc/src/eth/filter.rs#L423
     /// Returns all logs in the given _inclusive_ range that match the filter
     ///
+    /// Blocks whose logs bloom can't match the filter are skipped without reading their receipts.
+    /// If the logs are indexed by address and the filter has addresses, only the blocks
+    /// with logs of these addresses are checked.
+    ///
     /// Returns an error if:
     ///  - underlying database error
-    ///  - amount of matches exceeds configured limit
+    ///  - range or amount of matches exceeds configured limits
     pub fn get_logs_in_block_range(
         &self,
         working_set: &mut WorkingSet<C::Storage>,
         filter: &Filter,
         from_block_number: u64,
         to_block_number: u64,
+        limits: &LogsQueryLimits,
     ) -> Result<Vec<LogResponse>, FilterError> {
-        let max_blocks_per_filter: u64 = DEFAULT_MAX_BLOCKS_PER_FILTER;
-        if to_block_number - from_block_number >= max_blocks_per_filter {
-            return Err(FilterError::QueryExceedsMaxBlocks(max_blocks_per_filter));
+        if to_block_number - from_block_number >= limits.max_block_range {
+            return Err(FilterError::QueryExceedsMaxBlocks(limits.max_block_range));
         }
         // all of the logs we have in the block
         let mut all_logs: Vec<LogResponse> = Vec::new();
@@ -1363,40 +1709,54 @@ impl<C: sov_modules_api::Context> Evm<C> {
 
         let max_headers_range = MAX_HEADERS_RANGE;
 
-        // loop over the range of new blocks and check logs if the filter matches the log's bloom
-        // filter
-        for (from, to) in
-            BlockRangeInclusiveIter::new(from_block_number..=to_block_number, max_headers_range)
-        {
-            for idx in from..=to {
-                let block = match self
-                    .blocks
-                    .get((idx) as usize, &mut working_set.accessory_state())
-                {
-                    Some(block) => block,
-                    None => {
-                        return Err(FilterError::EthAPIError(
-                            // from and to are checked against last block
-                            // so this should never happen ideally
-                            ProviderError::BlockBodyIndicesNotFound(idx).into(),
-                        ));
-                    }
-                };
+        // the index narrows down the blocks to the ones with logs of the filtered addresses,
+        // which are still checked against the bloom filter for the topics
+        let block_numbers = match self.indexed_log_blocks(
+            &filter.address,
+            from_block_number,
+            to_block_number,
+            working_set,
+        ) {
+            Some(block_numbers) => Either::Left(block_numbers.into_iter()),
+            None => Either::Right(
+                BlockRangeInclusiveIter::new(
+                    from_block_number..=to_block_number,
+                    max_headers_range,
+                )
+                .flat_map(|(from, to)| from..=to),
+            ),
+        };
 
-                let logs_bloom = block.header.logs_bloom;
+        // loop over the blocks and check logs if the filter matches the log's bloom filter
+        for idx in block_numbers {
+            #[cfg(test)]
+            CHECKED_LOG_BLOCKS.with(|checked| checked.set(checked.get() + 1));
+
+            let block = match self.get_sealed_block(idx, working_set)? {
+                Some(block) => block,
+                None => {
+                    return Err(FilterError::EthAPIError(
+                        // from and to are checked against last block
+                        // so this should never happen ideally
+                        ProviderError::BlockBodyIndicesNotFound(idx).into(),
+                    ));
+                }
+            };
 
-                let alloy_logs_bloom = alloy_primitives::Bloom::from(logs_bloom.data());
-                if matches_address(alloy_logs_bloom, &address_filter)
-                    && matches_topics(alloy_logs_bloom, &topics_filter)
-                {
-                    self.append_matching_block_logs(working_set, &mut all_logs, filter, block);
-                    let max_logs_per_response = DEFAULT_MAX_LOGS_PER_RESPONSE;
-                    // size check but only if range is multiple blocks, so we always return all
-                    // logs of a single block
-                    let is_multi_block_range = from_block_number != to_block_number;
-                    if is_multi_block_range && all_logs.len() > max_logs_per_response {
-                        return Err(FilterError::QueryExceedsMaxResults(max_logs_per_response));
-                    }
+            let logs_bloom = block.header.logs_bloom;
+
+            let alloy_logs_bloom = alloy_primitives::Bloom::from(logs_bloom.data());
+            if matches_address(alloy_logs_bloom, &address_filter)
+                && matches_topics(alloy_logs_bloom, &topics_filter)
+            {
+                self.append_matching_block_logs(working_set, &mut all_logs, filter, block);
+                // size check but only if range is multiple blocks, so we always return all
+                // logs of a single block
+                let is_multi_block_range = from_block_number != to_block_number;
+                if is_multi_block_range && all_logs.len() > limits.max_logs_per_response {
+                    return Err(FilterError::QueryExceedsMaxResults(
+                        limits.max_logs_per_response,
+                    ));
                 }
             }
         }
@@ -1411,6 +1771,9 @@ impl<C: sov_modules_api::Context> Evm<C> {
         filter: &Filter,
         block: SealedBlock,
     ) {
+        #[cfg(test)]
+        SCANNED_LOG_BLOCKS.with(|scanned| scanned.set(scanned.get() + 1));
+
         // tracks the index of a log in the entire block
         let mut log_index: u32 = 0;
 
@@ -1458,6 +1821,11 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .expect("EVM chain config should be set")
     }
 
+    /// Helper function to get the gas of the block gas limit reserved for the system transactions
+    pub fn get_system_gas_reserve(&self, working_set: &mut WorkingSet<C::Storage>) -> u64 {
+        self.system_gas_reserve.get(working_set).unwrap_or(0)
+    }
+
     /// Helper function to get block hash from block number
     pub fn block_hash_from_number(
         &self,
@@ -1479,14 +1847,62 @@ impl<C: sov_modules_api::Context> Evm<C> {
         let mut headers = Vec::new();
         for i in range {
             let block = self
-                .blocks
-                .get(i as usize, &mut working_set.accessory_state())
+                .get_sealed_block(i, working_set)?
                 .ok_or_else(|| EthApiError::InvalidBlockRange)?;
             headers.push(block.header);
         }
         Ok(headers)
     }
 
+    /// Helper function to get the effective priority fee rewards of the blocks in range
+    /// at the given percentiles, weighted by the gas used of each transaction.
+    pub fn fee_history_rewards(
+        &self,
+        range: RangeInclusive<u64>,
+        percentiles: &[f64],
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<Vec<Vec<u128>>, EthApiError> {
+        if range.end().saturating_sub(*range.start()) >= MAX_FEE_HISTORY_BLOCK_COUNT {
+            return Err(EthApiError::InvalidBlockRange);
+        }
+
+        let mut rewards = Vec::new();
+        for i in range {
+            let block = self
+                .get_sealed_block(i, working_set)?
+                .ok_or_else(|| EthApiError::InvalidBlockRange)?;
+            let base_fee = block.header.base_fee_per_gas;
+
+            let txs_gas_and_reward = block
+                .transactions
+                .map(|id| {
+                    let tx = self
+                        .transactions
+                        .get(id as usize, &mut working_set.accessory_state())
+                        .expect("Transaction must be set");
+                    let receipt = self
+                        .receipts
+                        .get(id as usize, &mut working_set.accessory_state())
+                        .expect("Receipt for known transaction must be set");
+
+                    (
+                        receipt.gas_used,
+                        tx.signed_transaction
+                            .transaction
+                            .effective_tip_per_gas(base_fee)
+                            .unwrap_or_default(),
+                    )
+                })
+                .collect();
+
+            rewards.push(calculate_reward_percentiles(
+                percentiles,
+                txs_gas_and_reward,
+            ));
+        }
+        Ok(rewards)
+    }
+
     /// Helper function to check if the block number is valid
     /// If returns None, block doesn't exist
     pub fn block_number_for_id(
@@ -1525,12 +1941,11 @@ impl<C: sov_modules_api::Context> Evm<C> {
     ) -> Result<Option<SealedBlock>, EthApiError> {
         // safe, finalized, and pending are not supported
         match block_number {
-            Some(BlockNumberOrTag::Number(block_number)) => Ok(self
-                .blocks
-                .get(block_number as usize, &mut working_set.accessory_state())),
+            Some(BlockNumberOrTag::Number(block_number)) => {
+                self.get_sealed_block(block_number, working_set)
+            }
             Some(BlockNumberOrTag::Earliest) => Ok(Some(
-                self.blocks
-                    .get(0, &mut working_set.accessory_state())
+                self.get_sealed_block(0, working_set)?
                     .expect("Genesis block must be set"),
             )),
             Some(BlockNumberOrTag::Latest) => Ok(Some(
@@ -1549,6 +1964,25 @@ impl<C: sov_modules_api::Context> Evm<C> {
         }
     }
 
+    /// Helper function to get sealed block by number
+    /// If returns None, block doesn't exist
+    /// Returns an error if the block was pruned
+    fn get_sealed_block(
+        &self,
+        block_number: u64,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<Option<SealedBlock>, EthApiError> {
+        let mut accessory_state = working_set.accessory_state();
+        match self.blocks.get(block_number as usize, &mut accessory_state) {
+            Some(block) => Ok(Some(block)),
+            // Blocks below the head can only be missing if they were pruned
+            None if block_number < self.blocks.len(&mut accessory_state) as u64 => {
+                Err(EthApiError::PrunedHistoryUnavailable)
+            }
+            None => Ok(None),
+        }
+    }
+
     /// Returns the block number given block hash
     /// If block not found returns None
     pub fn get_block_number_by_block_hash(
@@ -1562,6 +1996,49 @@ impl<C: sov_modules_api::Context> Evm<C> {
         block_number
     }
 
+    /// Helper function to resolve the block number of an EIP-1898 block hash parameter
+    /// Unknown hashes, including the ones of pruned blocks, are reported as header not found
+    /// With `requireCanonical`, the block at the resolved number must have the given hash
+    pub fn block_number_for_hash(
+        &self,
+        block_hash: RpcBlockHash,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<u64, EthApiError> {
+        let block_number = self
+            .get_block_number_by_block_hash(block_hash.block_hash, working_set)
+            .ok_or(EthApiError::HeaderNotFound(block_hash.into()))?;
+
+        if block_hash.require_canonical == Some(true) {
+            let canonical_hash = self
+                .blocks
+                .get(block_number as usize, &mut working_set.accessory_state())
+                .map(|block| block.header.hash());
+            if canonical_hash != Some(block_hash.block_hash) {
+                return Err(EthApiError::HeaderNotFound(block_hash.into()));
+            }
+        }
+
+        Ok(block_number)
+    }
+
+    /// Returns the number of the block containing the transaction with given hash
+    /// If transaction not found returns None
+    pub fn get_block_number_by_tx_hash(
+        &self,
+        tx_hash: B256,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Option<u64> {
+        let mut accessory_state = working_set.accessory_state();
+        let tx_number = self
+            .transaction_hashes
+            .get(&tx_hash, &mut accessory_state)?;
+        let tx = self
+            .transactions
+            .get(tx_number as usize, &mut accessory_state)
+            .expect("Transaction with known hash must be set");
+        Some(tx.block_number)
+    }
+
     fn set_state_to_end_of_evm_block_by_block_id(
         &self,
         block_id: Option<BlockId>,
@@ -1597,9 +2074,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 }
             }
             Some(BlockId::Hash(block_hash)) => {
-                let block_number = self
-                    .get_block_number_by_block_hash(block_hash.block_hash, working_set)
-                    .ok_or_else(|| EthApiError::UnknownBlockOrTxIndex)?;
+                let block_number = self.block_number_for_hash(block_hash, working_set)?;
 
                 set_state_to_end_of_evm_block::<C>(block_number, working_set);
             }
@@ -1609,6 +2084,175 @@ impl<C: sov_modules_api::Context> Evm<C> {
     }
 }
 
+impl<C: sov_modules_api::Context> Evm<C>
+where
+    C::Storage: NativeStorage,
+{
+    /// Handler for: `eth_getProof`
+    ///
+    /// All EVM state lives in a single Jellyfish Merkle Tree, so both the account and the storage
+    /// proofs are made against the state root of the block, which is returned as `storageHash`.
+    /// Each proof is a single borsh encoded JMT `SparseMerkleProof` of the module's storage key.
+    pub fn get_proof(
+        &self,
+        address: Address,
+        keys: Vec<JsonStorageKey>,
+        block_id: Option<BlockId>,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> RpcResult<EIP1186AccountProofResponse> {
+        let block_number = match block_id {
+            None => self.block_number_for_id(&BlockNumberOrTag::Latest, working_set)?,
+            Some(BlockId::Number(block_number)) => {
+                self.block_number_for_id(&block_number, working_set)?
+            }
+            Some(BlockId::Hash(block_hash)) => {
+                self.block_number_for_hash(block_hash, working_set)?
+            }
+        };
+
+        // genesis is committed at db version 1
+        // so every block is offset by 1
+        let version = block_number + 1;
+        let state_root = working_set
+            .get_root_hash_option(version)
+            .map_err(|e| EthApiError::EvmCustom(format!("Failed to get state root: {}", e)))?
+            .ok_or_else(|| {
+                EthApiError::InvalidParams(format!(
+                    "State of block {} is not available, it is older than the pruning horizon",
+                    block_number
+                ))
+            })?;
+
+        let account_key = StorageKey::new(self.accounts.prefix(), &address, self.accounts.codec());
+        let account_proof = self.get_jmt_proof(account_key, version, working_set)?;
+        // Values are decoded from the proofs so that they always match the proven state
+        let account: AccountInfo = account_proof
+            .value
+            .as_ref()
+            .map(|value| self.accounts.codec().try_decode_value(value.value()))
+            .transpose()
+            .map_err(|e| EthApiError::EvmCustom(format!("Failed to decode account: {:?}", e)))?
+            .unwrap_or_default();
+
+        let db_account = DbAccount::new(address);
+        let storage_proof = keys
+            .into_iter()
+            .map(|key| {
+                let index = U256::from_be_bytes(key.0 .0);
+                let storage_key = StorageKey::new(
+                    db_account.storage.prefix(),
+                    &index,
+                    db_account.storage.codec(),
+                );
+                let proof = self.get_jmt_proof(storage_key, version, working_set)?;
+                let value = proof
+                    .value
+                    .as_ref()
+                    .map(|value| db_account.storage.codec().try_decode_value(value.value()))
+                    .transpose()
+                    .map_err(|e| {
+                        EthApiError::EvmCustom(format!("Failed to decode storage value: {:?}", e))
+                    })?
+                    .unwrap_or_default();
+
+                Ok(EIP1186StorageProof {
+                    key,
+                    value,
+                    proof: vec![encode_jmt_proof(&proof)],
+                })
+            })
+            .collect::<Result<Vec<_>, EthApiError>>()?;
+
+        Ok(EIP1186AccountProofResponse {
+            address,
+            balance: account.balance,
+            code_hash: account.code_hash.unwrap_or(KECCAK_EMPTY),
+            nonce: account.nonce,
+            storage_hash: B256::from_slice(state_root.as_ref()),
+            account_proof: vec![encode_jmt_proof(&account_proof)],
+            storage_proof,
+        })
+    }
+
+    /// Helper function to get the JMT proof of a key at the given version
+    fn get_jmt_proof(
+        &self,
+        key: StorageKey,
+        version: u64,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<StorageProof<<C::Storage as sov_state::Storage>::Proof>, EthApiError> {
+        working_set
+            .get_with_proof_at_version(key, version)
+            .map_err(|e| EthApiError::EvmCustom(format!("Failed to get state proof: {}", e)))
+    }
+}
+
+/// Borsh encodes the JMT proof to be embedded in the `eth_getProof` response
+fn encode_jmt_proof<P: borsh::BorshSerialize>(proof: &StorageProof<P>) -> Bytes {
+    Bytes::from(borsh::to_vec(&proof.proof).expect("Proof serialization must not fail"))
+}
+
+/// Computes the rewards at the given percentiles from `(gas_used, reward)` pairs of a block's
+/// transactions, the same way geth does.
+/// Percentiles must be monotonically increasing. Empty blocks result in zero rewards.
+pub(crate) fn calculate_reward_percentiles(
+    percentiles: &[f64],
+    mut txs_gas_and_reward: Vec<(u128, u128)>,
+) -> Vec<u128> {
+    if txs_gas_and_reward.is_empty() {
+        return vec![0; percentiles.len()];
+    }
+
+    // Sort the transactions by their rewards in ascending order
+    txs_gas_and_reward.sort_by_key(|(_, reward)| *reward);
+
+    let block_gas_used: u128 = txs_gas_and_reward
+        .iter()
+        .map(|(gas_used, _)| gas_used)
+        .sum();
+
+    // The percentiles are monotonically increasing, so the index is shared across them
+    let mut tx_index = 0;
+    let mut cumulative_gas_used = txs_gas_and_reward[0].0;
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let threshold = (block_gas_used as f64 * percentile / 100.) as u128;
+            while cumulative_gas_used < threshold && tx_index < txs_gas_and_reward.len() - 1 {
+                tx_index += 1;
+                cumulative_gas_used += txs_gas_and_reward[tx_index].0;
+            }
+            txs_gas_and_reward[tx_index].1
+        })
+        .collect()
+}
+
+/// Computes the L1 fee of a mined transaction the same way it was charged after its execution
+pub(crate) fn transaction_l1_fee(
+    block: &SealedBlock,
+    tx: &TransactionSignedAndRecovered,
+    receipt: &Receipt,
+) -> TransactionL1Fee {
+    let compression_discount_active =
+        citrea_spec_id_to_evm_spec_id(fork_from_block_number(block.header.number).spec_id)
+            .is_enabled_in(SpecId::CANCUN);
+
+    let l1_fee = if tx.signer == SYSTEM_SIGNER {
+        U256::ZERO
+    } else {
+        U256::from(block.l1_fee_rate)
+            * (U256::from(receipt.l1_diff_size) + U256::from(L1_FEE_OVERHEAD))
+    };
+
+    TransactionL1Fee {
+        l1_diff_size: U64::from(receipt.l1_diff_size),
+        l1_fee_rate: U128::from(block.l1_fee_rate),
+        l1_fee_overhead: U64::from(L1_FEE_OVERHEAD),
+        l1_fee,
+        compression_discount_active,
+    }
+}
+
 // modified from: https://github.com/paradigmxyz/reth/blob/cc576bc8690a3e16e6e5bf1cbbbfdd029e85e3d4/crates/rpc/rpc/src/eth/api/transactions.rs#L849
 pub(crate) fn build_rpc_receipt(
     block: &SealedBlock,
@@ -1716,7 +2360,7 @@ fn map_out_of_gas_err<C: sov_modules_api::Context>(
     cfg_env: revm::primitives::CfgEnvWithHandlerCfg,
     db: EvmDb<'_, C>,
     l1_fee_rate: u128,
-) -> EthApiError {
+) -> ErrorObjectOwned {
     let req_gas_limit = tx_env.gas_limit;
     tx_env.gas_limit = block_env.gas_limit.saturating_to();
 
@@ -1729,13 +2373,13 @@ fn map_out_of_gas_err<C: sov_modules_api::Context>(
             }
             ExecutionResult::Revert { output, .. } => {
                 // reverted again after bumping the limit
-                RpcInvalidTransactionError::Revert(RevertError::new(output)).into()
+                CallError::Revert(output).into()
             }
-            ExecutionResult::Halt { reason, .. } => {
-                RpcInvalidTransactionError::EvmHalt(reason).into()
+            ExecutionResult::Halt { reason, gas_used } => {
+                CallError::Halt { reason, gas_used }.into()
             }
         },
-        Err(err) => EthApiError::from(err),
+        Err(err) => EthApiError::from(err).into(),
     }
 }
 
@@ -1833,9 +2477,9 @@ fn get_pending_block_env<C: sov_modules_api::Context>(
         .last(&mut working_set.accessory_state())
         .expect("Head block must be set");
 
-    evm.latest_block_hashes.set(
-        &U256::from(latest_block.header.number),
-        &latest_block.header.hash(),
+    evm.update_latest_block_hashes(
+        latest_block.header.number + 1,
+        latest_block.header.hash(),
         working_set,
     );
 
@@ -1863,11 +2507,6 @@ fn get_pending_block_env<C: sov_modules_api::Context>(
         None
     };
 
-    if block_env.number > U256::from(256) {
-        evm.latest_block_hashes
-            .remove(&(block_env.number - U256::from(257)), working_set);
-    }
-
     block_env
 }
 
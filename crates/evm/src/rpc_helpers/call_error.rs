@@ -0,0 +1,154 @@
+use alloy_primitives::Bytes;
+use alloy_sol_types::{Panic, Revert, SolError};
+use jsonrpsee::types::error::{ErrorObject, CALL_EXECUTION_FAILED_CODE};
+use reth_rpc_eth_types::error::RpcInvalidTransactionError;
+use revm::primitives::{ExecutionResult, HaltReason};
+
+/// Error code of reverted executions, the same as geth and reth use.
+pub const EXECUTION_REVERTED_ERROR_CODE: i32 = 3;
+
+/// Errors of executions that did not succeed in eth_call, eth_estimateGas and similar
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CallError {
+    /// The execution reverted with the given return data
+    #[error("{}", revert_message(.0))]
+    Revert(Bytes),
+    /// The execution halted
+    #[error("{}", halt_message(.reason))]
+    Halt {
+        /// Reason the execution halted with
+        reason: HaltReason,
+        /// Gas used until the execution halted
+        gas_used: u64,
+    },
+}
+
+/// Returns the output of a successful execution, or the reason it failed
+pub(crate) fn ensure_call_success(result: ExecutionResult) -> Result<Bytes, CallError> {
+    match result {
+        ExecutionResult::Success { output, .. } => Ok(output.into_data()),
+        ExecutionResult::Revert { output, .. } => Err(CallError::Revert(output)),
+        ExecutionResult::Halt { reason, gas_used } => Err(CallError::Halt { reason, gas_used }),
+    }
+}
+
+/// Includes the reason of `Error(string)` and `Panic(uint256)` reverts in the message.
+/// Custom errors are only returned as data since they can't be decoded without the abi.
+fn revert_message(output: &[u8]) -> String {
+    let reason = match Revert::abi_decode(output, true) {
+        Ok(revert) => Some(revert.reason),
+        Err(_) => Panic::abi_decode(output, true)
+            .ok()
+            .map(|panic| panic.to_string()),
+    };
+    match reason {
+        Some(reason) => format!("execution reverted: {reason}"),
+        None => "execution reverted".to_string(),
+    }
+}
+
+/// Messages geth reports for halts that are not out of gas errors
+fn halt_message(reason: &HaltReason) -> String {
+    match reason {
+        HaltReason::OpcodeNotFound => "invalid opcode".to_string(),
+        HaltReason::InvalidFEOpcode => "invalid opcode: INVALID".to_string(),
+        HaltReason::InvalidJump => "invalid jump destination".to_string(),
+        HaltReason::NotActivated => "invalid opcode: not activated".to_string(),
+        HaltReason::StackUnderflow => "stack underflow".to_string(),
+        HaltReason::StackOverflow => "stack limit reached 1024".to_string(),
+        HaltReason::OutOfOffset => "return data out of bounds".to_string(),
+        HaltReason::CreateCollision => "contract address collision".to_string(),
+        HaltReason::CreateContractSizeLimit => "max code size exceeded".to_string(),
+        HaltReason::CreateContractStartingWithEF => {
+            "invalid code: must not begin with 0xef".to_string()
+        }
+        HaltReason::CreateInitCodeSizeLimit => "max initcode size exceeded".to_string(),
+        HaltReason::CallTooDeep => "max call depth exceeded".to_string(),
+        HaltReason::StateChangeDuringStaticCall => "write protection".to_string(),
+        HaltReason::OutOfFunds => "insufficient balance for transfer".to_string(),
+        reason => RpcInvalidTransactionError::EvmHalt(*reason).to_string(),
+    }
+}
+
+impl From<CallError> for ErrorObject<'static> {
+    fn from(err: CallError) -> Self {
+        match err {
+            CallError::Revert(output) => ErrorObject::owned(
+                EXECUTION_REVERTED_ERROR_CODE,
+                revert_message(&output),
+                // empty return data is left out, like geth does
+                (!output.is_empty()).then_some(output),
+            ),
+            // reported the way reth does, out of gas errors carry the gas limit the call ran with
+            CallError::Halt {
+                reason: reason @ (HaltReason::OutOfGas(_) | HaltReason::NonceOverflow),
+                gas_used,
+            } => RpcInvalidTransactionError::halt(reason, gas_used).into(),
+            err @ CallError::Halt { .. } => {
+                ErrorObject::owned::<()>(CALL_EXECUTION_FAILED_CODE, err.to_string(), None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::hex;
+    use revm::primitives::OutOfGasError;
+
+    use super::*;
+
+    #[test]
+    fn test_revert_reason() {
+        let output = Bytes::from(
+            Revert {
+                reason: "not allowed".to_string(),
+            }
+            .abi_encode(),
+        );
+        assert_eq!(
+            CallError::Revert(output).to_string(),
+            "execution reverted: not allowed"
+        );
+
+        // A custom error which happens to be valid utf8 is not decoded
+        let output = Bytes::from(hex!(
+            "110b3655000000000000000000000000000000000000000000000000000000000000002a"
+        ));
+        assert_eq!(
+            CallError::Revert(output.clone()).to_string(),
+            "execution reverted"
+        );
+        assert_eq!(
+            ErrorObject::from(CallError::Revert(output)),
+            ErrorObject::owned(
+                EXECUTION_REVERTED_ERROR_CODE,
+                "execution reverted",
+                Some("0x110b3655000000000000000000000000000000000000000000000000000000000000002a")
+            )
+        );
+
+        assert_eq!(
+            ErrorObject::from(CallError::Revert(Bytes::new())),
+            ErrorObject::owned::<()>(EXECUTION_REVERTED_ERROR_CODE, "execution reverted", None)
+        );
+    }
+
+    #[test]
+    fn test_halt_messages() {
+        assert_eq!(
+            ErrorObject::from(CallError::Halt {
+                reason: HaltReason::OutOfGas(OutOfGasError::Basic),
+                gas_used: 21000,
+            }),
+            RpcInvalidTransactionError::BasicOutOfGas(21000).into()
+        );
+        assert_eq!(
+            ErrorObject::from(CallError::Halt {
+                reason: HaltReason::InvalidFEOpcode,
+                gas_used: 100,
+            }),
+            ErrorObject::owned::<()>(CALL_EXECUTION_FAILED_CODE, "invalid opcode: INVALID", None)
+        );
+    }
+}
@@ -17,7 +17,27 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 /// The maximum number of blocks that can be queried in a single eth_getLogs request.
 pub const DEFAULT_MAX_BLOCKS_PER_FILTER: u64 = 1_000;
 /// The maximum number of logs that can be returned in a single eth_getLogs response.
-pub const DEFAULT_MAX_LOGS_PER_RESPONSE: usize = 5_000;
+pub const DEFAULT_MAX_LOGS_PER_RESPONSE: usize = 10_000;
+/// Error code of exceeded request limits, which clients use to split the queried range.
+pub const LIMIT_EXCEEDED_ERROR_CODE: i32 = -32005;
+
+/// Limits of a single eth_getLogs request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogsQueryLimits {
+    /// The maximum number of blocks that can be queried.
+    pub max_block_range: u64,
+    /// The maximum number of logs that can be returned, unless they are all from a single block.
+    pub max_logs_per_response: usize,
+}
+
+impl Default for LogsQueryLimits {
+    fn default() -> Self {
+        Self {
+            max_block_range: DEFAULT_MAX_BLOCKS_PER_FILTER,
+            max_logs_per_response: DEFAULT_MAX_LOGS_PER_RESPONSE,
+        }
+    }
+}
 /// The maximum number of headers we read at once when handling a range filter.
 pub const MAX_HEADERS_RANGE: u64 = 1_000; // with ~530bytes? per header this is ~500kb?
 
@@ -722,13 +742,14 @@ pub fn matches_address(bloom: Bloom, address_filter: &BloomFilter) -> bool {
 /// Errors that can occur in the handler implementation
 #[derive(Debug, thiserror::Error)]
 pub enum FilterError {
-    // #[error("filter not found")]
-    // FilterNotFound(FilterId),
+    /// The filter polled with `eth_getFilterChanges` was never installed, or has expired.
+    #[error("filter not found")]
+    FilterNotFound,
     /// There is a maximum number of blocks that can be queried in a single eth_getLogs request.
     #[error("query exceeds max block range {0}")]
     QueryExceedsMaxBlocks(u64),
     /// There is a maximum number of logs that can be returned in a single eth_getLogs response.
-    #[error("query exceeds max results {0}")]
+    #[error("query returned more than {0} results")]
     QueryExceedsMaxResults(usize),
     /// Error thrown when the eth api returns an error
     #[error(transparent)]
@@ -742,23 +763,21 @@ pub enum FilterError {
 impl From<FilterError> for jsonrpsee::types::error::ErrorObject<'static> {
     fn from(err: FilterError) -> Self {
         match err {
-            // FilterError::FilterNotFound(_) => rpc_error_with_code(
-            //     jsonrpsee::types::error::INVALID_PARAMS_CODE,
-            //     "filter not found",
-            // ),
-            err @ FilterError::InternalError => rpc_error_with_code(
-                jsonrpsee::types::error::INTERNAL_ERROR_CODE,
-                err.to_string(),
-            ),
-            FilterError::EthAPIError(err) => err.into(),
-            err @ FilterError::QueryExceedsMaxBlocks(_) => rpc_error_with_code(
+            err @ FilterError::FilterNotFound => rpc_error_with_code(
                 jsonrpsee::types::error::INVALID_PARAMS_CODE,
                 err.to_string(),
             ),
-            err @ FilterError::QueryExceedsMaxResults(_) => rpc_error_with_code(
-                jsonrpsee::types::error::INVALID_PARAMS_CODE,
+            err @ FilterError::InternalError => rpc_error_with_code(
+                jsonrpsee::types::error::INTERNAL_ERROR_CODE,
                 err.to_string(),
             ),
+            FilterError::EthAPIError(err) => err.into(),
+            err @ FilterError::QueryExceedsMaxBlocks(_) => {
+                rpc_error_with_code(LIMIT_EXCEEDED_ERROR_CODE, err.to_string())
+            }
+            err @ FilterError::QueryExceedsMaxResults(_) => {
+                rpc_error_with_code(LIMIT_EXCEEDED_ERROR_CODE, err.to_string())
+            }
         }
     }
 }
@@ -3,12 +3,14 @@ use std::collections::HashMap;
 use alloy_primitives::{keccak256, Address};
 use alloy_rpc_types::state::AccountOverride;
 use alloy_rpc_types::BlockOverrides;
+pub use call_error::*;
 pub use filter::*;
 pub use log_utils::*;
 pub use responses::*;
 use reth_rpc_eth_types::{EthApiError, EthResult};
 use revm::Database;
 
+mod call_error;
 mod filter;
 mod log_utils;
 mod responses;
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use alloy_primitives::{TxHash, U256};
 use alloy_rpc_types_trace::geth::{
     FourByteFrame, GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingOptions,
@@ -17,6 +19,49 @@ use crate::handler::{
     citrea_handle_register, CitreaExternal, CitreaExternalExt, TracingCitreaExternal, TxInfo,
 };
 
+/// Timeout applied to tracing requests that don't specify one, same as geth.
+pub(crate) const DEFAULT_TRACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Parses the `timeout` tracing option, a Go duration string such as `"300ms"` or `"1m30s"`.
+pub(crate) fn parse_trace_timeout(timeout: &str) -> EthResult<Duration> {
+    let invalid = || EthApiError::InvalidParams(format!("invalid tracing timeout: {timeout}"));
+
+    let mut rest = timeout.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    if rest == "0" {
+        return Ok(Duration::ZERO);
+    }
+
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let value_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(invalid)?;
+        let value: f64 = rest[..value_len].parse().map_err(|_| invalid())?;
+        rest = &rest[value_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let nanos_per_unit = match &rest[..unit_len] {
+            "ns" => 1.0,
+            "us" | "µs" => 1e3,
+            "ms" => 1e6,
+            "s" => 1e9,
+            "m" => 60e9,
+            "h" => 3600e9,
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit_len..];
+
+        total += Duration::from_nanos((value * nanos_per_unit) as u64);
+    }
+
+    Ok(total)
+}
+
 pub(crate) fn trace_transaction<C: sov_modules_api::Context>(
     opts: GethDebugTracingOptions,
     config_env: CfgEnvWithHandlerCfg,
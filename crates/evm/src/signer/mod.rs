@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use alloy_eips::eip7702::{Authorization, SignedAuthorization};
 use alloy_primitives::{Address, B256};
 use reth_primitives::{sign_message, Transaction, TransactionSigned};
 use reth_rpc_eth_types::SignError;
@@ -44,6 +45,23 @@ impl DevSigner {
         ))
     }
 
+    /// Signs an EIP-7702 authorization, delegating the code of `address` to `authorization.address`.
+    pub fn sign_authorization(
+        &self,
+        authorization: Authorization,
+        address: Address,
+    ) -> Result<SignedAuthorization, SignError> {
+        let signer = self.signers.get(&address).ok_or(SignError::NoAccount)?;
+
+        let signature = sign_message(
+            B256::from_slice(signer.as_ref()),
+            authorization.signature_hash(),
+        )
+        .map_err(|_| SignError::CouldNotSign)?;
+
+        Ok(authorization.into_signed(signature))
+    }
+
     /// List of signers.
     pub fn signers(&self) -> Vec<Address> {
         self.signers.keys().copied().collect()
@@ -10,6 +10,7 @@ mod kzg_point_evaluation_caller;
 mod logs_contract;
 mod mcopy_contract;
 mod payable_contract;
+mod reverter_contract;
 mod self_destructor_contract;
 mod selfdestructing_constructor;
 mod simple_storage_contract;
@@ -25,6 +26,7 @@ pub use kzg_point_evaluation_caller::KZGPointEvaluationCallerContract;
 pub use logs_contract::{AnotherLogEvent, LogEvent, LogsContract};
 pub use mcopy_contract::McopyContract;
 pub use payable_contract::SimplePayableContract;
+pub use reverter_contract::ReverterContract;
 pub use self_destructor_contract::SelfDestructorContract;
 pub use selfdestructing_constructor::SelfdestructingConstructorContract;
 pub use simple_storage_contract::SimpleStorageContract;
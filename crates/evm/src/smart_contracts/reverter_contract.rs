@@ -0,0 +1,51 @@
+use alloy_sol_types::{sol, SolCall};
+
+use super::TestContract;
+
+// Reverter wrapper.
+sol! {
+    #[sol(abi)]
+    Reverter,
+    "./src/evm/test_data/Reverter.abi"
+}
+
+/// ReverterContract wrapper.
+pub struct ReverterContract {
+    bytecode: Vec<u8>,
+}
+
+impl Default for ReverterContract {
+    fn default() -> Self {
+        let bytecode = {
+            let bytecode_hex = include_str!("../../../evm/src/evm/test_data/Reverter.bin");
+            hex::decode(bytecode_hex).unwrap()
+        };
+
+        Self { bytecode }
+    }
+}
+
+impl TestContract for ReverterContract {
+    fn byte_code(&self) -> Vec<u8> {
+        self.byte_code()
+    }
+}
+
+impl ReverterContract {
+    /// Reverter bytecode.
+    pub fn byte_code(&self) -> Vec<u8> {
+        self.bytecode.clone()
+    }
+    /// Calls Reverter::revertWithReason.
+    pub fn revert_with_reason(&self) -> Vec<u8> {
+        Reverter::revertWithReasonCall {}.abi_encode()
+    }
+    /// Calls Reverter::revertWithCustomError.
+    pub fn revert_with_custom_error(&self) -> Vec<u8> {
+        Reverter::revertWithCustomErrorCall {}.abi_encode()
+    }
+    /// Calls Reverter::invalidOpcode.
+    pub fn invalid_opcode(&self) -> Vec<u8> {
+        Reverter::invalidOpcodeCall {}.abi_encode()
+    }
+}
@@ -1,20 +1,23 @@
 use std::collections::BTreeMap;
 use std::str::FromStr;
 
+use alloy_eips::eip7702::{Authorization, SignedAuthorization};
 use alloy_eips::BlockId;
-use alloy_primitives::{address, b256, Address, Bytes, TxKind, B256, U64};
+use alloy_primitives::{address, b256, Address, Bytes, Signature, TxKind, B256, U128, U64};
 use alloy_rpc_types::{BlockOverrides, TransactionInput, TransactionRequest};
 use citrea_primitives::MIN_BASE_FEE_PER_GAS;
 use reth_primitives::constants::ETHEREUM_BLOCK_GAS_LIMIT;
 use reth_primitives::{BlockNumberOrTag, Log, LogData};
 use revm::primitives::SpecId::SHANGHAI;
-use revm::primitives::{hex, KECCAK_EMPTY, U256};
+use revm::primitives::{hex, Bytecode, KECCAK_EMPTY, U256};
 use revm::Database;
+use secp256k1::SecretKey;
 use sov_modules_api::default_context::DefaultContext;
 use sov_modules_api::hooks::HookSoftConfirmationInfo;
 use sov_modules_api::utils::generate_address;
 use sov_modules_api::{
-    Context, Module, SoftConfirmationModuleCallError, StateMapAccessor, StateVecAccessor,
+    CallResponse, Context, Module, SoftConfirmationModuleCallError, StateMapAccessor,
+    StateVecAccessor, WorkingSet,
 };
 use sov_rollup_interface::spec::SpecId as SovSpecId;
 
@@ -35,7 +38,8 @@ use crate::tests::utils::{
 };
 use crate::tests::DEFAULT_CHAIN_ID;
 use crate::{
-    AccountData, EvmConfig, RlpEvmTransaction, BASE_FEE_VAULT, L1_FEE_VAULT, PRIORITY_FEE_VAULT,
+    AccountData, Evm, EvmConfig, RlpEvmTransaction, BASE_FEE_VAULT, L1_FEE_VAULT,
+    PRIORITY_FEE_VAULT,
 };
 type C = DefaultContext;
 
@@ -842,16 +846,31 @@ fn test_block_hash_in_evm() {
 
     l2_height += 1;
 
+    // The fork activates on block 257, the first block evicting a hash from its window
+    let fork_activation_height = 257;
+
     for _i in 0..514 {
         // generate 514 more blocks
         let l1_fee_rate = 0;
+        let current_spec = if l2_height < fork_activation_height {
+            SovSpecId::Fork1
+        } else {
+            SovSpecId::Fork2
+        };
+
+        if l2_height == fork_activation_height + 1 {
+            // A stale hash below the window, as if an earlier eviction was missed
+            evm.latest_block_hashes
+                .set(&U256::from(0), &B256::repeat_byte(1), &mut working_set);
+        }
+
         let soft_confirmation_info = HookSoftConfirmationInfo {
             l2_height,
             da_slot_hash: [5u8; 32],
             da_slot_height: 1,
             da_slot_txs_commitment: [42u8; 32],
             pre_state_root: [99u8; 32].to_vec(),
-            current_spec: SovSpecId::Fork1,
+            current_spec,
             pub_key: vec![],
             deposit_data: vec![],
             l1_fee_rate,
@@ -861,6 +880,31 @@ fn test_block_hash_in_evm() {
         evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
         evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
 
+        // Whether the hashes at the edges of the window are kept
+        let window_edges: &[(u64, bool)] = if l2_height == fork_activation_height - 1 {
+            // The window of block 256 starts at genesis
+            &[(0, true), (255, true)]
+        } else if l2_height == fork_activation_height {
+            // The fork activation block evicts the genesis hash
+            &[(0, false), (1, true), (256, true)]
+        } else if l2_height == fork_activation_height + 1 {
+            // The stale hash is evicted along with the one leaving the window
+            &[(0, false), (1, false), (2, true), (257, true)]
+        } else {
+            &[]
+        };
+        for &(number, kept) in window_edges {
+            assert_eq!(
+                evm.latest_block_hashes
+                    .get(&U256::from(number), &mut working_set)
+                    .is_some(),
+                kept,
+                "Hash of block {} after block {}",
+                number,
+                l2_height
+            );
+        }
+
         l2_height += 1;
     }
 
@@ -1235,6 +1279,43 @@ fn test_l1_fee_success() {
         assert_eq!(coinbase_account.balance, expected_coinbase_balance);
         assert_eq!(l1_fee_vault.balance, expected_l1_fee_vault_balance);
 
+        // The L1 fee breakdown of the deploy transaction adds up to the charged balances
+        let tx_hash = evm
+            .transactions
+            .last(&mut working_set.accessory_state())
+            .unwrap()
+            .signed_transaction
+            .hash;
+        let l1_fee = evm
+            .citrea_get_transaction_l1_fee(tx_hash, &mut working_set)
+            .unwrap()
+            .unwrap();
+        assert_eq!(l1_fee.l1_diff_size, U64::from(52));
+        assert_eq!(l1_fee.l1_fee_rate, U128::from(l1_fee_rate));
+        assert_eq!(l1_fee.l1_fee_overhead, U64::from(L1_FEE_OVERHEAD));
+        assert!(l1_fee.compression_discount_active);
+        assert_eq!(l1_fee.l1_fee, expected_l1_fee_vault_balance);
+
+        let receipt = evm
+            .citrea_get_transaction_receipt(tx_hash, &mut working_set)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            U256::from_str("100000000000000").unwrap() - expected_balance,
+            U256::from(receipt.inner.gas_used) * U256::from(receipt.inner.effective_gas_price)
+                + l1_fee.l1_fee
+        );
+        assert_eq!(
+            receipt.other["l1Fee"],
+            format!("{:#x}", expected_l1_fee_vault_balance)
+        );
+        assert_eq!(receipt.other["l1DiffSize"], "0x34");
+        assert_eq!(
+            receipt.other["l1FeeOverhead"],
+            format!("{:#x}", L1_FEE_OVERHEAD)
+        );
+        assert_eq!(receipt.other["compressionDiscountActive"], true);
+
         assert_eq!(
             evm.receipts
                 .iter(&mut working_set.accessory_state())
@@ -1835,6 +1916,106 @@ fn test_l1_fee_compression_discount() {
     );
 }
 
+#[test]
+fn test_estimate_fee_ties_out_with_l1_fee() {
+    let (config, dev_signer, _) =
+        get_evm_config_starting_base_fee(U256::from_str("100000000000000").unwrap(), None, 1);
+
+    let (mut evm, mut working_set) = get_evm(&config);
+    let l1_fee_rate = 10;
+
+    let request = TransactionRequest {
+        from: Some(dev_signer.address()),
+        to: Some(TxKind::Create),
+        max_fee_per_gas: Some(20000000),
+        max_priority_fee_per_gas: Some(1),
+        input: TransactionInput::new(BlockHashContract::default().byte_code().into()),
+        ..Default::default()
+    };
+
+    // The latest block has zero l1 fee rate
+    let estimated = evm
+        .citrea_estimate_fee(
+            request.clone(),
+            Some(BlockNumberOrTag::Latest),
+            None,
+            &mut working_set,
+        )
+        .unwrap();
+    assert_eq!(estimated.l1_fee_rate, U128::ZERO);
+    assert_eq!(estimated.l1_fee, U256::ZERO);
+
+    let estimated = evm
+        .citrea_estimate_fee(
+            request,
+            Some(BlockNumberOrTag::Latest),
+            Some(U128::from(l1_fee_rate)),
+            &mut working_set,
+        )
+        .unwrap();
+    assert_eq!(estimated.l1_fee_rate, U128::from(l1_fee_rate));
+    assert_eq!(estimated.gas_price, U256::from(20000000));
+    assert_eq!(
+        estimated.l1_fee,
+        U256::from(l1_fee_rate)
+            * (U256::from(estimated.l1_diff_size) + U256::from(L1_FEE_OVERHEAD))
+    );
+    assert_eq!(
+        estimated.required_balance,
+        U256::from(estimated.gas) * estimated.gas_price + estimated.l1_fee
+    );
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height: 2,
+        da_slot_hash: [5u8; 32],
+        da_slot_height: 1,
+        da_slot_txs_commitment: [42u8; 32],
+        pre_state_root: [10u8; 32].to_vec(),
+        current_spec: SovSpecId::Fork1,
+        pub_key: vec![],
+        deposit_data: vec![],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    {
+        let sender_address = generate_address::<C>("sender");
+
+        let context = C::new(sender_address, 2, SovSpecId::Fork1, l1_fee_rate);
+
+        let deploy_message = create_contract_message_with_priority_fee(
+            &dev_signer,
+            0,
+            BlockHashContract::default(),
+            20000000,
+            1,
+        );
+
+        evm.call(
+            CallMessage {
+                txs: vec![deploy_message],
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+    }
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    // The L1 fee charged by call() is exactly the estimated one
+    let l1_fee_vault = evm.accounts.get(&L1_FEE_VAULT, &mut working_set).unwrap();
+    assert_eq!(l1_fee_vault.balance, estimated.l1_fee);
+
+    let receipt = evm
+        .receipts
+        .last(&mut working_set.accessory_state())
+        .unwrap();
+    assert_eq!(receipt.l1_diff_size, estimated.l1_diff_size.to::<u64>());
+    assert!(U64::from(receipt.gas_used) <= estimated.gas);
+}
+
 #[test]
 fn test_call_with_block_overrides() {
     let (config, dev_signer, contract_addr) =
@@ -1961,6 +2142,129 @@ fn test_call_with_block_overrides() {
     assert_eq!(call_result, expected_hash);
 }
 
+#[test]
+fn test_call_bundle() {
+    let (config, dev_signer, contract_addr) =
+        get_evm_config(U256::from_str("100000000000000000000").unwrap(), None);
+
+    let (mut evm, mut working_set) = get_evm(&config);
+    let l1_fee_rate = 0;
+    let mut l2_height = 2;
+
+    // Deploy simple storage and block hashes contracts
+    let block_hash_contract_addr = dev_signer.address().create(2);
+    let set_arg = 999;
+    for _i in 0..3 {
+        let soft_confirmation_info = HookSoftConfirmationInfo {
+            l2_height,
+            da_slot_hash: [5u8; 32],
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            current_spec: SovSpecId::Fork1,
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate,
+            timestamp: 0,
+        };
+        evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+        if l2_height == 2 {
+            let sender_address = generate_address::<C>("sender");
+            let context = C::new(sender_address, l2_height, SovSpecId::Fork1, l1_fee_rate);
+
+            let rlp_transactions = vec![
+                create_contract_message(&dev_signer, 0, SimpleStorageContract::default()),
+                set_arg_message(contract_addr, &dev_signer, 1, set_arg),
+                create_contract_message(&dev_signer, 2, BlockHashContract::default()),
+            ];
+
+            evm.call(
+                CallMessage {
+                    txs: rlp_transactions,
+                },
+                &context,
+                &mut working_set,
+            )
+            .unwrap();
+        }
+        evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+        evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+        l2_height += 1;
+    }
+
+    let get_request = TransactionRequest {
+        from: Some(dev_signer.address()),
+        to: Some(TxKind::Call(contract_addr)),
+        input: TransactionInput::new(SimpleStorageContract::default().get_call_data().into()),
+        ..Default::default()
+    };
+    let block_hash_request = TransactionRequest {
+        from: Some(dev_signer.address()),
+        to: Some(TxKind::Call(block_hash_contract_addr)),
+        input: TransactionInput::new(BlockHashContract::default().get_block_hash(1).into()),
+        ..Default::default()
+    };
+    let set_request = TransactionRequest {
+        from: Some(dev_signer.address()),
+        to: Some(TxKind::Call(contract_addr)),
+        input: TransactionInput::new(SimpleStorageContract::default().set_call_data(5).into()),
+        ..Default::default()
+    };
+    let calls = vec![
+        get_request.clone(),
+        block_hash_request.clone(),
+        set_request,
+        get_request.clone(),
+    ];
+
+    let stored_value = evm
+        .get_call(get_request, None, None, None, &mut working_set)
+        .unwrap();
+    assert_eq!(
+        stored_value,
+        Bytes::from(U256::from(set_arg).to_be_bytes::<32>())
+    );
+    let block_hash = evm
+        .get_call(block_hash_request, None, None, None, &mut working_set)
+        .unwrap();
+
+    // Independent calls are executed on the state of the block
+    let results = evm
+        .citrea_call_bundle(
+            calls.clone(),
+            None,
+            None,
+            None,
+            Some(true),
+            &mut working_set,
+        )
+        .unwrap();
+    assert_eq!(results.len(), 4);
+    assert!(results.iter().all(|result| result.success));
+    assert_eq!(results[0].return_data, stored_value);
+    assert_eq!(results[1].return_data, block_hash);
+    assert_eq!(results[3].return_data, stored_value);
+
+    let too_many_calls = vec![calls[0].clone(); crate::MAX_CALL_BUNDLE_SIZE + 1];
+    assert!(evm
+        .citrea_call_bundle(too_many_calls, None, None, None, None, &mut working_set)
+        .is_err());
+
+    // Calls see the writes of the calls before them
+    let results = evm
+        .citrea_call_bundle(calls, None, None, None, None, &mut working_set)
+        .unwrap();
+    assert_eq!(results.len(), 4);
+    assert!(results.iter().all(|result| result.success));
+    assert!(results.iter().all(|result| result.gas_used > U64::ZERO));
+    assert_eq!(results[0].return_data, stored_value);
+    assert_eq!(results[1].return_data, block_hash);
+    assert_eq!(
+        results[3].return_data,
+        Bytes::from(U256::from(5).to_be_bytes::<32>())
+    );
+}
+
 // TODO: test is not doing anything significant at the moment
 // after the cancun upgrade related issues are solved come back
 // and invoke point eval precompile
@@ -2008,3 +2312,249 @@ fn test_blob_tx() {
         );
     }
 }
+
+fn call_set_code_tx(
+    current_spec: SovSpecId,
+    authorization_list: Vec<SignedAuthorization>,
+) -> (
+    Evm<C>,
+    WorkingSet<<C as sov_modules_api::Spec>::Storage>,
+    Result<CallResponse, SoftConfirmationModuleCallError>,
+) {
+    let (config, dev_signer, _contract_addr) =
+        get_evm_config(U256::from_str("100000000000000000000").unwrap(), None);
+    let (mut evm, mut working_set) = get_evm_with_spec(&config, current_spec);
+
+    let l1_fee_rate = 1;
+    let l2_height = 2;
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height,
+        da_slot_hash: [5u8; 32],
+        da_slot_height: 1,
+        da_slot_txs_commitment: [42u8; 32],
+        pre_state_root: [10u8; 32].to_vec(),
+        current_spec,
+        pub_key: vec![],
+        deposit_data: vec![],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+
+    let sender_address = generate_address::<C>("sender");
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+
+    let context = C::new(sender_address, l2_height, current_spec, l1_fee_rate);
+    let set_code_message = dev_signer
+        .sign_set_code_transaction(Address::ZERO, authorization_list, 0)
+        .unwrap();
+
+    let result = evm.call(
+        CallMessage {
+            txs: vec![set_code_message],
+        },
+        &context,
+        &mut working_set,
+    );
+
+    (evm, working_set, result)
+}
+
+#[test]
+fn test_set_code_tx_before_fork() {
+    let authority = TestSigner::new(SecretKey::from_slice(&[7u8; 32]).unwrap());
+    let authorization = authority
+        .sign_authorization(address!("819c5497b157177315e1204f52e588b393771719"), 0)
+        .unwrap();
+
+    let (_, _, result) = call_set_code_tx(SovSpecId::Fork1, vec![authorization]);
+
+    assert_eq!(
+        result.unwrap_err(),
+        SoftConfirmationModuleCallError::EvmTxTypeNotSupported("EIP-7702".to_string())
+    );
+}
+
+#[test]
+fn test_set_code_tx_delegation() {
+    let authority = TestSigner::new(SecretKey::from_slice(&[7u8; 32]).unwrap());
+    let delegate = address!("819c5497b157177315e1204f52e588b393771719");
+    let authorization = authority.sign_authorization(delegate, 0).unwrap();
+
+    let (evm, mut working_set, result) = call_set_code_tx(SovSpecId::Fork2, vec![authorization]);
+    result.unwrap();
+
+    let pending_tx = evm.pending_transactions.last().unwrap().clone();
+    assert!(pending_tx.receipt.receipt.success);
+
+    // The authority account now points to the delegate code
+    let authority_info = evm
+        .accounts
+        .get(&authority.address(), &mut working_set)
+        .unwrap();
+    assert_eq!(authority_info.nonce, 1);
+    assert_eq!(
+        authority_info.code_hash,
+        Some(Bytecode::new_eip7702(delegate).hash_slow())
+    );
+
+    // Every authorization adds its bytes to the diff size
+    let second_authority = TestSigner::new(SecretKey::from_slice(&[8u8; 32]).unwrap());
+    let (evm_with_two, _, result) = call_set_code_tx(
+        SovSpecId::Fork2,
+        vec![
+            authority.sign_authorization(delegate, 0).unwrap(),
+            second_authority.sign_authorization(delegate, 0).unwrap(),
+        ],
+    );
+    result.unwrap();
+    assert!(
+        evm_with_two
+            .pending_transactions
+            .last()
+            .unwrap()
+            .receipt
+            .l1_diff_size
+            > pending_tx.receipt.l1_diff_size
+    );
+
+    // Set code transactions must carry at least one authorization
+    let (_, _, empty_result) = call_set_code_tx(SovSpecId::Fork2, vec![]);
+    assert_eq!(
+        empty_result.unwrap_err(),
+        SoftConfirmationModuleCallError::EvmTransactionExecutionError
+    );
+}
+
+#[test]
+fn test_set_code_tx_invalid_authorization_signature() {
+    let authority = TestSigner::new(SecretKey::from_slice(&[7u8; 32]).unwrap());
+    let delegate = address!("819c5497b157177315e1204f52e588b393771719");
+    let authorization = Authorization {
+        chain_id: DEFAULT_CHAIN_ID,
+        address: delegate,
+        nonce: 0,
+    }
+    .into_signed(Signature::from_rs_and_parity(U256::from(1), U256::from(1), false).unwrap());
+
+    let (evm, mut working_set, result) = call_set_code_tx(SovSpecId::Fork2, vec![authorization]);
+
+    // Invalid authorizations are skipped, the transaction itself is still executed
+    result.unwrap();
+    assert!(
+        evm.pending_transactions
+            .last()
+            .unwrap()
+            .receipt
+            .receipt
+            .success
+    );
+    assert_eq!(
+        evm.accounts.get(&authority.address(), &mut working_set),
+        None
+    );
+}
+
+/// Init code deploying a contract whose code is `code_size` zero bytes
+fn init_code_with_code_size(code_size: usize) -> Vec<u8> {
+    let [high, low] = (code_size as u16).to_be_bytes();
+    // PUSH2 code_size, PUSH1 0, RETURN
+    vec![0x61, high, low, 0x60, 0x00, 0xf3]
+}
+
+#[test]
+fn test_contract_code_size_limit() {
+    let code_size_limit = 1024;
+    let (mut config, dev_signer, _) =
+        get_evm_config_starting_base_fee(U256::from_str("100000000000000000000").unwrap(), None, 1);
+    config.limit_contract_code_size = Some(code_size_limit);
+
+    let (mut evm, mut working_set) = get_evm(&config);
+
+    let chain_config = evm.citrea_get_chain_config(&mut working_set).unwrap();
+    assert_eq!(
+        chain_config.limit_contract_code_size,
+        U64::from(code_size_limit)
+    );
+    assert_eq!(chain_config.chain_id, U64::from(config.chain_id));
+    assert_eq!(
+        chain_config.block_gas_limit,
+        U64::from(config.block_gas_limit)
+    );
+    assert_eq!(chain_config.base_fee_params, config.base_fee_params);
+
+    // Deploying over the limit fails with the code size error
+    let request = TransactionRequest {
+        from: Some(dev_signer.address()),
+        to: Some(TxKind::Create),
+        input: TransactionInput::new(init_code_with_code_size(code_size_limit + 1).into()),
+        ..Default::default()
+    };
+    let err = evm
+        .get_call(request, None, None, None, &mut working_set)
+        .unwrap_err();
+    assert_eq!(err.message(), "max code size exceeded");
+
+    let l1_fee_rate = 0;
+    let l2_height = 2;
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height,
+        da_slot_hash: [5u8; 32],
+        da_slot_height: 1,
+        da_slot_txs_commitment: [42u8; 32],
+        pre_state_root: [10u8; 32].to_vec(),
+        current_spec: SovSpecId::Fork1,
+        pub_key: vec![],
+        deposit_data: vec![],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    {
+        let sender_address = generate_address::<C>("sender");
+
+        let context = C::new(sender_address, l2_height, SovSpecId::Fork1, l1_fee_rate);
+
+        let txs = [code_size_limit, code_size_limit + 1]
+            .into_iter()
+            .enumerate()
+            .map(|(nonce, code_size)| {
+                dev_signer
+                    .sign_default_transaction(
+                        TxKind::Create,
+                        init_code_with_code_size(code_size),
+                        nonce as u64,
+                        0,
+                    )
+                    .unwrap()
+            })
+            .collect();
+
+        evm.call(CallMessage { txs }, &context, &mut working_set)
+            .unwrap();
+    }
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    let receipts = evm
+        .receipts
+        .iter(&mut working_set.accessory_state())
+        .collect::<Vec<_>>();
+    let [.., at_limit_receipt, over_limit_receipt] = receipts.as_slice() else {
+        panic!("Deployments must have receipts");
+    };
+    assert!(at_limit_receipt.receipt.success);
+    assert!(!over_limit_receipt.receipt.success);
+
+    let code = evm
+        .get_code(dev_signer.address().create(0), None, &mut working_set)
+        .unwrap();
+    assert_eq!(code.len(), code_size_limit);
+
+    let code = evm
+        .get_code(dev_signer.address().create(1), None, &mut working_set)
+        .unwrap();
+    assert!(code.is_empty());
+}
@@ -1,17 +1,21 @@
 use std::collections::BTreeMap;
 
+use alloy_eips::RpcBlockHash;
 use alloy_primitives::{address, b256, TxKind, U64};
 use alloy_rpc_types::{
     AnyNetworkBlock, AnyTransactionReceipt, TransactionInput, TransactionRequest,
 };
 use alloy_serde::OtherFields;
+use jsonrpsee::types::ErrorObjectOwned;
 use reth_primitives::{BlockId, BlockNumberOrTag};
 use reth_rpc_eth_types::EthApiError;
 use revm::primitives::{B256, U256};
 use serde_json::json;
+use sov_modules_api::WorkingSet;
 
 use crate::smart_contracts::SimpleStorageContract;
 use crate::tests::queries::init_evm;
+use crate::tests::utils::commit;
 
 #[test]
 fn get_block_by_hash_test() {
@@ -469,6 +473,75 @@ fn call_test() {
     // https://github.com/chainwayxyz/citrea/issues/134
 }
 
+#[test]
+fn state_queries_by_block_hash_test() {
+    let (evm, mut working_set, prover_storage, signer, _) = init_evm();
+    let contract_address = address!("819c5497b157177315e1204f52e588b393771719");
+
+    let query_state = |block_id: BlockId| {
+        let mut working_set = WorkingSet::new(prover_storage.clone());
+        (
+            evm.get_balance(signer.address(), Some(block_id), &mut working_set),
+            evm.get_transaction_count(signer.address(), Some(block_id), &mut working_set),
+            evm.get_code(contract_address, Some(block_id), &mut working_set),
+            evm.get_storage_at(
+                contract_address,
+                U256::ZERO,
+                Some(block_id),
+                &mut working_set,
+            ),
+            evm.get_proof(signer.address(), vec![], Some(block_id), &mut working_set),
+        )
+    };
+
+    let mut block_hashes = vec![];
+    for block_number in [1, 2] {
+        let block_hash = evm
+            .get_block_by_number(
+                Some(BlockNumberOrTag::Number(block_number)),
+                None,
+                &mut working_set,
+            )
+            .unwrap()
+            .unwrap()
+            .header
+            .hash;
+        block_hashes.push(block_hash);
+
+        let by_number = query_state(BlockId::Number(BlockNumberOrTag::Number(block_number)));
+        assert!(by_number.0.is_ok());
+        // EIP-1898 block parameter objects
+        let canonical_hash: BlockId = serde_json::from_value(json!({
+            "blockHash": block_hash,
+            "requireCanonical": true,
+        }))
+        .unwrap();
+        assert_eq!(query_state(canonical_hash), by_number);
+        assert_eq!(query_state(BlockId::Hash(block_hash.into())), by_number);
+    }
+    // The queries are made on the historical state of each block
+    assert_ne!(
+        query_state(BlockId::Hash(block_hashes[0].into())).1,
+        query_state(BlockId::Hash(block_hashes[1].into())).1
+    );
+
+    let unknown_hash = RpcBlockHash::from_hash(B256::from([1u8; 32]), Some(true));
+    assert_eq!(
+        query_state(BlockId::Hash(unknown_hash)).0,
+        Err(EthApiError::HeaderNotFound(unknown_hash.into()).into())
+    );
+
+    // Hashes of pruned blocks are not found anymore
+    evm.prune_accessory_state(1..=2, &mut working_set);
+    commit(working_set, prover_storage.clone());
+    let pruned_hash = RpcBlockHash::from_hash(block_hashes[0], Some(true));
+    let (balance, _, code, _, proof) = query_state(BlockId::Hash(pruned_hash));
+    let header_not_found: ErrorObjectOwned = EthApiError::HeaderNotFound(pruned_hash.into()).into();
+    assert_eq!(balance.unwrap_err(), header_not_found);
+    assert_eq!(code.unwrap_err(), header_not_found);
+    assert_eq!(proof.unwrap_err(), header_not_found);
+}
+
 fn check_against_third_block(block: &AnyNetworkBlock) {
     // details = false
     let inner_block = serde_json::from_value::<AnyNetworkBlock>(json!({
@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use alloy_eips::eip2930::{AccessList, AccessListItem, AccessListWithGasUsed};
-use alloy_primitives::{address, b256, Address, TxKind, U256};
+use alloy_primitives::{address, b256, Address, TxKind, U128, U256};
 use alloy_rpc_types::{TransactionInput, TransactionRequest};
 use jsonrpsee::core::RpcResult;
 use reth_primitives::BlockNumberOrTag;
@@ -11,10 +11,12 @@ use sov_modules_api::default_context::DefaultContext;
 use sov_modules_api::{Spec, WorkingSet};
 
 use crate::query::MIN_TRANSACTION_GAS;
-use crate::smart_contracts::{CallerContract, SimpleStorageContract};
-use crate::tests::queries::{init_evm, init_evm_single_block, init_evm_with_caller_contract};
+use crate::smart_contracts::{CallerContract, ReverterContract, SimpleStorageContract};
+use crate::tests::queries::{
+    init_evm, init_evm_single_block, init_evm_with_caller_contract, init_evm_with_reverter_contract,
+};
 use crate::tests::test_signer::TestSigner;
-use crate::{EstimatedDiffSize, Evm};
+use crate::{EstimatedDiffSize, EstimatedFee, Evm};
 
 type C = DefaultContext;
 
@@ -98,6 +100,25 @@ fn test_tx_request_fields_gas_fork1() {
             .unwrap()
     );
 
+    let estimated_fee = evm.citrea_estimate_fee(
+        tx_req_contract_call.clone(),
+        Some(BlockNumberOrTag::Latest),
+        Some(U128::from(10)),
+        &mut working_set,
+    );
+    assert_eq!(
+        estimated_fee.unwrap(),
+        serde_json::from_value::<EstimatedFee>(json![{
+            "gas": "0x6601",
+            "gasPrice": "0x64",
+            "l1FeeRate": "0xa",
+            "l1DiffSize": "0x1f",
+            "l1Fee": "0x154",
+            "requiredBalance": "0x27d9b8"
+        }])
+        .unwrap()
+    );
+
     let tx_req_no_gas = TransactionRequest {
         gas: None,
         ..tx_req_contract_call.clone()
@@ -439,6 +460,91 @@ fn test_pending_env() {
     assert_eq!(result_pending, result);
 }
 
+#[test]
+fn test_estimate_gas_revert_data() {
+    let (evm, mut working_set, signer, contract_address) = init_evm_with_reverter_contract();
+    let contract = ReverterContract::default();
+
+    let reason_data = "\"0x08c379a0\
+        0000000000000000000000000000000000000000000000000000000000000020\
+        0000000000000000000000000000000000000000000000000000000000000010\
+        52657665727465723a20726561736f6e00000000000000000000000000000000\"";
+    let custom_error_data =
+        "\"0x110b3655000000000000000000000000000000000000000000000000000000000000002a\"";
+
+    for (input, message, data) in [
+        (
+            contract.revert_with_reason(),
+            "execution reverted: Reverter: reason",
+            reason_data,
+        ),
+        (
+            contract.revert_with_custom_error(),
+            "execution reverted",
+            custom_error_data,
+        ),
+    ] {
+        let tx_req = TransactionRequest {
+            from: Some(signer.address()),
+            to: Some(TxKind::Call(contract_address)),
+            input: TransactionInput::new(input.into()),
+            ..Default::default()
+        };
+
+        let err = evm
+            .eth_estimate_gas(
+                tx_req.clone(),
+                Some(BlockNumberOrTag::Latest),
+                &mut working_set,
+            )
+            .unwrap_err();
+        assert_eq!(err.code(), 3);
+        assert_eq!(err.message(), message);
+        assert_eq!(err.data().unwrap().get(), data);
+        working_set.unset_archival_version();
+
+        let fee_err = evm
+            .citrea_estimate_fee(
+                tx_req.clone(),
+                Some(BlockNumberOrTag::Latest),
+                None,
+                &mut working_set,
+            )
+            .unwrap_err();
+        assert_eq!(fee_err, err);
+        working_set.unset_archival_version();
+
+        // With a gas limit the call is retried with the block gas limit, reverting the same way
+        let err_with_gas_limit = evm
+            .eth_estimate_gas(
+                TransactionRequest {
+                    gas: Some(100_000),
+                    ..tx_req
+                },
+                Some(BlockNumberOrTag::Latest),
+                &mut working_set,
+            )
+            .unwrap_err();
+        assert_eq!(err_with_gas_limit, err);
+        working_set.unset_archival_version();
+    }
+
+    let err = evm
+        .eth_estimate_gas(
+            TransactionRequest {
+                from: Some(signer.address()),
+                to: Some(TxKind::Call(contract_address)),
+                input: TransactionInput::new(contract.invalid_opcode().into()),
+                ..Default::default()
+            },
+            Some(BlockNumberOrTag::Latest),
+            &mut working_set,
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), -32000);
+    assert_eq!(err.message(), "invalid opcode: INVALID");
+}
+
 fn test_estimate_gas_with_input(
     evm: &Evm<C>,
     working_set: &mut WorkingSet<<C as Spec>::Storage>,
@@ -13,8 +13,8 @@ use sov_modules_api::hooks::HookSoftConfirmationInfo;
 use sov_modules_api::{Spec, WorkingSet};
 use sov_rollup_interface::spec::SpecId;
 
-use crate::smart_contracts::SimpleStorageContract;
-use crate::tests::queries::{init_evm, init_evm_single_block};
+use crate::smart_contracts::{ReverterContract, SimpleStorageContract};
+use crate::tests::queries::{init_evm, init_evm_single_block, init_evm_with_reverter_contract};
 use crate::tests::test_signer::TestSigner;
 use crate::Evm;
 
@@ -613,3 +613,58 @@ fn test_call_with_state_overrides() {
         U256::from(478).to_be_bytes_vec()
     );
 }
+
+#[test]
+fn test_eth_call_revert_data() {
+    let (evm, mut working_set, signer, contract_address) = init_evm_with_reverter_contract();
+    let contract = ReverterContract::default();
+
+    let mut call = |input: Vec<u8>| {
+        let result = evm.get_call(
+            TransactionRequest {
+                from: Some(signer.address()),
+                to: Some(TxKind::Call(contract_address)),
+                input: TransactionInput::new(input.into()),
+                ..Default::default()
+            },
+            Some(BlockId::Number(BlockNumberOrTag::Latest)),
+            None,
+            None,
+            &mut working_set,
+        );
+        working_set.unset_archival_version();
+        result.unwrap_err()
+    };
+
+    // Error(string) reason
+    let err = call(contract.revert_with_reason());
+    assert_eq!(err.code(), 3);
+    assert_eq!(err.message(), "execution reverted: Reverter: reason");
+    assert_eq!(
+        err.data().unwrap().get(),
+        "\"0x08c379a0\
+        0000000000000000000000000000000000000000000000000000000000000020\
+        0000000000000000000000000000000000000000000000000000000000000010\
+        52657665727465723a20726561736f6e00000000000000000000000000000000\""
+    );
+
+    // CustomError(42), which is not decoded into the message
+    let err = call(contract.revert_with_custom_error());
+    assert_eq!(err.code(), 3);
+    assert_eq!(err.message(), "execution reverted");
+    assert_eq!(
+        err.data().unwrap().get(),
+        "\"0x110b3655000000000000000000000000000000000000000000000000000000000000002a\""
+    );
+
+    // Unknown selector, reverting without data
+    let err = call(vec![0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(err.code(), 3);
+    assert_eq!(err.message(), "execution reverted");
+    assert!(err.data().is_none());
+
+    let err = call(contract.invalid_opcode());
+    assert_eq!(err.code(), -32000);
+    assert_eq!(err.message(), "invalid opcode: INVALID");
+    assert!(err.data().is_none());
+}
@@ -0,0 +1,259 @@
+use std::str::FromStr;
+
+use alloy_primitives::{Address, TxKind, U256};
+use reth_rpc_eth_types::EthApiError;
+use sov_modules_api::default_context::DefaultContext;
+use sov_modules_api::hooks::HookSoftConfirmationInfo;
+use sov_modules_api::utils::generate_address;
+use sov_modules_api::{Context, Module, Spec, WorkingSet};
+use sov_rollup_interface::spec::SpecId as SovSpecId;
+
+use crate::call::CallMessage;
+use crate::smart_contracts::{SimpleStorageContract, TestContract};
+use crate::tests::test_signer::TestSigner;
+use crate::tests::utils::{get_evm, get_evm_config_starting_base_fee};
+use crate::{
+    Evm, GasPriceOracleConfig, RlpEvmTransaction, DEFAULT_PRIORITY_FEE, MAX_FEE_HISTORY_BLOCK_COUNT,
+};
+
+type C = DefaultContext;
+
+const GWEI: u128 = 1_000_000_000;
+const MAX_FEE_PER_GAS: u128 = 100 * GWEI;
+
+fn produce_block(
+    evm: &mut Evm<C>,
+    working_set: &mut WorkingSet<<C as Spec>::Storage>,
+    l2_height: u64,
+    txs: Vec<RlpEvmTransaction>,
+) {
+    let l1_fee_rate = 1;
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height,
+        da_slot_hash: [5u8; 32],
+        da_slot_height: 1,
+        da_slot_txs_commitment: [42u8; 32],
+        pre_state_root: [l2_height as u8; 32].to_vec(),
+        current_spec: SovSpecId::Fork1,
+        pub_key: vec![],
+        deposit_data: vec![],
+        l1_fee_rate,
+        timestamp: l2_height,
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, working_set);
+    {
+        let sender_address = generate_address::<C>("sender");
+        let context = C::new(sender_address, l2_height, SovSpecId::Fork1, l1_fee_rate);
+        evm.call(CallMessage { txs }, &context, working_set)
+            .unwrap();
+    }
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, working_set);
+    evm.finalize_hook(
+        &[l2_height as u8 + 1; 32].into(),
+        &mut working_set.accessory_state(),
+    );
+}
+
+fn transfer(signer: &TestSigner, nonce: u64, priority_fee: u128) -> RlpEvmTransaction {
+    signer
+        .sign_default_transaction_with_priority_fee(
+            TxKind::Call(Address::random()),
+            vec![],
+            nonce,
+            0,
+            MAX_FEE_PER_GAS,
+            priority_fee,
+        )
+        .unwrap()
+}
+
+/// Creates evm instance with the following blocks on top of genesis and an empty block 1
+/// Block 2 has 3 transfers with priority fees 3, 1 and 2 gwei
+/// Block 3 has a contract deployment with 1 gwei and 2 transfers with 4 gwei priority fee
+/// Block 4 is empty
+fn init_evm_with_priority_fees() -> (Evm<C>, WorkingSet<<C as Spec>::Storage>) {
+    let (config, dev_signer, _) = get_evm_config_starting_base_fee(
+        U256::from_str("100000000000000000000").unwrap(),
+        None,
+        GWEI as u64,
+    );
+    let (mut evm, mut working_set) = get_evm(&config);
+
+    produce_block(
+        &mut evm,
+        &mut working_set,
+        2,
+        vec![
+            transfer(&dev_signer, 0, 3 * GWEI),
+            transfer(&dev_signer, 1, GWEI),
+            transfer(&dev_signer, 2, 2 * GWEI),
+        ],
+    );
+
+    let deploy_tx = dev_signer
+        .sign_default_transaction_with_priority_fee(
+            TxKind::Create,
+            SimpleStorageContract::default().byte_code(),
+            3,
+            0,
+            MAX_FEE_PER_GAS,
+            GWEI,
+        )
+        .unwrap();
+    produce_block(
+        &mut evm,
+        &mut working_set,
+        3,
+        vec![
+            deploy_tx,
+            transfer(&dev_signer, 4, 4 * GWEI),
+            transfer(&dev_signer, 5, 4 * GWEI),
+        ],
+    );
+
+    produce_block(&mut evm, &mut working_set, 4, vec![]);
+
+    (evm, working_set)
+}
+
+#[test]
+fn fee_history_rewards_equal_gas() {
+    let (evm, mut working_set) = init_evm_with_priority_fees();
+
+    let rewards = evm
+        .fee_history_rewards(2..=2, &[0., 10., 50., 90., 100.], &mut working_set)
+        .unwrap();
+
+    assert_eq!(
+        rewards,
+        vec![vec![GWEI, GWEI, 2 * GWEI, 3 * GWEI, 3 * GWEI]]
+    );
+}
+
+#[test]
+fn fee_history_rewards_weighted_by_gas_used() {
+    let (evm, mut working_set) = init_evm_with_priority_fees();
+
+    let rewards = evm
+        .fee_history_rewards(3..=3, &[10., 50., 99., 100.], &mut working_set)
+        .unwrap();
+
+    // The deployment uses more gas than both transfers together,
+    // so it covers the median even though most transactions pay 4 gwei
+    assert_eq!(rewards, vec![vec![GWEI, GWEI, 4 * GWEI, 4 * GWEI]]);
+}
+
+#[test]
+fn fee_history_rewards_multiple_blocks() {
+    let (evm, mut working_set) = init_evm_with_priority_fees();
+
+    let rewards = evm
+        .fee_history_rewards(2..=4, &[10., 50., 99.], &mut working_set)
+        .unwrap();
+
+    assert_eq!(
+        rewards,
+        vec![
+            vec![GWEI, 2 * GWEI, 3 * GWEI],
+            vec![GWEI, GWEI, 4 * GWEI],
+            // Empty block falls back to zero rewards
+            vec![0, 0, 0],
+        ]
+    );
+}
+
+#[test]
+fn fee_history_rewards_block_count_is_capped() {
+    let (evm, mut working_set) = init_evm_with_priority_fees();
+
+    let result = evm.fee_history_rewards(0..=MAX_FEE_HISTORY_BLOCK_COUNT, &[50.], &mut working_set);
+    assert!(matches!(result, Err(EthApiError::InvalidBlockRange)));
+
+    // Blocks that do not exist are rejected as well
+    let result = evm.fee_history_rewards(2..=5, &[50.], &mut working_set);
+    assert!(matches!(result, Err(EthApiError::InvalidBlockRange)));
+}
+
+#[test]
+fn suggest_priority_fee_samples_recent_blocks() {
+    let (evm, mut working_set) = init_evm_with_priority_fees();
+
+    // Tips of blocks 2 and 3 sorted: 1, 1, 2, 3, 4, 4 gwei
+    let config = GasPriceOracleConfig::default();
+    assert_eq!(
+        evm.suggest_priority_fee(&config, &mut working_set),
+        3 * GWEI
+    );
+
+    let config = GasPriceOracleConfig {
+        percentile: 0,
+        ..Default::default()
+    };
+    assert_eq!(evm.suggest_priority_fee(&config, &mut working_set), GWEI);
+
+    let config = GasPriceOracleConfig {
+        percentile: 100,
+        ..Default::default()
+    };
+    assert_eq!(
+        evm.suggest_priority_fee(&config, &mut working_set),
+        4 * GWEI
+    );
+}
+
+#[test]
+fn suggest_priority_fee_window_size() {
+    let (evm, mut working_set) = init_evm_with_priority_fees();
+
+    // Only the empty head block is sampled
+    let config = GasPriceOracleConfig {
+        blocks: 1,
+        ..Default::default()
+    };
+    assert_eq!(
+        evm.suggest_priority_fee(&config, &mut working_set),
+        DEFAULT_PRIORITY_FEE.saturating_to::<u128>()
+    );
+
+    let config = GasPriceOracleConfig {
+        blocks: 1,
+        default: Some(GWEI),
+        ..Default::default()
+    };
+    assert_eq!(evm.suggest_priority_fee(&config, &mut working_set), GWEI);
+
+    // Blocks 3 and 4, tips sorted: 1, 4, 4 gwei
+    let config = GasPriceOracleConfig {
+        blocks: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        evm.suggest_priority_fee(&config, &mut working_set),
+        4 * GWEI
+    );
+}
+
+#[test]
+fn suggest_priority_fee_ignore_and_max_price() {
+    let (evm, mut working_set) = init_evm_with_priority_fees();
+
+    // Tips under 2 gwei are left out: 2, 3, 4, 4 gwei
+    let config = GasPriceOracleConfig {
+        ignore_price: Some(2 * GWEI),
+        ..Default::default()
+    };
+    assert_eq!(
+        evm.suggest_priority_fee(&config, &mut working_set),
+        3 * GWEI
+    );
+
+    let config = GasPriceOracleConfig {
+        max_price: Some(2 * GWEI),
+        ..Default::default()
+    };
+    assert_eq!(
+        evm.suggest_priority_fee(&config, &mut working_set),
+        2 * GWEI
+    );
+}
@@ -0,0 +1,262 @@
+use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_serde::JsonStorageKey;
+use reth_primitives::{BlockId, BlockNumberOrTag};
+use sov_modules_api::default_context::DefaultContext;
+use sov_modules_api::hooks::HookSoftConfirmationInfo;
+use sov_modules_api::utils::generate_address;
+use sov_modules_api::{Context, Module, StateMapAccessor, WorkingSet};
+use sov_prover_storage_manager::{new_orphan_storage, SnapshotManager};
+use sov_rollup_interface::spec::SpecId as SovSpecId;
+use sov_state::storage::{StorageKey, StorageProof, StorageValue};
+use sov_state::{ProverStorage, Storage};
+
+use crate::call::CallMessage;
+use crate::evm::DbAccount;
+use crate::smart_contracts::SimpleStorageContract;
+use crate::tests::utils::{
+    commit, create_contract_transaction, get_evm_config, get_evm_test_config, set_arg_message,
+    GENESIS_STATE_ROOT,
+};
+use crate::{Evm, EvmConfig};
+
+type C = DefaultContext;
+type TestStorage = ProverStorage<SnapshotManager>;
+
+/// Commits the genesis of the given config and returns the working set on top of it
+fn init_evm_genesis(
+    config: &EvmConfig,
+) -> (Evm<C>, WorkingSet<TestStorage>, TestStorage, [u8; 32]) {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let storage = new_orphan_storage(tmpdir.path()).unwrap();
+    let mut working_set = WorkingSet::new(storage.clone());
+    let evm = Evm::<C>::default();
+    evm.genesis(config, &mut working_set);
+
+    let root = commit(working_set, storage.clone());
+
+    let mut working_set = WorkingSet::new(storage.clone());
+    evm.finalize_hook(&root.into(), &mut working_set.accessory_state());
+
+    (evm, working_set, storage, root)
+}
+
+/// Checks that the borsh encoded JMT proof opens to `value` for `key` under the state root of `version`
+fn verify_proof(
+    working_set: &WorkingSet<TestStorage>,
+    version: u64,
+    key: StorageKey,
+    value: Option<StorageValue>,
+    proof: &Bytes,
+) {
+    let root = working_set.get_root_hash(version).unwrap();
+    let proof = borsh::from_slice(proof).unwrap();
+
+    let (opened_key, opened_value) = TestStorage::open_proof(
+        root,
+        StorageProof {
+            key: key.clone(),
+            value: value.clone(),
+            proof,
+        },
+    )
+    .unwrap();
+    assert_eq!(opened_key, key);
+    assert_eq!(opened_value, value);
+}
+
+#[test]
+fn get_proof_against_genesis_state_root() {
+    let (evm, mut working_set, _, root) = init_evm_genesis(&get_evm_test_config());
+    assert_eq!(B256::from(root), *GENESIS_STATE_ROOT);
+
+    let address = Address::from([2u8; 20]);
+    let slot = B256::from(U256::from(0).to_be_bytes());
+    let empty_slot = B256::from(U256::from(1).to_be_bytes());
+
+    let proof = evm
+        .get_proof(
+            address,
+            vec![JsonStorageKey(slot), JsonStorageKey(empty_slot)],
+            Some(BlockId::Number(BlockNumberOrTag::Number(0))),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(proof.address, address);
+    assert_eq!(proof.nonce, 1);
+    assert_eq!(proof.storage_hash, *GENESIS_STATE_ROOT);
+    assert_eq!(proof.storage_proof[0].value, U256::from(0x4321));
+    assert_eq!(proof.storage_proof[1].value, U256::ZERO);
+
+    let account = evm.accounts.get(&address, &mut working_set).unwrap();
+    assert_eq!(proof.balance, account.balance);
+    assert_eq!(Some(proof.code_hash), account.code_hash);
+
+    // Genesis is committed at version 1
+    verify_proof(
+        &working_set,
+        1,
+        StorageKey::new(evm.accounts.prefix(), &address, evm.accounts.codec()),
+        Some(StorageValue::new(&account, evm.accounts.codec())),
+        &proof.account_proof[0],
+    );
+
+    let db_account = DbAccount::new(address);
+    verify_proof(
+        &working_set,
+        1,
+        StorageKey::new(
+            db_account.storage.prefix(),
+            &U256::from(0),
+            db_account.storage.codec(),
+        ),
+        Some(StorageValue::new(
+            &U256::from(0x4321),
+            db_account.storage.codec(),
+        )),
+        &proof.storage_proof[0].proof[0],
+    );
+    // Exclusion proof of the unset slot
+    verify_proof(
+        &working_set,
+        1,
+        StorageKey::new(
+            db_account.storage.prefix(),
+            &U256::from(1),
+            db_account.storage.codec(),
+        ),
+        None,
+        &proof.storage_proof[1].proof[0],
+    );
+
+    // Exclusion proof of an unknown account
+    let unknown_address = Address::from([9u8; 20]);
+    let proof = evm
+        .get_proof(unknown_address, vec![], None, &mut working_set)
+        .unwrap();
+    assert_eq!(proof.balance, U256::ZERO);
+    assert_eq!(proof.nonce, 0);
+    assert_eq!(proof.storage_hash, *GENESIS_STATE_ROOT);
+    verify_proof(
+        &working_set,
+        1,
+        StorageKey::new(
+            evm.accounts.prefix(),
+            &unknown_address,
+            evm.accounts.codec(),
+        ),
+        None,
+        &proof.account_proof[0],
+    );
+}
+
+#[test]
+fn get_proof_after_transaction() {
+    let (config, dev_signer, _) = get_evm_config(U256::from(10).pow(U256::from(20)), None);
+    let (mut evm, mut working_set, storage, genesis_root) = init_evm_genesis(&config);
+
+    let contract_addr = dev_signer.address().create(0);
+    let l1_fee_rate = 1;
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height: 1,
+        da_slot_hash: [5u8; 32],
+        da_slot_height: 1,
+        da_slot_txs_commitment: [42u8; 32],
+        pre_state_root: genesis_root.to_vec(),
+        current_spec: SovSpecId::Fork1,
+        pub_key: vec![],
+        deposit_data: vec![],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    {
+        let sender_address = generate_address::<C>("sender");
+        let context = C::new(sender_address, 1, SovSpecId::Fork1, l1_fee_rate);
+        let txs = vec![
+            create_contract_transaction(&dev_signer, 0, SimpleStorageContract::default()),
+            set_arg_message(contract_addr, &dev_signer, 1, 42),
+        ];
+        evm.call(CallMessage { txs }, &context, &mut working_set)
+            .unwrap();
+    }
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+
+    let root = commit(working_set, storage.clone());
+    let mut working_set = WorkingSet::new(storage.clone());
+    evm.finalize_hook(&root.into(), &mut working_set.accessory_state());
+
+    let slot = B256::from(U256::from(0).to_be_bytes());
+    let proof = evm
+        .get_proof(
+            contract_addr,
+            vec![JsonStorageKey(slot)],
+            Some(BlockId::Number(BlockNumberOrTag::Latest)),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(proof.storage_hash, B256::from(root));
+    assert_eq!(proof.nonce, 1);
+    assert_eq!(proof.storage_proof[0].value, U256::from(42));
+
+    let account = evm.accounts.get(&contract_addr, &mut working_set).unwrap();
+    // Block 1 is committed at version 2
+    verify_proof(
+        &working_set,
+        2,
+        StorageKey::new(evm.accounts.prefix(), &contract_addr, evm.accounts.codec()),
+        Some(StorageValue::new(&account, evm.accounts.codec())),
+        &proof.account_proof[0],
+    );
+
+    let db_account = DbAccount::new(contract_addr);
+    verify_proof(
+        &working_set,
+        2,
+        StorageKey::new(
+            db_account.storage.prefix(),
+            &U256::from(0),
+            db_account.storage.codec(),
+        ),
+        Some(StorageValue::new(
+            &U256::from(42),
+            db_account.storage.codec(),
+        )),
+        &proof.storage_proof[0].proof[0],
+    );
+
+    // The contract did not exist before the transaction
+    let proof = evm
+        .get_proof(
+            contract_addr,
+            vec![JsonStorageKey(slot)],
+            Some(BlockId::Number(BlockNumberOrTag::Number(0))),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(proof.storage_hash, B256::from(genesis_root));
+    assert_eq!(proof.nonce, 0);
+    assert_eq!(proof.storage_proof[0].value, U256::ZERO);
+    verify_proof(
+        &working_set,
+        1,
+        StorageKey::new(evm.accounts.prefix(), &contract_addr, evm.accounts.codec()),
+        None,
+        &proof.account_proof[0],
+    );
+}
+
+#[test]
+fn get_proof_unknown_block() {
+    let (evm, mut working_set, _, _) = init_evm_genesis(&get_evm_test_config());
+
+    let result = evm.get_proof(
+        Address::from([2u8; 20]),
+        vec![],
+        Some(BlockId::Number(BlockNumberOrTag::Number(5))),
+        &mut working_set,
+    );
+    assert!(result.is_err());
+}
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
-use alloy_primitives::b256;
+use alloy_primitives::{b256, Address};
+use alloy_sol_types::SolEvent;
 use reth_primitives::constants::ETHEREUM_BLOCK_GAS_LIMIT;
 use reth_primitives::BlockNumberOrTag;
 use reth_rpc_eth_types::EthApiError;
@@ -12,12 +13,16 @@ use sov_modules_api::{Context, Module, StateVecAccessor};
 use sov_rollup_interface::spec::SpecId;
 
 use crate::call::CallMessage;
-use crate::smart_contracts::LogsContract;
+use crate::evm::system_contracts::BitcoinLightClient;
+use crate::smart_contracts::{AnotherLogEvent, LogsContract};
 use crate::tests::queries::init_evm;
 use crate::tests::utils::{
     create_contract_message, get_evm, get_evm_config, publish_event_message,
 };
-use crate::{Filter, FilterBlockOption, FilterSet};
+use crate::{
+    EvmConfig, Filter, FilterBlockOption, FilterSet, LogsQueryLimits, CHECKED_LOG_BLOCKS,
+    SCANNED_LOG_BLOCKS,
+};
 
 type C = DefaultContext;
 
@@ -36,6 +41,7 @@ fn logs_for_filter_test() {
                 FilterSet::default(),
             ],
         },
+        &LogsQueryLimits::default(),
         &mut working_set,
     );
     assert_eq!(
@@ -56,6 +62,7 @@ fn logs_for_filter_test() {
                 FilterSet::default(),
             ],
         },
+        &LogsQueryLimits::default(),
         &mut working_set,
     );
 
@@ -149,7 +156,9 @@ fn log_filter_test_at_block_hash() {
         topics: topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
     // should get all the logs including system txs
     assert_eq!(rpc_logs.len(), 5);
 
@@ -161,7 +170,9 @@ fn log_filter_test_at_block_hash() {
         address: address.clone(),
         topics: topics.clone(),
     };
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
     // 1) should get all the logs with the contract address
     assert_eq!(rpc_logs.len(), 4);
 
@@ -182,7 +193,9 @@ fn log_filter_test_at_block_hash() {
         topics: topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
 
     // 2) should get the logs with the signature
     assert_eq!(rpc_logs.len(), 2);
@@ -202,7 +215,9 @@ fn log_filter_test_at_block_hash() {
         topics: topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
 
     // 3) should get only the first log with hello as message
     assert_eq!(rpc_logs.len(), 1);
@@ -224,7 +239,9 @@ fn log_filter_test_at_block_hash() {
         topics: topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
 
     // 3) should get the logs with hello and hi messages
     assert_eq!(rpc_logs.len(), 2);
@@ -242,7 +259,9 @@ fn log_filter_test_at_block_hash() {
         topics: topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
 
     // 4) should get the logs with given signature and hello message
     assert_eq!(rpc_logs.len(), 1);
@@ -266,7 +285,9 @@ fn log_filter_test_at_block_hash() {
         topics: topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
 
     // 5) should get the logs with given signatures and hello or hi messages, so in this case all logs with messages
     assert_eq!(rpc_logs.len(), 2);
@@ -341,7 +362,9 @@ fn log_filter_test_with_range() {
         topics: empty_topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
     assert_eq!(rpc_logs.len(), 8);
 
     let soft_confirmation_info = HookSoftConfirmationInfo {
@@ -388,7 +411,9 @@ fn log_filter_test_with_range() {
         topics: empty_topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set).unwrap();
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
     // In the last block we have 2 logs
     assert_eq!(rpc_logs.len(), 2);
 }
@@ -405,6 +430,11 @@ fn test_log_limits() {
 
     let (mut evm, mut working_set) = get_evm(&config);
 
+    let limits = LogsQueryLimits {
+        max_block_range: 1_000,
+        max_logs_per_response: 5_000,
+    };
+
     let l1_fee_rate = 1;
     let mut l2_height = 2;
 
@@ -490,13 +520,13 @@ fn test_log_limits() {
         topics: empty_topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set);
+    let rpc_logs = evm.eth_get_logs(filter, &limits, &mut working_set);
 
     assert!(rpc_logs.is_err());
     if let Err(rpc_err) = rpc_logs {
         assert_eq!(
             rpc_err.message(),
-            "query exceeds max results 5000".to_string()
+            "query returned more than 5000 results".to_string()
         );
     }
 
@@ -538,7 +568,7 @@ fn test_log_limits() {
         topics: empty_topics.clone(),
     };
 
-    let rpc_logs = evm.eth_get_logs(filter, &mut working_set);
+    let rpc_logs = evm.eth_get_logs(filter, &limits, &mut working_set);
 
     assert!(rpc_logs.is_err());
     assert_eq!(
@@ -546,3 +576,298 @@ fn test_log_limits() {
         "query exceeds max block range 1000".to_string()
     );
 }
+
+#[test]
+fn log_filter_skips_blocks_by_bloom() {
+    let (config, dev_signer, contract_addr) =
+        get_evm_config(U256::from_str("100000000000000000000").unwrap(), None);
+
+    let (mut evm, mut working_set) = get_evm(&config);
+
+    let l1_fee_rate = 1;
+    let mut nonce = 0;
+
+    // deploy the logs contract in block 2, then publish an event in every 50th block up to 300
+    for l2_height in 2..=300 {
+        let soft_confirmation_info = HookSoftConfirmationInfo {
+            l2_height,
+            da_slot_hash: [5u8; 32],
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            current_spec: SpecId::Fork1,
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate,
+            timestamp: 0,
+        };
+        evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+        let txs = if l2_height == 2 {
+            vec![create_contract_message(
+                &dev_signer,
+                nonce,
+                LogsContract::default(),
+            )]
+        } else if l2_height % 50 == 0 {
+            vec![publish_event_message(
+                contract_addr,
+                &dev_signer,
+                nonce,
+                "hello".to_string(),
+            )]
+        } else {
+            vec![]
+        };
+        if !txs.is_empty() {
+            nonce += 1;
+            let sender_address = generate_address::<C>("sender");
+            let context = C::new(sender_address, l2_height, SpecId::Fork1, l1_fee_rate);
+            evm.call(CallMessage { txs }, &context, &mut working_set)
+                .unwrap();
+        }
+        evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+        evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+    }
+
+    let empty_topics = [
+        FilterSet::default(),
+        FilterSet::default(),
+        FilterSet::default(),
+        FilterSet::default(),
+    ];
+    let filter = Filter {
+        block_option: crate::FilterBlockOption::Range {
+            from_block: Some(BlockNumberOrTag::Earliest),
+            to_block: Some(BlockNumberOrTag::Latest),
+        },
+        address: contract_addr.into(),
+        topics: empty_topics.clone(),
+    };
+
+    SCANNED_LOG_BLOCKS.with(|scanned| scanned.set(0));
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
+
+    // every publish emits two logs
+    assert_eq!(rpc_logs.len(), 12);
+    let mut block_numbers = rpc_logs
+        .iter()
+        .map(|log| log.block_number.unwrap().to::<u64>())
+        .collect::<Vec<_>>();
+    block_numbers.dedup();
+    assert_eq!(block_numbers, vec![50, 100, 150, 200, 250, 300]);
+    // only the receipts of blocks with matching blooms are read
+    assert_eq!(SCANNED_LOG_BLOCKS.with(|scanned| scanned.get()), 6);
+
+    // no block can contain logs of an unknown address
+    let filter = Filter {
+        block_option: crate::FilterBlockOption::Range {
+            from_block: Some(BlockNumberOrTag::Earliest),
+            to_block: Some(BlockNumberOrTag::Latest),
+        },
+        address: Address::repeat_byte(0xaa).into(),
+        topics: empty_topics,
+    };
+
+    SCANNED_LOG_BLOCKS.with(|scanned| scanned.set(0));
+    let rpc_logs = evm
+        .eth_get_logs(filter, &LogsQueryLimits::default(), &mut working_set)
+        .unwrap();
+    assert!(rpc_logs.is_empty());
+    assert_eq!(SCANNED_LOG_BLOCKS.with(|scanned| scanned.get()), 0);
+}
+
+#[test]
+fn log_filter_with_address_index() {
+    let (config, dev_signer, contract_addr) =
+        get_evm_config(U256::from_str("100000000000000000000").unwrap(), None);
+    let indexed_config = EvmConfig {
+        index_logs_by_address: true,
+        ..config.clone()
+    };
+
+    let (mut evm, mut working_set) = get_evm(&config);
+    let (mut indexed_evm, mut indexed_working_set) = get_evm(&indexed_config);
+
+    let l1_fee_rate = 1;
+    let mut nonce = 0;
+    let other_contract_addr = dev_signer.address().create(1);
+
+    // deploy two logs contracts in block 2, then publish events of the first contract in every
+    // 7th block and of the second one in every 11th block, with a new L1 block every 25 blocks
+    for l2_height in 2..=400 {
+        let soft_confirmation_info = HookSoftConfirmationInfo {
+            l2_height,
+            da_slot_hash: [(l2_height / 25 + 2) as u8; 32],
+            da_slot_height: l2_height / 25 + 2,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            current_spec: SpecId::Fork1,
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate,
+            timestamp: 0,
+        };
+        let mut txs = vec![];
+        if l2_height == 2 {
+            for _ in 0..2 {
+                txs.push(create_contract_message(
+                    &dev_signer,
+                    nonce,
+                    LogsContract::default(),
+                ));
+                nonce += 1;
+            }
+        }
+        for (contract, every) in [(contract_addr, 7), (other_contract_addr, 11)] {
+            if l2_height % every == 0 {
+                txs.push(publish_event_message(
+                    contract,
+                    &dev_signer,
+                    nonce,
+                    format!("hello {l2_height}"),
+                ));
+                nonce += 1;
+            }
+        }
+
+        for (evm, working_set) in [
+            (&mut evm, &mut working_set),
+            (&mut indexed_evm, &mut indexed_working_set),
+        ] {
+            evm.begin_soft_confirmation_hook(&soft_confirmation_info, working_set);
+            if !txs.is_empty() {
+                let sender_address = generate_address::<C>("sender");
+                let context = C::new(sender_address, l2_height, SpecId::Fork1, l1_fee_rate);
+                evm.call(CallMessage { txs: txs.clone() }, &context, working_set)
+                    .unwrap();
+            }
+            evm.end_soft_confirmation_hook(&soft_confirmation_info, working_set);
+            evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+        }
+    }
+
+    let empty_topics = [
+        FilterSet::default(),
+        FilterSet::default(),
+        FilterSet::default(),
+        FilterSet::default(),
+    ];
+    let mut another_log_topics = empty_topics.clone();
+    another_log_topics[0] = AnotherLogEvent::SIGNATURE_HASH.into();
+    let block_hash = evm
+        .blocks
+        .get(350, &mut working_set.accessory_state())
+        .unwrap()
+        .header
+        .hash();
+    let range = |from_block, to_block| FilterBlockOption::Range {
+        from_block: Some(from_block),
+        to_block: Some(to_block),
+    };
+
+    let filters = [
+        // addresses only
+        (
+            range(BlockNumberOrTag::Earliest, BlockNumberOrTag::Latest),
+            FilterSet::from(contract_addr),
+            empty_topics.clone(),
+        ),
+        (
+            range(BlockNumberOrTag::Number(150), BlockNumberOrTag::Number(351)),
+            FilterSet::from(vec![contract_addr, other_contract_addr]),
+            empty_topics.clone(),
+        ),
+        (
+            range(BlockNumberOrTag::Earliest, BlockNumberOrTag::Latest),
+            FilterSet::from(BitcoinLightClient::address()),
+            empty_topics.clone(),
+        ),
+        // addresses and topics
+        (
+            range(BlockNumberOrTag::Number(99), BlockNumberOrTag::Number(201)),
+            FilterSet::from(vec![other_contract_addr, BitcoinLightClient::address()]),
+            another_log_topics.clone(),
+        ),
+        (
+            FilterBlockOption::AtBlockHash(block_hash),
+            FilterSet::from(contract_addr),
+            another_log_topics,
+        ),
+        // no logs of the address
+        (
+            range(BlockNumberOrTag::Earliest, BlockNumberOrTag::Latest),
+            FilterSet::from(Address::repeat_byte(0xaa)),
+            empty_topics,
+        ),
+    ];
+
+    for (block_option, address, topics) in filters {
+        let filter = Filter {
+            block_option,
+            address,
+            topics,
+        };
+
+        CHECKED_LOG_BLOCKS.with(|checked| checked.set(0));
+        let logs = evm
+            .eth_get_logs(
+                filter.clone(),
+                &LogsQueryLimits::default(),
+                &mut working_set,
+            )
+            .unwrap();
+        let checked_blocks = CHECKED_LOG_BLOCKS.with(|checked| checked.get());
+
+        CHECKED_LOG_BLOCKS.with(|checked| checked.set(0));
+        let indexed_logs = indexed_evm
+            .eth_get_logs(
+                filter.clone(),
+                &LogsQueryLimits::default(),
+                &mut indexed_working_set,
+            )
+            .unwrap();
+        let indexed_checked_blocks = CHECKED_LOG_BLOCKS.with(|checked| checked.get());
+
+        assert_eq!(logs, indexed_logs, "{filter:?}");
+        if filter.get_block_hash().is_none() {
+            assert!(
+                indexed_checked_blocks < checked_blocks,
+                "{filter:?} checked {indexed_checked_blocks} of {checked_blocks} blocks"
+            );
+        }
+    }
+
+    // the logs of pruned blocks are removed from the index
+    indexed_evm.prune_accessory_state(1..=200, &mut indexed_working_set);
+    let filter = Filter {
+        block_option: range(BlockNumberOrTag::Number(201), BlockNumberOrTag::Latest),
+        address: FilterSet::from(contract_addr),
+        ..Default::default()
+    };
+    let logs = evm
+        .eth_get_logs(
+            filter.clone(),
+            &LogsQueryLimits::default(),
+            &mut working_set,
+        )
+        .unwrap();
+    let indexed_logs = indexed_evm
+        .eth_get_logs(
+            filter,
+            &LogsQueryLimits::default(),
+            &mut indexed_working_set,
+        )
+        .unwrap();
+    assert_eq!(logs, indexed_logs);
+    assert!(indexed_evm
+        .indexed_log_blocks(
+            &FilterSet::from(contract_addr),
+            0,
+            200,
+            &mut indexed_working_set
+        )
+        .unwrap()
+        .is_empty());
+}
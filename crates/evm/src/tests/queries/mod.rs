@@ -1,7 +1,11 @@
 mod basic_queries;
 mod estimate_gas_tests;
 mod evm_call_tests;
+mod fee_history_tests;
+mod get_proof_tests;
 mod log_tests;
+mod pruning_tests;
+mod trace_tests;
 
 use std::str::FromStr;
 
@@ -17,7 +21,7 @@ use sov_state::ProverStorage;
 
 use crate::call::CallMessage;
 use crate::smart_contracts::{
-    CallerContract, LogsContract, SimplePayableContract, SimpleStorageContract,
+    CallerContract, LogsContract, ReverterContract, SimplePayableContract, SimpleStorageContract,
 };
 use crate::tests::test_signer::TestSigner;
 use crate::tests::utils::{
@@ -391,3 +395,73 @@ pub fn init_evm_with_caller_contract() -> (Evm<C>, WorkingSet<<C as Spec>::Stora
 
     (evm, working_set, dev_signer, l2_height)
 }
+
+/// Creates evm instance with the reverter contract deployed in block 1
+pub fn init_evm_with_reverter_contract() -> (
+    Evm<C>,
+    WorkingSet<<C as Spec>::Storage>,
+    TestSigner,
+    Address,
+) {
+    let dev_signer: TestSigner = TestSigner::new_random();
+
+    let mut config = EvmConfig {
+        data: vec![AccountData {
+            address: dev_signer.address(),
+            balance: U256::from_str("100000000000000000000").unwrap(), // Setting initial balance
+            code_hash: KECCAK_EMPTY,
+            code: Bytes::default(),
+            nonce: 0,
+            storage: Default::default(),
+        }],
+        ..Default::default()
+    };
+    config_push_contracts(&mut config, None);
+    let (mut evm, mut working_set, prover_storage) = get_evm_with_storage(&config);
+
+    let l1_fee_rate = 1;
+    let l2_height = 1;
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height,
+        da_slot_hash: [1u8; 32],
+        da_slot_height: 1,
+        da_slot_txs_commitment: [42u8; 32],
+        pre_state_root: [0u8; 32].to_vec(),
+        current_spec: SovSpecId::Fork1,
+        pub_key: vec![],
+        deposit_data: vec![],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+
+    {
+        let sender_address = generate_address::<C>("sender");
+
+        let context = C::new(sender_address, l2_height, SovSpecId::Fork1, l1_fee_rate);
+
+        let transactions: Vec<RlpEvmTransaction> = vec![create_contract_transaction(
+            &dev_signer,
+            0,
+            ReverterContract::default(),
+        )];
+
+        evm.call(
+            CallMessage { txs: transactions },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+    }
+
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[2u8; 32].into(), &mut working_set.accessory_state());
+
+    commit(working_set, prover_storage.clone());
+
+    let working_set = WorkingSet::new(prover_storage);
+    let contract_address = dev_signer.address().create(0);
+
+    (evm, working_set, dev_signer, contract_address)
+}
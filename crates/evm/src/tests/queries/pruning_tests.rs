@@ -0,0 +1,120 @@
+use alloy_primitives::U64;
+use reth_primitives::{BlockId, BlockNumberOrTag};
+use reth_rpc_eth_types::EthApiError;
+use revm::primitives::U256;
+use sov_modules_api::WorkingSet;
+
+use crate::tests::queries::init_evm;
+use crate::tests::utils::commit;
+
+#[test]
+fn prune_accessory_state_test() {
+    let (evm, mut working_set, prover_storage, _, _) = init_evm();
+
+    let second_block = evm
+        .get_block_by_number(
+            Some(BlockNumberOrTag::Number(1)),
+            Some(false),
+            &mut working_set,
+        )
+        .unwrap()
+        .unwrap();
+    let pruned_tx_hash = second_block.inner.transactions.as_hashes().unwrap()[0];
+    let third_block_hash = evm
+        .get_block_by_number(
+            Some(BlockNumberOrTag::Number(2)),
+            Some(false),
+            &mut working_set,
+        )
+        .unwrap()
+        .unwrap()
+        .inner
+        .header
+        .hash;
+
+    // Head block is never pruned even if the range covers it
+    evm.prune_accessory_state(1..=3, &mut working_set);
+    commit(working_set, prover_storage.clone());
+
+    let mut working_set = WorkingSet::new(prover_storage);
+
+    for block_number in [1, 2] {
+        assert_eq!(
+            evm.get_block_by_number(
+                Some(BlockNumberOrTag::Number(block_number)),
+                Some(false),
+                &mut working_set,
+            ),
+            Err(EthApiError::PrunedHistoryUnavailable.into())
+        );
+        assert_eq!(
+            evm.get_block_receipts(
+                BlockId::Number(BlockNumberOrTag::Number(block_number)),
+                &mut working_set,
+            ),
+            Err(EthApiError::PrunedHistoryUnavailable.into())
+        );
+        assert_eq!(
+            evm.get_transaction_by_block_number_and_index(
+                BlockNumberOrTag::Number(block_number),
+                U64::from(0),
+                &mut working_set,
+            ),
+            Err(EthApiError::PrunedHistoryUnavailable.into())
+        );
+    }
+
+    // Hash indexes of the pruned blocks and transactions are removed
+    assert_eq!(
+        evm.get_block_by_hash(third_block_hash, Some(false), &mut working_set),
+        Ok(None)
+    );
+    assert!(evm
+        .get_transaction_receipt(pruned_tx_hash, &mut working_set)
+        .unwrap()
+        .is_none());
+    assert!(evm
+        .get_transaction_by_hash(pruned_tx_hash, &mut working_set)
+        .unwrap()
+        .is_none());
+
+    // Genesis and head blocks are retained
+    let genesis_block = evm
+        .get_block_by_number(Some(BlockNumberOrTag::Earliest), None, &mut working_set)
+        .unwrap()
+        .unwrap();
+    assert_eq!(genesis_block.inner.header.number, 0);
+
+    let head_block = evm
+        .get_block_by_number(
+            Some(BlockNumberOrTag::Number(3)),
+            Some(true),
+            &mut working_set,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(head_block.inner.transactions.len(), 2);
+    assert_eq!(
+        evm.get_block_by_hash(head_block.inner.header.hash, None, &mut working_set)
+            .unwrap()
+            .unwrap()
+            .inner
+            .header
+            .number,
+        3
+    );
+
+    let head_receipts = evm
+        .get_block_receipts(BlockId::Number(BlockNumberOrTag::Latest), &mut working_set)
+        .unwrap()
+        .unwrap();
+    assert_eq!(head_receipts.len(), 2);
+    for receipt in head_receipts {
+        assert!(evm
+            .get_transaction_receipt(receipt.transaction_hash, &mut working_set)
+            .unwrap()
+            .is_some());
+    }
+
+    assert_eq!(evm.block_number(&mut working_set), Ok(U256::from(3)));
+}
@@ -0,0 +1,310 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use alloy_primitives::{Address, Bytes, TxKind};
+use alloy_rpc_types_trace::geth::{
+    CallConfig, GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingOptions,
+    GethTrace, TraceResult,
+};
+use reth_rpc_eth_types::EthApiError;
+use revm::primitives::{KECCAK_EMPTY, U256};
+use sov_modules_api::hooks::HookSoftConfirmationInfo;
+use sov_modules_api::utils::generate_address;
+use sov_modules_api::{Context, Module, WorkingSet};
+use sov_rollup_interface::spec::SpecId as SovSpecId;
+
+use super::{Storage, C};
+use crate::call::CallMessage;
+use crate::smart_contracts::{LogsContract, SelfDestructorContract};
+use crate::tests::test_signer::TestSigner;
+use crate::tests::utils::{
+    commit, config_push_contracts, create_contract_transaction, get_evm_with_storage,
+    publish_event_message,
+};
+use crate::{AccountData, Evm, EvmConfig, RlpEvmTransaction};
+
+fn produce_block(
+    evm: &mut Evm<C>,
+    mut working_set: WorkingSet<Storage>,
+    prover_storage: &Storage,
+    l2_height: u64,
+    transactions: Vec<RlpEvmTransaction>,
+) {
+    let l1_fee_rate = 1;
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height,
+        da_slot_hash: [l2_height as u8; 32],
+        da_slot_height: 1,
+        da_slot_txs_commitment: [42u8; 32],
+        pre_state_root: [l2_height as u8 + 10; 32].to_vec(),
+        current_spec: SovSpecId::Genesis,
+        pub_key: vec![],
+        deposit_data: vec![],
+        l1_fee_rate,
+        timestamp: 24,
+    };
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+
+    let sender_address = generate_address::<C>("sender");
+    let context = C::new(sender_address, l2_height, SovSpecId::Genesis, l1_fee_rate);
+    evm.call(
+        CallMessage { txs: transactions },
+        &context,
+        &mut working_set,
+    )
+    .unwrap();
+
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(
+        &[l2_height as u8 + 11; 32].into(),
+        &mut working_set.accessory_state(),
+    );
+
+    commit(working_set, prover_storage.clone());
+}
+
+/// Deploys `SelfDestructor` and `Logs` in block 1,
+/// then calls `Logs` successfully and `SelfDestructor` with a reverting call in block 2.
+fn init_evm_with_traced_block() -> (Evm<C>, Storage, Address, Address, TestSigner) {
+    let dev_signer: TestSigner = TestSigner::new_random();
+
+    let mut config = EvmConfig {
+        data: vec![AccountData {
+            address: dev_signer.address(),
+            balance: U256::from_str("100000000000000000000").unwrap(),
+            code_hash: KECCAK_EMPTY,
+            code: Bytes::default(),
+            nonce: 0,
+            storage: Default::default(),
+        }],
+        ..Default::default()
+    };
+    config_push_contracts(&mut config, None);
+    let (mut evm, working_set, prover_storage) = get_evm_with_storage(&config);
+
+    let self_destructor_address = dev_signer.address().create(0);
+    let logs_address = dev_signer.address().create(1);
+
+    produce_block(
+        &mut evm,
+        working_set,
+        &prover_storage,
+        1,
+        vec![
+            create_contract_transaction(&dev_signer, 0, SelfDestructorContract::default()),
+            create_contract_transaction(&dev_signer, 1, LogsContract::default()),
+        ],
+    );
+
+    produce_block(
+        &mut evm,
+        WorkingSet::new(prover_storage.clone()),
+        &prover_storage,
+        2,
+        vec![
+            publish_event_message(logs_address, &dev_signer, 2, "hello".to_string()),
+            // SelfDestructor has no `publishEvent` and no fallback, so this call reverts
+            publish_event_message(self_destructor_address, &dev_signer, 3, "hi".to_string()),
+        ],
+    );
+
+    (
+        evm,
+        prover_storage,
+        self_destructor_address,
+        logs_address,
+        dev_signer,
+    )
+}
+
+fn call_tracer_opts(with_log: bool) -> GethDebugTracingOptions {
+    GethDebugTracingOptions::default()
+        .with_tracer(GethDebugTracerType::BuiltInTracer(
+            GethDebugBuiltInTracerType::CallTracer,
+        ))
+        .with_call_config(CallConfig {
+            only_top_call: None,
+            with_log: Some(with_log),
+        })
+}
+
+#[test]
+fn trace_block_with_call_tracer() {
+    let (evm, prover_storage, self_destructor_address, logs_address, dev_signer) =
+        init_evm_with_traced_block();
+    let mut working_set = WorkingSet::new(prover_storage);
+
+    let traces = evm
+        .trace_block_transactions_by_number(2, Some(call_tracer_opts(true)), None, &mut working_set)
+        .unwrap();
+    assert_eq!(traces.len(), 2);
+
+    let frames: Vec<_> = traces
+        .into_iter()
+        .map(|trace| match trace {
+            TraceResult::Success {
+                result: GethTrace::CallTracer(frame),
+                ..
+            } => frame,
+            _ => panic!("Expected call tracer result"),
+        })
+        .collect();
+
+    let contract = LogsContract::default();
+    let input = contract.publish_event("hello".to_string());
+
+    let success = &frames[0];
+    assert_eq!(success.from, dev_signer.address());
+    assert_eq!(success.to, Some(logs_address));
+    assert_eq!(success.input, Bytes::from(input));
+    assert_eq!(success.typ, "CALL");
+    assert!(success.error.is_none());
+    assert!(success.revert_reason.is_none());
+    assert_eq!(success.logs.len(), 2);
+    assert!(success
+        .logs
+        .iter()
+        .all(|log| log.address == Some(logs_address)));
+
+    let failure = &frames[1];
+    assert_eq!(failure.from, dev_signer.address());
+    assert_eq!(failure.to, Some(self_destructor_address));
+    assert_eq!(failure.error.as_deref(), Some("execution reverted"));
+    // The contract reverts without any data, so there is no reason to decode
+    assert!(failure.revert_reason.is_none());
+    assert!(failure.logs.is_empty());
+    assert!(failure.calls.is_empty());
+}
+
+#[test]
+fn trace_transaction_with_struct_logger() {
+    let (evm, prover_storage, _, _, _) = init_evm_with_traced_block();
+    let mut working_set = WorkingSet::new(prover_storage);
+
+    // Stop after the reverting transaction, traced with the default struct logger
+    let traces = evm
+        .trace_block_transactions_by_number(2, None, Some(1), &mut working_set)
+        .unwrap();
+    assert_eq!(traces.len(), 2);
+
+    let TraceResult::Success {
+        result: GethTrace::Default(success),
+        ..
+    } = &traces[0]
+    else {
+        panic!("Expected struct logger result");
+    };
+    assert!(!success.failed);
+    assert!(success.struct_logs.iter().any(|log| log.op == "LOG4"));
+
+    let TraceResult::Success {
+        result: GethTrace::Default(failure),
+        ..
+    } = &traces[1]
+    else {
+        panic!("Expected struct logger result");
+    };
+    assert!(failure.failed);
+    assert_eq!(failure.struct_logs.last().unwrap().op, "REVERT");
+}
+
+#[test]
+fn trace_with_timeout() {
+    let (evm, prover_storage, _, _, _) = init_evm_with_traced_block();
+
+    let traces = evm
+        .trace_block_transactions_by_number(
+            2,
+            Some(call_tracer_opts(false).with_timeout(Duration::from_secs(10))),
+            None,
+            &mut WorkingSet::new(prover_storage.clone()),
+        )
+        .unwrap();
+    assert_eq!(traces.len(), 2);
+
+    let timed_out = evm
+        .trace_block_transactions_by_number(
+            2,
+            Some(GethDebugTracingOptions {
+                timeout: Some("0s".to_string()),
+                ..call_tracer_opts(false)
+            }),
+            None,
+            &mut WorkingSet::new(prover_storage.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        timed_out,
+        EthApiError::ExecutionTimedOut(Duration::ZERO).into()
+    );
+
+    let invalid = evm.trace_block_transactions_by_number(
+        2,
+        Some(GethDebugTracingOptions {
+            timeout: Some("ten seconds".to_string()),
+            ..call_tracer_opts(false)
+        }),
+        None,
+        &mut WorkingSet::new(prover_storage),
+    );
+    assert!(invalid.is_err());
+}
+
+#[test]
+fn trace_unknown_or_pruned_block() {
+    let (mut evm, prover_storage, self_destructor_address, _, dev_signer) =
+        init_evm_with_traced_block();
+
+    // Block 3 makes block 2 prunable, as the head block is never pruned
+    let set_tx = dev_signer
+        .sign_default_transaction(
+            TxKind::Call(self_destructor_address),
+            SelfDestructorContract::default().set_call_data(5),
+            4,
+            0,
+        )
+        .unwrap();
+    produce_block(
+        &mut evm,
+        WorkingSet::new(prover_storage.clone()),
+        &prover_storage,
+        3,
+        vec![set_tx],
+    );
+
+    let unknown = evm
+        .trace_block_transactions_by_number(
+            10,
+            Some(call_tracer_opts(true)),
+            None,
+            &mut WorkingSet::new(prover_storage.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(unknown, EthApiError::HeaderNotFound(10.into()).into());
+
+    let mut working_set = WorkingSet::new(prover_storage.clone());
+    evm.prune_accessory_state(1..=2, &mut working_set);
+    commit(working_set, prover_storage.clone());
+
+    let pruned = evm
+        .trace_block_transactions_by_number(
+            2,
+            Some(call_tracer_opts(true)),
+            None,
+            &mut WorkingSet::new(prover_storage.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(pruned, EthApiError::PrunedHistoryUnavailable.into());
+
+    // The head block can still be traced
+    let traces = evm
+        .trace_block_transactions_by_number(
+            3,
+            Some(call_tracer_opts(true)),
+            None,
+            &mut WorkingSet::new(prover_storage),
+        )
+        .unwrap();
+    assert_eq!(traces.len(), 1);
+}
@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 use alloy_primitives::{address, b256, hex, LogData, TxKind, U64};
 use alloy_rpc_types::{TransactionInput, TransactionRequest};
+use alloy_sol_types::SolCall;
 use reth_primitives::constants::ETHEREUM_BLOCK_GAS_LIMIT;
 use reth_primitives::{BlockNumberOrTag, Log};
 use revm::primitives::{Bytes, KECCAK_EMPTY, U256};
@@ -19,13 +20,16 @@ use crate::evm::primitive_types::Receipt;
 use crate::evm::system_contracts::BitcoinLightClient;
 use crate::handler::L1_FEE_OVERHEAD;
 use crate::smart_contracts::{BlockHashContract, LogsContract};
-use crate::system_contracts::{BridgeWrapper, ProxyAdmin};
+use crate::system_contracts::{BridgeContract, BridgeWrapper, ProxyAdmin};
 use crate::tests::test_signer::TestSigner;
 use crate::tests::utils::{
     config_push_contracts, create_contract_message, create_contract_message_with_fee, get_evm,
     get_evm_config_starting_base_fee, publish_event_message,
 };
-use crate::{AccountData, BASE_FEE_VAULT, L1_FEE_VAULT, SYSTEM_SIGNER};
+use crate::{
+    AccountData, BASE_FEE_VAULT, BRIDGE_GENESIS_BALANCE, L1_FEE_VAULT, PRIORITY_FEE_VAULT,
+    SYSTEM_SIGNER,
+};
 
 type C = DefaultContext;
 
@@ -490,6 +494,51 @@ fn test_sys_tx_gas_usage_effect_on_block_gas_limit() {
     );
 }
 
+/// Params of a valid deposit of 10 ether to 0x0101010101010101010101010101010101010101,
+/// included in L1 block 2 with hash `[2; 32]`
+fn bridge_deposit_params() -> Vec<u8> {
+    vec![
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 32, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 1, 32, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 1, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 42, 1, 145, 22, 58, 104, 30,
+        248, 81, 242, 63, 79, 72, 216, 243, 241, 44, 60, 88, 230, 44, 206, 194, 243, 103, 224, 237,
+        31, 108, 29, 207, 112, 110, 94, 1, 0, 0, 0, 0, 253, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 87, 2, 248, 199, 154, 59, 0, 0, 0, 0, 34, 81,
+        32, 180, 253, 103, 250, 242, 234, 221, 209, 124, 86, 77, 184, 249, 147, 86, 132, 180, 238,
+        191, 207, 88, 164, 131, 206, 164, 3, 244, 185, 120, 165, 30, 115, 74, 1, 0, 0, 0, 0, 0, 0,
+        34, 0, 32, 74, 232, 21, 114, 240, 110, 27, 136, 253, 92, 237, 122, 26, 0, 9, 69, 67, 46,
+        131, 225, 85, 30, 111, 114, 30, 233, 192, 11, 140, 195, 50, 96, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 207, 3, 64, 161, 192, 181, 26, 246, 26, 75, 97, 75, 195, 25, 148, 167, 73, 18, 169, 134,
+        223, 209, 191, 199, 220, 243, 38, 223, 51, 57, 71, 136, 182, 41, 246, 233, 200, 87, 9, 234,
+        172, 247, 185, 237, 10, 63, 152, 75, 134, 182, 168, 7, 69, 187, 91, 93, 123, 216, 163, 176,
+        231, 145, 122, 34, 105, 83, 11, 74, 32, 159, 179, 169, 97, 216, 177, 244, 236, 28, 170, 34,
+        12, 106, 80, 184, 21, 254, 188, 11, 104, 157, 223, 11, 157, 223, 191, 153, 203, 116, 71,
+        158, 65, 172, 0, 99, 6, 99, 105, 116, 114, 101, 97, 20, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 8, 0, 0, 0, 0, 59, 154, 202, 0, 104, 65, 192, 147, 199, 55, 141,
+        150, 81, 138, 117, 68, 136, 33, 196, 247, 200, 244, 186, 231, 206, 96, 248, 4, 208, 61, 31,
+        6, 40, 221, 93, 208, 245, 222, 81, 37, 229, 146, 81, 60, 96, 31, 142, 155, 205, 125, 11,
+        153, 65, 84, 235, 108, 14, 51, 249, 43, 190, 34, 128, 62, 188, 105, 97, 131, 159, 232, 139,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 96, 188, 220, 18, 179, 65, 54, 53,
+        162, 189, 161, 197, 39, 81, 59, 20, 229, 165, 93, 101, 210, 169, 210, 96, 211, 140, 243,
+        192, 109, 227, 37, 32, 132, 152, 138, 124, 199, 15, 227, 162, 158, 170, 41, 163, 87, 12,
+        45, 65, 82, 173, 194, 121, 81, 159, 172, 64, 111, 49, 209, 54, 230, 132, 109, 96, 16, 58,
+        248, 121, 131, 161, 31, 16, 228, 37, 59, 51, 252, 102, 244, 110, 239, 88, 105, 90, 152,
+        229, 212, 121, 74, 52, 180, 88, 100, 172, 192, 227, 205,
+    ]
+}
+
 #[test]
 fn test_bridge() {
     let (mut config, _, _) =
@@ -513,49 +562,7 @@ fn test_bridge() {
         pre_state_root: [1u8; 32].to_vec(),
         current_spec: SpecId::Fork1,
         pub_key: vec![],
-        deposit_data: vec![[
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 32, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 32, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 42, 1, 145, 22, 58, 104, 30, 248, 81, 242, 63, 79, 72, 216, 243, 241, 44,
-            60, 88, 230, 44, 206, 194, 243, 103, 224, 237, 31, 108, 29, 207, 112, 110, 94, 1, 0, 0,
-            0, 0, 253, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 87, 2, 248, 199, 154, 59, 0, 0, 0, 0, 34, 81, 32, 180, 253, 103, 250, 242,
-            234, 221, 209, 124, 86, 77, 184, 249, 147, 86, 132, 180, 238, 191, 207, 88, 164, 131,
-            206, 164, 3, 244, 185, 120, 165, 30, 115, 74, 1, 0, 0, 0, 0, 0, 0, 34, 0, 32, 74, 232,
-            21, 114, 240, 110, 27, 136, 253, 92, 237, 122, 26, 0, 9, 69, 67, 46, 131, 225, 85, 30,
-            111, 114, 30, 233, 192, 11, 140, 195, 50, 96, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 207, 3,
-            64, 161, 192, 181, 26, 246, 26, 75, 97, 75, 195, 25, 148, 167, 73, 18, 169, 134, 223,
-            209, 191, 199, 220, 243, 38, 223, 51, 57, 71, 136, 182, 41, 246, 233, 200, 87, 9, 234,
-            172, 247, 185, 237, 10, 63, 152, 75, 134, 182, 168, 7, 69, 187, 91, 93, 123, 216, 163,
-            176, 231, 145, 122, 34, 105, 83, 11, 74, 32, 159, 179, 169, 97, 216, 177, 244, 236, 28,
-            170, 34, 12, 106, 80, 184, 21, 254, 188, 11, 104, 157, 223, 11, 157, 223, 191, 153,
-            203, 116, 71, 158, 65, 172, 0, 99, 6, 99, 105, 116, 114, 101, 97, 20, 1, 1, 1, 1, 1, 1,
-            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 8, 0, 0, 0, 0, 59, 154, 202, 0, 104, 65, 192,
-            147, 199, 55, 141, 150, 81, 138, 117, 68, 136, 33, 196, 247, 200, 244, 186, 231, 206,
-            96, 248, 4, 208, 61, 31, 6, 40, 221, 93, 208, 245, 222, 81, 37, 229, 146, 81, 60, 96,
-            31, 142, 155, 205, 125, 11, 153, 65, 84, 235, 108, 14, 51, 249, 43, 190, 34, 128, 62,
-            188, 105, 97, 131, 159, 232, 139, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 96, 188, 220, 18, 179, 65, 54, 53, 162, 189, 161, 197, 39, 81, 59, 20, 229, 165, 93,
-            101, 210, 169, 210, 96, 211, 140, 243, 192, 109, 227, 37, 32, 132, 152, 138, 124, 199,
-            15, 227, 162, 158, 170, 41, 163, 87, 12, 45, 65, 82, 173, 194, 121, 81, 159, 172, 64,
-            111, 49, 209, 54, 230, 132, 109, 96, 16, 58, 248, 121, 131, 161, 31, 16, 228, 37, 59,
-            51, 252, 102, 244, 110, 239, 88, 105, 90, 152, 229, 212, 121, 74, 52, 180, 88, 100,
-            172, 192, 227, 205,
-        ]
-        .to_vec()],
+        deposit_data: vec![bridge_deposit_params()],
         l1_fee_rate,
         timestamp: 0,
     };
@@ -576,6 +583,553 @@ fn test_bridge() {
     );
 }
 
+#[test]
+fn test_bridge_deposit_replay() {
+    let (mut config, _, _) =
+        get_evm_config_starting_base_fee(U256::from_str("1000000").unwrap(), None, 1);
+
+    config_push_contracts(&mut config, None);
+
+    let (mut evm, mut working_set) = get_evm(&config);
+
+    let l1_fee_rate = 1;
+    let mut l2_height = 2;
+    let recipient_address = address!("0101010101010101010101010101010101010101");
+    let deposit_amount = U256::from_str("0x8ac7230489e80000").unwrap();
+
+    // The same deposit twice in one block
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height,
+        da_slot_height: 2,
+        da_slot_hash: [2u8; 32],
+        da_slot_txs_commitment: [
+            35, 6, 15, 121, 7, 142, 70, 109, 219, 14, 211, 34, 120, 157, 121, 127, 164, 53, 23, 80,
+            188, 45, 73, 146, 108, 41, 125, 77, 133, 86, 235, 104,
+        ],
+        pre_state_root: [1u8; 32].to_vec(),
+        current_spec: SpecId::Fork2,
+        pub_key: vec![],
+        deposit_data: vec![bridge_deposit_params(), bridge_deposit_params()],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    let recipient_account = evm
+        .accounts
+        .get(&recipient_address, &mut working_set)
+        .unwrap();
+    assert_eq!(recipient_account.balance, deposit_amount);
+
+    let txid = BridgeWrapper::deposit_txid(&bridge_deposit_params()).unwrap();
+    assert_eq!(
+        evm.processed_deposits.get(&txid, &mut working_set),
+        Some(l2_height)
+    );
+
+    let receipts: Vec<_> = evm
+        .receipts
+        .iter(&mut working_set.accessory_state())
+        .collect();
+    let deposit_receipt = &receipts[receipts.len() - 2];
+    let replay_receipt = &receipts[receipts.len() - 1];
+    assert!(deposit_receipt.receipt.success);
+    assert!(!deposit_receipt.receipt.logs.is_empty());
+    // The replayed deposit is a no-op that doesn't reach the bridge
+    assert!(replay_receipt.receipt.success);
+    assert!(replay_receipt.receipt.logs.is_empty());
+
+    let mut working_set = working_set.checkpoint().to_revertable();
+    l2_height += 1;
+
+    // And once more in the next block
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height,
+        deposit_data: vec![bridge_deposit_params()],
+        ..soft_confirmation_info
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    let recipient_account = evm
+        .accounts
+        .get(&recipient_address, &mut working_set)
+        .unwrap();
+    assert_eq!(recipient_account.balance, deposit_amount);
+    assert_eq!(
+        evm.processed_deposits.get(&txid, &mut working_set),
+        Some(l2_height - 1)
+    );
+
+    let replay_receipt = evm
+        .receipts
+        .iter(&mut working_set.accessory_state())
+        .last()
+        .unwrap();
+    assert!(replay_receipt.receipt.success);
+    assert!(replay_receipt.receipt.logs.is_empty());
+}
+
+#[test]
+fn test_total_supply() {
+    let (config, dev_signer, _) =
+        get_evm_config_starting_base_fee(U256::from_str("100000000000000000000").unwrap(), None, 1);
+    let (mut evm, mut working_set) = get_evm(&config);
+
+    let l1_fee_rate = 1;
+    let recipient_address = address!("0101010101010101010101010101010101010101");
+    let deposit_amount = U256::from_str("0x8ac7230489e80000").unwrap();
+
+    // Only the cBTC that left the bridge is minted, other balances set in genesis are not
+    let genesis_bridge_balance = evm
+        .accounts
+        .get(&BridgeWrapper::address(), &mut working_set)
+        .unwrap()
+        .balance;
+    let genesis_total_supply = evm
+        .citrea_get_total_supply(None, &mut working_set)
+        .unwrap()
+        .total_supply;
+    assert_eq!(
+        genesis_total_supply,
+        BRIDGE_GENESIS_BALANCE - genesis_bridge_balance
+    );
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height: 2,
+        da_slot_height: 2,
+        da_slot_hash: [2u8; 32],
+        da_slot_txs_commitment: [
+            35, 6, 15, 121, 7, 142, 70, 109, 219, 14, 211, 34, 120, 157, 121, 127, 164, 53, 23, 80,
+            188, 45, 73, 146, 108, 41, 125, 77, 133, 86, 235, 104,
+        ],
+        pre_state_root: [1u8; 32].to_vec(),
+        current_spec: SpecId::Fork1,
+        pub_key: vec![],
+        deposit_data: vec![bridge_deposit_params()],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    // The deposit is minted, the counter is not written before the fork
+    let recipient_balance = evm
+        .accounts
+        .get(&recipient_address, &mut working_set)
+        .unwrap()
+        .balance;
+    assert_eq!(recipient_balance, deposit_amount);
+    let total_supply = evm.citrea_get_total_supply(None, &mut working_set).unwrap();
+    assert_eq!(
+        total_supply.total_supply,
+        genesis_total_supply + recipient_balance
+    );
+    assert!(evm.total_supply.get(&mut working_set).is_none());
+
+    let mut working_set = working_set.checkpoint().to_revertable();
+
+    // Withdraw after the fork, paying the fees to the vaults
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height: 3,
+        current_spec: SpecId::Fork2,
+        deposit_data: vec![],
+        ..soft_confirmation_info
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    {
+        let sender_address = generate_address::<C>("sender");
+        let context = C::new(sender_address, 3, SpecId::Fork2, l1_fee_rate);
+
+        let withdraw_message = dev_signer
+            .sign_default_transaction(
+                TxKind::Call(BridgeWrapper::address()),
+                BridgeContract::withdrawCall {
+                    txId: [1u8; 32].into(),
+                    outputId: [0u8; 4].into(),
+                }
+                .abi_encode(),
+                0,
+                deposit_amount.to(),
+            )
+            .unwrap();
+
+        evm.call(
+            CallMessage {
+                txs: vec![withdraw_message],
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+    }
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    let receipt = evm
+        .receipts
+        .iter(&mut working_set.accessory_state())
+        .last()
+        .unwrap();
+    assert!(receipt.receipt.success);
+
+    // The counter is seeded at the fork, the withdrawn cBTC is burned
+    // and the fees are still part of the supply
+    let total_supply = evm.citrea_get_total_supply(None, &mut working_set).unwrap();
+    assert_eq!(total_supply.total_supply, genesis_total_supply);
+    assert_eq!(
+        evm.total_supply.get(&mut working_set),
+        Some(genesis_total_supply)
+    );
+
+    let mut balance_of = |address| {
+        evm.accounts
+            .get(&address, &mut working_set)
+            .map(|info| info.balance)
+            .unwrap_or_default()
+    };
+    assert!(total_supply.base_fee_vault_balance > U256::ZERO);
+    assert!(total_supply.l1_fee_vault_balance > U256::ZERO);
+    assert_eq!(
+        total_supply.base_fee_vault_balance,
+        balance_of(BASE_FEE_VAULT)
+    );
+    assert_eq!(total_supply.l1_fee_vault_balance, balance_of(L1_FEE_VAULT));
+    assert_eq!(
+        total_supply.priority_fee_vault_balance,
+        balance_of(PRIORITY_FEE_VAULT)
+    );
+}
+
+#[test]
+fn test_system_gas_reserve() {
+    // one publish event message is 26388 gas, the user gas budget fits exactly 10 of them
+    let system_gas_reserve = 1_000_000;
+    let user_gas_budget = 10 * 26388;
+    let (mut config, dev_signer, contract_addr) = get_evm_config_starting_base_fee(
+        U256::from_str("100000000000000000000").unwrap(),
+        Some(system_gas_reserve + user_gas_budget),
+        1,
+    );
+    config.system_gas_reserve = system_gas_reserve;
+
+    let (mut evm, mut working_set) = get_evm(&config);
+    assert_eq!(
+        evm.get_system_gas_reserve(&mut working_set),
+        system_gas_reserve
+    );
+
+    let l1_fee_rate = 0;
+    let sender_address = generate_address::<C>("sender");
+    let recipient_address = address!("0101010101010101010101010101010101010101");
+
+    // deploy the logs contract before the fork, in the L1 block of genesis
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height: 2,
+        da_slot_hash: [1u8; 32],
+        da_slot_height: 1,
+        da_slot_txs_commitment: [2u8; 32],
+        pre_state_root: [10u8; 32].to_vec(),
+        current_spec: SpecId::Fork1,
+        pub_key: vec![],
+        deposit_data: vec![],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    {
+        let context = C::new(sender_address, 2, SpecId::Fork1, l1_fee_rate);
+        evm.call(
+            CallMessage {
+                txs: vec![create_contract_message(
+                    &dev_signer,
+                    0,
+                    LogsContract::default(),
+                )],
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+    }
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    let mut working_set = working_set.checkpoint().to_revertable();
+
+    // a new L1 block with a deposit, before the fork the user transactions can use the reserve
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height: 3,
+        da_slot_hash: [2u8; 32],
+        da_slot_height: 2,
+        da_slot_txs_commitment: [
+            35, 6, 15, 121, 7, 142, 70, 109, 219, 14, 211, 34, 120, 157, 121, 127, 164, 53, 23, 80,
+            188, 45, 73, 146, 108, 41, 125, 77, 133, 86, 235, 104,
+        ],
+        deposit_data: vec![bridge_deposit_params()],
+        ..soft_confirmation_info
+    };
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    {
+        let context = C::new(sender_address, 3, SpecId::Fork1, l1_fee_rate);
+
+        let rlp_transactions = (1..=11)
+            .map(|nonce| {
+                publish_event_message(contract_addr, &dev_signer, nonce, "hello".to_string())
+            })
+            .collect();
+        evm.call(
+            CallMessage {
+                txs: rlp_transactions,
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+    }
+
+    // let's start over after the fork
+    let mut working_set = working_set.revert().to_revertable();
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        current_spec: SpecId::Fork2,
+        ..soft_confirmation_info
+    };
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    let sys_tx_gas_usage = evm
+        .pending_transactions
+        .last()
+        .unwrap()
+        .cumulative_gas_used();
+    assert!(sys_tx_gas_usage <= system_gas_reserve);
+    {
+        let context = C::new(sender_address, 3, SpecId::Fork2, l1_fee_rate);
+
+        // the user transactions exactly fill the user gas budget
+        let rlp_transactions = (1..=10)
+            .map(|nonce| {
+                publish_event_message(contract_addr, &dev_signer, nonce, "hello".to_string())
+            })
+            .collect();
+        evm.call(
+            CallMessage {
+                txs: rlp_transactions,
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+
+        // one more would eat into the reserve, even though the block gas limit is not reached
+        assert!(sys_tx_gas_usage + user_gas_budget + 26388 < config.block_gas_limit);
+        assert_eq!(
+            evm.call(
+                CallMessage {
+                    txs: vec![publish_event_message(
+                        contract_addr,
+                        &dev_signer,
+                        11,
+                        "hello".to_string()
+                    )],
+                },
+                &context,
+                &mut working_set,
+            )
+            .unwrap_err(),
+            SoftConfirmationModuleCallError::EvmGasUsedExceedsBlockGasLimit {
+                cumulative_gas: user_gas_budget,
+                tx_gas_used: 26388,
+                block_gas_limit: user_gas_budget,
+            }
+        );
+    }
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    // the deposit is executed along with the user transactions
+    let recipient_account = evm
+        .accounts
+        .get(&recipient_address, &mut working_set)
+        .unwrap();
+    assert_eq!(
+        recipient_account.balance,
+        U256::from_str("0x8ac7230489e80000").unwrap(),
+    );
+
+    // the header gas limit is still the whole block gas limit
+    let block = evm.blocks.last(&mut working_set.accessory_state()).unwrap();
+    assert_eq!(block.header.gas_limit, config.block_gas_limit);
+    assert_eq!(block.header.gas_used, sys_tx_gas_usage + user_gas_budget);
+}
+
+#[test]
+fn test_system_txs_exceeding_system_gas_reserve() {
+    let (mut config, dev_signer, contract_addr) =
+        get_evm_config_starting_base_fee(U256::from_str("1000000").unwrap(), None, 1);
+    config.system_gas_reserve = 21000;
+
+    let (mut evm, mut working_set) = get_evm(&config);
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height: 2,
+        da_slot_height: 2,
+        da_slot_hash: [2u8; 32],
+        da_slot_txs_commitment: [
+            35, 6, 15, 121, 7, 142, 70, 109, 219, 14, 211, 34, 120, 157, 121, 127, 164, 53, 23, 80,
+            188, 45, 73, 146, 108, 41, 125, 77, 133, 86, 235, 104,
+        ],
+        pre_state_root: [1u8; 32].to_vec(),
+        current_spec: SpecId::Fork2,
+        pub_key: vec![],
+        deposit_data: vec![bridge_deposit_params()],
+        l1_fee_rate: 1,
+        timestamp: 0,
+    };
+
+    // the system transactions are still executed
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    let system_gas_used = evm
+        .pending_transactions
+        .last()
+        .unwrap()
+        .cumulative_gas_used();
+    assert!(system_gas_used > config.system_gas_reserve);
+
+    // but there is no room left for user transactions
+    let context = C::new(generate_address::<C>("sender"), 2, SpecId::Fork2, 1);
+    assert_eq!(
+        evm.call(
+            CallMessage {
+                txs: vec![publish_event_message(
+                    contract_addr,
+                    &dev_signer,
+                    0,
+                    "hello".to_string()
+                )],
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap_err(),
+        SoftConfirmationModuleCallError::EvmSystemGasExceedsReserve {
+            system_gas_used,
+            system_gas_reserve: config.system_gas_reserve,
+        }
+    );
+}
+
+#[test]
+fn test_bridge_malformed_deposit() {
+    let (mut config, _, _) =
+        get_evm_config_starting_base_fee(U256::from_str("1000000").unwrap(), None, 1);
+
+    config_push_contracts(&mut config, None);
+
+    let (mut evm, mut working_set) = get_evm(&config);
+
+    let l1_fee_rate = 1;
+    let l2_height = 2;
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height,
+        da_slot_height: 2,
+        da_slot_hash: [2u8; 32],
+        da_slot_txs_commitment: [
+            35, 6, 15, 121, 7, 142, 70, 109, 219, 14, 211, 34, 120, 157, 121, 127, 164, 53, 23, 80,
+            188, 45, 73, 146, 108, 41, 125, 77, 133, 86, 235, 104,
+        ],
+        pre_state_root: [1u8; 32].to_vec(),
+        current_spec: SpecId::Fork2,
+        pub_key: vec![],
+        deposit_data: vec![
+            vec![1, 2, 3],
+            // Too large to even fit in the gas limit of a system transaction
+            vec![0xff; 100_000],
+            bridge_deposit_params(),
+        ],
+        l1_fee_rate,
+        timestamp: 0,
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    let receipts: Vec<_> = evm
+        .receipts
+        .iter(&mut working_set.accessory_state())
+        .collect();
+    let malformed_receipts = &receipts[receipts.len() - 3..receipts.len() - 1];
+    for receipt in malformed_receipts {
+        assert!(!receipt.receipt.success);
+        assert!(receipt.receipt.logs.is_empty());
+    }
+    assert!(receipts.last().unwrap().receipt.success);
+
+    // Only the valid deposit is minted
+    let recipient_account = evm
+        .accounts
+        .get(
+            &address!("0101010101010101010101010101010101010101"),
+            &mut working_set,
+        )
+        .unwrap();
+    assert_eq!(
+        recipient_account.balance,
+        U256::from_str("0x8ac7230489e80000").unwrap(),
+    );
+}
+
+#[test]
+fn test_bridge_deposits_unchecked_before_fork2() {
+    let (mut config, _, _) =
+        get_evm_config_starting_base_fee(U256::from_str("1000000").unwrap(), None, 1);
+
+    config_push_contracts(&mut config, None);
+
+    let (mut evm, mut working_set) = get_evm(&config);
+
+    let soft_confirmation_info = HookSoftConfirmationInfo {
+        l2_height: 2,
+        da_slot_height: 2,
+        da_slot_hash: [2u8; 32],
+        da_slot_txs_commitment: [
+            35, 6, 15, 121, 7, 142, 70, 109, 219, 14, 211, 34, 120, 157, 121, 127, 164, 53, 23, 80,
+            188, 45, 73, 146, 108, 41, 125, 77, 133, 86, 235, 104,
+        ],
+        pre_state_root: [1u8; 32].to_vec(),
+        current_spec: SpecId::Fork1,
+        pub_key: vec![],
+        deposit_data: vec![bridge_deposit_params(), bridge_deposit_params()],
+        l1_fee_rate: 1,
+        timestamp: 0,
+    };
+
+    evm.begin_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.end_soft_confirmation_hook(&soft_confirmation_info, &mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    // Both deposits are passed to the bridge and none is recorded, as in the blocks before the fork
+    let receipts: Vec<_> = evm
+        .receipts
+        .iter(&mut working_set.accessory_state())
+        .collect();
+    let deposit_receipt = &receipts[receipts.len() - 2];
+    assert!(deposit_receipt.receipt.success);
+    assert!(!deposit_receipt.receipt.logs.is_empty());
+
+    let txid = BridgeWrapper::deposit_txid(&bridge_deposit_params()).unwrap();
+    assert_eq!(evm.processed_deposits.get(&txid, &mut working_set), None);
+}
+
 #[test]
 fn test_upgrade_light_client() {
     // initialize_logging(tracing::Level::INFO);
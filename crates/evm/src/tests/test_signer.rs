@@ -1,5 +1,8 @@
-use alloy_consensus::{TxEip1559 as RethTxEip1559, TxEip4844 as RethTxEip4844};
+use alloy_consensus::{
+    TxEip1559 as RethTxEip1559, TxEip4844 as RethTxEip4844, TxEip7702 as RethTxEip7702,
+};
 use alloy_eips::eip2718::Encodable2718;
+use alloy_eips::eip7702::{Authorization, SignedAuthorization};
 use alloy_primitives::{Address, Bytes as RethBytes, TxKind, B256, U256};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
@@ -158,4 +161,43 @@ impl TestSigner {
         signed.encode_2718(&mut buf);
         Ok(RlpEvmTransaction { rlp: buf })
     }
+
+    /// Signs an authorization delegating the code of the signer to `delegate`.
+    pub(crate) fn sign_authorization(
+        &self,
+        delegate: Address,
+        nonce: u64,
+    ) -> Result<SignedAuthorization, SignError> {
+        let authorization = Authorization {
+            chain_id: DEFAULT_CHAIN_ID,
+            address: delegate,
+            nonce,
+        };
+
+        self.signer.sign_authorization(authorization, self.address)
+    }
+
+    /// Signs Eip7702 transaction carrying the given authorizations.
+    pub(crate) fn sign_set_code_transaction(
+        &self,
+        to: Address,
+        authorization_list: Vec<SignedAuthorization>,
+        nonce: u64,
+    ) -> Result<RlpEvmTransaction, SignError> {
+        let reth_tx = RethTxEip7702 {
+            to,
+            nonce,
+            chain_id: DEFAULT_CHAIN_ID,
+            authorization_list,
+            max_fee_per_gas: 100000000000u128,
+            gas_limit: 1_000_000u64,
+            ..Default::default()
+        };
+
+        let reth_tx = RethTransaction::Eip7702(reth_tx);
+        let signed = self.signer.sign_transaction(reth_tx, self.address)?;
+        let mut buf = vec![];
+        signed.encode_2718(&mut buf);
+        Ok(RlpEvmTransaction { rlp: buf })
+    }
 }
@@ -0,0 +1,192 @@
+//! Merkle proofs of soft confirmations under the sequencer commitments covering them
+use alloy_primitives::U64;
+use anyhow::Context as _;
+use citrea_primitives::forks::fork_from_block_number;
+use citrea_primitives::soft_confirmation::compute_soft_confirmation_hash;
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::{MerkleProof, MerkleTree};
+use serde::{Deserialize, Serialize};
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_db::schema::types::{SoftConfirmationNumber, StoredSoftConfirmation};
+use sov_ledger_rpc::HexHash;
+use sov_rollup_interface::rpc::{
+    sequencer_commitment_to_response, SequencerCommitmentResponse, SoftConfirmationResponse,
+    SoftConfirmationStatus,
+};
+
+/// Response of `ledger_getCommitmentInclusionProof`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentInclusionProof {
+    /// Height of the soft confirmation
+    pub l2_height: u64,
+    /// Index of the soft confirmation among the leaves of the commitment's merkle tree
+    pub leaf_index: u64,
+    /// Number of leaves of the commitment's merkle tree, needed to verify the proof
+    pub leaf_count: u64,
+    /// Hash of the soft confirmation
+    #[serde(with = "hex::serde")]
+    pub leaf_hash: [u8; 32],
+    /// Sibling hashes from the leaf up to the root, as in [`rs_merkle::MerkleProof::proof_hashes`]
+    pub proof: Vec<HexHash>,
+    /// Commitment covering the soft confirmation
+    pub commitment: SequencerCommitmentResponse,
+}
+
+/// Merkle tree over the hashes of the soft confirmations of a sequencer commitment's range.
+pub(crate) fn soft_confirmations_merkle_tree(
+    soft_confirmations: &[StoredSoftConfirmation],
+) -> MerkleTree<Sha256> {
+    MerkleTree::<Sha256>::from_leaves(
+        soft_confirmations
+            .iter()
+            .map(|x| x.hash)
+            .collect::<Vec<_>>()
+            .as_slice(),
+    )
+}
+
+/// Proves the soft confirmation at `l2_height` is included in the finalized commitment covering it.
+/// Returns None if the soft confirmation is not covered by a commitment yet.
+pub fn get_commitment_inclusion_proof<DB: SharedLedgerOps>(
+    ledger_db: &DB,
+    l2_height: u64,
+) -> anyhow::Result<Option<CommitmentInclusionProof>> {
+    let status = ledger_db
+        .get_soft_confirmation_status(SoftConfirmationNumber(l2_height))?
+        .unwrap_or(SoftConfirmationStatus::Trusted);
+    if status == SoftConfirmationStatus::Trusted {
+        return Ok(None);
+    }
+    let Some((l1_height, commitment)) =
+        ledger_db.get_commitment_by_l2_height(SoftConfirmationNumber(l2_height))?
+    else {
+        return Ok(None);
+    };
+
+    let start_l2_height = commitment.l2_start_block_number;
+    let end_l2_height = commitment.l2_end_block_number;
+    let stored_soft_confirmations = ledger_db.get_soft_confirmation_range(
+        &(SoftConfirmationNumber(start_l2_height)..=SoftConfirmationNumber(end_l2_height)),
+    )?;
+    anyhow::ensure!(
+        stored_soft_confirmations.len() as u64 == end_l2_height - start_l2_height + 1,
+        "Soft confirmations of the commitment for L2 range {}-{} are missing",
+        start_l2_height,
+        end_l2_height,
+    );
+
+    let tree = soft_confirmations_merkle_tree(&stored_soft_confirmations);
+    anyhow::ensure!(
+        tree.root() == Some(commitment.merkle_root),
+        "Merkle root mismatch for the commitment for L2 range {}-{}",
+        start_l2_height,
+        end_l2_height,
+    );
+
+    let sender = ledger_db.get_commitment_sender(l1_height.0, &commitment)?;
+    let leaf_index = (l2_height - start_l2_height) as usize;
+    let proof = tree.proof(&[leaf_index]);
+
+    Ok(Some(CommitmentInclusionProof {
+        l2_height,
+        leaf_index: leaf_index as u64,
+        leaf_count: stored_soft_confirmations.len() as u64,
+        leaf_hash: stored_soft_confirmations[leaf_index].hash,
+        proof: proof
+            .proof_hashes()
+            .iter()
+            .copied()
+            .map(HexHash::from)
+            .collect(),
+        commitment: sequencer_commitment_to_response(commitment, l1_height.0, sender, None),
+    }))
+}
+
+/// Verifies offline that `soft_confirmation` is included in the commitment of `inclusion_proof`:
+/// its contents hash to its claimed hash and to the leaf of the proof, with the scheme of the fork
+/// active at its height, and the proof leads to the merkle root of the commitment.
+/// Returns the computed hash of the soft confirmation.
+pub fn verify_commitment_inclusion_proof(
+    soft_confirmation: &SoftConfirmationResponse,
+    inclusion_proof: &CommitmentInclusionProof,
+) -> anyhow::Result<[u8; 32]> {
+    let l2_height = soft_confirmation.l2_height;
+    anyhow::ensure!(
+        l2_height == inclusion_proof.l2_height,
+        "Soft confirmation at height {} does not match the proof for height {}",
+        l2_height,
+        inclusion_proof.l2_height,
+    );
+    let commitment = &inclusion_proof.commitment;
+    anyhow::ensure!(
+        (commitment.l2_start_block_number..=commitment.l2_end_block_number).contains(&l2_height)
+            && inclusion_proof.leaf_index == l2_height - commitment.l2_start_block_number,
+        "Soft confirmation at height {} is not the leaf {} of the commitment for L2 range {}-{}",
+        l2_height,
+        inclusion_proof.leaf_index,
+        commitment.l2_start_block_number,
+        commitment.l2_end_block_number,
+    );
+
+    let unparsed = soft_confirmation
+        .to_unparsed_soft_confirmation()
+        .context("Soft confirmation does not include its transactions")?;
+    let hash = compute_soft_confirmation_hash(fork_from_block_number(l2_height).spec_id, &unparsed);
+    anyhow::ensure!(
+        hash == soft_confirmation.hash,
+        "Soft confirmation at height {} hashes to {}, not to its claimed hash {}",
+        l2_height,
+        hex::encode(hash),
+        hex::encode(soft_confirmation.hash),
+    );
+    anyhow::ensure!(
+        hash == inclusion_proof.leaf_hash,
+        "Soft confirmation at height {} hashes to {}, not to the leaf hash {} of the proof",
+        l2_height,
+        hex::encode(hash),
+        hex::encode(inclusion_proof.leaf_hash),
+    );
+
+    let proof =
+        MerkleProof::<Sha256>::new(inclusion_proof.proof.iter().map(|hash| hash.0).collect());
+    anyhow::ensure!(
+        proof.verify(
+            commitment.merkle_root,
+            &[inclusion_proof.leaf_index as usize],
+            &[hash],
+            inclusion_proof.leaf_count as usize,
+        ),
+        "Inclusion proof does not lead to the merkle root {} of the commitment",
+        hex::encode(commitment.merkle_root),
+    );
+
+    Ok(hash)
+}
+
+/// Register the `ledger_getCommitmentInclusionProof` rpc.
+pub fn register_commitment_inclusion_proof_rpc<T: Send + Sync + 'static>(
+    rpc_methods: &mut RpcModule<T>,
+    ledger_db: LedgerDB,
+) -> anyhow::Result<()> {
+    let mut rpc = RpcModule::new(ledger_db);
+    rpc.register_blocking_method(
+        "ledger_getCommitmentInclusionProof",
+        |params, ledger_db, _| {
+            let l2_height: U64 = params.one()?;
+            get_commitment_inclusion_proof(&*ledger_db, l2_height.to()).map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(e.to_string()),
+                )
+            })
+        },
+    )?;
+
+    rpc_methods.merge(rpc)?;
+    Ok(())
+}
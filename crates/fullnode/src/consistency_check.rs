@@ -0,0 +1,432 @@
+//! Periodic comparison of the synced L2 blocks and state with the sequencer's.
+//! Catches state divergence between commitments, before a commitment or proof fails.
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use alloy_primitives::{Address, U256, U64};
+use anyhow::{bail, Context as _};
+use citrea_common::ConsistencyCheckConfig;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::http_client::HttpClient;
+use jsonrpsee::rpc_params;
+use sov_db::ledger_db::SharedLedgerOps;
+use sov_db::schema::types::SoftConfirmationNumber;
+use sov_ledger_rpc::LedgerRpcClient;
+use sov_rollup_interface::rpc::MAX_SOFT_CONFIRMATIONS_PER_REQUEST;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, warn};
+
+use crate::metrics::FULLNODE_METRICS;
+use crate::sequencer_clients::SequencerClients;
+
+/// Reads the balance of an account at the end of an L2 block from the local state
+pub type BalanceReader = Arc<dyn Fn(Address, u64) -> anyhow::Result<U256> + Send + Sync>;
+
+/// A difference between the synced chain and the sequencer's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Divergence {
+    /// The stored L2 block has a different hash or state root
+    L2Block {
+        l2_height: u64,
+        local_hash: [u8; 32],
+        sequencer_hash: [u8; 32],
+        local_state_root: Vec<u8>,
+        sequencer_state_root: Vec<u8>,
+    },
+    /// The account has a different balance at the end of the L2 block
+    Balance {
+        l2_height: u64,
+        address: Address,
+        local: U256,
+        sequencer: U256,
+    },
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Divergence::L2Block {
+                l2_height,
+                local_hash,
+                sequencer_hash,
+                local_state_root,
+                sequencer_state_root,
+            } => write!(
+                f,
+                "L2 block {} has hash 0x{} and state root 0x{}, the sequencer has hash 0x{} and state root 0x{}",
+                l2_height,
+                hex::encode(local_hash),
+                hex::encode(local_state_root),
+                hex::encode(sequencer_hash),
+                hex::encode(sequencer_state_root)
+            ),
+            Divergence::Balance {
+                l2_height,
+                address,
+                local,
+                sequencer,
+            } => write!(
+                f,
+                "Balance of {} at L2 block {} is {}, the sequencer has {}",
+                address, l2_height, local, sequencer
+            ),
+        }
+    }
+}
+
+/// Checks the L2 blocks committed after `last_checked_l2_height` against the sequencer once
+/// `config.interval` more blocks are committed.
+/// Divergences are logged and counted in the metrics, and stop the L2 block sync through
+/// `halt_sync` if `config.halt_on_divergence` is set.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run_consistency_checks<DB: SharedLedgerOps>(
+    ledger_db: DB,
+    sequencer_clients: Arc<SequencerClients>,
+    balance_reader: Option<BalanceReader>,
+    config: ConsistencyCheckConfig,
+    mut last_checked_l2_height: u64,
+    mut committed_l2_heights: broadcast::Receiver<u64>,
+    halt_sync: CancellationToken,
+    cancellation_token: CancellationToken,
+) {
+    let interval = config.interval.max(1);
+    loop {
+        let l2_height = select! {
+            _ = cancellation_token.cancelled() => return,
+            received = committed_l2_heights.recv() => match received {
+                Ok(l2_height) => l2_height,
+                // The heights since the last check are checked at once, missed notifications do not matter
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+        };
+        if l2_height < last_checked_l2_height + interval {
+            continue;
+        }
+
+        let l2_heights = last_checked_l2_height + 1..=l2_height;
+        let divergences = match find_divergences(
+            &ledger_db,
+            sequencer_clients.current(),
+            balance_reader.as_ref(),
+            &config.balance_addresses,
+            l2_heights.clone(),
+        )
+        .await
+        {
+            Ok(divergences) => divergences,
+            Err(e) => {
+                // Retried with the next committed L2 block
+                warn!(
+                    "Could not check L2 blocks {} to {} against the sequencer: {:?}",
+                    l2_heights.start(),
+                    l2_heights.end(),
+                    e
+                );
+                continue;
+            }
+        };
+        last_checked_l2_height = l2_height;
+        FULLNODE_METRICS.consistency_checks.increment(1);
+
+        if divergences.is_empty() {
+            debug!(
+                "L2 blocks {} to {} are consistent with the sequencer",
+                l2_heights.start(),
+                l2_heights.end()
+            );
+            continue;
+        }
+
+        FULLNODE_METRICS
+            .consistency_check_divergences
+            .increment(divergences.len() as u64);
+        for divergence in &divergences {
+            error!("Diverged from the sequencer: {}", divergence);
+        }
+        if config.halt_on_divergence {
+            error!(
+                "Halting L2 block sync at L2 height {} to preserve the diverged state",
+                l2_height
+            );
+            halt_sync.cancel();
+            return;
+        }
+    }
+}
+
+/// Compares the stored L2 blocks in `l2_heights` with the sequencer's, and the balances of
+/// `balance_addresses` at the end of the last one if a `balance_reader` is given.
+/// Pruned L2 blocks are skipped.
+pub(crate) async fn find_divergences<DB: SharedLedgerOps>(
+    ledger_db: &DB,
+    sequencer_client: &HttpClient,
+    balance_reader: Option<&BalanceReader>,
+    balance_addresses: &[Address],
+    l2_heights: RangeInclusive<u64>,
+) -> anyhow::Result<Vec<Divergence>> {
+    let mut divergences = vec![];
+
+    let (start, end) = l2_heights.into_inner();
+    let mut chunk_start = start;
+    while chunk_start <= end {
+        let chunk_end = end.min(chunk_start + MAX_SOFT_CONFIRMATIONS_PER_REQUEST - 1);
+        let sequencer_soft_confirmations = sequencer_client
+            .get_soft_confirmation_range(U64::from(chunk_start), U64::from(chunk_end), None)
+            .await?;
+
+        for (l2_height, sequencer_soft_confirmation) in
+            (chunk_start..=chunk_end).zip(sequencer_soft_confirmations)
+        {
+            let Some(sequencer_soft_confirmation) = sequencer_soft_confirmation else {
+                bail!("The sequencer does not have L2 block {}", l2_height);
+            };
+            let Some(stored) =
+                ledger_db.get_soft_confirmation_by_number(&SoftConfirmationNumber(l2_height))?
+            else {
+                continue;
+            };
+
+            if stored.hash != sequencer_soft_confirmation.hash
+                || stored.state_root != sequencer_soft_confirmation.state_root
+            {
+                divergences.push(Divergence::L2Block {
+                    l2_height,
+                    local_hash: stored.hash,
+                    sequencer_hash: sequencer_soft_confirmation.hash,
+                    local_state_root: stored.state_root,
+                    sequencer_state_root: sequencer_soft_confirmation.state_root,
+                });
+            }
+        }
+        chunk_start = chunk_end + 1;
+    }
+
+    if let Some(balance_reader) = balance_reader {
+        for address in balance_addresses {
+            let local = balance_reader(*address, end)
+                .with_context(|| format!("Could not read the balance of {}", address))?;
+            let sequencer: U256 = sequencer_client
+                .request("eth_getBalance", rpc_params![address, U64::from(end)])
+                .await?;
+            if local != sequencer {
+                divergences.push(Divergence::Balance {
+                    l2_height: end,
+                    address: *address,
+                    local,
+                    sequencer,
+                });
+            }
+        }
+    }
+
+    Ok(divergences)
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee::http_client::HttpClientBuilder;
+    use jsonrpsee::server::{ServerBuilder, ServerHandle};
+    use jsonrpsee::types::ErrorObjectOwned;
+    use jsonrpsee::RpcModule;
+    use sov_db::ledger_db::LedgerDB;
+    use sov_db::rocks_db_config::RocksdbConfig;
+    use sov_mock_da::{MockDaSpec, MockHash};
+    use sov_rollup_interface::rpc::{SoftConfirmationDetail, SoftConfirmationResponse};
+    use sov_rollup_interface::stf::SoftConfirmationReceipt;
+    use tokio::time::{timeout, Duration};
+
+    use super::*;
+
+    /// Head of the mock sequencer
+    const HEAD: u64 = 30;
+    /// Balance of every account on the mock sequencer
+    const BALANCE: u64 = 100;
+
+    fn state_root(l2_height: u64) -> Vec<u8> {
+        vec![l2_height as u8; 32]
+    }
+
+    fn soft_confirmation(l2_height: u64) -> SoftConfirmationResponse {
+        SoftConfirmationResponse {
+            l2_height,
+            da_slot_height: 1,
+            da_slot_hash: [0; 32],
+            da_slot_txs_commitment: [0; 32],
+            hash: [l2_height as u8; 32],
+            prev_hash: [l2_height as u8 - 1; 32],
+            txs: None,
+            state_root: state_root(l2_height),
+            soft_confirmation_signature: vec![],
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: l2_height,
+            tx_summaries: None,
+        }
+    }
+
+    async fn start_sequencer() -> (String, ServerHandle) {
+        let mut module = RpcModule::new(());
+        module
+            .register_method("ledger_getSoftConfirmationRange", |params, _, _| {
+                let (start, end, _detail): (U64, U64, Option<SoftConfirmationDetail>) =
+                    params.parse()?;
+                let soft_confirmations = (start.to::<u64>()..=end.to::<u64>())
+                    .map(|l2_height| (l2_height <= HEAD).then(|| soft_confirmation(l2_height)))
+                    .collect::<Vec<_>>();
+                Ok::<_, ErrorObjectOwned>(soft_confirmations)
+            })
+            .unwrap();
+        module
+            .register_method("eth_getBalance", |params, _, _| {
+                let (_address, _block): (Address, U64) = params.parse()?;
+                Ok::<_, ErrorObjectOwned>(U256::from(BALANCE))
+            })
+            .unwrap();
+        let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        (url, server.start(module))
+    }
+
+    /// Ledger with the sequencer's L2 blocks up to `HEAD`, except for a corrupted state root at
+    /// `corrupted_l2_height`
+    fn ledger_db(path: &std::path::Path, corrupted_l2_height: u64) -> LedgerDB {
+        let ledger_db = LedgerDB::with_config(&RocksdbConfig::new(path, None, None)).unwrap();
+        for l2_height in 1..=HEAD {
+            let soft_confirmation = soft_confirmation(l2_height);
+            let mut state_root = soft_confirmation.state_root.clone();
+            if l2_height == corrupted_l2_height {
+                state_root[0] ^= 1;
+            }
+            let receipt = SoftConfirmationReceipt::<MockDaSpec> {
+                l2_height,
+                da_slot_height: soft_confirmation.da_slot_height,
+                da_slot_hash: MockHash(soft_confirmation.da_slot_hash),
+                da_slot_txs_commitment: MockHash(soft_confirmation.da_slot_txs_commitment),
+                hash: soft_confirmation.hash,
+                prev_hash: soft_confirmation.prev_hash,
+                tx_hashes: vec![],
+                soft_confirmation_signature: vec![],
+                pub_key: vec![],
+                deposit_data: vec![],
+                l1_fee_rate: 0,
+                timestamp: soft_confirmation.timestamp,
+            };
+            ledger_db
+                .commit_soft_confirmation(&state_root, receipt, None)
+                .unwrap();
+        }
+        ledger_db
+    }
+
+    #[tokio::test]
+    async fn test_find_divergences() {
+        let (url, server) = start_sequencer().await;
+        let client = HttpClientBuilder::default().build(url).unwrap();
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = ledger_db(tmpdir.path(), 7);
+        let address = Address::with_last_byte(1);
+
+        let divergences = find_divergences(&ledger_db, &client, None, &[], 1..=5)
+            .await
+            .unwrap();
+        assert!(divergences.is_empty());
+
+        // Spans more L2 blocks than served in a single request
+        let divergences = find_divergences(&ledger_db, &client, None, &[], 6..=HEAD)
+            .await
+            .unwrap();
+        let mut local_state_root = state_root(7);
+        local_state_root[0] ^= 1;
+        assert_eq!(
+            divergences,
+            vec![Divergence::L2Block {
+                l2_height: 7,
+                local_hash: [7; 32],
+                sequencer_hash: [7; 32],
+                local_state_root,
+                sequencer_state_root: state_root(7),
+            }]
+        );
+
+        let same_balance: BalanceReader = Arc::new(|_, _| Ok(U256::from(BALANCE)));
+        let divergences =
+            find_divergences(&ledger_db, &client, Some(&same_balance), &[address], 8..=10)
+                .await
+                .unwrap();
+        assert!(divergences.is_empty());
+
+        let other_balance: BalanceReader = Arc::new(|_, _| Ok(U256::from(BALANCE + 1)));
+        let divergences = find_divergences(
+            &ledger_db,
+            &client,
+            Some(&other_balance),
+            &[address],
+            8..=10,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            divergences,
+            vec![Divergence::Balance {
+                l2_height: 10,
+                address,
+                local: U256::from(BALANCE + 1),
+                sequencer: U256::from(BALANCE),
+            }]
+        );
+
+        // L2 blocks the sequencer does not have can not be checked
+        assert!(
+            find_divergences(&ledger_db, &client, None, &[], HEAD..=HEAD + 1)
+                .await
+                .is_err()
+        );
+
+        server.stop().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_consistency_checks_halt_sync() {
+        let (url, server) = start_sequencer().await;
+        let sequencer_clients = Arc::new(SequencerClients::new(url, vec![]).unwrap());
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = ledger_db(tmpdir.path(), 7);
+
+        let (committed_tx, committed_rx) = broadcast::channel(HEAD as usize);
+        let halt_sync = CancellationToken::new();
+        let checks = tokio::spawn(run_consistency_checks(
+            ledger_db,
+            sequencer_clients,
+            None,
+            ConsistencyCheckConfig {
+                interval: 10,
+                balance_addresses: vec![],
+                halt_on_divergence: true,
+            },
+            0,
+            committed_rx,
+            halt_sync.clone(),
+            CancellationToken::new(),
+        ));
+
+        // The corrupted L2 block is flagged by the check of the window it is in
+        for l2_height in 1..=10 {
+            committed_tx.send(l2_height).unwrap();
+        }
+        timeout(Duration::from_secs(10), halt_sync.cancelled())
+            .await
+            .expect("Sync should be halted");
+        timeout(Duration::from_secs(10), checks)
+            .await
+            .unwrap()
+            .unwrap();
+
+        server.stop().unwrap();
+    }
+}
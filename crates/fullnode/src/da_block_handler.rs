@@ -7,12 +7,13 @@ use std::time::Instant;
 use anyhow::anyhow;
 use borsh::{BorshDeserialize, BorshSerialize};
 use citrea_common::cache::L1BlockCache;
-use citrea_common::da::{extract_sequencer_commitments, extract_zk_proofs, get_da_block_at_height};
+use citrea_common::da::{
+    extract_signed_sequencer_commitments, extract_zk_proofs, get_da_block_at_height,
+};
 use citrea_common::error::SyncError;
-use citrea_common::utils::check_l2_range_exists;
-use citrea_primitives::forks::fork_from_block_number;
-use rs_merkle::algorithms::Sha256;
-use rs_merkle::MerkleTree;
+use citrea_common::utils::{check_l2_range_exists, extract_batch_proof_output};
+use citrea_common::{events, SequencerKeySchedule};
+use citrea_primitives::forks::get_forks;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use sov_db::ledger_db::NodeLedgerOps;
@@ -21,16 +22,18 @@ use sov_db::schema::types::{
 };
 use sov_modules_api::{Context, Zkvm};
 use sov_rollup_interface::da::{BlockHeaderTrait, SequencerCommitment};
+use sov_rollup_interface::fork::{fork_pos_from_block_number, Fork};
 use sov_rollup_interface::rpc::SoftConfirmationStatus;
 use sov_rollup_interface::services::da::{DaService, SlotData};
 use sov_rollup_interface::spec::SpecId;
-use sov_rollup_interface::zk::{BatchProofCircuitOutput, Proof, ZkvmHost};
+use sov_rollup_interface::zk::{Proof, ZkvmHost};
 use tokio::select;
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::{sleep, Duration};
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
+use crate::commitment_proof::soft_confirmations_merkle_tree;
 use crate::metrics::FULLNODE_METRICS;
 
 pub(crate) struct L1BlockHandler<C, Vm, Da, StateRoot, DB>
@@ -49,8 +52,8 @@ where
 {
     ledger_db: DB,
     da_service: Arc<Da>,
-    sequencer_pub_key: Vec<u8>,
-    sequencer_da_pub_key: Vec<u8>,
+    sequencer_pub_keys: SequencerKeySchedule,
+    sequencer_da_pub_keys: Vec<Vec<u8>>,
     prover_da_pub_key: Vec<u8>,
     code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
     l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
@@ -77,8 +80,8 @@ where
     pub fn new(
         ledger_db: DB,
         da_service: Arc<Da>,
-        sequencer_pub_key: Vec<u8>,
-        sequencer_da_pub_key: Vec<u8>,
+        sequencer_pub_keys: SequencerKeySchedule,
+        sequencer_da_pub_keys: Vec<Vec<u8>>,
         prover_da_pub_key: Vec<u8>,
         code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
         l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
@@ -86,8 +89,8 @@ where
         Self {
             ledger_db,
             da_service,
-            sequencer_pub_key,
-            sequencer_da_pub_key,
+            sequencer_pub_keys,
+            sequencer_da_pub_keys,
             prover_da_pub_key,
             code_commitments_by_spec,
             l1_block_cache,
@@ -121,15 +124,27 @@ where
                     self.pending_l1_blocks.push_back(l1_block);
                 },
                 _ = interval.tick() => {
-                    self.process_l1_block().await
+                    if let Some(fork_l1_height) = self.process_l1_block().await {
+                        // Blocks fetched by the current worker may be orphaned, resync from the fork point
+                        let (l1_tx, new_l1_rx) = mpsc::channel(1);
+                        l1_rx = new_l1_rx;
+                        l1_sync_worker.set(sync_l1(
+                            fork_l1_height,
+                            self.da_service.clone(),
+                            l1_tx,
+                            self.l1_block_cache.clone(),
+                        ));
+                    }
                 },
             }
         }
     }
 
-    async fn process_l1_block(&mut self) {
+    /// Processes the next pending L1 block.
+    /// Returns the L1 height to resync from if the block revealed a reorg.
+    async fn process_l1_block(&mut self) -> Option<u64> {
         if self.pending_l1_blocks.is_empty() {
-            return;
+            return None;
         }
         let l1_block = self
             .pending_l1_blocks
@@ -138,15 +153,45 @@ where
         let l1_height = l1_block.header().height();
         info!("Processing L1 block at height: {}", l1_height);
 
+        // The block must build on the one processed at the previous height,
+        // otherwise the slots orphaned by the reorg have to be rolled back first
+        let parent_hash: [u8; 32] = l1_block.header().prev_hash().into();
+        let processed_parent_hash = match l1_height.checked_sub(1) {
+            Some(parent_height) => self
+                .ledger_db
+                .get_l1_hash_of_l1_height(parent_height)
+                .unwrap(),
+            None => None,
+        };
+        if processed_parent_hash.is_some_and(|hash| hash != parent_hash) {
+            warn!(
+                "L1 block at height {} does not build on the processed chain, rolling back",
+                l1_height
+            );
+            return match self.rollback_l1_reorg(l1_height).await {
+                Ok(fork_l1_height) => {
+                    info!("Rolled back L1 slots above height {}", fork_l1_height);
+                    Some(fork_l1_height)
+                }
+                Err(e) => {
+                    error!("Could not roll back L1 reorg: {}", e);
+                    None
+                }
+            };
+        }
+
         // Set the l1 height of the l1 hash
         self.ledger_db
             .set_l1_height_of_l1_hash(l1_block.header().hash().into(), l1_height)
             .unwrap();
+        self.ledger_db
+            .set_l1_hash_of_l1_height(l1_height, l1_block.header().hash().into())
+            .unwrap();
 
-        let sequencer_commitments = extract_sequencer_commitments(
+        let sequencer_commitments = extract_signed_sequencer_commitments(
             self.da_service.clone(),
             l1_block,
-            &self.sequencer_da_pub_key,
+            &self.sequencer_da_pub_keys,
         );
         let zk_proofs =
             match extract_zk_proofs(self.da_service.clone(), l1_block, &self.prover_da_pub_key)
@@ -155,7 +200,7 @@ where
                 Ok(proofs) => proofs,
                 Err(e) => {
                     error!("Could not process L1 block: {}...skipping", e);
-                    return;
+                    return None;
                 }
             };
 
@@ -164,11 +209,13 @@ where
             // We retry the L1 block at a later tick.
             if !check_l2_range_exists(
                 &self.ledger_db,
-                sequencer_commitments[0].l2_start_block_number,
-                sequencer_commitments[sequencer_commitments.len() - 1].l2_end_block_number,
+                sequencer_commitments[0].0.l2_start_block_number,
+                sequencer_commitments[sequencer_commitments.len() - 1]
+                    .0
+                    .l2_end_block_number,
             ) {
                 warn!("L1 commitment received, but L2 range is not synced yet...");
-                return;
+                return None;
             }
         }
 
@@ -177,7 +224,7 @@ where
                 match e {
                     SyncError::MissingL2(msg, start_l2_height, end_l2_height) => {
                         warn!("Could not completely process ZK proofs. Missing L2 blocks {:?} - {:?}. msg = {}", start_l2_height, end_l2_height, msg);
-                        return;
+                        return None;
                     }
                     SyncError::Error(e) => {
                         error!("Could not process ZK proofs: {}...skipping", e);
@@ -186,15 +233,15 @@ where
             }
         }
 
-        for sequencer_commitment in sequencer_commitments.clone().iter() {
+        for (sequencer_commitment, sender) in sequencer_commitments.iter() {
             if let Err(e) = self
-                .process_sequencer_commitment(l1_block, sequencer_commitment)
+                .process_sequencer_commitment(l1_block, sequencer_commitment, sender)
                 .await
             {
                 match e {
                     SyncError::MissingL2(msg, start_l2_height, end_l2_height) => {
                         warn!("Could not completely process sequencer commitments. Missing L2 blocks {:?} - {:?}, msg = {}", start_l2_height, end_l2_height, msg);
-                        return;
+                        return None;
                     }
                     SyncError::Error(e) => {
                         error!("Could not process sequencer commitments: {}... skipping", e);
@@ -216,23 +263,119 @@ where
         FULLNODE_METRICS.current_l1_block.set(l1_height as f64);
 
         self.pending_l1_blocks.pop_front();
+
+        None
+    }
+
+    /// Rolls back the slots orphaned by a reorg detected at `l1_height`.
+    /// Returns the height of the last block shared with the canonical chain.
+    async fn rollback_l1_reorg(&mut self, l1_height: u64) -> anyhow::Result<u64> {
+        let mut fork_l1_height = l1_height - 1;
+        while fork_l1_height > 0 {
+            let Some(processed_hash) = self.ledger_db.get_l1_hash_of_l1_height(fork_l1_height)?
+            else {
+                break;
+            };
+            let canonical_hash: [u8; 32] = self
+                .da_service
+                .get_block_at(fork_l1_height)
+                .await
+                .map_err(|e| anyhow!("Error while fetching L1 block: {}", e))?
+                .header()
+                .hash()
+                .into();
+            if processed_hash == canonical_hash {
+                break;
+            }
+            fork_l1_height -= 1;
+        }
+
+        let mut rolled_back_commitments = vec![];
+        for height in (fork_l1_height + 1..=l1_height).rev() {
+            let (commitments, verified_proofs) = self.ledger_db.delete_da_slot_data(height)?;
+
+            for verified_proof in verified_proofs {
+                let output = verified_proof.proof_output;
+                // Proofs of commitments in an orphaned slot are covered by resetting them below
+                let Ok(proven_commitments) = self.get_proven_commitments(
+                    output.da_slot_hash,
+                    &output.preproven_commitments,
+                    output.sequencer_commitments_range,
+                ) else {
+                    continue;
+                };
+                for commitment in proven_commitments {
+                    for i in commitment.l2_start_block_number..=commitment.l2_end_block_number {
+                        let l2_height = SoftConfirmationNumber(i);
+                        if self.ledger_db.get_soft_confirmation_status(l2_height)?
+                            == Some(SoftConfirmationStatus::Proven)
+                        {
+                            self.ledger_db.put_soft_confirmation_status(
+                                l2_height,
+                                SoftConfirmationStatus::Finalized,
+                            )?;
+                        }
+                    }
+                }
+            }
+
+            for commitment in &commitments {
+                for i in commitment.l2_start_block_number..=commitment.l2_end_block_number {
+                    self.ledger_db.put_soft_confirmation_status(
+                        SoftConfirmationNumber(i),
+                        SoftConfirmationStatus::Trusted,
+                    )?;
+                }
+            }
+            rolled_back_commitments.extend(commitments);
+        }
+
+        if let Some(l2_start_height) = rolled_back_commitments
+            .iter()
+            .map(|commitment| commitment.l2_start_block_number)
+            .min()
+        {
+            self.ledger_db
+                .set_last_commitment_l2_height(SoftConfirmationNumber(l2_start_height - 1))?;
+        }
+        self.ledger_db
+            .set_last_scanned_l1_height(SlotNumber(fork_l1_height))?;
+
+        self.l1_block_cache
+            .lock()
+            .await
+            .remove_above(fork_l1_height);
+        self.pending_l1_blocks.clear();
+
+        Ok(fork_l1_height)
     }
 
     async fn process_sequencer_commitment(
         &self,
         l1_block: &Da::FilteredBlock,
         sequencer_commitment: &SequencerCommitment,
+        sender: &[u8],
     ) -> Result<(), SyncError> {
         let start_l2_height = sequencer_commitment.l2_start_block_number;
         let end_l2_height = sequencer_commitment.l2_end_block_number;
+        let l1_height = l1_block.header().height();
 
-        tracing::info!(
+        tracing::debug!(
             "Processing sequencer commitment for L2 Range = {}-{} at L1 height {}.",
             start_l2_height,
             end_l2_height,
-            l1_block.header().height(),
+            l1_height,
         );
 
+        // The same commitment can land on the DA more than once, e.g. when the DA tx is rebroadcast
+        if self.is_commitment_processed(l1_height, sequencer_commitment)? {
+            info!(
+                "Sequencer commitment for L2 Range = {}-{} is already processed. Skipping duplicate at L1 height {}.",
+                start_l2_height, end_l2_height, l1_height,
+            );
+            return Ok(());
+        }
+
         // Traverse each item's field of vector of transactions, put them in merkle tree
         // and compare the root with the one from the ledger
         let stored_soft_confirmations: Vec<StoredSoftConfirmation> =
@@ -251,13 +394,7 @@ where
             ));
         }
 
-        let soft_confirmations_tree = MerkleTree::<Sha256>::from_leaves(
-            stored_soft_confirmations
-                .iter()
-                .map(|x| x.hash)
-                .collect::<Vec<_>>()
-                .as_slice(),
-        );
+        let soft_confirmations_tree = soft_confirmations_merkle_tree(&stored_soft_confirmations);
 
         if soft_confirmations_tree.root() != Some(sequencer_commitment.merkle_root) {
             return Err(anyhow!(
@@ -272,13 +409,31 @@ where
             .into());
         }
 
-        self.ledger_db.update_commitments_on_da_slot(
-            l1_block.header().height(),
-            sequencer_commitment.clone(),
-        )?;
+        // Commitments are only extracted for the sequencer's DA keys, but a commitment matching
+        // the local soft confirmations must never finalize them unless the sequencer sent it
+        if !self
+            .sequencer_da_pub_keys
+            .iter()
+            .any(|sequencer_da_pub_key| sequencer_da_pub_key.as_slice() == sender)
+        {
+            return Err(anyhow!(
+                "Commitment for L2 Range = {}-{} was not sent by the sequencer, sender: 0x{}. Skipping commitment.",
+                start_l2_height,
+                end_l2_height,
+                hex::encode(sender)
+            )
+            .into());
+        }
+
+        self.ledger_db
+            .update_commitments_on_da_slot(l1_height, sequencer_commitment.clone())?;
+        self.ledger_db
+            .put_commitment_by_l2_range(l1_height, sequencer_commitment.clone())?;
+        self.ledger_db
+            .put_commitment_sender(l1_height, sequencer_commitment, sender)?;
 
         for i in start_l2_height..=end_l2_height {
-            self.ledger_db.put_soft_confirmation_status(
+            self.ledger_db.upgrade_soft_confirmation_status(
                 SoftConfirmationNumber(i),
                 SoftConfirmationStatus::Finalized,
             )?;
@@ -286,43 +441,132 @@ where
         self.ledger_db
             .set_last_commitment_l2_height(SoftConfirmationNumber(end_l2_height))?;
 
+        events::commitment_processed(
+            l1_height,
+            start_l2_height,
+            end_l2_height,
+            sequencer_commitment.merkle_root,
+        );
+
         Ok(())
     }
 
+    /// Whether the commitment is already stored for the slot or was finalized at an earlier slot
+    fn is_commitment_processed(
+        &self,
+        l1_height: u64,
+        sequencer_commitment: &SequencerCommitment,
+    ) -> anyhow::Result<bool> {
+        if self
+            .ledger_db
+            .get_commitments_on_da_slot(l1_height)?
+            .is_some_and(|commitments| commitments.contains(sequencer_commitment))
+        {
+            return Ok(true);
+        }
+
+        let finalized_commitment =
+            self.ledger_db
+                .get_commitment_by_l2_height(SoftConfirmationNumber(
+                    sequencer_commitment.l2_start_block_number,
+                ))?;
+        Ok(matches!(
+            finalized_commitment,
+            Some((SlotNumber(found_l1_height), commitment))
+                if found_l1_height < l1_height && commitment == *sequencer_commitment
+        ))
+    }
+
     async fn process_zk_proof(
         &self,
         l1_block: &Da::FilteredBlock,
         proof: Proof,
     ) -> Result<(), SyncError> {
-        tracing::info!(
+        let start = Instant::now();
+        tracing::debug!(
             "Processing zk proof at height: {}",
             l1_block.header().height()
         );
         tracing::trace!("ZK proof: {:?}", proof);
 
-        // TODO: select output version based on spec
-        let batch_proof_output = Vm::extract_output::<
-            <Da as DaService>::Spec,
-            BatchProofCircuitOutput<<Da as DaService>::Spec, StateRoot>,
-        >(&proof)
-        .expect("Proof should be deserializable");
-        if batch_proof_output.sequencer_da_public_key != self.sequencer_da_pub_key
-            || batch_proof_output.sequencer_public_key != self.sequencer_pub_key
+        let (output_version, batch_proof_output) =
+            extract_batch_proof_output::<Vm, <Da as DaService>::Spec, StateRoot>(&proof)
+                .map_err(|e| anyhow!("Proof verification: {}. Skipping proof.", e))?;
+        if !self
+            .sequencer_da_pub_keys
+            .contains(&batch_proof_output.sequencer_da_public_key)
+            // A proof never spans a key rotation, its blocks are signed with the key of the last one
+            || batch_proof_output.sequencer_public_key
+                != self
+                    .sequencer_pub_keys
+                    .key_at(batch_proof_output.last_l2_height)
         {
             return Err(anyhow!(
                 "Proof verification: Sequencer public key or sequencer da public key mismatch. Skipping proof."
             ).into());
         }
 
-        let last_active_spec_id = fork_from_block_number(batch_proof_output.last_l2_height).spec_id;
-        let code_commitment = self
-            .code_commitments_by_spec
-            .get(&last_active_spec_id)
-            .expect("Proof public input must contain valid spec id");
-        Vm::verify(proof.as_slice(), code_commitment)
-            .map_err(|err| anyhow!("Failed to verify proof: {:?}. Skipping it...", err))?;
+        // These are the commitments read by the prover at the l1 height of the da slot hash
+        // We need to set them as proven
+        let proven_commitments = self.get_proven_commitments(
+            batch_proof_output.da_slot_hash.clone().into(),
+            &batch_proof_output.preproven_commitments,
+            batch_proof_output.sequencer_commitments_range,
+        )?;
+
+        let l2_height = proven_commitments
+            .first()
+            .ok_or_else(|| {
+                anyhow!("Proof verification: Proof covers no commitments. Skipping proof.")
+            })?
+            .l2_start_block_number;
+
+        // The proof must cover exactly the L2 blocks of the commitments it claims to prove,
+        // which start right after the blocks covered by the proofs verified before
+        let last_commitment_end = proven_commitments
+            .last()
+            .expect("Proof covers at least one commitment")
+            .l2_end_block_number;
+        if output_version.has_first_l2_height()
+            && (batch_proof_output.first_l2_height != l2_height
+                || batch_proof_output.last_l2_height != last_commitment_end)
+        {
+            return Err(anyhow!(
+                "Proof verification: Proof covers L2 range #{}-{}, but its commitments cover #{}-{}. Skipping proof.",
+                batch_proof_output.first_l2_height,
+                batch_proof_output.last_l2_height,
+                l2_height,
+                last_commitment_end
+            )
+            .into());
+        }
+
+        let verified_method_id = match verify_batch_proof::<Vm>(
+            proof.as_slice(),
+            &self.code_commitments_by_spec,
+            get_forks(),
+            l2_height,
+            batch_proof_output.last_l2_height,
+        ) {
+            Ok(method_id) => method_id,
+            Err(ProofVerificationError::WrongMethodId(spec_id)) => {
+                FULLNODE_METRICS.batch_proofs_wrong_method_id.increment(1);
+                return Err(anyhow!(
+                    "Proof verification: Proof is generated by the {:?} guest, which is not active in L2 range #{}-{}. Skipping proof.",
+                    spec_id,
+                    l2_height,
+                    batch_proof_output.last_l2_height
+                )
+                .into());
+            }
+            Err(ProofVerificationError::InvalidProof(err)) => {
+                FULLNODE_METRICS.batch_proofs_invalid.increment(1);
+                return Err(anyhow!("Failed to verify proof: {}. Skipping it...", err).into());
+            }
+        };
 
         let stored_batch_proof_output = StoredBatchProofOutput {
+            output_version,
             initial_state_root: batch_proof_output.initial_state_root.as_ref().to_vec(),
             final_state_root: batch_proof_output.final_state_root.as_ref().to_vec(),
             state_diff: batch_proof_output.state_diff,
@@ -334,48 +578,10 @@ where
             prev_soft_confirmation_hash: batch_proof_output.prev_soft_confirmation_hash,
             final_soft_confirmation_hash: batch_proof_output.final_soft_confirmation_hash,
             last_l2_height: batch_proof_output.last_l2_height,
+            first_l2_height: batch_proof_output.first_l2_height,
+            verified_method_id: Some(verified_method_id),
         };
 
-        let l1_hash = batch_proof_output.da_slot_hash.into();
-
-        // This is the l1 height where the sequencer commitment was read by the prover and proof generated by those commitments
-        // We need to get commitments in this l1 height and set them as proven
-        let l1_height = match self.ledger_db.get_l1_height_of_l1_hash(l1_hash)? {
-            Some(l1_height) => l1_height,
-            None => {
-                return Err(anyhow!(
-                    "Proof verification: L1 height not found for l1 hash: {:?}. Skipping proof.",
-                    l1_hash
-                )
-                .into());
-            }
-        };
-
-        let mut commitments_on_da_slot =
-            match self.ledger_db.get_commitments_on_da_slot(l1_height)? {
-                Some(commitments) => commitments,
-                None => {
-                    return Err(anyhow!(
-                    "Proof verification: No commitments found for l1 height: {}. Skipping proof.",
-                    l1_height
-                )
-                    .into());
-                }
-            };
-
-        commitments_on_da_slot.sort();
-
-        let excluded_commitment_indices = batch_proof_output.preproven_commitments.clone();
-        let filtered_commitments: Vec<SequencerCommitment> = commitments_on_da_slot
-            .into_iter()
-            .enumerate()
-            .filter(|(index, _)| !excluded_commitment_indices.contains(index))
-            .map(|(_, commitment)| commitment.clone())
-            .collect();
-
-        let l2_height = filtered_commitments
-            [batch_proof_output.sequencer_commitments_range.0 as usize]
-            .l2_start_block_number;
         // Fetch the block prior to the one at l2_height so compare state roots
 
         let prior_soft_confirmation_post_state_root = self
@@ -398,19 +604,11 @@ where
                 ).into());
         }
 
-        for commitment in filtered_commitments
-            .iter()
-            .skip(batch_proof_output.sequencer_commitments_range.0 as usize)
-            .take(
-                (batch_proof_output.sequencer_commitments_range.1
-                    - batch_proof_output.sequencer_commitments_range.0
-                    + 1) as usize,
-            )
-        {
+        for commitment in proven_commitments.iter() {
             let l2_start_height = commitment.l2_start_block_number;
             let l2_end_height = commitment.l2_end_block_number;
             for i in l2_start_height..=l2_end_height {
-                self.ledger_db.put_soft_confirmation_status(
+                self.ledger_db.upgrade_soft_confirmation_status(
                     SoftConfirmationNumber(i),
                     SoftConfirmationStatus::Proven,
                 )?;
@@ -422,8 +620,56 @@ where
             proof.clone(),
             stored_batch_proof_output,
         )?;
+
+        events::proof_verified(
+            l1_block.header().height(),
+            l2_height,
+            batch_proof_output.last_l2_height,
+            proof.len(),
+            start.elapsed(),
+        );
         Ok(())
     }
+
+    /// Returns the commitments in the da slot with given hash that a batch proof covers
+    fn get_proven_commitments(
+        &self,
+        da_slot_hash: [u8; 32],
+        preproven_commitments: &[usize],
+        sequencer_commitments_range: (u32, u32),
+    ) -> anyhow::Result<Vec<SequencerCommitment>> {
+        let l1_height = match self.ledger_db.get_l1_height_of_l1_hash(da_slot_hash)? {
+            Some(l1_height) => l1_height,
+            None => {
+                return Err(anyhow!(
+                    "Proof verification: L1 height not found for l1 hash: {:?}. Skipping proof.",
+                    da_slot_hash
+                ));
+            }
+        };
+
+        let mut commitments_on_da_slot =
+            match self.ledger_db.get_commitments_on_da_slot(l1_height)? {
+                Some(commitments) => commitments,
+                None => {
+                    return Err(anyhow!(
+                    "Proof verification: No commitments found for l1 height: {}. Skipping proof.",
+                    l1_height
+                ));
+                }
+            };
+
+        commitments_on_da_slot.sort();
+
+        Ok(commitments_on_da_slot
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !preproven_commitments.contains(index))
+            .map(|(_, commitment)| commitment)
+            .skip(sequencer_commitments_range.0 as usize)
+            .take((sequencer_commitments_range.1 - sequencer_commitments_range.0 + 1) as usize)
+            .collect())
+    }
 }
 
 async fn sync_l1<Da>(
@@ -484,3 +730,135 @@ async fn sync_l1<Da>(
         sleep(Duration::from_secs(2)).await;
     }
 }
+
+/// Reason a batch proof is rejected
+#[derive(Debug, PartialEq)]
+enum ProofVerificationError {
+    /// The proof verifies against the guest of a fork that is not active in the proven range
+    WrongMethodId(SpecId),
+    /// The proof does not verify against any known guest
+    InvalidProof(String),
+}
+
+/// Verifies the proof against the code commitments of the forks active at the first and the
+/// last L2 heights of the proven range, as a range crossing a fork activation may be proven
+/// by the guest of either fork.
+/// Returns the method id the proof verified against.
+fn verify_batch_proof<Vm: Zkvm>(
+    proof: &[u8],
+    code_commitments_by_spec: &HashMap<SpecId, Vm::CodeCommitment>,
+    forks: &[Fork],
+    first_l2_height: u64,
+    last_l2_height: u64,
+) -> Result<[u32; 8], ProofVerificationError> {
+    let last_spec_id = forks[fork_pos_from_block_number(forks, last_l2_height)].spec_id;
+    let first_spec_id = forks[fork_pos_from_block_number(forks, first_l2_height)].spec_id;
+
+    let mut candidate_spec_ids = vec![last_spec_id];
+    if first_spec_id != last_spec_id {
+        candidate_spec_ids.push(first_spec_id);
+    }
+
+    let mut errors = vec![];
+    for spec_id in candidate_spec_ids.iter() {
+        let code_commitment = code_commitments_by_spec
+            .get(spec_id)
+            .expect("Proof public input must contain valid spec id");
+        match Vm::verify(proof, code_commitment) {
+            Ok(_) => return Ok(code_commitment.clone().into()),
+            Err(err) => errors.push(format!("{:?}: {:?}", spec_id, err)),
+        }
+    }
+
+    // Tell a proof of another fork's guest apart from a proof that does not verify at all
+    match code_commitments_by_spec
+        .iter()
+        .filter(|(spec_id, _)| !candidate_spec_ids.contains(spec_id))
+        .find(|(_, code_commitment)| Vm::verify(proof, code_commitment).is_ok())
+    {
+        Some((spec_id, _)) => Err(ProofVerificationError::WrongMethodId(*spec_id)),
+        None => Err(ProofVerificationError::InvalidProof(errors.join(", "))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_mock_zkvm::{MockCodeCommitment, MockProof, MockZkvm};
+
+    use super::*;
+
+    const FORKS: [Fork; 2] = [Fork::new(SpecId::Genesis, 0), Fork::new(SpecId::Fork1, 100)];
+
+    fn code_commitments() -> HashMap<SpecId, MockCodeCommitment> {
+        HashMap::from([
+            (SpecId::Genesis, MockCodeCommitment([1; 32])),
+            (SpecId::Fork1, MockCodeCommitment([2; 32])),
+        ])
+    }
+
+    fn proof_of(program_id: MockCodeCommitment, is_valid: bool) -> Vec<u8> {
+        MockProof {
+            program_id,
+            is_valid,
+            log: vec![],
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_verify_proof_crossing_fork_boundary() {
+        let code_commitments = code_commitments();
+
+        // A range crossing the activation height is accepted with the guest of either fork
+        for spec_id in [SpecId::Genesis, SpecId::Fork1] {
+            let code_commitment = code_commitments[&spec_id].clone();
+            let proof = proof_of(code_commitment.clone(), true);
+            assert_eq!(
+                verify_batch_proof::<MockZkvm>(&proof, &code_commitments, &FORKS, 90, 110),
+                Ok(code_commitment.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_proof_wrong_method_id() {
+        let code_commitments = code_commitments();
+
+        // A range after the activation height is only accepted with the new guest
+        let old_guest_proof = proof_of(code_commitments[&SpecId::Genesis].clone(), true);
+        assert_eq!(
+            verify_batch_proof::<MockZkvm>(&old_guest_proof, &code_commitments, &FORKS, 101, 110),
+            Err(ProofVerificationError::WrongMethodId(SpecId::Genesis))
+        );
+
+        // A range before the activation height is only accepted with the old guest
+        let new_guest_proof = proof_of(code_commitments[&SpecId::Fork1].clone(), true);
+        assert_eq!(
+            verify_batch_proof::<MockZkvm>(&new_guest_proof, &code_commitments, &FORKS, 10, 99),
+            Err(ProofVerificationError::WrongMethodId(SpecId::Fork1))
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_invalid() {
+        let code_commitments = code_commitments();
+
+        let invalid_proof = proof_of(code_commitments[&SpecId::Fork1].clone(), false);
+        assert!(matches!(
+            verify_batch_proof::<MockZkvm>(&invalid_proof, &code_commitments, &FORKS, 90, 110),
+            Err(ProofVerificationError::InvalidProof(_))
+        ));
+
+        let unknown_guest_proof = proof_of(MockCodeCommitment([3; 32]), true);
+        assert!(matches!(
+            verify_batch_proof::<MockZkvm>(
+                &unknown_guest_proof,
+                &code_commitments,
+                &FORKS,
+                90,
+                110
+            ),
+            Err(ProofVerificationError::InvalidProof(_))
+        ));
+    }
+}
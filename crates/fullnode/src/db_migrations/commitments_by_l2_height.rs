@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use sov_db::ledger_db::migrations::{LedgerMigration, MigrationName, MigrationVersion};
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_rollup_interface::da::SequencerCommitment;
+
+/// Index migration
+/// Builds "CommitmentsByL2EndHeight" from the commitments already stored in "CommitmentsByNumber"
+pub(crate) struct MigrateCommitmentsByL2Height {}
+
+impl LedgerMigration for MigrateCommitmentsByL2Height {
+    fn identifier(&self) -> (MigrationName, MigrationVersion) {
+        ("MigrateCommitmentsByL2Height".to_owned(), 1)
+    }
+
+    fn execute(
+        &self,
+        ledger_db: Arc<LedgerDB>,
+        _tables_to_drop: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let from = "CommitmentsByNumber";
+
+        let migrate_from_handle = ledger_db.get_cf_handle(from)?;
+
+        let migrate_from_iterator = ledger_db.get_iterator_for_cf(migrate_from_handle, None)?;
+
+        // Keys are borsh encoded L1 heights, values are borsh encoded commitment lists
+        for key_value_res in migrate_from_iterator {
+            let (key, value) = key_value_res?;
+            let l1_height: u64 = borsh::from_slice(&key)?;
+            let commitments: Vec<SequencerCommitment> = borsh::from_slice(&value)?;
+
+            for commitment in commitments {
+                ledger_db.put_commitment_by_l2_range(l1_height, commitment)?;
+            }
+        }
+
+        Ok(())
+    }
+}
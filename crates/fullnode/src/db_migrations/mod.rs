@@ -2,12 +2,25 @@ use std::sync::OnceLock;
 
 use sov_db::ledger_db::migrations::LedgerMigration;
 
+use crate::db_migrations::commitments_by_l2_height::MigrateCommitmentsByL2Height;
 use crate::db_migrations::verified_proofs::MigrateVerifiedProofsBySlotNumber;
+use crate::db_migrations::verified_proofs_key_encoding::MigrateVerifiedProofsKeyEncoding;
+use crate::db_migrations::verified_proofs_method_id::MigrateVerifiedProofsMethodId;
 
+mod commitments_by_l2_height;
 mod verified_proofs;
+mod verified_proofs_key_encoding;
+mod verified_proofs_method_id;
 
 pub fn migrations() -> &'static Vec<Box<dyn LedgerMigration + Send + Sync + 'static>> {
     static MIGRATIONS: OnceLock<Vec<Box<dyn LedgerMigration + Send + Sync + 'static>>> =
         OnceLock::new();
-    MIGRATIONS.get_or_init(|| vec![Box::new(MigrateVerifiedProofsBySlotNumber {})])
+    MIGRATIONS.get_or_init(|| {
+        vec![
+            Box::new(MigrateVerifiedProofsBySlotNumber {}),
+            Box::new(MigrateCommitmentsByL2Height {}),
+            Box::new(MigrateVerifiedProofsKeyEncoding {}),
+            Box::new(MigrateVerifiedProofsMethodId {}),
+        ]
+    })
 }
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use sov_db::ledger_db::migrations::{LedgerMigration, MigrationName, MigrationVersion};
+use sov_db::ledger_db::LedgerDB;
+
+/// Key encoding migration
+/// "VerifiedBatchProofsBySlotNumber" keys were borsh (little-endian) encoded,
+/// they are now big-endian encoded so that the table can be range scanned.
+pub(crate) struct MigrateVerifiedProofsKeyEncoding {}
+
+impl LedgerMigration for MigrateVerifiedProofsKeyEncoding {
+    fn identifier(&self) -> (MigrationName, MigrationVersion) {
+        ("MigrateVerifiedProofsKeyEncoding".to_owned(), 1)
+    }
+
+    fn execute(
+        &self,
+        ledger_db: Arc<LedgerDB>,
+        _tables_to_drop: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let table = "VerifiedBatchProofsBySlotNumber";
+
+        let handle = ledger_db.get_cf_handle(table)?;
+
+        // Collect everything first, so that re-encoded keys are never read back as old ones
+        let entries = ledger_db
+            .get_iterator_for_cf(handle, None)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (key, _) in entries.iter() {
+            ledger_db.delete_from_cf_raw(handle, key)?;
+        }
+
+        for (key, value) in entries {
+            let l1_height: u64 = borsh::from_slice(&key)?;
+            ledger_db.insert_into_cf_raw(handle, &l1_height.to_be_bytes(), &value)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
+pub use consistency_check::BalanceReader;
 pub use runner::*;
 
+pub mod commitment_proof;
+mod consistency_check;
 mod da_block_handler;
 pub mod db_migrations;
 mod metrics;
+pub mod replay;
 mod runner;
+mod sequencer_clients;
+mod tx_body_backfill;
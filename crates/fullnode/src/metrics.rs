@@ -1,9 +1,9 @@
-use metrics::{Gauge, Histogram};
+use metrics::{Counter, Gauge, Histogram};
 use metrics_derive::Metrics;
 use once_cell::sync::Lazy;
 
 #[derive(Metrics)]
-#[metrics(scope = "fullnode")]
+#[metrics(scope = "citrea_fullnode")]
 pub struct FullnodeMetrics {
     #[metric(describe = "The current L1 block number which is used to produce L2 blocks")]
     pub current_l1_block: Gauge,
@@ -13,6 +13,26 @@ pub struct FullnodeMetrics {
     pub scan_l1_block: Histogram,
     #[metric(describe = "The duration of processing a single soft confirmation")]
     pub process_soft_confirmation: Histogram,
+    #[metric(
+        describe = "The number of batch proofs rejected for being generated by the guest of a fork not active in the proven range"
+    )]
+    pub batch_proofs_wrong_method_id: Counter,
+    #[metric(describe = "The number of batch proofs rejected for failing verification")]
+    pub batch_proofs_invalid: Counter,
+    #[metric(describe = "The number of times syncing failed over to the next sequencer endpoint")]
+    pub sequencer_failovers: Counter,
+    #[metric(
+        describe = "The number of times syncing switched back to the primary sequencer endpoint"
+    )]
+    pub sequencer_primary_restored: Counter,
+    #[metric(
+        describe = "The number of completed checks of synced L2 blocks against the sequencer"
+    )]
+    pub consistency_checks: Counter,
+    #[metric(
+        describe = "The number of L2 blocks and balances found to differ from the sequencer's"
+    )]
+    pub consistency_check_divergences: Counter,
 }
 
 /// Fullnode metrics
@@ -0,0 +1,495 @@
+//! Re-executes stored soft confirmations on the state they were applied to, to debug state mismatches
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use anyhow::{bail, ensure, Context as _};
+use citrea_common::cache::L1BlockCache;
+use citrea_common::da::get_da_block_at_height;
+use citrea_common::SequencerKeySchedule;
+use citrea_primitives::forks::fork_from_block_number;
+use sov_db::ledger_db::{BatchProverLedgerOps, LedgerDB, SharedLedgerOps};
+use sov_db::schema::types::SoftConfirmationNumber;
+use sov_modules_api::hooks::HookSoftConfirmationInfo;
+use sov_modules_api::{Context, SignedSoftConfirmation, Spec, StateCheckpoint};
+use sov_modules_stf_blueprint::{Runtime, StfBlueprint};
+use sov_prover_storage_manager::{ProverStorage, ProverStorageManager, SnapshotManager};
+use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::stf::{StateTransitionError, StateTransitionFunction};
+use sov_rollup_interface::zk::CumulativeStateDiff;
+use sov_state::storage::NativeStorage;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+type StfTransaction<C, Da, RT> =
+    <StfBlueprint<C, Da, RT> as StateTransitionFunction<Da>>::Transaction;
+
+/// Difference between the state diff of a replayed L2 block and the stored one
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDiffComparison {
+    /// Keys only written by the replay, with the replayed values
+    pub only_replayed: CumulativeStateDiff,
+    /// Keys only written in the stored diff, with the stored values
+    pub only_stored: CumulativeStateDiff,
+    /// Keys written with different values, as (stored, replayed)
+    pub differing: BTreeMap<Vec<u8>, (Option<Vec<u8>>, Option<Vec<u8>>)>,
+}
+
+impl StateDiffComparison {
+    pub fn new(stored: &CumulativeStateDiff, replayed: &CumulativeStateDiff) -> Self {
+        let mut comparison = Self::default();
+        for (key, replayed_value) in replayed {
+            match stored.get(key) {
+                None => {
+                    comparison
+                        .only_replayed
+                        .insert(key.clone(), replayed_value.clone());
+                }
+                Some(stored_value) if stored_value != replayed_value => {
+                    comparison
+                        .differing
+                        .insert(key.clone(), (stored_value.clone(), replayed_value.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, stored_value) in stored {
+            if !replayed.contains_key(key) {
+                comparison
+                    .only_stored
+                    .insert(key.clone(), stored_value.clone());
+            }
+        }
+        comparison
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.only_replayed.is_empty() && self.only_stored.is_empty() && self.differing.is_empty()
+    }
+
+    /// Keys the replay wrote differently from the stored diff
+    fn replayed_divergent_keys(&self) -> BTreeSet<Vec<u8>> {
+        self.only_replayed
+            .keys()
+            .chain(self.differing.keys())
+            .cloned()
+            .collect()
+    }
+}
+
+/// An L2 block whose replay does not match what the node stored for it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayMismatch {
+    pub l2_height: u64,
+    /// Spec the block was replayed with
+    pub spec_id: SpecId,
+    pub stored_state_root: Vec<u8>,
+    pub replayed_state_root: Vec<u8>,
+    /// `None` if the node did not store the state diff of the block
+    pub state_diff: Option<StateDiffComparison>,
+    /// Index of the first transaction writing a key differently from the stored diff.
+    /// `None` if there is no stored diff, or the hooks around the transactions write the key.
+    pub offending_tx_index: Option<usize>,
+}
+
+impl fmt::Display for ReplayMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn value(value: &Option<Vec<u8>>) -> String {
+            value.as_ref().map_or_else(
+                || "deleted".to_string(),
+                |value| format!("0x{}", hex::encode(value)),
+            )
+        }
+
+        writeln!(
+            f,
+            "Mismatch at L2 height {} replayed with {:?}",
+            self.l2_height, self.spec_id
+        )?;
+        writeln!(
+            f,
+            "Stored state root: 0x{}",
+            hex::encode(&self.stored_state_root)
+        )?;
+        writeln!(
+            f,
+            "Replayed state root: 0x{}",
+            hex::encode(&self.replayed_state_root)
+        )?;
+        let Some(state_diff) = &self.state_diff else {
+            return writeln!(f, "No stored state diff to compare against");
+        };
+        writeln!(
+            f,
+            "Keys only written by the replay: {}",
+            state_diff.only_replayed.len()
+        )?;
+        for (key, replayed) in &state_diff.only_replayed {
+            writeln!(f, "  0x{}: {}", hex::encode(key), value(replayed))?;
+        }
+        writeln!(
+            f,
+            "Keys only in the stored diff: {}",
+            state_diff.only_stored.len()
+        )?;
+        for (key, stored) in &state_diff.only_stored {
+            writeln!(f, "  0x{}: {}", hex::encode(key), value(stored))?;
+        }
+        writeln!(
+            f,
+            "Keys with differing values: {}",
+            state_diff.differing.len()
+        )?;
+        for (key, (stored, replayed)) in &state_diff.differing {
+            writeln!(
+                f,
+                "  0x{}: stored {}, replayed {}",
+                hex::encode(key),
+                value(stored),
+                value(replayed)
+            )?;
+        }
+        match self.offending_tx_index {
+            Some(index) => writeln!(f, "Offending transaction index: {}", index),
+            None => writeln!(f, "Offending transaction index: unknown"),
+        }
+    }
+}
+
+/// Re-executes stored L2 blocks on the historical state and compares the results with the stored ones.
+/// Signatures are not verified, so blocks can be replayed with a spec other than the one they were signed with.
+pub struct SoftConfirmationReplayer<Da, C, RT>
+where
+    Da: DaService,
+    C: Context + Spec<Storage = ProverStorage<SnapshotManager>>,
+    RT: Runtime<C, Da::Spec>,
+{
+    da_service: Arc<Da>,
+    stf: StfBlueprint<C, Da::Spec, RT>,
+    storage_manager: ProverStorageManager<Da::Spec>,
+    ledger_db: LedgerDB,
+    sequencer_pub_keys: SequencerKeySchedule,
+    /// Spec to replay every block with, instead of the one active at its height
+    spec_override: Option<SpecId>,
+    l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
+}
+
+impl<Da, C, RT> SoftConfirmationReplayer<Da, C, RT>
+where
+    Da: DaService,
+    C: Context + Spec<Storage = ProverStorage<SnapshotManager>>,
+    RT: Runtime<C, Da::Spec>,
+{
+    pub fn new(
+        da_service: Arc<Da>,
+        stf: StfBlueprint<C, Da::Spec, RT>,
+        storage_manager: ProverStorageManager<Da::Spec>,
+        ledger_db: LedgerDB,
+        sequencer_pub_keys: SequencerKeySchedule,
+        spec_override: Option<SpecId>,
+    ) -> Self {
+        Self {
+            da_service,
+            stf,
+            storage_manager,
+            ledger_db,
+            sequencer_pub_keys,
+            spec_override,
+            l1_block_cache: Arc::new(Mutex::new(L1BlockCache::new())),
+        }
+    }
+
+    /// Replays the L2 blocks in `l2_heights`, returning the ones which do not match the stored results
+    pub async fn replay(
+        &mut self,
+        l2_heights: RangeInclusive<u64>,
+    ) -> anyhow::Result<Vec<ReplayMismatch>> {
+        let mut mismatches = vec![];
+        for l2_height in l2_heights.clone() {
+            if let Some(mismatch) = self.replay_l2_block(l2_height).await? {
+                warn!("Replay mismatch at L2 height {}", l2_height);
+                mismatches.push(mismatch);
+            }
+        }
+
+        info!(
+            "Replayed L2 blocks {} to {}, {} mismatches",
+            l2_heights.start(),
+            l2_heights.end(),
+            mismatches.len()
+        );
+        Ok(mismatches)
+    }
+
+    /// Replays the L2 block at `l2_height`. A block mismatches if its state root differs from the stored one,
+    /// or the node stored its state diff and the replayed one differs.
+    pub async fn replay_l2_block(
+        &mut self,
+        l2_height: u64,
+    ) -> anyhow::Result<Option<ReplayMismatch>> {
+        let stored = self
+            .ledger_db
+            .get_soft_confirmation_by_number(&SoftConfirmationNumber(l2_height))?
+            .with_context(|| format!("L2 block {} is not stored", l2_height))?;
+
+        let (replayed_state_root, replayed_state_diff) = self.execute(l2_height, None).await?;
+
+        let state_diff = self
+            .ledger_db
+            .get_l2_state_diff(SoftConfirmationNumber(l2_height))?
+            .map(|stored_state_diff| {
+                StateDiffComparison::new(
+                    &stored_state_diff.into_iter().collect(),
+                    &replayed_state_diff,
+                )
+            });
+
+        if replayed_state_root == stored.state_root
+            && state_diff
+                .as_ref()
+                .map_or(true, StateDiffComparison::is_empty)
+        {
+            return Ok(None);
+        }
+
+        let offending_tx_index = match &state_diff {
+            Some(state_diff) if !state_diff.is_empty() => {
+                self.find_offending_tx(
+                    l2_height,
+                    stored.txs.len(),
+                    &state_diff.replayed_divergent_keys(),
+                )
+                .await?
+            }
+            _ => None,
+        };
+
+        Ok(Some(ReplayMismatch {
+            l2_height,
+            spec_id: self.spec_id(l2_height),
+            stored_state_root: stored.state_root,
+            replayed_state_root,
+            state_diff,
+            offending_tx_index,
+        }))
+    }
+
+    /// Re-executes the L2 block at `l2_height` on the state before it, returning the resulting state root and diff.
+    /// With `tx_count`, only the first `tx_count` transactions of the block are executed and the end hooks are skipped.
+    pub async fn execute(
+        &mut self,
+        l2_height: u64,
+        tx_count: Option<usize>,
+    ) -> anyhow::Result<(Vec<u8>, CumulativeStateDiff)> {
+        let pre_state = self
+            .storage_manager
+            .create_historical_storage_on_l2_height(l2_height)?;
+        let (state_root, state_diff, change_set) =
+            self.execute_on(pre_state, l2_height, tx_count).await?;
+        // The change set is discarded, the state of the node is left untouched
+        self.storage_manager
+            .save_change_set_l2(l2_height, change_set)?;
+
+        Ok((state_root, state_diff))
+    }
+
+    /// Applies the stored L2 blocks in `l2_heights` again on top of the node state and finalizes them.
+    /// Used to restore the finalized blocks whose state was not persisted before the node stopped.
+    /// Fails if a block does not result in its stored state root.
+    pub async fn restore(&mut self, l2_heights: RangeInclusive<u64>) -> anyhow::Result<()> {
+        for l2_height in l2_heights.clone() {
+            let stored_state_root = self
+                .ledger_db
+                .get_soft_confirmation_by_number(&SoftConfirmationNumber(l2_height))?
+                .with_context(|| format!("L2 block {} is not stored", l2_height))?
+                .state_root;
+
+            let pre_state = self
+                .storage_manager
+                .create_storage_on_l2_height(l2_height)?;
+            let (state_root, _, change_set) = self.execute_on(pre_state, l2_height, None).await?;
+            ensure!(
+                state_root == stored_state_root,
+                "Restored state root 0x{} of L2 block {} differs from the stored 0x{}",
+                hex::encode(&state_root),
+                l2_height,
+                hex::encode(&stored_state_root)
+            );
+
+            self.storage_manager
+                .save_change_set_l2(l2_height, change_set)?;
+            self.storage_manager.finalize_l2(l2_height)?;
+        }
+
+        info!(
+            "Restored L2 blocks {} to {}",
+            l2_heights.start(),
+            l2_heights.end()
+        );
+        Ok(())
+    }
+
+    pub fn into_storage_manager(self) -> ProverStorageManager<Da::Spec> {
+        self.storage_manager
+    }
+
+    /// Executes the L2 block at `l2_height` on `pre_state`, returning the resulting state root, diff and change set
+    async fn execute_on(
+        &mut self,
+        pre_state: ProverStorage<SnapshotManager>,
+        l2_height: u64,
+        tx_count: Option<usize>,
+    ) -> anyhow::Result<(Vec<u8>, CumulativeStateDiff, ProverStorage<SnapshotManager>)> {
+        let stored = self
+            .ledger_db
+            .get_soft_confirmation_by_number(&SoftConfirmationNumber(l2_height))?
+            .with_context(|| format!("L2 block {} is not stored", l2_height))?;
+        if stored.txs.iter().any(|tx| tx.body.is_none()) {
+            bail!(
+                "L2 block {} was stored without transaction bodies, replaying needs a node running with include_tx_body",
+                l2_height
+            );
+        }
+
+        let mut soft_confirmation: SignedSoftConfirmation<StfTransaction<C, Da::Spec, RT>> =
+            stored.try_into().context("Failed to parse transactions")?;
+        let current_spec = self.spec_id(l2_height);
+
+        let l1_block = get_da_block_at_height(
+            &self.da_service,
+            soft_confirmation.da_slot_height(),
+            self.l1_block_cache.clone(),
+        )
+        .await?;
+
+        let pre_state_root = pre_state.get_root_hash(l2_height)?.as_ref().to_vec();
+
+        let soft_confirmation_info =
+            HookSoftConfirmationInfo::new(&soft_confirmation, pre_state_root.clone(), current_spec);
+        let mut working_set = StateCheckpoint::new(pre_state.clone()).to_revertable();
+
+        self.stf
+            .begin_soft_confirmation(
+                self.sequencer_pub_keys.key_at(l2_height),
+                &mut working_set,
+                l1_block.header(),
+                &soft_confirmation_info,
+            )
+            .with_context(|| format!("Failed to begin L2 block {}", l2_height))?;
+
+        let executed_txs = tx_count
+            .unwrap_or(usize::MAX)
+            .min(soft_confirmation.blobs().len());
+        self.stf
+            .apply_soft_confirmation_txs(
+                soft_confirmation_info,
+                &soft_confirmation.blobs()[..executed_txs],
+                &soft_confirmation.txs()[..executed_txs],
+                &mut working_set,
+            )
+            .with_context(|| format!("Failed to apply transactions of L2 block {}", l2_height))?;
+
+        if tx_count.is_none() {
+            self.stf
+                .end_soft_confirmation_inner(
+                    current_spec,
+                    pre_state_root,
+                    &mut soft_confirmation,
+                    &mut working_set,
+                )
+                .map_err(StateTransitionError::HookError)
+                .with_context(|| format!("Failed to end L2 block {}", l2_height))?;
+        }
+
+        let result = self.stf.finalize_soft_confirmation(
+            current_spec,
+            working_set,
+            pre_state,
+            &mut soft_confirmation,
+        );
+
+        Ok((
+            result.state_root_transition.final_root.as_ref().to_vec(),
+            result.state_diff.into_iter().collect(),
+            result.change_set,
+        ))
+    }
+
+    /// Bisects the transactions of the L2 block for the first one after which one of `divergent_keys` is written.
+    /// `None` if the begin hooks already write one, or only the end hooks do.
+    async fn find_offending_tx(
+        &mut self,
+        l2_height: u64,
+        tx_count: usize,
+        divergent_keys: &BTreeSet<Vec<u8>>,
+    ) -> anyhow::Result<Option<usize>> {
+        let writes_divergent_key = |state_diff: &CumulativeStateDiff| {
+            state_diff.keys().any(|key| divergent_keys.contains(key))
+        };
+
+        let (_, begin_state_diff) = self.execute(l2_height, Some(0)).await?;
+        if writes_divergent_key(&begin_state_diff) {
+            return Ok(None);
+        }
+        let (_, txs_state_diff) = self.execute(l2_height, Some(tx_count)).await?;
+        if !writes_divergent_key(&txs_state_diff) {
+            return Ok(None);
+        }
+
+        // The first `low` transactions do not write a divergent key, the first `high` do
+        let (mut low, mut high) = (0, tx_count);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            let (_, state_diff) = self.execute(l2_height, Some(mid)).await?;
+            if writes_divergent_key(&state_diff) {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Ok(Some(high - 1))
+    }
+
+    fn spec_id(&self, l2_height: u64) -> SpecId {
+        self.spec_override
+            .unwrap_or_else(|| fork_from_block_number(l2_height).spec_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_diff(entries: &[(u8, Option<u8>)]) -> CumulativeStateDiff {
+        entries
+            .iter()
+            .map(|(key, value)| (vec![*key], value.map(|value| vec![value])))
+            .collect()
+    }
+
+    #[test]
+    fn test_state_diff_comparison() {
+        let stored = state_diff(&[(1, Some(1)), (2, Some(2)), (3, None), (4, Some(4))]);
+        let replayed = state_diff(&[(1, Some(1)), (2, Some(20)), (3, Some(3)), (5, None)]);
+
+        let comparison = StateDiffComparison::new(&stored, &replayed);
+        assert_eq!(comparison.only_replayed, state_diff(&[(5, None)]));
+        assert_eq!(comparison.only_stored, state_diff(&[(4, Some(4))]));
+        assert_eq!(
+            comparison.differing,
+            BTreeMap::from([
+                (vec![2], (Some(vec![2]), Some(vec![20]))),
+                (vec![3], (None, Some(vec![3]))),
+            ])
+        );
+        // Keys only in the stored diff are never written by the replay
+        assert_eq!(
+            comparison.replayed_divergent_keys(),
+            BTreeSet::from([vec![2], vec![3], vec![5]])
+        );
+
+        assert!(StateDiffComparison::new(&stored, &stored).is_empty());
+    }
+}
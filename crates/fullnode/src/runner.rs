@@ -10,39 +10,78 @@ use backoff::ExponentialBackoffBuilder;
 use citrea_common::cache::L1BlockCache;
 use citrea_common::da::get_da_block_at_height;
 use citrea_common::tasks::manager::TaskManager;
-use citrea_common::utils::{create_shutdown_signal, soft_confirmation_to_receipt};
-use citrea_common::{RollupPublicKeys, RpcConfig, RunnerConfig};
+use citrea_common::utils::{
+    check_l2_genesis_state_root, commit_finalized_staged_soft_confirmations,
+    create_shutdown_signal, soft_confirmation_to_receipt, soft_confirmations_with_heights,
+    state_diff_size,
+};
+use citrea_common::{
+    events, ConsistencyCheckConfig, RollupPublicKeys, RpcConfig, RunnerConfig,
+    SequencerKeySchedule, TxBodyBackfillConfig,
+};
 use citrea_primitives::types::SoftConfirmationHash;
-use citrea_pruning::{Pruner, PruningConfig};
+use citrea_pruning::{EvmPruningCallback, Pruner, PruningConfig};
 use jsonrpsee::core::client::Error as JsonrpseeError;
-use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
-use jsonrpsee::server::{BatchRequestConfig, RpcServiceBuilder, ServerBuilder};
 use jsonrpsee::RpcModule;
 use sov_db::ledger_db::NodeLedgerOps;
-use sov_db::schema::types::{SlotNumber, SoftConfirmationNumber};
-use sov_ledger_rpc::LedgerRpcClient;
+use sov_db::schema::types::SoftConfirmationNumber;
+use sov_ledger_rpc::{LedgerRpcClient, LedgerRpcClientError};
 use sov_modules_api::{Context, SignedSoftConfirmation, Spec};
-use sov_modules_stf_blueprint::{Runtime, StfBlueprint};
+use sov_modules_stf_blueprint::{verify_soft_confirmation, Runtime, StfBlueprint};
 use sov_prover_storage_manager::{ProverStorage, ProverStorageManager, SnapshotManager};
-use sov_rollup_interface::da::BlockHeaderTrait;
+use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec};
 use sov_rollup_interface::fork::ForkManager;
 use sov_rollup_interface::rpc::SoftConfirmationResponse;
 use sov_rollup_interface::services::da::{DaService, SlotData};
 use sov_rollup_interface::spec::SpecId;
-use sov_rollup_interface::stf::StateTransitionFunction;
+use sov_rollup_interface::stf::{
+    SoftConfirmationReceipt, StateTransitionError, StateTransitionFunction,
+};
 use sov_rollup_interface::zk::{Zkvm, ZkvmHost};
+use sov_state::storage::NativeStorage;
 use sov_stf_runner::InitVariant;
 use tokio::select;
 use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
 use tokio::time::{sleep, Duration};
-use tracing::{debug, error, info, instrument};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, instrument, warn};
 
+use crate::consistency_check::{run_consistency_checks, BalanceReader};
 use crate::da_block_handler::L1BlockHandler;
 use crate::metrics::FULLNODE_METRICS;
+use crate::sequencer_clients::SequencerClients;
+use crate::tx_body_backfill::backfill_tx_bodies;
 
 type StateRoot<C, Da, RT> = <StfBlueprint<C, Da, RT> as StateTransitionFunction<Da>>::StateRoot;
 type StfTransaction<C, Da, RT> =
     <StfBlueprint<C, Da, RT> as StateTransitionFunction<Da>>::Transaction;
+/// Ledger data of an applied L2 block waiting to be committed: (state root, receipt, tx bodies)
+type PendingL2Commit<Da> = (Vec<u8>, SoftConfirmationReceipt<Da>, Option<Vec<Vec<u8>>>);
+
+/// The DA slot txs commitment of a synced soft confirmation does not match the DA block it was built on.
+/// The soft confirmation is rejected, and syncing does not go past it.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "DA slot txs commitment mismatch at height: {l2_height}, soft confirmation has 0x{}, DA block #{da_slot_height} has 0x{}",
+    hex::encode(.soft_confirmation_txs_commitment),
+    hex::encode(.da_block_txs_commitment)
+)]
+pub struct DaSlotTxsCommitmentMismatch {
+    pub l2_height: u64,
+    pub da_slot_height: u64,
+    pub soft_confirmation_txs_commitment: [u8; 32],
+    pub da_block_txs_commitment: [u8; 32],
+}
+
+/// A synced soft confirmation has a lower timestamp than its parent.
+/// The soft confirmation is rejected, and syncing does not go past it.
+#[derive(Debug, thiserror::Error)]
+#[error("Decreasing timestamp at height: {l2_height}, {timestamp} is lower than the parent timestamp {parent_timestamp}")]
+pub struct DecreasingTimestamp {
+    pub l2_height: u64,
+    pub timestamp: u64,
+    pub parent_timestamp: u64,
+}
 
 /// Citrea's own STF runner implementation.
 pub struct CitreaFullnode<Da, Vm, C, DB, RT>
@@ -60,19 +99,33 @@ where
     ledger_db: DB,
     state_root: StateRoot<C, Da::Spec, RT>,
     batch_hash: SoftConfirmationHash,
+    /// Timestamp of the last applied soft confirmation, `None` before the first one
+    last_timestamp: Option<u64>,
     rpc_config: RpcConfig,
-    sequencer_client: HttpClient,
-    sequencer_pub_key: Vec<u8>,
-    sequencer_da_pub_key: Vec<u8>,
+    /// `None` for a read-only node, which does not sync L2 blocks
+    sequencer_clients: Option<Arc<SequencerClients>>,
+    sequencer_pub_keys: SequencerKeySchedule,
+    sequencer_da_pub_keys: Vec<Vec<u8>>,
     prover_da_pub_key: Vec<u8>,
     phantom: std::marker::PhantomData<C>,
     include_tx_body: bool,
     code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
     l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
+    current_l1_header: Option<<Da::Spec as DaSpec>::BlockHeader>,
     sync_blocks_count: u64,
+    commit_blocks_count: u64,
+    pending_l2_commits: Vec<PendingL2Commit<Da::Spec>>,
     fork_manager: ForkManager<'static>,
     soft_confirmation_tx: broadcast::Sender<u64>,
     pruning_config: Option<PruningConfig>,
+    evm_pruning_callback: Option<EvmPruningCallback>,
+    /// Set if bodies of L2 blocks synced without them are backfilled
+    tx_body_backfill: Option<TxBodyBackfillConfig>,
+    allow_genesis_state_root_mismatch: bool,
+    /// Set if synced L2 blocks are periodically compared with the sequencer's
+    consistency_check: Option<ConsistencyCheckConfig>,
+    /// Reads local balances for the consistency checks
+    balance_reader: Option<BalanceReader>,
     task_manager: TaskManager<()>,
 }
 
@@ -101,11 +154,13 @@ where
         mut storage_manager: ProverStorageManager<Da::Spec>,
         init_variant: InitVariant<StfBlueprint<C, Da::Spec, RT>, Da::Spec>,
         code_commitments_by_spec: HashMap<SpecId, Vm::CodeCommitment>,
-        fork_manager: ForkManager<'static>,
+        mut fork_manager: ForkManager<'static>,
         soft_confirmation_tx: broadcast::Sender<u64>,
+        evm_pruning_callback: Option<EvmPruningCallback>,
+        balance_reader: Option<BalanceReader>,
         task_manager: TaskManager<()>,
     ) -> Result<Self, anyhow::Error> {
-        let (prev_state_root, prev_batch_hash) = match init_variant {
+        let (mut prev_state_root, mut prev_batch_hash) = match init_variant {
             InitVariant::Initialized((state_root, batch_hash)) => {
                 info!("Chain is already initialized. Skipping initialization. State root: {}. Previous soft confirmation hash: {}", hex::encode(state_root.as_ref()), hex::encode(batch_hash));
                 (state_root, batch_hash)
@@ -125,10 +180,52 @@ where
             }
         };
 
+        // Commit the ledger data of L2 blocks whose state was finalized before the last shutdown
+        let finalized_storage = storage_manager.create_finalized_storage()?;
+        let recovered = commit_finalized_staged_soft_confirmations(&ledger_db, |l2_height| {
+            finalized_storage
+                .get_root_hash(l2_height + 1)
+                .ok()
+                .map(|root| root.as_ref().to_vec())
+        })?;
+        if let Some(last) = recovered.last() {
+            for soft_confirmation in &recovered {
+                fork_manager.register_block(soft_confirmation.l2_height)?;
+            }
+            prev_state_root = finalized_storage.get_root_hash(last.l2_height + 1)?;
+            prev_batch_hash = last.hash;
+            info!(
+                "Recovered ledger data of L2 blocks #{} to #{}",
+                recovered[0].l2_height, last.l2_height
+            );
+        }
+
         let start_l2_height = ledger_db.get_head_soft_confirmation_height()?.unwrap_or(0) + 1;
+        let last_timestamp = ledger_db
+            .get_head_soft_confirmation()?
+            .map(|(_, soft_confirmation)| soft_confirmation.timestamp);
 
         info!("Starting L2 height: {}", start_l2_height);
 
+        if runner_config.tx_body_backfill.is_some() && !runner_config.include_tx_body {
+            warn!(
+                "Transaction body backfill is configured but include_tx_body is off, skipping it"
+            );
+        }
+        let tx_body_backfill = runner_config
+            .tx_body_backfill
+            .filter(|_| runner_config.include_tx_body);
+
+        let sequencer_clients = if runner_config.read_only {
+            info!("Running in read-only mode, L2 blocks will not be synced");
+            None
+        } else {
+            Some(Arc::new(SequencerClients::new(
+                runner_config.sequencer_client_url,
+                runner_config.secondary_sequencer_client_urls,
+            )?))
+        };
+
         Ok(Self {
             start_l2_height,
             da_service,
@@ -137,20 +234,28 @@ where
             ledger_db,
             state_root: prev_state_root,
             batch_hash: prev_batch_hash,
+            last_timestamp,
             rpc_config,
-            sequencer_client: HttpClientBuilder::default()
-                .build(runner_config.sequencer_client_url)?,
-            sequencer_pub_key: public_keys.sequencer_public_key,
-            sequencer_da_pub_key: public_keys.sequencer_da_pub_key,
+            sequencer_clients,
+            sequencer_pub_keys: public_keys.sequencer_key_schedule(),
+            sequencer_da_pub_keys: public_keys.sequencer_da_pub_keys(),
             prover_da_pub_key: public_keys.prover_da_pub_key,
             phantom: std::marker::PhantomData,
             include_tx_body: runner_config.include_tx_body,
             code_commitments_by_spec,
             sync_blocks_count: runner_config.sync_blocks_count,
+            commit_blocks_count: runner_config.commit_blocks_count.max(1),
+            pending_l2_commits: vec![],
             l1_block_cache: Arc::new(Mutex::new(L1BlockCache::new())),
+            current_l1_header: None,
             fork_manager,
             soft_confirmation_tx,
             pruning_config: runner_config.pruning_config,
+            evm_pruning_callback,
+            tx_body_backfill,
+            allow_genesis_state_root_mismatch: runner_config.allow_genesis_state_root_mismatch,
+            consistency_check: runner_config.consistency_check,
+            balance_reader,
             task_manager,
         })
     }
@@ -160,65 +265,8 @@ where
         &mut self,
         methods: RpcModule<()>,
         channel: Option<oneshot::Sender<SocketAddr>>,
-    ) {
-        let bind_host = match self.rpc_config.bind_host.parse() {
-            Ok(bind_host) => bind_host,
-            Err(e) => {
-                error!("Failed to parse bind host: {}", e);
-                return;
-            }
-        };
-        let listen_address = SocketAddr::new(bind_host, self.rpc_config.bind_port);
-
-        let max_connections = self.rpc_config.max_connections;
-        let max_subscriptions_per_connection = self.rpc_config.max_subscriptions_per_connection;
-        let max_request_body_size = self.rpc_config.max_request_body_size;
-        let max_response_body_size = self.rpc_config.max_response_body_size;
-        let batch_requests_limit = self.rpc_config.batch_requests_limit;
-
-        let middleware = tower::ServiceBuilder::new()
-            .layer(citrea_common::rpc::get_cors_layer())
-            .layer(citrea_common::rpc::get_healthcheck_proxy_layer());
-        let rpc_middleware = RpcServiceBuilder::new().layer_fn(citrea_common::rpc::Logger);
-
-        self.task_manager
-            .spawn(move |cancellation_token| async move {
-                let server = ServerBuilder::default()
-                    .max_connections(max_connections)
-                    .max_subscriptions_per_connection(max_subscriptions_per_connection)
-                    .max_request_body_size(max_request_body_size)
-                    .max_response_body_size(max_response_body_size)
-                    .set_batch_request_config(BatchRequestConfig::Limit(batch_requests_limit))
-                    .set_http_middleware(middleware)
-                    .set_rpc_middleware(rpc_middleware)
-                    .build([listen_address].as_ref())
-                    .await;
-
-                match server {
-                    Ok(server) => {
-                        let bound_address = match server.local_addr() {
-                            Ok(address) => address,
-                            Err(e) => {
-                                error!("{}", e);
-                                return;
-                            }
-                        };
-                        if let Some(channel) = channel {
-                            if let Err(e) = channel.send(bound_address) {
-                                error!("Could not send bound_address {}: {}", bound_address, e);
-                                return;
-                            }
-                        }
-                        info!("Starting RPC server at {} ", &bound_address);
-
-                        let _server_handle = server.start(methods);
-                        cancellation_token.cancelled().await;
-                    }
-                    Err(e) => {
-                        error!("Could not start RPC server: {}", e);
-                    }
-                }
-            });
+    ) -> anyhow::Result<()> {
+        citrea_common::rpc::start_server(&self.rpc_config, methods, channel, &mut self.task_manager)
     }
 
     async fn process_l2_block(
@@ -228,24 +276,43 @@ where
     ) -> anyhow::Result<()> {
         let start = Instant::now();
 
-        let current_l1_block = get_da_block_at_height(
-            &self.da_service,
-            soft_confirmation.da_slot_height,
-            self.l1_block_cache.clone(),
-        )
-        .await?;
+        let current_l1_header = self.get_l1_header(soft_confirmation).await?;
 
-        info!(
+        debug!(
             "Running soft confirmation batch #{} with hash: 0x{} on DA block #{}",
             l2_height,
             hex::encode(soft_confirmation.hash),
-            current_l1_block.header().height()
+            current_l1_header.height()
         );
 
         if self.batch_hash != soft_confirmation.prev_hash {
             bail!("Previous hash mismatch at height: {}", l2_height);
         }
 
+        if let Some(parent_timestamp) = self.last_timestamp {
+            if soft_confirmation.timestamp < parent_timestamp {
+                return Err(DecreasingTimestamp {
+                    l2_height,
+                    timestamp: soft_confirmation.timestamp,
+                    parent_timestamp,
+                }
+                .into());
+            }
+        }
+
+        // The txs commitment of the header is computed by the DA service from the transactions
+        // of the fetched block, e.g. the wtxid merkle root on Bitcoin, so it is not taken from the sequencer
+        let da_block_txs_commitment: [u8; 32] = current_l1_header.txs_commitment().into();
+        if soft_confirmation.da_slot_txs_commitment != da_block_txs_commitment {
+            return Err(DaSlotTxsCommitmentMismatch {
+                l2_height,
+                da_slot_height: current_l1_header.height(),
+                soft_confirmation_txs_commitment: soft_confirmation.da_slot_txs_commitment,
+                da_block_txs_commitment,
+            }
+            .into());
+        }
+
         let pre_state = self
             .storage_manager
             .create_storage_on_l2_height(l2_height)?;
@@ -256,15 +323,31 @@ where
                 .try_into()
                 .context("Failed to parse transactions")?;
         let current_spec = self.fork_manager.active_fork().spec_id;
+
+        // Reject soft confirmations not signed by the sequencer before executing them,
+        // blocks are verified with the key that was active at their height
+        let sequencer_pub_key = self.sequencer_pub_keys.key_at(l2_height);
+        verify_soft_confirmation::<C, _>(
+            current_spec,
+            &signed_soft_confirmation,
+            sequencer_pub_key,
+        )
+        .map_err(|e| {
+            anyhow::Error::new(StateTransitionError::SoftConfirmationError(e)).context(format!(
+                "Invalid soft confirmation at height: {}",
+                l2_height
+            ))
+        })?;
+
         let soft_confirmation_result = self.stf.apply_soft_confirmation(
             current_spec,
-            self.sequencer_pub_key.as_slice(),
+            sequencer_pub_key,
             // TODO(https://github.com/Sovereign-Labs/sovereign-sdk/issues/1247): incorrect pre-state root in case of re-org
             &self.state_root,
             pre_state,
             Default::default(),
             Default::default(),
-            current_l1_block.header(),
+            &current_l1_header,
             &mut signed_soft_confirmation,
         )?;
 
@@ -274,11 +357,14 @@ where
             bail!("Post state root mismatch at height: {}", l2_height)
         }
 
+        self.ledger_db.put_state_diff_size(
+            SoftConfirmationNumber(l2_height),
+            state_diff_size(&soft_confirmation_result.state_diff),
+        )?;
+
         self.storage_manager
             .save_change_set_l2(l2_height, soft_confirmation_result.change_set)?;
 
-        self.storage_manager.finalize_l2(l2_height)?;
-
         let tx_bodies = if self.include_tx_body {
             Some(signed_soft_confirmation.blobs().to_owned())
         } else {
@@ -288,30 +374,25 @@ where
         let receipt =
             soft_confirmation_to_receipt::<C, _, Da::Spec>(signed_soft_confirmation, current_spec);
 
-        self.ledger_db
-            .commit_soft_confirmation(next_state_root.as_ref(), receipt, tx_bodies)?;
-
-        self.ledger_db.extend_l2_range_of_l1_slot(
-            SlotNumber(current_l1_block.header().height()),
-            SoftConfirmationNumber(l2_height),
-        )?;
+        self.pending_l2_commits
+            .push((next_state_root.as_ref().to_vec(), receipt, tx_bodies));
 
         // Register this new block with the fork manager to active
         // the new fork on the next block.
         self.fork_manager.register_block(l2_height)?;
 
-        // Only errors when there are no receivers
-        let _ = self.soft_confirmation_tx.send(l2_height);
-
         self.state_root = next_state_root;
         self.batch_hash = soft_confirmation.hash;
+        self.last_timestamp = Some(soft_confirmation.timestamp);
 
-        info!(
-            "New State Root after soft confirmation #{} is: {:?}",
-            l2_height, self.state_root
+        events::l2_block_applied(
+            l2_height,
+            soft_confirmation.hash,
+            current_l1_header.height(),
+            self.state_root.as_ref(),
+            start.elapsed(),
         );
 
-        FULLNODE_METRICS.current_l2_block.set(l2_height as f64);
         FULLNODE_METRICS.process_soft_confirmation.record(
             Instant::now()
                 .saturating_duration_since(start)
@@ -321,9 +402,106 @@ where
         Ok(())
     }
 
+    /// Returns the header of the DA block a soft confirmation was built on.
+    /// Consecutive soft confirmations on the same DA block reuse the previous header.
+    async fn get_l1_header(
+        &mut self,
+        soft_confirmation: &SoftConfirmationResponse,
+    ) -> anyhow::Result<<Da::Spec as DaSpec>::BlockHeader> {
+        if let Some(header) = &self.current_l1_header {
+            let hash: [u8; 32] = header.hash().into();
+            if header.height() == soft_confirmation.da_slot_height
+                && hash == soft_confirmation.da_slot_hash
+            {
+                return Ok(header.clone());
+            }
+        }
+
+        let l1_block = get_da_block_at_height(
+            &self.da_service,
+            soft_confirmation.da_slot_height,
+            self.l1_block_cache.clone(),
+        )
+        .await?;
+        let header = l1_block.header().clone();
+        self.current_l1_header = Some(header.clone());
+
+        Ok(header)
+    }
+
+    /// Finalizes the storage of the applied L2 blocks and commits their ledger data in a single write.
+    /// Storage is finalized before the ledger, so the ledger head never points at missing state.
+    /// The ledger data is staged first, so blocks finalized right before a crash are committed
+    /// on restart. Blocks applied after the last commit are synced again after a restart.
+    /// The pending blocks are dropped even if the commit fails, so they are never committed twice.
+    /// The in-memory head is already past them then, so a failed commit is fatal to the node,
+    /// see [`Self::commit_l2_blocks_or_shutdown`].
+    fn commit_l2_blocks(&mut self) -> anyhow::Result<()> {
+        let pending_l2_commits = std::mem::take(&mut self.pending_l2_commits);
+        let Some(last_l2_height) = pending_l2_commits
+            .last()
+            .map(|(_, receipt, _)| receipt.l2_height)
+        else {
+            return Ok(());
+        };
+        let first_l2_height = pending_l2_commits[0].1.l2_height;
+
+        self.ledger_db
+            .stage_soft_confirmations(pending_l2_commits)?;
+
+        for l2_height in first_l2_height..=last_l2_height {
+            self.storage_manager.finalize_l2(l2_height)?;
+        }
+
+        self.ledger_db
+            .commit_staged_soft_confirmations(Some(SoftConfirmationNumber(last_l2_height)))?;
+
+        for l2_height in first_l2_height..=last_l2_height {
+            // Only errors when there are no receivers
+            let _ = self.soft_confirmation_tx.send(l2_height);
+        }
+
+        debug!(
+            "Committed L2 blocks #{} to #{}",
+            first_l2_height, last_l2_height
+        );
+        FULLNODE_METRICS.current_l2_block.set(last_l2_height as f64);
+
+        Ok(())
+    }
+
+    /// Commits the pending L2 blocks once there are enough of them to fill a commit.
+    async fn commit_full_l2_blocks(&mut self) -> anyhow::Result<()> {
+        if (self.pending_l2_commits.len() as u64) < self.commit_blocks_count {
+            return Ok(());
+        }
+        self.commit_l2_blocks_or_shutdown().await
+    }
+
+    /// Commits the pending L2 blocks and shuts the node down if they could not be committed.
+    /// The in-memory head is ahead of the ledger then and the next blocks would not follow it,
+    /// so syncing can not go on. The applied state is recovered on restart.
+    async fn commit_l2_blocks_or_shutdown(&mut self) -> anyhow::Result<()> {
+        if let Err(e) = self.commit_l2_blocks() {
+            error!("Could not commit L2 blocks: {}", e);
+            self.shutdown().await?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
     /// Runs the rollup.
     #[instrument(level = "trace", skip_all, err)]
     pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        if let Some(sequencer_clients) = &self.sequencer_clients {
+            check_l2_genesis_state_root(
+                &self.ledger_db,
+                sequencer_clients.current(),
+                self.allow_genesis_state_root_mismatch,
+            )
+            .await?;
+        }
+
         // Last L1/L2 height before shutdown.
         let start_l1_height = {
             let last_scanned_l1_height = self
@@ -333,9 +511,15 @@ where
                     panic!("Failed to get last scanned l1 height from the ledger db")
                 });
 
-            match last_scanned_l1_height {
-                Some(height) => height.0,
-                None => get_initial_slot_height(&self.sequencer_client).await,
+            match (last_scanned_l1_height, &self.sequencer_clients) {
+                (Some(height), _) => height.0,
+                (None, Some(sequencer_clients)) => get_initial_slot_height(sequencer_clients).await,
+                // A read-only node can only start scanning from the L2 blocks it already has
+                (None, None) => self
+                    .ledger_db
+                    .get_soft_confirmation_by_number(&SoftConfirmationNumber(1))?
+                    .map(|soft_confirmation| soft_confirmation.da_slot_height)
+                    .context("Read-only node has no synced L2 blocks")?,
             }
         };
 
@@ -345,28 +529,78 @@ where
                 self.ledger_db.get_last_pruned_l2_height()?.unwrap_or(0),
                 self.soft_confirmation_tx.subscribe(),
                 self.ledger_db.clone(),
+                self.evm_pruning_callback.clone(),
             );
 
             self.task_manager
-                .spawn(|cancellation_token| pruner.run(cancellation_token));
+                .spawn_best_effort("pruner", |cancellation_token| {
+                    pruner.run(cancellation_token)
+                });
+        }
+
+        // Blocks synced from now on are stored with their bodies
+        if let (Some(config), Some(sequencer_clients)) =
+            (&self.tx_body_backfill, &self.sequencer_clients)
+        {
+            let ledger_db = self.ledger_db.clone();
+            let sequencer_clients = sequencer_clients.clone();
+            let config = config.clone();
+            let end_l2_height = self.start_l2_height - 1;
+            self.task_manager
+                .spawn_best_effort("tx_body_backfill", move |cancellation_token| {
+                    backfill_tx_bodies::<C, Da::Spec, DB>(
+                        ledger_db,
+                        sequencer_clients,
+                        end_l2_height,
+                        config,
+                        cancellation_token,
+                    )
+                });
+        }
+
+        // Cancelled by the consistency checks on a divergence from the sequencer
+        let l2_sync_halt = CancellationToken::new();
+        if let (Some(config), Some(sequencer_clients)) =
+            (&self.consistency_check, &self.sequencer_clients)
+        {
+            let ledger_db = self.ledger_db.clone();
+            let sequencer_clients = sequencer_clients.clone();
+            let balance_reader = self.balance_reader.clone();
+            let config = config.clone();
+            let last_checked_l2_height = self.start_l2_height - 1;
+            let committed_l2_heights = self.soft_confirmation_tx.subscribe();
+            let l2_sync_halt = l2_sync_halt.clone();
+            self.task_manager
+                .spawn_best_effort("consistency_check", move |cancellation_token| {
+                    run_consistency_checks(
+                        ledger_db,
+                        sequencer_clients,
+                        balance_reader,
+                        config,
+                        last_checked_l2_height,
+                        committed_l2_heights,
+                        l2_sync_halt,
+                        cancellation_token,
+                    )
+                });
         }
 
         let ledger_db = self.ledger_db.clone();
         let da_service = self.da_service.clone();
-        let sequencer_pub_key = self.sequencer_pub_key.clone();
-        let sequencer_da_pub_key = self.sequencer_da_pub_key.clone();
+        let sequencer_pub_keys = self.sequencer_pub_keys.clone();
+        let sequencer_da_pub_keys = self.sequencer_da_pub_keys.clone();
         let prover_da_pub_key = self.prover_da_pub_key.clone();
         let code_commitments_by_spec = self.code_commitments_by_spec.clone();
         let l1_block_cache = self.l1_block_cache.clone();
 
         self.task_manager
-            .spawn(move |cancellation_token| async move {
+            .spawn("l1_block_handler", move |cancellation_token| async move {
                 let l1_block_handler =
                     L1BlockHandler::<C, Vm, Da, StateRoot<C, Da::Spec, RT>, DB>::new(
                         ledger_db,
                         da_service,
-                        sequencer_pub_key,
-                        sequencer_da_pub_key,
+                        sequencer_pub_keys,
+                        sequencer_da_pub_keys,
                         prover_da_pub_key,
                         code_commitments_by_spec,
                         l1_block_cache.clone(),
@@ -376,10 +610,24 @@ where
                     .await
             });
 
+        let mut shutdown_signal = create_shutdown_signal().await;
+        let mut critical_task_failures = self.task_manager.critical_task_failures();
+
+        let Some(sequencer_clients) = self.sequencer_clients.clone() else {
+            // Read-only nodes only serve RPC and process L1 blocks until shutdown
+            select! {
+                failure = critical_task_failures.recv() => {
+                    self.shutdown().await?;
+                    return Err(failure.into());
+                },
+                _ = shutdown_signal.recv() => return self.shutdown().await,
+            }
+        };
+
         let (l2_tx, mut l2_rx) = mpsc::channel(1);
         let l2_sync_worker = sync_l2(
             self.start_l2_height,
-            self.sequencer_client.clone(),
+            sequencer_clients,
             l2_tx,
             self.sync_blocks_count,
         );
@@ -391,8 +639,6 @@ where
         let mut interval = tokio::time::interval(Duration::from_secs(1));
         interval.tick().await;
 
-        let mut shutdown_signal = create_shutdown_signal().await;
-
         loop {
             select! {
                 _ = &mut l2_sync_worker => {},
@@ -401,6 +647,8 @@ where
                     // However, when an L2 block fails to process for whatever reason, we want to block this process
                     // and make sure that we start processing L2 blocks in queue.
                     if pending_l2_blocks.is_empty() {
+                        // A full range means there are more blocks to catch up with
+                        let caught_up = (l2_blocks.len() as u64) < self.sync_blocks_count;
                         for (index, (l2_height, l2_block)) in l2_blocks.iter().enumerate() {
                             if let Err(e) = self.process_l2_block(*l2_height, l2_block).await {
                                 error!("Could not process L2 block: {}", e);
@@ -409,6 +657,11 @@ where
                                 pending_l2_blocks.extend(remaining_l2s);
                                 break;
                             }
+                            self.commit_full_l2_blocks().await?;
+                        }
+                        // Keep batching while catching up, commit right away at the tip or on failure
+                        if caught_up || !pending_l2_blocks.is_empty() {
+                            self.commit_l2_blocks_or_shutdown().await?;
                         }
                         continue;
                     } else {
@@ -416,13 +669,11 @@ where
                     }
                 },
                 _ = interval.tick() => {
-                    if pending_l2_blocks.is_empty() {
-                        continue;
-                    }
                     while let Some((l2_height, l2_block)) = pending_l2_blocks.front() {
                         match self.process_l2_block(*l2_height, l2_block).await {
                             Ok(_) => {
                                 pending_l2_blocks.pop_front();
+                                self.commit_full_l2_blocks().await?;
                             },
                             Err(e) => {
                                 error!("Could not process L2 block: {}", e);
@@ -431,14 +682,33 @@ where
                             }
                         }
                     }
+                    // Do not hold back the head if no more blocks arrived since the last tick
+                    self.commit_l2_blocks_or_shutdown().await?;
+                },
+                // The synced state diverged from the sequencer's, it is kept as is for inspection
+                // and the node only serves RPC and processes L1 blocks until shutdown
+                _ = l2_sync_halt.cancelled() => {
+                    select! {
+                        failure = critical_task_failures.recv() => {
+                            self.shutdown().await?;
+                            return Err(failure.into());
+                        },
+                        _ = shutdown_signal.recv() => return self.shutdown().await,
+                    }
+                },
+                // A critical task died, the node can not keep running without it
+                failure = critical_task_failures.recv() => {
+                    self.shutdown().await?;
+                    return Err(failure.into());
                 },
                 Some(_) = shutdown_signal.recv() => return self.shutdown().await,
             }
         }
     }
 
-    async fn shutdown(&self) -> anyhow::Result<()> {
+    async fn shutdown(&mut self) -> anyhow::Result<()> {
         info!("Shutting down");
+        self.commit_l2_blocks()?;
         self.task_manager.abort().await;
         Ok(())
     }
@@ -451,29 +721,34 @@ where
 
 async fn sync_l2(
     start_l2_height: u64,
-    sequencer_client: HttpClient,
+    sequencer_clients: Arc<SequencerClients>,
     sender: mpsc::Sender<Vec<(u64, SoftConfirmationResponse)>>,
     sync_blocks_count: u64,
 ) {
     let mut l2_height = start_l2_height;
     info!("Starting to sync from L2 height {}", l2_height);
     loop {
+        sequencer_clients.probe_primary().await;
+
         let exponential_backoff = ExponentialBackoffBuilder::new()
             .with_initial_interval(Duration::from_secs(1))
             .with_max_elapsed_time(Some(Duration::from_secs(15 * 60)))
             .build();
 
-        let inner_client = &sequencer_client;
+        let inner_clients = &sequencer_clients;
         let soft_confirmations = match retry_backoff(exponential_backoff.clone(), || async move {
-            match inner_client
+            match inner_clients
+                .current()
                 .get_soft_confirmation_range(
                     U64::from(l2_height),
                     U64::from(l2_height + sync_blocks_count - 1),
+                    None,
                 )
                 .await
             {
                 Ok(soft_confirmations) => {
-                    Ok(soft_confirmations.into_iter().flatten().collect::<Vec<_>>())
+                    inner_clients.on_success();
+                    Ok(soft_confirmations)
                 }
                 Err(e) => match e {
                     JsonrpseeError::Transport(e) => {
@@ -482,11 +757,17 @@ async fn sync_l2(
                             e
                         );
                         debug!(error_msg);
+                        inner_clients.on_transport_error();
                         Err(backoff::Error::Transient {
                             err: error_msg,
                             retry_after: None,
                         })
                     }
+                    // The sequencer has not produced the requested soft confirmations yet
+                    e if e.is_not_found() => {
+                        inner_clients.on_success();
+                        Ok(vec![])
+                    }
                     _ => Err(backoff::Error::Transient {
                         err: format!("Soft Confirmation: unknown error from RPC call: {:?}", e),
                         retry_after: None,
@@ -502,6 +783,9 @@ async fn sync_l2(
             }
         };
 
+        // Soft confirmations after a missing one are fetched again on the next request
+        let soft_confirmations = soft_confirmations_with_heights(l2_height, soft_confirmations);
+
         if soft_confirmations.is_empty() {
             debug!(
                 "Soft Confirmation: no batch at starting height {}, retrying...",
@@ -512,28 +796,28 @@ async fn sync_l2(
             continue;
         }
 
-        let mut soft_confirmations: Vec<(u64, SoftConfirmationResponse)> = (l2_height
-            ..l2_height + soft_confirmations.len() as u64)
-            .zip(soft_confirmations)
-            .collect();
-
         l2_height += soft_confirmations.len() as u64;
 
-        // Make sure soft confirmations are sorted for us to make sure they are processed
-        // in the correct order.
-        soft_confirmations.sort_by_key(|(height, _)| *height);
-
         if let Err(e) = sender.send(soft_confirmations).await {
             error!("Could not notify about L2 block: {}", e);
         }
     }
 }
 
-async fn get_initial_slot_height(client: &HttpClient) -> u64 {
+async fn get_initial_slot_height(sequencer_clients: &SequencerClients) -> u64 {
     loop {
-        match client.get_soft_confirmation_by_number(U64::from(1)).await {
+        sequencer_clients.probe_primary().await;
+
+        match sequencer_clients
+            .current()
+            .get_soft_confirmation_by_number(U64::from(1), None)
+            .await
+        {
             Ok(Some(soft_confirmation)) => return soft_confirmation.da_slot_height,
-            _ => {
+            result => {
+                if matches!(result, Err(JsonrpseeError::Transport(_))) {
+                    sequencer_clients.on_transport_error();
+                }
                 // sleep 1
                 tokio::time::sleep(std::time::Duration::from_secs(1)).await;
                 continue;
@@ -541,3 +825,137 @@ async fn get_initial_slot_height(client: &HttpClient) -> u64 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use jsonrpsee::server::ServerHandle;
+    use jsonrpsee::types::ErrorObjectOwned;
+    use sov_rollup_interface::rpc::SoftConfirmationDetail;
+
+    use super::*;
+
+    /// Head of the mock sequencer endpoints
+    const HEAD: u64 = 30;
+
+    fn soft_confirmation(l2_height: u64, endpoint: u64) -> SoftConfirmationResponse {
+        SoftConfirmationResponse {
+            l2_height,
+            da_slot_height: 1,
+            da_slot_hash: [0; 32],
+            da_slot_txs_commitment: [0; 32],
+            hash: [l2_height as u8; 32],
+            prev_hash: [l2_height as u8 - 1; 32],
+            txs: None,
+            state_root: vec![],
+            soft_confirmation_signature: vec![],
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            // Tells which endpoint served the soft confirmation
+            timestamp: endpoint,
+            tx_summaries: None,
+        }
+    }
+
+    async fn start_sequencer_endpoint(endpoint: u64) -> (String, ServerHandle) {
+        start_sequencer_endpoint_with_gap(endpoint, Arc::new(AtomicU64::new(0))).await
+    }
+
+    /// Starts an endpoint which returns null for the L2 height in `gap`, 0 for none
+    async fn start_sequencer_endpoint_with_gap(
+        endpoint: u64,
+        gap: Arc<AtomicU64>,
+    ) -> (String, ServerHandle) {
+        let mut module = RpcModule::new(());
+        module
+            .register_method("ledger_getSoftConfirmationRange", move |params, _, _| {
+                let (start, end, _detail): (U64, U64, Option<SoftConfirmationDetail>) =
+                    params.parse()?;
+                let gap = gap.load(Ordering::SeqCst);
+                let soft_confirmations = (start.to::<u64>()..=end.to::<u64>().min(HEAD))
+                    .map(|l2_height| {
+                        (l2_height != gap).then(|| soft_confirmation(l2_height, endpoint))
+                    })
+                    .collect::<Vec<_>>();
+                Ok::<_, ErrorObjectOwned>(soft_confirmations)
+            })
+            .unwrap();
+        let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        (url, server.start(module))
+    }
+
+    #[tokio::test]
+    async fn test_sync_l2_fails_over_to_secondary_endpoint() {
+        let (primary_url, primary) = start_sequencer_endpoint(1).await;
+        let (secondary_url, secondary) = start_sequencer_endpoint(2).await;
+        let sequencer_clients =
+            Arc::new(SequencerClients::new(primary_url, vec![secondary_url]).unwrap());
+
+        let (l2_tx, mut l2_rx) = mpsc::channel(1);
+        let sync_task = tokio::spawn(sync_l2(1, sequencer_clients, l2_tx, 5));
+
+        let mut synced = l2_rx.recv().await.unwrap();
+        assert!(synced.iter().all(|(_, l2_block)| l2_block.timestamp == 1));
+
+        // The primary goes down mid-sync
+        primary.stop().unwrap();
+        primary.stopped().await;
+
+        while synced.last().unwrap().0 < HEAD {
+            synced.extend(l2_rx.recv().await.unwrap());
+        }
+        sync_task.abort();
+
+        // No L2 block is missed or synced twice
+        let l2_heights = synced
+            .iter()
+            .map(|(l2_height, l2_block)| {
+                assert_eq!(*l2_height, l2_block.l2_height);
+                *l2_height
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(l2_heights, (1..=HEAD).collect::<Vec<_>>());
+        assert_eq!(synced.last().unwrap().1.timestamp, 2);
+
+        secondary.stop().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sync_l2_stops_at_missing_soft_confirmation() {
+        let gap = Arc::new(AtomicU64::new(8));
+        let (url, sequencer) = start_sequencer_endpoint_with_gap(1, gap.clone()).await;
+        let sequencer_clients = Arc::new(SequencerClients::new(url, vec![]).unwrap());
+
+        let (l2_tx, mut l2_rx) = mpsc::channel(1);
+        let sync_task = tokio::spawn(sync_l2(1, sequencer_clients, l2_tx, 5));
+
+        let mut synced = l2_rx.recv().await.unwrap();
+        while synced.last().unwrap().0 < 7 {
+            synced.extend(l2_rx.recv().await.unwrap());
+        }
+        // Nothing after the gap is synced while it is there
+        assert_eq!(synced.last().unwrap().0, 7);
+        sleep(Duration::from_secs(2)).await;
+        assert!(l2_rx.try_recv().is_err());
+
+        gap.store(0, Ordering::SeqCst);
+        while synced.last().unwrap().0 < HEAD {
+            synced.extend(l2_rx.recv().await.unwrap());
+        }
+        sync_task.abort();
+
+        let l2_heights = synced
+            .iter()
+            .map(|(l2_height, l2_block)| {
+                assert_eq!(*l2_height, l2_block.l2_height);
+                *l2_height
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(l2_heights, (1..=HEAD).collect::<Vec<_>>());
+
+        sequencer.stop().unwrap();
+    }
+}
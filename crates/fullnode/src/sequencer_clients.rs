@@ -0,0 +1,200 @@
+//! Sequencer endpoints a full node syncs from, with failover between them
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use sov_ledger_rpc::LedgerRpcClient;
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+use crate::metrics::FULLNODE_METRICS;
+
+/// Consecutive transport errors after which the next endpoint is used
+const FAILOVER_AFTER_ERRORS: u32 = 3;
+/// How often the primary endpoint is probed while syncing from a secondary one
+const PRIMARY_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Prioritized list of sequencer endpoints, the first one being the primary.
+/// Callers report transport errors, and the next endpoint is used once the current one keeps failing.
+pub(crate) struct SequencerClients {
+    endpoints: Vec<(String, HttpClient)>,
+    current: AtomicUsize,
+    consecutive_errors: AtomicU32,
+    last_primary_probe: Mutex<Instant>,
+    primary_probe_interval: Duration,
+}
+
+impl SequencerClients {
+    pub(crate) fn new(
+        primary_url: String,
+        secondary_urls: Vec<String>,
+    ) -> Result<Self, jsonrpsee::core::client::Error> {
+        let endpoints = std::iter::once(primary_url)
+            .chain(secondary_urls)
+            .map(|url| {
+                let client = HttpClientBuilder::default().build(&url)?;
+                Ok((url, client))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            endpoints,
+            current: AtomicUsize::new(0),
+            consecutive_errors: AtomicU32::new(0),
+            last_primary_probe: Mutex::new(Instant::now()),
+            primary_probe_interval: PRIMARY_PROBE_INTERVAL,
+        })
+    }
+
+    /// Client of the endpoint currently synced from
+    pub(crate) fn current(&self) -> &HttpClient {
+        &self.endpoints[self.current.load(Ordering::Relaxed)].1
+    }
+
+    pub(crate) fn on_success(&self) {
+        self.consecutive_errors.store(0, Ordering::Relaxed);
+    }
+
+    /// Moves on to the next endpoint after `FAILOVER_AFTER_ERRORS` consecutive transport errors
+    pub(crate) fn on_transport_error(&self) {
+        let errors = self.consecutive_errors.fetch_add(1, Ordering::Relaxed) + 1;
+        if errors < FAILOVER_AFTER_ERRORS || self.endpoints.len() == 1 {
+            return;
+        }
+
+        let from = self.current.load(Ordering::Relaxed);
+        let to = (from + 1) % self.endpoints.len();
+        self.current.store(to, Ordering::Relaxed);
+        self.consecutive_errors.store(0, Ordering::Relaxed);
+        *self.last_primary_probe.lock().unwrap() = Instant::now();
+
+        warn!(
+            "Sequencer endpoint {} is unreachable, failing over to {}",
+            self.endpoints[from].0, self.endpoints[to].0
+        );
+        FULLNODE_METRICS.sequencer_failovers.increment(1);
+    }
+
+    /// Switches back to the primary endpoint if it is reachable again.
+    /// The primary is probed at most once per `primary_probe_interval`.
+    pub(crate) async fn probe_primary(&self) {
+        if self.current.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        {
+            let mut last_primary_probe = self.last_primary_probe.lock().unwrap();
+            if last_primary_probe.elapsed() < self.primary_probe_interval {
+                return;
+            }
+            *last_primary_probe = Instant::now();
+        }
+
+        let (primary_url, primary_client) = &self.endpoints[0];
+        if primary_client
+            .get_head_soft_confirmation_height()
+            .await
+            .is_ok()
+        {
+            self.current.store(0, Ordering::Relaxed);
+            self.consecutive_errors.store(0, Ordering::Relaxed);
+
+            info!(
+                "Primary sequencer endpoint {} is reachable again, switching back to it",
+                primary_url
+            );
+            FULLNODE_METRICS.sequencer_primary_restored.increment(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use jsonrpsee::server::ServerBuilder;
+    use jsonrpsee::RpcModule;
+
+    use super::*;
+
+    /// An address nothing is listening on
+    fn unused_url() -> String {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn test_fails_over_after_consecutive_errors() {
+        let clients =
+            SequencerClients::new(unused_url(), vec![unused_url(), unused_url()]).unwrap();
+
+        clients.on_transport_error();
+        clients.on_transport_error();
+        // A success in between resets the count
+        clients.on_success();
+        clients.on_transport_error();
+        clients.on_transport_error();
+        assert_eq!(clients.current.load(Ordering::Relaxed), 0);
+
+        clients.on_transport_error();
+        assert_eq!(clients.current.load(Ordering::Relaxed), 1);
+
+        for _ in 0..FAILOVER_AFTER_ERRORS {
+            clients.on_transport_error();
+        }
+        assert_eq!(clients.current.load(Ordering::Relaxed), 2);
+
+        // Wraps around to the primary
+        for _ in 0..FAILOVER_AFTER_ERRORS {
+            clients.on_transport_error();
+        }
+        assert_eq!(clients.current.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_single_endpoint_never_fails_over() {
+        let clients = SequencerClients::new(unused_url(), vec![]).unwrap();
+
+        for _ in 0..FAILOVER_AFTER_ERRORS * 2 {
+            clients.on_transport_error();
+        }
+        assert_eq!(clients.current.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_primary_once_reachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let primary_addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let mut clients =
+            SequencerClients::new(format!("http://{}", primary_addr), vec![unused_url()]).unwrap();
+        clients.primary_probe_interval = Duration::ZERO;
+
+        for _ in 0..FAILOVER_AFTER_ERRORS {
+            clients.on_transport_error();
+        }
+        assert_eq!(clients.current.load(Ordering::Relaxed), 1);
+
+        // The primary is still down
+        clients.probe_primary().await;
+        assert_eq!(clients.current.load(Ordering::Relaxed), 1);
+
+        let mut module = RpcModule::new(());
+        module
+            .register_method("ledger_getHeadSoftConfirmationHeight", |_, _, _| {
+                jsonrpsee::core::RpcResult::Ok(1u64)
+            })
+            .unwrap();
+        let server = ServerBuilder::default().build(primary_addr).await.unwrap();
+        let primary = server.start(module);
+
+        clients.probe_primary().await;
+        assert_eq!(clients.current.load(Ordering::Relaxed), 0);
+
+        primary.stop().unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+//! Fills in the transaction bodies of L2 blocks synced while `include_tx_body` was off
+use std::sync::Arc;
+
+use alloy_primitives::U64;
+use anyhow::{bail, Context as _};
+use citrea_common::utils::soft_confirmation_to_receipt;
+use citrea_common::TxBodyBackfillConfig;
+use citrea_primitives::forks::fork_from_block_number;
+use jsonrpsee::core::client::Error as JsonrpseeError;
+use sov_db::ledger_db::SharedLedgerOps;
+use sov_db::schema::types::{SoftConfirmationNumber, StoredSoftConfirmation};
+use sov_ledger_rpc::LedgerRpcClient;
+use sov_modules_api::transaction::Transaction;
+use sov_modules_api::{Context, SignedSoftConfirmation};
+use sov_rollup_interface::da::DaSpec;
+use sov_rollup_interface::rpc::SoftConfirmationResponse;
+use tokio::select;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use crate::sequencer_clients::SequencerClients;
+
+/// Fetches the transaction bodies of the stored L2 blocks up to `end_l2_height` which lack them
+/// from the sequencer, requesting at most `requests_per_second` blocks per second.
+/// Each block is verified against the stored one before its bodies are written.
+/// Progress is persisted, so an interrupted backfill resumes where it left off.
+pub(crate) async fn backfill_tx_bodies<C, Da, DB>(
+    ledger_db: DB,
+    sequencer_clients: Arc<SequencerClients>,
+    end_l2_height: u64,
+    config: TxBodyBackfillConfig,
+    cancellation_token: CancellationToken,
+) where
+    C: Context,
+    Da: DaSpec,
+    DB: SharedLedgerOps,
+{
+    let start_l2_height = match ledger_db.get_last_tx_body_backfill_l2_height() {
+        Ok(last_backfilled) => last_backfilled.unwrap_or(0) + 1,
+        Err(e) => {
+            error!("Could not read transaction body backfill progress: {}", e);
+            return;
+        }
+    };
+    if start_l2_height > end_l2_height {
+        return;
+    }
+    info!(
+        "Backfilling transaction bodies of L2 blocks {} to {}",
+        start_l2_height, end_l2_height
+    );
+
+    let mut rate_limit = interval(Duration::from_secs(1) / config.requests_per_second.max(1));
+    rate_limit.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut backfilled_count = 0;
+    for l2_height in start_l2_height..=end_l2_height {
+        if cancellation_token.is_cancelled() {
+            return;
+        }
+
+        // Pruned blocks and blocks stored with their bodies are skipped
+        let stored =
+            match ledger_db.get_soft_confirmation_by_number(&SoftConfirmationNumber(l2_height)) {
+                Ok(Some(stored)) if stored.txs.iter().any(|tx| tx.body.is_none()) => stored,
+                Ok(_) => continue,
+                Err(e) => {
+                    error!("Could not read L2 block {}: {}", l2_height, e);
+                    return;
+                }
+            };
+
+        let fetch = fetch_soft_confirmation(&sequencer_clients, l2_height, &mut rate_limit);
+        let soft_confirmation = select! {
+            _ = cancellation_token.cancelled() => return,
+            soft_confirmation = fetch => soft_confirmation,
+        };
+
+        let tx_bodies = match verified_tx_bodies::<C, Da>(&stored, soft_confirmation) {
+            Ok(tx_bodies) => tx_bodies,
+            Err(e) => {
+                // The progress is kept, so the backfill continues from here after a restart
+                error!(
+                    "Stopping transaction body backfill, could not verify L2 block {} from the sequencer: {:?}",
+                    l2_height, e
+                );
+                return;
+            }
+        };
+
+        if let Err(e) = ledger_db
+            .put_tx_bodies(SoftConfirmationNumber(l2_height), tx_bodies)
+            .and_then(|_| ledger_db.set_last_tx_body_backfill_l2_height(l2_height))
+        {
+            error!(
+                "Could not store transaction bodies of L2 block {}: {}",
+                l2_height, e
+            );
+            return;
+        }
+        backfilled_count += 1;
+        debug!("Backfilled transaction bodies of L2 block {}", l2_height);
+    }
+
+    if let Err(e) = ledger_db.set_last_tx_body_backfill_l2_height(end_l2_height) {
+        error!("Could not store transaction body backfill progress: {}", e);
+        return;
+    }
+    info!(
+        "Backfilled transaction bodies of {} L2 blocks up to L2 height {}",
+        backfilled_count, end_l2_height
+    );
+}
+
+/// Requests the L2 block from the sequencer until it is served, at most once per `rate_limit` tick
+async fn fetch_soft_confirmation(
+    sequencer_clients: &SequencerClients,
+    l2_height: u64,
+    rate_limit: &mut tokio::time::Interval,
+) -> Option<SoftConfirmationResponse> {
+    loop {
+        rate_limit.tick().await;
+        sequencer_clients.probe_primary().await;
+
+        match sequencer_clients
+            .current()
+            .get_soft_confirmation_by_number(U64::from(l2_height), None)
+            .await
+        {
+            Ok(soft_confirmation) => {
+                sequencer_clients.on_success();
+                return soft_confirmation;
+            }
+            Err(e) => {
+                if matches!(e, JsonrpseeError::Transport(_)) {
+                    sequencer_clients.on_transport_error();
+                }
+                warn!(
+                    "Could not fetch L2 block {} for transaction body backfill: {}",
+                    l2_height, e
+                );
+            }
+        }
+    }
+}
+
+/// Returns the transaction bodies of the sequencer's L2 block if it is the stored one.
+/// The hashes of the bodies must match the stored transaction hashes.
+fn verified_tx_bodies<C: Context, Da: DaSpec>(
+    stored: &StoredSoftConfirmation,
+    soft_confirmation: Option<SoftConfirmationResponse>,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    let Some(soft_confirmation) = soft_confirmation else {
+        bail!("The sequencer does not have the L2 block");
+    };
+    if soft_confirmation.hash != stored.hash {
+        bail!(
+            "Hash mismatch, stored 0x{}, sequencer 0x{}",
+            hex::encode(stored.hash),
+            hex::encode(soft_confirmation.hash)
+        );
+    }
+
+    let tx_bodies: Vec<Vec<u8>> = soft_confirmation
+        .txs
+        .iter()
+        .flatten()
+        .map(|tx| tx.tx.clone())
+        .collect();
+    if tx_bodies.len() != stored.txs.len() {
+        bail!(
+            "Got {} transaction bodies for {} stored transactions",
+            tx_bodies.len(),
+            stored.txs.len()
+        );
+    }
+
+    let signed_soft_confirmation: SignedSoftConfirmation<Transaction<C>> = soft_confirmation
+        .try_into()
+        .context("Failed to parse transactions")?;
+    let spec_id = fork_from_block_number(stored.l2_height).spec_id;
+    let receipt = soft_confirmation_to_receipt::<C, _, Da>(signed_soft_confirmation, spec_id);
+    if receipt
+        .tx_hashes
+        .iter()
+        .ne(stored.txs.iter().map(|tx| &tx.hash))
+    {
+        bail!("Transaction hashes do not match the stored ones");
+    }
+
+    Ok(tx_bodies)
+}
@@ -1,9 +1,9 @@
-use borsh::BorshDeserialize;
 use sov_modules_api::BlobReaderTrait;
-use sov_rollup_interface::da::{DaDataLightClient, DaNamespace, DaVerifier};
+use sov_rollup_interface::da::{DaDataLightClient, DaNamespace, DaVerifier, VersionedDaData};
+use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::zk::{
-    BatchProofCircuitOutput, BatchProofInfo, LightClientCircuitInput, LightClientCircuitOutput,
-    ZkvmGuest,
+    BatchProofCircuitOutput, BatchProofInfo, BatchProofOutputVersion, LightClientCircuitInput,
+    LightClientCircuitOutput, ZkvmGuest,
 };
 
 use crate::utils::{collect_unchained_outputs, recursive_match_state_roots};
@@ -13,6 +13,7 @@ pub enum LightClientVerificationError {
     DaTxsCouldntBeVerified,
     HeaderChainVerificationFailed,
     InvalidPreviousLightClientProof,
+    InvalidBatchProofOutput,
 }
 
 pub fn run_circuit<DaV: DaVerifier, G: ZkvmGuest>(
@@ -20,6 +21,7 @@ pub fn run_circuit<DaV: DaVerifier, G: ZkvmGuest>(
     input: LightClientCircuitInput<DaV::Spec>,
     l2_genesis_root: [u8; 32],
     batch_proof_method_id: [u32; 8],
+    batch_proof_spec_id: SpecId,
     batch_prover_da_public_key: &[u8],
 ) -> Result<LightClientCircuitOutput<DaV::Spec>, LightClientVerificationError> {
     // Extract previous light client proof output
@@ -72,6 +74,7 @@ pub fn run_circuit<DaV: DaVerifier, G: ZkvmGuest>(
     if let Some(previous_output) = &previous_light_client_proof_output {
         for unchained_info in previous_output.unchained_batch_proofs_info.iter() {
             // Add them directly as they are the ones that could not be matched
+            // They are re-evaluated together with the new batch proofs below
             initial_to_final.insert(
                 unchained_info.initial_state_root,
                 (
@@ -81,27 +84,30 @@ pub fn run_circuit<DaV: DaVerifier, G: ZkvmGuest>(
             );
         }
     }
+    let batch_proof_output_version = BatchProofOutputVersion::from_spec(batch_proof_spec_id);
+
     // TODO: Test for multiple assumptions to see if the env::verify function does automatic matching between the journal and the assumption or do we need to verify them in order?
     // https://github.com/chainwayxyz/citrea/issues/1401
     // Parse the batch proof da data
     for blob in input.da_data {
         if blob.sender().as_ref() == batch_prover_da_public_key {
-            let data = DaDataLightClient::try_from_slice(blob.verified_data());
+            let data = DaDataLightClient::decode_versioned(blob.verified_data());
 
             if let Ok(data) = data {
                 match data {
                     DaDataLightClient::Complete(proof) => {
                         let journal =
                             G::extract_raw_output(&proof).expect("DaData proofs must be valid");
-                        // TODO: select output version based on the spec
+                        let Ok(journal) = G::verify(&journal, &batch_proof_method_id.into()) else {
+                            continue;
+                        };
+                        // A verified proof of the batch proof guest must be in the layout it outputs
                         let batch_proof_output: BatchProofCircuitOutput<DaV::Spec, [u8; 32]> =
-                            match G::verify_and_extract_output(
-                                &journal,
-                                &batch_proof_method_id.into(),
-                            ) {
-                                Ok(output) => output,
-                                Err(_) => continue,
-                            };
+                            batch_proof_output_version
+                                .decode_output(&journal)
+                                .map_err(|_| {
+                                    LightClientVerificationError::InvalidBatchProofOutput
+                                })?;
 
                         // Do not add if last l2 height is smaller or equal to previous output
                         // This is to defend against replay attacks, for example if somehow there is the script of batch proof 1 we do not need to go through it again
@@ -118,23 +124,23 @@ pub fn run_circuit<DaV: DaVerifier, G: ZkvmGuest>(
                             ),
                         );
                     }
-                    DaDataLightClient::Aggregate(_) => todo!(),
-                    DaDataLightClient::Chunk(_) => todo!(),
+                    // Chunked proofs are reassembled and verified by the DA verifier, they arrive as `Complete`.
+                    // An aggregate is only left as is when its chunks are missing from the block.
+                    DaDataLightClient::Aggregate(_) | DaDataLightClient::Chunk(_) => continue,
                 }
             }
         }
     }
 
-    // Do recursive matching for previous state root
-    recursive_match_state_roots(
-        &mut initial_to_final,
-        &BatchProofInfo::new(last_state_root, last_state_root, last_l2_height),
-    );
-
-    // Now only thing left is the state update if exists and others are unchained
-    if let Some((final_root, last_l2)) = initial_to_final.remove(&last_state_root) {
-        last_l2_height = last_l2;
-        last_state_root = final_root;
+    // Apply the transitions starting from the current state root until there are none left.
+    // Proofs of the previous output and of this block may chain in any order, e.g. a proof
+    // carried over from the previous output can continue from a proof that arrived in this block
+    while let Some((final_root, last_l2)) = initial_to_final.remove(&last_state_root) {
+        // A transition that does not move the l2 height forward is stale, it is dropped
+        if last_l2 > last_l2_height {
+            last_l2_height = last_l2;
+            last_state_root = final_root;
+        }
     }
 
     // Collect unchained outputs
@@ -1,19 +1,20 @@
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use alloy_primitives::U64;
 use anyhow::anyhow;
-use borsh::BorshDeserialize;
 use citrea_common::cache::L1BlockCache;
 use citrea_common::da::get_da_block_at_height;
+use citrea_common::utils::extract_batch_proof_output;
 use citrea_common::LightClientProverConfig;
 use citrea_primitives::forks::fork_from_block_number;
 use jsonrpsee::http_client::HttpClient;
 use sov_db::ledger_db::{LightClientProverLedgerOps, SharedLedgerOps};
 use sov_db::schema::types::{SlotNumber, StoredLightClientProofOutput};
 use sov_ledger_rpc::LedgerRpcClient;
-use sov_modules_api::{BatchProofCircuitOutput, BlobReaderTrait, DaSpec, Zkvm};
-use sov_rollup_interface::da::{BlockHeaderTrait, DaDataLightClient, DaNamespace};
+use sov_modules_api::{BlobReaderTrait, DaSpec, Zkvm};
+use sov_rollup_interface::da::{BlockHeaderTrait, DaDataLightClient, DaNamespace, VersionedDaData};
 use sov_rollup_interface::services::da::{DaService, SlotData};
 use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::zk::{
@@ -28,6 +29,9 @@ use tracing::{error, info};
 
 use crate::metrics::LIGHT_CLIENT_METRICS;
 
+/// How far L1 block timestamps can be past the local time, 2 hours
+const MAX_FUTURE_BLOCK_TIME: i64 = 60 * 60 * 2;
+
 pub(crate) struct L1BlockHandler<Vm, Da, Ps, DB>
 where
     Da: DaService,
@@ -143,6 +147,21 @@ where
         let l1_hash = l1_block.header().hash().into();
         let l1_height = l1_block.header().height();
 
+        // The local clock can not be proven, so headers too far past it are checked here
+        // and proven once the clock catches up
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as i64;
+        let block_time = l1_block.header().time().secs();
+        if block_time > now.saturating_add(MAX_FUTURE_BLOCK_TIME) {
+            return Err(anyhow!(
+                "L1 block {} timestamp {} is too far in the future",
+                l1_height,
+                block_time
+            ));
+        }
+
         // Set the l1 height of the l1 hash
         self.ledger_db
             .set_l1_height_of_l1_hash(l1_hash, l1_height)
@@ -162,11 +181,9 @@ where
         let mut assumptions = vec![];
         for batch_proof in batch_proofs {
             if let DaDataLightClient::Complete(proof) = batch_proof {
-                let batch_proof_output = Vm::extract_output::<
-                    <Da as DaService>::Spec,
-                    BatchProofCircuitOutput<<Da as DaService>::Spec, [u8; 32]>,
-                >(&proof)
-                .map_err(|_| anyhow!("Proof should be deserializable"))?;
+                let (_, batch_proof_output) =
+                    extract_batch_proof_output::<Vm, <Da as DaService>::Spec, [u8; 32]>(&proof)
+                        .map_err(|e| anyhow!("Proof should be deserializable: {}", e))?;
                 let last_l2_height = batch_proof_output.last_l2_height;
                 let current_spec = fork_from_block_number(last_l2_height).spec_id;
                 let batch_proof_method_id = self
@@ -196,7 +213,7 @@ where
             None => {
                 let soft_confirmation = self
                     .sequencer_client
-                    .get_soft_confirmation_by_number(U64::from(1))
+                    .get_soft_confirmation_by_number(U64::from(1), None)
                     .await?
                     .unwrap();
                 let initial_l1_height = soft_confirmation.da_slot_height;
@@ -293,7 +310,7 @@ where
         da_data.iter_mut().for_each(|tx| {
             // Check for commitment
             if tx.sender().as_ref() == self.batch_prover_da_pub_key.as_slice() {
-                let data = DaDataLightClient::try_from_slice(tx.full_data());
+                let data = DaDataLightClient::decode_versioned(tx.full_data());
 
                 if let Ok(proof) = data {
                     batch_proofs.push(proof);
@@ -3,7 +3,7 @@ use metrics_derive::Metrics;
 use once_cell::sync::Lazy;
 
 #[derive(Metrics)]
-#[metrics(scope = "light_client_prover")]
+#[metrics(scope = "citrea_light_client_prover")]
 pub struct LightClientProverMetrics {
     #[metric(describe = "The current L1 block number which is used to produce L2 blocks")]
     pub current_l1_block: Gauge,
@@ -22,6 +22,10 @@ pub trait LightClientProverRpc {
         &self,
         l1_height: u64,
     ) -> RpcResult<Option<LightClientProofResponse>>;
+
+    /// Gets the light client proof of the highest L1 height.
+    #[method(name = "getLastLightClientProof")]
+    async fn get_last_light_client_proof(&self) -> RpcResult<Option<LightClientProofResponse>>;
 }
 
 pub struct LightClientProverRpcServerImpl<DB>
@@ -65,6 +69,22 @@ where
         let res = proof.map(LightClientProofResponse::from);
         Ok(res)
     }
+
+    async fn get_last_light_client_proof(&self) -> RpcResult<Option<LightClientProofResponse>> {
+        let proof = self
+            .context
+            .ledger
+            .get_last_light_client_proof_data()
+            .map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(format!("{e}",)),
+                )
+            })?;
+        let res = proof.map(|(_, proof)| LightClientProofResponse::from(proof));
+        Ok(res)
+    }
 }
 
 pub fn create_rpc_module<DB>(
@@ -5,7 +5,6 @@ use std::sync::Arc;
 use citrea_common::tasks::manager::TaskManager;
 use citrea_common::{LightClientProverConfig, RollupPublicKeys, RpcConfig, RunnerConfig};
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
-use jsonrpsee::server::{BatchRequestConfig, ServerBuilder};
 use jsonrpsee::RpcModule;
 use sov_db::ledger_db::{LightClientProverLedgerOps, SharedLedgerOps};
 use sov_db::schema::types::SlotNumber;
@@ -15,7 +14,7 @@ use sov_rollup_interface::zk::ZkvmHost;
 use sov_stf_runner::ProverService;
 use tokio::signal;
 use tokio::sync::oneshot;
-use tracing::{error, info, instrument};
+use tracing::{info, instrument};
 
 use crate::da_block_handler::L1BlockHandler;
 use crate::rpc::{create_rpc_module, RpcContext};
@@ -86,60 +85,8 @@ where
         channel: Option<oneshot::Sender<SocketAddr>>,
     ) -> anyhow::Result<()> {
         let methods = self.register_rpc_methods(methods)?;
-        let listen_address = SocketAddr::new(
-            self.rpc_config
-                .bind_host
-                .parse()
-                .map_err(|e| anyhow::anyhow!("Failed to parse bind host: {}", e))?,
-            self.rpc_config.bind_port,
-        );
 
-        let max_connections = self.rpc_config.max_connections;
-        let max_subscriptions_per_connection = self.rpc_config.max_subscriptions_per_connection;
-        let max_request_body_size = self.rpc_config.max_request_body_size;
-        let max_response_body_size = self.rpc_config.max_response_body_size;
-        let batch_requests_limit = self.rpc_config.batch_requests_limit;
-
-        let middleware = tower::ServiceBuilder::new().layer(citrea_common::rpc::get_cors_layer());
-        //  .layer(citrea_common::rpc::get_healthcheck_proxy_layer());
-
-        self.task_manager.spawn(|cancellation_token| async move {
-            let server = ServerBuilder::default()
-                .max_connections(max_connections)
-                .max_subscriptions_per_connection(max_subscriptions_per_connection)
-                .max_request_body_size(max_request_body_size)
-                .max_response_body_size(max_response_body_size)
-                .set_batch_request_config(BatchRequestConfig::Limit(batch_requests_limit))
-                .set_http_middleware(middleware)
-                .build([listen_address].as_ref())
-                .await;
-
-            match server {
-                Ok(server) => {
-                    let bound_address = match server.local_addr() {
-                        Ok(address) => address,
-                        Err(e) => {
-                            error!("{}", e);
-                            return;
-                        }
-                    };
-                    if let Some(channel) = channel {
-                        if let Err(e) = channel.send(bound_address) {
-                            error!("Could not send bound_address {}: {}", bound_address, e);
-                            return;
-                        }
-                    }
-                    info!("Starting RPC server at {} ", &bound_address);
-
-                    let _server_handle = server.start(methods);
-                    cancellation_token.cancelled().await;
-                }
-                Err(e) => {
-                    error!("Could not start RPC server: {}", e);
-                }
-            }
-        });
-        Ok(())
+        citrea_common::rpc::start_server(&self.rpc_config, methods, channel, &mut self.task_manager)
     }
 
     /// Runs the rollup.
@@ -161,26 +108,38 @@ where
         let light_client_proof_elfs = self.light_client_proof_elfs.clone();
         let sequencer_client = self.sequencer_client.clone();
 
-        self.task_manager.spawn(|cancellation_token| async move {
-            let l1_block_handler = L1BlockHandler::<Vm, Da, Ps, DB>::new(
-                prover_config,
-                prover_service,
-                ledger_db,
-                da_service,
-                batch_prover_da_pub_key,
-                batch_proof_commitments_by_spec,
-                light_client_proof_commitment,
-                light_client_proof_elfs,
-                Arc::new(sequencer_client),
-            );
-            l1_block_handler
-                .run(last_l1_height_scanned.0, cancellation_token)
-                .await
-        });
+        self.task_manager
+            .spawn("l1_block_handler", |cancellation_token| async move {
+                let l1_block_handler = L1BlockHandler::<Vm, Da, Ps, DB>::new(
+                    prover_config,
+                    prover_service,
+                    ledger_db,
+                    da_service,
+                    batch_prover_da_pub_key,
+                    batch_proof_commitments_by_spec,
+                    light_client_proof_commitment,
+                    light_client_proof_elfs,
+                    Arc::new(sequencer_client),
+                );
+                l1_block_handler
+                    .run(last_l1_height_scanned.0, cancellation_token)
+                    .await
+            });
+
+        let mut critical_task_failures = self.task_manager.critical_task_failures();
 
         // Temporary fix
-        signal::ctrl_c().await.expect("Failed to listen ctrl+c");
-        Ok(())
+        tokio::select! {
+            result = signal::ctrl_c() => {
+                result.expect("Failed to listen ctrl+c");
+                Ok(())
+            }
+            // A critical task died, the node can not keep running without it
+            failure = critical_task_failures.recv() => {
+                self.task_manager.abort().await;
+                Err(failure.into())
+            }
+        }
 
         // TODO: update this once l2 sync is implemented
         // loop {
@@ -1,9 +1,15 @@
 mod test_utils;
 
-use sov_mock_da::{MockBlockHeader, MockDaVerifier};
+use sov_mock_da::{MockBlob, MockBlockHeader, MockDaSpec, MockDaVerifier};
 use sov_mock_zkvm::MockZkGuest;
-use sov_rollup_interface::zk::LightClientCircuitInput;
-use test_utils::{create_mock_blob, create_prev_lcp_serialized};
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::zk::{
+    BatchProofCircuitOutput, LightClientCircuitInput, LightClientCircuitOutput,
+};
+use test_utils::{
+    create_batch_proof_output, create_mock_blob, create_mock_blob_with_output,
+    create_prev_lcp_serialized,
+};
 
 use crate::circuit::{run_circuit, LightClientVerificationError};
 
@@ -35,6 +41,7 @@ fn test_light_client_circuit_valid_da_valid_data() {
         input,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     )
     .unwrap();
@@ -66,6 +73,7 @@ fn test_light_client_circuit_valid_da_valid_data() {
         input_2,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     )
     .unwrap();
@@ -104,6 +112,7 @@ fn test_wrong_order_da_blocks_should_still_work() {
         input,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     )
     .unwrap();
@@ -142,6 +151,7 @@ fn create_unchainable_outputs_then_chain_them_on_next_block() {
         input,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     )
     .unwrap();
@@ -184,6 +194,7 @@ fn create_unchainable_outputs_then_chain_them_on_next_block() {
         input_2,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     )
     .unwrap();
@@ -195,6 +206,122 @@ fn create_unchainable_outputs_then_chain_them_on_next_block() {
     assert_eq!(output_2.last_l2_height, 4);
 }
 
+/// Runs the circuit on two sequential l1 blocks with the given batch proofs
+fn run_circuit_on_two_blocks(
+    first_block_blobs: Vec<MockBlob>,
+    second_block_blobs: Vec<MockBlob>,
+) -> (
+    LightClientCircuitOutput<MockDaSpec>,
+    LightClientCircuitOutput<MockDaSpec>,
+) {
+    let light_client_proof_method_id = [1u32; 8];
+    let batch_proof_method_id = [1u32; 8];
+    let da_verifier = MockDaVerifier {};
+
+    let l2_genesis_state_root = [1u8; 32];
+    let batch_prover_da_pub_key = [9; 32].to_vec();
+
+    let input_1 = LightClientCircuitInput {
+        previous_light_client_proof_journal: None,
+        light_client_proof_method_id,
+        da_block_header: MockBlockHeader::from_height(1),
+        da_data: first_block_blobs,
+        inclusion_proof: [1u8; 32],
+        completeness_proof: (),
+    };
+
+    let output_1 = run_circuit::<_, MockZkGuest>(
+        da_verifier.clone(),
+        input_1,
+        l2_genesis_state_root,
+        batch_proof_method_id,
+        SpecId::Fork1,
+        &batch_prover_da_pub_key,
+    )
+    .unwrap();
+
+    let input_2 = LightClientCircuitInput {
+        previous_light_client_proof_journal: Some(create_prev_lcp_serialized(
+            output_1.clone(),
+            true,
+        )),
+        light_client_proof_method_id,
+        da_block_header: MockBlockHeader::from_height(2),
+        da_data: second_block_blobs,
+        inclusion_proof: [1u8; 32],
+        completeness_proof: (),
+    };
+
+    let output_2 = run_circuit::<_, MockZkGuest>(
+        da_verifier,
+        input_2,
+        l2_genesis_state_root,
+        batch_proof_method_id,
+        SpecId::Fork1,
+        &batch_prover_da_pub_key,
+    )
+    .unwrap();
+
+    (output_1, output_2)
+}
+
+#[test]
+fn test_reversed_batch_proofs_across_da_blocks() {
+    // Batch proofs of 1->2->3->4->5 in order
+    let (in_order_1, in_order_2) = run_circuit_on_two_blocks(
+        vec![
+            create_mock_blob([1u8; 32], [2u8; 32], 2, true),
+            create_mock_blob([2u8; 32], [3u8; 32], 3, true),
+        ],
+        vec![
+            create_mock_blob([3u8; 32], [4u8; 32], 4, true),
+            create_mock_blob([4u8; 32], [5u8; 32], 5, true),
+        ],
+    );
+    assert_eq!(in_order_1.state_root, [3; 32]);
+    assert_eq!(in_order_2.state_root, [5; 32]);
+    assert_eq!(in_order_2.last_l2_height, 5);
+    assert!(in_order_2.unchained_batch_proofs_info.is_empty());
+
+    // The same batch proofs in reversed order
+    let (reversed_1, reversed_2) = run_circuit_on_two_blocks(
+        vec![
+            create_mock_blob([4u8; 32], [5u8; 32], 5, true),
+            create_mock_blob([3u8; 32], [4u8; 32], 4, true),
+        ],
+        vec![
+            create_mock_blob([2u8; 32], [3u8; 32], 3, true),
+            create_mock_blob([1u8; 32], [2u8; 32], 2, true),
+        ],
+    );
+    // Nothing can be applied before 1->2 arrives
+    assert_eq!(reversed_1.state_root, [1; 32]);
+    assert_eq!(reversed_1.last_l2_height, 0);
+    assert_eq!(reversed_1.unchained_batch_proofs_info.len(), 1);
+
+    assert_eq!(reversed_2.state_root, in_order_2.state_root);
+    assert_eq!(reversed_2.last_l2_height, in_order_2.last_l2_height);
+    assert!(reversed_2.unchained_batch_proofs_info.is_empty());
+
+    // Interleaved so that the carried over proofs only chain after the new ones
+    let (interleaved_1, interleaved_2) = run_circuit_on_two_blocks(
+        vec![
+            create_mock_blob([4u8; 32], [5u8; 32], 5, true),
+            create_mock_blob([2u8; 32], [3u8; 32], 3, true),
+        ],
+        vec![
+            create_mock_blob([3u8; 32], [4u8; 32], 4, true),
+            create_mock_blob([1u8; 32], [2u8; 32], 2, true),
+        ],
+    );
+    assert_eq!(interleaved_1.state_root, [1; 32]);
+    assert_eq!(interleaved_1.unchained_batch_proofs_info.len(), 2);
+
+    assert_eq!(interleaved_2.state_root, in_order_2.state_root);
+    assert_eq!(interleaved_2.last_l2_height, in_order_2.last_l2_height);
+    assert!(interleaved_2.unchained_batch_proofs_info.is_empty());
+}
+
 #[test]
 fn test_header_chain_proof_height_and_hash() {
     let light_client_proof_method_id = [1u32; 8];
@@ -223,6 +350,7 @@ fn test_header_chain_proof_height_and_hash() {
         input,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     )
     .unwrap();
@@ -255,6 +383,7 @@ fn test_header_chain_proof_height_and_hash() {
         input_2,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     );
     assert!(matches!(
@@ -291,6 +420,7 @@ fn test_unverifiable_batch_proofs() {
         input,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     )
     .unwrap();
@@ -331,6 +461,7 @@ fn test_unverifiable_prev_light_client_proof() {
         input,
         l2_genesis_state_root,
         batch_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     )
     .unwrap();
@@ -360,6 +491,7 @@ fn test_unverifiable_prev_light_client_proof() {
         input_2,
         l2_genesis_state_root,
         light_client_proof_method_id,
+        SpecId::Fork1,
         &batch_prover_da_pub_key,
     );
     assert!(matches!(
@@ -367,3 +499,44 @@ fn test_unverifiable_prev_light_client_proof() {
         Err(LightClientVerificationError::InvalidPreviousLightClientProof)
     ));
 }
+
+#[test]
+fn test_batch_proof_output_in_wrong_layout() {
+    let light_client_proof_method_id = [1u32; 8];
+    let batch_proof_method_id = [1u32; 8];
+    let da_verifier = MockDaVerifier {};
+
+    // The Fork1 guest does not output the first l2 height
+    let bp = BatchProofCircuitOutput {
+        first_l2_height: 1,
+        ..BatchProofCircuitOutput::from(create_batch_proof_output([1u8; 32], [2u8; 32], 2))
+    };
+    let blob_1 = create_mock_blob_with_output(borsh::to_vec(&bp).unwrap(), true);
+
+    let block_header_1 = MockBlockHeader::from_height(1);
+
+    let input = LightClientCircuitInput {
+        previous_light_client_proof_journal: None,
+        light_client_proof_method_id,
+        da_block_header: block_header_1,
+        da_data: vec![blob_1],
+        inclusion_proof: [1u8; 32],
+        completeness_proof: (),
+    };
+
+    let l2_genesis_state_root = [1u8; 32];
+    let batch_prover_da_pub_key = [9; 32].to_vec();
+
+    let res = run_circuit::<_, MockZkGuest>(
+        da_verifier,
+        input,
+        l2_genesis_state_root,
+        batch_proof_method_id,
+        SpecId::Fork1,
+        &batch_prover_da_pub_key,
+    );
+    assert!(matches!(
+        res,
+        Err(LightClientVerificationError::InvalidBatchProofOutput)
+    ));
+}
@@ -2,8 +2,9 @@ use std::collections::BTreeMap;
 
 use sov_mock_da::{MockAddress, MockBlob, MockDaSpec, MockHash};
 use sov_mock_zkvm::{MockCodeCommitment, MockJournal, MockProof};
-use sov_rollup_interface::da::{BlobReaderTrait, DaDataLightClient};
-use sov_rollup_interface::zk::{BatchProofCircuitOutput, LightClientCircuitOutput};
+use sov_rollup_interface::da::{BlobReaderTrait, DaDataLightClient, VersionedDaData};
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::zk::{BatchProofCircuitOutputV1, LightClientCircuitOutput};
 
 pub(crate) fn create_mock_blob(
     initial_state_root: [u8; 32],
@@ -11,9 +12,18 @@ pub(crate) fn create_mock_blob(
     last_l2_height: u64,
     is_valid: bool,
 ) -> MockBlob {
-    let batch_proof_method_id = MockCodeCommitment([2u8; 32]);
+    let bp = create_batch_proof_output(initial_state_root, final_state_root, last_l2_height);
+
+    create_mock_blob_with_output(borsh::to_vec(&bp).expect("should serialize"), is_valid)
+}
 
-    let bp = BatchProofCircuitOutput::<MockDaSpec, [u8; 32]> {
+/// Batch proof output in the layout of the Fork1 guest
+pub(crate) fn create_batch_proof_output(
+    initial_state_root: [u8; 32],
+    final_state_root: [u8; 32],
+    last_l2_height: u64,
+) -> BatchProofCircuitOutputV1<MockDaSpec, [u8; 32]> {
+    BatchProofCircuitOutputV1 {
         initial_state_root,
         final_state_root,
         prev_soft_confirmation_hash: [3; 32],
@@ -25,9 +35,11 @@ pub(crate) fn create_mock_blob(
         sequencer_da_public_key: [9; 32].to_vec(),
         last_l2_height,
         preproven_commitments: vec![],
-    };
+    }
+}
 
-    let bp_serialized = borsh::to_vec(&bp).expect("should serialize");
+pub(crate) fn create_mock_blob_with_output(bp_serialized: Vec<u8>, is_valid: bool) -> MockBlob {
+    let batch_proof_method_id = MockCodeCommitment([2u8; 32]);
 
     let serialized_journal = match is_valid {
         true => borsh::to_vec(&MockJournal::Verifiable(bp_serialized.clone())).unwrap(),
@@ -43,7 +55,7 @@ pub(crate) fn create_mock_blob(
     let mock_serialized = mock_proof.encode_to_vec();
 
     let da_data = DaDataLightClient::Complete(mock_serialized);
-    let da_data_ser = borsh::to_vec(&da_data).expect("should serialize");
+    let da_data_ser = da_data.encode_versioned(SpecId::Fork1);
 
     let mut blob = MockBlob::new(da_data_ser, MockAddress::new([9u8; 32]), [0u8; 32]);
     blob.full_data();
@@ -17,12 +17,19 @@ pub(crate) fn recursive_match_state_roots(
     }
 }
 
+/// Maximum number of unchained batch proofs carried over to the next light client proof.
+/// Bounds the size of the circuit output if the missing batch proofs never arrive.
+pub(crate) const MAX_UNCHAINED_BATCH_PROOFS: usize = 256;
+
+/// Collects the batch proofs that could not be chained to the last state root, ordered by
+/// their last l2 height. Only the [`MAX_UNCHAINED_BATCH_PROOFS`] closest to the last l2 height
+/// are kept, the rest are dropped.
 pub(crate) fn collect_unchained_outputs(
     initial_to_final: &std::collections::BTreeMap<[u8; 32], ([u8; 32], u64)>,
     // This should not get anything less than the last l2 height
     state_root_l2_height: u64,
 ) -> Vec<BatchProofInfo> {
-    initial_to_final
+    let mut unchained_outputs: Vec<_> = initial_to_final
         .iter()
         .filter(|&(_, &(_, last_l2_height))| last_l2_height > state_root_l2_height)
         .map(
@@ -32,7 +39,11 @@ pub(crate) fn collect_unchained_outputs(
                 last_l2_height,
             },
         )
-        .collect()
+        .collect();
+    // Stable sort keeps the state root order between proofs of the same height
+    unchained_outputs.sort_by_key(|info| info.last_l2_height);
+    unchained_outputs.truncate(MAX_UNCHAINED_BATCH_PROOFS);
+    unchained_outputs
 }
 
 #[cfg(test)]
@@ -296,4 +307,29 @@ mod tests {
         assert_eq!(elem.0, [9u8; 32]);
         // Now the last state root is 9 and last l2 height is 9
     }
+
+    #[test]
+    fn test_collect_unchained_outputs_is_bounded() {
+        let mut initial_to_final = std::collections::BTreeMap::<[u8; 32], ([u8; 32], u64)>::new();
+
+        // Insert in reverse so that the state root order differs from the l2 height order
+        let count = MAX_UNCHAINED_BATCH_PROOFS as u64 + 10;
+        for i in 0..count {
+            let mut initial_state_root = [0u8; 32];
+            initial_state_root[..8].copy_from_slice(&(count - i).to_be_bytes());
+            initial_to_final.insert(initial_state_root, ([1u8; 32], 10 + i));
+        }
+
+        // The stale one at height 10 is left out
+        let res = collect_unchained_outputs(&initial_to_final, 10);
+        assert_eq!(res.len(), MAX_UNCHAINED_BATCH_PROOFS);
+        assert_eq!(res[0].last_l2_height, 11);
+        assert_eq!(
+            res.last().unwrap().last_l2_height,
+            10 + MAX_UNCHAINED_BATCH_PROOFS as u64
+        );
+        assert!(res
+            .windows(2)
+            .all(|w| w[0].last_l2_height < w[1].last_l2_height));
+    }
 }
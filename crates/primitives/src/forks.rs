@@ -1,3 +1,4 @@
+use std::fmt;
 use std::sync::OnceLock;
 
 use sov_rollup_interface::fork::{fork_pos_from_block_number, verify_forks, Fork};
@@ -8,13 +9,11 @@ static FORKS: OnceLock<&'static [Fork]> = OnceLock::new();
 
 /// Set forks globally based on the network. Must be called once at the start of the application.
 pub fn use_network_forks(network: Network) {
-    let forks: &[Fork] = match network {
-        Network::Mainnet => &MAINNET_FORKS,
-        Network::Testnet => &TESTNET_FORKS,
-        Network::Devnet => &DEVNET_FORKS,
-        Network::Nightly => &NIGHTLY_FORKS,
-    };
+    use_forks(network_forks(network));
+}
 
+/// Set the given fork schedule globally. Must be called once at the start of the application.
+pub fn use_forks(forks: &'static [Fork]) {
     #[cfg(not(feature = "testing"))]
     FORKS.set(forks).expect("Forks must be set exactly once");
 
@@ -22,6 +21,72 @@ pub fn use_network_forks(network: Network) {
     let _ = FORKS.set(forks);
 }
 
+/// Compiled fork schedule of the network.
+pub fn network_forks(network: Network) -> &'static [Fork] {
+    match network {
+        Network::Mainnet => &MAINNET_FORKS,
+        Network::Testnet => &TESTNET_FORKS,
+        Network::Devnet => &DEVNET_FORKS,
+        Network::Nightly => &NIGHTLY_FORKS,
+    }
+}
+
+/// Reasons a fork schedule override is rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForksOverrideError {
+    /// Only Devnet and Nightly fork schedules can be overridden
+    NotAllowed(Network),
+    /// The schedule must start at height 0 with consecutive spec ids and strictly increasing heights
+    InvalidSchedule,
+}
+
+impl fmt::Display for ForksOverrideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForksOverrideError::NotAllowed(network) => {
+                write!(f, "Forks cannot be overridden on {}", network)
+            }
+            ForksOverrideError::InvalidSchedule => write!(
+                f,
+                "Overridden forks must start at height 0 and activate in order at strictly increasing heights"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ForksOverrideError {}
+
+/// Returns the fork schedule of the network with the activation heights of `overrides` applied.
+/// Forks missing from the compiled schedule are added, so upcoming forks can be tested.
+pub fn network_forks_with_override(
+    network: Network,
+    overrides: &[Fork],
+) -> Result<&'static [Fork], ForksOverrideError> {
+    let compiled = network_forks(network);
+    if overrides.is_empty() {
+        return Ok(compiled);
+    }
+    if matches!(network, Network::Mainnet | Network::Testnet) {
+        return Err(ForksOverrideError::NotAllowed(network));
+    }
+
+    let mut forks = compiled.to_vec();
+    for fork in overrides {
+        match forks.iter_mut().find(|f| f.spec_id == fork.spec_id) {
+            Some(existing) => existing.activation_height = fork.activation_height,
+            None => forks.push(*fork),
+        }
+    }
+    forks.sort_by_key(|fork| fork.spec_id as u8);
+
+    if !verify_forks(&forks) {
+        return Err(ForksOverrideError::InvalidSchedule);
+    }
+
+    // Lives until the end of the application, like the compiled schedules
+    Ok(forks.leak())
+}
+
 /// Get forks. Forks need to be set before calling this method if not in testing environment.
 /// In testing environment default forks are used.
 pub fn get_forks() -> &'static [Fork] {
@@ -2,6 +2,7 @@ pub mod basefee;
 pub mod compression;
 mod constants;
 pub mod forks;
+pub mod soft_confirmation;
 pub mod types;
 
 pub use constants::*;
@@ -0,0 +1,51 @@
+//! Hashing of soft confirmations, as signed by the sequencer and committed to in sequencer commitments.
+use borsh::BorshSerialize;
+use sov_rollup_interface::digest::consts::U32;
+use sov_rollup_interface::digest::Digest;
+use sov_rollup_interface::soft_confirmation::{
+    SignedSoftConfirmation, UnsignedSoftConfirmation, UnsignedSoftConfirmationV1,
+};
+use sov_rollup_interface::spec::SpecId;
+
+/// Computes the hash of a soft confirmation from its contents, with the scheme of `spec`.
+/// Uses Sha256, the hasher of the rollup.
+///
+/// The hash covers the raw blobs of the transactions, the parsed transactions are not used.
+/// The claimed hash and the signature of the soft confirmation are not checked.
+pub fn compute_soft_confirmation_hash<Tx: Clone + BorshSerialize>(
+    spec: SpecId,
+    soft_confirmation: &SignedSoftConfirmation<Tx>,
+) -> [u8; 32] {
+    compute_soft_confirmation_hash_with::<sha2::Sha256, Tx>(spec, soft_confirmation)
+}
+
+/// Computes the hash of a soft confirmation from its contents, with the scheme of `spec`
+/// and the hasher `D`.
+pub fn compute_soft_confirmation_hash_with<D, Tx>(
+    spec: SpecId,
+    soft_confirmation: &SignedSoftConfirmation<Tx>,
+) -> [u8; 32]
+where
+    D: Digest<OutputSize = U32>,
+    Tx: Clone + BorshSerialize,
+{
+    let unsigned = UnsignedSoftConfirmation::new(
+        soft_confirmation.l2_height(),
+        soft_confirmation.da_slot_height(),
+        soft_confirmation.da_slot_hash(),
+        soft_confirmation.da_slot_txs_commitment(),
+        soft_confirmation.blobs(),
+        soft_confirmation.txs(),
+        soft_confirmation.deposit_data().to_vec(),
+        soft_confirmation.l1_fee_rate(),
+        soft_confirmation.timestamp(),
+    );
+
+    if spec >= SpecId::Fork1 {
+        unsigned.compute_digest::<D>().into()
+    } else {
+        UnsignedSoftConfirmationV1::from(unsigned)
+            .hash::<D>()
+            .into()
+    }
+}
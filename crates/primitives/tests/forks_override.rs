@@ -0,0 +1,78 @@
+use citrea_primitives::forks::{
+    fork_from_block_number, get_forks, network_forks, network_forks_with_override, use_forks,
+    ForksOverrideError,
+};
+use sov_rollup_interface::fork::Fork;
+use sov_rollup_interface::spec::SpecId;
+use sov_rollup_interface::Network;
+
+#[test]
+fn test_override_activates_fork2_at_low_height() {
+    let forks =
+        network_forks_with_override(Network::Nightly, &[Fork::new(SpecId::Fork2, 5)]).unwrap();
+    assert_eq!(
+        forks,
+        &[Fork::new(SpecId::Fork1, 0), Fork::new(SpecId::Fork2, 5)]
+    );
+
+    // Forks are global, so this is the only test in this binary setting them
+    use_forks(forks);
+    assert_eq!(get_forks(), forks);
+    assert_eq!(fork_from_block_number(4).spec_id, SpecId::Fork1);
+    assert_eq!(fork_from_block_number(5).spec_id, SpecId::Fork2);
+    assert_eq!(fork_from_block_number(1_000).spec_id, SpecId::Fork2);
+}
+
+#[test]
+fn test_override_updates_compiled_activation_height() {
+    let forks =
+        network_forks_with_override(Network::Devnet, &[Fork::new(SpecId::Fork1, 100)]).unwrap();
+    assert_eq!(
+        forks,
+        &[Fork::new(SpecId::Genesis, 0), Fork::new(SpecId::Fork1, 100)]
+    );
+}
+
+#[test]
+fn test_empty_override_keeps_compiled_schedule() {
+    for network in [
+        Network::Mainnet,
+        Network::Testnet,
+        Network::Devnet,
+        Network::Nightly,
+    ] {
+        assert_eq!(
+            network_forks_with_override(network, &[]).unwrap(),
+            network_forks(network)
+        );
+    }
+}
+
+#[test]
+fn test_override_rejected_on_public_networks() {
+    for network in [Network::Mainnet, Network::Testnet] {
+        assert_eq!(
+            network_forks_with_override(network, &[Fork::new(SpecId::Fork2, 5)]),
+            Err(ForksOverrideError::NotAllowed(network))
+        );
+    }
+}
+
+#[test]
+fn test_invalid_override_rejected() {
+    // Fork2 activating before Fork1
+    assert_eq!(
+        network_forks_with_override(Network::Devnet, &[Fork::new(SpecId::Fork2, 5)]),
+        Err(ForksOverrideError::InvalidSchedule)
+    );
+    // Skipping Fork2
+    assert_eq!(
+        network_forks_with_override(Network::Nightly, &[Fork::new(SpecId::Fork3, 5)]),
+        Err(ForksOverrideError::InvalidSchedule)
+    );
+    // Genesis moved away from height 0
+    assert_eq!(
+        network_forks_with_override(Network::Devnet, &[Fork::new(SpecId::Genesis, 1)]),
+        Err(ForksOverrideError::InvalidSchedule)
+    );
+}
@@ -1,13 +1,15 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use futures::future;
 use rand::Rng;
-use sov_db::ledger_db::LedgerDB;
+use sov_db::ledger_db::{LedgerDB, ProvingServiceLedgerOps};
 use sov_rollup_interface::da::DaData;
 use sov_rollup_interface::services::da::DaService;
+use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::zk::{Proof, ZkvmHost};
-use sov_stf_runner::ProverService;
+use sov_stf_runner::{ProverService, ProverServiceStatus};
 use tokio::sync::{oneshot, Mutex};
 use tracing::{info, warn};
 
@@ -29,9 +31,12 @@ where
 
     da_service: Arc<Da>,
     vm: Vm,
-    _ledger_db: LedgerDB,
+    ledger_db: LedgerDB,
 
     proof_queue: Arc<Mutex<Vec<ProofData>>>,
+    // Tracked separately since `proof_queue` is locked for the whole proving round
+    queued_proofs: AtomicUsize,
+    ongoing_proofs: Arc<AtomicUsize>,
 }
 
 impl<Da, Vm> ParallelProverService<Da, Vm>
@@ -45,7 +50,7 @@ where
         vm: Vm,
         proof_mode: ProofGenMode,
         thread_pool_size: usize,
-        _ledger_db: LedgerDB,
+        ledger_db: LedgerDB,
     ) -> anyhow::Result<Self> {
         assert!(
             thread_pool_size > 0,
@@ -83,8 +88,10 @@ where
             proof_mode,
             da_service,
             vm,
-            _ledger_db,
+            ledger_db,
             proof_queue: Arc::new(Mutex::new(vec![])),
+            queued_proofs: AtomicUsize::new(0),
+            ongoing_proofs: Arc::new(AtomicUsize::new(0)),
         })
     }
 
@@ -94,14 +101,14 @@ where
         da_service: Arc<Da>,
         vm: Vm,
         proof_mode: ProofGenMode,
-        _ledger_db: LedgerDB,
+        ledger_db: LedgerDB,
     ) -> anyhow::Result<Self> {
         let thread_pool_size = std::env::var("PARALLEL_PROOF_LIMIT")
             .expect("PARALLEL_PROOF_LIMIT must be set")
             .parse::<usize>()
             .expect("PARALLEL_PROOF_LIMIT must be valid unsigned number");
 
-        Self::new(da_service, vm, proof_mode, thread_pool_size, _ledger_db)
+        Self::new(da_service, vm, proof_mode, thread_pool_size, ledger_db)
     }
 
     async fn prove_all(&self, elf: Vec<u8>, proof_queue: Vec<ProofData>) -> Vec<Proof> {
@@ -128,9 +135,13 @@ where
             }
 
             info!("Starting proving task {}", idx);
+            self.queued_proofs.fetch_sub(1, Ordering::Relaxed);
+            self.ongoing_proofs.fetch_add(1, Ordering::Relaxed);
+            let ongoing_proofs_counter = self.ongoing_proofs.clone();
             let proof_fut = self.prove_one(elf.clone(), proof_data);
             ongoing_proofs.push(Box::pin(async move {
                 let proof = proof_fut.await;
+                ongoing_proofs_counter.fetch_sub(1, Ordering::Relaxed);
 
                 info!("Finished proving task {}", idx);
 
@@ -165,10 +176,14 @@ where
         rx.await.expect("Should not have channel errors")
     }
 
-    async fn submit_proof(&self, proof: Proof) -> anyhow::Result<<Da as DaService>::TransactionId> {
+    async fn submit_proof(
+        &self,
+        proof: Proof,
+        spec_id: SpecId,
+    ) -> anyhow::Result<<Da as DaService>::TransactionId> {
         let da_data = DaData::ZKProof(proof);
         self.da_service
-            .send_transaction(da_data)
+            .send_transaction(da_data, spec_id)
             .await
             .map_err(|e| anyhow::anyhow!(e))
     }
@@ -185,12 +200,15 @@ where
     async fn add_proof_data(&self, proof_data: ProofData) {
         let mut proof_queue = self.proof_queue.lock().await;
         proof_queue.push(proof_data);
+        self.queued_proofs.fetch_add(1, Ordering::Relaxed);
     }
 
     async fn prove(&self, elf: Vec<u8>) -> anyhow::Result<Vec<Proof>> {
         let mut proof_queue = self.proof_queue.lock().await;
         if let ProofGenMode::Skip = self.proof_mode {
             tracing::debug!("Skipped proving {} proofs", proof_queue.len());
+            self.queued_proofs
+                .fetch_sub(proof_queue.len(), Ordering::Relaxed);
             proof_queue.clear();
             return Ok(vec![]);
         }
@@ -210,22 +228,28 @@ where
     async fn submit_proofs(
         &self,
         proofs: Vec<Proof>,
+        spec_id: SpecId,
     ) -> anyhow::Result<Vec<(<Da as DaService>::TransactionId, Proof)>> {
         let mut tx_and_proof = Vec::with_capacity(proofs.len());
         for proof in proofs {
-            let tx_id = self.submit_proof(proof.clone()).await?;
+            let tx_id = self.submit_proof(proof.clone(), spec_id).await?;
             tx_and_proof.push((tx_id, proof));
         }
         Ok(tx_and_proof)
     }
 
-    async fn recover_and_submit_proving_sessions(
-        &self,
-    ) -> anyhow::Result<Vec<(<Da as DaService>::TransactionId, Proof)>> {
+    async fn recover_proving_sessions(&self) -> anyhow::Result<Vec<Proof>> {
         let vm = self.vm.clone();
-        let proofs = vm.recover_proving_sessions()?;
+        vm.recover_proving_sessions()
+    }
 
-        self.submit_proofs(proofs).await
+    fn status(&self) -> anyhow::Result<ProverServiceStatus> {
+        Ok(ProverServiceStatus {
+            queued_proofs: self.queued_proofs.load(Ordering::Relaxed),
+            ongoing_proofs: self.ongoing_proofs.load(Ordering::Relaxed),
+            parallel_proof_limit: self.thread_pool.current_num_threads(),
+            pending_sessions: self.ledger_db.get_pending_proving_sessions()?,
+        })
     }
 }
 
@@ -1,36 +1,52 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
 use criteria::DistanceCriteria;
 use futures::future;
 use serde::{Deserialize, Serialize};
-use sov_db::ledger_db::SharedLedgerOps;
+use sov_db::ledger_db::NodeLedgerOps;
 use tokio::select;
 use tokio::sync::broadcast;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
 use crate::criteria::Criteria;
-use crate::pruners::{prune_evm, prune_ledger};
+use crate::pruners::{prune_evm, prune_l1_ledger, prune_ledger};
 
 mod criteria;
 mod pruners;
 #[cfg(test)]
 mod tests;
 
+pub use pruners::evm_pruning_callback;
+
+/// Prunes the EVM accessory state of the given L2 blocks.
+/// Registered by the rollup blueprint, which owns the storage the EVM state lives in.
+pub type EvmPruningCallback = Arc<dyn Fn(RangeInclusive<u64>) -> anyhow::Result<()> + Send + Sync>;
+
 /// A configuration type to define the behaviour of the pruner.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct PruningConfig {
     /// Defines the number of blocks from the tip of the chain to remove.
     pub distance: u64,
+    /// Defines the number of L1 blocks below the last scanned one whose ledger data is kept.
+    /// L1 ledger data is never pruned if not set.
+    #[serde(default)]
+    pub l1_distance: Option<u64>,
 }
 
 impl Default for PruningConfig {
     fn default() -> Self {
-        Self { distance: 256 }
+        Self {
+            distance: 256,
+            l1_distance: None,
+        }
     }
 }
 
 pub struct Pruner<DB>
 where
-    DB: SharedLedgerOps,
+    DB: NodeLedgerOps,
 {
     /// The last block number which was pruned.
     last_pruned_block: u64,
@@ -40,17 +56,22 @@ where
     ledger_db: DB,
     /// Criteria to decide pruning
     criteria: Box<dyn Criteria + Send + Sync>,
+    /// Access to EVM accessory state, if registered.
+    evm_pruning_callback: Option<EvmPruningCallback>,
+    /// Number of L1 blocks whose ledger data is kept, L1 ledger data is not pruned if not set.
+    l1_distance: Option<u64>,
 }
 
 impl<DB> Pruner<DB>
 where
-    DB: SharedLedgerOps + Send + Sync + Clone + 'static,
+    DB: NodeLedgerOps + Send + Sync + Clone + 'static,
 {
     pub fn new(
         config: PruningConfig,
         last_pruned_block: u64,
         l2_receiver: broadcast::Receiver<u64>,
         ledger_db: DB,
+        evm_pruning_callback: Option<EvmPruningCallback>,
     ) -> Self {
         // distance is the only criteria implemented at the moment.
         let criteria = Box::new(DistanceCriteria {
@@ -61,6 +82,8 @@ where
             l2_receiver,
             ledger_db,
             criteria,
+            evm_pruning_callback,
+            l1_distance: config.l1_distance,
         }
     }
 
@@ -70,9 +93,22 @@ where
         let ledger_db = self.ledger_db.clone();
         let ledger_pruning_handle =
             tokio::task::spawn_blocking(move || prune_ledger(ledger_db, up_to_block));
-        let evm_pruning_handle = tokio::task::spawn_blocking(move || prune_evm(up_to_block));
+        // The genesis block is always kept
+        let evm_pruning_callback = self.evm_pruning_callback.clone();
+        let blocks = (self.last_pruned_block + 1)..=up_to_block;
+        let evm_pruning_handle =
+            tokio::task::spawn_blocking(move || prune_evm(evm_pruning_callback, blocks));
+
+        let mut handles = vec![ledger_pruning_handle, evm_pruning_handle];
+        // Commitments of L1 slots are kept as long as they cover unpruned L2 blocks
+        if let Some(l1_distance) = self.l1_distance {
+            let ledger_db = self.ledger_db.clone();
+            handles.push(tokio::task::spawn_blocking(move || {
+                prune_l1_ledger(ledger_db, l1_distance, up_to_block)
+            }));
+        }
 
-        future::join_all([ledger_pruning_handle, evm_pruning_handle]).await;
+        future::join_all(handles).await;
     }
 
     pub async fn run(mut self, cancellation_token: CancellationToken) {
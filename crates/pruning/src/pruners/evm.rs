@@ -1,10 +1,46 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
 use citrea_evm::Evm;
 use sov_modules_api::default_context::DefaultContext;
-use tracing::debug;
+use sov_modules_api::WorkingSet;
+use sov_prover_storage_manager::{AccessoryStatePruner, ProverStorage, SnapshotManager};
+use tracing::{debug, error};
+
+use crate::EvmPruningCallback;
+
+/// Creates the callback which prunes the EVM accessory state on top of the given finalized storage
+pub fn evm_pruning_callback(
+    storage: ProverStorage<SnapshotManager>,
+    accessory_state_pruner: AccessoryStatePruner,
+) -> EvmPruningCallback {
+    Arc::new(move |blocks| {
+        let evm = Evm::<DefaultContext>::default();
+        let mut working_set = WorkingSet::new(storage.clone());
+        evm.prune_accessory_state(blocks, &mut working_set);
+
+        // Deleting through the working set would only add tombstones on top of the previous
+        // versions, so the deleted keys are removed from the database with all of their versions
+        let accessory_writes = working_set.checkpoint().freeze_non_provable();
+        accessory_state_pruner.delete_keys(
+            accessory_writes
+                .ordered_writes
+                .into_iter()
+                .map(|(key, _)| key.key.to_vec()),
+        )
+    })
+}
 
 /// Prune evm
-pub(crate) fn prune_evm(up_to_block: u64) {
-    debug!("Pruning EVM, up to L2 block {}", up_to_block);
-    let _evm = Evm::<DefaultContext>::default();
-    // unimplemented!()
+pub(crate) fn prune_evm(
+    evm_pruning_callback: Option<EvmPruningCallback>,
+    blocks: RangeInclusive<u64>,
+) {
+    debug!("Pruning EVM, L2 blocks {:?}", blocks);
+    let Some(evm_pruning_callback) = evm_pruning_callback else {
+        return;
+    };
+    if let Err(e) = evm_pruning_callback(blocks) {
+        error!("Failed to prune EVM accessory state: {:?}", e);
+    }
 }
@@ -1,8 +1,56 @@
-use sov_db::ledger_db::SharedLedgerOps;
-use tracing::debug;
+use sov_db::ledger_db::{NodeLedgerOps, SharedLedgerOps};
+use tracing::{debug, error};
 
 /// Prune ledger
 pub(crate) fn prune_ledger<DB: SharedLedgerOps>(_ledger_db: DB, up_to_block: u64) {
     debug!("Pruning Ledger, up to L2 block {}", up_to_block);
     // unimplemented!()
 }
+
+/// Prune the ledger data of L1 slots more than `l1_distance` blocks below the last scanned one.
+///
+/// The slot of the most recent verified proofs is kept, it is served by `get_last_verified_batch_proof`,
+/// and so are the slots with commitments covering L2 blocks above `up_to_block`.
+pub(crate) fn prune_l1_ledger<DB: NodeLedgerOps>(
+    ledger_db: DB,
+    l1_distance: u64,
+    up_to_block: u64,
+) {
+    if let Err(e) = try_prune_l1_ledger(&ledger_db, l1_distance, up_to_block) {
+        error!("Failed to prune L1 ledger data: {:?}", e);
+    }
+}
+
+fn try_prune_l1_ledger<DB: NodeLedgerOps>(
+    ledger_db: &DB,
+    l1_distance: u64,
+    up_to_block: u64,
+) -> anyhow::Result<()> {
+    let Some(last_scanned_l1_height) = ledger_db.get_last_scanned_l1_height()? else {
+        return Ok(());
+    };
+    let Some(up_to_l1_height) = last_scanned_l1_height.0.checked_sub(l1_distance) else {
+        return Ok(());
+    };
+    debug!("Pruning L1 ledger data, below L1 block {}", up_to_l1_height);
+
+    let last_verified_proof_l1_height = ledger_db.get_last_verified_proof_l1_height()?;
+    for l1_height in ledger_db.get_da_slot_heights_before(up_to_l1_height)? {
+        if Some(l1_height) == last_verified_proof_l1_height {
+            continue;
+        }
+        let covers_unpruned_blocks = ledger_db
+            .get_commitments_on_da_slot(l1_height)?
+            .is_some_and(|commitments| {
+                commitments
+                    .iter()
+                    .any(|commitment| commitment.l2_end_block_number > up_to_block)
+            });
+        if covers_unpruned_blocks {
+            continue;
+        }
+
+        ledger_db.prune_da_slot_data(l1_height)?;
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 mod evm;
 mod ledger;
 
-pub(crate) use evm::*;
+pub use evm::evm_pruning_callback;
+pub(crate) use evm::prune_evm;
 pub(crate) use ledger::*;
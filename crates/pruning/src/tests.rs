@@ -1,12 +1,17 @@
 use std::thread::sleep;
 use std::time::Duration;
 
-use sov_db::ledger_db::LedgerDB;
+use sov_db::ledger_db::{LedgerDB, NodeLedgerOps, SharedLedgerOps};
 use sov_db::rocks_db_config::RocksdbConfig;
+use sov_db::schema::types::{SlotNumber, SoftConfirmationNumber, StoredBatchProofOutput};
+use sov_rollup_interface::da::SequencerCommitment;
+use sov_rollup_interface::rpc::LedgerRpcProvider;
+use sov_rollup_interface::zk::BatchProofOutputVersion;
 use tokio::sync::broadcast;
 use tokio_util::sync::CancellationToken;
 
 use crate::criteria::{Criteria, DistanceCriteria};
+use crate::pruners::prune_l1_ledger;
 use crate::{Pruner, PruningConfig};
 
 #[tokio::test(flavor = "multi_thread")]
@@ -16,7 +21,16 @@ async fn test_pruner_simple_run() {
     let cancellation_token = CancellationToken::new();
 
     let ledger_db = LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
-    let pruner = Pruner::new(PruningConfig { distance: 5 }, 0, receiver, ledger_db);
+    let pruner = Pruner::new(
+        PruningConfig {
+            distance: 5,
+            l1_distance: Some(5),
+        },
+        0,
+        receiver,
+        ledger_db,
+        None,
+    );
 
     tokio::spawn(pruner.run(cancellation_token.clone()));
 
@@ -42,3 +56,156 @@ pub fn test_should_prune() {
     assert_eq!(criteria.should_prune(1000, 3000), None);
     assert_eq!(criteria.should_prune(1000, 3001), Some(2000));
 }
+
+fn put_verified_proof(ledger_db: &LedgerDB, l1_height: u64) {
+    let proof_output = StoredBatchProofOutput {
+        output_version: BatchProofOutputVersion::V1,
+        initial_state_root: vec![0; 32],
+        final_state_root: vec![1; 32],
+        prev_soft_confirmation_hash: [0; 32],
+        final_soft_confirmation_hash: [1; 32],
+        state_diff: Default::default(),
+        da_slot_hash: [l1_height as u8; 32],
+        sequencer_commitments_range: (0, 0),
+        sequencer_public_key: vec![],
+        sequencer_da_public_key: vec![],
+        preproven_commitments: vec![],
+        last_l2_height: 5,
+        first_l2_height: 0,
+        verified_method_id: None,
+    };
+    ledger_db
+        .update_verified_proof_data(l1_height, vec![1, 2, 3], proof_output)
+        .unwrap();
+}
+
+/// Slots 1-12 with their hashes and L2 ranges, 20 scanned.
+/// Slot 3 has a commitment of L2 blocks 1-5 and slot 8 one of L2 blocks 6-9.
+/// Slots 4 and 5 have verified proofs.
+fn setup_l1_ledger(ledger_db: &LedgerDB) {
+    for l1_height in 1..=12 {
+        let hash = [l1_height as u8; 32];
+        ledger_db.set_l1_height_of_l1_hash(hash, l1_height).unwrap();
+        ledger_db.set_l1_hash_of_l1_height(l1_height, hash).unwrap();
+        ledger_db
+            .extend_l2_range_of_l1_slot(SlotNumber(l1_height), SoftConfirmationNumber(l1_height))
+            .unwrap();
+    }
+    for (l1_height, (l2_start, l2_end)) in [(3, (1, 5)), (8, (6, 9))] {
+        ledger_db
+            .update_commitments_on_da_slot(
+                l1_height,
+                SequencerCommitment {
+                    merkle_root: [l1_height as u8; 32],
+                    l2_start_block_number: l2_start,
+                    l2_end_block_number: l2_end,
+                },
+            )
+            .unwrap();
+    }
+    put_verified_proof(ledger_db, 4);
+    put_verified_proof(ledger_db, 5);
+    ledger_db
+        .set_last_scanned_l1_height(SlotNumber(20))
+        .unwrap();
+}
+
+#[test]
+fn test_prune_l1_ledger_retention() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
+    setup_l1_ledger(&ledger_db);
+
+    // Slots below 20 - 10 are pruned, L2 blocks up to 6 are pruned
+    prune_l1_ledger(ledger_db.clone(), 10, 6);
+
+    // 5 has the last verified proofs, 8 a commitment of unpruned L2 blocks
+    // and 10 is the first slot within the distance
+    assert_eq!(
+        ledger_db.get_da_slot_heights_before(13).unwrap(),
+        vec![5, 8, 10, 11, 12]
+    );
+
+    // The boundary slot is pruned with everything stored for it
+    assert_eq!(ledger_db.get_slot_number_by_hash([9; 32]).unwrap(), None);
+    assert_eq!(ledger_db.get_l1_hash_of_l1_height(9).unwrap(), None);
+    assert!(ledger_db
+        .get_soft_confirmations_by_l1_height(9, false)
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        ledger_db.get_slot_number_by_hash([10; 32]).unwrap(),
+        Some(10)
+    );
+    assert_eq!(
+        ledger_db.get_l2_range_by_l1_height(SlotNumber(10)).unwrap(),
+        Some((SoftConfirmationNumber(10), SoftConfirmationNumber(10)))
+    );
+
+    assert!(ledger_db
+        .get_sequencer_commitments_on_slot_by_number(3)
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        ledger_db
+            .get_sequencer_commitments_on_slot_by_number(8)
+            .unwrap()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(ledger_db.get_slot_number_by_hash([8; 32]).unwrap(), Some(8));
+
+    assert!(ledger_db
+        .get_verified_proof_data_by_l1_height(4)
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        ledger_db
+            .get_last_verified_batch_proof()
+            .unwrap()
+            .unwrap()
+            .height,
+        5
+    );
+
+    // Once a newer proof is verified and the covered L2 blocks are pruned, the kept slots go too
+    put_verified_proof(&ledger_db, 11);
+    prune_l1_ledger(ledger_db.clone(), 10, 9);
+    assert_eq!(
+        ledger_db.get_da_slot_heights_before(13).unwrap(),
+        vec![10, 11, 12]
+    );
+    assert_eq!(
+        ledger_db
+            .get_last_verified_batch_proof()
+            .unwrap()
+            .unwrap()
+            .height,
+        11
+    );
+}
+
+#[test]
+fn test_prune_l1_ledger_within_distance() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
+
+    // Nothing scanned yet
+    prune_l1_ledger(ledger_db.clone(), 10, 6);
+
+    setup_l1_ledger(&ledger_db);
+    let stored = ledger_db.get_da_slot_heights_before(13).unwrap();
+    assert_eq!(stored, (1..=12).collect::<Vec<_>>());
+
+    // The last scanned slot is not further than the distance from the first one
+    prune_l1_ledger(ledger_db.clone(), 20, 12);
+    assert_eq!(ledger_db.get_da_slot_heights_before(13).unwrap(), stored);
+
+    // Only slot 1 is older than 20 - 18
+    prune_l1_ledger(ledger_db.clone(), 18, 12);
+    assert_eq!(
+        ledger_db.get_da_slot_heights_before(13).unwrap(),
+        stored[1..]
+    );
+}
@@ -1,4 +1,6 @@
 //! This module implements the [`ZkvmHost`] trait for the RISC0 VM.
+use std::io::Cursor;
+use std::sync::Mutex;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use metrics::histogram;
@@ -8,11 +10,9 @@ use risc0_zkvm::{
     Receipt,
 };
 use sov_db::ledger_db::LedgerDB;
-use sov_rollup_interface::zk::{Proof, Zkvm, ZkvmHost};
+use sov_rollup_interface::zk::{Proof, Zkvm, ZkvmGuest, ZkvmHost};
 use tracing::{debug, info};
 
-use crate::guest::Risc0Guest;
-
 type StarkSessionId = String;
 type SnarkSessionId = String;
 
@@ -86,7 +86,7 @@ impl Risc0BonsaiHost {
 }
 
 impl ZkvmHost for Risc0BonsaiHost {
-    type Guest = Risc0Guest;
+    type Guest = Risc0SimulatedGuest;
 
     fn add_hint(&mut self, item: Vec<u8>) {
         info!("Added hint to guest with size {}", item.len());
@@ -95,9 +95,10 @@ impl ZkvmHost for Risc0BonsaiHost {
         self.env.extend_from_slice(&item);
     }
 
-    /// Guest simulation (execute mode) is run inside the Risc0 VM locally
+    /// Returns a guest which runs natively, reading the hints added so far.
+    /// Hints are kept, so the guest can be run in the Risc0 VM afterwards.
     fn simulate_with_hints(&mut self) -> Self::Guest {
-        todo!("we don't use it yet")
+        Risc0SimulatedGuest::new(self.env.clone())
     }
 
     fn add_assumption(&mut self, receipt_buf: Vec<u8>) {
@@ -138,7 +139,7 @@ impl ZkvmHost for Risc0BonsaiHost {
         let ProveInfo { receipt, stats } =
             prover.prove_with_opts(env, &elf, &ProverOpts::groth16())?;
 
-        histogram!("proving_session_cycle_count").record(stats.total_cycles as f64);
+        histogram!("citrea_proving_session_cycle_count").record(stats.total_cycles as f64);
 
         tracing::info!("Execution Stats: {:?}", stats);
 
@@ -234,3 +235,62 @@ impl Zkvm for Risc0BonsaiHost {
         Ok(T::deserialize(&mut receipt.journal.bytes.as_slice())?)
     }
 }
+
+/// A guest running natively in the host process, on the hints of a [`Risc0BonsaiHost`].
+/// Used to check that the circuit would accept its input without running the Risc0 VM.
+pub struct Risc0SimulatedGuest {
+    input: Mutex<Cursor<Vec<u8>>>,
+    journal: Mutex<Vec<u8>>,
+}
+
+impl Risc0SimulatedGuest {
+    /// Constructs a simulated guest reading the given hints in order.
+    pub fn new(hints: Vec<u8>) -> Self {
+        Self {
+            input: Mutex::new(Cursor::new(hints)),
+            journal: Mutex::new(vec![]),
+        }
+    }
+
+    /// Returns the serialized outputs committed by the guest so far.
+    pub fn journal(&self) -> Vec<u8> {
+        self.journal.lock().unwrap().clone()
+    }
+}
+
+impl ZkvmGuest for Risc0SimulatedGuest {
+    fn read_from_host<T: BorshDeserialize>(&self) -> T {
+        let mut reader = self.input.lock().unwrap();
+        BorshDeserialize::deserialize_reader(&mut *reader)
+            .expect("Failed to deserialize input from host")
+    }
+
+    fn commit<T: BorshSerialize>(&self, item: &T) {
+        let buf = borsh::to_vec(item).expect("Serialization to vec is infallible");
+        self.journal.lock().unwrap().extend_from_slice(&buf);
+    }
+}
+
+impl Zkvm for Risc0SimulatedGuest {
+    type CodeCommitment = Digest;
+
+    type Error = anyhow::Error;
+
+    fn verify(
+        serialized_proof: &[u8],
+        code_commitment: &Self::CodeCommitment,
+    ) -> Result<Vec<u8>, Self::Error> {
+        Risc0BonsaiHost::verify(serialized_proof, code_commitment)
+    }
+
+    fn extract_raw_output(serialized_proof: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        Risc0BonsaiHost::extract_raw_output(serialized_proof)
+    }
+
+    fn verify_and_extract_output<T: BorshDeserialize>(
+        serialized_proof: &[u8],
+        code_commitment: &Self::CodeCommitment,
+    ) -> Result<T, Self::Error> {
+        Risc0BonsaiHost::verify_and_extract_output(serialized_proof, code_commitment)
+    }
+}
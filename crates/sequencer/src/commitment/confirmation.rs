@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use sov_db::ledger_db::SequencerLedgerOps;
+use sov_db::schema::types::L2HeightRange;
+use sov_rollup_interface::services::da::DaService;
+use tracing::{debug, info};
+
+/// Records the L1 heights the submitted commitments are found in.
+///
+/// Scans the finalized L1 blocks for the commitments of the sequencer, as long as some
+/// submitted commitments are not confirmed yet.
+pub(crate) struct CommitmentConfirmationTracker<Da, Db>
+where
+    Da: DaService,
+    Db: SequencerLedgerOps,
+{
+    ledger_db: Db,
+    da_service: Arc<Da>,
+    sequencer_da_pub_key: Vec<u8>,
+    /// Next L1 height to scan, unknown while all commitments are confirmed
+    next_l1_height: Option<u64>,
+    /// L2 range of the newest commitment whose L1 blocks are being scanned
+    newest_tracked: Option<L2HeightRange>,
+}
+
+impl<Da, Db> CommitmentConfirmationTracker<Da, Db>
+where
+    Da: DaService,
+    Db: SequencerLedgerOps,
+{
+    pub fn new(ledger_db: Db, da_service: Arc<Da>, sequencer_da_pub_key: Vec<u8>) -> Self {
+        Self {
+            ledger_db,
+            da_service,
+            sequencer_da_pub_key,
+            next_l1_height: None,
+            newest_tracked: None,
+        }
+    }
+
+    /// Scans the L1 blocks up to `finalized_l1_height` for unconfirmed commitments
+    pub async fn update(&mut self, finalized_l1_height: u64) -> anyhow::Result<()> {
+        let mut unconfirmed = self.ledger_db.get_unconfirmed_commitment_submissions()?;
+        let Some((newest_l2_range, _)) = unconfirmed.last() else {
+            self.next_l1_height = None;
+            self.newest_tracked = None;
+            return Ok(());
+        };
+        let newest_l2_range = *newest_l2_range;
+
+        // A commitment can land before its submission is recorded, so the blocks of newly
+        // recorded commitments are scanned even if they were scanned already
+        let mut next_l1_height = self.next_l1_height.unwrap_or(u64::MAX);
+        for (l2_range, _) in unconfirmed
+            .iter()
+            .filter(|(l2_range, _)| Some(*l2_range) > self.newest_tracked)
+        {
+            // A commitment is submitted after the L1 block its last soft confirmation is built on
+            let da_slot_height = self
+                .ledger_db
+                .get_soft_confirmation_by_number(&l2_range.1)?
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Soft confirmation #{} of a submitted commitment is missing",
+                        l2_range.1 .0
+                    )
+                })?
+                .da_slot_height;
+            next_l1_height = next_l1_height.min(da_slot_height);
+        }
+        self.next_l1_height = Some(next_l1_height);
+        self.newest_tracked = Some(newest_l2_range);
+
+        for l1_height in next_l1_height..=finalized_l1_height {
+            let block = self
+                .da_service
+                .get_block_at(l1_height)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            let commitments = self
+                .da_service
+                .extract_relevant_sequencer_commitments(&block, &self.sequencer_da_pub_key)
+                .unwrap_or_default();
+
+            for commitment in commitments {
+                let Some((l2_range, submission)) = unconfirmed.iter_mut().find(|(l2_range, _)| {
+                    l2_range.0 .0 == commitment.l2_start_block_number
+                        && l2_range.1 .0 == commitment.l2_end_block_number
+                }) else {
+                    debug!(
+                        "Commitment #{}-{} at L1 height {} is not in the submission history",
+                        commitment.l2_start_block_number, commitment.l2_end_block_number, l1_height
+                    );
+                    continue;
+                };
+                if submission.confirmed_in_l1.is_some() {
+                    continue;
+                }
+
+                submission.confirmed_in_l1 = Some(l1_height);
+                self.ledger_db
+                    .put_commitment_submission(l2_range, submission)?;
+                info!(
+                    "Commitment #{}-{} is confirmed at L1 height {}",
+                    l2_range.0 .0, l2_range.1 .0, l1_height
+                );
+            }
+
+            self.next_l1_height = Some(l1_height + 1);
+        }
+
+        Ok(())
+    }
+
+    /// Records the DA transaction a submitted commitment was replaced with,
+    /// e.g. when the DA service rebuilt it with a higher fee
+    pub fn record_replacement(
+        &self,
+        replaced: [u8; 32],
+        replacement: [u8; 32],
+    ) -> anyhow::Result<()> {
+        let unconfirmed = self.ledger_db.get_unconfirmed_commitment_submissions()?;
+        let Some((l2_range, mut submission)) = unconfirmed
+            .into_iter()
+            .find(|(_, submission)| submission.da_tx_ids.last() == Some(&replaced))
+        else {
+            // Not a commitment, or one that is not recorded yet
+            return Ok(());
+        };
+
+        submission.da_tx_ids.push(replacement);
+        self.ledger_db
+            .put_commitment_submission(&l2_range, &submission)?;
+        info!(
+            "Commitment #{}-{} is replaced on DA with tx {}",
+            l2_range.0 .0,
+            l2_range.1 .0,
+            hex::encode(replacement)
+        );
+
+        Ok(())
+    }
+}
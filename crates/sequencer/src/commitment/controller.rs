@@ -1,7 +1,7 @@
 use std::cmp;
 
-use citrea_common::utils::merge_state_diffs;
-use citrea_primitives::compression::compress_blob;
+use citrea_common::utils::{merge_state_diffs, state_diff_size};
+use citrea_common::SequencerKeySchedule;
 use citrea_primitives::MAX_TXBODY_SIZE;
 use sov_db::ledger_db::SequencerLedgerOps;
 use sov_db::schema::types::SoftConfirmationNumber;
@@ -15,6 +15,14 @@ use super::CommitmentInfo;
 // estimation of 33% compression.
 const SAFE_MAX_UNCOMPRESSED_TXBODY_SIZE: usize = MAX_TXBODY_SIZE * 3 / 2;
 
+/// Compressed state diff size above which a commitment is submitted.
+pub(crate) const STATE_DIFF_THRESHOLD: usize = MAX_TXBODY_SIZE;
+
+/// Size of the state diff as it is written to the DA.
+pub(crate) fn compressed_state_diff_size(state_diff: &StateDiff) -> usize {
+    state_diff_size(state_diff).compressed_size as usize
+}
+
 pub struct CommitmentController<Db>
 where
     Db: SequencerLedgerOps,
@@ -22,18 +30,24 @@ where
     ledger_db: Db,
     min_soft_confirmations: u64,
     last_state_diff: StateDiff,
+    sequencer_key_schedule: SequencerKeySchedule,
 }
 
 impl<Db> CommitmentController<Db>
 where
     Db: SequencerLedgerOps,
 {
-    pub fn new(ledger_db: Db, min_soft_confirmations: u64) -> Self {
+    pub fn new(
+        ledger_db: Db,
+        min_soft_confirmations: u64,
+        sequencer_key_schedule: SequencerKeySchedule,
+    ) -> Self {
         let last_state_diff = ledger_db.get_state_diff().unwrap_or_default();
         Self {
             ledger_db,
             min_soft_confirmations,
             last_state_diff,
+            sequencer_key_schedule,
         }
     }
 
@@ -56,6 +70,15 @@ where
             .unwrap_or(SoftConfirmationNumber(0));
         let last_committed_l2_height = cmp::max(last_finalized_l2_height, last_pending_l2_height);
 
+        // Blocks of a commitment are proven with a single sequencer key, so a commitment can not
+        // span a key rotation. The blocks before the rotation are committed on the first block
+        // signed with the new key.
+        if let Some(info) = self.check_key_rotation(last_committed_l2_height, l2_height) {
+            // New state diff is current L2 block's state diff, as it starts the next commitment
+            self.set_state_diff(l2_state_diff)?;
+            return Ok(Some(info));
+        }
+
         // If block state diff is empty, it is certain that state diff threshold won't be exceeded.
         let updated_state_diff = if !l2_state_diff.is_empty() {
             // It is OK to take value of last_state_diff here to avoid cloning the value.
@@ -125,6 +148,31 @@ where
         })
     }
 
+    fn check_key_rotation(
+        &self,
+        last_committed_l2_height: SoftConfirmationNumber,
+        current_l2_height: u64,
+    ) -> Option<CommitmentInfo> {
+        if !self
+            .sequencer_key_schedule
+            .is_rotation_height(current_l2_height)
+        {
+            return None;
+        }
+
+        let l2_start = last_committed_l2_height.0 + 1;
+        // The current block is signed with the new key, so it is left to the next commitment
+        let l2_end = current_l2_height.checked_sub(1)?;
+        if l2_end < l2_start {
+            return None;
+        }
+
+        debug!("Sequencer key rotation at L2 height {current_l2_height}, submitting commitment");
+        Some(CommitmentInfo {
+            l2_height_range: SoftConfirmationNumber(l2_start)..=SoftConfirmationNumber(l2_end),
+        })
+    }
+
     fn check_state_diff_threshold(
         &self,
         last_committed_l2_height: SoftConfirmationNumber,
@@ -135,15 +183,14 @@ where
             return None;
         }
 
-        let uncompressed_state_diff =
-            borsh::to_vec(state_diff).expect("State diff serialization can not fail");
+        let uncompressed_state_diff_size =
+            borsh::object_length(state_diff).expect("State diff serialization can not fail");
         // Early return if uncompressed state diff doesn't exceed limit
-        if uncompressed_state_diff.len() <= SAFE_MAX_UNCOMPRESSED_TXBODY_SIZE {
+        if uncompressed_state_diff_size <= SAFE_MAX_UNCOMPRESSED_TXBODY_SIZE {
             return None;
         }
 
-        let compressed_state_diff = compress_blob(&uncompressed_state_diff);
-        if compressed_state_diff.len() <= MAX_TXBODY_SIZE {
+        if compressed_state_diff_size(state_diff) <= STATE_DIFF_THRESHOLD {
             return None;
         }
 
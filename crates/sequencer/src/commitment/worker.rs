@@ -0,0 +1,353 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use backoff::backoff::Backoff;
+use backoff::ExponentialBackoffBuilder;
+use citrea_common::events;
+use citrea_primitives::forks::fork_from_block_number;
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::MerkleTree;
+use sov_db::ledger_db::SequencerLedgerOps;
+use sov_db::schema::types::{SlotNumber, SoftConfirmationNumber, StoredCommitmentSubmission};
+use sov_rollup_interface::da::{BlockHeaderTrait, DaData, SequencerCommitment};
+use sov_rollup_interface::services::da::{DaService, SenderWithNotifier};
+use tokio::select;
+use tokio::sync::{oneshot, Notify};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, instrument, warn};
+
+use super::CommitmentInfo;
+use crate::metrics::SEQUENCER_METRICS;
+
+/// Bounds of the delay before retrying a failed commitment submission
+const MIN_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Submits the pending commitments to DA, one at a time and in order of their L2 ranges.
+///
+/// A pending commitment is only cleared once DA accepted it. Failed submissions are retried
+/// until they succeed, and pending commitments left over from a previous run are picked up
+/// on startup.
+pub(crate) struct CommitmentWorker<Da, Db>
+where
+    Da: DaService,
+    Db: SequencerLedgerOps,
+{
+    ledger_db: Db,
+    da_service: Arc<Da>,
+    sequencer_da_pub_key: Vec<u8>,
+    notify: Arc<Notify>,
+}
+
+impl<Da, Db> CommitmentWorker<Da, Db>
+where
+    Da: DaService,
+    Db: SequencerLedgerOps + Clone + Send + Sync + 'static,
+{
+    pub fn new(
+        ledger_db: Db,
+        da_service: Arc<Da>,
+        sequencer_da_pub_key: Vec<u8>,
+        notify: Arc<Notify>,
+    ) -> Self {
+        Self {
+            ledger_db,
+            da_service,
+            sequencer_da_pub_key,
+            notify,
+        }
+    }
+
+    pub async fn run(self, cancellation_token: CancellationToken) {
+        // A commitment interrupted by the shutdown stays pending, and is reconciled on restart
+        select! {
+            biased;
+            _ = cancellation_token.cancelled() => {},
+            _ = self.submit_pending_commitments() => {},
+        }
+    }
+
+    async fn submit_pending_commitments(&self) {
+        let mut retry_backoff = ExponentialBackoffBuilder::new()
+            .with_initial_interval(MIN_RETRY_INTERVAL)
+            .with_max_interval(MAX_RETRY_INTERVAL)
+            .with_max_elapsed_time(None)
+            .build();
+        // Commitments left pending by the previous run may have been sent already
+        let mut needs_reconciliation = true;
+
+        loop {
+            if needs_reconciliation {
+                match self.drop_landed_commitments().await {
+                    Ok(()) => needs_reconciliation = false,
+                    Err(e) => {
+                        error!("Could not reconcile pending commitments with DA: {:?}", e);
+                        tokio::time::sleep(
+                            retry_backoff.next_backoff().unwrap_or(MAX_RETRY_INTERVAL),
+                        )
+                        .await;
+                        continue;
+                    }
+                }
+            }
+
+            let (l2_start, l2_end) = match self.next_pending_commitment() {
+                Ok(Some(l2_range)) => l2_range,
+                Ok(None) => {
+                    self.notify.notified().await;
+                    continue;
+                }
+                Err(e) => {
+                    error!("Could not read pending commitments: {:?}", e);
+                    tokio::time::sleep(retry_backoff.next_backoff().unwrap_or(MAX_RETRY_INTERVAL))
+                        .await;
+                    continue;
+                }
+            };
+
+            match self.submit(l2_start, l2_end).await {
+                Ok(()) => retry_backoff.reset(),
+                Err(e) => {
+                    error!(
+                        "Could not submit commitment. L2 range: #{}-{}: {:?}",
+                        l2_start.0, l2_end.0, e
+                    );
+                    // The failed submission may have reached DA nonetheless
+                    needs_reconciliation = true;
+                    tokio::time::sleep(retry_backoff.next_backoff().unwrap_or(MAX_RETRY_INTERVAL))
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Returns the lowest pending commitment, dropping the ones overlapping with
+    /// the last submitted commitment
+    fn next_pending_commitment(
+        &self,
+    ) -> anyhow::Result<Option<(SoftConfirmationNumber, SoftConfirmationNumber)>> {
+        let last_commitment_l2_height = self
+            .ledger_db
+            .get_last_commitment_l2_height()?
+            .unwrap_or(SoftConfirmationNumber(0));
+
+        let mut pending_commitments = self.ledger_db.get_pending_commitments_l2_range()?;
+        pending_commitments.sort();
+        for (l2_start, l2_end) in pending_commitments {
+            if l2_start <= last_commitment_l2_height {
+                warn!(
+                    "Dropping pending commitment #{}-{}, L2 blocks up to #{} are already committed",
+                    l2_start.0, l2_end.0, last_commitment_l2_height.0
+                );
+                self.ledger_db
+                    .delete_pending_commitment_l2_range(&(l2_start, l2_end))?;
+                continue;
+            }
+            return Ok(Some((l2_start, l2_end)));
+        }
+        Ok(None)
+    }
+
+    async fn submit(
+        &self,
+        l2_start: SoftConfirmationNumber,
+        l2_end: SoftConfirmationNumber,
+    ) -> anyhow::Result<()> {
+        let soft_confirmation_hashes = self
+            .ledger_db
+            .get_soft_confirmation_range(&(l2_start..=l2_end))?
+            .iter()
+            .map(|sb| sb.hash)
+            .collect::<Vec<[u8; 32]>>();
+
+        SEQUENCER_METRICS
+            .commitment_blocks_count
+            .set(soft_confirmation_hashes.len() as f64);
+
+        let commitment_info = CommitmentInfo {
+            l2_height_range: l2_start..=l2_end,
+        };
+        let commitment = self.get_commitment(commitment_info, soft_confirmation_hashes)?;
+
+        debug!("Sequencer: submitting commitment: {:?}", commitment);
+
+        let merkle_root = commitment.merkle_root;
+        // Only recorded in the commitment history, the DA service picks its own fee rate
+        let fee_rate = self
+            .da_service
+            .get_fee_rate()
+            .await
+            .inspect_err(|e| warn!("Could not get DA fee rate: {}", e))
+            .ok();
+        let da_data = DaData::SequencerCommitment(commitment);
+        let spec_id = fork_from_block_number(l2_end.0).spec_id;
+        let (notify, rx) = oneshot::channel();
+        let request = SenderWithNotifier {
+            da_data,
+            spec_id,
+            notify,
+        };
+        self.da_service
+            .get_send_transaction_queue()
+            .send(request)
+            .map_err(|_| anyhow!("Bitcoin service already stopped!"))?;
+
+        info!(
+            "Sent commitment to DA queue. L2 range: #{}-{}",
+            l2_start.0, l2_end.0,
+        );
+
+        let start = Instant::now();
+        let tx_id = rx
+            .await
+            .map_err(|_| anyhow!("DA service is dead!"))
+            .and_then(|res| res)
+            .inspect_err(|_| SEQUENCER_METRICS.da_submission_failures.increment(1))?;
+
+        SEQUENCER_METRICS.send_commitment_execution.record(
+            Instant::now()
+                .saturating_duration_since(start)
+                .as_secs_f64(),
+        );
+        SEQUENCER_METRICS.commitments_sent.increment(1);
+
+        self.ledger_db
+            .set_last_commitment_l2_height(l2_end)
+            .map_err(|_| anyhow!("Sequencer: Failed to set last sequencer commitment L2 height"))?;
+
+        self.ledger_db
+            .delete_pending_commitment_l2_range(&(l2_start, l2_end))?;
+
+        let submission = StoredCommitmentSubmission {
+            merkle_root,
+            da_tx_ids: vec![tx_id.into()],
+            submitted_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            fee_rate,
+            confirmed_in_l1: None,
+        };
+        if let Err(e) = self
+            .ledger_db
+            .put_commitment_submission(&(l2_start, l2_end), &submission)
+        {
+            // The commitment is on DA already, only its history entry is missing
+            error!("Could not record commitment submission: {:?}", e);
+        }
+
+        events::commitment_submitted(l2_start.0, l2_end.0, merkle_root, start.elapsed());
+        Ok(())
+    }
+
+    /// Clears the pending commitments which are already in the DA mempool or mined
+    #[instrument(level = "trace", skip(self), err, ret)]
+    async fn drop_landed_commitments(&self) -> anyhow::Result<()> {
+        let pending_db_commitments = self.ledger_db.get_pending_commitments_l2_range()?;
+        if pending_db_commitments.is_empty() {
+            return Ok(());
+        }
+        info!("Pending db commitments: {:?}", pending_db_commitments);
+
+        let pending_mempool_commitments = self.get_pending_mempool_commitments().await;
+        info!(
+            "Commitments that are already in DA mempool: {:?}",
+            pending_mempool_commitments
+        );
+
+        let last_commitment_l1_height = self
+            .ledger_db
+            .get_l1_height_of_last_commitment()?
+            .unwrap_or(SlotNumber(1));
+        let mined_commitments = self
+            .get_mined_commitments_from(last_commitment_l1_height)
+            .await?;
+        info!(
+            "Commitments that are already mined by DA: {:?}",
+            mined_commitments
+        );
+
+        let mut landed_commitments = vec![];
+        landed_commitments.extend(pending_mempool_commitments);
+        landed_commitments.extend(mined_commitments);
+
+        for (l2_start, l2_end) in pending_db_commitments {
+            if landed_commitments.iter().any(|commitment| {
+                commitment.l2_start_block_number == l2_start.0
+                    && commitment.l2_end_block_number == l2_end.0
+            }) {
+                // Update last sequencer commitment l2 height
+                match self.ledger_db.get_last_commitment_l2_height()? {
+                    Some(last_commitment_l2_height) if last_commitment_l2_height >= l2_end => {}
+                    _ => {
+                        self.ledger_db.set_last_commitment_l2_height(l2_end)?;
+                    }
+                };
+
+                self.ledger_db
+                    .delete_pending_commitment_l2_range(&(l2_start, l2_end))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip_all, err)]
+    fn get_commitment(
+        &self,
+        commitment_info: CommitmentInfo,
+        soft_confirmation_hashes: Vec<[u8; 32]>,
+    ) -> anyhow::Result<SequencerCommitment> {
+        // sanity check
+        assert_eq!(
+            commitment_info.l2_height_range.end().0 - commitment_info.l2_height_range.start().0
+                + 1u64,
+            soft_confirmation_hashes.len() as u64,
+            "Sequencer: Soft confirmation hashes length does not match the commitment info"
+        );
+
+        // build merkle tree over soft confirmations
+        let merkle_root = MerkleTree::<Sha256>::from_leaves(soft_confirmation_hashes.as_slice())
+            .root()
+            .ok_or(anyhow!("Couldn't compute merkle root"))?;
+        Ok(SequencerCommitment {
+            merkle_root,
+            l2_start_block_number: commitment_info.l2_height_range.start().0,
+            l2_end_block_number: commitment_info.l2_height_range.end().0,
+        })
+    }
+
+    async fn get_pending_mempool_commitments(&self) -> Vec<SequencerCommitment> {
+        self.da_service
+            .get_pending_sequencer_commitments(&self.sequencer_da_pub_key)
+            .await
+    }
+
+    async fn get_mined_commitments_from(
+        &self,
+        da_height: SlotNumber,
+    ) -> anyhow::Result<Vec<SequencerCommitment>> {
+        let head_da_height = self
+            .da_service
+            .get_head_block_header()
+            .await
+            .map_err(|e| anyhow!(e))?
+            .height();
+        let mut mined_commitments = vec![];
+        for height in da_height.0..=head_da_height {
+            let block = self
+                .da_service
+                .get_block_at(height)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            let iter = self
+                .da_service
+                .extract_relevant_sequencer_commitments(&block, &self.sequencer_da_pub_key)
+                .unwrap_or_default();
+            mined_commitments.extend(iter);
+        }
+
+        Ok(mined_commitments)
+    }
+}
@@ -0,0 +1,221 @@
+//! Conditions of the transactions sent with `eth_sendRawTransactionConditional`.
+//! A conditional transaction is only included in a block while its conditions hold, they are
+//! checked against the state the block is built on.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use alloy_primitives::{Address, B256, U256, U64};
+use serde::{Deserialize, Serialize};
+
+/// Expected storage of an account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KnownAccount {
+    /// Storage root of the account.
+    /// Accounts do not have their own storage tries in Citrea, so these are rejected.
+    StorageRoot(B256),
+    /// Values of storage slots of the account
+    Slots(BTreeMap<B256, B256>),
+}
+
+/// Conditions of a transaction sent with `eth_sendRawTransactionConditional`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionConditional {
+    /// Last L2 height the transaction can be included at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until_l2_block: Option<U64>,
+    /// Storage the accounts must have when the transaction is included
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub known_accounts: BTreeMap<Address, KnownAccount>,
+}
+
+/// Why a conditional transaction can not be included in a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ConditionNotMet {
+    /// The block is past the validity window of the transaction
+    Expired {
+        valid_until_l2_block: u64,
+        l2_height: u64,
+    },
+    /// A storage slot does not have the expected value
+    StorageMismatch { address: Address, slot: B256 },
+    /// A storage root is expected, which can not be checked
+    StorageRootUnsupported { address: Address },
+}
+
+impl Display for ConditionNotMet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionNotMet::Expired {
+                valid_until_l2_block,
+                l2_height,
+            } => write!(
+                f,
+                "valid until L2 block {} but the next block is {}",
+                valid_until_l2_block, l2_height
+            ),
+            ConditionNotMet::StorageMismatch { address, slot } => {
+                write!(f, "storage slot {} of {} changed", slot, address)
+            }
+            ConditionNotMet::StorageRootUnsupported { address } => write!(
+                f,
+                "storage root of {} can not be checked, known accounts must list storage slots",
+                address
+            ),
+        }
+    }
+}
+
+impl TransactionConditional {
+    /// Number of storage slots whose values are checked, a storage root counts as one
+    pub(crate) fn slot_count(&self) -> usize {
+        self.known_accounts
+            .values()
+            .map(|account| match account {
+                KnownAccount::StorageRoot(_) => 1,
+                KnownAccount::Slots(slots) => slots.len(),
+            })
+            .sum()
+    }
+
+    /// Checks the conditions for a block at `l2_height`, on the state whose storage slot values
+    /// are returned by `storage_value`.
+    pub(crate) fn check(
+        &self,
+        l2_height: u64,
+        mut storage_value: impl FnMut(&Address, U256) -> U256,
+    ) -> Result<(), ConditionNotMet> {
+        if let Some(valid_until_l2_block) = self.valid_until_l2_block {
+            let valid_until_l2_block = valid_until_l2_block.to::<u64>();
+            if l2_height > valid_until_l2_block {
+                return Err(ConditionNotMet::Expired {
+                    valid_until_l2_block,
+                    l2_height,
+                });
+            }
+        }
+
+        for (address, account) in &self.known_accounts {
+            let slots = match account {
+                KnownAccount::StorageRoot(_) => {
+                    return Err(ConditionNotMet::StorageRootUnsupported { address: *address })
+                }
+                KnownAccount::Slots(slots) => slots,
+            };
+            for (slot, expected) in slots {
+                let value = storage_value(address, U256::from_be_bytes(slot.0));
+                if B256::from(value) != *expected {
+                    return Err(ConditionNotMet::StorageMismatch {
+                        address: *address,
+                        slot: *slot,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_conditional() {
+        let conditional: TransactionConditional = serde_json::from_str(
+            r#"{
+                "validUntilL2Block": "0x10",
+                "knownAccounts": {
+                    "0x0000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000002",
+                    "0x0000000000000000000000000000000000000003": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000000": "0x0000000000000000000000000000000000000000000000000000000000000004",
+                        "0x0000000000000000000000000000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000005"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(conditional.valid_until_l2_block, Some(U64::from(16)));
+        assert_eq!(
+            conditional.known_accounts[&Address::with_last_byte(1)],
+            KnownAccount::StorageRoot(B256::with_last_byte(2))
+        );
+        assert_eq!(
+            conditional.known_accounts[&Address::with_last_byte(3)],
+            KnownAccount::Slots(BTreeMap::from([
+                (B256::ZERO, B256::with_last_byte(4)),
+                (B256::with_last_byte(1), B256::with_last_byte(5)),
+            ]))
+        );
+        assert_eq!(conditional.slot_count(), 3);
+
+        // Both conditions are optional
+        let conditional: TransactionConditional = serde_json::from_str("{}").unwrap();
+        assert_eq!(conditional, TransactionConditional::default());
+    }
+
+    #[test]
+    fn test_check_validity_window() {
+        let conditional = TransactionConditional {
+            valid_until_l2_block: Some(U64::from(5)),
+            ..Default::default()
+        };
+        let storage_value = |_: &Address, _: U256| U256::ZERO;
+
+        assert_eq!(conditional.check(5, storage_value), Ok(()));
+        assert_eq!(
+            conditional.check(6, storage_value),
+            Err(ConditionNotMet::Expired {
+                valid_until_l2_block: 5,
+                l2_height: 6,
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_known_accounts() {
+        let address = Address::with_last_byte(1);
+        let conditional = TransactionConditional {
+            valid_until_l2_block: None,
+            known_accounts: BTreeMap::from([(
+                address,
+                KnownAccount::Slots(BTreeMap::from([
+                    (B256::ZERO, B256::with_last_byte(7)),
+                    (B256::with_last_byte(1), B256::ZERO),
+                ])),
+            )]),
+        };
+
+        let storage = |value: u64| {
+            move |account: &Address, slot: U256| {
+                assert_eq!(*account, address);
+                if slot.is_zero() {
+                    U256::from(value)
+                } else {
+                    U256::ZERO
+                }
+            }
+        };
+        assert_eq!(conditional.check(1, storage(7)), Ok(()));
+        assert_eq!(
+            conditional.check(1, storage(8)),
+            Err(ConditionNotMet::StorageMismatch {
+                address,
+                slot: B256::ZERO,
+            })
+        );
+
+        let conditional = TransactionConditional {
+            valid_until_l2_block: None,
+            known_accounts: BTreeMap::from([(address, KnownAccount::StorageRoot(B256::ZERO))]),
+        };
+        assert_eq!(
+            conditional.check(1, storage(7)),
+            Err(ConditionNotMet::StorageRootUnsupported { address })
+        );
+    }
+}
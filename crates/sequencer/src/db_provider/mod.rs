@@ -40,6 +40,16 @@ impl<C: sov_modules_api::Context> DbProvider<C> {
         self.evm.get_chain_config(&mut working_set)
     }
 
+    pub fn system_gas_reserve(&self) -> u64 {
+        let mut working_set = WorkingSet::new(self.storage.clone());
+        self.evm.get_system_gas_reserve(&mut working_set)
+    }
+
+    pub fn storage_value(&self, address: &Address, index: U256) -> U256 {
+        let mut working_set = WorkingSet::new(self.storage.clone());
+        self.evm.storage_value(address, index, &mut working_set)
+    }
+
     pub fn last_block_tx_hashes(&self) -> RpcResult<Vec<B256>> {
         let mut working_set = WorkingSet::new(self.storage.clone());
         let rich_block = self.evm.get_block_by_number(None, None, &mut working_set)?;
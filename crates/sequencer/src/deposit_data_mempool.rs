@@ -1,10 +1,79 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
-use alloy_primitives::TxKind;
+use alloy_primitives::{keccak256, TxKind};
 use alloy_rpc_types_eth::transaction::{TransactionInput, TransactionRequest};
 use citrea_evm::system_contracts::BridgeWrapper;
 use citrea_evm::SYSTEM_SIGNER;
-use tracing::instrument;
+use sov_db::ledger_db::SequencerLedgerOps;
+use tracing::{instrument, warn};
+
+/// Source of the deposits to include in the next soft confirmations
+pub trait DepositProvider {
+    /// Takes at most `limit` deposits out of the source, in their inclusion order
+    fn fetch_deposits(&mut self, limit: usize) -> anyhow::Result<Vec<Vec<u8>>>;
+}
+
+/// Identifies a deposit by the hash of its data
+pub fn deposit_id(deposit_data: &[u8]) -> [u8; 32] {
+    keccak256(deposit_data).0
+}
+
+/// Fetches the deposits of the next soft confirmation, at most `limit_per_block` of them.
+///
+/// Deposits already included in a soft confirmation, and the repeated ones, are skipped.
+/// If the deposits cannot be fetched, the soft confirmation goes out without deposits.
+pub fn fetch_block_deposits<P: DepositProvider, DB: SequencerLedgerOps>(
+    provider: &mut P,
+    ledger_db: &DB,
+    limit_per_block: usize,
+) -> Vec<Vec<u8>> {
+    match try_fetch_block_deposits(provider, ledger_db, limit_per_block) {
+        Ok(deposits) => deposits,
+        Err(e) => {
+            warn!(
+                "Failed to fetch deposits, producing the block without deposits: {:?}",
+                e
+            );
+            vec![]
+        }
+    }
+}
+
+fn try_fetch_block_deposits<P: DepositProvider, DB: SequencerLedgerOps>(
+    provider: &mut P,
+    ledger_db: &DB,
+    limit_per_block: usize,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut deposits = vec![];
+    let mut deposit_ids = HashSet::new();
+    while deposits.len() < limit_per_block {
+        let fetched = provider.fetch_deposits(limit_per_block - deposits.len())?;
+        if fetched.is_empty() {
+            break;
+        }
+        for deposit in fetched {
+            // a source returning more than asked for is capped, the rest is left to it
+            if deposits.len() == limit_per_block {
+                break;
+            }
+            let id = deposit_id(&deposit);
+            if !deposit_ids.insert(id) {
+                warn!("Skipping deposit {} repeated in the block", hex::encode(id));
+                continue;
+            }
+            if let Some(l2_height) = ledger_db.get_deposit_inclusion_height(&id)? {
+                warn!(
+                    "Skipping deposit {} already included at L2 height {}",
+                    hex::encode(id),
+                    l2_height.0
+                );
+                continue;
+            }
+            deposits.push(deposit);
+        }
+    }
+    Ok(deposits)
+}
 
 #[derive(Clone, Debug)]
 pub struct DepositDataMempool {
@@ -39,8 +108,144 @@ impl DepositDataMempool {
             .collect()
     }
 
+    /// Puts the deposits of rolled back blocks back in front of the queue, in their original order
+    pub fn requeue_deposit_txs(&mut self, deposits: Vec<Vec<u8>>) {
+        for deposit in deposits.into_iter().rev() {
+            self.accepted_deposit_txs.push_front(deposit);
+        }
+    }
+
     #[instrument(level = "trace", skip_all, ret)]
     pub fn add_deposit_tx(&mut self, req: Vec<u8>) {
         self.accepted_deposit_txs.push_back(req);
     }
 }
+
+impl DepositProvider for DepositDataMempool {
+    fn fetch_deposits(&mut self, limit: usize) -> anyhow::Result<Vec<Vec<u8>>> {
+        Ok(DepositDataMempool::fetch_deposits(self, limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_db::ledger_db::LedgerDB;
+    use sov_db::rocks_db_config::RocksdbConfig;
+    use sov_db::schema::types::SoftConfirmationNumber;
+
+    use super::*;
+
+    /// Returns its batches one per fetch, ignoring the limit as a misbehaving source could
+    struct StubDepositProvider {
+        batches: VecDeque<anyhow::Result<Vec<Vec<u8>>>>,
+    }
+
+    impl DepositProvider for StubDepositProvider {
+        fn fetch_deposits(&mut self, _limit: usize) -> anyhow::Result<Vec<Vec<u8>>> {
+            self.batches.pop_front().unwrap_or_else(|| Ok(vec![]))
+        }
+    }
+
+    /// Fetches the deposits of `block_count` blocks, recording them as included as the sequencer does
+    fn produce_blocks<P: DepositProvider>(
+        provider: &mut P,
+        ledger_db: &LedgerDB,
+        first_l2_height: u64,
+        block_count: u64,
+        limit_per_block: usize,
+    ) -> Vec<Vec<Vec<u8>>> {
+        (first_l2_height..first_l2_height + block_count)
+            .map(|l2_height| {
+                let deposits = fetch_block_deposits(provider, ledger_db, limit_per_block);
+                let ids = deposits.iter().map(|d| deposit_id(d)).collect::<Vec<_>>();
+                ledger_db
+                    .put_included_deposit_ids(&ids, SoftConfirmationNumber(l2_height))
+                    .unwrap();
+                deposits
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_each_deposit_is_included_once() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db =
+            LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
+
+        let deposit = |i: u8| vec![i; 10];
+        let mut provider = StubDepositProvider {
+            batches: VecDeque::from([
+                Ok(vec![deposit(1), deposit(2), deposit(2)]),
+                Ok(vec![deposit(1), deposit(3)]),
+                Ok(vec![deposit(4), deposit(3), deposit(5)]),
+                Ok(vec![deposit(5)]),
+            ]),
+        };
+
+        let blocks = produce_blocks(&mut provider, &ledger_db, 1, 4, 3);
+        assert!(blocks.iter().all(|deposits| deposits.len() <= 3));
+        for i in 1..=5 {
+            let inclusions = blocks
+                .iter()
+                .flatten()
+                .filter(|d| **d == deposit(i))
+                .count();
+            assert_eq!(inclusions, 1, "deposit {} included {} times", i, inclusions);
+        }
+        assert_eq!(
+            ledger_db
+                .get_deposit_inclusion_height(&deposit_id(&deposit(4)))
+                .unwrap(),
+            Some(SoftConfirmationNumber(2))
+        );
+    }
+
+    #[test]
+    fn test_included_deposits_are_skipped_after_restart() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db =
+            LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
+
+        let mut mempool = DepositDataMempool::new();
+        mempool.add_deposit_tx(vec![1; 10]);
+        mempool.add_deposit_tx(vec![2; 10]);
+        let blocks = produce_blocks(&mut mempool, &ledger_db, 1, 1, 10);
+        assert_eq!(blocks, vec![vec![vec![1; 10], vec![2; 10]]]);
+        drop(ledger_db);
+
+        // The source provides the same deposits again after the restart
+        let ledger_db =
+            LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
+        let mut mempool = DepositDataMempool::new();
+        mempool.add_deposit_tx(vec![1; 10]);
+        mempool.add_deposit_tx(vec![3; 10]);
+        mempool.add_deposit_tx(vec![2; 10]);
+        let blocks = produce_blocks(&mut mempool, &ledger_db, 2, 1, 10);
+        assert_eq!(blocks, vec![vec![vec![3; 10]]]);
+
+        // Rolled back deposits can be included again
+        ledger_db
+            .remove_included_deposit_ids(&[deposit_id(&[3; 10])])
+            .unwrap();
+        mempool.requeue_deposit_txs(vec![vec![3; 10]]);
+        let blocks = produce_blocks(&mut mempool, &ledger_db, 2, 1, 10);
+        assert_eq!(blocks, vec![vec![vec![3; 10]]]);
+    }
+
+    #[test]
+    fn test_fetch_failure_produces_block_without_deposits() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db =
+            LedgerDB::with_config(&RocksdbConfig::new(tmpdir.path(), None, None)).unwrap();
+
+        let mut provider = StubDepositProvider {
+            batches: VecDeque::from([
+                Err(anyhow::anyhow!("Bitcoin node is unreachable")),
+                Ok(vec![vec![1; 10]]),
+            ]),
+        };
+
+        let blocks = produce_blocks(&mut provider, &ledger_db, 1, 2, 10);
+        assert_eq!(blocks, vec![vec![], vec![vec![1; 10]]]);
+    }
+}
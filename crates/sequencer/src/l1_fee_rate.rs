@@ -0,0 +1,193 @@
+use anyhow::ensure;
+use citrea_common::L1FeeRateSmoothingConfig;
+
+/// Smooths the DA fee rate estimates into the L1 fee rates committed into the soft confirmations.
+///
+/// The fee rate of a soft confirmation is an exponential moving average of the estimates,
+/// whose change from the previous soft confirmation is capped, and which is kept within the
+/// configured bounds.
+#[derive(Debug)]
+pub(crate) struct L1FeeRateOracle {
+    config: L1FeeRateSmoothingConfig,
+    /// Latest DA fee rate estimate used for a soft confirmation
+    raw: Option<u128>,
+    /// Fee rate of the head soft confirmation
+    smoothed: Option<u128>,
+}
+
+impl L1FeeRateOracle {
+    /// Continues from the fee rate of the head soft confirmation, if any
+    pub(crate) fn new(
+        config: L1FeeRateSmoothingConfig,
+        head_l1_fee_rate: Option<u128>,
+    ) -> anyhow::Result<Self> {
+        ensure!(
+            config.alpha > 0.0 && config.alpha <= 1.0,
+            "L1 fee rate smoothing alpha must be in (0, 1], got {}",
+            config.alpha
+        );
+        if let (Some(min), Some(max)) = (config.min_fee_rate, config.max_fee_rate) {
+            ensure!(
+                min <= max,
+                "Min L1 fee rate {} is above the max L1 fee rate {}",
+                min,
+                max
+            );
+        }
+
+        Ok(Self {
+            config,
+            raw: None,
+            smoothed: head_l1_fee_rate,
+        })
+    }
+
+    /// L1 fee rate of the next soft confirmation, given the latest DA fee rate estimate.
+    /// Does not change the oracle, the fee rate is only recorded once the soft confirmation is produced.
+    pub(crate) fn next_fee_rate(&self, raw: u128) -> u128 {
+        let Some(previous) = self.smoothed else {
+            return self.clamp(raw);
+        };
+
+        // Rounded towards the estimate, so that the average reaches a steady estimate
+        let average = previous as f64 + self.config.alpha * (raw as f64 - previous as f64);
+        let average = if raw > previous {
+            average.ceil()
+        } else {
+            average.floor()
+        };
+        // Ceil so that a small fee rate can still change
+        let max_change = previous
+            .saturating_mul(self.config.max_change_per_block_bps as u128)
+            .div_ceil(10_000);
+        let next = (average as u128).clamp(
+            previous.saturating_sub(max_change),
+            previous.saturating_add(max_change),
+        );
+        self.clamp(next)
+    }
+
+    /// Records the fee rate of a produced soft confirmation and the estimate it was computed from
+    pub(crate) fn record(&mut self, raw: u128, l1_fee_rate: u128) {
+        self.raw = Some(raw);
+        self.smoothed = Some(l1_fee_rate);
+    }
+
+    /// Continues from the fee rate of the new head soft confirmation after a rollback
+    pub(crate) fn reset(&mut self, head_l1_fee_rate: Option<u128>) {
+        self.smoothed = head_l1_fee_rate;
+    }
+
+    /// Latest DA fee rate estimate, and the fee rate of the head soft confirmation
+    pub(crate) fn rates(&self) -> (Option<u128>, Option<u128>) {
+        (self.raw, self.smoothed)
+    }
+
+    fn clamp(&self, fee_rate: u128) -> u128 {
+        let fee_rate = self
+            .config
+            .min_fee_rate
+            .map_or(fee_rate, |min| fee_rate.max(min));
+        self.config
+            .max_fee_rate
+            .map_or(fee_rate, |max| fee_rate.min(max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Produces a soft confirmation per raw fee rate, returning their fee rates
+    fn produce(oracle: &mut L1FeeRateOracle, raw_series: &[u128]) -> Vec<u128> {
+        raw_series
+            .iter()
+            .map(|raw| {
+                let l1_fee_rate = oracle.next_fee_rate(*raw);
+                oracle.record(*raw, l1_fee_rate);
+                l1_fee_rate
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_per_block_change_is_bounded() {
+        let mut oracle = L1FeeRateOracle::new(
+            L1FeeRateSmoothingConfig {
+                alpha: 1.0,
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+
+        let spiky = [
+            1_000_000, 1_000_000, 10_000_000, 100_000, 50_000_000, 1, 1_000_000, 20_000_000,
+        ];
+        let fee_rates = produce(&mut oracle, &spiky);
+
+        assert_eq!(fee_rates[0], 1_000_000);
+        for pair in fee_rates.windows(2) {
+            let (previous, next) = (pair[0], pair[1]);
+            assert!(next.abs_diff(previous) <= previous.div_ceil(8));
+        }
+        // The spikes are followed as fast as the bound allows
+        assert_eq!(fee_rates[2], 1_125_000);
+        assert_eq!(fee_rates[3], 984_375);
+        assert_eq!(
+            oracle.rates(),
+            (Some(20_000_000), fee_rates.last().copied())
+        );
+    }
+
+    #[test]
+    fn test_converges_to_steady_fee_rate() {
+        let mut oracle =
+            L1FeeRateOracle::new(L1FeeRateSmoothingConfig::default(), Some(1_000_000)).unwrap();
+
+        // A single spike only moves the fee rate by the per block bound
+        let fee_rates = produce(&mut oracle, &[100_000_000, 1_000_000]);
+        assert_eq!(fee_rates, vec![1_125_000, 1_112_500]);
+
+        let fee_rates = produce(&mut oracle, &[4_000_000; 150]);
+        assert!(fee_rates.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(fee_rates[149], 4_000_000);
+
+        // A constant fee rate is committed as it is
+        let mut oracle =
+            L1FeeRateOracle::new(L1FeeRateSmoothingConfig::default(), Some(10)).unwrap();
+        assert_eq!(produce(&mut oracle, &[10; 5]), vec![10; 5]);
+    }
+
+    #[test]
+    fn test_fee_rate_bounds() {
+        let config = L1FeeRateSmoothingConfig {
+            alpha: 1.0,
+            max_change_per_block_bps: 10_000,
+            min_fee_rate: Some(100),
+            max_fee_rate: Some(1_000),
+        };
+        let mut oracle = L1FeeRateOracle::new(config.clone(), None).unwrap();
+        assert_eq!(produce(&mut oracle, &[1, 150, 5_000]), vec![100, 150, 300]);
+
+        let mut oracle = L1FeeRateOracle::new(config.clone(), Some(900)).unwrap();
+        assert_eq!(produce(&mut oracle, &[5_000]), vec![1_000]);
+
+        assert!(L1FeeRateOracle::new(
+            L1FeeRateSmoothingConfig {
+                alpha: 0.0,
+                ..config.clone()
+            },
+            None
+        )
+        .is_err());
+        assert!(L1FeeRateOracle::new(
+            L1FeeRateSmoothingConfig {
+                min_fee_rate: Some(2_000),
+                ..config
+            },
+            None
+        )
+        .is_err());
+    }
+}
@@ -1,13 +1,22 @@
 mod commitment;
+mod conditional;
 pub mod db_migrations;
 mod db_provider;
 mod deposit_data_mempool;
+mod l1_fee_rate;
 mod mempool;
 mod metrics;
 mod rpc;
 mod runner;
+mod timestamp;
 mod utils;
 
 pub use citrea_common::{SequencerConfig, SequencerMempoolConfig};
-pub use rpc::SequencerRpcClient;
+pub use conditional::{KnownAccount, TransactionConditional};
+pub use rpc::{
+    CommitmentL2Range, CommitmentSubmission, L1FeeRate, PendingCommitments, ProductionState,
+    SequencerRpcClient, TxpoolContent, TxpoolStatus, CONDITIONS_NOT_MET_ERROR_CODE,
+    MAX_COMMITMENT_HISTORY_LIMIT,
+};
 pub use runner::CitreaSequencer;
+pub use utils::recover_raw_transaction;
@@ -1,21 +1,26 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
 use std::sync::Arc;
 
 use alloy_genesis::Genesis;
-use alloy_primitives::TxHash;
+use alloy_primitives::{Address, TxHash, U256};
 use anyhow::{anyhow, bail};
-use citrea_common::SequencerMempoolConfig;
+use citrea_common::{SequencerMempoolConfig, TxOrderingPolicy};
 use citrea_evm::SYSTEM_SIGNER;
+use parking_lot::Mutex;
 use reth_chainspec::{Chain, ChainSpecBuilder};
 use reth_execution_types::ChangedAccount;
 use reth_tasks::TokioTaskExecutor;
 use reth_transaction_pool::blobstore::NoopBlobStore;
 use reth_transaction_pool::error::PoolError;
 use reth_transaction_pool::{
-    BestTransactions, BestTransactionsAttributes, CoinbaseTipOrdering, EthPooledTransaction,
-    EthTransactionValidator, Pool, PoolConfig, PoolResult, SubPoolLimit, TransactionPool,
-    TransactionPoolExt, TransactionValidationTaskExecutor, ValidPoolTransaction,
+    AllPoolTransactions, BestTransactionsAttributes, CoinbaseTipOrdering, EthPooledTransaction,
+    EthTransactionValidator, Pool, PoolConfig, PoolResult, PoolSize, PoolTransaction, SubPoolLimit,
+    TransactionPool, TransactionPoolExt, TransactionValidationTaskExecutor, ValidPoolTransaction,
 };
+use tokio::time::{Duration, Instant};
 
+use crate::conditional::{ConditionNotMet, TransactionConditional};
 pub use crate::db_provider::DbProvider;
 
 type CitreaMempoolImpl<C> = Pool<
@@ -26,7 +31,11 @@ type CitreaMempoolImpl<C> = Pool<
 
 type Transaction<C> = <CitreaMempoolImpl<C> as TransactionPool>::Transaction;
 
-pub(crate) struct CitreaMempool<C: sov_modules_api::Context>(CitreaMempoolImpl<C>);
+pub(crate) struct CitreaMempool<C: sov_modules_api::Context> {
+    pool: CitreaMempoolImpl<C>,
+    lifetimes: Mutex<TxLifetimes>,
+    conditionals: Mutex<ConditionalTxs>,
+}
 
 impl<C: sov_modules_api::Context> CitreaMempool<C> {
     pub(crate) fn new(
@@ -39,6 +48,10 @@ impl<C: sov_modules_api::Context> CitreaMempool<C> {
             .map(|b| b.ok_or(anyhow!("Genesis block does not exist")))
             .map_err(|e| anyhow!("{e}"))??;
         let evm_config = client.cfg();
+        // gas reserved for the system transactions can never be used by the pool transactions
+        let user_gas_limit = evm_config
+            .block_gas_limit
+            .saturating_sub(client.system_gas_reserve());
         let Some(nonce) = genesis_block.header.nonce else {
             bail!("Genesis nonce is not set");
         };
@@ -89,12 +102,22 @@ impl<C: sov_modules_api::Context> CitreaMempool<C> {
             .no_eip4844()
             // TODO: if we ever increase block gas limits, we need to pull this from
             // somewhere else
-            .set_block_gas_limit(evm_config.block_gas_limit)
+            .set_block_gas_limit(user_gas_limit)
             .set_shanghai(true)
             .with_additional_tasks(0)
             .build_with_tasks(client, TokioTaskExecutor::default(), blob_store);
 
-        Ok(Self(Pool::eth_pool(validator, blob_store, pool_config)))
+        Ok(Self {
+            pool: Pool::eth_pool(validator, blob_store, pool_config),
+            lifetimes: Mutex::new(TxLifetimes::new(
+                Duration::from_secs(mempool_conf.tx_ttl_secs),
+                Duration::from_secs(mempool_conf.queued_tx_ttl_secs),
+            )),
+            conditionals: Mutex::new(ConditionalTxs::new(
+                mempool_conf.conditional_tx_limit as usize,
+                mempool_conf.conditional_tx_max_slots as usize,
+            )),
+        })
     }
 
     pub(crate) async fn add_external_transaction(
@@ -107,33 +130,436 @@ impl<C: sov_modules_api::Context> CitreaMempool<C> {
                 "system transactions from rpc are not allowed",
             ));
         }
-        self.0.add_external_transaction(transaction).await
+        let hash = self.pool.add_external_transaction(transaction).await?;
+        self.lifetimes.lock().insert(hash);
+        Ok(hash)
+    }
+
+    /// Adds a transaction which is only included while its conditions hold.
+    /// The conditions must have been checked against the current state by the caller.
+    pub(crate) async fn add_conditional_transaction(
+        &self,
+        transaction: EthPooledTransaction,
+        conditional: TransactionConditional,
+    ) -> PoolResult<TxHash> {
+        if self.conditionals.lock().is_full() {
+            return Err(PoolError::other(
+                transaction.transaction().hash(),
+                "conditional transaction limit reached",
+            ));
+        }
+        let hash = self.add_external_transaction(transaction).await?;
+        self.conditionals.lock().insert(hash, conditional);
+        Ok(hash)
+    }
+
+    /// Max number of storage slots a conditional transaction can require known values for
+    pub(crate) fn conditional_tx_max_slots(&self) -> usize {
+        self.conditionals.lock().max_slots
+    }
+
+    /// Whether the transaction was added with conditions
+    pub(crate) fn is_conditional(&self, hash: &TxHash) -> bool {
+        self.conditionals.lock().conditions.contains_key(hash)
+    }
+
+    /// Removes the conditional transactions whose conditions do not hold for a block at
+    /// `l2_height`, on the state whose storage slot values are returned by `storage_value`.
+    pub(crate) fn remove_unmet_conditional_transactions(
+        &self,
+        l2_height: u64,
+        storage_value: impl FnMut(&Address, U256) -> U256,
+    ) -> Vec<(TxHash, ConditionNotMet)> {
+        let unmet = self.conditionals.lock().unmet(
+            l2_height,
+            |hash| self.pool.contains(hash),
+            storage_value,
+        );
+        if !unmet.is_empty() {
+            let hashes = unmet.iter().map(|(hash, _)| *hash).collect::<Vec<_>>();
+            self.lifetimes.lock().remove(&hashes);
+            self.pool.remove_transactions(hashes);
+        }
+        unmet
     }
 
     pub(crate) fn get(&self, hash: &TxHash) -> Option<Arc<ValidPoolTransaction<Transaction<C>>>> {
-        self.0.get(hash)
+        self.pool.get(hash)
     }
 
     pub(crate) fn remove_transactions(
         &self,
         tx_hashes: Vec<TxHash>,
     ) -> Vec<Arc<ValidPoolTransaction<Transaction<C>>>> {
-        self.0.remove_transactions(tx_hashes)
+        self.lifetimes.lock().remove(&tx_hashes);
+        self.conditionals.lock().remove(&tx_hashes);
+        self.pool.remove_transactions(tx_hashes)
+    }
+
+    /// Removes the transactions that outlived their TTL and returns their hashes.
+    pub(crate) fn remove_expired_transactions(&self) -> Vec<TxHash> {
+        let AllPoolTransactions { pending, queued } = self.pool.all_transactions();
+        let expired = self.lifetimes.lock().expired(
+            pending.iter().map(|tx| *tx.hash()),
+            queued.iter().map(|tx| *tx.hash()),
+        );
+        if !expired.is_empty() {
+            self.conditionals.lock().remove(&expired);
+            self.pool.remove_transactions(expired.clone());
+        }
+        expired
+    }
+
+    /// Returns the transactions the pool evicted since the last call, e.g. by the pool limits
+    /// or by a replacement.
+    pub(crate) fn take_evicted_transactions(&self) -> Vec<TxHash> {
+        self.lifetimes
+            .lock()
+            .evicted(|tx_hash| self.pool.contains(tx_hash))
+    }
+
+    /// How often expired transactions are looked for
+    pub(crate) fn expiry_sweep_interval(&self) -> Duration {
+        self.lifetimes.lock().sweep_interval()
     }
 
     pub(crate) fn update_accounts(&self, account_updates: Vec<ChangedAccount>) {
-        self.0.update_accounts(account_updates);
+        self.pool.update_accounts(account_updates);
     }
 
-    pub(crate) fn best_transactions_with_attributes(
+    /// Returns the transactions that can be included in the next block with the given base fee,
+    /// in the order of the policy.
+    pub(crate) fn best_transactions(
         &self,
-        best_transactions_attributes: BestTransactionsAttributes,
-    ) -> Box<dyn BestTransactions<Item = Arc<ValidPoolTransaction<Transaction<C>>>>> {
-        self.0
-            .best_transactions_with_attributes(best_transactions_attributes)
+        base_fee: u64,
+        policy: TxOrderingPolicy,
+    ) -> Vec<Arc<ValidPoolTransaction<Transaction<C>>>> {
+        let best_txs = self
+            .pool
+            .best_transactions_with_attributes(BestTransactionsAttributes::base_fee(base_fee));
+        order_transactions(best_txs, base_fee, policy)
     }
 
     pub(crate) fn len(&self) -> usize {
-        self.0.len()
+        self.pool.len()
+    }
+
+    /// Queued transactions include the ones that can't pay the current base fee.
+    pub(crate) fn all_transactions(&self) -> AllPoolTransactions<Transaction<C>> {
+        self.pool.all_transactions()
+    }
+
+    pub(crate) fn pool_size(&self) -> PoolSize {
+        self.pool.pool_size()
+    }
+}
+
+/// Tie-break between transactions paying the same effective tip, lower ones are included first
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum TieBreak {
+    SenderNonceHash(Address, u64, TxHash),
+    InsertedAt(std::time::Instant, TxHash),
+}
+
+/// Orders transactions so that the next transaction of the sender with the highest effective tip
+/// is always included first, which keeps the transactions of each sender in nonce order.
+/// The order only depends on the transactions and the policy, not on how the pool iterates them.
+fn order_transactions<T: PoolTransaction>(
+    txs: impl Iterator<Item = Arc<ValidPoolTransaction<T>>>,
+    base_fee: u64,
+    policy: TxOrderingPolicy,
+) -> Vec<Arc<ValidPoolTransaction<T>>> {
+    // The pool yields the transactions of a sender in nonce order
+    let mut txs_by_sender: BTreeMap<Address, VecDeque<Arc<ValidPoolTransaction<T>>>> =
+        BTreeMap::new();
+    for tx in txs {
+        txs_by_sender.entry(tx.sender()).or_default().push_back(tx);
+    }
+
+    let order_key = |tx: &Arc<ValidPoolTransaction<T>>| {
+        let tip = tx.effective_tip_per_gas(base_fee).unwrap_or_default();
+        let tie_break = match policy {
+            TxOrderingPolicy::PriorityFee => {
+                TieBreak::SenderNonceHash(tx.sender(), tx.nonce(), *tx.hash())
+            }
+            TxOrderingPolicy::Fifo => TieBreak::InsertedAt(tx.timestamp, *tx.hash()),
+        };
+        (tip, Reverse(tie_break))
+    };
+
+    let mut next_txs = BinaryHeap::new();
+    for (sender, txs) in txs_by_sender.iter() {
+        if let Some(tx) = txs.front() {
+            next_txs.push((order_key(tx), *sender));
+        }
+    }
+
+    let mut ordered_txs = vec![];
+    while let Some((_, sender)) = next_txs.pop() {
+        let txs = txs_by_sender
+            .get_mut(&sender)
+            .expect("Sender of a queued transaction must have transactions");
+        if let Some(tx) = txs.pop_front() {
+            ordered_txs.push(tx);
+        }
+        if let Some(tx) = txs.front() {
+            next_txs.push((order_key(tx), sender));
+        }
+    }
+    ordered_txs
+}
+
+/// Insertion times of the mempool transactions, to evict the ones that can not be mined in time.
+/// Executable transactions and queued ones, e.g. nonce-gapped, have separate TTLs.
+struct TxLifetimes {
+    tx_ttl: Duration,
+    queued_tx_ttl: Duration,
+    inserted_at: HashMap<TxHash, Instant>,
+}
+
+impl TxLifetimes {
+    fn new(tx_ttl: Duration, queued_tx_ttl: Duration) -> Self {
+        Self {
+            tx_ttl,
+            queued_tx_ttl,
+            inserted_at: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, tx_hash: TxHash) {
+        self.inserted_at.insert(tx_hash, Instant::now());
+    }
+
+    fn remove(&mut self, tx_hashes: &[TxHash]) {
+        for tx_hash in tx_hashes {
+            self.inserted_at.remove(tx_hash);
+        }
+    }
+
+    /// Returns the expired ones among the transactions currently in the pool and forgets them.
+    /// Transactions no longer in the pool, e.g. evicted by the pool limits, are kept
+    /// until they are taken by [`Self::evicted`].
+    fn expired(
+        &mut self,
+        pending: impl Iterator<Item = TxHash>,
+        queued: impl Iterator<Item = TxHash>,
+    ) -> Vec<TxHash> {
+        let now = Instant::now();
+        let mut expired = vec![];
+
+        let pool_txs = pending
+            .map(|tx_hash| (tx_hash, self.tx_ttl))
+            .chain(queued.map(|tx_hash| (tx_hash, self.queued_tx_ttl)));
+        for (tx_hash, ttl) in pool_txs {
+            let tx_inserted_at = *self.inserted_at.entry(tx_hash).or_insert(now);
+            if now.saturating_duration_since(tx_inserted_at) >= ttl {
+                expired.push(tx_hash);
+            }
+        }
+
+        self.remove(&expired);
+        expired
+    }
+
+    /// Returns the transactions no longer in the pool, e.g. evicted by the pool limits,
+    /// and forgets them.
+    fn evicted(&mut self, in_pool: impl Fn(&TxHash) -> bool) -> Vec<TxHash> {
+        let mut evicted = vec![];
+        self.inserted_at.retain(|tx_hash, _| {
+            if in_pool(tx_hash) {
+                return true;
+            }
+            evicted.push(*tx_hash);
+            false
+        });
+        evicted
+    }
+
+    /// Sweeps at half the shortest TTL, within 1 second and 1 minute
+    fn sweep_interval(&self) -> Duration {
+        (self.tx_ttl.min(self.queued_tx_ttl) / 2)
+            .clamp(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+/// Conditions of the conditional transactions in the mempool
+struct ConditionalTxs {
+    limit: usize,
+    max_slots: usize,
+    conditions: HashMap<TxHash, TransactionConditional>,
+}
+
+impl ConditionalTxs {
+    fn new(limit: usize, max_slots: usize) -> Self {
+        Self {
+            limit,
+            max_slots,
+            conditions: HashMap::new(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.conditions.len() >= self.limit
+    }
+
+    fn insert(&mut self, tx_hash: TxHash, conditional: TransactionConditional) {
+        self.conditions.insert(tx_hash, conditional);
+    }
+
+    fn remove(&mut self, tx_hashes: &[TxHash]) {
+        for tx_hash in tx_hashes {
+            self.conditions.remove(tx_hash);
+        }
+    }
+
+    /// Returns the transactions whose conditions do not hold for a block at `l2_height` and
+    /// forgets them. Transactions no longer in the pool, e.g. evicted by the pool limits,
+    /// are forgotten as well.
+    fn unmet(
+        &mut self,
+        l2_height: u64,
+        in_pool: impl Fn(&TxHash) -> bool,
+        mut storage_value: impl FnMut(&Address, U256) -> U256,
+    ) -> Vec<(TxHash, ConditionNotMet)> {
+        let mut unmet = vec![];
+        self.conditions.retain(|tx_hash, conditional| {
+            if !in_pool(tx_hash) {
+                return false;
+            }
+            match conditional.check(l2_height, &mut storage_value) {
+                Ok(()) => true,
+                Err(e) => {
+                    unmet.push((*tx_hash, e));
+                    false
+                }
+            }
+        });
+        unmet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_nonce_gapped_txs_expire_before_minable_ones() {
+        let mut lifetimes = TxLifetimes::new(Duration::from_secs(600), Duration::from_secs(60));
+
+        let minable = [TxHash::repeat_byte(1), TxHash::repeat_byte(2)];
+        let nonce_gapped = [TxHash::repeat_byte(3), TxHash::repeat_byte(4)];
+        for tx_hash in minable.iter().chain(&nonce_gapped) {
+            lifetimes.insert(*tx_hash);
+        }
+
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert!(lifetimes
+            .expired(minable.into_iter(), nonce_gapped.into_iter())
+            .is_empty());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(
+            lifetimes.expired(minable.into_iter(), nonce_gapped.into_iter()),
+            nonce_gapped
+        );
+
+        // Expired transactions are forgotten once evicted
+        tokio::time::advance(Duration::from_secs(539)).await;
+        assert!(lifetimes
+            .expired(minable.into_iter(), [].into_iter())
+            .is_empty());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(
+            lifetimes.expired(minable.into_iter(), [].into_iter()),
+            minable
+        );
+        assert!(lifetimes.inserted_at.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ttl_is_counted_from_insertion() {
+        let mut lifetimes = TxLifetimes::new(Duration::from_secs(60), Duration::from_secs(60));
+
+        let old = TxHash::repeat_byte(1);
+        lifetimes.insert(old);
+        tokio::time::advance(Duration::from_secs(30)).await;
+        let new = TxHash::repeat_byte(2);
+        lifetimes.insert(new);
+
+        // A transaction moving from the queued to the pending sub-pool keeps its insertion time
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert_eq!(
+            lifetimes.expired([old, new].into_iter(), [].into_iter()),
+            vec![old]
+        );
+
+        // Removed transactions are not tracked anymore
+        lifetimes.remove(&[new]);
+        assert!(lifetimes.inserted_at.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_evicted_txs_are_taken_once() {
+        let mut lifetimes = TxLifetimes::new(Duration::from_secs(60), Duration::from_secs(60));
+
+        let kept = TxHash::repeat_byte(1);
+        let evicted = TxHash::repeat_byte(2);
+        lifetimes.insert(kept);
+        lifetimes.insert(evicted);
+
+        // Evicted transactions are not forgotten by the expiry sweep
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(lifetimes
+            .expired([kept].into_iter(), [].into_iter())
+            .is_empty());
+
+        assert_eq!(lifetimes.evicted(|tx_hash| *tx_hash == kept), vec![evicted]);
+        assert!(lifetimes.evicted(|tx_hash| *tx_hash == kept).is_empty());
+        assert_eq!(lifetimes.inserted_at.len(), 1);
+    }
+
+    #[test]
+    fn test_unmet_conditional_txs_are_forgotten() {
+        let mut conditionals = ConditionalTxs::new(3, 10);
+        let window = |valid_until_l2_block: u64| TransactionConditional {
+            valid_until_l2_block: Some(alloy_primitives::U64::from(valid_until_l2_block)),
+            ..Default::default()
+        };
+        conditionals.insert(TxHash::repeat_byte(1), window(5));
+        conditionals.insert(TxHash::repeat_byte(2), window(6));
+        conditionals.insert(TxHash::repeat_byte(3), window(6));
+        assert!(conditionals.is_full());
+
+        let in_pool = |tx_hash: &TxHash| *tx_hash != TxHash::repeat_byte(3);
+        let storage_value = |_: &Address, _: U256| U256::ZERO;
+        assert!(conditionals.unmet(5, in_pool, storage_value).is_empty());
+        // Transactions no longer in the pool free their place
+        assert!(!conditionals.is_full());
+
+        let unmet = conditionals.unmet(6, in_pool, storage_value);
+        assert_eq!(
+            unmet,
+            vec![(
+                TxHash::repeat_byte(1),
+                ConditionNotMet::Expired {
+                    valid_until_l2_block: 5,
+                    l2_height: 6,
+                }
+            )]
+        );
+        assert_eq!(
+            conditionals.conditions.keys().collect::<Vec<_>>(),
+            vec![&TxHash::repeat_byte(2)]
+        );
+    }
+
+    #[test]
+    fn test_sweep_interval() {
+        let lifetimes = TxLifetimes::new(Duration::from_secs(3600), Duration::from_secs(600));
+        assert_eq!(lifetimes.sweep_interval(), Duration::from_secs(60));
+
+        let lifetimes = TxLifetimes::new(Duration::from_secs(3600), Duration::from_secs(1));
+        assert_eq!(lifetimes.sweep_interval(), Duration::from_secs(1));
     }
 }
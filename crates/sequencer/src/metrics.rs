@@ -1,18 +1,26 @@
-use metrics::{Gauge, Histogram};
+use metrics::{Counter, Gauge, Histogram};
 use metrics_derive::Metrics;
 use once_cell::sync::Lazy;
 
 #[derive(Metrics)]
-#[metrics(scope = "sequencer")]
+#[metrics(scope = "citrea_sequencer")]
 pub struct SequencerMetrics {
     #[metric(describe = "How many transactions are currently in the mempool")]
     pub mempool_txs: Gauge,
+    #[metric(describe = "The number of transactions evicted from the mempool after their TTL")]
+    pub mempool_txs_expired: Counter,
     #[metric(describe = "The duration of dry running transactions")]
     pub dry_run_execution: Histogram,
     #[metric(describe = "The duration of executing block transactions")]
     pub block_production_execution: Histogram,
     #[metric(describe = "The duration of sending a sequencer commitment")]
     pub send_commitment_execution: Histogram,
+    #[metric(describe = "The number of sequencer commitments submitted to DA")]
+    pub commitments_sent: Counter,
+    #[metric(describe = "The number of sequencer commitments that failed to be submitted to DA")]
+    pub da_submission_failures: Counter,
+    #[metric(describe = "The number of transactions included in a soft confirmation")]
+    pub soft_confirmation_txs: Histogram,
     #[metric(describe = "The number of blocks included in a sequencer commitment")]
     pub commitment_blocks_count: Gauge,
     #[metric(describe = "The current L2 block number")]
@@ -1,36 +1,168 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use alloy_eips::eip2718::Encodable2718;
 use alloy_network::AnyNetwork;
-use alloy_primitives::{Bytes, B256};
-use citrea_evm::Evm;
+use alloy_primitives::{Address, Bytes, B256, U128, U64};
+use citrea_evm::{Evm, LIMIT_EXCEEDED_ERROR_CODE};
 use futures::channel::mpsc::UnboundedSender;
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
-use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, INVALID_PARAMS_CODE};
 use jsonrpsee::types::{ErrorCode, ErrorObject, ErrorObjectOwned};
 use parking_lot::Mutex;
 use reth_rpc::eth::EthTxBuilder;
 use reth_rpc_eth_api::RpcTransaction;
 use reth_rpc_eth_types::error::EthApiError;
 use reth_rpc_types_compat::transaction::from_recovered;
-use reth_transaction_pool::{EthPooledTransaction, PoolTransaction};
+use reth_transaction_pool::{
+    AllPoolTransactions, EthPooledTransaction, PoolTransaction, ValidPoolTransaction,
+};
+use serde::{Deserialize, Serialize};
 use sov_db::ledger_db::SequencerLedgerOps;
+use sov_db::schema::types::{L2HeightRange, SoftConfirmationNumber, StoredCommitmentSubmission};
 use sov_modules_api::WorkingSet;
-use tracing::{debug, error};
+use sov_rollup_interface::Network;
+use subtle::ConstantTimeEq;
+use tokio::sync::{oneshot, watch};
+use tracing::{debug, error, info};
 
+use crate::commitment::{compressed_state_diff_size, STATE_DIFF_THRESHOLD};
+use crate::conditional::{ConditionNotMet, TransactionConditional};
 use crate::deposit_data_mempool::DepositDataMempool;
+use crate::l1_fee_rate::L1FeeRateOracle;
 use crate::mempool::CitreaMempool;
 use crate::metrics::SEQUENCER_METRICS;
 use crate::utils::recover_raw_transaction;
 
+/// Maximum number of transactions rendered by a single `txpool_content` request
+const MAX_TXPOOL_CONTENT_TXS: usize = 10_000;
+
+/// Error code of conditional transactions whose conditions do not hold, as in other rollups
+pub const CONDITIONS_NOT_MET_ERROR_CODE: i32 = -32003;
+
+/// Maximum number of commitments returned by a single `sequencer_getCommitmentHistory` request
+pub const MAX_COMMITMENT_HISTORY_LIMIT: u64 = 1000;
+
+/// Transactions of a txpool subpool grouped by sender and nonce
+pub type TxpoolSubpoolContent = BTreeMap<Address, BTreeMap<String, RpcTransaction<AnyNetwork>>>;
+
+/// Number of transactions in the mempool, in the shape of geth's `txpool_status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxpoolStatus {
+    /// Transactions ready to be included in the next block
+    pub pending: U64,
+    /// Transactions waiting for a nonce gap to be filled or for the base fee to drop
+    pub queued: U64,
+}
+
+/// Transactions in the mempool, in the shape of geth's `txpool_content`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TxpoolContent {
+    /// Transactions ready to be included in the next block
+    pub pending: TxpoolSubpoolContent,
+    /// Transactions waiting for a nonce gap to be filled or for the base fee to drop
+    pub queued: TxpoolSubpoolContent,
+}
+
+/// L2 range of a sequencer commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentL2Range {
+    /// First L2 height of the commitment
+    pub start: U64,
+    /// Last L2 height of the commitment
+    pub end: U64,
+}
+
+/// Commitments the sequencer has not landed on the DA yet, and how close the next one is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingCommitments {
+    /// Commitments sent to the DA whose transactions have not been confirmed yet
+    pub pending_l2_ranges: Vec<CommitmentL2Range>,
+    /// Last L2 height covered by a confirmed commitment
+    pub last_committed_l2_height: U64,
+    /// Compressed size of the state diff accumulated since the last commitment
+    pub state_diff_size: U64,
+    /// Compressed state diff size above which a commitment is submitted
+    pub state_diff_threshold: U64,
+    /// Number of soft confirmations after which a commitment is submitted
+    pub min_soft_confirmations_per_commitment: U64,
+}
+
+/// A commitment the sequencer submitted to DA
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentSubmission {
+    /// First L2 height of the commitment
+    pub l2_start_block_number: U64,
+    /// Last L2 height of the commitment
+    pub l2_end_block_number: U64,
+    /// Merkle root of the soft confirmation hashes of the commitment
+    pub merkle_root: B256,
+    /// Ids of the DA transactions the commitment was sent with
+    pub da_tx_ids: Vec<B256>,
+    /// Unix timestamp of the submission, in seconds
+    pub submitted_at: U64,
+    /// DA fee rate at the time of the submission
+    pub fee_rate: Option<U128>,
+    /// L1 height the commitment was found in, once it is in a finalized L1 block
+    pub confirmed_in_l1: Option<U64>,
+}
+
+impl From<(L2HeightRange, StoredCommitmentSubmission)> for CommitmentSubmission {
+    fn from(((l2_start, l2_end), submission): (L2HeightRange, StoredCommitmentSubmission)) -> Self {
+        Self {
+            l2_start_block_number: U64::from(l2_start.0),
+            l2_end_block_number: U64::from(l2_end.0),
+            merkle_root: B256::from(submission.merkle_root),
+            da_tx_ids: submission.da_tx_ids.into_iter().map(B256::from).collect(),
+            submitted_at: U64::from(submission.submitted_at),
+            fee_rate: submission.fee_rate.map(U128::from),
+            confirmed_in_l1: submission.confirmed_in_l1.map(U64::from),
+        }
+    }
+}
+
+/// L1 fee rate committed into the soft confirmations, and the DA fee rate estimate it follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L1FeeRate {
+    /// Latest DA fee rate estimate used for a soft confirmation
+    pub raw: Option<U128>,
+    /// L1 fee rate of the head soft confirmation, smoothed from the estimates
+    pub smoothed: Option<U128>,
+}
+
+/// Whether the sequencer is producing soft confirmations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProductionState {
+    /// Blocks are produced as usual
+    Running,
+    /// Block production is paused, transactions are still accepted into the mempool
+    Halted,
+    /// The sequencer is stopping, blocks are not produced and transactions are rejected
+    ShuttingDown,
+}
+
+/// Request to roll back the soft confirmations above an L2 height,
+/// answered with the number of transactions put back into the mempool
+pub(crate) type RollbackRequest = (u64, oneshot::Sender<anyhow::Result<usize>>);
+
 pub(crate) struct RpcContext<C: sov_modules_api::Context, DB: SequencerLedgerOps> {
     pub mempool: Arc<CitreaMempool<C>>,
     pub deposit_mempool: Arc<Mutex<DepositDataMempool>>,
+    pub l1_fee_rate_oracle: Arc<Mutex<L1FeeRateOracle>>,
     pub l2_force_block_tx: UnboundedSender<()>,
+    pub l2_rollback_tx: UnboundedSender<RollbackRequest>,
+    pub production_state_tx: Arc<watch::Sender<ProductionState>>,
     pub storage: C::Storage,
     pub ledger: DB,
     pub test_mode: bool,
+    pub network: Network,
+    pub admin_token: Option<String>,
+    pub min_soft_confirmations_per_commitment: u64,
 }
 
 #[rpc(client, server)]
@@ -38,6 +170,16 @@ pub trait SequencerRpc {
     #[method(name = "eth_sendRawTransaction")]
     async fn eth_send_raw_transaction(&self, data: Bytes) -> RpcResult<B256>;
 
+    /// Sends a transaction which is only included while its conditions hold, checked against
+    /// the state each block is built on. Once they do not hold, the transaction is evicted.
+    /// Conditional transactions are not restored after a restart.
+    #[method(name = "eth_sendRawTransactionConditional")]
+    async fn eth_send_raw_transaction_conditional(
+        &self,
+        data: Bytes,
+        options: TransactionConditional,
+    ) -> RpcResult<B256>;
+
     #[method(name = "eth_getTransactionByHash")]
     #[blocking]
     fn eth_get_transaction_by_hash(
@@ -52,6 +194,55 @@ pub trait SequencerRpc {
 
     #[method(name = "citrea_testPublishBlock")]
     async fn publish_test_block(&self) -> RpcResult<()>;
+
+    #[method(name = "txpool_status")]
+    #[blocking]
+    fn txpool_status(&self) -> RpcResult<TxpoolStatus>;
+
+    #[method(name = "txpool_content")]
+    #[blocking]
+    fn txpool_content(&self) -> RpcResult<TxpoolContent>;
+
+    #[method(name = "sequencer_getPendingCommitments")]
+    #[blocking]
+    fn get_pending_commitments(&self) -> RpcResult<PendingCommitments>;
+
+    /// Returns the commitments submitted to DA, newest first.
+    /// `limit` defaults to and is capped at [`MAX_COMMITMENT_HISTORY_LIMIT`].
+    #[method(name = "sequencer_getCommitmentHistory")]
+    #[blocking]
+    fn get_commitment_history(
+        &self,
+        limit: Option<U64>,
+        offset: Option<U64>,
+    ) -> RpcResult<Vec<CommitmentSubmission>>;
+
+    /// Returns the submitted commitment covering exactly the given L2 range
+    #[method(name = "sequencer_getCommitmentByL2Range")]
+    #[blocking]
+    fn get_commitment_by_l2_range(
+        &self,
+        l2_start_block_number: U64,
+        l2_end_block_number: U64,
+    ) -> RpcResult<Option<CommitmentSubmission>>;
+
+    #[method(name = "sequencer_getL1FeeRate")]
+    #[blocking]
+    fn get_l1_fee_rate(&self) -> RpcResult<L1FeeRate>;
+
+    #[method(name = "sequencer_haltProduction")]
+    #[blocking]
+    fn halt_production(&self, admin_token: String) -> RpcResult<ProductionState>;
+
+    #[method(name = "sequencer_resumeProduction")]
+    #[blocking]
+    fn resume_production(&self, admin_token: String) -> RpcResult<ProductionState>;
+
+    /// Rolls back the soft confirmations above `l2_height` and puts their transactions back into
+    /// the mempool, returning their number. Refused if any of them is covered by a commitment.
+    /// Only available on devnet and nightly.
+    #[method(name = "sequencer_rollbackToHeight")]
+    async fn rollback_to_height(&self, admin_token: String, l2_height: U64) -> RpcResult<U64>;
 }
 
 pub struct SequencerRpcServerImpl<
@@ -69,6 +260,50 @@ impl<C: sov_modules_api::Context, DB: SequencerLedgerOps + Send + Sync + 'static
             context: Arc::new(context),
         }
     }
+
+    /// Admin methods are only available when an admin token is configured.
+    /// The token is compared in constant time so that its bytes can't be guessed by timing.
+    fn check_admin_token(&self, admin_token: &str) -> RpcResult<()> {
+        match &self.context.admin_token {
+            None => Err(ErrorObject::from(ErrorCode::MethodNotFound).to_owned()),
+            Some(expected) if !bool::from(expected.as_bytes().ct_eq(admin_token.as_bytes())) => {
+                Err(ErrorObjectOwned::owned(
+                    INVALID_PARAMS_CODE,
+                    "Invalid admin token",
+                    None::<String>,
+                ))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn set_production_state(&self, state: ProductionState) -> RpcResult<ProductionState> {
+        let mut previous = state;
+        self.context
+            .production_state_tx
+            .send_if_modified(|current| {
+                previous = *current;
+                if previous == ProductionState::ShuttingDown || previous == state {
+                    return false;
+                }
+                *current = state;
+                true
+            });
+        if previous == ProductionState::ShuttingDown {
+            return Err(shutting_down_error());
+        }
+        if previous != state {
+            info!("Sequencer: block production state changed to {:?}", state);
+        }
+        Ok(state)
+    }
+
+    fn ensure_not_shutting_down(&self) -> RpcResult<()> {
+        if *self.context.production_state_tx.borrow() == ProductionState::ShuttingDown {
+            return Err(shutting_down_error());
+        }
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -77,6 +312,7 @@ impl<C: sov_modules_api::Context, DB: SequencerLedgerOps + Send + Sync + 'static
 {
     async fn eth_send_raw_transaction(&self, data: Bytes) -> RpcResult<B256> {
         debug!("Sequencer: eth_sendRawTransaction");
+        self.ensure_not_shutting_down()?;
 
         let recovered = recover_raw_transaction(data.clone())?;
         let pool_transaction = EthPooledTransaction::from_pooled(recovered);
@@ -109,6 +345,69 @@ impl<C: sov_modules_api::Context, DB: SequencerLedgerOps + Send + Sync + 'static
         Ok(hash)
     }
 
+    async fn eth_send_raw_transaction_conditional(
+        &self,
+        data: Bytes,
+        options: TransactionConditional,
+    ) -> RpcResult<B256> {
+        debug!("Sequencer: eth_sendRawTransactionConditional");
+        self.ensure_not_shutting_down()?;
+
+        let max_slots = self.context.mempool.conditional_tx_max_slots();
+        if options.slot_count() > max_slots {
+            return Err(ErrorObjectOwned::owned(
+                LIMIT_EXCEEDED_ERROR_CODE,
+                format!("known accounts list more than {} storage slots", max_slots),
+                None::<String>,
+            ));
+        }
+
+        let recovered = recover_raw_transaction(data)?;
+        let pool_transaction = EthPooledTransaction::from_pooled(recovered);
+
+        // The conditions must hold for the next block
+        let next_l2_height = self
+            .context
+            .ledger
+            .get_head_soft_confirmation_height()
+            .map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(format!("{e}")),
+                )
+            })?
+            .unwrap_or_default()
+            + 1;
+        let evm = Evm::<C>::default();
+        let mut working_set = WorkingSet::new(self.context.storage.clone());
+        options
+            .check(next_l2_height, |address, index| {
+                evm.storage_value(address, index, &mut working_set)
+            })
+            .map_err(|e| match e {
+                ConditionNotMet::StorageRootUnsupported { .. } => {
+                    ErrorObjectOwned::owned(INVALID_PARAMS_CODE, e.to_string(), None::<String>)
+                }
+                _ => ErrorObjectOwned::owned(
+                    CONDITIONS_NOT_MET_ERROR_CODE,
+                    format!("Transaction conditions not met: {e}"),
+                    None::<String>,
+                ),
+            })?;
+
+        // Not stored in the mempool db, so that it is never restored without its conditions
+        let hash = self
+            .context
+            .mempool
+            .add_conditional_transaction(pool_transaction, options)
+            .await
+            .map_err(EthApiError::from)?;
+        SEQUENCER_METRICS.mempool_txs.increment(1);
+
+        Ok(hash)
+    }
+
     fn eth_get_transaction_by_hash(
         &self,
         hash: B256,
@@ -143,6 +442,7 @@ impl<C: sov_modules_api::Context, DB: SequencerLedgerOps + Send + Sync + 'static
 
     fn send_raw_deposit_transaction(&self, deposit: Bytes) -> RpcResult<()> {
         debug!("Sequencer: citrea_sendRawDepositTransaction");
+        self.ensure_not_shutting_down()?;
 
         let evm = Evm::<C>::default();
         let mut working_set = WorkingSet::new(self.context.storage.clone());
@@ -188,6 +488,210 @@ impl<C: sov_modules_api::Context, DB: SequencerLedgerOps + Send + Sync + 'static
                 )
             })
     }
+
+    fn txpool_status(&self) -> RpcResult<TxpoolStatus> {
+        debug!("Sequencer: txpool_status");
+
+        let size = self.context.mempool.pool_size();
+        Ok(TxpoolStatus {
+            pending: U64::from(size.pending),
+            queued: U64::from(size.basefee + size.queued),
+        })
+    }
+
+    fn txpool_content(&self) -> RpcResult<TxpoolContent> {
+        debug!("Sequencer: txpool_content");
+
+        let AllPoolTransactions { pending, queued } = self.context.mempool.all_transactions();
+        if pending.len() + queued.len() > MAX_TXPOOL_CONTENT_TXS {
+            return Err(ErrorObjectOwned::owned(
+                LIMIT_EXCEEDED_ERROR_CODE,
+                format!(
+                    "txpool contains more than {} transactions, use txpool_status instead",
+                    MAX_TXPOOL_CONTENT_TXS
+                ),
+                None::<String>,
+            ));
+        }
+
+        Ok(TxpoolContent {
+            pending: group_by_sender(pending),
+            queued: group_by_sender(queued),
+        })
+    }
+
+    fn get_pending_commitments(&self) -> RpcResult<PendingCommitments> {
+        debug!("Sequencer: sequencer_getPendingCommitments");
+
+        let map_db_err = |e: anyhow::Error| {
+            ErrorObjectOwned::owned(
+                INTERNAL_ERROR_CODE,
+                INTERNAL_ERROR_MSG,
+                Some(format!("{e}")),
+            )
+        };
+
+        let mut pending_l2_ranges = self
+            .context
+            .ledger
+            .get_pending_commitments_l2_range()
+            .map_err(map_db_err)?
+            .into_iter()
+            .map(|(start, end)| CommitmentL2Range {
+                start: U64::from(start.0),
+                end: U64::from(end.0),
+            })
+            .collect::<Vec<_>>();
+        pending_l2_ranges.sort_by_key(|range| range.start);
+
+        let last_committed_l2_height = self
+            .context
+            .ledger
+            .get_last_commitment_l2_height()
+            .map_err(map_db_err)?
+            .map_or(0, |height| height.0);
+
+        let state_diff = self.context.ledger.get_state_diff().map_err(map_db_err)?;
+
+        Ok(PendingCommitments {
+            pending_l2_ranges,
+            last_committed_l2_height: U64::from(last_committed_l2_height),
+            state_diff_size: U64::from(compressed_state_diff_size(&state_diff)),
+            state_diff_threshold: U64::from(STATE_DIFF_THRESHOLD),
+            min_soft_confirmations_per_commitment: U64::from(
+                self.context.min_soft_confirmations_per_commitment,
+            ),
+        })
+    }
+
+    fn get_commitment_history(
+        &self,
+        limit: Option<U64>,
+        offset: Option<U64>,
+    ) -> RpcResult<Vec<CommitmentSubmission>> {
+        debug!("Sequencer: sequencer_getCommitmentHistory");
+
+        let limit = limit
+            .map_or(MAX_COMMITMENT_HISTORY_LIMIT, |limit| limit.saturating_to())
+            .min(MAX_COMMITMENT_HISTORY_LIMIT) as usize;
+        let offset = offset.map_or(0, |offset| offset.saturating_to::<usize>());
+
+        let submissions = self
+            .context
+            .ledger
+            .get_commitment_submissions(limit, offset)
+            .map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(format!("{e}")),
+                )
+            })?;
+
+        Ok(submissions.into_iter().map(Into::into).collect())
+    }
+
+    fn get_commitment_by_l2_range(
+        &self,
+        l2_start_block_number: U64,
+        l2_end_block_number: U64,
+    ) -> RpcResult<Option<CommitmentSubmission>> {
+        debug!("Sequencer: sequencer_getCommitmentByL2Range");
+
+        let l2_range = (
+            SoftConfirmationNumber(l2_start_block_number.saturating_to()),
+            SoftConfirmationNumber(l2_end_block_number.saturating_to()),
+        );
+        let submission = self
+            .context
+            .ledger
+            .get_commitment_submission(&l2_range)
+            .map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(format!("{e}")),
+                )
+            })?;
+
+        Ok(submission.map(|submission| (l2_range, submission).into()))
+    }
+
+    fn get_l1_fee_rate(&self) -> RpcResult<L1FeeRate> {
+        debug!("Sequencer: sequencer_getL1FeeRate");
+
+        let (raw, smoothed) = self.context.l1_fee_rate_oracle.lock().rates();
+        Ok(L1FeeRate {
+            raw: raw.map(U128::from),
+            smoothed: smoothed.map(U128::from),
+        })
+    }
+
+    fn halt_production(&self, admin_token: String) -> RpcResult<ProductionState> {
+        self.check_admin_token(&admin_token)?;
+
+        debug!("Sequencer: sequencer_haltProduction");
+        self.set_production_state(ProductionState::Halted)
+    }
+
+    fn resume_production(&self, admin_token: String) -> RpcResult<ProductionState> {
+        self.check_admin_token(&admin_token)?;
+
+        debug!("Sequencer: sequencer_resumeProduction");
+        self.set_production_state(ProductionState::Running)
+    }
+
+    async fn rollback_to_height(&self, admin_token: String, l2_height: U64) -> RpcResult<U64> {
+        self.check_admin_token(&admin_token)?;
+        if !matches!(self.context.network, Network::Devnet | Network::Nightly) {
+            return Err(ErrorObject::from(ErrorCode::MethodNotFound).to_owned());
+        }
+        self.ensure_not_shutting_down()?;
+
+        debug!("Sequencer: sequencer_rollbackToHeight({})", l2_height);
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.context
+            .l2_rollback_tx
+            .unbounded_send((l2_height.to(), reply_tx))
+            .map_err(|_| shutting_down_error())?;
+
+        // Dropped without a reply when the sequencer stops
+        let recycled_count = reply_rx
+            .await
+            .map_err(|_| shutting_down_error())?
+            .map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    format!("Rollback failed: {e}"),
+                    None::<String>,
+                )
+            })?;
+        Ok(U64::from(recycled_count))
+    }
+}
+
+fn shutting_down_error() -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        INTERNAL_ERROR_CODE,
+        "Sequencer is shutting down",
+        None::<String>,
+    )
+}
+
+/// Renders the transactions the same way as `eth_getTransactionByHash`
+fn group_by_sender(
+    transactions: Vec<Arc<ValidPoolTransaction<EthPooledTransaction>>>,
+) -> TxpoolSubpoolContent {
+    let mut content = TxpoolSubpoolContent::new();
+    for tx in transactions {
+        let rpc_tx: RpcTransaction<AnyNetwork> =
+            from_recovered::<EthTxBuilder>(tx.to_recovered_transaction());
+        content
+            .entry(tx.sender())
+            .or_default()
+            .insert(tx.nonce().to_string(), rpc_tx);
+    }
+    content
 }
 
 pub fn create_rpc_module<
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -6,29 +6,32 @@ use std::vec;
 
 use alloy_eips::eip2718::Encodable2718;
 use alloy_primitives::{Address, Bytes, TxHash};
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, ensure};
 use backoff::future::retry as retry_backoff;
 use backoff::ExponentialBackoffBuilder;
+use citrea_common::db_tools::ensure_sequencer_can_roll_back;
 use citrea_common::tasks::manager::TaskManager;
-use citrea_common::utils::soft_confirmation_to_receipt;
-use citrea_common::{RollupPublicKeys, RpcConfig, SequencerConfig};
-use citrea_evm::{CallMessage, RlpEvmTransaction, MIN_TRANSACTION_GAS};
+use citrea_common::utils::{create_shutdown_signal, soft_confirmation_to_receipt, state_diff_size};
+use citrea_common::{events, RollupPublicKeys, RpcConfig, SequencerConfig, SequencerKeySchedule};
+use citrea_evm::{
+    CallMessage, RlpEvmTransaction, BROTLI_COMPRESSION_PERCENTAGE, MIN_TRANSACTION_GAS,
+    SYSTEM_SIGNER,
+};
 use citrea_primitives::basefee::calculate_next_block_base_fee;
 use citrea_primitives::types::SoftConfirmationHash;
 use citrea_stf::runtime::Runtime;
 use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use futures::StreamExt;
-use jsonrpsee::server::{BatchRequestConfig, RpcServiceBuilder, ServerBuilder};
 use jsonrpsee::RpcModule;
 use parking_lot::Mutex;
 use reth_execution_types::ChangedAccount;
+use reth_primitives::TransactionSigned;
 use reth_provider::{AccountReader, BlockReaderIdExt};
 use reth_transaction_pool::{
-    BestTransactions, BestTransactionsAttributes, EthPooledTransaction, PoolTransaction,
-    ValidPoolTransaction,
+    AllPoolTransactions, EthPooledTransaction, PoolTransaction, ValidPoolTransaction,
 };
-use sov_accounts::Accounts;
 use sov_accounts::Response::{AccountEmpty, AccountExists};
+use sov_accounts::{AccountId, Accounts};
 use sov_db::ledger_db::SequencerLedgerOps;
 use sov_db::schema::types::{SlotNumber, SoftConfirmationNumber};
 use sov_modules_api::hooks::HookSoftConfirmationInfo;
@@ -43,22 +46,25 @@ use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec};
 use sov_rollup_interface::fork::ForkManager;
 use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::stf::StateTransitionFunction;
+use sov_rollup_interface::Network;
+use sov_state::storage::NativeStorage;
 use sov_state::ProverStorage;
 use sov_stf_runner::InitVariant;
-use tokio::signal;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, watch, Notify};
 use tokio::time::sleep;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, instrument, trace, warn};
 use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::layer::SubscriberExt;
 
-use crate::commitment::CommitmentService;
+use crate::commitment::{CommitmentConfirmationTracker, CommitmentService, CommitmentWorker};
 use crate::db_provider::DbProvider;
-use crate::deposit_data_mempool::DepositDataMempool;
+use crate::deposit_data_mempool::{deposit_id, fetch_block_deposits, DepositDataMempool};
+use crate::l1_fee_rate::L1FeeRateOracle;
 use crate::mempool::CitreaMempool;
 use crate::metrics::SEQUENCER_METRICS;
-use crate::rpc::{create_rpc_module, RpcContext};
+use crate::rpc::{create_rpc_module, ProductionState, RollbackRequest, RpcContext};
+use crate::timestamp::next_soft_confirmation_timestamp;
 use crate::utils::recover_raw_transaction;
 
 type StateRoot<C, Da, RT> = <StfBlueprint<C, Da, RT> as StateTransitionFunction<Da>>::StateRoot;
@@ -80,23 +86,33 @@ where
     da_service: Arc<Da>,
     mempool: Arc<CitreaMempool<C>>,
     sov_tx_signer_priv_key: C::PrivateKey,
+    /// Signing keys of the scheduled key rotations, swapped with the active one on rotation
+    rotated_priv_keys: Vec<C::PrivateKey>,
     l2_force_block_tx: UnboundedSender<()>,
     l2_force_block_rx: UnboundedReceiver<()>,
+    l2_rollback_tx: UnboundedSender<RollbackRequest>,
+    l2_rollback_rx: UnboundedReceiver<RollbackRequest>,
+    production_state_tx: Arc<watch::Sender<ProductionState>>,
     db_provider: DbProvider<C>,
     storage: C::Storage,
     ledger_db: DB,
     config: SequencerConfig,
     stf: StfBlueprint<C, Da::Spec, RT>,
     deposit_mempool: Arc<Mutex<DepositDataMempool>>,
+    l1_fee_rate_oracle: Arc<Mutex<L1FeeRateOracle>>,
     storage_manager: ProverStorageManager<Da::Spec>,
     state_root: StateRoot<C, Da::Spec, RT>,
     batch_hash: SoftConfirmationHash,
-    sequencer_pub_key: Vec<u8>,
+    sequencer_pub_keys: SequencerKeySchedule,
     sequencer_da_pub_key: Vec<u8>,
     rpc_config: RpcConfig,
     fork_manager: ForkManager<'static>,
     soft_confirmation_tx: broadcast::Sender<u64>,
+    network: Network,
     task_manager: TaskManager<()>,
+    // Cancelled before `task_manager` on shutdown, so that the commitments are drained
+    // while the DA queue is still running
+    commitment_task_manager: TaskManager<()>,
 }
 
 enum L2BlockMode {
@@ -124,9 +140,12 @@ where
         rpc_config: RpcConfig,
         fork_manager: ForkManager<'static>,
         soft_confirmation_tx: broadcast::Sender<u64>,
+        network: Network,
         task_manager: TaskManager<()>,
     ) -> anyhow::Result<Self> {
         let (l2_force_block_tx, l2_force_block_rx) = unbounded();
+        let (l2_rollback_tx, l2_rollback_rx) = unbounded();
+        let (production_state_tx, _) = watch::channel(ProductionState::Running);
 
         let (prev_state_root, prev_batch_hash) = match init_variant {
             InitVariant::Initialized((state_root, batch_hash)) => {
@@ -155,29 +174,49 @@ where
 
         let deposit_mempool = Arc::new(Mutex::new(DepositDataMempool::new()));
 
+        let head_l1_fee_rate = ledger_db
+            .get_head_soft_confirmation()?
+            .map(|(_, soft_confirmation)| soft_confirmation.l1_fee_rate);
+        let l1_fee_rate_oracle = Arc::new(Mutex::new(L1FeeRateOracle::new(
+            config.l1_fee_rate_smoothing.clone(),
+            head_l1_fee_rate,
+        )?));
+
         let sov_tx_signer_priv_key = C::PrivateKey::try_from(&hex::decode(&config.private_key)?)?;
+        let rotated_priv_keys = config
+            .rotated_private_keys
+            .iter()
+            .map(|key| Ok(C::PrivateKey::try_from(&hex::decode(key)?)?))
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
         Ok(Self {
             da_service,
             mempool: Arc::new(pool),
             sov_tx_signer_priv_key,
+            rotated_priv_keys,
             l2_force_block_tx,
             l2_force_block_rx,
+            l2_rollback_tx,
+            l2_rollback_rx,
+            production_state_tx: Arc::new(production_state_tx),
             db_provider,
             storage,
             ledger_db,
             config,
             stf,
             deposit_mempool,
+            l1_fee_rate_oracle,
             storage_manager,
             state_root: prev_state_root,
             batch_hash: prev_batch_hash,
-            sequencer_pub_key: public_keys.sequencer_public_key,
+            sequencer_pub_keys: public_keys.sequencer_key_schedule(),
             sequencer_da_pub_key: public_keys.sequencer_da_pub_key,
             rpc_config,
             fork_manager,
             soft_confirmation_tx,
+            network,
             task_manager,
+            commitment_task_manager: TaskManager::default(),
         })
     }
 
@@ -188,70 +227,13 @@ where
     ) -> anyhow::Result<()> {
         let methods = self.register_rpc_methods(methods).await?;
 
-        let listen_address = SocketAddr::new(
-            self.rpc_config
-                .bind_host
-                .parse()
-                .map_err(|e| anyhow!("Failed to parse bind host: {}", e))?,
-            self.rpc_config.bind_port,
-        );
-
-        let max_connections = self.rpc_config.max_connections;
-        let max_subscriptions_per_connection = self.rpc_config.max_subscriptions_per_connection;
-        let max_request_body_size = self.rpc_config.max_request_body_size;
-        let max_response_body_size = self.rpc_config.max_response_body_size;
-        let batch_requests_limit = self.rpc_config.batch_requests_limit;
-
-        let middleware = tower::ServiceBuilder::new().layer(citrea_common::rpc::get_cors_layer());
-        //  .layer(citrea_common::rpc::get_healthcheck_proxy_layer());
-        let rpc_middleware = RpcServiceBuilder::new().layer_fn(citrea_common::rpc::Logger);
-
-        self.task_manager.spawn(|cancellation_token| async move {
-            let server = ServerBuilder::default()
-                .max_connections(max_connections)
-                .max_subscriptions_per_connection(max_subscriptions_per_connection)
-                .max_request_body_size(max_request_body_size)
-                .max_response_body_size(max_response_body_size)
-                .set_batch_request_config(BatchRequestConfig::Limit(batch_requests_limit))
-                .set_http_middleware(middleware)
-                .set_rpc_middleware(rpc_middleware)
-                .build([listen_address].as_ref())
-                .await;
-
-            match server {
-                Ok(server) => {
-                    let bound_address = match server.local_addr() {
-                        Ok(address) => address,
-                        Err(e) => {
-                            error!("{}", e);
-                            return;
-                        }
-                    };
-                    if let Some(channel) = channel {
-                        if let Err(e) = channel.send(bound_address) {
-                            error!("Could not send bound_address {}: {}", bound_address, e);
-                            return;
-                        }
-                    }
-                    info!("Starting RPC server at {} ", &bound_address);
-
-                    let _server_handle = server.start(methods);
-                    cancellation_token.cancelled().await;
-                }
-                Err(e) => {
-                    error!("Could not start RPC server: {}", e);
-                }
-            }
-        });
-        Ok(())
+        citrea_common::rpc::start_server(&self.rpc_config, methods, channel, &mut self.task_manager)
     }
 
     #[allow(clippy::too_many_arguments)]
     async fn dry_run_transactions(
         &mut self,
-        transactions: Box<
-            dyn BestTransactions<Item = Arc<ValidPoolTransaction<EthPooledTransaction>>>,
-        >,
+        transactions: Vec<Arc<ValidPoolTransaction<EthPooledTransaction>>>,
         pub_key: &[u8],
         prestate: ProverStorage<SnapshotManager>,
         da_block_header: <<Da as DaService>::Spec as DaSpec>::BlockHeader,
@@ -282,6 +264,19 @@ where
                             let mut all_txs = vec![];
                             let mut l1_fee_failed_txs = vec![];
 
+                            // deposit data is executed as system transactions, so leave room for it
+                            let deposit_data_size = compressed_size(
+                                soft_confirmation_info
+                                    .deposit_data
+                                    .iter()
+                                    .map(Vec::len)
+                                    .sum(),
+                            );
+                            let max_txs_size = (self.config.max_soft_confirmation_size_bytes
+                                as usize)
+                                .saturating_sub(deposit_data_size);
+                            let mut txs_size = 0;
+
                             for evm_tx in transactions {
                                 let mut buf = vec![];
                                 evm_tx
@@ -290,6 +285,13 @@ where
                                     .encode_2718(&mut buf);
                                 let rlp_tx = RlpEvmTransaction { rlp: buf };
 
+                                // a transaction is never split, if it does not fit
+                                // it is left in the mempool for the next blocks
+                                let tx_size = compressed_size(rlp_tx.rlp.len());
+                                if txs_size + tx_size > max_txs_size {
+                                    continue;
+                                }
+
                                 let call_txs = CallMessage {
                                     txs: vec![rlp_tx.clone()],
                                 };
@@ -323,6 +325,7 @@ where
                                         sov_rollup_interface::stf::StateTransitionError::HookError(soft_confirmation_hook_error) => panic!("Hook error: {:?}", soft_confirmation_hook_error),
                                         sov_rollup_interface::stf::StateTransitionError::ModuleCallError(soft_confirmation_module_call_error) => match soft_confirmation_module_call_error {
                                             // if we are exceeding block gas limit with a transaction
+                                            // (less the system gas reserve if it is active)
                                             // we should inspect the gas usage and act accordingly
                                             // if there is room for another transaction
                                             // keep trying txs
@@ -339,6 +342,9 @@ where
                                                 continue;
                                                }
                                             },
+                                            // the system transactions of the block already used more than the reserve
+                                            // so no user transaction can be included
+                                            sov_modules_api::SoftConfirmationModuleCallError::EvmSystemGasExceedsReserve { .. } => break,
                                             // we configure mempool to never accept blob transactions
                                             // to mitigate potential bugs in reth-mempool we should look into continue instead of panicking here
                                             sov_modules_api::SoftConfirmationModuleCallError::EvmTxTypeNotSupported(_) => panic!("got unsupported tx type"),
@@ -361,6 +367,10 @@ where
                                             sov_modules_api::SoftConfirmationModuleCallError::EvmTxNotSerializable => panic!("Fed a non-serializable tx"),
                                             // we don't call the rule enforcer in the sequencer -- yet at least
                                             sov_modules_api::SoftConfirmationModuleCallError::RuleEnforcerUnauthorized => unreachable!(),
+                                            // neither do we call the accounts module, only EVM txs come from the mempool
+                                            sov_modules_api::SoftConfirmationModuleCallError::AccountsAccountNotFound
+                                            | sov_modules_api::SoftConfirmationModuleCallError::AccountsPublicKeyAlreadyBound
+                                            | sov_modules_api::SoftConfirmationModuleCallError::AccountsInvalidPublicKeySignature => unreachable!(),
                                         },
                                     },
                                 };
@@ -368,6 +378,7 @@ where
                                 // if no errors
                                 // we can include the transaction in the block
                                 working_set_to_discard = working_set.checkpoint().to_revertable();
+                                txs_size += tx_size;
                                 all_txs.push(rlp_tx);
                             }
                             SEQUENCER_METRICS.dry_run_execution.record(
@@ -398,32 +409,42 @@ where
     async fn produce_l2_block(
         &mut self,
         da_block: <Da as DaService>::FilteredBlock,
-        l1_fee_rate: u128,
+        raw_l1_fee_rate: u128,
         l2_block_mode: L2BlockMode,
     ) -> anyhow::Result<(u64, u64, StateDiff)> {
         let start = Instant::now();
+        let l1_fee_rate = self
+            .l1_fee_rate_oracle
+            .lock()
+            .next_fee_rate(raw_l1_fee_rate);
         let da_height = da_block.header().height();
-        let (l2_height, l1_height) = match self
+        let (l2_height, l1_height, parent_timestamp) = match self
             .ledger_db
             .get_head_soft_confirmation()
             .map_err(|e| anyhow!("Failed to get head soft confirmation: {}", e))?
         {
-            Some((l2_height, sb)) => (l2_height.0 + 1, sb.da_slot_height),
-            None => (1, da_height),
+            Some((l2_height, sb)) => (l2_height.0 + 1, sb.da_slot_height, Some(sb.timestamp)),
+            None => (1, da_height, None),
         };
         anyhow::ensure!(
             l1_height == da_height || l1_height + 1 == da_height,
             "Sequencer: L1 height mismatch, expected {da_height} (or {da_height}-1), got {l1_height}",
         );
 
-        let timestamp = chrono::Local::now().timestamp() as u64;
+        let timestamp = next_soft_confirmation_timestamp(
+            chrono::Local::now().timestamp() as u64,
+            parent_timestamp,
+            self.config.max_timestamp_drift_secs,
+        )?;
+        self.use_signing_key_at(l2_height)?;
         let pub_key = borsh::to_vec(&self.sov_tx_signer_priv_key.pub_key())
             .map_err(Into::<anyhow::Error>::into)?;
 
-        let deposit_data = self
-            .deposit_mempool
-            .lock()
-            .fetch_deposits(self.config.deposit_mempool_fetch_limit);
+        let deposit_data = fetch_block_deposits(
+            &mut *self.deposit_mempool.lock(),
+            &self.ledger_db,
+            self.config.deposit_mempool_fetch_limit,
+        );
 
         let active_fork_spec = self.fork_manager.active_fork().spec_id;
 
@@ -449,6 +470,7 @@ where
             hex::encode(da_block.header().hash().into())
         );
 
+        self.evict_unmet_conditional_txs(l2_height);
         let evm_txs = self.get_best_transactions()?;
 
         // Dry running transactions would basically allow for figuring out a list of
@@ -532,7 +554,7 @@ where
                 self.stf.end_soft_confirmation(
                     active_fork_spec,
                     self.state_root.as_ref().to_vec(),
-                    self.sequencer_pub_key.as_ref(),
+                    self.sequencer_pub_keys.key_at(l2_height),
                     &mut signed_soft_confirmation,
                     &mut working_set,
                 )?;
@@ -577,6 +599,20 @@ where
                     receipt,
                     Some(tx_bodies),
                 )?;
+                // so that deposits provided again are not included twice, even after a restart
+                let deposit_ids = deposit_data
+                    .iter()
+                    .map(|deposit| deposit_id(deposit))
+                    .collect::<Vec<_>>();
+                self.ledger_db
+                    .put_included_deposit_ids(&deposit_ids, SoftConfirmationNumber(l2_height))?;
+                self.ledger_db.put_state_diff_size(
+                    SoftConfirmationNumber(l2_height),
+                    state_diff_size(&soft_confirmation_result.state_diff),
+                )?;
+                self.l1_fee_rate_oracle
+                    .lock()
+                    .record(raw_l1_fee_rate, l1_fee_rate);
 
                 // connect L1 and L2 height
                 self.ledger_db.extend_l2_range_of_l1_slot(
@@ -588,10 +624,13 @@ where
                 // the new fork on the next block
                 self.fork_manager.register_block(l2_height)?;
 
-                let l1_height = da_block.header().height();
-                info!(
-                    "New block #{}, DA #{}, Tx count: #{}",
-                    l2_height, l1_height, evm_txs_count,
+                events::l2_block_produced(
+                    l2_height,
+                    soft_confirmation_hash,
+                    da_block.header().height(),
+                    evm_txs_count,
+                    self.config.tx_ordering_policy,
+                    start.elapsed(),
                 );
 
                 self.state_root = next_state_root;
@@ -614,6 +653,9 @@ where
                 if let Err(e) = self.ledger_db.remove_mempool_txs(txs) {
                     warn!("Failed to remove txs from mempool: {:?}", e);
                 }
+                if let Err(e) = self.remove_evicted_mempool_txs() {
+                    warn!("Failed to remove evicted txs from mempool: {:?}", e);
+                }
 
                 SEQUENCER_METRICS.block_production_execution.record(
                     Instant::now()
@@ -621,6 +663,9 @@ where
                         .as_secs_f64(),
                 );
                 SEQUENCER_METRICS.current_l2_block.set(l2_height as f64);
+                SEQUENCER_METRICS
+                    .soft_confirmation_txs
+                    .record(evm_txs_count as f64);
 
                 Ok((
                     l2_height,
@@ -641,8 +686,18 @@ where
         }
     }
 
-    #[instrument(level = "trace", skip(self), err, ret)]
+    /// Runs the sequencer until a SIGTERM or SIGINT is received.
     pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        let shutdown_signal = create_shutdown_signal().await;
+        self.run_until_shutdown(shutdown_signal).await
+    }
+
+    /// Runs the sequencer until a message is received on `shutdown_signal`.
+    #[instrument(level = "trace", skip_all, err, ret)]
+    pub async fn run_until_shutdown(
+        &mut self,
+        mut shutdown_signal: mpsc::Receiver<()>,
+    ) -> Result<(), anyhow::Error> {
         // TODO: hotfix for mock da
         self.da_service
             .get_block_at(1)
@@ -680,28 +735,45 @@ where
         let (da_height_update_tx, mut da_height_update_rx) = mpsc::channel(1);
         let (da_commitment_tx, da_commitment_rx) = unbounded::<(u64, StateDiff)>();
 
-        let mut commitment_service = CommitmentService::new(
+        let commitment_notify = Arc::new(Notify::new());
+        let commitment_service = CommitmentService::new(
             self.ledger_db.clone(),
-            self.da_service.clone(),
-            self.sequencer_da_pub_key.clone(),
             self.config.min_soft_confirmations_per_commitment,
+            self.sequencer_pub_keys.clone(),
             da_commitment_rx,
+            commitment_notify.clone(),
+        );
+        self.commitment_task_manager
+            .spawn("commitment_service", |cancellation_token| {
+                commitment_service.run(cancellation_token)
+            });
+
+        let commitment_worker = CommitmentWorker::new(
+            self.ledger_db.clone(),
+            self.da_service.clone(),
+            self.sequencer_da_pub_key.clone(),
+            commitment_notify,
+        );
+        self.commitment_task_manager
+            .spawn("commitment_worker", |cancellation_token| {
+                commitment_worker.run(cancellation_token)
+            });
+
+        let confirmation_tracker = CommitmentConfirmationTracker::new(
+            self.ledger_db.clone(),
+            self.da_service.clone(),
+            self.sequencer_da_pub_key.clone(),
         );
-        if self.batch_hash != [0; 32] {
-            // Resubmit if there were pending commitments on restart, skip it on first init
-            commitment_service.resubmit_pending_commitments().await?;
-        }
         self.task_manager
-            .spawn(|cancellation_token| commitment_service.run(cancellation_token));
-
-        self.task_manager.spawn(|cancellation_token| {
-            da_block_monitor(
-                self.da_service.clone(),
-                da_height_update_tx,
-                self.config.da_update_interval_ms,
-                cancellation_token,
-            )
-        });
+            .spawn("da_block_monitor", |cancellation_token| {
+                da_block_monitor(
+                    self.da_service.clone(),
+                    da_height_update_tx,
+                    confirmation_tracker,
+                    self.config.da_update_interval_ms,
+                    cancellation_token,
+                )
+            });
 
         let target_block_time = Duration::from_millis(self.config.block_production_interval_ms);
 
@@ -714,7 +786,19 @@ where
         let mut block_production_tick = tokio::time::interval(target_block_time);
         block_production_tick.tick().await;
 
+        let mut mempool_expiry_tick = tokio::time::interval(self.mempool.expiry_sweep_interval());
+        mempool_expiry_tick.tick().await;
+
+        let mut production_state_rx = self.production_state_tx.subscribe();
+        let mut critical_task_failures = self.task_manager.critical_task_failures();
+        let mut critical_commitment_task_failures =
+            self.commitment_task_manager.critical_task_failures();
+
         loop {
+            // While halted, blocks are not produced but DA updates are still tracked so that
+            // missed DA blocks are filled once production resumes.
+            let halted = *production_state_rx.borrow() != ProductionState::Running;
+
             tokio::select! {
                 // Receive updates from DA layer worker.
                 l1_data = da_height_update_rx.recv() => {
@@ -733,7 +817,13 @@ where
                 // If sequencer is in test mode, it will build a block every time it receives a message
                 // The RPC from which the sender can be called is only registered for test mode. This means
                 // that evey though we check the receiver here, it'll never be "ready" to be consumed unless in test mode.
-                _ = self.l2_force_block_rx.next(), if self.config.test_mode => {
+                _ = production_state_rx.changed() => {
+                    if *production_state_rx.borrow_and_update() == ProductionState::Running {
+                        // Do not burst the ticks missed while halted
+                        block_production_tick.reset();
+                    }
+                },
+                _ = self.l2_force_block_rx.next(), if self.config.test_mode && !halted => {
                     if missed_da_blocks_count > 0 {
                         if let Err(e) = self.process_missed_da_blocks(missed_da_blocks_count, last_used_l1_height, l1_fee_rate).await {
                             error!("Sequencer error: {}", e);
@@ -758,7 +848,7 @@ where
                     }
                 },
                 // If sequencer is in production mode, it will build a block every 2 seconds
-                _ = block_production_tick.tick(), if !self.config.test_mode => {
+                _ = block_production_tick.tick(), if !self.config.test_mode && !halted => {
                     // By default, we produce a non-empty block IFF we were caught up all the way to
                     // last_finalized_block. If there are missed DA blocks, we start producing
                     // empty blocks at ~2 second rate, 1 L2 block per respective missed DA block
@@ -789,20 +879,105 @@ where
                         }
                     };
                 },
-                _ = signal::ctrl_c() => {
-                    info!("Shutting down sequencer");
-                    self.task_manager.abort().await;
-                    return Ok(());
-                }
+                // Only sent on devnet and nightly, see `sequencer_rollbackToHeight`
+                Some((l2_height, reply)) = self.l2_rollback_rx.next() => {
+                    let result = self.rollback_to_l2_height(l2_height).await;
+                    if result.is_ok() {
+                        // The next blocks continue from the L1 block of the new head
+                        match self.ledger_db.get_head_soft_confirmation() {
+                            Ok(head) => {
+                                last_used_l1_height = head.map_or(last_finalized_height, |(_, sb)| sb.da_slot_height);
+                                missed_da_blocks_count = self.da_blocks_missed(last_finalized_height, last_used_l1_height);
+                            }
+                            Err(e) => error!("Sequencer: Failed to get head soft confirmation after rollback: {}", e),
+                        }
+                    }
+                    let _ = reply.send(result);
+                },
+                _ = mempool_expiry_tick.tick() => {
+                    if let Err(e) = self.evict_expired_mempool_txs() {
+                        warn!("Failed to evict expired txs from mempool: {:?}", e);
+                    }
+                },
+                // Blocks are produced inside the other branches, so the signal is only handled
+                // in between blocks, never while one is half-built.
+                // A critical task died, the node can not keep running without it
+                failure = critical_task_failures.recv() => {
+                    self.shutdown().await?;
+                    return Err(failure.into());
+                },
+                failure = critical_commitment_task_failures.recv() => {
+                    self.shutdown().await?;
+                    return Err(failure.into());
+                },
+                Some(_) = shutdown_signal.recv() => return self.shutdown().await,
             }
         }
     }
 
+    async fn shutdown(&mut self) -> anyhow::Result<()> {
+        info!("Shutting down sequencer");
+        // Stop accepting transactions over RPC, this state can not be changed afterwards
+        self.production_state_tx
+            .send_replace(ProductionState::ShuttingDown);
+
+        if let Err(e) = self.persist_mempool() {
+            warn!("Sequencer: Failed to persist mempool on shutdown: {:?}", e);
+        }
+
+        // The commitment service drains the last soft confirmations first,
+        // then the DA queue backs up what it has left
+        self.commitment_task_manager.abort().await;
+        self.task_manager.abort().await;
+        Ok(())
+    }
+
+    /// Writes all transactions in the mempool to the ledger, including the ones
+    /// that could not be stored when they were received, to be restored on the next start.
+    /// Conditional transactions are not persisted, as their conditions would be lost.
+    fn persist_mempool(&self) -> Result<(), anyhow::Error> {
+        let AllPoolTransactions { pending, queued } = self.mempool.all_transactions();
+        let txs = pending
+            .into_iter()
+            .chain(queued)
+            .filter(|tx| !self.mempool.is_conditional(tx.hash()))
+            .collect::<Vec<_>>();
+        let tx_count = txs.len();
+        for tx in txs {
+            let mut rlp_encoded_tx = vec![];
+            tx.to_recovered_transaction()
+                .into_signed()
+                .encode_2718(&mut rlp_encoded_tx);
+            self.ledger_db
+                .insert_mempool_tx(tx.hash().to_vec(), rlp_encoded_tx)?;
+        }
+
+        info!("Sequencer: Persisted {} mempool txs", tx_count);
+        Ok(())
+    }
+
+    /// Evicts the conditional transactions whose conditions do not hold for the block at
+    /// `l2_height`, checked on the state the block is built on.
+    fn evict_unmet_conditional_txs(&self, l2_height: u64) {
+        let unmet = self
+            .mempool
+            .remove_unmet_conditional_transactions(l2_height, |address, index| {
+                self.db_provider.storage_value(address, index)
+            });
+        if unmet.is_empty() {
+            return;
+        }
+
+        for (tx_hash, reason) in &unmet {
+            debug!("Evicting conditional mempool tx {}: {}", tx_hash, reason);
+        }
+        SEQUENCER_METRICS.mempool_txs.set(self.mempool.len() as f64);
+    }
+
+    /// Returns the mempool transactions for the next block in the order of the configured policy
     fn get_best_transactions(
         &self,
-    ) -> anyhow::Result<
-        Box<dyn BestTransactions<Item = Arc<ValidPoolTransaction<EthPooledTransaction>>>>,
-    > {
+    ) -> anyhow::Result<Vec<Arc<ValidPoolTransaction<EthPooledTransaction>>>> {
         let cfg = self.db_provider.cfg();
         let latest_header = self
             .db_provider
@@ -820,11 +995,9 @@ where
             cfg.base_fee_params,
         ) as u64;
 
-        let best_txs_with_base_fee = self
+        Ok(self
             .mempool
-            .best_transactions_with_attributes(BestTransactionsAttributes::base_fee(base_fee));
-
-        Ok(best_txs_with_base_fee)
+            .best_transactions(base_fee, self.config.tx_ordering_policy))
     }
 
     /// Signs batch of messages with sovereign priv key turns them into a sov blob
@@ -923,12 +1096,47 @@ where
         ))
     }
 
+    /// Switches the signing key to the one whose public key is scheduled for the given L2 height
+    fn use_signing_key_at(&mut self, l2_height: u64) -> anyhow::Result<()> {
+        let active_pub_key = self.sequencer_pub_keys.key_at(l2_height);
+        if borsh::to_vec(&self.sov_tx_signer_priv_key.pub_key())? == active_pub_key {
+            return Ok(());
+        }
+
+        let index = self
+            .rotated_priv_keys
+            .iter()
+            .position(|key| {
+                borsh::to_vec(&key.pub_key()).is_ok_and(|pub_key| pub_key == active_pub_key)
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "Sequencer: No private key for the public key {} active at L2 height {}",
+                    hex::encode(active_pub_key),
+                    l2_height
+                )
+            })?;
+        std::mem::swap(
+            &mut self.sov_tx_signer_priv_key,
+            &mut self.rotated_priv_keys[index],
+        );
+        info!(
+            "Sequencer: Signing with public key {} from L2 height {}",
+            hex::encode(active_pub_key),
+            l2_height
+        );
+        Ok(())
+    }
+
     /// Fetches nonce from state
     fn get_nonce(&self, working_set: &mut WorkingSet<C::Storage>) -> anyhow::Result<u64> {
         let accounts = Accounts::<C>::default();
 
         match accounts
-            .get_account(self.sov_tx_signer_priv_key.pub_key(), working_set)
+            .get_account(
+                AccountId::PublicKey(self.sov_tx_signer_priv_key.pub_key()),
+                working_set,
+            )
             .map_err(|e| anyhow!("Sequencer: Failed to get sov-account: {}", e))?
         {
             AccountExists { addr: _, nonce } => Ok(nonce),
@@ -943,10 +1151,18 @@ where
         RpcContext {
             mempool: self.mempool.clone(),
             deposit_mempool: self.deposit_mempool.clone(),
+            l1_fee_rate_oracle: self.l1_fee_rate_oracle.clone(),
             l2_force_block_tx,
+            l2_rollback_tx: self.l2_rollback_tx.clone(),
+            production_state_tx: self.production_state_tx.clone(),
             storage: self.storage.clone(),
             ledger: self.ledger_db.clone(),
             test_mode: self.config.test_mode,
+            network: self.network,
+            admin_token: self.rpc_config.admin_token.clone(),
+            min_soft_confirmations_per_commitment: self
+                .config
+                .min_soft_confirmations_per_commitment,
         }
     }
 
@@ -961,14 +1177,242 @@ where
         Ok(rpc_methods)
     }
 
+    /// Re-validates the transactions persisted in the ledger and adds them back to the mempool.
+    /// Transactions that are no longer valid, or exceed the account slots, are dropped from the ledger.
     pub async fn restore_mempool(&self) -> Result<(), anyhow::Error> {
         let mempool_txs = self.ledger_db.get_mempool_txs()?;
-        for (_, tx) in mempool_txs {
-            let recovered = recover_raw_transaction(Bytes::from(tx.as_slice().to_vec()))?;
-            let pooled_tx = EthPooledTransaction::from_pooled(recovered);
 
-            let _ = self.mempool.add_external_transaction(pooled_tx).await?;
+        let mut dropped_txs = vec![];
+        let mut pooled_txs = Vec::with_capacity(mempool_txs.len());
+        for (tx_hash, tx) in mempool_txs {
+            match recover_raw_transaction(Bytes::from(tx)) {
+                Ok(recovered) => pooled_txs.push(EthPooledTransaction::from_pooled(recovered)),
+                Err(e) => {
+                    debug!(
+                        "Dropping undecodable mempool tx 0x{}: {:?}",
+                        hex::encode(&tx_hash),
+                        e
+                    );
+                    dropped_txs.push(tx_hash);
+                }
+            }
+        }
+
+        // Lower nonces of a sender must take the account slots first
+        pooled_txs.sort_by_key(|tx| (tx.sender(), tx.nonce()));
+
+        let max_account_slots = self.config.mempool_conf.max_account_slots as usize;
+        let mut account_slots: HashMap<Address, usize> = HashMap::new();
+        let mut restored_count = 0;
+        for pooled_tx in pooled_txs {
+            let tx_hash = *pooled_tx.hash();
+            let used_slots = account_slots.entry(pooled_tx.sender()).or_default();
+            if *used_slots >= max_account_slots {
+                debug!("Dropping mempool tx {}: account slots exceeded", tx_hash);
+                dropped_txs.push(tx_hash.to_vec());
+                continue;
+            }
+
+            match self.mempool.add_external_transaction(pooled_tx).await {
+                Ok(_) => {
+                    *used_slots += 1;
+                    restored_count += 1;
+                }
+                Err(e) => {
+                    // Stale nonce, insufficient balance etc.
+                    debug!("Dropping mempool tx {}: {:?}", tx_hash, e);
+                    dropped_txs.push(tx_hash.to_vec());
+                }
+            }
+        }
+
+        info!(
+            "Sequencer: Restored {} mempool txs, dropped {}",
+            restored_count,
+            dropped_txs.len()
+        );
+        SEQUENCER_METRICS.mempool_txs.set(self.mempool.len() as f64);
+
+        if !dropped_txs.is_empty() {
+            self.ledger_db.remove_mempool_txs(dropped_txs)?;
         }
+
+        Ok(())
+    }
+
+    /// Rolls back the soft confirmations above `l2_height`, none of which may be covered by a commitment.
+    /// The state, accessory state and ledger are truncated to the height, and the transactions and
+    /// deposits of the dropped blocks are put back into the mempools to be included in the next blocks.
+    /// Returns the number of transactions put back into the mempool.
+    async fn rollback_to_l2_height(&mut self, l2_height: u64) -> anyhow::Result<usize> {
+        let head_l2_height = self
+            .ledger_db
+            .get_head_soft_confirmation_height()?
+            .unwrap_or(0);
+        ensure!(
+            l2_height <= head_l2_height,
+            "Cannot roll back to L2 height {} above the head L2 height {}",
+            l2_height,
+            head_l2_height
+        );
+        if l2_height == head_l2_height {
+            return Ok(0);
+        }
+        ensure_sequencer_can_roll_back(&self.ledger_db, l2_height)?;
+        // The target is checked before anything is truncated
+        ensure!(
+            self.storage.get_root_hash_option(l2_height + 1)?.is_some(),
+            "Cannot roll back to L2 height {}, its state is not available anymore",
+            l2_height
+        );
+
+        // Read before the blocks are rolled back
+        let mut working_set = WorkingSet::new(self.storage.clone());
+        let dropped_txs = (l2_height + 1..=head_l2_height)
+            .flat_map(|block_number| {
+                self.db_provider
+                    .evm
+                    .block_transactions(block_number, &mut working_set)
+            })
+            .collect::<Vec<_>>();
+        let dropped_deposits = self
+            .ledger_db
+            .get_soft_confirmation_range(
+                &(SoftConfirmationNumber(l2_height + 1)..=SoftConfirmationNumber(head_l2_height)),
+            )?
+            .into_iter()
+            .flat_map(|soft_confirmation| soft_confirmation.deposit_data)
+            .collect::<Vec<_>>();
+
+        info!(
+            "Sequencer: Rolling back from L2 height {} to {}",
+            head_l2_height, l2_height
+        );
+
+        // Storage before the ledger, so a failed rollback can be retried from the same head
+        self.storage_manager.rollback_l2_to(l2_height)?;
+        self.ledger_db.rollback_to_l2_height(l2_height)?;
+
+        // Both the EVM head in the state and the sealed blocks in the accessory state are back
+        // at the new head, otherwise the next block would be built on an inconsistent parent
+        // The storage and the ledger are already truncated at this point, so the node can't go on
+        let mut working_set = WorkingSet::new(self.storage.clone());
+        if !self.db_provider.evm.is_head_at(l2_height, &mut working_set) {
+            panic!(
+                "EVM head is not at L2 height {} after the rollback. The storage and the ledger are already rolled back, restore the node from a backup or resync it",
+                l2_height
+            );
+        }
+
+        self.state_root = self.storage.get_root_hash(l2_height + 1)?;
+        let head_soft_confirmation = self.ledger_db.get_head_soft_confirmation()?;
+        self.batch_hash = head_soft_confirmation
+            .as_ref()
+            .map_or([0; 32], |(_, soft_confirmation)| soft_confirmation.hash);
+        self.l1_fee_rate_oracle.lock().reset(
+            head_soft_confirmation.map(|(_, soft_confirmation)| soft_confirmation.l1_fee_rate),
+        );
+        self.fork_manager.rollback_to(l2_height);
+
+        let dropped_deposit_ids = dropped_deposits
+            .iter()
+            .map(|deposit| deposit_id(deposit))
+            .collect::<Vec<_>>();
+        self.ledger_db
+            .remove_included_deposit_ids(&dropped_deposit_ids)?;
+        self.deposit_mempool
+            .lock()
+            .requeue_deposit_txs(dropped_deposits);
+        let recycled_count = self.recycle_transactions(dropped_txs).await?;
+
+        info!(
+            "Sequencer: Rolled back to L2 height {}, put {} transactions back into the mempool",
+            l2_height, recycled_count
+        );
+        SEQUENCER_METRICS.current_l2_block.set(l2_height as f64);
+        Ok(recycled_count)
+    }
+
+    /// Puts the transactions of rolled back blocks back into the mempool and the mempool db.
+    /// The mempool is first updated with the rolled back nonces and balances of their senders.
+    async fn recycle_transactions(
+        &self,
+        transactions: Vec<TransactionSigned>,
+    ) -> anyhow::Result<usize> {
+        let mut pooled_txs = Vec::with_capacity(transactions.len());
+        for tx in transactions {
+            let mut rlp_encoded_tx = vec![];
+            tx.encode_2718(&mut rlp_encoded_tx);
+            match recover_raw_transaction(Bytes::from(rlp_encoded_tx.clone())) {
+                // System transactions are created by the sequencer for each block
+                Ok(recovered) if recovered.signer() == SYSTEM_SIGNER => {}
+                Ok(recovered) => {
+                    pooled_txs.push((EthPooledTransaction::from_pooled(recovered), rlp_encoded_tx))
+                }
+                Err(e) => warn!("Dropping rolled back tx {}: {:?}", tx.hash, e),
+            }
+        }
+
+        let senders: HashSet<Address> = pooled_txs.iter().map(|(tx, _)| tx.sender()).collect();
+        let mut account_updates = vec![];
+        for address in senders {
+            let account = self.db_provider.basic_account(address)?.unwrap_or_default();
+            account_updates.push(ChangedAccount {
+                address,
+                nonce: account.nonce,
+                balance: account.balance,
+            });
+        }
+        self.mempool.update_accounts(account_updates);
+
+        let mut recycled_count = 0;
+        for (pooled_tx, rlp_encoded_tx) in pooled_txs {
+            let tx_hash = *pooled_tx.hash();
+            if let Err(e) = self.mempool.add_external_transaction(pooled_tx).await {
+                warn!("Dropping rolled back tx {}: {:?}", tx_hash, e);
+                continue;
+            }
+            self.ledger_db
+                .insert_mempool_tx(tx_hash.to_vec(), rlp_encoded_tx)?;
+            recycled_count += 1;
+        }
+        SEQUENCER_METRICS.mempool_txs.set(self.mempool.len() as f64);
+
+        Ok(recycled_count)
+    }
+
+    /// Removes persisted transactions that were evicted from the in-memory mempool,
+    /// so they are not restored on the next start.
+    fn remove_evicted_mempool_txs(&self) -> Result<(), anyhow::Error> {
+        let evicted_txs = self.mempool.take_evicted_transactions();
+
+        if !evicted_txs.is_empty() {
+            debug!("Removing {} evicted txs from mempool db", evicted_txs.len());
+            self.ledger_db
+                .remove_mempool_txs(evicted_txs.iter().map(|tx_hash| tx_hash.to_vec()).collect())?;
+        }
+
+        Ok(())
+    }
+
+    /// Evicts the transactions that outlived their TTL in the mempool, also from the mempool db.
+    fn evict_expired_mempool_txs(&self) -> Result<(), anyhow::Error> {
+        let expired_txs = self.mempool.remove_expired_transactions();
+        if expired_txs.is_empty() {
+            return Ok(());
+        }
+
+        for tx_hash in &expired_txs {
+            info!("Evicting expired mempool tx {}", tx_hash);
+        }
+        SEQUENCER_METRICS
+            .mempool_txs_expired
+            .increment(expired_txs.len() as u64);
+        SEQUENCER_METRICS.mempool_txs.set(self.mempool.len() as f64);
+
+        self.ledger_db
+            .remove_mempool_txs(expired_txs.iter().map(|tx_hash| tx_hash.to_vec()).collect())?;
+
         Ok(())
     }
 
@@ -1061,20 +1505,38 @@ where
     }
 }
 
-async fn da_block_monitor<Da>(
+async fn da_block_monitor<Da, Db>(
     da_service: Arc<Da>,
     sender: mpsc::Sender<L1Data<Da>>,
+    mut confirmation_tracker: CommitmentConfirmationTracker<Da, Db>,
     loop_interval: u64,
     cancellation_token: CancellationToken,
 ) where
     Da: DaService,
+    Db: SequencerLedgerOps,
 {
+    let mut replaced_txs = da_service.subscribe_replaced_transactions();
+    let mut replaced_txs_open = true;
+
     loop {
         tokio::select! {
             biased;
             _ = cancellation_token.cancelled() => {
                 return;
             }
+            replaced = replaced_txs.recv(), if replaced_txs_open => {
+                match replaced {
+                    Ok((replaced, replacement)) => {
+                        if let Err(e) = confirmation_tracker.record_replacement(replaced.into(), replacement.into()) {
+                            error!("Could not record replaced commitment tx: {:?}", e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Missed {} replaced DA txs", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => replaced_txs_open = false,
+                }
+            }
             l1_data = get_da_block_data(da_service.clone()) => {
                 let l1_data = match l1_data {
                     Ok(l1_data) => l1_data,
@@ -1084,8 +1546,13 @@ async fn da_block_monitor<Da>(
                     }
                 };
 
+                let finalized_l1_height = l1_data.0.header().height();
                 let _ = sender.send(l1_data).await;
 
+                if let Err(e) = confirmation_tracker.update(finalized_l1_height).await {
+                    error!("Could not update commitment confirmations: {:?}", e);
+                }
+
                 sleep(Duration::from_millis(loop_interval)).await;
             },
         }
@@ -1124,3 +1591,8 @@ where
 
     Ok((last_finalized_block, l1_fee_rate))
 }
+
+/// Estimated size of the given bytes after the brotli compression, as charged by the L1 fee
+const fn compressed_size(size: usize) -> usize {
+    size * BROTLI_COMPRESSION_PERCENTAGE / 100
+}
@@ -0,0 +1,74 @@
+use anyhow::ensure;
+use tracing::warn;
+
+/// Returns the timestamp of the next soft confirmation given the wall clock time `now`.
+///
+/// Timestamps never decrease: if the clock is behind the parent's timestamp,
+/// the soft confirmation is timestamped one second after its parent instead.
+/// Fails if that puts the soft confirmation more than `max_drift_secs` ahead of the clock,
+/// the block is produced once the clock catches up.
+pub(crate) fn next_soft_confirmation_timestamp(
+    now: u64,
+    parent_timestamp: Option<u64>,
+    max_drift_secs: u64,
+) -> anyhow::Result<u64> {
+    let Some(parent_timestamp) = parent_timestamp else {
+        return Ok(now);
+    };
+    if now >= parent_timestamp {
+        return Ok(now);
+    }
+
+    let timestamp = parent_timestamp + 1;
+    ensure!(
+        timestamp - now <= max_drift_secs,
+        "Clock is {}s behind the head soft confirmation timestamp {}, over the max timestamp drift of {}s",
+        parent_timestamp - now,
+        parent_timestamp,
+        max_drift_secs
+    );
+    warn!(
+        "Clock is {}s behind the head soft confirmation timestamp {}, using timestamp {}",
+        parent_timestamp - now,
+        parent_timestamp,
+        timestamp
+    );
+    Ok(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_follows_clock() {
+        assert_eq!(
+            next_soft_confirmation_timestamp(100, None, 10).unwrap(),
+            100
+        );
+        assert_eq!(
+            next_soft_confirmation_timestamp(100, Some(90), 10).unwrap(),
+            100
+        );
+        assert_eq!(
+            next_soft_confirmation_timestamp(100, Some(100), 10).unwrap(),
+            100
+        );
+    }
+
+    #[test]
+    fn test_timestamp_is_clamped_to_parent() {
+        assert_eq!(
+            next_soft_confirmation_timestamp(95, Some(100), 10).unwrap(),
+            101
+        );
+        assert_eq!(
+            next_soft_confirmation_timestamp(99, Some(100), 2).unwrap(),
+            101
+        );
+
+        // Clamping would put the soft confirmation too far ahead of the clock
+        assert!(next_soft_confirmation_timestamp(90, Some(100), 10).is_err());
+        assert!(next_soft_confirmation_timestamp(99, Some(100), 1).is_err());
+    }
+}
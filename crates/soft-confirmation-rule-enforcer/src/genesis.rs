@@ -32,4 +32,16 @@ impl<C: Context, Da: DaSpec> SoftConfirmationRuleEnforcer<C, Da> {
             working_set,
         );
     }
+
+    /// Returns the config setting up the rule enforcer of the working set at genesis.
+    #[cfg(feature = "native")]
+    pub fn export_genesis_config(
+        &self,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Option<SoftConfirmationRuleEnforcerConfig<C>> {
+        Some(SoftConfirmationRuleEnforcerConfig {
+            authority: self.authority.get(working_set)?,
+            max_l2_blocks_per_l1: self.data.get(working_set)?.max_l2_blocks_per_l1,
+        })
+    }
 }
@@ -5,14 +5,14 @@ use std::task::{Context, Poll};
 use std::time::Duration;
 
 use async_trait::async_trait;
-use borsh::BorshDeserialize;
 use pin_project::pin_project;
 use sha2::Digest;
 use sov_rollup_interface::da::{
     BlobReaderTrait, BlockHeaderTrait, DaData, DaDataBatchProof, DaDataLightClient, DaNamespace,
-    DaSpec, SequencerCommitment, Time,
+    DaSpec, SequencerCommitment, Time, VersionedDaData,
 };
 use sov_rollup_interface::services::da::{DaService, SenderWithNotifier, SlotData};
+use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::zk::Proof;
 use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio::sync::{broadcast, Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
@@ -80,6 +80,7 @@ pub struct MockDaService {
     finalized_header_sender: broadcast::Sender<MockBlockHeader>,
     wait_attempts: usize,
     planned_fork: Arc<Mutex<Option<PlannedFork>>>,
+    send_transaction_delay: Duration,
 }
 
 impl MockDaService {
@@ -110,6 +111,7 @@ impl MockDaService {
             finalized_header_sender: tx,
             wait_attempts: 100_0000,
             planned_fork: Arc::new(Mutex::new(None)),
+            send_transaction_delay: Duration::ZERO,
         }
     }
 
@@ -123,6 +125,11 @@ impl MockDaService {
         self.wait_attempts = wait_attempts;
     }
 
+    /// Delay every transaction submission, to simulate a slow DA layer
+    pub fn set_send_transaction_delay(&mut self, delay: Duration) {
+        self.send_transaction_delay = delay;
+    }
+
     async fn wait_for_height(&self, height: u64) -> anyhow::Result<()> {
         // Waits self.wait_attempts * 10ms to get block at height
         for _ in 0..self.wait_attempts {
@@ -163,6 +170,19 @@ impl MockDaService {
         Ok(())
     }
 
+    /// Replaces the blocks above `height` with `num_blocks` empty blocks, ignoring finality.
+    /// Simulates a reorg of blocks that nodes have already processed as finalized.
+    pub async fn reorg_finalized_at(&self, height: u64, num_blocks: u64) -> anyhow::Result<()> {
+        let blocks = self.blocks.lock().await;
+        blocks.prune_above(height);
+
+        for _ in 0..num_blocks {
+            self.add_blob(&blocks, vec![], Default::default())?;
+        }
+
+        Ok(())
+    }
+
     /// Set planned fork, that will be executed at specified height
     pub async fn set_planned_fork(&self, planned_fork: PlannedFork) -> anyhow::Result<()> {
         let last_finalized_height = self.get_last_finalized_height().await;
@@ -202,28 +222,58 @@ impl MockDaService {
         Ok(())
     }
 
+    /// Adds a block with all of the given da data to the mock da layer for tests
+    pub async fn publish_test_block_with_da_data(
+        &self,
+        da_data: Vec<DaData>,
+        spec_id: SpecId,
+    ) -> anyhow::Result<()> {
+        let blocks = self.blocks.lock().await;
+        let blobs = da_data
+            .into_iter()
+            .map(|da_data| encode_da_data(da_data, spec_id))
+            .collect();
+        let _ = self.add_blobs(&blocks, blobs, Default::default())?;
+        Ok(())
+    }
+
     fn add_blob(
         &self,
         blocks: &AsyncMutexGuard<'_, DbConnector>,
         blob: Vec<u8>,
         zkp_proof: Vec<u8>,
+    ) -> anyhow::Result<u64> {
+        self.add_blobs(blocks, vec![blob], zkp_proof)
+    }
+
+    fn add_blobs(
+        &self,
+        blocks: &AsyncMutexGuard<'_, DbConnector>,
+        blobs: Vec<Vec<u8>>,
+        zkp_proof: Vec<u8>,
     ) -> anyhow::Result<u64> {
         let (previous_block_hash, height) = match blocks.last().map(|b| b.header().clone()) {
             None => (GENESIS_HEADER.hash(), GENESIS_HEADER.height() + 1),
             Some(block_header) => (block_header.hash(), block_header.height + 1),
         };
 
-        let data_hash = hash_to_array(&blob);
+        // Same as the hash of the blob for single blob blocks
+        let data_hash = hash_to_array(&blobs.concat());
         let proof_hash = hash_to_array(&zkp_proof);
-        // Hash only from single blob
         let block_hash = block_hash(height, data_hash, proof_hash, previous_block_hash.into());
 
-        let blob = MockBlob::new_with_zkp_proof(
-            blob.to_vec(),
-            zkp_proof,
-            self.sequencer_da_address.clone(),
-            data_hash,
-        );
+        let blobs = blobs
+            .into_iter()
+            .map(|blob| {
+                let blob_hash = hash_to_array(&blob);
+                MockBlob::new_with_zkp_proof(
+                    blob,
+                    zkp_proof.clone(),
+                    self.sequencer_da_address.clone(),
+                    blob_hash,
+                )
+            })
+            .collect();
         let header = MockBlockHeader {
             prev_hash: previous_block_hash,
             hash: block_hash,
@@ -235,7 +285,7 @@ impl MockDaService {
         let block = MockBlock {
             header,
             is_valid: true,
-            blobs: vec![blob],
+            blobs,
         };
 
         blocks.push_back(block.clone());
@@ -262,7 +312,7 @@ impl MockDaService {
         for b in block.blobs.clone() {
             let mut clone_for_full_data = b.clone();
             let full_data = clone_for_full_data.full_data();
-            if DaDataBatchProof::try_from_slice(full_data).is_ok() {
+            if DaDataBatchProof::decode_versioned(full_data).is_ok() {
                 res.push(b)
             }
         }
@@ -421,7 +471,7 @@ impl DaService for MockDaService {
     ) -> anyhow::Result<Vec<Proof>> {
         let mut res = vec![];
         for mut b in block.blobs.clone() {
-            if let Ok(r) = DaDataLightClient::try_from_slice(b.full_data()) {
+            if let Ok(r) = DaDataLightClient::decode_versioned(b.full_data()) {
                 if let DaDataLightClient::Complete(proof) = r {
                     res.push(proof);
                 } else {
@@ -435,11 +485,14 @@ impl DaService for MockDaService {
     fn extract_relevant_sequencer_commitments(
         &self,
         block: &Self::FilteredBlock,
-        _sequencer_da_pub_key: &[u8],
+        sequencer_da_pub_key: &[u8],
     ) -> anyhow::Result<Vec<SequencerCommitment>> {
         let mut res = vec![];
         for mut b in block.blobs.clone() {
-            if let Ok(r) = DaDataBatchProof::try_from_slice(b.full_data()) {
+            if b.address.as_ref() != sequencer_da_pub_key {
+                continue;
+            }
+            if let Ok(r) = DaDataBatchProof::decode_versioned(b.full_data()) {
                 let DaDataBatchProof::SequencerCommitment(seq_com) = r;
                 res.push(seq_com);
             }
@@ -462,12 +515,12 @@ impl DaService for MockDaService {
             let full_data = clone_for_full_data.full_data();
             match namespace {
                 DaNamespace::ToBatchProver => {
-                    if DaDataBatchProof::try_from_slice(full_data).is_ok() {
+                    if DaDataBatchProof::decode_versioned(full_data).is_ok() {
                         txs.push(b)
                     }
                 }
                 DaNamespace::ToLightClientProver => {
-                    if DaDataLightClient::try_from_slice(full_data).is_ok() {
+                    if DaDataLightClient::decode_versioned(full_data).is_ok() {
                         txs.push(b)
                     }
                 }
@@ -477,19 +530,14 @@ impl DaService for MockDaService {
     }
 
     #[tracing::instrument(name = "MockDA", level = "debug", skip_all)]
-    async fn send_transaction(&self, da_data: DaData) -> Result<Self::TransactionId, Self::Error> {
-        let blob = match da_data {
-            DaData::ZKProof(proof) => {
-                tracing::debug!("Adding a zkproof");
-                let data = DaDataLightClient::Complete(proof);
-                borsh::to_vec(&data).unwrap()
-            }
-            DaData::SequencerCommitment(seq_comm) => {
-                tracing::debug!("Adding a sequencer commitment");
-                let data = DaData::SequencerCommitment(seq_comm);
-                borsh::to_vec(&data).unwrap()
-            }
-        };
+    async fn send_transaction(
+        &self,
+        da_data: DaData,
+        spec_id: SpecId,
+    ) -> Result<Self::TransactionId, Self::Error> {
+        let blob = encode_da_data(da_data, spec_id);
+        // Sleep before locking the blocks, so that reading the chain is not delayed
+        tokio::time::sleep(self.send_transaction_delay).await;
         let blocks = self.blocks.lock().await;
         let _ = self.add_blob(&blocks, blob, Default::default())?;
         Ok(MockHash([0; 32]))
@@ -502,7 +550,7 @@ impl DaService for MockDaService {
         let this = self.clone();
         tokio::spawn(async move {
             while let Some(req) = rx.recv().await {
-                let res = this.send_transaction(req.da_data).await;
+                let res = this.send_transaction(req.da_data, req.spec_id).await;
                 let _ = req.notify.send(res);
             }
         });
@@ -533,6 +581,21 @@ impl DaService for MockDaService {
     }
 }
 
+fn encode_da_data(da_data: DaData, spec_id: SpecId) -> Vec<u8> {
+    match da_data {
+        DaData::ZKProof(proof) => {
+            tracing::debug!("Adding a zkproof");
+            let data = DaDataLightClient::Complete(proof);
+            data.encode_versioned(spec_id)
+        }
+        DaData::SequencerCommitment(seq_comm) => {
+            tracing::debug!("Adding a sequencer commitment");
+            let data = DaDataBatchProof::SequencerCommitment(seq_comm);
+            data.encode_versioned(spec_id)
+        }
+    }
+}
+
 fn hash_to_array(bytes: &[u8]) -> [u8; 32] {
     let mut hasher = sha2::Sha256::new();
     hasher.update(bytes);
@@ -643,7 +706,9 @@ mod tests {
             let published_blob = DaData::ZKProof(proof.clone());
             let height = (i + 1) as u64;
 
-            da.send_transaction(published_blob.clone()).await.unwrap();
+            da.send_transaction(published_blob.clone(), SpecId::Fork1)
+                .await
+                .unwrap();
 
             let mut block = da.get_block_at(height).await.unwrap();
 
@@ -651,7 +716,7 @@ mod tests {
             assert_eq!(1, block.blobs.len());
             let blob = &mut block.blobs[0];
             let retrieved_data = blob.full_data().to_vec();
-            let retrieved_data = DaDataLightClient::try_from_slice(&retrieved_data).unwrap();
+            let retrieved_data = DaDataLightClient::decode_versioned(&retrieved_data).unwrap();
             let DaDataLightClient::Complete(retrieved_proof) = retrieved_data else {
                 panic!("unexpected type");
             };
@@ -691,7 +756,7 @@ mod tests {
         for (i, blob) in blobs.iter().enumerate() {
             let height = (i + 1) as u64;
             // Send transaction should pass
-            da.send_transaction(DaData::ZKProof(blob.to_owned()))
+            da.send_transaction(DaData::ZKProof(blob.to_owned()), SpecId::Fork1)
                 .await
                 .unwrap();
             let last_finalized_block_response = da.get_last_finalized_block_header().await;
@@ -721,7 +786,7 @@ mod tests {
 
             let proof = blob;
             let retrieved_data = fetched_block.blobs[0].full_data();
-            let retrieved_data = DaDataLightClient::try_from_slice(retrieved_data).unwrap();
+            let retrieved_data = DaDataLightClient::decode_versioned(retrieved_data).unwrap();
             let DaDataLightClient::Complete(retrieved_proof) = retrieved_data else {
                 panic!("unexpected type");
             };
@@ -777,13 +842,13 @@ mod tests {
 
             // 1 -> 2 -> 3
 
-            da.send_transaction(DaData::ZKProof(vec![1, 2, 3, 4]))
+            da.send_transaction(DaData::ZKProof(vec![1, 2, 3, 4]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![4, 5, 6, 7]))
+            da.send_transaction(DaData::ZKProof(vec![4, 5, 6, 7]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![8, 9, 0, 1]))
+            da.send_transaction(DaData::ZKProof(vec![8, 9, 0, 1]), SpecId::Fork1)
                 .await
                 .unwrap();
 
@@ -821,6 +886,32 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_extract_sequencer_commitments_of_sender() -> Result<(), anyhow::Error> {
+        let db_path = tempfile::tempdir().unwrap();
+        let da = MockDaService::new(MockAddress::new([1; 32]), db_path.path());
+        let commitment = SequencerCommitment {
+            merkle_root: [2; 32],
+            l2_start_block_number: 1,
+            l2_end_block_number: 10,
+        };
+        da.publish_test_block_with_da_data(
+            vec![DaData::SequencerCommitment(commitment.clone())],
+            SpecId::Fork1,
+        )
+        .await?;
+        let block = da.get_block_at(1).await?;
+
+        assert_eq!(
+            vec![commitment],
+            da.extract_relevant_sequencer_commitments(&block, &[1; 32])?
+        );
+        assert!(da
+            .extract_relevant_sequencer_commitments(&block, &[3; 32])?
+            .is_empty());
+        Ok(())
+    }
+
     mod reo4g_control {
         use super::*;
         use crate::{MockAddress, MockDaService};
@@ -834,19 +925,19 @@ mod tests {
             //      \ -> 3.2 -> 4.2
 
             // 1
-            da.send_transaction(DaData::ZKProof(vec![1, 2, 3, 4]))
+            da.send_transaction(DaData::ZKProof(vec![1, 2, 3, 4]), SpecId::Fork1)
                 .await
                 .unwrap();
             // 2
-            da.send_transaction(DaData::ZKProof(vec![4, 5, 6, 7]))
+            da.send_transaction(DaData::ZKProof(vec![4, 5, 6, 7]), SpecId::Fork1)
                 .await
                 .unwrap();
             // 3.1
-            da.send_transaction(DaData::ZKProof(vec![8, 9, 0, 1]))
+            da.send_transaction(DaData::ZKProof(vec![8, 9, 0, 1]), SpecId::Fork1)
                 .await
                 .unwrap();
             // 4.1
-            da.send_transaction(DaData::ZKProof(vec![2, 3, 4, 5]))
+            da.send_transaction(DaData::ZKProof(vec![2, 3, 4, 5]), SpecId::Fork1)
                 .await
                 .unwrap();
 
@@ -876,16 +967,16 @@ mod tests {
 
             // 1 -> 2 -> 3 -> 4
 
-            da.send_transaction(DaData::ZKProof(vec![1, 2, 3, 4]))
+            da.send_transaction(DaData::ZKProof(vec![1, 2, 3, 4]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![4, 5, 6, 7]))
+            da.send_transaction(DaData::ZKProof(vec![4, 5, 6, 7]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![8, 9, 0, 1]))
+            da.send_transaction(DaData::ZKProof(vec![8, 9, 0, 1]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![2, 3, 4, 5]))
+            da.send_transaction(DaData::ZKProof(vec![2, 3, 4, 5]), SpecId::Fork1)
                 .await
                 .unwrap();
 
@@ -930,6 +1021,37 @@ mod tests {
             assert_ne!(block_3_after, block_3_after_reorg);
         }
 
+        #[tokio::test]
+        async fn test_reorg_finalized() {
+            let db_path = tempfile::tempdir().unwrap();
+            let da = MockDaService::new(MockAddress::new([1; 32]), db_path.path());
+
+            // 1 -> 2 -> 3
+            //  \ -> 2.1 -> 3.1 -> 4.1
+
+            for blob in [vec![1, 2, 3, 4], vec![4, 5, 6, 7], vec![8, 9, 0, 1]] {
+                da.send_transaction(DaData::ZKProof(blob), SpecId::Fork1)
+                    .await
+                    .unwrap();
+            }
+            let block_1 = da.get_block_at(1).await.unwrap();
+            let block_2 = da.get_block_at(2).await.unwrap();
+
+            // Regular forks refuse to touch finalized blocks
+            assert!(da.fork_at(1, vec![vec![3, 3, 3, 3]]).await.is_err());
+
+            da.reorg_finalized_at(1, 3).await.unwrap();
+            assert_eq!(da.get_height().await, 4);
+
+            let block_2_after = da.get_block_at(2).await.unwrap();
+            assert_ne!(block_2.header().hash(), block_2_after.header().hash());
+            assert_eq!(block_1.header().hash(), block_2_after.header().prev_hash());
+            assert_eq!(
+                &da.get_last_finalized_block_header().await.unwrap(),
+                da.get_block_at(4).await.unwrap().header()
+            );
+        }
+
         #[tokio::test]
         async fn test_planned_reorg() {
             let db_path = tempfile::tempdir().unwrap();
@@ -945,13 +1067,13 @@ mod tests {
                 assert!(has_planned_fork.is_some());
             }
 
-            da.send_transaction(DaData::ZKProof(vec![1, 2, 3, 4]))
+            da.send_transaction(DaData::ZKProof(vec![1, 2, 3, 4]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![4, 5, 6, 7]))
+            da.send_transaction(DaData::ZKProof(vec![4, 5, 6, 7]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![8, 9, 0, 1]))
+            da.send_transaction(DaData::ZKProof(vec![8, 9, 0, 1]), SpecId::Fork1)
                 .await
                 .unwrap();
 
@@ -983,19 +1105,19 @@ mod tests {
                 PlannedFork::new(4, 2, vec![vec![13, 13, 13, 13], vec![14, 14, 14, 14]]);
             da.set_planned_fork(planned_fork).await.unwrap();
 
-            da.send_transaction(DaData::ZKProof(vec![1, 1, 1, 1]))
+            da.send_transaction(DaData::ZKProof(vec![1, 1, 1, 1]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![2, 2, 2, 2]))
+            da.send_transaction(DaData::ZKProof(vec![2, 2, 2, 2]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![3, 3, 3, 3]))
+            da.send_transaction(DaData::ZKProof(vec![3, 3, 3, 3]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![4, 4, 4, 4]))
+            da.send_transaction(DaData::ZKProof(vec![4, 4, 4, 4]), SpecId::Fork1)
                 .await
                 .unwrap();
-            da.send_transaction(DaData::ZKProof(vec![5, 5, 5, 5]))
+            da.send_transaction(DaData::ZKProof(vec![5, 5, 5, 5]), SpecId::Fork1)
                 .await
                 .unwrap();
 
@@ -158,6 +158,9 @@ pub struct MockDaConfig {
     pub sender_address: MockAddress,
     /// The path in which DA db is stored
     pub db_path: PathBuf,
+    /// Delay of every transaction submission, simulating a slow DA layer
+    #[serde(default)]
+    pub send_transaction_delay_ms: u64,
 }
 
 #[derive(Clone, Default)]
@@ -233,7 +233,11 @@ impl sov_rollup_interface::zk::Zkvm for MockZkGuest {
         journal: &[u8],
         _code_commitment: &Self::CodeCommitment,
     ) -> Result<Vec<u8>, Self::Error> {
-        Ok(journal.to_vec())
+        let mock_journal = MockJournal::try_from_slice(journal).unwrap();
+        match mock_journal {
+            MockJournal::Verifiable(journal) => Ok(journal),
+            MockJournal::Unverifiable(_) => Err(anyhow::anyhow!("Journal is unverifiable")),
+        }
     }
 
     fn extract_raw_output(serialized_proof: &[u8]) -> Result<Vec<u8>, Self::Error> {
@@ -243,13 +247,10 @@ impl sov_rollup_interface::zk::Zkvm for MockZkGuest {
 
     fn verify_and_extract_output<T: BorshDeserialize>(
         journal: &[u8],
-        _code_commitment: &Self::CodeCommitment,
+        code_commitment: &Self::CodeCommitment,
     ) -> Result<T, Self::Error> {
-        let mock_journal = MockJournal::try_from_slice(journal).unwrap();
-        match mock_journal {
-            MockJournal::Verifiable(journal) => Ok(T::try_from_slice(&journal)?),
-            MockJournal::Unverifiable(_) => Err(anyhow::anyhow!("Journal is unverifiable")),
-        }
+        let journal = Self::verify(journal, code_commitment)?;
+        Ok(T::try_from_slice(&journal)?)
     }
 }
 
@@ -0,0 +1,146 @@
+//! On-disk formats of ledger types before they were changed.
+//! Migrations decode the existing values with these and re-encode them in the current format.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use sov_rollup_interface::zk::{BatchProofOutputVersion, CumulativeStateDiff, Proof};
+
+use crate::schema::types::{StoredBatchProof, StoredBatchProofOutput, StoredVerifiedProof};
+
+/// [`StoredBatchProofOutput`] before the verified method id was recorded.
+#[derive(Debug, PartialEq, BorshDeserialize, BorshSerialize, Clone)]
+pub struct StoredBatchProofOutputV1 {
+    /// The state of the rollup before the transition
+    pub initial_state_root: Vec<u8>,
+    /// The state of the rollup after the transition
+    pub final_state_root: Vec<u8>,
+    /// The hash of the last soft confirmation before the state transition
+    pub prev_soft_confirmation_hash: [u8; 32],
+    /// The hash of the last soft confirmation in the state transition
+    pub final_soft_confirmation_hash: [u8; 32],
+    /// State diff of L2 blocks in the processed sequencer commitments.
+    pub state_diff: CumulativeStateDiff,
+    /// The DA slot hash that the sequencer commitments causing this state transition were found in.
+    pub da_slot_hash: [u8; 32],
+    /// The range of sequencer commitments in the DA slot that were processed.
+    pub sequencer_commitments_range: (u32, u32),
+    /// Sequencer public key.
+    pub sequencer_public_key: Vec<u8>,
+    /// Sequencer DA public key.
+    pub sequencer_da_public_key: Vec<u8>,
+    /// Pre-proven commitments L2 ranges which also exist in the current L1 `da_data`.
+    pub preproven_commitments: Vec<usize>,
+    /// The last processed l2 height in the processed sequencer commitments.
+    pub last_l2_height: u64,
+}
+
+impl From<StoredBatchProofOutputV1> for StoredBatchProofOutput {
+    fn from(value: StoredBatchProofOutputV1) -> Self {
+        Self {
+            output_version: BatchProofOutputVersion::V1,
+            initial_state_root: value.initial_state_root,
+            final_state_root: value.final_state_root,
+            prev_soft_confirmation_hash: value.prev_soft_confirmation_hash,
+            final_soft_confirmation_hash: value.final_soft_confirmation_hash,
+            state_diff: value.state_diff,
+            da_slot_hash: value.da_slot_hash,
+            sequencer_commitments_range: value.sequencer_commitments_range,
+            sequencer_public_key: value.sequencer_public_key,
+            sequencer_da_public_key: value.sequencer_da_public_key,
+            preproven_commitments: value.preproven_commitments,
+            last_l2_height: value.last_l2_height,
+            first_l2_height: 0,
+            verified_method_id: None,
+        }
+    }
+}
+
+/// [`StoredBatchProofOutput`] before the output version was tagged.
+/// It is decoded in place by [`StoredBatchProofOutput`], so no migration is needed.
+#[derive(Debug, PartialEq, BorshDeserialize, BorshSerialize, Clone)]
+pub struct StoredBatchProofOutputV2 {
+    /// The state of the rollup before the transition
+    pub initial_state_root: Vec<u8>,
+    /// The state of the rollup after the transition
+    pub final_state_root: Vec<u8>,
+    /// The hash of the last soft confirmation before the state transition
+    pub prev_soft_confirmation_hash: [u8; 32],
+    /// The hash of the last soft confirmation in the state transition
+    pub final_soft_confirmation_hash: [u8; 32],
+    /// State diff of L2 blocks in the processed sequencer commitments.
+    pub state_diff: CumulativeStateDiff,
+    /// The DA slot hash that the sequencer commitments causing this state transition were found in.
+    pub da_slot_hash: [u8; 32],
+    /// The range of sequencer commitments in the DA slot that were processed.
+    pub sequencer_commitments_range: (u32, u32),
+    /// Sequencer public key.
+    pub sequencer_public_key: Vec<u8>,
+    /// Sequencer DA public key.
+    pub sequencer_da_public_key: Vec<u8>,
+    /// Pre-proven commitments L2 ranges which also exist in the current L1 `da_data`.
+    pub preproven_commitments: Vec<usize>,
+    /// The last processed l2 height in the processed sequencer commitments.
+    pub last_l2_height: u64,
+    /// The method id the proof was verified against.
+    pub verified_method_id: Option<[u32; 8]>,
+}
+
+impl From<StoredBatchProofOutputV2> for StoredBatchProofOutput {
+    /// Untagged outputs were all read from [`BatchProofOutputVersion::V1`] circuit outputs
+    fn from(value: StoredBatchProofOutputV2) -> Self {
+        Self {
+            output_version: BatchProofOutputVersion::V1,
+            initial_state_root: value.initial_state_root,
+            final_state_root: value.final_state_root,
+            prev_soft_confirmation_hash: value.prev_soft_confirmation_hash,
+            final_soft_confirmation_hash: value.final_soft_confirmation_hash,
+            state_diff: value.state_diff,
+            da_slot_hash: value.da_slot_hash,
+            sequencer_commitments_range: value.sequencer_commitments_range,
+            sequencer_public_key: value.sequencer_public_key,
+            sequencer_da_public_key: value.sequencer_da_public_key,
+            preproven_commitments: value.preproven_commitments,
+            last_l2_height: value.last_l2_height,
+            first_l2_height: 0,
+            verified_method_id: value.verified_method_id,
+        }
+    }
+}
+
+/// [`StoredBatchProof`] before the verified method id was recorded.
+#[derive(Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct StoredBatchProofV1 {
+    /// Tx id
+    pub l1_tx_id: [u8; 32],
+    /// Proof
+    pub proof: Proof,
+    /// Output
+    pub proof_output: StoredBatchProofOutputV1,
+}
+
+impl From<StoredBatchProofV1> for StoredBatchProof {
+    fn from(value: StoredBatchProofV1) -> Self {
+        Self {
+            l1_tx_id: value.l1_tx_id,
+            proof: value.proof,
+            proof_output: value.proof_output.into(),
+        }
+    }
+}
+
+/// [`StoredVerifiedProof`] before the verified method id was recorded.
+#[derive(Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct StoredVerifiedProofV1 {
+    /// Verified Proof
+    pub proof: Proof,
+    /// State transition
+    pub proof_output: StoredBatchProofOutputV1,
+}
+
+impl From<StoredVerifiedProofV1> for StoredVerifiedProof {
+    fn from(value: StoredVerifiedProofV1) -> Self {
+        Self {
+            proof: value.proof,
+            proof_output: value.proof_output.into(),
+        }
+    }
+}
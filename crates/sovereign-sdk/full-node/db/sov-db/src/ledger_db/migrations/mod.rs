@@ -12,6 +12,8 @@ use crate::ledger_db::{SharedLedgerOps, LEDGER_DB_PATH_SUFFIX};
 use crate::rocks_db_config::RocksdbConfig;
 use crate::schema::tables::LEDGER_TABLES;
 
+/// Previous on-disk formats of ledger types
+pub mod legacy_types;
 /// Utilities for ledger db migrations
 pub mod utils;
 
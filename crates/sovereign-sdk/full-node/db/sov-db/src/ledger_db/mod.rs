@@ -1,3 +1,5 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::RangeInclusive;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -14,17 +16,20 @@ use crate::rocks_db_config::RocksdbConfig;
 #[cfg(test)]
 use crate::schema::tables::TestTableNew;
 use crate::schema::tables::{
-    CommitmentsByNumber, ExecutedMigrations, L2GenesisStateRoot, L2RangeByL1Height, L2Witness,
-    LastPrunedBlock, LastSequencerCommitmentSent, LastStateDiff, LightClientProofBySlotNumber,
-    MempoolTxs, PendingProvingSessions, PendingSequencerCommitmentL2Range, ProofsBySlotNumberV2,
-    ProverLastScannedSlot, ProverStateDiffs, SlotByHash, SoftConfirmationByHash,
-    SoftConfirmationByNumber, SoftConfirmationStatus, VerifiedBatchProofsBySlotNumber,
-    LEDGER_TABLES,
+    BatchProofSimulations, BatchProvingSessions, CommitmentSenders, CommitmentSubmissions,
+    CommitmentsByL2EndHeight, CommitmentsByNumber, ExecutedMigrations, IncludedDepositIds,
+    L2GenesisStateRoot, L2RangeByL1Height, L2Witness, LastPrunedBlock, LastSequencerCommitmentSent,
+    LastStateDiff, LastTxBodyBackfillBlock, LightClientProofBySlotNumber, MempoolTxs,
+    PendingProvingSessions, PendingSequencerCommitmentL2Range, ProofsBySlotNumberV2,
+    ProverLastScannedSlot, ProverStateDiffs, SlotByHash, SlotHashByNumber, SoftConfirmationByHash,
+    SoftConfirmationByNumber, SoftConfirmationStatus, StagedSoftConfirmations, StateDiffSizes,
+    VerifiedBatchProofsBySlotNumber, LEDGER_TABLES,
 };
 use crate::schema::types::{
     L2HeightRange, SlotNumber, SoftConfirmationNumber, StoredBatchProof, StoredBatchProofOutput,
-    StoredLightClientProof, StoredLightClientProofOutput, StoredSoftConfirmation,
-    StoredTransaction, StoredVerifiedProof,
+    StoredBatchProofSimulation, StoredCommitmentSubmission, StoredLightClientProof,
+    StoredLightClientProofOutput, StoredProvingSessionStatus, StoredSoftConfirmation,
+    StoredStateDiffSize, StoredTransaction, StoredVerifiedProof,
 };
 
 /// Implementation of database migrator
@@ -60,6 +65,16 @@ impl LedgerDB {
             .column_families
             .clone()
             .unwrap_or_else(|| LEDGER_TABLES.iter().map(|e| e.to_string()).collect());
+        if let Some(cf_name) = cfg
+            .column_family_compression
+            .keys()
+            .find(|cf_name| !tables.contains(cf_name))
+        {
+            anyhow::bail!(
+                "column_family_compression has unknown column family {}",
+                cf_name
+            );
+        }
         let inner = DB::open(path, "ledger-db", tables, &raw_options)?;
 
         Ok(Self {
@@ -67,6 +82,27 @@ impl LedgerDB {
         })
     }
 
+    /// Open a [`LedgerDB`] at `{path}/ledger` in read-only mode.
+    /// This does not take the database lock, so it can be used while the node is running.
+    /// Writes made after opening are not visible to the returned instance.
+    #[instrument(level = "trace", skip_all, err)]
+    pub fn with_config_read_only(cfg: &RocksdbConfig) -> Result<Self, anyhow::Error> {
+        let path = cfg.path.join(LEDGER_DB_PATH_SUFFIX);
+        let raw_options = cfg.as_raw_options(true);
+        // Tables added by newer versions may not exist yet, and read-only mode cannot create them
+        let existing_tables = rocksdb::DB::list_cf(&raw_options.db_options, &path)?;
+        let tables = LEDGER_TABLES
+            .iter()
+            .copied()
+            .filter(|table| existing_tables.iter().any(|existing| existing == *table))
+            .collect();
+        let inner = DB::open_cf_readonly(&raw_options.db_options, path, "ledger-db", tables)?;
+
+        Ok(Self {
+            db: Arc::new(inner),
+        })
+    }
+
     /// Returns the handle foe the column family with the given name
     pub fn get_cf_handle(&self, cf_name: &str) -> anyhow::Result<&rocksdb::ColumnFamily> {
         self.db.get_cf_handle(cf_name)
@@ -82,6 +118,15 @@ impl LedgerDB {
         self.db.put_cf(cf_handle, key, value)
     }
 
+    /// Delete a key from the database given a column family
+    pub fn delete_from_cf_raw(
+        &self,
+        cf_handle: &rocksdb::ColumnFamily,
+        key: &[u8],
+    ) -> anyhow::Result<()> {
+        self.db.delete_cf(cf_handle, key)
+    }
+
     /// Get an iterator for the given column family
     pub fn get_iterator_for_cf<'a>(
         &'a self,
@@ -113,6 +158,24 @@ impl LedgerDB {
         Ok(out)
     }
 
+    /// Adds the heights below `end` of the slots with an entry in the table to `heights`.
+    /// The whole table is scanned, as tables with the default codec are not ordered by height.
+    fn collect_slot_heights_before<T: Schema<Key = SlotNumber>>(
+        &self,
+        end: SlotNumber,
+        heights: &mut BTreeSet<SlotNumber>,
+    ) -> anyhow::Result<()> {
+        let mut iter = self.db.iter::<T>()?;
+        iter.seek_to_first();
+        for item in iter {
+            let key = item?.key;
+            if key < end {
+                heights.insert(key);
+            }
+        }
+        Ok(())
+    }
+
     fn last_version_written<T: Schema<Key = U>, U: Into<u64>>(
         db: &DB,
         _schema: T,
@@ -126,34 +189,11 @@ impl LedgerDB {
             _ => Ok(None),
         }
     }
-}
-
-impl SharedLedgerOps for LedgerDB {
-    /// Returns the path of the DB
-    fn path(&self) -> &Path {
-        self.db.path()
-    }
-
-    #[instrument(level = "trace", skip(self, schema_batch), err, ret)]
-    fn put_soft_confirmation(
-        &self,
-        batch: &StoredSoftConfirmation,
-        batch_number: &SoftConfirmationNumber,
-        schema_batch: &mut SchemaBatch,
-    ) -> Result<(), anyhow::Error> {
-        schema_batch.put::<SoftConfirmationByNumber>(batch_number, batch)?;
-        schema_batch.put::<SoftConfirmationByHash>(&batch.hash, batch_number)
-    }
-
-    /// Commits a soft confirmation to the database by inserting its transactions and batches before
-    fn commit_soft_confirmation<DS: DaSpec>(
-        &self,
+    fn stored_soft_confirmation<DS: DaSpec>(
         state_root: &[u8],
         soft_confirmation_receipt: SoftConfirmationReceipt<DS>,
         tx_bodies: Option<Vec<Vec<u8>>>,
-    ) -> Result<(), anyhow::Error> {
-        let mut schema_batch = SchemaBatch::new();
-
+    ) -> StoredSoftConfirmation {
         let tx_bodies = if let Some(tx_bodies) = tx_bodies {
             tx_bodies.into_iter().map(Some).collect()
         } else {
@@ -169,12 +209,10 @@ impl SharedLedgerOps for LedgerDB {
                 body: tx_body,
             })
             .collect();
-        let l2_height = soft_confirmation_receipt.l2_height;
 
-        // Insert soft confirmation
-        let soft_confirmation_to_store = StoredSoftConfirmation {
+        StoredSoftConfirmation {
             da_slot_height: soft_confirmation_receipt.da_slot_height,
-            l2_height,
+            l2_height: soft_confirmation_receipt.l2_height,
             da_slot_hash: soft_confirmation_receipt.da_slot_hash.into(),
             da_slot_txs_commitment: soft_confirmation_receipt.da_slot_txs_commitment.into(),
             hash: soft_confirmation_receipt.hash,
@@ -186,7 +224,83 @@ impl SharedLedgerOps for LedgerDB {
             deposit_data: soft_confirmation_receipt.deposit_data,
             l1_fee_rate: soft_confirmation_receipt.l1_fee_rate,
             timestamp: soft_confirmation_receipt.timestamp,
-        };
+        }
+    }
+
+    /// Puts sequential soft confirmations to the batch along with their L2 ranges and trusted statuses
+    fn put_soft_confirmations_with_ranges(
+        &self,
+        soft_confirmations: Vec<StoredSoftConfirmation>,
+        schema_batch: &mut SchemaBatch,
+    ) -> anyhow::Result<()> {
+        let mut l2_ranges: Vec<(SlotNumber, L2HeightRange)> = vec![];
+
+        for soft_confirmation in soft_confirmations {
+            let l2_height = SoftConfirmationNumber(soft_confirmation.l2_height);
+            let l1_height = SlotNumber(soft_confirmation.da_slot_height);
+            self.put_soft_confirmation(&soft_confirmation, &l2_height, schema_batch)?;
+
+            // Same as `extend_l2_range_of_l1_slot`, against the ranges extended earlier in the batch
+            match l2_ranges.last_mut() {
+                Some((last_l1_height, range)) if *last_l1_height == l1_height => {
+                    range.1 = l2_height
+                }
+                _ => {
+                    let range = match self.db.get::<L2RangeByL1Height>(&l1_height)? {
+                        Some(existing) => (existing.0, l2_height),
+                        None => (l2_height, l2_height),
+                    };
+                    l2_ranges.push((l1_height, range));
+                }
+            }
+
+            // Same as `upgrade_soft_confirmation_status` with trusted, the lowest status
+            if self.db.get::<SoftConfirmationStatus>(&l2_height)?.is_none() {
+                schema_batch.put::<SoftConfirmationStatus>(
+                    &l2_height,
+                    &sov_rollup_interface::rpc::SoftConfirmationStatus::Trusted,
+                )?;
+            }
+        }
+
+        for (l1_height, range) in l2_ranges {
+            schema_batch.put::<L2RangeByL1Height>(&l1_height, &range)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SharedLedgerOps for LedgerDB {
+    /// Returns the path of the DB
+    fn path(&self) -> &Path {
+        self.db.path()
+    }
+
+    #[instrument(level = "trace", skip(self, schema_batch), err, ret)]
+    fn put_soft_confirmation(
+        &self,
+        batch: &StoredSoftConfirmation,
+        batch_number: &SoftConfirmationNumber,
+        schema_batch: &mut SchemaBatch,
+    ) -> Result<(), anyhow::Error> {
+        schema_batch.put::<SoftConfirmationByNumber>(batch_number, batch)?;
+        schema_batch.put::<SoftConfirmationByHash>(&batch.hash, batch_number)
+    }
+
+    /// Commits a soft confirmation to the database by inserting its transactions and batches before
+    fn commit_soft_confirmation<DS: DaSpec>(
+        &self,
+        state_root: &[u8],
+        soft_confirmation_receipt: SoftConfirmationReceipt<DS>,
+        tx_bodies: Option<Vec<Vec<u8>>>,
+    ) -> Result<(), anyhow::Error> {
+        let mut schema_batch = SchemaBatch::new();
+
+        // Insert soft confirmation
+        let l2_height = soft_confirmation_receipt.l2_height;
+        let soft_confirmation_to_store =
+            Self::stored_soft_confirmation(state_root, soft_confirmation_receipt, tx_bodies);
         self.put_soft_confirmation(
             &soft_confirmation_to_store,
             &SoftConfirmationNumber(l2_height),
@@ -198,6 +312,56 @@ impl SharedLedgerOps for LedgerDB {
         Ok(())
     }
 
+    #[instrument(level = "trace", skip_all, err)]
+    fn stage_soft_confirmations<DS: DaSpec>(
+        &self,
+        soft_confirmations: Vec<(Vec<u8>, SoftConfirmationReceipt<DS>, Option<Vec<Vec<u8>>>)>,
+    ) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+
+        for (state_root, soft_confirmation_receipt, tx_bodies) in soft_confirmations {
+            let l2_height = SoftConfirmationNumber(soft_confirmation_receipt.l2_height);
+            let soft_confirmation_to_store =
+                Self::stored_soft_confirmation(&state_root, soft_confirmation_receipt, tx_bodies);
+            schema_batch.put::<StagedSoftConfirmations>(&l2_height, &soft_confirmation_to_store)?;
+        }
+
+        self.db.write_schemas(schema_batch)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_staged_soft_confirmations(&self) -> anyhow::Result<Vec<StoredSoftConfirmation>> {
+        let mut iter = self.db.iter::<StagedSoftConfirmations>()?;
+        iter.seek_to_first();
+
+        let soft_confirmations = iter
+            .map(|item| item.map(|item| item.value))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(soft_confirmations)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn commit_staged_soft_confirmations(
+        &self,
+        up_to: Option<SoftConfirmationNumber>,
+    ) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        let mut to_commit = vec![];
+
+        for soft_confirmation in self.get_staged_soft_confirmations()? {
+            let l2_height = SoftConfirmationNumber(soft_confirmation.l2_height);
+            schema_batch.delete::<StagedSoftConfirmations>(&l2_height)?;
+            if up_to.is_some_and(|up_to| l2_height <= up_to) {
+                to_commit.push(soft_confirmation);
+            }
+        }
+
+        self.put_soft_confirmations_with_ranges(to_commit, &mut schema_batch)?;
+
+        self.db.write_schemas(schema_batch)
+    }
+
     /// Records the L2 height that was created as a soft confirmaiton of an L1 height
     #[instrument(level = "trace", skip(self), err, ret)]
     fn extend_l2_range_of_l1_slot(
@@ -220,6 +384,14 @@ impl SharedLedgerOps for LedgerDB {
         Ok(())
     }
 
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_l2_range_by_l1_height(
+        &self,
+        l1_height: SlotNumber,
+    ) -> anyhow::Result<Option<L2HeightRange>> {
+        self.db.get::<L2RangeByL1Height>(&l1_height)
+    }
+
     /// Gets l1 height of l1 hash
     #[instrument(level = "trace", skip(self), err, ret)]
     fn get_state_diff(&self) -> Result<StateDiff, anyhow::Error> {
@@ -255,6 +427,24 @@ impl SharedLedgerOps for LedgerDB {
         Ok(())
     }
 
+    /// Saves a soft confirmation status for a given L2 height unless a more final one is stored
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn upgrade_soft_confirmation_status(
+        &self,
+        height: SoftConfirmationNumber,
+        status: sov_rollup_interface::rpc::SoftConfirmationStatus,
+    ) -> Result<bool, anyhow::Error> {
+        if let Some(current) = self.db.get::<SoftConfirmationStatus>(&height)? {
+            if current >= status {
+                return Ok(false);
+            }
+        }
+
+        self.db.put::<SoftConfirmationStatus>(&height, &status)?;
+
+        Ok(true)
+    }
+
     /// Saves a soft confirmation status for a given L1 height
     #[instrument(level = "trace", skip(self), err, ret)]
     fn get_soft_confirmation_status(
@@ -295,6 +485,87 @@ impl SharedLedgerOps for LedgerDB {
         }
     }
 
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn put_commitment_by_l2_range(
+        &self,
+        l1_height: u64,
+        commitment: SequencerCommitment,
+    ) -> anyhow::Result<()> {
+        self.db.put::<CommitmentsByL2EndHeight>(
+            &SoftConfirmationNumber(commitment.l2_end_block_number),
+            &(SlotNumber(l1_height), commitment),
+        )
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_commitment_by_l2_height(
+        &self,
+        l2_height: SoftConfirmationNumber,
+    ) -> anyhow::Result<Option<(SlotNumber, SequencerCommitment)>> {
+        // The first commitment ending at or after the given height is the only candidate,
+        // it covers the height only if it also starts at or before it.
+        let mut iter = self.db.iter::<CommitmentsByL2EndHeight>()?;
+        iter.seek(&l2_height)?;
+
+        match iter.next() {
+            Some(Ok(item)) => {
+                let (l1_height, commitment) = item.value;
+                if commitment.l2_start_block_number <= l2_height.0 {
+                    Ok(Some((l1_height, commitment)))
+                } else {
+                    Ok(None)
+                }
+            }
+            Some(Err(e)) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn put_commitment_sender(
+        &self,
+        l1_height: u64,
+        commitment: &SequencerCommitment,
+        sender: &[u8],
+    ) -> anyhow::Result<()> {
+        self.db.put::<CommitmentSenders>(
+            &(
+                SlotNumber(l1_height),
+                SoftConfirmationNumber(commitment.l2_end_block_number),
+            ),
+            &sender.to_vec(),
+        )
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_commitment_sender(
+        &self,
+        l1_height: u64,
+        commitment: &SequencerCommitment,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        self.db.get::<CommitmentSenders>(&(
+            SlotNumber(l1_height),
+            SoftConfirmationNumber(commitment.l2_end_block_number),
+        ))
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn put_state_diff_size(
+        &self,
+        l2_height: SoftConfirmationNumber,
+        state_diff_size: StoredStateDiffSize,
+    ) -> anyhow::Result<()> {
+        self.db.put::<StateDiffSizes>(&l2_height, &state_diff_size)
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_state_diff_size(
+        &self,
+        l2_height: SoftConfirmationNumber,
+    ) -> anyhow::Result<Option<StoredStateDiffSize>> {
+        self.db.get::<StateDiffSizes>(&l2_height)
+    }
+
     /// Set the genesis state root
     #[instrument(level = "trace", skip_all, err, ret)]
     fn set_l2_genesis_state_root<StateRoot: Serialize>(
@@ -431,6 +702,232 @@ impl SharedLedgerOps for LedgerDB {
         Ok(())
     }
 
+    #[instrument(level = "trace", skip(self, tx_bodies), err)]
+    fn put_tx_bodies(
+        &self,
+        l2_height: SoftConfirmationNumber,
+        tx_bodies: Vec<Vec<u8>>,
+    ) -> anyhow::Result<()> {
+        let mut soft_confirmation = self
+            .db
+            .get::<SoftConfirmationByNumber>(&l2_height)?
+            .ok_or_else(|| anyhow::anyhow!("Soft confirmation {} is not stored", l2_height.0))?;
+        anyhow::ensure!(
+            soft_confirmation.txs.len() == tx_bodies.len(),
+            "Soft confirmation {} has {} transactions, got {} bodies",
+            l2_height.0,
+            soft_confirmation.txs.len(),
+            tx_bodies.len()
+        );
+
+        for (tx, body) in soft_confirmation.txs.iter_mut().zip(tx_bodies) {
+            tx.body = Some(body);
+        }
+        self.db
+            .put::<SoftConfirmationByNumber>(&l2_height, &soft_confirmation)
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_last_tx_body_backfill_l2_height(&self) -> anyhow::Result<Option<u64>> {
+        self.db.get::<LastTxBodyBackfillBlock>(&())
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_last_tx_body_backfill_l2_height(&self, l2_height: u64) -> anyhow::Result<()> {
+        self.db.put::<LastTxBodyBackfillBlock>(&(), &l2_height)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn rollback_to_l2_height(&self, l2_height: u64) -> anyhow::Result<()> {
+        if let Some(last_pruned_l2_height) = self.get_last_pruned_l2_height()? {
+            anyhow::ensure!(
+                l2_height >= last_pruned_l2_height,
+                "Cannot roll back to L2 height {} below the last pruned L2 height {}",
+                l2_height,
+                last_pruned_l2_height
+            );
+        }
+
+        let mut schema_batch = SchemaBatch::new();
+        let first_deleted = SoftConfirmationNumber(l2_height + 1);
+
+        // Soft confirmations above the target height and everything stored per L2 height
+        let mut da_slots_of_deleted = BTreeSet::new();
+        let mut iter = self.db.iter::<SoftConfirmationByNumber>()?;
+        iter.seek(&first_deleted)?;
+        for item in iter {
+            let item = item?;
+            schema_batch.delete::<SoftConfirmationByHash>(&item.value.hash)?;
+            schema_batch.delete::<SoftConfirmationByNumber>(&item.key)?;
+            schema_batch.delete::<SoftConfirmationStatus>(&item.key)?;
+            schema_batch.delete::<L2Witness>(&item.key)?;
+            schema_batch.delete::<ProverStateDiffs>(&item.key)?;
+            schema_batch.delete::<StateDiffSizes>(&item.key)?;
+            da_slots_of_deleted.insert(SlotNumber(item.value.da_slot_height));
+        }
+
+        let mut iter = self.db.iter::<StagedSoftConfirmations>()?;
+        iter.seek(&first_deleted)?;
+        for item in iter {
+            schema_batch.delete::<StagedSoftConfirmations>(&item?.key)?;
+        }
+
+        for l1_height in &da_slots_of_deleted {
+            let Some((start, end)) = self.db.get::<L2RangeByL1Height>(l1_height)? else {
+                continue;
+            };
+            if start >= first_deleted {
+                schema_batch.delete::<L2RangeByL1Height>(l1_height)?;
+            } else if end >= first_deleted {
+                schema_batch.put::<L2RangeByL1Height>(
+                    l1_height,
+                    &(start, SoftConfirmationNumber(l2_height)),
+                )?;
+            }
+        }
+
+        for range in self.get_pending_commitments_l2_range()? {
+            if range.1 >= first_deleted {
+                schema_batch.delete::<PendingSequencerCommitmentL2Range>(&range)?;
+            }
+        }
+        // The diff accumulated since the last commitment includes the deleted heights
+        if !da_slots_of_deleted.is_empty() {
+            schema_batch.delete::<LastStateDiff>(&())?;
+        }
+
+        // Commitments covering a deleted height can only be in L1 blocks after its DA slot.
+        // From the first such block on, the scanned L1 data is deleted to be processed again.
+        let last_scanned_l1_height = self.get_last_scanned_l1_height()?;
+        let rescan_from = match (da_slots_of_deleted.first(), last_scanned_l1_height) {
+            (Some(first_slot), Some(last_scanned)) => {
+                (first_slot.0..=last_scanned.0).find(|height| {
+                    self.db
+                        .get::<CommitmentsByNumber>(&SlotNumber(*height))
+                        .ok()
+                        .flatten()
+                        .is_some_and(|commitments| {
+                            commitments
+                                .iter()
+                                .any(|c| c.l2_end_block_number > l2_height)
+                        })
+                })
+            }
+            _ => None,
+        };
+
+        let mut statuses = HashMap::new();
+        if let (Some(rescan_from), Some(last_scanned)) = (rescan_from, last_scanned_l1_height) {
+            let mut rolled_back_commitments = vec![];
+            for height in rescan_from..=last_scanned.0 {
+                let slot = SlotNumber(height);
+
+                // Proven heights of the deleted proofs are only finalized until they are verified again
+                let verified_proofs = self
+                    .db
+                    .get::<VerifiedBatchProofsBySlotNumber>(&slot)?
+                    .unwrap_or_default();
+                for verified_proof in verified_proofs {
+                    let output = verified_proof.proof_output;
+                    let Some(commitments_slot) = self.db.get::<SlotByHash>(&output.da_slot_hash)?
+                    else {
+                        continue;
+                    };
+                    let commitments = self
+                        .db
+                        .get::<CommitmentsByNumber>(&commitments_slot)?
+                        .unwrap_or_default();
+                    let (start, end) = output.sequencer_commitments_range;
+                    for (index, commitment) in commitments
+                        .iter()
+                        .enumerate()
+                        .take(end as usize + 1)
+                        .skip(start as usize)
+                    {
+                        if output.preproven_commitments.contains(&index) {
+                            continue;
+                        }
+                        for i in commitment.l2_start_block_number
+                            ..=commitment.l2_end_block_number.min(l2_height)
+                        {
+                            let i = SoftConfirmationNumber(i);
+                            if self.db.get::<SoftConfirmationStatus>(&i)?
+                                == Some(sov_rollup_interface::rpc::SoftConfirmationStatus::Proven)
+                            {
+                                statuses.insert(
+                                    i,
+                                    sov_rollup_interface::rpc::SoftConfirmationStatus::Finalized,
+                                );
+                            }
+                        }
+                    }
+                }
+                schema_batch.delete::<VerifiedBatchProofsBySlotNumber>(&slot)?;
+
+                let commitments = self
+                    .db
+                    .get::<CommitmentsByNumber>(&slot)?
+                    .unwrap_or_default();
+                for commitment in &commitments {
+                    let l2_end = SoftConfirmationNumber(commitment.l2_end_block_number);
+                    if let Some((l1_height, _)) =
+                        self.db.get::<CommitmentsByL2EndHeight>(&l2_end)?
+                    {
+                        if l1_height == slot {
+                            schema_batch.delete::<CommitmentsByL2EndHeight>(&l2_end)?;
+                        }
+                    }
+                    schema_batch.delete::<CommitmentSenders>(&(slot, l2_end))?;
+                }
+                schema_batch.delete::<CommitmentsByNumber>(&slot)?;
+                rolled_back_commitments.extend(commitments);
+
+                if let Some(hash) = self.db.get::<SlotHashByNumber>(&slot)? {
+                    schema_batch.delete::<SlotByHash>(&hash)?;
+                }
+                schema_batch.delete::<SlotHashByNumber>(&slot)?;
+            }
+
+            // Heights of the deleted commitments are trusted until their commitments are scanned again
+            for commitment in &rolled_back_commitments {
+                for i in
+                    commitment.l2_start_block_number..=commitment.l2_end_block_number.min(l2_height)
+                {
+                    statuses.insert(
+                        SoftConfirmationNumber(i),
+                        sov_rollup_interface::rpc::SoftConfirmationStatus::Trusted,
+                    );
+                }
+            }
+
+            if let Some(l2_start_height) = rolled_back_commitments
+                .iter()
+                .map(|commitment| commitment.l2_start_block_number)
+                .min()
+            {
+                schema_batch.put::<LastSequencerCommitmentSent>(
+                    &(),
+                    &SoftConfirmationNumber(l2_start_height - 1),
+                )?;
+            }
+            schema_batch.put::<ProverLastScannedSlot>(&(), &SlotNumber(rescan_from - 1))?;
+        }
+
+        for (height, status) in statuses {
+            schema_batch.put::<SoftConfirmationStatus>(&height, &status)?;
+        }
+
+        // Deleted heights may be synced again without transaction bodies
+        if self
+            .get_last_tx_body_backfill_l2_height()?
+            .is_some_and(|backfilled| backfilled > l2_height)
+        {
+            schema_batch.put::<LastTxBodyBackfillBlock>(&(), &l2_height)?;
+        }
+
+        self.db.write_schemas(schema_batch)
+    }
+
     /// Gets all executed migrations.
     #[instrument(level = "trace", skip(self), err)]
     fn get_executed_migrations(&self) -> anyhow::Result<Vec<(String, u64)>> {
@@ -474,6 +971,22 @@ impl LightClientProverLedgerOps for LedgerDB {
         self.db
             .get::<LightClientProofBySlotNumber>(&SlotNumber(l1_height))
     }
+
+    fn get_last_light_client_proof_data(
+        &self,
+    ) -> anyhow::Result<Option<(u64, StoredLightClientProof)>> {
+        let mut iter = self.db.iter::<LightClientProofBySlotNumber>()?;
+        iter.seek_to_last();
+
+        match iter.next() {
+            Some(Ok(item)) => {
+                let (l1_height, proof) = item.into_tuple();
+                Ok(Some((l1_height.0, proof)))
+            }
+            Some(Err(e)) => Err(e),
+            _ => Ok(None),
+        }
+    }
 }
 
 impl BatchProverLedgerOps for LedgerDB {
@@ -588,6 +1101,96 @@ impl BatchProverLedgerOps for LedgerDB {
 
         Ok(())
     }
+
+    #[instrument(level = "trace", skip(self, status), err)]
+    fn put_batch_proving_session(
+        &self,
+        l1_height: u64,
+        sequencer_commitments_range: (u32, u32),
+        status: StoredProvingSessionStatus,
+    ) -> anyhow::Result<()> {
+        self.db.put::<BatchProvingSessions>(
+            &(SlotNumber(l1_height), sequencer_commitments_range),
+            &status,
+        )
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_batch_proving_sessions(
+        &self,
+    ) -> anyhow::Result<Vec<(u64, (u32, u32), StoredProvingSessionStatus)>> {
+        let mut iter = self.db.iter::<BatchProvingSessions>()?;
+        iter.seek_to_first();
+
+        let sessions = iter
+            .map(|item| {
+                item.map(|item| {
+                    let (SlotNumber(l1_height), range) = item.key;
+                    (l1_height, range, item.value)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn remove_batch_proving_session(
+        &self,
+        l1_height: u64,
+        sequencer_commitments_range: (u32, u32),
+    ) -> anyhow::Result<()> {
+        self.db
+            .delete::<BatchProvingSessions>(&(SlotNumber(l1_height), sequencer_commitments_range))
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn clear_batch_proving_sessions(&self) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        let mut iter = self.db.iter::<BatchProvingSessions>()?;
+        iter.seek_to_first();
+
+        for item in iter {
+            let item = item?;
+            schema_batch.delete::<BatchProvingSessions>(&item.key)?;
+        }
+
+        self.db.write_schemas(schema_batch)?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self, simulation), err)]
+    fn put_batch_proof_simulation(
+        &self,
+        l1_height: u64,
+        sequencer_commitments_range: (u32, u32),
+        simulation: StoredBatchProofSimulation,
+    ) -> anyhow::Result<()> {
+        self.db.put::<BatchProofSimulations>(
+            &(SlotNumber(l1_height), sequencer_commitments_range),
+            &simulation,
+        )
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_batch_proof_simulations(
+        &self,
+        l1_heights: RangeInclusive<u64>,
+    ) -> anyhow::Result<Vec<(u64, (u32, u32), StoredBatchProofSimulation)>> {
+        let mut iter = self.db.iter::<BatchProofSimulations>()?;
+        iter.seek(&(SlotNumber(*l1_heights.start()), (0, 0)))?;
+
+        let mut simulations = vec![];
+        for item in iter {
+            let item = item?;
+            let (SlotNumber(l1_height), range) = item.key;
+            if l1_height > *l1_heights.end() {
+                break;
+            }
+            simulations.push((l1_height, range, item.value));
+        }
+        Ok(simulations)
+    }
 }
 
 impl ProvingServiceLedgerOps for LedgerDB {
@@ -661,6 +1264,58 @@ impl SequencerLedgerOps for LedgerDB {
             .delete::<PendingSequencerCommitmentL2Range>(l2_range)
     }
 
+    #[instrument(level = "trace", skip(self), err)]
+    fn put_commitment_submission(
+        &self,
+        l2_range: &L2HeightRange,
+        submission: &StoredCommitmentSubmission,
+    ) -> anyhow::Result<()> {
+        self.db.put::<CommitmentSubmissions>(l2_range, submission)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_commitment_submission(
+        &self,
+        l2_range: &L2HeightRange,
+    ) -> anyhow::Result<Option<StoredCommitmentSubmission>> {
+        self.db.get::<CommitmentSubmissions>(l2_range)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_commitment_submissions(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> anyhow::Result<Vec<(L2HeightRange, StoredCommitmentSubmission)>> {
+        let mut iter = self.db.iter::<CommitmentSubmissions>()?.rev();
+        iter.seek_to_last();
+
+        iter.skip(offset)
+            .take(limit)
+            .map(|item| item.map(|item| item.into_tuple()))
+            .collect()
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_unconfirmed_commitment_submissions(
+        &self,
+    ) -> anyhow::Result<Vec<(L2HeightRange, StoredCommitmentSubmission)>> {
+        let mut iter = self.db.iter::<CommitmentSubmissions>()?.rev();
+        iter.seek_to_last();
+
+        let mut submissions = vec![];
+        for item in iter {
+            let (l2_range, submission) = item?.into_tuple();
+            // Commitments are mined in order, the older ones are confirmed too
+            if submission.confirmed_in_l1.is_some() {
+                break;
+            }
+            submissions.push((l2_range, submission));
+        }
+        submissions.reverse();
+        Ok(submissions)
+    }
+
     /// Sets the latest state diff
     #[instrument(level = "trace", skip(self), err, ret)]
     fn set_state_diff(&self, state_diff: &StateDiff) -> anyhow::Result<()> {
@@ -715,6 +1370,35 @@ impl SequencerLedgerOps for LedgerDB {
         self.db.write_schemas(schema_batch)?;
         Ok(())
     }
+
+    fn put_included_deposit_ids(
+        &self,
+        deposit_ids: &[[u8; 32]],
+        l2_height: SoftConfirmationNumber,
+    ) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        for deposit_id in deposit_ids {
+            schema_batch.put::<IncludedDepositIds>(deposit_id, &l2_height)?;
+        }
+        self.db.write_schemas(schema_batch)?;
+        Ok(())
+    }
+
+    fn get_deposit_inclusion_height(
+        &self,
+        deposit_id: &[u8; 32],
+    ) -> anyhow::Result<Option<SoftConfirmationNumber>> {
+        self.db.get::<IncludedDepositIds>(deposit_id)
+    }
+
+    fn remove_included_deposit_ids(&self, deposit_ids: &[[u8; 32]]) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        for deposit_id in deposit_ids {
+            schema_batch.delete::<IncludedDepositIds>(deposit_id)?;
+        }
+        self.db.write_schemas(schema_batch)?;
+        Ok(())
+    }
 }
 
 impl NodeLedgerOps for LedgerDB {
@@ -758,7 +1442,119 @@ impl NodeLedgerOps for LedgerDB {
         &self,
         height: u64,
     ) -> anyhow::Result<Option<Vec<SequencerCommitment>>> {
-        self.db.get::<CommitmentsByNumber>(&SlotNumber(height))
+        let commitments = self.db.get::<CommitmentsByNumber>(&SlotNumber(height))?;
+        // Slots written by older versions may hold the same commitment more than once
+        Ok(commitments.map(|mut commitments| {
+            commitments.sort_by_key(|commitment| {
+                (
+                    commitment.l2_start_block_number,
+                    commitment.l2_end_block_number,
+                    commitment.merkle_root,
+                )
+            });
+            commitments.dedup();
+            commitments
+        }))
+    }
+
+    #[instrument(level = "trace", skip_all, err)]
+    fn commit_soft_confirmation_batch<DS: DaSpec>(
+        &self,
+        soft_confirmations: Vec<(Vec<u8>, SoftConfirmationReceipt<DS>, Option<Vec<Vec<u8>>>)>,
+    ) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+
+        let soft_confirmations = soft_confirmations
+            .into_iter()
+            .map(|(state_root, soft_confirmation_receipt, tx_bodies)| {
+                Self::stored_soft_confirmation(&state_root, soft_confirmation_receipt, tx_bodies)
+            })
+            .collect();
+        self.put_soft_confirmations_with_ranges(soft_confirmations, &mut schema_batch)?;
+
+        self.db.write_schemas(schema_batch)
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_l1_hash_of_l1_height(&self, height: u64, hash: [u8; 32]) -> anyhow::Result<()> {
+        self.db.put::<SlotHashByNumber>(&SlotNumber(height), &hash)
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_l1_hash_of_l1_height(&self, height: u64) -> anyhow::Result<Option<[u8; 32]>> {
+        self.db.get::<SlotHashByNumber>(&SlotNumber(height))
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn delete_da_slot_data(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<(Vec<SequencerCommitment>, Vec<StoredVerifiedProof>)> {
+        let slot = SlotNumber(height);
+        let mut schema_batch = SchemaBatch::new();
+
+        let commitments = self
+            .db
+            .get::<CommitmentsByNumber>(&slot)?
+            .unwrap_or_default();
+        for commitment in &commitments {
+            let l2_end = SoftConfirmationNumber(commitment.l2_end_block_number);
+            // Only remove the index entry if it still points at this slot
+            if let Some((l1_height, _)) = self.db.get::<CommitmentsByL2EndHeight>(&l2_end)? {
+                if l1_height == slot {
+                    schema_batch.delete::<CommitmentsByL2EndHeight>(&l2_end)?;
+                }
+            }
+            schema_batch.delete::<CommitmentSenders>(&(slot, l2_end))?;
+        }
+        schema_batch.delete::<CommitmentsByNumber>(&slot)?;
+
+        let verified_proofs = self
+            .db
+            .get::<VerifiedBatchProofsBySlotNumber>(&slot)?
+            .unwrap_or_default();
+        schema_batch.delete::<VerifiedBatchProofsBySlotNumber>(&slot)?;
+
+        if let Some(hash) = self.db.get::<SlotHashByNumber>(&slot)? {
+            schema_batch.delete::<SlotByHash>(&hash)?;
+        }
+        schema_batch.delete::<SlotHashByNumber>(&slot)?;
+
+        self.db.write_schemas(schema_batch)?;
+
+        Ok((commitments, verified_proofs))
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_da_slot_heights_before(&self, height: u64) -> anyhow::Result<Vec<u64>> {
+        let end = SlotNumber(height);
+        let mut heights = BTreeSet::new();
+        self.collect_slot_heights_before::<SlotHashByNumber>(end, &mut heights)?;
+        self.collect_slot_heights_before::<CommitmentsByNumber>(end, &mut heights)?;
+        self.collect_slot_heights_before::<VerifiedBatchProofsBySlotNumber>(end, &mut heights)?;
+        self.collect_slot_heights_before::<L2RangeByL1Height>(end, &mut heights)?;
+        Ok(heights.into_iter().map(|height| height.0).collect())
+    }
+
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_last_verified_proof_l1_height(&self) -> anyhow::Result<Option<u64>> {
+        Self::last_version_written(&self.db, VerifiedBatchProofsBySlotNumber)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn prune_da_slot_data(&self, height: u64) -> anyhow::Result<()> {
+        let slot = SlotNumber(height);
+        let mut schema_batch = SchemaBatch::new();
+
+        schema_batch.delete::<CommitmentsByNumber>(&slot)?;
+        schema_batch.delete::<VerifiedBatchProofsBySlotNumber>(&slot)?;
+        schema_batch.delete::<L2RangeByL1Height>(&slot)?;
+        if let Some(hash) = self.db.get::<SlotHashByNumber>(&slot)? {
+            schema_batch.delete::<SlotByHash>(&hash)?;
+        }
+        schema_batch.delete::<SlotHashByNumber>(&slot)?;
+
+        self.db.write_schemas(schema_batch)
     }
 }
 
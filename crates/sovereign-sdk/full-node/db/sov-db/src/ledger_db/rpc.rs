@@ -1,22 +1,19 @@
+use sov_rollup_interface::da::SequencerCommitment;
 use sov_rollup_interface::rpc::{
-    sequencer_commitment_to_response, BatchProofResponse, LastVerifiedBatchProofResponse,
-    LedgerRpcProvider, SequencerCommitmentResponse, SoftConfirmationIdentifier,
-    SoftConfirmationResponse, VerifiedBatchProofResponse,
+    sequencer_commitment_to_response, BatchProofResponse, L1SlotSoftConfirmationsResponse,
+    LastVerifiedBatchProofResponse, LedgerRpcProvider, SequencerCommitmentResponse,
+    SlotVerifiedBatchProofsResponse, SoftConfirmationIdentifier, SoftConfirmationResponse,
+    VerifiedBatchProofResponse, MAX_SOFT_CONFIRMATIONS_PER_REQUEST,
 };
 
+use super::{L2GenesisStateRoot, LedgerDB, ProofsBySlotNumberV2, SharedLedgerOps};
 use crate::schema::tables::{
-    CommitmentsByNumber, SlotByHash, SoftConfirmationByHash, SoftConfirmationByNumber,
-    SoftConfirmationStatus, VerifiedBatchProofsBySlotNumber,
+    CommitmentSubmissions, CommitmentsByNumber, L2RangeByL1Height, SlotByHash,
+    SoftConfirmationByHash, SoftConfirmationByNumber, SoftConfirmationStatus,
+    VerifiedBatchProofsBySlotNumber,
 };
 use crate::schema::types::{SlotNumber, SoftConfirmationNumber};
 
-/// The maximum number of batches that can be requested in a single RPC range query
-const MAX_BATCHES_PER_REQUEST: u64 = 20;
-/// The maximum number of soft confirmations that can be requested in a single RPC range query
-const MAX_SOFT_CONFIRMATIONS_PER_REQUEST: u64 = 20;
-
-use super::{L2GenesisStateRoot, LedgerDB, ProofsBySlotNumberV2, SharedLedgerOps};
-
 impl LedgerRpcProvider for LedgerDB {
     fn get_soft_confirmation(
         &self,
@@ -42,6 +39,30 @@ impl LedgerRpcProvider for LedgerDB {
         self.get_soft_confirmation(&SoftConfirmationIdentifier::Hash(*hash))
     }
 
+    fn get_soft_confirmations_by_hashes(
+        &self,
+        hashes: &[[u8; 32]],
+    ) -> Result<Vec<Option<SoftConfirmationResponse>>, anyhow::Error> {
+        let numbers = self.db.multi_get::<SoftConfirmationByHash>(hashes)?;
+
+        let found_numbers: Vec<SoftConfirmationNumber> =
+            numbers.iter().flatten().copied().collect();
+        let mut stored_batches = self
+            .db
+            .multi_get::<SoftConfirmationByNumber>(&found_numbers)?
+            .into_iter();
+
+        let mut out = Vec::with_capacity(hashes.len());
+        for number in numbers {
+            let stored_batch = match number {
+                Some(_) => stored_batches.next().flatten(),
+                None => None,
+            };
+            out.push(stored_batch.map(TryInto::try_into).transpose()?);
+        }
+        Ok(out)
+    }
+
     fn get_soft_confirmation_by_number(
         &self,
         number: u64,
@@ -57,7 +78,7 @@ impl LedgerRpcProvider for LedgerDB {
             soft_confirmation_ids.len() <= MAX_SOFT_CONFIRMATIONS_PER_REQUEST as usize,
             "requested too many soft confirmations. Requested: {}. Max: {}",
             soft_confirmation_ids.len(),
-            MAX_BATCHES_PER_REQUEST
+            MAX_SOFT_CONFIRMATIONS_PER_REQUEST
         );
 
         let mut out = Vec::with_capacity(soft_confirmation_ids.len());
@@ -78,9 +99,9 @@ impl LedgerRpcProvider for LedgerDB {
     ) -> Result<Vec<Option<SoftConfirmationResponse>>, anyhow::Error> {
         anyhow::ensure!(start <= end, "start must be <= end");
         anyhow::ensure!(
-            end - start < MAX_BATCHES_PER_REQUEST,
+            end - start < MAX_SOFT_CONFIRMATIONS_PER_REQUEST,
             "requested batch range too large. Max: {}",
-            MAX_BATCHES_PER_REQUEST
+            MAX_SOFT_CONFIRMATIONS_PER_REQUEST
         );
         let ids: Vec<_> = (start..=end)
             .map(SoftConfirmationIdentifier::Number)
@@ -91,18 +112,13 @@ impl LedgerRpcProvider for LedgerDB {
     fn get_soft_confirmation_status(
         &self,
         l2_height: u64,
-    ) -> Result<sov_rollup_interface::rpc::SoftConfirmationStatus, anyhow::Error> {
+    ) -> Result<Option<sov_rollup_interface::rpc::SoftConfirmationStatus>, anyhow::Error> {
         if self
             .db
-            .get::<SoftConfirmationByNumber>(&SoftConfirmationNumber(l2_height))
-            .ok()
-            .flatten()
+            .get::<SoftConfirmationByNumber>(&SoftConfirmationNumber(l2_height))?
             .is_none()
         {
-            return Err(anyhow::anyhow!(
-                "Soft confirmation at height {} not processed yet.",
-                l2_height
-            ));
+            return Ok(None);
         }
 
         let status = self
@@ -110,19 +126,58 @@ impl LedgerRpcProvider for LedgerDB {
             .get::<SoftConfirmationStatus>(&SoftConfirmationNumber(l2_height))?;
 
         match status {
-            Some(status) => Ok(status),
-            None => Ok(sov_rollup_interface::rpc::SoftConfirmationStatus::Trusted),
+            Some(status) => Ok(Some(status)),
+            None => Ok(Some(
+                sov_rollup_interface::rpc::SoftConfirmationStatus::Trusted,
+            )),
         }
     }
 
-    fn get_l2_genesis_state_root(&self) -> Result<Option<Vec<u8>>, anyhow::Error> {
-        self.db.get::<L2GenesisStateRoot>(&())
+    fn get_last_pruned_l2_height(&self) -> Result<Option<u64>, anyhow::Error> {
+        SharedLedgerOps::get_last_pruned_l2_height(self)
+    }
+
+    fn get_l2_genesis_state_root(&self) -> Result<Option<[u8; 32]>, anyhow::Error> {
+        self.db
+            .get::<L2GenesisStateRoot>(&())?
+            .map(|state_root| bincode::deserialize(&state_root).map_err(Into::into))
+            .transpose()
     }
 
     fn get_slot_number_by_hash(&self, hash: [u8; 32]) -> Result<Option<u64>, anyhow::Error> {
         self.db.get::<SlotByHash>(&hash).map(|v| v.map(|a| a.0))
     }
 
+    fn get_soft_confirmations_by_l1_height(
+        &self,
+        height: u64,
+        include_txs: bool,
+    ) -> Result<Option<L1SlotSoftConfirmationsResponse>, anyhow::Error> {
+        let Some((start, end)) = self.db.get::<L2RangeByL1Height>(&SlotNumber(height))? else {
+            return Ok(None);
+        };
+
+        let numbers: Vec<_> = (start.0..=end.0).map(SoftConfirmationNumber).collect();
+        let mut soft_confirmations = Vec::with_capacity(numbers.len());
+        for stored in self.db.multi_get::<SoftConfirmationByNumber>(&numbers)? {
+            let Some(stored) = stored else {
+                continue;
+            };
+            let mut soft_confirmation: SoftConfirmationResponse = stored.try_into()?;
+            if !include_txs {
+                soft_confirmation.txs = None;
+            }
+            soft_confirmations.push(soft_confirmation);
+        }
+
+        Ok(Some(L1SlotSoftConfirmationsResponse {
+            l1_height: height,
+            l2_start: start.0,
+            l2_end: end.0,
+            soft_confirmations,
+        }))
+    }
+
     fn get_sequencer_commitments_on_slot_by_number(
         &self,
         height: u64,
@@ -131,13 +186,38 @@ impl LedgerRpcProvider for LedgerDB {
             Some(commitments) => Ok(Some(
                 commitments
                     .into_iter()
-                    .map(|commitment| sequencer_commitment_to_response(commitment, height))
-                    .collect(),
+                    .map(|commitment| {
+                        let sender = self.get_commitment_sender(height, &commitment)?;
+                        let da_tx_id = self.commitment_da_tx_id(&commitment)?;
+                        Ok(sequencer_commitment_to_response(
+                            commitment, height, sender, da_tx_id,
+                        ))
+                    })
+                    .collect::<Result<_, anyhow::Error>>()?,
             )),
             None => Ok(None),
         }
     }
 
+    fn get_sequencer_commitment_by_l2_height(
+        &self,
+        l2_height: u64,
+    ) -> Result<Option<SequencerCommitmentResponse>, anyhow::Error> {
+        let Some((l1_height, commitment)) =
+            self.get_commitment_by_l2_height(SoftConfirmationNumber(l2_height))?
+        else {
+            return Ok(None);
+        };
+        let sender = self.get_commitment_sender(l1_height.0, &commitment)?;
+        let da_tx_id = self.commitment_da_tx_id(&commitment)?;
+        Ok(Some(sequencer_commitment_to_response(
+            commitment,
+            l1_height.0,
+            sender,
+            da_tx_id,
+        )))
+    }
+
     fn get_last_scanned_l1_height(&self) -> Result<u64, anyhow::Error> {
         match SharedLedgerOps::get_last_scanned_l1_height(self)? {
             Some(height) => Ok(height.0),
@@ -178,6 +258,33 @@ impl LedgerRpcProvider for LedgerDB {
         }
     }
 
+    fn get_verified_proof_data_by_l1_height_range(
+        &self,
+        start: u64,
+        end: u64,
+        limit: usize,
+    ) -> Result<Vec<SlotVerifiedBatchProofsResponse>, anyhow::Error> {
+        let mut iter = self.db.iter::<VerifiedBatchProofsBySlotNumber>()?;
+        iter.seek(&SlotNumber(start))?;
+
+        let mut out = vec![];
+        for item in iter {
+            let item = item?;
+            if item.key.0 > end || out.len() >= limit {
+                break;
+            }
+            out.push(SlotVerifiedBatchProofsResponse {
+                height: item.key.0,
+                proofs: item
+                    .value
+                    .into_iter()
+                    .map(VerifiedBatchProofResponse::from)
+                    .collect(),
+            });
+        }
+        Ok(out)
+    }
+
     fn get_last_verified_batch_proof(
         &self,
     ) -> Result<Option<LastVerifiedBatchProofResponse>, anyhow::Error> {
@@ -225,4 +332,20 @@ impl LedgerDB {
             SoftConfirmationIdentifier::Number(num) => Ok(Some(SoftConfirmationNumber(*num))),
         }
     }
+
+    /// Id of the DA transaction of a commitment, if this node submitted it
+    fn commitment_da_tx_id(
+        &self,
+        commitment: &SequencerCommitment,
+    ) -> Result<Option<[u8; 32]>, anyhow::Error> {
+        let l2_range = (
+            SoftConfirmationNumber(commitment.l2_start_block_number),
+            SoftConfirmationNumber(commitment.l2_end_block_number),
+        );
+        Ok(self
+            .db
+            .get::<CommitmentSubmissions>(&l2_range)?
+            .filter(|submission| submission.merkle_root == commitment.merkle_root)
+            .and_then(|submission| submission.da_tx_ids.last().copied()))
+    }
 }
@@ -1,13 +1,32 @@
 use std::sync::OnceLock;
 
 use anyhow::anyhow;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sov_rollup_interface::da::SequencerCommitment;
+use sov_rollup_interface::rpc::LedgerRpcProvider;
+use sov_rollup_interface::rpc::SoftConfirmationStatus::{Finalized, Proven, Trusted};
+use sov_rollup_interface::zk::BatchProofOutputVersion;
+use sov_schema_db::schema::{KeyEncoder, ValueCodec};
 use sov_schema_db::SchemaBatch;
 
+use super::migrations::legacy_types::StoredBatchProofOutputV2;
 use super::migrations::{LedgerDBMigrator, LedgerMigration, MigrationName, MigrationVersion};
-use super::LedgerDB;
-use crate::ledger_db::{SharedLedgerOps, TestLedgerOps};
-use crate::rocks_db_config::RocksdbConfig;
-use crate::schema::tables::TestTableOld;
+use super::{LedgerDB, LEDGER_DB_PATH_SUFFIX};
+use crate::ledger_db::{
+    BatchProverLedgerOps, LightClientProverLedgerOps, NodeLedgerOps, SequencerLedgerOps,
+    SharedLedgerOps, TestLedgerOps,
+};
+use crate::rocks_db_config::{RocksdbCompression, RocksdbConfig, RocksdbTuning};
+use crate::schema::tables::{
+    CommitmentsByNumber, ProofsBySlotNumberV2, SoftConfirmationByHash, SoftConfirmationByNumber,
+    TestTableOld, VerifiedBatchProofsBySlotNumber, LEDGER_TABLES,
+};
+use crate::schema::types::{
+    SlotNumber, SoftConfirmationNumber, StoredBatchProofOutput, StoredBatchProofSimulation,
+    StoredCommitmentSubmission, StoredLightClientProofOutput, StoredProvingSessionStatus,
+    StoredSoftConfirmation, StoredStateDiffSize, StoredTransaction,
+};
 
 pub fn successful_migrations() -> &'static Vec<Box<dyn LedgerMigration + Send + Sync + 'static>> {
     static MIGRATIONS: OnceLock<Vec<Box<dyn LedgerMigration + Send + Sync + 'static>>> =
@@ -128,3 +147,1213 @@ fn test_failed_migrations() {
     let executed_migrations = ledger_db.get_executed_migrations().unwrap();
     assert_eq!(executed_migrations.len(), 0);
 }
+
+#[test]
+fn test_commitment_by_l2_height() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    let first = SequencerCommitment {
+        merkle_root: [1; 32],
+        l2_start_block_number: 1,
+        l2_end_block_number: 10,
+    };
+    // L2 heights 11..=20 were never committed
+    let second = SequencerCommitment {
+        merkle_root: [2; 32],
+        l2_start_block_number: 21,
+        l2_end_block_number: 30,
+    };
+    ledger_db
+        .put_commitment_by_l2_range(5, first.clone())
+        .unwrap();
+    ledger_db
+        .put_commitment_by_l2_range(7, second.clone())
+        .unwrap();
+
+    for (l2_height, expected) in [
+        (0, None),
+        (1, Some((SlotNumber(5), first.clone()))),
+        (10, Some((SlotNumber(5), first))),
+        (11, None),
+        (20, None),
+        (21, Some((SlotNumber(7), second.clone()))),
+        (30, Some((SlotNumber(7), second))),
+        (31, None),
+    ] {
+        assert_eq!(
+            ledger_db
+                .get_commitment_by_l2_height(SoftConfirmationNumber(l2_height))
+                .unwrap(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_l2_range_by_l1_height() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    for l2_height in 1..=3 {
+        ledger_db
+            .extend_l2_range_of_l1_slot(SlotNumber(1), SoftConfirmationNumber(l2_height))
+            .unwrap();
+    }
+    ledger_db
+        .extend_l2_range_of_l1_slot(SlotNumber(2), SoftConfirmationNumber(4))
+        .unwrap();
+
+    assert_eq!(
+        ledger_db.get_l2_range_by_l1_height(SlotNumber(1)).unwrap(),
+        Some((SoftConfirmationNumber(1), SoftConfirmationNumber(3)))
+    );
+    assert_eq!(
+        ledger_db.get_l2_range_by_l1_height(SlotNumber(2)).unwrap(),
+        Some((SoftConfirmationNumber(4), SoftConfirmationNumber(4)))
+    );
+    assert_eq!(
+        ledger_db.get_l2_range_by_l1_height(SlotNumber(3)).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_upgrade_soft_confirmation_status() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    let height = SoftConfirmationNumber(1);
+    assert_eq!(
+        ledger_db.get_soft_confirmation_status(height).unwrap(),
+        None
+    );
+
+    for (status, written, expected) in [
+        (Trusted, true, Trusted),
+        (Trusted, false, Trusted),
+        (Finalized, true, Finalized),
+        // Reprocessing L1 blocks must not downgrade the status
+        (Trusted, false, Finalized),
+        (Proven, true, Proven),
+        (Finalized, false, Proven),
+        (Trusted, false, Proven),
+        (Proven, false, Proven),
+    ] {
+        assert_eq!(
+            ledger_db
+                .upgrade_soft_confirmation_status(height, status)
+                .unwrap(),
+            written
+        );
+        assert_eq!(
+            ledger_db.get_soft_confirmation_status(height).unwrap(),
+            Some(expected)
+        );
+    }
+
+    // A status can still be set directly on a fresh height without passing through Trusted
+    assert!(ledger_db
+        .upgrade_soft_confirmation_status(SoftConfirmationNumber(2), Proven)
+        .unwrap());
+    assert_eq!(
+        ledger_db
+            .get_soft_confirmation_status(SoftConfirmationNumber(2))
+            .unwrap(),
+        Some(Proven)
+    );
+}
+
+#[test]
+fn test_delete_da_slot_data() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    let kept = SequencerCommitment {
+        merkle_root: [1; 32],
+        l2_start_block_number: 1,
+        l2_end_block_number: 10,
+    };
+    let orphaned = SequencerCommitment {
+        merkle_root: [2; 32],
+        l2_start_block_number: 11,
+        l2_end_block_number: 20,
+    };
+    for (l1_height, hash, commitment) in [(5, [5; 32], &kept), (6, [6; 32], &orphaned)] {
+        ledger_db.set_l1_height_of_l1_hash(hash, l1_height).unwrap();
+        ledger_db.set_l1_hash_of_l1_height(l1_height, hash).unwrap();
+        ledger_db
+            .update_commitments_on_da_slot(l1_height, commitment.clone())
+            .unwrap();
+        ledger_db
+            .put_commitment_by_l2_range(l1_height, commitment.clone())
+            .unwrap();
+        ledger_db
+            .put_commitment_sender(l1_height, commitment, &[7; 33])
+            .unwrap();
+    }
+    let proof_output = StoredBatchProofOutput {
+        output_version: BatchProofOutputVersion::V1,
+        initial_state_root: vec![0; 32],
+        final_state_root: vec![1; 32],
+        prev_soft_confirmation_hash: [0; 32],
+        final_soft_confirmation_hash: [1; 32],
+        state_diff: Default::default(),
+        da_slot_hash: [5; 32],
+        sequencer_commitments_range: (0, 0),
+        sequencer_public_key: vec![],
+        sequencer_da_public_key: vec![],
+        preproven_commitments: vec![],
+        last_l2_height: 10,
+        first_l2_height: 0,
+        verified_method_id: None,
+    };
+    ledger_db
+        .update_verified_proof_data(6, vec![1, 2, 3], proof_output.clone())
+        .unwrap();
+
+    let (commitments, verified_proofs) = ledger_db.delete_da_slot_data(6).unwrap();
+    assert_eq!(commitments, vec![orphaned.clone()]);
+    assert_eq!(verified_proofs.len(), 1);
+    assert_eq!(verified_proofs[0].proof_output, proof_output);
+
+    assert_eq!(ledger_db.get_commitments_on_da_slot(6).unwrap(), None);
+    assert_eq!(ledger_db.get_l1_hash_of_l1_height(6).unwrap(), None);
+    assert_eq!(ledger_db.get_l1_height_of_l1_hash([6; 32]).unwrap(), None);
+    assert_eq!(
+        ledger_db
+            .get_commitment_by_l2_height(SoftConfirmationNumber(15))
+            .unwrap(),
+        None
+    );
+    assert_eq!(ledger_db.get_commitment_sender(6, &orphaned).unwrap(), None);
+
+    // The slot below the fork point is untouched
+    assert_eq!(
+        ledger_db.get_commitments_on_da_slot(5).unwrap(),
+        Some(vec![kept.clone()])
+    );
+    assert_eq!(
+        ledger_db.get_l1_hash_of_l1_height(5).unwrap(),
+        Some([5; 32])
+    );
+    assert_eq!(
+        ledger_db.get_l1_height_of_l1_hash([5; 32]).unwrap(),
+        Some(5)
+    );
+    assert_eq!(
+        ledger_db
+            .get_commitment_by_l2_height(SoftConfirmationNumber(5))
+            .unwrap(),
+        Some((SlotNumber(5), kept.clone()))
+    );
+    assert_eq!(
+        ledger_db.get_commitment_sender(5, &kept).unwrap(),
+        Some(vec![7; 33])
+    );
+
+    // Deleting an already rolled back or unknown slot is a no-op
+    assert_eq!(ledger_db.delete_da_slot_data(6).unwrap(), (vec![], vec![]));
+    assert_eq!(ledger_db.delete_da_slot_data(7).unwrap(), (vec![], vec![]));
+}
+
+#[test]
+fn test_rollback_to_l2_height() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    // L2 blocks 1-3 are on L1 block 1 and 4-6 on L1 block 2
+    let mut schema_batch = SchemaBatch::new();
+    for l2_height in 1..=6u64 {
+        let da_slot_height = if l2_height <= 3 { 1 } else { 2 };
+        let soft_confirmation = StoredSoftConfirmation {
+            l2_height,
+            da_slot_height,
+            da_slot_hash: [da_slot_height as u8; 32],
+            da_slot_txs_commitment: [0; 32],
+            hash: [l2_height as u8; 32],
+            prev_hash: [l2_height as u8 - 1; 32],
+            txs: vec![],
+            deposit_data: vec![],
+            state_root: vec![],
+            soft_confirmation_signature: vec![],
+            pub_key: vec![],
+            l1_fee_rate: 0,
+            timestamp: 0,
+        };
+        ledger_db
+            .put_soft_confirmation(
+                &soft_confirmation,
+                &SoftConfirmationNumber(l2_height),
+                &mut schema_batch,
+            )
+            .unwrap();
+    }
+    ledger_db.db.write_schemas(schema_batch).unwrap();
+    for l2_height in 1..=6 {
+        ledger_db
+            .extend_l2_range_of_l1_slot(
+                SlotNumber(if l2_height <= 3 { 1 } else { 2 }),
+                SoftConfirmationNumber(l2_height),
+            )
+            .unwrap();
+    }
+
+    // Commitments are on L1 blocks 3 and 4, the proof of the first one is on L1 block 5
+    let first = SequencerCommitment {
+        merkle_root: [1; 32],
+        l2_start_block_number: 1,
+        l2_end_block_number: 3,
+    };
+    let second = SequencerCommitment {
+        merkle_root: [2; 32],
+        l2_start_block_number: 4,
+        l2_end_block_number: 6,
+    };
+    for (l1_height, commitment) in [(3, &first), (4, &second)] {
+        ledger_db
+            .set_l1_height_of_l1_hash([l1_height as u8; 32], l1_height)
+            .unwrap();
+        ledger_db
+            .set_l1_hash_of_l1_height(l1_height, [l1_height as u8; 32])
+            .unwrap();
+        ledger_db
+            .update_commitments_on_da_slot(l1_height, commitment.clone())
+            .unwrap();
+        ledger_db
+            .put_commitment_by_l2_range(l1_height, commitment.clone())
+            .unwrap();
+    }
+    let proof_output = StoredBatchProofOutput {
+        output_version: BatchProofOutputVersion::V1,
+        initial_state_root: vec![0; 32],
+        final_state_root: vec![1; 32],
+        prev_soft_confirmation_hash: [0; 32],
+        final_soft_confirmation_hash: [3; 32],
+        state_diff: Default::default(),
+        da_slot_hash: [3; 32],
+        sequencer_commitments_range: (0, 0),
+        sequencer_public_key: vec![],
+        sequencer_da_public_key: vec![],
+        preproven_commitments: vec![],
+        last_l2_height: 3,
+        first_l2_height: 0,
+        verified_method_id: None,
+    };
+    ledger_db
+        .update_verified_proof_data(5, vec![1, 2, 3], proof_output)
+        .unwrap();
+    for l2_height in 1..=6 {
+        let status = if l2_height <= 3 { Proven } else { Finalized };
+        ledger_db
+            .put_soft_confirmation_status(SoftConfirmationNumber(l2_height), status)
+            .unwrap();
+        ledger_db
+            .put_state_diff_size(
+                SoftConfirmationNumber(l2_height),
+                StoredStateDiffSize {
+                    size: l2_height * 10,
+                    compressed_size: l2_height,
+                },
+            )
+            .unwrap();
+    }
+    ledger_db
+        .set_last_commitment_l2_height(SoftConfirmationNumber(6))
+        .unwrap();
+    ledger_db.set_last_scanned_l1_height(SlotNumber(5)).unwrap();
+    ledger_db.set_last_pruned_l2_height(2).unwrap();
+
+    assert!(ledger_db.rollback_to_l2_height(1).is_err());
+    ledger_db.rollback_to_l2_height(4).unwrap();
+
+    assert_eq!(
+        ledger_db.get_head_soft_confirmation_height().unwrap(),
+        Some(4)
+    );
+    assert!(ledger_db
+        .db
+        .get::<SoftConfirmationByHash>(&[5; 32])
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        ledger_db
+            .get_soft_confirmation_status(SoftConfirmationNumber(5))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        ledger_db
+            .get_state_diff_size(SoftConfirmationNumber(5))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        ledger_db
+            .get_state_diff_size(SoftConfirmationNumber(4))
+            .unwrap(),
+        Some(StoredStateDiffSize {
+            size: 40,
+            compressed_size: 4
+        })
+    );
+    assert_eq!(
+        ledger_db.get_l2_range_by_l1_height(SlotNumber(2)).unwrap(),
+        Some((SoftConfirmationNumber(4), SoftConfirmationNumber(4)))
+    );
+
+    // L1 blocks from the one with the second commitment on are scanned again
+    assert_eq!(
+        ledger_db.get_last_scanned_l1_height().unwrap(),
+        Some(SlotNumber(3))
+    );
+    assert_eq!(
+        ledger_db.get_last_commitment_l2_height().unwrap(),
+        Some(SoftConfirmationNumber(3))
+    );
+    assert_eq!(ledger_db.get_commitments_on_da_slot(4).unwrap(), None);
+    assert_eq!(ledger_db.get_l1_hash_of_l1_height(4).unwrap(), None);
+    assert_eq!(ledger_db.get_l1_height_of_l1_hash([4; 32]).unwrap(), None);
+    assert_eq!(
+        ledger_db
+            .get_commitment_by_l2_height(SoftConfirmationNumber(4))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        ledger_db.get_commitments_on_da_slot(3).unwrap(),
+        Some(vec![first.clone()])
+    );
+    assert_eq!(
+        ledger_db
+            .get_commitment_by_l2_height(SoftConfirmationNumber(2))
+            .unwrap(),
+        Some((SlotNumber(3), first))
+    );
+
+    // The rolled back proof is verified again on rescan
+    for (l2_height, status) in [(1, Finalized), (3, Finalized), (4, Trusted)] {
+        assert_eq!(
+            ledger_db
+                .get_soft_confirmation_status(SoftConfirmationNumber(l2_height))
+                .unwrap(),
+            Some(status)
+        );
+    }
+}
+
+#[test]
+fn test_last_light_client_proof() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    assert!(ledger_db
+        .get_last_light_client_proof_data()
+        .unwrap()
+        .is_none());
+
+    let output = |l1_height: u64| StoredLightClientProofOutput {
+        state_root: [l1_height as u8; 32],
+        light_client_proof_method_id: [1; 8],
+        da_block_hash: [l1_height as u8; 32],
+        da_block_height: l1_height,
+        da_total_work: [0; 32],
+        da_current_target_bits: 0,
+        da_epoch_start_time: 0,
+        da_prev_11_timestamps: [0; 11],
+        unchained_batch_proofs_info: vec![],
+        last_l2_height: l1_height * 10,
+    };
+    for l1_height in [3, 7, 5] {
+        ledger_db
+            .insert_light_client_proof_data_by_l1_height(
+                l1_height,
+                vec![l1_height as u8],
+                output(l1_height),
+            )
+            .unwrap();
+    }
+
+    let (l1_height, proof) = ledger_db
+        .get_last_light_client_proof_data()
+        .unwrap()
+        .unwrap();
+    assert_eq!(l1_height, 7);
+    assert_eq!(proof.proof, vec![7]);
+    assert_eq!(proof.light_client_proof_output, output(7));
+}
+
+#[test]
+fn test_commitments_on_da_slot_are_deduplicated() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    let first = SequencerCommitment {
+        merkle_root: [1; 32],
+        l2_start_block_number: 1,
+        l2_end_block_number: 10,
+    };
+    let second = SequencerCommitment {
+        merkle_root: [2; 32],
+        l2_start_block_number: 11,
+        l2_end_block_number: 20,
+    };
+
+    // Written by a node that stored the same commitment twice
+    ledger_db
+        .db
+        .put::<CommitmentsByNumber>(
+            &SlotNumber(5),
+            &vec![second.clone(), first.clone(), second.clone()],
+        )
+        .unwrap();
+
+    assert_eq!(
+        ledger_db.get_commitments_on_da_slot(5).unwrap(),
+        Some(vec![first.clone(), second.clone()])
+    );
+
+    // Storing a commitment the slot already has is a no-op
+    ledger_db
+        .update_commitments_on_da_slot(5, first.clone())
+        .unwrap();
+    assert_eq!(
+        ledger_db.get_commitments_on_da_slot(5).unwrap(),
+        Some(vec![first, second])
+    );
+}
+
+#[test]
+fn test_batch_proving_sessions() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    ledger_db
+        .put_batch_proving_session(7, (0, 1), StoredProvingSessionStatus::Proving)
+        .unwrap();
+    ledger_db
+        .put_batch_proving_session(5, (2, 2), StoredProvingSessionStatus::Proving)
+        .unwrap();
+    // A generated proof replaces the status of its session
+    ledger_db
+        .put_batch_proving_session(5, (2, 2), StoredProvingSessionStatus::Proven(vec![1, 2, 3]))
+        .unwrap();
+
+    // Sessions are ordered by l1 height
+    assert_eq!(
+        ledger_db.get_batch_proving_sessions().unwrap(),
+        vec![
+            (5, (2, 2), StoredProvingSessionStatus::Proven(vec![1, 2, 3])),
+            (7, (0, 1), StoredProvingSessionStatus::Proving),
+        ]
+    );
+
+    ledger_db.remove_batch_proving_session(5, (2, 2)).unwrap();
+    assert_eq!(
+        ledger_db.get_batch_proving_sessions().unwrap(),
+        vec![(7, (0, 1), StoredProvingSessionStatus::Proving)]
+    );
+
+    ledger_db.clear_batch_proving_sessions().unwrap();
+    assert!(ledger_db.get_batch_proving_sessions().unwrap().is_empty());
+}
+
+#[test]
+fn test_commitment_submissions() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    let l2_range =
+        |start: u64, end: u64| (SoftConfirmationNumber(start), SoftConfirmationNumber(end));
+    let submission = |id: u8, confirmed_in_l1: Option<u64>| StoredCommitmentSubmission {
+        merkle_root: [id; 32],
+        da_tx_ids: vec![[id; 32]],
+        submitted_at: id as u64,
+        fee_rate: Some(id as u128),
+        confirmed_in_l1,
+    };
+
+    for (id, start) in [(1, 1), (2, 11), (3, 21)] {
+        ledger_db
+            .put_commitment_submission(&l2_range(start, start + 9), &submission(id, None))
+            .unwrap();
+    }
+    // Ranges are ordered numerically, not by their little endian encoding
+    ledger_db
+        .put_commitment_submission(&l2_range(256, 300), &submission(4, None))
+        .unwrap();
+
+    // Newest first
+    assert_eq!(
+        ledger_db.get_commitment_submissions(2, 0).unwrap(),
+        vec![
+            (l2_range(256, 300), submission(4, None)),
+            (l2_range(21, 30), submission(3, None)),
+        ]
+    );
+    assert_eq!(
+        ledger_db.get_commitment_submissions(10, 3).unwrap(),
+        vec![(l2_range(1, 10), submission(1, None))]
+    );
+    assert!(ledger_db
+        .get_commitment_submissions(10, 4)
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(
+        ledger_db
+            .get_unconfirmed_commitment_submissions()
+            .unwrap()
+            .len(),
+        4
+    );
+    ledger_db
+        .put_commitment_submission(&l2_range(11, 20), &submission(2, Some(5)))
+        .unwrap();
+    assert_eq!(
+        ledger_db
+            .get_commitment_submission(&l2_range(11, 20))
+            .unwrap(),
+        Some(submission(2, Some(5)))
+    );
+    // Submissions older than a confirmed one are not checked again
+    assert_eq!(
+        ledger_db.get_unconfirmed_commitment_submissions().unwrap(),
+        vec![
+            (l2_range(21, 30), submission(3, None)),
+            (l2_range(256, 300), submission(4, None)),
+        ]
+    );
+    assert_eq!(
+        ledger_db
+            .get_commitment_submission(&l2_range(1, 11))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_batch_proof_simulations() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    let success = StoredBatchProofSimulation {
+        cycles: Some(1000),
+        failure: None,
+    };
+    let failure = StoredBatchProofSimulation {
+        cycles: None,
+        failure: Some("Invalid merkle root".to_string()),
+    };
+    ledger_db
+        .put_batch_proof_simulation(5, (1, 1), failure.clone())
+        .unwrap();
+    ledger_db
+        .put_batch_proof_simulation(5, (0, 0), success.clone())
+        .unwrap();
+    ledger_db
+        .put_batch_proof_simulation(7, (0, 1), success.clone())
+        .unwrap();
+    ledger_db
+        .put_batch_proof_simulation(300, (0, 0), success.clone())
+        .unwrap();
+
+    // Simulations are ordered by l1 height and commitment range
+    assert_eq!(
+        ledger_db.get_batch_proof_simulations(5..=7).unwrap(),
+        vec![
+            (5, (0, 0), success.clone()),
+            (5, (1, 1), failure),
+            (7, (0, 1), success.clone()),
+        ]
+    );
+    assert_eq!(
+        ledger_db.get_batch_proof_simulations(6..=300).unwrap(),
+        vec![(7, (0, 1), success.clone()), (300, (0, 0), success)]
+    );
+    assert!(ledger_db
+        .get_batch_proof_simulations(8..=299)
+        .unwrap()
+        .is_empty());
+}
+
+fn untagged_batch_proof_output() -> StoredBatchProofOutputV2 {
+    StoredBatchProofOutputV2 {
+        initial_state_root: vec![1; 32],
+        final_state_root: vec![2; 32],
+        prev_soft_confirmation_hash: [3; 32],
+        final_soft_confirmation_hash: [4; 32],
+        state_diff: [(vec![5], Some(vec![6])), (vec![7], None)].into(),
+        da_slot_hash: [8; 32],
+        sequencer_commitments_range: (0, 1),
+        sequencer_public_key: vec![9; 33],
+        sequencer_da_public_key: vec![10; 33],
+        preproven_commitments: vec![0],
+        last_l2_height: 20,
+        verified_method_id: Some([11; 8]),
+    }
+}
+
+#[test]
+fn test_read_untagged_batch_proof_output() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    // Written before outputs were tagged with their version
+    let untagged = untagged_batch_proof_output();
+    let key = KeyEncoder::<ProofsBySlotNumberV2>::encode_key(&SlotNumber(5)).unwrap();
+    let value = borsh::to_vec(&vec![([12u8; 32], vec![1u8, 2, 3], untagged.clone())]).unwrap();
+    ledger_db
+        .insert_into_cf_raw(
+            ledger_db.get_cf_handle("ProofsBySlotNumberV2").unwrap(),
+            &key,
+            &value,
+        )
+        .unwrap();
+    let key = KeyEncoder::<VerifiedBatchProofsBySlotNumber>::encode_key(&SlotNumber(5)).unwrap();
+    let value = borsh::to_vec(&vec![(vec![1u8, 2, 3], untagged.clone())]).unwrap();
+    ledger_db
+        .insert_into_cf_raw(
+            ledger_db
+                .get_cf_handle("VerifiedBatchProofsBySlotNumber")
+                .unwrap(),
+            &key,
+            &value,
+        )
+        .unwrap();
+
+    let expected = StoredBatchProofOutput::from(untagged);
+    assert_eq!(expected.output_version, BatchProofOutputVersion::V1);
+
+    let proofs = ledger_db.get_proofs_by_l1_height(5).unwrap().unwrap();
+    assert_eq!(proofs.len(), 1);
+    assert_eq!(proofs[0].l1_tx_id, [12; 32]);
+    assert_eq!(proofs[0].proof_output, expected);
+
+    let proofs = ledger_db
+        .get_batch_proof_data_by_l1_height(5)
+        .unwrap()
+        .unwrap();
+    let response = serde_json::to_value(&proofs[0].proof_output).unwrap();
+    assert_eq!(response["outputVersion"], "V2");
+    assert_eq!(response["prevSoftConfirmationHash"], hex::encode([3; 32]));
+    assert_eq!(response["lastL2Height"], 20);
+
+    let proofs = ledger_db
+        .get_verified_proof_data_by_l1_height(5)
+        .unwrap()
+        .unwrap();
+    let response = serde_json::to_value(&proofs[0].proof_output).unwrap();
+    assert_eq!(response["outputVersion"], "V2");
+    assert_eq!(response["finalSoftConfirmationHash"], hex::encode([4; 32]));
+
+    // Re-written in the tagged layout once updated
+    ledger_db
+        .update_verified_proof_data(5, vec![4, 5, 6], expected.clone())
+        .unwrap();
+    let proofs = ledger_db
+        .db
+        .get::<VerifiedBatchProofsBySlotNumber>(&SlotNumber(5))
+        .unwrap()
+        .unwrap();
+    assert_eq!(proofs.len(), 2);
+    assert!(proofs.iter().all(|proof| proof.proof_output == expected));
+}
+
+#[test]
+fn test_batch_proof_output_version_round_trip() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+
+    let proof_output = StoredBatchProofOutput {
+        verified_method_id: None,
+        ..StoredBatchProofOutput::from(untagged_batch_proof_output())
+    };
+    ledger_db
+        .update_verified_proof_data(5, vec![1, 2, 3], proof_output.clone())
+        .unwrap();
+
+    let proofs = ledger_db
+        .db
+        .get::<VerifiedBatchProofsBySlotNumber>(&SlotNumber(5))
+        .unwrap()
+        .unwrap();
+    assert_eq!(proofs[0].proof_output, proof_output);
+
+    let proofs = ledger_db
+        .get_verified_proof_data_by_l1_height(5)
+        .unwrap()
+        .unwrap();
+    let response = serde_json::to_value(&proofs[0].proof_output).unwrap();
+    assert_eq!(response["outputVersion"], "V1");
+    assert_eq!(response["firstL2Height"], 0);
+
+    // Only the latest layout stores the first l2 height
+    let proof_output = StoredBatchProofOutput {
+        output_version: BatchProofOutputVersion::V2,
+        first_l2_height: 11,
+        ..StoredBatchProofOutput::from(untagged_batch_proof_output())
+    };
+    ledger_db
+        .update_verified_proof_data(6, vec![1, 2, 3], proof_output.clone())
+        .unwrap();
+
+    let proofs = ledger_db
+        .db
+        .get::<VerifiedBatchProofsBySlotNumber>(&SlotNumber(6))
+        .unwrap()
+        .unwrap();
+    assert_eq!(proofs[0].proof_output, proof_output);
+
+    let proofs = ledger_db
+        .get_verified_proof_data_by_l1_height(6)
+        .unwrap()
+        .unwrap();
+    let response = serde_json::to_value(&proofs[0].proof_output).unwrap();
+    assert_eq!(response["outputVersion"], "V2");
+    assert_eq!(response["firstL2Height"], 11);
+    assert_eq!(response["lastL2Height"], 20);
+}
+
+#[test]
+fn test_ledger_db_with_tuning() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let tuning = RocksdbTuning {
+        write_buffer_size: Some(8 * 1024 * 1024),
+        max_background_jobs: Some(4),
+        block_cache_size: Some(16 * 1024 * 1024),
+        compression: Some(RocksdbCompression::Zstd),
+        column_family_compression: [(
+            SoftConfirmationByNumber::table_name().to_string(),
+            RocksdbCompression::None,
+        )]
+        .into(),
+    };
+    tuning.validate(LEDGER_TABLES).unwrap();
+
+    let ledger_db = LedgerDB::with_config(
+        &RocksdbConfig::new(ledger_db_path.path(), None, None).with_tuning(&tuning),
+    )
+    .unwrap();
+
+    let soft_confirmations = (1..=3u64)
+        .map(|l2_height| StoredSoftConfirmation {
+            l2_height,
+            da_slot_height: 1,
+            da_slot_hash: [1; 32],
+            da_slot_txs_commitment: [0; 32],
+            hash: [l2_height as u8; 32],
+            prev_hash: [l2_height as u8 - 1; 32],
+            txs: vec![],
+            deposit_data: vec![],
+            state_root: vec![],
+            soft_confirmation_signature: vec![],
+            pub_key: vec![],
+            l1_fee_rate: 0,
+            timestamp: 0,
+        })
+        .collect::<Vec<_>>();
+    let mut schema_batch = SchemaBatch::new();
+    for soft_confirmation in &soft_confirmations {
+        ledger_db
+            .put_soft_confirmation(
+                soft_confirmation,
+                &SoftConfirmationNumber(soft_confirmation.l2_height),
+                &mut schema_batch,
+            )
+            .unwrap();
+    }
+    ledger_db.db.write_schemas(schema_batch).unwrap();
+
+    for soft_confirmation in &soft_confirmations {
+        assert_eq!(
+            SharedLedgerOps::get_soft_confirmation_by_number(
+                &ledger_db,
+                &SoftConfirmationNumber(soft_confirmation.l2_height)
+            )
+            .unwrap()
+            .as_ref(),
+            Some(soft_confirmation)
+        );
+    }
+
+    assert_eq!(
+        ledger_db
+            .db
+            .get_property(
+                SoftConfirmationByHash::table_name(),
+                "rocksdb.block-cache-capacity"
+            )
+            .unwrap(),
+        16 * 1024 * 1024
+    );
+
+    // RocksDB persists the options it was opened with
+    let options_file = std::fs::read_dir(ledger_db_path.path().join(LEDGER_DB_PATH_SUFFIX))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("OPTIONS-")
+        })
+        .max()
+        .unwrap();
+    let options = std::fs::read_to_string(options_file).unwrap();
+    let section = |name: &str| -> Vec<&str> {
+        options
+            .split('[')
+            .find(|section| section.starts_with(&format!("{}]", name)))
+            .unwrap()
+            .lines()
+            .map(str::trim)
+            .collect()
+    };
+
+    assert!(section("DBOptions").contains(&"max_background_jobs=4"));
+
+    let soft_confirmation_by_hash = section(&format!(
+        "CFOptions \"{}\"",
+        SoftConfirmationByHash::table_name()
+    ));
+    assert!(soft_confirmation_by_hash.contains(&"compression=kZSTD"));
+    assert!(soft_confirmation_by_hash.contains(&"write_buffer_size=8388608"));
+
+    let soft_confirmation_by_number = section(&format!(
+        "CFOptions \"{}\"",
+        SoftConfirmationByNumber::table_name()
+    ));
+    assert!(soft_confirmation_by_number.contains(&"compression=kNoCompression"));
+    assert!(soft_confirmation_by_number.contains(&"write_buffer_size=8388608"));
+}
+
+#[test]
+fn test_invalid_rocksdb_tuning() {
+    let invalid_tunings = [
+        (
+            RocksdbTuning {
+                write_buffer_size: Some(0),
+                ..Default::default()
+            },
+            "write_buffer_size must be greater than 0",
+        ),
+        (
+            RocksdbTuning {
+                write_buffer_size: Some(2 << 30),
+                ..Default::default()
+            },
+            "write_buffer_size 2147483648 exceeds the max total WAL size 1073741824",
+        ),
+        (
+            RocksdbTuning {
+                max_background_jobs: Some(0),
+                ..Default::default()
+            },
+            "max_background_jobs must be at least 1",
+        ),
+        (
+            RocksdbTuning {
+                block_cache_size: Some(0),
+                ..Default::default()
+            },
+            "block_cache_size must be greater than 0",
+        ),
+        (
+            RocksdbTuning {
+                column_family_compression: [("Unknown".to_string(), RocksdbCompression::Zstd)]
+                    .into(),
+                ..Default::default()
+            },
+            "column_family_compression has unknown column family Unknown",
+        ),
+    ];
+    for (tuning, error) in invalid_tunings {
+        assert_eq!(
+            tuning.validate(LEDGER_TABLES).unwrap_err().to_string(),
+            error
+        );
+    }
+
+    // The ledger db refuses to open with compression set for a column family it does not have
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let tuning = RocksdbTuning {
+        column_family_compression: [("Unknown".to_string(), RocksdbCompression::Zstd)].into(),
+        ..Default::default()
+    };
+    let err = LedgerDB::with_config(
+        &RocksdbConfig::new(ledger_db_path.path(), None, None).with_tuning(&tuning),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "column_family_compression has unknown column family Unknown"
+    );
+}
+
+/// Tx body shaped like a sov transaction wrapping a signed EIP-1559 EVM transfer.
+/// Signatures and amounts are random, recipients are picked from a small set of addresses.
+fn evm_transfer_tx_body(rng: &mut StdRng, recipients: &[[u8; 20]], nonce: u64) -> Vec<u8> {
+    let mut rlp_tx = vec![0x02, 0xf8, 0x73, 0x82, 0x16, 0x17, 0x88];
+    rlp_tx.extend(nonce.to_be_bytes());
+    // priority fee, max fee and gas limit
+    rlp_tx.extend([0x84, 0x3b, 0x9a, 0xca, 0x00, 0x84, 0x77, 0x35, 0x94, 0x00]);
+    rlp_tx.extend([0x82, 0x52, 0x08, 0x94]);
+    rlp_tx.extend(recipients[rng.gen_range(0..recipients.len())]);
+    rlp_tx.push(0x88);
+    rlp_tx.extend(rng.gen::<[u8; 8]>());
+    // empty data and access list
+    rlp_tx.extend([0x80, 0xc0, 0x01, 0xa0]);
+    rlp_tx.extend(rng.gen::<[u8; 32]>());
+    rlp_tx.push(0xa0);
+    rlp_tx.extend(rng.gen::<[u8; 32]>());
+
+    // Evm module call with a single transaction
+    let mut runtime_msg = vec![1, 0];
+    runtime_msg.extend(borsh::to_vec(&vec![rlp_tx]).unwrap());
+
+    let mut body = rng.gen::<[u8; 32]>().to_vec();
+    body.extend(rng.gen::<[u8; 32]>());
+    body.extend([7; 32]);
+    body.extend(borsh::to_vec(&runtime_msg).unwrap());
+    body.extend(5655u64.to_le_bytes());
+    body.extend(nonce.to_le_bytes());
+    body
+}
+
+fn soft_confirmation_with_transfers(
+    rng: &mut StdRng,
+    l2_height: u64,
+    tx_count: u64,
+) -> StoredSoftConfirmation {
+    let recipients: Vec<[u8; 20]> = (0..5).map(|_| rng.gen()).collect();
+    StoredSoftConfirmation {
+        l2_height,
+        da_slot_height: 1,
+        da_slot_hash: [1; 32],
+        da_slot_txs_commitment: [2; 32],
+        hash: [l2_height as u8; 32],
+        prev_hash: [l2_height as u8 - 1; 32],
+        txs: (0..tx_count)
+            .map(|nonce| StoredTransaction {
+                hash: rng.gen(),
+                body: Some(evm_transfer_tx_body(rng, &recipients, nonce)),
+            })
+            .collect(),
+        deposit_data: vec![],
+        state_root: vec![3; 32],
+        soft_confirmation_signature: vec![4; 64],
+        pub_key: vec![7; 32],
+        l1_fee_rate: 10,
+        timestamp: 1000 + l2_height,
+    }
+}
+
+#[test]
+fn test_tx_body_compression_round_trip() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+    let mut rng = StdRng::seed_from_u64(1);
+
+    // A soft confirmation with many transfers, one with a single transfer
+    // and one stored without tx bodies
+    let mut soft_confirmations = vec![
+        soft_confirmation_with_transfers(&mut rng, 1, 50),
+        soft_confirmation_with_transfers(&mut rng, 2, 1),
+        soft_confirmation_with_transfers(&mut rng, 3, 20),
+    ];
+    for tx in soft_confirmations[2].txs.iter_mut() {
+        tx.body = None;
+    }
+
+    let encoded =
+        ValueCodec::<SoftConfirmationByNumber>::encode_value(&soft_confirmations[0]).unwrap();
+    assert!(encoded.len() < borsh::to_vec(&soft_confirmations[0]).unwrap().len());
+    for soft_confirmation in &soft_confirmations[1..] {
+        assert_eq!(
+            ValueCodec::<SoftConfirmationByNumber>::encode_value(soft_confirmation).unwrap(),
+            borsh::to_vec(soft_confirmation).unwrap()
+        );
+    }
+
+    let mut schema_batch = SchemaBatch::new();
+    for soft_confirmation in &soft_confirmations {
+        ledger_db
+            .put_soft_confirmation(
+                soft_confirmation,
+                &SoftConfirmationNumber(soft_confirmation.l2_height),
+                &mut schema_batch,
+            )
+            .unwrap();
+    }
+    ledger_db.db.write_schemas(schema_batch).unwrap();
+
+    for soft_confirmation in &soft_confirmations {
+        let l2_height = SoftConfirmationNumber(soft_confirmation.l2_height);
+        assert_eq!(
+            SharedLedgerOps::get_soft_confirmation_by_number(&ledger_db, &l2_height)
+                .unwrap()
+                .as_ref(),
+            Some(soft_confirmation)
+        );
+
+        let response = LedgerRpcProvider::get_soft_confirmation_by_number(
+            &ledger_db,
+            soft_confirmation.l2_height,
+        )
+        .unwrap()
+        .unwrap();
+        let bodies: Vec<Vec<u8>> = soft_confirmation
+            .txs
+            .iter()
+            .filter_map(|tx| tx.body.clone())
+            .collect();
+        let response_bodies: Vec<Vec<u8>> =
+            response.txs.unwrap().into_iter().map(|tx| tx.tx).collect();
+        assert_eq!(response_bodies, bodies);
+    }
+
+    let range = ledger_db
+        .get_soft_confirmation_range(&(SoftConfirmationNumber(1)..=SoftConfirmationNumber(3)))
+        .unwrap();
+    assert_eq!(range, soft_confirmations);
+}
+
+#[test]
+fn test_read_uncompressed_soft_confirmation() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+    let mut rng = StdRng::seed_from_u64(2);
+
+    // Written before tx bodies were compressed, so without a flag byte
+    let soft_confirmation = soft_confirmation_with_transfers(&mut rng, 1, 50);
+    let key =
+        KeyEncoder::<SoftConfirmationByNumber>::encode_key(&SoftConfirmationNumber(1)).unwrap();
+    ledger_db
+        .insert_into_cf_raw(
+            ledger_db
+                .get_cf_handle(SoftConfirmationByNumber::table_name())
+                .unwrap(),
+            &key,
+            &borsh::to_vec(&soft_confirmation).unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(
+        SharedLedgerOps::get_soft_confirmation_by_number(&ledger_db, &SoftConfirmationNumber(1))
+            .unwrap(),
+        Some(soft_confirmation)
+    );
+}
+
+#[test]
+fn test_put_tx_bodies() {
+    let ledger_db_path = tempfile::tempdir().unwrap();
+    let ledger_db =
+        LedgerDB::with_config(&RocksdbConfig::new(ledger_db_path.path(), None, None)).unwrap();
+    let mut rng = StdRng::seed_from_u64(4);
+
+    // Synced without tx bodies
+    let soft_confirmation = soft_confirmation_with_transfers(&mut rng, 1, 20);
+    let mut without_bodies = soft_confirmation.clone();
+    for tx in without_bodies.txs.iter_mut() {
+        tx.body = None;
+    }
+    let mut schema_batch = SchemaBatch::new();
+    ledger_db
+        .put_soft_confirmation(
+            &without_bodies,
+            &SoftConfirmationNumber(1),
+            &mut schema_batch,
+        )
+        .unwrap();
+    ledger_db.db.write_schemas(schema_batch).unwrap();
+
+    let tx_bodies: Vec<Vec<u8>> = soft_confirmation
+        .txs
+        .iter()
+        .map(|tx| tx.body.clone().unwrap())
+        .collect();
+
+    // The bodies must match the stored transactions
+    assert!(ledger_db
+        .put_tx_bodies(SoftConfirmationNumber(1), tx_bodies[1..].to_vec())
+        .is_err());
+    assert!(ledger_db
+        .put_tx_bodies(SoftConfirmationNumber(2), tx_bodies.clone())
+        .is_err());
+
+    ledger_db
+        .put_tx_bodies(SoftConfirmationNumber(1), tx_bodies)
+        .unwrap();
+    assert_eq!(
+        SharedLedgerOps::get_soft_confirmation_by_number(&ledger_db, &SoftConfirmationNumber(1))
+            .unwrap(),
+        Some(soft_confirmation)
+    );
+    assert_eq!(
+        ledger_db
+            .get_soft_confirmation_by_hash(&without_bodies.hash)
+            .unwrap()
+            .map(|response| response.txs.unwrap().len()),
+        Some(20)
+    );
+
+    // Backfill progress does not go past a rollback
+    ledger_db.set_last_tx_body_backfill_l2_height(1).unwrap();
+    ledger_db.rollback_to_l2_height(0).unwrap();
+    assert_eq!(
+        ledger_db.get_last_tx_body_backfill_l2_height().unwrap(),
+        Some(0)
+    );
+}
+
+#[test]
+fn test_tx_body_compression_size_reduction() {
+    let mut rng = StdRng::seed_from_u64(3);
+
+    for tx_count in [10, 100, 1000] {
+        let soft_confirmation = soft_confirmation_with_transfers(&mut rng, 1, tx_count);
+        let uncompressed_size = borsh::to_vec(&soft_confirmation).unwrap().len();
+
+        let encoded =
+            ValueCodec::<SoftConfirmationByNumber>::encode_value(&soft_confirmation).unwrap();
+        let decoded =
+            <StoredSoftConfirmation as ValueCodec<SoftConfirmationByNumber>>::decode_value(
+                &encoded,
+            )
+            .unwrap();
+        assert_eq!(decoded, soft_confirmation);
+        assert!(encoded.len() < uncompressed_size);
+    }
+}
+
+#[test]
+fn test_decode_corrupt_compressed_tx_bodies() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let soft_confirmation = soft_confirmation_with_transfers(&mut rng, 1, 50);
+
+    let mut encoded =
+        ValueCodec::<SoftConfirmationByNumber>::encode_value(&soft_confirmation).unwrap();
+    // Cut the compressed tx bodies short
+    encoded.truncate(encoded.len() - 10);
+
+    assert!(
+        <StoredSoftConfirmation as ValueCodec<SoftConfirmationByNumber>>::decode_value(&encoded)
+            .is_err()
+    );
+}
@@ -1,3 +1,4 @@
+use std::ops::RangeInclusive;
 use std::path::Path;
 
 use anyhow::Result;
@@ -10,7 +11,9 @@ use sov_schema_db::SchemaBatch;
 
 use crate::schema::types::{
     L2HeightRange, SlotNumber, SoftConfirmationNumber, StoredBatchProof, StoredBatchProofOutput,
-    StoredLightClientProof, StoredLightClientProofOutput, StoredSoftConfirmation,
+    StoredBatchProofSimulation, StoredCommitmentSubmission, StoredLightClientProof,
+    StoredLightClientProofOutput, StoredProvingSessionStatus, StoredSoftConfirmation,
+    StoredStateDiffSize,
 };
 
 /// Shared ledger operations
@@ -34,6 +37,21 @@ pub trait SharedLedgerOps {
         tx_bodies: Option<Vec<Vec<u8>>>,
     ) -> Result<()>;
 
+    /// Stages the ledger data of soft confirmations right before their state is finalized.
+    /// Items are (state root, receipt, tx bodies).
+    fn stage_soft_confirmations<DS: DaSpec>(
+        &self,
+        soft_confirmations: Vec<(Vec<u8>, SoftConfirmationReceipt<DS>, Option<Vec<Vec<u8>>>)>,
+    ) -> Result<()>;
+
+    /// Gets the staged soft confirmations ordered by L2 height
+    fn get_staged_soft_confirmations(&self) -> Result<Vec<StoredSoftConfirmation>>;
+
+    /// Commits the staged soft confirmations up to and including the given L2 height with
+    /// their L2 ranges and trusted statuses, and drops all staged soft confirmations, in a single write.
+    fn commit_staged_soft_confirmations(&self, up_to: Option<SoftConfirmationNumber>)
+        -> Result<()>;
+
     /// Records the L2 height that was created as a soft confirmaiton of an L1 height
     fn extend_l2_range_of_l1_slot(
         &self,
@@ -41,6 +59,9 @@ pub trait SharedLedgerOps {
         l2_height: SoftConfirmationNumber,
     ) -> Result<()>;
 
+    /// Gets the inclusive range of L2 heights built on top of the given L1 height
+    fn get_l2_range_by_l1_height(&self, l1_height: SlotNumber) -> Result<Option<L2HeightRange>>;
+
     /// Gets l1 height of l1 hash
     fn get_state_diff(&self) -> Result<StateDiff>;
 
@@ -57,6 +78,15 @@ pub trait SharedLedgerOps {
         status: sov_rollup_interface::rpc::SoftConfirmationStatus,
     ) -> Result<()>;
 
+    /// Saves a soft confirmation status for a given L2 height only if it is
+    /// more final than the stored one, so statuses are never downgraded.
+    /// Returns whether the status was written.
+    fn upgrade_soft_confirmation_status(
+        &self,
+        height: SoftConfirmationNumber,
+        status: sov_rollup_interface::rpc::SoftConfirmationStatus,
+    ) -> Result<bool>;
+
     /// Returns a soft confirmation status for a given L1 height
     fn get_soft_confirmation_status(
         &self,
@@ -71,6 +101,47 @@ pub trait SharedLedgerOps {
         commitment: SequencerCommitment,
     ) -> Result<()>;
 
+    /// Indexes a commitment found on the given L1 height by the L2 range it covers
+    fn put_commitment_by_l2_range(
+        &self,
+        l1_height: u64,
+        commitment: SequencerCommitment,
+    ) -> Result<()>;
+
+    /// Gets the commitment covering the given L2 height, along with the L1 height it was found in
+    fn get_commitment_by_l2_height(
+        &self,
+        l2_height: SoftConfirmationNumber,
+    ) -> Result<Option<(SlotNumber, SequencerCommitment)>>;
+
+    /// Records the DA public key that sent a commitment processed on the given L1 height
+    fn put_commitment_sender(
+        &self,
+        l1_height: u64,
+        commitment: &SequencerCommitment,
+        sender: &[u8],
+    ) -> Result<()>;
+
+    /// Gets the DA public key that sent a commitment processed on the given L1 height
+    fn get_commitment_sender(
+        &self,
+        l1_height: u64,
+        commitment: &SequencerCommitment,
+    ) -> Result<Option<Vec<u8>>>;
+
+    /// Records the state diff size of the soft confirmation at the given L2 height
+    fn put_state_diff_size(
+        &self,
+        l2_height: SoftConfirmationNumber,
+        state_diff_size: StoredStateDiffSize,
+    ) -> Result<()>;
+
+    /// Gets the state diff size of the soft confirmation at the given L2 height
+    fn get_state_diff_size(
+        &self,
+        l2_height: SoftConfirmationNumber,
+    ) -> Result<Option<StoredStateDiffSize>>;
+
     /// Set the genesis state root
     fn set_l2_genesis_state_root<StateRoot: Serialize>(
         &self,
@@ -126,6 +197,26 @@ pub trait SharedLedgerOps {
     /// Set the last pruned block number
     fn set_last_pruned_l2_height(&self, l2_height: u64) -> Result<()>;
 
+    /// Stores the transaction bodies of a committed soft confirmation in place.
+    /// Fails if the soft confirmation is not stored or the bodies do not match its transaction count.
+    fn put_tx_bodies(
+        &self,
+        l2_height: SoftConfirmationNumber,
+        tx_bodies: Vec<Vec<u8>>,
+    ) -> Result<()>;
+
+    /// Get the last L2 height whose transaction bodies were backfilled
+    fn get_last_tx_body_backfill_l2_height(&self) -> Result<Option<u64>>;
+
+    /// Set the last L2 height whose transaction bodies were backfilled
+    fn set_last_tx_body_backfill_l2_height(&self, l2_height: u64) -> Result<()>;
+
+    /// Deletes the soft confirmations above `l2_height` along with their indexes.
+    /// The L1 slots with commitments covering any of the deleted heights are rolled back too,
+    /// and the last scanned L1 height is reset so that they are scanned again.
+    /// Fails if `l2_height` is below the last pruned height.
+    fn rollback_to_l2_height(&self, l2_height: u64) -> Result<()>;
+
     /// Gets all executed migrations.
     fn get_executed_migrations(&self) -> anyhow::Result<Vec<(String, u64)>>;
 
@@ -145,6 +236,40 @@ pub trait NodeLedgerOps: SharedLedgerOps {
 
     /// Gets the commitments in the da slot with given height if any
     fn get_commitments_on_da_slot(&self, height: u64) -> Result<Option<Vec<SequencerCommitment>>>;
+
+    /// Commits the ledger data of sequentially applied soft confirmations with a single write.
+    /// Equivalent to calling `commit_soft_confirmation`, `extend_l2_range_of_l1_slot` and
+    /// `upgrade_soft_confirmation_status` with trusted for each of them, in order.
+    /// Items are (state root, receipt, tx bodies).
+    fn commit_soft_confirmation_batch<DS: DaSpec>(
+        &self,
+        soft_confirmations: Vec<(Vec<u8>, SoftConfirmationReceipt<DS>, Option<Vec<Vec<u8>>>)>,
+    ) -> Result<()>;
+
+    /// Sets the hash of the l1 block processed at given height
+    fn set_l1_hash_of_l1_height(&self, height: u64, hash: [u8; 32]) -> Result<()>;
+
+    /// Gets the hash of the l1 block processed at given height
+    fn get_l1_hash_of_l1_height(&self, height: u64) -> Result<Option<[u8; 32]>>;
+
+    /// Deletes the commitments, verified proofs and hash index of the da slot with given height.
+    /// Used to roll back a slot orphaned by a DA reorg.
+    /// Returns the deleted commitments and verified proofs.
+    fn delete_da_slot_data(
+        &self,
+        height: u64,
+    ) -> Result<(Vec<SequencerCommitment>, Vec<StoredVerifiedProof>)>;
+
+    /// Gets the heights below `height` of the da slots with stored hashes, commitments,
+    /// verified proofs or L2 ranges, in ascending order
+    fn get_da_slot_heights_before(&self, height: u64) -> Result<Vec<u64>>;
+
+    /// Gets the height of the da slot with the most recent verified proofs
+    fn get_last_verified_proof_l1_height(&self) -> Result<Option<u64>>;
+
+    /// Deletes the hash, commitments, verified proofs and L2 range of the da slot with given height.
+    /// Used to prune old slots, the commitments stay indexed by their L2 range.
+    fn prune_da_slot_data(&self, height: u64) -> Result<()>;
 }
 
 /// Prover ledger operations
@@ -188,6 +313,44 @@ pub trait BatchProverLedgerOps: SharedLedgerOps + Send + Sync {
 
     /// Clears all pending proving sessions
     fn clear_pending_proving_sessions(&self) -> Result<()>;
+
+    /// Puts the status of the proving session of a commitment range found in an l1 block
+    fn put_batch_proving_session(
+        &self,
+        l1_height: u64,
+        sequencer_commitments_range: (u32, u32),
+        status: StoredProvingSessionStatus,
+    ) -> Result<()>;
+
+    /// Gets all proving sessions with their l1 heights and commitment ranges
+    fn get_batch_proving_sessions(
+        &self,
+    ) -> Result<Vec<(u64, (u32, u32), StoredProvingSessionStatus)>>;
+
+    /// Removes the proving session of a commitment range found in an l1 block
+    fn remove_batch_proving_session(
+        &self,
+        l1_height: u64,
+        sequencer_commitments_range: (u32, u32),
+    ) -> Result<()>;
+
+    /// Clears all proving sessions
+    fn clear_batch_proving_sessions(&self) -> Result<()>;
+
+    /// Puts the simulation result of a commitment range found in an l1 block
+    fn put_batch_proof_simulation(
+        &self,
+        l1_height: u64,
+        sequencer_commitments_range: (u32, u32),
+        simulation: StoredBatchProofSimulation,
+    ) -> Result<()>;
+
+    /// Gets the simulation results of the commitment ranges found in the given l1 blocks,
+    /// with their l1 heights and commitment ranges
+    fn get_batch_proof_simulations(
+        &self,
+        l1_heights: RangeInclusive<u64>,
+    ) -> Result<Vec<(u64, (u32, u32), StoredBatchProofSimulation)>>;
 }
 
 /// Light client prover ledger operations
@@ -205,6 +368,9 @@ pub trait LightClientProverLedgerOps: SharedLedgerOps + Send + Sync {
         &self,
         l1_height: u64,
     ) -> Result<Option<StoredLightClientProof>>;
+
+    /// Gets the light client proof data of the highest L1 height
+    fn get_last_light_client_proof_data(&self) -> Result<Option<(u64, StoredLightClientProof)>>;
 }
 
 /// Ledger operations for the prover service
@@ -234,6 +400,33 @@ pub trait SequencerLedgerOps: SharedLedgerOps {
     /// Delete a pending commitment l2 range
     fn delete_pending_commitment_l2_range(&self, l2_range: &L2HeightRange) -> Result<()>;
 
+    /// Records a commitment submitted to DA, or updates its record
+    fn put_commitment_submission(
+        &self,
+        l2_range: &L2HeightRange,
+        submission: &StoredCommitmentSubmission,
+    ) -> Result<()>;
+
+    /// Gets the record of the commitment submitted for the given L2 range
+    fn get_commitment_submission(
+        &self,
+        l2_range: &L2HeightRange,
+    ) -> Result<Option<StoredCommitmentSubmission>>;
+
+    /// Gets the records of the submitted commitments, newest first.
+    /// Skips the `offset` newest records and returns at most `limit` records.
+    fn get_commitment_submissions(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<(L2HeightRange, StoredCommitmentSubmission)>>;
+
+    /// Gets the records of the submitted commitments newer than the newest confirmed one,
+    /// oldest first
+    fn get_unconfirmed_commitment_submissions(
+        &self,
+    ) -> Result<Vec<(L2HeightRange, StoredCommitmentSubmission)>>;
+
     /// Sets the latest state diff
     fn set_state_diff(&self, state_diff: &StateDiff) -> Result<()>;
 
@@ -248,6 +441,22 @@ pub trait SequencerLedgerOps: SharedLedgerOps {
 
     /// Fetch mempool transactions
     fn get_mempool_txs(&self) -> anyhow::Result<Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// Records the ids of the deposits included in the soft confirmation at `l2_height`
+    fn put_included_deposit_ids(
+        &self,
+        deposit_ids: &[[u8; 32]],
+        l2_height: SoftConfirmationNumber,
+    ) -> anyhow::Result<()>;
+
+    /// Gets the height of the soft confirmation a deposit was included in, if any
+    fn get_deposit_inclusion_height(
+        &self,
+        deposit_id: &[u8; 32],
+    ) -> anyhow::Result<Option<SoftConfirmationNumber>>;
+
+    /// Forgets the ids of deposits, e.g. of rolled back soft confirmations
+    fn remove_included_deposit_ids(&self, deposit_ids: &[[u8; 32]]) -> anyhow::Result<()>;
 }
 
 /// Test ledger operations
@@ -41,6 +41,21 @@ impl<Q> NativeDB<Q> {
             &raw_options,
         )
     }
+
+    /// Deletes the values written after `version`
+    pub fn rollback_schema_db(db: &sov_schema_db::DB, version: Version) -> anyhow::Result<()> {
+        let mut batch = SchemaBatch::new();
+        let mut iter = db.iter::<ModuleAccessoryState>()?;
+        iter.seek_to_first();
+        for item in iter {
+            let key = item?.key;
+            if key.1 > version {
+                batch.delete::<ModuleAccessoryState>(&key)?;
+            }
+        }
+        db.write_schemas(batch)
+    }
+
     /// Convert it to [`ReadOnlyDbSnapshot`] which cannot be edited anymore
     pub fn freeze(self) -> anyhow::Result<ReadOnlyDbSnapshot> {
         let inner = Arc::into_inner(self.db).ok_or(anyhow::anyhow!(
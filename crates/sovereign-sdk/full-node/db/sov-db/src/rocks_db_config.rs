@@ -1,12 +1,112 @@
 // Adapted from Aptos-Core.
 // Modified to remove serde dependency
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::str::FromStr;
 
+use anyhow::{anyhow, bail};
 use rlimit::{getrlimit, Resource};
-use rocksdb::{BlockBasedOptions, Cache, Options};
+use rocksdb::{BlockBasedOptions, Cache, DBCompressionType, Options};
+use serde::{Deserialize, Serialize};
 use sov_schema_db::RawRocksdbOptions;
 use tracing::warn;
 
+/// Once write-ahead logs exceed this size, flushes of the oldest memtables are forced.
+const MAX_TOTAL_WAL_SIZE: u64 = 1 << 30;
+/// The default maximum number of background threads for flushing and compaction.
+const DEFAULT_MAX_BACKGROUND_JOBS: i32 = 16;
+/// The default capacity of the block cache.
+const DEFAULT_BLOCK_CACHE_SIZE: usize = 100 * 1024 * 1024;
+
+/// Compression of the data of a column family
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RocksdbCompression {
+    /// No compression
+    None,
+    /// LZ4 compression
+    #[default]
+    Lz4,
+    /// Zstandard compression, slower than lz4 with a better ratio
+    Zstd,
+}
+
+impl From<RocksdbCompression> for DBCompressionType {
+    fn from(compression: RocksdbCompression) -> Self {
+        match compression {
+            RocksdbCompression::None => DBCompressionType::None,
+            RocksdbCompression::Lz4 => DBCompressionType::Lz4,
+            RocksdbCompression::Zstd => DBCompressionType::Zstd,
+        }
+    }
+}
+
+impl FromStr for RocksdbCompression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "lz4" => Ok(Self::Lz4),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(anyhow!("Unknown rocksdb compression {}", s)),
+        }
+    }
+}
+
+/// User provided tuning of a RocksDB instance. Unset fields keep the defaults of [`RocksdbConfig`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RocksdbTuning {
+    /// Size of a single memtable of a column family in bytes
+    pub write_buffer_size: Option<usize>,
+    /// The maximum number of background threads, including threads for flushing and compaction
+    pub max_background_jobs: Option<i32>,
+    /// Capacity of the block cache in bytes
+    pub block_cache_size: Option<usize>,
+    /// Compression of the column families
+    pub compression: Option<RocksdbCompression>,
+    /// Compression of individual column families, overriding `compression`
+    #[serde(default)]
+    pub column_family_compression: BTreeMap<String, RocksdbCompression>,
+}
+
+impl RocksdbTuning {
+    /// Checks the tuning of a db with the given column families,
+    /// returning an error naming the invalid field if any.
+    pub fn validate(&self, column_families: &[&str]) -> anyhow::Result<()> {
+        if let Some(write_buffer_size) = self.write_buffer_size {
+            if write_buffer_size == 0 {
+                bail!("write_buffer_size must be greater than 0");
+            }
+            // Memtables would be flushed by the WAL size limit before they are full
+            if write_buffer_size as u64 > MAX_TOTAL_WAL_SIZE {
+                bail!(
+                    "write_buffer_size {} exceeds the max total WAL size {}",
+                    write_buffer_size,
+                    MAX_TOTAL_WAL_SIZE
+                );
+            }
+        }
+        if matches!(self.max_background_jobs, Some(jobs) if jobs < 1) {
+            bail!("max_background_jobs must be at least 1");
+        }
+        if self.block_cache_size == Some(0) {
+            bail!("block_cache_size must be greater than 0");
+        }
+        if let Some(cf_name) = self
+            .column_family_compression
+            .keys()
+            .find(|cf_name| !column_families.contains(&cf_name.as_str()))
+        {
+            bail!(
+                "column_family_compression has unknown column family {}",
+                cf_name
+            );
+        }
+        Ok(())
+    }
+}
+
 /// Port selected RocksDB options for tuning underlying rocksdb instance of our state db.
 /// The current default values are taken from Aptos. TODO: tune rocksdb for our workload.
 /// see <https://github.com/facebook/rocksdb/blob/master/include/rocksdb/options.h>
@@ -25,6 +125,14 @@ pub struct RocksdbConfig<'a> {
     pub max_background_jobs: i32,
     /// Provide a custom list of column families to use in Rocksdb
     pub column_families: Option<Vec<String>>,
+    /// Size of a single memtable of a column family. Defaults to the RocksDB default of 64MB.
+    pub write_buffer_size: Option<usize>,
+    /// Capacity of the block cache. Defaults to 100MB.
+    pub block_cache_size: usize,
+    /// Compression of the column families. Defaults to lz4.
+    pub compression: RocksdbCompression,
+    /// Compression of individual column families, overriding `compression`
+    pub column_family_compression: BTreeMap<String, RocksdbCompression>,
 }
 
 impl<'a> RocksdbConfig<'a> {
@@ -41,12 +149,35 @@ impl<'a> RocksdbConfig<'a> {
             max_open_files,
             // For now we set the max total WAL size to be 1G. This config can be useful when column
             // families are updated at non-uniform frequencies.
-            max_total_wal_size: 1u64 << 30,
+            max_total_wal_size: MAX_TOTAL_WAL_SIZE,
             // This includes threads for flushing and compaction. Rocksdb will decide the # of
             // threads to use internally.
-            max_background_jobs: 16,
+            max_background_jobs: DEFAULT_MAX_BACKGROUND_JOBS,
             column_families,
+            write_buffer_size: None,
+            block_cache_size: DEFAULT_BLOCK_CACHE_SIZE,
+            compression: RocksdbCompression::Lz4,
+            column_family_compression: BTreeMap::new(),
+        }
+    }
+
+    /// Overrides the defaults with the fields set in `tuning`
+    pub fn with_tuning(mut self, tuning: &RocksdbTuning) -> Self {
+        if let Some(write_buffer_size) = tuning.write_buffer_size {
+            self.write_buffer_size = Some(write_buffer_size);
+        }
+        if let Some(max_background_jobs) = tuning.max_background_jobs {
+            self.max_background_jobs = max_background_jobs;
+        }
+        if let Some(block_cache_size) = tuning.block_cache_size {
+            self.block_cache_size = block_cache_size;
+        }
+        if let Some(compression) = tuning.compression {
+            self.compression = compression;
         }
+        self.column_family_compression
+            .extend(tuning.column_family_compression.clone());
+        self
     }
 
     /// Build [`RawRocksdbOptions`] from [`RocksdbConfig`]
@@ -64,7 +195,7 @@ impl<'a> RocksdbConfig<'a> {
         // However, the rocksdb rust binding does not expose this functionality. This means that we
         // could still have OOM errors when trying to allocate more for the cache even if the capacity
         // is reached.
-        let cache = Cache::new_lru_cache(100 * 1024 * 1024); // 100 MB
+        let cache = Cache::new_lru_cache(self.block_cache_size);
         block_options.set_block_cache(&cache);
         // jemalloc friendly bloom filter sizing
         block_options.set_optimize_filters_for_memory(true);
@@ -74,7 +205,7 @@ impl<'a> RocksdbConfig<'a> {
         block_options.set_block_size(32 * 1024);
         // Default is Snappy but Lz4 is recommend
         // https://github.com/facebook/rocksdb/wiki/Compression
-        db_options.set_compression_type(rocksdb::DBCompressionType::Lz4);
+        db_options.set_compression_type(self.compression.into());
 
         let allowed_cores = std::cmp::max(1, num_cpus::get() / 2) as i32;
         db_options.set_compression_options_parallel_threads(allowed_cores);
@@ -91,6 +222,13 @@ impl<'a> RocksdbConfig<'a> {
         RawRocksdbOptions {
             db_options,
             block_options,
+            compression: self.compression.into(),
+            column_family_compression: self
+                .column_family_compression
+                .iter()
+                .map(|(cf_name, compression)| (cf_name.clone(), (*compression).into()))
+                .collect(),
+            write_buffer_size: self.write_buffer_size,
         }
     }
 }
@@ -19,8 +19,9 @@ use sov_schema_db::{CodecError, SeekKeyEncoder};
 
 use super::types::{
     AccessoryKey, AccessoryStateValue, DbHash, JmtValue, L2HeightRange, SlotNumber,
-    SoftConfirmationNumber, StateKey, StoredBatchProof, StoredLightClientProof,
-    StoredSoftConfirmation, StoredVerifiedProof,
+    SoftConfirmationNumber, StateKey, StoredBatchProof, StoredBatchProofSimulation,
+    StoredCommitmentSubmission, StoredLightClientProof, StoredProvingSessionStatus,
+    StoredSoftConfirmation, StoredStateDiffSize, StoredVerifiedProof,
 };
 
 /// A list of all tables used by the StateDB. These tables store rollup state - meaning
@@ -36,8 +37,10 @@ pub const STATE_TABLES: &[&str] = &[
 pub const LEDGER_TABLES: &[&str] = &[
     ExecutedMigrations::table_name(),
     SlotByHash::table_name(),
+    SlotHashByNumber::table_name(),
     SoftConfirmationByNumber::table_name(),
     SoftConfirmationByHash::table_name(),
+    StagedSoftConfirmations::table_name(),
     L2RangeByL1Height::table_name(),
     L2Witness::table_name(),
     L2GenesisStateRoot::table_name(),
@@ -45,16 +48,24 @@ pub const LEDGER_TABLES: &[&str] = &[
     LightClientProofBySlotNumber::table_name(),
     PendingSequencerCommitmentL2Range::table_name(),
     LastSequencerCommitmentSent::table_name(),
+    CommitmentSubmissions::table_name(),
     ProverLastScannedSlot::table_name(),
     SoftConfirmationStatus::table_name(),
     CommitmentsByNumber::table_name(),
+    CommitmentsByL2EndHeight::table_name(),
+    CommitmentSenders::table_name(),
     ProofsBySlotNumber::table_name(),
     ProofsBySlotNumberV2::table_name(),
     VerifiedBatchProofsBySlotNumber::table_name(),
     MempoolTxs::table_name(),
+    IncludedDepositIds::table_name(),
     PendingProvingSessions::table_name(),
+    BatchProvingSessions::table_name(),
+    BatchProofSimulations::table_name(),
     ProverStateDiffs::table_name(),
+    StateDiffSizes::table_name(),
     LastPrunedBlock::table_name(),
+    LastTxBodyBackfillBlock::table_name(),
     #[cfg(test)]
     TestTableOld::table_name(),
     #[cfg(test)]
@@ -222,16 +233,172 @@ define_table_with_default_codec!(
     (SlotByHash) DbHash => SlotNumber
 );
 
+define_table_with_default_codec!(
+    /// The hash of the L1 block processed at each height, used to detect DA reorgs
+    (SlotHashByNumber) SlotNumber => DbHash
+);
+
 define_table_with_default_codec!(
     /// The primary source for sequencer commitment data
     (CommitmentsByNumber) SlotNumber => Vec<SequencerCommitment>
 );
 
 define_table_with_seek_key_codec!(
+    /// Sequencer commitments indexed by the last L2 height they cover,
+    /// along with the L1 height they were found in
+    (CommitmentsByL2EndHeight) SoftConfirmationNumber => (SlotNumber, SequencerCommitment)
+);
+
+define_table_with_seek_key_codec!(
+    /// DA public keys of the senders of the processed sequencer commitments,
+    /// indexed by the L1 height and the last L2 height of the commitment
+    (CommitmentSenders) (SlotNumber, SoftConfirmationNumber) => Vec<u8>
+);
+
+define_table_without_codec!(
     /// The primary source for soft confirmation data
     (SoftConfirmationByNumber) SoftConfirmationNumber => StoredSoftConfirmation
 );
 
+// Same key codec as the tables defined with `define_table_with_seek_key_codec`,
+// the value codec compresses the tx bodies
+impl KeyEncoder<SoftConfirmationByNumber> for SoftConfirmationNumber {
+    fn encode_key(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        use anyhow::Context as _;
+        use bincode::Options as _;
+
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_big_endian()
+            .serialize(self)
+            .context("Failed to serialize key")
+            .map_err(Into::into)
+    }
+}
+
+impl KeyDecoder<SoftConfirmationByNumber> for SoftConfirmationNumber {
+    fn decode_key(data: &[u8]) -> sov_schema_db::schema::Result<Self> {
+        use anyhow::Context as _;
+        use bincode::Options as _;
+
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_big_endian()
+            .deserialize_from(&mut &data[..])
+            .context("Failed to deserialize key")
+            .map_err(Into::into)
+    }
+}
+
+impl SeekKeyEncoder<SoftConfirmationByNumber> for SoftConfirmationNumber {
+    fn encode_seek_key(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        <Self as KeyEncoder<SoftConfirmationByNumber>>::encode_key(self)
+    }
+}
+
+/// Tx bodies of a soft confirmation are compressed together
+/// once their total size exceeds this many bytes.
+pub const TX_BODIES_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Flag byte following a soft confirmation whose tx bodies are compressed.
+/// Soft confirmations stored as is, including the ones stored before tx body
+/// compression was introduced, have no flag byte.
+const TX_BODIES_COMPRESSED: u8 = 1;
+
+/// Brotli quality and window size of the tx body compression. Soft confirmations are
+/// stored on the block production path, so a fast level is used instead of the one of DA blobs.
+const TX_BODIES_COMPRESSION_QUALITY: u32 = 4;
+const TX_BODIES_COMPRESSION_LGWIN: u32 = 20;
+
+fn compress_tx_bodies(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut writer = brotli::CompressorWriter::new(
+        Vec::new(),
+        4096,
+        TX_BODIES_COMPRESSION_QUALITY,
+        TX_BODIES_COMPRESSION_LGWIN,
+    );
+    writer.write_all(data)?;
+    Ok(writer.into_inner())
+}
+
+fn decompress_tx_bodies(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut decompressed = Vec::new();
+    brotli::Decompressor::new(data, 4096).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+impl ValueCodec<SoftConfirmationByNumber> for StoredSoftConfirmation {
+    fn encode_value(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        // Tx bodies are only compressed if all of them are stored
+        let Some(tx_bodies) = self
+            .txs
+            .iter()
+            .map(|tx| tx.body.as_deref())
+            .collect::<Option<Vec<&[u8]>>>()
+        else {
+            return borsh::to_vec(self).map_err(CodecError::from);
+        };
+        let tx_bodies_size: usize = tx_bodies.iter().map(|body| body.len()).sum();
+        if tx_bodies_size <= TX_BODIES_COMPRESSION_THRESHOLD {
+            return borsh::to_vec(self).map_err(CodecError::from);
+        }
+
+        let compressed_tx_bodies = compress_tx_bodies(&borsh::to_vec(&tx_bodies)?)?;
+
+        let mut without_tx_bodies = self.clone();
+        for tx in without_tx_bodies.txs.iter_mut() {
+            tx.body = None;
+        }
+        let mut value = borsh::to_vec(&without_tx_bodies)?;
+        value.push(TX_BODIES_COMPRESSED);
+        value.extend(compressed_tx_bodies);
+        Ok(value)
+    }
+
+    fn decode_value(data: &[u8]) -> sov_schema_db::schema::Result<Self> {
+        let mut reader = data;
+        let mut soft_confirmation: Self = BorshDeserialize::deserialize_reader(&mut reader)?;
+        match reader.split_first() {
+            None => {}
+            Some((&TX_BODIES_COMPRESSED, compressed_tx_bodies)) => {
+                let tx_bodies: Vec<Vec<u8>> =
+                    borsh::from_slice(&decompress_tx_bodies(compressed_tx_bodies)?)?;
+                if tx_bodies.len() != soft_confirmation.txs.len() {
+                    return Err(anyhow::anyhow!(
+                        "Soft confirmation #{} has {} txs but {} compressed tx bodies",
+                        soft_confirmation.l2_height,
+                        soft_confirmation.txs.len(),
+                        tx_bodies.len()
+                    )
+                    .into());
+                }
+                for (tx, body) in soft_confirmation.txs.iter_mut().zip(tx_bodies) {
+                    tx.body = Some(body);
+                }
+            }
+            Some((flag, _)) => {
+                return Err(anyhow::anyhow!(
+                    "Unknown tx bodies flag {} of soft confirmation #{}",
+                    flag,
+                    soft_confirmation.l2_height
+                )
+                .into());
+            }
+        }
+        Ok(soft_confirmation)
+    }
+}
+
+define_table_with_seek_key_codec!(
+    /// Soft confirmations whose state is being finalized but whose ledger data is not committed yet.
+    /// Replayed on startup if the node stopped between finalizing the state and committing the ledger.
+    (StagedSoftConfirmations) SoftConfirmationNumber => StoredSoftConfirmation
+);
+
 define_table_with_default_codec!(
     /// A "secondary index" for soft confirmation data by hash
     (SoftConfirmationByHash) DbHash => SoftConfirmationNumber
@@ -262,6 +429,11 @@ define_table_with_seek_key_codec!(
     (LastSequencerCommitmentSent) () => SoftConfirmationNumber
 );
 
+define_table_with_seek_key_codec!(
+    /// Sequencer uses this table to record the commitments it submitted to DA, by their L2 range
+    (CommitmentSubmissions) L2HeightRange => StoredCommitmentSubmission
+);
+
 define_table_with_seek_key_codec!(
     /// Prover uses this table to store the last slot it scanned
     /// Full node also uses this table to store the last slot it scanned
@@ -295,7 +467,7 @@ define_table_with_default_codec!(
     (ProofsBySlotNumberV2) SlotNumber => Vec<StoredBatchProof>
 );
 
-define_table_with_default_codec!(
+define_table_with_seek_key_codec!(
     /// Proof data on L1 slot verified by full node
     (VerifiedBatchProofsBySlotNumber) SlotNumber => Vec<StoredVerifiedProof>
 );
@@ -306,21 +478,48 @@ define_table_with_seek_key_codec!(
     (PendingProvingSessions) Vec<u8> => ()
 );
 
+define_table_with_seek_key_codec!(
+    /// Batch prover uses this table to store its proving sessions by l1 height and commitment range
+    /// If the proof of a session is stored after DA submission, remove it
+    (BatchProvingSessions) (SlotNumber, (u32, u32)) => StoredProvingSessionStatus
+);
+
+define_table_with_seek_key_codec!(
+    /// Batch prover in simulate mode stores here whether the commitment ranges would prove,
+    /// by l1 height and commitment range
+    (BatchProofSimulations) (SlotNumber, (u32, u32)) => StoredBatchProofSimulation
+);
+
 define_table_with_default_codec!(
     /// Transactions in mempool (TxHash, TxData)
     (MempoolTxs) Vec<u8> => Vec<u8>
 );
 
+define_table_with_default_codec!(
+    /// Ids of the deposits included by the sequencer, with the L2 height including them
+    (IncludedDepositIds) DbHash => SoftConfirmationNumber
+);
+
 define_table_with_default_codec!(
     /// L2 height to state diff for prover
     (ProverStateDiffs) SoftConfirmationNumber => StateDiff
 );
 
+define_table_with_default_codec!(
+    /// Size of the state diff of each soft confirmation, recorded when it is produced or applied
+    (StateDiffSizes) SoftConfirmationNumber => StoredStateDiffSize
+);
+
 define_table_with_seek_key_codec!(
     /// Stores the last pruned L2 block number
     (LastPrunedBlock) () => u64
 );
 
+define_table_with_seek_key_codec!(
+    /// Stores the last L2 block number whose transaction bodies were backfilled
+    (LastTxBodyBackfillBlock) () => u64
+);
+
 #[cfg(test)]
 define_table_with_seek_key_codec!(
     /// Test table old
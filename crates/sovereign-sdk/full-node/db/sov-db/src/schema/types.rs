@@ -1,13 +1,18 @@
 use std::fmt::Debug;
 use std::sync::Arc;
 
+use borsh::io::{Read, Write};
 use borsh::{BorshDeserialize, BorshSerialize};
 use sov_rollup_interface::rpc::{
     BatchProofOutputRpcResponse, BatchProofResponse, HexTx, LightClientProofOutputRpcResponse,
     LightClientProofResponse, SoftConfirmationResponse, VerifiedBatchProofResponse,
 };
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmation;
-use sov_rollup_interface::zk::{BatchProofInfo, CumulativeStateDiff, Proof};
+use sov_rollup_interface::zk::{
+    BatchProofInfo, BatchProofOutputVersion, CumulativeStateDiff, Proof,
+};
+
+use crate::ledger_db::migrations::legacy_types::StoredBatchProofOutputV2;
 
 /// A cheaply cloneable bytes abstraction for use within the trust boundary of the node
 /// (i.e. when interfacing with the database). Serializes and deserializes more efficiently,
@@ -120,9 +125,16 @@ impl From<StoredLightClientProof> for LightClientProofResponse {
     }
 }
 
+/// Prefix of the encoded [`StoredBatchProofOutput`]. Never the first byte of the untagged
+/// [`StoredBatchProofOutputV2`], which is the length of the 32 byte initial state root.
+const STORED_BATCH_PROOF_OUTPUT_TAG: u8 = u8::MAX;
+
 /// The on-disk format for a state transition.
-#[derive(Debug, PartialEq, BorshDeserialize, BorshSerialize, Clone)]
+/// Outputs stored before the version was tagged are decoded as [`BatchProofOutputVersion::V1`].
+#[derive(Debug, PartialEq, Clone)]
 pub struct StoredBatchProofOutput {
+    /// The layout of the circuit output the fields were read from
+    pub output_version: BatchProofOutputVersion,
     /// The state of the rollup before the transition
     pub initial_state_root: Vec<u8>,
     /// The state of the rollup after the transition
@@ -146,6 +158,70 @@ pub struct StoredBatchProofOutput {
     pub preproven_commitments: Vec<usize>,
     /// The last processed l2 height in the processed sequencer commitments.
     pub last_l2_height: u64,
+    /// The first processed l2 height in the processed sequencer commitments.
+    /// Zeroed for outputs which do not commit to it, see [`BatchProofOutputVersion::has_first_l2_height`].
+    pub first_l2_height: u64,
+    /// The method id the proof was verified against.
+    /// `None` for proofs stored before it was recorded.
+    pub verified_method_id: Option<[u32; 8]>,
+}
+
+impl BorshSerialize for StoredBatchProofOutput {
+    fn serialize<W: Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        STORED_BATCH_PROOF_OUTPUT_TAG.serialize(writer)?;
+        self.output_version.serialize(writer)?;
+        self.initial_state_root.serialize(writer)?;
+        self.final_state_root.serialize(writer)?;
+        self.prev_soft_confirmation_hash.serialize(writer)?;
+        self.final_soft_confirmation_hash.serialize(writer)?;
+        self.state_diff.serialize(writer)?;
+        self.da_slot_hash.serialize(writer)?;
+        self.sequencer_commitments_range.serialize(writer)?;
+        self.sequencer_public_key.serialize(writer)?;
+        self.sequencer_da_public_key.serialize(writer)?;
+        self.preproven_commitments.serialize(writer)?;
+        self.last_l2_height.serialize(writer)?;
+        self.verified_method_id.serialize(writer)?;
+        // Only written for the layouts which have it, so older entries stay decodable
+        if self.output_version.has_first_l2_height() {
+            self.first_l2_height.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for StoredBatchProofOutput {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let tag = u8::deserialize_reader(reader)?;
+        if tag != STORED_BATCH_PROOF_OUTPUT_TAG {
+            // The byte read belongs to the untagged output
+            let tag = [tag];
+            let mut reader = tag.as_slice().chain(reader);
+            return StoredBatchProofOutputV2::deserialize_reader(&mut reader).map(Into::into);
+        }
+
+        let output_version: BatchProofOutputVersion = BorshDeserialize::deserialize_reader(reader)?;
+        let mut output = Self {
+            output_version,
+            initial_state_root: BorshDeserialize::deserialize_reader(reader)?,
+            final_state_root: BorshDeserialize::deserialize_reader(reader)?,
+            prev_soft_confirmation_hash: BorshDeserialize::deserialize_reader(reader)?,
+            final_soft_confirmation_hash: BorshDeserialize::deserialize_reader(reader)?,
+            state_diff: BorshDeserialize::deserialize_reader(reader)?,
+            da_slot_hash: BorshDeserialize::deserialize_reader(reader)?,
+            sequencer_commitments_range: BorshDeserialize::deserialize_reader(reader)?,
+            sequencer_public_key: BorshDeserialize::deserialize_reader(reader)?,
+            sequencer_da_public_key: BorshDeserialize::deserialize_reader(reader)?,
+            preproven_commitments: BorshDeserialize::deserialize_reader(reader)?,
+            last_l2_height: BorshDeserialize::deserialize_reader(reader)?,
+            verified_method_id: BorshDeserialize::deserialize_reader(reader)?,
+            first_l2_height: 0,
+        };
+        if output_version.has_first_l2_height() {
+            output.first_l2_height = BorshDeserialize::deserialize_reader(reader)?;
+        }
+        Ok(output)
+    }
 }
 
 /// The on-disk format for a proof. Stores the tx id of the proof sent to da, proof data and state transition
@@ -169,6 +245,24 @@ impl From<StoredBatchProof> for BatchProofResponse {
     }
 }
 
+/// Progress of a batch proving session, kept until its proof is stored after DA submission
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub enum StoredProvingSessionStatus {
+    /// The proof is being generated
+    Proving,
+    /// The proof is generated but not submitted to DA yet
+    Proven(Proof),
+}
+
+/// Result of simulating the batch proof of a commitment range, without proving it
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct StoredBatchProofSimulation {
+    /// Cycles the circuit took, if measured
+    pub cycles: Option<u64>,
+    /// Why the circuit rejected its input, `None` if the range would prove
+    pub failure: Option<String>,
+}
+
 /// The on-disk format for a proof verified by full node. Stores proof data and state transition
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
 pub struct StoredVerifiedProof {
@@ -190,6 +284,7 @@ impl From<StoredVerifiedProof> for VerifiedBatchProofResponse {
 impl From<StoredBatchProofOutput> for BatchProofOutputRpcResponse {
     fn from(value: StoredBatchProofOutput) -> Self {
         Self {
+            output_version: value.output_version,
             initial_state_root: value.initial_state_root,
             final_state_root: value.final_state_root,
             state_diff: value.state_diff,
@@ -201,13 +296,14 @@ impl From<StoredBatchProofOutput> for BatchProofOutputRpcResponse {
             prev_soft_confirmation_hash: value.prev_soft_confirmation_hash,
             final_soft_confirmation_hash: value.final_soft_confirmation_hash,
             last_l2_height: value.last_l2_height,
+            first_l2_height: value.first_l2_height,
         }
     }
 }
 
 /// The on-disk format for a batch. Stores the hash and identifies the range of transactions
 /// included in the batch.
-#[derive(Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+#[derive(Debug, PartialEq, BorshDeserialize, BorshSerialize, Clone)]
 pub struct StoredSoftConfirmation {
     /// The l2 height of the soft confirmation
     pub l2_height: u64,
@@ -301,10 +397,35 @@ impl TryFrom<StoredSoftConfirmation> for SoftConfirmationResponse {
                 .collect(),
             l1_fee_rate: value.l1_fee_rate,
             timestamp: value.timestamp,
+            tx_summaries: None,
         })
     }
 }
 
+/// The on-disk format of the state diff size of a soft confirmation
+#[derive(Debug, PartialEq, Eq, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct StoredStateDiffSize {
+    /// Size of the borsh serialized state diff
+    pub size: u64,
+    /// Size of the serialized state diff once compressed, as it would be written to the DA
+    pub compressed_size: u64,
+}
+
+/// The on-disk format of a sequencer commitment submitted to DA, recorded by the sequencer
+#[derive(Debug, PartialEq, Eq, BorshDeserialize, BorshSerialize, Clone)]
+pub struct StoredCommitmentSubmission {
+    /// Merkle root of the soft confirmation hashes of the commitment
+    pub merkle_root: DbHash,
+    /// Ids of the DA transactions the commitment was sent with
+    pub da_tx_ids: Vec<DbHash>,
+    /// Unix timestamp of the submission, in seconds
+    pub submitted_at: u64,
+    /// DA fee rate at the time of the submission, if it could be fetched
+    pub fee_rate: Option<u128>,
+    /// L1 height the commitment was found in, once it is in a finalized L1 block
+    pub confirmed_in_l1: Option<u64>,
+}
+
 /// The on-disk format of a transaction. Includes the txhash, the serialized tx data,
 /// and identifies the events emitted by this transaction
 #[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize, Clone)]
@@ -22,6 +22,9 @@ pub struct StateDB<Q> {
     /// This [`Version`] is also used for querying data,
     /// so if this instance of StateDB is used as read only, it won't see newer data.
     next_version: Arc<Mutex<Version>>,
+    /// The [`Version`] used for querying data instead of `next_version`, if set.
+    /// Set when re-executing an already committed version, whose own writes must not be seen.
+    read_version: Option<Version>,
 }
 
 // Manual implementation of [`Clone`] to satisfy compiler
@@ -30,6 +33,7 @@ impl<Q> Clone for StateDB<Q> {
         StateDB {
             db: self.db.clone(),
             next_version: self.next_version.clone(),
+            read_version: self.read_version,
         }
     }
 }
@@ -50,6 +54,96 @@ impl<Q> StateDB<Q> {
         )
     }
 
+    /// Open the state [`sov_schema_db::DB`] in read-only mode.
+    /// This does not take the database lock, so it can be used next to the instance opened by the node.
+    /// Writes made after opening are not visible to the returned instance.
+    pub fn setup_read_only_schema_db(cfg: &RocksdbConfig) -> anyhow::Result<sov_schema_db::DB> {
+        let raw_options = cfg.as_raw_options(true);
+        let state_db_path = cfg.path.join(Self::DB_PATH_SUFFIX);
+        sov_schema_db::DB::open_cf_readonly(
+            &raw_options.db_options,
+            state_db_path,
+            Self::DB_NAME,
+            STATE_TABLES.to_vec(),
+        )
+    }
+
+    /// Iterates over the keys of length `key_len` which start with `prefix`, in key order.
+    /// Every key is yielded once whatever the number of versions it was written at,
+    /// so keys which have no value at a given version are yielded too.
+    pub fn iter_keys<'a>(
+        db: &'a sov_schema_db::DB,
+        prefix: &[u8],
+        key_len: usize,
+    ) -> anyhow::Result<impl Iterator<Item = anyhow::Result<StateKey>> + 'a> {
+        anyhow::ensure!(
+            prefix.len() <= key_len,
+            "Prefix of {} bytes is longer than the keys",
+            prefix.len()
+        );
+        let prefix = prefix.to_vec();
+        let mut first_key = prefix.clone();
+        first_key.resize(key_len, 0);
+
+        // Keys are encoded with their length first, so keys of the same length are sorted by their bytes
+        let mut iter = db.iter::<JmtValues>()?;
+        iter.seek(&(first_key, 0))?;
+
+        let mut last_key: Option<StateKey> = None;
+        Ok(std::iter::from_fn(move || loop {
+            let key = match iter.next()? {
+                Ok(item) => item.key.0,
+                Err(e) => return Some(Err(e)),
+            };
+            if key.len() != key_len || !key.starts_with(&prefix) {
+                return None;
+            }
+            if last_key.as_ref() != Some(&key) {
+                last_key = Some(key.clone());
+                return Some(Ok(key));
+            }
+        })
+        .fuse())
+    }
+
+    /// Returns the latest version of the state written to `db`, None if nothing was written yet.
+    pub fn latest_version_of_schema_db(db: &sov_schema_db::DB) -> anyhow::Result<Option<Version>> {
+        // Node keys start with the big endian version, so newer nodes are at the end
+        let mut iter = db.iter::<JmtNodes>()?.rev();
+        iter.seek_to_last();
+        iter.next()
+            .transpose()
+            .map(|item| item.map(|item| item.key.version()))
+    }
+
+    /// Deletes the JMT nodes and values written after `version`,
+    /// so that `version` becomes the latest version of the state.
+    pub fn rollback_schema_db(db: &sov_schema_db::DB, version: Version) -> anyhow::Result<()> {
+        let mut batch = SchemaBatch::new();
+
+        // Node keys start with the big endian version, so newer nodes are at the end
+        let mut iter = db.iter::<JmtNodes>()?.rev();
+        iter.seek_to_last();
+        for item in iter {
+            let key = item?.key;
+            if key.version() <= version {
+                break;
+            }
+            batch.delete::<JmtNodes>(&key)?;
+        }
+
+        let mut iter = db.iter::<JmtValues>()?;
+        iter.seek_to_first();
+        for item in iter {
+            let key = item?.key;
+            if key.1 > version {
+                batch.delete::<JmtValues>(&key)?;
+            }
+        }
+
+        db.write_schemas(batch)
+    }
+
     /// Convert it to [`ReadOnlyDbSnapshot`] which cannot be edited anymore
     pub fn freeze(self) -> anyhow::Result<ReadOnlyDbSnapshot> {
         let inner = Arc::into_inner(self.db).ok_or(anyhow::anyhow!(
@@ -66,6 +160,7 @@ impl<Q: QueryManager> StateDB<Q> {
         Ok(Self {
             db: Arc::new(db_snapshot),
             next_version: Arc::new(Mutex::new(next_version)),
+            read_version: None,
         })
     }
 
@@ -121,6 +216,19 @@ impl<Q: QueryManager> StateDB<Q> {
         *version = u64::MAX - 1;
     }
 
+    /// Makes this instance see the state as of `version`, with the next batch of writes
+    /// computed on top of it. Newer versions already in the DB are not seen.
+    pub fn pin_to_version(&mut self, version: Version) {
+        let mut next_version = self.next_version.lock().unwrap();
+        *next_version = version + 1;
+        self.read_version = Some(version);
+    }
+
+    /// Get the [`Version`] data is queried at
+    pub fn get_read_version(&self) -> Version {
+        self.read_version.unwrap_or_else(|| self.get_next_version())
+    }
+
     fn next_version_from(db_snapshot: &DbSnapshot<Q>) -> anyhow::Result<Version> {
         let last_key_value = db_snapshot.get_largest::<JmtNodes>()?;
         let largest_version = last_key_value.map(|(k, _)| k.version());
@@ -108,7 +108,7 @@ where
         let raw_value = self.db_iter.value().expect("db_iter.value() failed.");
         let value_size_bytes = raw_value.len();
 
-        histogram!("schemadb_iter_bytes", "cf_name" => S::COLUMN_FAMILY_NAME)
+        histogram!("citrea_schemadb_iter_bytes", "cf_name" => S::COLUMN_FAMILY_NAME)
             .record((raw_key.len() + raw_value.len()) as f64);
 
         let key = <S::Key as KeyDecoder<S>>::decode_key(raw_key)?;
@@ -119,7 +119,7 @@ where
             ScanDirection::Backward => self.db_iter.prev(),
         }
 
-        histogram!("schemadb_iter_latency_seconds", "cf_name" => S::COLUMN_FAMILY_NAME).record(
+        histogram!("citrea_schemadb_iter_latency_seconds", "cf_name" => S::COLUMN_FAMILY_NAME).record(
             Instant::now()
                 .saturating_duration_since(start)
                 .as_secs_f64(),
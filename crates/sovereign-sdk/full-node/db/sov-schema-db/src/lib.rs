@@ -22,6 +22,7 @@ pub mod snapshot;
 #[cfg(feature = "test-utils")]
 pub mod test;
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::time::Instant;
 
@@ -49,8 +50,8 @@ pub struct DB {
 }
 
 impl DB {
-    /// Opens a database backed by RocksDB, using the provided column family names and default
-    /// column family options.
+    /// Opens a database backed by RocksDB, using the provided column family names and the
+    /// column family options of `options`.
     pub fn open(
         path: impl AsRef<Path>,
         name: &'static str,
@@ -62,9 +63,8 @@ impl DB {
             path,
             name,
             column_families.into_iter().map(|cf_name| {
-                let mut cf_opts = rocksdb::Options::default();
-                cf_opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
-                cf_opts.set_block_based_table_factory(&options.block_options);
+                let cf_name: String = cf_name.into();
+                let cf_opts = options.column_family_options(&cf_name);
                 rocksdb::ColumnFamilyDescriptor::new(cf_name, cf_opts)
             }),
         )?;
@@ -143,7 +143,7 @@ impl DB {
 
         let result = self.inner.get_pinned_cf(cf_handle, k)?;
 
-        histogram!("schemadb_get_bytes", "cf_name" => S::COLUMN_FAMILY_NAME)
+        histogram!("citrea_schemadb_get_bytes", "cf_name" => S::COLUMN_FAMILY_NAME)
             .record(result.as_ref().map_or(0.0, |v| v.len() as f64));
 
         let result = result
@@ -151,7 +151,7 @@ impl DB {
             .transpose()
             .map_err(|err| err.into());
 
-        histogram!("schemadb_get_latency_seconds", "cf_name" => S::COLUMN_FAMILY_NAME).record(
+        histogram!("citrea_schemadb_get_latency_seconds", "cf_name" => S::COLUMN_FAMILY_NAME).record(
             Instant::now()
                 .saturating_duration_since(start)
                 .as_secs_f64(),
@@ -159,6 +159,50 @@ impl DB {
         result
     }
 
+    /// Reads multiple records by key in a single batched lookup.
+    /// Results are returned in the same order as the given keys.
+    pub fn multi_get<S: Schema>(
+        &self,
+        schema_keys: &[impl KeyCodec<S>],
+    ) -> anyhow::Result<Vec<Option<S::Value>>> {
+        tokio::task::block_in_place(|| self._multi_get(schema_keys))
+    }
+
+    fn _multi_get<S: Schema>(
+        &self,
+        schema_keys: &[impl KeyCodec<S>],
+    ) -> anyhow::Result<Vec<Option<S::Value>>> {
+        let start = Instant::now();
+
+        let keys = schema_keys
+            .iter()
+            .map(|key| key.encode_key())
+            .collect::<Result<Vec<_>, _>>()?;
+        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
+
+        let results = self
+            .inner
+            .batched_multi_get_cf(cf_handle, &keys, false)
+            .into_iter()
+            .map(|result| {
+                let result = result?;
+                histogram!("citrea_schemadb_get_bytes", "cf_name" => S::COLUMN_FAMILY_NAME)
+                    .record(result.as_ref().map_or(0.0, |v| v.len() as f64));
+                result
+                    .map(|raw_value| <S::Value as ValueCodec<S>>::decode_value(&raw_value))
+                    .transpose()
+                    .map_err(|err| err.into())
+            })
+            .collect::<anyhow::Result<Vec<_>>>();
+
+        histogram!("citrea_schemadb_get_latency_seconds", "cf_name" => S::COLUMN_FAMILY_NAME).record(
+            Instant::now()
+                .saturating_duration_since(start)
+                .as_secs_f64(),
+        );
+        results
+    }
+
     /// Writes single record.
     pub fn put<S: Schema>(
         &self,
@@ -253,6 +297,12 @@ impl DB {
         Ok(())
     }
 
+    /// Deletes a key from a column family.
+    pub fn delete_cf(&self, cf_handle: &rocksdb::ColumnFamily, key: &[u8]) -> anyhow::Result<()> {
+        self.inner.delete_cf(cf_handle, key)?;
+        Ok(())
+    }
+
     /// Returns an iterator over a column family
     pub fn iter_cf<'a>(
         &'a self,
@@ -282,10 +332,16 @@ impl DB {
 
     /// Writes a group of records wrapped in a [`SchemaBatch`].
     pub fn write_schemas(&self, batch: SchemaBatch) -> anyhow::Result<()> {
+        self.write_schemas_ref(&batch)
+    }
+
+    /// Writes a group of records wrapped in a [`SchemaBatch`] which is still shared,
+    /// e.g. with the readers of a snapshot.
+    pub fn write_schemas_ref(&self, batch: &SchemaBatch) -> anyhow::Result<()> {
         tokio::task::block_in_place(|| self._write_schemas(batch))
     }
 
-    fn _write_schemas(&self, batch: SchemaBatch) -> anyhow::Result<()> {
+    fn _write_schemas(&self, batch: &SchemaBatch) -> anyhow::Result<()> {
         let start = Instant::now();
 
         let mut db_batch = rocksdb::WriteBatch::default();
@@ -307,18 +363,18 @@ impl DB {
             for (key, operation) in rows {
                 match operation {
                     Operation::Put { value } => {
-                        histogram!("schemadb_put_bytes").record((key.len() + value.len()) as f64);
+                        histogram!("citrea_schemadb_put_bytes").record((key.len() + value.len()) as f64);
                     }
                     Operation::Delete => {
-                        gauge!("schemadb_deletes", "cf_name" => cf_name.to_owned()).increment(1)
+                        gauge!("citrea_schemadb_deletes", "cf_name" => cf_name.to_owned()).increment(1)
                     }
                 }
             }
         }
 
-        histogram!("schemadb_batch_commit_bytes").record(serialized_size as f64);
+        histogram!("citrea_schemadb_batch_commit_bytes").record(serialized_size as f64);
 
-        histogram!("schemadb_batch_commit_latency_seconds", "db_name" => self.name).record(
+        histogram!("citrea_schemadb_batch_commit_latency_seconds", "db_name" => self.name).record(
             Instant::now()
                 .saturating_duration_since(start)
                 .as_secs_f64(),
@@ -375,6 +431,42 @@ pub struct RawRocksdbOptions {
     pub db_options: rocksdb::Options,
     /// Per column-family options
     pub block_options: rocksdb::BlockBasedOptions,
+    /// Compression of the column families
+    pub compression: rocksdb::DBCompressionType,
+    /// Compression of individual column families, overriding `compression`
+    pub column_family_compression: HashMap<String, rocksdb::DBCompressionType>,
+    /// Size of a single memtable of a column family, the RocksDB default if not set
+    pub write_buffer_size: Option<usize>,
+}
+
+impl RawRocksdbOptions {
+    /// Creates options with the given db and table options, keeping the defaults
+    /// of the column family options.
+    pub fn new(db_options: rocksdb::Options, block_options: rocksdb::BlockBasedOptions) -> Self {
+        Self {
+            db_options,
+            block_options,
+            compression: rocksdb::DBCompressionType::Lz4,
+            column_family_compression: HashMap::new(),
+            write_buffer_size: None,
+        }
+    }
+
+    /// Options of the column family with the given name
+    pub fn column_family_options(&self, cf_name: &str) -> rocksdb::Options {
+        let mut cf_opts = rocksdb::Options::default();
+        cf_opts.set_compression_type(
+            self.column_family_compression
+                .get(cf_name)
+                .copied()
+                .unwrap_or(self.compression),
+        );
+        cf_opts.set_block_based_table_factory(&self.block_options);
+        if let Some(write_buffer_size) = self.write_buffer_size {
+            cf_opts.set_write_buffer_size(write_buffer_size);
+        }
+        cf_opts
+    }
 }
 
 /// Readability alias for a key in the DB.
@@ -439,10 +531,7 @@ mod tests {
             tmpdir.path(),
             "test_db_debug",
             column_families,
-            &RawRocksdbOptions {
-                db_options: db_opts,
-                block_options: block_opts,
-            },
+            &RawRocksdbOptions::new(db_opts, block_opts),
         )
         .expect("Failed to open DB.");
 
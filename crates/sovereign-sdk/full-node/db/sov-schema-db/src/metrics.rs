@@ -13,7 +13,7 @@ use once_cell::sync::Lazy;
 /// the metrics.
 #[allow(unused)]
 #[derive(Metrics)]
-#[metrics(scope = "schemadb")]
+#[metrics(scope = "citrea_schemadb")]
 pub struct SchemaDbMetrics {
     #[metric(describe = "Storage delete calls")]
     pub(crate) deletes: Counter,
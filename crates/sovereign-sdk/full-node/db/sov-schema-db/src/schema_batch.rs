@@ -34,7 +34,7 @@ impl SchemaBatch {
             value: value.encode_value()?,
         };
         self.insert_operation::<S>(key, put_operation);
-        histogram!("schemadb_batch_put_latency_seconds").record(
+        histogram!("citrea_schemadb_batch_put_latency_seconds").record(
             Instant::now()
                 .saturating_duration_since(start)
                 .as_secs_f64(),
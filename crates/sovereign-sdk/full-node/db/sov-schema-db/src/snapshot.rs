@@ -283,6 +283,11 @@ impl ReadOnlyDbSnapshot {
         self.id
     }
 
+    /// Operations of this snapshot, to be written to the database
+    pub fn as_batch(&self) -> &SchemaBatch {
+        &self.cache
+    }
+
     /// Iterate over all operations in snapshot in reversed lexicographic order
     pub fn iter<S: Schema>(
         &self,
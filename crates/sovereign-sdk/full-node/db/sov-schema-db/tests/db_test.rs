@@ -33,10 +33,7 @@ fn open_db(dir: impl AsRef<Path>) -> DB {
         dir,
         "test",
         get_column_families(),
-        &RawRocksdbOptions {
-            db_options: db_opts,
-            block_options: block_opts,
-        },
+        &RawRocksdbOptions::new(db_opts, block_opts),
     )
     .expect("Failed to open DB.")
 }
@@ -139,6 +136,36 @@ fn test_schema_put_get() {
     assert_eq!(db.get::<TestSchema2>(&TestField(400)).unwrap(), None);
 }
 
+#[test]
+fn test_schema_multi_get() {
+    let db = TestDB::new();
+
+    for i in 0..10 {
+        db.put::<TestSchema1>(&TestField(i), &TestField(i + 1))
+            .unwrap();
+    }
+
+    // Results follow the order of the requested keys, with `None` for missing ones.
+    assert_eq!(
+        db.multi_get::<TestSchema1>(&[TestField(5), TestField(42), TestField(0), TestField(9)])
+            .unwrap(),
+        vec![
+            Some(TestField(6)),
+            None,
+            Some(TestField(1)),
+            Some(TestField(10)),
+        ],
+    );
+    assert_eq!(
+        db.multi_get::<TestSchema2>(&[TestField(0)]).unwrap(),
+        vec![None]
+    );
+    assert!(db
+        .multi_get::<TestSchema1>(&[] as &[TestField])
+        .unwrap()
+        .is_empty());
+}
+
 fn collect_values<S: Schema>(db: &TestDB) -> Vec<(S::Key, S::Value)> {
     let mut iter = db.iter::<S>().expect("Failed to create iterator.");
     iter.seek_to_first();
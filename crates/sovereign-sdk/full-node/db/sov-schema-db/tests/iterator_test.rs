@@ -50,10 +50,7 @@ impl TestDB {
             tmpdir.path(),
             "test",
             column_families,
-            &RawRocksdbOptions {
-                db_options: db_opts,
-                block_options: block_opts,
-            },
+            &RawRocksdbOptions::new(db_opts, block_opts),
         )
         .unwrap();
 
@@ -0,0 +1,136 @@
+//! Error codes of the ledger JSON-RPC namespace.
+//!
+//! Every error returned by the ledger RPC server carries one of the [`LedgerRpcErrorCode`]s.
+//! Messages are kept stable, and errors about a specific height or range carry it as data.
+
+use serde::{Deserialize, Serialize};
+
+/// Message of the [`LedgerRpcErrorCode::Database`] errors, the error itself is the data
+pub const LEDGER_RPC_ERROR: &str = "LEDGER_RPC_ERROR";
+
+/// Error codes returned by the ledger RPC server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerRpcErrorCode {
+    /// The requested item is not in the ledger yet.
+    /// Data: [`L2HeightErrorData`]
+    NotFound,
+    /// The requested item was removed from the ledger by pruning.
+    /// Data: [`PrunedErrorData`]
+    Pruned,
+    /// The request spans more items than the server serves at once.
+    /// Data: [`RangeTooLargeErrorData`]
+    RangeTooLarge,
+    /// The request parameters are inconsistent or not supported by the node.
+    /// Data: [`InvalidRangeErrorData`] for ranges whose start is past their end
+    InvalidParams,
+    /// The ledger database failed to serve the request.
+    /// Data: the error message
+    Database,
+}
+
+impl LedgerRpcErrorCode {
+    /// All the ledger error codes
+    pub const ALL: [LedgerRpcErrorCode; 5] = [
+        LedgerRpcErrorCode::NotFound,
+        LedgerRpcErrorCode::Pruned,
+        LedgerRpcErrorCode::RangeTooLarge,
+        LedgerRpcErrorCode::InvalidParams,
+        LedgerRpcErrorCode::Database,
+    ];
+
+    /// Returns the JSON-RPC error code.
+    pub const fn code(self) -> i32 {
+        match self {
+            LedgerRpcErrorCode::NotFound => -39001,
+            LedgerRpcErrorCode::Pruned => -39002,
+            LedgerRpcErrorCode::RangeTooLarge => -39003,
+            LedgerRpcErrorCode::InvalidParams => -39004,
+            LedgerRpcErrorCode::Database => -39005,
+        }
+    }
+
+    /// Returns the ledger error code of a JSON-RPC error code, if it is one.
+    pub fn from_code(code: i32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|error_code| error_code.code() == code)
+    }
+}
+
+/// Data of the errors about a single L2 height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L2HeightErrorData {
+    /// The requested L2 height
+    pub l2_height: u64,
+}
+
+/// Data of the [`LedgerRpcErrorCode::Pruned`] errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunedErrorData {
+    /// The requested L2 height
+    pub l2_height: u64,
+    /// The last L2 height whose data was pruned
+    pub last_pruned_l2_height: u64,
+}
+
+/// Data of the [`LedgerRpcErrorCode::RangeTooLarge`] errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeTooLargeErrorData {
+    /// Number of items requested
+    pub requested: u64,
+    /// Maximum number of items served at once
+    pub max: u64,
+}
+
+/// Data of the [`LedgerRpcErrorCode::InvalidParams`] errors about ranges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidRangeErrorData {
+    /// Start of the requested range
+    pub start: u64,
+    /// End of the requested range
+    pub end: u64,
+}
+
+/// Helpers to match the errors of ledger RPC calls.
+#[cfg(feature = "client")]
+pub trait LedgerRpcClientError {
+    /// Returns the ledger error code of the error, if the server returned one.
+    fn ledger_error_code(&self) -> Option<LedgerRpcErrorCode>;
+
+    /// Returns the data of the error, if the server returned a ledger error with data of type `T`.
+    fn ledger_error_data<T: serde::de::DeserializeOwned>(&self) -> Option<T>;
+
+    /// Returns whether the requested item is not in the ledger yet.
+    fn is_not_found(&self) -> bool {
+        self.ledger_error_code() == Some(LedgerRpcErrorCode::NotFound)
+    }
+
+    /// Returns whether the requested item was pruned.
+    fn is_pruned(&self) -> bool {
+        self.ledger_error_code() == Some(LedgerRpcErrorCode::Pruned)
+    }
+}
+
+#[cfg(feature = "client")]
+impl LedgerRpcClientError for jsonrpsee::core::ClientError {
+    fn ledger_error_code(&self) -> Option<LedgerRpcErrorCode> {
+        match self {
+            jsonrpsee::core::ClientError::Call(err) => LedgerRpcErrorCode::from_code(err.code()),
+            _ => None,
+        }
+    }
+
+    fn ledger_error_data<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        match self {
+            jsonrpsee::core::ClientError::Call(err) => {
+                LedgerRpcErrorCode::from_code(err.code())?;
+                serde_json::from_str(err.data()?.get()).ok()
+            }
+            _ => None,
+        }
+    }
+}
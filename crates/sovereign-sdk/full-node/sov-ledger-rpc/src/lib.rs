@@ -1,19 +1,26 @@
 #![forbid(unsafe_code)]
 
 use alloy_primitives::U64;
-use jsonrpsee::core::RpcResult;
+use jsonrpsee::core::{RpcResult, SubscriptionResult};
 use jsonrpsee::proc_macros::rpc;
 use sov_rollup_interface::rpc::{
-    BatchProofResponse, LastVerifiedBatchProofResponse, SequencerCommitmentResponse,
-    SoftConfirmationResponse, SoftConfirmationStatus, VerifiedBatchProofResponse,
+    BatchProofResponse, L1SlotSoftConfirmationsResponse, LastVerifiedBatchProofResponse,
+    SequencerCommitmentResponse, SlotVerifiedBatchProofsResponse, SoftConfirmationDetail,
+    SoftConfirmationHashResponse, SoftConfirmationResponse, SoftConfirmationStatus,
+    VerifiedBatchProofResponse,
 };
 
+pub mod error;
 #[cfg(feature = "server")]
 pub mod server;
 
+#[cfg(feature = "client")]
+pub use error::LedgerRpcClientError;
+pub use error::LedgerRpcErrorCode;
+
 /// A 32-byte hash [`serde`]-encoded as a hex string optionally prefixed with
 /// `0x`. See [`sov_rollup_interface::rpc::utils::rpc_hex`].
-#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct HexHash(#[serde(with = "sov_rollup_interface::rpc::utils::rpc_hex")] pub [u8; 32]);
 
 impl From<[u8; 32]> for HexHash {
@@ -44,11 +51,13 @@ impl From<[u8; 32]> for HexHash {
 )]
 pub trait LedgerRpc {
     /// Gets a single soft confirmation by number.
+    /// The response is at the `full` detail level unless `detail` is given.
     #[method(name = "getSoftConfirmationByNumber")]
     #[blocking]
     fn get_soft_confirmation_by_number(
         &self,
         number: U64,
+        detail: Option<SoftConfirmationDetail>,
     ) -> RpcResult<Option<SoftConfirmationResponse>>;
 
     /// Gets a single soft confirmation by hash.
@@ -59,16 +68,28 @@ pub trait LedgerRpc {
         hash: HexHash,
     ) -> RpcResult<Option<SoftConfirmationResponse>>;
 
+    /// Gets soft confirmations by hashes, in the same order as the given hashes.
+    #[method(name = "getSoftConfirmationsByHashes")]
+    #[blocking]
+    fn get_soft_confirmations_by_hashes(
+        &self,
+        hashes: Vec<HexHash>,
+    ) -> RpcResult<Vec<Option<SoftConfirmationResponse>>>;
+
     /// Gets all soft confirmations with numbers `range.start` to `range.end`.
+    /// The responses are at the `full` detail level unless `detail` is given.
     #[method(name = "getSoftConfirmationRange")]
     #[blocking]
     fn get_soft_confirmation_range(
         &self,
         start: U64,
         end: U64,
+        detail: Option<SoftConfirmationDetail>,
     ) -> RpcResult<Vec<Option<SoftConfirmationResponse>>>;
 
-    /// Gets a single event by number.
+    /// Gets the status of the soft confirmation with the given number.
+    /// Fails with [`LedgerRpcErrorCode::NotFound`] if it is not processed yet,
+    /// or with [`LedgerRpcErrorCode::Pruned`] if it was pruned.
     #[method(name = "getSoftConfirmationStatus")]
     #[blocking]
     fn get_soft_confirmation_status(
@@ -79,7 +100,17 @@ pub trait LedgerRpc {
     /// Gets the L2 genesis state root.
     #[method(name = "getL2GenesisStateRoot")]
     #[blocking]
-    fn get_l2_genesis_state_root(&self) -> RpcResult<Option<Vec<u8>>>;
+    fn get_l2_genesis_state_root(&self) -> RpcResult<Option<HexHash>>;
+
+    /// Gets the range of soft confirmations built on top of the DA slot with the given height.
+    /// Transactions are included unless `include_txs` is `false`.
+    #[method(name = "getSoftConfirmationRangeByL1Height")]
+    #[blocking]
+    fn get_soft_confirmation_range_by_l1_height(
+        &self,
+        height: U64,
+        include_txs: Option<bool>,
+    ) -> RpcResult<Option<L1SlotSoftConfirmationsResponse>>;
 
     /// Gets the commitments in the DA slot with the given height.
     #[method(name = "getSequencerCommitmentsOnSlotByNumber")]
@@ -97,6 +128,14 @@ pub trait LedgerRpc {
         hash: HexHash,
     ) -> RpcResult<Option<Vec<SequencerCommitmentResponse>>>;
 
+    /// Gets the sequencer commitment covering the given L2 height.
+    #[method(name = "getSequencerCommitmentByL2Height")]
+    #[blocking]
+    fn get_sequencer_commitment_by_l2_height(
+        &self,
+        l2_height: U64,
+    ) -> RpcResult<Option<SequencerCommitmentResponse>>;
+
     /// Gets proof by slot height.
     #[method(name = "getBatchProofsBySlotHeight")]
     #[blocking]
@@ -131,6 +170,17 @@ pub trait LedgerRpc {
         height: U64,
     ) -> RpcResult<Option<Vec<VerifiedBatchProofResponse>>>;
 
+    /// Gets verified proofs of slots with heights `start` to `end` (inclusive).
+    /// Only slots containing proofs are returned, at most `limit` of them.
+    #[method(name = "getVerifiedBatchProofsBySlotRange")]
+    #[blocking]
+    fn get_verified_batch_proofs_by_slot_range(
+        &self,
+        start: U64,
+        end: U64,
+        limit: U64,
+    ) -> RpcResult<Vec<SlotVerifiedBatchProofsResponse>>;
+
     /// Gets last verified proog
     #[method(name = "getLastVerifiedBatchProof")]
     #[blocking]
@@ -140,4 +190,18 @@ pub trait LedgerRpc {
     #[method(name = "getLastScannedL1Height")]
     #[blocking]
     fn get_last_scanned_l1_height(&self) -> RpcResult<u64>;
+
+    /// Recomputes the hash of a soft confirmation from its contents, with the scheme of the fork
+    /// active at its height, and compares it to the hash of the soft confirmation stored at that height.
+    /// The soft confirmation must include its transactions.
+    #[method(name = "computeSoftConfirmationHash")]
+    #[blocking]
+    fn compute_soft_confirmation_hash(
+        &self,
+        soft_confirmation: SoftConfirmationResponse,
+    ) -> RpcResult<SoftConfirmationHashResponse>;
+
+    /// Subscribes to newly committed soft confirmations.
+    #[subscription(name = "subscribeSoftConfirmations" => "softConfirmationSubscription", unsubscribe = "unsubscribeSoftConfirmations", item = SoftConfirmationResponse)]
+    async fn subscribe_soft_confirmations(&self) -> SubscriptionResult;
 }
@@ -1,44 +1,230 @@
 //! A JSON-RPC server implementation for any [`LedgerRpcProvider`].
 
+use std::sync::Arc;
+
 use alloy_primitives::U64;
-use jsonrpsee::core::RpcResult;
+use citrea_primitives::forks::fork_from_block_number;
+use citrea_primitives::soft_confirmation::compute_soft_confirmation_hash;
+use jsonrpsee::core::{RpcResult, SubscriptionResult};
 use jsonrpsee::types::ErrorObjectOwned;
-use jsonrpsee::RpcModule;
-use sov_modules_api::utils::to_jsonrpsee_error_object;
+use jsonrpsee::{PendingSubscriptionSink, RpcModule, SubscriptionMessage, SubscriptionSink};
+use serde::Serialize;
 use sov_rollup_interface::rpc::{
-    BatchProofResponse, LastVerifiedBatchProofResponse, LedgerRpcProvider,
-    SequencerCommitmentResponse, SoftConfirmationResponse, SoftConfirmationStatus,
-    VerifiedBatchProofResponse,
+    BatchProofResponse, L1SlotSoftConfirmationsResponse, LastVerifiedBatchProofResponse,
+    LedgerRpcProvider, SequencerCommitmentResponse, SlotVerifiedBatchProofsResponse,
+    SoftConfirmationDetail, SoftConfirmationHashResponse, SoftConfirmationResponse,
+    SoftConfirmationStatus, SoftConfirmationTxSummary, VerifiedBatchProofResponse,
+    MAX_SOFT_CONFIRMATIONS_PER_REQUEST,
 };
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 
+use crate::error::{
+    InvalidRangeErrorData, L2HeightErrorData, LedgerRpcErrorCode, PrunedErrorData,
+    RangeTooLargeErrorData, LEDGER_RPC_ERROR,
+};
 use crate::{HexHash, LedgerRpcServer};
 
-const LEDGER_RPC_ERROR: &str = "LEDGER_RPC_ERROR";
+/// The default maximum number of hashes accepted by `ledger_getSoftConfirmationsByHashes`.
+pub const DEFAULT_MAX_HASHES_PER_REQUEST: usize = 100;
+/// The default maximum number of slots spanned by `ledger_getVerifiedBatchProofsBySlotRange`.
+pub const DEFAULT_MAX_SLOT_RANGE: u64 = 1000;
+
+fn ledger_error<S: Serialize>(
+    code: LedgerRpcErrorCode,
+    message: impl Into<String>,
+    data: Option<S>,
+) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(code.code(), message, data)
+}
 
 fn to_ledger_rpc_error(err: impl ToString) -> ErrorObjectOwned {
-    to_jsonrpsee_error_object(LEDGER_RPC_ERROR, err)
+    ledger_error(
+        LedgerRpcErrorCode::Database,
+        LEDGER_RPC_ERROR,
+        Some(err.to_string()),
+    )
+}
+
+fn to_invalid_params_error(err: impl ToString) -> ErrorObjectOwned {
+    ledger_error(
+        LedgerRpcErrorCode::InvalidParams,
+        err.to_string(),
+        None::<()>,
+    )
+}
+
+fn invalid_range_error(kind: &str, start: u64, end: u64) -> ErrorObjectOwned {
+    ledger_error(
+        LedgerRpcErrorCode::InvalidParams,
+        format!(
+            "invalid {} range. Start: {} is greater than end: {}",
+            kind, start, end
+        ),
+        Some(InvalidRangeErrorData { start, end }),
+    )
+}
+
+fn not_found_error(l2_height: u64) -> ErrorObjectOwned {
+    ledger_error(
+        LedgerRpcErrorCode::NotFound,
+        format!(
+            "Soft confirmation at height {} not processed yet.",
+            l2_height
+        ),
+        Some(L2HeightErrorData { l2_height }),
+    )
+}
+
+fn range_too_large_error(kind: &str, requested: u64, max: u64) -> ErrorObjectOwned {
+    ledger_error(
+        LedgerRpcErrorCode::RangeTooLarge,
+        format!(
+            "requested too many {}. Requested: {}. Max: {}",
+            kind, requested, max
+        ),
+        Some(RangeTooLargeErrorData { requested, max }),
+    )
+}
+
+/// Request limits of the ledger RPC server.
+#[derive(Debug, Clone, Copy)]
+pub struct LedgerRpcServerConfig {
+    /// Maximum number of hashes in a single batch lookup
+    pub max_hashes_per_request: usize,
+    /// Maximum number of slots a single range query can span
+    pub max_slot_range: u64,
+}
+
+impl Default for LedgerRpcServerConfig {
+    fn default() -> Self {
+        Self {
+            max_hashes_per_request: DEFAULT_MAX_HASHES_PER_REQUEST,
+            max_slot_range: DEFAULT_MAX_SLOT_RANGE,
+        }
+    }
 }
+
+/// Provides the summaries of the transactions executed in soft confirmations,
+/// served with the [`SoftConfirmationDetail::Hashes`] detail level.
+pub trait TxSummaryProvider: Send + Sync {
+    /// Returns the summaries of the transactions executed in the soft confirmation at the given height.
+    fn get_tx_summaries(&self, l2_height: u64) -> anyhow::Result<Vec<SoftConfirmationTxSummary>>;
+}
+
 pub struct LedgerRpcServerImpl<T> {
     ledger: T,
+    config: LedgerRpcServerConfig,
+    soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
+    tx_summary_provider: Option<Arc<dyn TxSummaryProvider>>,
+}
+
+impl<T: LedgerRpcProvider> LedgerRpcServerImpl<T> {
+    /// Returns the [`LedgerRpcErrorCode::Pruned`] error if the data at the given L2 height
+    /// was pruned.
+    fn check_pruned(&self, l2_height: u64) -> RpcResult<()> {
+        let last_pruned_l2_height = self
+            .ledger
+            .get_last_pruned_l2_height()
+            .map_err(to_ledger_rpc_error)?;
+        match last_pruned_l2_height {
+            Some(last_pruned_l2_height) if l2_height <= last_pruned_l2_height => Err(ledger_error(
+                LedgerRpcErrorCode::Pruned,
+                format!("Soft confirmation at height {} was pruned.", l2_height),
+                Some(PrunedErrorData {
+                    l2_height,
+                    last_pruned_l2_height,
+                }),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl<T> LedgerRpcServerImpl<T> {
     pub fn new(ledger: T) -> Self {
-        Self { ledger }
+        Self {
+            ledger,
+            config: LedgerRpcServerConfig::default(),
+            soft_confirmation_rx: None,
+            tx_summary_provider: None,
+        }
+    }
+
+    /// Enables the `hashes` detail level, served by the given provider.
+    pub fn with_tx_summary_provider(
+        mut self,
+        tx_summary_provider: Option<Arc<dyn TxSummaryProvider>>,
+    ) -> Self {
+        self.tx_summary_provider = tx_summary_provider;
+        self
+    }
+
+    /// Trims the soft confirmation to the requested detail level.
+    fn apply_detail(
+        &self,
+        soft_confirmation: &mut SoftConfirmationResponse,
+        detail: SoftConfirmationDetail,
+    ) -> RpcResult<()> {
+        match detail {
+            SoftConfirmationDetail::Full => {}
+            SoftConfirmationDetail::Hashes => {
+                let Some(tx_summary_provider) = self.tx_summary_provider.as_ref() else {
+                    return Err(to_invalid_params_error(
+                        "hashes detail level is not supported by this node",
+                    ));
+                };
+                soft_confirmation.txs = None;
+                soft_confirmation.tx_summaries = Some(
+                    tx_summary_provider
+                        .get_tx_summaries(soft_confirmation.l2_height)
+                        .map_err(to_ledger_rpc_error)?,
+                );
+            }
+            SoftConfirmationDetail::Minimal => {
+                soft_confirmation.txs = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the request limits of the server.
+    pub fn with_config(mut self, config: LedgerRpcServerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Enables soft confirmation subscriptions, fed by the heights sent on the given channel.
+    pub fn with_soft_confirmation_rx(
+        mut self,
+        soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
+    ) -> Self {
+        self.soft_confirmation_rx = soft_confirmation_rx;
+        self
     }
 }
 
+#[async_trait::async_trait]
 impl<T> LedgerRpcServer for LedgerRpcServerImpl<T>
 where
-    T: LedgerRpcProvider + Send + Sync + 'static,
+    T: LedgerRpcProvider + Clone + Send + Sync + 'static,
 {
     fn get_soft_confirmation_by_number(
         &self,
         number: U64,
+        detail: Option<SoftConfirmationDetail>,
     ) -> RpcResult<Option<SoftConfirmationResponse>> {
-        self.ledger
+        let mut soft_confirmation = self
+            .ledger
             .get_soft_confirmation_by_number(number.to())
-            .map_err(to_ledger_rpc_error)
+            .map_err(to_ledger_rpc_error)?;
+        match soft_confirmation.as_mut() {
+            Some(soft_confirmation) => {
+                self.apply_detail(soft_confirmation, detail.unwrap_or_default())?
+            }
+            None => self.check_pruned(number.to())?,
+        }
+        Ok(soft_confirmation)
     }
 
     fn get_soft_confirmation_by_hash(
@@ -50,28 +236,75 @@ where
             .map_err(to_ledger_rpc_error)
     }
 
+    fn get_soft_confirmations_by_hashes(
+        &self,
+        hashes: Vec<HexHash>,
+    ) -> RpcResult<Vec<Option<SoftConfirmationResponse>>> {
+        if hashes.len() > self.config.max_hashes_per_request {
+            return Err(range_too_large_error(
+                "soft confirmation hashes",
+                hashes.len() as u64,
+                self.config.max_hashes_per_request as u64,
+            ));
+        }
+
+        let hashes: Vec<[u8; 32]> = hashes.into_iter().map(|hash| hash.0).collect();
+        self.ledger
+            .get_soft_confirmations_by_hashes(&hashes)
+            .map_err(to_ledger_rpc_error)
+    }
+
     fn get_soft_confirmation_range(
         &self,
         start: U64,
         end: U64,
+        detail: Option<SoftConfirmationDetail>,
     ) -> RpcResult<Vec<Option<SoftConfirmationResponse>>> {
-        self.ledger
-            .get_soft_confirmations_range(start.to(), end.to())
-            .map_err(to_ledger_rpc_error)
+        let start: u64 = start.to();
+        let end: u64 = end.to();
+        if start > end {
+            return Err(invalid_range_error("soft confirmation", start, end));
+        }
+        if end - start >= MAX_SOFT_CONFIRMATIONS_PER_REQUEST {
+            return Err(range_too_large_error(
+                "soft confirmations",
+                end - start + 1,
+                MAX_SOFT_CONFIRMATIONS_PER_REQUEST,
+            ));
+        }
+
+        let mut soft_confirmations = self
+            .ledger
+            .get_soft_confirmations_range(start, end)
+            .map_err(to_ledger_rpc_error)?;
+        let detail = detail.unwrap_or_default();
+        for soft_confirmation in soft_confirmations.iter_mut().flatten() {
+            self.apply_detail(soft_confirmation, detail)?;
+        }
+        Ok(soft_confirmations)
     }
 
     fn get_soft_confirmation_status(
         &self,
         soft_confirmation_receipt: U64,
     ) -> RpcResult<SoftConfirmationStatus> {
-        self.ledger
-            .get_soft_confirmation_status(soft_confirmation_receipt.to())
-            .map_err(to_ledger_rpc_error)
+        let l2_height: u64 = soft_confirmation_receipt.to();
+        if let Some(status) = self
+            .ledger
+            .get_soft_confirmation_status(l2_height)
+            .map_err(to_ledger_rpc_error)?
+        {
+            return Ok(status);
+        }
+
+        self.check_pruned(l2_height)?;
+        Err(not_found_error(l2_height))
     }
 
-    fn get_l2_genesis_state_root(&self) -> RpcResult<Option<Vec<u8>>> {
+    fn get_l2_genesis_state_root(&self) -> RpcResult<Option<HexHash>> {
         self.ledger
             .get_l2_genesis_state_root()
+            .map(|state_root| state_root.map(HexHash))
             .map_err(to_ledger_rpc_error)
     }
 
@@ -81,6 +314,44 @@ where
             .map_err(to_ledger_rpc_error)
     }
 
+    fn compute_soft_confirmation_hash(
+        &self,
+        soft_confirmation: SoftConfirmationResponse,
+    ) -> RpcResult<SoftConfirmationHashResponse> {
+        let l2_height = soft_confirmation.l2_height;
+        let Some(unparsed) = soft_confirmation.to_unparsed_soft_confirmation() else {
+            return Err(to_invalid_params_error(
+                "soft confirmation transactions are required to compute its hash",
+            ));
+        };
+        let spec = fork_from_block_number(l2_height).spec_id;
+        let hash = compute_soft_confirmation_hash(spec, &unparsed);
+
+        let Some(stored) = self
+            .ledger
+            .get_soft_confirmation_by_number(l2_height)
+            .map_err(to_ledger_rpc_error)?
+        else {
+            self.check_pruned(l2_height)?;
+            return Err(not_found_error(l2_height));
+        };
+
+        Ok(SoftConfirmationHashResponse {
+            hash,
+            matches_stored: hash == stored.hash,
+        })
+    }
+
+    fn get_soft_confirmation_range_by_l1_height(
+        &self,
+        height: U64,
+        include_txs: Option<bool>,
+    ) -> RpcResult<Option<L1SlotSoftConfirmationsResponse>> {
+        self.ledger
+            .get_soft_confirmations_by_l1_height(height.to(), include_txs.unwrap_or(true))
+            .map_err(to_ledger_rpc_error)
+    }
+
     fn get_sequencer_commitments_on_slot_by_number(
         &self,
         height: U64,
@@ -107,6 +378,15 @@ where
             .map_err(to_ledger_rpc_error)
     }
 
+    fn get_sequencer_commitment_by_l2_height(
+        &self,
+        l2_height: U64,
+    ) -> RpcResult<Option<SequencerCommitmentResponse>> {
+        self.ledger
+            .get_sequencer_commitment_by_l2_height(l2_height.to())
+            .map_err(to_ledger_rpc_error)
+    }
+
     fn get_batch_proofs_by_slot_height(
         &self,
         height: U64,
@@ -142,6 +422,30 @@ where
             .map_err(to_ledger_rpc_error)
     }
 
+    fn get_verified_batch_proofs_by_slot_range(
+        &self,
+        start: U64,
+        end: U64,
+        limit: U64,
+    ) -> RpcResult<Vec<SlotVerifiedBatchProofsResponse>> {
+        let start: u64 = start.to();
+        let end: u64 = end.to();
+        if start > end {
+            return Err(invalid_range_error("slot", start, end));
+        }
+        if end - start >= self.config.max_slot_range {
+            return Err(range_too_large_error(
+                "slots",
+                end - start + 1,
+                self.config.max_slot_range,
+            ));
+        }
+
+        self.ledger
+            .get_verified_proof_data_by_l1_height_range(start, end, limit.saturating_to())
+            .map_err(to_ledger_rpc_error)
+    }
+
     fn get_last_verified_batch_proof(&self) -> RpcResult<Option<LastVerifiedBatchProofResponse>> {
         self.ledger
             .get_last_verified_batch_proof()
@@ -159,12 +463,95 @@ where
             .get_head_soft_confirmation_height()
             .map_err(to_ledger_rpc_error)
     }
+
+    async fn subscribe_soft_confirmations(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        let Some(soft_confirmation_rx) = self.soft_confirmation_rx.as_ref() else {
+            pending
+                .reject(to_ledger_rpc_error("Subscriptions are disabled"))
+                .await;
+            return Ok(());
+        };
+        let soft_confirmation_rx = soft_confirmation_rx.resubscribe();
+
+        let subscription = pending.accept().await?;
+        tokio::spawn(soft_confirmation_notifier(
+            self.ledger.clone(),
+            soft_confirmation_rx,
+            subscription,
+        ));
+        Ok(())
+    }
+}
+
+/// Pushes every newly committed soft confirmation to the subscriber until
+/// it unsubscribes. Subscribers that can't keep up are dropped instead of
+/// being waited on.
+async fn soft_confirmation_notifier<T: LedgerRpcProvider>(
+    ledger: T,
+    mut soft_confirmation_rx: broadcast::Receiver<u64>,
+    subscription: SubscriptionSink,
+) {
+    loop {
+        let height = tokio::select! {
+            _ = subscription.closed() => return,
+            height = soft_confirmation_rx.recv() => match height {
+                Ok(height) => height,
+                Err(RecvError::Lagged(_)) | Err(RecvError::Closed) => return,
+            },
+        };
+
+        let soft_confirmation = match ledger.get_soft_confirmation_by_number(height) {
+            Ok(Some(soft_confirmation)) => soft_confirmation,
+            Ok(None) | Err(_) => return,
+        };
+
+        let Ok(msg) = SubscriptionMessage::new(
+            subscription.method_name(),
+            subscription.subscription_id(),
+            &soft_confirmation,
+        ) else {
+            return;
+        };
+        if subscription.try_send(msg).is_err() {
+            return;
+        }
+    }
 }
 
 pub fn create_rpc_module<T>(ledger: T) -> RpcModule<LedgerRpcServerImpl<T>>
 where
-    T: LedgerRpcProvider + Send + Sync + 'static,
+    T: LedgerRpcProvider + Clone + Send + Sync + 'static,
 {
-    let server = LedgerRpcServerImpl::new(ledger);
-    LedgerRpcServer::into_rpc(server)
+    create_rpc_module_with_config(ledger, LedgerRpcServerConfig::default(), None, None)
+}
+
+/// Creates the ledger RPC module. Subscriptions are only registered
+/// if a soft confirmation receiver is given, and the `hashes` detail level
+/// is only served if a transaction summary provider is given.
+pub fn create_rpc_module_with_config<T>(
+    ledger: T,
+    config: LedgerRpcServerConfig,
+    soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
+    tx_summary_provider: Option<Arc<dyn TxSummaryProvider>>,
+) -> RpcModule<LedgerRpcServerImpl<T>>
+where
+    T: LedgerRpcProvider + Clone + Send + Sync + 'static,
+{
+    let enable_subscriptions = soft_confirmation_rx.is_some();
+
+    let server = LedgerRpcServerImpl::new(ledger)
+        .with_config(config)
+        .with_soft_confirmation_rx(soft_confirmation_rx)
+        .with_tx_summary_provider(tx_summary_provider);
+    let mut module = LedgerRpcServer::into_rpc(server);
+
+    if !enable_subscriptions {
+        module.remove_method("ledger_subscribeSoftConfirmations");
+        module.remove_method("ledger_unsubscribeSoftConfirmations");
+    }
+
+    module
 }
@@ -2,15 +2,25 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use alloy_primitives::U64;
-use sov_db::ledger_db::LedgerDB;
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
 use sov_db::rocks_db_config::RocksdbConfig;
-use sov_ledger_rpc::server::create_rpc_module;
-use sov_ledger_rpc::{HexHash, LedgerRpcClient};
+use sov_ledger_rpc::error::{
+    InvalidRangeErrorData, L2HeightErrorData, PrunedErrorData, RangeTooLargeErrorData,
+};
+use sov_ledger_rpc::server::{
+    create_rpc_module, DEFAULT_MAX_HASHES_PER_REQUEST, DEFAULT_MAX_SLOT_RANGE,
+};
+use sov_ledger_rpc::{HexHash, LedgerRpcClient, LedgerRpcClientError, LedgerRpcErrorCode};
+use sov_rollup_interface::rpc::{SoftConfirmationResponse, MAX_SOFT_CONFIRMATIONS_PER_REQUEST};
 use tempfile::tempdir;
 
 async fn rpc_server() -> (jsonrpsee::server::ServerHandle, SocketAddr) {
     let dir = tempdir().unwrap();
     let db = LedgerDB::with_config(&RocksdbConfig::new(dir.path(), None, None)).unwrap();
+    rpc_server_with_ledger(db).await
+}
+
+async fn rpc_server_with_ledger(db: LedgerDB) -> (jsonrpsee::server::ServerHandle, SocketAddr) {
     let rpc_module = create_rpc_module::<LedgerDB>(db);
 
     let server = jsonrpsee::server::ServerBuilder::default()
@@ -40,8 +50,15 @@ async fn getters_succeed() {
         .await
         .unwrap();
 
+    let soft_confirmations = rpc_client
+        .get_soft_confirmations_by_hashes(vec![hash, HexHash([1; 32])])
+        .await
+        .unwrap();
+    assert_eq!(soft_confirmations.len(), 2);
+    assert!(soft_confirmations.iter().all(Option::is_none));
+
     rpc_client
-        .get_soft_confirmation_by_number(U64::from(0))
+        .get_soft_confirmation_by_number(U64::from(0), None)
         .await
         .unwrap();
 
@@ -55,6 +72,11 @@ async fn getters_succeed() {
         .await
         .unwrap();
 
+    rpc_client
+        .get_sequencer_commitment_by_l2_height(U64::from(0))
+        .await
+        .unwrap();
+
     rpc_client
         .get_batch_proofs_by_slot_height(U64::from(0))
         .await
@@ -77,5 +99,223 @@ async fn getters_succeed() {
         .await
         .unwrap();
 
+    rpc_client
+        .get_verified_batch_proofs_by_slot_range(U64::from(0), U64::from(10), U64::from(10))
+        .await
+        .unwrap();
+
     rpc_client.get_last_verified_batch_proof().await.unwrap();
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn too_wide_slot_range_rejected() {
+    let (_server_handle, addr) = rpc_server().await;
+    let rpc_client = rpc_client(addr).await;
+
+    let err = rpc_client
+        .get_verified_batch_proofs_by_slot_range(U64::from(10), U64::from(5), U64::from(1))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.ledger_error_code(),
+        Some(LedgerRpcErrorCode::InvalidParams)
+    );
+    assert_eq!(
+        err.ledger_error_data(),
+        Some(InvalidRangeErrorData { start: 10, end: 5 })
+    );
+
+    let err = rpc_client
+        .get_verified_batch_proofs_by_slot_range(
+            U64::from(0),
+            U64::from(DEFAULT_MAX_SLOT_RANGE),
+            U64::from(1),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.ledger_error_code(),
+        Some(LedgerRpcErrorCode::RangeTooLarge)
+    );
+    assert_eq!(
+        err.ledger_error_data(),
+        Some(RangeTooLargeErrorData {
+            requested: DEFAULT_MAX_SLOT_RANGE + 1,
+            max: DEFAULT_MAX_SLOT_RANGE,
+        })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn too_wide_soft_confirmation_range_rejected() {
+    let (_server_handle, addr) = rpc_server().await;
+    let rpc_client = rpc_client(addr).await;
+
+    let err = rpc_client
+        .get_soft_confirmation_range(U64::from(10), U64::from(5), None)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.ledger_error_code(),
+        Some(LedgerRpcErrorCode::InvalidParams)
+    );
+
+    let err = rpc_client
+        .get_soft_confirmation_range(
+            U64::from(1),
+            U64::from(MAX_SOFT_CONFIRMATIONS_PER_REQUEST + 1),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.ledger_error_code(),
+        Some(LedgerRpcErrorCode::RangeTooLarge)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn too_many_hashes_rejected() {
+    let (_server_handle, addr) = rpc_server().await;
+    let rpc_client = rpc_client(addr).await;
+
+    let hashes = vec![HexHash([0; 32]); DEFAULT_MAX_HASHES_PER_REQUEST + 1];
+    let err = rpc_client
+        .get_soft_confirmations_by_hashes(hashes)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.ledger_error_code(),
+        Some(LedgerRpcErrorCode::RangeTooLarge)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn missing_soft_confirmation_status_not_found() {
+    let (_server_handle, addr) = rpc_server().await;
+    let rpc_client = rpc_client(addr).await;
+
+    let err = rpc_client
+        .get_soft_confirmation_status(U64::from(5))
+        .await
+        .unwrap_err();
+    match &err {
+        jsonrpsee::core::ClientError::Call(err) => {
+            assert_eq!(err.code(), -39001);
+            assert_eq!(
+                err.message(),
+                "Soft confirmation at height 5 not processed yet."
+            );
+        }
+        err => panic!("unexpected error: {err}"),
+    }
+    assert!(err.is_not_found());
+    assert_eq!(
+        err.ledger_error_data(),
+        Some(L2HeightErrorData { l2_height: 5 })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pruned_soft_confirmation_rejected() {
+    let dir = tempdir().unwrap();
+    let db = LedgerDB::with_config(&RocksdbConfig::new(dir.path(), None, None)).unwrap();
+    db.set_last_pruned_l2_height(10).unwrap();
+    let (_server_handle, addr) = rpc_server_with_ledger(db).await;
+    let rpc_client = rpc_client(addr).await;
+
+    let err = rpc_client
+        .get_soft_confirmation_status(U64::from(5))
+        .await
+        .unwrap_err();
+    assert!(err.is_pruned());
+    assert_eq!(
+        err.ledger_error_data(),
+        Some(PrunedErrorData {
+            l2_height: 5,
+            last_pruned_l2_height: 10,
+        })
+    );
+
+    let err = rpc_client
+        .get_soft_confirmation_by_number(U64::from(10), None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.ledger_error_code(), Some(LedgerRpcErrorCode::Pruned));
+
+    // Heights past the pruned ones are not processed yet
+    let err = rpc_client
+        .get_soft_confirmation_status(U64::from(11))
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found());
+    assert!(rpc_client
+        .get_soft_confirmation_by_number(U64::from(11), None)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+fn soft_confirmation_response(l2_height: u64) -> SoftConfirmationResponse {
+    SoftConfirmationResponse {
+        l2_height,
+        da_slot_height: 1,
+        da_slot_hash: [1; 32],
+        da_slot_txs_commitment: [2; 32],
+        hash: [3; 32],
+        prev_hash: [4; 32],
+        txs: Some(vec![vec![5; 10].into()]),
+        state_root: vec![6; 32],
+        soft_confirmation_signature: vec![7; 64],
+        pub_key: vec![8; 33],
+        deposit_data: vec![],
+        l1_fee_rate: 10,
+        timestamp: 11,
+        tx_summaries: None,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn compute_soft_confirmation_hash_requires_stored_soft_confirmation() {
+    let (_server_handle, addr) = rpc_server().await;
+    let rpc_client = rpc_client(addr).await;
+
+    let err = rpc_client
+        .compute_soft_confirmation_hash(soft_confirmation_response(5))
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found());
+    assert_eq!(
+        err.ledger_error_data(),
+        Some(L2HeightErrorData { l2_height: 5 })
+    );
+
+    let mut soft_confirmation = soft_confirmation_response(5);
+    soft_confirmation.txs = None;
+    let err = rpc_client
+        .compute_soft_confirmation_hash(soft_confirmation)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.ledger_error_code(),
+        Some(LedgerRpcErrorCode::InvalidParams)
+    );
+}
+
+#[test]
+fn error_codes_are_stable() {
+    for (code, value) in [
+        (LedgerRpcErrorCode::NotFound, -39001),
+        (LedgerRpcErrorCode::Pruned, -39002),
+        (LedgerRpcErrorCode::RangeTooLarge, -39003),
+        (LedgerRpcErrorCode::InvalidParams, -39004),
+        (LedgerRpcErrorCode::Database, -39005),
+    ] {
+        assert_eq!(code.code(), value);
+        assert_eq!(LedgerRpcErrorCode::from_code(value), Some(code));
+    }
+    assert_eq!(
+        LedgerRpcErrorCode::from_code(jsonrpsee::types::error::INVALID_PARAMS_CODE),
+        None
+    );
+}
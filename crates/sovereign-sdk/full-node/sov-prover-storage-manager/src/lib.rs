@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use sov_db::native_db::NativeDB;
 use sov_db::rocks_db_config::RocksdbConfig;
@@ -10,7 +11,9 @@ use sov_schema_db::snapshot::{DbSnapshot, ReadOnlyLock, SnapshotId};
 pub use sov_state::ProverStorage;
 use tracing::{debug, trace};
 
+use crate::persister::{PersistenceQueue, SnapshotPersister, DEFAULT_PERSISTENCE_QUEUE_SIZE};
 pub use crate::snapshot_manager::SnapshotManager;
+mod persister;
 mod snapshot_manager;
 
 /// Implementation that handles relation between snapshots
@@ -37,6 +40,9 @@ pub struct ProverStorageManager<Da: DaSpec> {
 
     state_snapshot_manager: Arc<RwLock<SnapshotManager>>,
     accessory_snapshot_manager: Arc<RwLock<SnapshotManager>>,
+
+    // Writes finalized L2 snapshots to the databases in the background
+    persister: SnapshotPersister,
 }
 
 impl<Da: DaSpec> ProverStorageManager<Da>
@@ -44,11 +50,36 @@ where
     Da::SlotHash: Hash,
 {
     fn with_db_handles(state_db: sov_schema_db::DB, native_db: sov_schema_db::DB) -> Self {
+        Self::with_persistence_config(
+            state_db,
+            native_db,
+            DEFAULT_PERSISTENCE_QUEUE_SIZE,
+            Duration::ZERO,
+        )
+    }
+
+    fn with_persistence_config(
+        state_db: sov_schema_db::DB,
+        native_db: sov_schema_db::DB,
+        persistence_queue_size: usize,
+        write_delay: Duration,
+    ) -> Self {
         let snapshot_id_to_parent = Arc::new(RwLock::new(HashMap::new()));
 
-        let state_snapshot_manager = SnapshotManager::new(state_db, snapshot_id_to_parent.clone());
-        let accessory_snapshot_manager =
-            SnapshotManager::new(native_db, snapshot_id_to_parent.clone());
+        let state_snapshot_manager = Arc::new(RwLock::new(SnapshotManager::new(
+            state_db,
+            snapshot_id_to_parent.clone(),
+        )));
+        let accessory_snapshot_manager = Arc::new(RwLock::new(SnapshotManager::new(
+            native_db,
+            snapshot_id_to_parent.clone(),
+        )));
+        let persister = SnapshotPersister::spawn(
+            state_snapshot_manager.clone(),
+            accessory_snapshot_manager.clone(),
+            persistence_queue_size,
+            write_delay,
+        );
 
         Self {
             chain_forks: Default::default(),
@@ -58,8 +89,9 @@ where
             block_height_to_snapshot_id: Default::default(),
             orphaned_snapshots: Default::default(),
             snapshot_id_to_parent,
-            state_snapshot_manager: Arc::new(RwLock::new(state_snapshot_manager)),
-            accessory_snapshot_manager: Arc::new(RwLock::new(accessory_snapshot_manager)),
+            state_snapshot_manager,
+            accessory_snapshot_manager,
+            persister,
         }
     }
 
@@ -103,21 +135,41 @@ where
     }
 
     fn finalize_by_l2_height(&mut self, l2_block_height: u64) -> anyhow::Result<()> {
-        let snapshot_id = self
+        let snapshot_id = *self
             .block_height_to_snapshot_id
-            .remove(&l2_block_height)
+            .get(&l2_block_height)
             .ok_or(anyhow::anyhow!("Attempt to finalize non existing snapshot"))?;
 
-        let mut state_manager = self.state_snapshot_manager.write().unwrap();
-        let mut native_manager = self.accessory_snapshot_manager.write().unwrap();
-        let mut snapshot_id_to_parent = self.snapshot_id_to_parent.write().unwrap();
-        snapshot_id_to_parent.remove(&snapshot_id);
+        let (state_snapshot, native_snapshot) = {
+            let mut state_manager = self.state_snapshot_manager.write().unwrap();
+            let mut native_manager = self.accessory_snapshot_manager.write().unwrap();
+            let mut snapshot_id_to_parent = self.snapshot_id_to_parent.write().unwrap();
+            // Heights have to be finalized in order, the parent is committed first
+            if snapshot_id_to_parent.contains_key(&snapshot_id) {
+                anyhow::bail!(
+                    "Attempt to finalize L2 height {} before its parent",
+                    l2_block_height
+                );
+            }
+            self.block_height_to_snapshot_id.remove(&l2_block_height);
 
-        // Return error here, as underlying database can return error
-        state_manager.commit_snapshot(&snapshot_id)?;
-        native_manager.commit_snapshot(&snapshot_id)?;
+            let state_snapshot = state_manager.finalize_snapshot(&snapshot_id)?;
+            let native_snapshot = native_manager.finalize_snapshot(&snapshot_id)?;
 
-        Ok(())
+            // Removing snapshot id pointer of the child, it reads the finalized state now
+            if let Some(child_snapshot_id) =
+                self.block_height_to_snapshot_id.get(&(l2_block_height + 1))
+            {
+                snapshot_id_to_parent.remove(child_snapshot_id);
+            }
+
+            (state_snapshot, native_snapshot)
+        };
+
+        // Outside of the locks, the persister takes them to release the written snapshots
+        self.persister
+            .queue()
+            .persist(state_snapshot, native_snapshot)
     }
 
     fn finalize_by_hash_pair(
@@ -202,6 +254,14 @@ where
             Some(snapshot_id) => *snapshot_id,
             None => {
                 let new_snapshot_id = self.latest_snapshot_id + 1;
+                // Storage on top of a saved but not yet finalized height reads through it
+                if let Some(parent_snapshot_id) = l2_block_height
+                    .checked_sub(1)
+                    .and_then(|parent_height| self.block_height_to_snapshot_id.get(&parent_height))
+                {
+                    let mut snapshot_id_to_parent = self.snapshot_id_to_parent.write().unwrap();
+                    snapshot_id_to_parent.insert(new_snapshot_id, *parent_snapshot_id);
+                }
                 self.block_height_to_snapshot_id
                     .insert(l2_block_height, new_snapshot_id);
                 self.latest_snapshot_id = new_snapshot_id;
@@ -216,10 +276,28 @@ where
         self.get_storage_with_snapshot_id(snapshot_id)
     }
 
+    /// Finalizes the snapshot of the L2 block, which is written to the database in the background.
+    /// Blocks only if too many finalized snapshots are waiting to be written.
     pub fn finalize_l2(&mut self, l2_block_height: u64) -> anyhow::Result<()> {
         self.finalize_by_l2_height(l2_block_height)
     }
 
+    /// Blocks until all the finalized L2 snapshots are written to the database.
+    pub fn flush(&self) -> anyhow::Result<()> {
+        self.persister.queue().flush()
+    }
+
+    /// Returns the L2 height whose state is the latest one written to the database,
+    /// None if not even the genesis state is.
+    /// Finalized L2 blocks above it were lost if the node crashed before writing them.
+    pub fn persisted_l2_height(&self) -> anyhow::Result<Option<u64>> {
+        self.flush()?;
+        let state_manager = self.state_snapshot_manager.read().unwrap();
+        // Genesis is committed at state version 1, so L2 height `h` is at version `h + 1`
+        let version = StateDB::<SnapshotManager>::latest_version_of_schema_db(state_manager.db())?;
+        Ok(version.and_then(|version| version.checked_sub(1)))
+    }
+
     pub fn save_change_set_l2(
         &mut self,
         l2_block_height: u64,
@@ -342,6 +420,37 @@ where
         Ok(ProverStorage::with_db_handles(state_db, native_db))
     }
 
+    /// Creates storage with the finalized state before the L2 block at `l2_block_height` was applied,
+    /// to re-execute the block. Its change set is discarded when saved.
+    pub fn create_historical_storage_on_l2_height(
+        &mut self,
+        l2_block_height: u64,
+    ) -> anyhow::Result<ProverStorage<SnapshotManager>> {
+        self.latest_snapshot_id += 1;
+        let snapshot_id = self.latest_snapshot_id;
+        debug!(
+            "Giving historical storage ref with id {} for block at height: {:?}",
+            snapshot_id, l2_block_height
+        );
+        self.orphaned_snapshots.insert(snapshot_id);
+        let state_db_snapshot = DbSnapshot::new(
+            snapshot_id,
+            ReadOnlyLock::new(self.state_snapshot_manager.clone()),
+        );
+
+        let mut state_db = StateDB::with_db_snapshot(state_db_snapshot)?;
+        // Genesis is version 1, so the state before block `l2_block_height` is that version
+        state_db.pin_to_version(l2_block_height);
+
+        let native_db_snapshot = DbSnapshot::new(
+            snapshot_id,
+            ReadOnlyLock::new(self.accessory_snapshot_manager.clone()),
+        );
+
+        let native_db = NativeDB::with_db_snapshot(native_db_snapshot)?;
+        Ok(ProverStorage::with_db_handles(state_db, native_db))
+    }
+
     pub fn save_change_set(
         &mut self,
         block_header: &Da::BlockHeader,
@@ -406,6 +515,60 @@ where
         let prev_block_hash = block_header.prev_hash();
         self.finalize_by_hash_pair(prev_block_hash, current_block_hash)
     }
+
+    /// Rolls back the finalized state and accessory state to `l2_block_height`.
+    /// Snapshots of the heights above it which are not finalized yet are discarded.
+    pub fn rollback_l2_to(&mut self, l2_block_height: u64) -> anyhow::Result<()> {
+        // Finalized snapshots above the height would be written after the rollback otherwise
+        self.flush()?;
+
+        let mut state_manager = self.state_snapshot_manager.write().unwrap();
+        let mut native_manager = self.accessory_snapshot_manager.write().unwrap();
+        let mut snapshot_id_to_parent = self.snapshot_id_to_parent.write().unwrap();
+
+        self.block_height_to_snapshot_id
+            .retain(|height, snapshot_id| {
+                if *height <= l2_block_height {
+                    return true;
+                }
+                state_manager.discard_snapshot(snapshot_id);
+                native_manager.discard_snapshot(snapshot_id);
+                snapshot_id_to_parent.remove(snapshot_id);
+                false
+            });
+
+        // Genesis is committed at state version 1, so L2 height `h` is at version `h + 1`
+        StateDB::<SnapshotManager>::rollback_schema_db(state_manager.db(), l2_block_height + 1)?;
+        NativeDB::<SnapshotManager>::rollback_schema_db(native_manager.db(), l2_block_height)?;
+        debug!("Rolled back storage to L2 height {}", l2_block_height);
+        Ok(())
+    }
+
+    /// Returns a handle which deletes finalized accessory state, e.g. from a pruning task
+    pub fn accessory_state_pruner(&self) -> AccessoryStatePruner {
+        AccessoryStatePruner {
+            accessory_snapshot_manager: self.accessory_snapshot_manager.clone(),
+            persistence_queue: self.persister.queue().clone(),
+        }
+    }
+}
+
+/// Deletes finalized accessory state, which is not part of the state root, from the database.
+/// All versions of a key are deleted, so it should only be used for keys which are not written anymore.
+#[derive(Clone)]
+pub struct AccessoryStatePruner {
+    accessory_snapshot_manager: Arc<RwLock<SnapshotManager>>,
+    persistence_queue: PersistenceQueue,
+}
+
+impl AccessoryStatePruner {
+    /// Deletes the given keys with all of their versions in a single write
+    pub fn delete_keys(&self, keys: impl IntoIterator<Item = Vec<u8>>) -> anyhow::Result<()> {
+        // Versions still waiting to be persisted would be written after the deletion otherwise
+        self.persistence_queue.flush()?;
+        let accessory_snapshot_manager = self.accessory_snapshot_manager.read().unwrap();
+        accessory_snapshot_manager.delete_accessory_keys(keys)
+    }
 }
 
 /// Creates orphan [`ProverStorage`] which just points directly to the underlying database for previous data
@@ -974,6 +1137,122 @@ mod tests {
         assert!(storage_manager.is_empty());
     }
 
+    #[test]
+    fn deferred_l2_finalization() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+
+        let mut storage_manager = ProverStorageManager::<Da>::with_db_handles(state_db, native_db);
+        let mut witness = ArrayWitness::default();
+
+        let storage_1 = storage_manager.create_storage_on_l2_height(1).unwrap();
+        {
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations.ordered_writes.push(write_op(1, 2));
+            let mut native_operations = OrderedReadsAndWrites::default();
+            let offchain_operations = OrderedReadsAndWrites::default();
+            native_operations.ordered_writes.push(write_op(30, 40));
+            let (_, state_update, _) = storage_1
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage_1.commit(&state_update, &native_operations, &offchain_operations);
+        }
+        storage_manager.save_change_set_l2(1, storage_1).unwrap();
+
+        // Height 1 is not finalized, height 2 has to read through its snapshot
+        let storage_2 = storage_manager.create_storage_on_l2_height(2).unwrap();
+        assert_eq!(
+            Some(value_from(2).into()),
+            storage_2.get(&key_from(1).into(), None, &mut witness)
+        );
+        assert_eq!(
+            Some(value_from(40).into()),
+            storage_2.get_accessory(&key_from(30).into(), None)
+        );
+        {
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations.ordered_writes.push(write_op(3, 4));
+            let native_operations = OrderedReadsAndWrites::default();
+            let offchain_operations = OrderedReadsAndWrites::default();
+            let (_, state_update, _) = storage_2
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage_2.commit(&state_update, &native_operations, &offchain_operations);
+        }
+        storage_manager.save_change_set_l2(2, storage_2).unwrap();
+
+        let result = storage_manager.finalize_l2(2);
+        assert_eq!(
+            "Attempt to finalize L2 height 2 before its parent",
+            result.unwrap_err().to_string()
+        );
+
+        storage_manager.finalize_l2(1).unwrap();
+        storage_manager.finalize_l2(2).unwrap();
+        assert!(storage_manager.is_empty());
+
+        let storage_3 = storage_manager.create_storage_on_l2_height(3).unwrap();
+        assert_eq!(
+            Some(value_from(2).into()),
+            storage_3.get(&key_from(1).into(), None, &mut witness)
+        );
+        assert_eq!(
+            Some(value_from(4).into()),
+            storage_3.get(&key_from(3).into(), None, &mut witness)
+        );
+        assert_eq!(
+            Some(value_from(40).into()),
+            storage_3.get_accessory(&key_from(30).into(), None)
+        );
+    }
+
+    #[test]
+    fn prune_accessory_state() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+
+        let mut storage_manager = ProverStorageManager::<Da>::with_db_handles(state_db, native_db);
+        let mut witness = ArrayWitness::default();
+
+        for (l2_height, native_writes) in [
+            (1, vec![write_op(30, 40), write_op(31, 41)]),
+            (2, vec![write_op(30, 50)]),
+        ] {
+            let storage = storage_manager
+                .create_storage_on_l2_height(l2_height)
+                .unwrap();
+            let mut native_operations = OrderedReadsAndWrites::default();
+            native_operations.ordered_writes.extend(native_writes);
+            let (_, state_update, _) = storage
+                .compute_state_update(OrderedReadsAndWrites::default(), &mut witness)
+                .unwrap();
+            storage.commit(
+                &state_update,
+                &native_operations,
+                &OrderedReadsAndWrites::default(),
+            );
+            storage_manager
+                .save_change_set_l2(l2_height, storage)
+                .unwrap();
+            storage_manager.finalize_l2(l2_height).unwrap();
+        }
+
+        // Deleting only the latest version would expose the previous one
+        storage_manager
+            .accessory_state_pruner()
+            .delete_keys([key_from(30).key.to_vec()])
+            .unwrap();
+
+        let storage = storage_manager.create_storage_on_l2_height(3).unwrap();
+        assert_eq!(None, storage.get_accessory(&key_from(30).into(), None));
+        assert_eq!(
+            Some(value_from(41).into()),
+            storage.get_accessory(&key_from(31).into(), None)
+        );
+    }
+
     #[test]
     fn lifecycle_simulation() {
         let tmpdir = tempfile::tempdir().unwrap();
@@ -1384,4 +1663,164 @@ mod tests {
             storage_last.get_accessory(&key_from(3).into(), None)
         );
     }
+
+    /// Applies an L2 block writing `l2_height` to the key `l2_height` of both the state and the accessory state
+    fn apply_l2_block(storage_manager: &mut ProverStorageManager<Da>, l2_height: u64) {
+        let mut witness = ArrayWitness::default();
+        let storage = storage_manager
+            .create_storage_on_l2_height(l2_height)
+            .unwrap();
+        let mut state_operations = OrderedReadsAndWrites::default();
+        state_operations
+            .ordered_writes
+            .push(write_op(l2_height, l2_height));
+        let mut native_operations = OrderedReadsAndWrites::default();
+        native_operations
+            .ordered_writes
+            .push(write_op(l2_height, l2_height));
+        let (_, state_update, _) = storage
+            .compute_state_update(state_operations, &mut witness)
+            .unwrap();
+        storage.commit(
+            &state_update,
+            &native_operations,
+            &OrderedReadsAndWrites::default(),
+        );
+        storage_manager
+            .save_change_set_l2(l2_height, storage)
+            .unwrap();
+    }
+
+    fn assert_l2_blocks_applied(storage_manager: &mut ProverStorageManager<Da>, l2_heights: u64) {
+        let mut witness = ArrayWitness::default();
+        let storage = storage_manager
+            .create_storage_on_l2_height(l2_heights + 1)
+            .unwrap();
+        for l2_height in 1..=l2_heights {
+            assert_eq!(
+                Some(value_from(l2_height).into()),
+                storage.get(&key_from(l2_height).into(), None, &mut witness)
+            );
+            assert_eq!(
+                Some(value_from(l2_height).into()),
+                storage.get_accessory(&key_from(l2_height).into(), None)
+            );
+        }
+    }
+
+    #[test]
+    fn finalized_l2_snapshots_are_read_until_persisted() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        {
+            let (state_db, native_db) = build_dbs(tmpdir.path());
+            let mut storage_manager = ProverStorageManager::<Da>::with_persistence_config(
+                state_db,
+                native_db,
+                4,
+                Duration::from_millis(100),
+            );
+
+            for l2_height in 1..=3 {
+                apply_l2_block(&mut storage_manager, l2_height);
+                storage_manager.finalize_l2(l2_height).unwrap();
+            }
+            assert!(storage_manager.is_empty());
+            assert!(
+                storage_manager
+                    .state_snapshot_manager
+                    .read()
+                    .unwrap()
+                    .unpersisted_count()
+                    > 0
+            );
+            assert_l2_blocks_applied(&mut storage_manager, 3);
+
+            storage_manager.flush().unwrap();
+            assert_eq!(
+                0,
+                storage_manager
+                    .state_snapshot_manager
+                    .read()
+                    .unwrap()
+                    .unpersisted_count()
+            );
+            assert_eq!(
+                0,
+                storage_manager
+                    .accessory_snapshot_manager
+                    .read()
+                    .unwrap()
+                    .unpersisted_count()
+            );
+
+            apply_l2_block(&mut storage_manager, 4);
+            storage_manager.finalize_l2(4).unwrap();
+            // Dropping the manager writes the queued snapshots
+        }
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+        let mut storage_manager = ProverStorageManager::<Da>::with_db_handles(state_db, native_db);
+        assert_l2_blocks_applied(&mut storage_manager, 4);
+    }
+
+    #[test]
+    fn slow_disk_does_not_delay_l2_blocks_until_queue_is_full() {
+        const QUEUE_SIZE: usize = 4;
+        const BLOCK_INTERVAL: Duration = Duration::from_millis(20);
+        // Both the state and the accessory state are written, so a block takes twice as long to persist
+        const WRITE_DELAY: Duration = Duration::from_millis(100);
+        const TOLERANCE: Duration = Duration::from_millis(50);
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+        let mut storage_manager = ProverStorageManager::<Da>::with_persistence_config(
+            state_db,
+            native_db,
+            QUEUE_SIZE,
+            WRITE_DELAY,
+        );
+
+        let l2_heights = 3 * QUEUE_SIZE as u64;
+        let mut block_times = vec![];
+        for l2_height in 1..=l2_heights {
+            let start = std::time::Instant::now();
+            apply_l2_block(&mut storage_manager, l2_height);
+            storage_manager.finalize_l2(l2_height).unwrap();
+            let block_time = start.elapsed();
+            block_times.push(block_time);
+            std::thread::sleep(BLOCK_INTERVAL.saturating_sub(block_time));
+        }
+
+        // One snapshot is being written while the queue fills up
+        for (index, block_time) in block_times.iter().take(QUEUE_SIZE + 1).enumerate() {
+            assert!(
+                *block_time < TOLERANCE,
+                "L2 block {} took {:?} before the persistence queue was full",
+                index + 1,
+                block_time
+            );
+        }
+        // Then block production is held back to the speed of the disk
+        assert!(
+            block_times[QUEUE_SIZE + 1..]
+                .iter()
+                .any(|block_time| *block_time >= WRITE_DELAY),
+            "No backpressure with a full persistence queue: {:?}",
+            block_times
+        );
+        let unpersisted_count = storage_manager
+            .state_snapshot_manager
+            .read()
+            .unwrap()
+            .unpersisted_count();
+        assert!(
+            unpersisted_count <= QUEUE_SIZE + 2,
+            "{} finalized snapshots are waiting to be persisted",
+            unpersisted_count
+        );
+
+        storage_manager.flush().unwrap();
+        assert_l2_blocks_applied(&mut storage_manager, l2_heights);
+    }
 }
@@ -0,0 +1,165 @@
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use sov_schema_db::snapshot::ReadOnlyDbSnapshot;
+use tracing::{debug, error};
+
+use crate::SnapshotManager;
+
+/// Number of finalized snapshots which can wait to be persisted before finalization blocks
+pub(crate) const DEFAULT_PERSISTENCE_QUEUE_SIZE: usize = 32;
+
+/// Delay before writing a finalized snapshot again after the database failed to write it
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+enum PersistenceJob {
+    Persist {
+        state_snapshot: Arc<ReadOnlyDbSnapshot>,
+        native_snapshot: Arc<ReadOnlyDbSnapshot>,
+    },
+    /// Acknowledged once the snapshots queued before it are persisted
+    Flush(mpsc::Sender<()>),
+    Stop,
+}
+
+/// Queue of the finalized snapshots to be written to the databases, in order of finalization.
+#[derive(Clone)]
+pub(crate) struct PersistenceQueue(SyncSender<PersistenceJob>);
+
+impl PersistenceQueue {
+    /// Queues the finalized snapshots to be written.
+    /// Blocks only while the queue is full, until the oldest queued snapshot is persisted.
+    pub(crate) fn persist(
+        &self,
+        state_snapshot: Arc<ReadOnlyDbSnapshot>,
+        native_snapshot: Arc<ReadOnlyDbSnapshot>,
+    ) -> anyhow::Result<()> {
+        self.0
+            .send(PersistenceJob::Persist {
+                state_snapshot,
+                native_snapshot,
+            })
+            .map_err(|_| anyhow::anyhow!("Snapshot persistence has stopped"))
+    }
+
+    /// Blocks until all the queued snapshots are written to the databases.
+    pub(crate) fn flush(&self) -> anyhow::Result<()> {
+        let (done_tx, done_rx) = mpsc::channel();
+        self.0
+            .send(PersistenceJob::Flush(done_tx))
+            .map_err(|_| anyhow::anyhow!("Snapshot persistence has stopped"))?;
+        done_rx
+            .recv()
+            .map_err(|_| anyhow::anyhow!("Snapshot persistence has stopped"))
+    }
+}
+
+/// Writes the finalized snapshots to the databases on a background thread,
+/// so that finalization only moves snapshot references.
+/// Snapshots keep being read from memory until they are written.
+/// The queued snapshots are written before the persister is dropped.
+pub(crate) struct SnapshotPersister {
+    queue: PersistenceQueue,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl SnapshotPersister {
+    /// Spawns the writing thread. Each write is delayed by `write_delay`, to simulate a slow disk.
+    pub(crate) fn spawn(
+        state_snapshot_manager: Arc<RwLock<SnapshotManager>>,
+        accessory_snapshot_manager: Arc<RwLock<SnapshotManager>>,
+        queue_size: usize,
+        write_delay: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(queue_size);
+        let worker = std::thread::Builder::new()
+            .name("snapshot-persister".to_string())
+            .spawn(move || {
+                run(
+                    receiver,
+                    state_snapshot_manager,
+                    accessory_snapshot_manager,
+                    write_delay,
+                )
+            })
+            .expect("Failed to spawn the snapshot persister thread");
+
+        Self {
+            queue: PersistenceQueue(sender),
+            worker: Some(worker),
+        }
+    }
+
+    pub(crate) fn queue(&self) -> &PersistenceQueue {
+        &self.queue
+    }
+}
+
+impl Drop for SnapshotPersister {
+    fn drop(&mut self) {
+        // Clones of the queue may outlive the persister, so the worker is stopped explicitly
+        let _ = self.queue.0.send(PersistenceJob::Stop);
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                error!("Snapshot persister thread panicked");
+            }
+        }
+    }
+}
+
+fn run(
+    receiver: Receiver<PersistenceJob>,
+    state_snapshot_manager: Arc<RwLock<SnapshotManager>>,
+    accessory_snapshot_manager: Arc<RwLock<SnapshotManager>>,
+    write_delay: Duration,
+) {
+    for job in receiver {
+        match job {
+            PersistenceJob::Persist {
+                state_snapshot,
+                native_snapshot,
+            } => {
+                persist_snapshot(&state_snapshot_manager, &state_snapshot, write_delay);
+                persist_snapshot(&accessory_snapshot_manager, &native_snapshot, write_delay);
+            }
+            PersistenceJob::Flush(done) => {
+                let _ = done.send(());
+            }
+            PersistenceJob::Stop => break,
+        }
+    }
+}
+
+/// Writes the snapshot to the database of its manager, retrying until it succeeds.
+/// The snapshot is read from memory in the meantime, so a failed write loses nothing.
+fn persist_snapshot(
+    snapshot_manager: &RwLock<SnapshotManager>,
+    snapshot: &ReadOnlyDbSnapshot,
+    write_delay: Duration,
+) {
+    let db = snapshot_manager.read().unwrap().db_handle();
+    loop {
+        if !write_delay.is_zero() {
+            std::thread::sleep(write_delay);
+        }
+        match db.write_schemas_ref(snapshot.as_batch()) {
+            Ok(()) => break,
+            Err(e) => {
+                error!(
+                    "Failed to persist finalized snapshot id={}, retrying: {:?}",
+                    snapshot.get_id(),
+                    e
+                );
+                std::thread::sleep(RETRY_INTERVAL);
+            }
+        }
+    }
+
+    snapshot_manager
+        .write()
+        .unwrap()
+        .mark_persisted(&snapshot.get_id());
+    debug!("Finalized snapshot id={} is persisted", snapshot.get_id());
+}
@@ -1,12 +1,15 @@
 use std::cmp::Ordering;
-use std::collections::{btree_map, HashMap};
+use std::collections::{btree_map, HashMap, VecDeque};
 use std::iter::{Peekable, Rev};
 use std::sync::{Arc, RwLock};
 
+use sov_db::schema::tables::ModuleAccessoryState;
+use sov_db::schema::types::AccessoryKey;
 use sov_schema_db::schema::{KeyCodec, ValueCodec};
 use sov_schema_db::snapshot::{QueryManager, ReadOnlyDbSnapshot, SnapshotId};
 use sov_schema_db::{
-    Operation, RawDbReverseIterator, Schema, SchemaBatchIterator, SchemaKey, SchemaValue,
+    Operation, RawDbReverseIterator, Schema, SchemaBatch, SchemaBatchIterator, SchemaKey,
+    SchemaValue,
 };
 
 use crate::snapshot_manager::DataLocation::Snapshot;
@@ -15,8 +18,11 @@ use crate::snapshot_manager::DataLocation::Snapshot;
 /// down to DB level
 /// Managed externally by [`crate::ProverStorageManager`]
 pub struct SnapshotManager {
-    db: sov_schema_db::DB,
+    db: Arc<sov_schema_db::DB>,
     snapshots: HashMap<SnapshotId, ReadOnlyDbSnapshot>,
+    /// Finalized snapshots which are not written to the database yet, oldest first.
+    /// Every snapshot reads through them as if they were part of the database.
+    unpersisted: VecDeque<Arc<ReadOnlyDbSnapshot>>,
     /// Hierarchical
     to_parent: Arc<RwLock<HashMap<SnapshotId, SnapshotId>>>,
 }
@@ -27,8 +33,9 @@ impl SnapshotManager {
         to_parent: Arc<RwLock<HashMap<SnapshotId, SnapshotId>>>,
     ) -> Self {
         Self {
-            db,
+            db: Arc::new(db),
             snapshots: HashMap::new(),
+            unpersisted: VecDeque::new(),
             to_parent,
         }
     }
@@ -37,8 +44,9 @@ impl SnapshotManager {
     /// So it only reads from database.
     pub fn orphan(db: sov_schema_db::DB) -> Self {
         Self {
-            db,
+            db: Arc::new(db),
             snapshots: HashMap::new(),
+            unpersisted: VecDeque::new(),
             to_parent: Arc::new(RwLock::new(Default::default())),
         }
     }
@@ -63,11 +71,75 @@ impl SnapshotManager {
         self.db.write_schemas(snapshot.into())
     }
 
+    /// Marks the snapshot as finalized without writing it to the database.
+    /// It is still read until [`Self::mark_persisted`] is called once the returned snapshot is written.
+    pub(crate) fn finalize_snapshot(
+        &mut self,
+        snapshot_id: &SnapshotId,
+    ) -> anyhow::Result<Arc<ReadOnlyDbSnapshot>> {
+        let Some(snapshot) = self.snapshots.remove(snapshot_id) else {
+            anyhow::bail!("Attempt to commit unknown snapshot");
+        };
+        let snapshot = Arc::new(snapshot);
+        self.unpersisted.push_back(snapshot.clone());
+        Ok(snapshot)
+    }
+
+    /// Stops reading the oldest finalized snapshot, once it was written to the database.
+    pub(crate) fn mark_persisted(&mut self, snapshot_id: &SnapshotId) {
+        let snapshot = self
+            .unpersisted
+            .pop_front()
+            .expect("No finalized snapshot is waiting to be persisted");
+        assert_eq!(
+            snapshot.get_id(),
+            *snapshot_id,
+            "Finalized snapshots must be persisted in order"
+        );
+    }
+
+    /// Database the snapshots are committed to
+    pub(crate) fn db(&self) -> &sov_schema_db::DB {
+        &self.db
+    }
+
+    /// Handle to the database, to write finalized snapshots without holding the manager
+    pub(crate) fn db_handle(&self) -> Arc<sov_schema_db::DB> {
+        self.db.clone()
+    }
+
+    /// Deletes all versions of the given accessory keys from the database in a single write.
+    /// Snapshots are not touched, so only keys which are not written anymore should be deleted.
+    pub(crate) fn delete_accessory_keys(
+        &self,
+        keys: impl IntoIterator<Item = AccessoryKey>,
+    ) -> anyhow::Result<()> {
+        let mut batch = SchemaBatch::new();
+        let mut iter = self.db.iter::<ModuleAccessoryState>()?;
+        for key in keys {
+            // Versions of a key are stored next to each other, starting from the lowest one
+            iter.seek(&(key.clone(), 0))?;
+            for item in iter.by_ref() {
+                let ((found_key, version), _) = item?.into_tuple();
+                if found_key != key {
+                    break;
+                }
+                batch.delete::<ModuleAccessoryState>(&(found_key, version))?;
+            }
+        }
+        self.db.write_schemas(batch)
+    }
+
     #[cfg(test)]
     pub(crate) fn is_empty(&self) -> bool {
         self.snapshots.is_empty()
     }
 
+    #[cfg(test)]
+    pub(crate) fn unpersisted_count(&self) -> usize {
+        self.unpersisted.len()
+    }
+
     pub(crate) fn contains_snapshot(&self, snapshot_id: &SnapshotId) -> bool {
         self.snapshots.contains_key(snapshot_id)
     }
@@ -89,6 +161,9 @@ impl SnapshotManager {
 
             snapshot_id = *parent_snapshot_id;
         }
+        for snapshot in self.unpersisted.iter().rev() {
+            snapshot_iterators.push(snapshot.iter::<S>());
+        }
 
         snapshot_iterators.reverse();
         let db_iter = self.db.raw_iter::<S>()?;
@@ -112,6 +187,9 @@ impl SnapshotManager {
 
             snapshot_id = *parent_snapshot_id;
         }
+        for snapshot in self.unpersisted.iter().rev() {
+            snapshot_iterators.push(snapshot.iter_range::<S>(upper_bound.clone()));
+        }
 
         snapshot_iterators.reverse();
         let mut db_iter = self.db.raw_iter::<S>()?;
@@ -273,6 +351,14 @@ impl QueryManager for SnapshotManager {
 
             snapshot_id = *parent_snapshot_id;
         }
+        for snapshot in self.unpersisted.iter().rev() {
+            if let Some(operation) = snapshot.get(key)? {
+                return match operation {
+                    Operation::Put { value } => Ok(Some(S::Value::decode_value(value)?)),
+                    Operation::Delete => Ok(None),
+                };
+            }
+        }
         self.db.get(key)
     }
 
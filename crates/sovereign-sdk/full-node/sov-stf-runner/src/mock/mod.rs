@@ -50,8 +50,8 @@ impl<Da: DaSpec> StateTransitionFunction<Da> for MockStf {
 
     fn apply_soft_confirmations_from_sequencer_commitments(
         &mut self,
-        _sequencer_public_key: &[u8],
-        _sequencer_da_public_key: &[u8],
+        _sequencer_public_keys: &[(u64, &[u8])],
+        _sequencer_da_public_keys: &[&[u8]],
         _initial_state_root: &Self::StateRoot,
         _pre_state: Self::PreState,
         _da_data: Vec<<Da as DaSpec>::BlobTransaction>,
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sov_rollup_interface::services::da::DaService;
+use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::zk::Proof;
 use thiserror::Error;
 
@@ -16,6 +17,9 @@ pub enum ProverGuestRunConfig {
     Prove,
     /// Run the rollup verifier and create a SNARK or a fake proof of execution.
     ProveWithFakeProofs,
+    /// Run the rollup verifier natively on the simulated guest, to check that the
+    /// commitments would prove. Nothing is proven or submitted to the DA.
+    Simulate,
 }
 
 impl<'de> Deserialize<'de> for ProverGuestRunConfig {
@@ -29,6 +33,7 @@ impl<'de> Deserialize<'de> for ProverGuestRunConfig {
             "execute" => Ok(ProverGuestRunConfig::Execute),
             "prove" => Ok(ProverGuestRunConfig::Prove),
             "prove-with-fakes" => Ok(ProverGuestRunConfig::ProveWithFakeProofs),
+            "simulate" => Ok(ProverGuestRunConfig::Simulate),
             _ => Err(serde::de::Error::custom("invalid prover guest run config")),
         }
     }
@@ -61,6 +66,19 @@ pub enum ProofProcessingStatus {
     Busy,
 }
 
+/// Snapshot of the work handled by a prover service.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProverServiceStatus {
+    /// Number of proofs whose data was added but whose proving has not started yet.
+    pub queued_proofs: usize,
+    /// Number of proofs currently being generated.
+    pub ongoing_proofs: usize,
+    /// Maximum number of proofs generated in parallel.
+    pub parallel_proof_limit: usize,
+    /// Identifiers of proving sessions that have been started but not finished yet.
+    pub pending_sessions: Vec<Vec<u8>>,
+}
+
 /// An error that occurred during ZKP proving.
 #[derive(Error, Debug)]
 pub enum ProverServiceError {
@@ -91,14 +109,16 @@ pub trait ProverService {
     /// Prove added input and assumptions.
     async fn prove(&self, elf: Vec<u8>) -> anyhow::Result<Vec<Proof>>;
 
-    /// Submit proofs to DA.
+    /// Submit proofs of L2 blocks of `spec_id` to DA.
     async fn submit_proofs(
         &self,
         proofs: Vec<Proof>,
+        spec_id: SpecId,
     ) -> anyhow::Result<Vec<(<Self::DaService as DaService>::TransactionId, Proof)>>;
 
-    /// Recover the ongoing sessions and submit them to DA.
-    async fn recover_and_submit_proving_sessions(
-        &self,
-    ) -> anyhow::Result<Vec<(<Self::DaService as DaService>::TransactionId, Proof)>>;
+    /// Recover the proofs of the ongoing sessions, without submitting them to DA.
+    async fn recover_proving_sessions(&self) -> anyhow::Result<Vec<Proof>>;
+
+    /// Returns the current proving workload.
+    fn status(&self) -> anyhow::Result<ProverServiceStatus>;
 }
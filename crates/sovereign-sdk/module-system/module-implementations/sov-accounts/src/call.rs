@@ -0,0 +1,85 @@
+use core::result::Result;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use sov_modules_api::{
+    CallResponse, Context, Signature, SoftConfirmationModuleCallError, StateMapAccessor, WorkingSet,
+};
+
+use crate::Accounts;
+
+/// Domain of the messages signed to update the public key of an account
+const UPDATE_PUBLIC_KEY_DOMAIN: &[u8] = b"sov-accounts/update-public-key";
+
+/// Represents the available call messages for interacting with the sov-accounts module.
+#[cfg_attr(
+    feature = "native",
+    derive(schemars::JsonSchema),
+    schemars(
+        bound = "C::PublicKey: ::schemars::JsonSchema, C::Signature: ::schemars::JsonSchema",
+        rename = "CallMessage"
+    )
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    derive(serde::Deserialize)
+)]
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize, PartialEq)]
+pub enum CallMessage<C: Context> {
+    /// Binds the account of the sender to a new public key.
+    /// The previous public key no longer has an account afterwards.
+    UpdatePublicKey {
+        /// The new public key of the account.
+        new_pub_key: C::PublicKey,
+        /// Signature of [`update_public_key_message`] by the new public key,
+        /// so that a key can only be bound by its holder.
+        signature: C::Signature,
+    },
+}
+
+/// Returns the message the new public key of the account at `address` signs to authorize the update.
+/// It commits to the nonce of the account, so the signature can not be replayed by later transactions.
+pub fn update_public_key_message<C: Context>(
+    address: &C::Address,
+    nonce: u64,
+    new_pub_key: &C::PublicKey,
+) -> Vec<u8> {
+    borsh::to_vec(&(UPDATE_PUBLIC_KEY_DOMAIN, address, nonce, new_pub_key))
+        .expect("Public key update message serialization can not fail")
+}
+
+impl<C: Context> Accounts<C> {
+    pub(crate) fn update_public_key(
+        &self,
+        new_pub_key: C::PublicKey,
+        signature: C::Signature,
+        context: &C,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> Result<CallResponse, SoftConfirmationModuleCallError> {
+        if self.accounts.get(&new_pub_key, working_set).is_some() {
+            return Err(SoftConfirmationModuleCallError::AccountsPublicKeyAlreadyBound);
+        }
+
+        let pub_key = self
+            .public_keys
+            .get(context.sender(), working_set)
+            .ok_or(SoftConfirmationModuleCallError::AccountsAccountNotFound)?;
+        let account = self
+            .accounts
+            .get(&pub_key, working_set)
+            .ok_or(SoftConfirmationModuleCallError::AccountsAccountNotFound)?;
+
+        let message = update_public_key_message::<C>(&account.addr, account.nonce, &new_pub_key);
+        signature
+            .verify(&new_pub_key, &message)
+            .map_err(|_| SoftConfirmationModuleCallError::AccountsInvalidPublicKeySignature)?;
+
+        // The account keeps its address and nonce
+        self.accounts.remove(&pub_key, working_set);
+        self.accounts.set(&new_pub_key, &account, working_set);
+        self.public_keys
+            .set(context.sender(), &new_pub_key, working_set);
+
+        Ok(CallResponse::default())
+    }
+}
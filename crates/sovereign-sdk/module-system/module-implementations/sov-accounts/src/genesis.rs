@@ -3,6 +3,10 @@ use core::result::Result;
 use sov_modules_api::{
     Context, PublicKey, SoftConfirmationHookError, StateMapAccessor, WorkingSet,
 };
+#[cfg(feature = "native")]
+use sov_state::codec::BorshCodec;
+#[cfg(feature = "native")]
+use sov_state::storage::{StateKeyCodec, StateValueCodec};
 
 use crate::{Account, Accounts};
 
@@ -61,6 +65,29 @@ impl<C: sov_modules_api::Context> Accounts<C> {
     }
 }
 
+#[cfg(feature = "native")]
+impl<C: sov_modules_api::Context> Accounts<C> {
+    /// Returns the config creating the accounts of the working set at genesis.
+    /// Nonces are not part of the config, so the accounts start over from nonce 0.
+    pub fn export_genesis_config(
+        &self,
+        keys: &sov_modules_api::StateKeys,
+        working_set: &mut WorkingSet<C::Storage>,
+    ) -> anyhow::Result<AccountConfig<C>> {
+        let prefix: &[u8] = self.public_keys.prefix().as_aligned_vec().as_ref();
+        let key_len = prefix.len() + BorshCodec.encode_key(&self.address).len();
+
+        let mut pub_keys = vec![];
+        for key in keys(prefix, key_len)? {
+            let address: C::Address = BorshCodec.try_decode_value(&key?[prefix.len()..])?;
+            if let Some(pub_key) = self.public_keys.get(&address, working_set) {
+                pub_keys.push(pub_key);
+            }
+        }
+        Ok(AccountConfig { pub_keys })
+    }
+}
+
 #[cfg(all(test, feature = "native"))]
 mod tests {
     use std::str::FromStr;
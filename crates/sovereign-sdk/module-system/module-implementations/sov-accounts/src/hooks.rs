@@ -47,16 +47,21 @@ impl<C: Context> TxHooks for Accounts<C> {
 
     fn post_dispatch_tx_hook(
         &self,
-        tx: &Transaction<Self::Context>,
-        _ctx: &C,
+        _tx: &Transaction<Self::Context>,
+        ctx: &C,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<(), SoftConfirmationHookError> {
+        // The transaction may have updated the public key of the sender
+        let pub_key = self
+            .public_keys
+            .get_or_err(ctx.sender(), working_set)
+            .map_err(|_| SoftConfirmationHookError::SovTxAccountNotFound)?;
         let mut account = self
             .accounts
-            .get_or_err(tx.pub_key(), working_set)
+            .get_or_err(&pub_key, working_set)
             .map_err(|_| SoftConfirmationHookError::SovTxAccountNotFound)?;
         account.nonce += 1;
-        self.accounts.set(tx.pub_key(), &account, working_set);
+        self.accounts.set(&pub_key, &account, working_set);
         Ok(())
     }
 }
@@ -1,5 +1,7 @@
+mod call;
 mod genesis;
 mod hooks;
+pub use call::*;
 pub use genesis::*;
 #[cfg(feature = "native")]
 mod query;
@@ -50,7 +52,7 @@ impl<C: Context> sov_modules_api::Module for Accounts<C> {
 
     type Config = AccountConfig<C>;
 
-    type CallMessage = ();
+    type CallMessage = CallMessage<C>;
 
     fn genesis(&self, config: &Self::Config, working_set: &mut WorkingSet<C::Storage>) {
         self.init_module(config, working_set)
@@ -58,10 +60,15 @@ impl<C: Context> sov_modules_api::Module for Accounts<C> {
 
     fn call(
         &mut self,
-        _msg: Self::CallMessage,
-        _context: &Self::Context,
-        _working_set: &mut WorkingSet<C::Storage>,
+        msg: Self::CallMessage,
+        context: &Self::Context,
+        working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<sov_modules_api::CallResponse, SoftConfirmationModuleCallError> {
-        Ok(sov_modules_api::CallResponse::default())
+        match msg {
+            CallMessage::UpdatePublicKey {
+                new_pub_key,
+                signature,
+            } => self.update_public_key(new_pub_key, signature, context, working_set),
+        }
     }
 }
@@ -8,27 +8,49 @@ use crate::{Account, Accounts};
 /// This is the response returned from the accounts_getAccount endpoint.
 #[derive(Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
 pub enum Response {
-    /// The account corresponding to the given public key exists.
+    /// The account exists.
     AccountExists {
         /// The address of the account,
         addr: AddressBech32,
         /// The nonce of the account.
         nonce: u64,
     },
-    /// The account corresponding to the given public key does not exist.
+    /// The account does not exist.
     AccountEmpty,
 }
 
+/// An account given by its address or by its public key.
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
+#[serde(
+    untagged,
+    bound = "C::PublicKey: serde::Serialize + serde::de::DeserializeOwned"
+)]
+pub enum AccountId<C: sov_modules_api::Context> {
+    /// The address of the account.
+    Address(AddressBech32),
+    /// The public key of the account.
+    PublicKey(C::PublicKey),
+}
+
 #[rpc_gen(client, server, namespace = "accounts")]
 impl<C: sov_modules_api::Context> Accounts<C> {
     #[rpc_method(name = "getAccount")]
-    /// Get the account corresponding to the given public key.
+    /// Get the account corresponding to the given address or public key.
     pub fn get_account(
         &self,
-        pub_key: C::PublicKey,
+        account_id: AccountId<C>,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> RpcResult<Response> {
-        let response = match self.accounts.get(&pub_key, working_set) {
+        let pub_key = match account_id {
+            AccountId::Address(addr) => {
+                let addr: C::Address = addr.into();
+                self.public_keys.get(&addr, working_set)
+            }
+            AccountId::PublicKey(pub_key) => Some(pub_key),
+        };
+        let account = pub_key.and_then(|pub_key| self.accounts.get(&pub_key, working_set));
+
+        let response = match account {
             Some(Account { addr, nonce }) => Response::AccountExists {
                 addr: addr.into(),
                 nonce,
@@ -1,13 +1,42 @@
 use sov_modules_api::default_context::DefaultContext;
 use sov_modules_api::default_signature::private_key::DefaultPrivateKey;
-use sov_modules_api::{AddressBech32, PrivateKey, PublicKey, Spec, WorkingSet};
+use sov_modules_api::{
+    AddressBech32, Context, Module, PrivateKey, PublicKey, SoftConfirmationModuleCallError, Spec,
+    SpecId, StateMapAccessor, WorkingSet,
+};
 use sov_prover_storage_manager::new_orphan_storage;
 
-use crate::query::{self, Response};
-use crate::{AccountConfig, Accounts};
+use crate::query::{self, AccountId, Response};
+use crate::{update_public_key_message, AccountConfig, Accounts, CallMessage};
 
 type C = DefaultContext;
 
+/// Signs the update of the public key of the account at `addr` to the key of `new_priv_key`
+fn update_public_key(
+    addr: &<C as Spec>::Address,
+    nonce: u64,
+    new_priv_key: &DefaultPrivateKey,
+) -> CallMessage<C> {
+    let new_pub_key = new_priv_key.pub_key();
+    let message = update_public_key_message::<C>(addr, nonce, &new_pub_key);
+    CallMessage::UpdatePublicKey {
+        signature: new_priv_key.sign(&message),
+        new_pub_key,
+    }
+}
+
+/// Increments the nonce of the account, as the transaction hooks do
+fn bump_nonce(
+    accounts: &Accounts<C>,
+    addr: &<C as Spec>::Address,
+    working_set: &mut WorkingSet<<C as Spec>::Storage>,
+) {
+    let pub_key = accounts.public_keys.get(addr, working_set).unwrap();
+    let mut account = accounts.accounts.get(&pub_key, working_set).unwrap();
+    account.nonce += 1;
+    accounts.accounts.set(&pub_key, &account, working_set);
+}
+
 #[test]
 fn test_config_account() {
     let priv_key = DefaultPrivateKey::generate();
@@ -24,15 +53,208 @@ fn test_config_account() {
 
     accounts.init_module(&account_config, working_set);
 
-    let query_response = accounts.get_account(init_pub_key, working_set).unwrap();
+    let query_response = accounts
+        .get_account(AccountId::PublicKey(init_pub_key), working_set)
+        .unwrap();
+
+    assert_eq!(
+        query_response,
+        query::Response::AccountExists {
+            addr: AddressBech32::from(&init_pub_key_addr),
+            nonce: 0
+        }
+    );
 
+    let query_response = accounts
+        .get_account(
+            AccountId::Address(AddressBech32::from(&init_pub_key_addr)),
+            working_set,
+        )
+        .unwrap();
     assert_eq!(
         query_response,
         query::Response::AccountExists {
             addr: AddressBech32::from(&init_pub_key_addr),
             nonce: 0
         }
-    )
+    );
+
+    let unknown_pub_key = DefaultPrivateKey::generate().pub_key();
+    let unknown_addr = unknown_pub_key.to_address::<<C as Spec>::Address>();
+    for account_id in [
+        AccountId::PublicKey(unknown_pub_key),
+        AccountId::Address(AddressBech32::from(&unknown_addr)),
+    ] {
+        assert_eq!(
+            accounts.get_account(account_id, working_set).unwrap(),
+            query::Response::AccountEmpty
+        );
+    }
+}
+
+#[test]
+fn test_update_public_key() {
+    let priv_key = DefaultPrivateKey::generate();
+    let addr = priv_key.pub_key().to_address::<<C as Spec>::Address>();
+    let new_priv_key = DefaultPrivateKey::generate();
+
+    let accounts = &mut Accounts::<C>::default();
+    let tmpdir = tempfile::tempdir().unwrap();
+    let working_set = &mut WorkingSet::new(new_orphan_storage(tmpdir.path()).unwrap());
+    accounts.init_module(&[priv_key.pub_key()].into_iter().collect(), working_set);
+    bump_nonce(accounts, &addr, working_set);
+
+    let context = C::new(addr, 1, SpecId::Genesis, 0);
+
+    // The new key must sign the update itself
+    let CallMessage::UpdatePublicKey { new_pub_key, .. } =
+        update_public_key(&addr, 1, &new_priv_key);
+    let signature =
+        DefaultPrivateKey::generate().sign(&update_public_key_message::<C>(&addr, 1, &new_pub_key));
+    assert_eq!(
+        accounts
+            .call(
+                CallMessage::UpdatePublicKey {
+                    new_pub_key,
+                    signature,
+                },
+                &context,
+                working_set,
+            )
+            .unwrap_err(),
+        SoftConfirmationModuleCallError::AccountsInvalidPublicKeySignature
+    );
+
+    accounts
+        .call(
+            update_public_key(&addr, 1, &new_priv_key),
+            &context,
+            working_set,
+        )
+        .unwrap();
+
+    // The account keeps its address and nonce under the new key
+    for account_id in [
+        AccountId::PublicKey(new_priv_key.pub_key()),
+        AccountId::Address(AddressBech32::from(&addr)),
+    ] {
+        assert_eq!(
+            accounts.get_account(account_id, working_set).unwrap(),
+            Response::AccountExists {
+                addr: AddressBech32::from(&addr),
+                nonce: 1
+            }
+        );
+    }
+    assert_eq!(
+        accounts
+            .get_account(AccountId::PublicKey(priv_key.pub_key()), working_set)
+            .unwrap(),
+        Response::AccountEmpty
+    );
+    assert_eq!(
+        accounts.public_keys.get(&addr, working_set),
+        Some(new_priv_key.pub_key())
+    );
+
+    // The previous key can not open a new account at the same address
+    assert!(accounts
+        .create_default_account(&priv_key.pub_key(), working_set)
+        .is_err());
+}
+
+#[test]
+fn test_update_public_key_replay() {
+    let priv_key = DefaultPrivateKey::generate();
+    let addr = priv_key.pub_key().to_address::<<C as Spec>::Address>();
+    let new_priv_key = DefaultPrivateKey::generate();
+
+    let accounts = &mut Accounts::<C>::default();
+    let tmpdir = tempfile::tempdir().unwrap();
+    let working_set = &mut WorkingSet::new(new_orphan_storage(tmpdir.path()).unwrap());
+    accounts.init_module(&[priv_key.pub_key()].into_iter().collect(), working_set);
+
+    let context = C::new(addr, 1, SpecId::Genesis, 0);
+    let rotation = update_public_key(&addr, 0, &new_priv_key);
+
+    accounts
+        .call(rotation.clone(), &context, working_set)
+        .unwrap();
+    bump_nonce(accounts, &addr, working_set);
+
+    // Rotate back, then replay the first rotation
+    accounts
+        .call(
+            update_public_key(&addr, 1, &priv_key),
+            &context,
+            working_set,
+        )
+        .unwrap();
+    bump_nonce(accounts, &addr, working_set);
+
+    assert_eq!(
+        accounts.call(rotation, &context, working_set).unwrap_err(),
+        SoftConfirmationModuleCallError::AccountsInvalidPublicKeySignature
+    );
+    assert_eq!(
+        accounts.public_keys.get(&addr, working_set),
+        Some(priv_key.pub_key())
+    );
+    assert_eq!(
+        accounts
+            .get_account(AccountId::PublicKey(new_priv_key.pub_key()), working_set)
+            .unwrap(),
+        Response::AccountEmpty
+    );
+}
+
+#[test]
+fn test_update_public_key_to_existing_key() {
+    let priv_key = DefaultPrivateKey::generate();
+    let addr = priv_key.pub_key().to_address::<<C as Spec>::Address>();
+    let other_priv_key = DefaultPrivateKey::generate();
+    let other_addr = other_priv_key
+        .pub_key()
+        .to_address::<<C as Spec>::Address>();
+
+    let accounts = &mut Accounts::<C>::default();
+    let tmpdir = tempfile::tempdir().unwrap();
+    let working_set = &mut WorkingSet::new(new_orphan_storage(tmpdir.path()).unwrap());
+    accounts.init_module(
+        &[priv_key.pub_key(), other_priv_key.pub_key()]
+            .into_iter()
+            .collect(),
+        working_set,
+    );
+
+    let context = C::new(addr, 1, SpecId::Genesis, 0);
+    for new_priv_key in [&other_priv_key, &priv_key] {
+        assert_eq!(
+            accounts
+                .call(
+                    update_public_key(&addr, 0, new_priv_key),
+                    &context,
+                    working_set
+                )
+                .unwrap_err(),
+            SoftConfirmationModuleCallError::AccountsPublicKeyAlreadyBound
+        );
+    }
+
+    // Both accounts are left as they were
+    assert_eq!(
+        accounts.public_keys.get(&addr, working_set),
+        Some(priv_key.pub_key())
+    );
+    assert_eq!(
+        accounts
+            .get_account(AccountId::PublicKey(other_priv_key.pub_key()), working_set)
+            .unwrap(),
+        Response::AccountExists {
+            addr: AddressBech32::from(&other_addr),
+            nonce: 0
+        }
+    );
 }
 
 #[test]
@@ -101,6 +101,12 @@ where
         Some(elem)
     }
 
+    /// Deletes the value at the given index without changing the length of the vector.
+    /// Other values keep their indices and [`StateVecAccessor::get`] returns [`None`] for the deleted one.
+    fn delete(&self, index: usize, working_set: &mut W) {
+        self.elems().delete(&index, working_set);
+    }
+
     /// Removes all values from this vector.
     fn clear(&self, working_set: &mut W) {
         let len = self.len_value().remove(working_set).unwrap_or_default();
@@ -261,6 +267,7 @@ pub mod tests {
         CheckContents(Vec<T>),
         CheckContentsReverse(Vec<T>),
         CheckGet(usize, Option<T>),
+        Delete(usize),
         Clear,
     }
 
@@ -297,6 +304,10 @@ pub mod tests {
             TestCaseAction::CheckContents(vec![1, 2, 3]),
             TestCaseAction::CheckContentsReverse(vec![3, 2, 1]),
             TestCaseAction::Last(3),
+            TestCaseAction::Delete(1),
+            TestCaseAction::CheckGet(1, None),
+            TestCaseAction::CheckGet(2, Some(3)),
+            TestCaseAction::CheckLen(3),
         ]
     }
 
@@ -329,6 +340,9 @@ pub mod tests {
             TestCaseAction::SetAll(values) => {
                 state_vec.set_all(values, ws);
             }
+            TestCaseAction::Delete(index) => {
+                state_vec.delete(index, ws);
+            }
             TestCaseAction::CheckGet(index, expected) => {
                 let actual = state_vec.get(index, ws);
                 assert_eq!(actual, expected);
@@ -343,3 +343,10 @@ pub trait CliWallet: sov_modules_core::DispatchCall {
     /// typical end-usage will impl traits only in the case where `CliStringRepr<T>: Into::RuntimeCall`
     type CliStringRepr<T>;
 }
+
+/// Lists the state keys of a given length which start with a given prefix, in key order.
+/// The state can only be read by key, so modules exporting their state find the keys through this.
+/// Keys written at any version are listed, whether they have a value in the working set or not.
+#[cfg(feature = "native")]
+pub type StateKeys<'a> = dyn Fn(&[u8], usize) -> anyhow::Result<Box<dyn Iterator<Item = anyhow::Result<Vec<u8>>> + 'a>>
+    + 'a;
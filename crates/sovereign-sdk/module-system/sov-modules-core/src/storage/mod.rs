@@ -331,6 +331,18 @@ pub trait NativeStorage: Storage {
     /// get the value.
     fn get_with_proof(&self, key: StorageKey) -> StorageProof<Self::Proof>;
 
+    /// Returns the value corresponding to the key at the requested version and a proof
+    /// against the root hash of that version.
+    fn get_with_proof_at_version(
+        &self,
+        key: StorageKey,
+        version: Version,
+    ) -> Result<StorageProof<Self::Proof>, anyhow::Error>;
+
     /// Get the root hash of the tree at the requested version
     fn get_root_hash(&self, version: Version) -> Result<Self::Root, anyhow::Error>;
+
+    /// Get the root hash of the tree at the requested version, or None if there is no tree
+    /// at that version
+    fn get_root_hash_option(&self, version: Version) -> Result<Option<Self::Root>, anyhow::Error>;
 }
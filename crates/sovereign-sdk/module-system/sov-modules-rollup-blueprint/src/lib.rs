@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use citrea_common::tasks::manager::TaskManager;
-use citrea_common::FullNodeConfig;
+use citrea_common::{FullNodeConfig, RpcConfig};
 use sov_db::ledger_db::LedgerDB;
 use sov_db::rocks_db_config::RocksdbConfig;
 use sov_modules_api::{Context, DaSpec, Spec};
@@ -59,6 +59,9 @@ pub trait RollupBlueprint: Sized + Send + Sync {
     /// Creates a new instance of the blueprint.
     fn new(network: Network) -> Self;
 
+    /// Network the rollup runs on.
+    fn network(&self) -> Network;
+
     /// Get batch proof guest code elfs by fork.
     fn get_batch_proof_elfs(&self) -> HashMap<SpecId, Vec<u8>>;
 
@@ -76,13 +79,19 @@ pub trait RollupBlueprint: Sized + Send + Sync {
     ) -> HashMap<SpecId, <Self::Vm as Zkvm>::CodeCommitment>;
 
     /// Creates RPC methods for the rollup.
+    /// A read-only node has no sequencer client and rejects transactions.
+    /// Background tasks backing the methods are spawned on the node's task manager.
+    #[allow(clippy::too_many_arguments)]
     fn create_rpc_methods(
         &self,
         storage: &ProverStorage<SnapshotManager>,
         ledger_db: &LedgerDB,
+        rpc_config: &RpcConfig,
         da_service: &Arc<Self::DaService>,
         sequencer_client_url: Option<String>,
+        read_only: bool,
         soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
+        task_manager: &mut TaskManager<()>,
     ) -> Result<jsonrpsee::RpcModule<()>, anyhow::Error>;
 
     /// Creates GenesisConfig from genesis files.
@@ -120,6 +129,9 @@ pub trait RollupBlueprint: Sized + Send + Sync {
     /// Creates instance of [`BitcoinDaVerifier`]
     fn create_da_verifier(&self) -> Self::DaVerifier;
 
+    /// Creates instance of the zkVM host.
+    fn create_vm(&self, ledger_db: LedgerDB) -> Self::Vm;
+
     /// Creates instance of [`ProverService`].
     async fn create_prover_service(
         &self,
@@ -1,15 +1,23 @@
+use std::sync::Arc;
+
+use citrea_common::rpc::EvmTxSummaryProvider;
+use citrea_common::RpcConfig;
 use sov_db::ledger_db::LedgerDB;
+use sov_ledger_rpc::server::LedgerRpcServerConfig;
 use sov_modules_api::{Context, Spec};
 use sov_modules_stf_blueprint::Runtime as RuntimeTrait;
 use sov_prover_storage_manager::{ProverStorage, SnapshotManager};
 use sov_rollup_interface::services::da::DaService;
+use tokio::sync::broadcast;
 
 /// Register rollup's default rpc methods.
 pub fn register_rpc<RT, C, Da>(
     storage: &ProverStorage<SnapshotManager>,
     ledger_db: &LedgerDB,
+    rpc_config: &RpcConfig,
     _da_service: &Da,
     _sequencer: C::Address,
+    soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
 ) -> Result<jsonrpsee::RpcModule<()>, anyhow::Error>
 where
     RT: RuntimeTrait<C, <Da as DaService>::Spec> + Send + Sync + 'static,
@@ -21,8 +29,17 @@ where
 
     // ledger rpc.
     {
-        rpc_methods.merge(sov_ledger_rpc::server::create_rpc_module::<LedgerDB>(
+        rpc_methods.merge(sov_ledger_rpc::server::create_rpc_module_with_config::<
+            LedgerDB,
+        >(
             ledger_db.clone(),
+            LedgerRpcServerConfig {
+                max_hashes_per_request: rpc_config.max_soft_confirmation_hashes_per_request
+                    as usize,
+                max_slot_range: rpc_config.max_verified_proofs_slot_range,
+            },
+            soft_confirmation_rx,
+            Some(Arc::new(EvmTxSummaryProvider::new(storage.clone()))),
         ))?;
     }
 
@@ -1,7 +1,8 @@
 #![deny(missing_docs)]
 #![doc = include_str!("../README.md")]
 
-use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use citrea_primitives::soft_confirmation::compute_soft_confirmation_hash_with;
 use itertools::Itertools;
 use rs_merkle::algorithms::Sha256;
 use rs_merkle::MerkleTree;
@@ -15,13 +16,13 @@ use sov_modules_api::{
     native_debug, BasicAddress, BlobReaderTrait, Context, DaSpec, DispatchCall, Genesis, Signature,
     Spec, StateCheckpoint, UnsignedSoftConfirmation, WorkingSet,
 };
-use sov_rollup_interface::da::DaDataBatchProof;
+use sov_rollup_interface::da::{DaDataBatchProof, VersionedDaData};
 use sov_rollup_interface::fork::ForkManager;
 use sov_rollup_interface::soft_confirmation::{SignedSoftConfirmation, UnsignedSoftConfirmationV1};
 use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::stf::{
-    ApplySequencerCommitmentsOutput, SoftConfirmationError, SoftConfirmationResult,
-    StateTransitionError, StateTransitionFunction,
+    sequencer_public_key_at, ApplySequencerCommitmentsOutput, SoftConfirmationError,
+    SoftConfirmationResult, StateTransitionError, StateTransitionFunction,
 };
 use sov_rollup_interface::zk::CumulativeStateDiff;
 use sov_state::Storage;
@@ -182,63 +183,8 @@ where
         >,
         working_set: &mut WorkingSet<C::Storage>,
     ) -> Result<(), StateTransitionError> {
-        let unsigned = UnsignedSoftConfirmation::new(
-            soft_confirmation.l2_height(),
-            soft_confirmation.da_slot_height(),
-            soft_confirmation.da_slot_hash(),
-            soft_confirmation.da_slot_txs_commitment(),
-            soft_confirmation.blobs(),
-            soft_confirmation.txs(),
-            soft_confirmation.deposit_data().to_vec(),
-            soft_confirmation.l1_fee_rate(),
-            soft_confirmation.timestamp(),
-        );
-
-        // check the claimed hash
-        if current_spec >= SpecId::Fork1 {
-            let digest = unsigned.compute_digest::<<C as Spec>::Hasher>();
-            let hash = Into::<[u8; 32]>::into(digest);
-            if soft_confirmation.hash() != hash {
-                return Err(StateTransitionError::SoftConfirmationError(
-                    SoftConfirmationError::InvalidSoftConfirmationHash,
-                ));
-            }
-
-            // verify signature
-            if verify_soft_confirmation_signature::<C, _>(
-                soft_confirmation,
-                soft_confirmation.signature(),
-                sequencer_public_key,
-            )
-            .is_err()
-            {
-                return Err(StateTransitionError::SoftConfirmationError(
-                    SoftConfirmationError::InvalidSoftConfirmationSignature,
-                ));
-            }
-        } else {
-            let unsigned = UnsignedSoftConfirmationV1::from(unsigned);
-            let digest = unsigned.hash::<<C as Spec>::Hasher>();
-            let hash = Into::<[u8; 32]>::into(digest);
-            if soft_confirmation.hash() != hash {
-                return Err(StateTransitionError::SoftConfirmationError(
-                    SoftConfirmationError::InvalidSoftConfirmationHash,
-                ));
-            }
-
-            // verify signature
-            if pre_fork1_verify_soft_confirmation_signature::<C>(
-                &unsigned,
-                soft_confirmation.signature(),
-                sequencer_public_key,
-            )
-            .is_err()
-            {
-                return Err(StateTransitionError::SoftConfirmationError(
-                    SoftConfirmationError::InvalidSoftConfirmationSignature,
-                ));
-            }
-        };
+        verify_soft_confirmation::<C, _>(current_spec, soft_confirmation, sequencer_public_key)
+            .map_err(StateTransitionError::SoftConfirmationError)?;
 
         self.end_soft_confirmation_inner(
             current_spec,
@@ -426,8 +372,8 @@ where
 
     fn apply_soft_confirmations_from_sequencer_commitments(
         &mut self,
-        sequencer_public_key: &[u8],
-        sequencer_da_public_key: &[u8],
+        sequencer_public_keys: &[(u64, &[u8])],
+        sequencer_da_public_keys: &[&[u8]],
         initial_state_root: &Self::StateRoot,
         pre_state: Self::PreState,
         da_data: Vec<<Da as DaSpec>::BlobTransaction>,
@@ -442,16 +388,18 @@ where
     ) -> ApplySequencerCommitmentsOutput<Self::StateRoot> {
         let mut state_diff = CumulativeStateDiff::default();
 
-        // Extract all sequencer commitments.
+        // Extract all sequencer commitments signed with a whitelisted key, along with their signer.
         // Ignore broken DaData and zk proofs. Also ignore ForcedTransaction's (will be implemented in the future).
         let mut sequencer_commitments = da_data
             .into_iter()
             .filter_map(|blob| {
-                if blob.sender().as_ref() == sequencer_da_public_key {
-                    let da_data = DaDataBatchProof::try_from_slice(blob.verified_data());
+                let sender = blob.sender();
+                let sender: &[u8] = sender.as_ref();
+                if sequencer_da_public_keys.contains(&sender) {
+                    let da_data = DaDataBatchProof::decode_versioned(blob.verified_data());
 
                     if let Ok(DaDataBatchProof::SequencerCommitment(commitment)) = da_data {
-                        return Some(commitment);
+                        return Some((commitment, sender.to_vec()));
                     }
                 }
 
@@ -484,6 +432,9 @@ where
         //
         // Again, since the zk circuit verify the state transition, the prover can not leave out any commitments or change the ordering of
         // rollup state transitions.
+        //
+        // Commitments with the same start height are ordered by their signer, so the order does not depend
+        // on the order of the whitelisted keys.
         sequencer_commitments.sort();
 
         // The preproven indices are sorted by the prover when originally passed.
@@ -502,17 +453,20 @@ where
                 }
                 true
             })
-            .map(|(_, commitment)| commitment);
+            .map(|(_, signed_commitment)| signed_commitment);
 
         // Then verify these soft confirmations.
         let mut current_state_root = initial_state_root.clone();
         let mut previous_batch_hash = soft_confirmations[0][0].prev_hash();
+        let mut previous_timestamp: Option<u64> = None;
+        let mut first_commitment_start_height: Option<u64> = None;
         let mut last_commitment_end_height: Option<u64> = None;
+        let mut commitments_signer: Option<Vec<u8>> = None;
 
         let mut fork_manager = ForkManager::new(forks, sequencer_commitments_range.0 as u64);
 
         // should panic if number of sequencer commitments, soft confirmations, slot headers and witnesses don't match
-        for (((sequencer_commitment, soft_confirmations), da_block_headers), witnesses) in
+        for ((((sequencer_commitment, signer), soft_confirmations), da_block_headers), witnesses) in
             sequencer_commitments_iter
                 .skip(sequencer_commitments_range.0 as usize)
                 .take(
@@ -523,15 +477,22 @@ where
                 .zip_eq(slot_headers)
                 .zip_eq(witnesses)
         {
-            // if the commitment is not sequential, then the proof is invalid.
-            if let Some(end_height) = last_commitment_end_height {
+            // if the commitments are not contiguous, then the proof is invalid.
+            // the processed blocks must be exactly the ones claimed by the output range
+            if let Err(e) = sequencer_commitment.check_follows(last_commitment_end_height) {
+                panic!("Sequencer commitments must be sequential: {}", e);
+            }
+            first_commitment_start_height.get_or_insert(sequencer_commitment.l2_start_block_number);
+            last_commitment_end_height = Some(sequencer_commitment.l2_end_block_number);
+
+            // the output records a single signer for all proven commitments
+            if let Some(commitments_signer) = &commitments_signer {
                 assert_eq!(
-                    end_height + 1,
-                    sequencer_commitment.l2_start_block_number,
-                    "Sequencer commitments must be sequential"
+                    commitments_signer, &signer,
+                    "Sequencer commitments must be signed with the same DA key"
                 );
             }
-            last_commitment_end_height = Some(sequencer_commitment.l2_end_block_number);
+            commitments_signer = Some(signer);
 
             // we must verify given DA headers match the commitments
             let mut index_headers = 0;
@@ -677,6 +638,20 @@ where
                     "Soft confirmation heights not sequential"
                 );
 
+                // the rule enforcer checks each timestamp against the previous one in state,
+                // this makes the guarantee part of the proof across all proven commitments
+                if let Some(previous_timestamp) = previous_timestamp {
+                    assert!(
+                        soft_confirmation.timestamp() >= previous_timestamp,
+                        "Soft confirmation timestamps must not decrease"
+                    );
+                }
+                previous_timestamp = Some(soft_confirmation.timestamp());
+
+                // blocks keep verifying with the key that was active at their height after a rotation
+                let sequencer_public_key =
+                    sequencer_public_key_at(sequencer_public_keys, l2_height);
+
                 let result = self
                     .apply_soft_confirmation(
                         fork_manager.active_fork().spec_id,
@@ -711,11 +686,57 @@ where
             final_state_root: current_state_root,
             state_diff,
             // There has to be a height
+            first_l2_height: first_commitment_start_height.unwrap(),
             last_l2_height: last_commitment_end_height.unwrap(),
+            sequencer_da_public_key: commitments_signer.unwrap(),
         }
     }
 }
 
+/// Checks that the claimed hash of a soft confirmation matches its contents
+/// and that it is signed by the sequencer, using the scheme of `current_spec`.
+pub fn verify_soft_confirmation<C: Context, Tx: Clone + BorshSerialize>(
+    current_spec: SpecId,
+    soft_confirmation: &SignedSoftConfirmation<Tx>,
+    sequencer_public_key: &[u8],
+) -> Result<(), SoftConfirmationError> {
+    let hash = compute_soft_confirmation_hash_with::<<C as Spec>::Hasher, _>(
+        current_spec,
+        soft_confirmation,
+    );
+    if soft_confirmation.hash() != hash {
+        return Err(SoftConfirmationError::InvalidSoftConfirmationHash);
+    }
+
+    if current_spec >= SpecId::Fork1 {
+        verify_soft_confirmation_signature::<C, _>(
+            soft_confirmation,
+            soft_confirmation.signature(),
+            sequencer_public_key,
+        )
+        .map_err(|_| SoftConfirmationError::InvalidSoftConfirmationSignature)
+    } else {
+        let unsigned = UnsignedSoftConfirmationV1::from(UnsignedSoftConfirmation::new(
+            soft_confirmation.l2_height(),
+            soft_confirmation.da_slot_height(),
+            soft_confirmation.da_slot_hash(),
+            soft_confirmation.da_slot_txs_commitment(),
+            soft_confirmation.blobs(),
+            soft_confirmation.txs(),
+            soft_confirmation.deposit_data().to_vec(),
+            soft_confirmation.l1_fee_rate(),
+            soft_confirmation.timestamp(),
+        ));
+
+        pre_fork1_verify_soft_confirmation_signature::<C>(
+            &unsigned,
+            soft_confirmation.signature(),
+            sequencer_public_key,
+        )
+        .map_err(|_| SoftConfirmationError::InvalidSoftConfirmationSignature)
+    }
+}
+
 fn verify_soft_confirmation_signature<C: Context, Tx: Clone>(
     signed_soft_confirmation: &SignedSoftConfirmation<Tx>,
     signature: &[u8],
@@ -753,3 +774,137 @@ fn pre_fork1_verify_soft_confirmation_signature<C: Context>(
 
     Ok(())
 }
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use sov_modules_api::default_context::DefaultContext;
+    use sov_modules_api::default_signature::private_key::DefaultPrivateKey;
+    use sov_modules_api::PrivateKey;
+
+    use super::*;
+
+    type Hasher = <DefaultContext as Spec>::Hasher;
+
+    const BLOBS: &[Vec<u8>] = &[];
+    const TXS: &[Vec<u8>] = &[];
+
+    fn unsigned(l1_fee_rate: u128) -> UnsignedSoftConfirmation<'static, Vec<u8>> {
+        UnsignedSoftConfirmation::new(
+            10,
+            2,
+            [1; 32],
+            [2; 32],
+            BLOBS,
+            TXS,
+            vec![vec![3; 8]],
+            l1_fee_rate,
+            1_000,
+        )
+    }
+
+    /// Signs `unsigned` the way the sequencer does under `spec`
+    fn sign(
+        spec: SpecId,
+        key: &DefaultPrivateKey,
+        unsigned: &UnsignedSoftConfirmation<'static, Vec<u8>>,
+    ) -> ([u8; 32], Vec<u8>) {
+        let (hash, signature) = if spec >= SpecId::Fork1 {
+            let hash: [u8; 32] = unsigned.compute_digest::<Hasher>().into();
+            (hash, key.sign(&hash))
+        } else {
+            let unsigned = UnsignedSoftConfirmationV1::from(unsigned.clone());
+            let raw = borsh::to_vec(&unsigned).unwrap();
+            (unsigned.hash::<Hasher>().into(), key.sign(&raw))
+        };
+        (hash, borsh::to_vec(&signature).unwrap())
+    }
+
+    /// Builds a signed soft confirmation with the contents of `body` but the hash and signature given
+    fn signed(
+        body: &UnsignedSoftConfirmation<'static, Vec<u8>>,
+        hash: [u8; 32],
+        signature: Vec<u8>,
+        key: &DefaultPrivateKey,
+    ) -> SignedSoftConfirmation<'static, Vec<u8>> {
+        SignedSoftConfirmation::new(
+            body.l2_height(),
+            hash,
+            [0; 32],
+            body.da_slot_height(),
+            body.da_slot_hash(),
+            body.da_slot_txs_commitment(),
+            body.l1_fee_rate(),
+            body.blobs().into(),
+            body.txs().into(),
+            body.deposit_data(),
+            signature,
+            borsh::to_vec(&key.pub_key()).unwrap(),
+            body.timestamp(),
+        )
+    }
+
+    #[test]
+    fn valid_soft_confirmation() {
+        let key = DefaultPrivateKey::generate();
+        let sequencer_public_key = borsh::to_vec(&key.pub_key()).unwrap();
+
+        for spec in [SpecId::Genesis, SpecId::Fork1] {
+            let body = unsigned(10);
+            let (hash, signature) = sign(spec, &key, &body);
+            let soft_confirmation = signed(&body, hash, signature, &key);
+
+            assert_eq!(
+                verify_soft_confirmation::<DefaultContext, _>(
+                    spec,
+                    &soft_confirmation,
+                    &sequencer_public_key
+                ),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn tampered_signature() {
+        let key = DefaultPrivateKey::generate();
+        let sequencer_public_key = borsh::to_vec(&key.pub_key()).unwrap();
+        let other_key = DefaultPrivateKey::generate();
+
+        for spec in [SpecId::Genesis, SpecId::Fork1] {
+            let body = unsigned(10);
+            let (hash, _) = sign(spec, &key, &body);
+            let (_, signature) = sign(spec, &other_key, &body);
+            let soft_confirmation = signed(&body, hash, signature, &key);
+
+            assert_eq!(
+                verify_soft_confirmation::<DefaultContext, _>(
+                    spec,
+                    &soft_confirmation,
+                    &sequencer_public_key
+                ),
+                Err(SoftConfirmationError::InvalidSoftConfirmationSignature)
+            );
+        }
+    }
+
+    #[test]
+    fn tampered_body() {
+        let key = DefaultPrivateKey::generate();
+        let sequencer_public_key = borsh::to_vec(&key.pub_key()).unwrap();
+
+        for spec in [SpecId::Genesis, SpecId::Fork1] {
+            let (hash, signature) = sign(spec, &key, &unsigned(10));
+            // Same hash and signature over a different L1 fee rate
+            let soft_confirmation = signed(&unsigned(11), hash, signature, &key);
+
+            assert_eq!(
+                verify_soft_confirmation::<DefaultContext, _>(
+                    spec,
+                    &soft_confirmation,
+                    &sequencer_public_key
+                ),
+                Err(SoftConfirmationError::InvalidSoftConfirmationHash)
+            );
+        }
+    }
+}
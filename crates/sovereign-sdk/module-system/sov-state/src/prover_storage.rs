@@ -52,7 +52,7 @@ where
     Q: QueryManager,
 {
     fn read_value(&self, key: &StorageKey, version: Option<Version>) -> Option<StorageValue> {
-        let version_to_use = version.unwrap_or_else(|| self.db.get_next_version());
+        let version_to_use = version.unwrap_or_else(|| self.db.get_read_version());
         match self
             .db
             .get_value_option_by_key(version_to_use, key.as_ref())
@@ -291,9 +291,30 @@ where
         }
     }
 
+    fn get_with_proof_at_version(
+        &self,
+        key: StorageKey,
+        version: Version,
+    ) -> anyhow::Result<StorageProof<Self::Proof>> {
+        let merkle = JellyfishMerkleTree::<StateDB<Q>, DefaultHasher>::new(&self.db);
+        let (val_opt, proof) =
+            merkle.get_with_proof(KeyHash::with::<DefaultHasher>(key.as_ref()), version)?;
+        Ok(StorageProof {
+            key,
+            value: val_opt.map(StorageValue::from),
+            proof,
+        })
+    }
+
     fn get_root_hash(&self, version: Version) -> anyhow::Result<jmt::RootHash> {
         let temp_merkle: JellyfishMerkleTree<'_, StateDB<Q>, DefaultHasher> =
             JellyfishMerkleTree::new(&self.db);
         temp_merkle.get_root_hash(version)
     }
+
+    fn get_root_hash_option(&self, version: Version) -> anyhow::Result<Option<jmt::RootHash>> {
+        let temp_merkle: JellyfishMerkleTree<'_, StateDB<Q>, DefaultHasher> =
+            JellyfishMerkleTree::new(&self.db);
+        temp_merkle.get_root_hash_option(version)
+    }
 }
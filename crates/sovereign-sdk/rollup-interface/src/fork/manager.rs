@@ -32,6 +32,12 @@ impl<'a> ForkManager<'a> {
         self.forks[self.active_fork_idx]
     }
 
+    /// Sets the active fork back to the one of the given height, after the chain is rolled back.
+    /// Migrations of the forks activated above the height are not reverted.
+    pub fn rollback_to(&mut self, height: u64) {
+        self.active_fork_idx = fork_pos_from_block_number(self.forks, height);
+    }
+
     pub fn register_block(&mut self, height: u64) -> anyhow::Result<()> {
         // Skip if we are already at the last fork
         if self.active_fork_idx == self.forks.len() - 1 {
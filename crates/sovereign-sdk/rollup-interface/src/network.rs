@@ -1,7 +1,7 @@
 use core::fmt::Display;
 
 /// The network currently running.
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub enum Network {
     /// Mainnet
     #[default]
@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::da::SequencerCommitment;
 use crate::soft_confirmation::SignedSoftConfirmation;
-use crate::zk::{BatchProofInfo, CumulativeStateDiff};
+use crate::zk::{BatchProofInfo, BatchProofOutputVersion, CumulativeStateDiff};
 
 /// A struct containing enough information to uniquely specify single batch.
 
@@ -81,6 +81,36 @@ pub struct SoftConfirmationResponse {
     pub l1_fee_rate: u128,
     /// Sequencer's block timestamp.
     pub timestamp: u64,
+    /// Summaries of the transactions executed in the soft confirmation.
+    /// Only set with the [`SoftConfirmationDetail::Hashes`] detail level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_summaries: Option<Vec<SoftConfirmationTxSummary>>,
+}
+
+/// The level of detail of soft confirmation responses
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SoftConfirmationDetail {
+    /// Includes the transaction bodies, if stored
+    #[default]
+    Full,
+    /// Includes the hashes, success flags and gas used of the executed transactions instead of their bodies
+    Hashes,
+    /// Includes neither transaction bodies nor summaries
+    Minimal,
+}
+
+/// The summary of a transaction executed in a soft confirmation
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoftConfirmationTxSummary {
+    /// The hash of the transaction.
+    #[serde(with = "utils::rpc_hex")]
+    pub hash: [u8; 32],
+    /// Whether the transaction succeeded.
+    pub success: bool,
+    /// Gas used by the transaction.
+    pub gas_used: u64,
 }
 
 impl<'txs, Tx> TryFrom<SoftConfirmationResponse> for SignedSoftConfirmation<'txs, Tx>
@@ -121,6 +151,45 @@ where
     }
 }
 
+impl SoftConfirmationResponse {
+    /// Returns the soft confirmation with its transactions left as raw blobs, which is enough to hash it.
+    /// Returns `None` if the response does not include the transactions.
+    pub fn to_unparsed_soft_confirmation(&self) -> Option<SignedSoftConfirmation<'static, ()>> {
+        let blobs = self
+            .txs
+            .as_ref()?
+            .iter()
+            .map(|tx| tx.tx.clone())
+            .collect::<Vec<_>>();
+        Some(SignedSoftConfirmation::new(
+            self.l2_height,
+            self.hash,
+            self.prev_hash,
+            self.da_slot_height,
+            self.da_slot_hash,
+            self.da_slot_txs_commitment,
+            self.l1_fee_rate,
+            blobs.into(),
+            Vec::new().into(),
+            self.deposit_data.iter().map(|tx| tx.tx.clone()).collect(),
+            self.soft_confirmation_signature.clone(),
+            self.pub_key.clone(),
+            self.timestamp,
+        ))
+    }
+}
+
+/// The response to a JSON-RPC request to recompute the hash of a soft confirmation.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoftConfirmationHashResponse {
+    /// The hash computed from the contents of the soft confirmation.
+    #[serde(with = "hex::serde")]
+    pub hash: [u8; 32],
+    /// Whether the computed hash matches the hash of the soft confirmation stored at the same height.
+    pub matches_stored: bool,
+}
+
 /// The response to a JSON-RPC request for sequencer commitments on a DA Slot.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -134,6 +203,14 @@ pub struct SequencerCommitmentResponse {
     pub l2_start_block_number: u64,
     /// Hex encoded End L2 block's number
     pub l2_end_block_number: u64,
+    /// DA public key of the sender of the commitment.
+    /// Only recorded by full nodes, for the commitments they processed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<HexTx>,
+    /// Id of the DA transaction the commitment was sent with.
+    /// Only known by the sequencer that submitted the commitment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub da_tx_id: Option<HexTx>,
 }
 
 /// The output of a light client proof
@@ -210,6 +287,30 @@ pub struct LastVerifiedBatchProofResponse {
     pub height: u64,
 }
 
+/// The rpc response of verified proofs on a single l1 slot
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotVerifiedBatchProofsResponse {
+    /// L1 height of the proofs
+    pub height: u64,
+    /// Proofs verified on the slot
+    pub proofs: Vec<VerifiedBatchProofResponse>,
+}
+
+/// The rpc response of the soft confirmations built on top of a single l1 slot
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L1SlotSoftConfirmationsResponse {
+    /// L1 height of the slot
+    pub l1_height: u64,
+    /// First L2 height built on the slot
+    pub l2_start: u64,
+    /// Last L2 height built on the slot (inclusive)
+    pub l2_end: u64,
+    /// Soft confirmations from `l2_start` to `l2_end`
+    pub soft_confirmations: Vec<SoftConfirmationResponse>,
+}
+
 /// The ZK proof generated by the [`ZkvmHost::run`] method to be served by rpc.
 pub type ProofRpcResponse = Vec<u8>;
 
@@ -217,6 +318,8 @@ pub type ProofRpcResponse = Vec<u8>;
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BatchProofOutputRpcResponse {
+    /// The layout of the circuit output, telling which fields the proof commits to
+    pub output_version: BatchProofOutputVersion,
     /// The state of the rollup before the transition
     #[serde(with = "hex::serde")]
     pub initial_state_root: Vec<u8>,
@@ -251,6 +354,10 @@ pub struct BatchProofOutputRpcResponse {
     pub preproven_commitments: Vec<usize>,
     /// The last processed l2 height in the processed sequencer commitments.
     pub last_l2_height: u64,
+    /// The first processed l2 height in the processed sequencer commitments.
+    /// Zeroed for `V1` outputs, which do not commit to it.
+    #[serde(default)]
+    pub first_l2_height: u64,
 }
 
 /// Custom serialization for BTreeMap
@@ -317,12 +424,16 @@ where
 pub fn sequencer_commitment_to_response(
     commitment: SequencerCommitment,
     l1_height: u64,
+    sender: Option<Vec<u8>>,
+    da_tx_id: Option<[u8; 32]>,
 ) -> SequencerCommitmentResponse {
     SequencerCommitmentResponse {
         found_in_l1: l1_height,
         merkle_root: commitment.merkle_root,
         l2_start_block_number: commitment.l2_start_block_number,
         l2_end_block_number: commitment.l2_end_block_number,
+        sender: sender.map(HexTx::from),
+        da_tx_id: da_tx_id.map(|id| HexTx::from(id.to_vec())),
     }
 }
 
@@ -336,8 +447,20 @@ pub enum ItemOrHash<T> {
     Full(T),
 }
 
-/// Statuses for soft confirmation
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+/// Statuses for soft confirmation.
+/// Variants are ordered by finality, so a status only ever moves to a greater one.
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
 #[serde(rename_all = "camelCase")]
 pub enum SoftConfirmationStatus {
     /// No confirmation yet, rely on the sequencer
@@ -348,6 +471,9 @@ pub enum SoftConfirmationStatus {
     Proven,
 }
 
+/// The maximum number of soft confirmations that can be requested in a single range query
+pub const MAX_SOFT_CONFIRMATIONS_PER_REQUEST: u64 = 20;
+
 /// A LedgerRpcProvider provides a way to query the ledger for information about slots, batches, transactions, and events.
 #[cfg(feature = "native")]
 pub trait LedgerRpcProvider {
@@ -369,6 +495,13 @@ pub trait LedgerRpcProvider {
         hash: &[u8; 32],
     ) -> Result<Option<SoftConfirmationResponse>, anyhow::Error>;
 
+    /// Get a list of soft confirmations by hash, in the same order as the given hashes.
+    /// Unknown hashes resolve to `None`.
+    fn get_soft_confirmations_by_hashes(
+        &self,
+        hashes: &[[u8; 32]],
+    ) -> Result<Vec<Option<SoftConfirmationResponse>>, anyhow::Error>;
+
     /// Get a single soft confirmation by number.
     fn get_soft_confirmation_by_number(
         &self,
@@ -382,14 +515,18 @@ pub trait LedgerRpcProvider {
         end: u64,
     ) -> Result<Vec<Option<SoftConfirmationResponse>>, anyhow::Error>;
 
-    /// Takes an L2 Height and and returns the soft confirmation status of the soft confirmation
+    /// Takes an L2 Height and and returns the soft confirmation status of the soft confirmation,
+    /// `None` if the soft confirmation is not processed yet
     fn get_soft_confirmation_status(
         &self,
         soft_confirmation_receipt: u64,
-    ) -> Result<SoftConfirmationStatus, anyhow::Error>;
+    ) -> Result<Option<SoftConfirmationStatus>, anyhow::Error>;
+
+    /// Returns the last L2 height whose data was pruned, if any
+    fn get_last_pruned_l2_height(&self) -> Result<Option<u64>, anyhow::Error>;
 
     /// Returns the L2 genesis state root
-    fn get_l2_genesis_state_root(&self) -> Result<Option<Vec<u8>>, anyhow::Error>;
+    fn get_l2_genesis_state_root(&self) -> Result<Option<[u8; 32]>, anyhow::Error>;
 
     /// Returns the last scanned L1 height (for sequencer commitments)
     fn get_last_scanned_l1_height(&self) -> Result<u64, anyhow::Error>;
@@ -397,12 +534,26 @@ pub trait LedgerRpcProvider {
     /// Returns the slot number of a given hash
     fn get_slot_number_by_hash(&self, hash: [u8; 32]) -> Result<Option<u64>, anyhow::Error>;
 
+    /// Takes an L1 height and returns the range of soft confirmations built on top of it.
+    /// Transactions are left out of the responses unless `include_txs` is set.
+    fn get_soft_confirmations_by_l1_height(
+        &self,
+        height: u64,
+        include_txs: bool,
+    ) -> Result<Option<L1SlotSoftConfirmationsResponse>, anyhow::Error>;
+
     /// Takes an L1 height and and returns all the sequencer commitments on the slot
     fn get_sequencer_commitments_on_slot_by_number(
         &self,
         height: u64,
     ) -> Result<Option<Vec<SequencerCommitmentResponse>>, anyhow::Error>;
 
+    /// Takes an L2 height and returns the sequencer commitment covering it, if any
+    fn get_sequencer_commitment_by_l2_height(
+        &self,
+        l2_height: u64,
+    ) -> Result<Option<SequencerCommitmentResponse>, anyhow::Error>;
+
     /// Get batch proof by l1 height
     fn get_batch_proof_data_by_l1_height(
         &self,
@@ -415,6 +566,15 @@ pub trait LedgerRpcProvider {
         height: u64,
     ) -> Result<Option<Vec<VerifiedBatchProofResponse>>, anyhow::Error>;
 
+    /// Get verified proofs of slots with heights `start` to `end` (inclusive),
+    /// skipping slots without proofs. Returns at most `limit` slots.
+    fn get_verified_proof_data_by_l1_height_range(
+        &self,
+        start: u64,
+        end: u64,
+        limit: usize,
+    ) -> Result<Vec<SlotVerifiedBatchProofsResponse>, anyhow::Error>;
+
     /// Get last verified proof
     fn get_last_verified_batch_proof(
         &self,
@@ -3,6 +3,8 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 #[cfg(feature = "native")]
+use tokio::sync::broadcast;
+#[cfg(feature = "native")]
 use tokio::sync::mpsc::UnboundedSender;
 #[cfg(feature = "native")]
 use tokio::sync::oneshot::Sender as OneshotSender;
@@ -11,6 +13,8 @@ use crate::da::BlockHeaderTrait;
 #[cfg(feature = "native")]
 use crate::da::{DaData, DaNamespace, DaSpec, DaVerifier, SequencerCommitment};
 #[cfg(feature = "native")]
+use crate::spec::SpecId;
+#[cfg(feature = "native")]
 use crate::zk::Proof;
 
 /// This type represents a queued request to send_transaction
@@ -18,6 +22,8 @@ use crate::zk::Proof;
 pub struct SenderWithNotifier<TxID> {
     /// Data to send.
     pub da_data: DaData,
+    /// Spec of the L2 blocks the data belongs to, which selects its DA encoding.
+    pub spec_id: SpecId,
     /// Channel to receive result of the operation.
     pub notify: OneshotSender<Result<TxID, anyhow::Error>>,
 }
@@ -45,7 +51,14 @@ pub trait DaService: Send + Sync + 'static {
     >;
 
     /// A transaction ID, used to identify the transaction in the DA layer.
-    type TransactionId: Send + PartialEq + Eq + PartialOrd + Ord + core::hash::Hash + Into<[u8; 32]>;
+    type TransactionId: Send
+        + Clone
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + core::hash::Hash
+        + Into<[u8; 32]>;
 
     /// The error type for fallible methods.
     type Error: core::fmt::Debug + Send + Sync + core::fmt::Display;
@@ -110,7 +123,12 @@ pub trait DaService: Send + Sync + 'static {
     /// Send a transaction directly to the DA layer.
     /// blob is the serialized and signed transaction.
     /// Returns nothing if the transaction was successfully sent.
-    async fn send_transaction(&self, da_data: DaData) -> Result<Self::TransactionId, Self::Error>;
+    /// `spec_id` is the spec of the L2 blocks the data belongs to and selects its encoding.
+    async fn send_transaction(
+        &self,
+        da_data: DaData,
+        spec_id: SpecId,
+    ) -> Result<Self::TransactionId, Self::Error>;
 
     /// A tx part of the queue to send transactions in order
     fn get_send_transaction_queue(
@@ -119,6 +137,14 @@ pub trait DaService: Send + Sync + 'static {
         unimplemented!()
     }
 
+    /// Subscribes to the transactions replaced with a higher fee, as `(replaced, replacement)` ids.
+    /// The receiver is closed if the DA layer never replaces transactions.
+    fn subscribe_replaced_transactions(
+        &self,
+    ) -> broadcast::Receiver<(Self::TransactionId, Self::TransactionId)> {
+        broadcast::channel(1).1
+    }
+
     /// Returns fee rate per byte on DA layer.
     async fn get_fee_rate(&self) -> Result<u128, Self::Error>;
 
@@ -7,6 +7,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::spec::SpecId;
 use crate::zk::{LightClientCircuitOutput, Proof};
 use crate::BasicAddress;
 
@@ -22,6 +23,110 @@ pub struct SequencerCommitment {
     pub l2_end_block_number: u64,
 }
 
+impl SequencerCommitment {
+    /// Checks that the commitment covers at least one L2 block, starting right after
+    /// `previous_end`, the last L2 height of the commitment before it.
+    pub fn check_follows(
+        &self,
+        previous_end: Option<u64>,
+    ) -> Result<(), SequencerCommitmentRangeError> {
+        if self.l2_start_block_number > self.l2_end_block_number {
+            return Err(SequencerCommitmentRangeError::InvertedRange {
+                l2_start_block_number: self.l2_start_block_number,
+                l2_end_block_number: self.l2_end_block_number,
+            });
+        }
+        let Some(previous_end) = previous_end else {
+            return Ok(());
+        };
+        if self.l2_start_block_number <= previous_end {
+            return Err(SequencerCommitmentRangeError::Overlap {
+                previous_end,
+                l2_start_block_number: self.l2_start_block_number,
+            });
+        }
+        if self.l2_start_block_number != previous_end + 1 {
+            return Err(SequencerCommitmentRangeError::Gap {
+                previous_end,
+                l2_start_block_number: self.l2_start_block_number,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the commitments, in order, cover a contiguous L2 range without gaps or overlaps.
+pub fn check_sequencer_commitments_contiguous<'a>(
+    commitments: impl IntoIterator<Item = &'a SequencerCommitment>,
+) -> Result<(), SequencerCommitmentRangeError> {
+    let mut previous_end = None;
+    for commitment in commitments {
+        commitment.check_follows(previous_end)?;
+        previous_end = Some(commitment.l2_end_block_number);
+    }
+    Ok(())
+}
+
+/// Why sequencer commitments do not cover a contiguous L2 range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequencerCommitmentRangeError {
+    /// The commitment ends before it starts
+    InvertedRange {
+        /// Start L2 block's number
+        l2_start_block_number: u64,
+        /// End L2 block's number
+        l2_end_block_number: u64,
+    },
+    /// The commitment starts at or before the end of the previous commitment
+    Overlap {
+        /// End L2 block's number of the previous commitment
+        previous_end: u64,
+        /// Start L2 block's number
+        l2_start_block_number: u64,
+    },
+    /// The commitment leaves out L2 blocks after the end of the previous commitment
+    Gap {
+        /// End L2 block's number of the previous commitment
+        previous_end: u64,
+        /// Start L2 block's number
+        l2_start_block_number: u64,
+    },
+}
+
+impl core::fmt::Display for SequencerCommitmentRangeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvertedRange {
+                l2_start_block_number,
+                l2_end_block_number,
+            } => write!(
+                f,
+                "Sequencer commitment starts at L2 height {} after it ends at {}",
+                l2_start_block_number, l2_end_block_number
+            ),
+            Self::Overlap {
+                previous_end,
+                l2_start_block_number,
+            } => write!(
+                f,
+                "Sequencer commitment starting at L2 height {} overlaps the previous one ending at {}",
+                l2_start_block_number, previous_end
+            ),
+            Self::Gap {
+                previous_end,
+                l2_start_block_number,
+            } => write!(
+                f,
+                "Sequencer commitment starting at L2 height {} leaves a gap after the previous one ending at {}",
+                l2_start_block_number, previous_end
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SequencerCommitmentRangeError {}
+
 impl core::cmp::PartialOrd for SequencerCommitment {
     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
@@ -55,7 +160,7 @@ pub struct UpdatedDaState<Spec: DaSpec> {
 // TODO: rename to da service request smth smth
 // DaDataOutgoing
 /// Data written to DA can only be one of these two types
-/// Data written to DA and read from DA is must be borsh serialization of this enum
+/// Data written to DA and read from DA is encoded with [`VersionedDaData`]
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
 pub enum DaData {
     /// A commitment from the sequencer
@@ -64,18 +169,18 @@ pub enum DaData {
     ZKProof(Proof),
 }
 
-/// Data written to DA and read from DA must be the borsh serialization of this enum
+/// Data written to DA and read from DA is encoded with [`VersionedDaData`]
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
 pub enum DaDataLightClient {
     /// A zk proof and state diff
     Complete(Proof),
-    /// A list of tx ids
+    /// A list of wtxids of the chunks, in order
     Aggregate(Vec<[u8; 32]>),
     /// A chunk of an aggregate
     Chunk(Vec<u8>),
 }
 
-/// Data written to DA and read from DA must be the borsh serialization of this enum
+/// Data written to DA and read from DA is encoded with [`VersionedDaData`]
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
 pub enum DaDataBatchProof {
     /// A commitment from the sequencer
@@ -84,6 +189,68 @@ pub enum DaDataBatchProof {
     // ForcedTransaction(ForcedTransaction),
 }
 
+/// Version prefix of the data written to DA.
+/// Blobs written before versioning start with the borsh variant index of the data enum,
+/// so versions are numbered from 128 to never be mistaken for a variant index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DaDataVersion {
+    /// Borsh serialization of the data enum
+    V1 = 128,
+}
+
+impl DaDataVersion {
+    /// Version the data of `spec_id` is written with.
+    /// Genesis guests decode raw borsh only, so Genesis data is written without a version.
+    pub const fn for_spec(spec_id: SpecId) -> Option<Self> {
+        match spec_id {
+            SpecId::Genesis => None,
+            _ => Some(Self::V1),
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            128 => Some(Self::V1),
+            _ => None,
+        }
+    }
+}
+
+/// Codec of the data written to DA: a one-byte [`DaDataVersion`] followed by the payload.
+/// Data not starting with a known version is decoded as legacy raw borsh, which is how
+/// blobs were written before versioning.
+pub trait VersionedDaData: BorshSerialize + BorshDeserialize {
+    /// Encodes the data with the version of `spec_id`, or as raw borsh if the spec
+    /// predates versioning.
+    fn encode_versioned(&self, spec_id: SpecId) -> Vec<u8> {
+        let mut data = Vec::new();
+        if let Some(version) = DaDataVersion::for_spec(spec_id) {
+            data.push(version as u8);
+        }
+        self.serialize(&mut data)
+            .expect("Serialization to vec is infallible");
+        data
+    }
+
+    /// Decodes versioned data, falling back to legacy raw borsh.
+    fn decode_versioned(data: &[u8]) -> borsh::io::Result<Self> {
+        let versioned = data
+            .split_first()
+            .and_then(|(&version, payload)| Some((DaDataVersion::from_byte(version)?, payload)));
+        match versioned {
+            Some((DaDataVersion::V1, payload)) => borsh::from_slice(payload),
+            None => borsh::from_slice(data),
+        }
+    }
+}
+
+impl VersionedDaData for DaData {}
+
+impl VersionedDaData for DaDataLightClient {}
+
+impl VersionedDaData for DaDataBatchProof {}
+
 /// Which type of tx we operate on in DaVerifier
 pub enum DaNamespace {
     /// Txs going to batch-prover
@@ -161,7 +328,7 @@ pub trait DaVerifier: Send + Sync {
         namespace: DaNamespace,
     ) -> Result<(), Self::Error>;
 
-    /// Verify that the block header is valid for the given previous light client proof output
+    /// Verify that the block header is valid for the given previous light client proof output.
     fn verify_header_chain(
         &self,
         previous_light_client_proof_output: &Option<LightClientCircuitOutput<Self::Spec>>,
@@ -396,3 +563,137 @@ impl Time {
         self.nanos
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commitment() -> SequencerCommitment {
+        SequencerCommitment {
+            merkle_root: [0xab; 32],
+            l2_start_block_number: 1001,
+            l2_end_block_number: 1100,
+        }
+    }
+
+    fn assert_round_trip<T: VersionedDaData + PartialEq + Debug>(data: T) {
+        let encoded = data.encode_versioned(SpecId::Fork1);
+        assert_eq!(encoded[0], DaDataVersion::V1 as u8);
+        assert_eq!(T::decode_versioned(&encoded).unwrap(), data);
+
+        // Genesis data is written as raw borsh, which is still decoded
+        let legacy = data.encode_versioned(SpecId::Genesis);
+        assert_eq!(legacy, borsh::to_vec(&data).unwrap());
+        assert_eq!(T::decode_versioned(&legacy).unwrap(), data);
+    }
+
+    #[test]
+    fn test_versioned_round_trip() {
+        assert_round_trip(DaData::SequencerCommitment(commitment()));
+        assert_round_trip(DaData::ZKProof(vec![1, 2, 3]));
+        assert_round_trip(DaData::ZKProof(vec![]));
+
+        assert_round_trip(DaDataBatchProof::SequencerCommitment(commitment()));
+
+        assert_round_trip(DaDataLightClient::Complete(vec![1, 2, 3]));
+        assert_round_trip(DaDataLightClient::Complete(vec![]));
+        assert_round_trip(DaDataLightClient::Aggregate(vec![[1; 32], [2; 32]]));
+        assert_round_trip(DaDataLightClient::Aggregate(vec![]));
+        assert_round_trip(DaDataLightClient::Chunk(vec![4, 5, 6]));
+        assert_round_trip(DaDataLightClient::Chunk(vec![]));
+    }
+
+    #[test]
+    fn test_decode_legacy_blobs() {
+        // Body of a sequencer commitment inscribed before versioning,
+        // taken from bitcoin-da's test_data/mock_txs.txt
+        let legacy = hex::decode(
+            "000d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0dea030000000000004c04000000000000",
+        )
+        .unwrap();
+        let commitment = SequencerCommitment {
+            merkle_root: [0x0d; 32],
+            l2_start_block_number: 1002,
+            l2_end_block_number: 1100,
+        };
+        assert_eq!(
+            DaDataBatchProof::decode_versioned(&legacy).unwrap(),
+            DaDataBatchProof::SequencerCommitment(commitment.clone())
+        );
+        assert_eq!(
+            DaData::decode_versioned(&legacy).unwrap(),
+            DaData::SequencerCommitment(commitment)
+        );
+    }
+
+    fn commitment_range(start: u64, end: u64) -> SequencerCommitment {
+        SequencerCommitment {
+            merkle_root: [0; 32],
+            l2_start_block_number: start,
+            l2_end_block_number: end,
+        }
+    }
+
+    #[test]
+    fn test_contiguous_commitments() {
+        let commitments = [
+            commitment_range(1, 10),
+            commitment_range(11, 11),
+            commitment_range(12, 30),
+        ];
+        assert_eq!(check_sequencer_commitments_contiguous(&commitments), Ok(()));
+        assert_eq!(check_sequencer_commitments_contiguous(&[]), Ok(()));
+    }
+
+    #[test]
+    fn test_gapped_commitments() {
+        let commitments = [commitment_range(1, 10), commitment_range(12, 20)];
+        assert_eq!(
+            check_sequencer_commitments_contiguous(&commitments),
+            Err(SequencerCommitmentRangeError::Gap {
+                previous_end: 10,
+                l2_start_block_number: 12,
+            })
+        );
+    }
+
+    #[test]
+    fn test_overlapping_commitments() {
+        let commitments = [commitment_range(1, 10), commitment_range(10, 20)];
+        assert_eq!(
+            check_sequencer_commitments_contiguous(&commitments),
+            Err(SequencerCommitmentRangeError::Overlap {
+                previous_end: 10,
+                l2_start_block_number: 10,
+            })
+        );
+
+        // A commitment repeated in the range
+        let commitments = [commitment_range(1, 10), commitment_range(1, 10)];
+        assert!(matches!(
+            check_sequencer_commitments_contiguous(&commitments),
+            Err(SequencerCommitmentRangeError::Overlap { .. })
+        ));
+    }
+
+    #[test]
+    fn test_inverted_commitment() {
+        let commitments = [commitment_range(1, 10), commitment_range(20, 11)];
+        assert_eq!(
+            check_sequencer_commitments_contiguous(&commitments),
+            Err(SequencerCommitmentRangeError::InvertedRange {
+                l2_start_block_number: 20,
+                l2_end_block_number: 11,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_data() {
+        assert!(DaDataBatchProof::decode_versioned(&[]).is_err());
+        // Known version with a broken payload
+        assert!(DaDataBatchProof::decode_versioned(&[DaDataVersion::V1 as u8, 0]).is_err());
+        // Unknown variant index
+        assert!(DaDataLightClient::decode_versioned(&[3]).is_err());
+    }
+}
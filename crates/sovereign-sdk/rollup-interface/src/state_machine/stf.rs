@@ -52,8 +52,26 @@ pub struct ApplySequencerCommitmentsOutput<StateRoot> {
     pub final_state_root: StateRoot,
     /// State diff generated after applying
     pub state_diff: CumulativeStateDiff,
+    /// First processed L2 block height
+    pub first_l2_height: u64,
     /// Last processed L2 block height
     pub last_l2_height: u64,
+    /// DA public key that signed the applied sequencer commitments
+    pub sequencer_da_public_key: Vec<u8>,
+}
+
+/// Public key of the Sequencer that signs the soft confirmation at the given L2 height.
+///
+/// `sequencer_public_keys` is the key schedule as `(activation_l2_height, public_key)`,
+/// sorted by activation height with the first key active from genesis.
+pub fn sequencer_public_key_at<'a>(
+    sequencer_public_keys: &[(u64, &'a [u8])],
+    l2_height: u64,
+) -> &'a [u8] {
+    let idx = sequencer_public_keys
+        .partition_point(|(activation_l2_height, _)| *activation_l2_height <= l2_height);
+    // The first key activates at genesis, so there is always one at or before the height
+    sequencer_public_keys[idx.saturating_sub(1)].1
 }
 
 /// A receipt for a soft confirmation of transactions. These receipts are stored in the rollup's database
@@ -215,13 +233,16 @@ pub trait StateTransitionFunction<Da: DaSpec> {
 
     /// Runs a vector of Soft Confirmations
     /// Used for proving the L2 block state transitions
+    /// Each soft confirmation is verified with the key of `sequencer_public_keys` active at its height.
+    /// Sequencer commitments signed with any of the given DA public keys are accepted,
+    /// the applied ones must share a single signer.
     // TODO: don't use tuple as return type.
     #[allow(clippy::type_complexity)]
     #[allow(clippy::too_many_arguments)]
     fn apply_soft_confirmations_from_sequencer_commitments(
         &mut self,
-        sequencer_public_key: &[u8],
-        sequencer_da_public_key: &[u8],
+        sequencer_public_keys: &[(u64, &[u8])],
+        sequencer_da_public_keys: &[&[u8]],
         initial_state_root: &Self::StateRoot,
         pre_state: Self::PreState,
         da_data: Vec<<Da as DaSpec>::BlobTransaction>,
@@ -285,9 +306,18 @@ pub enum SoftConfirmationModuleCallError {
         /// The gas used by the transaction
         /// that causes the error
         tx_gas_used: u64,
-        /// The block gas limit
+        /// The block gas limit, less the system gas reserve
+        /// if the transaction is a user transaction and the reserve is active
         block_gas_limit: u64,
     },
+    /// The system transactions of the soft confirmation used more gas
+    /// than the system gas reserve, so there is no room for user transactions
+    EvmSystemGasExceedsReserve {
+        /// The gas used by the system transactions
+        system_gas_used: u64,
+        /// The system gas reserve
+        system_gas_reserve: u64,
+    },
     /// There was an error during EVM transaction execution
     EvmTransactionExecutionError,
     /// There is a system transaction where it should not be
@@ -300,6 +330,12 @@ pub enum SoftConfirmationModuleCallError {
     RuleEnforcerUnauthorized,
     /// The EVM transaction type is not supported
     EvmTxTypeNotSupported(String),
+    /// The account of the sov-tx sender does not exist
+    AccountsAccountNotFound,
+    /// The new public key of an account is already bound to an account
+    AccountsPublicKeyAlreadyBound,
+    /// The new public key of an account did not sign the key update
+    AccountsInvalidPublicKeySignature,
 }
 
 #[derive(Debug, PartialEq)]
@@ -384,6 +420,16 @@ impl std::fmt::Display for SoftConfirmationModuleCallError {
                     cumulative_gas, tx_gas_used, block_gas_limit
                 )
             }
+            SoftConfirmationModuleCallError::EvmSystemGasExceedsReserve {
+                system_gas_used,
+                system_gas_reserve,
+            } => {
+                write!(
+                    f,
+                    "EVM system transactions used {} gas, exceeding the system gas reserve {}",
+                    system_gas_used, system_gas_reserve
+                )
+            }
             SoftConfirmationModuleCallError::EvmTransactionExecutionError => {
                 write!(f, "EVM transaction execution error")
             }
@@ -402,6 +448,15 @@ impl std::fmt::Display for SoftConfirmationModuleCallError {
             SoftConfirmationModuleCallError::EvmTxNotSerializable => {
                 write!(f, "EVM tx not serializable")
             }
+            SoftConfirmationModuleCallError::AccountsAccountNotFound => {
+                write!(f, "Account of the sender not found")
+            }
+            SoftConfirmationModuleCallError::AccountsPublicKeyAlreadyBound => {
+                write!(f, "Public key is already bound to an account")
+            }
+            SoftConfirmationModuleCallError::AccountsInvalidPublicKeySignature => {
+                write!(f, "Invalid signature of the new public key")
+            }
         }
     }
 }
@@ -416,3 +471,20 @@ impl std::fmt::Display for StateTransitionError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::sequencer_public_key_at;
+
+    #[test]
+    fn test_sequencer_public_key_at() {
+        let keys: &[(u64, &[u8])] = &[(0, &[0; 32]), (10, &[1; 32]), (20, &[2; 32])];
+
+        assert_eq!(sequencer_public_key_at(keys, 0), [0; 32]);
+        assert_eq!(sequencer_public_key_at(keys, 9), [0; 32]);
+        assert_eq!(sequencer_public_key_at(keys, 10), [1; 32]);
+        assert_eq!(sequencer_public_key_at(keys, 19), [1; 32]);
+        assert_eq!(sequencer_public_key_at(keys, 20), [2; 32]);
+        assert_eq!(sequencer_public_key_at(keys, u64::MAX), [2; 32]);
+    }
+}
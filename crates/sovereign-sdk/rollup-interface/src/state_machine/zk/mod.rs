@@ -20,6 +20,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::da::DaSpec;
 use crate::soft_confirmation::SignedSoftConfirmation;
+use crate::spec::SpecId;
 
 /// The ZK proof generated by the [`ZkvmHost::run`] method.
 pub type Proof = Vec<u8>;
@@ -133,6 +134,131 @@ pub struct BatchProofCircuitOutput<Da: DaSpec, Root> {
     pub last_l2_height: u64,
     /// Pre-proven commitments L2 ranges which also exist in the current L1 `da_data`.
     pub preproven_commitments: Vec<usize>,
+    /// The first processed l2 height in the processed sequencer commitments.
+    /// The processed commitments cover `first_l2_height..=last_l2_height` without gaps.
+    pub first_l2_height: u64,
+}
+
+/// [`BatchProofCircuitOutput`] before it committed to the first processed l2 height.
+#[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct BatchProofCircuitOutputV1<Da: DaSpec, Root> {
+    /// The state of the rollup before the transition
+    pub initial_state_root: Root,
+    /// The state of the rollup after the transition
+    pub final_state_root: Root,
+    /// The hash of the last soft confirmation before the state transition
+    pub prev_soft_confirmation_hash: [u8; 32],
+    /// The hash of the last soft confirmation in the state transition
+    pub final_soft_confirmation_hash: [u8; 32],
+    /// State diff of L2 blocks in the processed sequencer commitments.
+    pub state_diff: CumulativeStateDiff,
+    /// The DA slot hash that the sequencer commitments causing this state transition were found in.
+    pub da_slot_hash: Da::SlotHash,
+    /// The range of sequencer commitments in the DA slot that were processed.
+    /// The range is inclusive.
+    pub sequencer_commitments_range: (u32, u32),
+    /// Sequencer public key.
+    pub sequencer_public_key: Vec<u8>,
+    /// Sequencer DA public key.
+    pub sequencer_da_public_key: Vec<u8>,
+    /// The last processed l2 height in the processed sequencer commitments.
+    pub last_l2_height: u64,
+    /// Pre-proven commitments L2 ranges which also exist in the current L1 `da_data`.
+    pub preproven_commitments: Vec<usize>,
+}
+
+impl<Da: DaSpec, Root> From<BatchProofCircuitOutputV1<Da, Root>>
+    for BatchProofCircuitOutput<Da, Root>
+{
+    /// `first_l2_height` is zeroed, as V1 outputs do not commit to it
+    fn from(value: BatchProofCircuitOutputV1<Da, Root>) -> Self {
+        Self {
+            initial_state_root: value.initial_state_root,
+            final_state_root: value.final_state_root,
+            prev_soft_confirmation_hash: value.prev_soft_confirmation_hash,
+            final_soft_confirmation_hash: value.final_soft_confirmation_hash,
+            state_diff: value.state_diff,
+            da_slot_hash: value.da_slot_hash,
+            sequencer_commitments_range: value.sequencer_commitments_range,
+            sequencer_public_key: value.sequencer_public_key,
+            sequencer_da_public_key: value.sequencer_da_public_key,
+            last_l2_height: value.last_l2_height,
+            preproven_commitments: value.preproven_commitments,
+            first_l2_height: 0,
+        }
+    }
+}
+
+impl<Da: DaSpec, Root> From<BatchProofCircuitOutput<Da, Root>>
+    for BatchProofCircuitOutputV1<Da, Root>
+{
+    /// Drops `first_l2_height`, for the guests which output the V1 layout
+    fn from(value: BatchProofCircuitOutput<Da, Root>) -> Self {
+        Self {
+            initial_state_root: value.initial_state_root,
+            final_state_root: value.final_state_root,
+            prev_soft_confirmation_hash: value.prev_soft_confirmation_hash,
+            final_soft_confirmation_hash: value.final_soft_confirmation_hash,
+            state_diff: value.state_diff,
+            da_slot_hash: value.da_slot_hash,
+            sequencer_commitments_range: value.sequencer_commitments_range,
+            sequencer_public_key: value.sequencer_public_key,
+            sequencer_da_public_key: value.sequencer_da_public_key,
+            last_l2_height: value.last_l2_height,
+            preproven_commitments: value.preproven_commitments,
+        }
+    }
+}
+
+/// Layout of the batch proof circuit output
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[borsh(use_discriminant = true)]
+pub enum BatchProofOutputVersion {
+    /// [`BatchProofCircuitOutputV1`], output by the Genesis and Fork1 guests
+    V1 = 1,
+    /// [`BatchProofCircuitOutput`], output by the guests of the forks after Fork1
+    V2 = 2,
+}
+
+impl BatchProofOutputVersion {
+    /// The layout the guest of the given spec outputs
+    pub const fn from_spec(spec_id: SpecId) -> Self {
+        match spec_id {
+            SpecId::Genesis | SpecId::Fork1 => Self::V1,
+            #[allow(unreachable_patterns)]
+            _ => Self::V2,
+        }
+    }
+
+    /// Decodes a batch proof journal in this layout. The whole journal must be consumed
+    pub fn decode_output<Da: DaSpec, Root: BorshDeserialize>(
+        &self,
+        journal: &[u8],
+    ) -> borsh::io::Result<BatchProofCircuitOutput<Da, Root>> {
+        match self {
+            Self::V1 => {
+                borsh::from_slice::<BatchProofCircuitOutputV1<Da, Root>>(journal).map(Into::into)
+            }
+            Self::V2 => borsh::from_slice(journal),
+        }
+    }
+
+    /// Whether the output commits to the first processed l2 height
+    pub const fn has_first_l2_height(&self) -> bool {
+        matches!(self, Self::V2)
+    }
 }
 
 /// A trait expressing that two items of a type are (potentially fuzzy) matches.
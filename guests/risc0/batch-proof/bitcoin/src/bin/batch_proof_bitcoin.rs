@@ -1,5 +1,5 @@
 #![no_main]
-use bitcoin_da::spec::RollupParams;
+use bitcoin_da::spec::{BitcoinNetwork, RollupParams};
 use bitcoin_da::verifier::BitcoinVerifier;
 use citrea_primitives::forks::{DEVNET_FORKS, MAINNET_FORKS, NIGHTLY_FORKS, TESTNET_FORKS};
 use citrea_primitives::{TO_BATCH_PROOF_PREFIX, TO_LIGHT_CLIENT_PREFIX};
@@ -10,7 +10,6 @@ use sov_modules_api::default_context::ZkDefaultContext;
 use sov_modules_api::fork::Fork;
 use sov_modules_stf_blueprint::StfBlueprint;
 use sov_rollup_interface::da::DaVerifier;
-use sov_rollup_interface::zk::ZkvmGuest;
 use sov_rollup_interface::Network;
 use sov_state::ZkStorage;
 
@@ -45,6 +44,11 @@ const SEQUENCER_PUBLIC_KEY: [u8; 32] = {
     }
 };
 
+// Soft confirmation signing keys of the Sequencer with the L2 heights they activate at, sorted by height.
+// Every block is verified with the key active at its height, a rotation is scheduled by
+// appending the new key here with the same activation height as in the nodes' `sequencer_key_rotations`
+const SEQUENCER_PUBLIC_KEYS: &[(u64, &[u8])] = &[(0, &SEQUENCER_PUBLIC_KEY)];
+
 const SEQUENCER_DA_PUBLIC_KEY: [u8; 33] = {
     let hex_pub_key = match NETWORK {
         Network::Mainnet => "030000000000000000000000000000000000000000000000000000000000000000",
@@ -64,6 +68,10 @@ const SEQUENCER_DA_PUBLIC_KEY: [u8; 33] = {
     }
 };
 
+// Commitments signed with any of these keys are accepted,
+// the previous key is kept here during a rotation of the Sequencer's DA key
+const SEQUENCER_DA_PUBLIC_KEYS: &[&[u8]] = &[&SEQUENCER_DA_PUBLIC_KEY];
+
 const FORKS: &[Fork] = match NETWORK {
     Network::Mainnet => &MAINNET_FORKS,
     Network::Testnet => &TESTNET_FORKS,
@@ -81,14 +89,11 @@ pub fn main() {
         BitcoinVerifier::new(RollupParams {
             to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
             to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
+            network: BitcoinNetwork::from_network(NETWORK),
         }),
     );
 
-    let data = guest.read_from_host();
-
-    let out = stf_verifier
-        .run_sequencer_commitments_in_da_slot(data, storage, &SEQUENCER_PUBLIC_KEY, &SEQUENCER_DA_PUBLIC_KEY, FORKS)
+    stf_verifier
+        .run_batch_proof_guest(&guest, storage, SEQUENCER_PUBLIC_KEYS, SEQUENCER_DA_PUBLIC_KEYS, FORKS)
         .expect("Prover must be honest");
-
-    guest.commit(&out);
 }
@@ -8,7 +8,6 @@ use sov_modules_api::fork::Fork;
 use sov_modules_stf_blueprint::StfBlueprint;
 use citrea_risc0_adapter::guest::Risc0Guest;
 use sov_state::ZkStorage;
-use sov_rollup_interface::zk::ZkvmGuest;
 
 risc0_zkvm::guest::entry!(main);
 
@@ -17,11 +16,24 @@ const SEQUENCER_PUBLIC_KEY: [u8; 32] = match const_hex::const_decode_to_array(b"
     Err(_) => panic!("Can't happen"),
 };
 
+// Soft confirmation signing keys of the Sequencer with the L2 heights they activate at, sorted by height.
+// Every block is verified with the key active at its height, a rotation is scheduled by
+// appending the new key here with the same activation height as in the nodes' `sequencer_key_rotations`
+const SEQUENCER_PUBLIC_KEYS: &[(u64, &[u8])] = &[(0, &SEQUENCER_PUBLIC_KEY)];
+
 const SEQUENCER_DA_PUBLIC_KEY: [u8; 33] = match const_hex::const_decode_to_array(b"02588d202afcc1ee4ab5254c7847ec25b9a135bbda0f2bc69ee1a714749fd77dc9") {
     Ok(pub_key) => pub_key,
     Err(_) => panic!("Can't happen"),
 };
 
+// Second key of the Sequencer, accepted so that commitments signed during a DA key rotation can be tested
+const ROTATED_SEQUENCER_DA_PUBLIC_KEY: [u8; 33] = match const_hex::const_decode_to_array(b"021111111111111111111111111111111111111111111111111111111111111111") {
+    Ok(pub_key) => pub_key,
+    Err(_) => panic!("Can't happen"),
+};
+
+const SEQUENCER_DA_PUBLIC_KEYS: &[&[u8]] = &[&SEQUENCER_DA_PUBLIC_KEY, &ROTATED_SEQUENCER_DA_PUBLIC_KEY];
+
 const FORKS: &[Fork] = &NIGHTLY_FORKS;
 
 pub fn main() {
@@ -34,11 +46,7 @@ pub fn main() {
         MockDaVerifier {}
     );
 
-    let data = guest.read_from_host();
-
-    let out = stf_verifier
-        .run_sequencer_commitments_in_da_slot(data, storage, &SEQUENCER_PUBLIC_KEY, &SEQUENCER_DA_PUBLIC_KEY, FORKS)
+    stf_verifier
+        .run_batch_proof_guest(&guest, storage, SEQUENCER_PUBLIC_KEYS, SEQUENCER_DA_PUBLIC_KEYS, FORKS)
         .expect("Prover must be honest");
-
-    guest.commit(&out);
 }
@@ -1,10 +1,11 @@
 #![no_main]
-use bitcoin_da::spec::RollupParams;
+use bitcoin_da::spec::{BitcoinNetwork, RollupParams};
 use bitcoin_da::verifier::BitcoinVerifier;
 use citrea_light_client_prover::circuit::run_circuit;
 use citrea_primitives::{TO_BATCH_PROOF_PREFIX, TO_LIGHT_CLIENT_PREFIX};
 use citrea_risc0_adapter::guest::Risc0Guest;
 use sov_rollup_interface::da::DaVerifier;
+use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::zk::ZkvmGuest;
 use sov_rollup_interface::Network;
 
@@ -65,6 +66,9 @@ const BATCH_PROOF_METHOD_ID: [u32; 8] = {
     }
 };
 
+// Spec of the batch proof guest with the method id above, the layout of its output depends on it
+const BATCH_PROOF_SPEC_ID: SpecId = SpecId::Fork1;
+
 const BATCH_PROVER_DA_PUBLIC_KEY: [u8; 33] = {
     let hex_pub_key = match NETWORK {
         Network::Mainnet => "030000000000000000000000000000000000000000000000000000000000000000",
@@ -90,11 +94,12 @@ pub fn main() {
     let da_verifier = BitcoinVerifier::new(RollupParams {
         to_batch_proof_prefix: TO_BATCH_PROOF_PREFIX.to_vec(),
         to_light_client_prefix: TO_LIGHT_CLIENT_PREFIX.to_vec(),
+        network: BitcoinNetwork::from_network(NETWORK),
     });
 
     let input = guest.read_from_host();
 
-    let output = run_circuit::<BitcoinVerifier, Risc0Guest>(da_verifier, input, L2_GENESIS_ROOT, BATCH_PROOF_METHOD_ID, &BATCH_PROVER_DA_PUBLIC_KEY).unwrap();
+    let output = run_circuit::<BitcoinVerifier, Risc0Guest>(da_verifier, input, L2_GENESIS_ROOT, BATCH_PROOF_METHOD_ID, BATCH_PROOF_SPEC_ID, &BATCH_PROVER_DA_PUBLIC_KEY).unwrap();
 
     guest.commit(&output);
 }
@@ -2,6 +2,7 @@
 use citrea_light_client_prover::circuit::run_circuit;
 use citrea_risc0_adapter::guest::Risc0Guest;
 use sov_mock_da::MockDaVerifier;
+use sov_rollup_interface::spec::SpecId;
 use sov_rollup_interface::zk::ZkvmGuest;
 
 risc0_zkvm::guest::entry!(main);
@@ -13,6 +14,9 @@ const L2_GENESIS_ROOT: [u8; 32] = match const_hex::const_decode_to_array(b"dacb5
 
 const BATCH_PROOF_METHOD_ID: [u32; 8] = citrea_risc0_batch_proof::BATCH_PROOF_MOCK_ID;
 
+// Spec of the batch proof guest with the method id above, the layout of its output depends on it
+const BATCH_PROOF_SPEC_ID: SpecId = SpecId::Fork1;
+
 const BATCH_PROVER_DA_PUBLIC_KEY: [u8; 33] = match const_hex::const_decode_to_array(b"03eedab888e45f3bdc3ec9918c491c11e5cf7af0a91f38b97fbc1e135ae4056601") {
     Ok(pub_key) => pub_key,
     Err(_) => panic!("Can't happen"),
@@ -25,7 +29,7 @@ pub fn main() {
 
     let input = guest.read_from_host();
 
-    let output = run_circuit::<MockDaVerifier, Risc0Guest>(da_verifier, input, L2_GENESIS_ROOT, BATCH_PROOF_METHOD_ID, &BATCH_PROVER_DA_PUBLIC_KEY).unwrap();
+    let output = run_circuit::<MockDaVerifier, Risc0Guest>(da_verifier, input, L2_GENESIS_ROOT, BATCH_PROOF_METHOD_ID, BATCH_PROOF_SPEC_ID, &BATCH_PROVER_DA_PUBLIC_KEY).unwrap();
 
     guest.commit(&output);
 }